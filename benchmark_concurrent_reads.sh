@@ -0,0 +1,80 @@
+#!/bin/bash
+# Concurrent read benchmark: queries take shared locks, writes exclusive ones
+
+CLIENTS=${CLIENTS:-8}
+QUERIES=${QUERIES:-20}
+ROWS=${ROWS:-5000}
+
+echo "╔══════════════════════════════════════════════════════════╗"
+echo "║     Concurrent Reads Benchmark                           ║"
+echo "╚══════════════════════════════════════════════════════════╝"
+echo ""
+
+cargo build --release --quiet 2>/dev/null
+
+rm -rf data
+mkdir -p data
+
+# Start server
+timeout 120 cargo run --release &>/dev/null &
+PID=$!
+sleep 2
+
+# One session: $ROWS rows
+{
+    printf "CREATE TABLE t (id INTEGER, data TEXT);\n"
+    for i in $(seq 1 "$ROWS"); do
+        printf "INSERT INTO t VALUES (%d, 'data%d');\n" "$i" "$i"
+    done
+} | nc -q 2 127.0.0.1 5432 &>/dev/null
+
+# $QUERIES full scans in one session
+reader() {
+    for _ in $(seq 1 "$QUERIES"); do
+        printf "SELECT * FROM t WHERE data LIKE '%%9%%' ORDER BY data DESC;\n"
+    done | nc -q 5 127.0.0.1 5432 &>/dev/null
+}
+
+now_ms() {
+    date +%s%3N
+}
+
+echo "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
+echo "Scenario: $ROWS rows, $QUERIES scans per client"
+echo "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
+
+START=$(now_ms)
+reader
+SINGLE=$(( $(now_ms) - START ))
+echo "  1 client:          ${SINGLE} ms"
+
+START=$(now_ms)
+for _ in $(seq 1 "$CLIENTS"); do
+    reader &
+done
+wait $(jobs -p | grep -v "^$PID$") 2>/dev/null
+PARALLEL=$(( $(now_ms) - START ))
+echo "  $CLIENTS clients at once: ${PARALLEL} ms"
+
+# Latency of a single write while the readers are busy
+for _ in $(seq 1 "$CLIENTS"); do
+    reader &
+done
+sleep 0.2
+START=$(now_ms)
+printf "INSERT INTO t VALUES (0, 'write');\n" | nc -q 1 127.0.0.1 5432 &>/dev/null
+echo "  INSERT during reads: $(( $(now_ms) - START )) ms"
+wait $(jobs -p | grep -v "^$PID$") 2>/dev/null
+
+kill $PID 2>/dev/null
+wait $PID 2>/dev/null
+
+echo ""
+echo "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
+echo "Expected:"
+echo "  • Readers only share a read lock: $CLIENTS clients take about as"
+echo "    long as one as long as there are enough CPU cores"
+echo "  • Writes wait for the scans running when they arrive"
+echo "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━"
+
+rm -rf data
@@ -0,0 +1,8 @@
+//! Any script runs against a fresh in-memory database without panicking
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    postgrustql::fuzzing::fuzz_execute(data);
+});
@@ -0,0 +1,8 @@
+//! Any input parses or is rejected, never panics
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    postgrustql::fuzzing::fuzz_parse(data);
+});
@@ -0,0 +1,533 @@
+use clap::{Parser, ValueEnum};
+use postgrustql::client::{Client, ClientConfig, ClientError};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Rows of each table per scale factor, as in pgbench
+const ACCOUNTS_PER_BRANCH: i64 = 100_000;
+const TELLERS_PER_BRANCH: i64 = 10;
+
+/// Rows per INSERT while initializing
+const INIT_BATCH_ROWS: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Builtin {
+    /// TPC-B like: three updates, a select and an insert per transaction
+    TpcbLike,
+    /// Update of one account, a select and an insert
+    SimpleUpdate,
+    /// Select of one account
+    SelectOnly,
+}
+
+/// `PostgrustSQL` benchmark client (pgbench-like)
+#[derive(Debug, Parser)]
+#[command(name = "pgr_bench")]
+#[command(about = "Run TPC-B like or custom SQL scripts against a PostgrustSQL server", long_about = None)]
+struct Args {
+    /// Create and fill the pgbench_* tables, then exit
+    #[arg(short = 'i', long)]
+    initialize: bool,
+
+    /// Scale factor: branches when initializing, `:scale` in scripts
+    #[arg(short = 's', long, default_value_t = 1)]
+    scale: i64,
+
+    /// Built-in script to run
+    #[arg(short = 'b', long, value_enum, default_value = "tpcb-like")]
+    builtin: Builtin,
+
+    /// Custom script to run instead of the built-in one
+    #[arg(short = 'f', long)]
+    file: Option<PathBuf>,
+
+    /// Concurrent client connections
+    #[arg(short = 'c', long, default_value_t = 1)]
+    clients: usize,
+
+    /// Worker threads driving the clients
+    #[arg(short = 'j', long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Transactions per client
+    #[arg(short = 't', long, default_value_t = 10, conflicts_with = "time")]
+    transactions: u64,
+
+    /// Run for this many seconds instead of a number of transactions
+    #[arg(short = 'T', long)]
+    time: Option<u64>,
+
+    /// Server host
+    #[arg(short = 'H', long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Server port
+    #[arg(short = 'p', long, default_value_t = 5432)]
+    port: u16,
+
+    /// Database user
+    #[arg(short = 'U', long, default_value = "postgres")]
+    user: String,
+
+    /// Database name
+    #[arg(short = 'd', long, default_value = "postgres")]
+    database: String,
+
+    /// Password (also read from PGPASSWORD)
+    #[arg(short = 'W', long)]
+    password: Option<String>,
+}
+
+/// One line of a script
+#[derive(Debug, Clone)]
+enum Command {
+    /// `\set name expression`
+    Set(String, Expr),
+    /// SQL with `:name` variables
+    Sql(String),
+}
+
+/// Integer expression of `\set`
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(i64),
+    Variable(String),
+    Random(Box<Self>, Box<Self>),
+    Binary(char, Box<Self>, Box<Self>),
+}
+
+/// Outcome of one client
+#[derive(Debug, Default)]
+struct ClientStats {
+    /// Latency of every committed transaction, in microseconds
+    latencies: Vec<u64>,
+    failed: u64,
+}
+
+const TPCB_LIKE: &str = r"
+\set aid random(1, 100000 * :scale)
+\set bid random(1, 1 * :scale)
+\set tid random(1, 10 * :scale)
+\set delta random(-5000, 5000)
+BEGIN;
+UPDATE pgbench_accounts SET abalance = abalance + :delta WHERE aid = :aid;
+SELECT abalance FROM pgbench_accounts WHERE aid = :aid;
+UPDATE pgbench_tellers SET tbalance = tbalance + :delta WHERE tid = :tid;
+UPDATE pgbench_branches SET bbalance = bbalance + :delta WHERE bid = :bid;
+INSERT INTO pgbench_history (tid, bid, aid, delta) VALUES (:tid, :bid, :aid, :delta);
+COMMIT;
+";
+
+const SIMPLE_UPDATE: &str = r"
+\set aid random(1, 100000 * :scale)
+\set bid random(1, 1 * :scale)
+\set tid random(1, 10 * :scale)
+\set delta random(-5000, 5000)
+BEGIN;
+UPDATE pgbench_accounts SET abalance = abalance + :delta WHERE aid = :aid;
+SELECT abalance FROM pgbench_accounts WHERE aid = :aid;
+INSERT INTO pgbench_history (tid, bid, aid, delta) VALUES (:tid, :bid, :aid, :delta);
+COMMIT;
+";
+
+const SELECT_ONLY: &str = r"
+\set aid random(1, 100000 * :scale)
+SELECT abalance FROM pgbench_accounts WHERE aid = :aid;
+";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let config = {
+        let config = ClientConfig::new(&format!("{}:{}", args.host, args.port))
+            .with_user(&args.user)
+            .with_database(&args.database);
+        match args.password.clone().or_else(|| std::env::var("PGPASSWORD").ok()) {
+            Some(password) => config.with_password(&password),
+            None => config,
+        }
+    };
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(args.jobs.max(1))
+        .enable_all()
+        .build()?;
+
+    if args.initialize {
+        return runtime.block_on(initialize(&config, args.scale));
+    }
+
+    let script = match &args.file {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => match args.builtin {
+            Builtin::TpcbLike => TPCB_LIKE,
+            Builtin::SimpleUpdate => SIMPLE_UPDATE,
+            Builtin::SelectOnly => SELECT_ONLY,
+        }
+        .to_string(),
+    };
+    let script = Arc::new(parse_script(&script)?);
+
+    let clients = args.clients.max(1);
+    let deadline = args.time.map(Duration::from_secs);
+    let started = Instant::now();
+    let stats = runtime.block_on(async {
+        let mut tasks = Vec::with_capacity(clients);
+        for seed in 0..clients {
+            let (config, script) = (config.clone(), Arc::clone(&script));
+            let (scale, transactions) = (args.scale, args.transactions);
+            tasks.push(tokio::spawn(async move {
+                let client = config.connect().await?;
+                run_client(client, &script, scale, seed as u64, transactions, deadline).await
+            }));
+        }
+        let mut stats = Vec::with_capacity(clients);
+        for task in tasks {
+            stats.push(task.await?.map_err(|e| e as Box<dyn std::error::Error>)?);
+        }
+        Ok::<_, Box<dyn std::error::Error>>(stats)
+    })?;
+    let elapsed = started.elapsed();
+
+    report(&args, clients, stats, elapsed);
+    Ok(())
+}
+
+/// Create the pgbench_* tables for `scale` branches
+async fn initialize(config: &ClientConfig, scale: i64) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = config.connect().await?;
+
+    println!("dropping old tables...");
+    for table in ["pgbench_history", "pgbench_tellers", "pgbench_accounts", "pgbench_branches"] {
+        // Missing on the first run
+        let _ = client.batch_execute(&format!("DROP TABLE {table}")).await;
+    }
+
+    println!("creating tables...");
+    client.batch_execute("CREATE TABLE pgbench_branches (bid INTEGER PRIMARY KEY, bbalance INTEGER, filler TEXT)").await?;
+    client.batch_execute("CREATE TABLE pgbench_tellers (tid INTEGER PRIMARY KEY, bid INTEGER, tbalance INTEGER, filler TEXT)").await?;
+    client.batch_execute("CREATE TABLE pgbench_accounts (aid INTEGER PRIMARY KEY, bid INTEGER, abalance INTEGER, filler TEXT)").await?;
+    client.batch_execute("CREATE TABLE pgbench_history (tid INTEGER, bid INTEGER, aid INTEGER, delta INTEGER, filler TEXT)").await?;
+
+    println!("generating data...");
+    insert_rows(&mut client, "pgbench_branches", scale, |bid| format!("({bid}, 0, '')")).await?;
+    insert_rows(&mut client, "pgbench_tellers", TELLERS_PER_BRANCH * scale, |tid| {
+        format!("({tid}, {}, 0, '')", (tid - 1) / TELLERS_PER_BRANCH + 1)
+    })
+    .await?;
+    let accounts = ACCOUNTS_PER_BRANCH * scale;
+    insert_rows(&mut client, "pgbench_accounts", accounts, |aid| {
+        format!("({aid}, {}, 0, '')", (aid - 1) / ACCOUNTS_PER_BRANCH + 1)
+    })
+    .await?;
+
+    client.close().await?;
+    println!("done: {scale} branches, {} tellers, {accounts} accounts", TELLERS_PER_BRANCH * scale);
+    Ok(())
+}
+
+/// Insert rows 1..=`count` of `table` in batches, `row` giving the VALUES tuple of each
+async fn insert_rows(
+    client: &mut Client,
+    table: &str,
+    count: i64,
+    row: impl Fn(i64) -> String,
+) -> Result<(), ClientError> {
+    let mut first = 1;
+    while first <= count {
+        let last = (first + INIT_BATCH_ROWS - 1).min(count);
+        let values = (first..=last).map(&row).collect::<Vec<_>>().join(", ");
+        client.batch_execute(&format!("INSERT INTO {table} VALUES {values}")).await?;
+        first = last + 1;
+    }
+    Ok(())
+}
+
+/// Run the script over one connection until `transactions` are done or `deadline` has passed
+async fn run_client(
+    mut client: Client,
+    script: &[Command],
+    scale: i64,
+    seed: u64,
+    transactions: u64,
+    deadline: Option<Duration>,
+) -> Result<ClientStats, Box<dyn std::error::Error + Send + Sync>> {
+    let mut stats = ClientStats::default();
+    let mut rng = Rng::new(seed);
+    let started = Instant::now();
+    let mut done = 0;
+    loop {
+        match deadline {
+            Some(deadline) if started.elapsed() >= deadline => break,
+            None if done >= transactions => break,
+            _ => {}
+        }
+        done += 1;
+
+        let mut variables = HashMap::from([("scale".to_string(), scale)]);
+        let transaction = Instant::now();
+        match run_script(&mut client, script, &mut variables, &mut rng).await {
+            Ok(()) => stats.latencies.push(u64::try_from(transaction.elapsed().as_micros()).unwrap_or(u64::MAX)),
+            Err(e) => {
+                eprintln!("client {seed}: {e}");
+                // A failed statement leaves the transaction open (the server
+                // complains if there was none)
+                stats.failed += 1;
+                if let Err(ClientError::Io(e)) = client.batch_execute("ROLLBACK").await {
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+    client.close().await?;
+    Ok(stats)
+}
+
+async fn run_script(
+    client: &mut Client,
+    script: &[Command],
+    variables: &mut HashMap<String, i64>,
+    rng: &mut Rng,
+) -> Result<(), String> {
+    for command in script {
+        match command {
+            Command::Set(name, expr) => {
+                let value = expr.eval(variables, rng)?;
+                variables.insert(name.clone(), value);
+            }
+            Command::Sql(sql) => {
+                let sql = substitute(sql, variables)?;
+                client.simple_query(&sql).await.map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn report(args: &Args, clients: usize, stats: Vec<ClientStats>, elapsed: Duration) {
+    let failed: u64 = stats.iter().map(|stats| stats.failed).sum();
+    let mut latencies: Vec<u64> = stats.into_iter().flat_map(|stats| stats.latencies).collect();
+    latencies.sort_unstable();
+    let committed = latencies.len();
+
+    let script = args.file.as_ref().map_or_else(|| format!("{:?}", args.builtin), |path| path.display().to_string());
+    println!("script: {script}");
+    println!("scaling factor: {}", args.scale);
+    println!("number of clients: {clients}");
+    println!("number of threads: {}", args.jobs.max(1));
+    match args.time {
+        Some(seconds) => println!("duration: {seconds} s"),
+        None => println!("number of transactions per client: {}", args.transactions),
+    }
+    println!("number of transactions actually processed: {committed}");
+    println!("number of failed transactions: {failed}");
+    if committed == 0 {
+        return;
+    }
+
+    let millis = |micros: u64| micros as f64 / 1000.0;
+    let percentile = |p: usize| latencies[(committed * p / 100).min(committed - 1)];
+    let average = latencies.iter().sum::<u64>() as f64 / committed as f64;
+    println!("latency average = {:.3} ms", average / 1000.0);
+    println!("latency p50 = {:.3} ms", millis(percentile(50)));
+    println!("latency p90 = {:.3} ms", millis(percentile(90)));
+    println!("latency p99 = {:.3} ms", millis(percentile(99)));
+    println!("latency max = {:.3} ms", millis(latencies[committed - 1]));
+    println!("tps = {:.3}", committed as f64 / elapsed.as_secs_f64());
+}
+
+/// Parse a script: `\set` lines and SQL statements ending with `;`
+fn parse_script(text: &str) -> Result<Vec<Command>, String> {
+    let mut commands = Vec::new();
+    let mut sql = String::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("--") {
+            continue;
+        }
+        if let Some(set) = line.strip_prefix("\\set ") {
+            let (name, expr) = set.trim().split_once(char::is_whitespace).ok_or_else(|| format!("line {}: \\set needs a name and a value", number + 1))?;
+            let expr = Expr::parse(expr).map_err(|e| format!("line {}: {e}", number + 1))?;
+            commands.push(Command::Set(name.to_string(), expr));
+            continue;
+        }
+        if line.starts_with('\\') {
+            return Err(format!("line {}: unknown meta command {line}", number + 1));
+        }
+        if !sql.is_empty() {
+            sql.push(' ');
+        }
+        sql.push_str(line);
+        if let Some(statement) = sql.strip_suffix(';') {
+            commands.push(Command::Sql(statement.to_string()));
+            sql.clear();
+        }
+    }
+    if !sql.is_empty() {
+        commands.push(Command::Sql(sql));
+    }
+    if commands.is_empty() {
+        return Err("script is empty".to_string());
+    }
+    Ok(commands)
+}
+
+/// Replace `:name` in `sql` with the value of the variable (`::type` casts are kept)
+fn substitute(sql: &str, variables: &HashMap<String, i64>) -> Result<String, String> {
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(colon) = rest.find(':') {
+        out.push_str(&rest[..colon]);
+        let after = &rest[colon + 1..];
+        if let Some(cast) = after.strip_prefix(':') {
+            out.push_str("::");
+            rest = cast;
+            continue;
+        }
+        let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+        if end == 0 {
+            out.push(':');
+        } else {
+            let name = &after[..end];
+            let value = variables.get(name).ok_or_else(|| format!("undefined variable :{name}"))?;
+            out.push_str(&value.to_string());
+        }
+        rest = &after[end..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+impl Expr {
+    /// Parse `random(lo, hi)`, integers, `:variables`, `+ - * / %` and parentheses
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut tokens = Tokens { text: text.trim() };
+        let expr = tokens.sum()?;
+        if !tokens.text.is_empty() {
+            return Err(format!("unexpected '{}'", tokens.text));
+        }
+        Ok(expr)
+    }
+
+    fn eval(&self, variables: &HashMap<String, i64>, rng: &mut Rng) -> Result<i64, String> {
+        match self {
+            Self::Number(n) => Ok(*n),
+            Self::Variable(name) => variables.get(name).copied().ok_or_else(|| format!("undefined variable :{name}")),
+            Self::Random(lo, hi) => {
+                let (lo, hi) = (lo.eval(variables, rng)?, hi.eval(variables, rng)?);
+                if lo > hi {
+                    return Err(format!("empty range random({lo}, {hi})"));
+                }
+                Ok(rng.between(lo, hi))
+            }
+            Self::Binary(op, left, right) => {
+                let (left, right) = (left.eval(variables, rng)?, right.eval(variables, rng)?);
+                match op {
+                    '+' => Ok(left.wrapping_add(right)),
+                    '-' => Ok(left.wrapping_sub(right)),
+                    '*' => Ok(left.wrapping_mul(right)),
+                    _ if right == 0 => Err("division by zero".to_string()),
+                    '/' => Ok(left / right),
+                    _ => Ok(left % right),
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent over the text of a `\set` expression
+struct Tokens<'a> {
+    text: &'a str,
+}
+
+impl Tokens<'_> {
+    fn eat(&mut self, c: char) -> bool {
+        if let Some(rest) = self.text.strip_prefix(c) {
+            self.text = rest.trim_start();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(op) = ['+', '-'].into_iter().find(|&op| self.eat(op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        while let Some(op) = ['*', '/', '%'].into_iter().find(|&op| self.eat(op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        if self.eat('(') {
+            let expr = self.sum()?;
+            return if self.eat(')') { Ok(expr) } else { Err("missing ')'".to_string()) };
+        }
+        if self.eat('-') {
+            return Ok(Expr::Binary('-', Box::new(Expr::Number(0)), Box::new(self.term()?)));
+        }
+        let word_end = |text: &str| text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(text.len());
+        if self.eat(':') {
+            let end = word_end(self.text);
+            let name = &self.text[..end];
+            if name.is_empty() {
+                return Err("missing variable name after ':'".to_string());
+            }
+            self.text = self.text[end..].trim_start();
+            return Ok(Expr::Variable(name.to_string()));
+        }
+
+        let end = word_end(self.text);
+        let word = &self.text[..end];
+        self.text = self.text[end..].trim_start();
+        if let Ok(number) = word.parse() {
+            return Ok(Expr::Number(number));
+        }
+        if word.eq_ignore_ascii_case("random") && self.eat('(') {
+            let lo = self.sum()?;
+            if !self.eat(',') {
+                return Err("random() takes two arguments".to_string());
+            }
+            let hi = self.sum()?;
+            if !self.eat(')') {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(Expr::Random(Box::new(lo), Box::new(hi)));
+        }
+        Err(format!("unexpected '{word}'"))
+    }
+}
+
+/// Small xorshift generator, one per client so clients never contend on it
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| u64::try_from(time.as_nanos()).unwrap_or(u64::MAX));
+        Self((time ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)) | 1)
+    }
+
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `lo..=hi`
+    const fn between(&mut self, lo: i64, hi: i64) -> i64 {
+        let span = hi.abs_diff(lo).saturating_add(1);
+        lo.wrapping_add((self.next() % span).cast_signed())
+    }
+}
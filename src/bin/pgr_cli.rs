@@ -24,7 +24,7 @@ struct Args {
     #[arg(short = 'U', long)]
     user: Option<String>,
 
-    /// Database name (not used for connection, reserved for future auth)
+    /// Database to switch to after connecting
     #[arg(short = 'd', long)]
     database: Option<String>,
 }
@@ -93,6 +93,17 @@ impl ClientConfig {
     }
 }
 
+/// Ask for a new password twice, as `psql`'s `\password` does
+fn read_new_password(rl: &mut DefaultEditor, user: &str) -> Option<String> {
+    let password = rl.readline(&format!("Enter new password for user \"{user}\": ")).ok()?;
+    let again = rl.readline("Enter it again: ").ok()?;
+    if password != again {
+        println!("Passwords didn't match.");
+        return None;
+    }
+    Some(password)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
@@ -136,6 +147,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // The text protocol always starts in "postgres"; switch to the requested database
+    if config.database != "postgres" {
+        writer.write_all(format!("\\c {}\n", config.database).as_bytes()).await?;
+        writer.flush().await?;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await? == 0 || line.trim().ends_with('>') {
+                break;
+            }
+            print!("{line}");
+        }
+    }
+
     // Initialize rustyline editor for history and line editing
     let mut rl = DefaultEditor::new()?;
 
@@ -169,26 +193,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let _ = rl.add_history_entry(query);
 
                 // Handle meta-commands (psql-like)
+                let password_change;
                 let actual_query = if query.starts_with('\\') {
                     match query {
                         "\\q" | "\\quit" => "quit",
-                        "\\l" | "\\list" => "SELECT datname FROM pg_database;",
+                        "\\l" | "\\list" => "\\l",
                         "\\d" | "\\dt" => "SHOW TABLES;",
+                        "\\du" => "\\du",
+                        _ if query.starts_with("\\c ") || query.starts_with("\\connect ") => query,
+                        // v2.7.0: Only the SCRAM verifier leaves the client, never the password
+                        _ if query == "\\password" || query.starts_with("\\password ") => {
+                            let user = query["\\password".len()..].trim();
+                            let user = if user.is_empty() { config.user.as_str() } else { user };
+                            let Some(password) = read_new_password(&mut rl, user) else {
+                                continue;
+                            };
+                            let verifier = postgrustql::types::ScramVerifier::generate(&password);
+                            password_change = format!("ALTER USER {user} PASSWORD '{verifier}'");
+                            &password_change
+                        }
                         "\\?" | "\\h" | "\\help" => {
                             println!("Meta-commands:");
                             println!("  \\q, \\quit          - Quit");
                             println!("  \\l, \\list          - List databases");
+                            println!("  \\c, \\connect <db>  - Switch to another database");
                             println!("  \\d, \\dt            - List tables");
-                            println!("  \\d <table>         - Describe table (not implemented)");
+                            println!("  \\du                - List users");
+                            println!("  \\password [user]   - Change a password (sent hashed)");
+                            println!("  \\d[+] <table>      - Describe table (+ adds column descriptions)");
                             println!("  \\?, \\h, \\help      - Show this help");
                             println!("\nSQL commands: CREATE, INSERT, SELECT, UPDATE, DELETE, etc.");
                             continue;
                         }
-                        _ if query.starts_with("\\d ") => {
-                            // \d table_name - for now just pass as-is
-                            // TODO: implement DESCRIBE table_name
-                            query
-                        }
+                        // v2.7.0: \d table_name / \d+ table_name are described by the server
+                        _ if query.starts_with("\\d ") || query.starts_with("\\d+ ") => query,
                         _ => {
                             println!("Unknown meta-command: {}. Use \\? for help.", query);
                             continue;
@@ -1,6 +1,6 @@
 use clap::{Parser, ValueEnum};
-use postgrustql::storage::StorageEngine;
-use postgrustql::types::{Database, DataType, Table, Value};
+use postgrustql::storage::{format, StorageEngine};
+use postgrustql::types::{AccessMethod, Database, DataType, DescribedObject, Table, Value};
 use std::io::{self, Write};
 use std::fs::File;
 use std::path::PathBuf;
@@ -99,7 +99,7 @@ fn dump_sql(
     Ok(())
 }
 
-/// Dump schema: CREATE TYPE, CREATE TABLE, CREATE INDEX, CREATE VIEW
+/// Dump schema: CREATE TYPE, CREATE TABLE, CREATE INDEX, CREATE VIEW, COMMENT ON
 fn dump_schema(
     db: &Database,
     output: &mut dyn Write,
@@ -110,7 +110,7 @@ fn dump_schema(
     // 1. CREATE TYPE for ENUMs
     if !db.enums.is_empty() {
         writeln!(output, "-- Enums")?;
-        for (enum_name, values) in &db.enums {
+        for (enum_name, values) in db.enums.iter() {
             let values_str = values.iter()
                 .map(|v| format!("'{}'", escape_sql_string(v)))
                 .collect::<Vec<_>>()
@@ -147,6 +147,23 @@ fn dump_schema(
         writeln!(output)?;
     }
 
+    // 5. COMMENT ON (v2.7.0)
+    if db.descriptions.iter().next().is_some() {
+        writeln!(output, "-- Comments")?;
+        for (object, text) in db.descriptions.iter() {
+            let object = match object {
+                DescribedObject::Relation(name) if db.views.contains_key(name) => format!("VIEW {name}"),
+                DescribedObject::Relation(name) if db.indexes.contains_key(name) => format!("INDEX {name}"),
+                DescribedObject::Relation(name) => format!("TABLE {name}"),
+                DescribedObject::Column { table, column } => format!("COLUMN {table}.{column}"),
+                DescribedObject::Type(name) => format!("TYPE {name}"),
+                DescribedObject::Schema(name) => format!("SCHEMA {name}"),
+            };
+            writeln!(output, "COMMENT ON {} IS '{}';", object, escape_sql_string(text))?;
+        }
+        writeln!(output)?;
+    }
+
     Ok(())
 }
 
@@ -155,7 +172,9 @@ fn dump_create_table(
     table: &Table,
     output: &mut dyn Write,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    writeln!(output, "CREATE TABLE {} (", table.name)?;
+    // v2.7.0: Foreign tables keep only their definition; the rows stay in the file
+    let keyword = if table.foreign.is_some() { "CREATE FOREIGN TABLE" } else { "CREATE TABLE" };
+    writeln!(output, "{} {} (", keyword, table.name)?;
 
     let column_definitions: Vec<String> = table.columns.iter().map(|col| {
         let mut def = format!("  {} {}", col.name, datatype_to_sql(&col.data_type));
@@ -180,7 +199,18 @@ fn dump_create_table(
     }).collect();
 
     writeln!(output, "{}", column_definitions.join(",\n"))?;
-    writeln!(output, ");")?;
+    // v2.7.0: Keep the access method of columnar / LSM tables
+    if let Some(ref source) = table.foreign {
+        let options = source.options.iter()
+            .map(|(key, value)| format!("{} '{}'", key, escape_sql_string(value)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(output, ") SERVER {} OPTIONS ({});", source.server, options)?;
+    } else if table.access_method == AccessMethod::Heap {
+        writeln!(output, ");")?;
+    } else {
+        writeln!(output, ") USING {};", table.access_method.name())?;
+    }
 
     Ok(())
 }
@@ -268,12 +298,12 @@ fn dump_data(
     Ok(())
 }
 
-/// Dump database as binary (bincode serialization)
+/// Dump database as binary (bincode serialization, v2.7.0: with a format version header)
 fn dump_binary(
     db: &Database,
     output: &mut dyn Write,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let encoded = bincode::serialize(db)?;
+    let encoded = format::encode(db)?;
     output.write_all(&encoded)?;
     Ok(())
 }
@@ -282,6 +312,7 @@ fn dump_binary(
 fn datatype_to_sql(dt: &DataType) -> String {
     match dt {
         DataType::Integer => "INTEGER".to_string(),
+        DataType::BigInt => "BIGINT".to_string(),
         DataType::SmallInt => "SMALLINT".to_string(),
         DataType::Serial => "SERIAL".to_string(),
         DataType::BigSerial => "BIGSERIAL".to_string(),
@@ -1,7 +1,9 @@
 use clap::{Parser, ValueEnum};
-use postgrustql::parser::parse_statement;
+use postgrustql::network::TextCopyDecoder;
+use postgrustql::network::copy_text::END_OF_DATA;
+use postgrustql::parser::{parse_statement, CopyFormat, InsertSource, Statement};
 use postgrustql::executor::QueryExecutor;
-use postgrustql::storage::{DatabaseStorage, StorageEngine};
+use postgrustql::storage::{format, PageStorage, StorageEngine};
 use postgrustql::types::Database;
 use postgrustql::transaction::GlobalTransactionManager;
 use std::sync::Arc;
@@ -115,13 +117,15 @@ fn restore_sql(
 ) -> Result<usize, Box<dyn std::error::Error>> {
     let mut statements_executed = 0;
     const BUFFER_POOL_SIZE: usize = 1000; // 1000 pages * 8KB = 8MB cache
-    let mut db_storage = DatabaseStorage::new(data_dir, BUFFER_POOL_SIZE)?;
+    // Pages of the restored database, where the server looks for them
+    let db_storage = PageStorage::new(data_dir, BUFFER_POOL_SIZE)?.database(&db.name)?;
+    let mut db_storage = db_storage.blocking_write();
     let tx_manager = Arc::new(GlobalTransactionManager::new());
 
     // Split input into individual SQL statements
     // Simple splitting by semicolon (good enough for dumps)
-    for statement_str in split_sql_statements(input) {
-        let trimmed = statement_str.trim();
+    for script_statement in split_sql_statements(input) {
+        let trimmed = script_statement.sql.trim();
 
         // Skip empty lines and comments
         if trimmed.is_empty() || trimmed.starts_with("--") {
@@ -132,11 +136,23 @@ fn restore_sql(
         let statement = match parse_statement(trimmed) {
             Ok(stmt) => stmt,
             Err(e) => {
-                eprintln!("Parse error in statement:\n{}\nError: {:?}", trimmed, e);
-                return Err(format!("Parse error: {:?}", e).into());
+                eprintln!("Parse error in statement:\n{}\nError: {e}", trimmed);
+                return Err(format!("Parse error: {e}").into());
             }
         };
 
+        // v2.7.0: COPY ... FROM stdin takes the data lines that follow it (pg_dump)
+        let statement = match statement {
+            Statement::Copy { table, columns, from_stdin: true, format } => {
+                if format != CopyFormat::Text {
+                    return Err(format!("COPY data for '{table}' must be in text format").into());
+                }
+                let rows = script_statement.copy_data.iter().map(|line| TextCopyDecoder::decode_line(line)).collect();
+                Statement::InsertMany { table, columns, source: InsertSource::Values(rows) }
+            }
+            statement => statement,
+        };
+
         if dry_run {
             // Just validate, don't execute
             statements_executed += 1;
@@ -170,19 +186,39 @@ fn restore_sql(
     Ok(statements_executed)
 }
 
+/// A statement of the script, with the data lines that follow `COPY ... FROM stdin`
+struct ScriptStatement {
+    sql: String,
+    copy_data: Vec<String>,
+}
+
 /// Split SQL input into individual statements
 /// Handles multi-line statements and comments
-fn split_sql_statements(input: &str) -> Vec<String> {
-    let mut statements = Vec::new();
+///
+/// v2.7.0: After `COPY ... FROM stdin;` every line up to `\.` is data of that
+/// COPY, as in `pg_dump`'s plain format. psql meta-commands between statements
+/// (`\connect`, `\restrict`) are skipped.
+fn split_sql_statements(input: &str) -> Vec<ScriptStatement> {
+    let mut statements: Vec<ScriptStatement> = Vec::new();
     let mut current_statement = String::new();
     let mut in_string = false;
     let mut escape_next = false;
+    let mut in_copy_data = false;
 
     for line in input.lines() {
+        if in_copy_data {
+            if line == END_OF_DATA {
+                in_copy_data = false;
+            } else if let Some(copy) = statements.last_mut() {
+                copy.copy_data.push(line.to_string());
+            }
+            continue;
+        }
+
         let trimmed = line.trim();
 
-        // Skip comment-only lines
-        if trimmed.starts_with("--") && current_statement.is_empty() {
+        // Skip comment-only lines and psql meta-commands
+        if (trimmed.starts_with("--") || trimmed.starts_with('\\')) && current_statement.is_empty() {
             continue;
         }
 
@@ -206,7 +242,9 @@ fn split_sql_statements(input: &str) -> Vec<String> {
                 ';' if !in_string => {
                     // End of statement
                     current_statement.push(ch);
-                    statements.push(current_statement.trim().to_string());
+                    let sql = current_statement.trim().to_string();
+                    in_copy_data = is_copy_from_stdin(&sql);
+                    statements.push(ScriptStatement { sql, copy_data: Vec::new() });
                     current_statement.clear();
                 }
                 _ => {
@@ -223,20 +261,28 @@ fn split_sql_statements(input: &str) -> Vec<String> {
 
     // Add final statement if exists
     if !current_statement.trim().is_empty() {
-        statements.push(current_statement.trim().to_string());
+        statements.push(ScriptStatement { sql: current_statement.trim().to_string(), copy_data: Vec::new() });
     }
 
     statements
 }
 
+/// `COPY ... FROM stdin`, followed by inline data in a script
+fn is_copy_from_stdin(sql: &str) -> bool {
+    matches!(
+        parse_statement(sql),
+        Ok(Statement::Copy { from_stdin: true, .. })
+    )
+}
+
 /// Restore from binary dump
 fn restore_binary(
     data: &[u8],
     db_name: &str,
     storage: &mut StorageEngine,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Deserialize database
-    let db: Database = bincode::deserialize(data)?;
+    // Deserialize database (v2.7.0: dumps without a header are migrated from version 1)
+    let db = format::decode_database(data)?;
 
     // Verify database name matches
     if db.name != db_name {
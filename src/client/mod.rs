@@ -0,0 +1,914 @@
+/// Async client for the `PostgreSQL` wire protocol spoken by this server (v2.7.0)
+///
+/// A deliberately small client: cleartext or SCRAM-SHA-256 password
+/// authentication, simple and extended queries in text format, named
+/// prepared statements and typed row decoding. Enough to drive the
+/// server end-to-end from integration tests and applications without pulling
+/// in `tokio-postgres`.
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), postgrustql::client::ClientError> {
+/// use postgrustql::client::ClientConfig;
+///
+/// let mut client = ClientConfig::new("127.0.0.1:5432")
+///     .with_user("postgres")
+///     .with_password("postgres")
+///     .connect()
+///     .await?;
+/// client.batch_execute("CREATE TABLE t (id INTEGER, name TEXT)").await?;
+/// client.execute("INSERT INTO t (id, name) VALUES ($1, $2)", &[&1_i64, &"Alice"]).await?;
+/// for row in client.query("SELECT id, name FROM t", &[]).await? {
+///     let id: i64 = row.get("id");
+///     let name: String = row.get("name");
+///     println!("{id} {name}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+mod row;
+mod scram;
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use row::{FromSql, Row, RowIndex, ToSql};
+
+use crate::network::pg_protocol::{PROTOCOL_VERSION, backend, error_field, extract_cstring, frontend, transaction_status};
+use scram::ScramClient;
+use bytes::{BufMut, BytesMut};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+/// Authentication request codes sent in `AuthenticationXXX` messages
+const AUTH_OK: i32 = 0;
+const AUTH_CLEARTEXT_PASSWORD: i32 = 3;
+const AUTH_SASL: i32 = 10;
+const AUTH_SASL_CONTINUE: i32 = 11;
+const AUTH_SASL_FINAL: i32 = 12;
+
+/// Messages the client does not act on
+const NOTICE_RESPONSE: u8 = b'N';
+const BACKEND_KEY_DATA: u8 = b'K';
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{severity} {code}: {message}")]
+    Server {
+        severity: String,
+        code: String,
+        message: String,
+    },
+    #[error("Authentication failed: {0}")]
+    Authentication(String),
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+    #[error("Column '{0}' not found")]
+    ColumnNotFound(String),
+    #[error("Decode error: {0}")]
+    Decode(String),
+}
+
+/// Connection settings
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    addr: String,
+    user: String,
+    database: String,
+    password: Option<String>,
+    parameters: Vec<(String, String)>,
+}
+
+impl ClientConfig {
+    /// Settings for `addr` (`host:port`), connecting as `postgres` to database `postgres`
+    #[must_use]
+    pub fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_string(),
+            user: "postgres".to_string(),
+            database: "postgres".to_string(),
+            password: None,
+            parameters: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_user(mut self, user: &str) -> Self {
+        self.user = user.to_string();
+        self
+    }
+
+    #[must_use]
+    pub fn with_database(mut self, database: &str) -> Self {
+        self.database = database.to_string();
+        self
+    }
+
+    #[must_use]
+    pub fn with_password(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// Run-time parameter sent at startup (`application_name`, `options`, ...)
+    #[must_use]
+    pub fn with_parameter(mut self, name: &str, value: &str) -> Self {
+        self.parameters.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Open a connection and authenticate
+    pub async fn connect(&self) -> Result<Client, ClientError> {
+        Client::connect(self).await
+    }
+}
+
+/// Server-side prepared statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    name: String,
+    param_types: Vec<i32>,
+}
+
+impl Statement {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Parameter type OIDs declared at prepare time
+    #[must_use]
+    pub fn param_types(&self) -> &[i32] {
+        &self.param_types
+    }
+}
+
+/// Everything the server sent up to `ReadyForQuery`
+#[derive(Default)]
+struct Response {
+    rows: Vec<Row>,
+    tags: Vec<String>,
+}
+
+pub struct Client {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    parameters: HashMap<String, String>,
+    transaction_status: u8,
+    next_statement: u32,
+}
+
+impl Client {
+    /// Open a connection and authenticate
+    pub async fn connect(config: &ClientConfig) -> Result<Self, ClientError> {
+        let socket = TcpStream::connect(&config.addr).await?;
+        socket.set_nodelay(true)?;
+        let (reader, writer) = socket.into_split();
+        let mut client = Self {
+            reader: BufReader::new(reader),
+            writer,
+            parameters: HashMap::new(),
+            transaction_status: transaction_status::IDLE,
+            next_statement: 0,
+        };
+        client.startup(config).await?;
+        Ok(client)
+    }
+
+    async fn startup(&mut self, config: &ClientConfig) -> Result<(), ClientError> {
+        let mut body = BytesMut::new();
+        body.put_i32(PROTOCOL_VERSION);
+        let parameters = config.parameters.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+        for (key, value) in [("user", config.user.as_str()), ("database", config.database.as_str())]
+            .into_iter()
+            .chain(parameters)
+        {
+            put_cstring(&mut body, key);
+            put_cstring(&mut body, value);
+        }
+        body.put_u8(0);
+
+        let mut startup = BytesMut::new();
+        startup.put_i32(body.len() as i32 + 4);
+        startup.put_slice(&body);
+        self.writer.write_all(&startup).await?;
+
+        let password = || {
+            config
+                .password
+                .as_deref()
+                .ok_or_else(|| ClientError::Authentication("server requested a password".to_string()))
+        };
+        let mut scram: Option<ScramClient> = None;
+        loop {
+            let (msg_type, data) = self.read_message().await?;
+            match msg_type {
+                backend::AUTHENTICATION => match read_i32(&data, 0)? {
+                    AUTH_OK => {}
+                    AUTH_CLEARTEXT_PASSWORD => {
+                        let password = password()?;
+                        let mut msg = BytesMut::new();
+                        put_message(&mut msg, frontend::PASSWORD, |body| put_cstring(body, password));
+                        self.writer.write_all(&msg).await?;
+                    }
+                    AUTH_SASL => {
+                        let mechanisms = parse_sasl_mechanisms(&data[4..]);
+                        if !mechanisms.iter().any(|m| m == scram::MECHANISM) {
+                            return Err(ClientError::Authentication(format!(
+                                "unsupported SASL mechanisms: {}",
+                                mechanisms.join(", ")
+                            )));
+                        }
+                        let (client, client_first) = ScramClient::start(password()?);
+                        scram = Some(client);
+                        let mut msg = BytesMut::new();
+                        put_message(&mut msg, frontend::PASSWORD, |body| {
+                            put_cstring(body, scram::MECHANISM);
+                            body.put_i32(client_first.len() as i32);
+                            body.put_slice(client_first.as_bytes());
+                        });
+                        self.writer.write_all(&msg).await?;
+                    }
+                    AUTH_SASL_CONTINUE => {
+                        let client = scram.as_mut().ok_or_else(|| unexpected_sasl("AuthenticationSASLContinue"))?;
+                        let client_final = client.finish(&String::from_utf8_lossy(&data[4..]))?;
+                        let mut msg = BytesMut::new();
+                        put_message(&mut msg, frontend::PASSWORD, |body| body.put_slice(client_final.as_bytes()));
+                        self.writer.write_all(&msg).await?;
+                    }
+                    AUTH_SASL_FINAL => {
+                        let client = scram.take().ok_or_else(|| unexpected_sasl("AuthenticationSASLFinal"))?;
+                        client.verify(&String::from_utf8_lossy(&data[4..]))?;
+                    }
+                    code => {
+                        return Err(ClientError::Authentication(format!(
+                            "unsupported authentication method: {}",
+                            auth_method_name(code)
+                        )));
+                    }
+                },
+                backend::ERROR_RESPONSE => return Err(parse_error(&data)),
+                backend::PARAMETER_STATUS => self.set_parameter(&data)?,
+                backend::READY_FOR_QUERY => {
+                    self.transaction_status = data.first().copied().unwrap_or(transaction_status::IDLE);
+                    return Ok(());
+                }
+                BACKEND_KEY_DATA | NOTICE_RESPONSE => {}
+                other => {
+                    return Err(ClientError::Protocol(format!("unexpected message '{}' during startup", other as char)));
+                }
+            }
+        }
+    }
+
+    /// Value of a parameter reported by the server (`server_version`, `client_encoding`, ...)
+    #[must_use]
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.get(name).map(String::as_str)
+    }
+
+    /// Transaction status from the last `ReadyForQuery` (see `transaction_status`)
+    #[must_use]
+    pub const fn transaction_status(&self) -> u8 {
+        self.transaction_status
+    }
+
+    /// Run one or more `;`-separated statements with the simple query protocol
+    /// and return the rows of all of them
+    pub async fn simple_query(&mut self, sql: &str) -> Result<Vec<Row>, ClientError> {
+        let mut msg = BytesMut::new();
+        put_message(&mut msg, frontend::QUERY, |body| put_cstring(body, sql));
+        self.writer.write_all(&msg).await?;
+        Ok(self.read_response().await?.rows)
+    }
+
+    /// Run statements with the simple query protocol, discarding any rows
+    pub async fn batch_execute(&mut self, sql: &str) -> Result<(), ClientError> {
+        self.simple_query(sql).await.map(drop)
+    }
+
+    /// Change a user's password the way `psql`'s `\password` does: the SCRAM
+    /// verifier is computed here, so the password itself never reaches the
+    /// server or its logs
+    pub async fn set_password(&mut self, user: &str, password: &str) -> Result<(), ClientError> {
+        let verifier = crate::core::ScramVerifier::generate(password);
+        self.batch_execute(&format!("ALTER USER {user} PASSWORD '{verifier}'")).await
+    }
+
+    /// Run a query with `$1, $2, ...` parameters and return its rows
+    pub async fn query(&mut self, sql: &str, params: &[&dyn ToSql]) -> Result<Vec<Row>, ClientError> {
+        let param_types: Vec<i32> = params.iter().map(|param| param.type_oid()).collect();
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, "", sql, &param_types);
+        put_bind_execute_sync(&mut msg, "", params);
+        self.writer.write_all(&msg).await?;
+        Ok(self.read_response().await?.rows)
+    }
+
+    /// Run a query that must return exactly one row
+    pub async fn query_one(&mut self, sql: &str, params: &[&dyn ToSql]) -> Result<Row, ClientError> {
+        let mut rows = self.query(sql, params).await?;
+        match rows.len() {
+            1 => Ok(rows.remove(0)),
+            n => Err(ClientError::Protocol(format!("query returned {n} rows, expected one"))),
+        }
+    }
+
+    /// Run a statement with parameters and return the number of rows it affected
+    pub async fn execute(&mut self, sql: &str, params: &[&dyn ToSql]) -> Result<u64, ClientError> {
+        let param_types: Vec<i32> = params.iter().map(|param| param.type_oid()).collect();
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, "", sql, &param_types);
+        put_bind_execute_sync(&mut msg, "", params);
+        self.writer.write_all(&msg).await?;
+        Ok(self.read_response().await?.tags.last().map_or(0, |tag| rows_affected(tag)))
+    }
+
+    /// Prepare a named statement; parameters are sent as untyped text
+    pub async fn prepare(&mut self, sql: &str) -> Result<Statement, ClientError> {
+        self.prepare_typed(sql, &[]).await
+    }
+
+    /// Prepare a named statement with parameter type OIDs (see `pg_protocol::oid`)
+    pub async fn prepare_typed(&mut self, sql: &str, param_types: &[i32]) -> Result<Statement, ClientError> {
+        self.next_statement += 1;
+        let statement = Statement {
+            name: format!("s{}", self.next_statement),
+            param_types: param_types.to_vec(),
+        };
+
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, &statement.name, sql, param_types);
+        put_message(&mut msg, frontend::SYNC, |_| {});
+        self.writer.write_all(&msg).await?;
+        self.read_response().await?;
+        Ok(statement)
+    }
+
+    /// Execute a prepared statement and return its rows
+    pub async fn query_prepared(&mut self, statement: &Statement, params: &[&dyn ToSql]) -> Result<Vec<Row>, ClientError> {
+        let mut msg = BytesMut::new();
+        put_bind_execute_sync(&mut msg, &statement.name, params);
+        self.writer.write_all(&msg).await?;
+        Ok(self.read_response().await?.rows)
+    }
+
+    /// Execute a prepared statement and return the number of rows it affected
+    pub async fn execute_prepared(&mut self, statement: &Statement, params: &[&dyn ToSql]) -> Result<u64, ClientError> {
+        let mut msg = BytesMut::new();
+        put_bind_execute_sync(&mut msg, &statement.name, params);
+        self.writer.write_all(&msg).await?;
+        Ok(self.read_response().await?.tags.last().map_or(0, |tag| rows_affected(tag)))
+    }
+
+    /// Release a prepared statement on the server
+    pub async fn close_statement(&mut self, statement: Statement) -> Result<(), ClientError> {
+        let mut msg = BytesMut::new();
+        put_message(&mut msg, frontend::CLOSE, |body| {
+            body.put_u8(b'S');
+            put_cstring(body, &statement.name);
+        });
+        put_message(&mut msg, frontend::SYNC, |_| {});
+        self.writer.write_all(&msg).await?;
+        self.read_response().await.map(drop)
+    }
+
+    /// Say goodbye to the server and close the connection
+    pub async fn close(mut self) -> Result<(), ClientError> {
+        let mut msg = BytesMut::new();
+        put_message(&mut msg, frontend::TERMINATE, |_| {});
+        self.writer.write_all(&msg).await?;
+        self.writer.shutdown().await?;
+        Ok(())
+    }
+
+    /// Collect rows and command tags until `ReadyForQuery`; the first error
+    /// the server reported is returned once the connection is in sync again
+    async fn read_response(&mut self) -> Result<Response, ClientError> {
+        let mut response = Response::default();
+        let mut columns: Arc<Vec<String>> = Arc::default();
+        let mut error = None;
+
+        loop {
+            let (msg_type, data) = self.read_message().await?;
+            match msg_type {
+                backend::ROW_DESCRIPTION => columns = Arc::new(parse_row_description(&data)?),
+                backend::DATA_ROW => response.rows.push(Row::new(Arc::clone(&columns), parse_data_row(&data)?)),
+                backend::COMMAND_COMPLETE => {
+                    let (tag, _) = extract_cstring(&data).unwrap_or_default();
+                    response.tags.push(tag);
+                }
+                backend::ERROR_RESPONSE => {
+                    error.get_or_insert_with(|| parse_error(&data));
+                }
+                backend::PARAMETER_STATUS => self.set_parameter(&data)?,
+                backend::READY_FOR_QUERY => {
+                    self.transaction_status = data.first().copied().unwrap_or(transaction_status::IDLE);
+                    return error.map_or(Ok(response), Err);
+                }
+                // ParseComplete, BindComplete, NoData, notices, ...
+                _ => {}
+            }
+        }
+    }
+
+    async fn read_message(&mut self) -> Result<(u8, Vec<u8>), ClientError> {
+        let msg_type = self.reader.read_u8().await?;
+        let length = self.reader.read_i32().await?;
+        if length < 4 {
+            return Err(ClientError::Protocol(format!("invalid message length {length}")));
+        }
+        let mut data = vec![0u8; length as usize - 4];
+        self.reader.read_exact(&mut data).await?;
+        Ok((msg_type, data))
+    }
+
+    fn set_parameter(&mut self, data: &[u8]) -> Result<(), ClientError> {
+        let invalid = || ClientError::Protocol("invalid ParameterStatus".to_string());
+        let (name, used) = extract_cstring(data).ok_or_else(invalid)?;
+        let (value, _) = extract_cstring(&data[used..]).ok_or_else(invalid)?;
+        self.parameters.insert(name, value);
+        Ok(())
+    }
+}
+
+/// Rows affected according to a command tag
+///
+/// Tags that count rows end with the count (`INSERT 0 3`, `UPDATE 2`, `SELECT 5`).
+fn rows_affected(tag: &str) -> u64 {
+    tag.split_whitespace().next_back().and_then(|word| word.parse().ok()).unwrap_or(0)
+}
+
+pub(crate) fn put_cstring(buf: &mut BytesMut, s: &str) {
+    buf.put_slice(s.as_bytes());
+    buf.put_u8(0);
+}
+
+/// Append a typed frontend message; `fill` writes the body
+pub(crate) fn put_message(buf: &mut BytesMut, msg_type: u8, fill: impl FnOnce(&mut BytesMut)) {
+    let mut body = BytesMut::new();
+    fill(&mut body);
+    buf.put_u8(msg_type);
+    buf.put_i32(body.len() as i32 + 4);
+    buf.put_slice(&body);
+}
+
+pub(crate) fn put_parse(buf: &mut BytesMut, statement: &str, sql: &str, param_types: &[i32]) {
+    put_message(buf, frontend::PARSE, |body| {
+        put_cstring(body, statement);
+        put_cstring(body, sql);
+        body.put_i16(param_types.len() as i16);
+        for &type_oid in param_types {
+            body.put_i32(type_oid);
+        }
+    });
+}
+
+/// Bind `params` to the unnamed portal, describe and execute it and sync
+pub(crate) fn put_bind_execute_sync(buf: &mut BytesMut, statement: &str, params: &[&dyn ToSql]) {
+    put_message(buf, frontend::BIND, |body| {
+        put_cstring(body, "");
+        put_cstring(body, statement);
+        body.put_i16(0); // all parameters in text format
+        body.put_i16(params.len() as i16);
+        for param in params {
+            match param.to_sql() {
+                Some(text) => {
+                    body.put_i32(text.len() as i32);
+                    body.put_slice(text.as_bytes());
+                }
+                None => body.put_i32(-1),
+            }
+        }
+        body.put_i16(0); // all result columns in text format
+    });
+    put_message(buf, frontend::DESCRIBE, |body| {
+        body.put_u8(b'P');
+        put_cstring(body, "");
+    });
+    put_message(buf, frontend::EXECUTE, |body| {
+        put_cstring(body, "");
+        body.put_i32(0); // no row limit
+    });
+    put_message(buf, frontend::SYNC, |_| {});
+}
+
+fn read_i16(data: &[u8], pos: usize) -> Result<i16, ClientError> {
+    data.get(pos..pos + 2)
+        .map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| ClientError::Protocol("truncated message".to_string()))
+}
+
+fn read_i32(data: &[u8], pos: usize) -> Result<i32, ClientError> {
+    data.get(pos..pos + 4)
+        .map(|bytes| i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| ClientError::Protocol("truncated message".to_string()))
+}
+
+/// Mechanism names of `AuthenticationSASL`, a list of strings ended by an empty one
+fn parse_sasl_mechanisms(data: &[u8]) -> Vec<String> {
+    let mut mechanisms = Vec::new();
+    let mut pos = 0;
+    while let Some((name, used)) = extract_cstring(data.get(pos..).unwrap_or_default()) {
+        if name.is_empty() {
+            break;
+        }
+        mechanisms.push(name);
+        pos += used;
+    }
+    mechanisms
+}
+
+fn unexpected_sasl(message: &str) -> ClientError {
+    ClientError::Protocol(format!("unexpected {message} during startup"))
+}
+
+/// Authentication methods a server may ask for that the client does not speak
+fn auth_method_name(code: i32) -> String {
+    match code {
+        2 => "Kerberos V5".to_string(),
+        5 => "MD5 password".to_string(),
+        7 => "GSSAPI".to_string(),
+        9 => "SSPI".to_string(),
+        code => format!("request code {code}"),
+    }
+}
+
+pub(crate) fn parse_row_description(data: &[u8]) -> Result<Vec<String>, ClientError> {
+    let count = read_i16(data, 0)?;
+    let mut pos = 2;
+    let mut columns = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        let (name, used) = extract_cstring(data.get(pos..).unwrap_or_default())
+            .ok_or_else(|| ClientError::Protocol("invalid RowDescription".to_string()))?;
+        // table OID, attribute number, type OID, type size, type modifier, format
+        pos += used + 18;
+        columns.push(name);
+    }
+    Ok(columns)
+}
+
+fn parse_data_row(data: &[u8]) -> Result<Vec<Option<String>>, ClientError> {
+    let count = read_i16(data, 0)?;
+    let mut pos = 2;
+    let mut values = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        let length = read_i32(data, pos)?;
+        pos += 4;
+        if length < 0 {
+            values.push(None);
+            continue;
+        }
+        let bytes = data
+            .get(pos..pos + length as usize)
+            .ok_or_else(|| ClientError::Protocol("truncated DataRow".to_string()))?;
+        values.push(Some(String::from_utf8_lossy(bytes).into_owned()));
+        pos += length as usize;
+    }
+    Ok(values)
+}
+
+fn parse_error(data: &[u8]) -> ClientError {
+    let mut fields = HashMap::new();
+    let mut pos = 0;
+    while let Some(&field) = data.get(pos) {
+        if field == 0 {
+            break;
+        }
+        match extract_cstring(&data[pos + 1..]) {
+            Some((value, used)) => {
+                fields.insert(field, value);
+                pos += 1 + used;
+            }
+            None => break,
+        }
+    }
+    let mut take = |field| fields.remove(&field).unwrap_or_default();
+    ClientError::Server {
+        severity: take(error_field::SEVERITY),
+        code: take(error_field::CODE),
+        message: take(error_field::MESSAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{connect_to, connect_to_new_server};
+    use super::*;
+    use crate::network::Server;
+
+    #[test]
+    fn test_rows_affected() {
+        assert_eq!(rows_affected("INSERT 0 3"), 3);
+        assert_eq!(rows_affected("UPDATE 2"), 2);
+        assert_eq!(rows_affected("DELETE 2"), 2);
+        assert_eq!(rows_affected("CREATE TABLE"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_client_end_to_end() {
+        let mut client = connect_to_new_server().await;
+        let addr = client.writer.peer_addr().unwrap().to_string();
+        assert!(client.parameter("server_version").is_some());
+
+        client.batch_execute("CREATE TABLE users (id INTEGER, name TEXT)").await.unwrap();
+        assert_eq!(client.execute("INSERT INTO users (id, name) VALUES ($1, $2)", &[&1_i64, &"Alice"]).await.unwrap(), 1);
+
+        let insert = client.prepare_typed("INSERT INTO users (id, name) VALUES ($1, $2)", &[23, 25]).await.unwrap();
+        client.execute_prepared(&insert, &[&2_i64, &"Bob"]).await.unwrap();
+        client.close_statement(insert).await.unwrap();
+
+        let row = client.query_one("SELECT id, name FROM users WHERE id = $1", &[&2_i64]).await.unwrap();
+        assert_eq!(row.get::<_, i64>("id"), 2);
+        assert_eq!(row.get::<_, String>(1), "Bob");
+        assert_eq!(client.simple_query("SELECT * FROM users").await.unwrap().len(), 2);
+
+        match client.simple_query("SELECT * FROM missing").await {
+            Err(ClientError::Server { message, .. }) => assert!(message.contains("missing")),
+            other => panic!("expected server error, got {other:?}"),
+        }
+        // The connection is usable after an error
+        assert_eq!(client.query("SELECT name FROM users", &[]).await.unwrap().len(), 2);
+        client.close().await.unwrap();
+
+        let wrong = ClientConfig::new(&addr).with_password("nope").connect().await;
+        assert!(wrong.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scram_authentication() {
+        use crate::network::hba::{HbaConfig, HbaMethod, HbaRule};
+
+        let rule = HbaRule {
+            address: "all".to_string(),
+            database: "all".to_string(),
+            user: "all".to_string(),
+            method: HbaMethod::Scram,
+            map: None,
+        };
+        let server = Server::new_in_memory("postgres", "secret", "postgres").with_hba(HbaConfig::new(vec![rule]));
+        let mut client = connect_to(server).await;
+        assert_eq!(client.simple_query("SELECT 1").await.unwrap()[0].get::<_, i64>(0), 1);
+
+        let addr = client.writer.peer_addr().unwrap().to_string();
+        match ClientConfig::new(&addr).with_password("wrong").connect().await {
+            Err(ClientError::Server { code, .. }) => assert_eq!(code, "28P01"),
+            other => panic!("expected an authentication error, got {:?}", other.map(drop)),
+        }
+        match ClientConfig::new(&addr).connect().await {
+            Err(ClientError::Authentication(message)) => assert_eq!(message, "server requested a password"),
+            other => panic!("expected an authentication error, got {:?}", other.map(drop)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_authentication_method() {
+        // A server that asks for an MD5 password
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let length = socket.read_i32().await.unwrap();
+            let mut startup = vec![0; length as usize - 4];
+            socket.read_exact(&mut startup).await.unwrap();
+            let mut msg = BytesMut::new();
+            put_message(&mut msg, backend::AUTHENTICATION, |body| {
+                body.put_i32(5);
+                body.put_slice(b"salt");
+            });
+            socket.write_all(&msg).await.unwrap();
+        });
+
+        match ClientConfig::new(&addr).with_password("secret").connect().await {
+            Err(ClientError::Authentication(message)) => {
+                assert_eq!(message, "unsupported authentication method: MD5 password");
+            }
+            other => panic!("expected an authentication error, got {:?}", other.map(drop)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_password_policy_and_hashed_change() {
+        let mut client = connect_to(Server::new_in_memory("postgres", "secret", "postgres").with_password_min_length(8)).await;
+        let addr = client.writer.peer_addr().unwrap().to_string();
+
+        match client.batch_execute("CREATE USER app WITH PASSWORD 'short'").await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!(code, "22023");
+                assert_eq!(message, "password is too short (at least 8 characters)");
+            }
+            other => panic!("expected a password policy error, got {other:?}"),
+        }
+        client.batch_execute("CREATE USER app WITH PASSWORD 'first password'").await.unwrap();
+
+        // \password: only the verifier is sent, and it logs in like the password
+        client.set_password("app", "second password").await.unwrap();
+        let login = |password: &str| ClientConfig::new(&addr).with_user("app").with_password(password);
+        assert!(login("first password").connect().await.is_err());
+        assert!(login("second password").connect().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_user_attributes() {
+        let mut admin = connect_to_new_server().await;
+        let addr = admin.writer.peer_addr().unwrap().to_string();
+        let login = |user: &str| ClientConfig::new(&addr).with_user(user).with_password("app password");
+        admin.batch_execute("CREATE USER app WITH PASSWORD 'app password' NOLOGIN").await.unwrap();
+        match login("app").connect().await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!(code, "28000");
+                assert_eq!(message, "role \"app\" is not permitted to log in");
+            }
+            other => panic!("expected a NOLOGIN error, got {:?}", other.map(drop)),
+        }
+
+        // CREATE DATABASE needs CREATEDB
+        admin.batch_execute("ALTER USER app LOGIN").await.unwrap();
+        let mut app = login("app").connect().await.unwrap();
+        let denied = app.batch_execute("CREATE DATABASE shop").await.unwrap_err().to_string();
+        assert!(denied.contains("must have CREATEDB"), "{denied}");
+        admin.batch_execute("ALTER USER app CREATEDB").await.unwrap();
+        app.batch_execute("CREATE DATABASE shop").await.unwrap();
+        // Users are managed with CREATEROLE, but anyone may change their own password
+        assert!(app.batch_execute("CREATE USER other WITH PASSWORD 'x'").await.is_err());
+        assert!(app.batch_execute("ALTER USER app SUPERUSER").await.is_err());
+        app.batch_execute("ALTER USER app PASSWORD 'app password'").await.unwrap();
+
+        // An expired password no longer logs in
+        admin.batch_execute("ALTER USER app VALID UNTIL '2000-01-01'").await.unwrap();
+        assert!(login("app").connect().await.is_err());
+        admin.batch_execute("ALTER USER app VALID UNTIL 'infinity'").await.unwrap();
+        assert!(login("app").connect().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_database_from_template() {
+        let mut admin = connect_to_new_server().await;
+        let addr = admin.writer.peer_addr().unwrap().to_string();
+        let connect = |database: &str| ClientConfig::new(&addr).with_password("secret").with_database(database);
+        let mut template = connect("template1").connect().await.unwrap();
+        template.batch_execute("CREATE FUNCTION add(a int, b int) RETURNS int AS 'SELECT a + b'").await.unwrap();
+        template.batch_execute("CREATE TABLE settings (id INTEGER)").await.unwrap();
+        template.batch_execute("INSERT INTO settings VALUES (1)").await.unwrap();
+
+        // Not while another session is connected to the template
+        match admin.batch_execute("CREATE DATABASE shop").await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!(code, "55006");
+                assert_eq!(message, "database \"template1\" is being accessed by other users");
+            }
+            other => panic!("expected an object in use error, got {other:?}"),
+        }
+        template.close().await.unwrap();
+        // The server forgets the session once the connection is closed
+        let mut created = admin.batch_execute("CREATE DATABASE shop").await;
+        for _ in 0..50 {
+            if created.is_ok() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            created = admin.batch_execute("CREATE DATABASE shop").await;
+        }
+        created.unwrap();
+
+        let mut shop = connect("shop").connect().await.unwrap();
+        let rows = shop.simple_query("SELECT add(1, 2)").await.unwrap();
+        assert_eq!(rows[0].get::<_, String>(0), "3");
+        // The copy has the template's rows, in pages of its own
+        shop.batch_execute("INSERT INTO settings VALUES (2)").await.unwrap();
+        assert_eq!(shop.simple_query("SELECT id FROM settings").await.unwrap().len(), 2);
+        shop.close().await.unwrap();
+
+        // Ordinary databases are copied by their owner only; templates are not dropped
+        admin.batch_execute("CREATE USER app WITH PASSWORD 'app password' CREATEDB").await.unwrap();
+        let mut app = ClientConfig::new(&addr).with_user("app").with_password("app password").connect().await.unwrap();
+        let denied = app.batch_execute("CREATE DATABASE copy TEMPLATE shop").await.unwrap_err().to_string();
+        assert!(denied.contains("must be database owner or superuser to copy database 'shop'"), "{denied}");
+        admin.batch_execute("ALTER DATABASE shop IS_TEMPLATE true").await.unwrap();
+        app.batch_execute("CREATE DATABASE copy TEMPLATE shop").await.unwrap();
+        let mut copy = connect("copy").connect().await.unwrap();
+        assert_eq!(copy.simple_query("SELECT add(2, 2)").await.unwrap()[0].get::<_, String>(0), "4");
+        assert!(admin.batch_execute("DROP DATABASE template1").await.is_err());
+        let mut template = connect("template1").connect().await.unwrap();
+        assert_eq!(template.simple_query("SELECT id FROM settings").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_databases_keep_their_own_tables() {
+        let mut admin = connect_to_new_server().await;
+        let addr = admin.writer.peer_addr().unwrap().to_string();
+        let connect = |database: &str| ClientConfig::new(&addr).with_password("secret").with_database(database);
+        admin.batch_execute("CREATE DATABASE shop").await.unwrap();
+        admin.batch_execute("CREATE DATABASE blog").await.unwrap();
+
+        // Same table name in two databases
+        let mut shop = connect("shop").connect().await.unwrap();
+        let mut blog = connect("blog").connect().await.unwrap();
+        shop.batch_execute("CREATE TABLE users (name TEXT)").await.unwrap();
+        blog.batch_execute("CREATE TABLE users (name TEXT)").await.unwrap();
+        shop.batch_execute("INSERT INTO users VALUES ('alice')").await.unwrap();
+        blog.batch_execute("INSERT INTO users VALUES ('bob'), ('carol')").await.unwrap();
+        let rows = shop.simple_query("SELECT name FROM users").await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get::<_, String>(0), "alice");
+        assert_eq!(blog.simple_query("SELECT name FROM users").await.unwrap().len(), 2);
+
+        // Dropping one leaves the other alone
+        blog.batch_execute("DROP TABLE users").await.unwrap();
+        assert_eq!(shop.simple_query("SELECT name FROM users").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_drop_database_force() {
+        let mut admin = connect_to_new_server().await;
+        let addr = admin.writer.peer_addr().unwrap().to_string();
+        let connect = |database: &str| ClientConfig::new(&addr).with_password("secret").with_database(database);
+        admin.batch_execute("CREATE DATABASE shop").await.unwrap();
+        let mut shop = connect("shop").connect().await.unwrap();
+        shop.batch_execute("CREATE TABLE orders (id INTEGER)").await.unwrap();
+
+        match admin.batch_execute("DROP DATABASE shop").await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!(code, "55006");
+                assert_eq!(message, "database \"shop\" is being accessed by other users");
+            }
+            other => panic!("expected an object in use error, got {other:?}"),
+        }
+        let denied = shop.batch_execute("DROP DATABASE shop WITH (FORCE)").await.unwrap_err().to_string();
+        assert!(denied.contains("cannot drop the currently open database"), "{denied}");
+
+        // FORCE ends the other session
+        admin.batch_execute("DROP DATABASE shop WITH (FORCE)").await.unwrap();
+        assert!(shop.simple_query("SELECT 1").await.is_err());
+        assert!(connect("shop").connect().await.is_err());
+        // The name (and the table name) can be used again
+        admin.batch_execute("CREATE DATABASE shop").await.unwrap();
+        let mut shop = connect("shop").connect().await.unwrap();
+        shop.batch_execute("CREATE TABLE orders (id INTEGER)").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_login_lockout() {
+        let server = Server::new_in_memory("postgres", "secret", "postgres").with_login_lockout(
+            2,
+            std::time::Duration::from_mins(1),
+            std::time::Duration::from_mins(1),
+        );
+        let client = connect_to(server).await;
+        let addr = client.writer.peer_addr().unwrap().to_string();
+        let login = |password: &str| ClientConfig::new(&addr).with_password(password);
+
+        for _ in 0..2 {
+            match login("guess").connect().await {
+                Err(ClientError::Server { message, .. }) => assert_eq!(message, "Authentication failed"),
+                other => panic!("expected an authentication error, got {:?}", other.map(drop)),
+            }
+        }
+        // Locked out: even the right password is refused for now
+        match login("secret").connect().await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!(code, "28000");
+                assert!(message.starts_with("too many failed authentication attempts for user \"postgres\""), "{message}");
+            }
+            other => panic!("expected a lockout error, got {:?}", other.map(drop)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_alter_system() {
+        use crate::network::AutoConfig;
+
+        let data_dir = tempfile::tempdir().unwrap();
+        let data_path = data_dir.path().to_str().unwrap();
+        let mut client = connect_to(Server::new_with_config("postgres", "secret", "postgres", data_path, true).unwrap()).await;
+        let addr = client.writer.peer_addr().unwrap().to_string();
+
+        client.batch_execute("ALTER SYSTEM SET work_mem = 8192").await.unwrap();
+        client.batch_execute("ALTER SYSTEM SET log_level TO 'debug'").await.unwrap();
+        client.batch_execute("alter system set wal_compression = off").await.unwrap();
+        client.batch_execute("ALTER SYSTEM RESET log_level").await.unwrap();
+        let config = AutoConfig::load(data_dir.path()).unwrap();
+        assert_eq!(config.get("work_mem"), Some("8192"));
+        assert_eq!(config.get("wal_compression"), Some("false"));
+        assert_eq!(config.get("log_level"), None);
+
+        let error = client.batch_execute("ALTER SYSTEM SET data_dir = '/tmp'").await.unwrap_err();
+        assert!(error.to_string().contains("cannot be changed with ALTER SYSTEM"), "{error}");
+        client.batch_execute("CREATE USER alice WITH PASSWORD 'alice'").await.unwrap();
+        client.batch_execute("ALTER SYSTEM RESET ALL").await.unwrap();
+        assert_eq!(AutoConfig::load(data_dir.path()).unwrap(), AutoConfig::default());
+
+        // Only superusers change the server configuration
+        let mut alice = ClientConfig::new(&addr).with_user("alice").with_password("alice").connect().await.unwrap();
+        let error = alice.batch_execute("ALTER SYSTEM SET work_mem = 1").await.unwrap_err();
+        assert!(error.to_string().contains("must be superuser"), "{error}");
+
+        // Nothing to persist on an in-memory server
+        let mut client = connect_to_new_server().await;
+        assert!(client.batch_execute("ALTER SYSTEM SET work_mem = 1").await.is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use super::ClientError;
+use crate::network::pg_protocol::oid;
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// One result row in text format (v2.7.0)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    columns: Arc<Vec<String>>,
+    values: Vec<Option<String>>,
+}
+
+impl Row {
+    pub(super) const fn new(columns: Arc<Vec<String>>, values: Vec<Option<String>>) -> Self {
+        Self { columns, values }
+    }
+
+    /// Column names, in result order
+    #[must_use]
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Raw text of a column (`None` for a wire-level NULL)
+    #[must_use]
+    pub fn raw(&self, index: usize) -> Option<&str> {
+        self.values.get(index).and_then(Option::as_deref)
+    }
+
+    /// Decode a column by position or name, panicking on failure
+    ///
+    /// # Panics
+    /// If the column does not exist or cannot be decoded as `T`.
+    #[must_use]
+    pub fn get<I: RowIndex, T: FromSql>(&self, index: I) -> T {
+        match self.try_get(index) {
+            Ok(value) => value,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Decode a column by position or name
+    pub fn try_get<I: RowIndex, T: FromSql>(&self, index: I) -> Result<T, ClientError> {
+        let position = index
+            .position(&self.columns)
+            .ok_or_else(|| ClientError::ColumnNotFound(index.to_string()))?;
+        T::from_sql(self.values[position].as_deref())
+    }
+}
+
+/// Column lookup by position (`usize`) or name (`&str`)
+pub trait RowIndex: std::fmt::Display {
+    fn position(&self, columns: &[String]) -> Option<usize>;
+}
+
+impl RowIndex for usize {
+    fn position(&self, columns: &[String]) -> Option<usize> {
+        (*self < columns.len()).then_some(*self)
+    }
+}
+
+impl RowIndex for &str {
+    fn position(&self, columns: &[String]) -> Option<usize> {
+        columns.iter().position(|column| column == self)
+    }
+}
+
+/// Decode a text-format column value
+///
+/// `raw` is `None` for a NULL field (length -1); `Option<T>` decodes only
+/// that as `None`.
+pub trait FromSql: Sized {
+    fn from_sql(raw: Option<&str>) -> Result<Self, ClientError>;
+}
+
+fn not_null(raw: Option<&str>) -> Result<&str, ClientError> {
+    raw.ok_or_else(|| ClientError::Decode("unexpected NULL".to_string()))
+}
+
+fn parse_as<T: std::str::FromStr>(raw: Option<&str>, type_name: &str) -> Result<T, ClientError> {
+    let text = not_null(raw)?;
+    text.trim()
+        .parse()
+        .map_err(|_| ClientError::Decode(format!("invalid {type_name} value: {text}")))
+}
+
+impl<T: FromSql> FromSql for Option<T> {
+    fn from_sql(raw: Option<&str>) -> Result<Self, ClientError> {
+        match raw {
+            None => Ok(None),
+            Some(_) => T::from_sql(raw).map(Some),
+        }
+    }
+}
+
+impl FromSql for String {
+    fn from_sql(raw: Option<&str>) -> Result<Self, ClientError> {
+        not_null(raw).map(str::to_string)
+    }
+}
+
+impl FromSql for bool {
+    fn from_sql(raw: Option<&str>) -> Result<Self, ClientError> {
+        match not_null(raw)? {
+            "t" | "true" => Ok(true),
+            "f" | "false" => Ok(false),
+            other => Err(ClientError::Decode(format!("invalid boolean value: {other}"))),
+        }
+    }
+}
+
+macro_rules! from_sql_via_parse {
+    ($($ty:ty => $name:literal),* $(,)?) => {
+        $(
+            impl FromSql for $ty {
+                fn from_sql(raw: Option<&str>) -> Result<Self, ClientError> {
+                    parse_as(raw, $name)
+                }
+            }
+        )*
+    };
+}
+
+from_sql_via_parse! {
+    i16 => "smallint",
+    i32 => "integer",
+    i64 => "bigint",
+    f32 => "real",
+    f64 => "double precision",
+    Decimal => "numeric",
+    Uuid => "uuid",
+}
+
+impl FromSql for NaiveDate {
+    fn from_sql(raw: Option<&str>) -> Result<Self, ClientError> {
+        let text = not_null(raw)?;
+        Self::parse_from_str(text, "%Y-%m-%d")
+            .map_err(|_| ClientError::Decode(format!("invalid date value: {text}")))
+    }
+}
+
+impl FromSql for NaiveDateTime {
+    fn from_sql(raw: Option<&str>) -> Result<Self, ClientError> {
+        let text = not_null(raw)?;
+        Self::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+            .map_err(|_| ClientError::Decode(format!("invalid timestamp value: {text}")))
+    }
+}
+
+/// Encode a query parameter in text format, with the type sent in Parse
+pub trait ToSql: Sync {
+    /// Parameter type OID (0 lets the server treat it as text)
+    fn type_oid(&self) -> i32;
+
+    /// Text representation (`None` for NULL)
+    fn to_sql(&self) -> Option<String>;
+}
+
+macro_rules! to_sql_via_display {
+    ($($ty:ty => $oid:expr),* $(,)?) => {
+        $(
+            impl ToSql for $ty {
+                fn type_oid(&self) -> i32 {
+                    $oid
+                }
+
+                fn to_sql(&self) -> Option<String> {
+                    Some(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+to_sql_via_display! {
+    i16 => oid::INT2,
+    i32 => oid::INT4,
+    i64 => oid::INT8,
+    f64 => oid::FLOAT8,
+    bool => oid::BOOL,
+    Decimal => oid::NUMERIC,
+    String => oid::TEXT,
+    Uuid => oid::UUID,
+}
+
+impl ToSql for &str {
+    fn type_oid(&self) -> i32 {
+        oid::TEXT
+    }
+
+    fn to_sql(&self) -> Option<String> {
+        Some((*self).to_string())
+    }
+}
+
+impl ToSql for NaiveDate {
+    fn type_oid(&self) -> i32 {
+        oid::DATE
+    }
+
+    fn to_sql(&self) -> Option<String> {
+        Some(self.format("%Y-%m-%d").to_string())
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn type_oid(&self) -> i32 {
+        self.as_ref().map_or(0, ToSql::type_oid)
+    }
+
+    fn to_sql(&self) -> Option<String> {
+        self.as_ref().and_then(ToSql::to_sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_decoding() {
+        let columns = Arc::new(vec!["id".to_string(), "name".to_string(), "active".to_string(), "note".to_string()]);
+        let row = Row::new(
+            columns,
+            vec![Some("7".to_string()), Some("Alice".to_string()), Some("true".to_string()), None],
+        );
+
+        assert_eq!(row.get::<_, i32>(0), 7);
+        assert_eq!(row.get::<_, String>("name"), "Alice");
+        assert!(row.get::<_, bool>("active"));
+        assert_eq!(row.get::<_, Option<String>>("note"), None);
+        assert!(matches!(row.try_get::<_, String>("note"), Err(ClientError::Decode(_))));
+        assert!(matches!(row.try_get::<_, i32>("missing"), Err(ClientError::ColumnNotFound(_))));
+        assert!(matches!(row.try_get::<_, i64>("name"), Err(ClientError::Decode(_))));
+
+        // Only a NULL field is NULL, not the text
+        let row = Row::new(Arc::new(vec!["note".to_string()]), vec![Some("NULL".to_string())]);
+        assert_eq!(row.get::<_, Option<String>>("note"), Some("NULL".to_string()));
+    }
+}
@@ -0,0 +1,130 @@
+/// SCRAM-SHA-256, the client side of the SASL exchange (v2.7.0)
+///
+/// The counterpart of `network::scram`: the client-first-message carries a
+/// random nonce, the client-final-message proves the password against the
+/// salt and iteration count from the server-first-message, and the
+/// server-final-message is checked so a server that does not know the
+/// password cannot pretend it does. Channel binding is not used.
+use super::ClientError;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// SASL mechanism name
+pub const MECHANISM: &str = "SCRAM-SHA-256";
+
+/// gs2 header without channel binding, base64-encoded in `c=`
+const GS2_HEADER: &str = "n,,";
+
+#[derive(Debug)]
+pub struct ScramClient {
+    password: String,
+    client_first_bare: String,
+    nonce: String,
+    /// Expected in the server-final-message, known after the client-final-message
+    server_signature: Option<Vec<u8>>,
+}
+
+impl ScramClient {
+    /// Start an exchange for `password`; returns the client-first-message
+    pub fn start(password: &str) -> (Self, String) {
+        let mut random = [0u8; 18];
+        getrandom::getrandom(&mut random).expect("system random number generator is unavailable");
+        Self::with_nonce(password, &BASE64.encode(random))
+    }
+
+    fn with_nonce(password: &str, nonce: &str) -> (Self, String) {
+        // The server takes the user name from the startup message
+        let client_first_bare = format!("n=,r={nonce}");
+        let client_first = format!("{GS2_HEADER}{client_first_bare}");
+        let client = Self {
+            password: password.to_string(),
+            client_first_bare,
+            nonce: nonce.to_string(),
+            server_signature: None,
+        };
+        (client, client_first)
+    }
+
+    /// Answer the server-first-message with the client-final-message
+    pub fn finish(&mut self, server_first: &str) -> Result<String, ClientError> {
+        let malformed = || ClientError::Authentication("malformed SCRAM server-first-message".to_string());
+        let nonce = attribute(server_first, 'r').ok_or_else(malformed)?;
+        if !nonce.starts_with(&self.nonce) || nonce.len() == self.nonce.len() {
+            return Err(ClientError::Authentication("SCRAM nonce does not match".to_string()));
+        }
+        let salt = attribute(server_first, 's')
+            .and_then(|salt| BASE64.decode(salt).ok())
+            .ok_or_else(malformed)?;
+        let iterations: u32 = attribute(server_first, 'i')
+            .and_then(|i| i.parse().ok())
+            .filter(|i| *i > 0)
+            .ok_or_else(malformed)?;
+
+        let mut salted = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(self.password.as_bytes(), &salt, iterations, &mut salted);
+        let client_key = hmac(&salted, b"Client Key");
+        let stored_key = Sha256::digest(&client_key);
+        let server_key = hmac(&salted, b"Server Key");
+
+        let without_proof = format!("c={},r={nonce}", BASE64.encode(GS2_HEADER));
+        let auth_message = format!("{},{server_first},{without_proof}", self.client_first_bare);
+        let signature = hmac(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key.iter().zip(signature).map(|(k, s)| k ^ s).collect();
+        self.server_signature = Some(hmac(&server_key, auth_message.as_bytes()));
+        Ok(format!("{without_proof},p={}", BASE64.encode(proof)))
+    }
+
+    /// Check the server-final-message
+    pub fn verify(&self, server_final: &str) -> Result<(), ClientError> {
+        if let Some(error) = attribute(server_final, 'e') {
+            return Err(ClientError::Authentication(format!("SCRAM exchange failed: {error}")));
+        }
+        let signature = attribute(server_final, 'v').and_then(|v| BASE64.decode(v).ok());
+        match (&self.server_signature, signature) {
+            (Some(expected), Some(signature)) if *expected == signature => Ok(()),
+            _ => Err(ClientError::Authentication("invalid SCRAM server signature".to_string())),
+        }
+    }
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Value of the `name=` attribute of a SCRAM message
+fn attribute(message: &str, name: char) -> Option<&str> {
+    message
+        .split(',')
+        .find_map(|part| part.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scram_client() {
+        // RFC 7677's example, where the user name is sent in `n=`
+        let (mut client, client_first) = ScramClient::with_nonce("pencil", "rOprNGfwEbeRWgbNEkqO");
+        assert_eq!(client_first, "n,,n=,r=rOprNGfwEbeRWgbNEkqO");
+        client.client_first_bare = "n=user,r=rOprNGfwEbeRWgbNEkqO".to_string();
+
+        let server_first = "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+        assert_eq!(
+            client.finish(server_first).unwrap(),
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+        );
+        client.verify("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=").unwrap();
+        assert!(client.verify("v=AAAA").is_err());
+        assert!(client.verify("e=invalid-proof").is_err());
+
+        // The server nonce has to extend the client's
+        let (mut client, _) = ScramClient::with_nonce("pencil", "abc");
+        assert!(client.finish("r=xyz123,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096").is_err());
+        assert!(client.finish("r=abc123,i=4096").is_err());
+    }
+}
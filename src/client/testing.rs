@@ -0,0 +1,63 @@
+/// Servers on a free port and clients connected to them, for tests that
+/// drive the server over the wire protocol
+use super::{Client, ClientConfig};
+use crate::network::Server;
+use tokio::io::AsyncWriteExt;
+
+/// Start a server on a free port and return its address
+pub fn spawn(server: Server) -> String {
+    let addr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    };
+    let server_addr = addr.clone();
+    tokio::spawn(async move {
+        let _ = server.start(&server_addr).await;
+    });
+    addr
+}
+
+/// Connect as postgres/secret, waiting for the server to listen
+pub async fn connect(addr: &str) -> Client {
+    let config = ClientConfig::new(addr).with_password("secret");
+    loop {
+        match config.connect().await {
+            Ok(client) => break client,
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(20)).await,
+        }
+    }
+}
+
+pub async fn connect_to(server: Server) -> Client {
+    connect(&spawn(server)).await
+}
+
+pub async fn connect_to_new_server() -> Client {
+    connect_to(Server::new_in_memory("postgres", "secret", "postgres")).await
+}
+
+
+impl Client {
+    /// Write frontend messages as they are
+    pub async fn send_raw(&mut self, messages: &[u8]) {
+        self.writer.write_all(messages).await.unwrap();
+    }
+
+    /// Next backend message: type and body
+    pub async fn receive_raw(&mut self) -> (u8, Vec<u8>) {
+        self.read_message().await.unwrap()
+    }
+
+    /// Backend messages up to and including `ReadyForQuery`
+    pub async fn receive_until_ready(&mut self) -> Vec<(u8, Vec<u8>)> {
+        let mut messages = Vec::new();
+        loop {
+            let message = self.receive_raw().await;
+            let done = message.0 == super::backend::READY_FOR_QUERY;
+            messages.push(message);
+            if done {
+                return messages;
+            }
+        }
+    }
+}
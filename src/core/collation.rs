@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use super::value::{compare_values, Value};
+
+/// Правило сравнения текста столбца: `name TEXT COLLATE "unicode"` (v2.7.0)
+///
+/// Влияет на =, <, >, BETWEEN, IN, LIKE и ORDER BY по столбцу. Значения
+/// других типов сравниваются как обычно (`compare_values`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Collation {
+    /// "C", "POSIX", "default" - побайтовый порядок ("B" < "a")
+    #[default]
+    C,
+    /// "unicode", "und-x-icu", локали вида `en_US.utf8` - по алфавиту
+    /// без учёта регистра, при равенстве - побайтово ("a" < "B" < "b")
+    Unicode,
+    /// `case_insensitive`, "nocase" - регистр не значим ни для порядка, ни
+    /// для равенства, как у типа CITEXT ('ABC' = 'abc')
+    CaseInsensitive,
+}
+
+impl Collation {
+    /// Правило по имени из COLLATE; `None` - такого правила нет
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        match name.as_str() {
+            "c" | "posix" | "default" | "ucs_basic" => Some(Self::C),
+            "unicode" | "und-x-icu" => Some(Self::Unicode),
+            "case_insensitive" | "nocase" | "und-u-ks-level2" => Some(Self::CaseInsensitive),
+            _ if Self::is_locale(&name) => Some(Self::Unicode),
+            _ => None,
+        }
+    }
+
+    /// Имя, под которым правило показывается в каталогах
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::C => "C",
+            Self::Unicode => "unicode",
+            Self::CaseInsensitive => "case_insensitive",
+        }
+    }
+
+    /// Локаль libc или ICU: `en_US`, `de_DE.UTF-8`, "fr-x-icu"
+    fn is_locale(name: &str) -> bool {
+        let base = name.split('.').next().unwrap_or(name);
+        let base = base.strip_suffix("-x-icu").unwrap_or(base);
+        let mut parts = base.split(['_', '-']);
+        let language = parts.next().unwrap_or_default();
+        (2..=3).contains(&language.len())
+            && language.chars().all(|c| c.is_ascii_alphabetic())
+            && parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+    }
+
+    /// Сравнение по правилам SQL с учётом правила для текста
+    ///
+    /// `None` - значения несравнимы, как у `compare_values`.
+    #[must_use]
+    pub fn compare(self, a: &Value, b: &Value) -> Option<Ordering> {
+        let (Some(x), Some(y)) = (Self::text(a), Self::text(b)) else {
+            return compare_values(a, b);
+        };
+        match self {
+            Self::C => compare_values(a, b),
+            Self::Unicode => Some(x.to_lowercase().cmp(&y.to_lowercase()).then_with(|| x.cmp(y))),
+            Self::CaseInsensitive => Some(x.to_lowercase().cmp(&y.to_lowercase())),
+        }
+    }
+
+    /// Равенство по `compare`; NULL здесь равен NULL, как у `values_equal`
+    #[must_use]
+    pub fn equal(self, a: &Value, b: &Value) -> bool {
+        a == b || self.compare(a, b) == Some(Ordering::Equal)
+    }
+
+    /// Порядок для ORDER BY: NULL первым, несравнимые значения равны
+    #[must_use]
+    pub fn compare_for_sort(self, a: &Value, b: &Value) -> Ordering {
+        match (a, b) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => Ordering::Less,
+            (_, Value::Null) => Ordering::Greater,
+            _ => self.compare(a, b).unwrap_or(Ordering::Equal),
+        }
+    }
+
+    /// Текст значения; у CHAR(n) хвостовые пробелы не значимы
+    fn text(value: &Value) -> Option<&str> {
+        match value {
+            Value::Text(text) => Some(text),
+            Value::Char(text) => Some(text.trim_end()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Value {
+        Value::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_collation_order_and_equality() {
+        assert_eq!(Collation::C.compare(&text("Banana"), &text("apple")), Some(Ordering::Less));
+        assert_eq!(Collation::Unicode.compare(&text("Banana"), &text("apple")), Some(Ordering::Greater));
+        assert_eq!(Collation::Unicode.compare(&text("a"), &text("A")), Some(Ordering::Greater));
+        assert!(!Collation::Unicode.equal(&text("a"), &text("A")));
+        assert!(Collation::CaseInsensitive.equal(&text("ÉCOLE"), &text("école")));
+        assert_eq!(Collation::CaseInsensitive.compare(&text("10"), &Value::Integer(9)), Some(Ordering::Greater));
+        assert_eq!(Collation::Unicode.compare_for_sort(&Value::Null, &text("a")), Ordering::Less);
+    }
+
+    #[test]
+    fn test_collation_names() {
+        assert_eq!(Collation::from_name("C"), Some(Collation::C));
+        assert_eq!(Collation::from_name("en_US.utf8"), Some(Collation::Unicode));
+        assert_eq!(Collation::from_name("de-x-icu"), Some(Collation::Unicode));
+        assert_eq!(Collation::from_name("NOCASE"), Some(Collation::CaseInsensitive));
+        assert_eq!(Collation::from_name("no such collation"), None);
+        assert_eq!(Collation::from_name(Collation::CaseInsensitive.name()), Some(Collation::CaseInsensitive));
+    }
+}
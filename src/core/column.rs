@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use super::data_type::DataType;
 use super::constraints::ForeignKey;
+use super::collation::Collation;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Column {
@@ -10,4 +11,6 @@ pub struct Column {
     pub primary_key: bool,
     pub unique: bool,
     pub foreign_key: Option<ForeignKey>,
+    /// v2.7.0: COLLATE - сравнение и порядок текста
+    pub collation: Collation,
 }
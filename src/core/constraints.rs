@@ -5,3 +5,24 @@ pub struct ForeignKey {
     pub referenced_table: String,
     pub referenced_column: String,
 }
+
+/// Named constraint added with ALTER TABLE ... ADD CONSTRAINT (v2.7.0)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TableConstraint {
+    pub name: String,
+    pub kind: ConstraintKind,
+    /// False after ADD CONSTRAINT ... NOT VALID until VALIDATE CONSTRAINT;
+    /// new rows are checked either way
+    pub validated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// CHECK (condition), kept as source like generation expressions
+    Check(String),
+    /// UNIQUE (columns), backed by a unique index named after the constraint
+    Unique(Vec<String>),
+    /// FOREIGN KEY (column) REFERENCES table (column), backed by the column's foreign key
+    ForeignKey { column: String, references: ForeignKey },
+}
+
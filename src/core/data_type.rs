@@ -25,4 +25,35 @@ pub enum DataType {
     Jsonb,  // Binary JSON (stored same as JSON for now)
     Bytea,
     Enum { name: String, values: Vec<String> },
+    BigInt,  // v2.7.0: 64-bit integer; INTEGER is 32-bit (last, so stored schemas keep their tags)
+}
+
+impl DataType {
+    /// SQL type name as shown in catalogs and error messages
+    #[must_use]
+    pub fn sql_name(&self) -> String {
+        match self {
+            Self::Boolean => "boolean".to_string(),
+            Self::SmallInt => "smallint".to_string(),
+            Self::Integer => "integer".to_string(),
+            Self::BigInt => "bigint".to_string(),
+            Self::Serial => "serial".to_string(),
+            Self::BigSerial => "bigserial".to_string(),
+            Self::Real => "real".to_string(),
+            Self::Numeric { precision, scale } => {
+                format!("numeric({precision},{scale})")
+            }
+            Self::Text => "text".to_string(),
+            Self::Varchar { max_length } => format!("varchar({max_length})"),
+            Self::Char { length } => format!("char({length})"),
+            Self::Date => "date".to_string(),
+            Self::Timestamp => "timestamp".to_string(),
+            Self::TimestampTz => "timestamptz".to_string(),
+            Self::Uuid => "uuid".to_string(),
+            Self::Json => "json".to_string(),
+            Self::Jsonb => "jsonb".to_string(),
+            Self::Bytea => "bytea".to_string(),
+            Self::Enum { name, .. } => name.clone(),
+        }
+    }
 }
@@ -1,20 +1,64 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use super::table::Table;
 use super::table_metadata::TableMetadata;
+use super::function::StoredFunction;
+use super::description::Descriptions;
 use super::error::DatabaseError;
+use super::data_type::DataType;
 use crate::index::Index;
+use crate::executor::plan_cache::{CatalogVersion, PlanCache};
+use crate::executor::query_stats::QueryStats;
+use crate::executor::activity::ActivityStats;
+use crate::executor::analyze::Statistics;
+use crate::executor::sessions::SessionRegistry;
+
+/// v2.7.0: OID of a database created on its own (`ServerInstance` numbers
+/// its databases from here on)
+pub const FIRST_DATABASE_OID: u32 = 16384;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Database {
     pub name: String,
+    /// v2.7.0: `pg_database.oid`, also shown in `pg_locks.database`
+    pub oid: u32,
     pub tables: HashMap<String, Table>,
-    pub enums: HashMap<String, Vec<String>>, // enum_name -> allowed values
+    /// Labels of each enum type, by type name (v2.7.0: shared with running
+    /// statements through `with_enum_labels`, copied only when a type changes)
+    pub enums: Arc<HashMap<String, Vec<String>>>,
     #[serde(skip)] // Don't serialize indexes (rebuild on load)
     pub indexes: HashMap<String, Index>, // index_name -> Index (BTree or Hash)
     pub views: HashMap<String, String>, // view_name -> SQL query (v1.10.0)
     /// v2.3.0: Table metadata (owner + privileges)
     pub table_metadata: HashMap<String, TableMetadata>, // table_name -> TableMetadata
+    /// v2.7.0: SQL functions (CREATE FUNCTION)
+    pub functions: HashMap<String, StoredFunction>, // function_name -> definition
+    /// v2.7.0: Descriptions set by COMMENT ON
+    pub descriptions: Descriptions,
+    /// v2.7.0: Parsed statement cache (rebuilt on demand, cleared on DDL)
+    ///
+    /// Shared like `activity`, so sessions holding only a read lock on the
+    /// server instance can still use it.
+    #[serde(skip)]
+    pub plan_cache: Arc<Mutex<PlanCache>>,
+    /// v2.7.0: Bumped by every DDL statement; prepared statements compare it
+    /// to the version they were planned against
+    #[serde(skip)]
+    pub catalog_version: CatalogVersion,
+    /// v2.7.0: Statement statistics for `pg_stat_statements` (in memory only)
+    #[serde(skip)]
+    pub query_stats: Arc<Mutex<QueryStats>>,
+    /// v2.7.0: Scan and tuple counters for `pg_stat_user_tables` / `pg_stat_user_indexes` (in memory only)
+    #[serde(skip)]
+    pub activity: ActivityStats,
+    /// v2.7.0: Column statistics collected by ANALYZE for `pg_stats` and the planner (in memory only)
+    #[serde(skip)]
+    pub statistics: Statistics,
+    /// v2.7.0: Sessions of the whole server for `pg_stat_activity`, shared
+    /// with the `ServerInstance` (see `ServerInstance::share_sessions`)
+    #[serde(skip)]
+    pub sessions: SessionRegistry,
 }
 
 impl Database {
@@ -22,11 +66,20 @@ impl Database {
     pub fn new(name: String) -> Self {
         Self {
             name,
+            oid: FIRST_DATABASE_OID,
             tables: HashMap::new(),
-            enums: HashMap::new(),
+            enums: Arc::default(),
             indexes: HashMap::new(),
             views: HashMap::new(),
             table_metadata: HashMap::new(),
+            functions: HashMap::new(),
+            descriptions: Descriptions::default(),
+            plan_cache: Arc::new(Mutex::new(PlanCache::new())),
+            catalog_version: CatalogVersion::default(),
+            query_stats: Arc::new(Mutex::new(QueryStats::new())),
+            activity: ActivityStats::new(),
+            statistics: Statistics::new(),
+            sessions: SessionRegistry::new(),
         }
     }
 
@@ -34,7 +87,29 @@ impl Database {
         if self.enums.contains_key(&name) {
             return Err(DatabaseError::ParseError(format!("Enum '{name}' already exists")));
         }
-        self.enums.insert(name, values);
+        Arc::make_mut(&mut self.enums).insert(name, values);
+        Ok(())
+    }
+
+    /// v2.7.0: Replace the labels of an existing enum
+    ///
+    /// Columns of this enum type carry their own copy of the labels, so they are
+    /// updated too. Used by ALTER TYPE and WAL replay.
+    pub fn set_enum_values(&mut self, name: &str, values: Vec<String>) -> Result<(), DatabaseError> {
+        let Some(labels) = Arc::make_mut(&mut self.enums).get_mut(name) else {
+            return Err(DatabaseError::ParseError(format!("Type '{name}' does not exist")));
+        };
+        labels.clone_from(&values);
+
+        for table in self.tables.values_mut() {
+            for column in &mut table.columns {
+                if let DataType::Enum { name: type_name, values: column_values } = &mut column.data_type
+                    && type_name == name
+                {
+                    column_values.clone_from(&values);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -73,9 +148,32 @@ impl Database {
         // v2.3.0: Remove table metadata
         self.table_metadata.remove(name);
 
+        // v2.7.0: Indexes go with their table, descriptions with their objects
+        let indexes: Vec<String> = self
+            .indexes
+            .iter()
+            .filter(|(_, index)| index.table_name() == name)
+            .map(|(index_name, _)| index_name.clone())
+            .collect();
+        for index in indexes {
+            self.indexes.remove(&index);
+            self.descriptions.forget_relation(&index);
+        }
+        self.descriptions.forget_relation(name);
+
+        // v2.7.0: Forget activity counters of the table and its indexes
+        self.activity.forget_table(name);
+        self.statistics.forget_table(name);
+
         Ok(())
     }
 
+    /// v2.7.0: Drop view `name` and its description; false if there is no such view
+    pub fn drop_view(&mut self, name: &str) -> bool {
+        self.descriptions.forget_relation(name);
+        self.views.remove(name).is_some()
+    }
+
     /// v2.3.0: Check if user has permission on a table
     ///
     /// Returns true if:
@@ -9,6 +9,9 @@ pub struct DatabaseMetadata {
     pub owner: String,
     /// Права доступа: username -> set of privileges
     pub privileges: HashMap<String, HashSet<Privilege>>,
+    /// v2.7.0: Шаблон - копировать его (CREATE DATABASE ... TEMPLATE) может
+    /// любой пользователь с CREATEDB, удалить нельзя
+    pub is_template: bool,
 }
 
 impl DatabaseMetadata {
@@ -24,6 +27,7 @@ impl DatabaseMetadata {
             name,
             owner,
             privileges,
+            is_template: false,
         }
     }
 
@@ -51,4 +55,23 @@ impl DatabaseMetadata {
             false
         }
     }
+
+    /// v2.7.0: Проверяет, является ли пользователь (или роль) владельцем БД
+    #[must_use]
+    pub fn is_owner(&self, username: &str) -> bool {
+        self.owner == username
+    }
+
+    /// v2.7.0: Передает владение БД (ALTER DATABASE ... OWNER TO)
+    ///
+    /// Права прежнего владельца переходят к новому.
+    pub fn set_owner(&mut self, new_owner: &str) {
+        if let Some(privs) = self.privileges.remove(&self.owner) {
+            self.privileges
+                .entry(new_owner.to_string())
+                .or_default()
+                .extend(privs);
+        }
+        self.owner = new_owner.to_string();
+    }
 }
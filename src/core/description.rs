@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Объект, к которому COMMENT ON привязал описание (v2.7.0)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DescribedObject {
+    /// Таблица, представление или индекс (как в `pg_class`)
+    Relation(String),
+    Column { table: String, column: String },
+    Type(String),
+    Schema(String),
+}
+
+/// Описания объектов базы данных (v2.7.0)
+///
+/// Заполняется COMMENT ON, показывается в `pg_description` и `\d+`.
+/// Описание удаляется вместе с объектом и следует за ним при переименовании.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Descriptions(BTreeMap<DescribedObject, String>);
+
+impl Descriptions {
+    /// Задать описание; `None` или пустая строка удаляют его (как в `PostgreSQL`)
+    pub fn set(&mut self, object: DescribedObject, description: Option<String>) {
+        match description.filter(|text| !text.is_empty()) {
+            Some(text) => {
+                self.0.insert(object, text);
+            }
+            None => {
+                self.0.remove(&object);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, object: &DescribedObject) -> Option<&str> {
+        self.0.get(object).map(String::as_str)
+    }
+
+    /// Все описания, по объекту
+    pub fn iter(&self) -> impl Iterator<Item = (&DescribedObject, &str)> {
+        self.0.iter().map(|(object, text)| (object, text.as_str()))
+    }
+
+    /// Удалить описания отношения и его столбцов
+    pub fn forget_relation(&mut self, name: &str) {
+        self.0.retain(|object, _| match object {
+            DescribedObject::Relation(relation) | DescribedObject::Column { table: relation, .. } => relation != name,
+            _ => true,
+        });
+    }
+
+    pub fn forget_column(&mut self, table: &str, column: &str) {
+        self.0.remove(&Self::column(table, column));
+    }
+
+    /// Перенести описания отношения и его столбцов на новое имя
+    pub fn rename_relation(&mut self, old_name: &str, new_name: &str) {
+        let moved: Vec<DescribedObject> = self
+            .0
+            .keys()
+            .filter(|object| match object {
+                DescribedObject::Relation(relation) | DescribedObject::Column { table: relation, .. } => {
+                    relation == old_name
+                }
+                _ => false,
+            })
+            .cloned()
+            .collect();
+        for object in moved {
+            let text = self.0.remove(&object).unwrap_or_default();
+            let renamed = match object {
+                DescribedObject::Column { column, .. } => Self::column(new_name, &column),
+                _ => DescribedObject::Relation(new_name.to_string()),
+            };
+            self.0.insert(renamed, text);
+        }
+    }
+
+    pub fn rename_column(&mut self, table: &str, old_name: &str, new_name: &str) {
+        if let Some(text) = self.0.remove(&Self::column(table, old_name)) {
+            self.0.insert(Self::column(table, new_name), text);
+        }
+    }
+
+    fn column(table: &str, column: &str) -> DescribedObject {
+        DescribedObject::Column { table: table.to_string(), column: column.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descriptions_follow_their_objects() {
+        let mut descriptions = Descriptions::default();
+        let users = DescribedObject::Relation("users".to_string());
+        descriptions.set(users.clone(), Some("Registered users".to_string()));
+        descriptions.set(
+            DescribedObject::Column { table: "users".to_string(), column: "name".to_string() },
+            Some("Display name".to_string()),
+        );
+        descriptions.set(DescribedObject::Relation("orders".to_string()), Some("Orders".to_string()));
+
+        descriptions.rename_relation("users", "members");
+        descriptions.rename_column("members", "name", "full_name");
+        assert_eq!(descriptions.get(&users), None);
+        assert_eq!(descriptions.get(&DescribedObject::Relation("members".to_string())), Some("Registered users"));
+        let full_name = DescribedObject::Column { table: "members".to_string(), column: "full_name".to_string() };
+        assert_eq!(descriptions.get(&full_name), Some("Display name"));
+
+        descriptions.forget_relation("members");
+        assert_eq!(descriptions.iter().count(), 1);
+        // An empty comment removes the description
+        descriptions.set(DescribedObject::Relation("orders".to_string()), Some(String::new()));
+        assert_eq!(descriptions.iter().count(), 0);
+    }
+}
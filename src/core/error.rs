@@ -40,4 +40,226 @@ pub enum DatabaseError {
     BinarySerialization(String),
     #[error("Parse error: {0}")]
     ParseError(String),
+    /// v2.7.0: Statement text the parser rejects, with the 1-based character
+    /// position of the offending token (SQLSTATE 42601)
+    #[error("Parse error: {message}")]
+    SyntaxError { message: String, position: usize },
+    /// v2.7.0: Arithmetic overflow (SQLSTATE 22003), e.g. "integer out of range"
+    #[error("{0}")]
+    NumericOutOfRange(String),
+    /// v2.7.0: SQLSTATE 22012
+    #[error("division by zero")]
+    DivisionByZero,
+    /// v2.7.0: SERIAL column ran out of values (SQLSTATE 2200H)
+    #[error("nextval: reached maximum value of sequence \"{0}\" ({1})")]
+    SequenceExhausted(String, i64),
+    /// v2.7.0: NULL written to a NOT NULL or PRIMARY KEY column (SQLSTATE 23502)
+    #[error("null value in column \"{1}\" of relation \"{0}\" violates not-null constraint")]
+    NotNullViolation(String, String),
+    /// v2.7.0: Text that does not parse as a value of the type: type and text (SQLSTATE 22P02)
+    #[error("invalid input syntax for type {0}: \"{1}\"")]
+    InvalidTextRepresentation(String, String),
+    /// v2.7.0: Value longer than a VARCHAR(n) / CHAR(n) column allows: the
+    /// column type (SQLSTATE 22001)
+    #[error("value too long for type {0}")]
+    StringDataRightTruncation(String),
+    /// v2.7.0: Label an enum type does not have: type and label (SQLSTATE 22P02)
+    #[error("invalid input value for enum {0}: \"{1}\"")]
+    InvalidEnumValue(String, String),
+    /// v2.7.0: Value of a type that does not convert to the column's: column,
+    /// column type and value type (SQLSTATE 42804)
+    #[error("column \"{0}\" is of type {1} but expression is of type {2}")]
+    DatatypeMismatch(String, String, String),
+    /// v2.7.0: INSERT or UPDATE giving a generated column a value other than
+    /// DEFAULT (SQLSTATE 428C9)
+    #[error("column \"{0}\" is a generated column and can only be set to DEFAULT")]
+    GeneratedAlways(String),
+    /// v2.7.0: SET NOT NULL on a column that holds NULL: table and column (SQLSTATE 23502)
+    #[error("column \"{1}\" of relation \"{0}\" contains null values")]
+    ColumnContainsNulls(String, String),
+    /// v2.7.0: Row for which a CHECK constraint is false: table and constraint (SQLSTATE 23514)
+    #[error("new row for relation \"{0}\" violates check constraint \"{1}\"")]
+    CheckViolation(String, String),
+    /// v2.7.0: ADD or VALIDATE CONSTRAINT found an existing row the
+    /// constraint rejects: table and constraint (SQLSTATE 23514)
+    #[error("check constraint \"{1}\" of relation \"{0}\" is violated by some row")]
+    CheckViolatedByExistingRow(String, String),
+    /// v2.7.0: ALTER TABLE on a constraint the table does not have: constraint and table
+    #[error("constraint \"{0}\" of relation \"{1}\" does not exist")]
+    ConstraintNotFound(String, String),
+    /// v2.7.0: New password rejected by the password policy (SQLSTATE 22023)
+    #[error("password is too short (at least {0} characters)")]
+    PasswordTooShort(usize),
+    /// v2.7.0: Too many failed logins from this client (SQLSTATE 28000)
+    #[error("too many failed authentication attempts for user \"{0}\"; try again in {1} s")]
+    LoginLockedOut(String, u64),
+    /// v2.7.0: User with NOLOGIN (SQLSTATE 28000)
+    #[error("role \"{0}\" is not permitted to log in")]
+    LoginNotPermitted(String),
+    /// v2.7.0: Server certificate, key or CA file that cannot be used
+    #[error("invalid TLS configuration: {0}")]
+    TlsConfig(String),
+    /// v2.7.0: Database copied or dropped while other sessions are connected to it (SQLSTATE 55006)
+    #[error("database \"{0}\" is being accessed by other users")]
+    DatabaseInUse(String),
+    /// v2.7.0: DROP DATABASE of the database the session is connected to (SQLSTATE 55006)
+    #[error("cannot drop the currently open database")]
+    DropOpenDatabase,
+    /// v2.7.0: DROP ... RESTRICT of an object others depend on: the object
+    /// and the dependent objects (SQLSTATE 2BP01)
+    #[error("cannot drop {0} because other objects depend on it: {1}")]
+    DependentObjectsStillExist(String, String),
+    /// v2.7.0: Renaming a table or column views read (SQLSTATE 2BP01)
+    #[error("cannot rename {0} because other objects depend on it: {1}")]
+    DependentObjectsBlockRename(String, String),
+    /// v2.7.0: `pg_undo_transaction` refused: the transaction and why (SQLSTATE 55000)
+    #[error("cannot undo transaction {0}: {1}")]
+    UndoConflict(u64, String),
+    /// v2.7.0: SET TRANSACTION SNAPSHOT names no snapshot exported by a
+    /// transaction in progress (SQLSTATE 22023)
+    #[error("invalid snapshot identifier: \"{0}\"")]
+    InvalidSnapshot(String),
+    /// v2.7.0: Statement that needs BEGIN run outside one (SQLSTATE 25P01)
+    #[error("{0} can only be used in transaction blocks")]
+    NoActiveTransaction(String),
+    /// v2.7.0: Query holding more than `max_query_memory` kB in memory (SQLSTATE 53200)
+    #[error("out of memory: query needs more than max_query_memory ({0} kB)")]
+    OutOfMemory(usize),
+    /// v2.7.0: Temp files of a query past `temp_file_limit` kB (SQLSTATE 53400)
+    #[error("temporary file size exceeds temp_file_limit ({0}kB)")]
+    TempFileLimitExceeded(usize),
+    /// v2.7.0: Query result longer than `max_result_rows` (SQLSTATE 54000)
+    #[error("query result exceeds max_result_rows ({0} rows); add a LIMIT or read it with a cursor")]
+    TooManyResultRows(usize),
+    /// v2.7.0: Result held in memory past `max_result_memory` kB (SQLSTATE 54000)
+    #[error("Query result exceeds max_result_memory ({0} kB); read it with a cursor or LIMIT")]
+    ResultMemoryExceeded(usize),
+    /// v2.7.0: BEGIN inside a transaction block (SQLSTATE 25001)
+    #[error("Transaction already active")]
+    TransactionAlreadyActive,
+    /// v2.7.0: COMMIT or ROLLBACK outside a transaction block (SQLSTATE 25P01)
+    #[error("No active transaction")]
+    NoTransactionInProgress,
+    /// v2.7.0: Bind, Describe or Execute naming an unknown statement (SQLSTATE 26000)
+    #[error("Prepared statement '{0}' not found")]
+    PreparedStatementNotFound(String),
+    /// v2.7.0: Describe or Execute naming an unknown portal (SQLSTATE 34000)
+    #[error("Portal '{0}' not found")]
+    PortalNotFound(String),
+    /// v2.7.0: Frontend message that is malformed or out of place (SQLSTATE 08P01)
+    #[error("{0}")]
+    ProtocolViolation(String),
+    /// v2.7.0: Client not allowed to connect as the user it asked for (SQLSTATE 28000)
+    #[error("{0}")]
+    InvalidAuthorization(String),
+    /// v2.7.0: SET of a parameter fixed for the session (SQLSTATE 55P02)
+    #[error("parameter \"{0}\" cannot be changed")]
+    CantChangeRuntimeParam(String),
+    /// v2.7.0: SHOW or `current_setting` of a parameter the session does not have (SQLSTATE 42704)
+    #[error("unrecognized configuration parameter \"{0}\"")]
+    UnrecognizedParameter(String),
+    /// v2.7.0: Value a run-time parameter does not accept: parameter and value (SQLSTATE 22023)
+    #[error("invalid value for parameter \"{0}\": \"{1}\"")]
+    InvalidParameterValue(String, String),
+    /// v2.7.0: Configuration file that cannot be read or written (SQLSTATE F0000)
+    #[error("{0}")]
+    ConfigFile(String),
+    /// v2.7.0: Request the server cannot carry out as configured (SQLSTATE 0A000)
+    #[error("{0}")]
+    FeatureNotSupported(String),
+    /// v2.7.0: Bind parameter in binary format whose length does not fit its
+    /// type: the type OID (SQLSTATE 22P03)
+    #[error("incorrect binary data format in bind parameter of type {0}")]
+    InvalidBinaryParameter(i32),
+    /// v2.7.0: COPY data that does not decode (SQLSTATE 22P04)
+    #[error("{0}")]
+    BadCopyFormat(String),
+    /// v2.7.0: COPY ended by the client with `CopyFail` (SQLSTATE 57014)
+    #[error("COPY failed by client")]
+    CopyFailed,
+    /// v2.7.0: Storage failure after the statement ran, e.g. a failed checkpoint (SQLSTATE XX000)
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl DatabaseError {
+    /// v2.7.0: SQLSTATE the error is reported with to `PostgreSQL` clients
+    #[must_use]
+    #[allow(clippy::match_same_arms)]
+    pub const fn sqlstate(&self) -> &'static str {
+        match self {
+            Self::TableNotFound(_) => "42P01",              // undefined_table
+            Self::TableAlreadyExists(_) => "42P07",         // duplicate_table
+            Self::ColumnNotFound(_) => "42703",             // undefined_column
+            Self::ColumnCountMismatch => "42601",           // syntax_error
+            Self::TypeMismatch => "42804",                  // datatype_mismatch
+            Self::DatabaseNotFound(_) => "3D000",           // invalid_catalog_name
+            Self::DatabaseAlreadyExists(_) => "42P04",      // duplicate_database
+            Self::UserNotFound(_) => "42704",               // undefined_object
+            Self::UserAlreadyExists(_) => "42710",          // duplicate_object
+            Self::RoleNotFound(_) => "42704",               // undefined_object
+            Self::RoleAlreadyExists(_) => "42710",          // duplicate_object
+            Self::AuthenticationFailed => "28P01",          // invalid_password
+            Self::PermissionDenied(_) => "42501",           // insufficient_privilege
+            Self::ForeignKeyViolation(_) => "23503",        // foreign_key_violation
+            Self::UniqueViolation(_) => "23505",            // unique_violation
+            Self::Io(_) => "58030",                         // io_error
+            Self::Serialization(_) => "XX000",              // internal_error
+            Self::BinarySerialization(_) => "XX000",        // internal_error
+            Self::ParseError(_) => "42601",                 // syntax_error
+            Self::SyntaxError { .. } => "42601",            // syntax_error
+            Self::NumericOutOfRange(_) => "22003",          // numeric_value_out_of_range
+            Self::DivisionByZero => "22012",                // division_by_zero
+            Self::SequenceExhausted(..) => "2200H",         // sequence_generator_limit_exceeded
+            Self::NotNullViolation(..) => "23502",          // not_null_violation
+            Self::InvalidTextRepresentation(..) => "22P02", // invalid_text_representation
+            Self::StringDataRightTruncation(_) => "22001",  // string_data_right_truncation
+            Self::InvalidEnumValue(..) => "22P02",          // invalid_text_representation
+            Self::DatatypeMismatch(..) => "42804",          // datatype_mismatch
+            Self::GeneratedAlways(_) => "428C9",            // generated_always
+            Self::ColumnContainsNulls(..) => "23502",       // not_null_violation
+            Self::CheckViolation(..) => "23514",            // check_violation
+            Self::CheckViolatedByExistingRow(..) => "23514", // check_violation
+            Self::ConstraintNotFound(..) => "42704",        // undefined_object
+            Self::PasswordTooShort(_) => "22023",           // invalid_parameter_value
+            Self::LoginLockedOut(..) => "28000",            // invalid_authorization_specification
+            Self::LoginNotPermitted(_) => "28000",          // invalid_authorization_specification
+            Self::TlsConfig(_) => "F0000",                  // config_file_error
+            Self::DatabaseInUse(_) => "55006",              // object_in_use
+            Self::DropOpenDatabase => "55006",              // object_in_use
+            Self::DependentObjectsStillExist(..) => "2BP01", // dependent_objects_still_exist
+            Self::DependentObjectsBlockRename(..) => "2BP01", // dependent_objects_still_exist
+            Self::UndoConflict(..) => "55000",              // object_not_in_prerequisite_state
+            Self::InvalidSnapshot(_) => "22023",            // invalid_parameter_value
+            Self::NoActiveTransaction(_) => "25P01",        // no_active_sql_transaction
+            Self::OutOfMemory(_) => "53200",                // out_of_memory
+            Self::TempFileLimitExceeded(_) => "53400",      // configuration_limit_exceeded
+            Self::TooManyResultRows(_) => "54000",          // program_limit_exceeded
+            Self::ResultMemoryExceeded(_) => "54000",       // program_limit_exceeded
+            Self::TransactionAlreadyActive => "25001",      // active_sql_transaction
+            Self::NoTransactionInProgress => "25P01",       // no_active_sql_transaction
+            Self::PreparedStatementNotFound(_) => "26000",  // invalid_sql_statement_name
+            Self::PortalNotFound(_) => "34000",             // invalid_cursor_name
+            Self::ProtocolViolation(_) => "08P01",          // protocol_violation
+            Self::InvalidAuthorization(_) => "28000",       // invalid_authorization_specification
+            Self::CantChangeRuntimeParam(_) => "55P02",     // cant_change_runtime_param
+            Self::UnrecognizedParameter(_) => "42704",      // undefined_object
+            Self::InvalidParameterValue(..) => "22023",     // invalid_parameter_value
+            Self::ConfigFile(_) => "F0000",                 // config_file_error
+            Self::FeatureNotSupported(_) => "0A000",        // feature_not_supported
+            Self::InvalidBinaryParameter(_) => "22P03",     // invalid_binary_representation
+            Self::BadCopyFormat(_) => "22P04",              // bad_copy_file_format
+            Self::CopyFailed => "57014",                    // query_canceled
+            Self::Internal(_) => "XX000",                   // internal_error
+        }
+    }
+
+    /// v2.7.0: Character position in the statement text the error points at
+    #[must_use]
+    pub const fn position(&self) -> Option<usize> {
+        match self {
+            Self::SyntaxError { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use super::data_type::DataType;
+
+/// Хранимая SQL-функция (v2.7.0)
+///
+/// Тело хранится как SQL-строка (как и у представлений). Параметры
+/// подставляются в тело при каждом вызове.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredFunction {
+    pub name: String,
+    /// Параметры: (имя, тип)
+    pub params: Vec<(String, DataType)>,
+    pub returns: DataType,
+    /// RETURNS SETOF - функция возвращает набор строк и вызывается в FROM
+    pub returns_set: bool,
+    pub body: String,
+}
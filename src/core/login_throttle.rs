@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use super::error::DatabaseError;
+
+/// Блокировка входа после неудачных попыток (v2.7.0)
+///
+/// Неудачи считаются по паре (пользователь, адрес клиента): после
+/// `max_failures` неудач за `window` попытки с этой пары отклоняются, не
+/// проверяя пароль, пока не пройдёт `lockout`. Успешный вход сбрасывает
+/// счётчик. Простая защита от перебора паролей для открытого в сеть
+/// сервера; состояние не сохраняется на диск.
+#[derive(Debug, Default)]
+pub struct LoginThrottle {
+    policy: LockoutPolicy,
+    attempts: Mutex<HashMap<(String, IpAddr), Attempts>>,
+}
+
+/// Параметры блокировки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    /// Неудач до блокировки (0 - не блокировать)
+    pub max_failures: u32,
+    /// Окно, в котором считаются неудачи
+    pub window: Duration,
+    /// Длительность блокировки
+    pub lockout: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self { max_failures: 0, window: Duration::from_mins(1), lockout: Duration::from_mins(5) }
+    }
+}
+
+/// Недавние неудачи одной пары
+#[derive(Debug, Default)]
+struct Attempts {
+    failures: Vec<Instant>,
+    locked_until: Option<Instant>,
+}
+
+impl LoginThrottle {
+    pub const fn set_policy(&mut self, policy: LockoutPolicy) {
+        self.policy = policy;
+    }
+
+    #[must_use]
+    pub const fn policy(&self) -> LockoutPolicy {
+        self.policy
+    }
+
+    /// Ошибка, если попытки пары сейчас заблокированы
+    pub fn check(&self, username: &str, peer: IpAddr) -> Result<(), DatabaseError> {
+        let attempts = self.attempts.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let now = Instant::now();
+        match attempts.get(&(username.to_string(), peer)).and_then(|a| a.locked_until) {
+            Some(until) if until > now => {
+                Err(DatabaseError::LoginLockedOut(username.to_string(), (until - now).as_secs().max(1)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Учесть неудачную попытку; после `max_failures` пара блокируется
+    pub fn record_failure(&self, username: &str, peer: IpAddr) {
+        if self.policy.max_failures == 0 {
+            return;
+        }
+        let mut attempts = self.attempts.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let now = Instant::now();
+        let window = self.policy.window;
+        // Старые записи не копятся, даже если перебирают имена пользователей
+        attempts.retain(|_, a| {
+            a.locked_until.is_some_and(|until| until > now)
+                || a.failures.last().is_some_and(|last| now.duration_since(*last) < window)
+        });
+
+        let entry = attempts.entry((username.to_string(), peer)).or_default();
+        entry.failures.retain(|at| now.duration_since(*at) < window);
+        entry.failures.push(now);
+        if entry.failures.len() >= self.policy.max_failures as usize {
+            warn!(
+                user = username,
+                client = %peer,
+                failures = entry.failures.len(),
+                lockout_secs = self.policy.lockout.as_secs(),
+                "audit: login locked out after repeated authentication failures"
+            );
+            entry.failures.clear();
+            entry.locked_until = Some(now + self.policy.lockout);
+        }
+    }
+
+    /// Успешный вход сбрасывает счётчик пары
+    pub fn record_success(&self, username: &str, peer: IpAddr) {
+        let mut attempts = self.attempts.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        attempts.remove(&(username.to_string(), peer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockout_after_failures() {
+        let mut throttle = LoginThrottle::default();
+        throttle.set_policy(LockoutPolicy {
+            max_failures: 3,
+            window: Duration::from_mins(1),
+            lockout: Duration::from_millis(200),
+        });
+        let (attacker, neighbour): (IpAddr, IpAddr) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+
+        throttle.record_failure("alice", attacker);
+        throttle.record_failure("alice", attacker);
+        throttle.record_success("alice", attacker);
+        throttle.record_failure("alice", attacker);
+        throttle.record_failure("alice", attacker);
+        assert!(throttle.check("alice", attacker).is_ok());
+
+        throttle.record_failure("alice", attacker);
+        assert!(matches!(throttle.check("alice", attacker), Err(DatabaseError::LoginLockedOut(user, _)) if user == "alice"));
+        // Другие пользователи и адреса не затронуты
+        assert!(throttle.check("bob", attacker).is_ok());
+        assert!(throttle.check("alice", neighbour).is_ok());
+
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(throttle.check("alice", attacker).is_ok());
+
+        // Без политики ничего не блокируется
+        let throttle = LoginThrottle::default();
+        for _ in 0..10 {
+            throttle.record_failure("alice", attacker);
+        }
+        assert!(throttle.check("alice", attacker).is_ok());
+    }
+}
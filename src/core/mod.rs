@@ -13,22 +13,34 @@ pub mod role;
 pub mod database_metadata;
 pub mod table_metadata;
 pub mod server_instance;
+pub mod function;
+pub mod partition;
+pub mod collation;
+pub mod time_zone;
+pub mod login_throttle;
+pub mod description;
 
 // Re-exports for convenience
 pub use error::DatabaseError;
 pub use value::Value;
 pub use data_type::DataType;
-pub use constraints::ForeignKey;
+pub use constraints::{ConstraintKind, ForeignKey, TableConstraint};
 pub use column::Column;
-pub use row::Row;
-pub use table::Table;
+pub use row::{Ctid, Row};
+pub use table::{AccessMethod, ForeignSource, SerialSequence, Table};
 pub use database::Database;
 pub use privilege::Privilege;
-pub use user::User;
+pub use user::{PasswordPolicy, ScramVerifier, User, UserAttributes, UserOptions};
 pub use role::Role;
 pub use database_metadata::DatabaseMetadata;
 pub use table_metadata::TableMetadata;
-pub use server_instance::ServerInstance;
+pub use server_instance::{DEFAULT_TEMPLATE, InstanceSnapshot, ServerInstance};
+pub use function::StoredFunction;
+pub use partition::{Partitioning, PartitionBound, PartitionStrategy};
+pub use collation::Collation;
+pub use time_zone::Zone;
+pub use description::{DescribedObject, Descriptions};
+pub use login_throttle::{LockoutPolicy, LoginThrottle};
 
 #[cfg(test)]
 mod tests {
@@ -72,6 +84,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
             Column {
@@ -80,6 +93,7 @@ mod tests {
                 nullable: false,
                 primary_key: false,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -99,6 +113,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
             Column {
@@ -107,6 +122,7 @@ mod tests {
                 nullable: false,
                 primary_key: false,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -127,6 +143,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -149,6 +166,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
             Column {
@@ -157,6 +175,7 @@ mod tests {
                 nullable: false,
                 primary_key: false,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -184,6 +203,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -204,6 +224,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -228,6 +249,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             },
         ];
@@ -255,4 +277,32 @@ mod tests {
         assert_eq!(Value::Text("hello".to_string()), Value::Text("hello".to_string()));
         assert_eq!(Value::Boolean(true), Value::Boolean(true));
     }
+
+    #[test]
+    fn test_compare_values_across_types() {
+        use std::cmp::Ordering;
+        use value::{compare_values, values_equal};
+
+        assert_eq!(compare_values(&Value::Integer(2), &Value::Real(2.5)), Some(Ordering::Less));
+        assert_eq!(compare_values(&Value::SmallInt(7), &Value::Integer(7)), Some(Ordering::Equal));
+        assert_eq!(
+            compare_values(&Value::Numeric("1.50".parse().unwrap()), &Value::Integer(1)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(compare_values(&Value::Text("10".to_string()), &Value::Integer(9)), Some(Ordering::Greater));
+        assert_eq!(compare_values(&Value::Text("ten".to_string()), &Value::Integer(9)), None);
+
+        let date = Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        let noon = Value::Timestamp(date_time("2024-03-01 12:00:00"));
+        assert_eq!(compare_values(&date, &noon), Some(Ordering::Less));
+        assert_eq!(compare_values(&noon, &Value::Text("2024-03-01 12:00:00".to_string())), Some(Ordering::Equal));
+
+        assert!(values_equal(&Value::Char("ab  ".to_string()), &Value::Text("ab".to_string())));
+        assert!(!values_equal(&Value::Text("ab ".to_string()), &Value::Text("ab".to_string())));
+        assert_eq!(compare_values(&Value::Null, &Value::Integer(1)), None);
+    }
+
+    fn date_time(text: &str) -> chrono::NaiveDateTime {
+        chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
 }
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use super::value::Value;
+
+/// Способ секционирования таблицы (v2.7.0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PartitionStrategy {
+    /// PARTITION BY RANGE (col)
+    Range,
+    /// PARTITION BY LIST (col)
+    List,
+}
+
+/// Границы одной секции (v2.7.0)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PartitionBound {
+    /// FOR VALUES FROM (from) TO (to): from включительно, to исключительно.
+    /// `None` - MINVALUE / MAXVALUE
+    Range { from: Option<Value>, to: Option<Value> },
+    /// FOR VALUES IN (v1, v2, ...)
+    List(Vec<Value>),
+    /// DEFAULT - строки, не попавшие ни в одну другую секцию
+    Default,
+}
+
+impl PartitionBound {
+    /// Попадает ли значение ключа в эту секцию (DEFAULT не учитывается)
+    #[must_use]
+    pub fn contains(&self, key: &Value) -> bool {
+        match self {
+            Self::Range { from, to } => {
+                if matches!(key, Value::Null) {
+                    return false;
+                }
+                let above_from = from
+                    .as_ref()
+                    .is_none_or(|from| compare_values(key, from).is_some_and(Ordering::is_ge));
+                let below_to = to
+                    .as_ref()
+                    .is_none_or(|to| compare_values(key, to).is_some_and(Ordering::is_lt));
+                above_from && below_to
+            }
+            Self::List(values) => values
+                .iter()
+                .any(|value| compare_values(key, value) == Some(Ordering::Equal)),
+            Self::Default => false,
+        }
+    }
+
+    /// Пересекаются ли границы двух секций
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Range { from: a_from, to: a_to }, Self::Range { from: b_from, to: b_to }) => {
+                // [a_from, a_to) и [b_from, b_to) пересекаются, если a_from < b_to и b_from < a_to
+                let before = |from: &Option<Value>, to: &Option<Value>| match (from, to) {
+                    (Some(from), Some(to)) => compare_values(from, to).is_none_or(Ordering::is_lt),
+                    _ => true,
+                };
+                before(a_from, b_to) && before(b_from, a_to)
+            }
+            (Self::List(a), Self::List(b)) => a.iter().any(|value| other.contains(value)) || b.iter().any(|value| self.contains(value)),
+            (Self::Default, Self::Default) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Секционирование таблицы: ключ и список секций (v2.7.0)
+///
+/// Хранится в родительской таблице. Сама родительская таблица строк не
+/// содержит - каждая секция является обычной таблицей с теми же колонками.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Partitioning {
+    pub strategy: PartitionStrategy,
+    /// Колонка ключа секционирования
+    pub column: String,
+    /// (имя таблицы-секции, границы) в порядке создания
+    pub partitions: Vec<(String, PartitionBound)>,
+}
+
+impl Partitioning {
+    #[must_use]
+    pub const fn new(strategy: PartitionStrategy, column: String) -> Self {
+        Self {
+            strategy,
+            column,
+            partitions: Vec::new(),
+        }
+    }
+
+    /// Секция, в которую попадает значение ключа
+    #[must_use]
+    pub fn route(&self, key: &Value) -> Option<&str> {
+        self.partitions
+            .iter()
+            .find(|(_, bound)| bound.contains(key))
+            .or_else(|| self.partitions.iter().find(|(_, bound)| *bound == PartitionBound::Default))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+pub use super::value::compare_values;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(from: i64, to: i64) -> PartitionBound {
+        PartitionBound::Range {
+            from: Some(Value::Integer(from)),
+            to: Some(Value::Integer(to)),
+        }
+    }
+
+    #[test]
+    fn test_route() {
+        let mut partitioning = Partitioning::new(PartitionStrategy::Range, "id".to_string());
+        partitioning.partitions.push(("low".to_string(), range(0, 10)));
+        partitioning.partitions.push(("high".to_string(), range(10, 20)));
+
+        assert_eq!(partitioning.route(&Value::Integer(0)), Some("low"));
+        assert_eq!(partitioning.route(&Value::Integer(10)), Some("high"));
+        assert_eq!(partitioning.route(&Value::Integer(20)), None);
+
+        partitioning.partitions.push(("rest".to_string(), PartitionBound::Default));
+        assert_eq!(partitioning.route(&Value::Integer(20)), Some("rest"));
+        assert_eq!(partitioning.route(&Value::Null), Some("rest"));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(range(0, 10).overlaps(&range(5, 15)));
+        assert!(!range(0, 10).overlaps(&range(10, 20)));
+
+        let a = PartitionBound::List(vec![Value::Text("eu".to_string())]);
+        let b = PartitionBound::List(vec![Value::Text("us".to_string()), Value::Text("eu".to_string())]);
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&PartitionBound::List(vec![Value::Text("us".to_string())])));
+    }
+
+    #[test]
+    fn test_compare_text_and_date() {
+        let date = Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(compare_values(&date, &Value::Text("2024-01-01".to_string())), Some(Ordering::Greater));
+        assert_eq!(compare_values(&Value::SmallInt(3), &Value::Real(2.5)), Some(Ordering::Greater));
+    }
+}
@@ -1,7 +1,24 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use super::value::Value;
 use crate::transaction::Snapshot;
 
+/// Physical location of a row version: page number and slot within the page (v2.7.0)
+///
+/// Written as `(page,item)` with the item number 1-based, like the line
+/// pointers of a `PostgreSQL` tid: slot 0 is `(0,1)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ctid {
+    pub page: u32,
+    pub slot: u16,
+}
+
+impl fmt::Display for Ctid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{})", self.page, u32::from(self.slot) + 1)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Row {
     pub values: Vec<Value>,
@@ -9,6 +26,9 @@ pub struct Row {
     pub xmin: u64,
     /// Transaction ID that deleted this row (None if still visible, for MVCC)
     pub xmax: Option<u64>,
+    /// v2.7.0: Where the row was read from; set by heap pages, never stored
+    #[serde(skip)]
+    pub ctid: Option<Ctid>,
 }
 
 impl Row {
@@ -18,6 +38,7 @@ impl Row {
             values,
             xmin: 0, // Will be set by TransactionManager
             xmax: None,
+            ctid: None,
         }
     }
 
@@ -27,6 +48,7 @@ impl Row {
             values,
             xmin,
             xmax: None,
+            ctid: None,
         }
     }
 
@@ -53,20 +75,27 @@ impl Row {
     /// are never visible to other transactions.
     #[must_use]
     pub fn is_visible_to_snapshot(&self, snapshot: &Snapshot) -> bool {
+        Self::version_visible_to_snapshot(self.xmin, self.xmax, snapshot)
+    }
+
+    /// `is_visible_to_snapshot` for a version known only by its xmin and
+    /// xmax (v2.7.0: see `VersionCounts`)
+    #[must_use]
+    pub fn version_visible_to_snapshot(xmin: u64, xmax: Option<u64>, snapshot: &Snapshot) -> bool {
         // 1. Row created by uncommitted transaction? Invisible
         //    (xmin is in snapshot's active_txs list)
-        if snapshot.active_txs.contains(&self.xmin) {
+        if snapshot.active_txs.contains(&xmin) {
             return false;
         }
 
         // 2. Row created after snapshot was taken? Invisible
         //    (xmin > snapshot.xmax, not >= because xmax is inclusive for current statement)
-        if self.xmin > snapshot.xmax {
+        if xmin > snapshot.xmax {
             return false;
         }
 
         // 3. Check if row was deleted
-        if let Some(xmax) = self.xmax {
+        if let Some(xmax) = xmax {
             // 3a. Deleted by uncommitted transaction? Still visible
             //     (xmax in snapshot's active_txs)
             if snapshot.active_txs.contains(&xmax) {
@@ -123,6 +152,7 @@ mod tests {
             values: vec![],
             xmin: 100,
             xmax: None,
+            ctid: None,
         };
 
         // Alive row is never dead
@@ -137,6 +167,7 @@ mod tests {
             values: vec![],
             xmin: 100,
             xmax: Some(150),
+            ctid: None,
         };
 
         // Dead if xmax <= oldest_active_tx
@@ -151,6 +182,7 @@ mod tests {
             values: vec![],
             xmin: 100,
             xmax: Some(150),
+            ctid: None,
         };
 
         // Not dead if some transaction can still see it
@@ -168,6 +200,7 @@ mod tests {
             values: vec![],
             xmin: 1,
             xmax: None,
+            ctid: None,
         };
 
         // Snapshot taken at tx=2, no active txs
@@ -186,6 +219,7 @@ mod tests {
             values: vec![],
             xmin: 2,
             xmax: None,
+            ctid: None,
         };
 
         // Snapshot with tx=2 as active (uncommitted)
@@ -204,6 +238,7 @@ mod tests {
             values: vec![],
             xmin: 5,
             xmax: None,
+            ctid: None,
         };
 
         // Snapshot taken at tx=3
@@ -222,6 +257,7 @@ mod tests {
             values: vec![],
             xmin: 1,
             xmax: Some(3),
+            ctid: None,
         };
 
         // Snapshot with tx=3 as active (delete not committed yet)
@@ -240,6 +276,7 @@ mod tests {
             values: vec![],
             xmin: 1,
             xmax: Some(5),
+            ctid: None,
         };
 
         // Snapshot taken at tx=3
@@ -258,6 +295,7 @@ mod tests {
             values: vec![],
             xmin: 1,
             xmax: Some(2),
+            ctid: None,
         };
 
         // Snapshot taken at tx=5 (delete already committed)
@@ -1,23 +1,45 @@
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::{HashMap, HashSet};
-use super::database::Database;
+use std::net::IpAddr;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use super::database::{Database, FIRST_DATABASE_OID};
 use super::database_metadata::DatabaseMetadata;
-use super::user::User;
+use super::user::{ScramVerifier, User, UserOptions};
 use super::role::Role;
 use super::privilege::Privilege;
 use super::error::DatabaseError;
+use super::login_throttle::LoginThrottle;
+use crate::executor::sessions::SessionRegistry;
+use crate::storage::{DatabaseStorage, PageStorage};
+
+/// v2.7.0: Шаблон, который создаёт initdb; из него CREATE DATABASE копирует по умолчанию
+pub const DEFAULT_TEMPLATE: &str = "template1";
 
 /// Корневой объект сервера - содержит все БД и пользователей
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// v2.7.0: У каждой БД своя блокировка. Сессии берут блокировку экземпляра
+/// на чтение и блокировку своей БД, так что сессии разных БД работают
+/// параллельно; эксклюзивная блокировка экземпляра нужна только командам
+/// уровня сервера (пользователи, роли, CREATE/DROP DATABASE). Сохраняется
+/// через `InstanceSnapshot`.
+#[derive(Debug, Deserialize)]
 pub struct ServerInstance {
     /// Все базы данных: name -> Database
-    pub databases: HashMap<String, Database>,
+    #[serde(deserialize_with = "deserialize_databases")]
+    pub databases: HashMap<String, RwLock<Database>>,
     /// Метаданные баз данных: name -> `DatabaseMetadata`
     pub database_metadata: HashMap<String, DatabaseMetadata>,
     /// Все пользователи: username -> User
     pub users: HashMap<String, User>,
     /// Все роли: role_name -> Role
     pub roles: HashMap<String, Role>,
+    /// v2.7.0: Подключенные сессии (`pg_stat_activity`), общие для всех БД
+    #[serde(skip)]
+    pub sessions: SessionRegistry,
+    /// v2.7.0: Неудачные попытки входа и блокировки
+    #[serde(skip)]
+    pub login_throttle: LoginThrottle,
 }
 
 impl ServerInstance {
@@ -28,9 +50,51 @@ impl ServerInstance {
             database_metadata: HashMap::new(),
             users: HashMap::new(),
             roles: HashMap::new(),
+            sessions: SessionRegistry::new(),
+            login_throttle: LoginThrottle::default(),
+        }
+    }
+
+    /// v2.7.0: Дает всем БД общий реестр сессий (после загрузки с диска)
+    pub fn share_sessions(&mut self) {
+        for db in self.databases.values_mut() {
+            db.get_mut().sessions = self.sessions.clone();
+        }
+    }
+
+    /// v2.7.0: Нумерует БД, сохраненные без OID (после загрузки с диска), по имени
+    pub fn assign_database_oids(&mut self) {
+        let mut unnumbered: Vec<String> = self
+            .databases
+            .iter_mut()
+            .filter_map(|(name, db)| (db.get_mut().oid == 0).then(|| name.clone()))
+            .collect();
+        unnumbered.sort();
+        for name in unnumbered {
+            let oid = self.next_database_oid();
+            if let Some(db) = self.databases.get_mut(&name) {
+                db.get_mut().oid = oid;
+            }
         }
     }
 
+    /// v2.7.0: OID для новой БД - следующий после наибольшего
+    fn next_database_oid(&mut self) -> u32 {
+        self.databases
+            .values_mut()
+            .map(|db| db.get_mut().oid)
+            .max()
+            .map_or(FIRST_DATABASE_OID, |oid| oid.max(FIRST_DATABASE_OID - 1) + 1)
+    }
+
+    /// v2.7.0: Добавляет БД с новым OID и общим реестром сессий
+    fn insert_database(&mut self, mut db: Database, db_meta: DatabaseMetadata) {
+        db.oid = self.next_database_oid();
+        db.sessions = self.sessions.clone();
+        self.databases.insert(db.name.clone(), RwLock::new(db));
+        self.database_metadata.insert(db_meta.name.clone(), db_meta);
+    }
+
     /// Создает начальную конфигурацию (суперпользователь + БД)
     #[must_use] 
     pub fn initialize(
@@ -44,12 +108,12 @@ impl ServerInstance {
         let superuser = User::new(superuser_name.to_string(), superuser_password, true);
         instance.users.insert(superuser_name.to_string(), superuser);
 
+        // v2.7.0: Пустой template1
+        instance.create_template_database(superuser_name);
+
         // Создаем начальную БД
-        let db = Database::new(initial_db_name.to_string());
         let db_meta = DatabaseMetadata::new(initial_db_name.to_string(), superuser_name.to_string());
-
-        instance.databases.insert(initial_db_name.to_string(), db);
-        instance.database_metadata.insert(initial_db_name.to_string(), db_meta);
+        instance.insert_database(Database::new(initial_db_name.to_string()), db_meta);
 
         instance
     }
@@ -64,6 +128,26 @@ impl ServerInstance {
         Ok(())
     }
 
+    /// Меняет пароль пользователя (v2.7.0)
+    pub fn set_password(&mut self, username: &str, password: &str) -> Result<(), DatabaseError> {
+        let user = self
+            .users
+            .get_mut(username)
+            .ok_or_else(|| DatabaseError::UserNotFound(username.to_string()))?;
+        user.set_password(password);
+        Ok(())
+    }
+
+    /// Меняет атрибуты пользователя (v2.7.0: CREATEDB, LOGIN, VALID UNTIL...)
+    pub fn alter_user(&mut self, username: &str, options: &UserOptions) -> Result<(), DatabaseError> {
+        let user = self
+            .users
+            .get_mut(username)
+            .ok_or_else(|| DatabaseError::UserNotFound(username.to_string()))?;
+        user.apply_options(options);
+        Ok(())
+    }
+
     /// Удаляет пользователя
     pub fn drop_user(&mut self, username: &str) -> Result<(), DatabaseError> {
         if !self.users.contains_key(username) {
@@ -82,34 +166,239 @@ impl ServerInstance {
             return Err(DatabaseError::UserNotFound(owner.to_string()));
         }
 
-        let db = Database::new(db_name.to_string());
         let db_meta = DatabaseMetadata::new(db_name.to_string(), owner.to_string());
+        self.insert_database(Database::new(db_name.to_string()), db_meta);
 
-        self.databases.insert(db_name.to_string(), db);
-        self.database_metadata.insert(db_name.to_string(), db_meta);
+        Ok(())
+    }
 
+    /// v2.7.0: Создает пустой `template1`, если его нет (initdb и каталоги
+    /// данных, созданные до появления шаблонов)
+    pub fn create_template_database(&mut self, owner: &str) {
+        if self.databases.contains_key(DEFAULT_TEMPLATE) {
+            return;
+        }
+        let mut db_meta = DatabaseMetadata::new(DEFAULT_TEMPLATE.to_string(), owner.to_string());
+        db_meta.is_template = true;
+        self.insert_database(Database::new(DEFAULT_TEMPLATE.to_string()), db_meta);
+    }
+
+    /// v2.7.0: Создает БД копией шаблона (CREATE DATABASE ... TEMPLATE)
+    ///
+    /// Копируются схема (таблицы, ENUM, представления, функции, индексы) и
+    /// строки таблиц: из страниц шаблона `source_pages` в страницы новой БД
+    /// `target_pages`. Пока к шаблону подключены другие
+    /// сессии (кроме `own_pid` - сессии, выполняющей команду), копия могла бы
+    /// получиться несогласованной, поэтому это ошибка. Права проверяет
+    /// вызывающий (см. `can_copy_database`).
+    pub fn create_database_from_template(
+        &mut self,
+        db_name: &str,
+        owner: &str,
+        template: &str,
+        own_pid: Option<u32>,
+        source_pages: &DatabaseStorage,
+        target_pages: &mut DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        if self.databases.contains_key(db_name) {
+            return Err(DatabaseError::DatabaseAlreadyExists(db_name.to_string()));
+        }
+        if !self.users.contains_key(owner) {
+            return Err(DatabaseError::UserNotFound(owner.to_string()));
+        }
+        let source = self
+            .databases
+            .get_mut(template)
+            .ok_or_else(|| DatabaseError::DatabaseNotFound(template.to_string()))?
+            .get_mut();
+        if self.sessions.list().iter().any(|session| session.datname == template && Some(session.pid) != own_pid) {
+            return Err(DatabaseError::DatabaseInUse(template.to_string()));
+        }
+
+        Self::copy_table_data(source, source_pages, target_pages)?;
+        let db = Database {
+            tables: source.tables.clone(),
+            enums: source.enums.clone(),
+            // Строки копируются в том же порядке, позиции в индексах остаются верными
+            indexes: source.indexes.clone(),
+            views: source.views.clone(),
+            table_metadata: source.table_metadata.clone(),
+            functions: source.functions.clone(),
+            ..Database::new(db_name.to_string())
+        };
+        let db_meta = DatabaseMetadata::new(db_name.to_string(), owner.to_string());
+        self.insert_database(db, db_meta);
         Ok(())
     }
 
+    /// Создает страничные таблицы копии и переносит в них строки шаблона;
+    /// при ошибке созданные таблицы удаляются
+    fn copy_table_data(
+        source: &Database,
+        source_pages: &DatabaseStorage,
+        target_pages: &mut DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        let mut names: Vec<&String> = source.tables.keys().collect();
+        names.sort();
+
+        let mut created = Vec::new();
+        let copied = names.into_iter().try_for_each(|name| {
+            // Таблицы без страниц (например, временные) не копируются
+            if source_pages.get_paged_table(name).is_none() {
+                return Ok(());
+            }
+            let rows = source_pages.get_all_rows(name)?;
+            target_pages.create_table_using(name.clone(), source.tables[name].access_method)?;
+            created.push(name);
+            rows.into_iter().try_for_each(|row| target_pages.insert(name, row))
+        });
+        if copied.is_err() {
+            for name in created {
+                target_pages.drop_table(name).ok();
+            }
+        }
+        copied
+    }
+
+    /// v2.7.0: Может ли пользователь копировать БД: шаблоны - любой с
+    /// CREATEDB, остальные БД - владелец и суперпользователь
+    #[must_use]
+    pub fn can_copy_database(&self, username: &str, db_name: &str) -> bool {
+        self.database_metadata.get(db_name).is_some_and(|db_meta| db_meta.is_template)
+            || self.is_database_owner_or_superuser(username, db_name)
+    }
+
     /// Удаляет базу данных
+    ///
+    /// v2.7.0: Шаблоны удалить нельзя
     pub fn drop_database(&mut self, db_name: &str) -> Result<(), DatabaseError> {
         if !self.databases.contains_key(db_name) {
             return Err(DatabaseError::DatabaseNotFound(db_name.to_string()));
         }
+        self.check_not_template(db_name)?;
         self.databases.remove(db_name);
         self.database_metadata.remove(db_name);
         Ok(())
     }
 
-    /// Получает БД
+    /// v2.7.0: Удаляет БД вместе со страницами её таблиц (DROP DATABASE)
+    ///
+    /// Пока к БД подключены другие сессии, это ошибка; с `force` они
+    /// завершаются. Сессия `own_pid`, выполняющая команду, не может удалить
+    /// БД, к которой подключена сама.
+    pub fn drop_database_with_data(
+        &mut self,
+        db_name: &str,
+        own_pid: Option<u32>,
+        force: bool,
+        pages: &PageStorage,
+    ) -> Result<(), DatabaseError> {
+        if !self.databases.contains_key(db_name) {
+            return Err(DatabaseError::DatabaseNotFound(db_name.to_string()));
+        }
+        self.check_not_template(db_name)?;
+        let connected = self.sessions.connected_to(db_name);
+        if connected.iter().any(|session| Some(session.pid) == own_pid) {
+            return Err(DatabaseError::DropOpenDatabase);
+        }
+        if !connected.is_empty() && !force {
+            return Err(DatabaseError::DatabaseInUse(db_name.to_string()));
+        }
+        for session in &connected {
+            self.sessions.terminate(session.pid);
+        }
+
+        self.drop_database(db_name)?;
+        pages.drop_database(db_name)
+    }
+
+    fn check_not_template(&self, db_name: &str) -> Result<(), DatabaseError> {
+        if self.database_metadata.get(db_name).is_some_and(|db_meta| db_meta.is_template) {
+            return Err(DatabaseError::PermissionDenied(format!("cannot drop a template database \"{db_name}\"")));
+        }
+        Ok(())
+    }
+
+    /// v2.7.0: Передает владение БД пользователю или роли (ALTER DATABASE ... OWNER TO)
+    pub fn alter_database_owner(&mut self, db_name: &str, new_owner: &str) -> Result<(), DatabaseError> {
+        if !self.users.contains_key(new_owner) && !self.roles.contains_key(new_owner) {
+            return Err(DatabaseError::UserNotFound(new_owner.to_string()));
+        }
+        let db_meta = self
+            .database_metadata
+            .get_mut(db_name)
+            .ok_or_else(|| DatabaseError::DatabaseNotFound(db_name.to_string()))?;
+        db_meta.set_owner(new_owner);
+        Ok(())
+    }
+
+    /// v2.7.0: Делает БД шаблоном или обычной БД (ALTER DATABASE ... `IS_TEMPLATE`)
+    pub fn set_database_template(&mut self, db_name: &str, is_template: bool) -> Result<(), DatabaseError> {
+        let db_meta = self
+            .database_metadata
+            .get_mut(db_name)
+            .ok_or_else(|| DatabaseError::DatabaseNotFound(db_name.to_string()))?;
+        db_meta.is_template = is_template;
+        Ok(())
+    }
+
+    /// Получает БД (v2.7.0: вместе с её блокировкой)
     #[must_use] 
-    pub fn get_database(&self, name: &str) -> Option<&Database> {
+    pub fn get_database(&self, name: &str) -> Option<&RwLock<Database>> {
         self.databases.get(name)
     }
 
     /// Получает мутабельную БД
+    ///
+    /// v2.7.0: Доступ к экземпляру эксклюзивный, поэтому блокировка БД не нужна
     pub fn get_database_mut(&mut self, name: &str) -> Option<&mut Database> {
-        self.databases.get_mut(name)
+        self.databases.get_mut(name).map(RwLock::get_mut)
+    }
+
+    /// v2.7.0: Снимок для checkpoint - ждёт блокировку на чтение каждой БД
+    ///
+    /// БД блокируются в порядке имён, поэтому два снимка не ждут друг друга.
+    /// Вызывающий не должен держать блокировку ни одной БД.
+    pub async fn snapshot(&self) -> InstanceSnapshot<'_> {
+        let mut databases = Vec::with_capacity(self.databases.len());
+        for name in self.database_names() {
+            databases.push((name, self.databases[name].read().await));
+        }
+        InstanceSnapshot { instance: self, databases }
+    }
+
+    /// v2.7.0: Снимок при эксклюзивном доступе (старт сервера, тесты) - без ожидания
+    pub fn snapshot_exclusive(&mut self) -> InstanceSnapshot<'_> {
+        let this: &Self = self;
+        let databases = this
+            .database_names()
+            .into_iter()
+            .map(|name| {
+                let db = this.databases[name]
+                    .try_read()
+                    .expect("no database is locked while the instance is borrowed mutably");
+                (name, db)
+            })
+            .collect();
+        InstanceSnapshot { instance: this, databases }
+    }
+
+    /// Имена БД по порядку
+    fn database_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.databases.keys().collect();
+        names.sort();
+        names
+    }
+
+    /// v2.7.0: Первая по имени БД, кроме шаблонов
+    ///
+    /// Ей достаются данные, у которых нет имени БД: записи WAL и файлы
+    /// страниц старого формата.
+    #[must_use]
+    pub fn default_database(&self) -> Option<&String> {
+        self.database_names()
+            .into_iter()
+            .find(|name| !self.get_database_metadata(name).is_some_and(|db_meta| db_meta.is_template))
     }
 
     /// Получает метаданные БД
@@ -124,15 +413,74 @@ impl ServerInstance {
     }
 
     /// Проверяет пароль пользователя
+    ///
+    /// v2.7.0: Пароль с истёкшим VALID UNTIL не подходит
     #[must_use] 
     pub fn authenticate(&self, username: &str, password: &str) -> bool {
         if let Some(user) = self.users.get(username) {
-            user.verify_password(password)
+            user.verify_password(password) && !user.password_expired()
         } else {
             false
         }
     }
 
+    /// v2.7.0: Может ли пользователь войти (есть и не NOLOGIN) - после
+    /// любого способа аутентификации
+    pub fn check_login(&self, username: &str) -> Result<(), DatabaseError> {
+        match self.users.get(username) {
+            Some(user) if user.can_login() => Ok(()),
+            Some(_) => Err(DatabaseError::LoginNotPermitted(username.to_string())),
+            None => Err(DatabaseError::UserNotFound(username.to_string())),
+        }
+    }
+
+    /// v2.7.0: Может ли пользователь создавать БД (CREATEDB или суперпользователь)
+    #[must_use]
+    pub fn can_create_database(&self, username: &str) -> bool {
+        self.is_superuser(username) || self.users.get(username).is_some_and(User::can_create_db)
+    }
+
+    /// v2.7.0: Может ли пользователь создавать и менять пользователей
+    /// (CREATEROLE или суперпользователь)
+    #[must_use]
+    pub fn can_create_role(&self, username: &str) -> bool {
+        self.is_superuser(username) || self.users.get(username).is_some_and(User::can_create_role)
+    }
+
+    /// v2.7.0: Вход клиента с адреса `peer`: проверка пароля с учётом
+    /// блокировки после неудачных попыток (`LoginThrottle`)
+    pub fn authenticate_from(&self, username: &str, password: &str, peer: IpAddr) -> Result<(), DatabaseError> {
+        self.login_throttle.check(username, peer)?;
+        if self.authenticate(username, password) {
+            self.login_throttle.record_success(username, peer);
+            self.check_login(username)
+        } else {
+            self.login_throttle.record_failure(username, peer);
+            Err(DatabaseError::AuthenticationFailed)
+        }
+    }
+
+    /// v2.7.0: SCRAM-SHA-256 верификатор пароля пользователя; `None`, если
+    /// пользователя нет или пароль сохранён в старом виде
+    #[must_use]
+    pub fn scram_verifier(&self, username: &str) -> Option<ScramVerifier> {
+        self.users.get(username).and_then(|user| ScramVerifier::parse(&user.password_hash))
+    }
+
+    /// v2.7.0: Итог входа по SCRAM-SHA-256 с адреса `peer`: `proof_valid` -
+    /// доказательство клиента сошлось с верификатором. Блокировка и срок
+    /// действия пароля - как в `authenticate_from`
+    pub fn finish_scram_from(&self, username: &str, proof_valid: bool, peer: IpAddr) -> Result<(), DatabaseError> {
+        self.login_throttle.check(username, peer)?;
+        if proof_valid && self.users.get(username).is_some_and(|user| !user.password_expired()) {
+            self.login_throttle.record_success(username, peer);
+            self.check_login(username)
+        } else {
+            self.login_throttle.record_failure(username, peer);
+            Err(DatabaseError::AuthenticationFailed)
+        }
+    }
+
     /// Создает роль
     pub fn create_role(&mut self, role_name: &str, is_superuser: bool) -> Result<(), DatabaseError> {
         if self.roles.contains_key(role_name) {
@@ -237,17 +585,35 @@ impl ServerInstance {
         }
     }
 
+    /// v2.7.0: Является ли пользователь суперпользователем (напрямую или через роль)
+    #[must_use]
+    pub fn is_superuser(&self, username: &str) -> bool {
+        if self.users.get(username).is_some_and(User::is_superuser) {
+            return true;
+        }
+        self.get_user_roles(username)
+            .iter()
+            .any(|role_name| self.roles.get(role_name).is_some_and(|role| role.is_superuser))
+    }
+
+    /// v2.7.0: Действует ли пользователь от имени `grantee` (это он сам или одна из его ролей)
+    #[must_use]
+    pub fn is_member_of(&self, username: &str, grantee: &str) -> bool {
+        username == grantee || self.get_user_roles(username).contains(grantee)
+    }
+
     /// Проверяет, есть ли у пользователя право на БД (с учетом ролей)
     pub fn check_privilege(&self, username: &str, db_name: &str, privilege: &Privilege) -> Result<bool, DatabaseError> {
         // Суперпользователь имеет все права
         if let Some(user) = self.users.get(username)
-            && user.is_superuser {
+            && user.is_superuser() {
                 return Ok(true);
             }
 
         // Проверяем права в метаданных БД для пользователя
         if let Some(db_meta) = self.database_metadata.get(db_name) {
-            if db_meta.has_privilege(username, privilege) {
+            // v2.7.0: Владелец БД (или член роли-владельца) имеет все права
+            if db_meta.has_privilege(username, privilege) || self.is_member_of(username, &db_meta.owner) {
                 return Ok(true);
             }
 
@@ -283,13 +649,13 @@ impl ServerInstance {
     pub fn check_table_permission(
         &self,
         username: &str,
-        db_name: &str,
+        db: &Database,
         table_name: &str,
         privilege: &Privilege,
     ) -> bool {
         // Суперпользователь имеет все права
         if let Some(user) = self.users.get(username) {
-            if user.is_superuser {
+            if user.is_superuser() {
                 return true;
             }
         }
@@ -304,22 +670,15 @@ impl ServerInstance {
             }
         }
 
-        // Проверяем права на таблицу в базе данных
-        if let Some(db) = self.databases.get(db_name) {
-            // Проверяем права пользователя
-            if db.check_table_permission(username, table_name, privilege.clone()) {
-                return true;
-            }
-
-            // Проверяем права через роли
-            for role_name in &user_roles {
-                if db.check_table_permission(role_name, table_name, privilege.clone()) {
-                    return true;
-                }
-            }
+        // Проверяем права пользователя на таблицу в базе данных
+        if db.check_table_permission(username, table_name, privilege.clone()) {
+            return true;
         }
 
-        false
+        // Проверяем права через роли
+        user_roles
+            .iter()
+            .any(|role_name| db.check_table_permission(role_name, table_name, privilege.clone()))
     }
 
     /// v2.3.0: Проверяет, является ли пользователь владельцем таблицы или суперпользователем
@@ -327,32 +686,75 @@ impl ServerInstance {
     pub fn is_table_owner_or_superuser(
         &self,
         username: &str,
-        db_name: &str,
+        db: &Database,
         table_name: &str,
     ) -> bool {
         // Суперпользователь имеет все права
-        if let Some(user) = self.users.get(username) {
-            if user.is_superuser {
-                return true;
-            }
+        if self.is_superuser(username) {
+            return true;
         }
 
-        // Проверяем через роли на суперпользователя
-        let user_roles = self.get_user_roles(username);
-        for role_name in &user_roles {
-            if let Some(role) = self.roles.get(role_name) {
-                if role.is_superuser {
-                    return true;
-                }
-            }
-        }
+        // Проверяем владение таблицей (v2.7.0: в том числе через роль-владельца)
+        db.table_metadata
+            .get(table_name)
+            .is_some_and(|metadata| self.is_member_of(username, &metadata.owner))
+    }
 
-        // Проверяем владение таблицей
-        if let Some(db) = self.databases.get(db_name) {
-            db.is_table_owner(username, table_name)
-        } else {
-            false
+    /// v2.7.0: Проверяет, является ли пользователь владельцем БД или суперпользователем
+    #[must_use]
+    pub fn is_database_owner_or_superuser(&self, username: &str, db_name: &str) -> bool {
+        if self.is_superuser(username) {
+            return true;
         }
+
+        self.database_metadata
+            .get(db_name)
+            .is_some_and(|db_meta| self.is_member_of(username, &db_meta.owner))
+    }
+}
+
+/// v2.7.0: Базы данных загружаются без блокировок
+fn deserialize_databases<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, RwLock<Database>>, D::Error> {
+    let databases = HashMap::<String, Database>::deserialize(deserializer)?;
+    Ok(databases.into_iter().map(|(name, db)| (name, RwLock::new(db))).collect())
+}
+
+/// v2.7.0: Согласованный снимок `ServerInstance` для checkpoint
+///
+/// Держит блокировку на чтение каждой БД, пока жив. Сериализуется с теми же
+/// полями, что и `ServerInstance`, и читается обратно как `ServerInstance`.
+/// Snapshot'ы прежних версий читает `storage::format`.
+pub struct InstanceSnapshot<'a> {
+    instance: &'a ServerInstance,
+    databases: Vec<(&'a String, RwLockReadGuard<'a, Database>)>,
+}
+
+impl<'a> InstanceSnapshot<'a> {
+    /// v2.7.0: Экземпляр, с которого снят снимок (пользователи, роли, метаданные БД)
+    #[must_use]
+    pub const fn instance(&self) -> &'a ServerInstance {
+        self.instance
+    }
+
+    /// v2.7.0: БД снимка в порядке имён
+    pub fn databases(&self) -> impl Iterator<Item = (&String, &Database)> {
+        self.databases.iter().map(|(name, db)| (*name, &**db))
+    }
+}
+
+impl Serialize for InstanceSnapshot<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let databases: HashMap<&String, &Database> =
+            self.databases.iter().map(|(name, db)| (*name, &**db)).collect();
+
+        let mut state = serializer.serialize_struct("ServerInstance", 4)?;
+        state.serialize_field("databases", &databases)?;
+        state.serialize_field("database_metadata", &self.instance.database_metadata)?;
+        state.serialize_field("users", &self.instance.users)?;
+        state.serialize_field("roles", &self.instance.roles)?;
+        state.end()
     }
 }
 
@@ -365,14 +767,24 @@ impl Default for ServerInstance {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::Table;
-    use crate::types::Column;
+    use crate::core::{Row, Table, Value};
+    use crate::types::{Collation, Column};
     use crate::types::DataType;
 
     fn create_test_instance() -> ServerInstance {
         ServerInstance::initialize("postgres", "password", "testdb")
     }
 
+    fn can(inst: &ServerInstance, username: &str, table: &str, privilege: &Privilege) -> bool {
+        let db = inst.get_database("testdb").unwrap().try_read().unwrap();
+        inst.check_table_permission(username, &db, table, privilege)
+    }
+
+    fn owns(inst: &ServerInstance, username: &str, table: &str) -> bool {
+        let db = inst.get_database("testdb").unwrap().try_read().unwrap();
+        inst.is_table_owner_or_superuser(username, &db, table)
+    }
+
     #[test]
     fn test_create_role() {
         let mut inst = create_test_instance();
@@ -470,6 +882,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
             "alice".to_string(),
         );
@@ -480,7 +893,7 @@ mod tests {
         } // Drop mutable borrow
 
         // Check ownership
-        let db = inst.get_database("testdb").unwrap();
+        let db = inst.get_database_mut("testdb").unwrap();
         assert!(db.is_table_owner("alice", "users"));
         assert!(!db.is_table_owner("postgres", "users"));
     }
@@ -503,6 +916,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
             "alice".to_string(),
         );
@@ -513,11 +927,11 @@ mod tests {
         } // Drop mutable borrow
 
         // Alice (owner) should have all permissions
-        assert!(inst.check_table_permission("alice", "testdb", "orders", &Privilege::Select));
-        assert!(inst.check_table_permission("alice", "testdb", "orders", &Privilege::Insert));
+        assert!(can(&inst, "alice", "orders", &Privilege::Select));
+        assert!(can(&inst, "alice", "orders", &Privilege::Insert));
 
         // Bob should NOT have permissions
-        assert!(!inst.check_table_permission("bob", "testdb", "orders", &Privilege::Select));
+        assert!(!can(&inst, "bob", "orders", &Privilege::Select));
 
         // Grant SELECT to bob
         {
@@ -527,8 +941,8 @@ mod tests {
         } // Drop mutable borrow
 
         // Now bob should have SELECT but not INSERT
-        assert!(inst.check_table_permission("bob", "testdb", "orders", &Privilege::Select));
-        assert!(!inst.check_table_permission("bob", "testdb", "orders", &Privilege::Insert));
+        assert!(can(&inst, "bob", "orders", &Privilege::Select));
+        assert!(!can(&inst, "bob", "orders", &Privilege::Insert));
     }
 
     #[test]
@@ -548,6 +962,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
             "alice".to_string(),
         );
@@ -558,9 +973,9 @@ mod tests {
         } // Drop mutable borrow
 
         // postgres (superuser) should have all permissions even without grants
-        assert!(inst.check_table_permission("postgres", "testdb", "data", &Privilege::Select));
-        assert!(inst.check_table_permission("postgres", "testdb", "data", &Privilege::Insert));
-        assert!(inst.check_table_permission("postgres", "testdb", "data", &Privilege::Delete));
+        assert!(can(&inst, "postgres", "data", &Privilege::Select));
+        assert!(can(&inst, "postgres", "data", &Privilege::Insert));
+        assert!(can(&inst, "postgres", "data", &Privilege::Delete));
     }
 
     #[test]
@@ -583,6 +998,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
             "alice".to_string(),
         );
@@ -597,8 +1013,8 @@ mod tests {
         } // Drop mutable borrow
 
         // Bob should have SELECT through role membership
-        assert!(inst.check_table_permission("bob", "testdb", "products", &Privilege::Select));
-        assert!(!inst.check_table_permission("bob", "testdb", "products", &Privilege::Insert));
+        assert!(can(&inst, "bob", "products", &Privilege::Select));
+        assert!(!can(&inst, "bob", "products", &Privilege::Insert));
     }
 
     #[test]
@@ -618,6 +1034,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
             "alice".to_string(),
         );
@@ -628,13 +1045,169 @@ mod tests {
         } // Drop mutable borrow
 
         // Alice should be owner
-        assert!(inst.is_table_owner_or_superuser("alice", "testdb", "test_table"));
+        assert!(owns(&inst, "alice", "test_table"));
 
         // postgres should be superuser
-        assert!(inst.is_table_owner_or_superuser("postgres", "testdb", "test_table"));
+        assert!(owns(&inst, "postgres", "test_table"));
 
         // Create another user who is neither owner nor superuser
         inst.create_user("bob", "password", false).unwrap();
-        assert!(!inst.is_table_owner_or_superuser("bob", "testdb", "test_table"));
+        assert!(!owns(&inst, "bob", "test_table"));
+
+        // v2.7.0: Members of an owning role act as owners
+        inst.create_role("maintainers", false).unwrap();
+        inst.grant_role_to_user("maintainers", "bob").unwrap();
+        inst.get_database_mut("testdb")
+            .unwrap()
+            .table_metadata
+            .get_mut("test_table")
+            .unwrap()
+            .set_owner("maintainers");
+        assert!(owns(&inst, "bob", "test_table"));
+        assert!(!owns(&inst, "alice", "test_table"));
+    }
+
+    #[test]
+    fn test_alter_database_owner() {
+        let mut inst = create_test_instance();
+        inst.create_user("alice", "password", false).unwrap();
+        inst.create_user("bob", "password", false).unwrap();
+        inst.create_database("shop", "alice").unwrap();
+
+        assert!(inst.is_database_owner_or_superuser("alice", "shop"));
+        assert!(inst.check_privilege("alice", "shop", &Privilege::Create).unwrap());
+        assert!(!inst.is_database_owner_or_superuser("bob", "shop"));
+        assert!(!inst.check_privilege("bob", "shop", &Privilege::Create).unwrap());
+
+        inst.alter_database_owner("shop", "bob").unwrap();
+        assert_eq!(inst.get_database_metadata("shop").unwrap().owner, "bob");
+        assert!(inst.is_database_owner_or_superuser("bob", "shop"));
+        assert!(inst.check_privilege("bob", "shop", &Privilege::Create).unwrap());
+        assert!(!inst.is_database_owner_or_superuser("alice", "shop"));
+        assert!(!inst.check_privilege("alice", "shop", &Privilege::Create).unwrap());
+
+        // Superusers keep full access; unknown owners are rejected
+        assert!(inst.is_database_owner_or_superuser("postgres", "shop"));
+        assert!(inst.alter_database_owner("shop", "nobody").is_err());
+        assert!(inst.alter_database_owner("missing", "bob").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut inst = create_test_instance();
+        inst.create_database("shop", "postgres").unwrap();
+        inst.create_user("alice", "password", false).unwrap();
+
+        let encoded = bincode::serialize(&inst.snapshot_exclusive()).unwrap();
+        let mut loaded: ServerInstance = bincode::deserialize(&encoded).unwrap();
+
+        assert!(loaded.users.contains_key("alice"));
+        assert_eq!(loaded.get_database_metadata("shop").unwrap().owner, "postgres");
+        assert_eq!(loaded.get_database_mut("shop").unwrap().name, "shop");
+        assert!(loaded.get_database("testdb").is_some());
+    }
+
+    #[test]
+    fn test_database_oids() {
+        let mut inst = create_test_instance();
+        inst.create_database("shop", "postgres").unwrap();
+        let oid = |inst: &mut ServerInstance, name: &str| inst.get_database_mut(name).unwrap().oid;
+        assert_eq!(oid(&mut inst, DEFAULT_TEMPLATE), 16384);
+        assert_eq!(oid(&mut inst, "testdb"), 16385);
+        assert_eq!(oid(&mut inst, "shop"), 16386);
+
+        // Databases saved before OIDs were kept are numbered after the others
+        inst.get_database_mut("testdb").unwrap().oid = 0;
+        inst.get_database_mut(DEFAULT_TEMPLATE).unwrap().oid = 0;
+        inst.assign_database_oids();
+        assert_eq!(oid(&mut inst, "shop"), 16386);
+        assert_eq!(oid(&mut inst, DEFAULT_TEMPLATE), 16387);
+        assert_eq!(oid(&mut inst, "testdb"), 16388);
+    }
+
+    #[test]
+    fn test_database_templates() {
+        let mut inst = create_test_instance();
+        let mut template_pages = DatabaseStorage::in_memory(16);
+        let mut shop_pages = DatabaseStorage::in_memory(16);
+        assert!(inst.get_database_metadata(DEFAULT_TEMPLATE).unwrap().is_template);
+        assert!(!inst.get_database_metadata("testdb").unwrap().is_template);
+
+        let template = inst.get_database_mut(DEFAULT_TEMPLATE).unwrap();
+        template.create_enum("mood".to_string(), vec!["ok".to_string(), "sad".to_string()]).unwrap();
+        template.views.insert("moods".to_string(), "SELECT 'ok'".to_string());
+        template.create_table(Table::new("settings".to_string(), vec![])).unwrap();
+        template_pages.create_table("settings".to_string()).unwrap();
+        template_pages.insert("settings", Row::new(vec![Value::Integer(1)])).unwrap();
+        inst.create_user("alice", "password", false).unwrap();
+        inst.create_database_from_template("shop", "alice", DEFAULT_TEMPLATE, None, &template_pages, &mut shop_pages)
+            .unwrap();
+        let shop = inst.get_database_mut("shop").unwrap();
+        assert_eq!(shop.name, "shop");
+        let shop_oid = shop.oid;
+        assert_eq!(shop.get_enum("mood").unwrap().len(), 2);
+        assert!(shop.views.contains_key("moods"));
+        assert_eq!(shop_pages.get_all_rows("settings").unwrap()[0].values, vec![Value::Integer(1)]);
+        assert_eq!(inst.get_database_metadata("shop").unwrap().owner, "alice");
+        assert!(!inst.get_database_metadata("shop").unwrap().is_template);
+
+        // Only templates are copied by anyone; other databases by their owner
+        assert!(inst.can_copy_database("alice", DEFAULT_TEMPLATE));
+        assert!(inst.can_copy_database("alice", "shop"));
+        assert!(!inst.can_copy_database("alice", "testdb"));
+
+        // Other sessions must not be connected to the template
+        let mut copy_pages = DatabaseStorage::in_memory(16);
+        let other = inst.sessions.register("postgres", shop_oid, "shop", "", None);
+        let own = inst.sessions.register("alice", shop_oid, "shop", "", None);
+        assert!(matches!(
+            inst.create_database_from_template("copy", "alice", "shop", Some(own.pid()), &shop_pages, &mut copy_pages),
+            Err(DatabaseError::DatabaseInUse(name)) if name == "shop"
+        ));
+        drop(other);
+        inst.create_database_from_template("copy", "alice", "shop", Some(own.pid()), &shop_pages, &mut copy_pages)
+            .unwrap();
+        assert_eq!(copy_pages.row_count("settings"), Some(1));
+        assert!(inst.create_database_from_template("copy", "alice", "shop", None, &shop_pages, &mut copy_pages).is_err());
+        assert!(
+            inst.create_database_from_template("other", "alice", "missing", None, &shop_pages, &mut copy_pages).is_err()
+        );
+
+        // Templates cannot be dropped until they are made ordinary databases
+        assert!(inst.drop_database(DEFAULT_TEMPLATE).is_err());
+        inst.set_database_template(DEFAULT_TEMPLATE, false).unwrap();
+        inst.drop_database(DEFAULT_TEMPLATE).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drop_database_with_sessions() {
+        let mut inst = create_test_instance();
+        let pages = PageStorage::in_memory(16);
+        inst.create_database("shop", "postgres").unwrap();
+        pages.database("shop").unwrap().write().await.create_table("orders".to_string()).unwrap();
+        inst.get_database_mut("shop").unwrap().create_table(Table::new("orders".to_string(), vec![])).unwrap();
+        let shop_oid = inst.get_database_mut("shop").unwrap().oid;
+        let testdb_oid = inst.get_database_mut("testdb").unwrap().oid;
+
+        let own = inst.sessions.register("postgres", shop_oid, "shop", "", None);
+        assert!(matches!(
+            inst.drop_database_with_data("shop", Some(own.pid()), true, &pages),
+            Err(DatabaseError::DropOpenDatabase)
+        ));
+        own.set_database(testdb_oid, "testdb");
+        let other = inst.sessions.register("alice", shop_oid, "shop", "", None);
+        assert!(matches!(
+            inst.drop_database_with_data("shop", Some(own.pid()), false, &pages),
+            Err(DatabaseError::DatabaseInUse(name)) if name == "shop"
+        ));
+        assert!(inst.get_database("shop").is_some());
+        assert_eq!(pages.databases().len(), 1);
+
+        // FORCE ends the other session and drops the pages of the tables
+        inst.drop_database_with_data("shop", Some(own.pid()), true, &pages).unwrap();
+        assert!(inst.get_database("shop").is_none());
+        assert!(pages.databases().is_empty());
+        tokio::time::timeout(std::time::Duration::from_secs(1), other.terminated()).await.unwrap();
+        assert!(inst.drop_database_with_data(DEFAULT_TEMPLATE, None, true, &pages).is_err());
     }
 }
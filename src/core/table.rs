@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
 use super::column::Column;
+use super::constraints::TableConstraint;
 use super::row::Row;
 use super::data_type::DataType;
 use super::error::DatabaseError;
+use super::partition::Partitioning;
 
 /// Storage backend mode for Table
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +18,114 @@ pub enum StorageMode {
     PageBased,
 }
 
+/// v2.7.0: How a table lays out its rows (`CREATE TABLE ... USING method`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessMethod {
+    /// Row-wise pages (default)
+    #[default]
+    Heap,
+    /// Column-wise compressed stripes, for analytics
+    Columnar,
+    /// Memtable + sorted runs with compaction, for write-heavy tables
+    Lsm,
+    /// Rows read from an external file (`CREATE FOREIGN TABLE`), never via `USING`
+    Foreign,
+}
+
+impl AccessMethod {
+    /// Access method for a `USING` name
+    pub fn from_name(name: &str) -> Result<Self, DatabaseError> {
+        match name.to_lowercase().as_str() {
+            "heap" => Ok(Self::Heap),
+            "columnar" => Ok(Self::Columnar),
+            "lsm" => Ok(Self::Lsm),
+            _ => Err(DatabaseError::ParseError(format!("access method \"{name}\" does not exist"))),
+        }
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Heap => "heap",
+            Self::Columnar => "columnar",
+            Self::Lsm => "lsm",
+            Self::Foreign => "foreign",
+        }
+    }
+}
+
+/// v2.7.0: Where a foreign table reads its rows from
+/// (`CREATE FOREIGN TABLE ... SERVER server OPTIONS (key 'value', ...)`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForeignSource {
+    /// Foreign data wrapper name (`csv`, `parquet`)
+    pub server: String,
+    pub options: Vec<(String, String)>,
+}
+
+impl ForeignSource {
+    #[must_use]
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// v2.7.0: Counter of a SERIAL / BIGSERIAL column
+///
+/// Values are handed out with an atomic fetch-and-add, so two inserts never
+/// get the same value, and a value is never handed out twice even when the
+/// insert that took it fails (like `nextval`, gaps are allowed). Serialized as
+/// the plain next value.
+#[derive(Debug)]
+pub struct SerialSequence(AtomicI64);
+
+impl SerialSequence {
+    #[must_use]
+    pub const fn new(next_value: i64) -> Self {
+        Self(AtomicI64::new(next_value))
+    }
+
+    /// Take the next value
+    pub fn next_value(&self) -> i64 {
+        self.0.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// The value the next call to `next_value` returns
+    #[must_use]
+    pub fn peek(&self) -> i64 {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Make sure `value` is never handed out (after an explicit insert of it)
+    pub fn advance_past(&self, value: i64) {
+        self.0.fetch_max(value.saturating_add(1), Ordering::SeqCst);
+    }
+}
+
+impl Default for SerialSequence {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Clone for SerialSequence {
+    fn clone(&self) -> Self {
+        Self::new(self.peek())
+    }
+}
+
+impl Serialize for SerialSequence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.peek())
+    }
+}
+
+impl<'de> Deserialize<'de> for SerialSequence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(Self::new)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Table {
     pub name: String,
@@ -25,9 +136,19 @@ pub struct Table {
     #[deprecated(since = "2.0.0", note = "Use DatabaseStorage::get_paged_table() instead")]
     pub rows: Vec<Row>,
     /// Sequence counters for SERIAL columns: `column_name` -> `next_value`
-    pub sequences: HashMap<String, i64>,
+    pub sequences: HashMap<String, SerialSequence>,
     /// v2.3.0: Owner of the table (username who created it)
     pub owner: String,
+    /// v2.7.0: Generated (STORED) columns: `column_name` -> expression source
+    pub generated: HashMap<String, String>,
+    /// v2.7.0: PARTITION BY - set only on the partitioned (parent) table
+    pub partitioning: Option<Partitioning>,
+    /// v2.7.0: USING heap | columnar | lsm
+    pub access_method: AccessMethod,
+    /// v2.7.0: Set on foreign tables (`access_method` is then `Foreign`)
+    pub foreign: Option<ForeignSource>,
+    /// v2.7.0: Constraints added with ALTER TABLE ... ADD CONSTRAINT
+    pub constraints: Vec<TableConstraint>,
     // Note: PagedTable cannot be stored here because:
     // 1. Arc<Mutex<PageManager>> is not serializable
     // 2. PagedTable is managed externally by Database
@@ -50,7 +171,7 @@ impl Table {
         // Initialize sequences for SERIAL and BIGSERIAL columns
         for col in &columns {
             if matches!(col.data_type, DataType::Serial | DataType::BigSerial) {
-                sequences.insert(col.name.clone(), 1);
+                sequences.insert(col.name.clone(), SerialSequence::default());
             }
         }
 
@@ -60,6 +181,11 @@ impl Table {
             rows: Vec::new(),
             sequences,
             owner,
+            generated: HashMap::new(),
+            partitioning: None,
+            access_method: AccessMethod::Heap,
+            foreign: None,
+            constraints: Vec::new(),
         }
     }
 
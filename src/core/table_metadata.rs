@@ -12,6 +12,8 @@ pub struct TableMetadata {
     pub owner: String,
     /// Права доступа: username/role_name -> set of privileges
     pub privileges: HashMap<String, HashSet<Privilege>>,
+    /// v2.7.0: Индекс, в порядке которого таблица была переписана последним CLUSTER
+    pub clustered_on: Option<String>,
 }
 
 impl TableMetadata {
@@ -28,6 +30,7 @@ impl TableMetadata {
             table_name,
             owner,
             privileges,
+            clustered_on: None,
         }
     }
 
@@ -65,6 +68,19 @@ impl TableMetadata {
     pub fn is_owner(&self, username: &str) -> bool {
         self.owner == username
     }
+
+    /// v2.7.0: Передает владение таблицей (ALTER TABLE ... OWNER TO)
+    ///
+    /// Права прежнего владельца переходят к новому.
+    pub fn set_owner(&mut self, new_owner: &str) {
+        if let Some(privs) = self.privileges.remove(&self.owner) {
+            self.privileges
+                .entry(new_owner.to_string())
+                .or_default()
+                .extend(privs);
+        }
+        self.owner = new_owner.to_string();
+    }
 }
 
 #[cfg(test)]
@@ -80,6 +96,17 @@ mod tests {
         assert!(meta.is_owner("alice"));
     }
 
+    #[test]
+    fn test_set_owner() {
+        let mut meta = TableMetadata::new("users".to_string(), "alice".to_string());
+
+        meta.set_owner("bob");
+        assert!(meta.is_owner("bob"));
+        assert!(!meta.is_owner("alice"));
+        assert!(meta.has_privilege("bob", &Privilege::All));
+        assert!(!meta.has_privilege("alice", &Privilege::Select));
+    }
+
     #[test]
     fn test_grant_privilege() {
         let mut meta = TableMetadata::new("users".to_string(), "alice".to_string());
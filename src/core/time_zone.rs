@@ -0,0 +1,424 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+
+/// Часовой пояс сессии: параметр `TimeZone` (v2.7.0)
+///
+/// TIMESTAMPTZ всегда хранится в UTC; зона сессии определяет, как он
+/// выводится (`2024-03-01 13:00:00+01`) и как читается время без явного
+/// смещения ('2024-03-01 13:00' - местное время сессии).
+///
+/// Допустимые значения:
+/// - `UTC`, `GMT`, `Z`;
+/// - число часов к востоку от Гринвича: `3`, `-7`, `5.5` (`SET TIME ZONE -7`);
+/// - имя из базы IANA (`Europe/Berlin`), файл `TZif` из `$TZDIR` или
+///   `/usr/share/zoneinfo`; после последнего перехода действует правило
+///   POSIX из конца файла.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zone {
+    name: String,
+    offsets: Arc<Offsets>,
+}
+
+/// Смещения зоны от UTC (секунды к востоку)
+#[derive(Debug, PartialEq, Eq)]
+struct Offsets {
+    /// До первого перехода
+    initial: i32,
+    /// Переходы: (момент UTC в секундах, новое смещение), по возрастанию
+    transitions: Vec<(i64, i32)>,
+    /// Правило после последнего перехода
+    rule: Option<PosixRule>,
+}
+
+thread_local! {
+    /// Зона сессии, чей запрос выполняется в этом потоке
+    static CURRENT: RefCell<Zone> = RefCell::new(Zone::utc());
+}
+
+impl Default for Zone {
+    fn default() -> Self {
+        Self::utc()
+    }
+}
+
+impl Zone {
+    #[must_use]
+    pub fn utc() -> Self {
+        Self::fixed("UTC", 0)
+    }
+
+    fn fixed(name: &str, offset: i32) -> Self {
+        Self {
+            name: name.to_string(),
+            offsets: Arc::new(Offsets { initial: offset, transitions: Vec::new(), rule: None }),
+        }
+    }
+
+    /// Зона по значению `SET TIME ZONE`; `None` - такой зоны нет
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if matches!(value.to_ascii_uppercase().as_str(), "UTC" | "GMT" | "Z" | "UCT" | "ETC/UTC") {
+            return Some(Self::utc());
+        }
+        if let Ok(hours) = value.parse::<f64>() {
+            #[allow(clippy::cast_possible_truncation)]
+            return (hours.abs() <= 15.0).then(|| Self::fixed(value, (hours * 3600.0).round() as i32));
+        }
+        Self::load(value)
+    }
+
+    /// Имя, под которым зона сообщается клиенту
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Выполнить `f` с этой зоной как зоной сессии в текущем потоке
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        /// Возвращает прежнюю зону и при панике внутри `f`
+        struct Restore(Option<Zone>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                if let Some(previous) = self.0.take() {
+                    CURRENT.with(|current| *current.borrow_mut() = previous);
+                }
+            }
+        }
+
+        let _restore = Restore(Some(CURRENT.with(|current| current.replace(self.clone()))));
+        f()
+    }
+
+    /// `f` с зоной сессии текущего потока (UTC вне `scope`)
+    pub fn with_current<R>(f: impl FnOnce(&Self) -> R) -> R {
+        CURRENT.with(|current| f(&current.borrow()))
+    }
+
+    /// Смещение от UTC в момент `at`, секунды к востоку
+    #[must_use]
+    pub fn offset_at(&self, at: &DateTime<Utc>) -> i32 {
+        self.offsets.at_utc(at.timestamp())
+    }
+
+    /// Местное время зоны в момент `at`
+    #[must_use]
+    pub fn to_local(&self, at: &DateTime<Utc>) -> NaiveDateTime {
+        at.naive_utc() + chrono::Duration::seconds(i64::from(self.offset_at(at)))
+    }
+
+    /// Местное время зоны как момент UTC
+    ///
+    /// В пропущенный при переходе на летнее время час берётся смещение до
+    /// перехода, повторяющийся час считается летним временем.
+    #[must_use]
+    pub fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let local_secs = local.and_utc().timestamp();
+        let guess = self.offsets.at_utc(local_secs);
+        let offset = self.offsets.at_utc(local_secs - i64::from(guess));
+        let offset = if offset == guess { offset } else { self.offsets.at_utc(local_secs - i64::from(offset)) };
+        local.and_utc() - chrono::Duration::seconds(i64::from(offset))
+    }
+
+    /// TIMESTAMPTZ в местном времени, со смещением как в `PostgreSQL`:
+    /// `2024-03-01 13:00:00+01`, `... +05:30`
+    #[must_use]
+    pub fn format(&self, at: &DateTime<Utc>) -> String {
+        format!("{}{}", self.to_local(at).format("%Y-%m-%d %H:%M:%S"), format_offset(self.offset_at(at)))
+    }
+
+    fn load(name: &str) -> Option<Self> {
+        // Только имена из базы зон, без выхода за её каталог
+        if name.is_empty()
+            || name.starts_with('/')
+            || name.split('/').any(|part| part.is_empty() || part == "." || part == "..")
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || "/_-+".contains(c))
+        {
+            return None;
+        }
+        let dir = std::env::var_os("TZDIR").map_or_else(|| PathBuf::from("/usr/share/zoneinfo"), PathBuf::from);
+        let bytes = std::fs::read(dir.join(name)).ok()?;
+        Some(Self { name: name.to_string(), offsets: Arc::new(Offsets::from_tzif(&bytes)?) })
+    }
+}
+
+/// Смещение как в выводе `PostgreSQL`: `+01`, `-03:30`, `+00:53:28`
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    let (hours, minutes, seconds) = (offset / 3600, offset / 60 % 60, offset % 60);
+    match (minutes, seconds) {
+        (0, 0) => format!("{sign}{hours:02}"),
+        (_, 0) => format!("{sign}{hours:02}:{minutes:02}"),
+        _ => format!("{sign}{hours:02}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+impl Offsets {
+    fn at_utc(&self, at: i64) -> i32 {
+        let index = self.transitions.partition_point(|(time, _)| *time <= at);
+        match (index, &self.rule) {
+            (i, Some(rule)) if i == self.transitions.len() => rule.offset_at(at),
+            (0, _) => self.initial,
+            (i, _) => self.transitions[i - 1].1,
+        }
+    }
+
+    /// Файл `TZif` (RFC 8536): переходы из блока версии 2+, если он есть
+    fn from_tzif(bytes: &[u8]) -> Option<Self> {
+        let (v1, version) = TzifHeader::read(bytes)?;
+        let v1_len = v1.data_len(4);
+        let (header, data, time_size) = if version >= b'2' {
+            let rest = bytes.get(44 + v1_len..)?;
+            let (header, _) = TzifHeader::read(rest)?;
+            (header, &rest[44..], 8)
+        } else {
+            (v1, &bytes[44..], 4)
+        };
+        let data_len = header.data_len(time_size);
+        if data.len() < data_len || header.typecnt == 0 {
+            return None;
+        }
+
+        let times = &data[..header.timecnt * time_size];
+        let indices = &data[header.timecnt * time_size..header.timecnt * (time_size + 1)];
+        let types_start = header.timecnt * (time_size + 1);
+        let utoffs: Vec<i32> = (0..header.typecnt)
+            .map(|i| {
+                let at = types_start + i * 6;
+                i32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+            })
+            .collect();
+
+        let mut transitions = Vec::with_capacity(header.timecnt);
+        for (i, &index) in indices.iter().enumerate() {
+            let raw = &times[i * time_size..(i + 1) * time_size];
+            let time = if time_size == 8 {
+                i64::from_be_bytes(raw.try_into().ok()?)
+            } else {
+                i64::from(i32::from_be_bytes(raw.try_into().ok()?))
+            };
+            transitions.push((time, *utoffs.get(usize::from(index))?));
+        }
+
+        // Правило POSIX между двумя переводами строки после данных
+        let rule = (version >= b'2')
+            .then(|| data.get(data_len..))
+            .flatten()
+            .and_then(|footer| std::str::from_utf8(footer).ok())
+            .and_then(|footer| footer.strip_prefix('\n')?.split('\n').next())
+            .and_then(PosixRule::parse);
+        Some(Self { initial: utoffs[0], transitions, rule })
+    }
+}
+
+/// Заголовок `TZif`: число записей каждого вида
+struct TzifHeader {
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl TzifHeader {
+    fn read(bytes: &[u8]) -> Option<(Self, u8)> {
+        if bytes.len() < 44 || &bytes[..4] != b"TZif" {
+            return None;
+        }
+        let count = |i: usize| {
+            let at = 20 + i * 4;
+            u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize
+        };
+        let header = Self {
+            isutcnt: count(0),
+            isstdcnt: count(1),
+            leapcnt: count(2),
+            timecnt: count(3),
+            typecnt: count(4),
+            charcnt: count(5),
+        };
+        Some((header, bytes[4]))
+    }
+
+    /// Длина блока данных при `time_size` байтах на момент времени
+    const fn data_len(&self, time_size: usize) -> usize {
+        self.timecnt * (time_size + 1)
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+/// Правило POSIX `TZ`: `CET-1CEST,M3.5.0,M10.5.0/3`
+///
+/// Поддерживаются даты вида `Mm.w.d`; зона без летнего времени (`UTC0`,
+/// `<+04>-4`) - постоянное смещение.
+#[derive(Debug, PartialEq, Eq)]
+struct PosixRule {
+    std_offset: i32,
+    dst: Option<(i32, RuleDate, RuleDate)>,
+}
+
+/// День перехода `Mm.w.d/time`: месяц, неделя (5 - последняя), день недели
+/// (0 - воскресенье), местное время перехода в секундах
+#[derive(Debug, PartialEq, Eq)]
+struct RuleDate {
+    month: u32,
+    week: u32,
+    weekday: u32,
+    time: i32,
+}
+
+impl PosixRule {
+    fn parse(text: &str) -> Option<Self> {
+        let rest = Self::skip_name(text)?;
+        let (std_offset, rest) = Self::parse_offset(rest)?;
+        // В POSIX смещение к западу положительно
+        let std_offset = -std_offset;
+        if rest.is_empty() {
+            return Some(Self { std_offset, dst: None });
+        }
+
+        let rest = Self::skip_name(rest)?;
+        let (dst_offset, rest) = match Self::parse_offset(rest) {
+            Some((offset, rest)) => (-offset, rest),
+            None => (std_offset + 3600, rest),
+        };
+        let mut dates = rest.strip_prefix(',')?.split(',');
+        let start = RuleDate::parse(dates.next()?)?;
+        let end = RuleDate::parse(dates.next()?)?;
+        Some(Self { std_offset, dst: Some((dst_offset, start, end)) })
+    }
+
+    fn skip_name(text: &str) -> Option<&str> {
+        if let Some(quoted) = text.strip_prefix('<') {
+            return quoted.split_once('>').map(|(_, rest)| rest);
+        }
+        let end = text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len());
+        (end >= 3).then(|| &text[end..])
+    }
+
+    /// `[+-]hh[:mm[:ss]]` в секундах
+    fn parse_offset(text: &str) -> Option<(i32, &str)> {
+        let end = text.find(|c: char| !(c.is_ascii_digit() || "+-:".contains(c))).unwrap_or(text.len());
+        let (offset, rest) = text.split_at(end);
+        let (sign, digits) = match offset.strip_prefix('-') {
+            Some(digits) => (-1, digits),
+            None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+        };
+        if digits.is_empty() {
+            return None;
+        }
+        let mut seconds = 0;
+        for (part, scale) in digits.split(':').zip([3600, 60, 1]) {
+            seconds += part.parse::<i32>().ok()? * scale;
+        }
+        Some((sign * seconds, rest))
+    }
+
+    fn offset_at(&self, at: i64) -> i32 {
+        let Some((dst_offset, start, end)) = &self.dst else {
+            return self.std_offset;
+        };
+        let Some(year) = DateTime::from_timestamp(at + i64::from(self.std_offset), 0).map(|t| t.year()) else {
+            return self.std_offset;
+        };
+        // Начало задано в зимнем времени, конец - в летнем
+        let (Some(start), Some(end)) = (start.local_time(year), end.local_time(year)) else {
+            return self.std_offset;
+        };
+        let start = start - i64::from(self.std_offset);
+        let end = end - i64::from(*dst_offset);
+        let in_dst = if start < end { start <= at && at < end } else { !(end <= at && at < start) };
+        if in_dst { *dst_offset } else { self.std_offset }
+    }
+}
+
+impl RuleDate {
+    fn parse(text: &str) -> Option<Self> {
+        let (date, time) = match text.split_once('/') {
+            Some((date, time)) => (date, PosixRule::parse_offset(time).filter(|(_, rest)| rest.is_empty())?.0),
+            None => (text, 2 * 3600),
+        };
+        let mut parts = date.strip_prefix('M')?.split('.').map(str::parse::<u32>);
+        let (month, week, weekday) = (parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?);
+        ((1..=12).contains(&month) && (1..=5).contains(&week) && weekday <= 6)
+            .then_some(Self { month, week, weekday, time })
+    }
+
+    /// Местное время перехода в году `year`, секунды от эпохи
+    fn local_time(&self, year: i32) -> Option<i64> {
+        let first = NaiveDate::from_ymd_opt(year, self.month, 1)?;
+        let first_weekday = first.weekday().num_days_from_sunday();
+        let mut day = 1 + (self.weekday + 7 - first_weekday) % 7 + (self.week - 1) * 7;
+        while NaiveDate::from_ymd_opt(year, self.month, day).is_none() {
+            day -= 7;
+        }
+        let midnight = NaiveDate::from_ymd_opt(year, self.month, day)?.and_hms_opt(0, 0, 0)?;
+        Some(midnight.and_utc().timestamp() + i64::from(self.time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap().and_utc()
+    }
+
+    #[test]
+    fn test_fixed_zones() {
+        let noon = utc("2024-03-01 12:00:00");
+        assert_eq!(Zone::utc().format(&noon), "2024-03-01 12:00:00+00");
+        assert_eq!(Zone::parse("gmt").unwrap().name(), "UTC");
+        assert_eq!(Zone::parse("5.5").unwrap().format(&noon), "2024-03-01 17:30:00+05:30");
+        assert_eq!(Zone::parse("-7").unwrap().format(&noon), "2024-03-01 05:00:00-07");
+        assert_eq!(Zone::parse("-7").unwrap().to_utc(noon.naive_utc()), utc("2024-03-01 19:00:00"));
+
+        assert!(Zone::parse("Mars/Olympus_Mons").is_none());
+        assert!(Zone::parse("../../etc/passwd").is_none());
+        assert!(Zone::parse("99").is_none());
+    }
+
+    #[test]
+    fn test_posix_rule() {
+        // Правило Europe/Berlin: летнее время с последнего воскресенья марта
+        // 01:00 UTC до последнего воскресенья октября 01:00 UTC
+        let rule = PosixRule::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let at = |text: &str| rule.offset_at(utc(text).timestamp());
+        assert_eq!(at("2030-01-15 12:00:00"), 3600);
+        assert_eq!(at("2030-03-31 00:59:59"), 3600);
+        assert_eq!(at("2030-03-31 01:00:00"), 7200);
+        assert_eq!(at("2030-10-27 00:59:59"), 7200);
+        assert_eq!(at("2030-10-27 01:00:00"), 3600);
+
+        // Южное полушарие: летнее время через Новый год
+        let sydney = PosixRule::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(sydney.offset_at(utc("2030-01-15 00:00:00").timestamp()), 11 * 3600);
+        assert_eq!(sydney.offset_at(utc("2030-07-15 00:00:00").timestamp()), 10 * 3600);
+        assert_eq!(PosixRule::parse("<+0530>-5:30").unwrap().offset_at(0), 5 * 3600 + 1800);
+    }
+
+    #[test]
+    fn test_named_zone_and_scope() {
+        // База зон может отсутствовать в окружении сборки
+        let Some(berlin) = Zone::parse("Europe/Berlin") else {
+            return;
+        };
+        assert_eq!(berlin.format(&utc("2024-01-15 12:00:00")), "2024-01-15 13:00:00+01");
+        assert_eq!(berlin.format(&utc("2024-07-15 12:00:00")), "2024-07-15 14:00:00+02");
+        assert_eq!(berlin.to_utc(utc("2024-07-15 14:00:00").naive_utc()), utc("2024-07-15 12:00:00"));
+
+        let shown = berlin.scope(|| Zone::with_current(|zone| zone.name().to_string()));
+        assert_eq!(shown, "Europe/Berlin");
+        assert_eq!(Zone::with_current(|zone| zone.name().to_string()), "UTC");
+    }
+}
@@ -1,35 +1,108 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashSet;
+use std::fmt;
+use super::error::DatabaseError;
+
+bitflags::bitflags! {
+    /// Атрибуты пользователя (v2.7.0)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct UserAttributes: u8 {
+        /// SUPERUSER - полные права на всё
+        const SUPERUSER = 1;
+        /// CREATEDB
+        const CREATEDB = 1 << 1;
+        /// CREATEROLE
+        const CREATEROLE = 1 << 2;
+        /// LOGIN - может входить на сервер
+        const LOGIN = 1 << 3;
+    }
+}
 
 /// Пользователь базы данных
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub username: String,
-    /// SHA-256 хэш пароля (hex string)
+    /// v2.7.0: SCRAM-SHA-256 верификатор пароля (`ScramVerifier`); у
+    /// пользователей из старых версий - SHA-256 хэш пароля (hex string),
+    /// он заменяется верификатором при следующей смене пароля
     pub password_hash: String,
-    /// Является ли суперпользователем (полные права на всё)
-    pub is_superuser: bool,
-    /// Права на уровне сервера
-    pub can_create_db: bool,
-    pub can_create_user: bool,
+    /// v2.7.0: SUPERUSER, CREATEDB, CREATEROLE, LOGIN
+    pub attributes: UserAttributes,
     /// Роли, к которым принадлежит пользователь
     pub roles: HashSet<String>,
+    /// v2.7.0: Пароль действует до этого момента (VALID UNTIL; `None` - бессрочно)
+    pub valid_until: Option<DateTime<Utc>>,
 }
 
 impl User {
     #[must_use]
     pub fn new(username: String, password: &str, is_superuser: bool) -> Self {
+        let mut attributes = UserAttributes::LOGIN;
+        if is_superuser {
+            attributes |= UserAttributes::SUPERUSER | UserAttributes::CREATEDB | UserAttributes::CREATEROLE;
+        }
         Self {
             username,
             password_hash: Self::hash_password(password),
-            is_superuser,
-            can_create_db: is_superuser,
-            can_create_user: is_superuser,
+            attributes,
             roles: HashSet::new(),
+            valid_until: None,
         }
     }
 
+    /// Является ли суперпользователем (полные права на всё)
+    #[must_use]
+    pub const fn is_superuser(&self) -> bool {
+        self.attributes.contains(UserAttributes::SUPERUSER)
+    }
+
+    /// Может ли создавать базы данных
+    #[must_use]
+    pub const fn can_create_db(&self) -> bool {
+        self.attributes.contains(UserAttributes::CREATEDB)
+    }
+
+    /// Может ли создавать пользователей и роли
+    #[must_use]
+    pub const fn can_create_role(&self) -> bool {
+        self.attributes.contains(UserAttributes::CREATEROLE)
+    }
+
+    /// v2.7.0: Может ли входить на сервер (LOGIN / NOLOGIN)
+    #[must_use]
+    pub const fn can_login(&self) -> bool {
+        self.attributes.contains(UserAttributes::LOGIN)
+    }
+
+    /// Применяет атрибуты из CREATE USER / ALTER USER (v2.7.0)
+    pub fn apply_options(&mut self, options: &UserOptions) {
+        let flags = [
+            (options.superuser, UserAttributes::SUPERUSER),
+            (options.create_db, UserAttributes::CREATEDB),
+            (options.create_role, UserAttributes::CREATEROLE),
+            (options.login, UserAttributes::LOGIN),
+        ];
+        for (value, flag) in flags {
+            if let Some(value) = value {
+                self.attributes.set(flag, value);
+            }
+        }
+        if let Some(valid_until) = options.valid_until {
+            self.valid_until = valid_until;
+        }
+    }
+
+    /// Истёк ли срок действия пароля (v2.7.0)
+    #[must_use]
+    pub fn password_expired(&self) -> bool {
+        self.valid_until.is_some_and(|until| until <= Utc::now())
+    }
+
     /// Добавляет роль пользователю
     pub fn add_role(&mut self, role_name: &str) {
         self.roles.insert(role_name.to_string());
@@ -46,18 +119,26 @@ impl User {
         self.roles.contains(role_name)
     }
 
-    /// Хэширует пароль с использованием SHA-256
-    #[must_use] 
+    /// Верификатор пароля со случайной солью (v2.7.0)
+    ///
+    /// Готовый верификатор (`\password` в клиенте считает его сам, чтобы
+    /// пароль не попал на сервер и в его логи) сохраняется как есть.
+    #[must_use]
     pub fn hash_password(password: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(password.as_bytes());
-        format!("{:x}", hasher.finalize())
+        if ScramVerifier::parse(password).is_some() {
+            return password.to_string();
+        }
+        ScramVerifier::generate(password).to_string()
     }
 
     /// Проверяет пароль
-    #[must_use] 
+    #[must_use]
     pub fn verify_password(&self, password: &str) -> bool {
-        self.password_hash == Self::hash_password(password)
+        if let Some(verifier) = ScramVerifier::parse(&self.password_hash) {
+            return verifier.verify(password);
+        }
+        let legacy = format!("{:x}", Sha256::digest(password.as_bytes()));
+        constant_time_eq(self.password_hash.as_bytes(), legacy.as_bytes())
     }
 
     /// Меняет пароль
@@ -65,3 +146,248 @@ impl User {
         self.password_hash = Self::hash_password(password);
     }
 }
+
+/// Атрибуты пользователя в CREATE USER / ALTER USER (v2.7.0)
+///
+/// `None` - атрибут не указан: CREATE USER оставляет значение по умолчанию,
+/// ALTER USER - текущее.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserOptions {
+    /// SUPERUSER / NOSUPERUSER
+    pub superuser: Option<bool>,
+    /// CREATEDB / NOCREATEDB
+    pub create_db: Option<bool>,
+    /// CREATEROLE / NOCREATEROLE
+    pub create_role: Option<bool>,
+    /// LOGIN / NOLOGIN
+    pub login: Option<bool>,
+    /// VALID UNTIL; `Some(None)` - 'infinity'
+    pub valid_until: Option<Option<DateTime<Utc>>>,
+}
+
+impl UserOptions {
+    /// Указан ли хоть один атрибут
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Требования к новым паролям (v2.7.0)
+///
+/// Проверяются в CREATE USER и ALTER USER; готовый верификатор проверить
+/// нельзя, он принимается как есть.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// Минимальная длина в символах (0 - без ограничения)
+    pub min_length: usize,
+}
+
+impl PasswordPolicy {
+    pub fn check(&self, password: &str) -> Result<(), DatabaseError> {
+        if ScramVerifier::parse(password).is_none() && password.chars().count() < self.min_length {
+            return Err(DatabaseError::PasswordTooShort(self.min_length));
+        }
+        Ok(())
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// SCRAM-SHA-256 верификатор (RFC 5802, RFC 7677) в формате `PostgreSQL` (v2.7.0)
+///
+/// `SCRAM-SHA-256$<итерации>:<соль>$<StoredKey>:<ServerKey>`, base64.
+/// Из пароля выводится `SaltedPassword = PBKDF2-HMAC-SHA-256(пароль, соль)`;
+/// сам пароль и `SaltedPassword` не хранятся, а по `StoredKey` пароль
+/// можно только проверить.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScramVerifier {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub stored_key: [u8; 32],
+    pub server_key: [u8; 32],
+}
+
+impl ScramVerifier {
+    /// Итераций PBKDF2 по умолчанию, как в `PostgreSQL`
+    pub const ITERATIONS: u32 = 4096;
+    const PREFIX: &'static str = "SCRAM-SHA-256$";
+
+    /// Верификатор для пароля со случайной 16-байтной солью
+    #[must_use]
+    pub fn generate(password: &str) -> Self {
+        let mut salt = vec![0u8; 16];
+        getrandom::getrandom(&mut salt).expect("system random number generator is unavailable");
+        Self::with_salt(password, salt, Self::ITERATIONS)
+    }
+
+    #[must_use]
+    pub fn with_salt(password: &str, salt: Vec<u8>, iterations: u32) -> Self {
+        let salted = Self::salted_password(password, &salt, iterations);
+        let client_key = hmac(&salted, b"Client Key");
+        Self {
+            iterations,
+            salt,
+            stored_key: Sha256::digest(client_key).into(),
+            server_key: hmac(&salted, b"Server Key"),
+        }
+    }
+
+    /// Подставной верификатор для пользователя без своего: соль -
+    /// `HMAC(secret, имя)`, одна и та же при каждой попытке входа под этим
+    /// именем, а ключи не подходят ни к одному паролю
+    #[must_use]
+    pub fn mock(secret: &[u8], username: &str) -> Self {
+        Self {
+            iterations: Self::ITERATIONS,
+            salt: hmac(secret, username.as_bytes())[..16].to_vec(),
+            stored_key: [0; 32],
+            server_key: [0; 32],
+        }
+    }
+
+    /// Разбор текстового вида; `None` - это не верификатор
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let (params, keys) = text.strip_prefix(Self::PREFIX)?.split_once('$')?;
+        let (iterations, salt) = params.split_once(':')?;
+        let (stored_key, server_key) = keys.split_once(':')?;
+        Some(Self {
+            iterations: iterations.parse().ok().filter(|n| *n > 0)?,
+            salt: BASE64.decode(salt).ok()?,
+            stored_key: BASE64.decode(stored_key).ok()?.try_into().ok()?,
+            server_key: BASE64.decode(server_key).ok()?.try_into().ok()?,
+        })
+    }
+
+    /// Проверка пароля: `StoredKey` из него совпадает с сохранённым
+    #[must_use]
+    pub fn verify(&self, password: &str) -> bool {
+        let salted = Self::salted_password(password, &self.salt, self.iterations);
+        let stored_key: [u8; 32] = Sha256::digest(hmac(&salted, b"Client Key")).into();
+        constant_time_eq(&stored_key, &self.stored_key)
+    }
+
+    /// Проверка `ClientProof` из обмена SCRAM: `ClientKey`, восстановленный
+    /// из доказательства и `ClientSignature = HMAC(StoredKey, AuthMessage)`,
+    /// даёт сохранённый `StoredKey`
+    #[must_use]
+    pub fn verify_proof(&self, auth_message: &[u8], proof: &[u8]) -> bool {
+        if proof.len() != 32 {
+            return false;
+        }
+        let signature = hmac(&self.stored_key, auth_message);
+        let client_key: Vec<u8> = proof.iter().zip(signature).map(|(p, s)| p ^ s).collect();
+        let stored_key: [u8; 32] = Sha256::digest(client_key).into();
+        constant_time_eq(&stored_key, &self.stored_key)
+    }
+
+    /// `ServerSignature = HMAC(ServerKey, AuthMessage)`: им сервер
+    /// доказывает клиенту, что знает верификатор
+    #[must_use]
+    pub fn server_signature(&self, auth_message: &[u8]) -> [u8; 32] {
+        hmac(&self.server_key, auth_message)
+    }
+
+    fn salted_password(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+        let mut salted = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut salted);
+        salted
+    }
+}
+
+impl fmt::Display for ScramVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}:{}${}:{}",
+            Self::PREFIX,
+            self.iterations,
+            BASE64.encode(&self.salt),
+            BASE64.encode(self.stored_key),
+            BASE64.encode(self.server_key)
+        )
+    }
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// Сравнение без раннего выхода: время не зависит от места расхождения
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scram_verifier() {
+        // Пример из RFC 7677: пароль "pencil", соль W22ZaJ0SNY7soEsUEjb6gQ==
+        let salt = BASE64.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let verifier = ScramVerifier::with_salt("pencil", salt, 4096);
+        // ServerSignature из RFC получается из этого ServerKey
+        let auth_message = "n=user,r=rOprNGfwEbeRWgbNEkqO,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096,c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0";
+        assert_eq!(
+            BASE64.encode(verifier.server_signature(auth_message.as_bytes())),
+            "6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4="
+        );
+        // ClientProof из RFC проходит проверку, испорченный - нет
+        let mut proof = BASE64.decode("dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=").unwrap();
+        assert!(verifier.verify_proof(auth_message.as_bytes(), &proof));
+        proof[0] ^= 1;
+        assert!(!verifier.verify_proof(auth_message.as_bytes(), &proof));
+
+        let text = verifier.to_string();
+        assert!(text.starts_with("SCRAM-SHA-256$4096:W22ZaJ0SNY7soEsUEjb6gQ==$"));
+        assert_eq!(ScramVerifier::parse(&text), Some(verifier.clone()));
+        assert!(verifier.verify("pencil"));
+        assert!(!verifier.verify("pencil2"));
+        assert!(ScramVerifier::parse("SCRAM-SHA-256$0:abc$def:ghi").is_none());
+        assert!(ScramVerifier::parse("pencil").is_none());
+    }
+
+    #[test]
+    fn test_user_passwords() {
+        let mut user = User::new("alice".to_string(), "s3cret", false);
+        assert!(user.password_hash.starts_with("SCRAM-SHA-256$4096:"));
+        assert!(!user.password_hash.contains("s3cret"));
+        assert!(user.verify_password("s3cret"));
+        assert!(!user.verify_password("secret"));
+        // Соль случайная: одинаковые пароли дают разные верификаторы
+        assert_ne!(User::new("bob".to_string(), "s3cret", false).password_hash, user.password_hash);
+
+        // Готовый верификатор сохраняется как есть
+        let verifier = ScramVerifier::generate("from client").to_string();
+        user.set_password(&verifier);
+        assert_eq!(user.password_hash, verifier);
+        assert!(user.verify_password("from client"));
+
+        // Хэш из старых версий по-прежнему проверяется
+        user.password_hash = format!("{:x}", Sha256::digest(b"legacy"));
+        assert!(user.verify_password("legacy"));
+        assert!(!user.verify_password("s3cret"));
+
+        // v2.7.0: Атрибуты и срок действия пароля
+        assert!(user.can_login() && !user.can_create_db() && !user.password_expired());
+        user.apply_options(&UserOptions {
+            create_db: Some(true),
+            login: Some(false),
+            valid_until: Some(Some(Utc::now() - chrono::Duration::days(1))),
+            ..UserOptions::default()
+        });
+        assert!(user.can_create_db() && !user.can_login() && !user.is_superuser());
+        assert!(user.password_expired());
+        user.apply_options(&UserOptions { valid_until: Some(None), ..UserOptions::default() });
+        assert!(!user.password_expired() && user.can_create_db());
+
+        let policy = PasswordPolicy { min_length: 8 };
+        assert!(matches!(policy.check("short"), Err(DatabaseError::PasswordTooShort(8))));
+        assert!(policy.check("long enough").is_ok());
+        assert!(policy.check(&verifier).is_ok());
+    }
+}
@@ -2,6 +2,17 @@ use serde::{Deserialize, Serialize};
 use chrono::{NaiveDate, NaiveDateTime, DateTime, Utc};
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::time_zone::Zone;
+
+thread_local! {
+    /// Метки перечислений базы, чей запрос выполняется в этом потоке, в объявленном порядке
+    static ENUM_LABELS: RefCell<Arc<HashMap<String, Vec<String>>>> = RefCell::new(Arc::default());
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Value {
@@ -69,7 +80,8 @@ impl std::fmt::Display for Value {
             Self::Boolean(b) => write!(f, "{b}"),
             Self::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
             Self::Timestamp(t) => write!(f, "{}", t.format("%Y-%m-%d %H:%M:%S")),
-            Self::TimestampTz(t) => write!(f, "{}", t.format("%Y-%m-%d %H:%M:%S %Z")),
+            // v2.7.0: Местное время зоны сессии со смещением
+            Self::TimestampTz(t) => write!(f, "{}", Zone::with_current(|zone| zone.format(t))),
             Self::Uuid(u) => write!(f, "{u}"),
             Self::Json(j) => write!(f, "{j}"),
             Self::Bytea(b) => write!(f, "\\x{}", hex::encode(b)),
@@ -77,3 +89,199 @@ impl std::fmt::Display for Value {
         }
     }
 }
+
+/// Сравнение двух значений по правилам SQL (v2.7.0)
+///
+/// Единая точка сравнения для WHERE, ORDER BY, ключей JOIN и секционирования:
+/// - числа сравниваются независимо от типа: целые точно, с NUMERIC - как
+///   NUMERIC, с REAL - как f64;
+/// - текст приводится к типу второго операнда (число, дата, время, boolean,
+///   uuid), как нетипизированный литерал в `PostgreSQL`;
+/// - DATE расширяется до TIMESTAMP (полночь); TIMESTAMP без зоны рядом с
+///   TIMESTAMPTZ - местное время зоны сессии.
+///
+/// `None` - значения несравнимы (NULL, разные типы, текст не приводится).
+#[must_use]
+pub fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    use Value as V;
+
+    match (left, right) {
+        (V::Null, _) | (_, V::Null) => None,
+        (V::Text(lhs), V::Text(rhs)) => Some(lhs.cmp(rhs)),
+        // CHAR(n) дополняется пробелами, они не значимы
+        (V::Text(lhs) | V::Char(lhs), V::Text(rhs) | V::Char(rhs)) => Some(lhs.trim_end().cmp(rhs.trim_end())),
+        (V::Text(text) | V::Char(text), other) => {
+            coerce_text(text, other).and_then(|coerced| compare_values(&coerced, other))
+        }
+        (other, V::Text(text) | V::Char(text)) => {
+            coerce_text(text, other).and_then(|coerced| compare_values(other, &coerced))
+        }
+        (V::Boolean(lhs), V::Boolean(rhs)) => Some(lhs.cmp(rhs)),
+        (V::Uuid(lhs), V::Uuid(rhs)) => Some(lhs.cmp(rhs)),
+        (V::Enum(type_name, lhs), V::Enum(other_type, rhs)) if type_name == other_type => {
+            compare_enum_labels(type_name, lhs, rhs)
+        }
+        (V::Enum(..), V::Enum(..)) => None,
+        (V::Bytea(lhs), V::Bytea(rhs)) => Some(lhs.cmp(rhs)),
+        (V::Json(lhs), V::Json(rhs)) => Some(lhs.cmp(rhs)),
+        _ => compare_numbers(left, right).or_else(|| compare_timestamps(left, right)),
+    }
+}
+
+/// `f` с порядком меток перечислений базы `enums` (v2.7.0)
+///
+/// Значения ENUM сравниваются по месту метки в типе, как в `PostgreSQL`;
+/// вне `with_enum_labels` (и для неизвестного типа) - по тексту метки.
+#[allow(clippy::implicit_hasher)] // хранится в потоке как есть
+pub fn with_enum_labels<R>(enums: &Arc<HashMap<String, Vec<String>>>, f: impl FnOnce() -> R) -> R {
+    /// Возвращает прежние метки и при панике внутри `f`
+    struct Restore(Option<Arc<HashMap<String, Vec<String>>>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(previous) = self.0.take() {
+                ENUM_LABELS.set(previous);
+            }
+        }
+    }
+
+    let _restore = Restore(Some(ENUM_LABELS.replace(Arc::clone(enums))));
+    f()
+}
+
+/// Порядок меток `x` и `y` перечисления `type_name`; `None`, если метки нет в типе
+fn compare_enum_labels(type_name: &str, x: &str, y: &str) -> Option<Ordering> {
+    ENUM_LABELS.with_borrow(|enums| match enums.get(type_name) {
+        Some(labels) => {
+            let position = |label: &str| labels.iter().position(|l| l == label);
+            Some(position(x)?.cmp(&position(y)?))
+        }
+        None => Some(x.cmp(y)),
+    })
+}
+
+/// Равенство по `compare_values`, для несравнимых типов - по `PartialEq`
+///
+/// NULL здесь равен NULL: трёхзначную логику обеспечивает вызывающий.
+#[must_use]
+pub fn values_equal(a: &Value, b: &Value) -> bool {
+    a == b || compare_values(a, b) == Some(Ordering::Equal)
+}
+
+/// Порядок для ORDER BY: NULL первым, несравнимые значения равны
+#[must_use]
+pub fn compare_for_sort(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        _ => compare_values(a, b).unwrap_or(Ordering::Equal),
+    }
+}
+
+fn compare_numbers(a: &Value, b: &Value) -> Option<Ordering> {
+    let integer = |value: &Value| match value {
+        Value::SmallInt(n) => Some(i64::from(*n)),
+        Value::Integer(n) => Some(*n),
+        _ => None,
+    };
+    let decimal = |value: &Value| match value {
+        Value::Numeric(n) => Some(*n),
+        Value::Real(n) => Decimal::from_f64(*n),
+        _ => integer(value).map(Decimal::from),
+    };
+    let real = |value: &Value| match value {
+        Value::Real(n) => Some(*n),
+        Value::Numeric(n) => n.to_f64(),
+        #[allow(clippy::cast_precision_loss)]
+        _ => integer(value).map(|n| n as f64),
+    };
+
+    match (a, b) {
+        (Value::Real(_), _) | (_, Value::Real(_)) => real(a)?.partial_cmp(&real(b)?),
+        (Value::Numeric(_), _) | (_, Value::Numeric(_)) => Some(decimal(a)?.cmp(&decimal(b)?)),
+        _ => Some(integer(a)?.cmp(&integer(b)?)),
+    }
+}
+
+fn compare_timestamps(a: &Value, b: &Value) -> Option<Ordering> {
+    let utc = |value: &Value| match value {
+        Value::TimestampTz(t) => Some(*t),
+        other => as_timestamp(other).map(|local| Zone::with_current(|zone| zone.to_utc(local))),
+    };
+    match (a, b) {
+        (Value::TimestampTz(_), _) | (_, Value::TimestampTz(_)) => Some(utc(a)?.cmp(&utc(b)?)),
+        _ => Some(as_timestamp(a)?.cmp(&as_timestamp(b)?)),
+    }
+}
+
+/// DATE и TIMESTAMP на общей шкале
+const fn as_timestamp(value: &Value) -> Option<NaiveDateTime> {
+    match value {
+        Value::Date(d) => d.and_hms_opt(0, 0, 0),
+        Value::Timestamp(t) => Some(*t),
+        _ => None,
+    }
+}
+
+/// Текст как значение типа `like` (для сравнения с ним)
+fn coerce_text(text: &str, like: &Value) -> Option<Value> {
+    let text = text.trim();
+    match like {
+        Value::SmallInt(_) | Value::Integer(_) => text
+            .parse()
+            .map(Value::Integer)
+            .ok()
+            .or_else(|| text.parse().ok().map(Value::Numeric)),
+        Value::Numeric(_) => text.parse().ok().map(Value::Numeric),
+        Value::Real(_) => text.parse().ok().map(Value::Real),
+        Value::Boolean(_) => match text.to_lowercase().as_str() {
+            "t" | "true" | "yes" | "on" | "1" => Some(Value::Boolean(true)),
+            "f" | "false" | "no" | "off" | "0" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        Value::Date(_) | Value::Timestamp(_) | Value::TimestampTz(_) => parse_timestamp(text),
+        Value::Uuid(_) => text.parse().ok().map(Value::Uuid),
+        Value::Enum(name, _) => Some(Value::Enum(name.clone(), text.to_string())),
+        Value::Json(_) => Some(Value::Json(text.to_string())),
+        Value::Bytea(_) => parse_bytea(text),
+        _ => None,
+    }
+}
+
+/// Текст в формате DATE, TIMESTAMP или TIMESTAMPTZ (со смещением)
+#[must_use]
+pub fn parse_timestamp(text: &str) -> Option<Value> {
+    if let Ok(d) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(Value::Date(d));
+    }
+    if let Some(t) = parse_timestamptz(text) {
+        return Some(Value::TimestampTz(t));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(Value::Timestamp)
+}
+
+/// Время с явным смещением: RFC 3339 или вывод `PostgreSQL`
+/// (`2024-03-01 13:00:00+01`, v2.7.0)
+#[must_use]
+pub fn parse_timestamptz(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(text) {
+        return Some(t.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"]
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(text, format).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// BYTEA в hex-формате (`\x...`), иначе байты самого текста
+#[must_use]
+pub fn parse_bytea(text: &str) -> Option<Value> {
+    match text.strip_prefix("\\x") {
+        Some(digits) => hex::decode(digits).ok().map(Value::Bytea),
+        None => Some(Value::Bytea(text.as_bytes().to_vec())),
+    }
+}
@@ -0,0 +1,169 @@
+/// Per-table and per-index activity counters (v2.7.0)
+///
+/// The executor counts sequential and index scans, inserted / updated /
+/// deleted tuples and the dead tuples left behind by UPDATE and DELETE until
+/// VACUUM removes them. Counters are kept in memory only and are exposed via
+/// `pg_stat_user_tables` and `pg_stat_user_indexes`.
+///
+/// Queries only borrow `Database` immutably, so the counters sit behind a
+/// shared mutex; clones of a `Database` (transaction snapshots) keep counting
+/// into the same statistics.
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Activity of one table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableActivity {
+    pub seq_scan: u64,
+    pub idx_scan: u64,
+    pub n_tup_ins: u64,
+    pub n_tup_upd: u64,
+    pub n_tup_del: u64,
+    /// Row versions made invisible by UPDATE / DELETE and not yet vacuumed
+    pub n_dead_tup: u64,
+    pub last_vacuum: Option<DateTime<Utc>>,
+}
+
+/// Activity of one index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexActivity {
+    pub table: String,
+    pub idx_scan: u64,
+    /// Index entries returned by scans
+    pub idx_tup_read: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    tables: HashMap<String, TableActivity>,
+    indexes: HashMap<String, IndexActivity>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActivityStats {
+    counters: Arc<Mutex<Counters>>,
+}
+
+impl ActivityStats {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_table(&self, table: &str, update: impl FnOnce(&mut TableActivity)) {
+        let mut counters = self.counters.lock().expect("activity stats poisoned");
+        update(counters.tables.entry(table.to_string()).or_default());
+    }
+
+    /// A full scan of `table`
+    pub fn count_seq_scan(&self, table: &str) {
+        self.with_table(table, |activity| activity.seq_scan += 1);
+    }
+
+    /// A lookup through `index` on `table` that returned `tuples` entries
+    pub fn count_index_scan(&self, table: &str, index: &str, tuples: usize) {
+        self.with_table(table, |activity| activity.idx_scan += 1);
+        let mut counters = self.counters.lock().expect("activity stats poisoned");
+        let activity = counters.indexes.entry(index.to_string()).or_default();
+        activity.table = table.to_string();
+        activity.idx_scan += 1;
+        activity.idx_tup_read += tuples as u64;
+    }
+
+    pub fn count_inserted(&self, table: &str, rows: usize) {
+        self.with_table(table, |activity| activity.n_tup_ins += rows as u64);
+    }
+
+    /// Updated rows leave their old versions behind as dead tuples
+    pub fn count_updated(&self, table: &str, rows: usize) {
+        self.with_table(table, |activity| {
+            activity.n_tup_upd += rows as u64;
+            activity.n_dead_tup += rows as u64;
+        });
+    }
+
+    /// Deleted rows stay in the pages as dead tuples until VACUUM
+    pub fn count_deleted(&self, table: &str, rows: usize) {
+        self.with_table(table, |activity| {
+            activity.n_tup_del += rows as u64;
+            activity.n_dead_tup += rows as u64;
+        });
+    }
+
+    /// VACUUM physically removed `removed` dead tuples from `table`
+    pub fn count_vacuum(&self, table: &str, removed: usize) {
+        self.with_table(table, |activity| {
+            activity.n_dead_tup = activity.n_dead_tup.saturating_sub(removed as u64);
+            activity.last_vacuum = Some(Utc::now());
+        });
+    }
+
+    /// Drop the counters of a removed table and its indexes
+    pub fn forget_table(&self, table: &str) {
+        let mut counters = self.counters.lock().expect("activity stats poisoned");
+        counters.tables.remove(table);
+        counters.indexes.retain(|_, activity| activity.table != table);
+    }
+
+    /// Drop the counters of a removed index
+    pub fn forget_index(&self, index: &str) {
+        self.counters.lock().expect("activity stats poisoned").indexes.remove(index);
+    }
+
+    /// Counters of `table` (all zero if it was never touched)
+    #[must_use]
+    pub fn table(&self, table: &str) -> TableActivity {
+        let counters = self.counters.lock().expect("activity stats poisoned");
+        counters.tables.get(table).cloned().unwrap_or_default()
+    }
+
+    /// Counters of `index` (all zero if it was never used)
+    #[must_use]
+    pub fn index(&self, index: &str) -> IndexActivity {
+        let counters = self.counters.lock().expect("activity stats poisoned");
+        counters.indexes.get(index).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_tuples_until_vacuum() {
+        let stats = ActivityStats::new();
+        stats.count_inserted("users", 3);
+        stats.count_updated("users", 2);
+        stats.count_deleted("users", 1);
+        stats.count_seq_scan("users");
+
+        let users = stats.table("users");
+        assert_eq!((users.n_tup_ins, users.n_tup_upd, users.n_tup_del), (3, 2, 1));
+        assert_eq!(users.n_dead_tup, 3);
+        assert_eq!(users.seq_scan, 1);
+        assert!(users.last_vacuum.is_none());
+
+        stats.count_vacuum("users", 3);
+        let users = stats.table("users");
+        assert_eq!(users.n_dead_tup, 0);
+        assert!(users.last_vacuum.is_some());
+    }
+
+    #[test]
+    fn test_index_scans_and_forget() {
+        let stats = ActivityStats::new();
+        stats.count_index_scan("users", "idx_users_id", 1);
+        stats.count_index_scan("users", "idx_users_id", 0);
+
+        assert_eq!(stats.table("users").idx_scan, 2);
+        let index = stats.index("idx_users_id");
+        assert_eq!((index.idx_scan, index.idx_tup_read), (2, 1));
+
+        // Clones share the counters
+        let clone = stats.clone();
+        clone.forget_table("users");
+        assert_eq!(stats.table("users"), TableActivity::default());
+        assert_eq!(stats.index("idx_users_id"), IndexActivity::default());
+    }
+}
@@ -0,0 +1,382 @@
+/// ANALYZE: per-column statistics for the planner (v2.7.0)
+///
+/// ANALYZE reads a sample of at most `SAMPLE_ROWS` live rows of a table
+/// (reservoir sampling, so every row is equally likely to be picked) and
+/// records for each column the share of NULLs, the average value width, the
+/// number of distinct values and its most common values with their
+/// frequencies. `pg_stats` shows them; the planner and EXPLAIN use them to
+/// estimate how many rows `col = value`, `col IS NULL` and the like match
+/// instead of fixed guesses.
+///
+/// Like the activity counters, statistics are kept in memory only and are
+/// shared between clones of a `Database`. After a restart the planner falls
+/// back to its fixed estimates until the next ANALYZE.
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::parser::Condition;
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError, Row, Value};
+use crate::types::value::{compare_for_sort, compare_values};
+use super::dispatcher_executor::QueryResult;
+use super::partitions::Partitions;
+
+/// Rows sampled per table (`PostgreSQL`: 300 × `default_statistics_target`)
+pub const SAMPLE_ROWS: usize = 30_000;
+/// Most common values kept per column
+pub const STATISTICS_TARGET: usize = 100;
+
+/// Statistics of one column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    /// Share of sampled rows where the column is NULL
+    pub null_frac: f64,
+    /// Average serialized size of the non-NULL values, in bytes
+    pub avg_width: usize,
+    /// Distinct non-NULL values. Negative, like in `PostgreSQL`: minus their
+    /// share of the rows, for columns whose distinct count grows with the table
+    pub n_distinct: f64,
+    /// Most common values, most frequent first
+    pub most_common_vals: Vec<Value>,
+    /// Share of the rows holding each of `most_common_vals`
+    pub most_common_freqs: Vec<f64>,
+}
+
+impl ColumnStatistics {
+    /// Share of rows where the column equals `value`
+    #[must_use]
+    pub fn equal_fraction(&self, value: &Value, reltuples: usize) -> f64 {
+        if matches!(value, Value::Null) {
+            return 0.0;
+        }
+        let common = self
+            .most_common_vals
+            .iter()
+            .position(|common| compare_values(common, value) == Some(Ordering::Equal));
+        if let Some(idx) = common {
+            return self.most_common_freqs[idx];
+        }
+
+        // The rest is spread evenly over the other values, none of them more
+        // frequent than the least common value kept
+        let distinct = if self.n_distinct < 0.0 { -self.n_distinct * float(reltuples) } else { self.n_distinct };
+        let others = distinct - float(self.most_common_vals.len());
+        if others < 1.0 {
+            return 0.0;
+        }
+        let rest = (1.0 - self.null_frac - self.most_common_freqs.iter().sum::<f64>()).max(0.0);
+        let least_common = self.most_common_freqs.last().copied().unwrap_or(1.0);
+        (rest / others).min(least_common)
+    }
+}
+
+/// Statistics of one table
+#[derive(Debug, Clone)]
+pub struct TableStatistics {
+    /// Live rows when the table was analyzed
+    pub reltuples: usize,
+    /// Columns in table order
+    pub columns: Vec<(String, ColumnStatistics)>,
+    pub last_analyze: DateTime<Utc>,
+}
+
+impl TableStatistics {
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<&ColumnStatistics> {
+        self.columns.iter().find(|(column, _)| column == name).map(|(_, stats)| stats)
+    }
+
+    /// Share of rows matching a single-column condition, `None` when the
+    /// statistics say nothing about it
+    #[must_use]
+    pub fn selectivity(&self, cond: &Condition) -> Option<f64> {
+        let fraction = match cond {
+            Condition::Equals(col, value) => self.column(col)?.equal_fraction(value, self.reltuples),
+            Condition::NotEquals(col, value) => {
+                let stats = self.column(col)?;
+                1.0 - stats.null_frac - stats.equal_fraction(value, self.reltuples)
+            }
+            Condition::In(col, values) => {
+                let stats = self.column(col)?;
+                values.iter().map(|value| stats.equal_fraction(value, self.reltuples)).sum()
+            }
+            Condition::IsNull(col) => self.column(col)?.null_frac,
+            Condition::IsNotNull(col) => 1.0 - self.column(col)?.null_frac,
+            _ => return None,
+        };
+        Some(fraction.clamp(0.0, 1.0))
+    }
+}
+
+/// Statistics of all analyzed tables, shared by clones like `ActivityStats`
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    tables: Arc<Mutex<HashMap<String, Arc<TableStatistics>>>>,
+}
+
+impl Statistics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statistics of `table`, `None` until it is analyzed
+    #[must_use]
+    pub fn table(&self, table: &str) -> Option<Arc<TableStatistics>> {
+        self.lock().get(table).cloned()
+    }
+
+    pub fn set_table(&self, table: &str, stats: TableStatistics) {
+        self.lock().insert(table.to_string(), Arc::new(stats));
+    }
+
+    /// Drop the statistics of a removed table
+    pub fn forget_table(&self, table: &str) {
+        self.lock().remove(table);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<TableStatistics>>> {
+        self.tables.lock().expect("table statistics poisoned")
+    }
+}
+
+pub struct AnalyzeExecutor;
+
+impl AnalyzeExecutor {
+    /// ANALYZE [table]: collect statistics of one table or of every table
+    pub fn analyze(
+        db: &Database,
+        table_name: Option<String>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let tables: Vec<String> = if let Some(name) = table_name {
+            if !db.tables.contains_key(&name) {
+                return Err(DatabaseError::TableNotFound(name));
+            }
+            vec![name]
+        } else {
+            let mut names: Vec<String> = db.tables.keys().cloned().collect();
+            names.sort();
+            names
+        };
+
+        for table in &tables {
+            let stats = Self::analyze_table(db, table, tx_manager, database_storage)?;
+            db.statistics.set_table(table, stats);
+        }
+
+        Ok(QueryResult::Success("ANALYZE".to_string()))
+    }
+
+    fn analyze_table(
+        db: &Database,
+        table_name: &str,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<TableStatistics, DatabaseError> {
+        let table = db.get_table(table_name).ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let snapshot = tx_manager.get_snapshot();
+
+        // Reservoir sampling: the n-th live row replaces a random sampled one
+        // with probability SAMPLE_ROWS / n
+        let mut random = SampleRandom::new();
+        let mut sample: Vec<Row> = Vec::new();
+        let mut live = 0;
+        // A partitioned table is analyzed over all of its partitions
+        for row in Partitions::scan_for_index(db, database_storage, table_name, None)? {
+            let row = row?;
+            if !row.is_visible_to_snapshot(&snapshot) {
+                continue;
+            }
+            live += 1;
+            if sample.len() < SAMPLE_ROWS {
+                sample.push(row);
+            } else {
+                let slot = random.below(live);
+                if slot < SAMPLE_ROWS {
+                    sample[slot] = row;
+                }
+            }
+        }
+
+        let columns = table
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let values: Vec<&Value> = sample.iter().filter_map(|row| row.values.get(idx)).collect();
+                (column.name.clone(), Self::column_statistics(&values, live))
+            })
+            .collect();
+
+        Ok(TableStatistics { reltuples: live, columns, last_analyze: Utc::now() })
+    }
+
+    /// Statistics of one column from its sampled values; `live` rows exist in all
+    fn column_statistics(values: &[&Value], live: usize) -> ColumnStatistics {
+        let sampled = values.len();
+        let mut counts: HashMap<String, (&Value, usize)> = HashMap::new();
+        let mut nulls = 0;
+        let mut width = 0;
+        for value in values {
+            if matches!(value, Value::Null) {
+                nulls += 1;
+                continue;
+            }
+            width += bincode::serialized_size(value).map_or(0, |size| usize::try_from(size).unwrap_or(usize::MAX));
+            counts.entry(value.to_string()).or_insert((value, 0)).1 += 1;
+        }
+
+        let non_null = sampled - nulls;
+        let distinct = counts.len();
+        let singles = counts.values().filter(|(_, count)| *count == 1).count();
+        let complete = sampled == live;
+
+        // Haas-Stokes estimate of the distinct values in the whole table, as
+        // `PostgreSQL` does; exact when every row was read
+        let n_distinct = if complete || distinct == 0 {
+            float(distinct)
+        } else if singles == distinct {
+            // Every sampled value is unique: assume the column is
+            -float(live)
+        } else {
+            let n = float(non_null);
+            let estimate = n * float(distinct) / (n - float(singles) + float(singles) * n / float(live));
+            estimate.clamp(float(distinct), float(live))
+        };
+        // Many distinct values scale with the table: store them as a share of it
+        let n_distinct = if live > 0 && n_distinct.abs() > 0.1 * float(live) {
+            -(n_distinct.abs() / float(live))
+        } else {
+            n_distinct
+        };
+
+        // Every value is kept when they all fit, the sample shows them all and
+        // their number does not grow with the table; otherwise only those
+        // clearly more frequent than average
+        let mut common: Vec<(&Value, usize)> = counts.into_values().collect();
+        if !(distinct <= STATISTICS_TARGET && n_distinct > 0.0 && (complete || singles == 0)) {
+            let average = float(non_null) / float(distinct.max(1));
+            common.retain(|(_, count)| *count >= 2 && float(*count) > 1.25 * average);
+        }
+        common.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| compare_for_sort(a.0, b.0)));
+        common.truncate(STATISTICS_TARGET);
+
+        ColumnStatistics {
+            null_frac: if sampled == 0 { 0.0 } else { float(nulls) / float(sampled) },
+            avg_width: width.checked_div(non_null).unwrap_or(0),
+            n_distinct,
+            most_common_freqs: common.iter().map(|(_, count)| float(*count) / float(sampled)).collect(),
+            most_common_vals: common.into_iter().map(|(value, _)| value.clone()).collect(),
+        }
+    }
+}
+
+/// xorshift64: sampling only needs to be unbiased, not unpredictable
+struct SampleRandom(u64);
+
+impl SampleRandom {
+    const fn new() -> Self {
+        Self(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in `0..n`
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        usize::try_from(self.0 % n as u64).unwrap_or(0)
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn float(n: usize) -> f64 {
+    n as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_column_statistics() {
+        let values: Vec<Value> = (0..1000_i64)
+            .map(|n| match n % 10 {
+                0 => Value::Null,
+                1..=6 => Value::Text("active".to_string()),
+                7 | 8 => Value::Text("closed".to_string()),
+                _ => Value::Text(format!("other{n}")),
+            })
+            .collect();
+        let refs: Vec<&Value> = values.iter().collect();
+
+        // Whole table read: exact counts
+        let stats = AnalyzeExecutor::column_statistics(&refs, 1000);
+        assert!((stats.null_frac - 0.1).abs() < 1e-9);
+        // 102 distinct values are over a tenth of the rows: stored as a share
+        assert!((stats.n_distinct + 0.102).abs() < 1e-9);
+        assert_eq!(stats.most_common_vals[..2], [Value::Text("active".to_string()), Value::Text("closed".to_string())]);
+        assert!((stats.most_common_freqs[0] - 0.6).abs() < 1e-9);
+        assert!((stats.equal_fraction(&Value::Text("closed".to_string()), 1000) - 0.2).abs() < 1e-9);
+
+        // A sample of a larger table: the unique values are no MCVs and their
+        // count is scaled up to the table
+        let stats = AnalyzeExecutor::column_statistics(&refs, 100_000);
+        assert_eq!(stats.most_common_vals.len(), 2);
+        assert!(stats.n_distinct > 102.0 && stats.n_distinct < 200.0, "{}", stats.n_distinct);
+        let rare = stats.equal_fraction(&Value::Text("unseen".to_string()), 100_000);
+        assert!(rare > 0.0 && rare < 0.001, "{rare}");
+
+        // All distinct: a unique column
+        let ids: Vec<Value> = (0..500_i64).map(Value::Integer).collect();
+        let stats = AnalyzeExecutor::column_statistics(&ids.iter().collect::<Vec<_>>(), 500);
+        assert!((stats.n_distinct + 1.0).abs() < 1e-9);
+        assert!(stats.most_common_vals.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_and_pg_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| {
+            run_sql(db, &mut storage, &tx_manager, sql).unwrap()
+        };
+
+        exec(&mut db, "CREATE TABLE orders (id INTEGER, status TEXT, note TEXT)");
+        for id in 0..100 {
+            let status = if id % 4 == 0 { "shipped" } else { "pending" };
+            let note = if id % 2 == 0 { "NULL".to_string() } else { format!("'note {id}'") };
+            exec(&mut db, &format!("INSERT INTO orders VALUES ({id}, '{status}', {note})"));
+        }
+        assert!(db.statistics.table("orders").is_none());
+
+        exec(&mut db, "ANALYZE orders");
+        let stats = db.statistics.table("orders").unwrap();
+        assert_eq!(stats.reltuples, 100);
+        let status_stats = stats.column("status").unwrap();
+        assert_eq!(status_stats.most_common_vals, [Value::Text("pending".to_string()), Value::Text("shipped".to_string())]);
+        assert!((status_stats.most_common_freqs[1] - 0.25).abs() < 1e-9);
+        assert!((stats.column("note").unwrap().null_frac - 0.5).abs() < 1e-9);
+
+        let QueryResult::Rows(rows, columns) = exec(&mut db, "SELECT * FROM pg_stats") else {
+            panic!("Expected Rows result");
+        };
+        assert_eq!(columns, ["schemaname", "tablename", "attname", "null_frac", "avg_width", "n_distinct", "most_common_vals", "most_common_freqs"]);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][..3], ["public", "orders", "id"]);
+        assert_eq!(rows[0][5], "-1");
+        assert_eq!(rows[0][6], "NULL");
+        assert_eq!(rows[1][2..], ["status", "0", "19", "2", "{pending,shipped}", "{0.75,0.25}"]);
+        assert_eq!(rows[2][3], "0.5");
+
+        exec(&mut db, "DROP TABLE orders");
+        assert!(db.statistics.table("orders").is_none());
+    }
+}
@@ -0,0 +1,205 @@
+/// CLUSTER: rewrite a table in the order of one of its indexes (v2.7.0)
+///
+/// The rows are read in the key order of a B-tree index and written back into
+/// fresh pages, so rows with neighbouring keys share pages: range scans on
+/// that index touch few pages, and block-range summaries over the key stay
+/// narrow. Useful after bulk loads that arrive out of order. Like VACUUM,
+/// the rewrite leaves out row versions no transaction can see anymore.
+///
+/// The index is recorded in the table metadata (`clustered_on`, shown as
+/// `pg_index.indisclustered`), so `CLUSTER table` and a bare `CLUSTER` can
+/// repeat the rewrite later. The order is not maintained for new rows.
+use std::ops::Bound;
+use crate::index::{Index, IndexType};
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError, Row};
+use super::dispatcher_executor::QueryResult;
+use super::index::IndexExecutor;
+
+pub struct ClusterExecutor;
+
+impl ClusterExecutor {
+    /// CLUSTER [table [USING index]]
+    ///
+    /// Without an index the one the table was clustered on before is used;
+    /// without a table every table clustered before is rewritten again.
+    pub fn cluster(
+        db: &mut Database,
+        table: Option<String>,
+        index: Option<String>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &mut DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let targets: Vec<(String, String)> = match (table, index) {
+            (Some(table), Some(index)) => vec![(table, index)],
+            (Some(table), None) => {
+                if !db.tables.contains_key(&table) {
+                    return Err(DatabaseError::TableNotFound(table));
+                }
+                let index = db
+                    .table_metadata
+                    .get(&table)
+                    .and_then(|metadata| metadata.clustered_on.clone())
+                    .ok_or_else(|| {
+                        DatabaseError::ParseError(format!("There is no previously clustered index for table '{table}'"))
+                    })?;
+                vec![(table, index)]
+            }
+            (None, _) => {
+                let mut targets: Vec<(String, String)> = db
+                    .table_metadata
+                    .iter()
+                    .filter_map(|(table, metadata)| Some((table.clone(), metadata.clustered_on.clone()?)))
+                    .collect();
+                targets.sort();
+                targets
+            }
+        };
+
+        for (table, index) in &targets {
+            Self::cluster_table(db, table, index, tx_manager, database_storage)?;
+        }
+
+        Ok(QueryResult::Success("CLUSTER".to_string()))
+    }
+
+    fn cluster_table(
+        db: &mut Database,
+        table_name: &str,
+        index_name: &str,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &mut DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        let table = db.get_table(table_name).ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        if table.partitioning.is_some() || table.foreign.is_some() {
+            return Err(DatabaseError::ParseError(format!("Cannot cluster '{table_name}': it has no rows of its own")));
+        }
+        let cluster_index = db
+            .indexes
+            .get(index_name)
+            .filter(|index| index.table_name() == table_name)
+            .ok_or_else(|| {
+                DatabaseError::ParseError(format!("Index '{index_name}' for table '{table_name}' does not exist"))
+            })?;
+        if cluster_index.index_type() != IndexType::BTree {
+            return Err(DatabaseError::ParseError(format!(
+                "Cannot cluster on index '{index_name}': hash indexes have no order"
+            )));
+        }
+
+        let paged_table = database_storage
+            .get_paged_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let mut rows: Vec<Option<Row>> = paged_table.get_all_rows()?.into_iter().map(Some).collect();
+
+        // Key order first; rows the index does not know keep their order at the end
+        let mut order = cluster_index.search_range(&[], Bound::Unbounded, Bound::Unbounded).unwrap_or_default();
+        order.extend(0..rows.len());
+        let horizon = tx_manager.get_oldest_active_tx();
+        let clustered: Vec<Row> = order
+            .into_iter()
+            .filter_map(|position| rows.get_mut(position).and_then(Option::take))
+            .filter(|row| !row.is_dead(horizon))
+            .collect();
+
+        // Indexes point at row positions: build them for the new order before
+        // anything is changed, so a failure leaves the table as it was
+        let mut rebuilt: Vec<(String, Index)> = Vec::new();
+        for (name, index) in db.indexes.iter().filter(|(_, index)| index.table_name() == table_name) {
+            let column_indices = index
+                .column_names()
+                .iter()
+                .map(|column| table.get_column_index(column).ok_or_else(|| DatabaseError::ColumnNotFound(column.clone())))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut index = index.clone();
+            index.clear();
+            IndexExecutor::populate(&mut index, &column_indices, &clustered)?;
+            rebuilt.push((name.clone(), index));
+        }
+
+        database_storage
+            .get_paged_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?
+            .rewrite(clustered)?;
+        database_storage.note_vacuum(horizon);  // v2.7.0
+        db.indexes.extend(rebuilt);
+        if let Some(metadata) = db.table_metadata.get_mut(table_name) {
+            metadata.clustered_on = Some(index_name.to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::executor::system_catalogs::SystemCatalog;
+    use tempfile::TempDir;
+
+    struct Session {
+        db: Database,
+        storage: DatabaseStorage,
+        tx_manager: GlobalTransactionManager,
+    }
+
+    impl Session {
+        fn exec(&mut self, sql: &str) -> Result<QueryResult, DatabaseError> {
+            run_sql(&mut self.db, &mut self.storage, &self.tx_manager, sql)
+        }
+
+        fn column(&mut self, sql: &str) -> Vec<String> {
+            match self.exec(sql).unwrap() {
+                QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect(),
+                QueryResult::Success(_) => panic!("Expected Rows result"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cluster_rewrites_in_index_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut session = Session {
+            db: Database::new("test".to_string()),
+            storage: DatabaseStorage::new(temp_dir.path(), 100).unwrap(),
+            tx_manager: GlobalTransactionManager::new(),
+        };
+
+        session.exec("CREATE TABLE readings (id INTEGER, taken_at INTEGER, sensor TEXT)").unwrap();
+        session.exec("CREATE INDEX idx_taken_at ON readings (taken_at)").unwrap();
+        session.exec("CREATE UNIQUE INDEX idx_id ON readings (id)").unwrap();
+        session.exec("CREATE INDEX idx_sensor ON readings (sensor) USING HASH").unwrap();
+        // Loaded out of order
+        for id in 0..50 {
+            session.exec(&format!("INSERT INTO readings VALUES ({id}, {}, 's{}')", (id * 17) % 50, id % 5)).unwrap();
+        }
+
+        assert!(session.exec("CLUSTER readings").is_err());
+        assert!(session.exec("CLUSTER readings USING idx_sensor").is_err());
+        assert!(session.exec("CLUSTER readings USING missing").is_err());
+
+        session.exec("CLUSTER readings USING idx_taken_at").unwrap();
+        // The heap now holds the rows in taken_at order
+        let taken: Vec<i64> = session.column("SELECT taken_at FROM readings").iter().map(|v| v.parse().unwrap()).collect();
+        assert_eq!(taken, (0..50).collect::<Vec<_>>());
+
+        // Every index points at the new positions
+        assert_eq!(session.column("SELECT taken_at FROM readings WHERE id = 3"), ["1"]);
+        assert_eq!(session.column("SELECT id FROM readings WHERE taken_at = 17"), ["1"]);
+        assert_eq!(session.column("SELECT id FROM readings WHERE sensor = 's2'").len(), 10);
+
+        // Recorded for the next CLUSTER and in pg_index
+        assert_eq!(session.db.table_metadata["readings"].clustered_on.as_deref(), Some("idx_taken_at"));
+        session.exec("INSERT INTO readings VALUES (50, -1, 's0')").unwrap();
+        session.exec("CLUSTER").unwrap();
+        assert_eq!(session.column("SELECT id FROM readings")[0], "50");
+        match SystemCatalog::query("pg_catalog.pg_index", &session.db, &session.storage).unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(rows.iter().filter(|row| row[4] == "true").count(), 1),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+
+        session.exec("DROP INDEX idx_taken_at").unwrap();
+        assert!(session.db.table_metadata["readings"].clustered_on.is_none());
+    }
+}
@@ -3,12 +3,16 @@
 /// This module handles evaluation of SQL WHERE conditions against rows.
 /// Supports: =, !=, >, <, >=, <=, BETWEEN, LIKE, IN, IS NULL, AND, OR operators (v1.8.0).
 /// v2.6.0: Added subquery support (IN, EXISTS, scalar subqueries).
-use crate::types::{Column, Row, Value, DatabaseError, Table};
-use crate::parser::Condition;
+/// v2.7.0: Three-valued logic - comparisons with NULL are UNKNOWN, NOT added.
+/// v2.7.0: Comparisons between expressions (`CASE ... END = 'x'`, `a + b > c`).
+use std::cmp::Ordering;
+use crate::types::{Collation, Column, Row, Value, DatabaseError, Table};
+use crate::parser::{CompareOperator, Condition};
 use crate::core::Database;
 use crate::storage::DatabaseStorage;
 use crate::transaction::GlobalTransactionManager;
 use crate::executor::subquery::{SubqueryExecutor, SubqueryContext};
+use crate::executor::functions::FunctionExecutor;
 
 pub struct ConditionEvaluator;
 
@@ -25,72 +29,98 @@ impl ConditionEvaluator {
     /// Evaluate condition against a row using column metadata
     ///
     /// This is the core evaluation function that works with any column slice.
+    /// A row passes only when the condition is true; UNKNOWN filters it out.
     pub fn evaluate_with_columns(
         columns: &[Column],
         row: &Row,
         condition: &Condition,
     ) -> Result<bool, DatabaseError> {
+        Ok(Self::truth_with_columns(columns, row, condition)? == Some(true))
+    }
+
+    /// Three-valued truth of a condition (v2.7.0): `None` is UNKNOWN
+    ///
+    /// Comparisons with NULL are UNKNOWN; AND, OR and NOT follow the SQL truth
+    /// tables (`false AND unknown` is false, `true OR unknown` is true).
+    pub fn truth_with_columns(
+        columns: &[Column],
+        row: &Row,
+        condition: &Condition,
+    ) -> Result<Option<bool>, DatabaseError> {
         match condition {
             Condition::Equals(col, val) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Ok(&row.values[idx] == val)
+                Ok(Self::equals(columns[idx].collation, &row.values[idx], val))
             }
             Condition::NotEquals(col, val) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Ok(&row.values[idx] != val)
+                Ok(Self::equals(columns[idx].collation, &row.values[idx], val).map(|eq| !eq))
+            }
+            Condition::ColumnEquals(left, right) => {
+                let left = Self::get_column_index(columns, left)?;
+                let right = Self::get_column_index(columns, right)?;
+                Ok(Self::equals(columns[left].collation, &row.values[left], &row.values[right]))
+            }
+            Condition::Compare(left, op, right) => {
+                let left = FunctionExecutor::evaluate_values(left, columns, &row.values)?;
+                let right = FunctionExecutor::evaluate_values(right, columns, &row.values)?;
+                Self::compare_with(&left, *op, &right)
             }
             Condition::GreaterThan(col, val) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Self::compare_greater_than(&row.values[idx], val)
+                Self::compare_is(columns[idx].collation, &row.values[idx], val, Ordering::is_gt)
             }
             Condition::LessThan(col, val) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Self::compare_less_than(&row.values[idx], val)
+                Self::compare_is(columns[idx].collation, &row.values[idx], val, Ordering::is_lt)
             }
             Condition::GreaterThanOrEqual(col, val) => {
                 let idx = Self::get_column_index(columns, col)?;
-                let gt = Self::compare_greater_than(&row.values[idx], val)?;
-                let eq = &row.values[idx] == val;
-                Ok(gt || eq)
+                Self::compare_is(columns[idx].collation, &row.values[idx], val, Ordering::is_ge)
             }
             Condition::LessThanOrEqual(col, val) => {
                 let idx = Self::get_column_index(columns, col)?;
-                let lt = Self::compare_less_than(&row.values[idx], val)?;
-                let eq = &row.values[idx] == val;
-                Ok(lt || eq)
+                Self::compare_is(columns[idx].collation, &row.values[idx], val, Ordering::is_le)
             }
             Condition::Between(col, low, high) => {
                 let idx = Self::get_column_index(columns, col)?;
                 let val = &row.values[idx];
-                let ge_low = Self::compare_greater_than(val, low)? || val == low;
-                let le_high = Self::compare_less_than(val, high)? || val == high;
-                Ok(ge_low && le_high)
+                let ge_low = Self::compare_is(columns[idx].collation, val, low, Ordering::is_ge)?;
+                let le_high = Self::compare_is(columns[idx].collation, val, high, Ordering::is_le)?;
+                Ok(Self::and(ge_low, le_high))
             }
             Condition::Like(col, pattern) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Self::match_like(&row.values[idx], pattern)
+                Self::match_like(&row.values[idx], pattern, columns[idx].collation == Collation::CaseInsensitive)
+            }
+            Condition::ILike(col, pattern) => {
+                let idx = Self::get_column_index(columns, col)?;
+                Self::match_like(&row.values[idx], pattern, true)
             }
             Condition::In(col, values) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Ok(values.contains(&row.values[idx]))
+                Ok(Self::in_list(columns[idx].collation, &row.values[idx], values))
             }
             Condition::IsNull(col) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Ok(matches!(row.values[idx], Value::Null))
+                Ok(Some(matches!(row.values[idx], Value::Null)))
             }
             Condition::IsNotNull(col) => {
                 let idx = Self::get_column_index(columns, col)?;
-                Ok(!matches!(row.values[idx], Value::Null))
+                Ok(Some(!matches!(row.values[idx], Value::Null)))
             }
             Condition::And(left, right) => {
-                let left_result = Self::evaluate_with_columns(columns, row, left)?;
-                let right_result = Self::evaluate_with_columns(columns, row, right)?;
-                Ok(left_result && right_result)
+                let left_result = Self::truth_with_columns(columns, row, left)?;
+                let right_result = Self::truth_with_columns(columns, row, right)?;
+                Ok(Self::and(left_result, right_result))
             }
             Condition::Or(left, right) => {
-                let left_result = Self::evaluate_with_columns(columns, row, left)?;
-                let right_result = Self::evaluate_with_columns(columns, row, right)?;
-                Ok(left_result || right_result)
+                let left_result = Self::truth_with_columns(columns, row, left)?;
+                let right_result = Self::truth_with_columns(columns, row, right)?;
+                Ok(Self::or(left_result, right_result))
+            }
+            Condition::Not(inner) => {
+                Ok(Self::truth_with_columns(columns, row, inner)?.map(|truth| !truth))
             }
             // v2.6.0: Subquery conditions (stub implementations)
             Condition::InSubquery(_, _) => {
@@ -131,11 +161,33 @@ impl ConditionEvaluator {
         database_storage: &DatabaseStorage,
         subquery_context: &SubqueryContext,
     ) -> Result<bool, DatabaseError> {
+        let truth = Self::truth_with_context(
+            columns,
+            row,
+            condition,
+            db,
+            tx_manager,
+            database_storage,
+            subquery_context,
+        )?;
+        Ok(truth == Some(true))
+    }
+
+    /// Three-valued truth of a condition with subqueries (v2.7.0)
+    #[allow(clippy::too_many_arguments)]
+    pub fn truth_with_context(
+        columns: &[Column],
+        row: &Row,
+        condition: &Condition,
+        db: &Database,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+        subquery_context: &SubqueryContext,
+    ) -> Result<Option<bool>, DatabaseError> {
         match condition {
             // v2.6.0: Subquery conditions
             Condition::InSubquery(col, stmt) => {
                 let idx = Self::get_column_index(columns, col)?;
-                let row_value_str = row.values[idx].to_string();
                 let subquery_values = SubqueryExecutor::execute_in(
                     db,
                     stmt,
@@ -143,13 +195,10 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                // Compare string representations since QueryResult returns strings
-                let subquery_strings: Vec<String> = subquery_values.iter().map(|v| v.to_string()).collect();
-                Ok(subquery_strings.contains(&row_value_str))
+                Ok(Self::in_list(columns[idx].collation, &row.values[idx], &subquery_values))
             }
             Condition::NotInSubquery(col, stmt) => {
                 let idx = Self::get_column_index(columns, col)?;
-                let row_value_str = row.values[idx].to_string();
                 let subquery_values = SubqueryExecutor::execute_in(
                     db,
                     stmt,
@@ -157,18 +206,17 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                // Compare string representations since QueryResult returns strings
-                let subquery_strings: Vec<String> = subquery_values.iter().map(|v| v.to_string()).collect();
-                Ok(!subquery_strings.contains(&row_value_str))
+                Ok(Self::in_list(columns[idx].collation, &row.values[idx], &subquery_values).map(|found| !found))
             }
             Condition::Exists(stmt) => {
-                SubqueryExecutor::execute_exists(
+                let exists = SubqueryExecutor::execute_exists(
                     db,
                     stmt,
                     tx_manager,
                     database_storage,
                     subquery_context,
-                )
+                )?;
+                Ok(Some(exists))
             }
             Condition::NotExists(stmt) => {
                 let exists = SubqueryExecutor::execute_exists(
@@ -178,7 +226,7 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                Ok(!exists)
+                Ok(Some(!exists))
             }
             Condition::EqualsSubquery(col, stmt) => {
                 let idx = Self::get_column_index(columns, col)?;
@@ -190,7 +238,7 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                Ok(row_value == &subquery_value)
+                Ok(Self::equals(columns[idx].collation, row_value, &subquery_value))
             }
             Condition::GreaterThanSubquery(col, stmt) => {
                 let idx = Self::get_column_index(columns, col)?;
@@ -202,7 +250,7 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                Self::compare_greater_than(row_value, &subquery_value)
+                Self::compare_is(columns[idx].collation, row_value, &subquery_value, Ordering::is_gt)
             }
             Condition::LessThanSubquery(col, stmt) => {
                 let idx = Self::get_column_index(columns, col)?;
@@ -214,11 +262,17 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                Self::compare_less_than(row_value, &subquery_value)
+                Self::compare_is(columns[idx].collation, row_value, &subquery_value, Ordering::is_lt)
+            }
+            // v2.7.0: expressions may call stored functions or contain subqueries
+            Condition::Compare(left, op, right) => {
+                let left = FunctionExecutor::evaluate(left, columns, row, db, tx_manager, database_storage)?;
+                let right = FunctionExecutor::evaluate(right, columns, row, db, tx_manager, database_storage)?;
+                Self::compare_with(&left, *op, &right)
             }
-            // Recursive handling of AND/OR with subquery support
+            // Recursive handling of AND/OR/NOT with subquery support
             Condition::And(left, right) => {
-                let left_result = Self::evaluate_with_context(
+                let left_result = Self::truth_with_context(
                     columns,
                     row,
                     left,
@@ -227,7 +281,7 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                let right_result = Self::evaluate_with_context(
+                let right_result = Self::truth_with_context(
                     columns,
                     row,
                     right,
@@ -236,10 +290,10 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                Ok(left_result && right_result)
+                Ok(Self::and(left_result, right_result))
             }
             Condition::Or(left, right) => {
-                let left_result = Self::evaluate_with_context(
+                let left_result = Self::truth_with_context(
                     columns,
                     row,
                     left,
@@ -248,7 +302,7 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                let right_result = Self::evaluate_with_context(
+                let right_result = Self::truth_with_context(
                     columns,
                     row,
                     right,
@@ -257,13 +311,70 @@ impl ConditionEvaluator {
                     database_storage,
                     subquery_context,
                 )?;
-                Ok(left_result || right_result)
+                Ok(Self::or(left_result, right_result))
             }
-            // For all other conditions, delegate to evaluate_with_columns
-            _ => Self::evaluate_with_columns(columns, row, condition),
+            Condition::Not(inner) => {
+                let truth = Self::truth_with_context(
+                    columns,
+                    row,
+                    inner,
+                    db,
+                    tx_manager,
+                    database_storage,
+                    subquery_context,
+                )?;
+                Ok(truth.map(|truth| !truth))
+            }
+            // For all other conditions, delegate to truth_with_columns
+            _ => Self::truth_with_columns(columns, row, condition),
+        }
+    }
+
+    /// Three-valued AND: false wins over UNKNOWN
+    const fn and(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+        match (left, right) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Three-valued OR: true wins over UNKNOWN
+    const fn or(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+        match (left, right) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
         }
     }
 
+    /// `left op right` for two evaluated expressions (v2.7.0)
+    pub fn compare_with(left: &Value, op: CompareOperator, right: &Value) -> Result<Option<bool>, DatabaseError> {
+        match op {
+            CompareOperator::Equal => Ok(Self::equals(Collation::C, left, right)),
+            CompareOperator::NotEqual => Ok(Self::equals(Collation::C, left, right).map(|eq| !eq)),
+            CompareOperator::Less => Self::compare_is(Collation::C, left, right, Ordering::is_lt),
+            CompareOperator::LessOrEqual => Self::compare_is(Collation::C, left, right, Ordering::is_le),
+            CompareOperator::Greater => Self::compare_is(Collation::C, left, right, Ordering::is_gt),
+            CompareOperator::GreaterOrEqual => Self::compare_is(Collation::C, left, right, Ordering::is_ge),
+        }
+    }
+
+    /// `a = b`; UNKNOWN if either side is NULL (v2.7.0: text by the column's collation)
+    fn equals(collation: Collation, a: &Value, b: &Value) -> Option<bool> {
+        if matches!(a, Value::Null) || matches!(b, Value::Null) {
+            return None;
+        }
+        Some(collation.equal(a, b))
+    }
+
+    /// `value IN (list)`: true on a match, otherwise UNKNOWN if NULL is involved
+    fn in_list(collation: Collation, value: &Value, list: &[Value]) -> Option<bool> {
+        list.iter()
+            .map(|item| Self::equals(collation, value, item))
+            .fold(Some(false), Self::or)
+    }
+
     /// Get column index by name
     fn get_column_index(columns: &[Column], col_name: &str) -> Result<usize, DatabaseError> {
         columns
@@ -272,66 +383,37 @@ impl ConditionEvaluator {
             .ok_or_else(|| DatabaseError::ParseError(format!("Unknown column: {col_name}")))
     }
 
-    /// Compare two values for greater-than
-    fn compare_greater_than(a: &Value, b: &Value) -> Result<bool, DatabaseError> {
-        match (a, b) {
-            (Value::Integer(x), Value::Integer(y)) => Ok(x > y),
-            (Value::SmallInt(x), Value::SmallInt(y)) => Ok(x > y),
-            (Value::Real(x), Value::Real(y)) => Ok(x > y),
-            (Value::Text(x), Value::Text(y)) => Ok(x > y),
-            // Cross-type numeric comparisons
-            (Value::Integer(x), Value::SmallInt(y)) => Ok(*x > i64::from(*y)),
-            (Value::SmallInt(x), Value::Integer(y)) => Ok(i64::from(*x) > *y),
-            // Text to numeric coercion (v2.6.0: for subqueries)
-            (Value::SmallInt(x), Value::Text(y)) => {
-                y.parse::<i16>().map(|y_num| *x > y_num).or(Err(DatabaseError::TypeMismatch))
-            }
-            (Value::Integer(x), Value::Text(y)) => {
-                y.parse::<i64>().map(|y_num| *x > y_num).or(Err(DatabaseError::TypeMismatch))
-            }
-            (Value::Text(x), Value::SmallInt(y)) => {
-                x.parse::<i16>().map(|x_num| x_num > *y).or(Err(DatabaseError::TypeMismatch))
-            }
-            (Value::Text(x), Value::Integer(y)) => {
-                x.parse::<i64>().map(|x_num| x_num > *y).or(Err(DatabaseError::TypeMismatch))
-            }
-            _ => Err(DatabaseError::TypeMismatch),
+    /// Order of two values for <, >, <=, >= and BETWEEN (v2.7.0)
+    ///
+    /// Numbers are promoted to a common type and text is coerced to the type of
+    /// the other side (see `compare_values`). `None` if either side is NULL, so
+    /// the comparison is UNKNOWN; values that cannot be compared are an error.
+    fn compare(collation: Collation, a: &Value, b: &Value) -> Result<Option<Ordering>, DatabaseError> {
+        if matches!(a, Value::Null) || matches!(b, Value::Null) {
+            return Ok(None);
         }
+        collation.compare(a, b).map(Some).ok_or(DatabaseError::TypeMismatch)
     }
 
-    /// Compare two values for less-than
-    fn compare_less_than(a: &Value, b: &Value) -> Result<bool, DatabaseError> {
-        match (a, b) {
-            (Value::Integer(x), Value::Integer(y)) => Ok(x < y),
-            (Value::SmallInt(x), Value::SmallInt(y)) => Ok(x < y),
-            (Value::Real(x), Value::Real(y)) => Ok(x < y),
-            (Value::Text(x), Value::Text(y)) => Ok(x < y),
-            // Cross-type numeric comparisons
-            (Value::Integer(x), Value::SmallInt(y)) => Ok(*x < i64::from(*y)),
-            (Value::SmallInt(x), Value::Integer(y)) => Ok(i64::from(*x) < *y),
-            // Text to numeric coercion (v2.6.0: for subqueries)
-            (Value::SmallInt(x), Value::Text(y)) => {
-                y.parse::<i16>().map(|y_num| *x < y_num).or(Err(DatabaseError::TypeMismatch))
-            }
-            (Value::Integer(x), Value::Text(y)) => {
-                y.parse::<i64>().map(|y_num| *x < y_num).or(Err(DatabaseError::TypeMismatch))
-            }
-            (Value::Text(x), Value::SmallInt(y)) => {
-                x.parse::<i16>().map(|x_num| x_num < *y).or(Err(DatabaseError::TypeMismatch))
-            }
-            (Value::Text(x), Value::Integer(y)) => {
-                x.parse::<i64>().map(|x_num| x_num < *y).or(Err(DatabaseError::TypeMismatch))
-            }
-            _ => Err(DatabaseError::TypeMismatch),
-        }
+    fn compare_is(
+        collation: Collation,
+        a: &Value,
+        b: &Value,
+        accept: fn(Ordering) -> bool,
+    ) -> Result<Option<bool>, DatabaseError> {
+        Ok(Self::compare(collation, a, b)?.map(accept))
     }
 
     /// Match LIKE pattern (v1.8.0)
     /// Supports: % (any chars), _ (single char)
-    fn match_like(value: &Value, pattern: &str) -> Result<bool, DatabaseError> {
+    /// v2.7.0: `ignore_case` for ILIKE and case-insensitive columns
+    fn match_like(value: &Value, pattern: &str, ignore_case: bool) -> Result<Option<bool>, DatabaseError> {
         match value {
-            Value::Text(text) => Ok(Self::like_pattern_match(text, pattern)),
-            Value::Null => Ok(false), // NULL doesn't match anything
+            Value::Text(text) if ignore_case => {
+                Ok(Some(Self::like_pattern_match(&text.to_lowercase(), &pattern.to_lowercase())))
+            }
+            Value::Text(text) => Ok(Some(Self::like_pattern_match(text, pattern))),
+            Value::Null => Ok(None), // NULL LIKE anything is UNKNOWN
             _ => Err(DatabaseError::TypeMismatch),
         }
     }
@@ -403,6 +485,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "name".to_string(),
@@ -411,6 +494,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "age".to_string(),
@@ -419,6 +503,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ]
     }
@@ -487,6 +572,32 @@ mod tests {
         assert!(!ConditionEvaluator::evaluate_with_columns(&columns, &row, &cond).unwrap());
     }
 
+    #[test]
+    fn test_mixed_type_comparisons() {
+        let mut columns = create_test_columns();
+        columns[2].data_type = DataType::SmallInt;
+        let row = Row::new(vec![
+            Value::Integer(1),
+            Value::Text("Alice".to_string()),
+            Value::SmallInt(30),
+        ]);
+
+        let check = |cond: Condition| ConditionEvaluator::evaluate_with_columns(&columns, &row, &cond).unwrap();
+        assert!(check(Condition::Equals("age".to_string(), Value::Integer(30))));
+        assert!(check(Condition::GreaterThan("age".to_string(), Value::Real(29.5))));
+        assert!(check(Condition::LessThanOrEqual("id".to_string(), Value::Text("1".to_string()))));
+        assert!(check(Condition::Between("age".to_string(), Value::Real(29.9), Value::Integer(31))));
+        assert!(check(Condition::In("id".to_string(), vec![Value::Real(1.0)])));
+
+        // NULL is never greater or smaller than anything
+        let row = Row::new(vec![Value::Integer(1), Value::Text("Bob".to_string()), Value::Null]);
+        let cond = Condition::GreaterThan("age".to_string(), Value::Integer(0));
+        assert!(!ConditionEvaluator::evaluate_with_columns(&columns, &row, &cond).unwrap());
+
+        let cond = Condition::GreaterThan("name".to_string(), Value::Integer(0));
+        assert!(ConditionEvaluator::evaluate_with_columns(&columns, &row, &cond).is_err());
+    }
+
     #[test]
     fn test_and_condition() {
         let columns = create_test_columns();
@@ -734,4 +845,35 @@ mod tests {
         // name IS NOT NULL should be true for row without NULL
         assert!(ConditionEvaluator::evaluate_with_columns(&columns, &row_without_null, &cond).unwrap());
     }
+
+    #[test]
+    fn test_three_valued_logic() {
+        let columns = create_test_columns();
+        let row = Row::new(vec![Value::Integer(1), Value::Text("Bob".to_string()), Value::Null]);
+        let truth = |cond: &Condition| ConditionEvaluator::truth_with_columns(&columns, &row, cond).unwrap();
+        let age = |op: fn(String, Value) -> Condition| Box::new(op("age".to_string(), Value::Integer(30)));
+        let id_is = |v: i64| Box::new(Condition::Equals("id".to_string(), Value::Integer(v)));
+
+        // Comparisons with NULL are UNKNOWN, and so is their negation
+        assert_eq!(truth(&Condition::Equals("age".to_string(), Value::Integer(30))), None);
+        assert_eq!(truth(&Condition::NotEquals("age".to_string(), Value::Integer(30))), None);
+        assert_eq!(truth(&Condition::Not(age(Condition::LessThan))), None);
+        assert_eq!(truth(&Condition::Equals("age".to_string(), Value::Null)), None);
+        assert_eq!(truth(&Condition::Like("age".to_string(), "%".to_string())), None);
+
+        assert_eq!(truth(&Condition::And(age(Condition::Equals), id_is(2))), Some(false));
+        assert_eq!(truth(&Condition::And(age(Condition::Equals), id_is(1))), None);
+        assert_eq!(truth(&Condition::Or(age(Condition::Equals), id_is(1))), Some(true));
+        assert_eq!(truth(&Condition::Or(age(Condition::Equals), id_is(2))), None);
+
+        // x NOT IN (..., NULL) is never true
+        let in_list = |values: Vec<Value>| Condition::Not(Box::new(Condition::In("id".to_string(), values)));
+        assert_eq!(truth(&in_list(vec![Value::Integer(2), Value::Null])), None);
+        assert_eq!(truth(&in_list(vec![Value::Integer(1), Value::Null])), Some(false));
+        assert_eq!(truth(&in_list(vec![Value::Integer(2)])), Some(true));
+
+        // Filtering only keeps rows where the condition is true
+        let cond = Condition::Not(age(Condition::Equals));
+        assert!(!ConditionEvaluator::evaluate_with_columns(&columns, &row, &cond).unwrap());
+    }
 }
@@ -0,0 +1,496 @@
+/// Table constraints: ALTER TABLE ... ADD / VALIDATE / DROP CONSTRAINT (v2.7.0)
+///
+/// A UNIQUE constraint is a unique index named after the constraint and a
+/// FOREIGN KEY constraint sets the foreign key of its column, so both are
+/// enforced like their column-level forms. CHECK constraints keep the
+/// condition source in `Table::constraints`; DML compiles them once per
+/// statement and rejects rows for which a condition is false (NULL passes).
+///
+/// ADD CONSTRAINT checks the existing rows first. With NOT VALID, CHECK and
+/// FOREIGN KEY constraints skip that scan and only apply to new rows until
+/// VALIDATE CONSTRAINT checks the old ones, so a large table is not scanned
+/// while the constraint is being added.
+use crate::index::IndexType;
+use crate::parser::Condition;
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Column, ConstraintKind, Database, DatabaseError, Row, Table, TableConstraint};
+use super::conditions::ConditionEvaluator;
+use super::dispatcher_executor::QueryResult;
+use super::dml::DmlExecutor;
+use super::index::IndexExecutor;
+use super::views::ViewResolver;
+
+/// Compiled CHECK constraints as (constraint name, condition)
+pub type CompiledChecks = Vec<(String, Condition)>;
+
+pub struct TableConstraints;
+
+impl TableConstraints {
+    /// ALTER TABLE ... ADD CONSTRAINT
+    pub fn add(
+        db: &mut Database,
+        table_name: &str,
+        name: String,
+        kind: ConstraintKind,
+        not_valid: bool,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &mut DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let table = db.get_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        if table.constraints.iter().any(|c| c.name == name) {
+            return Err(DatabaseError::ParseError(format!(
+                "constraint \"{name}\" for relation \"{table_name}\" already exists"
+            )));
+        }
+
+        match &kind {
+            ConstraintKind::Check(source) => {
+                let condition = Self::compile_check(&name, source, &table.columns)?;
+                if !not_valid {
+                    Self::check_existing_rows(table, &name, &condition, tx_manager, database_storage)?;
+                }
+            }
+            ConstraintKind::Unique(columns) => {
+                if not_valid {
+                    return Err(DatabaseError::ParseError(
+                        "UNIQUE constraints cannot be marked NOT VALID".to_string()
+                    ));
+                }
+                IndexExecutor::create_index(
+                    db, name.clone(), table_name.to_string(), columns.clone(), true, IndexType::BTree, database_storage,
+                )?;
+            }
+            ConstraintKind::ForeignKey { column, references } => {
+                let col = table.columns.iter().find(|c| c.name == *column)
+                    .ok_or_else(|| DatabaseError::ColumnNotFound(column.clone()))?;
+                if col.foreign_key.is_some() {
+                    return Err(DatabaseError::ParseError(format!(
+                        "Column '{column}' already has a foreign key"
+                    )));
+                }
+                let ref_table = db.get_table(&references.referenced_table)
+                    .ok_or_else(|| DatabaseError::ForeignKeyViolation(
+                        format!("Referenced table '{}' does not exist", references.referenced_table)
+                    ))?;
+                let ref_col = ref_table.columns.iter().find(|c| c.name == references.referenced_column)
+                    .ok_or_else(|| DatabaseError::ForeignKeyViolation(
+                        format!("Referenced column '{}' does not exist", references.referenced_column)
+                    ))?;
+                if !ref_col.primary_key && !ref_col.unique {
+                    return Err(DatabaseError::ForeignKeyViolation(
+                        "Referenced column must be PRIMARY KEY or UNIQUE".to_string()
+                    ));
+                }
+
+                let mut fk_column = col.clone();
+                fk_column.foreign_key = Some(references.clone());
+                if !not_valid {
+                    Self::check_foreign_key(db, table_name, &name, &fk_column, tx_manager, database_storage)?;
+                }
+                let table = db.get_table_mut(table_name)
+                    .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+                if let Some(col) = table.columns.iter_mut().find(|c| c.name == *column) {
+                    col.foreign_key = Some(references.clone());
+                }
+            }
+        }
+
+        let table = db.get_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        table.constraints.push(TableConstraint { name, kind, validated: !not_valid });
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
+    }
+
+    /// ALTER TABLE ... VALIDATE CONSTRAINT: check the rows NOT VALID skipped
+    pub fn validate(
+        db: &mut Database,
+        table_name: &str,
+        name: &str,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let table = db.get_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let constraint = table.constraints.iter().find(|c| c.name == name)
+            .ok_or_else(|| DatabaseError::ConstraintNotFound(name.to_string(), table_name.to_string()))?;
+
+        if !constraint.validated {
+            match &constraint.kind {
+                ConstraintKind::Check(source) => {
+                    let condition = Self::compile_check(name, source, &table.columns)?;
+                    Self::check_existing_rows(table, name, &condition, tx_manager, database_storage)?;
+                }
+                ConstraintKind::ForeignKey { column, .. } => {
+                    let fk_column = table.columns.iter().find(|c| c.name == *column)
+                        .ok_or_else(|| DatabaseError::ColumnNotFound(column.clone()))?
+                        .clone();
+                    Self::check_foreign_key(db, table_name, name, &fk_column, tx_manager, database_storage)?;
+                }
+                ConstraintKind::Unique(_) => {}
+            }
+        }
+
+        let table = db.get_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        if let Some(constraint) = table.constraints.iter_mut().find(|c| c.name == name) {
+            constraint.validated = true;
+        }
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
+    }
+
+    /// ALTER TABLE ... DROP CONSTRAINT
+    pub fn drop(
+        db: &mut Database,
+        table_name: &str,
+        name: &str,
+        if_exists: bool,
+    ) -> Result<QueryResult, DatabaseError> {
+        let table = db.get_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let Some(position) = table.constraints.iter().position(|c| c.name == name) else {
+            if if_exists {
+                return Ok(QueryResult::Success("ALTER TABLE".to_string()));
+            }
+            return Err(DatabaseError::ConstraintNotFound(name.to_string(), table_name.to_string()));
+        };
+
+        match table.constraints.remove(position).kind {
+            ConstraintKind::Check(_) => {}
+            ConstraintKind::ForeignKey { column, .. } => {
+                if let Some(col) = table.columns.iter_mut().find(|c| c.name == column) {
+                    col.foreign_key = None;
+                }
+            }
+            ConstraintKind::Unique(_) => {
+                if db.indexes.contains_key(name) {
+                    IndexExecutor::drop_index(db, name.to_string())?;
+                }
+            }
+        }
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
+    }
+
+    /// Parse and check the CHECK constraints of `table`
+    pub fn compile_checks(table: &Table) -> Result<CompiledChecks, DatabaseError> {
+        table.constraints
+            .iter()
+            .filter_map(|constraint| match &constraint.kind {
+                ConstraintKind::Check(source) => Some((&constraint.name, source)),
+                _ => None,
+            })
+            .map(|(name, source)| Ok((name.clone(), Self::compile_check(name, source, &table.columns)?)))
+            .collect()
+    }
+
+    /// Reject a full row (in table order) for which a CHECK constraint is false
+    pub fn check_row(
+        checks: &CompiledChecks,
+        table_name: &str,
+        columns: &[Column],
+        values: &[crate::types::Value],
+    ) -> Result<(), DatabaseError> {
+        if checks.is_empty() {
+            return Ok(());
+        }
+        let row = Row::new(values.to_vec());
+        for (name, condition) in checks {
+            if ConditionEvaluator::truth_with_columns(columns, &row, condition)? == Some(false) {
+                return Err(DatabaseError::CheckViolation(table_name.to_string(), name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse DROP INDEX of the index behind a UNIQUE constraint
+    pub fn check_drop_index(db: &Database, index: &str) -> Result<(), DatabaseError> {
+        let owner = db.tables.values().find(|table| {
+            table.constraints.iter().any(|c| c.name == index && matches!(c.kind, ConstraintKind::Unique(_)))
+        });
+        match owner {
+            Some(table) => Err(DatabaseError::DependentObjectsStillExist(
+                format!("index {index}"),
+                format!("constraint {index} on table {}", table.name),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Name of a CHECK constraint of `table` that reads `column`, if any
+    #[must_use]
+    pub fn dependent_check<'a>(table: &'a Table, column: &str) -> Option<&'a str> {
+        table.constraints.iter()
+            .find(|constraint| match &constraint.kind {
+                ConstraintKind::Check(source) => Self::check_references(source, column),
+                _ => false,
+            })
+            .map(|constraint| constraint.name.as_str())
+    }
+
+    /// Drop the constraints that use `column`, which is being dropped
+    pub fn forget_column(table: &mut Table, column: &str) {
+        table.constraints.retain(|constraint| match &constraint.kind {
+            ConstraintKind::Check(source) => !Self::check_references(source, column),
+            ConstraintKind::Unique(columns) => !columns.iter().any(|c| c == column),
+            ConstraintKind::ForeignKey { column: fk_column, .. } => fk_column != column,
+        });
+    }
+
+    /// Follow a column rename in the constraints of every table; CHECK
+    /// constraints reading the column block the rename (see `dependent_check`)
+    pub fn rename_column(db: &mut Database, table_name: &str, old_name: &str, new_name: &str) {
+        for table in db.tables.values_mut() {
+            let own = table.name == table_name;
+            for constraint in &mut table.constraints {
+                match &mut constraint.kind {
+                    ConstraintKind::Unique(columns) if own => {
+                        for column in columns.iter_mut().filter(|c| *c == old_name) {
+                            *column = new_name.to_string();
+                        }
+                    }
+                    ConstraintKind::ForeignKey { column, references } => {
+                        if own && column == old_name {
+                            *column = new_name.to_string();
+                        }
+                        if references.referenced_table == table_name && references.referenced_column == old_name {
+                            references.referenced_column = new_name.to_string();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Follow a table rename in the foreign key constraints referencing it
+    pub fn rename_table(db: &mut Database, old_name: &str, new_name: &str) {
+        for constraint in db.tables.values_mut().flat_map(|table| table.constraints.iter_mut()) {
+            if let ConstraintKind::ForeignKey { references, .. } = &mut constraint.kind
+                && references.referenced_table == old_name
+            {
+                references.referenced_table = new_name.to_string();
+            }
+        }
+    }
+
+    fn compile_check(name: &str, source: &str, columns: &[Column]) -> Result<Condition, DatabaseError> {
+        let condition = crate::parser::parse_condition(source).map_err(|e| {
+            DatabaseError::ParseError(format!("Invalid check constraint '{name}': {e}"))
+        })?;
+        let mut referenced = Vec::new();
+        ViewResolver::collect_condition_columns(&condition, &mut referenced);
+        if let Some(missing) = referenced.into_iter().find(|r| !columns.iter().any(|c| c.name == *r)) {
+            return Err(DatabaseError::ColumnNotFound(missing));
+        }
+        Ok(condition)
+    }
+
+    fn check_references(source: &str, column: &str) -> bool {
+        crate::parser::parse_condition(source).is_ok_and(|condition| {
+            let mut referenced = Vec::new();
+            ViewResolver::collect_condition_columns(&condition, &mut referenced);
+            referenced.iter().any(|r| r == column)
+        })
+    }
+
+    /// Fail if a current row of `table` makes `condition` false
+    fn check_existing_rows(
+        table: &Table,
+        name: &str,
+        condition: &Condition,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        let rows = Self::current_rows(&table.name, tx_manager, database_storage)?;
+        for row in &rows {
+            if ConditionEvaluator::truth_with_columns(&table.columns, row, condition)? == Some(false) {
+                return Err(DatabaseError::CheckViolatedByExistingRow(table.name.clone(), name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail if a current row of `table_name` has no match for the foreign key of `fk_column`
+    fn check_foreign_key(
+        db: &Database,
+        table_name: &str,
+        name: &str,
+        fk_column: &Column,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        let table = db.get_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let idx = table.get_column_index(&fk_column.name)
+            .ok_or_else(|| DatabaseError::ColumnNotFound(fk_column.name.clone()))?;
+        for row in Self::current_rows(table_name, tx_manager, database_storage)? {
+            DmlExecutor::validate_foreign_keys_with_storage(
+                &db.tables,
+                std::slice::from_ref(fk_column),
+                std::slice::from_ref(&row.values[idx]),
+                tx_manager,
+                database_storage,
+            )
+            .map_err(|e| match e {
+                DatabaseError::ForeignKeyViolation(reason) => DatabaseError::ForeignKeyViolation(format!(
+                    "constraint \"{name}\" of relation \"{table_name}\" is violated by some row: {reason}"
+                )),
+                other => other,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn current_rows(
+        table_name: &str,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let paged_table = database_storage.get_paged_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let current_tx_id = tx_manager.current_tx_id();
+        Ok(paged_table.get_all_rows()?.into_iter().filter(|row| row.is_visible(current_tx_id)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+
+    #[test]
+    fn test_add_validate_drop_constraints() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT)",
+            "CREATE TABLE orders (id INTEGER, user_id INTEGER, qty INTEGER)",
+            "INSERT INTO users VALUES (1, 'a@x'), (2, 'b@x')",
+            "INSERT INTO orders VALUES (1, 1, 5), (2, 3, 0), (3, NULL, NULL)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        // CHECK: existing rows are validated; NULL passes
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders ADD CONSTRAINT qty_positive CHECK (qty > 0)").unwrap_err();
+        assert!(matches!(err, DatabaseError::CheckViolatedByExistingRow(ref t, ref c) if t == "orders" && c == "qty_positive"));
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders ADD CONSTRAINT qty_positive CHECK (qty > 0) NOT VALID").unwrap();
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO orders VALUES (4, 1, -1)").unwrap_err();
+        assert!(matches!(err, DatabaseError::CheckViolation(_, ref c) if c == "qty_positive"));
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET qty = 0 WHERE id = 1").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders VALIDATE CONSTRAINT qty_positive").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET qty = 1 WHERE id = 2").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders VALIDATE CONSTRAINT qty_positive").unwrap();
+        assert!(db.get_table("orders").unwrap().constraints[0].validated);
+
+        // FOREIGN KEY: user 3 does not exist until NOT VALID + fix + VALIDATE
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders ADD CONSTRAINT orders_user_fk FOREIGN KEY (user_id) REFERENCES users (id)").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders ADD CONSTRAINT orders_user_fk FOREIGN KEY (user_id) REFERENCES users (id) NOT VALID").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO orders VALUES (5, 9, 1)").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders VALIDATE CONSTRAINT orders_user_fk").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET user_id = 2 WHERE id = 2").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders VALIDATE CONSTRAINT orders_user_fk").unwrap();
+
+        // UNIQUE: backed by an index, cannot be NOT VALID
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ADD CONSTRAINT email_key UNIQUE (email) NOT VALID").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ADD CONSTRAINT email_key UNIQUE (email)").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (3, 'a@x')").is_err());
+        assert!(matches!(run_sql(&mut db, &mut storage, &tx_manager, "DROP INDEX email_key"), Err(DatabaseError::DependentObjectsStillExist(..))));
+
+        // DROP CONSTRAINT lifts each of them
+        for sql in [
+            "ALTER TABLE users DROP CONSTRAINT email_key",
+            "ALTER TABLE orders DROP CONSTRAINT orders_user_fk",
+            "ALTER TABLE orders DROP CONSTRAINT qty_positive",
+            "ALTER TABLE orders DROP CONSTRAINT IF EXISTS qty_positive",
+            "INSERT INTO users VALUES (3, 'a@x')",
+            "INSERT INTO orders VALUES (6, 9, -1)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+        assert!(!db.indexes.contains_key("email_key"));
+        assert!(matches!(
+            run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders DROP CONSTRAINT qty_positive"),
+            Err(DatabaseError::ConstraintNotFound(..))
+        ));
+    }
+
+    #[test]
+    fn test_constraints_follow_columns() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE t (a INTEGER, b INTEGER)",
+            "ALTER TABLE t ADD CONSTRAINT a_small CHECK (a < 10)",
+            "ALTER TABLE t ADD CONSTRAINT b_key UNIQUE (b)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE t ADD CONSTRAINT bad CHECK (c > 0)").is_err());
+
+        // The CHECK reads `a` by name, so `a` cannot be renamed; `b` can
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE t RENAME COLUMN a TO x").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE t RENAME COLUMN b TO c").unwrap();
+        assert_eq!(db.get_table("t").unwrap().constraints[1].kind, ConstraintKind::Unique(vec!["c".to_string()]));
+
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE t DROP COLUMN a").unwrap();
+        let names: Vec<&str> = db.get_table("t").unwrap().constraints.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["b_key"]);
+    }
+
+    #[test]
+    fn test_not_null_constraints() {
+        // v2.7.0: NOT NULL and PRIMARY KEY columns reject NULL on INSERT and UPDATE
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, note TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (1, 'Alice', NULL)").unwrap();
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users (id, note) VALUES (2, 'x')").unwrap_err();
+        assert!(matches!(err, DatabaseError::NotNullViolation(ref t, ref c) if t == "users" && c == "name"));
+        assert_eq!(err.to_string(), "null value in column \"name\" of relation \"users\" violates not-null constraint");
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (NULL, 'Bob', NULL)").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users (name) VALUES ('Bob')").is_err());
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "UPDATE users SET name = NULL WHERE id = 1").unwrap_err();
+        assert!(matches!(err, DatabaseError::NotNullViolation(_, ref c) if c == "name"));
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE users SET note = NULL").unwrap();
+
+        match run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, name, note FROM users").unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(rows, vec![vec!["1", "Alice", "NULL"]]),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_alter_column_not_null() {
+        // v2.7.0: SET NOT NULL checks the existing rows, through an index when there is one
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_users_email ON users (email)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (1, NULL, NULL), (2, 'Bob', 'bob@x')").unwrap();
+
+        for column in ["name", "email"] {
+            let err = run_sql(&mut db, &mut storage, &tx_manager, &format!("ALTER TABLE users ALTER COLUMN {column} SET NOT NULL")).unwrap_err();
+            assert!(matches!(err, DatabaseError::ColumnContainsNulls(ref t, ref c) if t == "users" && c == column));
+        }
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE users SET name = 'Alice', email = 'alice@x' WHERE id = 1").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ALTER COLUMN name SET NOT NULL").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ALTER COLUMN email SET NOT NULL").unwrap();
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (3, NULL, 'c@x')").unwrap_err();
+        assert!(matches!(err, DatabaseError::NotNullViolation(_, ref c) if c == "name"));
+
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ALTER COLUMN name DROP NOT NULL").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (3, NULL, 'c@x')").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ALTER COLUMN id DROP NOT NULL").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users ALTER COLUMN nope SET NOT NULL").is_err());
+    }
+}
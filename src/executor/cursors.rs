@@ -0,0 +1,421 @@
+/// SQL cursors: DECLARE / FETCH / MOVE / CLOSE (v2.7.0)
+///
+/// A cursor lets a client walk a large result set in chunks instead of
+/// receiving it in one piece. It only lives inside a transaction block: the
+/// `GlobalTransactionManager` keeps the cursors of each transaction and
+/// drops them at COMMIT or ROLLBACK.
+///
+/// DECLARE runs the query once, through the snapshot of that moment, and
+/// writes its rows out like `ResultChunks` does (to a temp file, or kept in
+/// memory without a data directory). FETCH and MOVE page through the stored
+/// rows, so every FETCH continues the same result: rows other transactions
+/// commit in between neither show up nor go missing, and queries whose row
+/// order is not fixed (GROUP BY, no ORDER BY) are not read twice.
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use crate::parser::Statement;
+use crate::storage::{DatabaseStorage, TempFiles};
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError};
+use super::dispatcher_executor::{QueryExecutor, QueryResult, RowSink};
+use super::system_catalogs::SystemCatalog;
+
+/// Result rows written out once, read back in order
+type RowStream = Box<dyn Iterator<Item = Result<Vec<String>, DatabaseError>> + Send>;
+
+/// Is the query a SELECT (or a set operation) whose rows can be written out
+/// as they are produced? System catalogs are built in one piece
+fn readable(query: &Statement) -> bool {
+    match query {
+        Statement::Select { from, .. } => !SystemCatalog::is_system_catalog(from),
+        Statement::Union { .. } | Statement::Intersect { .. } | Statement::Except { .. } => true,
+        _ => false,
+    }
+}
+
+/// Hands the rows `run` produces to a temp file (or keeps them in memory
+/// without a data directory) and returns the column names and the rows
+fn write_out(
+    database_storage: &DatabaseStorage,
+    run: impl FnOnce(&mut RowSink<'_>) -> Result<Vec<String>, DatabaseError>,
+) -> Result<(Vec<String>, RowStream), DatabaseError> {
+    let mut file = database_storage.temp_files().map(TempFiles::create_result).transpose()?;
+    let mut held = Vec::new();
+    let columns = run(&mut |row| {
+        if let Some(file) = &mut file {
+            return file.append(&row);
+        }
+        held.push(row);
+        Ok(())
+    })?;
+    let rows: RowStream = match file {
+        Some(file) => Box::new(file.read()?),
+        None => Box::new(held.into_iter().map(Ok)),
+    };
+    Ok((columns, rows))
+}
+
+/// An open cursor
+#[derive(Clone)]
+pub struct Cursor {
+    columns: Vec<String>,
+    /// Rows not read yet, shared with the copy FETCH works on
+    rows: Arc<Mutex<RowStream>>,
+}
+
+impl fmt::Debug for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cursor").field("columns", &self.columns).finish_non_exhaustive()
+    }
+}
+
+impl Cursor {
+    /// Run the query and write out its whole result; a cursor is read past
+    /// `max_result_rows` a FETCH at a time
+    fn declare(
+        db: &Database,
+        query: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<Self, DatabaseError> {
+        if !readable(&query) {
+            return Err(DatabaseError::ParseError("Cursor query must be a SELECT from tables or views".to_string()));
+        }
+        let (columns, rows) = write_out(database_storage, |each| {
+            QueryExecutor::execute_read_unlimited_each(db, query, tx_manager, database_storage, each)
+        })?;
+        Ok(Self { columns, rows: Arc::new(Mutex::new(rows)) })
+    }
+
+    /// Read the next `count` rows (`None` = all remaining) and move past them
+    fn read(&self, count: Option<usize>) -> Result<QueryResult, DatabaseError> {
+        let mut rows = self.rows.lock().expect("cursor poisoned");
+        let read = rows.by_ref().take(count.unwrap_or(usize::MAX)).collect::<Result<Vec<_>, _>>()?;
+        Ok(QueryResult::Rows(read, self.columns.clone()))
+    }
+}
+
+/// Cursors of one transaction, by name
+pub type Cursors = HashMap<String, Cursor>;
+
+/// A query result read in chunks that stay within a memory budget
+///
+/// The server sends large results chunk by chunk (`max_result_memory`),
+/// so only one chunk is held at a time. The query runs once, with one
+/// snapshot: its rows are written to a temp file as they are produced (or
+/// kept in memory without a data directory) and the chunks are read back
+/// from there. Rows other transactions commit while the result is sent do
+/// not change it, and the database is not touched after the query.
+pub struct ResultChunks {
+    /// Taken by `run`
+    query: Option<Statement>,
+    budget: usize,
+    rows: RowStream,
+}
+
+impl ResultChunks {
+    /// `None` if the query cannot be read in chunks (not a SELECT)
+    #[must_use]
+    pub fn new(query: Statement, budget: usize) -> Option<Self> {
+        readable(&query).then_some(Self { query: Some(query), budget, rows: Box::new(std::iter::empty()) })
+    }
+
+    /// Run the query and write out its result; returns the column names
+    pub fn run(
+        &mut self,
+        db: &Database,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let query = self.query.take().expect("ResultChunks::run runs the query once");
+        let (columns, rows) = write_out(database_storage, |each| {
+            QueryExecutor::execute_read_each(db, query, tx_manager, database_storage, each)
+        })?;
+        self.rows = rows;
+        Ok(columns)
+    }
+    /// The next chunk of rows, about `budget` bytes; `None` after the last one
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<Vec<String>>>, DatabaseError> {
+        let (mut chunk, mut bytes) = (Vec::new(), 0);
+        while bytes < self.budget {
+            let Some(row) = self.rows.next().transpose()? else {
+                break;
+            };
+            bytes += Self::size_of(std::slice::from_ref(&row));
+            chunk.push(row);
+        }
+        Ok((!chunk.is_empty()).then_some(chunk))
+    }
+
+    /// Approximate memory held by result rows, in bytes
+    #[must_use]
+    pub fn size_of(rows: &[Vec<String>]) -> usize {
+        rows.iter()
+            .map(|row| size_of::<Vec<String>>() + row.iter().map(|value| size_of::<String>() + value.len()).sum::<usize>())
+            .sum()
+    }
+}
+
+pub struct CursorExecutor;
+
+impl CursorExecutor {
+    /// DECLARE name CURSOR FOR query
+    pub fn declare(
+        db: &Database,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+        tx_id: Option<u64>,
+        name: String,
+        query: Statement,
+    ) -> Result<QueryResult, DatabaseError> {
+        let tx_id = Self::transaction(tx_id, "DECLARE CURSOR")?;
+        if tx_manager.with_cursors(tx_id, |cursors| cursors.contains_key(&name)) {
+            return Err(DatabaseError::ParseError(format!("Cursor '{name}' already exists")));
+        }
+        let cursor = Cursor::declare(db, query, tx_manager, database_storage)?;
+        tx_manager.with_cursors(tx_id, |cursors| cursors.insert(name, cursor));
+        Ok(QueryResult::Success("DECLARE CURSOR".to_string()))
+    }
+
+    /// FETCH / MOVE `count` rows (`None` = all remaining) from a cursor
+    pub fn fetch(
+        tx_manager: &GlobalTransactionManager,
+        tx_id: Option<u64>,
+        name: &str,
+        count: Option<usize>,
+        move_only: bool,
+    ) -> Result<QueryResult, DatabaseError> {
+        let tx_id = Self::transaction(tx_id, if move_only { "MOVE" } else { "FETCH" })?;
+        let cursor = tx_manager
+            .with_cursors(tx_id, |cursors| cursors.get(name).cloned())
+            .ok_or_else(|| DatabaseError::ParseError(format!("Cursor '{name}' does not exist")))?;
+
+        let result = cursor.read(count)?;
+        if move_only {
+            Ok(QueryResult::Success(format!("MOVE {}", result.row_count())))
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// CLOSE name | ALL
+    pub fn close(
+        tx_manager: &GlobalTransactionManager,
+        tx_id: Option<u64>,
+        name: Option<&str>,
+    ) -> Result<QueryResult, DatabaseError> {
+        let Some(tx_id) = tx_id else {
+            // Outside a transaction no cursor can be open
+            return match name {
+                None => Ok(QueryResult::Success("CLOSE CURSOR ALL".to_string())),
+                Some(name) => Err(DatabaseError::ParseError(format!("Cursor '{name}' does not exist"))),
+            };
+        };
+        tx_manager.with_cursors(tx_id, |cursors| match name {
+            None => {
+                cursors.clear();
+                Ok(QueryResult::Success("CLOSE CURSOR ALL".to_string()))
+            }
+            Some(name) => cursors
+                .remove(name)
+                .map(|_| QueryResult::Success("CLOSE CURSOR".to_string()))
+                .ok_or_else(|| DatabaseError::ParseError(format!("Cursor '{name}' does not exist"))),
+        })
+    }
+
+    fn transaction(tx_id: Option<u64>, command: &str) -> Result<u64, DatabaseError> {
+        tx_id.ok_or_else(|| DatabaseError::NoActiveTransaction(command.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::parser::parse_statement;
+
+    #[test]
+    fn test_cursor_fetches_in_chunks() {
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str, tx_id: Option<u64>| {
+            QueryExecutor::execute(db, parse_statement(sql).unwrap(), None, &tx_manager, &mut storage, tx_id)
+        };
+        let ids = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>(),
+            QueryResult::Success(msg) => panic!("Expected Rows result, got {msg}"),
+        };
+
+        exec(&mut db, "CREATE TABLE items (id INTEGER)", None).unwrap();
+        for id in 0..10 {
+            exec(&mut db, &format!("INSERT INTO items VALUES ({id})"), None).unwrap();
+        }
+        let error = exec(&mut db, "DECLARE c CURSOR FOR SELECT id FROM items", None).unwrap_err();
+        assert!(matches!(&error, DatabaseError::NoActiveTransaction(command) if command == "DECLARE CURSOR"), "{error}");
+        let error = exec(&mut db, "FETCH c", None).unwrap_err();
+        assert!(matches!(&error, DatabaseError::NoActiveTransaction(command) if command == "FETCH"), "{error}");
+
+        let (tx_id, _) = tx_manager.begin_transaction();
+        let tx = Some(tx_id);
+        exec(&mut db, "DECLARE c CURSOR FOR SELECT id FROM items WHERE id > 0 LIMIT 8", tx).unwrap();
+        assert!(exec(&mut db, "DECLARE c CURSOR FOR SELECT id FROM items", tx).is_err());
+
+        assert_eq!(ids(exec(&mut db, "FETCH 3 FROM c", tx).unwrap()), ["1", "2", "3"]);
+        assert_eq!(ids(exec(&mut db, "FETCH NEXT FROM c", tx).unwrap()), ["4"]);
+        match exec(&mut db, "MOVE FORWARD 2 IN c", tx).unwrap() {
+            QueryResult::Success(msg) => assert_eq!(msg, "MOVE 2"),
+            QueryResult::Rows(..) => panic!("Expected Success result"),
+        }
+        // The query's own LIMIT still ends the result
+        assert_eq!(ids(exec(&mut db, "FETCH ALL FROM c", tx).unwrap()), ["7", "8"]);
+        assert!(ids(exec(&mut db, "FETCH c", tx).unwrap()).is_empty());
+
+        exec(&mut db, "CLOSE c", tx).unwrap();
+        assert!(exec(&mut db, "FETCH c", tx).is_err());
+
+        // Cursors end with their transaction
+        exec(&mut db, "DECLARE c CURSOR FOR SELECT id FROM items", tx).unwrap();
+        tx_manager.commit_transaction(tx_id);
+        assert!(exec(&mut db, "FETCH c", tx).is_err());
+    }
+
+    #[test]
+    fn test_cursor_reads_through_declare_snapshot() {
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str, tx_id: Option<u64>| {
+            QueryExecutor::execute(db, parse_statement(sql).unwrap(), None, &tx_manager, &mut storage, tx_id)
+        };
+        let ids = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>(),
+            QueryResult::Success(msg) => panic!("Expected Rows result, got {msg}"),
+        };
+
+        exec(&mut db, "CREATE TABLE items (id INTEGER)", None).unwrap();
+        for id in 0..6 {
+            exec(&mut db, &format!("INSERT INTO items VALUES ({id})"), None).unwrap();
+        }
+
+        let (tx_id, _) = tx_manager.begin_transaction();
+        let tx = Some(tx_id);
+        exec(&mut db, "DECLARE c CURSOR FOR SELECT id FROM items", tx).unwrap();
+        assert_eq!(ids(exec(&mut db, "FETCH 2 FROM c", tx).unwrap()), ["0", "1"]);
+
+        // Another session commits an insert and a delete between two FETCHes
+        exec(&mut db, "INSERT INTO items VALUES (6)", None).unwrap();
+        exec(&mut db, "DELETE FROM items WHERE id = 0 OR id = 3", None).unwrap();
+        assert_eq!(ids(exec(&mut db, "SELECT id FROM items", None).unwrap()), ["1", "2", "4", "5", "6"]);
+
+        assert_eq!(ids(exec(&mut db, "FETCH ALL FROM c", tx).unwrap()), ["2", "3", "4", "5"]);
+        tx_manager.commit_transaction(tx_id);
+    }
+
+    #[test]
+    fn test_cursor_pages_through_one_group_by_result() {
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str, tx_id: Option<u64>| {
+            QueryExecutor::execute(db, parse_statement(sql).unwrap(), None, &tx_manager, &mut storage, tx_id)
+        };
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(msg) => panic!("Expected Rows result, got {msg}"),
+        };
+
+        exec(&mut db, "CREATE TABLE sales (region INTEGER, amount INTEGER)", None).unwrap();
+        let values = (0..400).map(|i| format!("({}, {})", i % 40, i)).collect::<Vec<_>>().join(", ");
+        exec(&mut db, &format!("INSERT INTO sales VALUES {values}"), None).unwrap();
+
+        // Groups come out in hash order; re-running the query for every FETCH
+        // would page through a different order each time
+        let (tx_id, _) = tx_manager.begin_transaction();
+        let tx = Some(tx_id);
+        exec(&mut db, "DECLARE c CURSOR FOR SELECT region, COUNT(*) FROM sales GROUP BY region", tx).unwrap();
+        let mut groups = Vec::new();
+        loop {
+            let chunk = rows(exec(&mut db, "FETCH 7 FROM c", tx).unwrap());
+            if chunk.is_empty() {
+                break;
+            }
+            assert!(chunk.len() <= 7);
+            groups.extend(chunk);
+        }
+        assert!(groups.iter().all(|group| group[1] == "10"), "{groups:?}");
+        let mut regions: Vec<i64> = groups.iter().map(|group| group[0].parse().unwrap()).collect();
+        regions.sort_unstable();
+        assert_eq!(regions, (0..40).collect::<Vec<_>>());
+        tx_manager.commit_transaction(tx_id);
+    }
+
+    #[test]
+    fn test_result_chunks_follow_the_budget() {
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| {
+            run_sql(db, &mut storage, &tx_manager, sql).unwrap();
+        };
+        exec(&mut db, "CREATE TABLE items (id INTEGER, note TEXT)");
+        for id in 0..2500 {
+            exec(&mut db, &format!("INSERT INTO items VALUES ({id}, 'note {id}')"));
+        }
+
+        let budget = 16 * 1024;
+        let mut chunks = ResultChunks::new(parse_statement("SELECT * FROM items WHERE id >= 10").unwrap(), budget).unwrap();
+        assert_eq!(chunks.run(&db, &tx_manager, &storage).unwrap(), ["id", "note"]);
+        let (mut ids, mut sizes) = (Vec::new(), Vec::new());
+        while let Some(rows) = chunks.next_chunk().unwrap() {
+            sizes.push(ResultChunks::size_of(&rows));
+            ids.extend(rows.into_iter().map(|row| row[0].parse::<i64>().unwrap()));
+        }
+        assert_eq!(ids, (10..2500).collect::<Vec<_>>());
+        // Every chunk is about the size of the budget
+        assert!(sizes.len() > 3, "{sizes:?}");
+        assert!(sizes.iter().all(|&size| size <= budget * 11 / 10), "{sizes:?}");
+
+        assert!(ResultChunks::new(parse_statement("SELECT * FROM pg_stat_activity").unwrap(), budget).is_none());
+        assert!(ResultChunks::new(parse_statement("SHOW TABLES").unwrap(), budget).is_none());
+    }
+
+    #[test]
+    fn test_result_chunks_ignore_changes_while_sent() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 32).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let exec = |db: &mut Database, storage: &mut DatabaseStorage, sql: &str| {
+            run_sql(db, storage, &tx_manager, sql).unwrap();
+        };
+        let values = (0..2500).map(|id| format!("({id}, 'note {id}')")).collect::<Vec<_>>().join(", ");
+        exec(&mut db, &mut storage, "CREATE TABLE items (id INTEGER, note TEXT)");
+        exec(&mut db, &mut storage, &format!("INSERT INTO items VALUES {values}"));
+
+        let mut chunks = ResultChunks::new(parse_statement("SELECT id FROM items ORDER BY id DESC").unwrap(), 4 * 1024).unwrap();
+        assert_eq!(chunks.run(&db, &tx_manager, &storage).unwrap(), ["id"]);
+        let temp_files = storage.temp_files().unwrap().clone();
+        assert_eq!(temp_files.live_files(), 1);
+        let first = chunks.next_chunk().unwrap().unwrap();
+        assert!(first.len() < 2500);
+
+        // Another session deletes rows already sent and rows still to come,
+        // and inserts some, while the rest of the result is sent
+        exec(&mut db, &mut storage, "DELETE FROM items WHERE id = 2499 OR id = 5");
+        exec(&mut db, &mut storage, "INSERT INTO items VALUES (2500, 'new'), (-1, 'new')");
+        exec(&mut db, &mut storage, "VACUUM items");
+
+        let mut ids = first;
+        while let Some(rows) = chunks.next_chunk().unwrap() {
+            ids.extend(rows);
+        }
+        let ids = ids.into_iter().map(|row| row[0].parse::<i64>().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, (0..2500).rev().collect::<Vec<_>>());
+
+        // The written out result goes with the chunks
+        drop(chunks);
+        assert_eq!(temp_files.live_files(), 0);
+    }
+}
@@ -1,10 +1,16 @@
 /// DDL (Data Definition Language) operations
 ///
-/// CREATE TABLE, DROP TABLE, ALTER TABLE, SHOW TABLES
-use crate::types::{Database, DatabaseError, Table, Column, DataType};
-use crate::parser::{ColumnDef, AlterTableOperation};
+/// CREATE TABLE, DROP TABLE, ALTER TABLE, SHOW TABLES, CREATE/ALTER TYPE
+use crate::types::{AccessMethod, Database, DatabaseError, DescribedObject, Table, Collation, Column, DataType, Partitioning};
+use crate::parser::{ColumnDef, AlterTableOperation, ObjectRef, EnumValuePosition};
 use crate::storage::StorageEngine;
+use crate::transaction::GlobalTransactionManager;
+use super::constraints::TableConstraints;
+use super::dependencies::{DbObject, DependencyGraph};
 use super::dispatcher_executor::QueryResult;
+use super::generated::GeneratedColumns;
+use super::partitions::Partitions;
+use std::collections::HashMap;
 
 pub struct DdlExecutor;
 
@@ -14,15 +20,102 @@ impl DdlExecutor {
     /// Validates:
     /// - ENUM type resolution from db.enums
     /// - Foreign key references (table/column existence, PRIMARY KEY)
+    /// - Partition key column (v2.7.0)
     pub fn create_table(
         db: &mut Database,
         name: String,
         column_defs: Vec<ColumnDef>,
         owner: Option<String>,  // v2.3.0: Table owner
+        partition_by: Option<Partitioning>,  // v2.7.0: PARTITION BY
+        access_method: Option<&str>,  // v2.7.0: USING heap|columnar|lsm
         storage: Option<&mut StorageEngine>,
         database_storage: Option<&mut crate::storage::DatabaseStorage>,
     ) -> Result<QueryResult, DatabaseError> {
-        // Build columns from column definitions
+        let access_method = access_method.map_or(Ok(AccessMethod::Heap), AccessMethod::from_name)?;
+        let (columns, generated) = Self::build_columns(db, column_defs)?;
+        if let Some(ref partitioning) = partition_by {
+            Partitions::check_partition_key(&columns, partitioning)?;
+            if access_method != AccessMethod::Heap {
+                return Err(DatabaseError::ParseError(format!(
+                    "A partitioned table cannot use the {} access method",
+                    access_method.name()
+                )));
+            }
+        }
+
+        // Create table with columns (metadata always in Database)
+        let table_owner = owner.unwrap_or_else(|| "postgres".to_string());
+        let mut table = Table::new_with_owner(name.clone(), columns, table_owner);
+        table.generated = generated;
+        table.partitioning = partition_by;
+        table.access_method = access_method;
+
+        if let Some(db_storage) = database_storage {
+            // Page-based storage: create PagedTable for data
+            // v2.7.0: columnar / LSM tables keep their rows in a storage backend
+            db_storage.create_table_using(name, access_method)?;
+        } else if let Some(storage) = storage {
+            // Legacy storage: use Vec<Row> embedded in Table
+            // Log to WAL before executing
+            storage.log_create_table(&table)?;
+        }
+
+        db.create_table(table)?;
+        Ok(QueryResult::Success("CREATE TABLE".to_string()))
+    }
+
+    /// Execute CREATE TEMP TABLE statement (v2.7.0)
+    ///
+    /// The table is created with in-memory pages and is never written to the WAL.
+    /// Session scoping (visibility, ON COMMIT, drop at disconnect) is handled by
+    /// `TempTables`, which owns the table between statements.
+    pub fn create_temp_table(
+        db: &mut Database,
+        name: String,
+        column_defs: Vec<ColumnDef>,
+        owner: Option<String>,
+        database_storage: &mut crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        if db.tables.contains_key(&name) {
+            return Err(DatabaseError::TableAlreadyExists(name));
+        }
+
+        let (columns, generated) = Self::build_columns(db, column_defs)?;
+        let table_owner = owner.unwrap_or_else(|| "postgres".to_string());
+        let mut table = Table::new_with_owner(name.clone(), columns, table_owner);
+        table.generated = generated;
+
+        database_storage.create_temp_table(name)?;
+        db.create_table(table)?;
+        Ok(QueryResult::Success("CREATE TABLE".to_string()))
+    }
+
+    /// COLLATE of a column definition; only text types have a collation (v2.7.0)
+    fn resolve_collation(name: Option<&str>, data_type: &DataType) -> Result<Collation, DatabaseError> {
+        let Some(name) = name else {
+            return Ok(Collation::default());
+        };
+        if !matches!(data_type, DataType::Text | DataType::Varchar { .. } | DataType::Char { .. }) {
+            return Err(DatabaseError::ParseError(format!(
+                "collations are not supported by type {}",
+                data_type.sql_name()
+            )));
+        }
+        Collation::from_name(name)
+            .ok_or_else(|| DatabaseError::ParseError(format!("collation \"{name}\" does not exist")))
+    }
+
+    /// Build columns from column definitions, resolving ENUM types and
+    /// validating foreign key references and generation expressions
+    pub(crate) fn build_columns(
+        db: &Database,
+        column_defs: Vec<ColumnDef>,
+    ) -> Result<(Vec<Column>, HashMap<String, String>), DatabaseError> {
+        let generated: HashMap<String, String> = column_defs
+            .iter()
+            .filter_map(|def| def.generated.clone().map(|expr| (def.name.clone(), expr)))
+            .collect();
+
         let columns: Vec<Column> = column_defs
             .into_iter()
             .map(|def| {
@@ -46,10 +139,11 @@ impl DdlExecutor {
 
                 Ok(Column {
                     name: def.name.clone(),
-                    data_type,
                     nullable: def.nullable,
                     primary_key: def.primary_key,
                     unique: def.unique,
+                    collation: Self::resolve_collation(def.collation.as_deref(), &data_type)?,
+                    data_type,
                     foreign_key: def.foreign_key,
                 })
             })
@@ -80,36 +174,21 @@ impl DdlExecutor {
             }
         }
 
-        // Create table with columns (metadata always in Database)
-        let table_owner = owner.unwrap_or_else(|| "postgres".to_string());
-        let table = Table::new_with_owner(name.clone(), columns, table_owner);
+        // v2.7.0: GENERATED ALWAYS AS ... STORED
+        GeneratedColumns::compile(&columns, &generated)?;
 
-        if let Some(db_storage) = database_storage {
-            // Page-based storage: create PagedTable for data
-            db_storage.create_table(name.clone())?;
-            db.create_table(table)?;
-            Ok(QueryResult::Success(format!(
-                "Table '{name}' created successfully (page-based storage)"
-            )))
-        } else {
-            // Legacy storage: use Vec<Row> embedded in Table
-            // Log to WAL before executing
-            if let Some(storage) = storage {
-                storage.log_create_table(&table)?;
-            }
-
-            db.create_table(table)?;
-            Ok(QueryResult::Success(format!(
-                "Table '{name}' created successfully"
-            )))
-        }
+        Ok((columns, generated))
     }
 
     /// Execute DROP TABLE statement
+    ///
+    /// v2.7.0: The table's pages are deleted too: evicted from the buffer
+    /// pool and their files removed, so the disk space is released.
     pub fn drop_table(
         db: &mut Database,
         name: String,
         storage: Option<&mut StorageEngine>,
+        database_storage: &mut crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
         // Log to WAL before executing
         if let Some(storage) = storage {
@@ -117,9 +196,138 @@ impl DdlExecutor {
         }
 
         db.drop_table(&name)?;
-        Ok(QueryResult::Success(format!(
-            "Table '{name}' dropped successfully"
-        )))
+        if database_storage.get_paged_table(&name).is_some() {
+            database_storage.drop_table(&name)?;
+        }
+        Ok(QueryResult::Success("DROP TABLE".to_string()))
+    }
+
+    /// Execute CREATE TYPE ... AS ENUM statement
+    pub fn create_type(
+        db: &mut Database,
+        name: String,
+        values: Vec<String>,
+        storage: Option<&mut StorageEngine>,
+    ) -> Result<QueryResult, DatabaseError> {
+        if db.enums.contains_key(&name) {
+            return Err(DatabaseError::ParseError(format!("Enum '{name}' already exists")));
+        }
+
+        if let Some(storage) = storage {
+            storage.log_create_enum(&name, &values)?;
+        }
+
+        db.create_enum(name.clone(), values)?;
+        Ok(QueryResult::Success("CREATE TYPE".to_string()))
+    }
+
+    /// Execute ALTER TYPE ... ADD VALUE statement (v2.7.0)
+    ///
+    /// The label is appended unless BEFORE/AFTER names an existing label.
+    /// Columns of the type accept the new label immediately.
+    pub fn alter_type_add_value(
+        db: &mut Database,
+        name: &str,
+        value: String,
+        if_not_exists: bool,
+        position: Option<EnumValuePosition>,
+        storage: Option<&mut StorageEngine>,
+    ) -> Result<QueryResult, DatabaseError> {
+        let Some(current) = db.enums.get(name) else {
+            return Err(DatabaseError::ParseError(format!("Type '{name}' does not exist")));
+        };
+
+        if current.contains(&value) {
+            return if if_not_exists {
+                Ok(QueryResult::Success("ALTER TYPE".to_string()))
+            } else {
+                Err(DatabaseError::ParseError(format!(
+                    "Enum label '{value}' already exists in type '{name}'"
+                )))
+            };
+        }
+
+        let position_of = |label: &str| {
+            current.iter().position(|v| v == label).ok_or_else(|| {
+                DatabaseError::ParseError(format!("'{label}' is not an existing enum label of type '{name}'"))
+            })
+        };
+        let index = match &position {
+            None => current.len(),
+            Some(EnumValuePosition::Before(label)) => position_of(label)?,
+            Some(EnumValuePosition::After(label)) => position_of(label)? + 1,
+        };
+
+        let mut values = current.clone();
+        values.insert(index, value);
+
+        if let Some(storage) = storage {
+            storage.log_alter_enum(name, &values)?;
+        }
+
+        db.set_enum_values(name, values)?;
+        Ok(QueryResult::Success("ALTER TYPE".to_string()))
+    }
+
+    /// Execute COMMENT ON statement (v2.7.0)
+    ///
+    /// The description is kept in `Database::descriptions`. Comments on
+    /// databases are accepted but not kept: a database is not described from
+    /// inside another one.
+    pub fn comment_on(db: &mut Database, object: ObjectRef, comment: Option<String>) -> Result<QueryResult, DatabaseError> {
+        Self::check_object_exists(db, &object)?;
+        let object = match object {
+            ObjectRef::Table(name) | ObjectRef::View(name) | ObjectRef::Index(name) => DescribedObject::Relation(name),
+            ObjectRef::Column { table, column } => DescribedObject::Column { table, column },
+            ObjectRef::Type(name) => DescribedObject::Type(name),
+            ObjectRef::Schema(name) => DescribedObject::Schema(name),
+            ObjectRef::Database(_) => return Ok(QueryResult::Success("COMMENT".to_string())),
+        };
+        db.descriptions.set(object, comment);
+        Ok(QueryResult::Success("COMMENT".to_string()))
+    }
+
+    /// Execute ALTER VIEW | TYPE | SCHEMA ... OWNER TO statement (v2.7.0)
+    ///
+    /// Only tables and databases record an owner; for other objects this
+    /// checks that the object exists and changes nothing.
+    pub fn alter_owner(db: &Database, object: &ObjectRef) -> Result<QueryResult, DatabaseError> {
+        Self::check_object_exists(db, object)?;
+        let tag = match object {
+            ObjectRef::Table(_) | ObjectRef::Column { .. } => "ALTER TABLE",
+            ObjectRef::View(_) => "ALTER VIEW",
+            ObjectRef::Index(_) => "ALTER INDEX",
+            ObjectRef::Type(_) => "ALTER TYPE",
+            ObjectRef::Database(_) => "ALTER DATABASE",
+            ObjectRef::Schema(_) => "ALTER SCHEMA",
+        };
+        Ok(QueryResult::Success(tag.to_string()))
+    }
+
+    fn check_object_exists(db: &Database, object: &ObjectRef) -> Result<(), DatabaseError> {
+        match object {
+            ObjectRef::Table(name) => {
+                db.get_table(name).ok_or_else(|| DatabaseError::TableNotFound(name.clone()))?;
+            }
+            ObjectRef::Column { table, column } => {
+                let table_obj = db.get_table(table).ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
+                table_obj.get_column_index(column).ok_or_else(|| DatabaseError::ColumnNotFound(column.clone()))?;
+            }
+            ObjectRef::View(name) if !db.views.contains_key(name) => {
+                return Err(DatabaseError::ParseError(format!("View '{name}' does not exist")));
+            }
+            ObjectRef::Index(name) if !db.indexes.contains_key(name) => {
+                return Err(DatabaseError::ParseError(format!("Index '{name}' does not exist")));
+            }
+            ObjectRef::Type(name) if !db.enums.contains_key(name) => {
+                return Err(DatabaseError::ParseError(format!("Type '{name}' does not exist")));
+            }
+            ObjectRef::Schema(name) if name != "public" => {
+                return Err(DatabaseError::ParseError(format!("Schema '{name}' does not exist")));
+            }
+            _ => {}
+        }
+        Ok(())
     }
 
     /// Execute ALTER TABLE statement
@@ -129,21 +337,27 @@ impl DdlExecutor {
     /// - DROP COLUMN
     /// - RENAME COLUMN
     /// - RENAME TO (table rename)
+    /// - ADD / VALIDATE / DROP CONSTRAINT (v2.7.0)
+    /// - ALTER COLUMN SET / DROP NOT NULL (v2.7.0)
     pub fn alter_table(
         db: &mut Database,
         table_name: String,
         operation: AlterTableOperation,
         storage: Option<&mut StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
         database_storage: &mut crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
-        use AlterTableOperation::{AddColumn, DropColumn, OwnerTo, RenameColumn, RenameTable};
+        use AlterTableOperation::{
+            AddColumn, AddConstraint, DropColumn, DropConstraint, DropNotNull, OwnerTo, RenameColumn, RenameTable,
+            SetNotNull, ValidateConstraint,
+        };
 
         match operation {
             AddColumn(column_def) => {
                 Self::alter_table_add_column(db, &table_name, column_def, storage, database_storage)
             }
-            DropColumn(column_name) => {
-                Self::alter_table_drop_column(db, &table_name, column_name, storage, database_storage)
+            DropColumn { name, cascade } => {
+                Self::alter_table_drop_column(db, &table_name, name, cascade, storage, database_storage)
             }
             RenameColumn { old_name, new_name } => {
                 Self::alter_table_rename_column(db, &table_name, old_name, new_name, storage)
@@ -154,7 +368,104 @@ impl DdlExecutor {
             OwnerTo(new_owner) => {
                 Self::alter_table_owner_to(db, &table_name, new_owner, storage)
             }
+            AddConstraint { name, kind, not_valid } => {
+                TableConstraints::add(db, &table_name, name, kind, not_valid, tx_manager, database_storage)
+            }
+            ValidateConstraint(name) => {
+                TableConstraints::validate(db, &table_name, &name, tx_manager, database_storage)
+            }
+            DropConstraint { name, if_exists } => {
+                TableConstraints::drop(db, &table_name, &name, if_exists)
+            }
+            SetNotNull(column) => {
+                Self::alter_table_set_nullable(db, &table_name, &column, false, storage, tx_manager, database_storage)
+            }
+            DropNotNull(column) => {
+                Self::alter_table_set_nullable(db, &table_name, &column, true, storage, tx_manager, database_storage)
+            }
+        }
+    }
+
+    /// ALTER TABLE ALTER COLUMN SET NOT NULL | DROP NOT NULL (v2.7.0)
+    ///
+    /// SET NOT NULL fails if a current row holds NULL in the column.
+    fn alter_table_set_nullable(
+        db: &mut Database,
+        table_name: &str,
+        column_name: &str,
+        nullable: bool,
+        storage: Option<&mut StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let table = db.get_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let col_idx = table.get_column_index(column_name)
+            .ok_or_else(|| DatabaseError::ColumnNotFound(column_name.to_string()))?;
+        let column = &table.columns[col_idx];
+
+        if column.nullable == nullable {
+            return Ok(QueryResult::Success("ALTER TABLE".to_string()));
+        }
+        if nullable && column.primary_key {
+            return Err(DatabaseError::ParseError(format!(
+                "column \"{column_name}\" is in a primary key"
+            )));
+        }
+        if !nullable && Self::column_has_nulls(db, table_name, col_idx, tx_manager, database_storage)? {
+            return Err(DatabaseError::ColumnContainsNulls(table_name.to_string(), column_name.to_string()));
+        }
+
+        // Log to WAL
+        if let Some(storage) = storage {
+            storage.log_alter_table_set_nullable(table_name, column_name, nullable)?;
+        }
+
+        let table = db.get_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        table.columns[col_idx].nullable = nullable;
+
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
+    }
+
+    /// Does a current row of the table hold NULL in column `col_idx`? (v2.7.0)
+    ///
+    /// An index on the column answers without a scan when it has no NULL
+    /// entry; otherwise the table is read page by page up to the first NULL.
+    fn column_has_nulls(
+        db: &Database,
+        table_name: &str,
+        col_idx: usize,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<bool, DatabaseError> {
+        let column_name = db.get_table(table_name)
+            .map(|table| table.columns[col_idx].name.as_str())
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let paged_table = database_storage.get_paged_table(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        let current_tx_id = tx_manager.current_tx_id();
+
+        let index = db.indexes.values().find(|index| {
+            index.table_name() == table_name && !index.is_composite() && index.column_name() == column_name
+        });
+        if let Some(index) = index {
+            let positions = index.search(&crate::types::Value::Null);
+            if positions.is_empty() {
+                return Ok(false);
+            }
+            // Index positions are row ordinals, so the table has to be loaded to resolve them
+            let rows = paged_table.get_all_rows()?;
+            return Ok(positions.into_iter().filter_map(|pos| rows.get(pos)).any(|row| row.is_visible(current_tx_id)));
         }
+
+        for row in paged_table.scan() {
+            let row = row?;
+            if row.is_visible(current_tx_id) && matches!(row.values[col_idx], crate::types::Value::Null) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
     /// ALTER TABLE ADD COLUMN
@@ -217,6 +528,7 @@ impl DdlExecutor {
 
         let new_column = Column {
             name: column_def.name.clone(),
+            collation: Self::resolve_collation(column_def.collation.as_deref(), &data_type)?,
             data_type,
             nullable: column_def.nullable,
             primary_key: column_def.primary_key,
@@ -224,6 +536,18 @@ impl DdlExecutor {
             foreign_key: column_def.foreign_key.clone(),
         };
 
+        // v2.7.0: A generated column is computed for the existing rows
+        let mut columns = db.get_table(table_name).map(|t| t.columns.clone()).unwrap_or_default();
+        columns.push(new_column.clone());
+        let mut generated = db.get_table(table_name).map(|t| t.generated.clone()).unwrap_or_default();
+        if let Some(ref expr) = column_def.generated {
+            generated.insert(column_def.name.clone(), expr.clone());
+        }
+        let compiled: Vec<_> = GeneratedColumns::compile(&columns, &generated)?
+            .into_iter()
+            .filter(|(idx, _)| *idx == columns.len() - 1)
+            .collect();
+
         // Log to WAL
         if let Some(storage) = storage {
             storage.log_alter_table_add_column(table_name, &new_column)?;
@@ -234,6 +558,7 @@ impl DdlExecutor {
 
         // Add column to schema
         table.columns.push(new_column);
+        table.generated = generated;
 
         // Add NULL value to all existing rows in PagedTable (v2.0.0)
         use crate::types::Value;
@@ -245,22 +570,25 @@ impl DdlExecutor {
             |row| {
                 let mut new_row = row.clone();
                 new_row.values.push(Value::Null);
+                // Generated value stays NULL when it cannot be computed (e.g. division by zero)
+                GeneratedColumns::compute(&compiled, &columns, &mut new_row.values).ok();
                 new_row
             },
             0,  // No tx_id needed for schema changes
         )?;
 
-        Ok(QueryResult::Success(format!(
-            "Column '{}' added to table '{}'",
-            column_def.name, table_name
-        )))
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
     }
 
     /// ALTER TABLE DROP COLUMN
+    ///
+    /// v2.7.0: Indexes and the foreign key of the column go with it; views
+    /// reading it and foreign keys referencing it need CASCADE
     fn alter_table_drop_column(
         db: &mut Database,
         table_name: &str,
         column_name: String,
+        cascade: bool,
         storage: Option<&mut StorageEngine>,
         database_storage: &mut crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
@@ -280,6 +608,18 @@ impl DdlExecutor {
             ));
         }
 
+        // v2.7.0: Generated columns must not lose their inputs
+        if let Some(dependent) = GeneratedColumns::dependent(&table.generated, &column_name) {
+            return Err(DatabaseError::ParseError(format!(
+                "Cannot drop column '{column_name}': generated column '{dependent}' depends on it"
+            )));
+        }
+
+        let column = DbObject::Column { table: table_name.to_string(), column: column_name.clone() };
+        DependencyGraph::drop_dependents(db, &column, cascade)?;
+        let table = db.get_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+
         // Log to WAL
         if let Some(storage) = storage {
             storage.log_alter_table_drop_column(table_name, &column_name)?;
@@ -287,6 +627,9 @@ impl DdlExecutor {
 
         // Remove column from schema
         table.columns.remove(col_idx);
+        table.generated.remove(&column_name);
+        TableConstraints::forget_column(table, &column_name);  // v2.7.0
+        db.descriptions.forget_column(table_name, &column_name);  // v2.7.0
 
         // Remove value from all rows in PagedTable (v2.0.0)
         let paged_table = database_storage.get_paged_table_mut(table_name)
@@ -302,9 +645,7 @@ impl DdlExecutor {
             0,  // No tx_id needed for schema changes
         )?;
 
-        Ok(QueryResult::Success(format!(
-            "Column '{column_name}' dropped from table '{table_name}'"
-        )))
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
     }
 
     /// ALTER TABLE RENAME COLUMN
@@ -331,17 +672,44 @@ impl DdlExecutor {
             )));
         }
 
+        // v2.7.0: Generation expressions refer to columns by name
+        if let Some(dependent) = GeneratedColumns::dependent(&table.generated, &old_name) {
+            return Err(DatabaseError::ParseError(format!(
+                "Cannot rename column '{old_name}': generated column '{dependent}' depends on it"
+            )));
+        }
+        if let Some(dependent) = TableConstraints::dependent_check(table, &old_name) {
+            return Err(DatabaseError::ParseError(format!(
+                "Cannot rename column '{old_name}': check constraint '{dependent}' depends on it"
+            )));
+        }
+        DependencyGraph::check_rename(db, &DbObject::Column { table: table_name.to_string(), column: old_name.clone() })?;
+
         // Log to WAL
         if let Some(storage) = storage {
             storage.log_alter_table_rename_column(table_name, &old_name, &new_name)?;
         }
 
         // Rename column
-        table.columns[col_idx].name = new_name.clone();
+        let table = db.get_table_mut(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
+        table.columns[col_idx].name.clone_from(&new_name);
+        if let Some(expr) = table.generated.remove(&old_name) {
+            table.generated.insert(new_name.clone(), expr);
+        }
+        db.descriptions.rename_column(table_name, &old_name, &new_name);  // v2.7.0
+        TableConstraints::rename_column(db, table_name, &old_name, &new_name);  // v2.7.0
+        // v2.7.0: Foreign keys follow the column they reference
+        for column in db.tables.values_mut().flat_map(|table| table.columns.iter_mut()) {
+            if let Some(fk) = &mut column.foreign_key
+                && fk.referenced_table == table_name
+                && fk.referenced_column == old_name
+            {
+                fk.referenced_column.clone_from(&new_name);
+            }
+        }
 
-        Ok(QueryResult::Success(format!(
-            "Column '{old_name}' renamed to '{new_name}'"
-        )))
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
     }
 
     /// ALTER TABLE RENAME TO
@@ -355,6 +723,7 @@ impl DdlExecutor {
         if db.tables.contains_key(&new_name) {
             return Err(DatabaseError::TableAlreadyExists(new_name));
         }
+        DependencyGraph::check_rename(db, &DbObject::Table(old_name.to_string()))?;  // v2.7.0
 
         // Check if old table exists
         let mut table = db.tables.remove(old_name)
@@ -366,12 +735,20 @@ impl DdlExecutor {
         }
 
         // Rename table
-        table.name = new_name.clone();
+        table.name.clone_from(&new_name);
         db.tables.insert(new_name.clone(), table);
+        db.descriptions.rename_relation(old_name, &new_name);  // v2.7.0
+        TableConstraints::rename_table(db, old_name, &new_name);  // v2.7.0
+        // v2.7.0: Foreign keys follow the table they reference
+        for column in db.tables.values_mut().flat_map(|table| table.columns.iter_mut()) {
+            if let Some(fk) = &mut column.foreign_key
+                && fk.referenced_table == old_name
+            {
+                fk.referenced_table.clone_from(&new_name);
+            }
+        }
 
-        Ok(QueryResult::Success(format!(
-            "Table '{old_name}' renamed to '{new_name}'"
-        )))
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
     }
 
     /// ALTER TABLE OWNER TO (v2.3.0)
@@ -381,6 +758,11 @@ impl DdlExecutor {
         new_owner: String,
         storage: Option<&mut StorageEngine>,
     ) -> Result<QueryResult, DatabaseError> {
+        // v2.7.0: pg_dump changes the owner of views with ALTER TABLE too; views keep none
+        if !db.tables.contains_key(table_name) && db.views.contains_key(table_name) {
+            return Ok(QueryResult::Success("ALTER TABLE".to_string()));
+        }
+
         // Get table
         let table = db.tables.get_mut(table_name)
             .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
@@ -392,12 +774,52 @@ impl DdlExecutor {
         }
 
         // Change owner
-        let old_owner = table.owner.clone();
-        table.owner = new_owner.clone();
+        table.owner.clone_from(&new_owner);
+
+        // v2.7.0: Permission checks read table_metadata, so transfer ownership there too
+        if let Some(metadata) = db.table_metadata.get_mut(table_name) {
+            metadata.set_owner(&new_owner);
+        }
+
+        Ok(QueryResult::Success("ALTER TABLE".to_string()))
+    }
+
+    /// Execute \d / \d+ table (v2.7.0)
+    ///
+    /// One row per column, like psql; \d+ adds the column descriptions.
+    pub fn describe_table(db: &Database, name: &str, verbose: bool) -> Result<QueryResult, DatabaseError> {
+        let table = db.get_table(name).ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))?;
+        let mut columns = vec!["Column".to_string(), "Type".to_string(), "Nullable".to_string(), "Default".to_string()];
+        if verbose {
+            columns.push("Description".to_string());
+        }
+
+        let rows = table
+            .columns
+            .iter()
+            .map(|column| {
+                let default = if table.sequences.contains_key(&column.name) {
+                    format!("nextval('{name}_{}_seq'::regclass)", column.name)
+                } else if let Some(expr) = table.generated.get(&column.name) {
+                    format!("generated always as ({expr}) stored")
+                } else {
+                    String::new()
+                };
+                let mut row = vec![
+                    column.name.clone(),
+                    column.data_type.sql_name(),
+                    if column.nullable { String::new() } else { "not null".to_string() },
+                    default,
+                ];
+                if verbose {
+                    let object = DescribedObject::Column { table: name.to_string(), column: column.name.clone() };
+                    row.push(db.descriptions.get(&object).unwrap_or_default().to_string());
+                }
+                row
+            })
+            .collect();
 
-        Ok(QueryResult::Success(format!(
-            "Changed owner of table '{table_name}' from '{old_owner}' to '{new_owner}'"
-        )))
+        Ok(QueryResult::Rows(rows, columns))
     }
 
     /// Execute SHOW TABLES statement
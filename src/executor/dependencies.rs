@@ -0,0 +1,374 @@
+/// Dependencies between the objects of a database (v2.7.0)
+///
+/// The graph is built from the catalog whenever it is needed, so it cannot
+/// go stale: a view depends on every table or view its query reads and on
+/// the columns it reads, a foreign key on the table and column it references
+/// and on its own column, an index on the table and columns it indexes, the
+/// default of a SERIAL column on the column's sequence. Columns, and the
+/// sequences and defaults of SERIAL columns, belong to their table.
+///
+/// DROP uses it. With RESTRICT (the default) the drop is refused while other
+/// objects depend on the dropped one; CASCADE drops them first, and whatever
+/// depends on them in turn. Objects that belong to the dropped one (its
+/// indexes and foreign keys) always go with it. `pg_depend` shows the graph.
+use std::fmt;
+use crate::parser::{SelectColumn, Statement};
+use crate::types::{ConstraintKind, Database, DatabaseError, Table};
+use super::index::IndexExecutor;
+use super::views::ViewResolver;
+
+/// An object other objects can depend on
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DbObject {
+    Table(String),
+    View(String),
+    Index(String),
+    /// FOREIGN KEY of `column` of `table`
+    ForeignKey { table: String, column: String },
+    Column { table: String, column: String },
+    /// Sequence of a SERIAL column, named like `PostgreSQL` names it
+    Sequence(String),
+    /// Default value (`nextval`) of a SERIAL column
+    Default { table: String, column: String },
+}
+
+impl fmt::Display for DbObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Table(name) => write!(f, "table {name}"),
+            Self::View(name) => write!(f, "view {name}"),
+            Self::Index(name) => write!(f, "index {name}"),
+            // Named the way PostgreSQL names foreign keys
+            Self::ForeignKey { table, column } => write!(f, "constraint {table}_{column}_fkey on table {table}"),
+            Self::Column { table, column } => write!(f, "column {column} of table {table}"),
+            Self::Sequence(name) => write!(f, "sequence {name}"),
+            Self::Default { table, column } => write!(f, "default value for column {column} of table {table}"),
+        }
+    }
+}
+
+/// How a dependent object is affected when the object it depends on is dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyKind {
+    /// Blocks DROP ... RESTRICT, dropped by DROP ... CASCADE
+    Normal,
+    /// Part of the referenced object, dropped with it either way
+    Auto,
+}
+
+/// `dependent` depends on `referenced`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dependency {
+    pub dependent: DbObject,
+    pub referenced: DbObject,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    dependencies: Vec<Dependency>,
+}
+
+impl DependencyGraph {
+    /// Dependencies among the objects of `db`
+    #[must_use]
+    pub fn build(db: &Database) -> Self {
+        let mut dependencies = Vec::new();
+        let mut depend = |dependent: DbObject, referenced: DbObject, kind: DependencyKind| {
+            dependencies.push(Dependency { dependent, referenced, kind });
+        };
+
+        for (name, table) in &db.tables {
+            for column in &table.columns {
+                depend(Self::column(name, &column.name), DbObject::Table(name.clone()), DependencyKind::Auto);
+            }
+            for column in table.sequences.keys() {
+                let sequence = DbObject::Sequence(format!("{name}_{column}_seq"));
+                let default = DbObject::Default { table: name.clone(), column: column.clone() };
+                depend(sequence.clone(), Self::column(name, column), DependencyKind::Auto);
+                depend(default.clone(), Self::column(name, column), DependencyKind::Auto);
+                depend(default, sequence, DependencyKind::Normal);
+            }
+        }
+
+        for (view, query) in &db.views {
+            let Ok(stmt) = crate::parser::parse_statement(query) else {
+                continue;
+            };
+            for relation in ViewResolver::referenced_relations(&stmt) {
+                if relation == *view {
+                    continue;
+                }
+                if db.views.contains_key(&relation) {
+                    depend(DbObject::View(view.clone()), DbObject::View(relation), DependencyKind::Normal);
+                } else if let Some(table) = db.tables.get(&relation) {
+                    for column in Self::view_columns(&stmt, query, table) {
+                        depend(DbObject::View(view.clone()), Self::column(&relation, column), DependencyKind::Normal);
+                    }
+                    depend(DbObject::View(view.clone()), DbObject::Table(relation), DependencyKind::Normal);
+                }
+            }
+        }
+
+        for (name, table) in &db.tables {
+            for column in &table.columns {
+                if let Some(fk) = &column.foreign_key {
+                    let constraint = DbObject::ForeignKey { table: name.clone(), column: column.name.clone() };
+                    depend(constraint.clone(), DbObject::Table(name.clone()), DependencyKind::Auto);
+                    depend(constraint.clone(), Self::column(name, &column.name), DependencyKind::Auto);
+                    depend(constraint.clone(), DbObject::Table(fk.referenced_table.clone()), DependencyKind::Normal);
+                    depend(constraint, Self::column(&fk.referenced_table, &fk.referenced_column), DependencyKind::Normal);
+                }
+            }
+        }
+
+        for (name, index) in &db.indexes {
+            depend(DbObject::Index(name.clone()), DbObject::Table(index.table_name().to_string()), DependencyKind::Auto);
+            for column in index.column_names() {
+                depend(DbObject::Index(name.clone()), Self::column(index.table_name(), column), DependencyKind::Auto);
+            }
+        }
+
+        dependencies.sort();
+        Self { dependencies }
+    }
+
+    fn column(table: &str, column: &str) -> DbObject {
+        DbObject::Column { table: table.to_string(), column: column.to_string() }
+    }
+
+    /// Columns of `table` a view reads: all of them for `SELECT *`, otherwise
+    /// those its query names
+    ///
+    /// Names are matched in the query text, so a column of another relation
+    /// with the same name counts too; that only ever makes DROP stricter.
+    fn view_columns<'a>(stmt: &Statement, query: &str, table: &'a Table) -> Vec<&'a str> {
+        let columns = table.columns.iter().map(|column| column.name.as_str());
+        if Self::selects_all(stmt) {
+            return columns.collect();
+        }
+        let words: Vec<&str> = query.split(|c: char| !(c.is_alphanumeric() || c == '_')).collect();
+        columns.filter(|column| words.iter().any(|word| word.eq_ignore_ascii_case(column))).collect()
+    }
+
+    fn selects_all(stmt: &Statement) -> bool {
+        match stmt {
+            Statement::Select { columns, .. } => columns
+                .iter()
+                .any(|column| matches!(column, SelectColumn::Regular { name, .. } if name == "*" || name.ends_with(".*"))),
+            Statement::Union { left, right, .. }
+            | Statement::Intersect { left, right, .. }
+            | Statement::Except { left, right, .. } => Self::selects_all(left) || Self::selects_all(right),
+            _ => false,
+        }
+    }
+
+    /// Every dependency, sorted
+    #[must_use]
+    pub fn dependencies(&self) -> &[Dependency] {
+        &self.dependencies
+    }
+
+    /// Dependencies on `object`
+    pub fn dependents_of<'a>(&'a self, object: &'a DbObject) -> impl Iterator<Item = &'a Dependency> {
+        self.dependencies.iter().filter(move |dependency| dependency.referenced == *object)
+    }
+
+    /// Objects dropped along with `object`, each before the objects it depends on
+    ///
+    /// Without `cascade` only objects that belong to `object` (or to the
+    /// objects going with it) may go; anything else is an error listing them.
+    pub fn drop_order(&self, object: &DbObject, cascade: bool) -> Result<Vec<DbObject>, DatabaseError> {
+        let mut dropped = vec![object.clone()];
+        let mut blocking = Vec::new();
+        let mut next = 0;
+        while let Some(current) = dropped.get(next).cloned() {
+            next += 1;
+            for dependency in self.dependents_of(&current) {
+                if dropped.contains(&dependency.dependent) {
+                    continue;
+                }
+                if dependency.kind == DependencyKind::Normal && !self.belongs_to_any(&dependency.dependent, &dropped) {
+                    blocking.push(dependency.dependent.to_string());
+                }
+                dropped.push(dependency.dependent.clone());
+            }
+        }
+
+        if !blocking.is_empty() && !cascade {
+            return Err(DatabaseError::DependentObjectsStillExist(object.to_string(), blocking.join(", ")));
+        }
+        dropped.remove(0);
+        dropped.reverse();
+        Ok(dropped)
+    }
+
+    /// Is `object` part of one of `owners` (an `Auto` dependency)?
+    fn belongs_to_any(&self, object: &DbObject, owners: &[DbObject]) -> bool {
+        self.dependencies.iter().any(|dependency| {
+            dependency.kind == DependencyKind::Auto
+                && dependency.dependent == *object
+                && owners.contains(&dependency.referenced)
+        })
+    }
+
+    /// Error out if views depend on `object`, which is about to be renamed
+    ///
+    /// Views keep their query as text, so they cannot follow the new name.
+    pub fn check_rename(db: &Database, object: &DbObject) -> Result<(), DatabaseError> {
+        let mut views: Vec<String> = Self::build(db)
+            .dependents_of(object)
+            .filter(|dependency| matches!(dependency.dependent, DbObject::View(_)))
+            .map(|dependency| dependency.dependent.to_string())
+            .collect();
+        views.dedup();
+        if views.is_empty() {
+            return Ok(());
+        }
+        Err(DatabaseError::DependentObjectsBlockRename(object.to_string(), views.join(", ")))
+    }
+
+    /// Drop the objects that go along with `object` (see `drop_order`), so
+    /// that `object` itself can be dropped next
+    pub fn drop_dependents(db: &mut Database, object: &DbObject, cascade: bool) -> Result<(), DatabaseError> {
+        for dependent in Self::build(db).drop_order(object, cascade)? {
+            match dependent {
+                DbObject::View(name) => {
+                    db.drop_view(&name);
+                }
+                DbObject::Index(name) => {
+                    IndexExecutor::drop_index(db, name)?;
+                }
+                DbObject::ForeignKey { table, column } => {
+                    let Some(table) = db.get_table_mut(&table) else {
+                        continue;
+                    };
+                    if let Some(column) = table.columns.iter_mut().find(|c| c.name == column) {
+                        column.foreign_key = None;
+                    }
+                    // v2.7.0: ... together with the constraint that added it
+                    table.constraints.retain(|c| !matches!(&c.kind, ConstraintKind::ForeignKey { column: fk, .. } if *fk == column));
+                }
+                // Only the object being dropped is a table; the others go
+                // with the table or column being dropped
+                DbObject::Table(_) | DbObject::Column { .. } | DbObject::Sequence(_) | DbObject::Default { .. } => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+
+    fn setup() -> (Database, DatabaseStorage, GlobalTransactionManager) {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+            "CREATE TABLE orders (id INTEGER, user_id INTEGER REFERENCES users(id))",
+            "CREATE INDEX idx_users_name ON users (name)",
+            "CREATE VIEW named AS SELECT id, name FROM users",
+            "CREATE VIEW named_ids AS SELECT id FROM named",
+            "CREATE VIEW order_ids AS SELECT id FROM orders",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+        (db, storage, tx_manager)
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let (db, _, _) = setup();
+        let graph = DependencyGraph::build(&db);
+        let users = DbObject::Table("users".to_string());
+        let dependents: Vec<(String, DependencyKind)> =
+            graph.dependents_of(&users).map(|d| (d.dependent.to_string(), d.kind)).collect();
+        assert_eq!(
+            dependents,
+            vec![
+                ("view named".to_string(), DependencyKind::Normal),
+                ("index idx_users_name".to_string(), DependencyKind::Auto),
+                ("constraint orders_user_id_fkey on table orders".to_string(), DependencyKind::Normal),
+                ("column id of table users".to_string(), DependencyKind::Auto),
+                ("column name of table users".to_string(), DependencyKind::Auto),
+            ]
+        );
+
+        // RESTRICT lists everything CASCADE would drop besides the table's own objects
+        let err = graph.drop_order(&users, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot drop table users because other objects depend on it: \
+             view named, constraint orders_user_id_fkey on table orders, view named_ids"
+        );
+        // Dependents go before the objects they depend on
+        let order = graph.drop_order(&users, true).unwrap();
+        let position = |object: DbObject| order.iter().position(|o| *o == object).unwrap();
+        assert!(position(DbObject::View("named_ids".to_string())) < position(DbObject::View("named".to_string())));
+        assert!(!order.contains(&DbObject::View("order_ids".to_string())));
+
+        // A table whose only dependents are its own
+        assert_eq!(graph.drop_order(&DbObject::Table("missing".to_string()), false).unwrap(), vec![]);
+        let orders = graph.drop_order(&DbObject::Table("orders".to_string()), false);
+        assert!(orders.is_err(), "order_ids depends on orders");
+    }
+
+    #[test]
+    fn test_drop_cascade() {
+        let (mut db, mut storage, tx_manager) = setup();
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE users").unwrap_err();
+        assert!(matches!(err, DatabaseError::DependentObjectsStillExist(..)));
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE users RESTRICT").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "DROP VIEW named").is_err());
+
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE users CASCADE").unwrap();
+        assert!(db.get_table("users").is_none());
+        assert!(!db.views.contains_key("named") && !db.views.contains_key("named_ids"));
+        assert!(db.indexes.is_empty());
+        // The referencing table stays, without its foreign key
+        assert!(db.get_table("orders").unwrap().columns[1].foreign_key.is_none());
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO orders VALUES (1, 42)").unwrap();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP VIEW order_ids CASCADE").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE orders").unwrap();
+    }
+
+    #[test]
+    fn test_alter_table_dependencies() {
+        let (mut db, mut storage, tx_manager) = setup();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE notes (id SERIAL, body TEXT, extra TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_notes_extra ON notes (extra)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW bodies AS SELECT body FROM notes").unwrap();
+
+        // Only the columns a view reads are held by it
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE notes DROP COLUMN body").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot drop column body of table notes because other objects depend on it: view bodies"
+        );
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE notes DROP COLUMN extra").unwrap();
+        assert!(!db.indexes.contains_key("idx_notes_extra"));
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE notes DROP COLUMN body CASCADE").unwrap();
+        assert!(!db.views.contains_key("bodies"));
+
+        // Views keep their query as text, so renames they would miss are refused
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE orders RENAME TO purchases").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot rename table orders because other objects depend on it: view order_ids"
+        );
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users RENAME COLUMN name TO full_name").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP VIEW named CASCADE").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users RENAME COLUMN name TO full_name").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users RENAME TO members").unwrap();
+        // Foreign keys follow the renamed table
+        let fk = db.get_table("orders").unwrap().columns[1].foreign_key.clone().unwrap();
+        assert_eq!((fk.referenced_table.as_str(), fk.referenced_column.as_str()), ("members", "id"));
+    }
+}
@@ -0,0 +1,267 @@
+/// Subqueries in FROM and JOIN: derived tables and LATERAL (v2.7.0)
+///
+/// `SELECT ... FROM (SELECT ...) AS t(a, b)` runs the subquery once and reads
+/// its rows like those of a materialized view. A LATERAL subquery may refer to
+/// columns of the FROM items before it (`users.id`); it runs again for every
+/// row of those, with the references replaced by that row's values. Outer
+/// columns can be compared with `=` in its WHERE clause and appear in its
+/// SELECT list.
+use crate::parser::{CaseExpression, Condition, DerivedTable, Expression, SelectColumn, Statement, WhenClause, WhenCondition};
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError, Value};
+use super::dispatcher_executor::QueryResult;
+use super::queries::QueryExecutor as QueriesExecutor;
+use super::subquery::SubqueryExecutor;
+
+pub struct DerivedTables;
+
+impl DerivedTables {
+    /// Is this FROM item the source text of a subquery?
+    #[must_use]
+    pub fn is_derived(name: &str) -> bool {
+        name.starts_with('(') || name.get(..7).is_some_and(|prefix| prefix.eq_ignore_ascii_case("LATERAL"))
+    }
+
+    pub fn parse(name: &str) -> Result<DerivedTable, DatabaseError> {
+        crate::parser::parse_derived_table(name).map_err(|e| DatabaseError::ParseError(e.to_string()))
+    }
+
+    /// Name the columns of a FROM item are qualified with - the alias of a subquery
+    #[must_use]
+    pub fn relation_name(name: &str) -> String {
+        if Self::is_derived(name)
+            && let Ok(table) = Self::parse(name)
+        {
+            return table.alias;
+        }
+        name.to_string()
+    }
+
+    /// Rows and column names of a subquery
+    ///
+    /// `outer` holds the qualified column names and rendered values of the row
+    /// a LATERAL subquery is evaluated for.
+    pub fn load(
+        db: &Database,
+        table: &DerivedTable,
+        outer: Option<(&[String], &[String])>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<(Vec<Vec<String>>, Vec<String>), DatabaseError> {
+        let query = match outer {
+            Some((columns, values)) => {
+                let outer_value = |name: &str| {
+                    columns
+                        .iter()
+                        .position(|col| col == name)
+                        .map(|idx| SubqueryExecutor::rendered_value(&values[idx]))
+                };
+                Self::bind_outer(&table.query, &outer_value)
+            }
+            None => (*table.query).clone(),
+        };
+
+        let QueryResult::Rows(rows, columns) = QueriesExecutor::execute_query_stmt(db, &query, tx_manager, database_storage)?
+        else {
+            return Err(DatabaseError::ParseError(format!("Subquery '{}' did not produce rows", table.alias)));
+        };
+
+        let mut columns: Vec<String> = columns
+            .into_iter()
+            .map(|col| col.rsplit('.').next().unwrap_or(&col).to_string())
+            .collect();
+        if table.columns.len() > columns.len() {
+            return Err(DatabaseError::ParseError(format!(
+                "Table '{}' has {} columns available but {} columns specified",
+                table.alias,
+                columns.len(),
+                table.columns.len()
+            )));
+        }
+        // Columns without a new name keep their own
+        columns[..table.columns.len()].clone_from_slice(&table.columns);
+
+        Ok((rows, columns))
+    }
+
+    /// Replace references to outer columns with their values
+    fn bind_outer(stmt: &Statement, outer: &dyn Fn(&str) -> Option<Value>) -> Statement {
+        match stmt {
+            Statement::Select { distinct, columns, from, joins, filter, group_by, order_by, limit, offset } => {
+                let columns = columns
+                    .iter()
+                    .map(|col| match col {
+                        SelectColumn::Regular { name, alias } => match (outer(name), alias) {
+                            (None, _) => col.clone(),
+                            (Some(value), None) => SelectColumn::Literal(value),
+                            (Some(value), Some(_)) => SelectColumn::Expression { expr: Expression::Literal(value), alias: alias.clone() },
+                        },
+                        SelectColumn::Expression { expr, alias } => SelectColumn::Expression {
+                            expr: Self::bind_expression(expr, outer),
+                            alias: alias.clone(),
+                        },
+                        _ => col.clone(),
+                    })
+                    .collect();
+                Statement::Select {
+                    distinct: *distinct,
+                    columns,
+                    from: from.clone(),
+                    joins: joins.clone(),
+                    filter: filter.as_ref().map(|cond| Self::bind_condition(cond, from, outer)),
+                    group_by: group_by.clone(),
+                    order_by: order_by.clone(),
+                    limit: *limit,
+                    offset: *offset,
+                }
+            }
+            Statement::Union { left, right, all, order_by, limit, offset } => Statement::Union {
+                left: Box::new(Self::bind_outer(left, outer)),
+                right: Box::new(Self::bind_outer(right, outer)),
+                all: *all,
+                order_by: order_by.clone(),
+                limit: *limit,
+                offset: *offset,
+            },
+            Statement::Intersect { left, right, order_by, limit, offset } => Statement::Intersect {
+                left: Box::new(Self::bind_outer(left, outer)),
+                right: Box::new(Self::bind_outer(right, outer)),
+                order_by: order_by.clone(),
+                limit: *limit,
+                offset: *offset,
+            },
+            Statement::Except { left, right, order_by, limit, offset } => Statement::Except {
+                left: Box::new(Self::bind_outer(left, outer)),
+                right: Box::new(Self::bind_outer(right, outer)),
+                order_by: order_by.clone(),
+                limit: *limit,
+                offset: *offset,
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// `inner = outer` becomes `inner = value`; `from` is the subquery's own
+    /// table, whose qualified column names are shortened
+    fn bind_condition(cond: &Condition, from: &str, outer: &dyn Fn(&str) -> Option<Value>) -> Condition {
+        let inner = |name: &str| {
+            name.strip_prefix(from)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .unwrap_or(name)
+                .to_string()
+        };
+
+        match cond {
+            Condition::ColumnEquals(left, right) => match (outer(left), outer(right)) {
+                (_, Some(value)) => Condition::Equals(inner(left), value),
+                (Some(value), None) => Condition::Equals(inner(right), value),
+                (None, None) => cond.clone(),
+            },
+            Condition::And(left, right) => Condition::And(
+                Box::new(Self::bind_condition(left, from, outer)),
+                Box::new(Self::bind_condition(right, from, outer)),
+            ),
+            Condition::Or(left, right) => Condition::Or(
+                Box::new(Self::bind_condition(left, from, outer)),
+                Box::new(Self::bind_condition(right, from, outer)),
+            ),
+            Condition::Not(inner_cond) => Condition::Not(Box::new(Self::bind_condition(inner_cond, from, outer))),
+            _ => cond.clone(),
+        }
+    }
+
+    fn bind_expression(expr: &Expression, outer: &dyn Fn(&str) -> Option<Value>) -> Expression {
+        match expr {
+            Expression::Column(name) => outer(name).map_or_else(|| expr.clone(), Expression::Literal),
+            Expression::Binary { left, op, right } => Expression::Binary {
+                left: Box::new(Self::bind_expression(left, outer)),
+                op: *op,
+                right: Box::new(Self::bind_expression(right, outer)),
+            },
+            Expression::Function { name, args } => Expression::Function {
+                name: name.clone(),
+                args: args.iter().map(|arg| Self::bind_expression(arg, outer)).collect(),
+            },
+            Expression::Case(case) => Expression::Case(Box::new(CaseExpression {
+                operand: case.operand.as_ref().map(|operand| Self::bind_expression(operand, outer)),
+                when_clauses: case
+                    .when_clauses
+                    .iter()
+                    .map(|when| WhenClause {
+                        condition: match &when.condition {
+                            WhenCondition::Value(value) => WhenCondition::Value(Self::bind_expression(value, outer)),
+                            WhenCondition::Condition(cond) => WhenCondition::Condition(cond.clone()),
+                        },
+                        result: Self::bind_expression(&when.result, outer),
+                    })
+                    .collect(),
+                else_value: case.else_value.as_ref().map(|value| Self::bind_expression(value, outer)),
+            })),
+            Expression::Literal(_) => expr.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::storage::DatabaseStorage;
+
+    #[test]
+    fn test_derived_tables_and_lateral() {
+        // v2.7.0: subqueries in FROM / JOIN, LATERAL runs once per outer row
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, columns) => (rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(), columns),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER, name TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE orders (id INTEGER, user_id INTEGER, total INTEGER)").unwrap();
+        for sql in [
+            "INSERT INTO users VALUES (1, 'Alice')",
+            "INSERT INTO users VALUES (2, 'Bob')",
+            "INSERT INTO users VALUES (3, 'Carol')",
+            "INSERT INTO orders VALUES (1, 1, 5)",
+            "INSERT INTO orders VALUES (2, 1, 40)",
+            "INSERT INTO orders VALUES (3, 2, 12)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        let (result, columns) = rows(
+            run_sql(&mut db, &mut storage, &tx_manager, "SELECT n FROM (SELECT id, name FROM users) AS u(uid, n) WHERE uid > 1 ORDER BY n DESC").unwrap(),
+        );
+        assert_eq!((result, columns), (vec!["Carol".to_string(), "Bob".to_string()], vec!["n".to_string()]));
+
+        let (result, columns) = rows(
+            run_sql(
+                &mut db,
+                &mut storage,
+                &tx_manager,
+                "SELECT * FROM users JOIN (SELECT user_id, total FROM orders WHERE total > 10) AS big ON users.id = big.user_id",
+            )
+            .unwrap(),
+        );
+        assert_eq!(columns, ["users.id", "users.name", "big.user_id", "big.total"]);
+        let mut result = result;
+        result.sort();
+        assert_eq!(result, ["1,Alice,1,40", "2,Bob,2,12"]);
+
+        // Largest order per user; users without orders are kept by LEFT JOIN
+        let lateral = "(SELECT total FROM orders WHERE orders.user_id = users.id ORDER BY total DESC LIMIT 1) AS top";
+        let (mut result, columns) = rows(run_sql(&mut db, &mut storage, &tx_manager, &format!("SELECT * FROM users CROSS JOIN LATERAL {lateral}")).unwrap());
+        result.sort();
+        assert_eq!(columns, ["users.id", "users.name", "top.total"]);
+        assert_eq!(result, ["1,Alice,40", "2,Bob,12"]);
+        let (mut result, _) = rows(run_sql(&mut db, &mut storage, &tx_manager, &format!("SELECT * FROM users LEFT JOIN LATERAL {lateral} ON TRUE")).unwrap());
+        result.sort();
+        assert_eq!(result, ["1,Alice,40", "2,Bob,12", "3,Carol,NULL"]);
+
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, &format!("SELECT * FROM users RIGHT JOIN LATERAL {lateral} ON TRUE")).is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT * FROM (SELECT id FROM users) AS u(a, b)").is_err());
+    }
+}
@@ -1,23 +1,352 @@
-use crate::parser::Statement;
+use crate::parser::{InsertSource, Statement};
 use crate::storage::StorageEngine;
 use crate::transaction::GlobalTransactionManager;
-use crate::types::{Database, DatabaseError};
+use crate::types::{Database, DatabaseError, Value};
+use crate::types::value::with_enum_labels;
 
 // Import new modular executors
 use super::ddl::DdlExecutor;
 use super::dml::DmlExecutor;
 use super::queries::QueryExecutor as QueriesExecutor;
 use super::storage_adapter::PagedStorage;
+use super::views::ViewResolver;
+use super::dependencies::{DbObject, DependencyGraph};
+use super::functions::FunctionExecutor;
+use super::generated::GeneratedColumns;
+use super::constraints::TableConstraints;
+use super::partitions::Partitions;
+use super::fdw::ForeignTables;
+use super::from_tables::FromTables;
+use super::set_operations::SetOperations;
 
 pub struct QueryExecutor;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum QueryResult {
     Success(String),
     Rows(Vec<Vec<String>>, Vec<String>), // (rows, column_names)
 }
 
+/// Takes the rows of a result one at a time (v2.7.0: see `QueryExecutor::execute_read_each`)
+pub type RowSink<'a> = dyn FnMut(Vec<String>) -> Result<(), DatabaseError> + 'a;
+
+impl QueryResult {
+    /// Rows returned, or affected according to the command tag (v2.7.0)
+    ///
+    /// Tags that count rows end with the count (`INSERT 0 3`, `UPDATE 2`,
+    /// `DELETE 1`, `MOVE 5`); any other tag counts none.
+    #[must_use]
+    pub fn row_count(&self) -> usize {
+        match self {
+            Self::Success(tag) => tag
+                .split_whitespace()
+                .next_back()
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(0),
+            Self::Rows(rows, _) => rows.len(),
+        }
+    }
+}
+
 impl QueryExecutor {
+    /// Register a native scalar function callable from SQL (v2.7.0)
+    ///
+    /// The function is available in every database of the process; see `udf`.
+    pub fn register_scalar_function<F>(name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[crate::types::Value]) -> Result<crate::types::Value, DatabaseError> + Send + Sync + 'static,
+    {
+        super::udf::FunctionRegistry::register_scalar(name, arity, function);
+    }
+
+    /// Register a native aggregate function; `factory` creates one accumulator per group (v2.7.0)
+    pub fn register_aggregate_function<F>(name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn super::udf::Accumulator> + Send + Sync + 'static,
+    {
+        super::udf::FunctionRegistry::register_aggregate(name, factory);
+    }
+
+    /// INSERT of `rows`, all validated before any is written (v2.7.0)
+    fn insert_rows(
+        db: &mut Database,
+        table: String,
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<Value>>,
+        storage: Option<&mut StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &mut crate::storage::DatabaseStorage,
+        active_tx_id: Option<u64>,
+    ) -> Result<QueryResult, DatabaseError> {
+        ForeignTables::check_writable(db, &table)?;  // v2.7.0
+
+        // v2.7.0: Rows of a partitioned table go to the partition matching the key
+        let mut storage = storage;
+        let mut routed: Vec<(String, Vec<String>, Vec<Vec<Value>>)> = Vec::new();
+        let mut rows_here = Vec::new();
+        for values in rows {
+            match Partitions::route_insert(db, &table, columns.as_ref(), &values, storage.as_deref_mut())? {
+                Some((partition, columns, values)) => match routed.iter_mut().find(|(name, ..)| *name == partition) {
+                    Some((_, _, partition_rows)) => partition_rows.push(values),
+                    None => routed.push((partition, columns, vec![values])),
+                },
+                None => rows_here.push(values),
+            }
+        }
+        if !routed.is_empty() {
+            let mut results = Vec::new();
+            for (partition, columns, rows) in routed {
+                results.push(Self::insert_rows(db, partition, Some(columns), rows, storage.as_deref_mut(), tx_manager, database_storage, active_tx_id)?);
+            }
+            if results.len() == 1 {
+                return Ok(results.remove(0));
+            }
+            let inserted: usize = results.iter().map(QueryResult::row_count).sum();
+            return Ok(QueryResult::Success(format!("INSERT 0 {inserted}")));
+        }
+
+        // Clone necessary data before mutable borrow
+        let table_ref = db.get_table(&table)
+            .ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
+        let table_columns = table_ref.columns.clone();
+        let all_tables = db.tables.clone();  // Clone to avoid borrow conflict
+
+        // v2.7.0: Generated columns are never written directly
+        let generated = GeneratedColumns::compile(&table_columns, &table_ref.generated)?;
+        let checks = TableConstraints::compile_checks(table_ref)?;  // v2.7.0
+        let mut insert_columns = columns.clone();
+        let mut batch = Vec::with_capacity(rows_here.len());
+        for values in rows_here {
+            let values = if generated.is_empty() {
+                values
+            } else {
+                let (kept_columns, kept_values) = GeneratedColumns::prepare_insert(&table_columns, &table_ref.generated, columns.clone(), values)?;
+                insert_columns = kept_columns;
+                kept_values
+            };
+
+            // Reorder values to match table schema (v2.0.0)
+            let ordered_values = DmlExecutor::reorder_values(&table_columns, insert_columns.clone(), values.clone())?;
+
+            // Validate foreign keys BEFORE mutable borrows (v2.0.0)
+            DmlExecutor::validate_foreign_keys_with_storage(
+                &all_tables,
+                &table_columns,
+                &ordered_values,
+                tx_manager,
+                database_storage,
+            )?;
+            batch.push(values);
+        }
+
+        // v2.0.0: Page-based storage only
+        let paged_table = database_storage.get_paged_table_mut(&table)
+            .ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
+        let mut storage_adapter = PagedStorage::new(paged_table);
+
+        // Split borrow: get separate mutable references to different fields
+        let table_mut = db.tables.get_mut(&table).unwrap();
+        let sequences = &table_mut.sequences;
+        let indexes = &mut db.indexes;
+
+        let result = DmlExecutor::insert_many(
+            &table_columns,
+            sequences,
+            &table,
+            insert_columns,
+            batch,
+            &mut storage_adapter,
+            storage,
+            tx_manager,
+            indexes,
+            active_tx_id,
+            &generated,
+            &checks,
+        )?;
+        db.activity.count_inserted(&table, result.row_count());  // v2.7.0
+        Ok(result)
+    }
+
+    /// Statements that never modify the database (v2.7.0)
+    ///
+    /// They only need shared borrows, so the server runs them under read
+    /// locks and concurrent readers of a database do not wait for each other.
+    #[must_use]
+    pub const fn is_read_only(stmt: &Statement) -> bool {
+        matches!(
+            stmt,
+            Statement::Select { .. }
+                | Statement::Union { .. }
+                | Statement::Intersect { .. }
+                | Statement::Except { .. }
+                | Statement::ShowTables
+                | Statement::DescribeTable { .. }
+                | Statement::Explain { .. }
+                | Statement::Show { .. }
+        )
+    }
+
+    /// Executes a read-only statement (see `is_read_only`) (v2.7.0)
+    ///
+    /// A result longer than `max_result_rows` is an error; a SELECT stops
+    /// reading one row past it.
+    pub fn execute_read(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        // v2.7.0: Enum values compare in the order of their type's labels
+        with_enum_labels(&db.enums, || Self::read_limited(db, stmt, tx_manager, database_storage))
+    }
+
+    /// `execute_read` handing the result rows to `each` one at a time;
+    /// returns the column names (v2.7.0)
+    ///
+    /// A regular SELECT never holds its rows together, so a large result can
+    /// be written out as it is produced (see `ResultChunks`).
+    pub fn execute_read_each(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+        each: &mut RowSink<'_>,
+    ) -> Result<Vec<String>, DatabaseError> {
+        with_enum_labels(&db.enums, || {
+            let max_rows = database_storage.max_result_rows();
+            let mut count = 0;
+            let mut counted = |row| {
+                count += 1;
+                if max_rows > 0 && count > max_rows {
+                    return Err(DatabaseError::TooManyResultRows(max_rows));
+                }
+                each(row)
+            };
+            Self::read_all_each(db, Self::limit_rows(stmt, max_rows), tx_manager, database_storage, &mut counted)
+        })
+    }
+
+    /// `execute_read_each` without the `max_result_rows` check, for the
+    /// result a cursor writes out at DECLARE (v2.7.0)
+    pub fn execute_read_unlimited_each(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+        each: &mut RowSink<'_>,
+    ) -> Result<Vec<String>, DatabaseError> {
+        with_enum_labels(&db.enums, || Self::read_all_each(db, stmt, tx_manager, database_storage, each))
+    }
+
+    /// `read_each` handing every result row to `each`, also those of
+    /// queries that return their rows together
+    fn read_all_each(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+        each: &mut RowSink<'_>,
+    ) -> Result<Vec<String>, DatabaseError> {
+        match Self::read_each(db, stmt, tx_manager, database_storage, Some(&mut *each))? {
+            QueryResult::Rows(rows, columns) => {
+                rows.into_iter().try_for_each(each)?;
+                Ok(columns)
+            }
+            QueryResult::Success(_) => Ok(Vec::new()),
+        }
+    }
+
+    fn read_limited(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let max_rows = database_storage.max_result_rows();
+        match Self::read(db, Self::limit_rows(stmt, max_rows), tx_manager, database_storage)? {
+            QueryResult::Rows(rows, _) if max_rows > 0 && rows.len() > max_rows => {
+                Err(DatabaseError::TooManyResultRows(max_rows))
+            }
+            result => Ok(result),
+        }
+    }
+
+    /// A SELECT that stops reading one row past `max_result_rows` (0 = no limit)
+    fn limit_rows(stmt: Statement, max_rows: usize) -> Statement {
+        match stmt {
+            Statement::Select { distinct, columns, from, joins, filter, group_by, order_by, limit, offset } if max_rows > 0 => {
+                let limit = Some(limit.map_or(max_rows + 1, |limit| limit.min(max_rows + 1)));
+                Statement::Select { distinct, columns, from, joins, filter, group_by, order_by, limit, offset }
+            }
+            stmt => stmt,
+        }
+    }
+
+    /// `execute_read` without the `max_result_rows` check, for the rows of
+    /// INSERT ... SELECT
+    fn read(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        Self::read_each(db, stmt, tx_manager, database_storage, None)
+    }
+
+    /// `read` handing the rows of a regular SELECT to `each` (see `QueriesExecutor::select_each`)
+    fn read_each(
+        db: &Database,
+        stmt: Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+        each: Option<&mut RowSink<'_>>,
+    ) -> Result<QueryResult, DatabaseError> {
+        // v2.7.0: Temp files the query leaves behind go away with it
+        let _temp_files = database_storage.temp_files().map(crate::storage::TempFiles::begin_query);
+
+        match stmt {
+            Statement::Select {
+                distinct,
+                columns,
+                from,
+                joins,
+                filter,
+                group_by,
+                order_by,
+                limit,
+                offset,
+            } => {
+                QueriesExecutor::select_each(db, distinct, columns, from, joins, filter, group_by, order_by, limit, offset, tx_manager, database_storage, each)
+            }
+            // Set operations (v1.10.0)
+            stmt @ (Statement::Union { .. } | Statement::Intersect { .. } | Statement::Except { .. }) => {
+                SetOperations::execute(db, &stmt, tx_manager, database_storage)
+            }
+            Statement::ShowTables => DdlExecutor::show_tables(db),
+            Statement::DescribeTable { name, verbose } => DdlExecutor::describe_table(db, &name, verbose),
+            Statement::Explain { statement } => Self::explain(db, &statement, database_storage),
+            // v2.7.0: The server shows its session's parameters before this
+            Statement::Show { name } => {
+                let value = super::system_functions::SystemFunctions::current_setting(&name)?;
+                Ok(QueryResult::Rows(vec![vec![value]], vec!["TimeZone".to_string()]))
+            }
+            _ => Err(DatabaseError::ParseError("Statement is not read-only".to_string())),
+        }
+    }
+
+    /// EXPLAIN: the plan of a statement, which is not run
+    fn explain(
+        db: &Database,
+        statement: &Statement,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        // Convert explain::QueryResult to legacy::QueryResult
+        match super::explain::ExplainExecutor::explain(db, statement, database_storage)? {
+            super::explain::QueryResult::Success(msg) => Ok(QueryResult::Success(msg)),
+            super::explain::QueryResult::Rows(rows, cols) => Ok(QueryResult::Rows(rows, cols)),
+        }
+    }
+
     /// Executes a query with automatic WAL logging and MVCC support
     ///
     /// v2.0.0: `database_storage` is now required (page-based storage only)
@@ -33,143 +362,218 @@ impl QueryExecutor {
         database_storage: &mut crate::storage::DatabaseStorage,
         active_tx_id: Option<u64>,
     ) -> Result<QueryResult, DatabaseError> {
+        // v2.7.0: Enum values compare in the order of their type's labels
+        let enums = std::sync::Arc::clone(&db.enums);
+        with_enum_labels(&enums, || {
+            Self::execute_statement(db, stmt, storage, tx_manager, database_storage, active_tx_id)
+        })
+    }
+
+    fn execute_statement(
+        db: &mut Database,
+        stmt: Statement,
+        storage: Option<&mut StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &mut crate::storage::DatabaseStorage,
+        active_tx_id: Option<u64>,
+    ) -> Result<QueryResult, DatabaseError> {
+        // v2.7.0: Temp files the query leaves behind go away with it
+        let _temp_files = database_storage.temp_files().map(crate::storage::TempFiles::begin_query);
+
+        // v2.7.0: Schema changes make cached plans and prepared statements stale
+        if super::plan_cache::PlanCache::invalidated_by(&stmt) {
+            db.catalog_version.bump();
+            let affected = super::plan_cache::PlanCache::affected_relations(db, &stmt);
+            let mut plan_cache = db.plan_cache.lock().expect("plan cache poisoned");
+            match affected {
+                Some(relations) => plan_cache.invalidate_relations(&relations),
+                None => plan_cache.invalidate(),
+            }
+        }
+
+        // v2.7.0: Anything but a read may change the database, so the next
+        // checkpoint writes it out again
+        let mut storage = storage;
+        if !Self::is_read_only(&stmt)
+            && let Some(storage) = storage.as_deref_mut()
+        {
+            storage.mark_database_dirty(&db.name);
+        }
+
         match stmt {
             // DDL operations - delegate to DdlExecutor
-            Statement::CreateTable { name, columns, owner } => {
-                DdlExecutor::create_table(db, name, columns, owner, storage, Some(database_storage))
+            Statement::CreateTable { name, columns, owner, partition_by, access_method } => {
+                DdlExecutor::create_table(db, name, columns, owner, partition_by, access_method.as_deref(), storage, Some(database_storage))
+            }
+            Statement::CreatePartition { name, parent, bound, owner } => {
+                Partitions::create_partition(db, name, &parent, bound, owner, database_storage)
+            }
+            Statement::CreateTempTable { name, columns, owner, .. } => {
+                // v2.7.0: No WAL for temporary tables; ON COMMIT is applied by TempTables
+                DdlExecutor::create_temp_table(db, name, columns, owner, database_storage)
+            }
+            Statement::CreateForeignTable { name, columns, server, options, owner } => {
+                ForeignTables::create(db, name, columns, &server, options, owner, database_storage)
+            }
+            Statement::DropTable { name, cascade } => {
+                // v2.7.0: Views and foreign keys on the table block the drop
+                // unless CASCADE drops them too
+                DependencyGraph::drop_dependents(db, &DbObject::Table(name.clone()), cascade)?;
+
+                // v2.7.0: Partitions go away with their partitioned table
+                for partition in Partitions::detach(db, &name) {
+                    let stmt = Statement::DropTable { name: partition, cascade };
+                    Self::execute(db, stmt, storage.as_deref_mut(), tx_manager, database_storage, active_tx_id)?;
+                }
+                DdlExecutor::drop_table(db, name, storage, database_storage)
             }
-            Statement::DropTable { name } => DdlExecutor::drop_table(db, name, storage),
             Statement::AlterTable { name, operation } => {
-                DdlExecutor::alter_table(db, name, operation, storage, database_storage)
+                ForeignTables::check_writable(db, &name)?;  // v2.7.0
+                Partitions::check_alter(db, &name, &operation)?;
+                DdlExecutor::alter_table(db, name, operation, storage, tx_manager, database_storage)
             }
-            Statement::ShowTables => DdlExecutor::show_tables(db),
 
             // DML operations - delegate to DmlExecutor
             Statement::Insert {
                 table,
                 columns,
                 values,
-            } => {
-                // Clone necessary data before mutable borrow
-                let table_ref = db.get_table(&table)
-                    .ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
-                let table_columns = table_ref.columns.clone();
-                let table_sequences = table_ref.sequences.clone();
-                let all_tables = db.tables.clone();  // Clone to avoid borrow conflict
-
-                // Reorder values to match table schema (v2.0.0)
-                let ordered_values = DmlExecutor::reorder_values(&table_columns, columns.clone(), values.clone())?;
-
-                // Validate foreign keys BEFORE mutable borrows (v2.0.0)
-                DmlExecutor::validate_foreign_keys_with_storage(
-                    &all_tables,
-                    &table_columns,
-                    &ordered_values,
-                    tx_manager,
-                    database_storage,
-                )?;
-
-                // v2.0.0: Page-based storage only
-                let paged_table = database_storage.get_paged_table_mut(&table)
-                    .ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
-                let mut storage_adapter = PagedStorage::new(paged_table);
-
-                // Split borrow: get separate mutable references to different fields
-                let table_mut = db.tables.get_mut(&table).unwrap();
-                let sequences_mut = &mut table_mut.sequences;
-                let indexes = &mut db.indexes;
-
-                DmlExecutor::insert_with_storage(
-                    &table_columns,
-                    &table_sequences,
-                    sequences_mut,
-                    &table,
-                    columns,
-                    values,
-                    &mut storage_adapter,
-                    storage,
-                    tx_manager,
-                    indexes,
-                    active_tx_id,
-                )
+            } => Self::insert_rows(db, table, columns, vec![values], storage, tx_manager, database_storage, active_tx_id),
+            // v2.7.0: Multi-row VALUES and INSERT ... SELECT go in as one batch
+            Statement::InsertMany { table, columns, source } => {
+                let rows = match source {
+                    InsertSource::Values(rows) => rows,
+                    InsertSource::Query(query) => match Self::read(db, *query, tx_manager, database_storage)? {
+                        QueryResult::Rows(rows, _) => rows
+                            .into_iter()
+                            .map(|row| row.into_iter().map(|value| if value == "NULL" { Value::Null } else { Value::Text(value) }).collect())
+                            .collect(),
+                        QueryResult::Success(_) => Vec::new(),
+                    },
+                };
+                Self::insert_rows(db, table, columns, rows, storage, tx_manager, database_storage, active_tx_id)
             }
             Statement::Update {
                 table,
                 assignments,
+                from,
                 filter,
             } => {
+                ForeignTables::check_writable(db, &table)?;  // v2.7.0
+
+                // v2.7.0: Partitioned table - update each partition the WHERE clause can reach
+                Partitions::check_update(db, &table, &assignments, filter.as_ref())?;
+                if let Some(partitions) = Partitions::write_targets(db, &table, filter.as_ref()) {
+                    let mut storage = storage;
+                    let mut updated = 0;
+                    for partition in partitions {
+                        let update = Statement::Update { table: partition, assignments: assignments.clone(), from: from.clone(), filter: filter.clone() };
+                        updated += Self::execute(db, update, storage.as_deref_mut(), tx_manager, database_storage, active_tx_id)?.row_count();
+                    }
+                    return Ok(QueryResult::Success(format!("UPDATE {updated}")));
+                }
+
                 // v2.0.0: Page-based storage only
                 let table_ref = db.get_table(&table)
                     .ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
                 let table_columns = table_ref.columns.clone();
 
+                // v2.7.0: Generated columns are recomputed, never assigned
+                GeneratedColumns::check_assignments(&table_ref.generated, &assignments)?;
+                let generated = GeneratedColumns::compile(&table_columns, &table_ref.generated)?;
+                let checks = TableConstraints::compile_checks(table_ref)?;  // v2.7.0
+                let from = FromTables::load(db, database_storage, tx_manager, &from)?;  // v2.7.0
+
                 let paged_table = database_storage.get_paged_table_mut(&table)
                     .ok_or_else(|| DatabaseError::TableNotFound(table.clone()))?;
                 let mut storage_adapter = PagedStorage::new(paged_table);
                 let indexes = &mut db.indexes;
 
-                DmlExecutor::update_with_storage(
-                    &table_columns, assignments, filter, &mut storage_adapter, storage, tx_manager, &table, indexes, active_tx_id
-                )
+                let result = DmlExecutor::update_with_storage(
+                    &table_columns, assignments, filter, &mut storage_adapter, storage, tx_manager, &table, indexes, active_tx_id, &generated, &checks, &from
+                )?;
+                // v2.7.0: UPDATE scans the whole table and leaves the old versions dead
+                db.activity.count_seq_scan(&table);
+                db.activity.count_updated(&table, result.row_count());
+                Ok(result)
             }
-            Statement::Delete { from, filter } => {
+            Statement::Delete { from, using, filter } => {
+                ForeignTables::check_writable(db, &from)?;  // v2.7.0
+
+                // v2.7.0: Partitioned table - delete from each partition the WHERE clause can reach
+                if let Some(partitions) = Partitions::write_targets(db, &from, filter.as_ref()) {
+                    let mut storage = storage;
+                    let mut deleted = 0;
+                    for partition in partitions {
+                        let delete = Statement::Delete { from: partition, using: using.clone(), filter: filter.clone() };
+                        deleted += Self::execute(db, delete, storage.as_deref_mut(), tx_manager, database_storage, active_tx_id)?.row_count();
+                    }
+                    return Ok(QueryResult::Success(format!("DELETE {deleted}")));
+                }
+
                 // v2.0.0: Page-based storage only
                 let table_ref = db.get_table(&from)
                     .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
                 let table_columns = table_ref.columns.clone();
+                let using = FromTables::load(db, database_storage, tx_manager, &using)?;  // v2.7.0
 
                 let paged_table = database_storage.get_paged_table_mut(&from)
                     .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
                 let mut storage_adapter = PagedStorage::new(paged_table);
                 let indexes = &mut db.indexes;
 
-                DmlExecutor::delete_with_storage(
-                    &table_columns, filter, &mut storage_adapter, storage, tx_manager, &from, indexes, active_tx_id
-                )
+                let result = DmlExecutor::delete_with_storage(
+                    &table_columns, filter, &mut storage_adapter, storage, tx_manager, &from, indexes, active_tx_id, &using
+                )?;
+                db.activity.count_seq_scan(&from);  // v2.7.0
+                db.activity.count_deleted(&from, result.row_count());
+                Ok(result)
             }
 
-            // Query operations - delegate to QueriesExecutor
-            Statement::Select {
-                distinct,
-                columns,
-                from,
-                joins,
-                filter,
-                group_by,
-                order_by,
-                limit,
-                offset,
-            } => {
-                // v2.0.0: database_storage is always available
-                QueriesExecutor::select(db, distinct, columns, from, joins, filter, group_by, order_by, limit, offset, tx_manager, database_storage)
-            }
-            // Set operations (v1.10.0)
-            Statement::Union { left, right, all } => {
-                QueriesExecutor::union(db, &left, &right, all, tx_manager, database_storage)
-            }
-            Statement::Intersect { left, right } => {
-                QueriesExecutor::intersect(db, &left, &right, tx_manager, database_storage)
-            }
-            Statement::Except { left, right } => {
-                QueriesExecutor::except(db, &left, &right, tx_manager, database_storage)
-            }
+            // Query operations - v2.7.0: shared with the read-only path
+            stmt @ (Statement::Select { .. }
+            | Statement::Union { .. }
+            | Statement::Intersect { .. }
+            | Statement::Except { .. }
+            | Statement::ShowTables
+            | Statement::DescribeTable { .. }
+            | Statement::Show { .. }) => Self::execute_read(db, stmt, tx_manager, database_storage),
             Statement::CreateIndex { name, table, columns, unique, index_type } => {
+                Partitions::check_index(db, &table)?;
+                ForeignTables::check_writable(db, &table)?;  // v2.7.0
                 super::index::IndexExecutor::create_index(db, name, table, columns, unique, index_type, database_storage)
             }
             Statement::DropIndex { name } => {
+                TableConstraints::check_drop_index(db, &name)?;  // v2.7.0
                 super::index::IndexExecutor::drop_index(db, name)
             }
             Statement::Vacuum { table } => {
                 super::vacuum::VacuumExecutor::vacuum(db, table, tx_manager, database_storage)
             }
-            Statement::Explain { statement } => {
-                let result = super::explain::ExplainExecutor::explain(db, &statement, database_storage)?;
-                // Convert explain::QueryResult to legacy::QueryResult
-                match result {
-                    super::explain::QueryResult::Success(msg) => Ok(QueryResult::Success(msg)),
-                    super::explain::QueryResult::Rows(rows, cols) => Ok(QueryResult::Rows(rows, cols)),
-                }
+            Statement::Cluster { table, index } => {
+                super::cluster::ClusterExecutor::cluster(db, table, index, tx_manager, database_storage)
+            }
+            Statement::Analyze { table } => {
+                super::analyze::AnalyzeExecutor::analyze(db, table, tx_manager, database_storage)
+            }
+            Statement::UndoTransaction { xid } => {
+                super::flashback::Flashback::undo_transaction(db, xid, tx_manager, database_storage, active_tx_id)
+            }
+            // v2.7.0: Sessions keep their settings (see `SessionParameters::set`), here SET is a no-op
+            Statement::Set { .. } => Ok(QueryResult::Success("SET".to_string())),
+            Statement::CommentOn { object, comment } => DdlExecutor::comment_on(db, object, comment),
+            Statement::AlterOwner { object, .. } => DdlExecutor::alter_owner(db, &object),
+            // v2.7.0: Cursors belong to the transaction running the statement
+            Statement::DeclareCursor { name, query } => {
+                super::cursors::CursorExecutor::declare(db, tx_manager, database_storage, active_tx_id, name, *query)
+            }
+            Statement::Fetch { cursor, count, move_only } => {
+                super::cursors::CursorExecutor::fetch(tx_manager, active_tx_id, &cursor, count, move_only)
+            }
+            Statement::CloseCursor { cursor } => {
+                super::cursors::CursorExecutor::close(tx_manager, active_tx_id, cursor.as_deref())
             }
+            Statement::Explain { statement } => Self::explain(db, &statement, database_storage),
             // Views (v1.10.0)
             Statement::CreateView { name, query } => {
                 if db.views.contains_key(&name) {
@@ -180,18 +584,35 @@ impl QueryExecutor {
                 }
                 // Validate query by parsing it
                 crate::parser::parse_statement(&query)
-                    .map_err(DatabaseError::ParseError)?;
+                    .map_err(|e| DatabaseError::ParseError(e.to_string()))?;
                 db.views.insert(name.clone(), query);
-                Ok(QueryResult::Success(format!("View '{name}' created")))
+                // v2.7.0: Reject definitions that (indirectly) reference themselves
+                if let Err(e) = ViewResolver::check_cycles(db, &name) {
+                    db.views.remove(&name);
+                    return Err(e);
+                }
+                Ok(QueryResult::Success("CREATE VIEW".to_string()))
             }
-            Statement::DropView { name } => {
-                if db.views.remove(&name).is_some() {
-                    Ok(QueryResult::Success(format!("View '{name}' dropped")))
+            Statement::DropView { name, cascade } => {
+                DependencyGraph::drop_dependents(db, &DbObject::View(name.clone()), cascade)?; // v2.7.0
+                if db.drop_view(&name) {
+                    Ok(QueryResult::Success("DROP VIEW".to_string()))
                 } else {
                     Err(DatabaseError::ParseError(format!("View '{name}' does not exist")))
                 }
             }
-            Statement::Begin | Statement::Commit | Statement::Rollback => {
+            // Stored functions (v2.7.0)
+            Statement::CreateFunction { name, params, returns, returns_set, body, or_replace } => {
+                FunctionExecutor::create_function(db, name, params, returns, returns_set, body, or_replace)
+            }
+            Statement::DropFunction { name, if_exists } => {
+                FunctionExecutor::drop_function(db, &name, if_exists)
+            }
+            Statement::Begin
+            | Statement::Commit
+            | Statement::Rollback
+            | Statement::ExportSnapshot
+            | Statement::SetTransactionSnapshot { .. } => {
                 // Transaction commands should be handled at the server level
                 Err(DatabaseError::ParseError(
                     "Transaction commands should not reach executor".to_string(),
@@ -211,7 +632,8 @@ impl QueryExecutor {
                 ))
             }
             // Database management commands - handled at server level
-            Statement::CreateDatabase { .. } | Statement::DropDatabase { .. } => {
+            Statement::CreateDatabase { .. } | Statement::DropDatabase { .. }
+            | Statement::AlterDatabaseOwner { .. } | Statement::AlterDatabaseTemplate { .. } => {
                 Err(DatabaseError::ParseError(
                     "Database management commands should be handled at server level".to_string(),
                 ))
@@ -222,16 +644,24 @@ impl QueryExecutor {
                     "Privilege management commands should be handled at server level".to_string(),
                 ))
             }
+            // Server configuration - handled at server level (v2.7.0)
+            Statement::AlterSystemSet { .. } | Statement::AlterSystemReset { .. } => {
+                Err(DatabaseError::ParseError(
+                    "ALTER SYSTEM should be handled at server level".to_string(),
+                ))
+            }
             // Metadata queries - handled at server level
-            Statement::ShowUsers | Statement::ShowDatabases => {
+            Statement::ShowUsers | Statement::ShowDatabases | Statement::Connect { .. } => {
                 Err(DatabaseError::ParseError(
                     "Metadata queries should be handled at server level".to_string(),
                 ))
             }
             // Type management
             Statement::CreateType { name, values } => {
-                db.create_enum(name.clone(), values)?;
-                Ok(QueryResult::Success(format!("Type '{name}' created successfully")))
+                DdlExecutor::create_type(db, name, values, storage)
+            }
+            Statement::AlterTypeAddValue { name, value, if_not_exists, position } => {
+                DdlExecutor::alter_type_add_value(db, &name, value, if_not_exists, position, storage)
             }
             // COPY protocol (v2.4.0)
             // COPY is handled through PostgreSQL protocol in server.rs, not here
@@ -248,6 +678,8 @@ impl QueryExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Collation;
+    use crate::executor::run_sql;
     use crate::parser::{SelectColumn, Statement};
     use crate::transaction::GlobalTransactionManager;
     use crate::types::{Column, DataType, Database, Row, Table, Value};
@@ -261,6 +693,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
             },
             Column {
                 name: "name".to_string(),
@@ -269,6 +702,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
             },
             Column {
                 name: "age".to_string(),
@@ -277,6 +711,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
             },
         ];
         Table::new("users".to_string(), columns)
@@ -320,6 +755,8 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
                 crate::parser::ColumnDef {
                     name: "name".to_string(),
@@ -328,6 +765,8 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
                 crate::parser::ColumnDef {
                     name: "age".to_string(),
@@ -336,9 +775,13 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
             ],
             owner: None,
+            partition_by: None,
+            access_method: None,
         };
         QueryExecutor::execute(db, create_stmt, None, tx_manager, storage, None).unwrap();
     }
@@ -377,6 +820,8 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
                 crate::parser::ColumnDef {
                     name: "name".to_string(),
@@ -385,14 +830,18 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
             ],
             owner: None,
+            partition_by: None,
+            access_method: None,
         };
 
         let tx_manager = GlobalTransactionManager::new();
         let result = QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut create_test_storage(), None).unwrap();
-        assert!(matches!(result, QueryResult::Success(_)));
+        assert!(matches!(result, QueryResult::Success(ref tag) if tag == "CREATE TABLE"));
         assert!(db.get_table("users").is_some());
     }
 
@@ -404,20 +853,127 @@ mod tests {
 
         let stmt = Statement::DropTable {
             name: "users".to_string(),
+            cascade: false,
         };
 
         let tx_manager = GlobalTransactionManager::new();
         let result = QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut create_test_storage(), None).unwrap();
-        assert!(matches!(result, QueryResult::Success(_)));
+        assert!(matches!(result, QueryResult::Success(ref tag) if tag == "DROP TABLE"));
         assert!(db.get_table("users").is_none());
     }
 
     #[test]
-    fn test_execute_insert() {
+    fn test_drop_table_releases_pages() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut db = Database::new("test".to_string());
+        let mut storage = crate::storage::DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE notes (id INTEGER, body TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_notes_id ON notes (id)").unwrap();
+        for id in 0..50 {
+            run_sql(&mut db, &mut storage, &tx_manager, &format!("INSERT INTO notes VALUES ({id}, '{}')", "x".repeat(200))).unwrap();
+        }
+        storage.checkpoint().unwrap();
+        assert!(temp_dir.path().join("table_1").exists());
+
+        // A view on the table refuses the drop and keeps the pages
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW short_notes AS SELECT id FROM notes").unwrap();
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE notes").unwrap_err();
+        assert!(matches!(err, DatabaseError::DependentObjectsStillExist(..)));
+        assert!(storage.get_paged_table("notes").is_some());
+        assert!(temp_dir.path().join("table_1").exists());
+
+        // Pages, buffered pages, files, indexes and with CASCADE the views
+        // all go with the table
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE notes CASCADE").unwrap();
+        assert!(!db.views.contains_key("short_notes"));
+        assert!(storage.get_paged_table("notes").is_none());
+        assert!(!db.indexes.contains_key("idx_notes_id"));
+        assert_eq!(storage.buffer_pool_stats().size, 0);
+        assert!(!temp_dir.path().join("table_1").exists());
+
+        // A new table of the same name starts empty
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE notes (id INTEGER, body TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_notes_id ON notes (id)").unwrap();
+        match run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM notes").unwrap() {
+            QueryResult::Rows(rows, _) => assert!(rows.is_empty()),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_result_columns_match_execution() {
+        // v2.7.0: Describe reports these columns before the query runs
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE items (id INTEGER, name TEXT, price INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items VALUES (1, 'apple', 3)").unwrap();
+
+        for sql in [
+            "SELECT * FROM items",
+            "SELECT name, id FROM items WHERE price > 1",
+            "SELECT 1, CASE WHEN price > 2 THEN name END AS label, id, price * 2 FROM items",
+            "SELECT COUNT(*), SUM(price) FROM items",
+            "SELECT name, COUNT(*) FROM items GROUP BY name",
+            "SELECT 1, current_database() AS db",
+            "SELECT id FROM items UNION SELECT price FROM items",
+        ] {
+            let stmt = crate::parser::parse_statement(sql).unwrap();
+            let result = run_sql(&mut db, &mut storage, &tx_manager, sql);
+            let Ok(QueryResult::Rows(_, columns)) = result else {
+                panic!("Expected Rows result for {sql}: {result:?}");
+            };
+            assert_eq!(QueriesExecutor::result_columns(&db, &stmt), Some(columns), "{sql}");
+        }
+
+        // Views and catalogs are only known by reading them
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW cheap AS SELECT id FROM items WHERE price < 5").unwrap();
+        let stmt = crate::parser::parse_statement("SELECT * FROM cheap").unwrap();
+        assert_eq!(QueriesExecutor::result_columns(&db, &stmt), None);
+    }
+
+    #[test]
+    fn test_comment_on() {
         let mut db = Database::new("test".to_string());
         let mut storage = create_test_storage();
         let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "COMMENT ON TABLE users IS 'Registered users'").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "COMMENT ON COLUMN users.name IS 'Display name'").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "COMMENT ON COLUMN users.missing IS 'x'").is_err());
+
+        match run_sql(&mut db, &mut storage, &tx_manager, "\\d+ users").unwrap() {
+            QueryResult::Rows(rows, columns) => {
+                assert_eq!(columns, ["Column", "Type", "Nullable", "Default", "Description"]);
+                assert_eq!(rows[0], ["id", "serial", "not null", "nextval('users_id_seq'::regclass)", ""]);
+                assert_eq!(rows[1][4], "Display name");
+            }
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+        // The table's OID in pg_class, in the catalog with OID 1259 (pg_class)
+        match run_sql(&mut db, &mut storage, &tx_manager, "SELECT * FROM pg_description").unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(
+                rows,
+                vec![vec!["16384", "1259", "0", "Registered users"], vec!["16384", "1259", "2", "Display name"]]
+            ),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+
+        // Descriptions follow renames and go with their objects
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE users RENAME COLUMN name TO full_name").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "COMMENT ON TABLE users IS NULL").unwrap();
+        let column = crate::types::DescribedObject::Column { table: "users".to_string(), column: "full_name".to_string() };
+        assert_eq!(db.descriptions.get(&column), Some("Display name"));
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE users").unwrap();
+        assert_eq!(db.descriptions.iter().count(), 0);
+    }
 
+    #[test]
+    fn test_execute_insert() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
         // Create table
         let create_stmt = Statement::CreateTable {
             name: "users".to_string(),
@@ -429,6 +985,8 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
                 crate::parser::ColumnDef {
                     name: "name".to_string(),
@@ -437,6 +995,8 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
                 crate::parser::ColumnDef {
                     name: "age".to_string(),
@@ -445,9 +1005,13 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    generated: None,
+                    collation: None,
                 },
             ],
             owner: None,
+            partition_by: None,
+            access_method: None,
         };
         QueryExecutor::execute(&mut db, create_stmt, None, &tx_manager, &mut storage, None).unwrap();
 
@@ -463,7 +1027,8 @@ mod tests {
         };
 
         let result = QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
-        assert!(matches!(result, QueryResult::Success(_)));
+        assert!(matches!(result, QueryResult::Success(ref tag) if tag == "INSERT 0 1"));
+        assert_eq!(result.row_count(), 1);
     }
 
     #[test]
@@ -489,7 +1054,7 @@ mod tests {
         // Verify via SELECT instead of direct table access
         let select_stmt = Statement::Select {
             distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
             joins: vec![],
             filter: None,
@@ -515,7 +1080,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
@@ -545,7 +1110,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: Some(crate::parser::Condition::GreaterThan(
@@ -578,7 +1143,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("name".to_string()), SelectColumn::Regular("age".to_string())],
+            columns: vec![SelectColumn::Regular { name: "name".to_string(), alias: None }, SelectColumn::Regular { name: "age".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
@@ -611,7 +1176,8 @@ mod tests {
         // Update Alice's age
         let stmt = Statement::Update {
             table: "users".to_string(),
-            assignments: vec![("age".to_string(), Value::Integer(31))],
+            assignments: vec![("age".to_string(), crate::parser::Expression::Literal(Value::Integer(31)))],
+            from: vec![],
             filter: Some(crate::parser::Condition::Equals(
                 "name".to_string(),
                 Value::Text("Alice".to_string()),
@@ -619,14 +1185,14 @@ mod tests {
         };
 
         let result = QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
-        assert!(matches!(result, QueryResult::Success(_)));
+        assert!(matches!(result, QueryResult::Success(ref tag) if tag == "UPDATE 1"));
 
         // Verify using SELECT
         // Note: In page-based storage, both old and new row versions may be visible
         // until VACUUM is implemented for PagedTable (currently only works with legacy Vec<Row>)
         let select_stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("age".to_string())],
+            columns: vec![SelectColumn::Regular { name: "age".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: Some(crate::parser::Condition::Equals(
@@ -661,7 +1227,8 @@ mod tests {
 
         let stmt = Statement::Update {
             table: "users".to_string(),
-            assignments: vec![("age".to_string(), Value::Integer(100))],
+            assignments: vec![("age".to_string(), crate::parser::Expression::Literal(Value::Integer(100)))],
+            from: vec![],
             filter: None,
         };
 
@@ -672,7 +1239,7 @@ mod tests {
         // Note: Page-based storage may show both old and new versions until VACUUM for PagedTable is implemented
         let select_stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("age".to_string())],
+            columns: vec![SelectColumn::Regular { name: "age".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
@@ -705,6 +1272,7 @@ mod tests {
 
         let stmt = Statement::Delete {
             from: "users".to_string(),
+            using: vec![],
             filter: Some(crate::parser::Condition::LessThan(
                 "age".to_string(),
                 Value::Integer(30),
@@ -712,13 +1280,13 @@ mod tests {
         };
 
         let result = QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
-        assert!(matches!(result, QueryResult::Success(_)));
+        assert!(matches!(result, QueryResult::Success(ref tag) if tag == "DELETE 1"));
 
         // Verify using SELECT
         // Note: Page-based storage may show deleted rows until VACUUM for PagedTable is implemented
         let select_stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("name".to_string())],
+            columns: vec![SelectColumn::Regular { name: "name".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
@@ -749,6 +1317,7 @@ mod tests {
 
         let stmt = Statement::Delete {
             from: "users".to_string(),
+            using: vec![],
             filter: None,
         };
 
@@ -760,7 +1329,7 @@ mod tests {
         // until VACUUM is implemented for PagedTable (currently only works with legacy Vec<Row>)
         let select_stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
@@ -792,7 +1361,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: Some(crate::parser::Condition::Equals(
@@ -824,7 +1393,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: Some(crate::parser::Condition::NotEquals(
@@ -857,7 +1426,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: Some(crate::parser::Condition::And(
@@ -896,7 +1465,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: Some(crate::parser::Condition::Or(
@@ -934,12 +1503,12 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
             group_by: None,
-            order_by: Some(("age".to_string(), crate::parser::SortOrder::Asc)),
+            order_by: Some((crate::parser::Expression::Column("age".to_string()), crate::parser::SortOrder::Asc)),
             limit: None,
                 offset: None,
         };
@@ -966,12 +1535,12 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
             group_by: None,
-            order_by: Some(("age".to_string(), crate::parser::SortOrder::Desc)),
+            order_by: Some((crate::parser::Expression::Column("age".to_string()), crate::parser::SortOrder::Desc)),
             limit: None,
                 offset: None,
         };
@@ -998,7 +1567,7 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
@@ -1027,12 +1596,12 @@ mod tests {
 
         let stmt = Statement::Select {
                 distinct: false,
-            columns: vec![SelectColumn::Regular("*".to_string())],
+            columns: vec![SelectColumn::Regular { name: "*".to_string(), alias: None }],
             from: "users".to_string(),
                 joins: vec![],
             filter: None,
             group_by: None,
-            order_by: Some(("age".to_string(), crate::parser::SortOrder::Desc)),
+            order_by: Some((crate::parser::Expression::Column("age".to_string()), crate::parser::SortOrder::Desc)),
             limit: Some(2),
                 offset: None,
         };
@@ -1048,6 +1617,128 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_order_by_position() {
+        // v2.7.0: ORDER BY n sorts by the n-th column of the select list
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE t (a INTEGER, b TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE u (a INTEGER, c TEXT)").unwrap();
+        for sql in [
+            "INSERT INTO t VALUES (2, 'x')",
+            "INSERT INTO t VALUES (10, 'y')",
+            "INSERT INTO t VALUES (1, 'x')",
+            "INSERT INTO u VALUES (1, 'one')",
+            "INSERT INTO u VALUES (10, 'ten')",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT a FROM t ORDER BY 1 DESC").unwrap());
+        assert_eq!(result, ["10", "2", "1"]);
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT b, a FROM t ORDER BY 2").unwrap());
+        assert_eq!(result, ["x,1", "x,2", "y,10"]);
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT * FROM t ORDER BY 1 LIMIT 2").unwrap());
+        assert_eq!(result, ["1,x", "2,x"]);
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT DISTINCT b FROM t ORDER BY 1 DESC").unwrap());
+        assert_eq!(result, ["y", "x"]);
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT b, COUNT(*) FROM t GROUP BY b ORDER BY 2 DESC").unwrap());
+        assert_eq!(result, ["x,2", "y,1"]);
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT * FROM t JOIN u ON t.a = u.a ORDER BY 1 DESC").unwrap());
+        assert_eq!(result, ["10,y,10,ten", "1,x,1,one"]);
+
+        for sql in ["SELECT a FROM t ORDER BY 2", "SELECT a FROM t ORDER BY 0", "SELECT COUNT(*) FROM t ORDER BY 2"] {
+            let err = run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap_err();
+            assert!(err.to_string().contains("is not in select list"), "{sql}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_projection_with_pushdown_and_order_by() {
+        // v2.7.0: scan returns only `name` and `age`, sort key is not in the output
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        setup_test_table(&mut db, &mut storage, &tx_manager);
+        insert_test_data(&mut db, &mut storage, &tx_manager, &[(1, "Charlie", 35), (2, "Alice", 30), (3, "Bob", 25)]);
+
+        let stmt = Statement::Select {
+                distinct: false,
+            columns: vec![SelectColumn::Regular { name: "name".to_string(), alias: None }],
+            from: "users".to_string(),
+                joins: vec![],
+            filter: Some(crate::parser::Condition::GreaterThan("age".to_string(), Value::Integer(26))),
+            group_by: None,
+            order_by: Some((crate::parser::Expression::Column("age".to_string()), crate::parser::SortOrder::Asc)),
+            limit: None,
+                offset: None,
+        };
+
+        let result = QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
+        match result {
+            QueryResult::Rows(rows, columns) => {
+                assert_eq!(columns, vec!["name".to_string()]);
+                assert_eq!(rows, vec![vec!["Alice".to_string()], vec!["Charlie".to_string()]]);
+            }
+            _ => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_alter_type_add_value() {
+        // v2.7.0: ALTER TYPE ... ADD VALUE
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TYPE status AS ENUM ('active', 'done')").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE tasks (id INTEGER, state status)").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO tasks VALUES (1, 'archived')").is_err());
+
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE status ADD VALUE 'archived'").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE status ADD VALUE 'draft' BEFORE 'active'").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO tasks VALUES (1, 'archived')").unwrap();
+        assert_eq!(
+            db.get_enum("status").unwrap(),
+            &vec!["draft".to_string(), "active".to_string(), "done".to_string(), "archived".to_string()]
+        );
+
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE status ADD VALUE 'done'").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE status ADD VALUE IF NOT EXISTS 'done'").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE status ADD VALUE 'x' AFTER 'missing'").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE missing ADD VALUE 'x'").is_err());
+    }
+
+    #[test]
+    fn test_enum_values_compare_in_declared_order() {
+        // v2.7.0: Enum labels order by their place in the type, not alphabetically
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let names = |result| match result {
+            Ok(QueryResult::Rows(rows, _)) => rows.into_iter().map(|row: Vec<String>| row[0].clone()).collect::<Vec<_>>(),
+            other => panic!("Expected rows, got {other:?}"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TYPE mood AS ENUM ('sad', 'meh', 'happy')").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE people (name TEXT, feeling mood)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO people VALUES ('ann', 'happy'), ('bob', 'sad'), ('cy', 'meh')").unwrap();
+
+        assert_eq!(names(run_sql(&mut db, &mut storage, &tx_manager, "SELECT name FROM people ORDER BY feeling")), ["bob", "cy", "ann"]);
+        assert_eq!(names(run_sql(&mut db, &mut storage, &tx_manager, "SELECT name FROM people WHERE feeling > 'sad' ORDER BY name")), ["ann", "cy"]);
+
+        // A label added BEFORE another sorts there
+        run_sql(&mut db, &mut storage, &tx_manager, "ALTER TYPE mood ADD VALUE 'awful' BEFORE 'sad'").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO people VALUES ('dee', 'awful')").unwrap();
+        let sorted = names(QueryExecutor::execute_read(&db, crate::parser::parse_statement("SELECT name FROM people ORDER BY feeling DESC").unwrap(), &tx_manager, &storage));
+        assert_eq!(sorted, ["ann", "cy", "bob", "dee"]);
+    }
+
     #[test]
     fn test_aggregate_count_all() {
         let mut db = Database::new("test".to_string());
@@ -1083,6 +1774,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_count_all_without_scan() {
+        // v2.7.0: After the first scan COUNT(*) answers from the version counts
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut run = |sql: &str, tx_id: Option<u64>| {
+            let stmt = crate::parser::parse_statement(sql).unwrap();
+            match QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, tx_id).unwrap() {
+                QueryResult::Rows(rows, _) => rows.concat(),
+                QueryResult::Success(_) => Vec::new(),
+            }
+        };
+        run("CREATE TABLE items (id INTEGER)", None);
+        run("INSERT INTO items VALUES (1), (2), (3)", None);
+        assert_eq!(run("SELECT COUNT(*), 'x' FROM items", None), vec!["3", "x"]);
+        run("UPDATE items SET id = id + 10", None);
+        run("VACUUM items", None);
+        assert_eq!(run("SELECT COUNT(*) FROM items", None), vec!["3"]);
+
+        // Changes of a transaction count once it commits
+        let (tx_id, _) = tx_manager.begin_transaction();
+        run("INSERT INTO items VALUES (4), (5)", Some(tx_id));
+        run("DELETE FROM items WHERE id = 11", Some(tx_id));
+        assert_eq!(run("SELECT COUNT(*) FROM items", None), vec!["3"]);
+        tx_manager.commit_transaction(tx_id);
+        assert_eq!(run("SELECT COUNT(*) FROM items", None), vec!["4"]);
+
+        run("DELETE FROM items WHERE id = 12", None);
+        assert_eq!(run("SELECT COUNT(*) FROM items", None), vec!["3"]);
+        assert_eq!(run("SELECT COUNT(id) FROM items", None), vec!["3"]);
+
+        let snapshot = tx_manager.get_snapshot();
+        let paged_table = storage.get_paged_table("items").unwrap();
+        assert_eq!(paged_table.visible_row_count(&snapshot, tx_manager.get_oldest_active_tx()), Some(3));
+    }
+
     #[test]
     fn test_aggregate_sum() {
         let mut db = Database::new("test".to_string());
@@ -1276,6 +2004,7 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "category".to_string(),
@@ -1284,6 +2013,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "price".to_string(),
@@ -1292,6 +2022,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -1317,7 +2048,7 @@ mod tests {
         let stmt = Statement::Select {
                 distinct: false,
             columns: vec![
-                SelectColumn::Regular("category".to_string()),
+                SelectColumn::Regular { name: "category".to_string(), alias: None },
                 SelectColumn::Aggregate(crate::parser::AggregateFunction::Count(
                     crate::parser::CountTarget::All,
                 )),
@@ -1366,6 +2097,7 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "category".to_string(),
@@ -1374,6 +2106,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "price".to_string(),
@@ -1382,6 +2115,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -1407,7 +2141,7 @@ mod tests {
         let stmt = Statement::Select {
                 distinct: false,
             columns: vec![
-                SelectColumn::Regular("category".to_string()),
+                SelectColumn::Regular { name: "category".to_string(), alias: None },
                 SelectColumn::Aggregate(crate::parser::AggregateFunction::Sum(
                     "price".to_string(),
                 )),
@@ -1456,6 +2190,7 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "category".to_string(),
@@ -1464,6 +2199,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "price".to_string(),
@@ -1472,6 +2208,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -1488,8 +2225,8 @@ mod tests {
         let stmt = Statement::Select {
                 distinct: false,
             columns: vec![
-                SelectColumn::Regular("category".to_string()),
-                SelectColumn::Regular("price".to_string()), // ERROR: not in GROUP BY
+                SelectColumn::Regular { name: "category".to_string(), alias: None },
+                SelectColumn::Regular { name: "price".to_string(), alias: None }, // ERROR: not in GROUP BY
             ],
             from: "products".to_string(),
                 joins: vec![],
@@ -1524,6 +2261,7 @@ mod tests {
                     primary_key: true,
                 unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "category".to_string(),
@@ -1532,6 +2270,7 @@ mod tests {
                     primary_key: false,
                 unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "price".to_string(),
@@ -1540,6 +2279,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -1570,7 +2310,7 @@ mod tests {
         let stmt = Statement::Select {
                 distinct: false,
             columns: vec![
-                SelectColumn::Regular("category".to_string()),
+                SelectColumn::Regular { name: "category".to_string(), alias: None },
                 SelectColumn::Aggregate(crate::parser::AggregateFunction::Count(
                     crate::parser::CountTarget::All,
                 )),
@@ -1598,4 +2338,409 @@ mod tests {
             _ => panic!("Expected Rows result"),
         }
     }
+
+    #[test]
+    fn test_insert_and_update_type_checking() {
+        // v2.7.0: Values are converted to the column type or rejected
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE items (id INTEGER, price REAL, active BOOLEAN)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_items_id ON items (id)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items VALUES ('7', 3, 't')").unwrap();
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items VALUES ('seven', 1.5, true)").unwrap_err();
+        assert!(matches!(err, DatabaseError::InvalidTextRepresentation(ref t, ref s) if t == "integer" && s == "seven"));
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items VALUES (8, 'cheap', true)").is_err());
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "UPDATE items SET active = 5").unwrap_err();
+        assert!(matches!(err, DatabaseError::DatatypeMismatch(ref c, ..) if c == "active"));
+
+        // The literal 7 finds the row stored from the text '7' through the index
+        match run_sql(&mut db, &mut storage, &tx_manager, "SELECT price, active FROM items WHERE id = 7").unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(rows, vec![vec!["3".to_string(), "true".to_string()]]),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_update_with_expressions() {
+        // v2.7.0: SET takes expressions over the old row version
+        QueryExecutor::register_scalar_function("update_test_upper", 1, |args| {
+            Ok(Value::Text(args[0].to_string().to_uppercase()))
+        });
+
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE orders (id INTEGER, name TEXT, price INTEGER, qty INTEGER, total INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO orders VALUES (1, 'tea', 4, 3, 0)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO orders VALUES (2, 'cake', 10, 2, 0)").unwrap();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET total = price * qty + 1, name = update_test_upper(name) WHERE id = 1").unwrap();
+        assert_eq!(
+            rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, name FROM orders WHERE total = 13").unwrap()),
+            vec![vec!["1".to_string(), "TEA".to_string()]]
+        );
+
+        // Both assignments read the old values, so this swaps them
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET price = qty, qty = price WHERE id = 2").unwrap();
+        assert_eq!(
+            rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT price, qty FROM orders WHERE id = 2 AND price = 2").unwrap()),
+            vec![vec!["2".to_string(), "10".to_string()]]
+        );
+
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET total = missing + 1").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "UPDATE orders SET total = name * 2").is_err());
+    }
+
+    #[test]
+    fn test_delete_using_and_update_from() {
+        // v2.7.0: DELETE ... USING / UPDATE ... FROM join other tables in
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE items (id INTEGER, price INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE prices (id INTEGER, price INTEGER, obsolete BOOLEAN)").unwrap();
+        for sql in [
+            "INSERT INTO items VALUES (1, 10)",
+            "INSERT INTO items VALUES (2, 20)",
+            "INSERT INTO items VALUES (3, 30)",
+            "INSERT INTO prices VALUES (1, 15, FALSE)",
+            "INSERT INTO prices VALUES (2, 25, TRUE)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        let deleted = run_sql(&mut db, &mut storage, &tx_manager, "DELETE FROM items USING prices WHERE items.id = prices.id AND prices.obsolete");
+        assert_eq!(deleted.unwrap().row_count(), 1);
+
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE items SET price = prices.price FROM prices WHERE items.id = prices.id AND NOT prices.obsolete").unwrap();
+        let prices = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, price FROM items WHERE price > 12").unwrap());
+        assert!(prices.contains(&vec!["1".to_string(), "15".to_string()]));
+        assert!(prices.contains(&vec!["3".to_string(), "30".to_string()]));
+        assert!(!prices.contains(&vec!["2".to_string(), "25".to_string()]));
+
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "DELETE FROM items USING missing WHERE items.id = missing.id").is_err());
+    }
+
+    #[test]
+    fn test_case_in_where_order_by_and_update() {
+        // v2.7.0: CASE is an expression - usable in WHERE, ORDER BY and UPDATE ... SET
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE tasks (id INTEGER, priority TEXT, score INTEGER)").unwrap();
+        for sql in [
+            "INSERT INTO tasks VALUES (1, 'low', 10)",
+            "INSERT INTO tasks VALUES (2, 'high', 95)",
+            "INSERT INTO tasks VALUES (3, 'medium', 50)",
+            "INSERT INTO tasks VALUES (4, NULL, 70)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        // Simple CASE in ORDER BY: high, medium, low, then the rest
+        let order = "CASE priority WHEN 'high' THEN 1 WHEN 'medium' THEN 2 WHEN 'low' THEN 3 ELSE 4 END";
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, &format!("SELECT id FROM tasks ORDER BY {order}")).unwrap());
+        assert_eq!(result, ["2", "3", "1", "4"]);
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, &format!("SELECT id FROM tasks ORDER BY {order} DESC LIMIT 1")).unwrap());
+        assert_eq!(result, ["4"]);
+
+        // Searched CASE compared in WHERE, and a CASE on its own as the condition
+        let result = rows(
+            run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM tasks WHERE CASE WHEN score >= 60 THEN 'pass' ELSE 'fail' END = 'pass' ORDER BY id")
+                .unwrap(),
+        );
+        assert_eq!(result, ["2", "4"]);
+        let result = rows(
+            run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM tasks WHERE CASE priority WHEN 'low' THEN true ELSE false END").unwrap(),
+        );
+        assert_eq!(result, ["1"]);
+
+        // SELECT list: simple CASE, NULL operand matches no WHEN
+        let result = rows(
+            run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, CASE priority WHEN 'high' THEN score * 2 ELSE 0 END AS weight FROM tasks ORDER BY id").unwrap(),
+        );
+        assert_eq!(result, ["1,0", "2,190", "3,0", "4,0"]);
+
+        // UPDATE ... SET with a CASE over the old row
+        let updated = run_sql(
+            &mut db,
+            &mut storage,
+            &tx_manager,
+            "UPDATE tasks SET priority = CASE WHEN score > 60 THEN 'high' ELSE priority END WHERE priority IS NULL",
+        )
+        .unwrap();
+        assert!(matches!(updated, QueryResult::Success(ref msg) if msg.contains('1')));
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT priority FROM tasks WHERE id = 4").unwrap());
+        assert!(result.contains(&"high".to_string()));
+    }
+
+    #[test]
+    fn test_aggregate_filter() {
+        // v2.7.0: agg FILTER (WHERE ...) - conditional aggregation in one pass
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, columns) => (rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(), columns),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE tasks (id INTEGER, owner TEXT, status TEXT, hours INTEGER)").unwrap();
+        for sql in [
+            "INSERT INTO tasks VALUES (1, 'ann', 'done', 3)",
+            "INSERT INTO tasks VALUES (2, 'ann', 'open', 5)",
+            "INSERT INTO tasks VALUES (3, 'bob', 'done', 2)",
+            "INSERT INTO tasks VALUES (4, 'bob', 'done', 4)",
+            "INSERT INTO tasks VALUES (5, 'bob', NULL, 1)",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        let (result, columns) = rows(
+            run_sql(
+                &mut db,
+                &mut storage,
+                &tx_manager,
+                "SELECT COUNT(*) FILTER (WHERE status = 'done'), COUNT(*), SUM(hours) FILTER (WHERE status != 'done') FROM tasks",
+            )
+            .unwrap(),
+        );
+        assert_eq!(result, ["3,5,5"]);
+        assert_eq!(columns, ["count", "count", "sum(hours)"]);
+
+        // Per group; a group without matching rows counts 0
+        let (mut result, _) = rows(
+            run_sql(
+                &mut db,
+                &mut storage,
+                &tx_manager,
+                "SELECT owner, COUNT(*) FILTER (WHERE status = 'done' AND hours > 2), MAX(hours) FILTER (WHERE status = 'open') FROM tasks GROUP BY owner",
+            )
+            .unwrap(),
+        );
+        result.sort();
+        assert_eq!(result, ["ann,1,5", "bob,1,NULL"]);
+    }
+
+    #[test]
+    fn test_column_collations_and_ilike() {
+        // v2.7.0: COLLATE per column, ILIKE
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(
+            &mut db,
+            &mut storage,
+            &tx_manager,
+            "CREATE TABLE words (raw TEXT, word TEXT COLLATE \"en_US.utf8\", tag VARCHAR(20) COLLATE case_insensitive)",
+        )
+        .unwrap();
+        for sql in [
+            "INSERT INTO words VALUES ('banana', 'banana', 'Fruit')",
+            "INSERT INTO words VALUES ('Cherry', 'Cherry', 'FRUIT')",
+            "INSERT INTO words VALUES ('apple', 'apple', 'tree')",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        // Byte order puts upper case first, the locale sorts alphabetically
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words ORDER BY raw").unwrap()), ["Cherry", "apple", "banana"]);
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT word FROM words ORDER BY word DESC").unwrap()), ["Cherry", "banana", "apple"]);
+
+        // Case-insensitive column: =, IN and LIKE ignore case
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words WHERE tag = 'fruit' ORDER BY raw").unwrap()), ["Cherry", "banana"]);
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words WHERE tag IN ('TREE')").unwrap()), ["apple"]);
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words WHERE tag LIKE 'fr%' ORDER BY raw").unwrap()), ["Cherry", "banana"]);
+        assert!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words WHERE raw = 'cherry'").unwrap()).is_empty());
+
+        // ILIKE on any text column
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words WHERE raw ILIKE 'CH%'").unwrap()), ["Cherry"]);
+        assert!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT raw FROM words WHERE raw LIKE 'CH%'").unwrap()).is_empty());
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE bad (name TEXT COLLATE \"klingon\")").unwrap_err();
+        assert!(err.to_string().contains("collation \"klingon\" does not exist"), "{err}");
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE bad (id INTEGER COLLATE \"C\")").unwrap_err();
+        assert!(err.to_string().contains("collations are not supported by type"), "{err}");
+    }
+
+    #[test]
+    fn test_update_enforces_char_lengths() {
+        // v2.7.0: UPDATE goes through the same coercion as INSERT, also with parameters
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE codes (id INTEGER, code CHAR(4), label VARCHAR(5))").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO codes VALUES (1, 'ab', 'één')").unwrap();
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "UPDATE codes SET label = 'toolong' WHERE id = 1").unwrap_err();
+        assert!(matches!(err, DatabaseError::StringDataRightTruncation(ref t) if t == "character varying(5)"));
+        let update = crate::network::substitute_parameters(
+            "UPDATE codes SET code = $1 WHERE id = $2",
+            &[Some(Value::Text("abcde".to_string())), Some(Value::Integer(1))],
+        );
+        let err = run_sql(&mut db, &mut storage, &tx_manager, &update).unwrap_err();
+        assert!(matches!(err, DatabaseError::StringDataRightTruncation(ref t) if t == "character(4)"));
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT code, label FROM codes").unwrap()), ["ab  ,één"]);
+
+        let update = crate::network::substitute_parameters(
+            "UPDATE codes SET code = $1 WHERE id = $2",
+            &[Some(Value::Text("xy".to_string())), Some(Value::Integer(1))],
+        );
+        run_sql(&mut db, &mut storage, &tx_manager, &update).unwrap();
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT code FROM codes WHERE code = 'xy  '").unwrap()), ["xy  "]);
+    }
+
+    #[test]
+    fn test_update_keeps_unique_columns() {
+        // v2.7.0: UPDATE checks UNIQUE and PRIMARY KEY columns and leaves old versions dead
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT NOT NULL UNIQUE, n INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO t VALUES (1, 'a', 0), (2, 'b', 0), (3, 'c', 0)").unwrap();
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "UPDATE t SET id = 1 WHERE id = 2").unwrap_err();
+        assert!(matches!(err, DatabaseError::UniqueViolation(_)), "{err:?}");
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "UPDATE t SET code = 'x' WHERE id > 1").unwrap_err();
+        assert!(matches!(err, DatabaseError::UniqueViolation(_)), "{err:?}");
+
+        // Keys may move as long as they stay distinct, also onto a key given up in the same UPDATE
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE t SET id = id + 10").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE t SET code = 'b' WHERE id = 11").unwrap_err();
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE t SET n = 1 WHERE code = 'a'").unwrap();
+
+        // Versions replaced by an earlier UPDATE are not updated (and revived) again
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE t SET n = n + 1").unwrap();
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, code, n FROM t ORDER BY id").unwrap()), ["11,a,2", "12,b,1", "13,c,1"]);
+    }
+
+    #[test]
+    fn test_insert_many() {
+        // v2.7.0: Multi-row VALUES and INSERT ... SELECT are one batch
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        let ids = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE src (id INTEGER PRIMARY KEY, name TEXT, active BOOLEAN)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE dst (id INTEGER, name TEXT, active BOOLEAN)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE UNIQUE INDEX idx_dst_id ON dst (id)").unwrap();
+
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO src VALUES (1, 'a', true), (2, NULL, false), (3, 'c', true)").unwrap();
+        assert_eq!(result.row_count(), 3);
+
+        // A duplicate anywhere in the batch keeps every row out
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO src VALUES (4, 'd', true), (4, 'e', false)").unwrap_err();
+        assert!(matches!(err, DatabaseError::UniqueViolation(_)), "{err:?}");
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO src (id) VALUES (5), (1)").is_err());
+        assert_eq!(ids(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM src").unwrap()), ["1", "2", "3"]);
+
+        // INSERT ... SELECT converts the values back to the column types, NULL stays NULL
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO dst SELECT id, name, active FROM src WHERE id > 1").unwrap();
+        assert_eq!(result.row_count(), 2);
+        assert_eq!(ids(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM dst WHERE name IS NULL").unwrap()), ["2"]);
+        assert_eq!(ids(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM dst WHERE active = true").unwrap()), ["3"]);
+
+        // The unique index was updated for every row of the batch
+        let index = db.indexes.get("idx_dst_id").unwrap();
+        assert_eq!((index.search(&Value::Integer(2)).len(), index.search(&Value::Integer(3)).len()), (1, 1));
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO dst SELECT id, name, active FROM src").unwrap_err();
+        assert!(matches!(err, DatabaseError::UniqueViolation(_)), "{err:?}");
+        assert_eq!(ids(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM dst").unwrap()), ["2", "3"]);
+    }
+
+    #[test]
+    fn test_max_result_rows() {
+        // v2.7.0: Only what goes back to the client is capped
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        storage.set_max_result_rows(3);
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE t (id INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO t VALUES (1), (2), (3), (4), (5)").unwrap();
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM t").unwrap_err();
+        assert!(matches!(err, DatabaseError::TooManyResultRows(3)), "{err:?}");
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM t UNION ALL SELECT id FROM t WHERE id = 1").is_err());
+        assert_eq!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM t ORDER BY id DESC LIMIT 3").unwrap().row_count(), 3);
+        assert_eq!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM t WHERE id > 2").unwrap().row_count(), 3);
+        assert_eq!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM t").unwrap().row_count(), 1);
+
+        // INSERT ... SELECT and subqueries read all rows
+        assert_eq!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO t SELECT id FROM t").unwrap().row_count(), 5);
+        assert_eq!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM t WHERE id IN (SELECT id FROM t WHERE id < 2)").unwrap().row_count(), 2);
+    }
+
+    #[test]
+    fn test_select_list_keeps_its_order() {
+        // v2.7.0: Columns, literals, expressions, subqueries and window functions
+        // come back in the order the select list names them
+        let mut db = Database::new("test".to_string());
+        let mut storage = create_test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE pc (id INTEGER, name TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO pc VALUES (1, 'a'), (2, 'b')").unwrap();
+
+        let mut result = |sql: &str| match run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap() {
+            QueryResult::Rows(rows, columns) => (columns, rows),
+            QueryResult::Success(msg) => panic!("Expected Rows result, got {msg}"),
+        };
+
+        let (columns, rows) = result("SELECT id * 10, name FROM pc WHERE id = 1");
+        assert_eq!(columns, ["?column?", "name"]);
+        assert_eq!(rows, [["10", "a"]]);
+
+        let (columns, rows) = result(
+            "SELECT 7, name, ROW_NUMBER() OVER (ORDER BY id DESC) AS rn, (SELECT COUNT(*) FROM pc) AS total, id + 1 AS next, id FROM pc ORDER BY id",
+        );
+        assert_eq!(columns, ["?column?", "name", "rn", "total", "next", "id"]);
+        assert_eq!(rows, [["7", "a", "2", "2", "2", "1"], ["7", "b", "1", "2", "3", "2"]]);
+
+        let (columns, rows) = result("SELECT id * 2 AS double, * FROM pc WHERE id = 2");
+        assert_eq!(columns, ["double", "id", "name"]);
+        assert_eq!(rows, [["4", "2", "b"]]);
+
+        let (columns, rows) = result("SELECT name AS n, id FROM pc ORDER BY id");
+        assert_eq!(columns, ["n", "id"]);
+        assert_eq!(rows, [["a", "1"], ["b", "2"]]);
+
+        let (columns, rows) = result("SELECT name AS n, COUNT(*) FROM pc GROUP BY name ORDER BY name");
+        assert_eq!(columns, ["n", "count"]);
+        assert_eq!(rows, [["a", "1"], ["b", "1"]]);
+    }
 }
@@ -2,13 +2,18 @@
 ///
 /// INSERT, UPDATE, DELETE using `RowStorage` abstraction.
 /// This allows seamless operation with both Vec<Row> and `PagedTable`.
-use crate::types::{Database, DatabaseError, Row, Value, Column, DataType};
-use crate::parser::Condition;
-use crate::storage::StorageEngine;
-use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError, Row, Value, Column, DataType, SerialSequence, Zone};
+use crate::types::value::values_equal;
+use crate::parser::{Condition, Expression};
+use crate::storage::{Operation, StorageEngine};
+use crate::transaction::{GlobalTransactionManager, RowChange};
 use super::storage_adapter::RowStorage;
 use super::dispatcher_executor::QueryResult;
-use super::conditions::ConditionEvaluator;
+use super::from_tables::FromTables;
+use super::functions::FunctionExecutor;
+use super::constraints::{CompiledChecks, TableConstraints};
+use super::generated::{CompiledGenerated, GeneratedColumns};
+use super::system_columns::SystemColumns;
 use crate::index::Index;
 use std::collections::HashMap;
 
@@ -24,8 +29,7 @@ impl DmlExecutor {
     /// Borrow-checker friendly: accepts table parts separately instead of &mut Database
     pub fn insert_with_storage<S: RowStorage>(
         table_columns: &[Column],
-        table_sequences: &std::collections::HashMap<String, i64>,
-        sequences_mut: &mut std::collections::HashMap<String, i64>,
+        sequences: &HashMap<String, SerialSequence>,
         table_name: &str,
         columns: Option<Vec<String>>,
         values: Vec<Value>,
@@ -34,22 +38,60 @@ impl DmlExecutor {
         tx_manager: &GlobalTransactionManager,
         indexes: &mut HashMap<String, Index>,
         active_tx_id: Option<u64>,
+        generated: &CompiledGenerated,  // v2.7.0: GENERATED ALWAYS AS ... STORED
+        checks: &CompiledChecks,  // v2.7.0: CHECK constraints
     ) -> Result<QueryResult, DatabaseError> {
-        // Reorder values to match table schema if columns specified
-        let mut ordered_values = Self::reorder_values(table_columns, columns, values)?;
-
-        // Handle SERIAL/BIGSERIAL auto-generation
-        Self::handle_serial_columns(table_columns, table_sequences, &mut ordered_values);
+        Self::insert_many(
+            table_columns, sequences, table_name, columns, vec![values], storage, storage_engine, tx_manager, indexes, active_tx_id, generated, checks,
+        )
+    }
 
-        // Validate types, VARCHAR lengths, CHAR padding, ENUM values
-        Self::validate_and_coerce_types(table_columns, &mut ordered_values)?;
+    /// Execute an INSERT of several rows as one batch (v2.7.0)
+    ///
+    /// Every row is validated before any is written. UNIQUE columns are then
+    /// checked in one pass over the table and unique indexes in one pass over
+    /// the batch, the rows go to the WAL in a single write, and each index of
+    /// the table is updated in one pass.
+    pub fn insert_many<S: RowStorage>(
+        table_columns: &[Column],
+        sequences: &HashMap<String, SerialSequence>,
+        table_name: &str,
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<Value>>,
+        storage: &mut S,
+        storage_engine: Option<&mut StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        indexes: &mut HashMap<String, Index>,
+        active_tx_id: Option<u64>,
+        generated: &CompiledGenerated,
+        checks: &CompiledChecks,
+    ) -> Result<QueryResult, DatabaseError> {
+        let mut batch = Vec::with_capacity(rows.len());
+        for values in rows {
+            let values = Self::prepare_row(table_columns, sequences, table_name, columns.clone(), values, generated)?;
+            TableConstraints::check_row(checks, table_name, table_columns, &values)?;  // v2.7.0
+            batch.push(values);
+        }
 
         // Note: Foreign key validation moved to dispatcher (before mutable borrows)
 
-        // Validate UNIQUE constraints
-        Self::validate_unique_constraints(table_columns, &ordered_values, storage, tx_manager)?;
+        // Validate UNIQUE constraints and unique indexes
+        Self::validate_unique_constraints(table_columns, &batch, storage, tx_manager)?;
+        let table_indexes: Vec<(&mut Index, Vec<usize>)> = indexes
+            .values_mut()
+            .filter(|index| index.table_name() == table_name)
+            .filter_map(|index| {
+                let positions = index
+                    .column_names()
+                    .iter()
+                    .map(|name| table_columns.iter().position(|c| &c.name == name))
+                    .collect::<Option<Vec<usize>>>()?;
+                Some((index, positions))
+            })
+            .collect();
+        Self::validate_unique_indexes(&table_indexes, &batch)?;
 
-        // Create row with MVCC
+        // Create rows with MVCC
         // v2.1.0: Use active_tx_id if in transaction, otherwise allocate new tx_id
         let (tx_id, auto_commit) = if let Some(tx_id) = active_tx_id {
             (tx_id, false)
@@ -58,50 +100,61 @@ impl DmlExecutor {
             (new_tx_id, true)
         };
 
-        let row = Row::new_with_xmin(ordered_values.clone(), tx_id);
+        let rows: Vec<Row> = batch.iter().map(|values| Row::new_with_xmin(values.clone(), tx_id)).collect();
 
-        // Log to WAL before executing
+        // Log to WAL before executing (v2.7.0: with the SERIAL values, so the
+        // counters never go back below them after recovery)
         if let Some(se) = storage_engine {
-            se.log_insert(table_name, &row)?;
+            let mut operations = Vec::new();
+            for row in &rows {
+                for (idx, col) in table_columns.iter().enumerate() {
+                    if matches!(col.data_type, DataType::Serial | DataType::BigSerial)
+                        && let Value::Integer(value) = row.values[idx] {
+                            operations.push(Operation::SerialValue {
+                                table_name: table_name.to_string(),
+                                column_name: col.name.clone(),
+                                value,
+                            });
+                        }
+                }
+                operations.push(Operation::Insert { table_name: table_name.to_string(), row: row.clone() });
+            }
+            se.log_batch(operations)?;
         }
 
-        // Insert using RowStorage abstraction
-        storage.insert(row)?;
-
-        // Get row index (newly inserted row is at the end)
-        let row_index = storage.count() - 1;
+        // Insert using RowStorage abstraction; the new rows go at the end
+        let first_row_index = storage.count();
+        for row in rows {
+            storage.insert(row)?;
+        }
 
         // Update all indexes on this table (v1.9.0: supports composite)
-        for (_idx_name, index) in indexes.iter_mut() {
-            if index.table_name() == table_name {
+        for (index, positions) in table_indexes {
+            for (offset, values) in batch.iter().enumerate() {
+                let row_index = first_row_index + offset;
                 if index.is_composite() {
-                    // Composite index - extract all column values
-                    let mut index_values = Vec::new();
-                    for col_name in index.column_names() {
-                        if let Some(col_idx) = table_columns.iter().position(|c| &c.name == col_name) {
-                            index_values.push(ordered_values[col_idx].clone());
-                        }
-                    }
-                    if index_values.len() == index.column_names().len() {
-                        index.insert_composite(&index_values, row_index)?;
-                    }
+                    let index_values: Vec<Value> = positions.iter().map(|&idx| values[idx].clone()).collect();
+                    index.insert_composite(&index_values, row_index)?;
                 } else {
-                    // Single column index
-                    if let Some(col_idx) = table_columns.iter().position(|c| c.name == index.column_name()) {
-                        let value = &ordered_values[col_idx];
-                        index.insert(value, row_index)?;
-                    }
+                    index.insert(&values[positions[0]], row_index)?;
                 }
             }
         }
 
-        // Update sequences for SERIAL columns (using mutable reference)
-        for (idx, col) in table_columns.iter().enumerate() {
-            if matches!(col.data_type, DataType::Serial | DataType::BigSerial)
-                && let Value::Integer(val) = ordered_values[idx] {
-                    let current_seq = sequences_mut.get(&col.name).copied().unwrap_or(1);
-                    sequences_mut.insert(col.name.clone(), current_seq.max(val + 1));
-                }
+        // Explicit SERIAL values are never handed out by the counter afterwards
+        for values in &batch {
+            for (idx, col) in table_columns.iter().enumerate() {
+                if matches!(col.data_type, DataType::Serial | DataType::BigSerial)
+                    && let Value::Integer(val) = values[idx]
+                    && let Some(sequence) = sequences.get(&col.name) {
+                        sequence.advance_past(val);
+                    }
+            }
+        }
+
+        // v2.7.0: Change observers hear of the rows once the transaction commits
+        if tx_manager.observes(table_name) {
+            tx_manager.record_changes(tx_id, batch.iter().map(|values| RowChange::insert(table_name, values.clone(), tx_id)));
         }
 
         // v2.1.0: Auto-commit if not in explicit transaction
@@ -109,7 +162,37 @@ impl DmlExecutor {
             tx_manager.commit_transaction(tx_id);
         }
 
-        Ok(QueryResult::Success("1 row inserted".to_string()))
+        Ok(QueryResult::Success(format!("INSERT 0 {}", batch.len())))
+    }
+
+    /// Values of one row to insert, in table order, with SERIAL and generated
+    /// columns filled in and every constraint on the row itself checked
+    fn prepare_row(
+        table_columns: &[Column],
+        sequences: &HashMap<String, SerialSequence>,
+        table_name: &str,
+        columns: Option<Vec<String>>,
+        values: Vec<Value>,
+        generated: &CompiledGenerated,
+    ) -> Result<Vec<Value>, DatabaseError> {
+        // Reorder values to match table schema if columns specified
+        let mut ordered_values = Self::reorder_values(table_columns, columns, values)?;
+
+        // Handle SERIAL/BIGSERIAL auto-generation
+        Self::handle_serial_columns(table_name, table_columns, sequences, &mut ordered_values)?;
+
+        // Validate types, VARCHAR lengths, CHAR padding, ENUM values
+        Self::validate_and_coerce_types(table_columns, &mut ordered_values)?;
+
+        // v2.7.0: Compute generated columns from the final input values
+        if !generated.is_empty() {
+            GeneratedColumns::compute(generated, table_columns, &mut ordered_values)?;
+            Self::validate_and_coerce_types(table_columns, &mut ordered_values)?;
+        }
+
+        // v2.7.0: Omitted columns are NULL by now, so this covers them too
+        Self::validate_not_null(table_name, table_columns, &ordered_values)?;
+        Ok(ordered_values)
     }
 
     /// Reorder values to match table schema when columns are specified
@@ -135,77 +218,248 @@ impl DmlExecutor {
     }
 
     /// Handle SERIAL/BIGSERIAL auto-generation for NULL values
+    ///
+    /// v2.7.0: A SERIAL sequence ends at the INTEGER maximum instead of wrapping.
+    /// v2.7.0: Values come from the table's atomic counters.
     fn handle_serial_columns(
+        table_name: &str,
         columns: &[Column],
-        sequences: &std::collections::HashMap<String, i64>,
+        sequences: &HashMap<String, SerialSequence>,
         values: &mut [Value],
-    ) {
+    ) -> Result<(), DatabaseError> {
         for (idx, col) in columns.iter().enumerate() {
-            if matches!(col.data_type, crate::types::DataType::Serial | crate::types::DataType::BigSerial)
-                && matches!(values[idx], Value::Null) {
-                    let seq_value = sequences.get(&col.name).copied().unwrap_or(1);
-                    values[idx] = Value::Integer(seq_value);
+            let max_value = match col.data_type {
+                DataType::Serial => i64::from(i32::MAX),
+                DataType::BigSerial => i64::MAX,
+                _ => continue,
+            };
+            if matches!(values[idx], Value::Null) {
+                let seq_value = sequences.get(&col.name).map_or(1, SerialSequence::next_value);
+                if seq_value > max_value {
+                    return Err(DatabaseError::SequenceExhausted(
+                        format!("{table_name}_{}_seq", col.name),
+                        max_value,
+                    ));
                 }
+                values[idx] = Value::Integer(seq_value);
+            }
         }
+        Ok(())
+    }
+
+    /// Reject NULL in NOT NULL and PRIMARY KEY columns (v2.7.0)
+    fn validate_not_null(
+        table_name: &str,
+        columns: &[Column],
+        values: &[Value],
+    ) -> Result<(), DatabaseError> {
+        for (col, value) in columns.iter().zip(values) {
+            if (!col.nullable || col.primary_key) && matches!(value, Value::Null) {
+                return Err(DatabaseError::NotNullViolation(table_name.to_string(), col.name.clone()));
+            }
+        }
+        Ok(())
     }
 
-    /// Validate and coerce value types (VARCHAR, CHAR, ENUM)
+    /// Validate and coerce every value to its column's type
+    ///
+    /// v2.7.0: Covers all types, not only VARCHAR, CHAR and ENUM.
     fn validate_and_coerce_types(
         columns: &[Column],
         values: &mut [Value],
     ) -> Result<(), DatabaseError> {
-        for (idx, col) in columns.iter().enumerate() {
-            let value = &mut values[idx];
-
-            // Validate VARCHAR length
-            if let crate::types::DataType::Varchar { max_length } = col.data_type
-                && let Value::Text(s) = value
-                    && s.len() > max_length {
-                        return Err(DatabaseError::ParseError(format!(
-                            "Value too long for column '{}': {} exceeds VARCHAR({})",
-                            col.name, s.len(), max_length
-                        )));
-                    }
+        for (col, value) in columns.iter().zip(values.iter_mut()) {
+            *value = Self::coerce_value(col, std::mem::replace(value, Value::Null))?;
+        }
+        Ok(())
+    }
 
-            // Validate and pad CHAR length
-            if let crate::types::DataType::Char { length } = col.data_type {
-                match value {
-                    Value::Text(s) | Value::Char(s) => {
-                        if s.len() > length {
-                            return Err(DatabaseError::ParseError(format!(
-                                "Value too long for column '{}': {} exceeds CHAR({})",
-                                col.name, s.len(), length
-                            )));
-                        }
-                        *value = Value::Char(format!("{s:<length$}"));
-                    }
-                    _ => {}
+    /// Convert a value to the column's type (v2.7.0)
+    ///
+    /// Follows the assignment casts of `PostgreSQL`: numbers convert between the
+    /// numeric types (rounded for integer columns), string literals are parsed
+    /// as the column type, dates and timestamps convert into each other and
+    /// any scalar can be stored in a text column. VARCHAR/CHAR lengths and ENUM
+    /// labels are checked here too. Public so index lookups can convert their
+    /// keys the same way.
+    pub fn coerce_value(col: &Column, value: Value) -> Result<Value, DatabaseError> {
+        use rust_decimal::prelude::{Decimal, FromPrimitive, ToPrimitive};
+
+        if matches!(value, Value::Null) {
+            return Ok(value);
+        }
+        let type_name = col.data_type.sql_name();
+        let mismatch = |value: &Value| {
+            DatabaseError::DatatypeMismatch(col.name.clone(), type_name.clone(), Self::value_type_name(value).to_string())
+        };
+        let invalid = |text: &str| DatabaseError::InvalidTextRepresentation(type_name.clone(), text.to_string());
+        let out_of_range = || DatabaseError::NumericOutOfRange(format!("{type_name} out of range"));
+
+        let integer = |value: &Value| -> Result<i64, DatabaseError> {
+            match value {
+                Value::SmallInt(n) => Ok(i64::from(*n)),
+                Value::Integer(n) => Ok(*n),
+                Value::Numeric(d) => d.round().to_i64().ok_or_else(out_of_range),
+                #[allow(clippy::cast_precision_loss)]
+                Value::Real(f) if f.is_finite() && f.abs() < i64::MAX as f64 => {
+                    #[allow(clippy::cast_possible_truncation)]
+                    Ok(f.round() as i64)
                 }
+                Value::Real(_) => Err(out_of_range()),
+                Value::Text(s) | Value::Char(s) => s.trim().parse().map_err(|_| invalid(s)),
+                other => Err(mismatch(other)),
             }
+        };
+        let text = |value: Value| match value {
+            Value::Text(s) | Value::Char(s) => Ok(s),
+            Value::Bytea(_) => Err(mismatch(&value)),
+            other => Ok(other.to_string()),
+        };
 
-            // Validate ENUM values
-            if let crate::types::DataType::Enum { ref name, ref values } = col.data_type {
+        let value = match &col.data_type {
+            DataType::SmallInt => {
+                let n = integer(&value)?;
+                Value::SmallInt(i16::try_from(n).map_err(|_| out_of_range())?)
+            }
+            DataType::Integer | DataType::Serial => {
+                let n = integer(&value)?;
+                i32::try_from(n).map_err(|_| out_of_range())?;
+                Value::Integer(n)
+            }
+            DataType::BigInt | DataType::BigSerial => Value::Integer(integer(&value)?),
+            DataType::Real => match value {
+                Value::Real(_) => value,
+                Value::Numeric(d) => Value::Real(d.to_f64().ok_or_else(out_of_range)?),
+                Value::Text(ref s) | Value::Char(ref s) => {
+                    Value::Real(s.trim().parse().map_err(|_| invalid(s))?)
+                }
+                #[allow(clippy::cast_precision_loss)]
+                other => Value::Real(integer(&other)? as f64),
+            },
+            DataType::Numeric { .. } => match value {
+                Value::Numeric(_) => value,
+                Value::SmallInt(n) => Value::Numeric(Decimal::from(n)),
+                Value::Integer(n) => Value::Numeric(Decimal::from(n)),
+                Value::Real(f) => Value::Numeric(Decimal::from_f64(f).ok_or_else(out_of_range)?),
+                Value::Text(ref s) | Value::Char(ref s) => {
+                    Value::Numeric(s.trim().parse().map_err(|_| invalid(s))?)
+                }
+                other => return Err(mismatch(&other)),
+            },
+            DataType::Text => Value::Text(text(value)?),
+            DataType::Varchar { max_length } => Value::Text(Self::fit_length(col, text(value)?, *max_length)?),
+            DataType::Char { length } => {
+                let s = Self::fit_length(col, text(value)?, *length)?;
+                Value::Char(format!("{s:<length$}"))
+            }
+            DataType::Boolean => match value {
+                Value::Boolean(_) => value,
+                Value::Text(ref s) | Value::Char(ref s) => match s.trim().to_lowercase().as_str() {
+                    "t" | "true" | "y" | "yes" | "on" | "1" => Value::Boolean(true),
+                    "f" | "false" | "n" | "no" | "off" | "0" => Value::Boolean(false),
+                    _ => return Err(invalid(s)),
+                },
+                other => return Err(mismatch(&other)),
+            },
+            DataType::Date | DataType::Timestamp | DataType::TimestampTz => {
+                let value = match value {
+                    Value::Text(ref s) | Value::Char(ref s) => {
+                        crate::core::value::parse_timestamp(s.trim()).ok_or_else(|| invalid(s))?
+                    }
+                    other => other,
+                };
+                // v2.7.0: Time without an offset is local time of the session's zone
                 match value {
-                    Value::Text(s) => {
-                        if !values.contains(s) {
-                            return Err(DatabaseError::ParseError(format!(
-                                "Invalid value '{s}' for ENUM type '{name}'. Expected one of: {values:?}"
-                            )));
+                    Value::TimestampTz(t) if col.data_type == DataType::TimestampTz => Value::TimestampTz(t),
+                    value => {
+                        let timestamp = match value {
+                            Value::Date(d) => d.and_time(chrono::NaiveTime::MIN),
+                            Value::Timestamp(t) => t,
+                            Value::TimestampTz(t) => Zone::with_current(|zone| zone.to_local(&t)),
+                            other => return Err(mismatch(&other)),
+                        };
+                        match col.data_type {
+                            DataType::Date => Value::Date(timestamp.date()),
+                            DataType::Timestamp => Value::Timestamp(timestamp),
+                            _ => Value::TimestampTz(Zone::with_current(|zone| zone.to_utc(timestamp))),
                         }
-                        *value = Value::Enum(name.clone(), s.clone());
                     }
-                    Value::Enum(_, val) => {
-                        if !values.contains(val) {
-                            return Err(DatabaseError::ParseError(format!(
-                                "Invalid value '{val}' for ENUM type '{name}'"
-                            )));
-                        }
+                }
+            }
+            DataType::Uuid => match value {
+                Value::Uuid(_) => value,
+                Value::Text(ref s) | Value::Char(ref s) => {
+                    Value::Uuid(s.trim().parse().map_err(|_| invalid(s))?)
+                }
+                other => return Err(mismatch(&other)),
+            },
+            DataType::Json | DataType::Jsonb => match value {
+                Value::Json(_) => value,
+                Value::Text(s) | Value::Char(s) => {
+                    if serde_json::from_str::<serde_json::Value>(&s).is_err() {
+                        return Err(invalid(&s));
                     }
-                    _ => {}
+                    Value::Json(s)
                 }
+                other => return Err(mismatch(&other)),
+            },
+            DataType::Bytea => match value {
+                Value::Bytea(_) => value,
+                Value::Text(ref s) | Value::Char(ref s) => {
+                    crate::core::value::parse_bytea(s).ok_or_else(|| invalid(s))?
+                }
+                other => return Err(mismatch(&other)),
+            },
+            DataType::Enum { name, values } => {
+                let label = match value {
+                    Value::Text(s) => s,
+                    Value::Enum(_, s) => s,
+                    other => return Err(mismatch(&other)),
+                };
+                if !values.contains(&label) {
+                    return Err(DatabaseError::InvalidEnumValue(name.clone(), label));
+                }
+                Value::Enum(name.clone(), label)
             }
+        };
+        Ok(value)
+    }
+
+    /// Enforce the length of a VARCHAR(n) / CHAR(n) column (v2.7.0)
+    ///
+    /// Lengths count characters, not bytes. As in `PostgreSQL`, a longer value
+    /// is cut to `n` characters when everything past them is spaces; anything
+    /// else past them is an error.
+    fn fit_length(col: &Column, s: String, limit: usize) -> Result<String, DatabaseError> {
+        match s.char_indices().nth(limit) {
+            None => Ok(s),
+            Some((end, _)) if s[end..].chars().all(|c| c == ' ') => Ok(s[..end].to_string()),
+            Some(_) => Err(DatabaseError::StringDataRightTruncation(match col.data_type {
+                DataType::Char { .. } => format!("character({limit})"),
+                _ => format!("character varying({limit})"),
+            })),
+        }
+    }
+
+    /// SQL type name of a value, for error messages
+    const fn value_type_name(value: &Value) -> &'static str {
+        match value {
+            Value::Null => "unknown",
+            Value::SmallInt(_) => "smallint",
+            Value::Integer(_) => "integer",
+            Value::Real(_) => "real",
+            Value::Numeric(_) => "numeric",
+            Value::Text(_) => "text",
+            Value::Char(_) => "character",
+            Value::Boolean(_) => "boolean",
+            Value::Date(_) => "date",
+            Value::Timestamp(_) => "timestamp",
+            Value::TimestampTz(_) => "timestamptz",
+            Value::Uuid(_) => "uuid",
+            Value::Json(_) => "json",
+            Value::Bytea(_) => "bytea",
+            Value::Enum(..) => "enum",
         }
-        Ok(())
     }
 
     /// Validate foreign key constraints (using `HashMap`<String, Table>)
@@ -253,7 +507,7 @@ impl DmlExecutor {
 
                 let current_tx_id = tx_manager.current_tx_id();
                 let exists = ref_rows.iter()
-                    .any(|row| row.is_visible(current_tx_id) && &row.values[ref_col_idx] == value);
+                    .any(|row| row.is_visible(current_tx_id) && values_equal(&row.values[ref_col_idx], value));
 
                 if !exists {
                     return Err(DatabaseError::ForeignKeyViolation(
@@ -304,7 +558,7 @@ impl DmlExecutor {
 
                 let current_tx_id = tx_manager.current_tx_id();
                 let exists = ref_table.rows.iter()
-                    .any(|row| row.is_visible(current_tx_id) && &row.values[ref_col_idx] == value);
+                    .any(|row| row.is_visible(current_tx_id) && values_equal(&row.values[ref_col_idx], value));
 
                 if !exists {
                     return Err(DatabaseError::ForeignKeyViolation(
@@ -318,17 +572,26 @@ impl DmlExecutor {
     }
 
     /// Validate UNIQUE constraints using `RowStorage`
+    ///
+    /// v2.7.0: For a whole batch of rows, which must not repeat a value among
+    /// themselves either; the table is read once.
     fn validate_unique_constraints<S: RowStorage>(
         columns: &[Column],
-        values: &[Value],
+        batch: &[Vec<Value>],
         storage: &S,
         tx_manager: &GlobalTransactionManager,
     ) -> Result<(), DatabaseError> {
+        let unique: Vec<usize> = (0..columns.len()).filter(|&idx| columns[idx].unique || columns[idx].primary_key).collect();
+        if unique.is_empty() {
+            return Ok(());
+        }
+
         let all_rows = storage.get_all()?;
         let current_tx_id = tx_manager.current_tx_id();
+        let visible: Vec<&Row> = all_rows.iter().filter(|row| row.is_visible(current_tx_id)).collect();
 
-        for (idx, col) in columns.iter().enumerate() {
-            if col.unique || col.primary_key {
+        for idx in unique {
+            for (position, values) in batch.iter().enumerate() {
                 let value = &values[idx];
 
                 // NULL values are allowed in UNIQUE columns
@@ -336,14 +599,68 @@ impl DmlExecutor {
                     continue;
                 }
 
-                // Check if value already exists
-                let exists = all_rows.iter()
-                    .any(|row| row.is_visible(current_tx_id) && &row.values[idx] == value);
+                // Check if value already exists, in the table or earlier in the batch
+                let exists = visible.iter().any(|row| values_equal(&row.values[idx], value))
+                    || batch[..position].iter().any(|earlier| values_equal(&earlier[idx], value));
 
                 if exists {
                     return Err(DatabaseError::UniqueViolation(
                         format!("UNIQUE constraint violation: value {:?} already exists in column '{}'",
-                                value, col.name)
+                                value, columns[idx].name)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// UNIQUE and PRIMARY KEY columns as an UPDATE leaves them: the rows it
+    /// does not touch and the new versions of the others (v2.7.0)
+    ///
+    /// Only columns the UPDATE changes are checked, by sorting their values.
+    fn validate_unique_update(
+        columns: &[Column],
+        rows: &[Row],
+        updated: &[(usize, Row)],
+        new_values: &[Vec<Value>],
+        tx_id: u64,
+    ) -> Result<(), DatabaseError> {
+        let changed = |idx: usize| updated.iter().zip(new_values).any(|((_, old), new)| !values_equal(&old.values[idx], &new[idx]));
+        for idx in (0..columns.len()).filter(|&idx| (columns[idx].unique || columns[idx].primary_key) && changed(idx)) {
+            let mut updated_rows = updated.iter().map(|(row_idx, _)| *row_idx).peekable();
+            let mut values: Vec<&Value> = rows
+                .iter()
+                .enumerate()
+                .filter(|(row_idx, row)| {
+                    let is_updated = updated_rows.next_if_eq(row_idx).is_some();
+                    !is_updated && row.is_visible(tx_id)
+                })
+                .map(|(_, row)| &row.values[idx])
+                .chain(new_values.iter().map(|values| &values[idx]))
+                .filter(|value| !matches!(value, Value::Null))
+                .collect();
+            values.sort_by(|a, b| crate::types::value::compare_for_sort(a, b));
+            if let Some(pair) = values.windows(2).find(|pair| values_equal(pair[0], pair[1])) {
+                return Err(DatabaseError::UniqueViolation(
+                    format!("UNIQUE constraint violation: value {:?} already exists in column '{}'", pair[1], columns[idx].name)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys of a batch that a unique index already holds, or that repeat within the batch (v2.7.0)
+    fn validate_unique_indexes(indexes: &[(&mut Index, Vec<usize>)], batch: &[Vec<Value>]) -> Result<(), DatabaseError> {
+        for (index, positions) in indexes.iter().filter(|(index, _)| index.is_unique()) {
+            let keys: Vec<Vec<Value>> = batch
+                .iter()
+                .map(|values| positions.iter().map(|&idx| values[idx].clone()).collect())
+                .collect();
+            for (position, key) in keys.iter().enumerate() {
+                let existing = if index.is_composite() { index.search_composite(key) } else { index.search(&key[0]) };
+                if !existing.is_empty() || keys[..position].contains(key) {
+                    return Err(DatabaseError::UniqueViolation(
+                        format!("Duplicate key value violates unique constraint '{}'", index.name())
                     ));
                 }
             }
@@ -364,9 +681,7 @@ impl DmlExecutor {
                     Value::SmallInt(v) => i64::from(v),
                     _ => continue,
                 };
-                let current_seq = table.sequences.get(&col.name).copied().unwrap_or(1);
-                let new_seq = std::cmp::max(current_seq, val + 1);
-                table.sequences.insert(col.name.clone(), new_seq);
+                table.sequences.entry(col.name.clone()).or_default().advance_past(val);
             }
         }
     }
@@ -376,7 +691,7 @@ impl DmlExecutor {
     /// Updates rows matching the filter condition.
     pub fn update_with_storage<S: RowStorage>(
         table_columns: &[Column],
-        assignments: Vec<(String, Value)>,
+        assignments: Vec<(String, Expression)>,
         filter: Option<Condition>,
         storage: &mut S,
         storage_engine: Option<&mut StorageEngine>,
@@ -384,16 +699,19 @@ impl DmlExecutor {
         table_name: &str,
         indexes: &mut HashMap<String, Index>,
         active_tx_id: Option<u64>,
+        generated: &CompiledGenerated,  // v2.7.0: recomputed for every updated row
+        checks: &CompiledChecks,  // v2.7.0: CHECK constraints
+        from: &FromTables,  // v2.7.0: UPDATE ... FROM
     ) -> Result<QueryResult, DatabaseError> {
         // Pre-calculate column indices
-        let column_updates: Vec<(usize, Value)> = assignments
+        let column_updates: Vec<(usize, Expression)> = assignments
             .into_iter()
-            .map(|(col_name, value)| {
+            .map(|(col_name, expr)| {
                 let idx = table_columns
                     .iter()
                     .position(|c| c.name == col_name)
                     .ok_or_else(|| DatabaseError::ParseError(format!("Unknown column: {col_name}")))?;
-                Ok((idx, value))
+                Ok((idx, expr))
             })
             .collect::<Result<Vec<_>, DatabaseError>>()?;
 
@@ -406,32 +724,77 @@ impl DmlExecutor {
         };
 
         // Define predicate and updater closures
+        // v2.7.0: WHERE may read system columns (ctid), which follow the table's own
+        let with_system = SystemColumns::used_in(table_columns, filter.as_ref());
+        let filter_columns = if with_system { SystemColumns::extend(table_columns) } else { table_columns.to_vec() };
+        let joined_columns = from.joined_columns(table_name, &filter_columns);
+        let filter_row = |row: &Row| SystemColumns::for_filter(table_columns, row, with_system).into_owned();
         let predicate = |row: &Row| -> bool {
-            if let Some(ref cond) = filter {
-                ConditionEvaluator::evaluate_with_columns(table_columns, row, cond).unwrap_or(false)
-            } else {
-                true
-            }
+            // Only versions this statement sees: rewriting a deleted one would revive it
+            row.is_visible(current_tx_id)
+                && from.matches(&joined_columns, &filter_row(row), filter.as_ref()).unwrap_or(false)
         };
 
-        let updater = |row: &Row| -> Row {
+        let new_values = |row: &Row| -> Result<Vec<Value>, DatabaseError> {
+            // v2.7.0: Every assignment sees the old row version (`SET a = b, b = a` swaps),
+            // joined with the first matching row of the FROM tables
+            let joined = from
+                .first_match(&joined_columns, &filter_row(row), filter.as_ref())?
+                .unwrap_or_else(|| row.values.clone());
             let mut new_values = row.values.clone();
-            for (idx, new_value) in &column_updates {
-                new_values[*idx] = new_value.clone();
+            for (idx, expr) in &column_updates {
+                new_values[*idx] = FunctionExecutor::evaluate_values(expr, &joined_columns, &joined)?;
             }
-            Row::new_with_xmin(new_values, current_tx_id)
+            // v2.7.0: The new row version is checked like an inserted row
+            Self::validate_and_coerce_types(table_columns, &mut new_values)?;
+            if !generated.is_empty() {
+                GeneratedColumns::compute(generated, table_columns, &mut new_values)?;
+                Self::validate_and_coerce_types(table_columns, &mut new_values)?;
+            }
+            Ok(new_values)
+        };
+
+        // Errors were surfaced while collecting the matching rows below
+        let updater = |row: &Row| -> Row {
+            let values = new_values(row).unwrap_or_else(|_| row.values.clone());
+            Row::new_with_xmin(values, current_tx_id)
         };
 
         // For index updates: collect old rows before update
         let all_rows_before = storage.get_all()?;
         let mut updated_indices = Vec::new();
+        let mut updated_values = Vec::new();
 
         for (idx, row) in all_rows_before.iter().enumerate() {
-            if row.is_visible(current_tx_id) && predicate(row) {
+            if row.is_visible(current_tx_id) && from.matches(&joined_columns, &filter_row(row), filter.as_ref())? {
+                // v2.7.0: Fail before writing anything if a generated value cannot be
+                // computed or the new row version breaks a NOT NULL or CHECK constraint
+                let checked = new_values(row).and_then(|values| {
+                    Self::validate_not_null(table_name, table_columns, &values)?;
+                    TableConstraints::check_row(checks, table_name, table_columns, &values)?;
+                    Ok(values)
+                });
+                match checked {
+                    Ok(values) => updated_values.push(values),
+                    Err(e) => {
+                        if auto_commit {
+                            tx_manager.rollback_transaction(current_tx_id);
+                        }
+                        return Err(e);
+                    }
+                }
                 updated_indices.push((idx, row.clone()));
             }
         }
 
+        // v2.7.0: ... or leaves a value twice in a UNIQUE or PRIMARY KEY column
+        if let Err(e) = Self::validate_unique_update(table_columns, &all_rows_before, &updated_indices, &updated_values, current_tx_id) {
+            if auto_commit {
+                tx_manager.rollback_transaction(current_tx_id);
+            }
+            return Err(e);
+        }
+
         // Execute update (MVCC: mark old + insert new versions)
         let updated_count = storage.update_where(predicate, updater, current_tx_id)?;
 
@@ -443,6 +806,17 @@ impl DmlExecutor {
             // New row indices start after original rows
             let new_row_start_idx = total_rows - updated_count;
 
+            // v2.7.0: Change observers hear of the new versions once the transaction commits
+            if tx_manager.observes(table_name) {
+                tx_manager.record_changes(
+                    current_tx_id,
+                    updated_indices.iter().enumerate().map(|(offset, (_, old_row))| {
+                        let new_row = &all_rows_after[new_row_start_idx + offset];
+                        RowChange::update(table_name, old_row.values.clone(), new_row.values.clone(), current_tx_id)
+                    }),
+                );
+            }
+
             for (new_idx, (old_idx, old_row)) in updated_indices.iter().enumerate() {
                 let new_row_idx = new_row_start_idx + new_idx;
                 let new_row = &all_rows_after[new_row_idx];
@@ -500,7 +874,7 @@ impl DmlExecutor {
             tx_manager.commit_transaction(current_tx_id);
         }
 
-        Ok(QueryResult::Success(format!("{updated_count} row(s) updated")))
+        Ok(QueryResult::Success(format!("UPDATE {updated_count}")))
     }
 
     /// Execute DELETE statement using `RowStorage` abstraction
@@ -515,6 +889,7 @@ impl DmlExecutor {
         table_name: &str,
         indexes: &mut HashMap<String, Index>,
         active_tx_id: Option<u64>,
+        using: &FromTables,  // v2.7.0: DELETE ... USING
     ) -> Result<QueryResult, DatabaseError> {
         // v2.1.0: Use active_tx_id if in transaction, otherwise allocate new tx_id
         let (current_tx_id, auto_commit) = if let Some(tx_id) = active_tx_id {
@@ -525,6 +900,10 @@ impl DmlExecutor {
         };
 
         // Collect rows to delete (for index updates)
+        // v2.7.0: WHERE may read system columns (ctid), which follow the table's own
+        let with_system = SystemColumns::used_in(table_columns, filter.as_ref());
+        let filter_columns = if with_system { SystemColumns::extend(table_columns) } else { table_columns.to_vec() };
+        let joined_columns = using.joined_columns(table_name, &filter_columns);
         let all_rows = storage.get_all()?;
         let mut deleted_indices = Vec::new();
 
@@ -533,13 +912,7 @@ impl DmlExecutor {
                 continue;
             }
 
-            let matches = if let Some(ref cond) = filter {
-                ConditionEvaluator::evaluate_with_columns(table_columns, row, cond).unwrap_or(false)
-            } else {
-                true
-            };
-
-            if matches {
+            if using.matches(&joined_columns, &SystemColumns::for_filter(table_columns, row, with_system), filter.as_ref())? {
                 deleted_indices.push((idx, row.clone()));
             }
         }
@@ -551,16 +924,21 @@ impl DmlExecutor {
                 return false;
             }
 
-            if let Some(ref cond) = filter {
-                ConditionEvaluator::evaluate_with_columns(table_columns, row, cond).unwrap_or(false)
-            } else {
-                true
-            }
+            using.matches(&joined_columns, &SystemColumns::for_filter(table_columns, row, with_system), filter.as_ref())
+                .unwrap_or(false)
         };
 
         // Execute delete (MVCC: mark with xmax instead of physical removal)
         let deleted_count = storage.delete_where(predicate, current_tx_id)?;
 
+        // v2.7.0: Change observers hear of the rows once the transaction commits
+        if tx_manager.observes(table_name) {
+            tx_manager.record_changes(
+                current_tx_id,
+                deleted_indices.iter().map(|(_, row)| RowChange::delete(table_name, row.values.clone(), current_tx_id)),
+            );
+        }
+
         // Update indexes: remove deleted entries (v1.9.0: supports composite)
         for (row_idx, row) in deleted_indices {
             for (_idx_name, index) in indexes.iter_mut() {
@@ -597,7 +975,7 @@ impl DmlExecutor {
             tx_manager.commit_transaction(current_tx_id);
         }
 
-        Ok(QueryResult::Success(format!("{deleted_count} row(s) deleted")))
+        Ok(QueryResult::Success(format!("DELETE {deleted_count}")))
     }
 
     /// Convenience wrapper that uses `LegacyStorage` (Vec<Row>)
@@ -624,7 +1002,7 @@ impl DmlExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Column, DataType};
+    use crate::types::{Collation, Column, DataType};
 
     #[test]
     fn test_reorder_values() {
@@ -636,6 +1014,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "name".to_string(),
@@ -644,6 +1023,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
 
@@ -667,6 +1047,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "name".to_string(),
@@ -675,16 +1056,81 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
 
         let mut sequences = std::collections::HashMap::new();
-        sequences.insert("id".to_string(), 5);
+        sequences.insert("id".to_string(), SerialSequence::new(5));
 
         let mut values = vec![Value::Null, Value::Text("Alice".to_string())];
-        DmlExecutor::handle_serial_columns(&columns, &sequences, &mut values);
+        DmlExecutor::handle_serial_columns("users", &columns, &sequences, &mut values).unwrap();
 
         assert_eq!(values[0], Value::Integer(5));
         assert_eq!(values[1], Value::Text("Alice".to_string()));
+        assert_eq!(sequences["id"].peek(), 6);
+
+        // SERIAL stops at the INTEGER maximum
+        sequences["id"].advance_past(i64::from(i32::MAX));
+        let mut values = vec![Value::Null, Value::Text("Bob".to_string())];
+        let err = DmlExecutor::handle_serial_columns("users", &columns, &sequences, &mut values).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "nextval: reached maximum value of sequence \"users_id_seq\" (2147483647)"
+        );
+    }
+
+    #[test]
+    fn test_coerce_value() {
+        let column = |data_type: DataType| Column {
+            name: "c".to_string(),
+            data_type,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            foreign_key: None,
+            collation: Collation::C,
+        };
+        let coerce = |data_type: DataType, value: Value| DmlExecutor::coerce_value(&column(data_type), value);
+        let text = |s: &str| Value::Text(s.to_string());
+
+        assert_eq!(coerce(DataType::Integer, Value::SmallInt(5)).unwrap(), Value::Integer(5));
+        assert_eq!(coerce(DataType::Integer, text(" 42 ")).unwrap(), Value::Integer(42));
+        assert_eq!(coerce(DataType::Integer, Value::Real(2.5)).unwrap(), Value::Integer(3));
+        assert_eq!(coerce(DataType::Real, Value::Integer(2)).unwrap(), Value::Real(2.0));
+        assert_eq!(coerce(DataType::Text, Value::Integer(7)).unwrap(), text("7"));
+        assert_eq!(coerce(DataType::Boolean, text("yes")).unwrap(), Value::Boolean(true));
+        assert_eq!(coerce(DataType::Integer, Value::Null).unwrap(), Value::Null);
+        assert_eq!(
+            coerce(DataType::Timestamp, text("2024-03-01")).unwrap(),
+            Value::Timestamp(chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap())
+        );
+        assert_eq!(coerce(DataType::Json, text("{\"a\": 1}")).unwrap(), Value::Json("{\"a\": 1}".to_string()));
+
+        let err = coerce(DataType::Integer, text("abc")).unwrap_err();
+        assert_eq!(err.to_string(), "invalid input syntax for type integer: \"abc\"");
+        let err = coerce(DataType::Boolean, Value::Integer(1)).unwrap_err();
+        assert_eq!(err.to_string(), "column \"c\" is of type boolean but expression is of type integer");
+        let mood = DataType::Enum { name: "mood".to_string(), values: vec!["sad".to_string(), "happy".to_string()] };
+        let err = coerce(mood, text("meh")).unwrap_err();
+        assert!(matches!(err, DatabaseError::InvalidEnumValue(ref t, ref l) if t == "mood" && l == "meh"));
+        let err = coerce(DataType::SmallInt, Value::Integer(70_000)).unwrap_err();
+        assert_eq!(err.to_string(), "smallint out of range");
+        let err = coerce(DataType::Integer, Value::Integer(3_000_000_000)).unwrap_err();
+        assert_eq!(err.to_string(), "integer out of range");
+        assert_eq!(coerce(DataType::BigInt, text("3000000000")).unwrap(), Value::Integer(3_000_000_000));
+        assert!(coerce(DataType::Json, text("{oops")).is_err());
+        assert!(coerce(DataType::Date, text("yesterday")).is_err());
+        assert!(coerce(DataType::Uuid, text("not-a-uuid")).is_err());
+
+        // v2.7.0: lengths in characters, trailing spaces past the limit are dropped
+        assert_eq!(coerce(DataType::Varchar { max_length: 3 }, text("äöü")).unwrap(), text("äöü"));
+        assert_eq!(coerce(DataType::Varchar { max_length: 3 }, text("ab   ")).unwrap(), text("ab "));
+        assert_eq!(coerce(DataType::Char { length: 4 }, text("é")).unwrap(), Value::Char("é   ".to_string()));
+        assert_eq!(coerce(DataType::Char { length: 2 }, Value::Integer(7)).unwrap(), Value::Char("7 ".to_string()));
+        let err = coerce(DataType::Varchar { max_length: 3 }, text("abcd ")).unwrap_err();
+        assert!(matches!(err, DatabaseError::StringDataRightTruncation(ref t) if t == "character varying(3)"));
+        assert_eq!(err.to_string(), "value too long for type character varying(3)");
+        assert!(coerce(DataType::Char { length: 1 }, text("ab")).is_err());
     }
 }
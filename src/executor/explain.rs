@@ -1,13 +1,39 @@
 /// EXPLAIN query analyzer (v1.8.0)
 ///
-/// Analyzes SELECT queries and returns execution plan details:
-/// - Index usage (index scan vs sequential scan)
-/// - Estimated cost (O(1), O(log n), O(n))
-/// - Estimated row count
-/// - Join strategy
-/// - Filter conditions
-use crate::parser::{Statement, Condition};
-use crate::types::{Database, DatabaseError};
+/// v2.7.0: Builds the plan tree of a SELECT, UNION / INTERSECT / EXCEPT,
+/// INSERT, UPDATE or DELETE the way the executor runs it, and renders it like
+/// `PostgreSQL` does:
+///
+/// ```text
+/// Limit  (cost=16.37..16.40 rows=10)
+///   ->  Sort  (cost=16.37..16.40 rows=100)
+///         Sort Key: total DESC
+///         ->  Hash Join  (cost=2.25..11.75 rows=100)
+///               Hash Cond: (users.id = orders.user_id)
+///               ->  Seq Scan on users  (cost=0.00..1.10 rows=10)
+///               ->  Hash  (cost=2.00..2.00 rows=100)
+///                     ->  Seq Scan on orders  (cost=0.00..2.00 rows=100)
+/// ```
+///
+/// Scans show the index they use with the conditions it answers (`Index
+/// Cond`); the rest of the WHERE clause is a `Filter`. Costs and row counts
+/// are estimates from the table sizes and fixed selectivities, in the units
+/// `PostgreSQL` uses (reading a page sequentially costs 1.0). Conditions on a
+/// column of an analyzed table use its statistics instead (see `analyze`).
+use crate::parser::{
+    BinaryOperator, CompareOperator, Condition, Expression, InsertSource, JoinClause, JoinType, SelectColumn, SortOrder,
+    Statement,
+};
+use crate::storage::DatabaseStorage;
+use crate::types::{Database, DatabaseError, Value};
+use super::analyze::TableStatistics;
+use super::derived_tables::DerivedTables;
+use super::partitions::Partitions;
+use super::planner::{IndexAccess, IndexPath, Planner};
+use super::queries::QueryExecutor as QueriesExecutor;
+use super::system_catalogs::SystemCatalog;
+use super::udf::FunctionRegistry;
+use super::views::ViewResolver;
 
 // Define QueryResult locally to avoid circular dependency
 #[derive(Debug)]
@@ -16,278 +42,699 @@ pub enum QueryResult {
     Rows(Vec<Vec<String>>, Vec<String>), // (rows, column_names)
 }
 
-pub struct ExplainExecutor;
+const SEQ_PAGE_COST: f64 = 1.0;
+const RANDOM_PAGE_COST: f64 = 4.0;
+const CPU_TUPLE_COST: f64 = 0.01;
+const CPU_OPERATOR_COST: f64 = 0.0025;
+/// Rows assumed to share a page
+const ROWS_PER_PAGE: usize = 100;
+/// Rows assumed for relations of unknown size (functions, system catalogs)
+const DEFAULT_ROWS: usize = 1000;
 
-#[derive(Debug)]
-pub struct QueryPlan {
-    pub scan_type: ScanType,
-    pub table_name: String,
-    pub index_name: Option<String>,
-    pub index_type: Option<String>,  // "hash" or "btree"
-    pub filter: Option<String>,
-    pub estimated_rows: usize,
-    pub cost: String,  // "O(1)", "O(log n)", "O(n)"
+/// One node of a query plan (v2.7.0)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    /// `Seq Scan on users`, `Hash Join`, `Sort`, ...
+    pub name: String,
+    /// Lines under the node: `Filter: ...`, `Index Cond: ...`, `Sort Key: ...`
+    pub details: Vec<String>,
+    /// Cost until the first row can be returned
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    pub rows: usize,
+    pub children: Vec<Self>,
 }
 
-#[derive(Debug)]
-pub enum ScanType {
-    SequentialScan,
-    IndexScan,
-    UniqueIndexScan,
+impl PlanNode {
+    fn new(name: impl Into<String>, startup_cost: f64, total_cost: f64, rows: usize) -> Self {
+        Self { name: name.into(), details: Vec::new(), startup_cost, total_cost, rows, children: Vec::new() }
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.details.push(detail.into());
+        self
+    }
+
+    fn child(mut self, child: Self) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Append the node and its children, indented like `psql` shows plans
+    fn render(&self, depth: usize, output: &mut String) {
+        let (prefix, detail_indent) = match depth {
+            0 => (String::new(), 2),
+            _ => (format!("{}->  ", " ".repeat(6 * depth - 4)), 6 * depth + 2),
+        };
+        output.push_str(&format!(
+            "{prefix}{}  (cost={:.2}..{:.2} rows={})\n",
+            self.name, self.startup_cost, self.total_cost, self.rows
+        ));
+        for detail in &self.details {
+            output.push_str(&format!("{}{detail}\n", " ".repeat(detail_indent)));
+        }
+        for child in &self.children {
+            child.render(depth + 1, output);
+        }
+    }
 }
 
+pub struct ExplainExecutor;
+
 impl ExplainExecutor {
     pub fn explain(
         db: &Database,
         statement: &Statement,
-        database_storage: &mut crate::storage::DatabaseStorage,
+        database_storage: &DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
+        let plan = Self::plan(db, statement, database_storage)?;
+
+        // v2.7.0: One `QUERY PLAN` row per line, as `PostgreSQL` returns plans
+        let mut output = String::new();
+        plan.render(0, &mut output);
+        let rows = output.lines().map(|line| vec![line.to_string()]).collect();
+        Ok(QueryResult::Rows(rows, vec!["QUERY PLAN".to_string()]))
+    }
+
+    /// Plan tree of a statement
+    pub fn plan(db: &Database, statement: &Statement, storage: &DatabaseStorage) -> Result<PlanNode, DatabaseError> {
         match statement {
-            Statement::Select {
-                from,
-                filter,
-                joins,
-                ..
-            } => {
-                let plan = Self::analyze_select(db, from, filter, database_storage)?;
-                let output = Self::format_plan(&plan, joins.is_empty());
-                Ok(QueryResult::Success(output))
+            Statement::Select { distinct, columns, from, joins, filter, group_by, order_by, limit, offset } => {
+                Self::plan_select(
+                    db,
+                    storage,
+                    *distinct,
+                    columns,
+                    from,
+                    joins,
+                    filter.as_ref(),
+                    group_by.as_deref(),
+                    order_by.as_ref(),
+                    *limit,
+                    *offset,
+                )
+            }
+            Statement::Union { left, right, all, order_by, limit, offset } => {
+                let (left, right) = (Self::plan(db, left, storage)?, Self::plan(db, right, storage)?);
+                let rows = left.rows + right.rows;
+                let append = PlanNode::new("Append", left.startup_cost, left.total_cost + right.total_cost, rows)
+                    .child(left)
+                    .child(right);
+                let node = if *all { append } else { Self::hash_aggregate(append, None) };
+                Ok(Self::sort_and_limit(node, order_by.as_ref(), *limit, *offset))
+            }
+            Statement::Intersect { left, right, order_by, limit, offset } => {
+                let node = Self::set_op("Intersect", Self::plan(db, left, storage)?, Self::plan(db, right, storage)?);
+                Ok(Self::sort_and_limit(node, order_by.as_ref(), *limit, *offset))
+            }
+            Statement::Except { left, right, order_by, limit, offset } => {
+                let node = Self::set_op("Except", Self::plan(db, left, storage)?, Self::plan(db, right, storage)?);
+                Ok(Self::sort_and_limit(node, order_by.as_ref(), *limit, *offset))
+            }
+            Statement::Insert { table, .. } => {
+                Self::check_table(db, table)?;
+                let values = PlanNode::new("Result", 0.0, CPU_TUPLE_COST, 1);
+                Ok(PlanNode::new(format!("Insert on {table}"), 0.0, values.total_cost + CPU_TUPLE_COST, 0).child(values))
+            }
+            Statement::InsertMany { table, source, .. } => {
+                Self::check_table(db, table)?;
+                let rows = match source {
+                    InsertSource::Values(rows) => {
+                        PlanNode::new("Values Scan on \"*VALUES*\"", 0.0, Self::tuples(rows.len()), rows.len())
+                    }
+                    InsertSource::Query(query) => Self::plan(db, query, storage)?,
+                };
+                let total = rows.total_cost + Self::tuples(rows.rows);
+                Ok(PlanNode::new(format!("Insert on {table}"), rows.startup_cost, total, 0).child(rows))
+            }
+            Statement::Update { table, from, filter, .. } => {
+                Self::plan_modify(db, storage, "Update", table, from, filter.as_ref())
+            }
+            Statement::Delete { from, using, filter } => {
+                Self::plan_modify(db, storage, "Delete", from, using, filter.as_ref())
             }
             _ => Err(DatabaseError::ParseError(
-                "EXPLAIN only supports SELECT statements".to_string(),
+                "EXPLAIN only supports SELECT, INSERT, UPDATE and DELETE statements".to_string(),
             )),
         }
     }
 
-    fn analyze_select(
+    #[allow(clippy::too_many_arguments)]
+    fn plan_select(
         db: &Database,
-        table_name: &str,
-        filter: &Option<Condition>,
-        database_storage: &mut crate::storage::DatabaseStorage,
-    ) -> Result<QueryPlan, DatabaseError> {
-        // Validate table exists
-        let _table = db
-            .get_table(table_name)
-            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
-
-        let paged_table = database_storage.get_paged_table(table_name)
-            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
-        let total_rows = paged_table.row_count();
-
-        // Check if an index can be used
-        let (scan_type, index_info, cost, estimated_rows) = if let Some(cond) = filter {
-            Self::find_index_for_condition(db, table_name, cond, total_rows)
-        } else {
-            // No filter = full table scan
-            (ScanType::SequentialScan, None, "O(n)".to_string(), total_rows)
-        };
+        storage: &DatabaseStorage,
+        distinct: bool,
+        columns: &[SelectColumn],
+        from: &str,
+        joins: &[JoinClause],
+        filter: Option<&Condition>,
+        group_by: Option<&[String]>,
+        order_by: Option<&(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PlanNode, DatabaseError> {
+        let has_aggregates = columns.iter().any(|col| match col {
+            SelectColumn::Aggregate(_) => true,
+            SelectColumn::Expression { expr, .. } => FunctionRegistry::aggregate_call(expr).is_some(),
+            _ => false,
+        });
 
-        let (index_name, index_type) = match index_info {
-            Some((name, itype)) => (Some(name), Some(itype)),
-            None => (None, None),
-        };
+        if SystemCatalog::is_system_catalog(from) {
+            return Ok(PlanNode::new(format!("Catalog Scan on {from}"), 0.0, Self::tuples(DEFAULT_ROWS), DEFAULT_ROWS));
+        }
+        if from.is_empty() {
+            return Ok(PlanNode::new("Result", 0.0, CPU_TUPLE_COST, 1));
+        }
 
-        Ok(QueryPlan {
-            scan_type,
-            table_name: table_name.to_string(),
-            index_name,
-            index_type,
-            filter: filter.as_ref().map(Self::format_condition),
-            estimated_rows,
-            cost,
-        })
+        // Joins: every relation is read in full and joined left to right; DISTINCT,
+        // aggregates and LIMIT apply to the joined rows
+        if !joins.is_empty() {
+            let mut node = Self::relation(db, storage, from)?;
+            for join in joins {
+                let right = Self::relation(db, storage, &join.table)?;
+                node = Self::join(join, node, right);
+            }
+            if has_aggregates {
+                return Ok(Self::aggregate(node));
+            }
+            if distinct {
+                node = Self::unique(node);
+            }
+            return Ok(Self::limit(node, limit, offset));
+        }
+
+        if db.views.contains_key(from) {
+            let view = ViewResolver::definition(db, from)?;
+            let outer = Statement::Select {
+                distinct,
+                columns: columns.to_vec(),
+                from: from.to_string(),
+                joins: Vec::new(),
+                filter: filter.cloned(),
+                group_by: group_by.map(<[String]>::to_vec),
+                order_by: order_by.cloned(),
+                limit,
+                offset,
+            };
+            // A simple view is merged into the query
+            if let Some(merged) = ViewResolver::inline(&view, &outer) {
+                return Self::plan(db, &merged, storage);
+            }
+            let scan = Self::subquery_scan(from, Self::plan(db, &view, storage)?, filter);
+            return Ok(Self::finish(scan, distinct, order_by, limit, offset));
+        }
+
+        if DerivedTables::is_derived(from) {
+            let table = DerivedTables::parse(from)?;
+            let scan = Self::subquery_scan(&table.alias, Self::plan(db, &table.query, storage)?, filter);
+            return Ok(Self::finish(scan, distinct, order_by, limit, offset));
+        }
+
+        if from.contains('(') {
+            let function = from.split('(').next().unwrap_or(from).trim();
+            let mut scan = PlanNode::new(format!("Function Scan on {function}"), 0.0, Self::tuples(DEFAULT_ROWS), DEFAULT_ROWS);
+            if let Some(cond) = filter {
+                scan = Self::filtered(scan, cond, None);
+            }
+            return Ok(Self::finish(scan, distinct, order_by, limit, offset));
+        }
+
+        if let Some(keys) = group_by {
+            // Groups are read in the order of an index on the keys, or hashed
+            let grouped = match Planner::group_index(db, from, keys) {
+                Some((name, _)) => Self::group_aggregate(Self::ordered_scan(db, storage, from, name, filter)?, keys),
+                None => Self::hash_aggregate(Self::seq_scan(db, storage, from, filter)?, Some(keys)),
+            };
+            return Ok(Self::finish(grouped, distinct, order_by, limit, offset));
+        }
+        let scan = Self::scan(db, storage, from, filter)?;
+        if has_aggregates {
+            return Ok(Self::aggregate(scan));
+        }
+
+        let mut node = scan;
+        let windowed = columns.iter().any(|col| matches!(col, SelectColumn::Window { .. }));
+        if windowed {
+            let rows = node.rows;
+            node = PlanNode::new("WindowAgg", node.total_cost, node.total_cost + Self::operators(rows), rows).child(node);
+        }
+
+        // An index search that returns the rows in ORDER BY order is read up to the LIMIT, unsorted
+        let ordered = limit.is_some()
+            && !distinct
+            && !windowed
+            && order_by.is_some_and(|(key, _)| {
+                let Expression::Column(column) = key else { return false };
+                Planner::choose_index(db, from, filter)
+                    .is_some_and(|access| Planner::ordered_path(db, from, &access, column).is_some())
+            });
+        if ordered {
+            return Ok(Self::limit(node, limit, offset));
+        }
+        Ok(Self::finish(node, distinct, order_by, limit, offset))
     }
 
-    fn find_index_for_condition(
+    /// UPDATE / DELETE: the target is scanned in full, joined with the FROM / USING tables
+    fn plan_modify(
         db: &Database,
-        table_name: &str,
-        condition: &Condition,
-        total_rows: usize,
-    ) -> (ScanType, Option<(String, String)>, String, usize) {
-        // v1.9.0: Try composite index first (AND chain of Equals)
-        let mut equals_cols: Vec<&str> = Vec::new();
-        Self::extract_equals_columns(condition, &mut equals_cols);
-
-        if equals_cols.len() >= 2 {
-            // Multiple equality conditions - look for matching composite index
-            for (idx_name, index) in &db.indexes {
-                if index.table_name() != table_name || !index.is_composite() {
-                    continue;
-                }
-
-                let index_cols = index.column_names();
-                if index_cols.len() == equals_cols.len() {
-                    // Check if all index columns match
-                    let all_match = index_cols.iter().all(|ic| equals_cols.contains(&ic.as_str()));
-                    if all_match {
-                        let index_type_str = match index.index_type() {
-                            crate::index::IndexType::Hash => "hash",
-                            crate::index::IndexType::BTree => "btree",
-                        };
-
-                        let scan_type = if index.is_unique() {
-                            ScanType::UniqueIndexScan
-                        } else {
-                            ScanType::IndexScan
-                        };
-
-                        let cost = if index_type_str == "hash" {
-                            "O(1)"
-                        } else {
-                            "O(log n)"
-                        };
-
-                        let estimated = if index.is_unique() { 1 } else { total_rows / 10 };
-
-                        return (
-                            scan_type,
-                            Some((idx_name.clone(), index_type_str.to_string())),
-                            cost.to_string(),
-                            estimated,
-                        );
-                    }
+        storage: &DatabaseStorage,
+        operation: &str,
+        table: &str,
+        others: &[String],
+        filter: Option<&Condition>,
+    ) -> Result<PlanNode, DatabaseError> {
+        let node = if others.is_empty() {
+            Self::seq_scan(db, storage, table, filter)?
+        } else {
+            let mut node = Self::seq_scan(db, storage, table, None)?;
+            for other in others {
+                let right = Self::seq_scan(db, storage, other, None)?;
+                node = Self::nested_loop("Nested Loop", node, right, None);
+            }
+            match filter {
+                Some(cond) => {
+                    let rows = Self::estimate(node.rows, Self::selectivity(cond));
+                    PlanNode { rows, ..node }.detail(format!("Join Filter: {}", Self::format_condition(cond)))
                 }
+                None => node,
             }
+        };
+        let total = node.total_cost + Self::tuples(node.rows);
+        Ok(PlanNode::new(format!("{operation} on {table}"), node.startup_cost, total, 0).child(node))
+    }
+
+    /// A FROM item of a join, read without a filter
+    fn relation(db: &Database, storage: &DatabaseStorage, name: &str) -> Result<PlanNode, DatabaseError> {
+        if db.views.contains_key(name) {
+            let view = ViewResolver::definition(db, name)?;
+            return Ok(Self::subquery_scan(name, Self::plan(db, &view, storage)?, None));
+        }
+        if DerivedTables::is_derived(name) {
+            let table = DerivedTables::parse(name)?;
+            return Ok(Self::subquery_scan(&table.alias, Self::plan(db, &table.query, storage)?, None));
         }
+        Self::seq_scan(db, storage, name, None)
+    }
 
-        // Fall back to single-column index
-        let (column, op) = match condition {
-            Condition::Equals(col, _) => (col, "="),
-            Condition::GreaterThan(col, _) => (col, ">"),
-            Condition::LessThan(col, _) => (col, "<"),
-            Condition::GreaterThanOrEqual(col, _) => (col, ">="),
-            Condition::LessThanOrEqual(col, _) => (col, "<="),
-            Condition::Like(col, _) => (col, "LIKE"),
-            Condition::In(col, _) => (col, "IN"),
-            _ => return (ScanType::SequentialScan, None, "O(n)".to_string(), total_rows),
+    /// Scan of a table: through the index the executor picks, or sequential
+    fn scan(
+        db: &Database,
+        storage: &DatabaseStorage,
+        table_name: &str,
+        filter: Option<&Condition>,
+    ) -> Result<PlanNode, DatabaseError> {
+        let Some(access) = Planner::choose_index(db, table_name, filter) else {
+            return Self::seq_scan(db, storage, table_name, filter);
         };
+        let filter = filter.expect("an index is only used for a WHERE clause");
+        let total_rows = Self::check_table_rows(db, storage, table_name)?;
+        let stats = db.statistics.table(table_name);
+        let stats = stats.as_deref();
 
-        // Find single-column index on this column
-        for (idx_name, index) in &db.indexes {
-            if index.table_name() == table_name && !index.is_composite() && index.column_name() == column {
-                let index_type_str = match index.index_type() {
-                    crate::index::IndexType::Hash => "hash",
-                    crate::index::IndexType::BTree => "btree",
-                };
+        // Conditions answered by the indexes: equalities on leading columns and
+        // a range on the next one, or a whole OR; the rest is checked on the rows
+        // they return
+        let index_conds = access.conditions();
+        let residual: Vec<&Condition> = Self::conjuncts(filter)
+            .into_iter()
+            .filter(|cond| !index_conds.iter().any(|used| std::ptr::eq(*used, *cond)))
+            .collect();
 
-                // Hash index only supports equality
-                if index_type_str == "hash" {
-                    if op == "=" || op == "IN" {
-                        let scan_type = if index.is_unique() {
-                            ScanType::UniqueIndexScan
-                        } else {
-                            ScanType::IndexScan
-                        };
-                        // O(1) for hash index
-                        return (
-                            scan_type,
-                            Some((idx_name.clone(), index_type_str.to_string())),
-                            "O(1)".to_string(),
-                            if index.is_unique() { 1 } else { total_rows / 10 }, // Estimate 10% selectivity
-                        );
-                    }
-                } else {
-                    // B-tree supports all comparison operators
-                    let scan_type = if index.is_unique() && op == "=" {
-                        ScanType::UniqueIndexScan
-                    } else {
-                        ScanType::IndexScan
-                    };
-
-                    let estimated = match op {
-                        "=" => if index.is_unique() { 1 } else { total_rows / 10 },
-                        ">" | "<" | ">=" | "<=" => total_rows / 3, // Estimate 33% selectivity for range
-                        "IN" => total_rows / 10, // Similar to equality
-                        _ => total_rows,
-                    };
-
-                    return (
-                        scan_type,
-                        Some((idx_name.clone(), index_type_str.to_string())),
-                        "O(log n)".to_string(),
-                        estimated,
+        let mut node = match &access {
+            IndexAccess::Scan(path) => {
+                let (method, startup, total, matched) = Self::index_search(path, total_rows, stats);
+                let scan_name = if path.is_unique() { "Unique Index Scan" } else { "Index Scan" };
+                let rows = residual.iter().fold(matched, |rows, cond| Self::estimate(rows, Self::table_selectivity(stats, cond)));
+                PlanNode::new(format!("{scan_name} using {} on {table_name} ({method})", path.name), startup, total, rows)
+                    .detail(format!("Index Cond: {}", Self::format_conditions(&path.conditions)))
+            }
+            IndexAccess::BitmapOr { condition, branches } => {
+                let mut bitmap = PlanNode::new("BitmapOr".to_string(), 0.0, 0.0, 0);
+                let mut matched = 0;
+                for path in branches {
+                    let (method, startup, total, rows) = Self::index_search(path, total_rows, stats);
+                    bitmap.total_cost += total;
+                    matched += rows;
+                    bitmap = bitmap.child(
+                        PlanNode::new(format!("Bitmap Index Scan on {} ({method})", path.name), startup, total, rows)
+                            .detail(format!("Index Cond: {}", Self::format_conditions(&path.conditions))),
                     );
                 }
+                bitmap.startup_cost = bitmap.total_cost;
+                let matched = matched.min(total_rows);
+                bitmap.rows = matched;
+
+                // Rows are fetched page by page in table order
+                let startup = bitmap.total_cost;
+                let total = startup + Self::float(matched.div_ceil(ROWS_PER_PAGE)) * SEQ_PAGE_COST + Self::tuples(matched);
+                let rows = residual.iter().fold(matched, |rows, cond| Self::estimate(rows, Self::table_selectivity(stats, cond)));
+                PlanNode::new(format!("Bitmap Heap Scan on {table_name}"), startup, total, rows)
+                    .detail(format!("Recheck Cond: {}", Self::format_condition(condition)))
+                    .child(bitmap)
             }
+        };
+        if !residual.is_empty() {
+            node = node.detail(format!("Filter: {}", Self::format_conditions(&residual)));
         }
+        Ok(node)
+    }
 
-        // No suitable index found
-        (ScanType::SequentialScan, None, "O(n)".to_string(), total_rows)
+    /// One index search: access method, costs and rows found
+    fn index_search(path: &IndexPath<'_>, total_rows: usize, stats: Option<&TableStatistics>) -> (&'static str, f64, f64, usize) {
+        let matched = if path.is_unique() {
+            total_rows.min(1)
+        } else {
+            Self::estimate(total_rows, path.conditions.iter().map(|cond| Self::table_selectivity(stats, cond)).product())
+        };
+        let (method, levels) = match path.index.index_type() {
+            crate::index::IndexType::Hash => ("hash", 1.0),
+            crate::index::IndexType::BTree => ("btree", Self::float(total_rows.max(1)).log(Self::float(ROWS_PER_PAGE)).ceil().max(1.0)),
+        };
+        let startup = RANDOM_PAGE_COST * levels;
+        let total = startup + Self::float(matched) * (RANDOM_PAGE_COST / Self::float(ROWS_PER_PAGE) + CPU_TUPLE_COST);
+        (method, startup, total, matched)
     }
 
-    fn format_plan(plan: &QueryPlan, no_joins: bool) -> String {
-        let mut output = String::new();
-        output.push_str("QUERY PLAN\n");
-        output.push_str("──────────────────────────────────────────────────\n");
-
-        match plan.scan_type {
-            ScanType::SequentialScan => {
-                output.push_str(&format!(
-                    "→ Seq Scan on {}\n",
-                    plan.table_name
-                ));
-                if let Some(ref filter) = plan.filter {
-                    output.push_str(&format!("  Filter: {filter}\n"));
-                }
-            }
-            ScanType::IndexScan | ScanType::UniqueIndexScan => {
-                let scan_name = if matches!(plan.scan_type, ScanType::UniqueIndexScan) {
-                    "Unique Index Scan"
-                } else {
-                    "Index Scan"
-                };
+    /// Every row of the table in the order of a B-tree index
+    fn ordered_scan(
+        db: &Database,
+        storage: &DatabaseStorage,
+        table_name: &str,
+        index_name: &str,
+        filter: Option<&Condition>,
+    ) -> Result<PlanNode, DatabaseError> {
+        let total_rows = Self::check_table_rows(db, storage, table_name)?;
+        let levels = Self::float(total_rows.max(1)).log(Self::float(ROWS_PER_PAGE)).ceil().max(1.0);
+        let startup = RANDOM_PAGE_COST * levels;
+        let total = Self::float(total_rows).mul_add(RANDOM_PAGE_COST / Self::float(ROWS_PER_PAGE) + CPU_TUPLE_COST, startup);
+        let node = PlanNode::new(format!("Index Scan using {index_name} on {table_name} (btree)"), startup, total, total_rows);
+        Ok(match filter {
+            Some(cond) => Self::filtered(node, cond, db.statistics.table(table_name).as_deref()),
+            None => node,
+        })
+    }
 
-                output.push_str(&format!(
-                    "→ {} using {} ({})\n",
-                    scan_name,
-                    plan.index_name.as_ref().unwrap(),
-                    plan.index_type.as_ref().unwrap()
-                ));
-                output.push_str(&format!("  on {}\n", plan.table_name));
-                if let Some(ref filter) = plan.filter {
-                    output.push_str(&format!("  Index Cond: {filter}\n"));
-                }
-            }
+    /// Sequential scan, of the partitions left after pruning for a partitioned table
+    fn seq_scan(
+        db: &Database,
+        storage: &DatabaseStorage,
+        table_name: &str,
+        filter: Option<&Condition>,
+    ) -> Result<PlanNode, DatabaseError> {
+        let table = Self::check_table(db, table_name)?;
+        let scanned = Partitions::leaf_tables(db, table_name, filter);
+        let mut total_rows = 0;
+        for name in &scanned {
+            total_rows += storage.row_count(name).ok_or_else(|| DatabaseError::TableNotFound(name.clone()))?;
+        }
+
+        let pages = total_rows.div_ceil(ROWS_PER_PAGE);
+        let total = Self::float(pages) * SEQ_PAGE_COST + Self::tuples(total_rows);
+        let mut node = PlanNode::new(format!("Seq Scan on {table_name}"), 0.0, total, total_rows);
+        if let Some(cond) = filter {
+            node = Self::filtered(node, cond, db.statistics.table(table_name).as_deref());
+        }
+        if table.partitioning.is_some() {
+            let partitions = Partitions::leaf_tables(db, table_name, None).len();
+            node = node.detail(format!(
+                "Partitions: {} of {partitions} ({})",
+                scanned.len(),
+                if scanned.is_empty() { "none".to_string() } else { scanned.join(", ") }
+            ));
+        }
+        if table.access_method != crate::types::AccessMethod::Heap {
+            node = node.detail(format!("Storage: {}", table.access_method.name()));
+        }
+        Ok(node)
+    }
+
+    fn subquery_scan(alias: &str, query: PlanNode, filter: Option<&Condition>) -> PlanNode {
+        let rows = query.rows;
+        let scan = PlanNode::new(format!("Subquery Scan on {alias}"), query.startup_cost, query.total_cost + Self::tuples(rows), rows)
+            .child(query);
+        match filter {
+            Some(cond) => Self::filtered(scan, cond, None),
+            None => scan,
+        }
+    }
+
+    /// `cond` checked for every row the node reads
+    fn filtered(node: PlanNode, cond: &Condition, stats: Option<&TableStatistics>) -> PlanNode {
+        let checks = Self::float(node.rows) * CPU_OPERATOR_COST * Self::float(Self::conjuncts(cond).len());
+        PlanNode {
+            total_cost: node.total_cost + checks,
+            rows: Self::estimate(node.rows, Self::table_selectivity(stats, cond)),
+            ..node
+        }
+        .detail(format!("Filter: {}", Self::format_condition(cond)))
+    }
+
+    fn join(join: &JoinClause, left: PlanNode, right: PlanNode) -> PlanNode {
+        let kind = match join.join_type {
+            JoinType::Inner => "",
+            JoinType::Left => " Left",
+            JoinType::Right => " Right",
+        };
+        if !QueriesExecutor::uses_hash_join(join) {
+            let name = if kind.is_empty() { "Nested Loop".to_string() } else { format!("Nested Loop{kind} Join") };
+            let cond = (!join.on_left.is_empty()).then(|| format!("({} = {})", join.on_left, join.on_right));
+            return Self::nested_loop(&name, left, right, cond);
         }
 
-        output.push_str(&format!("  Rows: ~{}\n", plan.estimated_rows));
-        output.push_str(&format!("  Cost: {}\n", plan.cost));
+        let hash = PlanNode::new("Hash", right.total_cost, right.total_cost, right.rows).child(right);
+        // An equi-join is assumed to match every row of the larger side once
+        let rows = left.rows.max(hash.rows);
+        let startup = hash.total_cost + Self::operators(hash.rows) + Self::tuples(hash.rows);
+        let total = startup + left.total_cost + Self::operators(left.rows) + Self::tuples(rows);
+        PlanNode::new(format!("Hash{kind} Join"), startup, total, rows)
+            .detail(format!("Hash Cond: ({} = {})", join.on_left, join.on_right))
+            .child(left)
+            .child(hash)
+    }
+
+    /// Every left row is compared with every right row; `cond` is the join key
+    /// of a LATERAL join, whose right side runs once per left row
+    fn nested_loop(name: &str, left: PlanNode, right: PlanNode, cond: Option<String>) -> PlanNode {
+        let pairs = left.rows.saturating_mul(right.rows.max(1));
+        let rows = if cond.is_some() { left.rows.max(right.rows) } else { pairs };
+        let total = left.total_cost
+            + Self::float(left.rows.max(1)) * right.total_cost
+            + Self::operators(pairs)
+            + Self::tuples(rows);
+        let node = PlanNode::new(name, left.startup_cost + right.startup_cost, total, rows);
+        let node = match cond {
+            Some(cond) => node.detail(format!("Join Filter: {cond}")),
+            None => node,
+        };
+        node.child(left).child(right)
+    }
+
+    fn aggregate(input: PlanNode) -> PlanNode {
+        let cost = input.total_cost + Self::operators(input.rows);
+        PlanNode::new("Aggregate", cost, cost + CPU_TUPLE_COST, 1).child(input)
+    }
+
+    /// GROUP BY `keys`, or duplicate removal of whole rows without keys
+    fn hash_aggregate(input: PlanNode, keys: Option<&[String]>) -> PlanNode {
+        let cost = input.total_cost + Self::operators(input.rows);
+        let groups = match keys {
+            Some(_) => Self::groups(input.rows),
+            None => input.rows,
+        };
+        let node = PlanNode::new("HashAggregate", cost, cost + Self::tuples(groups), groups);
+        let node = match keys {
+            Some(keys) => node.detail(format!("Group Key: {}", keys.join(", "))),
+            None => node,
+        };
+        node.child(input)
+    }
+
+    /// GROUP BY `keys` over rows sorted on them: each group is done when the next starts
+    fn group_aggregate(input: PlanNode, keys: &[String]) -> PlanNode {
+        let groups = Self::groups(input.rows);
+        let total = input.total_cost + Self::operators(input.rows) + Self::tuples(groups);
+        PlanNode::new("GroupAggregate", input.startup_cost, total, groups)
+            .detail(format!("Group Key: {}", keys.join(", ")))
+            .child(input)
+    }
+
+    /// Groups are assumed to hold 10 rows each
+    fn groups(rows: usize) -> usize {
+        (rows / 10).max(1).min(rows)
+    }
+
+    fn set_op(operation: &str, left: PlanNode, right: PlanNode) -> PlanNode {
+        let rows = left.rows;
+        let cost = left.total_cost + right.total_cost + Self::operators(left.rows + right.rows);
+        PlanNode::new(format!("HashSetOp {operation}"), cost, cost + Self::tuples(rows), rows)
+            .child(left)
+            .child(right)
+    }
+
+    fn unique(input: PlanNode) -> PlanNode {
+        let rows = input.rows;
+        PlanNode::new("Unique", input.startup_cost, input.total_cost + Self::operators(rows), rows).child(input)
+    }
+
+    /// ORDER BY, then DISTINCT, then OFFSET / LIMIT - the executor's order
+    fn finish(
+        node: PlanNode,
+        distinct: bool,
+        order_by: Option<&(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> PlanNode {
+        let node = Self::sort(node, order_by);
+        let node = if distinct { Self::unique(node) } else { node };
+        Self::limit(node, limit, offset)
+    }
+
+    fn sort_and_limit(
+        node: PlanNode,
+        order_by: Option<&(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> PlanNode {
+        Self::limit(Self::sort(node, order_by), limit, offset)
+    }
+
+    fn sort(input: PlanNode, order_by: Option<&(Expression, SortOrder)>) -> PlanNode {
+        let Some((key, order)) = order_by else {
+            return input;
+        };
+        let n = Self::float(input.rows);
+        let comparisons = if input.rows > 1 { 2.0 * n * n.log2() * CPU_OPERATOR_COST } else { 0.0 };
+        let startup = input.total_cost + comparisons;
+        let direction = match order {
+            SortOrder::Asc => "",
+            SortOrder::Desc => " DESC",
+        };
+        PlanNode::new("Sort", startup, startup + Self::tuples(input.rows), input.rows)
+            .detail(format!("Sort Key: {}{direction}", Self::format_expression(key)))
+            .child(input)
+    }
 
-        if !no_joins {
-            output.push_str("\n  (Note: JOIN analysis not yet implemented)\n");
+    /// OFFSET / LIMIT: only the rows up to the last one returned are read
+    fn limit(input: PlanNode, limit: Option<usize>, offset: Option<usize>) -> PlanNode {
+        if limit.is_none() && offset.is_none() {
+            return input;
         }
+        let skipped = offset.unwrap_or(0).min(input.rows);
+        let rows = limit.map_or(input.rows - skipped, |limit| limit.min(input.rows - skipped));
+        let read = if input.rows == 0 { 1.0 } else { Self::float(skipped + rows) / Self::float(input.rows) };
+        let total = (input.total_cost - input.startup_cost).mul_add(read, input.startup_cost);
+        PlanNode::new("Limit", input.startup_cost, total, rows).child(input)
+    }
 
-        output.push_str("──────────────────────────────────────────────────");
-        output
+    fn check_table<'a>(db: &'a Database, name: &str) -> Result<&'a crate::types::Table, DatabaseError> {
+        db.get_table(name).ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))
     }
 
-    /// Extract column names from Equals conditions in AND chain (v1.9.0)
-    fn extract_equals_columns<'a>(cond: &'a Condition, result: &mut Vec<&'a str>) {
+    fn check_table_rows(db: &Database, storage: &DatabaseStorage, name: &str) -> Result<usize, DatabaseError> {
+        Self::check_table(db, name)?;
+        storage.row_count(name).ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))
+    }
+
+    /// Share of rows expected to satisfy `cond`
+    fn selectivity(cond: &Condition) -> f64 {
+        Self::table_selectivity(None, cond)
+    }
+
+    /// Share of rows of a table expected to satisfy `cond`, from its
+    /// statistics where they cover a condition
+    fn table_selectivity(stats: Option<&TableStatistics>, cond: &Condition) -> f64 {
+        if let Some(share) = stats.and_then(|stats| stats.selectivity(cond)) {
+            return share;
+        }
         match cond {
-            Condition::Equals(col, _) => {
-                result.push(col.as_str());
+            Condition::Equals(..) | Condition::ColumnEquals(..) | Condition::EqualsSubquery(..) => 0.1,
+            Condition::In(_, values) => (0.1 * Self::float(values.len())).min(1.0),
+            Condition::GreaterThan(..)
+            | Condition::LessThan(..)
+            | Condition::GreaterThanOrEqual(..)
+            | Condition::LessThanOrEqual(..)
+            | Condition::GreaterThanSubquery(..)
+            | Condition::LessThanSubquery(..) => 1.0 / 3.0,
+            Condition::Between(..) | Condition::Like(..) | Condition::ILike(..) => 0.25,
+            Condition::IsNull(_) => 0.05,
+            Condition::IsNotNull(_) | Condition::NotEquals(..) => 0.95,
+            Condition::And(left, right) => Self::table_selectivity(stats, left) * Self::table_selectivity(stats, right),
+            Condition::Or(left, right) => {
+                let (left, right) = (Self::table_selectivity(stats, left), Self::table_selectivity(stats, right));
+                left.mul_add(-right, left + right)
             }
+            Condition::Not(inner) => 1.0 - Self::table_selectivity(stats, inner),
+            _ => 0.5,
+        }
+    }
+
+    /// The AND-ed parts of a condition
+    fn conjuncts(cond: &Condition) -> Vec<&Condition> {
+        match cond {
             Condition::And(left, right) => {
-                Self::extract_equals_columns(left, result);
-                Self::extract_equals_columns(right, result);
+                let mut parts = Self::conjuncts(left);
+                parts.extend(Self::conjuncts(right));
+                parts
             }
-            _ => {}
+            other => vec![other],
         }
     }
 
+    #[allow(clippy::cast_precision_loss)]
+    const fn float(n: usize) -> f64 {
+        n as f64
+    }
+
+    /// `rows * selectivity`, at least one row of a non-empty input
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn estimate(rows: usize, selectivity: f64) -> usize {
+        if rows == 0 {
+            return 0;
+        }
+        ((Self::float(rows) * selectivity).round() as usize).clamp(1, rows)
+    }
+
+    fn tuples(rows: usize) -> f64 {
+        Self::float(rows) * CPU_TUPLE_COST
+    }
+
+    fn operators(rows: usize) -> f64 {
+        Self::float(rows) * CPU_OPERATOR_COST
+    }
+
+    fn format_conditions(conds: &[&Condition]) -> String {
+        conds.iter().map(|cond| Self::format_condition(cond)).collect::<Vec<_>>().join(" AND ")
+    }
+
     fn format_condition(cond: &Condition) -> String {
+        let literal = Self::format_literal;
         match cond {
-            Condition::Equals(col, val) => format!("{col} = {val:?}"),
-            Condition::NotEquals(col, val) => format!("{col} != {val:?}"),
-            Condition::GreaterThan(col, val) => format!("{col} > {val:?}"),
-            Condition::LessThan(col, val) => format!("{col} < {val:?}"),
-            Condition::GreaterThanOrEqual(col, val) => format!("{col} >= {val:?}"),
-            Condition::LessThanOrEqual(col, val) => format!("{col} <= {val:?}"),
+            Condition::Equals(col, val) => format!("{col} = {}", literal(val)),
+            Condition::NotEquals(col, val) => format!("{col} != {}", literal(val)),
+            Condition::ColumnEquals(left, right) => format!("{left} = {right}"),
+            Condition::Compare(left, op, right) => {
+                let op = match op {
+                    CompareOperator::Equal => "=",
+                    CompareOperator::NotEqual => "!=",
+                    CompareOperator::Less => "<",
+                    CompareOperator::LessOrEqual => "<=",
+                    CompareOperator::Greater => ">",
+                    CompareOperator::GreaterOrEqual => ">=",
+                };
+                format!("{} {op} {}", Self::format_expression(left), Self::format_expression(right))
+            }
+            Condition::GreaterThan(col, val) => format!("{col} > {}", literal(val)),
+            Condition::LessThan(col, val) => format!("{col} < {}", literal(val)),
+            Condition::GreaterThanOrEqual(col, val) => format!("{col} >= {}", literal(val)),
+            Condition::LessThanOrEqual(col, val) => format!("{col} <= {}", literal(val)),
             Condition::Between(col, low, high) => {
-                format!("{col} BETWEEN {low:?} AND {high:?}")
+                format!("{col} BETWEEN {} AND {}", literal(low), literal(high))
             }
             Condition::Like(col, pattern) => format!("{col} LIKE '{pattern}'"),
-            Condition::In(col, values) => format!("{col} IN ({values:?})"),
+            Condition::ILike(col, pattern) => format!("{col} ILIKE '{pattern}'"),
+            Condition::In(col, values) => {
+                format!("{col} IN ({})", values.iter().map(literal).collect::<Vec<_>>().join(", "))
+            }
             Condition::IsNull(col) => format!("{col} IS NULL"),
             Condition::IsNotNull(col) => format!("{col} IS NOT NULL"),
             Condition::And(left, right) => {
@@ -296,6 +743,7 @@ impl ExplainExecutor {
             Condition::Or(left, right) => {
                 format!("({}) OR ({})", Self::format_condition(left), Self::format_condition(right))
             }
+            Condition::Not(inner) => format!("NOT ({})", Self::format_condition(inner)),
             // v2.6.0: Subquery conditions
             Condition::InSubquery(col, _) => format!("{col} IN (subquery)"),
             Condition::NotInSubquery(col, _) => format!("{col} NOT IN (subquery)"),
@@ -306,4 +754,153 @@ impl ExplainExecutor {
             Condition::LessThanSubquery(col, _) => format!("{col} < (subquery)"),
         }
     }
+
+    fn format_expression(expr: &Expression) -> String {
+        match expr {
+            Expression::Literal(value) => Self::format_literal(value),
+            Expression::Column(name) => name.clone(),
+            Expression::Binary { left, op, right } => {
+                let op = match op {
+                    BinaryOperator::Add => "+",
+                    BinaryOperator::Subtract => "-",
+                    BinaryOperator::Multiply => "*",
+                    BinaryOperator::Divide => "/",
+                    BinaryOperator::Modulo => "%",
+                    BinaryOperator::Concat => "||",
+                };
+                format!("({} {op} {})", Self::format_expression(left), Self::format_expression(right))
+            }
+            Expression::Function { name, args } => {
+                format!("{name}({})", args.iter().map(Self::format_expression).collect::<Vec<_>>().join(", "))
+            }
+            Expression::Case(_) => "CASE ... END".to_string(),
+        }
+    }
+
+    /// A value as it would be written in SQL
+    fn format_literal(value: &Value) -> String {
+        match value {
+            Value::Null => "NULL".to_string(),
+            Value::SmallInt(_) | Value::Integer(_) | Value::Real(_) | Value::Numeric(_) | Value::Boolean(_) => {
+                value.to_string()
+            }
+            other => format!("'{}'", other.to_string().replace('\'', "''")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryResult};
+    use crate::transaction::GlobalTransactionManager;
+
+    #[test]
+    fn test_render_plan_tree() {
+        let scan = PlanNode::new("Seq Scan on t", 0.0, 1.1, 10).detail("Filter: a > 1");
+        let plan = PlanNode::new("Limit", 0.0, 0.5, 5).child(PlanNode::new("Sort", 1.2, 1.3, 10).detail("Sort Key: a").child(scan));
+        let mut output = String::new();
+        plan.render(0, &mut output);
+        assert_eq!(
+            output,
+            "Limit  (cost=0.00..0.50 rows=5)\n\
+             \x20 ->  Sort  (cost=1.20..1.30 rows=10)\n\
+             \x20       Sort Key: a\n\
+             \x20       ->  Seq Scan on t  (cost=0.00..1.10 rows=10)\n\
+             \x20             Filter: a > 1\n"
+        );
+    }
+
+    #[test]
+    fn test_estimates() {
+        assert_eq!(ExplainExecutor::estimate(0, 0.5), 0);
+        assert_eq!(ExplainExecutor::estimate(1000, 0.1), 100);
+        assert_eq!(ExplainExecutor::estimate(3, 0.01), 1);
+
+        let cond = Condition::And(
+            Box::new(Condition::Equals("a".to_string(), Value::Integer(1))),
+            Box::new(Condition::GreaterThan("b".to_string(), Value::Text("x".to_string()))),
+        );
+        assert_eq!(ExplainExecutor::conjuncts(&cond).len(), 2);
+        assert_eq!(ExplainExecutor::format_condition(&cond), "(a = 1) AND (b > 'x')");
+        assert!((ExplainExecutor::selectivity(&cond) - 0.1 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_explain_plan_tree() {
+        // v2.7.0: Plan trees with access paths, join strategies and costs
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        let explain = |db: &mut Database, storage: &mut crate::storage::DatabaseStorage, sql: &str| {
+            match run_sql(db, storage, &tx_manager, &format!("EXPLAIN {sql}")).unwrap() {
+                QueryResult::Rows(rows, columns) => {
+                    assert_eq!(columns, vec!["QUERY PLAN".to_string()]);
+                    rows.into_iter().map(|row| row[0].trim().to_string()).collect::<Vec<_>>()
+                }
+                QueryResult::Success(_) => panic!("Expected plan rows"),
+            }
+        };
+        let position = |plan: &[String], prefix: &str| {
+            plan.iter()
+                .position(|line| line.trim_start_matches("->  ").starts_with(prefix))
+                .unwrap_or_else(|| panic!("no '{prefix}' in {plan:#?}"))
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER, name TEXT, city TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE orders (id INTEGER, user_id INTEGER, total INTEGER)").unwrap();
+        for i in 1..=20 {
+            run_sql(&mut db, &mut storage, &tx_manager, &format!("INSERT INTO users VALUES ({i}, 'user{i}', 'city{}')", i % 3)).unwrap();
+            run_sql(&mut db, &mut storage, &tx_manager, &format!("INSERT INTO orders VALUES ({i}, {}, {})", i % 5 + 1, i * 10)).unwrap();
+        }
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE UNIQUE INDEX idx_users_id ON users (id)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_orders ON orders (user_id, total)").unwrap();
+
+        let plan = explain(&mut db, &mut storage, "SELECT * FROM users");
+        assert!(plan[0].starts_with("Seq Scan on users  (cost=0.00..1.20 rows=20)"), "{plan:#?}");
+
+        let plan = explain(&mut db, &mut storage, "SELECT name FROM users WHERE id = 7");
+        assert!(plan[0].starts_with("Unique Index Scan using idx_users_id on users (btree)"), "{plan:#?}");
+        assert!(plan[0].ends_with("rows=1)"), "{plan:#?}");
+        assert_eq!(plan[1], "Index Cond: id = 7");
+
+        // The composite index answers the equalities, the rest is a filter
+        let plan = explain(&mut db, &mut storage, "SELECT * FROM orders WHERE user_id = 2 AND total = 40 AND id > 1");
+        assert!(plan[0].starts_with("Index Scan using idx_orders on orders (btree)"), "{plan:#?}");
+        assert_eq!(plan[1], "Index Cond: user_id = 2 AND total = 40");
+        assert_eq!(plan[2], "Filter: id > 1");
+
+        let plan = explain(&mut db, &mut storage, "SELECT city, COUNT(*) FROM users WHERE name LIKE 'u%' GROUP BY city ORDER BY city DESC LIMIT 2");
+        let order: Vec<usize> = ["Limit", "Sort", "Sort Key: city DESC", "HashAggregate", "Group Key: city", "Seq Scan on users", "Filter: name LIKE 'u%'"]
+            .iter()
+            .map(|prefix| position(&plan, prefix))
+            .collect();
+        assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{plan:#?}");
+
+        let plan = explain(&mut db, &mut storage, "SELECT * FROM users JOIN orders ON users.id = orders.user_id");
+        assert!(plan[0].starts_with("Hash Join"), "{plan:#?}");
+        assert_eq!(plan[1], "Hash Cond: (users.id = orders.user_id)");
+        assert!(position(&plan, "Seq Scan on users") < position(&plan, "Hash  (cost="));
+        assert!(position(&plan, "Hash  (cost=") < position(&plan, "Seq Scan on orders"));
+
+        let plan = explain(&mut db, &mut storage, "SELECT * FROM users CROSS JOIN orders");
+        assert!(plan[0].starts_with("Nested Loop  (cost="), "{plan:#?}");
+        assert!(plan[0].ends_with("rows=400)"), "{plan:#?}");
+
+        let plan = explain(&mut db, &mut storage, "SELECT id FROM users UNION SELECT user_id FROM orders ORDER BY id");
+        let order: Vec<usize> = ["Sort", "HashAggregate", "Append"].iter().map(|prefix| position(&plan, prefix)).collect();
+        assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{plan:#?}");
+
+        // DML is planned, not run
+        let plan = explain(&mut db, &mut storage, "DELETE FROM orders WHERE total > 100");
+        assert!(plan[0].starts_with("Delete on orders"), "{plan:#?}");
+        assert!(plan[1].starts_with("->  Seq Scan on orders"), "{plan:#?}");
+        assert_eq!(plan[2], "Filter: total > 100");
+        match run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM orders").unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(rows, vec![vec!["20".to_string()]]),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+        assert!(crate::parser::parse_statement("EXPLAIN DROP TABLE users").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "EXPLAIN SELECT * FROM missing").is_err());
+    }
 }
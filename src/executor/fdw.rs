@@ -0,0 +1,645 @@
+/// Foreign data wrappers (v2.7.0)
+///
+/// `CREATE FOREIGN TABLE t (...) SERVER csv OPTIONS (path '...')` declares a
+/// table whose rows live in an external file. The table gets a `ForeignScan`
+/// storage backend that asks the wrapper named by SERVER to read the file on
+/// every scan, so queries - joins with native tables included - always see the
+/// current file and nothing is imported. Foreign tables are read-only.
+///
+/// Built-in wrappers:
+/// - `csv`: options `path`, `header` (default false), `delimiter` (default `,`)
+///   and `null` (default: empty unquoted field); columns are matched by position
+/// - `parquet` (cargo feature `parquet`): option `path`; columns are matched by name
+use crate::parser::ColumnDef;
+use crate::storage::{DatabaseStorage, StorageBackend};
+use crate::types::{AccessMethod, Column, Database, DataType, DatabaseError, ForeignSource, Row, Table, Value};
+use super::ddl::DdlExecutor;
+use super::dispatcher_executor::QueryResult;
+
+/// Reads the rows of foreign tables from external data
+pub trait ForeignDataWrapper: Send + Sync + std::fmt::Debug {
+    /// SERVER name the wrapper is registered under
+    fn name(&self) -> &'static str;
+
+    /// Check the OPTIONS of a new foreign table
+    fn validate(&self, source: &ForeignSource) -> Result<(), DatabaseError>;
+
+    /// Read every row, converted to the types of `columns`
+    fn scan(&self, columns: &[Column], source: &ForeignSource) -> Result<Vec<Row>, DatabaseError>;
+}
+
+/// Wrapper registered under a SERVER name
+pub fn wrapper(server: &str) -> Result<&'static dyn ForeignDataWrapper, DatabaseError> {
+    match server.to_lowercase().as_str() {
+        "csv" => Ok(&CsvWrapper),
+        #[cfg(feature = "parquet")]
+        "parquet" => Ok(&ParquetWrapper),
+        _ => Err(DatabaseError::ParseError(format!("server \"{server}\" does not exist"))),
+    }
+}
+
+/// Reject options outside `allowed` and require `path`
+fn check_options(source: &ForeignSource, allowed: &[&str]) -> Result<(), DatabaseError> {
+    if let Some((key, _)) = source.options.iter().find(|(key, _)| !allowed.contains(&key.as_str())) {
+        return Err(DatabaseError::ParseError(format!(
+            "invalid option \"{key}\" for server \"{}\" (valid options: {})",
+            source.server,
+            allowed.join(", ")
+        )));
+    }
+    if source.option("path").is_none() {
+        return Err(DatabaseError::ParseError(format!(
+            "server \"{}\" requires the \"path\" option",
+            source.server
+        )));
+    }
+    Ok(())
+}
+
+fn read_error(path: &str, e: impl std::fmt::Display) -> DatabaseError {
+    DatabaseError::Io(std::io::Error::other(format!("could not read file \"{path}\": {e}")))
+}
+
+/// Storage backend of a foreign table: every scan reads the external data again
+#[derive(Debug)]
+pub struct ForeignScan {
+    wrapper: &'static dyn ForeignDataWrapper,
+    columns: Vec<Column>,
+    source: ForeignSource,
+}
+
+impl ForeignScan {
+    #[must_use]
+    pub fn new(wrapper: &'static dyn ForeignDataWrapper, columns: Vec<Column>, source: ForeignSource) -> Self {
+        Self { wrapper, columns, source }
+    }
+}
+
+/// Writes never reach the backend: `ForeignTables::check_writable` rejects them first
+impl StorageBackend for ForeignScan {
+    fn access_method(&self) -> AccessMethod {
+        AccessMethod::Foreign
+    }
+
+    fn insert(&mut self, _row: Row) {}
+
+    /// Not known without reading the file
+    fn row_count(&self) -> usize {
+        0
+    }
+
+    fn chunk_count(&self) -> usize {
+        1
+    }
+
+    /// Files are read whole, so every column is returned
+    fn chunk_rows(&self, idx: usize, _columns: Option<&[usize]>) -> Result<Vec<Row>, DatabaseError> {
+        if idx == 0 {
+            self.wrapper.scan(&self.columns, &self.source)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn delete_where(&mut self, _predicate: &dyn Fn(&Row) -> bool, _tx_id: u64) -> usize {
+        0
+    }
+
+    fn update_where(&mut self, _predicate: &dyn Fn(&Row) -> bool, _updater: &dyn Fn(&Row) -> Row, _tx_id: u64) -> usize {
+        0
+    }
+
+    fn truncate(&mut self) {}
+
+    fn vacuum(&mut self, _oldest_tx: u64) -> usize {
+        0
+    }
+}
+
+pub struct ForeignTables;
+
+impl ForeignTables {
+    /// Execute CREATE FOREIGN TABLE
+    pub fn create(
+        db: &mut Database,
+        name: String,
+        column_defs: Vec<ColumnDef>,
+        server: &str,
+        options: Vec<(String, String)>,
+        owner: Option<String>,
+        database_storage: &mut DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        if db.tables.contains_key(&name) {
+            return Err(DatabaseError::TableAlreadyExists(name));
+        }
+
+        let wrapper = wrapper(server)?;
+        let source = ForeignSource { server: wrapper.name().to_string(), options };
+        wrapper.validate(&source)?;
+
+        let (columns, generated) = DdlExecutor::build_columns(db, column_defs)?;
+        if !generated.is_empty() {
+            return Err(DatabaseError::ParseError(
+                "A foreign table cannot have generated columns".to_string(),
+            ));
+        }
+
+        let backend = ForeignScan::new(wrapper, columns.clone(), source.clone());
+        database_storage.create_table_with_backend(name.clone(), Box::new(backend))?;
+
+        let mut table = Table::new_with_owner(name, columns, owner.unwrap_or_else(|| "postgres".to_string()));
+        table.access_method = AccessMethod::Foreign;
+        table.foreign = Some(source);
+        db.create_table(table)?;
+
+        Ok(QueryResult::Success("CREATE FOREIGN TABLE".to_string()))
+    }
+
+    /// Foreign tables are read-only: refuse INSERT, UPDATE, DELETE, ALTER and indexes
+    pub fn check_writable(db: &Database, table: &str) -> Result<(), DatabaseError> {
+        if db.get_table(table).is_some_and(|t| t.foreign.is_some()) {
+            return Err(DatabaseError::ParseError(format!("Foreign table '{table}' is read-only")));
+        }
+        Ok(())
+    }
+}
+
+/// Parse the text of a field as a value of the column's type
+fn parse_field(text: &str, column: &Column) -> Result<Value, DatabaseError> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+    let invalid = || {
+        DatabaseError::ParseError(format!(
+            "invalid input for column '{}' ({:?}): \"{text}\"",
+            column.name, column.data_type
+        ))
+    };
+    let trimmed = text.trim();
+    let parse_timestamp = |s: &str| {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f"))
+            .ok()
+    };
+
+    let value = match &column.data_type {
+        DataType::SmallInt => trimmed.parse().ok().map(Value::SmallInt),
+        DataType::Integer | DataType::BigInt | DataType::Serial | DataType::BigSerial => trimmed.parse().ok().map(Value::Integer),
+        DataType::Real => trimmed.parse().ok().map(Value::Real),
+        DataType::Numeric { .. } => trimmed.parse().ok().map(Value::Numeric),
+        DataType::Text => Some(Value::Text(text.to_string())),
+        DataType::Varchar { max_length } => (text.len() <= *max_length).then(|| Value::Text(text.to_string())),
+        DataType::Char { length } => (text.len() <= *length).then(|| Value::Char(format!("{text:<length$}"))),
+        DataType::Boolean => match trimmed.to_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "1" => Some(Value::Boolean(true)),
+            "f" | "false" | "n" | "no" | "0" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        DataType::Date => NaiveDate::parse_from_str(trimmed, "%Y-%m-%d").ok().map(Value::Date),
+        DataType::Timestamp => parse_timestamp(trimmed).map(Value::Timestamp),
+        DataType::TimestampTz => DateTime::parse_from_rfc3339(trimmed)
+            .or_else(|_| DateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%.f%#z"))
+            .map(|t| t.with_timezone(&Utc))
+            .ok()
+            .or_else(|| parse_timestamp(trimmed).map(|t| t.and_utc()))
+            .map(Value::TimestampTz),
+        DataType::Uuid => uuid::Uuid::parse_str(trimmed).ok().map(Value::Uuid),
+        DataType::Json | DataType::Jsonb => Some(Value::Json(text.to_string())),
+        DataType::Bytea => trimmed
+            .strip_prefix("\\x")
+            .and_then(|digits| hex::decode(digits).ok())
+            .map(Value::Bytea),
+        DataType::Enum { name, values } => values
+            .iter()
+            .any(|v| v == trimmed)
+            .then(|| Value::Enum(name.clone(), trimmed.to_string())),
+    };
+    value.ok_or_else(invalid)
+}
+
+/// `SERVER csv`: RFC 4180 files (quoted fields may contain delimiters, quotes and newlines)
+#[derive(Debug)]
+pub struct CsvWrapper;
+
+/// One parsed CSV field; quoted fields are never NULL
+struct CsvField {
+    text: String,
+    quoted: bool,
+}
+
+/// Split CSV text into records, each with the line it starts on
+fn parse_csv(input: &str, delimiter: char) -> Result<Vec<(usize, Vec<CsvField>)>, DatabaseError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = CsvField { text: String::new(), quoted: false };
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut in_quotes = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.text.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.text.push(c);
+                }
+                _ => field.text.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.text.is_empty() && !field.quoted => {
+                in_quotes = true;
+                field.quoted = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::replace(&mut field, CsvField { text: String::new(), quoted: false }));
+                // Blank lines are skipped
+                if !(record.len() == 1 && record[0].text.is_empty() && !record[0].quoted) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            c if c == delimiter => {
+                record.push(std::mem::replace(&mut field, CsvField { text: String::new(), quoted: false }));
+            }
+            _ => field.text.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(DatabaseError::ParseError(format!(
+            "unterminated quoted field in CSV record starting on line {record_line}"
+        )));
+    }
+    if !record.is_empty() || !field.text.is_empty() || field.quoted {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+impl ForeignDataWrapper for CsvWrapper {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn validate(&self, source: &ForeignSource) -> Result<(), DatabaseError> {
+        check_options(source, &["path", "header", "delimiter", "null"])?;
+        if let Some(header) = source.option("header")
+            && !matches!(header.to_lowercase().as_str(), "true" | "false")
+        {
+            return Err(DatabaseError::ParseError(format!("header requires a boolean value, got '{header}'")));
+        }
+        if let Some(delimiter) = source.option("delimiter")
+            && (delimiter.chars().count() != 1 || delimiter == "\"" || delimiter == "\n")
+        {
+            return Err(DatabaseError::ParseError(format!(
+                "CSV delimiter must be a single character other than quote or newline, got '{delimiter}'"
+            )));
+        }
+        Ok(())
+    }
+
+    fn scan(&self, columns: &[Column], source: &ForeignSource) -> Result<Vec<Row>, DatabaseError> {
+        let path = source.option("path").unwrap_or_default();
+        let header = source.option("header").is_some_and(|h| h.eq_ignore_ascii_case("true"));
+        let delimiter = source.option("delimiter").and_then(|d| d.chars().next()).unwrap_or(',');
+        let null = source.option("null").unwrap_or("");
+
+        let text = std::fs::read_to_string(path).map_err(|e| read_error(path, e))?;
+        let records = parse_csv(&text, delimiter)?;
+
+        records
+            .into_iter()
+            .skip(usize::from(header))
+            .map(|(line, fields)| {
+                if fields.len() != columns.len() {
+                    return Err(DatabaseError::ParseError(format!(
+                        "{path}, line {line}: expected {} fields, found {}",
+                        columns.len(),
+                        fields.len()
+                    )));
+                }
+                let values = fields
+                    .into_iter()
+                    .zip(columns)
+                    .map(|(field, column)| {
+                        if !field.quoted && field.text == null {
+                            Ok(Value::Null)
+                        } else {
+                            parse_field(&field.text, column)
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| DatabaseError::ParseError(format!("{path}, line {line}: {e}")))?;
+                Ok(Row::new(values))
+            })
+            .collect()
+    }
+}
+
+/// `SERVER parquet`: columns are looked up by name (case-insensitive) among
+/// the top-level fields of the file
+#[cfg(feature = "parquet")]
+#[derive(Debug)]
+pub struct ParquetWrapper;
+
+#[cfg(feature = "parquet")]
+impl ParquetWrapper {
+    /// Convert a parquet value to the column's type
+    ///
+    /// Values that already have the right type are kept; anything else is parsed from its text.
+    fn coerce(value: Value, column: &Column) -> Result<Value, DatabaseError> {
+        let kept = matches!(
+            (&column.data_type, &value),
+            (_, Value::Null)
+                | (DataType::Integer | DataType::BigInt | DataType::Serial | DataType::BigSerial, Value::Integer(_))
+                | (DataType::SmallInt, Value::SmallInt(_))
+                | (DataType::Real, Value::Real(_))
+                | (DataType::Numeric { .. }, Value::Numeric(_))
+                | (DataType::Text, Value::Text(_))
+                | (DataType::Boolean, Value::Boolean(_))
+                | (DataType::Date, Value::Date(_))
+                | (DataType::Timestamp, Value::Timestamp(_))
+                | (DataType::TimestampTz, Value::TimestampTz(_))
+                | (DataType::Uuid, Value::Uuid(_))
+                | (DataType::Bytea, Value::Bytea(_))
+        );
+        match value {
+            value if kept => Ok(value),
+            Value::Text(text) => parse_field(&text, column),
+            other => parse_field(&other.to_string(), column),
+        }
+    }
+
+    fn to_value(field: &parquet::record::Field) -> Result<Value, DatabaseError> {
+        use chrono::{DateTime, NaiveDate};
+        use parquet::record::Field;
+
+        Ok(match field {
+            Field::Null => Value::Null,
+            Field::Bool(b) => Value::Boolean(*b),
+            Field::Byte(n) => Value::Integer(i64::from(*n)),
+            Field::Short(n) => Value::Integer(i64::from(*n)),
+            Field::Int(n) => Value::Integer(i64::from(*n)),
+            Field::Long(n) => Value::Integer(*n),
+            Field::UByte(n) => Value::Integer(i64::from(*n)),
+            Field::UShort(n) => Value::Integer(i64::from(*n)),
+            Field::UInt(n) => Value::Integer(i64::from(*n)),
+            Field::ULong(n) => i64::try_from(*n).map_or_else(|_| Value::Text(n.to_string()), Value::Integer),
+            Field::Float16(f) => Value::Real(f.to_f64()),
+            Field::Float(f) => Value::Real(f64::from(*f)),
+            Field::Double(f) => Value::Real(*f),
+            Field::Decimal(_) => Value::Text(field.to_string()),
+            Field::Str(s) => Value::Text(s.clone()),
+            Field::Bytes(b) => Value::Bytea(b.data().to_vec()),
+            Field::Date(days) => NaiveDate::from_ymd_opt(1970, 1, 1)
+                .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(i64::from(*days))))
+                .map_or(Value::Null, Value::Date),
+            Field::TimestampMillis(ms) => {
+                DateTime::from_timestamp_millis(*ms).map_or(Value::Null, |t| Value::Timestamp(t.naive_utc()))
+            }
+            Field::TimestampMicros(us) => {
+                DateTime::from_timestamp_micros(*us).map_or(Value::Null, |t| Value::Timestamp(t.naive_utc()))
+            }
+            Field::Group(_) | Field::ListInternal(_) | Field::MapInternal(_) => {
+                return Err(DatabaseError::ParseError(format!(
+                    "nested parquet values are not supported: {field}"
+                )));
+            }
+        })
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl ForeignDataWrapper for ParquetWrapper {
+    fn name(&self) -> &'static str {
+        "parquet"
+    }
+
+    fn validate(&self, source: &ForeignSource) -> Result<(), DatabaseError> {
+        check_options(source, &["path"])
+    }
+
+    fn scan(&self, columns: &[Column], source: &ForeignSource) -> Result<Vec<Row>, DatabaseError> {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let path = source.option("path").unwrap_or_default();
+        let file = std::fs::File::open(path).map_err(|e| read_error(path, e))?;
+        let reader = SerializedFileReader::new(file).map_err(|e| read_error(path, e))?;
+
+        let fields = reader.metadata().file_metadata().schema_descr().root_schema().get_fields().to_vec();
+        let positions = columns
+            .iter()
+            .map(|column| {
+                fields
+                    .iter()
+                    .position(|field| field.name().eq_ignore_ascii_case(&column.name))
+                    .ok_or_else(|| {
+                        DatabaseError::ParseError(format!("column '{}' not found in \"{path}\"", column.name))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rows = Vec::new();
+        for record in reader.get_row_iter(None).map_err(|e| read_error(path, e))? {
+            let record = record.map_err(|e| read_error(path, e))?;
+            let values: Vec<&parquet::record::Field> = record.get_column_iter().map(|(_, field)| field).collect();
+            let row = positions
+                .iter()
+                .zip(columns)
+                .map(|(&position, column)| Self::coerce(Self::to_value(values[position])?, column))
+                .collect::<Result<Vec<_>, _>>()?;
+            rows.push(Row::new(row));
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::Collation;
+
+    fn column(name: &str, data_type: DataType) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            foreign_key: None,
+            collation: Collation::C,
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_quoting() {
+        let records = parse_csv("1,\"a, \"\"b\"\"\",\n\n2,\"multi\nline\",\"\"\r\n", ',').unwrap();
+        assert_eq!(records.len(), 2);
+
+        let (line, fields) = &records[0];
+        assert_eq!(*line, 1);
+        assert_eq!(fields[1].text, "a, \"b\"");
+        assert!(!fields[2].quoted && fields[2].text.is_empty());
+
+        let (line, fields) = &records[1];
+        assert_eq!(*line, 3);
+        assert_eq!(fields[1].text, "multi\nline");
+        assert!(fields[2].quoted && fields[2].text.is_empty());
+
+        assert!(parse_csv("1,\"open\n", ',').is_err());
+    }
+
+    #[test]
+    fn test_csv_scan_converts_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("people.csv");
+        std::fs::write(&path, "id;name;born;active\n1;Alice;1990-04-01;t\n2;\"Bob; Jr\";-;false\n").unwrap();
+
+        let columns = vec![
+            column("id", DataType::Integer),
+            column("name", DataType::Text),
+            column("born", DataType::Date),
+            column("active", DataType::Boolean),
+        ];
+        let source = ForeignSource {
+            server: "csv".to_string(),
+            options: vec![
+                ("path".to_string(), path.to_string_lossy().into_owned()),
+                ("header".to_string(), "true".to_string()),
+                ("delimiter".to_string(), ";".to_string()),
+                ("null".to_string(), "-".to_string()),
+            ],
+        };
+        CsvWrapper.validate(&source).unwrap();
+
+        let rows = CsvWrapper.scan(&columns, &source).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].values[0], Value::Integer(1));
+        assert_eq!(rows[0].values[2], Value::Date(chrono::NaiveDate::from_ymd_opt(1990, 4, 1).unwrap()));
+        assert_eq!(rows[1].values[1], Value::Text("Bob; Jr".to_string()));
+        assert_eq!(rows[1].values[2], Value::Null);
+        assert_eq!(rows[1].values[3], Value::Boolean(false));
+
+        std::fs::write(&path, "id;name;born;active\nx;Carol;2000-01-01;t\n").unwrap();
+        let err = CsvWrapper.scan(&columns, &source).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let source = |options: &[(&str, &str)]| ForeignSource {
+            server: "csv".to_string(),
+            options: options.iter().map(|(k, v)| ((*k).to_string(), (*v).to_string())).collect(),
+        };
+        assert!(CsvWrapper.validate(&source(&[("path", "/tmp/x.csv")])).is_ok());
+        assert!(CsvWrapper.validate(&source(&[("header", "true")])).is_err());
+        assert!(CsvWrapper.validate(&source(&[("path", "/tmp/x.csv"), ("format", "csv")])).is_err());
+        assert!(CsvWrapper.validate(&source(&[("path", "/tmp/x.csv"), ("delimiter", "||")])).is_err());
+        assert!(wrapper("mysql").is_err());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_scan() {
+        use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cities.parquet");
+        let schema = Arc::new(
+            parquet::schema::parser::parse_message_type(
+                "message schema { REQUIRED BYTE_ARRAY city (UTF8); REQUIRED INT64 population; }",
+            )
+            .unwrap(),
+        );
+        let file = std::fs::File::create(&path).unwrap();
+        let mut writer = SerializedFileWriter::new(file, schema, Arc::new(WriterProperties::builder().build())).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut city = row_group.next_column().unwrap().unwrap();
+        city.typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from("Oslo"), ByteArray::from("Lima")], None, None)
+            .unwrap();
+        city.close().unwrap();
+        let mut population = row_group.next_column().unwrap().unwrap();
+        population.typed::<Int64Type>().write_batch(&[709_000, 10_000_000], None, None).unwrap();
+        population.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        // Columns are matched by name, not position
+        let columns = vec![column("population", DataType::Integer), column("city", DataType::Text)];
+        let source = ForeignSource {
+            server: "parquet".to_string(),
+            options: vec![("path".to_string(), path.to_string_lossy().into_owned())],
+        };
+        let rows = ParquetWrapper.scan(&columns, &source).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].values, vec![Value::Integer(10_000_000), Value::Text("Lima".to_string())]);
+
+        let missing = vec![column("country", DataType::Text)];
+        assert!(ParquetWrapper.scan(&missing, &source).is_err());
+    }
+
+    #[test]
+    fn test_foreign_csv_table() {
+        // v2.7.0: CREATE FOREIGN TABLE ... SERVER csv
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("visits.csv");
+        std::fs::write(&path, "id,city,visitors\n1,Oslo,120\n2,\"Lima, Peru\",80\n3,Oslo,\n").unwrap();
+
+        let create = format!(
+            "CREATE FOREIGN TABLE visits (id INTEGER, city TEXT, visitors INTEGER) SERVER csv OPTIONS (path '{}', header 'true')",
+            path.display()
+        );
+        run_sql(&mut db, &mut storage, &tx_manager, &create).unwrap();
+        assert_eq!(db.get_table("visits").unwrap().access_method, crate::types::AccessMethod::Foreign);
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "CREATE FOREIGN TABLE bad (id INTEGER) SERVER mysql OPTIONS (path 'x')").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "CREATE FOREIGN TABLE bad (id INTEGER) SERVER csv OPTIONS (header 'true')").is_err());
+
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT city FROM visits WHERE visitors IS NULL").unwrap());
+        assert_eq!(result, vec![vec!["Oslo".to_string()]]);
+
+        // Joined against a native table without importing the file
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE cities (name TEXT, country TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO cities VALUES ('Oslo', 'Norway')").unwrap();
+        let result = rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT * FROM visits JOIN cities ON visits.city = cities.name").unwrap());
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|row| row[4] == "Norway"));
+
+        // Foreign tables are read-only
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO visits VALUES (4, 'Rome', 10)").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "UPDATE visits SET visitors = 0").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "DELETE FROM visits").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_visits ON visits (id)").is_err());
+
+        // Every scan reads the file as it is now
+        std::fs::write(&path, "id,city,visitors\n1,Oslo,120\n").unwrap();
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM visits").unwrap())[0][0], "1");
+        std::fs::remove_file(&path).unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT * FROM visits").is_err());
+    }
+}
@@ -0,0 +1,374 @@
+/// Undo of a committed transaction: `SELECT pg_undo_transaction(xid)` (v2.7.0, experimental)
+///
+/// Every row version records the transaction that wrote it (xmin) and the
+/// one that deleted it (xmax), so what a committed transaction did to the
+/// tables can be read back from them as long as VACUUM has not removed the
+/// versions it deleted. The undo is written in a transaction of its own:
+/// the versions the transaction wrote are deleted and the versions it
+/// deleted are inserted again, like a ROLLBACK after the fact.
+///
+/// It is refused when later changes conflict with it:
+/// - a later transaction changed or deleted a row the transaction wrote
+/// - the columns of a table changed since
+/// - a restored row would clash on a PRIMARY KEY, UNIQUE column or unique
+///   index with a row added later
+/// - a foreign key would break: a restored row references a row that is
+///   gone, or a row written by the transaction is referenced by now
+///
+/// Only row changes are undone; DDL keeps no transaction id. Transaction
+/// ids start over when the server restarts, so only transactions since the
+/// last start can be undone reliably.
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError, Row, Table, Value};
+use super::dispatcher_executor::QueryResult;
+
+pub struct Flashback;
+
+/// What the transaction did to one table
+struct TableChanges {
+    table: String,
+    /// Live versions the transaction wrote (deleted by the undo)
+    written: Vec<Row>,
+    /// Versions the transaction deleted (inserted again by the undo)
+    deleted: Vec<Row>,
+}
+
+impl Flashback {
+    /// Undo committed transaction `xid`; returns the number of row versions
+    /// deleted and inserted
+    pub fn undo_transaction(
+        db: &mut Database,
+        xid: u64,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &mut DatabaseStorage,
+        active_tx_id: Option<u64>,
+    ) -> Result<QueryResult, DatabaseError> {
+        let refuse = |reason: &str| DatabaseError::UndoConflict(xid, reason.to_string());
+        if xid == 0 || xid >= tx_manager.current_tx_id() {
+            return Err(refuse("no such transaction"));
+        }
+        if tx_manager.is_active(xid) || active_tx_id == Some(xid) {
+            return Err(refuse("it is still in progress"));
+        }
+        if xid <= database_storage.vacuum_horizon() {
+            return Err(refuse("VACUUM may have removed the row versions it deleted"));
+        }
+
+        let changes = Self::collect(db, xid, database_storage)?;
+        Self::check_keys(db, xid, &changes, database_storage)?;
+        Self::check_foreign_keys(db, xid, &changes, database_storage)?;
+
+        let (undo_tx, auto_commit) = match active_tx_id {
+            Some(tx_id) => (tx_id, false),
+            None => (tx_manager.begin_transaction().0, true),
+        };
+        let mut count = 0;
+        for change in &changes {
+            let paged_table = database_storage
+                .get_paged_table_mut(&change.table)
+                .ok_or_else(|| DatabaseError::TableNotFound(change.table.clone()))?;
+            count += paged_table.delete_where(|row| row.xmin == xid && row.xmax.is_none(), undo_tx)?;
+            for row in &change.deleted {
+                paged_table.insert(Row::new_with_xmin(row.values.clone(), undo_tx))?;
+                count += 1;
+            }
+            Self::rebuild_indexes(db, &change.table, database_storage)?;
+        }
+        if auto_commit {
+            tx_manager.commit_transaction(undo_tx);
+        }
+
+        Ok(QueryResult::Rows(vec![vec![count.to_string()]], vec!["pg_undo_transaction".to_string()]))
+    }
+
+    /// Row versions `xid` wrote and deleted, by table
+    fn collect(db: &Database, xid: u64, database_storage: &DatabaseStorage) -> Result<Vec<TableChanges>, DatabaseError> {
+        let mut names: Vec<&String> = db.tables.keys().collect();
+        names.sort();
+
+        let mut changes = Vec::new();
+        for name in names {
+            let Some(paged_table) = database_storage.get_paged_table(name) else {
+                continue;
+            };
+            let width = db.tables[name].columns.len();
+            let (mut written, mut deleted) = (Vec::new(), Vec::new());
+            for row in paged_table.get_all_rows()? {
+                if row.xmin == xid {
+                    match row.xmax {
+                        None => written.push(row),
+                        // Written and deleted again by the transaction itself
+                        Some(xmax) if xmax == xid => {}
+                        Some(_) => {
+                            return Err(DatabaseError::UndoConflict(
+                                xid,
+                                format!("a row it wrote to table \"{name}\" was changed by a later transaction"),
+                            ));
+                        }
+                    }
+                } else if row.xmax == Some(xid) {
+                    if row.values.len() != width {
+                        return Err(DatabaseError::UndoConflict(
+                            xid,
+                            format!("the columns of table \"{name}\" changed since"),
+                        ));
+                    }
+                    deleted.push(row);
+                }
+            }
+            if !written.is_empty() || !deleted.is_empty() {
+                changes.push(TableChanges { table: name.clone(), written, deleted });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Live rows of `table` once the undo is done
+    fn rows_after(
+        xid: u64,
+        table: &str,
+        changes: &[TableChanges],
+        database_storage: &DatabaseStorage,
+    ) -> Result<Vec<Vec<Value>>, DatabaseError> {
+        let mut rows: Vec<Vec<Value>> = match database_storage.get_paged_table(table) {
+            Some(paged_table) => paged_table
+                .get_all_rows()?
+                .into_iter()
+                .filter(|row| row.xmax.is_none() && row.xmin != xid)
+                .map(|row| row.values)
+                .collect(),
+            None => Vec::new(),
+        };
+        if let Some(change) = changes.iter().find(|change| change.table == table) {
+            rows.extend(change.deleted.iter().map(|row| row.values.clone()));
+        }
+        Ok(rows)
+    }
+
+    /// Restored rows must not clash with each other or with the rows the
+    /// undo leaves alone on a PRIMARY KEY, UNIQUE column or unique index
+    fn check_keys(
+        db: &Database,
+        xid: u64,
+        changes: &[TableChanges],
+        database_storage: &DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        for change in changes.iter().filter(|change| !change.deleted.is_empty()) {
+            let table = &db.tables[&change.table];
+            let mut keys: Vec<Vec<usize>> = table
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| column.primary_key || column.unique)
+                .map(|(idx, _)| vec![idx])
+                .collect();
+            for index in db.indexes.values().filter(|index| index.table_name() == change.table && index.is_unique()) {
+                let columns: Option<Vec<usize>> =
+                    index.column_names().iter().map(|column| table.get_column_index(column)).collect();
+                keys.extend(columns);
+            }
+            let kept: Vec<Row> = database_storage
+                .get_paged_table(&change.table)
+                .ok_or_else(|| DatabaseError::TableNotFound(change.table.clone()))?
+                .get_all_rows()?
+                .into_iter()
+                .filter(|row| row.xmax.is_none() && row.xmin != xid)
+                .collect();
+
+            for key in keys {
+                // NULLs never clash
+                let key_of = |values: &[Value]| -> Option<Vec<Value>> {
+                    let key: Vec<Value> = key.iter().map(|&idx| values[idx].clone()).collect();
+                    (!key.contains(&Value::Null)).then_some(key)
+                };
+                let mut taken: Vec<Vec<Value>> = kept.iter().filter_map(|row| key_of(&row.values)).collect();
+                for row in &change.deleted {
+                    let Some(restored) = key_of(&row.values) else {
+                        continue;
+                    };
+                    if taken.contains(&restored) {
+                        return Err(Self::duplicate(xid, &change.table, table, &key, &restored));
+                    }
+                    taken.push(restored);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn duplicate(xid: u64, name: &str, table: &Table, key: &[usize], values: &[Value]) -> DatabaseError {
+        let columns: Vec<&str> = key.iter().map(|&idx| table.columns[idx].name.as_str()).collect();
+        let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        DatabaseError::UndoConflict(
+            xid,
+            format!(
+                "a row it deleted from table \"{name}\" has key ({})=({}), which a later row took",
+                columns.join(", "),
+                values.join(", ")
+            ),
+        )
+    }
+
+    /// Foreign keys must hold once the undo is done
+    fn check_foreign_keys(
+        db: &Database,
+        xid: u64,
+        changes: &[TableChanges],
+        database_storage: &DatabaseStorage,
+    ) -> Result<(), DatabaseError> {
+        for (name, table) in &db.tables {
+            for (idx, column) in table.columns.iter().enumerate() {
+                let Some(fk) = &column.foreign_key else {
+                    continue;
+                };
+                let child_changed = changes.iter().any(|change| change.table == *name && !change.deleted.is_empty());
+                let parent_changed =
+                    changes.iter().any(|change| change.table == fk.referenced_table && !change.written.is_empty());
+                if !child_changed && !parent_changed {
+                    continue;
+                }
+                let Some(referenced) = db
+                    .get_table(&fk.referenced_table)
+                    .and_then(|parent| parent.get_column_index(&fk.referenced_column))
+                else {
+                    continue;
+                };
+
+                let parents: Vec<Value> = Self::rows_after(xid, &fk.referenced_table, changes, database_storage)?
+                    .into_iter()
+                    .map(|mut values| values.swap_remove(referenced))
+                    .collect();
+                let missing = Self::rows_after(xid, name, changes, database_storage)?
+                    .into_iter()
+                    .map(|mut values| values.swap_remove(idx))
+                    .find(|value| *value != Value::Null && !parents.contains(value));
+                if let Some(value) = missing {
+                    return Err(DatabaseError::UndoConflict(
+                        xid,
+                        format!(
+                            "{name}.{} = {value} would reference a missing row of table \"{}\"",
+                            column.name, fk.referenced_table
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Indexes point at row positions, which the undo moves
+    fn rebuild_indexes(db: &mut Database, table: &str, database_storage: &DatabaseStorage) -> Result<(), DatabaseError> {
+        let rows = database_storage
+            .get_paged_table(table)
+            .ok_or_else(|| DatabaseError::TableNotFound(table.to_string()))?
+            .get_all_rows()?;
+        let columns = db.get_table(table).ok_or_else(|| DatabaseError::TableNotFound(table.to_string()))?.columns.clone();
+
+        for index in db.indexes.values_mut().filter(|index| index.table_name() == table) {
+            let column_indices = index
+                .column_names()
+                .iter()
+                .map(|name| {
+                    columns.iter().position(|c| c.name == *name).ok_or_else(|| DatabaseError::ColumnNotFound(name.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            index.clear();
+            for (position, row) in rows.iter().enumerate().filter(|(_, row)| row.xmax.is_none()) {
+                if index.is_composite() {
+                    let values: Vec<Value> = column_indices.iter().map(|&idx| row.values[idx].clone()).collect();
+                    index.insert_composite(&values, position)?;
+                } else {
+                    index.insert(&row.values[column_indices[0]], position)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+
+    fn first_column(result: QueryResult) -> Vec<String> {
+        match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|mut row| row.remove(0)).collect(),
+            other @ QueryResult::Success(_) => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_undo_transaction() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut exec = |sql: &str| run_sql(&mut db, &mut storage, &tx_manager, sql);
+        exec("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)").unwrap();
+        exec("CREATE INDEX idx_users_name ON users (name)").unwrap();
+        exec("INSERT INTO users VALUES (1, 'alice')").unwrap();
+        exec("INSERT INTO users VALUES (2, 'bob')").unwrap();
+
+        let update = tx_manager.current_tx_id();
+        exec("UPDATE users SET name = 'robert' WHERE id = 2").unwrap();
+        let delete = tx_manager.current_tx_id();
+        exec("DELETE FROM users WHERE id = 1").unwrap();
+        let insert = tx_manager.current_tx_id();
+        exec("INSERT INTO users VALUES (3, 'carol')").unwrap();
+
+        let undo = format!("SELECT pg_undo_transaction({delete})");
+        assert_eq!(first_column(exec(&undo).unwrap()), vec!["1"]);
+        // The old version comes back and the new one goes: 2 row versions
+        let undo = format!("SELECT pg_undo_transaction({update})");
+        assert_eq!(first_column(exec(&undo).unwrap()), vec!["2"]);
+        let undo = format!("SELECT pg_undo_transaction({insert})");
+        exec(&undo).unwrap();
+        assert_eq!(first_column(exec("SELECT name FROM users ORDER BY id").unwrap()), vec!["alice", "bob"]);
+        // Indexes follow the rows that moved
+        assert_eq!(first_column(exec("SELECT id FROM users WHERE name = 'bob'").unwrap()), vec!["2"]);
+
+        // Undone already: the undo deleted what it wrote
+        assert!(matches!(exec(&undo), Err(DatabaseError::UndoConflict(xid, _)) if xid == insert));
+        let err = exec("SELECT pg_undo_transaction(999)").unwrap_err();
+        assert_eq!(err.to_string(), "cannot undo transaction 999: no such transaction");
+    }
+
+    #[test]
+    fn test_undo_conflicts() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut exec = |sql: &str| run_sql(&mut db, &mut storage, &tx_manager, sql);
+        exec("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)").unwrap();
+        exec("CREATE TABLE orders (id INTEGER, user_id INTEGER REFERENCES users(id))").unwrap();
+        let first = tx_manager.current_tx_id();
+        exec("INSERT INTO users VALUES (1, 'alice')").unwrap();
+        exec("INSERT INTO orders VALUES (10, 1)").unwrap();
+        exec("INSERT INTO users VALUES (2, 'bob')").unwrap();
+
+        // A later order references the row the insert wrote
+        let err = exec(&format!("SELECT pg_undo_transaction({first})")).unwrap_err();
+        assert!(matches!(err, DatabaseError::UndoConflict(xid, _) if xid == first), "{err}");
+
+        // A later transaction changed the row
+        let renamed = tx_manager.current_tx_id();
+        exec("UPDATE users SET name = 'robert' WHERE id = 2").unwrap();
+        exec("UPDATE users SET name = 'rob' WHERE id = 2").unwrap();
+        let err = exec(&format!("SELECT pg_undo_transaction({renamed})")).unwrap_err();
+        assert!(err.to_string().contains("changed by a later transaction"), "{err}");
+
+        // A later row took the primary key of the deleted one
+        let delete = tx_manager.current_tx_id();
+        exec("DELETE FROM users WHERE id = 2").unwrap();
+        exec("INSERT INTO users VALUES (2, 'other')").unwrap();
+        let err = exec(&format!("SELECT pg_undo_transaction({delete})")).unwrap_err();
+        assert!(err.to_string().contains("has key (id)=(2)"), "{err}");
+
+        // VACUUM may have removed what the transaction deleted
+        exec("DELETE FROM users WHERE id = 2").unwrap();
+        exec("VACUUM").unwrap();
+        let err = exec(&format!("SELECT pg_undo_transaction({delete})")).unwrap_err();
+        assert!(err.to_string().contains("VACUUM"), "{err}");
+    }
+}
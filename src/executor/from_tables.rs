@@ -0,0 +1,130 @@
+/// Extra tables of `DELETE ... USING` and `UPDATE ... FROM` (v2.7.0)
+///
+/// `DELETE FROM a USING b WHERE a.id = b.id AND b.flag` removes every row of
+/// `a` for which some row of `b` satisfies the WHERE clause; `UPDATE a SET ...
+/// FROM b WHERE ...` evaluates the SET expressions over the first such row.
+///
+/// The visible rows of the tables are loaded up front, as every combination of
+/// them. A target row is joined with one combination at a time and the
+/// condition sees each column twice: as `col` and as `table.col`. The target's
+/// columns come first, so an unqualified name shared with another table
+/// refers to the target.
+use crate::parser::Condition;
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Column, Database, DatabaseError, Row, Value};
+use super::conditions::ConditionEvaluator;
+use super::partitions::Partitions;
+
+#[derive(Debug, Default)]
+pub struct FromTables {
+    /// `table.col` for every column of every table, then the unqualified names
+    columns: Vec<Column>,
+    /// Every combination of the tables' visible rows, laid out like `columns`
+    rows: Vec<Vec<Value>>,
+}
+
+impl FromTables {
+    /// Load the visible rows of `names`; without names nothing is joined
+    pub fn load(
+        db: &Database,
+        database_storage: &DatabaseStorage,
+        tx_manager: &GlobalTransactionManager,
+        names: &[String],
+    ) -> Result<Self, DatabaseError> {
+        if names.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let snapshot = tx_manager.get_snapshot();
+        let mut qualified = Vec::new();
+        let mut unqualified = Vec::new();
+        let mut combinations: Vec<Vec<Value>> = vec![Vec::new()];
+
+        for name in names {
+            let table = db
+                .get_table(name)
+                .ok_or_else(|| DatabaseError::TableNotFound(name.clone()))?;
+            for col in &table.columns {
+                qualified.push(Column { name: format!("{name}.{}", col.name), ..col.clone() });
+                unqualified.push(col.clone());
+            }
+
+            let mut rows = Vec::new();
+            for row in Partitions::scan(db, database_storage, name, None)? {
+                let row = row?;
+                if row.is_visible_to_snapshot(&snapshot) {
+                    rows.push(row.values);
+                }
+            }
+            combinations = combinations
+                .iter()
+                .flat_map(|prefix| rows.iter().map(move |row| [prefix.as_slice(), row].concat()))
+                .collect();
+        }
+
+        qualified.extend(unqualified);
+        Ok(Self {
+            columns: qualified,
+            rows: combinations.into_iter().map(|values| [values.as_slice(), &values].concat()).collect(),
+        })
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Columns a joined row of `table_name` is evaluated against
+    #[must_use]
+    pub fn joined_columns(&self, table_name: &str, table_columns: &[Column]) -> Vec<Column> {
+        let mut columns = table_columns.to_vec();
+        if self.is_empty() {
+            return columns;
+        }
+        columns.extend(
+            table_columns
+                .iter()
+                .map(|col| Column { name: format!("{table_name}.{}", col.name), ..col.clone() }),
+        );
+        columns.extend(self.columns.iter().cloned());
+        columns
+    }
+
+    /// Does `row` satisfy `filter` together with some combination?
+    pub fn matches(&self, columns: &[Column], row: &Row, filter: Option<&Condition>) -> Result<bool, DatabaseError> {
+        if self.is_empty() {
+            return match filter {
+                Some(cond) => ConditionEvaluator::evaluate_with_columns(columns, row, cond),
+                None => Ok(true),
+            };
+        }
+        Ok(self.first_match(columns, row, filter)?.is_some())
+    }
+
+    /// Values of `row` joined with the first combination that satisfies `filter`
+    ///
+    /// Without tables this is just the row's own values when it matches.
+    pub fn first_match(
+        &self,
+        columns: &[Column],
+        row: &Row,
+        filter: Option<&Condition>,
+    ) -> Result<Option<Vec<Value>>, DatabaseError> {
+        let satisfies = |joined: &Row| match filter {
+            Some(cond) => ConditionEvaluator::evaluate_with_columns(columns, joined, cond),
+            None => Ok(true),
+        };
+
+        if self.is_empty() {
+            return Ok(satisfies(row)?.then(|| row.values.clone()));
+        }
+        for combination in &self.rows {
+            let joined = Row::new([row.values.as_slice(), &row.values, combination].concat());
+            if satisfies(&joined)? {
+                return Ok(Some(joined.values));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,610 @@
+/// Stored SQL functions and scalar expression evaluation (v2.7.0)
+///
+/// `CREATE FUNCTION add(a int, b int) RETURNS int AS 'SELECT a + b'` stores the
+/// body as SQL text in `Database::functions`. On every call the arguments are
+/// rendered as SQL literals and substituted for the parameter names (or `$1`,
+/// `$2`, ...), then the body is parsed and executed like any other query.
+///
+/// - bodies without FROM (`SELECT a + b`) are evaluated directly, keeping the
+///   value types, so calls can be nested inside arithmetic
+/// - `RETURNS SETOF` functions (or any query body) can be used in FROM:
+///   `SELECT * FROM adults_over(30)`
+use crate::parser::{BinaryOperator, CaseExpression, CompareOperator, Condition, Expression, Lexer, SelectColumn, Statement, TokenKind, WhenCondition};
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Column, DataType, Database, DatabaseError, Row, StoredFunction, Value};
+use super::conditions::ConditionEvaluator;
+use super::dispatcher_executor::QueryResult;
+use super::queries::QueryExecutor as QueriesExecutor;
+use super::subquery::SubqueryContext;
+use super::system_functions::SystemFunctions;
+use super::udf::FunctionRegistry;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use std::cell::Cell;
+
+/// Maximum nesting of SQL function calls (guards against runaway recursion)
+const MAX_CALL_DEPTH: usize = 64;
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Decrements the call depth when a function call returns
+struct DepthGuard;
+
+impl DepthGuard {
+    fn enter(name: &str) -> Result<Self, DatabaseError> {
+        let depth = CALL_DEPTH.with(Cell::get);
+        if depth >= MAX_CALL_DEPTH {
+            return Err(DatabaseError::ParseError(format!(
+                "stack depth limit exceeded while calling function '{name}'"
+            )));
+        }
+        CALL_DEPTH.with(|d| d.set(depth + 1));
+        Ok(Self)
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        CALL_DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+    }
+}
+
+pub struct FunctionExecutor;
+
+impl FunctionExecutor {
+    /// CREATE [OR REPLACE] FUNCTION
+    pub fn create_function(
+        db: &mut Database,
+        name: String,
+        params: Vec<(String, DataType)>,
+        returns: DataType,
+        returns_set: bool,
+        body: String,
+        or_replace: bool,
+    ) -> Result<QueryResult, DatabaseError> {
+        if SystemFunctions::is_system_function(&name) || FunctionRegistry::contains(&name) {
+            return Err(DatabaseError::ParseError(format!(
+                "Function '{name}' is a built-in function and cannot be redefined"
+            )));
+        }
+        if !or_replace && db.functions.contains_key(&name) {
+            return Err(DatabaseError::ParseError(format!("Function '{name}' already exists")));
+        }
+
+        let function = StoredFunction {
+            name: name.clone(),
+            params,
+            returns,
+            returns_set,
+            body,
+        };
+
+        // Validate the body by parsing it with NULL arguments
+        let probe = Self::bind(&function, &vec![Value::Null; function.params.len()]);
+        let stmt = crate::parser::parse_statement(&probe).map_err(|e| {
+            DatabaseError::ParseError(format!("Invalid body for function '{name}': {e}"))
+        })?;
+        if !matches!(
+            stmt,
+            Statement::Select { .. } | Statement::Union { .. } | Statement::Intersect { .. } | Statement::Except { .. }
+        ) {
+            return Err(DatabaseError::ParseError(format!(
+                "Body of function '{name}' must be a SELECT query"
+            )));
+        }
+
+        db.functions.insert(name, function);
+        Ok(QueryResult::Success("CREATE FUNCTION".to_string()))
+    }
+
+    /// DROP FUNCTION [IF EXISTS]
+    pub fn drop_function(
+        db: &mut Database,
+        name: &str,
+        if_exists: bool,
+    ) -> Result<QueryResult, DatabaseError> {
+        if db.functions.remove(name).is_some() || if_exists {
+            Ok(QueryResult::Success("DROP FUNCTION".to_string()))
+        } else {
+            Err(DatabaseError::ParseError(format!("Function '{name}' does not exist")))
+        }
+    }
+
+    /// Evaluate a scalar expression against a row
+    ///
+    /// `columns` describes `row`; both are empty for SELECT without FROM.
+    pub fn evaluate(
+        expr: &Expression,
+        columns: &[Column],
+        row: &Row,
+        db: &Database,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<Value, DatabaseError> {
+        match expr {
+            Expression::Literal(value) => Ok(value.clone()),
+            Expression::Column(name) => columns
+                .iter()
+                .position(|col| col.name == *name)
+                .and_then(|idx| row.values.get(idx).cloned())
+                .ok_or_else(|| DatabaseError::ColumnNotFound(name.clone())),
+            Expression::Binary { left, op, right } => {
+                let left = Self::evaluate(left, columns, row, db, tx_manager, database_storage)?;
+                let right = Self::evaluate(right, columns, row, db, tx_manager, database_storage)?;
+                Self::fit_int4(Self::apply_operator(*op, &left, &right)?, expr, columns)
+            }
+            Expression::Function { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| Self::evaluate(arg, columns, row, db, tx_manager, database_storage))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self::call(db, name, &args, tx_manager, database_storage)
+            }
+            Expression::Case(case) => {
+                let subquery_ctx = SubqueryContext::new();
+                Self::evaluate_case(
+                    case,
+                    &|expr| Self::evaluate(expr, columns, row, db, tx_manager, database_storage),
+                    &|cond| {
+                        ConditionEvaluator::truth_with_context(columns, row, cond, db, tx_manager, database_storage, &subquery_ctx)
+                    },
+                )
+            }
+        }
+    }
+
+    /// Evaluate a scalar expression against the values of a row without database access
+    ///
+    /// Only native scalar functions from `FunctionRegistry` can be called. Used
+    /// for generated columns and UPDATE ... SET (v2.7.0).
+    pub fn evaluate_values(expr: &Expression, columns: &[Column], values: &[Value]) -> Result<Value, DatabaseError> {
+        match expr {
+            Expression::Literal(value) => Ok(value.clone()),
+            Expression::Column(name) => columns
+                .iter()
+                .position(|col| col.name == *name)
+                .and_then(|idx| values.get(idx).cloned())
+                .ok_or_else(|| DatabaseError::ColumnNotFound(name.clone())),
+            Expression::Binary { left, op, right } => {
+                let left = Self::evaluate_values(left, columns, values)?;
+                let right = Self::evaluate_values(right, columns, values)?;
+                Self::fit_int4(Self::apply_operator(*op, &left, &right)?, expr, columns)
+            }
+            Expression::Function { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| Self::evaluate_values(arg, columns, values))
+                    .collect::<Result<Vec<_>, _>>()?;
+                FunctionRegistry::call_scalar(name, &args).unwrap_or_else(|| {
+                    Err(DatabaseError::ParseError(format!("Function '{name}' does not exist")))
+                })
+            }
+            Expression::Case(case) => {
+                let row = Row::new(values.to_vec());
+                Self::evaluate_case(
+                    case,
+                    &|expr| Self::evaluate_values(expr, columns, values),
+                    &|cond| ConditionEvaluator::truth_with_columns(columns, &row, cond),
+                )
+            }
+        }
+    }
+
+    /// Whether an expression has type integer (int4) rather than bigint (v2.7.0)
+    ///
+    /// As in `PostgreSQL`, integer literals that fit in 32 bits, SMALLINT,
+    /// INTEGER and SERIAL columns and arithmetic between them are int4; a
+    /// BIGINT column or a larger literal makes the expression bigint.
+    pub(crate) fn is_int4(expr: &Expression, columns: &[Column]) -> bool {
+        match expr {
+            Expression::Literal(Value::SmallInt(_)) => true,
+            Expression::Literal(Value::Integer(n)) => i32::try_from(*n).is_ok(),
+            Expression::Column(name) => columns.iter().find(|col| col.name == *name).is_some_and(|col| {
+                matches!(col.data_type, DataType::SmallInt | DataType::Integer | DataType::Serial)
+            }),
+            Expression::Binary { left, op, right } => {
+                *op != BinaryOperator::Concat && Self::is_int4(left, columns) && Self::is_int4(right, columns)
+            }
+            _ => false,
+        }
+    }
+
+    /// Reject the result of int4 arithmetic that does not fit in 32 bits (v2.7.0)
+    fn fit_int4(value: Value, expr: &Expression, columns: &[Column]) -> Result<Value, DatabaseError> {
+        match value {
+            Value::Integer(n) if i32::try_from(n).is_err() && Self::is_int4(expr, columns) => {
+                Err(DatabaseError::NumericOutOfRange("integer out of range".to_string()))
+            }
+            value => Ok(value),
+        }
+    }
+
+    /// Value of a CASE expression (v2.7.0)
+    ///
+    /// `value` evaluates the sub-expressions and `truth` the WHEN conditions
+    /// against the same row. A simple CASE takes the first WHEN value equal to
+    /// the operand; NULL matches nothing. Without a match the result is ELSE or NULL.
+    fn evaluate_case(
+        case: &CaseExpression,
+        value: &dyn Fn(&Expression) -> Result<Value, DatabaseError>,
+        truth: &dyn Fn(&Condition) -> Result<Option<bool>, DatabaseError>,
+    ) -> Result<Value, DatabaseError> {
+        let operand = case.operand.as_ref().map(value).transpose()?;
+
+        for when in &case.when_clauses {
+            let matched = match (&when.condition, &operand) {
+                (WhenCondition::Condition(cond), _) => truth(cond)?,
+                (WhenCondition::Value(expr), Some(operand)) => {
+                    ConditionEvaluator::compare_with(operand, CompareOperator::Equal, &value(expr)?)?
+                }
+                (WhenCondition::Value(_), None) => None,
+            };
+            if matched == Some(true) {
+                return value(&when.result);
+            }
+        }
+
+        case.else_value.as_ref().map_or(Ok(Value::Null), value)
+    }
+
+    /// Output column name for an expression (function name, like `PostgreSQL`)
+    #[must_use]
+    pub fn column_name(expr: &Expression, alias: Option<&String>) -> String {
+        match (alias, expr) {
+            (Some(alias), _) => alias.clone(),
+            (None, Expression::Function { name, .. }) => name.clone(),
+            (None, Expression::Case(_)) => "case".to_string(),
+            (None, _) => "?column?".to_string(),
+        }
+    }
+
+    /// Call a function in scalar context
+    pub fn call(
+        db: &Database,
+        name: &str,
+        args: &[Value],
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<Value, DatabaseError> {
+        if SystemFunctions::is_system_function(name) {
+            let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+            let result = SystemFunctions::evaluate(name, &args, db, Some(database_storage))?;
+            if SystemFunctions::returns_size(name) {
+                return result.parse().map(Value::Integer).map_err(|_| DatabaseError::ParseError(format!("Invalid size: {result}")));
+            }
+            return Ok(Value::Text(result));
+        }
+
+        // Native functions registered by the embedder (v2.7.0)
+        if let Some(result) = FunctionRegistry::call_scalar(name, args) {
+            return result;
+        }
+        if FunctionRegistry::is_aggregate(name) {
+            return Err(DatabaseError::ParseError(format!(
+                "Aggregate function '{name}' cannot be nested inside an expression"
+            )));
+        }
+
+        let function = Self::lookup(db, name, args)?;
+        if function.returns_set {
+            return Err(DatabaseError::ParseError(format!(
+                "Set-returning function '{name}' can only be called in FROM"
+            )));
+        }
+
+        let _guard = DepthGuard::enter(name)?;
+        let stmt = crate::parser::parse_statement(&Self::bind(function, args))
+            .map_err(|e| DatabaseError::ParseError(e.to_string()))?;
+
+        // `SELECT <expr>` bodies are evaluated directly so the value keeps its type
+        if let Statement::Select { ref from, ref columns, .. } = stmt
+            && from.is_empty()
+            && columns.len() == 1
+        {
+            let empty = Row::new(Vec::new());
+            return match &columns[0] {
+                SelectColumn::Literal(value) => Ok(value.clone()),
+                SelectColumn::Expression { expr, .. } => {
+                    Self::evaluate(expr, &[], &empty, db, tx_manager, database_storage)
+                }
+                _ => Err(DatabaseError::ParseError(format!(
+                    "Function '{name}' must return a single value"
+                ))),
+            };
+        }
+
+        match QueriesExecutor::execute_query_stmt(db, &stmt, tx_manager, database_storage)? {
+            QueryResult::Rows(rows, _) => Ok(rows
+                .first()
+                .and_then(|row| row.first())
+                .map_or(Value::Null, |rendered| Self::from_rendered(rendered, &function.returns))),
+            QueryResult::Success(_) => Ok(Value::Null),
+        }
+    }
+
+    /// Rows produced by a function called in FROM: `SELECT * FROM name(args)`
+    ///
+    /// `call` is the source text of the call as kept in `Statement::Select::from`.
+    pub fn call_set(
+        db: &Database,
+        call: &str,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<(Vec<Vec<String>>, Vec<String>), DatabaseError> {
+        let Ok(Expression::Function { name, args }) = crate::parser::parse_expression(call) else {
+            return Err(DatabaseError::ParseError(format!("Invalid function call in FROM: {call}")));
+        };
+
+        let empty = Row::new(Vec::new());
+        let args = args
+            .iter()
+            .map(|arg| Self::evaluate(arg, &[], &empty, db, tx_manager, database_storage))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let returns_rows = db
+            .functions
+            .get(&name)
+            .is_some_and(|function| function.returns_set);
+        if !returns_rows {
+            // Scalar function in FROM - a single row named after the function
+            let value = Self::call(db, &name, &args, tx_manager, database_storage)?;
+            return Ok((vec![vec![value.to_string()]], vec![name]));
+        }
+
+        let function = Self::lookup(db, &name, &args)?;
+        let _guard = DepthGuard::enter(&name)?;
+        let stmt = crate::parser::parse_statement(&Self::bind(function, &args))
+            .map_err(|e| DatabaseError::ParseError(e.to_string()))?;
+
+        match QueriesExecutor::execute_query_stmt(db, &stmt, tx_manager, database_storage)? {
+            QueryResult::Rows(rows, columns) => {
+                let columns = columns
+                    .into_iter()
+                    .map(|col| col.rsplit('.').next().unwrap_or(&col).to_string())
+                    .collect();
+                Ok((rows, columns))
+            }
+            QueryResult::Success(_) => Err(DatabaseError::ParseError(format!(
+                "Function '{name}' did not produce rows"
+            ))),
+        }
+    }
+
+    fn lookup<'a>(db: &'a Database, name: &str, args: &[Value]) -> Result<&'a StoredFunction, DatabaseError> {
+        let function = db
+            .functions
+            .get(name)
+            .ok_or_else(|| DatabaseError::ParseError(format!("Function '{name}' does not exist")))?;
+        if function.params.len() != args.len() {
+            return Err(DatabaseError::ParseError(format!(
+                "Function '{name}' expects {} argument(s), got {}",
+                function.params.len(),
+                args.len()
+            )));
+        }
+        Ok(function)
+    }
+
+    /// Substitute arguments into the function body
+    ///
+    /// Parameter names and `$n` placeholders outside of string literals are
+    /// replaced by the SQL literal of the corresponding argument (v2.7.0: going
+    /// by the tokens of the body, so quoted names and comments are left alone).
+    fn bind(function: &StoredFunction, args: &[Value]) -> String {
+        let body = function.body.as_str();
+        let mut bound = String::with_capacity(body.len());
+        let mut copied = 0;
+
+        for token in Lexer::new(body).map_while(Result::ok) {
+            let index = match token.kind {
+                TokenKind::Parameter => token.parameter_number().and_then(|n| n.checked_sub(1)),
+                TokenKind::Identifier => function.params.iter().position(|(param, _)| param == token.text),
+                _ => None,
+            };
+            if let Some(arg) = index.and_then(|i| args.get(i)) {
+                bound.push_str(&body[copied..token.offset]);
+                bound.push_str(&Self::sql_literal(arg));
+                copied = token.end();
+            }
+        }
+        bound.push_str(&body[copied..]);
+
+        bound
+    }
+
+    /// Render a value as a SQL literal the parser accepts
+    #[must_use]
+    pub fn sql_literal(value: &Value) -> String {
+        match value {
+            Value::Null => "NULL".to_string(),
+            Value::SmallInt(_) | Value::Integer(_) | Value::Real(_) | Value::Numeric(_) => value.to_string(),
+            Value::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            other => format!("'{other}'"),
+        }
+    }
+
+    /// Convert a rendered query result back into a value of the declared type
+    fn from_rendered(rendered: &str, data_type: &DataType) -> Value {
+        if rendered == "NULL" {
+            return Value::Null;
+        }
+        let parsed = match data_type {
+            DataType::SmallInt | DataType::Integer | DataType::BigInt | DataType::Serial | DataType::BigSerial => {
+                rendered.parse().ok().map(Value::Integer)
+            }
+            DataType::Real => rendered.parse().ok().map(Value::Real),
+            DataType::Numeric { .. } => rendered.parse().ok().map(Value::Numeric),
+            DataType::Boolean => match rendered {
+                "true" | "t" => Some(Value::Boolean(true)),
+                "false" | "f" => Some(Value::Boolean(false)),
+                _ => None,
+            },
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| Value::Text(rendered.to_string()))
+    }
+
+    /// Apply a binary operator; NULL on either side yields NULL
+    pub(crate) fn apply_operator(op: BinaryOperator, left: &Value, right: &Value) -> Result<Value, DatabaseError> {
+        if matches!(left, Value::Null) || matches!(right, Value::Null) {
+            return Ok(Value::Null);
+        }
+
+        if op == BinaryOperator::Concat {
+            return Ok(Value::Text(format!("{left}{right}")));
+        }
+
+        let as_int = |v: &Value| match v {
+            Value::SmallInt(n) => Some(i64::from(*n)),
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        };
+        let mismatch = || {
+            DatabaseError::ParseError(format!("Operator {op:?} is not defined for {left} and {right}"))
+        };
+
+        if let (Some(a), Some(b)) = (as_int(left), as_int(right)) {
+            let result = match op {
+                BinaryOperator::Add => a.checked_add(b),
+                BinaryOperator::Subtract => a.checked_sub(b),
+                BinaryOperator::Multiply => a.checked_mul(b),
+                BinaryOperator::Divide | BinaryOperator::Modulo if b == 0 => {
+                    return Err(DatabaseError::DivisionByZero);
+                }
+                BinaryOperator::Divide => a.checked_div(b),
+                BinaryOperator::Modulo => a.checked_rem(b),
+                BinaryOperator::Concat => unreachable!(),
+            };
+            return result
+                .map(Value::Integer)
+                .ok_or_else(|| DatabaseError::NumericOutOfRange("integer out of range".to_string()));
+        }
+
+        let as_decimal = |v: &Value| match v {
+            Value::Numeric(d) => Some(*d),
+            other => as_int(other).map(Decimal::from),
+        };
+        if (matches!(left, Value::Numeric(_)) || matches!(right, Value::Numeric(_)))
+            && let (Some(a), Some(b)) = (as_decimal(left), as_decimal(right))
+        {
+            if matches!(op, BinaryOperator::Divide | BinaryOperator::Modulo) && b.is_zero() {
+                return Err(DatabaseError::DivisionByZero);
+            }
+            let result = match op {
+                BinaryOperator::Add => a.checked_add(b),
+                BinaryOperator::Subtract => a.checked_sub(b),
+                BinaryOperator::Multiply => a.checked_mul(b),
+                BinaryOperator::Divide => a.checked_div(b),
+                BinaryOperator::Modulo => a.checked_rem(b),
+                BinaryOperator::Concat => unreachable!(),
+            };
+            return result
+                .map(Value::Numeric)
+                .ok_or_else(|| DatabaseError::NumericOutOfRange("numeric field overflow".to_string()));
+        }
+
+        let as_float = |v: &Value| match v {
+            Value::Real(f) => Some(*f),
+            Value::Numeric(d) => d.to_f64(),
+            other => as_int(other).and_then(f64::from_i64),
+        };
+        let (a, b) = (as_float(left).ok_or_else(mismatch)?, as_float(right).ok_or_else(mismatch)?);
+        let result = match op {
+            BinaryOperator::Add => a + b,
+            BinaryOperator::Subtract => a - b,
+            BinaryOperator::Multiply => a * b,
+            BinaryOperator::Divide | BinaryOperator::Modulo if b == 0.0 => {
+                return Err(DatabaseError::DivisionByZero);
+            }
+            BinaryOperator::Divide => a / b,
+            BinaryOperator::Modulo => a % b,
+            BinaryOperator::Concat => unreachable!(),
+        };
+        if result.is_infinite() && a.is_finite() && b.is_finite() {
+            return Err(DatabaseError::NumericOutOfRange("value out of range: overflow".to_string()));
+        }
+        Ok(Value::Real(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::storage::DatabaseStorage;
+
+    fn function(params: &[&str], body: &str) -> StoredFunction {
+        StoredFunction {
+            name: "f".to_string(),
+            params: params.iter().map(|p| ((*p).to_string(), DataType::Integer)).collect(),
+            returns: DataType::Integer,
+            returns_set: false,
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let f = function(&["a", "b"], "SELECT a + b + $1 FROM t WHERE name = 'a'");
+        let bound = FunctionExecutor::bind(&f, &[Value::Integer(1), Value::Text("x".to_string())]);
+        assert_eq!(bound, "SELECT 1 + 'x' + 1 FROM t WHERE name = 'a'");
+        let f = function(&["a"], "SELECT \"a\", a FROM t -- a\n");
+        assert_eq!(FunctionExecutor::bind(&f, &[Value::Integer(1)]), "SELECT \"a\", 1 FROM t -- a\n");
+    }
+
+    #[test]
+    fn test_apply_operator() {
+        let sum = FunctionExecutor::apply_operator(BinaryOperator::Add, &Value::SmallInt(2), &Value::Integer(40)).unwrap();
+        assert_eq!(sum, Value::Integer(42));
+
+        let half = FunctionExecutor::apply_operator(BinaryOperator::Divide, &Value::Real(1.0), &Value::Integer(2)).unwrap();
+        assert_eq!(half, Value::Real(0.5));
+
+        let null = FunctionExecutor::apply_operator(BinaryOperator::Multiply, &Value::Null, &Value::Integer(2)).unwrap();
+        assert_eq!(null, Value::Null);
+
+        assert!(FunctionExecutor::apply_operator(BinaryOperator::Divide, &Value::Integer(1), &Value::Integer(0)).is_err());
+        assert!(FunctionExecutor::apply_operator(BinaryOperator::Add, &Value::Integer(i64::MAX), &Value::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn test_sql_functions() {
+        // v2.7.0: scalar functions in expressions, set-returning functions in FROM
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (1, 'Alice', 30), (2, 'Bob', 25), (3, 'Charlie', 35)").unwrap();
+
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE FUNCTION add(a int, b int) RETURNS int AS 'SELECT a + b'").unwrap();
+        assert_eq!(rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT add(1, 2) * 10").unwrap()), vec![vec!["30".to_string()]]);
+        assert_eq!(
+            rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT name, add(age, 1) AS next FROM users WHERE age < 26").unwrap()),
+            vec![vec!["Bob".to_string(), "26".to_string()]]
+        );
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT add(1)").is_err());
+
+        run_sql(
+            &mut db,
+            &mut storage,
+            &tx_manager,
+            "CREATE FUNCTION older_than(n int) RETURNS SETOF users AS $$ SELECT name FROM users WHERE age > n $$ LANGUAGE SQL",
+        )
+        .unwrap();
+        assert_eq!(
+            rows(run_sql(&mut db, &mut storage, &tx_manager, "SELECT name FROM older_than(28) ORDER BY name DESC").unwrap()),
+            vec![vec!["Charlie".to_string()], vec!["Alice".to_string()]]
+        );
+
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP FUNCTION add").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT add(1, 2)").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP FUNCTION IF EXISTS add").unwrap();
+    }
+}
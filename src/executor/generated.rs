@@ -0,0 +1,285 @@
+/// Generated columns: `GENERATED ALWAYS AS (expr) STORED` (v2.7.0)
+///
+/// The expression source is kept in `Table::generated` and compiled once per
+/// statement. Values are computed on INSERT and recomputed on UPDATE; writing a
+/// generated column directly is rejected. Expressions may use the row's other
+/// (non-generated) columns, literals, operators and native scalar functions
+/// from `FunctionRegistry`, so they are evaluated without touching the database.
+use std::collections::HashMap;
+use crate::parser::Expression;
+use crate::types::{Column, DatabaseError, Value};
+use super::functions::FunctionExecutor;
+use super::udf::FunctionRegistry;
+use super::views::ViewResolver;
+
+/// Compiled generation expressions as (column index, expression), in column order
+pub type CompiledGenerated = Vec<(usize, Expression)>;
+
+pub struct GeneratedColumns;
+
+impl GeneratedColumns {
+    /// Parse and check the generation expressions of `columns`
+    pub fn compile(
+        columns: &[Column],
+        generated: &HashMap<String, String>,
+    ) -> Result<CompiledGenerated, DatabaseError> {
+        let mut compiled = Vec::new();
+
+        for (idx, column) in columns.iter().enumerate() {
+            let Some(source) = generated.get(&column.name) else {
+                continue;
+            };
+            let expr = crate::parser::parse_expression(source).map_err(|e| {
+                DatabaseError::ParseError(format!(
+                    "Invalid generation expression for column '{}': {e}",
+                    column.name
+                ))
+            })?;
+            Self::check_expression(&expr, &column.name, columns, generated)?;
+            compiled.push((idx, expr));
+        }
+
+        Ok(compiled)
+    }
+
+    fn check_expression(
+        expr: &Expression,
+        column: &str,
+        columns: &[Column],
+        generated: &HashMap<String, String>,
+    ) -> Result<(), DatabaseError> {
+        match expr {
+            Expression::Literal(_) => Ok(()),
+            Expression::Column(name) => {
+                if generated.contains_key(name) {
+                    Err(DatabaseError::ParseError(format!(
+                        "Generated column '{column}' cannot reference generated column '{name}'"
+                    )))
+                } else if columns.iter().any(|col| col.name == *name) {
+                    Ok(())
+                } else {
+                    Err(DatabaseError::ColumnNotFound(name.clone()))
+                }
+            }
+            Expression::Binary { left, right, .. } => {
+                Self::check_expression(left, column, columns, generated)?;
+                Self::check_expression(right, column, columns, generated)
+            }
+            Expression::Function { name, args } => {
+                if !FunctionRegistry::contains(name) || FunctionRegistry::is_aggregate(name) {
+                    return Err(DatabaseError::ParseError(format!(
+                        "Generation expression for column '{column}' can only call native scalar functions, not '{name}'"
+                    )));
+                }
+                args.iter()
+                    .try_for_each(|arg| Self::check_expression(arg, column, columns, generated))
+            }
+            // v2.7.0: every column a CASE reads, its WHEN conditions included
+            Expression::Case(_) => {
+                let mut names = Vec::new();
+                ViewResolver::collect_expression_columns(expr, &mut names);
+                names
+                    .into_iter()
+                    .try_for_each(|name| Self::check_expression(&Expression::Column(name), column, columns, generated))
+            }
+        }
+    }
+
+    /// Does the generation expression `source` reference column `name`?
+    #[must_use]
+    pub fn references(source: &str, name: &str) -> bool {
+        crate::parser::parse_expression(source).is_ok_and(|expr| {
+            let mut names = Vec::new();
+            ViewResolver::collect_expression_columns(&expr, &mut names);
+            names.iter().any(|column| column == name)
+        })
+    }
+
+    /// Name of a generated column whose expression uses `column`, if any
+    #[must_use]
+    pub fn dependent<'a>(generated: &'a HashMap<String, String>, column: &str) -> Option<&'a str> {
+        generated
+            .iter()
+            .find(|(name, source)| name.as_str() != column && Self::references(source, column))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Compute every generated column of a full row in place
+    pub fn compute(
+        compiled: &CompiledGenerated,
+        columns: &[Column],
+        values: &mut [Value],
+    ) -> Result<(), DatabaseError> {
+        for (idx, expr) in compiled {
+            values[*idx] = FunctionExecutor::evaluate_values(expr, columns, values)?;
+        }
+        Ok(())
+    }
+
+    /// Drop generated columns from an INSERT column/value list
+    ///
+    /// NULL stands in for DEFAULT; any other explicit value is rejected. A
+    /// positional VALUES list may also leave the generated columns out entirely.
+    pub fn prepare_insert(
+        columns: &[Column],
+        generated: &HashMap<String, String>,
+        insert_columns: Option<Vec<String>>,
+        values: Vec<Value>,
+    ) -> Result<(Option<Vec<String>>, Vec<Value>), DatabaseError> {
+        let names: Vec<String> = match insert_columns {
+            Some(names) => names,
+            None if values.len() == columns.len() - generated.len() => columns
+                .iter()
+                .filter(|col| !generated.contains_key(&col.name))
+                .map(|col| col.name.clone())
+                .collect(),
+            None => columns.iter().map(|col| col.name.clone()).collect(),
+        };
+
+        let mut kept_names = Vec::new();
+        let mut kept_values = Vec::new();
+        for (name, value) in names.into_iter().zip(values) {
+            if generated.contains_key(&name) {
+                if !matches!(value, Value::Null) {
+                    return Err(DatabaseError::GeneratedAlways(name));
+                }
+            } else {
+                kept_names.push(name);
+                kept_values.push(value);
+            }
+        }
+
+        Ok((Some(kept_names), kept_values))
+    }
+
+    /// Reject UPDATE ... SET on a generated column
+    pub fn check_assignments(
+        generated: &HashMap<String, String>,
+        assignments: &[(String, Expression)],
+    ) -> Result<(), DatabaseError> {
+        match assignments.iter().find(|(name, _)| generated.contains_key(name)) {
+            Some((name, _)) => Err(DatabaseError::GeneratedAlways(name.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryResult};
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::{Collation, Database, DataType};
+
+    fn column(name: &str) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: DataType::Integer,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            foreign_key: None,
+            collation: Collation::C,
+        }
+    }
+
+    #[test]
+    fn test_compile_and_compute() {
+        let columns = vec![column("price"), column("qty"), column("total")];
+        let generated = HashMap::from([("total".to_string(), "price * qty".to_string())]);
+
+        let compiled = GeneratedColumns::compile(&columns, &generated).unwrap();
+        let mut values = vec![Value::Integer(5), Value::Integer(3), Value::Null];
+        GeneratedColumns::compute(&compiled, &columns, &mut values).unwrap();
+        assert_eq!(values[2], Value::Integer(15));
+
+        assert!(GeneratedColumns::references("price * qty", "qty"));
+        assert!(!GeneratedColumns::references("price * qty", "total"));
+    }
+
+    #[test]
+    fn test_invalid_expressions_rejected() {
+        let columns = vec![column("a"), column("b"), column("c")];
+
+        let chained = HashMap::from([
+            ("b".to_string(), "a + 1".to_string()),
+            ("c".to_string(), "b + 1".to_string()),
+        ]);
+        assert!(GeneratedColumns::compile(&columns, &chained).is_err());
+
+        let unknown = HashMap::from([("c".to_string(), "missing + 1".to_string())]);
+        assert!(GeneratedColumns::compile(&columns, &unknown).is_err());
+
+        let volatile = HashMap::from([("c".to_string(), "version()".to_string())]);
+        assert!(GeneratedColumns::compile(&columns, &volatile).is_err());
+    }
+
+    #[test]
+    fn test_prepare_insert() {
+        let columns = vec![column("a"), column("b"), column("c")];
+        let generated = HashMap::from([("c".to_string(), "a + b".to_string())]);
+
+        // Positional list without the generated column
+        let (names, values) =
+            GeneratedColumns::prepare_insert(&columns, &generated, None, vec![Value::Integer(1), Value::Integer(2)])
+                .unwrap();
+        assert_eq!(names.unwrap(), vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(values.len(), 2);
+
+        // Explicit value for the generated column
+        let explicit = GeneratedColumns::prepare_insert(
+            &columns,
+            &generated,
+            Some(vec!["a".to_string(), "c".to_string()]),
+            vec![Value::Integer(1), Value::Integer(9)],
+        );
+        assert!(matches!(explicit, Err(DatabaseError::GeneratedAlways(ref c)) if c == "c"));
+    }
+
+    #[test]
+    fn test_generated_columns() {
+        // v2.7.0: GENERATED ALWAYS AS (...) STORED
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+
+        let totals = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(
+            &mut db,
+            &mut storage,
+            &tx_manager,
+            "CREATE TABLE items (price INTEGER, qty INTEGER, total INTEGER GENERATED ALWAYS AS (price * qty) STORED)",
+        )
+        .unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items (price, qty) VALUES (5, 3)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items VALUES (2, 4)").unwrap();
+        assert_eq!(
+            totals(run_sql(&mut db, &mut storage, &tx_manager, "SELECT total FROM items ORDER BY total").unwrap()),
+            vec!["8".to_string(), "15".to_string()]
+        );
+
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE items SET qty = 10 WHERE price = 5").unwrap();
+        match run_sql(&mut db, &mut storage, &tx_manager, "SELECT price, qty, total FROM items ORDER BY price").unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(rows, vec![vec!["2", "4", "8"], vec!["5", "10", "50"]]),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO items (price, total) VALUES (1, 1)").unwrap_err();
+        assert_eq!(err.to_string(), "column \"total\" is a generated column and can only be set to DEFAULT");
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "UPDATE items SET total = 0").unwrap_err();
+        assert!(matches!(err, DatabaseError::GeneratedAlways(ref c) if c == "total"));
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "ALTER TABLE items DROP COLUMN qty").is_err());
+        assert!(run_sql(
+            &mut db,
+            &mut storage,
+            &tx_manager,
+            "CREATE TABLE bad (a INTEGER, b INTEGER GENERATED ALWAYS AS (missing + 1) STORED)",
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,152 @@
+/// GROUP BY groups that spill to disk: partitioned hash aggregation (v2.7.0)
+///
+/// Rows are gathered per group in a hash table. Once the rows held reach
+/// `work_mem`, a row of a group that is not in the table yet goes to one of
+/// `PARTITIONS` temp files instead, picked by the hash of its group key, so
+/// every group ends up whole in one place. The groups in memory are finished
+/// first; then each partition is read back and grouped the same way, spilling
+/// again with a different hash if it is still too large.
+///
+/// Without a data directory every group stays in memory, up to the query's
+/// `max_query_memory`.
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use crate::storage::{TempFile, TempFiles};
+use crate::types::{DatabaseError, Row};
+use super::memory::QueryMemory;
+
+/// Temp files a spilling pass splits its remaining groups into
+const PARTITIONS: usize = 8;
+/// Passes after which a partition is grouped in memory whatever its size
+const MAX_DEPTH: u64 = 4;
+
+pub struct HashGroups {
+    work_mem: usize,
+    temp_files: Option<TempFiles>,
+    /// Spilling pass, mixed into the partition hash
+    depth: u64,
+    groups: HashMap<Vec<String>, Vec<Row>>,
+    /// Serialized size of the rows in `groups`
+    bytes: usize,
+    partitions: Vec<Option<TempFile>>,
+    /// Memory of the query the rows in `groups` count against
+    memory: QueryMemory,
+}
+
+impl HashGroups {
+    #[must_use]
+    pub fn new(work_mem: usize, temp_files: Option<TempFiles>) -> Self {
+        Self::at_depth(work_mem, temp_files, 0, QueryMemory::unlimited())
+    }
+
+    /// Count the rows held in memory against the query's `memory` (v2.7.0)
+    #[must_use]
+    pub fn with_memory(mut self, memory: QueryMemory) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    fn at_depth(work_mem: usize, temp_files: Option<TempFiles>, depth: u64, memory: QueryMemory) -> Self {
+        Self {
+            work_mem,
+            temp_files,
+            depth,
+            groups: HashMap::new(),
+            bytes: 0,
+            partitions: (0..PARTITIONS).map(|_| None).collect(),
+            memory,
+        }
+    }
+
+    pub fn push(&mut self, key: Vec<String>, row: Row) -> Result<(), DatabaseError> {
+        if let Some(rows) = self.groups.get_mut(&key) {
+            let size = Self::size(&row);
+            self.bytes += size;
+            rows.push(row);
+            return self.memory.reserve(size);
+        }
+
+        if self.bytes >= self.work_mem
+            && self.depth < MAX_DEPTH
+            && let Some(temp_files) = &self.temp_files
+        {
+            let partition = self.partition(&key);
+            let file = match &mut self.partitions[partition] {
+                Some(file) => file,
+                slot => slot.insert(temp_files.create()?),
+            };
+            return file.append(&(key, row));
+        }
+
+        let size = Self::size(&row);
+        self.bytes += size;
+        self.groups.insert(key, vec![row]);
+        self.memory.reserve(size)
+    }
+
+    /// Temp files written so far
+    #[must_use]
+    pub fn spilled_partitions(&self) -> usize {
+        self.partitions.iter().flatten().count()
+    }
+
+    /// Hand every group to `emit`, those in memory first, then the spilled ones
+    pub fn finish(self, emit: &mut dyn FnMut(Vec<String>, Vec<Row>) -> Result<(), DatabaseError>) -> Result<(), DatabaseError> {
+        for (key, rows) in self.groups {
+            emit(key, rows)?;
+        }
+        self.memory.release(self.bytes);
+
+        for file in self.partitions.into_iter().flatten() {
+            let mut groups = Self::at_depth(self.work_mem, self.temp_files.clone(), self.depth + 1, self.memory.clone());
+            for entry in file.read::<(Vec<String>, Row)>()? {
+                let (key, row) = entry?;
+                groups.push(key, row)?;
+            }
+            groups.finish(emit)?;
+        }
+        Ok(())
+    }
+
+    fn partition(&self, key: &[String]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        self.depth.hash(&mut hasher);
+        key.hash(&mut hasher);
+        usize::try_from(hasher.finish() % PARTITIONS as u64).unwrap_or(0)
+    }
+
+    fn size(row: &Row) -> usize {
+        bincode::serialized_size(row).map_or(0, |size| usize::try_from(size).unwrap_or(usize::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Value;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_groups_spill_and_come_back_whole() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_files = TempFiles::open(temp_dir.path());
+        let mut groups = HashGroups::new(256, Some(temp_files.clone()));
+        for n in 0..400_i64 {
+            groups.push(vec![(n % 50).to_string()], Row::new(vec![Value::Integer(n)])).unwrap();
+        }
+        assert!(groups.spilled_partitions() > 0);
+
+        let mut seen: HashMap<String, Vec<i64>> = HashMap::new();
+        let mut emit = |key: Vec<String>, rows: Vec<Row>| {
+            let values = rows.iter().map(|row| row.values[0].as_int().unwrap()).collect();
+            assert!(seen.insert(key[0].clone(), values).is_none(), "group {key:?} emitted twice");
+            Ok(())
+        };
+        groups.finish(&mut emit).unwrap();
+
+        assert_eq!(seen.len(), 50);
+        assert!(seen.iter().all(|(key, values)| values.len() == 8 && values.iter().all(|n| (n % 50).to_string() == *key)));
+        assert_eq!(std::fs::read_dir(temp_files.dir()).unwrap().count(), 0);
+    }
+}
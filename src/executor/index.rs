@@ -1,7 +1,7 @@
 /// Index management executor
 ///
 /// Handles CREATE INDEX, DROP INDEX operations
-use crate::types::{Database, DatabaseError};
+use crate::types::{Database, DatabaseError, Row};
 use crate::executor::QueryResult;
 use crate::index::{Index, IndexType, BTreeIndex, HashIndex};
 
@@ -51,7 +51,7 @@ impl IndexExecutor {
                     Index::BTree(BTreeIndex::new_composite(
                         name.clone(),
                         table_name.clone(),
-                        column_names.clone(),
+                        column_names,
                         unique,
                     ))
                 }
@@ -59,7 +59,7 @@ impl IndexExecutor {
                     Index::Hash(HashIndex::new_composite(
                         name.clone(),
                         table_name.clone(),
-                        column_names.clone(),
+                        column_names,
                         unique,
                     ))
                 }
@@ -90,9 +90,20 @@ impl IndexExecutor {
         let paged_table = database_storage.get_paged_table(&table_name)
             .ok_or_else(|| DatabaseError::TableNotFound(table_name.clone()))?;
         let rows = paged_table.get_all_rows()?;
+        Self::populate(&mut index, &column_indices, &rows)?;
 
+        // Store index
+        db.indexes.insert(name, index);
+
+        Ok(QueryResult::Success("CREATE INDEX".to_string()))
+    }
+
+    /// Add an entry for every row to `index`, keyed by the row's position
+    ///
+    /// `column_indices` are the positions of the indexed columns in the table.
+    pub fn populate(index: &mut Index, column_indices: &[usize], rows: &[Row]) -> Result<(), DatabaseError> {
         for (row_idx, row) in rows.iter().enumerate() {
-            if is_composite {
+            if index.is_composite() {
                 // Extract values for all indexed columns
                 let values: Vec<_> = column_indices.iter()
                     .map(|&idx| row.values[idx].clone())
@@ -104,15 +115,7 @@ impl IndexExecutor {
                 index.insert(value, row_idx)?;
             }
         }
-
-        // Store index
-        db.indexes.insert(name.clone(), index);
-
-        let columns_str = column_names.join(", ");
-        Ok(QueryResult::Success(format!(
-            "Index '{}' created on {}.({}) using {}",
-            name, table_name, columns_str, index_type.as_str()
-        )))
+        Ok(())
     }
 
     /// Execute DROP INDEX
@@ -120,20 +123,70 @@ impl IndexExecutor {
         db: &mut Database,
         name: String,
     ) -> Result<QueryResult, DatabaseError> {
-        if db.indexes.remove(&name).is_none() {
+        let Some(index) = db.indexes.remove(&name) else {
             return Err(DatabaseError::ParseError(
                 format!("Index '{name}' does not exist")
             ));
+        };
+        db.activity.forget_index(&name);  // v2.7.0
+        db.descriptions.forget_relation(&name);  // v2.7.0
+        // v2.7.0: The table is no longer clustered on it
+        if let Some(metadata) = db.table_metadata.get_mut(index.table_name())
+            && metadata.clustered_on.as_deref() == Some(name.as_str())
+        {
+            metadata.clustered_on = None;
         }
 
-        Ok(QueryResult::Success(format!("Index '{name}' dropped")))
+        Ok(QueryResult::Success("DROP INDEX".to_string()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Table, Column, DataType, Row, Value};
+    use crate::executor::run_sql;
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::{Collation, Table, Column, DataType, Row, Value};
+
+    fn names(result: Result<QueryResult, DatabaseError>) -> Vec<String> {
+        match result.unwrap() {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_drop_index_forgets_the_index() {
+        // v2.7.0: Indexes live in memory only, so DROP INDEX releases the
+        // index itself and what is kept about it: counters and CLUSTER
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE users (id INTEGER, name TEXT)",
+            "INSERT INTO users VALUES (1, 'ann'), (2, 'bob')",
+            "CREATE INDEX idx_users_id ON users (id)",
+            "CLUSTER users USING idx_users_id",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+        assert_eq!(names(run_sql(&mut db, &mut storage, &tx_manager, "SELECT name FROM users WHERE id = 1")), ["ann"]);
+        assert_eq!(db.activity.index("idx_users_id").idx_scan, 1);
+
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP INDEX idx_users_id").unwrap();
+        assert!(!db.indexes.contains_key("idx_users_id"));
+        assert_eq!(db.activity.index("idx_users_id").idx_scan, 0);
+        assert_eq!(db.table_metadata["users"].clustered_on, None);
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "DROP INDEX idx_users_id").is_err());
+
+        // Queries scan the table again; an index of the same name starts afresh
+        assert_eq!(names(run_sql(&mut db, &mut storage, &tx_manager, "SELECT name FROM users WHERE id = 1")), ["ann"]);
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (3, 'cy')").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE INDEX idx_users_id ON users (name)").unwrap();
+        assert_eq!(names(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM users WHERE name = 'cy'")), ["3"]);
+        assert_eq!(db.activity.index("idx_users_id").idx_scan, 1);
+    }
 
     #[test]
     #[ignore]
@@ -151,6 +204,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "name".to_string(),
@@ -159,6 +213,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("users".to_string(), columns);
@@ -204,6 +259,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("products".to_string(), columns);
@@ -248,6 +304,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("users".to_string(), columns);
@@ -294,6 +351,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("users".to_string(), columns);
@@ -332,6 +390,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "age".to_string(),
@@ -340,6 +399,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("users".to_string(), columns);
@@ -392,6 +452,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "last_name".to_string(),
@@ -400,6 +461,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("people".to_string(), columns);
@@ -457,6 +519,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
             Column {
                 name: "provider".to_string(),
@@ -465,6 +528,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ];
         let table = Table::new("accounts".to_string(), columns);
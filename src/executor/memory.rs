@@ -0,0 +1,85 @@
+/// Memory accounting of a query (v2.7.0)
+///
+/// Sorts, hash tables and result buffers add the estimated size of the rows
+/// they hold to the query's `QueryMemory` - their serialized size, cheap to
+/// compute and close to what the rows take. Operators that can spill do so
+/// once their share passes `work_mem` and give the spilled bytes back. What
+/// has to stay in memory (no data directory to spill to, window functions,
+/// the result itself) counts against `max_query_memory`: a query going past
+/// it fails with "out of memory" (SQLSTATE 53200) instead of taking the whole
+/// server down with it.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::Serialize;
+use crate::types::DatabaseError;
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryMemory {
+    /// Bytes the query may hold, 0 = unlimited
+    limit: usize,
+    /// Bytes held now, shared by the operators of the query
+    used: Arc<AtomicUsize>,
+}
+
+impl QueryMemory {
+    /// Accounting for a new query that may hold `limit` bytes (0 = unlimited)
+    #[must_use]
+    pub fn new(limit: usize) -> Self {
+        Self { limit, used: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Accounting without a limit
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self::new(0)
+    }
+
+    /// `bytes` more are held, an error once that is past the limit
+    pub fn reserve(&self, bytes: usize) -> Result<(), DatabaseError> {
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed).saturating_add(bytes);
+        if self.limit > 0 && used > self.limit {
+            return Err(DatabaseError::OutOfMemory(self.limit / 1024));
+        }
+        Ok(())
+    }
+
+    /// `bytes` are no longer held (written to a temp file, or dropped)
+    pub fn release(&self, bytes: usize) {
+        let _ = self.used.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| Some(used.saturating_sub(bytes)));
+    }
+
+    /// Bytes held now
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Estimated size of `item` in memory: its serialized size
+    #[must_use]
+    pub fn size_of<T: Serialize + ?Sized>(item: &T) -> usize {
+        bincode::serialized_size(item).map_or(0, |size| usize::try_from(size).unwrap_or(usize::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_past_limit() {
+        let memory = QueryMemory::new(4096);
+        let operator = memory.clone();
+        memory.reserve(3000).unwrap();
+        assert!(matches!(operator.reserve(2000), Err(DatabaseError::OutOfMemory(4))));
+
+        // Spilled bytes make room again
+        operator.release(2000);
+        memory.release(2000);
+        assert_eq!(memory.used(), 1000);
+        operator.reserve(3000).unwrap();
+
+        let unlimited = QueryMemory::unlimited();
+        unlimited.reserve(usize::MAX).unwrap();
+        unlimited.reserve(1).unwrap();
+    }
+}
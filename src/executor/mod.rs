@@ -28,12 +28,39 @@ pub mod system_catalogs;  // v2.0.0
 pub mod system_functions;  // v2.0.0
 pub mod subquery;  // v2.6.0
 pub mod window;  // v2.6.0
+pub mod pipeline;  // v2.7.0
+pub mod plan_cache;  // v2.7.0
+pub mod query_stats;  // v2.7.0
+pub mod activity;  // v2.7.0
+pub mod sessions;  // v2.7.0
+pub mod views;  // v2.7.0
+pub mod functions;  // v2.7.0
+pub mod udf;  // v2.7.0
+pub mod temp_tables;  // v2.7.0
+pub mod generated;  // v2.7.0
+pub mod partitions;  // v2.7.0
+pub mod fdw;  // v2.7.0
+pub mod from_tables;  // v2.7.0
+pub mod set_operations;  // v2.7.0
+pub mod derived_tables;  // v2.7.0
+pub mod planner;  // v2.7.0
+pub mod sort;  // v2.7.0
+pub mod hash_agg;  // v2.7.0
+pub mod vectorized;  // v2.7.0
+pub mod analyze;  // v2.7.0
+pub mod cluster;  // v2.7.0
+pub mod cursors;  // v2.7.0
+pub mod dependencies;  // v2.7.0
+pub mod flashback;  // v2.7.0
+pub mod constraints;  // v2.7.0
+pub mod system_columns;  // v2.7.0
+pub mod memory;  // v2.7.0
 
 // Re-export main executor
 pub use dispatcher_executor::{QueryExecutor, QueryResult};
 
 // Re-export new modular components
-pub use storage_adapter::RowStorage;
+pub use storage_adapter::{RowStorage, ScanProjection, ScanPushdown};  // v2.7.0: pushdown
 pub use conditions::ConditionEvaluator;
 pub use dml::DmlExecutor;
 pub use ddl::DdlExecutor;
@@ -44,6 +71,36 @@ pub use explain::ExplainExecutor;  // v1.8.0
 pub use system_catalogs::SystemCatalog;  // v2.0.0
 pub use system_functions::SystemFunctions;  // v2.0.0
 pub use subquery::{SubqueryExecutor, SubqueryContext};  // v2.6.0
+pub use plan_cache::PlanCache;  // v2.7.0
+pub use flashback::Flashback;  // v2.7.0
+pub use query_stats::QueryStats;  // v2.7.0
+pub use activity::ActivityStats;  // v2.7.0
+pub use analyze::{AnalyzeExecutor, Statistics};  // v2.7.0
+pub use cluster::ClusterExecutor;  // v2.7.0
+pub use sessions::{Backend, BackendInfo, DatabaseLock, SessionRegistry};  // v2.7.0
+pub use views::ViewResolver;  // v2.7.0
+pub use dependencies::{DbObject, Dependency, DependencyGraph, DependencyKind};  // v2.7.0
+pub use functions::FunctionExecutor;  // v2.7.0
+pub use udf::{Accumulator, FunctionRegistry};  // v2.7.0
+pub use temp_tables::TempTables;  // v2.7.0
+pub use generated::GeneratedColumns;  // v2.7.0
+pub use constraints::TableConstraints;  // v2.7.0
+pub use system_columns::SystemColumns;  // v2.7.0
+pub use partitions::Partitions;  // v2.7.0
+pub use fdw::{ForeignDataWrapper, ForeignTables};  // v2.7.0
+pub use set_operations::SetOperations;  // v2.7.0
+pub use derived_tables::DerivedTables;  // v2.7.0
 
 #[cfg(feature = "page_storage")]
 pub use storage_adapter::PagedStorage;
+
+/// Test helper - parse one statement and run it through the executor (autocommit)
+#[cfg(test)]
+pub(crate) fn run_sql(
+    db: &mut crate::types::Database,
+    storage: &mut crate::storage::DatabaseStorage,
+    tx_manager: &crate::transaction::GlobalTransactionManager,
+    sql: &str,
+) -> Result<QueryResult, crate::types::DatabaseError> {
+    QueryExecutor::execute(db, crate::parser::parse_statement(sql).unwrap(), None, tx_manager, storage, None)
+}
@@ -0,0 +1,522 @@
+/// Declarative table partitioning (v2.7.0)
+///
+/// `CREATE TABLE t (...) PARTITION BY RANGE|LIST (col)` creates a partitioned
+/// table that stores no rows itself. `CREATE TABLE p PARTITION OF t FOR VALUES ...`
+/// adds a partition: a regular table with the parent's columns. INSERTs into the
+/// parent are routed by the key value; scans, UPDATEs and DELETEs only visit the
+/// partitions whose bounds can satisfy the WHERE clause.
+use crate::parser::{AlterTableOperation, Condition, Expression};
+use crate::storage::{DatabaseStorage, PagedTableScan, StorageEngine};
+use crate::types::{
+    Column, DataType, Database, DatabaseError, PartitionBound, PartitionStrategy, Partitioning, Table, Value,
+};
+use crate::types::partition::compare_values;
+use super::dispatcher_executor::QueryResult;
+use std::cmp::Ordering;
+
+/// Values of the partition key allowed by a WHERE clause, as one interval
+/// (bound value, inclusive); `None` is unbounded
+struct KeyRange<'a> {
+    low: Option<(&'a Value, bool)>,
+    high: Option<(&'a Value, bool)>,
+}
+
+impl KeyRange<'_> {
+    /// May some value of the interval lie in `bound`?
+    fn overlaps(&self, bound: &PartitionBound) -> bool {
+        match bound {
+            PartitionBound::Range { from, to } => {
+                // Unknown ordering (mixed types) keeps the partition
+                let starts_before_high = match (from, self.high) {
+                    (Some(from), Some((high, inclusive))) => match compare_values(from, high) {
+                        Some(Ordering::Less) => true,
+                        Some(Ordering::Equal) => inclusive,
+                        Some(Ordering::Greater) => false,
+                        None => true,
+                    },
+                    _ => true,
+                };
+                let ends_after_low = match (to, self.low) {
+                    (Some(to), Some((low, _))) => compare_values(low, to).is_none_or(Ordering::is_lt),
+                    _ => true,
+                };
+                starts_before_high && ends_after_low
+            }
+            PartitionBound::List(values) => values.iter().any(|value| self.contains(value)),
+            PartitionBound::Default => true,
+        }
+    }
+
+    fn contains(&self, value: &Value) -> bool {
+        let above_low = self.low.is_none_or(|(low, inclusive)| match compare_values(value, low) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Equal) => inclusive,
+            Some(Ordering::Less) => false,
+            None => true,
+        });
+        let below_high = self.high.is_none_or(|(high, inclusive)| match compare_values(value, high) {
+            Some(Ordering::Less) => true,
+            Some(Ordering::Equal) => inclusive,
+            Some(Ordering::Greater) => false,
+            None => true,
+        });
+        above_low && below_high
+    }
+}
+
+pub struct Partitions;
+
+impl Partitions {
+    /// Execute CREATE TABLE ... PARTITION OF
+    ///
+    /// Validates:
+    /// - the parent exists and is partitioned
+    /// - the bound matches the partitioning strategy
+    /// - the bound does not overlap another partition
+    pub fn create_partition(
+        db: &mut Database,
+        name: String,
+        parent: &str,
+        bound: PartitionBound,
+        owner: Option<String>,
+        database_storage: &mut DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        if db.tables.contains_key(&name) {
+            return Err(DatabaseError::TableAlreadyExists(name));
+        }
+        let parent_table = db
+            .get_table(parent)
+            .ok_or_else(|| DatabaseError::TableNotFound(parent.to_string()))?;
+        let Some(partitioning) = &parent_table.partitioning else {
+            return Err(DatabaseError::ParseError(format!("Table '{parent}' is not partitioned")));
+        };
+
+        let strategy_matches = matches!(
+            (&bound, partitioning.strategy),
+            (PartitionBound::Range { .. }, PartitionStrategy::Range)
+                | (PartitionBound::List(_), PartitionStrategy::List)
+                | (PartitionBound::Default, _)
+        );
+        if !strategy_matches {
+            return Err(DatabaseError::ParseError(format!(
+                "Invalid bound specification for a {:?} partition of '{parent}'",
+                partitioning.strategy
+            )));
+        }
+        if let PartitionBound::Range { from: Some(from), to: Some(to) } = &bound
+            && compare_values(from, to).is_none_or(Ordering::is_ge)
+        {
+            return Err(DatabaseError::ParseError(format!(
+                "Empty range bound for partition '{name}': {from} is not below {to}"
+            )));
+        }
+        if let Some((other, _)) = partitioning.partitions.iter().find(|(_, other)| bound.overlaps(other)) {
+            return Err(DatabaseError::ParseError(format!(
+                "Partition '{name}' would overlap partition '{other}'"
+            )));
+        }
+
+        let mut table = Table::new_with_owner(
+            name.clone(),
+            parent_table.columns.clone(),
+            owner.unwrap_or_else(|| parent_table.owner.clone()),
+        );
+        table.generated.clone_from(&parent_table.generated);
+
+        database_storage.create_table(name.clone())?;
+        db.create_table(table)?;
+        if let Some(partitioning) = db.get_table_mut(parent).and_then(|t| t.partitioning.as_mut()) {
+            partitioning.partitions.push((name, bound));
+        }
+
+        Ok(QueryResult::Success("CREATE TABLE".to_string()))
+    }
+
+    /// Check the PARTITION BY clause of a new table against its columns
+    pub fn check_partition_key(columns: &[Column], partitioning: &Partitioning) -> Result<(), DatabaseError> {
+        let column = columns
+            .iter()
+            .find(|col| col.name == partitioning.column)
+            .ok_or_else(|| DatabaseError::ColumnNotFound(partitioning.column.clone()))?;
+
+        if matches!(column.data_type, DataType::Json | DataType::Jsonb | DataType::Bytea) {
+            return Err(DatabaseError::ParseError(format!(
+                "Column '{}' cannot be used as a partition key",
+                column.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Parent table and bound of `name`, if it is a partition
+    #[must_use]
+    pub fn parent_of<'a>(db: &'a Database, name: &str) -> Option<(&'a Table, &'a PartitionBound)> {
+        db.tables.values().find_map(|table| {
+            let partitioning = table.partitioning.as_ref()?;
+            partitioning
+                .partitions
+                .iter()
+                .find(|(partition, _)| partition == name)
+                .map(|(_, bound)| (table, bound))
+        })
+    }
+
+    /// Prepare an INSERT into `table`
+    ///
+    /// For a partitioned table returns the partition to insert into, with the
+    /// row reordered to the full column list (SERIAL values are taken from the
+    /// parent's sequences, so they stay unique across partitions). For a
+    /// partition the row is checked against the partition bound.
+    pub fn route_insert(
+        db: &mut Database,
+        table: &str,
+        columns: Option<&Vec<String>>,
+        values: &[Value],
+        storage: Option<&mut StorageEngine>,
+    ) -> Result<Option<(String, Vec<String>, Vec<Value>)>, DatabaseError> {
+        let Some(parent) = db.tables.get_mut(table) else {
+            return Ok(None);
+        };
+        let Some(partitioning) = &parent.partitioning else {
+            if let Some((parent, bound)) = Self::parent_of(db, table) {
+                let row = super::dml::DmlExecutor::reorder_values(&parent.columns, columns.cloned(), values.to_vec())?;
+                let key = Self::key(parent.partitioning.as_ref(), &parent.columns, &row);
+                if !Self::accepts(parent.partitioning.as_ref(), bound, key) {
+                    return Err(Self::no_partition_error(table, key));
+                }
+            }
+            return Ok(None);
+        };
+
+        let mut row = super::dml::DmlExecutor::reorder_values(&parent.columns, columns.cloned(), values.to_vec())?;
+        if row.len() != parent.columns.len() {
+            return Err(DatabaseError::ColumnCountMismatch);
+        }
+        let key = Self::key(Some(partitioning), &parent.columns, &row);
+        let partition = partitioning
+            .route(key)
+            .map(str::to_string)
+            .ok_or_else(|| Self::no_partition_error(table, key))?;
+
+        let mut storage = storage;
+        for (idx, column) in parent.columns.iter().enumerate() {
+            if matches!(column.data_type, DataType::Serial | DataType::BigSerial) && row[idx] == Value::Null {
+                let value = parent.sequences.entry(column.name.clone()).or_default().next_value();
+                if let Some(storage) = storage.as_deref_mut() {
+                    storage.log_serial_value(table, &column.name, value)?;
+                }
+                row[idx] = Value::Integer(value);
+            }
+        }
+
+        let names = parent.columns.iter().map(|col| col.name.clone()).collect();
+        Ok(Some((partition, names, row)))
+    }
+
+    /// Check that an UPDATE of the partition key keeps every row in its partition
+    ///
+    /// Moving rows between partitions is not supported: the new key value must
+    /// belong to the only partition the WHERE clause can reach. v2.7.0: The key
+    /// can only be set to a constant, so this is known before any row is read.
+    pub fn check_update(
+        db: &Database,
+        table: &str,
+        assignments: &[(String, Expression)],
+        filter: Option<&Condition>,
+    ) -> Result<(), DatabaseError> {
+        let key_value = |partitioning: &Partitioning| -> Result<Option<&Value>, DatabaseError> {
+            match assignments.iter().find(|(col, _)| *col == partitioning.column) {
+                None => Ok(None),
+                Some((_, Expression::Literal(value))) => Ok(Some(value)),
+                Some(_) => Err(DatabaseError::ParseError(format!(
+                    "Partition key '{}' of '{table}' can only be set to a constant",
+                    partitioning.column
+                ))),
+            }
+        };
+
+        if let Some(partitioning) = db.get_table(table).and_then(|t| t.partitioning.as_ref())
+            && let Some(value) = key_value(partitioning)?
+        {
+            let target = partitioning.route(value);
+            let reached = Self::prune(partitioning, filter);
+            if target.is_none() || reached.iter().any(|name| Some(*name) != target) {
+                return Err(DatabaseError::ParseError(format!(
+                    "Updating partition key '{}' would move rows between partitions of '{table}'",
+                    partitioning.column
+                )));
+            }
+            return Ok(());
+        }
+
+        if let Some((parent, bound)) = Self::parent_of(db, table)
+            && let Some(partitioning) = &parent.partitioning
+            && let Some(value) = key_value(partitioning)?
+            && !Self::accepts(Some(partitioning), bound, value)
+        {
+            return Err(Self::no_partition_error(table, value));
+        }
+        Ok(())
+    }
+
+    /// Partitions of `table` that an UPDATE/DELETE with `filter` has to visit;
+    /// `None` if the table is not partitioned
+    #[must_use]
+    pub fn write_targets(db: &Database, table: &str, filter: Option<&Condition>) -> Option<Vec<String>> {
+        let partitioning = db.get_table(table)?.partitioning.as_ref()?;
+        Some(Self::prune(partitioning, filter).into_iter().map(str::to_string).collect())
+    }
+
+    /// Scan of `from`: the table itself, or the partitions (recursively) left
+    /// after pruning with `filter`. Counted as a sequential scan of every table read.
+    pub fn scan<'a>(
+        db: &Database,
+        database_storage: &'a DatabaseStorage,
+        from: &str,
+        filter: Option<&Condition>,
+    ) -> Result<PagedTableScan<'a>, DatabaseError> {
+        let leaves = Self::leaf_tables(db, from, filter);
+        for leaf in &leaves {
+            db.activity.count_seq_scan(leaf);
+        }
+        Self::open(database_storage, leaves)
+    }
+
+    /// Like `scan`, but not counted in the activity statistics (index scans
+    /// resolve row ordinals through it)
+    pub fn scan_for_index<'a>(
+        db: &Database,
+        database_storage: &'a DatabaseStorage,
+        from: &str,
+        filter: Option<&Condition>,
+    ) -> Result<PagedTableScan<'a>, DatabaseError> {
+        Self::open(database_storage, Self::leaf_tables(db, from, filter))
+    }
+
+    fn open(database_storage: &DatabaseStorage, tables: Vec<String>) -> Result<PagedTableScan<'_>, DatabaseError> {
+        let tables = tables
+            .into_iter()
+            .map(|name| database_storage.get_paged_table(&name).ok_or(DatabaseError::TableNotFound(name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PagedTableScan::over(tables))
+    }
+
+    /// Tables holding the rows of `name` that may match `filter`
+    #[must_use]
+    pub fn leaf_tables(db: &Database, name: &str, filter: Option<&Condition>) -> Vec<String> {
+        match db.get_table(name).and_then(|t| t.partitioning.as_ref()) {
+            Some(partitioning) => Self::prune(partitioning, filter)
+                .into_iter()
+                .flat_map(|partition| Self::leaf_tables(db, partition, filter))
+                .collect(),
+            None => vec![name.to_string()],
+        }
+    }
+
+    /// Partitions whose bounds can hold rows matching `filter`
+    #[must_use]
+    pub fn prune<'a>(partitioning: &'a Partitioning, filter: Option<&Condition>) -> Vec<&'a str> {
+        partitioning
+            .partitions
+            .iter()
+            .filter(|(_, bound)| filter.is_none_or(|cond| Self::may_match(bound, &partitioning.column, cond)))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Can a row in `bound` satisfy `cond`? Conditions on other columns can't exclude it.
+    fn may_match(bound: &PartitionBound, key: &str, cond: &Condition) -> bool {
+        let range = |low: Option<(&Value, bool)>, high: Option<(&Value, bool)>| KeyRange { low, high }.overlaps(bound);
+
+        match cond {
+            Condition::And(left, right) => Self::may_match(bound, key, left) && Self::may_match(bound, key, right),
+            Condition::Or(left, right) => Self::may_match(bound, key, left) || Self::may_match(bound, key, right),
+            Condition::Equals(col, value) if col == key => range(Some((value, true)), Some((value, true))),
+            Condition::In(col, values) if col == key => {
+                values.iter().any(|value| range(Some((value, true)), Some((value, true))))
+            }
+            Condition::GreaterThan(col, value) if col == key => range(Some((value, false)), None),
+            Condition::GreaterThanOrEqual(col, value) if col == key => range(Some((value, true)), None),
+            Condition::LessThan(col, value) if col == key => range(None, Some((value, false))),
+            Condition::LessThanOrEqual(col, value) if col == key => range(None, Some((value, true))),
+            Condition::Between(col, low, high) if col == key => range(Some((low, true)), Some((high, true))),
+            // NULL keys are only ever stored in the DEFAULT partition
+            Condition::IsNull(col) if col == key => *bound == PartitionBound::Default,
+            _ => true,
+        }
+    }
+
+    /// Release the partitioning links of a table that is being dropped
+    ///
+    /// Returns the partitions of `name` (dropped along with it); a partition is
+    /// removed from its parent's list.
+    pub fn detach(db: &mut Database, name: &str) -> Vec<String> {
+        for table in db.tables.values_mut() {
+            if let Some(partitioning) = &mut table.partitioning {
+                partitioning.partitions.retain(|(partition, _)| partition != name);
+            }
+        }
+
+        db.get_table(name)
+            .and_then(|t| t.partitioning.as_ref())
+            .map(|p| p.partitions.iter().map(|(partition, _)| partition.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Reject schema changes that would make partitions diverge from their parent
+    pub fn check_alter(db: &Database, name: &str, operation: &AlterTableOperation) -> Result<(), DatabaseError> {
+        let partitioned = db.get_table(name).is_some_and(|t| t.partitioning.is_some());
+        if (partitioned || Self::parent_of(db, name).is_some()) && !matches!(operation, AlterTableOperation::OwnerTo(_)) {
+            return Err(DatabaseError::ParseError(format!(
+                "ALTER TABLE is not supported for partitioned table or partition '{name}'"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reject CREATE INDEX on a partitioned table (index each partition instead)
+    pub fn check_index(db: &Database, table: &str) -> Result<(), DatabaseError> {
+        if db.get_table(table).is_some_and(|t| t.partitioning.is_some()) {
+            return Err(DatabaseError::ParseError(format!(
+                "Cannot create index on partitioned table '{table}'; create it on each partition"
+            )));
+        }
+        Ok(())
+    }
+
+    fn key<'a>(partitioning: Option<&Partitioning>, columns: &[Column], row: &'a [Value]) -> &'a Value {
+        partitioning
+            .and_then(|p| columns.iter().position(|col| col.name == p.column))
+            .and_then(|idx| row.get(idx))
+            .unwrap_or(&Value::Null)
+    }
+
+    /// Does the partition with `bound` accept `key`?
+    fn accepts(partitioning: Option<&Partitioning>, bound: &PartitionBound, key: &Value) -> bool {
+        match bound {
+            PartitionBound::Default => partitioning.is_none_or(|p| {
+                !p.partitions.iter().any(|(_, other)| other.contains(key))
+            }),
+            bound => bound.contains(key),
+        }
+    }
+
+    fn no_partition_error(table: &str, key: &Value) -> DatabaseError {
+        DatabaseError::ParseError(format!("No partition of '{table}' accepts key value {key}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::transaction::GlobalTransactionManager;
+    use tempfile::TempDir;
+
+    fn values(result: QueryResult) -> Vec<String> {
+        match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_prune_range() {
+        let mut partitioning = Partitioning::new(PartitionStrategy::Range, "id".to_string());
+        for (name, from, to) in [("p0", 0, 10), ("p1", 10, 20), ("p2", 20, 30)] {
+            partitioning.partitions.push((
+                name.to_string(),
+                PartitionBound::Range { from: Some(Value::Integer(from)), to: Some(Value::Integer(to)) },
+            ));
+        }
+
+        let equals = Condition::Equals("id".to_string(), Value::SmallInt(15));
+        assert_eq!(Partitions::prune(&partitioning, Some(&equals)), vec!["p1"]);
+
+        let below = Condition::LessThan("id".to_string(), Value::Integer(10));
+        assert_eq!(Partitions::prune(&partitioning, Some(&below)), vec!["p0"]);
+
+        let between = Condition::And(
+            Box::new(Condition::GreaterThanOrEqual("id".to_string(), Value::Integer(10))),
+            Box::new(Condition::LessThanOrEqual("id".to_string(), Value::Integer(20))),
+        );
+        assert_eq!(Partitions::prune(&partitioning, Some(&between)), vec!["p1", "p2"]);
+
+        let other = Condition::Equals("name".to_string(), Value::Text("x".to_string()));
+        assert_eq!(Partitions::prune(&partitioning, Some(&other)).len(), 3);
+    }
+
+    #[test]
+    fn test_partitioned_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE events (id SERIAL, ts DATE, kind TEXT) PARTITION BY RANGE (ts)").unwrap();
+        exec(&mut db, "CREATE TABLE events_2023 PARTITION OF events FOR VALUES FROM ('2023-01-01') TO ('2024-01-01')").unwrap();
+        exec(&mut db, "CREATE TABLE events_2024 PARTITION OF events FOR VALUES FROM ('2024-01-01') TO ('2025-01-01')").unwrap();
+        assert!(exec(&mut db, "CREATE TABLE bad PARTITION OF events FOR VALUES FROM ('2024-06-01') TO ('2026-01-01')").is_err());
+        assert!(exec(&mut db, "CREATE TABLE bad PARTITION OF events FOR VALUES IN ('2024-06-01')").is_err());
+
+        exec(&mut db, "INSERT INTO events (ts, kind) VALUES ('2023-05-01', 'a')").unwrap();
+        exec(&mut db, "INSERT INTO events (ts, kind) VALUES ('2024-02-01', 'b')").unwrap();
+        exec(&mut db, "INSERT INTO events (ts, kind) VALUES ('2024-12-31', 'c')").unwrap();
+        assert!(exec(&mut db, "INSERT INTO events (ts, kind) VALUES ('2025-01-01', 'd')").is_err());
+
+        // Rows live in the partitions, SERIAL stays unique across them
+        assert_eq!(values(exec(&mut db, "SELECT kind FROM events_2024").unwrap()), vec!["b", "c"]);
+        assert_eq!(values(exec(&mut db, "SELECT id FROM events ORDER BY id").unwrap()), vec!["1", "2", "3"]);
+        assert_eq!(values(exec(&mut db, "SELECT kind FROM events WHERE ts = '2023-05-01'").unwrap()), vec!["a"]);
+        assert_eq!(values(exec(&mut db, "SELECT COUNT(*) FROM events").unwrap()), vec!["3"]);
+
+        // Direct inserts must respect the partition bound
+        assert!(exec(&mut db, "INSERT INTO events_2023 (ts, kind) VALUES ('2024-02-01', 'x')").is_err());
+
+        exec(&mut db, "CREATE TABLE events_rest PARTITION OF events DEFAULT").unwrap();
+        exec(&mut db, "INSERT INTO events (ts, kind) VALUES ('2025-01-01', 'd')").unwrap();
+        assert_eq!(values(exec(&mut db, "SELECT kind FROM events_rest").unwrap()), vec!["d"]);
+
+        match exec(&mut db, "DELETE FROM events WHERE ts = '2024-12-31' OR kind = 'd'").unwrap() {
+            QueryResult::Success(message) => assert_eq!(message, "DELETE 2"),
+            QueryResult::Rows(..) => panic!("Expected Success result"),
+        }
+        assert!(exec(&mut db, "UPDATE events SET ts = '2024-03-01'").is_err());
+        exec(&mut db, "UPDATE events SET kind = 'z' WHERE ts = '2023-05-01'").unwrap();
+
+        exec(&mut db, "DROP TABLE events").unwrap();
+        assert!(db.tables.is_empty());
+    }
+
+    #[test]
+    fn test_list_partitions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE users (id INTEGER, region TEXT) PARTITION BY LIST (region)").unwrap();
+        exec(&mut db, "CREATE TABLE users_eu PARTITION OF users FOR VALUES IN ('de', 'fr')").unwrap();
+        exec(&mut db, "CREATE TABLE users_us PARTITION OF users FOR VALUES IN ('us')").unwrap();
+        assert!(exec(&mut db, "CREATE INDEX idx_region ON users (region)").is_err());
+
+        exec(&mut db, "INSERT INTO users VALUES (1, 'de')").unwrap();
+        exec(&mut db, "INSERT INTO users VALUES (2, 'us')").unwrap();
+        assert!(exec(&mut db, "INSERT INTO users VALUES (3, 'jp')").is_err());
+
+        let partitioning = db.get_table("users").unwrap().partitioning.clone().unwrap();
+        let filter = Condition::In("region".to_string(), vec![Value::Text("us".to_string())]);
+        assert_eq!(Partitions::prune(&partitioning, Some(&filter)), vec!["users_us"]);
+        assert_eq!(values(exec(&mut db, "SELECT id FROM users WHERE region = 'us'").unwrap()), vec!["2"]);
+        match exec(&mut db, "EXPLAIN SELECT * FROM users WHERE region = 'us'").unwrap() {
+            QueryResult::Rows(rows, _) => assert!(rows.iter().any(|row| row[0].contains("Partitions: 1 of 2 (users_us)"))),
+            QueryResult::Success(_) => panic!("Expected plan rows"),
+        }
+
+        // Dropping a partition detaches it from the parent
+        exec(&mut db, "DROP TABLE users_us").unwrap();
+        assert_eq!(values(exec(&mut db, "SELECT id FROM users").unwrap()), vec!["1"]);
+    }
+}
@@ -0,0 +1,211 @@
+/// Pull-based row pipeline for SELECT (v2.7.0)
+///
+/// Each operator wraps an upstream iterator and yields rows on demand, so a
+/// query like `SELECT * FROM big LIMIT 10` stops scanning after ten matches
+/// instead of materializing the whole table first.
+///
+/// Typical chain:
+/// `PagedTableScan` → `Visible` → `Filter` → projection (`map`) → `Distinct` → `Paginate`
+///
+/// Blocking steps (ORDER BY, window functions) still collect their input,
+/// but everything downstream of them keeps streaming.
+use crate::types::{DatabaseError, Row};
+use crate::transaction::Snapshot;
+use std::collections::HashSet;
+
+/// Boxed row source feeding the pipeline
+pub type RowSource<'a> = Box<dyn Iterator<Item = Result<Row, DatabaseError>> + 'a>;
+
+/// MVCC visibility operator - drops rows not visible to the snapshot
+pub struct Visible<'a, I> {
+    input: I,
+    snapshot: &'a Snapshot,
+}
+
+impl<'a, I> Visible<'a, I> {
+    pub const fn new(input: I, snapshot: &'a Snapshot) -> Self {
+        Self { input, snapshot }
+    }
+}
+
+impl<I> Iterator for Visible<'_, I>
+where
+    I: Iterator<Item = Result<Row, DatabaseError>>,
+{
+    type Item = Result<Row, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.input.next()? {
+                Ok(row) if !row.is_visible_to_snapshot(self.snapshot) => {}
+                other => return Some(other),
+            }
+        }
+    }
+}
+
+/// WHERE operator - keeps rows for which the predicate returns `Ok(true)`
+///
+/// Predicate errors are forwarded downstream instead of being swallowed.
+pub struct Filter<I, P> {
+    input: I,
+    predicate: P,
+}
+
+impl<I, P> Filter<I, P> {
+    pub const fn new(input: I, predicate: P) -> Self {
+        Self { input, predicate }
+    }
+}
+
+impl<I, P> Iterator for Filter<I, P>
+where
+    I: Iterator<Item = Result<Row, DatabaseError>>,
+    P: FnMut(&Row) -> Result<bool, DatabaseError>,
+{
+    type Item = Result<Row, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let row = match self.input.next()? {
+                Ok(row) => row,
+                Err(e) => return Some(Err(e)),
+            };
+
+            match (self.predicate)(&row) {
+                Ok(true) => return Some(Ok(row)),
+                Ok(false) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// DISTINCT operator - remembers only the rows already emitted
+pub struct Distinct<I> {
+    input: I,
+    seen: HashSet<Vec<String>>,
+}
+
+impl<I> Distinct<I> {
+    pub fn new(input: I) -> Self {
+        Self {
+            input,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl<I> Iterator for Distinct<I>
+where
+    I: Iterator<Item = Result<Vec<String>, DatabaseError>>,
+{
+    type Item = Result<Vec<String>, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.input.next()? {
+                Ok(row) => {
+                    if self.seen.insert(row.clone()) {
+                        return Some(Ok(row));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// OFFSET/LIMIT operator
+///
+/// Unlike `skip().take()`, errors produced while skipping are still reported,
+/// and once `limit` rows have been emitted the upstream is never polled again.
+pub struct Paginate<I> {
+    input: I,
+    to_skip: usize,
+    remaining: Option<usize>,
+}
+
+impl<I> Paginate<I> {
+    pub fn new(input: I, offset: Option<usize>, limit: Option<usize>) -> Self {
+        Self {
+            input,
+            to_skip: offset.unwrap_or(0),
+            remaining: limit,
+        }
+    }
+}
+
+impl<T, I> Iterator for Paginate<I>
+where
+    I: Iterator<Item = Result<T, DatabaseError>>,
+{
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        loop {
+            match self.input.next()? {
+                Ok(_) if self.to_skip > 0 => self.to_skip -= 1,
+                Ok(item) => {
+                    if let Some(remaining) = self.remaining.as_mut() {
+                        *remaining -= 1;
+                    }
+                    return Some(Ok(item));
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Value;
+    use std::cell::Cell;
+
+    fn int_rows(n: i64) -> Vec<Result<Row, DatabaseError>> {
+        (0..n).map(|i| Ok(Row::new(vec![Value::Integer(i)]))).collect()
+    }
+
+    #[test]
+    fn test_paginate_short_circuits() {
+        let pulled = Cell::new(0);
+        let source = int_rows(1000).into_iter().inspect(|_| pulled.set(pulled.get() + 1));
+
+        let rows: Vec<Row> = Paginate::new(source, Some(5), Some(3))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].values[0], Value::Integer(5));
+        assert_eq!(pulled.get(), 8); // 5 skipped + 3 emitted, nothing more
+    }
+
+    #[test]
+    fn test_filter_forwards_errors() {
+        let mut filtered = Filter::new(int_rows(3).into_iter(), |row: &Row| match row.values[0] {
+            Value::Integer(1) => Err(DatabaseError::ParseError("boom".to_string())),
+            _ => Ok(true),
+        });
+
+        assert!(filtered.next().unwrap().is_ok());
+        assert!(filtered.next().unwrap().is_err());
+        assert!(filtered.next().unwrap().is_ok());
+        assert!(filtered.next().is_none());
+    }
+
+    #[test]
+    fn test_distinct() {
+        let source = ["a", "b", "a", "c", "b"]
+            .iter()
+            .map(|s| Ok(vec![(*s).to_string()]));
+
+        let rows: Vec<Vec<String>> = Distinct::new(source).collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows.len(), 3);
+    }
+}
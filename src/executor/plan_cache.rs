@@ -0,0 +1,379 @@
+/// Per-database query plan cache (v2.7.0)
+///
+/// Queries are normalized into a template where literals are replaced by
+/// `$n` placeholders (`SELECT * FROM t WHERE id = $1`). Each template keeps the
+/// parsed statements for the literal combinations seen so far, so repeating an
+/// identical query skips the parser entirely.
+///
+/// The cache lives on `Database` (not persisted). Each template remembers the
+/// relations its statement touches; a DDL statement drops the templates that
+/// touch the relation it changes (or views built on it) and bumps the
+/// database's `CatalogVersion`, which prepared statements are checked against.
+/// Statistics are exposed via `pg_plan_cache`.
+use crate::parser::{InsertSource, Lexer, Statement, SyntaxError, Token, TokenKind, parse_statement};
+use crate::types::Database;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Maximum number of templates kept per database
+const MAX_TEMPLATES: usize = 256;
+
+/// Maximum number of literal combinations kept per template
+const MAX_VARIANTS: usize = 16;
+
+/// Cached statements for one normalized query template
+#[derive(Debug, Clone, Default)]
+pub struct CachedPlan {
+    /// Parsed statements keyed by the literal values of the original query
+    variants: HashMap<Vec<String>, Statement>,
+    /// Relations the statement reads or writes
+    relations: Vec<String>,
+    /// Lookups for this template
+    pub calls: u64,
+    /// Lookups answered without parsing
+    pub hits: u64,
+}
+
+impl CachedPlan {
+    /// Number of cached literal combinations
+    #[must_use]
+    pub fn variant_count(&self) -> usize {
+        self.variants.len()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlanCache {
+    plans: HashMap<String, CachedPlan>,
+    /// Total lookups answered from the cache
+    pub hits: u64,
+    /// Total lookups that had to parse
+    pub misses: u64,
+    /// Number of times DDL dropped cached plans
+    pub invalidations: u64,
+}
+
+/// Catalog version of a database, bumped by every schema change
+///
+/// Shared between clones of a `Database` (like `plan_cache`), so a rolled back
+/// transaction snapshot never moves it backwards.
+#[derive(Debug, Clone, Default)]
+pub struct CatalogVersion(Arc<AtomicU64>);
+
+impl CatalogVersion {
+    #[must_use]
+    pub fn current(&self) -> u64 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Record a schema change
+    pub fn bump(&self) {
+        self.0.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl PlanCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `query`, reusing a cached statement when the same query was seen before
+    pub fn get_or_parse(&mut self, query: &str) -> Result<Statement, SyntaxError> {
+        let (template, literals) = normalize_query(query);
+
+        if let Some(plan) = self.plans.get_mut(&template) {
+            plan.calls += 1;
+            if let Some(stmt) = plan.variants.get(&literals) {
+                plan.hits += 1;
+                self.hits += 1;
+                return Ok(stmt.clone());
+            }
+        }
+
+        self.misses += 1;
+        let stmt = parse_statement(query)?;
+
+        if Self::is_cacheable(&stmt) {
+            if !self.plans.contains_key(&template) {
+                if self.plans.len() >= MAX_TEMPLATES {
+                    self.evict_coldest();
+                }
+                self.plans.insert(
+                    template.clone(),
+                    CachedPlan {
+                        relations: Self::touched_relations(&stmt),
+                        calls: 1,
+                        ..CachedPlan::default()
+                    },
+                );
+            }
+
+            if let Some(plan) = self.plans.get_mut(&template) {
+                if plan.variants.len() >= MAX_VARIANTS {
+                    plan.variants.clear();
+                }
+                plan.variants.insert(literals, stmt.clone());
+            }
+        }
+
+        Ok(stmt)
+    }
+
+    /// Drop every cached plan (called after schema changes)
+    pub fn invalidate(&mut self) {
+        self.plans.clear();
+        self.invalidations += 1;
+    }
+
+    /// Drop the plans touching any of `relations` (called after schema changes)
+    pub fn invalidate_relations(&mut self, relations: &[String]) {
+        let before = self.plans.len();
+        self.plans
+            .retain(|_, plan| !plan.relations.iter().any(|relation| relations.contains(relation)));
+        if self.plans.len() != before {
+            self.invalidations += 1;
+        }
+    }
+
+    /// Relations whose cached plans executing `stmt` makes stale: the relation
+    /// it changes and every view built on it, directly or through other views.
+    /// `None` when the change may affect any relation (types, unknown index).
+    #[must_use]
+    pub fn affected_relations(db: &Database, stmt: &Statement) -> Option<Vec<String>> {
+        let changed = match stmt {
+            Statement::CreateTable { name, .. }
+            | Statement::CreateForeignTable { name, .. }
+            | Statement::DropTable { name, .. }
+            | Statement::AlterTable { name, .. }
+            | Statement::CreateView { name, .. }
+            | Statement::DropView { name, .. } => vec![name.clone()],
+            Statement::CreatePartition { name, parent, .. } => vec![name.clone(), parent.clone()],
+            Statement::CreateIndex { table, .. } => vec![table.clone()],
+            Statement::DropIndex { name } => vec![db.indexes.get(name)?.table_name().to_string()],
+            _ => return None,
+        };
+
+        let mut affected = changed.clone();
+        let mut pending = changed;
+        while let Some(relation) = pending.pop() {
+            for view in super::views::ViewResolver::dependent_views(db, &relation) {
+                if !affected.contains(&view) {
+                    affected.push(view.clone());
+                    pending.push(view);
+                }
+            }
+        }
+        Some(affected)
+    }
+
+    /// Does executing `stmt` change the schema cached plans depend on?
+    #[must_use]
+    pub const fn invalidated_by(stmt: &Statement) -> bool {
+        matches!(
+            stmt,
+            Statement::CreateTable { .. }
+                | Statement::CreatePartition { .. }
+                | Statement::CreateForeignTable { .. }
+                | Statement::DropTable { .. }
+                | Statement::AlterTable { .. }
+                | Statement::CreateIndex { .. }
+                | Statement::DropIndex { .. }
+                | Statement::CreateView { .. }
+                | Statement::DropView { .. }
+                | Statement::CreateType { .. }
+                | Statement::AlterTypeAddValue { .. }
+        )
+    }
+
+    /// Cached templates with their statistics
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &CachedPlan)> {
+        self.plans.iter()
+    }
+
+    /// Number of cached templates
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.plans.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.plans.is_empty()
+    }
+
+    /// Only queries and DML are worth caching - DDL runs once and clears the cache anyway
+    const fn is_cacheable(stmt: &Statement) -> bool {
+        matches!(
+            stmt,
+            Statement::Select { .. }
+                | Statement::Insert { .. }
+                | Statement::InsertMany { .. }
+                | Statement::Update { .. }
+                | Statement::Delete { .. }
+                | Statement::Union { .. }
+                | Statement::Intersect { .. }
+                | Statement::Except { .. }
+        )
+    }
+
+    fn touched_relations(stmt: &Statement) -> Vec<String> {
+        let mut relations = super::views::ViewResolver::referenced_relations(stmt);
+        match stmt {
+            Statement::Insert { table, .. } | Statement::InsertMany { table, source: InsertSource::Values(_), .. } => {
+                relations.push(table.clone());
+            }
+            Statement::InsertMany { table, source: InsertSource::Query(query), .. } => {
+                relations.push(table.clone());
+                relations.extend(super::views::ViewResolver::referenced_relations(query));
+            }
+            Statement::Update { table, from, .. } => {
+                relations.push(table.clone());
+                relations.extend(from.iter().cloned());
+            }
+            Statement::Delete { from, using, .. } => {
+                relations.push(from.clone());
+                relations.extend(using.iter().cloned());
+            }
+            _ => {}
+        }
+        relations
+    }
+
+    fn evict_coldest(&mut self) {
+        if let Some(coldest) = self
+            .plans
+            .iter()
+            .min_by_key(|(_, plan)| plan.calls)
+            .map(|(template, _)| template.clone())
+        {
+            self.plans.remove(&coldest);
+        }
+    }
+}
+
+/// Normalize a query into a template and its literal values
+///
+/// - whitespace and comments between tokens become single spaces
+/// - trailing semicolons are dropped
+/// - string and numeric literals become `$1`, `$2`, ...
+///
+/// v2.7.0: the query is split by the parser's lexer; text from a quote or
+/// comment that does not end is kept as it is.
+#[must_use]
+pub fn normalize_query(query: &str) -> (String, Vec<String>) {
+    let tokens: Vec<Token<'_>> = Lexer::new(query).map_while(Result::ok).collect();
+    let unlexed = query[tokens.last().map_or(0, Token::end)..].trim();
+    let mut tokens: Vec<Token<'_>> = tokens.into_iter().filter(|token| token.kind != TokenKind::Comment).collect();
+    if unlexed.is_empty() {
+        while tokens.last().is_some_and(|token| token.text == ";") {
+            tokens.pop();
+        }
+    }
+
+    let mut template = String::with_capacity(query.len());
+    let mut literals = Vec::new();
+    let mut previous_end = None;
+    for token in tokens {
+        if previous_end.is_some_and(|end| end < token.offset) {
+            template.push(' ');
+        }
+        match token.kind {
+            TokenKind::String | TokenKind::Number => {
+                literals.push(token.text.to_string());
+                template.push_str(&format!("${}", literals.len()));
+            }
+            _ => template.push_str(token.text),
+        }
+        previous_end = Some(token.end());
+    }
+    if !unlexed.is_empty() {
+        if !template.is_empty() {
+            template.push(' ');
+        }
+        template.push_str(unlexed);
+    }
+
+    (template, literals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_query() {
+        let (template, literals) =
+            normalize_query("SELECT  *\n FROM t1 WHERE id = 42 AND name = 'it''s';");
+        assert_eq!(template, "SELECT * FROM t1 WHERE id = $1 AND name = $2");
+        assert_eq!(literals, vec!["42".to_string(), "'it''s'".to_string()]);
+
+        // Comments and line breaks do not make another template
+        let (template, literals) = normalize_query("SELECT * -- all\nFROM t1 /* rows */ WHERE id=-7 AND t1.x = 'a';");
+        assert_eq!(template, "SELECT * FROM t1 WHERE id=-$1 AND t1.x = $2");
+        assert_eq!(literals, vec!["7".to_string(), "'a'".to_string()]);
+        assert_eq!(normalize_query("SELECT 'open").0, "SELECT 'open");
+    }
+
+    #[test]
+    fn test_cache_hits_and_invalidation() {
+        let mut cache = PlanCache::new();
+
+        cache.get_or_parse("SELECT * FROM users WHERE id = 1").unwrap();
+        cache.get_or_parse("SELECT *  FROM users WHERE id = 1;").unwrap();
+        cache.get_or_parse("SELECT * FROM users WHERE id = 2").unwrap();
+
+        assert_eq!(cache.hits, 1);
+        assert_eq!(cache.misses, 2);
+        assert_eq!(cache.len(), 1);
+
+        let (_, plan) = cache.entries().next().unwrap();
+        assert_eq!(plan.calls, 3);
+        assert_eq!(plan.variant_count(), 2);
+
+        cache.invalidate();
+        assert!(cache.is_empty());
+        assert_eq!(cache.invalidations, 1);
+    }
+
+    #[test]
+    fn test_ddl_invalidates_only_affected_plans() {
+        let mut db = Database::new("test".to_string());
+        db.views.insert("active_users".to_string(), "SELECT * FROM users WHERE active = true".to_string());
+        db.views.insert("active_admins".to_string(), "SELECT * FROM active_users WHERE admin = true".to_string());
+
+        let mut cache = PlanCache::new();
+        cache.get_or_parse("SELECT * FROM users WHERE id = 1").unwrap();
+        cache.get_or_parse("SELECT * FROM active_admins").unwrap();
+        cache.get_or_parse("DELETE FROM orders WHERE id = 1").unwrap();
+        cache.get_or_parse("SELECT * FROM products").unwrap();
+
+        let alter = parse_statement("ALTER TABLE users ADD COLUMN age INTEGER").unwrap();
+        assert!(PlanCache::invalidated_by(&alter));
+        let affected = PlanCache::affected_relations(&db, &alter).unwrap();
+        assert_eq!(affected, vec!["users", "active_users", "active_admins"]);
+        cache.invalidate_relations(&affected);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.invalidations, 1);
+
+        cache.invalidate_relations(&PlanCache::affected_relations(&db, &parse_statement("DROP TABLE orders").unwrap()).unwrap());
+        let templates: Vec<&String> = cache.entries().map(|(template, _)| template).collect();
+        assert_eq!(templates, vec!["SELECT * FROM products"]);
+
+        // Changing a type may affect anything
+        assert!(PlanCache::affected_relations(&db, &parse_statement("ALTER TYPE mood ADD VALUE 'meh'").unwrap()).is_none());
+
+        let version = CatalogVersion::default();
+        let shared = version.clone();
+        version.bump();
+        assert_eq!(shared.current(), 1);
+    }
+
+    #[test]
+    fn test_ddl_not_cached() {
+        let mut cache = PlanCache::new();
+        cache.get_or_parse("CREATE TABLE t (id INTEGER)").unwrap();
+        assert!(cache.is_empty());
+    }
+}
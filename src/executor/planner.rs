@@ -0,0 +1,636 @@
+/// Access path selection for a table scan (v2.7.0)
+///
+/// The WHERE clause is split into its AND-ed conditions and every index on the
+/// table is matched against them the way a B-tree is searched: `=` on the
+/// leading columns, then at most one range (`>`, `<`, `>=`, `<=`, BETWEEN) on
+/// the next column. `WHERE a = 1 AND b > 5` with an index on (a, b) reads only
+/// the entries under `a = 1` from `b > 5` on. Hash indexes answer equality on
+/// all of their columns only.
+///
+/// The index with the most columns used wins, a unique index matched by
+/// equality on every column beats everything else. Conditions the index does
+/// not answer are still checked on the rows it returns.
+///
+/// Without such an index, an OR of conditions (`a = 1 OR a = 7`, `a = 1 OR
+/// b = 2`) is answered by one index search per branch, their rows united like
+/// a bitmap OR - as long as every branch has an index to search.
+///
+/// Once the table is analyzed (see `analyze`), equally ranked indexes are
+/// decided by the share of rows their conditions are expected to match, and
+/// a non-unique search expected to return more than `SEQ_SCAN_FRACTION` of
+/// the table is not used: reading every page is cheaper then.
+use std::cmp::Ordering;
+use std::ops::Bound;
+use crate::index::{Index, IndexType};
+use crate::parser::Condition;
+use crate::types::{Collation, Column, DataType, Database, Value};
+use crate::types::value::compare_values;
+use super::analyze::TableStatistics;
+use super::dml::DmlExecutor;
+
+/// Share of the rows above which a sequential scan beats an index search
+/// (where EXPLAIN's costs of reading a row each way break even)
+const SEQ_SCAN_FRACTION: f64 = 0.4;
+
+/// How a table is read through an index
+#[derive(Debug)]
+pub struct IndexPath<'a> {
+    pub name: &'a str,
+    pub index: &'a Index,
+    /// Values of the leading index columns, compared with `=`
+    pub prefix: Vec<Value>,
+    /// Range on the column after the prefix
+    pub lower: Bound<Value>,
+    pub upper: Bound<Value>,
+    /// WHERE conditions answered by the index
+    pub conditions: Vec<&'a Condition>,
+}
+
+impl<'a> IndexPath<'a> {
+    /// Is every index column compared with `=`?
+    #[must_use]
+    pub fn is_equality(&self) -> bool {
+        self.prefix.len() == self.index.column_names().len()
+    }
+
+    /// Does the path find at most one row?
+    #[must_use]
+    pub fn is_unique(&self) -> bool {
+        self.index.is_unique() && self.is_equality()
+    }
+
+    /// Row ordinals the index returns, in table order
+    #[must_use]
+    pub fn row_indices(&self) -> Vec<usize> {
+        let mut rows = if self.is_equality() {
+            if self.index.is_composite() {
+                self.index.search_composite(&self.prefix)
+            } else {
+                self.index.search(&self.prefix[0])
+            }
+        } else {
+            self.index
+                .search_range(&self.prefix, self.lower.as_ref(), self.upper.as_ref())
+                .unwrap_or_default()
+        };
+        // Reading rows in table order keeps page access sequential
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+
+    /// Row ordinals the index returns, in key order or against it, read as
+    /// they are consumed (v2.7.0, see `Planner::ordered_path`)
+    #[must_use]
+    pub fn ordered_indices(&self, descending: bool) -> Box<dyn Iterator<Item = usize> + 'a> {
+        self.index
+            .scan_range(&self.prefix, self.lower.as_ref(), self.upper.as_ref(), descending)
+            .unwrap_or_else(|| Box::new(std::iter::empty()))
+    }
+
+    /// Preference between two paths: unique equality, then columns used
+    fn rank(&self) -> (bool, usize, bool) {
+        let range = !matches!((&self.lower, &self.upper), (Bound::Unbounded, Bound::Unbounded));
+        (self.is_unique(), self.prefix.len(), range)
+    }
+}
+
+/// How a table is read through its indexes
+#[derive(Debug)]
+pub enum IndexAccess<'a> {
+    /// One index search for the AND-ed conditions
+    Scan(IndexPath<'a>),
+    /// One search per branch of `condition`, an OR; a row found by any of
+    /// them is read once
+    BitmapOr { condition: &'a Condition, branches: Vec<IndexPath<'a>> },
+}
+
+impl<'a> IndexAccess<'a> {
+    /// The index searches, in the order they run
+    #[must_use]
+    pub fn paths(&self) -> &[IndexPath<'a>] {
+        match self {
+            Self::Scan(path) => std::slice::from_ref(path),
+            Self::BitmapOr { branches, .. } => branches,
+        }
+    }
+
+    /// WHERE conditions answered by the indexes
+    #[must_use]
+    pub fn conditions(&self) -> Vec<&'a Condition> {
+        match self {
+            Self::Scan(path) => path.conditions.clone(),
+            Self::BitmapOr { condition, .. } => vec![*condition],
+        }
+    }
+}
+
+pub struct Planner;
+
+impl Planner {
+    /// Indexes to read `table_name` through for `filter`, `None` for a sequential scan
+    #[must_use]
+    pub fn choose_index<'a>(db: &'a Database, table_name: &str, filter: Option<&'a Condition>) -> Option<IndexAccess<'a>> {
+        let conjuncts = Self::conjuncts(filter?);
+        if let Some(path) = Self::choose_path(db, table_name, &conjuncts) {
+            return Some(IndexAccess::Scan(path));
+        }
+
+        // Bitmap OR over the first OR whose branches all have an index
+        conjuncts.into_iter().find_map(|cond| {
+            if !matches!(cond, Condition::Or(..)) {
+                return None;
+            }
+            let branches = Self::disjuncts(cond)
+                .into_iter()
+                .map(|branch| Self::choose_path(db, table_name, &Self::conjuncts(branch)))
+                .collect::<Option<Vec<_>>>()?;
+            Some(IndexAccess::BitmapOr { condition: cond, branches })
+        })
+    }
+
+    /// Search of `access` that returns its rows sorted by `column` (v2.7.0)
+    ///
+    /// A B-tree returns a range in key order, so with `=` on the leading
+    /// columns its rows come sorted by the next column - and trivially by the
+    /// leading ones. ORDER BY such a column walks the index instead of sorting
+    /// and stops once LIMIT has its rows.
+    #[must_use]
+    pub fn ordered_path<'p, 'a>(
+        db: &Database,
+        table_name: &str,
+        access: &'p IndexAccess<'a>,
+        column: &str,
+    ) -> Option<&'p IndexPath<'a>> {
+        let table = db.get_table(table_name)?;
+        let IndexAccess::Scan(path) = access else {
+            return None;
+        };
+        if table.partitioning.is_some() || path.index.index_type() != IndexType::BTree {
+            return None;
+        }
+
+        let position = path.index.column_names().iter().position(|name| name == column)?;
+        if position < path.prefix.len() {
+            return Some(path);
+        }
+        // Keys sort like the values only for ordered types in byte order
+        let column = table.columns.iter().find(|col| col.name == column)?;
+        (position == path.prefix.len() && column.collation == Collation::C && Self::ordered(&column.data_type))
+            .then_some(path)
+    }
+
+    /// B-tree index whose leading columns are the GROUP BY columns (v2.7.0)
+    ///
+    /// Its key order keeps the rows of a group together, so groups can be
+    /// aggregated one after another instead of all at once in a hash table.
+    #[must_use]
+    pub fn group_index<'a>(db: &'a Database, table_name: &str, group_by: &[String]) -> Option<(&'a str, &'a Index)> {
+        let table = db.get_table(table_name)?;
+        if table.partitioning.is_some() || group_by.is_empty() {
+            return None;
+        }
+
+        let mut candidates: Vec<(&'a str, &'a Index)> = db
+            .indexes
+            .iter()
+            .filter(|(_, index)| {
+                let columns = index.column_names();
+                index.table_name() == table_name
+                    && index.index_type() == IndexType::BTree
+                    && columns.len() >= group_by.len()
+                    && group_by.iter().all(|col| columns[..group_by.len()].contains(col))
+                    && columns[..group_by.len()].iter().all(|col| group_by.contains(col))
+            })
+            .map(|(name, index)| (name.as_str(), index))
+            .collect();
+        // The narrowest index, by name among equals
+        candidates.sort_by(|a, b| a.1.column_names().len().cmp(&b.1.column_names().len()).then(a.0.cmp(b.0)));
+        candidates.into_iter().next()
+    }
+
+    /// Best single index for AND-ed conditions
+    fn choose_path<'a>(db: &'a Database, table_name: &str, conjuncts: &[&'a Condition]) -> Option<IndexPath<'a>> {
+        let table = db.get_table(table_name)?;
+        let mut indexes: Vec<(&String, &Index)> =
+            db.indexes.iter().filter(|(_, index)| index.table_name() == table_name).collect();
+        // Equal candidates are decided by name, not by hash map order
+        indexes.sort_by(|a, b| a.0.cmp(b.0));
+
+        let stats = db.statistics.table(table_name);
+        let fraction = |path: &IndexPath<'_>| Self::fraction(stats.as_deref(), path);
+
+        let mut best: Option<IndexPath<'a>> = None;
+        for (name, index) in indexes {
+            let Some(path) = Self::match_index(name, index, &table.columns, conjuncts) else {
+                continue;
+            };
+            let better = best.as_ref().is_none_or(|best| match path.rank().cmp(&best.rank()) {
+                Ordering::Greater => true,
+                Ordering::Less => false,
+                Ordering::Equal => matches!((fraction(&path), fraction(best)), (Some(new), Some(old)) if new < old),
+            });
+            if better {
+                best = Some(path);
+            }
+        }
+
+        match best {
+            Some(path) if !path.is_unique() && fraction(&path).is_some_and(|share| share > SEQ_SCAN_FRACTION) => None,
+            best => best,
+        }
+    }
+
+    /// Share of the table a path is expected to return, `None` without
+    /// statistics covering all of its conditions
+    fn fraction(stats: Option<&TableStatistics>, path: &IndexPath<'_>) -> Option<f64> {
+        let stats = stats?;
+        path.conditions.iter().map(|cond| stats.selectivity(cond)).product()
+    }
+
+    /// Match the conditions against one index's columns
+    fn match_index<'a>(
+        name: &'a str,
+        index: &'a Index,
+        columns: &[Column],
+        conjuncts: &[&'a Condition],
+    ) -> Option<IndexPath<'a>> {
+        let mut path = IndexPath {
+            name,
+            index,
+            prefix: Vec::new(),
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            conditions: Vec::new(),
+        };
+
+        for column_name in index.column_names() {
+            // Indexes keep byte order, so a collated column is not searched through them
+            let column = columns.iter().find(|col| &col.name == column_name)?;
+            if column.collation != Collation::C {
+                break;
+            }
+
+            let equality = conjuncts.iter().find_map(|cond| match cond {
+                Condition::Equals(col, value) if col == column_name => {
+                    Self::key(column, value).map(|key| (*cond, key))
+                }
+                _ => None,
+            });
+            if let Some((cond, key)) = equality {
+                path.prefix.push(key);
+                path.conditions.push(cond);
+                continue;
+            }
+
+            if index.index_type() == IndexType::BTree && Self::ordered(&column.data_type) {
+                for cond in conjuncts {
+                    // The first bound found on each side is used
+                    let (lower, upper) = Self::range(column, cond);
+                    let new_lower = matches!(path.lower, Bound::Unbounded) && !matches!(lower, Bound::Unbounded);
+                    let new_upper = matches!(path.upper, Bound::Unbounded) && !matches!(upper, Bound::Unbounded);
+                    if new_lower {
+                        path.lower = lower;
+                    }
+                    if new_upper {
+                        path.upper = upper;
+                    }
+                    if new_lower || new_upper {
+                        path.conditions.push(cond);
+                    }
+                }
+            }
+            break;
+        }
+
+        let usable = match index.index_type() {
+            IndexType::Hash => path.is_equality(),
+            IndexType::BTree => !path.conditions.is_empty(),
+        };
+        usable.then_some(path)
+    }
+
+    /// Bounds a condition puts on `column`
+    fn range(column: &Column, cond: &Condition) -> (Bound<Value>, Bound<Value>) {
+        let bound = |col: &str, value: &Value, make: fn(Value) -> Bound<Value>| {
+            if col != column.name {
+                return Bound::Unbounded;
+            }
+            Self::key(column, value)
+                .filter(|key| !matches!(key, Value::Null))
+                .map_or(Bound::Unbounded, make)
+        };
+
+        match cond {
+            Condition::GreaterThan(col, value) => (bound(col, value, Bound::Excluded), Bound::Unbounded),
+            Condition::GreaterThanOrEqual(col, value) => (bound(col, value, Bound::Included), Bound::Unbounded),
+            Condition::LessThan(col, value) => (Bound::Unbounded, bound(col, value, Bound::Excluded)),
+            Condition::LessThanOrEqual(col, value) => (Bound::Unbounded, bound(col, value, Bound::Included)),
+            Condition::Between(col, low, high) => {
+                (bound(col, low, Bound::Included), bound(col, high, Bound::Included))
+            }
+            _ => (Bound::Unbounded, Bound::Unbounded),
+        }
+    }
+
+    /// A literal converted to the column's type, as the stored values are
+    ///
+    /// `None` when the conversion changes the value (`int_col < 2.5`), a
+    /// rounded key would miss rows.
+    fn key(column: &Column, value: &Value) -> Option<Value> {
+        let key = DmlExecutor::coerce_value(column, value.clone()).ok()?;
+        let exact = matches!(value, Value::Null | Value::Text(_) | Value::Char(_))
+            || compare_values(&key, value) == Some(Ordering::Equal);
+        exact.then_some(key)
+    }
+
+    /// Do index keys of this type sort like its values?
+    const fn ordered(data_type: &DataType) -> bool {
+        !matches!(data_type, DataType::Json | DataType::Jsonb | DataType::Bytea | DataType::Enum { .. })
+    }
+
+    fn disjuncts(cond: &Condition) -> Vec<&Condition> {
+        match cond {
+            Condition::Or(left, right) => {
+                let mut parts = Self::disjuncts(left);
+                parts.extend(Self::disjuncts(right));
+                parts
+            }
+            other => vec![other],
+        }
+    }
+
+    fn conjuncts(cond: &Condition) -> Vec<&Condition> {
+        match cond {
+            Condition::And(left, right) => {
+                let mut parts = Self::conjuncts(left);
+                parts.extend(Self::conjuncts(right));
+                parts
+            }
+            other => vec![other],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryResult};
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use tempfile::TempDir;
+
+    /// Lines of the plan, without tree prefixes
+    fn plan(result: QueryResult) -> Vec<String> {
+        match result {
+            QueryResult::Rows(rows, _) => rows
+                .iter()
+                .map(|row| row[0].trim().trim_start_matches("->  ").to_string())
+                .collect(),
+            QueryResult::Success(_) => panic!("Expected plan rows"),
+        }
+    }
+
+    fn values(result: QueryResult) -> Vec<String> {
+        match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row[0].clone()).collect(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_access_path_selection() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE events (id INTEGER, a INTEGER, b INTEGER, kind TEXT)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_a_b ON events (a, b)").unwrap();
+        exec(&mut db, "CREATE UNIQUE INDEX idx_id ON events (id)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_kind ON events (kind) USING HASH").unwrap();
+
+        // Equality on the leading column, range on the next one
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE a = 1 AND b > 5").unwrap());
+        assert!(explain[0].starts_with("Index Scan using idx_a_b on events (btree)"), "{explain:#?}");
+        assert_eq!(explain[1], "Index Cond: a = 1 AND b > 5");
+
+        // Only the leading column: a prefix scan, the rest stays a filter
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE b = 2 AND a = 1 AND kind LIKE 'x%'").unwrap());
+        assert_eq!(explain[1], "Index Cond: a = 1 AND b = 2");
+        assert_eq!(explain[2], "Filter: kind LIKE 'x%'");
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE a BETWEEN 1 AND 3 AND b = 2").unwrap());
+        assert_eq!(explain[1], "Index Cond: a BETWEEN 1 AND 3");
+        assert_eq!(explain[2], "Filter: b = 2");
+
+        // A unique match wins over a longer range match
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE a = 1 AND b > 5 AND id = 3").unwrap());
+        assert!(explain[0].starts_with("Unique Index Scan using idx_id"), "{explain:#?}");
+
+        // Ranges need an ordered index, a rounded bound would miss rows
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE kind > 'x'").unwrap());
+        assert!(explain[0].starts_with("Seq Scan on events"), "{explain:#?}");
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE a < 2.5").unwrap());
+        assert!(explain[0].starts_with("Seq Scan on events"), "{explain:#?}");
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM events WHERE b > 5").unwrap());
+        assert!(explain[0].starts_with("Seq Scan on events"), "{explain:#?}");
+    }
+
+    #[test]
+    fn test_range_scan_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE events (id INTEGER, a INTEGER, b INTEGER)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_a_b ON events (a, b)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_id ON events (id)").unwrap();
+        let rows = [(1, 1, 4), (2, 1, 5), (3, 1, 6), (4, 2, 9), (5, 1, -3), (6, 1, 50), (7, -1, 7)];
+        for (id, a, b) in rows {
+            exec(&mut db, &format!("INSERT INTO events VALUES ({id}, {a}, {b})")).unwrap();
+        }
+
+        let mut ids = |filter: &str| values(exec(&mut db, &format!("SELECT id FROM events WHERE {filter}")).unwrap());
+        assert_eq!(ids("a = 1 AND b > 5"), ["3", "6"]);
+        assert_eq!(ids("a = 1 AND b >= 5 AND b < 50"), ["2", "3"]);
+        assert_eq!(ids("a = 1 AND b <= 4"), ["1", "5"]);
+        assert_eq!(ids("a = 1"), ["1", "2", "3", "5", "6"]);
+        assert_eq!(ids("a > -1"), ["1", "2", "3", "4", "5", "6"]);
+        assert_eq!(ids("id > 5"), ["6", "7"]);
+        assert_eq!(ids("id BETWEEN 2 AND 3"), ["2", "3"]);
+        assert_eq!(ids("id < 2.5"), ["1", "2"]);
+    }
+
+    #[test]
+    fn test_ordered_limit_scan() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE events (id INTEGER, a INTEGER, b INTEGER, note TEXT)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_a_b ON events (a, b)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_id ON events (id)").unwrap();
+        for id in 0..300 {
+            let (a, b) = (id % 3, (id * 37) % 300);
+            exec(&mut db, &format!("INSERT INTO events VALUES ({id}, {a}, {b}, 'event {id} with a note long enough to fill pages')")).unwrap();
+        }
+        exec(&mut db, "UPDATE events SET b = b + 1000 WHERE id % 7 = 0").unwrap();
+        exec(&mut db, "DELETE FROM events WHERE id % 10 = 0").unwrap();
+
+        // The index order matches the sorted result, whatever was updated or deleted
+        for (filter, order) in [
+            ("a = 1 AND b > 50", "b"),
+            ("a = 1 AND b > 50", "b DESC"),
+            ("a = 2", "b DESC"),
+            ("id >= 100 AND a = 0", "id"),
+            ("id < 200 AND note LIKE 'event 1%'", "id DESC"),
+        ] {
+            let sorted = values(exec(&mut db, &format!("SELECT id FROM events WHERE {filter} ORDER BY {order}")).unwrap());
+            for (limit, offset) in [(5, 0), (3, 4), (1000, 0)] {
+                let page = values(
+                    exec(&mut db, &format!("SELECT id FROM events WHERE {filter} ORDER BY {order} LIMIT {limit} OFFSET {offset}")).unwrap(),
+                );
+                let expected: Vec<String> = sorted.iter().skip(offset).take(limit).cloned().collect();
+                assert_eq!(page, expected, "{filter} ORDER BY {order} LIMIT {limit} OFFSET {offset}");
+            }
+        }
+
+        // Only the index entries up to the last row returned are read
+        let before = db.activity.index("idx_id").idx_tup_read;
+        let first = values(exec(&mut db, "SELECT id FROM events WHERE id > 10 ORDER BY id LIMIT 3").unwrap());
+        assert_eq!(first, ["11", "12", "13"]);
+        assert_eq!(db.activity.index("idx_id").idx_tup_read - before, 3);
+
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT id FROM events WHERE id > 10 ORDER BY id DESC LIMIT 3").unwrap());
+        assert!(explain[0].starts_with("Limit"), "{explain:#?}");
+        assert!(explain[1].starts_with("Index Scan using idx_id on events"), "{explain:#?}");
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT id FROM events WHERE id > 10 ORDER BY a LIMIT 3").unwrap());
+        assert!(explain[1].starts_with("Sort"), "{explain:#?}");
+    }
+
+    #[test]
+    fn test_bitmap_or() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE items (id INTEGER, a INTEGER, b TEXT, c INTEGER)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_a ON items (a)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_b ON items (b) USING HASH").unwrap();
+        for id in 1..=10 {
+            exec(&mut db, &format!("INSERT INTO items VALUES ({id}, {}, 'b{}', {id})", id % 4, id % 3)).unwrap();
+        }
+
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM items WHERE a = 1 OR a = 3").unwrap());
+        assert!(explain[0].starts_with("Bitmap Heap Scan on items"), "{explain:#?}");
+        assert_eq!(explain[1], "Recheck Cond: (a = 1) OR (a = 3)");
+        assert!(explain[2].starts_with("BitmapOr"), "{explain:#?}");
+        assert!(explain[3].starts_with("Bitmap Index Scan on idx_a (btree)"), "{explain:#?}");
+        assert_eq!(explain[4], "Index Cond: a = 1");
+        assert_eq!(explain[6], "Index Cond: a = 3");
+
+        // Different indexes per branch, other conditions stay a filter
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM items WHERE (a = 1 OR b = 'b2') AND c > 2").unwrap());
+        assert!(explain[0].starts_with("Bitmap Heap Scan on items"), "{explain:#?}");
+        assert_eq!(explain[2], "Filter: c > 2");
+        assert!(explain[4].starts_with("Bitmap Index Scan on idx_a"), "{explain:#?}");
+        assert!(explain[6].starts_with("Bitmap Index Scan on idx_b (hash)"), "{explain:#?}");
+
+        // A branch without an index means reading the whole table anyway
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM items WHERE a = 1 OR c = 2").unwrap());
+        assert!(explain[0].starts_with("Seq Scan on items"), "{explain:#?}");
+
+        let mut ids = |filter: &str| values(exec(&mut db, &format!("SELECT id FROM items WHERE {filter}")).unwrap());
+        assert_eq!(ids("a = 1 OR a = 3"), ["1", "3", "5", "7", "9"]);
+        assert_eq!(ids("(a = 1 OR b = 'b2') AND c > 2"), ["5", "8", "9"]);
+        assert_eq!(ids("a = 1 OR a > 2"), ["1", "3", "5", "7", "9"]);
+        assert_eq!(ids("a = 0 OR a = 0"), ["4", "8"]);
+    }
+
+    #[test]
+    fn test_analyzed_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE tickets (id INTEGER, status TEXT, owner INTEGER)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_status ON tickets (status)").unwrap();
+        exec(&mut db, "CREATE INDEX idx_owner ON tickets (owner)").unwrap();
+        for id in 0..300 {
+            let status = if id % 10 == 0 { "open" } else { "closed" };
+            exec(&mut db, &format!("INSERT INTO tickets VALUES ({id}, '{status}', {})", id % 100)).unwrap();
+        }
+
+        // Without statistics every equality is worth an index search
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM tickets WHERE status = 'closed'").unwrap());
+        assert!(explain[0].starts_with("Index Scan using idx_status"), "{explain:#?}");
+        assert!(explain[0].ends_with("rows=30)"), "{explain:#?}");
+
+        exec(&mut db, "ANALYZE tickets").unwrap();
+
+        // 90% of the rows: reading the table is cheaper
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM tickets WHERE status = 'closed'").unwrap());
+        assert!(explain[0].starts_with("Seq Scan on tickets"), "{explain:#?}");
+        assert!(explain[0].ends_with("rows=270)"), "{explain:#?}");
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM tickets WHERE status = 'open'").unwrap());
+        assert!(explain[0].starts_with("Index Scan using idx_status"), "{explain:#?}");
+        assert!(explain[0].ends_with("rows=30)"), "{explain:#?}");
+
+        // The more selective of two equally ranked indexes
+        let explain = plan(exec(&mut db, "EXPLAIN SELECT * FROM tickets WHERE status = 'open' AND owner = 7").unwrap());
+        assert!(explain[0].starts_with("Index Scan using idx_owner"), "{explain:#?}");
+        assert_eq!(explain[2], "Filter: status = 'open'");
+
+        let count = |result: QueryResult| values(result).len();
+        assert_eq!(count(exec(&mut db, "SELECT id FROM tickets WHERE status = 'closed'").unwrap()), 270);
+        assert_eq!(count(exec(&mut db, "SELECT id FROM tickets WHERE status = 'open' AND owner = 70").unwrap()), 3);
+    }
+
+    #[test]
+    fn test_group_by_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        storage.set_work_mem(256);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| run_sql(db, &mut storage, &tx_manager, sql);
+
+        exec(&mut db, "CREATE TABLE sales (region INTEGER, shop INTEGER, amount INTEGER)").unwrap();
+        for n in 0..200 {
+            exec(&mut db, &format!("INSERT INTO sales VALUES ({}, {}, {n})", (n * 7) % 20 - 10, n % 3)).unwrap();
+        }
+        let query = "SELECT region, COUNT(*), SUM(amount) FROM sales WHERE amount >= 20 GROUP BY region ORDER BY region";
+        let grouped = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        // Hashed, with groups spilling past work_mem
+        let explain = plan(exec(&mut db, &format!("EXPLAIN {query}")).unwrap());
+        assert!(explain.iter().any(|line| line.starts_with("HashAggregate")), "{explain:#?}");
+        let hashed = grouped(exec(&mut db, query).unwrap());
+        assert_eq!(hashed.len(), 20);
+        assert_eq!(hashed.iter().map(|row| row[1].parse::<i64>().unwrap()).sum::<i64>(), 180);
+
+        // Streamed in index order: same groups
+        exec(&mut db, "CREATE INDEX idx_region_shop ON sales (region, shop)").unwrap();
+        assert_eq!(Planner::group_index(&db, "sales", &["region".to_string()]).map(|(name, _)| name), Some("idx_region_shop"));
+        assert!(Planner::group_index(&db, "sales", &["shop".to_string()]).is_none());
+        let explain = plan(exec(&mut db, &format!("EXPLAIN {query}")).unwrap());
+        assert!(explain.iter().any(|line| line.starts_with("GroupAggregate")), "{explain:#?}");
+        assert!(explain.iter().any(|line| line.starts_with("Index Scan using idx_region_shop on sales (btree)")), "{explain:#?}");
+        assert_eq!(grouped(exec(&mut db, query).unwrap()), hashed);
+
+        // Every spilled partition is gone once the query is done
+        assert_eq!(std::fs::read_dir(temp_dir.path().join("pgsql_tmp")).unwrap().count(), 0);
+    }
+}
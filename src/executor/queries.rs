@@ -1,15 +1,48 @@
 /// Query (SELECT) operations
 ///
 /// SELECT, JOIN, aggregate functions, GROUP BY
-use crate::types::{Database, DatabaseError, Row, Table, Value};
-use crate::parser::{SelectColumn, Condition, AggregateFunction, CountTarget, SortOrder, CaseExpression, Statement};
+use crate::types::{Collation, Database, DatabaseError, Row, Table, Value};
+use crate::types::value::compare_for_sort;
+use crate::parser::{SelectColumn, Condition, AggregateFunction, CountTarget, Expression, SortOrder, Statement};
 use crate::transaction::GlobalTransactionManager;
-use super::dispatcher_executor::QueryResult;
+use super::dispatcher_executor::{QueryResult, RowSink};
 use super::conditions::ConditionEvaluator;
-use crate::index::Index;
+use super::pipeline::{Distinct, Filter, Paginate, RowSource, Visible};
+use super::storage_adapter::{ScanProjection, ScanPushdown};
+use super::views::ViewResolver;
+use super::functions::FunctionExecutor;
+use super::udf::FunctionRegistry;
+use super::partitions::Partitions;
+use super::set_operations::SetOperations;
+use super::derived_tables::DerivedTables;
+use super::system_columns::SystemColumns;
+use super::subquery::SubqueryExecutor;
+use super::planner::Planner;
+use super::sort::ExternalSort;
+use super::hash_agg::HashGroups;
+use super::memory::QueryMemory;
+use super::vectorized::{VectorPlan, VectorScan};
 
 pub struct QueryExecutor;
 
+/// What ORDER BY sorts a table's rows on (v2.7.0)
+enum SortKey {
+    Column(usize),
+    Expression(Expression),
+}
+
+/// One output column of a regular SELECT, in select-list order (v2.7.0)
+enum Output<'q> {
+    /// Index of the value in the scanned row
+    Column(usize),
+    Literal(&'q Value),
+    Subquery(&'q Statement),
+    /// Position among the select list's expressions
+    Expression(usize),
+    /// Filled in once every row is known
+    Window,
+}
+
 /// Helper struct for managing intermediate state during multi-JOIN processing (v2.6.0)
 struct IntermediateJoinState {
     /// Accumulated result rows (each row is Vec<String> of all column values)
@@ -34,195 +67,501 @@ impl IntermediateJoinState {
 }
 
 impl QueryExecutor {
-    /// Evaluate CASE expression for a given row (v1.10.0)
-    fn evaluate_case(
-        case_expr: &CaseExpression,
-        columns: &[crate::types::Column],
-        row: &Row,
-    ) -> Result<Value, DatabaseError> {
-        // Evaluate each WHEN clause
-        for when in &case_expr.when_clauses {
-            if ConditionEvaluator::evaluate_with_columns(columns, row, &when.condition)? {
-                return Ok(when.result.clone());
+    /// Main SELECT dispatcher
+    ///
+    /// Routes to appropriate handler based on:
+    /// - JOIN presence → `select_with_join()`
+    /// - GROUP BY → `select_with_group_by()`
+    /// - Aggregates without GROUP BY → `select_aggregate()`
+    /// - Regular → `select_regular()`
+    pub fn select(
+        db: &Database,
+        distinct: bool,
+        columns: Vec<SelectColumn>,
+        from: String,
+        joins: Vec<crate::parser::JoinClause>,
+        filter: Option<Condition>,
+        group_by: Option<Vec<String>>,
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        Self::select_each(db, distinct, columns, from, joins, filter, group_by, order_by, limit, offset, tx_manager, database_storage, None)
+    }
+
+    /// Result columns of a query, worked out from the catalog without running it (v2.7.0)
+    ///
+    /// Known for SELECTs from a table or without FROM, and for set operations
+    /// (the columns of their first arm). `None` where the columns only come
+    /// out of reading the source: views, joins, system catalogs, subqueries
+    /// and functions in FROM, system columns.
+    #[must_use]
+    pub fn result_columns(db: &Database, stmt: &Statement) -> Option<Vec<String>> {
+        let (columns, from, group_by) = match stmt {
+            Statement::Select { columns, from, joins, filter, group_by, order_by, .. } => {
+                if from.is_empty() {
+                    // Columns in the order they are listed, like `select_without_from`
+                    return columns
+                        .iter()
+                        .map(|col| match col {
+                            SelectColumn::Literal(_) => Some("?column?".to_string()),
+                            SelectColumn::Expression { expr, alias } => Some(FunctionExecutor::column_name(expr, alias.as_ref())),
+                            SelectColumn::Subquery { alias, .. } => Some(alias.clone().unwrap_or_else(|| "?column?".to_string())),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                let table = db.get_table(from)?;
+                if !joins.is_empty()
+                    || super::system_catalogs::SystemCatalog::is_system_catalog(from)
+                    || SystemColumns::used_by_select(table, columns, filter.as_ref(), order_by.as_ref())
+                {
+                    return None;
+                }
+                (columns, table, group_by)
+            }
+            Statement::Union { left, .. } | Statement::Intersect { left, .. } | Statement::Except { left, .. } => {
+                return Self::result_columns(db, left);
             }
+            _ => return None,
+        };
+
+        let has_aggregates = columns.iter().any(|col| match col {
+            SelectColumn::Aggregate(_) => true,
+            SelectColumn::Expression { expr, .. } => FunctionRegistry::aggregate_call(expr).is_some(),
+            _ => false,
+        });
+        if group_by.is_some() || has_aggregates {
+            // Columns in the order they are listed, like `select_with_group_by` and `select_aggregate`
+            return columns
+                .iter()
+                .map(|col| match col {
+                    SelectColumn::Regular { name, alias } if group_by.is_some() => Some(alias.as_ref().unwrap_or(name).clone()),
+                    SelectColumn::Aggregate(agg_func) => Self::compute_aggregate(agg_func, from, &[]).ok().map(|(_, name)| name),
+                    SelectColumn::Literal(_) => Some("?column?".to_string()),
+                    SelectColumn::Expression { expr, alias } => Some(FunctionExecutor::column_name(expr, alias.as_ref())),
+                    _ => None,
+                })
+                .collect();
         }
 
-        // If no WHEN matched, return ELSE or NULL
-        Ok(case_expr.else_value.clone().unwrap_or(Value::Null))
+        // Columns in the order they are listed, `*` expanded in place, like `select_regular`
+        let unnamed = |alias: &Option<String>| alias.clone().unwrap_or_else(|| "?column?".to_string());
+        let mut names = Vec::new();
+        for col in columns {
+            match col {
+                SelectColumn::Regular { name, .. } if name == "*" => names.extend(from.columns.iter().map(|c| c.name.clone())),
+                SelectColumn::Regular { name, alias } => {
+                    let column = &from.columns[from.get_column_index(name)?].name;
+                    names.push(alias.as_ref().unwrap_or(column).clone());
+                }
+                SelectColumn::Literal(_) => names.push("?column?".to_string()),
+                SelectColumn::Subquery { alias, .. } | SelectColumn::Window { alias, .. } => names.push(unnamed(alias)),
+                SelectColumn::Expression { expr, alias } => names.push(FunctionExecutor::column_name(expr, alias.as_ref())),
+                SelectColumn::Aggregate(_) => return None,
+            }
+        }
+        Some(names)
     }
-}
 
-impl QueryExecutor {
-    /// Find usable index for WHERE condition (v1.9.0: supports composite indexes)
+    /// `select` handing the rows of a regular SELECT to `each` as they are
+    /// produced instead of collecting them (v2.7.0)
     ///
-    /// Returns Some for:
-    /// - Single column: Equals(col, val) or GreaterThan/LessThan
-    /// - Composite: AND of multiple Equals conditions matching index columns
-    fn find_usable_index<'a>(
-        db: &'a Database,
-        table_name: &str,
-        filter: &'a Option<Condition>,
-    ) -> Option<(&'a str, &'a Index, Vec<(&'a str, &'a Value)>)> {
-        let filter = match filter {
-            Some(f) => f,
-            None => return None,
-        };
+    /// The returned result then has the column names and no rows. Every
+    /// other kind of SELECT returns its rows as usual.
+    pub(crate) fn select_each(
+        db: &Database,
+        distinct: bool,
+        columns: Vec<SelectColumn>,
+        from: String,
+        joins: Vec<crate::parser::JoinClause>,
+        filter: Option<Condition>,
+        group_by: Option<Vec<String>>,
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+        each: Option<&mut RowSink<'_>>,
+    ) -> Result<QueryResult, DatabaseError> {
+        // v2.0.0: Check if 'from' is a system catalog
+        if super::system_catalogs::SystemCatalog::is_system_catalog(&from) {
+            return super::system_catalogs::SystemCatalog::query(&from, db, database_storage);
+        }
 
-        // First, try to find composite index usage (v1.9.0)
-        // Check if filter is AND chain of Equals conditions
-        let mut equals_conditions: Vec<(&str, &Value)> = Vec::new();
-        Self::extract_equals_from_and(filter, &mut equals_conditions);
+        // v2.7.0: SELECT without FROM (`SELECT add(1, 2)`)
+        if from.is_empty() {
+            return Self::select_without_from(db, &columns, filter.as_ref(), limit, offset, tx_manager, database_storage);
+        }
 
-        if equals_conditions.len() >= 2 {
-            // Multiple equality conditions - look for matching composite index
-            for (idx_name, index) in &db.indexes {
-                if index.table_name() != table_name || !index.is_composite() {
-                    continue;
-                }
+        // Check if this is a JOIN query
+        if !joins.is_empty() {
+            return Self::select_with_join(db, distinct, columns, from, joins, filter, order_by, limit, offset, tx_manager, database_storage);
+        }
 
-                let index_cols = index.column_names();
-                if index_cols.len() != equals_conditions.len() {
-                    continue; // Composite index must match all columns
-                }
+        // Views (v1.10.0, v2.7.0: inlined into this query or materialized)
+        if db.views.contains_key(&from) {
+            return Self::select_from_view(db, distinct, columns, from, filter, group_by, order_by, limit, offset, tx_manager, database_storage);
+        }
 
-                // Check if all index columns are present in equals_conditions
-                let mut matched_values: Vec<(&str, &Value)> = Vec::new();
-                let mut all_matched = true;
+        // v2.7.0: Subquery in FROM (`SELECT * FROM (SELECT ...) AS t`)
+        if DerivedTables::is_derived(&from) {
+            let table = DerivedTables::parse(&from)?;
+            let (rows, row_columns) = DerivedTables::load(db, &table, None, tx_manager, database_storage)?;
+            return Self::select_from_rows(&table.alias, rows, &row_columns, distinct, columns, filter, group_by, order_by, limit, offset);
+        }
 
-                for col_name in index_cols {
-                    if let Some((_col, val)) = equals_conditions.iter().find(|(c, _)| *c == col_name) {
-                        matched_values.push((col_name, val));
-                    } else {
-                        all_matched = false;
-                        break;
-                    }
-                }
+        // v2.7.0: Function call in FROM (`SELECT * FROM gen(3)`)
+        if from.contains('(') {
+            let (rows, row_columns) = FunctionExecutor::call_set(db, &from, tx_manager, database_storage)?;
+            return Self::select_from_rows(&from, rows, &row_columns, distinct, columns, filter, group_by, order_by, limit, offset);
+        }
 
-                if all_matched {
-                    return Some((idx_name, index, matched_values));
-                }
+        // v2.7.0: ctid is read through its own path
+        if let Some(table) = db.get_table(&from)
+            && SystemColumns::used_by_select(table, &columns, filter.as_ref(), order_by.as_ref())
+        {
+            if group_by.is_some() {
+                return Err(DatabaseError::ParseError("System columns cannot be used with GROUP BY".to_string()));
             }
+            return SystemColumns::select(
+                db, table, distinct, &columns, filter.as_ref(), order_by.as_ref(), limit, offset, tx_manager, database_storage,
+            );
         }
 
-        // Fall back to single-column index (existing logic)
-        let (column, value) = match filter {
-            Condition::Equals(col, val) => (col.as_str(), val),
-            Condition::GreaterThan(col, val) => (col.as_str(), val),
-            Condition::LessThan(col, val) => (col.as_str(), val),
-            _ => return None, // Other conditions require full scan
-        };
+        // Check if this is an aggregate query
+        let has_aggregates = columns.iter().any(|col| match col {
+            SelectColumn::Aggregate(_) => true,
+            // v2.7.0: Native aggregates registered through the UDF API
+            SelectColumn::Expression { expr, .. } => FunctionRegistry::aggregate_call(expr).is_some(),
+            _ => false,
+        });
 
-        // Find single-column index
-        for (idx_name, index) in &db.indexes {
-            if index.table_name() == table_name && !index.is_composite() && index.column_name() == column {
-                return Some((idx_name, index, vec![(column, value)]));
+        if group_by.is_some() {
+            Self::select_with_group_by(db, distinct, columns, from, filter, group_by.unwrap(), order_by, limit, offset, tx_manager, database_storage)
+        } else if has_aggregates {
+            // A single row: ORDER BY has nothing to sort, a position is only checked
+            if let Some((key, _)) = &order_by {
+                Self::sort_position(key, columns.len())?;
             }
+            Self::select_aggregate(db, distinct, columns, from, filter, tx_manager, database_storage)
+        } else {
+            Self::select_regular(db, distinct, columns, from, filter, order_by, limit, offset, tx_manager, database_storage, each)
         }
+    }
 
-        None
+    /// SELECT from a view (v2.7.0)
+    ///
+    /// Simple views are merged into the outer query (see `ViewResolver::inline`),
+    /// so the outer WHERE can use the base table's indexes. Other views are
+    /// materialized; on top of those only a column list, DISTINCT, ORDER BY,
+    /// OFFSET and LIMIT are applied.
+    fn select_from_view(
+        db: &Database,
+        distinct: bool,
+        columns: Vec<SelectColumn>,
+        from: String,
+        filter: Option<Condition>,
+        group_by: Option<Vec<String>>,
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        ViewResolver::check_cycles(db, &from)?;
+        let view_stmt = ViewResolver::definition(db, &from)?;
+
+        let outer = Statement::Select {
+            distinct,
+            columns,
+            from: from.clone(),
+            joins: Vec::new(),
+            filter,
+            group_by,
+            order_by,
+            limit,
+            offset,
+        };
+        if let Some(merged) = ViewResolver::inline(&view_stmt, &outer) {
+            return Self::execute_query_stmt(db, &merged, tx_manager, database_storage);
+        }
+
+        let Statement::Select { columns, filter, group_by, order_by, .. } = outer else {
+            unreachable!("outer query is always a SELECT");
+        };
+        let (rows, view_columns) = ViewResolver::materialize(db, &from, tx_manager, database_storage)?;
+        Self::select_from_rows(&from, rows, &view_columns, distinct, columns, filter, group_by, order_by, limit, offset)
     }
 
-    /// Extract Equals conditions from AND chain (v1.9.0)
-    fn extract_equals_from_and<'a>(cond: &'a Condition, result: &mut Vec<(&'a str, &'a Value)>) {
-        match cond {
-            Condition::Equals(col, val) => {
-                result.push((col.as_str(), val));
-            }
-            Condition::And(left, right) => {
-                Self::extract_equals_from_and(left, result);
-                Self::extract_equals_from_and(right, result);
-            }
-            _ => {
-                // Non-equals conditions stop composite index usage
+    /// Rendered rows that satisfy `cond`, their values read back from the text (v2.7.0)
+    fn filter_rendered_rows(
+        rows: Vec<Vec<String>>,
+        row_columns: &[String],
+        cond: &Condition,
+    ) -> Result<Vec<Vec<String>>, DatabaseError> {
+        let text_columns: Vec<crate::types::Column> = row_columns
+            .iter()
+            .map(|name| crate::types::Column {
+                name: name.clone(),
+                data_type: crate::types::DataType::Text,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                foreign_key: None,
+                collation: Collation::C,
+            })
+            .collect();
+        let mut kept = Vec::with_capacity(rows.len());
+        for row in rows {
+            let values = Row::new(row.iter().map(|text| SubqueryExecutor::rendered_value(text)).collect());
+            if ConditionEvaluator::evaluate_with_columns(&text_columns, &values, cond)? {
+                kept.push(row);
             }
         }
+        Ok(kept)
     }
 
-    /// Main SELECT dispatcher
+    /// SELECT over already materialized rows - views, set-returning functions
+    /// and subqueries in FROM (v2.7.0)
     ///
-    /// Routes to appropriate handler based on:
-    /// - JOIN presence → `select_with_join()`
-    /// - GROUP BY → `select_with_group_by()`
-    /// - Aggregates without GROUP BY → `select_aggregate()`
-    /// - Regular → `select_regular()`
-    pub fn select(
-        db: &Database,
+    /// Only a column list, WHERE, DISTINCT, ORDER BY, OFFSET and LIMIT are
+    /// applied. WHERE sees the rendered values as text, which comparisons
+    /// convert to the type of the other side.
+    fn select_from_rows(
+        relation: &str,
+        rows: Vec<Vec<String>>,
+        row_columns: &[String],
         distinct: bool,
         columns: Vec<SelectColumn>,
-        from: String,
-        joins: Vec<crate::parser::JoinClause>,
         filter: Option<Condition>,
         group_by: Option<Vec<String>>,
-        order_by: Option<(String, SortOrder)>,
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<QueryResult, DatabaseError> {
+        if group_by.is_some() || columns.iter().any(|col| !matches!(col, SelectColumn::Regular { .. })) {
+            return Err(DatabaseError::ParseError(format!(
+                "'{relation}' cannot be inlined: only a column list, WHERE, DISTINCT, ORDER BY, LIMIT and OFFSET are supported on top of it"
+            )));
+        }
+
+        // `relation.col` refers to `col`
+        let position = |name: &str| {
+            let name = name
+                .strip_prefix(relation)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .unwrap_or(name);
+            row_columns
+                .iter()
+                .position(|col| col == name)
+                .ok_or_else(|| DatabaseError::ColumnNotFound(name.to_string()))
+        };
+
+        let mut rows = match filter {
+            Some(ref cond) => Self::filter_rendered_rows(rows, row_columns, cond)?,
+            None => rows,
+        };
+
+        let mut indices = Vec::new();
+        let mut column_names = Vec::new();
+        for col in &columns {
+            if let SelectColumn::Regular { name, alias } = col {
+                if name == "*" {
+                    indices.extend(0..row_columns.len());
+                    column_names.extend(row_columns.iter().cloned());
+                } else {
+                    let idx = position(name)?;
+                    indices.push(idx);
+                    column_names.push(alias.as_ref().unwrap_or(&row_columns[idx]).clone());
+                }
+            }
+        }
+        let sort_key = match order_by {
+            Some((key, order)) => {
+                let key = Self::resolve_sort_key(key, &columns, row_columns)?;
+                Some((position(Self::sort_column(&key)?)?, order))
+            }
+            None => None,
+        };
+
+        if let Some((sort_idx, sort_order)) = sort_key {
+            rows.sort_by(|a, b| {
+                let cmp = Self::compare_rendered(&a[sort_idx], &b[sort_idx]);
+                match sort_order {
+                    SortOrder::Asc => cmp,
+                    SortOrder::Desc => cmp.reverse(),
+                }
+            });
+        }
+
+        let projected: Box<dyn Iterator<Item = Result<Vec<String>, DatabaseError>>> = Box::new(
+            rows.into_iter()
+                .map(move |row| Ok(indices.iter().map(|&idx| row[idx].clone()).collect())),
+        );
+        let projected = if distinct {
+            Box::new(Distinct::new(projected))
+        } else {
+            projected
+        };
+        let result_rows = Paginate::new(projected, offset, limit)
+            .collect::<Result<Vec<Vec<String>>, DatabaseError>>()?;
+
+        Ok(QueryResult::Rows(result_rows, column_names))
+    }
+
+    /// SELECT without FROM: a single row of constants and function calls (v2.7.0)
+    fn select_without_from(
+        db: &Database,
+        columns: &[SelectColumn],
+        filter: Option<&Condition>,
         limit: Option<usize>,
         offset: Option<usize>,
         tx_manager: &GlobalTransactionManager,
         database_storage: &crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
-        // v2.0.0: Check if 'from' is a system catalog
-        if super::system_catalogs::SystemCatalog::is_system_catalog(&from) {
-            return super::system_catalogs::SystemCatalog::query(&from, db);
+        if filter.is_some() {
+            return Err(DatabaseError::ParseError("WHERE requires a FROM clause".to_string()));
         }
 
-        // Check if 'from' is a view (v1.10.0)
-        if let Some(view_query) = db.views.get(&from) {
-            // Parse the view's SQL
-            let view_stmt = crate::parser::parse_statement(view_query)
-                .map_err(DatabaseError::ParseError)?;
-
-            // Execute the view query (only SELECT is supported in views)
-            match view_stmt {
-                crate::parser::Statement::Select {
-                    distinct: view_distinct,
-                    columns: view_columns,
-                    from: view_from,
-                    joins: view_joins,
-                    filter: view_filter,
-                    group_by: view_group_by,
-                    order_by: view_order_by,
-                    limit: view_limit,
-                    offset: view_offset,
-                } => {
-                    // Recursively call select (handles nested views)
-                    return Self::select(
+        let empty = Row::new(Vec::new());
+        let subquery_ctx = crate::executor::subquery::SubqueryContext::new();
+        let mut row = Vec::new();
+        let mut column_names = Vec::new();
+
+        for col in columns {
+            match col {
+                SelectColumn::Literal(val) => {
+                    row.push(val.to_string());
+                    column_names.push("?column?".to_string());
+                }
+                SelectColumn::Expression { expr, alias } => {
+                    let value = FunctionExecutor::evaluate(expr, &[], &empty, db, tx_manager, database_storage)?;
+                    row.push(value.to_string());
+                    column_names.push(FunctionExecutor::column_name(expr, alias.as_ref()));
+                }
+                SelectColumn::Subquery { query, alias } => {
+                    let value = crate::executor::subquery::SubqueryExecutor::execute_scalar(
                         db,
-                        view_distinct,
-                        view_columns,
-                        view_from,
-                        view_joins,
-                        view_filter,
-                        view_group_by,
-                        view_order_by,
-                        view_limit,
-                        view_offset,
+                        query,
                         tx_manager,
                         database_storage,
-                    );
+                        &subquery_ctx,
+                    )?;
+                    row.push(value.to_string());
+                    column_names.push(alias.clone().unwrap_or_else(|| "?column?".to_string()));
                 }
-                _ => {
+                SelectColumn::Regular { name, .. } if name == "*" => {
                     return Err(DatabaseError::ParseError(
-                        format!("View '{from}' contains non-SELECT statement")
+                        "SELECT * with no tables specified is not valid".to_string(),
+                    ));
+                }
+                SelectColumn::Regular { name, .. } => return Err(DatabaseError::ColumnNotFound(name.clone())),
+                SelectColumn::Aggregate(_) | SelectColumn::Window { .. } => {
+                    return Err(DatabaseError::ParseError(
+                        "Only constants, function calls and subqueries are supported without FROM".to_string(),
                     ));
                 }
             }
         }
 
-        // Check if this is a JOIN query
-        if !joins.is_empty() {
-            return Self::select_with_join(db, distinct, columns, from, joins, filter, order_by, limit, offset, tx_manager, database_storage);
+        let rows = Paginate::new(std::iter::once(Ok(row)), offset, limit)
+            .collect::<Result<Vec<Vec<String>>, DatabaseError>>()?;
+        Ok(QueryResult::Rows(rows, column_names))
+    }
+
+    /// Compare two rendered values: numerically if both parse as numbers, NULL first (v2.7.0)
+    pub(crate) fn compare_rendered(a: &str, b: &str) -> std::cmp::Ordering {
+        match (a, b) {
+            ("NULL", "NULL") => std::cmp::Ordering::Equal,
+            ("NULL", _) => std::cmp::Ordering::Less,
+            (_, "NULL") => std::cmp::Ordering::Greater,
+            _ => match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal),
+                _ => a.cmp(b),
+            },
+        }
+    }
+
+    /// Join keys are equal, numerically if both render as numbers (1 = 1.0)
+    fn join_keys_match(a: &str, b: &str) -> bool {
+        a == b || Self::compare_rendered(a, b).is_eq()
+    }
+
+    /// Hash join key: equal for keys that `join_keys_match` (v2.7.0)
+    fn join_key(value: &str) -> String {
+        match value.parse::<f64>() {
+            // `+ 0.0` turns -0 into 0
+            Ok(number) => (number + 0.0).to_string(),
+            Err(_) => value.to_string(),
         }
+    }
 
-        // Check if this is an aggregate query
-        let has_aggregates = columns
-            .iter()
-            .any(|col| matches!(col, SelectColumn::Aggregate(_)));
+    /// Is `join` run as a hash join? Joins with ON keys are, CROSS and LATERAL
+    /// joins are nested loops (v2.7.0)
+    pub(crate) fn uses_hash_join(join: &crate::parser::JoinClause) -> bool {
+        let lateral = DerivedTables::is_derived(&join.table)
+            && DerivedTables::parse(&join.table).is_ok_and(|table| table.lateral);
+        !join.on_left.is_empty() && !lateral
+    }
 
-        if group_by.is_some() {
-            Self::select_with_group_by(db, distinct, columns, from, filter, group_by.unwrap(), order_by, limit, offset, tx_manager, database_storage)
-        } else if has_aggregates {
-            Self::select_aggregate(db, distinct, columns, from, filter, tx_manager, database_storage)
-        } else {
-            Self::select_regular(db, distinct, columns, from, filter, order_by, limit, offset, tx_manager, database_storage)
+    /// ORDER BY key of a table: a column or an expression (v2.7.0)
+    fn sort_key(table: &Table, key: &Expression) -> Result<SortKey, DatabaseError> {
+        match key {
+            Expression::Column(name) => table
+                .get_column_index(name)
+                .map(SortKey::Column)
+                .ok_or_else(|| DatabaseError::ColumnNotFound(name.clone())),
+            expr => Ok(SortKey::Expression(expr.clone())),
+        }
+    }
+
+    /// ORDER BY n: index of the n-th of `count` output columns; `None` for
+    /// any other key (v2.7.0)
+    pub(crate) fn sort_position(key: &Expression, count: usize) -> Result<Option<usize>, DatabaseError> {
+        let Expression::Literal(Value::Integer(position)) = key else {
+            return Ok(None);
+        };
+        usize::try_from(*position)
+            .ok()
+            .filter(|position| (1..=count).contains(position))
+            .map(|position| Some(position - 1))
+            .ok_or_else(|| DatabaseError::ParseError(format!("ORDER BY position {position} is not in select list")))
+    }
+
+    /// ORDER BY key with a position replaced by the select list item it
+    /// names, `*` standing for the columns `star` (v2.7.0)
+    pub(crate) fn resolve_sort_key(key: Expression, columns: &[SelectColumn], star: &[String]) -> Result<Expression, DatabaseError> {
+        let mut items = Vec::new();
+        for col in columns {
+            match col {
+                SelectColumn::Regular { name, .. } if name == "*" => {
+                    items.extend(star.iter().map(|name| Some(Expression::Column(name.clone()))));
+                }
+                SelectColumn::Regular { name, .. } => items.push(Some(Expression::Column(name.clone()))),
+                SelectColumn::Expression { expr, .. } => items.push(Some(expr.clone())),
+                SelectColumn::Literal(value) => items.push(Some(Expression::Literal(value.clone()))),
+                // Computed from rows that are already sorted
+                SelectColumn::Aggregate(_) | SelectColumn::Subquery { .. } | SelectColumn::Window { .. } => items.push(None),
+            }
+        }
+        match Self::sort_position(&key, items.len())? {
+            Some(idx) => items.swap_remove(idx).ok_or_else(|| {
+                DatabaseError::ParseError(format!("ORDER BY position {} refers to a column that cannot be sorted on", idx + 1))
+            }),
+            None => Ok(key),
+        }
+    }
+
+    /// Output column an ORDER BY key names, where only columns can be sorted by (v2.7.0)
+    pub(crate) fn sort_column(key: &Expression) -> Result<&str, DatabaseError> {
+        match key {
+            Expression::Column(name) => Ok(name),
+            _ => Err(DatabaseError::ParseError(
+                "ORDER BY an expression is not supported here, only a column name".to_string(),
+            )),
         }
     }
 
@@ -235,39 +574,71 @@ impl QueryExecutor {
     /// 4. DISTINCT
     /// 5. OFFSET
     /// 6. LIMIT
+    ///
+    /// v2.7.0: Steps run as a pull-based pipeline (see `pipeline`), so without
+    /// ORDER BY/window functions the scan stops as soon as LIMIT is satisfied.
     fn select_regular(
         db: &Database,
         distinct: bool,
         columns: Vec<SelectColumn>,
         from: String,
         filter: Option<Condition>,
-        order_by: Option<(String, SortOrder)>,
+        order_by: Option<(Expression, SortOrder)>,
         limit: Option<usize>,
         offset: Option<usize>,
         tx_manager: &GlobalTransactionManager,
         database_storage: &crate::storage::DatabaseStorage,
+        each: Option<&mut RowSink<'_>>,
     ) -> Result<QueryResult, DatabaseError> {
         let table = db
             .get_table(&from)
             .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
+        let table_columns: Vec<String> = table.columns.iter().map(|col| col.name.clone()).collect();
+        let order_by = match order_by {
+            Some((key, order)) => Some((Self::resolve_sort_key(key, &columns, &table_columns)?, order)),
+            None => None,
+        };
 
-        // Separate regular columns from CASE expressions (v1.10.0)
-        let mut regular_col_names: Vec<String> = Vec::new();
-        let mut case_expressions: Vec<(usize, &CaseExpression)> = Vec::new();
-        let mut literals: Vec<(usize, &Value)> = Vec::new(); // v2.6.0: Track literal values
-        let mut scalar_subqueries: Vec<(usize, &Box<Statement>, &Option<String>)> = Vec::new(); // v2.6.0: Track subqueries
-        let mut window_functions: Vec<(usize, &crate::parser::WindowFunction, &crate::parser::WindowSpec, &Option<String>)> = Vec::new(); // v2.6.0
-
-        for (idx, col) in columns.iter().enumerate() {
+        // Output columns and their names in select-list order (v2.7.0: one
+        // pass, so mixed kinds keep their places; `*` expands in place)
+        let mut outputs: Vec<Output<'_>> = Vec::new();
+        let mut column_names: Vec<String> = Vec::new();
+        let mut expressions: Vec<&Expression> = Vec::new();
+        let mut window_functions: Vec<(&crate::parser::WindowFunction, &crate::parser::WindowSpec)> = Vec::new(); // v2.6.0
+        let mut window_positions: Vec<usize> = Vec::new();
+        for col in &columns {
             match col {
-                SelectColumn::Regular(name) => regular_col_names.push(name.clone()),
-                SelectColumn::Case(case_expr) => case_expressions.push((idx, case_expr)),
-                SelectColumn::Literal(val) => literals.push((idx, val)), // v2.6.0
+                SelectColumn::Regular { name, .. } if name == "*" => {
+                    for (idx, column) in table.columns.iter().enumerate() {
+                        outputs.push(Output::Column(idx));
+                        column_names.push(column.name.clone());
+                    }
+                }
+                SelectColumn::Regular { name, alias } => {
+                    let idx = table
+                        .get_column_index(name)
+                        .ok_or_else(|| DatabaseError::ParseError(format!("Unknown column: {name}")))?;
+                    outputs.push(Output::Column(idx));
+                    column_names.push(alias.as_ref().unwrap_or(&table.columns[idx].name).clone());
+                }
+                SelectColumn::Literal(val) => { // v2.6.0
+                    outputs.push(Output::Literal(val));
+                    column_names.push("?column?".to_string()); // PostgreSQL uses ?column? for unnamed literals
+                }
                 SelectColumn::Subquery { query, alias } => { // v2.6.0
-                    scalar_subqueries.push((idx, query, alias));
+                    outputs.push(Output::Subquery(query));
+                    column_names.push(alias.clone().unwrap_or_else(|| "?column?".to_string()));
                 }
                 SelectColumn::Window { function, spec, alias } => { // v2.6.0
-                    window_functions.push((idx, function, spec, alias));
+                    window_positions.push(outputs.len());
+                    outputs.push(Output::Window);
+                    window_functions.push((function, spec));
+                    column_names.push(alias.clone().unwrap_or_else(|| "?column?".to_string()));
+                }
+                SelectColumn::Expression { expr, alias } => { // v2.7.0
+                    outputs.push(Output::Expression(expressions.len()));
+                    expressions.push(expr);
+                    column_names.push(FunctionExecutor::column_name(expr, alias.as_ref()));
                 }
                 SelectColumn::Aggregate(_) => {
                     panic!("Aggregate in regular select should not happen")
@@ -275,262 +646,329 @@ impl QueryExecutor {
             }
         }
 
-        let is_select_all = regular_col_names.len() == 1 && !regular_col_names.is_empty() && regular_col_names[0] == "*";
-
-        // Only process regular columns for indices
-        let column_indices: Vec<usize> = if is_select_all && case_expressions.is_empty() {
-            (0..table.columns.len()).collect()
-        } else {
-            regular_col_names
-                .iter()
-                .map(|col| {
-                    table
-                        .get_column_index(col)
-                        .ok_or_else(|| DatabaseError::ParseError(format!("Unknown column: {col}")))
-                })
-                .collect::<Result<Vec<_>, _>>()?
-        };
-
-        // Build column names for result (include CASE aliases and literals)
-        let mut column_names: Vec<String> = column_indices
+        let is_select_all = columns.iter().any(|col| matches!(col, SelectColumn::Regular { name, .. } if name == "*"));
+        let column_indices: Vec<usize> = outputs
             .iter()
-            .map(|&idx| table.columns[idx].name.clone())
+            .filter_map(|output| match output {
+                Output::Column(idx) => Some(*idx),
+                _ => None,
+            })
             .collect();
 
-        // Add CASE expression column names (use alias or "case")
-        for (_, case_expr) in &case_expressions {
-            column_names.push(case_expr.alias.clone().unwrap_or_else(|| "case".to_string()));
-        }
-
-        // Add literal column names (v2.6.0)
-        for (_, _val) in &literals {
-            column_names.push("?column?".to_string()); // PostgreSQL uses ?column? for unnamed literals
-        }
-
-        // Add scalar subquery column names (v2.6.0)
-        for (_, _, alias) in &scalar_subqueries {
-            let col_name = alias.as_ref().map(|s| s.clone()).unwrap_or_else(|| "?column?".to_string());
-            column_names.push(col_name);
-        }
-
-        // Add window function column names (v2.6.0)
-        for (_, _, _, alias) in &window_functions {
-            let col_name = alias.as_ref().map(|s| s.clone()).unwrap_or_else(|| "?column?".to_string());
-            column_names.push(col_name);
-        }
-
         // Get snapshot for READ COMMITTED isolation (v2.1.0)
         // Creates new snapshot before each statement
         let snapshot = tx_manager.get_snapshot();
         let subquery_ctx = crate::executor::subquery::SubqueryContext::new();  // v2.6.0
+        let memory = database_storage.query_memory();  // v2.7.0
 
-        // Try to use index if available
-        let use_index = Self::find_usable_index(db, &from, &filter);
-
-        // Get rows from PagedTable (v2.0.0)
-        let paged_table = database_storage.get_paged_table(&from)
-            .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
-        let rows_vec = paged_table.get_all_rows()?;
-        let rows_iter: Box<dyn Iterator<Item = &Row>> = Box::new(rows_vec.iter());
-
-        // Collect rows with their original indices (for sorting)
-        let mut rows_with_data: Vec<(Row, Vec<String>)> = Vec::new();
-
-        // Index scan vs sequential scan (v1.9.0: supports composite indexes)
-        if let Some((_idx_name, index, col_values)) = use_index {
-            // INDEX SCAN: Use index for fast lookup (single or composite)
-            let row_indices = if index.is_composite() && col_values.len() > 1 {
-                // Composite index: extract values in column order
-                let values: Vec<Value> = col_values.iter().map(|(_, v)| (*v).clone()).collect();
-                index.search_composite(&values)
-            } else {
-                // Single column index
-                index.search(col_values[0].1)
-            };
+        // Try to use index if available (v2.7.0: equality prefix plus a range)
+        let use_index = Planner::choose_index(db, &from, filter.as_ref());
 
-            // Get all rows first (needed to access by index)
-            let paged_table = database_storage.get_paged_table(&from)
-                .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
-            let all_rows = paged_table.get_all_rows()?;
+        // Get rows from PagedTable (v2.0.0; v2.7.0: partitions pruned by the WHERE clause)
+        let scan = if use_index.is_some() {
+            Partitions::scan_for_index(db, database_storage, &from, filter.as_ref())?
+        } else {
+            Partitions::scan(db, database_storage, &from, filter.as_ref())?
+        };
 
-            for &row_idx in &row_indices {
-                if row_idx >= all_rows.len() {
-                    continue; // Skip invalid indices
-                }
+        let sort_key = match order_by {
+            Some((ref key, ref sort_order)) => {
+                Some((Self::sort_key(table, key)?, sort_order.clone()))
+            }
+            None => None,
+        };
+        // v2.7.0: a text column sorts by its collation
+        let sort_collation = match &sort_key {
+            Some((SortKey::Column(idx), _)) => table.columns[*idx].collation,
+            _ => Collation::C,
+        };
 
-                let row = &all_rows[row_idx];
+        // v2.7.0: ORDER BY a column the index search returns in order, with a
+        // LIMIT: rows are read in index order until the last one needed
+        let ordered_path = match (&use_index, &order_by, limit) {
+            (Some(access), Some((Expression::Column(column), _)), Some(_)) if !distinct && window_functions.is_empty() => {
+                Planner::ordered_path(db, &from, access, column)
+            }
+            _ => None,
+        };
+        let ordered = ordered_path.is_some();
 
-                // MVCC: Check row visibility
-                if !row.is_visible_to_snapshot(&snapshot) {
-                    continue;
-                }
+        // Predicate pushdown (v2.7.0): simple conjuncts are checked inside the page scan,
+        // only subquery conditions are left for the executor
+        let pushdown = ScanPushdown::split(filter.as_ref());
 
-                // Index already filtered by equality, but double-check condition (v2.6.0: subquery support)
-                if let Some(ref cond) = filter
-                    && !ConditionEvaluator::evaluate_with_context(&table.columns, row, cond, db, tx_manager, database_storage, &subquery_ctx)? {
-                        continue;
-                    }
-
-                // Build result row: regular columns + CASE expressions + literals
-                let mut result_row: Vec<String> = column_indices
-                    .iter()
-                    .map(|&idx| row.values[idx].to_string())
-                    .collect();
+        // Vectorized execution (v2.7.0, opt-in): WHERE and SELECT arithmetic of a
+        // sequential scan run over batches of rows when every part compiles
+        let vector_plan = if database_storage.vectorized() && use_index.is_none() && pushdown.residual().is_none() {
+            VectorPlan::compile(&table.columns, filter.as_ref(), expressions.iter().copied())
+        } else {
+            None
+        };
 
-                // Evaluate CASE expressions (v1.10.0)
-                for (_, case_expr) in &case_expressions {
-                    let case_value = Self::evaluate_case(case_expr, &table.columns, row)?;
-                    result_row.push(case_value.to_string());
-                }
+        // Scan-level projection (v2.7.0): when the output is plain columns, the scan
+        // only hands over the columns the query reads
+        let projection = if use_index.is_none()
+            && vector_plan.is_none()
+            && !is_select_all
+            && outputs.iter().all(|output| matches!(output, Output::Column(_) | Output::Literal(_)))
+            && pushdown.residual().is_none()
+            && !matches!(sort_key, Some((SortKey::Expression(_), _)))
+        {
+            Some(ScanProjection::new(
+                column_indices.iter().copied().chain(sort_key.as_ref().and_then(|(key, _)| match key {
+                    SortKey::Column(idx) => Some(*idx),
+                    SortKey::Expression(_) => None,
+                })),
+            ))
+        } else {
+            None
+        };
+        let value_index = |table_idx: usize| {
+            projection
+                .as_ref()
+                .and_then(|p| p.position(table_idx))
+                .unwrap_or(table_idx)
+        };
+        let outputs: Vec<Output<'_>> = outputs
+            .into_iter()
+            .map(|output| match output {
+                Output::Column(idx) => Output::Column(value_index(idx)),
+                output => output,
+            })
+            .collect();
+        let sort_key = sort_key.map(|(key, order)| match key {
+            SortKey::Column(idx) => (SortKey::Column(value_index(idx)), order),
+            expr @ SortKey::Expression(_) => (expr, order),
+        });
+
+        // Build result row in select-list order
+        let project = |row: &Row| -> Result<Vec<String>, DatabaseError> {
+            outputs
+                .iter()
+                .map(|output| {
+                    Ok(match output {
+                        Output::Column(idx) => row.values[*idx].to_string(),
+                        Output::Literal(val) => val.to_string(), // v2.6.0
+                        // Execute scalar subqueries (v2.6.0)
+                        Output::Subquery(query) => {
+                            SubqueryExecutor::execute_scalar(db, query, tx_manager, database_storage, &subquery_ctx)?.to_string()
+                        }
+                        // Evaluate function calls and arithmetic (v2.7.0); a vectorized scan
+                        // has already appended their values after the table columns
+                        Output::Expression(position) => match row.values.get(table.columns.len() + position) {
+                            Some(value) if vector_plan.is_some() => value.to_string(),
+                            _ => FunctionExecutor::evaluate(expressions[*position], &table.columns, row, db, tx_manager, database_storage)?
+                                .to_string(),
+                        },
+                        Output::Window => String::new(),
+                    })
+                })
+                .collect()
+        };
 
-                // Add literal values (v2.6.0)
-                for (_, val) in &literals {
-                    result_row.push(val.to_string());
+        // Index scan vs sequential scan (v1.9.0: supports composite indexes)
+        let matching: RowSource<'_> = if let Some(path) = ordered_path {
+            // ORDERED INDEX SCAN (v2.7.0): rows are fetched by ordinal, only the
+            // pages holding them are read, until OFFSET + LIMIT of them match
+            let wanted = offset.unwrap_or(0).saturating_add(limit.unwrap_or(usize::MAX));
+            let descending = matches!(sort_key, Some((_, SortOrder::Desc)));
+            let paged_table = database_storage
+                .get_paged_table(&from)
+                .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
+            let mut fetch = paged_table.fetch();
+            let mut read = 0;
+            let mut rows = Vec::new();
+            for row_idx in path.ordered_indices(descending) {
+                if rows.len() >= wanted {
+                    break;
                 }
-
-                // Execute scalar subqueries (v2.6.0)
-                for (_, query, _) in &scalar_subqueries {
-                    let subquery_value = crate::executor::subquery::SubqueryExecutor::execute_scalar(
-                        db,
-                        query,
-                        tx_manager,
-                        database_storage,
-                        &subquery_ctx,
-                    )?;
-                    result_row.push(subquery_value.to_string());
+                read += 1;
+                let Some(row) = fetch.row(row_idx)? else {
+                    continue; // Skip invalid indices
+                };
+                let matches = row.is_visible_to_snapshot(&snapshot)
+                    && match filter {
+                        Some(ref cond) => ConditionEvaluator::evaluate_with_context(&table.columns, &row, cond, db, tx_manager, database_storage, &subquery_ctx)?,
+                        None => true,
+                    };
+                if matches {
+                    rows.push(row);
                 }
-
-                rows_with_data.push((row.clone(), result_row));
             }
+            db.activity.count_index_scan(&from, path.name, read);
+            Box::new(rows.into_iter().map(Ok))
+        } else if let Some(access) = use_index {
+            // INDEX SCAN: Use index for fast lookup (single or composite;
+            // v2.7.0: one search per OR branch, their rows united)
+            let mut row_indices = Vec::new();
+            for path in access.paths() {
+                let found = path.row_indices();
+                db.activity.count_index_scan(&from, path.name, found.len());  // v2.7.0
+                row_indices.extend(found);
+            }
+            row_indices.sort_unstable();
+            row_indices.dedup();
+
+            // Index positions are row ordinals, so the table has to be loaded to resolve them
+            let all_rows = scan.collect::<Result<Vec<Row>, DatabaseError>>()?;
+            let source = row_indices
+                .into_iter()
+                .filter_map(move |row_idx| all_rows.get(row_idx).cloned()) // Skip invalid indices
+                .map(Ok);
+
+            // Index answered part of the WHERE clause, the whole condition is checked (v2.6.0: subquery support)
+            Box::new(Filter::new(Visible::new(source, &snapshot), |row: &Row| match filter {
+                Some(ref cond) => ConditionEvaluator::evaluate_with_context(&table.columns, row, cond, db, tx_manager, database_storage, &subquery_ctx),
+                None => Ok(true),
+            }))
         } else {
-            // SEQUENTIAL SCAN: Full table scan
-            for row in rows_iter {
-                // MVCC: Check row visibility
+            // SEQUENTIAL SCAN: stream pages lazily (v2.7.0), MVCC visibility and
+            // pushed-down predicates are checked before a tuple leaves the scan
+            let mut scan = scan.with_predicate(Box::new(|row: &Row| {
                 if !row.is_visible_to_snapshot(&snapshot) {
-                    continue;
+                    return Ok(false);
                 }
+                if vector_plan.is_some() {
+                    return Ok(true);
+                }
+                pushdown.matches(&table.columns, row)
+            }));
+            if let Some(ref projection) = projection {
+                scan = scan
+                    .reading(projection.columns().iter().copied().chain(pushdown.columns(&table.columns)).collect())
+                    .with_projection(projection.columns().to_vec());
+            }
 
-                if let Some(ref cond) = filter
-                    && !ConditionEvaluator::evaluate_with_context(&table.columns, row, cond, db, tx_manager, database_storage, &subquery_ctx)? {
-                        continue;
-                    }
-
-                // Build result row: regular columns + CASE expressions + literals
-                let mut result_row: Vec<String> = column_indices
-                    .iter()
-                    .map(|&idx| row.values[idx].to_string())
-                    .collect();
+            // Residual WHERE (subqueries) sees full rows - projection is disabled in that case
+            match (pushdown.residual(), &vector_plan) {
+                (Some(cond), _) => Box::new(Filter::new(scan, |row: &Row| {
+                    ConditionEvaluator::evaluate_with_context(&table.columns, row, cond, db, tx_manager, database_storage, &subquery_ctx)
+                })),
+                (None, Some(plan)) => Box::new(VectorScan::new(scan, plan, &table.columns)),
+                (None, None) => Box::new(scan),
+            }
+        };
 
-                // Evaluate CASE expressions (v1.10.0)
-                for (_, case_expr) in &case_expressions {
-                    let case_value = Self::evaluate_case(case_expr, &table.columns, row)?;
-                    result_row.push(case_value.to_string());
+        // ORDER BY and window functions need every matching row before emitting any,
+        // everything else streams straight through to DISTINCT/OFFSET/LIMIT (v2.7.0)
+        let projected: Box<dyn Iterator<Item = Result<Vec<String>, DatabaseError>> + '_> =
+            if (sort_key.is_none() || ordered) && window_functions.is_empty() {
+                Box::new(matching.map(|row| row.and_then(|row| project(&row))))
+            } else if let (Some((key, sort_order)), true) = (&sort_key, window_functions.is_empty()) {
+                // ORDER BY alone: only the sort key and the output row are kept, and
+                // they spill to temp files past work_mem (v2.7.0)
+                let descending = *sort_order == SortOrder::Desc;
+                let mut sort = ExternalSort::new(
+                    database_storage.work_mem(),
+                    database_storage.temp_files().cloned(),
+                    move |(a, _): &(Value, Vec<String>), (b, _): &(Value, Vec<String>)| {
+                        let cmp = sort_collation.compare_for_sort(a, b);
+                        if descending { cmp.reverse() } else { cmp }
+                    },
+                )
+                .with_memory(memory.clone());
+                for row in matching {
+                    let row = row?;
+                    let sort_value = match key {
+                        SortKey::Column(idx) => row.values[*idx].clone(),
+                        SortKey::Expression(expr) => {
+                            FunctionExecutor::evaluate(expr, &table.columns, &row, db, tx_manager, database_storage)?
+                        }
+                    };
+                    sort.push((sort_value, project(&row)?))?;
                 }
-
-                // Add literal values (v2.6.0)
-                for (_, val) in &literals {
-                    result_row.push(val.to_string());
+                Box::new(sort.finish()?.map(|item| item.map(|(_, row_data)| row_data)))
+            } else {
+                let mut rows_with_data: Vec<(Row, Vec<String>)> = Vec::new();
+                for row in matching {
+                    let row = row?;
+                    let row_data = project(&row)?;
+                    memory.reserve(QueryMemory::size_of(&row) + QueryMemory::size_of(&row_data))?;
+                    rows_with_data.push((row, row_data));
                 }
 
-                // Execute scalar subqueries (v2.6.0)
-                for (_, query, _) in &scalar_subqueries {
-                    let subquery_value = crate::executor::subquery::SubqueryExecutor::execute_scalar(
-                        db,
-                        query,
-                        tx_manager,
-                        database_storage,
-                        &subquery_ctx,
-                    )?;
-                    result_row.push(subquery_value.to_string());
+                // Execute window functions (v2.6.0)
+                // Window functions need ALL rows to compute results
+                if !window_functions.is_empty() {
+                    let all_rows: Vec<&Row> = rows_with_data.iter().map(|(r, _)| r).collect();
+
+                    // Collect all window function results first
+                    let mut all_window_results = Vec::new();
+                    for (function, spec) in &window_functions {
+                        let window_results = crate::executor::window::WindowFunctionExecutor::execute(
+                            function,
+                            spec,
+                            &all_rows,
+                            &table.columns,
+                        )?;
+                        all_window_results.push(window_results);
+                    }
+
+                    // Put the window results into their places in each row's result_row
+                    for (row_idx, (_, result_row)) in rows_with_data.iter_mut().enumerate() {
+                        for (window_results, &position) in all_window_results.iter().zip(&window_positions) {
+                            result_row[position].clone_from(&window_results[row_idx]);
+                        }
+                    }
                 }
 
-                rows_with_data.push((row.clone(), result_row));
-            }
-        }
+                // Apply ORDER BY if specified
+                if let Some((SortKey::Column(sort_col_idx), sort_order)) = &sort_key {
+                    rows_with_data.sort_by(|(row_a, _), (row_b, _)| {
+                        let val_a = &row_a.values[*sort_col_idx];
+                        let val_b = &row_b.values[*sort_col_idx];
 
-        // Execute window functions (v2.6.0)
-        // Window functions need ALL rows to compute results
-        if !window_functions.is_empty() {
-            let all_rows: Vec<&Row> = rows_with_data.iter().map(|(r, _)| r).collect();
-
-            // Collect all window function results first
-            let mut all_window_results = Vec::new();
-            for (_, function, spec, _) in &window_functions {
-                let window_results = crate::executor::window::WindowFunctionExecutor::execute(
-                    function,
-                    spec,
-                    &all_rows,
-                    &table.columns,
-                )?;
-                all_window_results.push(window_results);
-            }
+                        let cmp = sort_collation.compare_for_sort(val_a, val_b);
 
-            // Append all window results to each row's result_row
-            for (row_idx, (_, result_row)) in rows_with_data.iter_mut().enumerate() {
-                for window_results in &all_window_results {
-                    result_row.push(window_results[row_idx].clone());
+                        match sort_order {
+                            SortOrder::Asc => cmp,
+                            SortOrder::Desc => cmp.reverse(),
+                        }
+                    });
                 }
-            }
-        }
 
-        // Apply ORDER BY if specified
-        if let Some((sort_column, sort_order)) = order_by {
-            let sort_col_idx = table
-                .get_column_index(&sort_column)
-                .ok_or_else(|| DatabaseError::ParseError(format!("Unknown column: {sort_column}")))?;
-
-            rows_with_data.sort_by(|(row_a, _), (row_b, _)| {
-                let val_a = &row_a.values[sort_col_idx];
-                let val_b = &row_b.values[sort_col_idx];
-
-                let cmp = match (val_a, val_b) {
-                    (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
-                    (Value::Real(a), Value::Real(b)) => {
-                        if a < b {
-                            std::cmp::Ordering::Less
-                        } else if a > b {
-                            std::cmp::Ordering::Greater
-                        } else {
-                            std::cmp::Ordering::Equal
+                // ORDER BY an expression: evaluate it once per row, then sort (v2.7.0)
+                if let Some((SortKey::Expression(expr), sort_order)) = &sort_key {
+                    let mut keyed = rows_with_data
+                        .into_iter()
+                        .map(|(row, row_data)| {
+                            let key = FunctionExecutor::evaluate(expr, &table.columns, &row, db, tx_manager, database_storage)?;
+                            Ok((key, row, row_data))
+                        })
+                        .collect::<Result<Vec<_>, DatabaseError>>()?;
+                    keyed.sort_by(|(key_a, ..), (key_b, ..)| {
+                        let cmp = compare_for_sort(key_a, key_b);
+                        match sort_order {
+                            SortOrder::Asc => cmp,
+                            SortOrder::Desc => cmp.reverse(),
                         }
-                    }
-                    (Value::Text(a), Value::Text(b)) => a.cmp(b),
-                    (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
-                    (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
-                    (Value::Null, _) => std::cmp::Ordering::Less,
-                    (_, Value::Null) => std::cmp::Ordering::Greater,
-                    _ => std::cmp::Ordering::Equal,
-                };
-
-                match sort_order {
-                    SortOrder::Asc => cmp,
-                    SortOrder::Desc => cmp.reverse(),
+                    });
+                    rows_with_data = keyed.into_iter().map(|(_, row, row_data)| (row, row_data)).collect();
                 }
-            });
-        }
 
-        // Extract result rows
-        let mut result_rows: Vec<Vec<String>> = rows_with_data
-            .into_iter()
-            .map(|(_, row_data)| row_data)
-            .collect();
+                Box::new(rows_with_data.into_iter().map(|(_, row_data)| Ok(row_data)))
+            };
 
         // Apply DISTINCT if specified
-        if distinct {
-            use std::collections::HashSet;
-            let mut seen: HashSet<Vec<String>> = HashSet::new();
-            result_rows.retain(|row| seen.insert(row.clone()));
-        }
-
-        // Apply OFFSET
-        if let Some(offset_val) = offset {
-            result_rows = result_rows.into_iter().skip(offset_val).collect();
-        }
+        let projected = if distinct {
+            Box::new(Distinct::new(projected))
+        } else {
+            projected
+        };
 
-        // Apply LIMIT
-        if let Some(limit_val) = limit {
-            result_rows.truncate(limit_val);
+        // Apply OFFSET and LIMIT - stops pulling from the scan once LIMIT is reached
+        // (v2.7.0: the result counts against max_query_memory unless it is
+        // handed on row by row)
+        let paginated = Paginate::new(projected, offset, limit);
+        if let Some(each) = each {
+            for row in paginated {
+                each(row?)?;
+            }
+            return Ok(QueryResult::Rows(Vec::new(), column_names));
         }
+        let result_rows = paginated
+            .map(|row| row.and_then(|row| memory.reserve(QueryMemory::size_of(&row)).map(|()| row)))
+            .collect::<Result<Vec<Vec<String>>, DatabaseError>>()?;
 
         Ok(QueryResult::Rows(result_rows, column_names))
     }
@@ -556,10 +994,31 @@ impl QueryExecutor {
         let snapshot = tx_manager.get_snapshot();
         let subquery_ctx = crate::executor::subquery::SubqueryContext::new();  // v2.6.0
 
-        // Get rows from PagedTable
-        let paged_table = database_storage.get_paged_table(&from)
-            .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
-        let rows_vec = paged_table.get_all_rows()?;
+        // v2.7.0: An unfiltered COUNT(*) reads the version counts of the table
+        // once a previous one scanned it
+        let counted_table = Self::counts_all_rows(table, &columns, filter.as_ref())
+            .then(|| database_storage.get_paged_table(&from))
+            .flatten();
+        if let Some(count) = counted_table.and_then(|t| t.visible_row_count(&snapshot, tx_manager.get_oldest_active_tx())) {
+            let (result_row, column_names) = columns
+                .iter()
+                .map(|col| match col {
+                    SelectColumn::Literal(val) => (val.to_string(), "?column?".to_string()),
+                    _ => (count.to_string(), "count".to_string()),
+                })
+                .unzip();
+            return Ok(QueryResult::Rows(vec![result_row], column_names));
+        }
+
+        // Get rows from PagedTable (v2.7.0: or the partitions left after pruning)
+        let mut scan = Partitions::scan(db, database_storage, &from, filter.as_ref())?;
+        if let Some(reading) = Self::aggregate_reading(table, &columns, filter.as_ref()) {
+            scan = scan.reading(reading);
+        }
+        let rows_vec = scan.collect::<Result<Vec<Row>, DatabaseError>>()?;
+        if let Some(paged_table) = counted_table {
+            paged_table.count_versions(&rows_vec);
+        }
 
         // Collect visible rows that match the filter (v2.6.0: subquery support)
         let visible_rows: Vec<&Row> = rows_vec
@@ -595,16 +1054,11 @@ impl QueryExecutor {
                     result_row.push(val.to_string());
                     column_names.push("?column?".to_string());
                 }
-                SelectColumn::Regular(_) => {
+                SelectColumn::Regular { .. } => {
                     return Err(DatabaseError::ParseError(
                         "Cannot mix aggregates with regular columns without GROUP BY".to_string(),
                     ));
                 }
-                SelectColumn::Case(_) => {
-                    return Err(DatabaseError::ParseError(
-                        "Cannot use CASE expressions with aggregates without GROUP BY".to_string(),
-                    ));
-                }
                 SelectColumn::Subquery { .. } => {
                     return Err(DatabaseError::ParseError(
                         "Scalar subqueries in SELECT not yet implemented".to_string(),
@@ -615,12 +1069,86 @@ impl QueryExecutor {
                         "Window functions not supported with aggregates/GROUP BY".to_string(),
                     ));
                 }
+                SelectColumn::Expression { expr, alias } => {
+                    // Native aggregates (v2.7.0)
+                    let Some((name, arg)) = FunctionRegistry::aggregate_call(&expr) else {
+                        return Err(DatabaseError::ParseError(
+                            "Cannot mix aggregates with expressions without GROUP BY".to_string(),
+                        ));
+                    };
+                    result_row.push(Self::compute_udf_aggregate(name, arg, table, &visible_rows, db, tx_manager, database_storage)?);
+                    column_names.push(FunctionExecutor::column_name(&expr, alias.as_ref()));
+                }
             }
         }
 
         Ok(QueryResult::Rows(vec![result_row], column_names))
     }
 
+    /// Is this `SELECT COUNT(*)` over the whole of a plain table? (v2.7.0)
+    ///
+    /// Literals may go along; partitioned and foreign tables have no
+    /// versions of their own to count.
+    fn counts_all_rows(table: &Table, columns: &[SelectColumn], filter: Option<&Condition>) -> bool {
+        filter.is_none()
+            && table.partitioning.is_none()
+            && table.foreign.is_none()
+            && columns.iter().any(|col| matches!(col, SelectColumn::Aggregate(AggregateFunction::Count(CountTarget::All))))
+            && columns.iter().all(|col| {
+                matches!(col, SelectColumn::Aggregate(AggregateFunction::Count(CountTarget::All)) | SelectColumn::Literal(_))
+            })
+    }
+
+    /// Table columns an aggregate query reads, so columnar tables skip the rest (v2.7.0)
+    ///
+    /// `None` when some select item or condition may need the whole tuple.
+    fn aggregate_reading(table: &Table, columns: &[SelectColumn], filter: Option<&Condition>) -> Option<Vec<usize>> {
+        let mut names = Vec::new();
+        for col in columns {
+            match col {
+                SelectColumn::Aggregate(AggregateFunction::Filter { filter, .. }) if !ScanPushdown::is_pushable(filter) => {
+                    return None;
+                }
+                SelectColumn::Aggregate(agg) => ViewResolver::collect_aggregate_columns(agg, &mut names),
+                SelectColumn::Literal(_) => {}
+                _ => return None,
+            }
+        }
+        if let Some(cond) = filter {
+            if !ScanPushdown::is_pushable(cond) {
+                return None;
+            }
+            ViewResolver::collect_condition_columns(cond, &mut names);
+        }
+        names.iter().map(|name| table.get_column_index(name)).collect()
+    }
+
+    /// Compute a native aggregate registered through the UDF API (v2.7.0)
+    fn compute_udf_aggregate(
+        name: &str,
+        arg: &crate::parser::Expression,
+        table: &Table,
+        rows: &[&Row],
+        db: &Database,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<String, DatabaseError> {
+        let mut accumulator = FunctionRegistry::accumulator(name)
+            .ok_or_else(|| DatabaseError::ParseError(format!("Aggregate function '{name}' does not exist")))?;
+        for row in rows {
+            let value = FunctionExecutor::evaluate(arg, &table.columns, row, db, tx_manager, database_storage)?;
+            accumulator.update(&value)?;
+        }
+        Ok(accumulator.finish()?.to_string())
+    }
+
+    /// Add to an integer SUM; overflow is an error instead of wrapping (v2.7.0)
+    fn add_to_sum(sum: Option<i64>, value: i64) -> Result<i64, DatabaseError> {
+        sum.unwrap_or(0)
+            .checked_add(value)
+            .ok_or_else(|| DatabaseError::NumericOutOfRange("integer out of range".to_string()))
+    }
+
     /// Compute aggregate function (COUNT, SUM, AVG, MIN, MAX)
     fn compute_aggregate(
         agg_func: &AggregateFunction,
@@ -628,6 +1156,16 @@ impl QueryExecutor {
         rows: &[&Row],
     ) -> Result<(String, String), DatabaseError> {
         match agg_func {
+            // v2.7.0: FILTER (WHERE ...) narrows the rows the aggregate sees
+            AggregateFunction::Filter { function, filter } => {
+                let mut kept = Vec::with_capacity(rows.len());
+                for row in rows {
+                    if ConditionEvaluator::evaluate(table, row, filter)? {
+                        kept.push(*row);
+                    }
+                }
+                Self::compute_aggregate(function, table, &kept)
+            }
             AggregateFunction::Count(target) => {
                 let count = match target {
                     CountTarget::All => rows.len(),
@@ -655,7 +1193,10 @@ impl QueryExecutor {
                 for row in rows {
                     match &row.values[col_idx] {
                         Value::Integer(i) => {
-                            sum_int = Some(sum_int.unwrap_or(0) + i);
+                            sum_int = Some(Self::add_to_sum(sum_int, *i)?);
+                        }
+                        Value::SmallInt(i) => {
+                            sum_int = Some(Self::add_to_sum(sum_int, i64::from(*i))?);
                         }
                         Value::Real(r) => {
                             sum_real = Some(sum_real.unwrap_or(0.0) + r);
@@ -665,6 +1206,9 @@ impl QueryExecutor {
                     }
                 }
 
+                if sum_real.is_some_and(f64::is_infinite) {
+                    return Err(DatabaseError::NumericOutOfRange("value out of range: overflow".to_string()));
+                }
                 let value = if let Some(r) = sum_real {
                     r.to_string()
                 } else if let Some(i) = sum_int {
@@ -774,6 +1318,10 @@ impl QueryExecutor {
         column_names: &[String],
     ) -> Result<(String, String), DatabaseError> {
         match agg_func {
+            AggregateFunction::Filter { function, filter } => {
+                let kept = Self::filter_rendered_rows(rows.to_vec(), column_names, filter)?;
+                Self::compute_aggregate_from_joined_rows(function, &kept, column_names)
+            }
             AggregateFunction::Count(target) => {
                 let count = match target {
                     CountTarget::All => rows.len(),
@@ -815,13 +1363,16 @@ impl QueryExecutor {
 
                         // Try to parse as integer first, then float
                         if let Ok(i) = val_str.parse::<i64>() {
-                            sum_int = Some(sum_int.unwrap_or(0) + i);
+                            sum_int = Some(Self::add_to_sum(sum_int, i)?);
                         } else if let Ok(f) = val_str.parse::<f64>() {
                             sum_real = Some(sum_real.unwrap_or(0.0) + f);
                         }
                     }
                 }
 
+                if sum_real.is_some_and(f64::is_infinite) {
+                    return Err(DatabaseError::NumericOutOfRange("value out of range: overflow".to_string()));
+                }
                 let value = if let Some(r) = sum_real {
                     r.to_string()
                 } else if let Some(i) = sum_int {
@@ -926,14 +1477,12 @@ impl QueryExecutor {
         from: String,
         filter: Option<Condition>,
         group_by: Vec<String>,
-        order_by: Option<(String, SortOrder)>,
+        order_by: Option<(Expression, SortOrder)>,
         limit: Option<usize>,
         offset: Option<usize>,
         tx_manager: &GlobalTransactionManager,
         database_storage: &crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
-        use std::collections::HashMap;
-
         let table = db
             .get_table(&from)
             .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
@@ -954,36 +1503,6 @@ impl QueryExecutor {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        // Get rows from PagedTable
-        let paged_table = database_storage.get_paged_table(&from)
-            .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
-        let rows_vec = paged_table.get_all_rows()?;
-
-        // Filter visible rows (v2.6.0: subquery support)
-        let visible_rows: Vec<&Row> = rows_vec
-            .iter()
-            .filter(|row| {
-                if !row.is_visible_to_snapshot(&snapshot) {
-                    return false;
-                }
-                if let Some(ref f) = filter {
-                    ConditionEvaluator::evaluate_with_context(&table.columns, row, f, db, tx_manager, database_storage, &subquery_ctx).unwrap_or(false)
-                } else {
-                    true
-                }
-            })
-            .collect();
-
-        // Group rows by GROUP BY columns
-        let mut groups: HashMap<Vec<String>, Vec<&Row>> = HashMap::new();
-        for row in visible_rows {
-            let key: Vec<String> = group_by_indices
-                .iter()
-                .map(|&idx| row.values[idx].to_string())
-                .collect();
-            groups.entry(key).or_default().push(row);
-        }
-
         // Build result rows
         let mut result_rows = Vec::new();
         let mut column_names = Vec::new();
@@ -991,14 +1510,14 @@ impl QueryExecutor {
         // Determine column names
         for col in &columns {
             match col {
-                SelectColumn::Regular(name) => {
+                SelectColumn::Regular { name, alias } => {
                     // Must be in GROUP BY list
                     if !group_by.contains(name) {
                         return Err(DatabaseError::ParseError(format!(
                             "Column '{name}' must appear in GROUP BY clause or be used in an aggregate function"
                         )));
                     }
-                    column_names.push(name.clone());
+                    column_names.push(alias.as_ref().unwrap_or(name).clone());
                 }
                 SelectColumn::Aggregate(agg_func) => {
                     let (_, name) = Self::compute_aggregate(agg_func, table, &[])?;
@@ -1008,9 +1527,9 @@ impl QueryExecutor {
                     // Literals are allowed with GROUP BY (v2.6.0)
                     column_names.push("?column?".to_string());
                 }
-                SelectColumn::Case(case_expr) => {
-                    // CASE expressions are allowed in GROUP BY context (v1.10.0)
-                    column_names.push(case_expr.alias.clone().unwrap_or_else(|| "case".to_string()));
+                SelectColumn::Expression { expr, alias } => {
+                    // Evaluated per group (v2.7.0)
+                    column_names.push(FunctionExecutor::column_name(expr, alias.as_ref()));
                 }
                 SelectColumn::Subquery { .. } => {
                     return Err(DatabaseError::ParseError(
@@ -1025,31 +1544,33 @@ impl QueryExecutor {
             }
         }
 
-        // Compute result for each group
-        for (group_key, group_rows) in groups {
+        // One output row per group (v2.7.0: a group comes whole from either path below)
+        let group_row = |group_key: Vec<String>, group_rows: &[&Row]| -> Result<Vec<String>, DatabaseError> {
             let mut row_values = Vec::new();
 
             for col in &columns {
                 match col {
-                    SelectColumn::Regular(name) => {
+                    SelectColumn::Regular { name, .. } => {
                         // Get value from group key
                         let idx = group_by.iter().position(|g| g == name).unwrap();
                         row_values.push(group_key[idx].clone());
                     }
                     SelectColumn::Aggregate(agg_func) => {
-                        let (value, _) = Self::compute_aggregate(agg_func, table, &group_rows)?;
+                        let (value, _) = Self::compute_aggregate(agg_func, table, group_rows)?;
                         row_values.push(value);
                     }
                     SelectColumn::Literal(val) => {
                         // Literals are constant, same for every group (v2.6.0)
                         row_values.push(val.to_string());
                     }
-                    SelectColumn::Case(case_expr) => {
-                        // Evaluate CASE expression on first row of group (v1.10.0)
-                        // In GROUP BY context, CASE should be deterministic per group
-                        if let Some(first_row) = group_rows.first() {
-                            let case_value = Self::evaluate_case(case_expr, &table.columns, first_row)?;
-                            row_values.push(case_value.to_string());
+                    SelectColumn::Expression { expr, .. } => {
+                        // Native aggregates run over the group, other expressions
+                        // are evaluated on its first row (v2.7.0)
+                        if let Some((name, arg)) = FunctionRegistry::aggregate_call(expr) {
+                            row_values.push(Self::compute_udf_aggregate(name, arg, table, group_rows, db, tx_manager, database_storage)?);
+                        } else if let Some(first_row) = group_rows.first() {
+                            let value = FunctionExecutor::evaluate(expr, &table.columns, first_row, db, tx_manager, database_storage)?;
+                            row_values.push(value.to_string());
                         } else {
                             row_values.push("NULL".to_string());
                         }
@@ -1067,22 +1588,79 @@ impl QueryExecutor {
                 }
             }
 
-            result_rows.push(row_values);
+            Ok(row_values)
+        };
+        let matches = |row: &Row| {
+            row.is_visible_to_snapshot(&snapshot)
+                && filter.as_ref().is_none_or(|f| {
+                    ConditionEvaluator::evaluate_with_context(&table.columns, row, f, db, tx_manager, database_storage, &subquery_ctx).unwrap_or(false)
+                })
+        };
+        let group_key = |row: &Row| -> Vec<String> { group_by_indices.iter().map(|&idx| row.values[idx].to_string()).collect() };
+
+        if let Some((index_name, index)) = Planner::group_index(db, &from, &group_by) {
+            // GROUP AGGREGATE: rows come in index order, one group after another
+            let all_rows = Partitions::scan_for_index(db, database_storage, &from, filter.as_ref())?
+                .collect::<Result<Vec<Row>, DatabaseError>>()?;
+            let ordinals = index
+                .search_range(&[], std::ops::Bound::Unbounded, std::ops::Bound::Unbounded)
+                .unwrap_or_default();
+            db.activity.count_index_scan(&from, index_name, ordinals.len());
+
+            let mut current: Option<(Vec<String>, Vec<&Row>)> = None;
+            for row in ordinals.into_iter().filter_map(|row_idx| all_rows.get(row_idx)) {
+                if !matches(row) {
+                    continue;
+                }
+                let key = group_key(row);
+                match &mut current {
+                    Some((current_key, rows)) if *current_key == key => rows.push(row),
+                    _ => {
+                        if let Some((done_key, rows)) = current.replace((key, vec![row])) {
+                            result_rows.push(group_row(done_key, &rows)?);
+                        }
+                    }
+                }
+            }
+            if let Some((done_key, rows)) = current {
+                result_rows.push(group_row(done_key, &rows)?);
+            }
+        } else {
+            // HASH AGGREGATE: groups past work_mem spill to temp files
+            let mut groups = HashGroups::new(database_storage.work_mem(), database_storage.temp_files().cloned())
+                .with_memory(database_storage.query_memory());
+            for row in Partitions::scan(db, database_storage, &from, filter.as_ref())? {
+                let row = row?;
+                if matches(&row) {
+                    groups.push(group_key(&row), row)?;
+                }
+            }
+            groups.finish(&mut |key, rows| {
+                let rows: Vec<&Row> = rows.iter().collect();
+                result_rows.push(group_row(key, &rows)?);
+                Ok(())
+            })?;
         }
 
         // Apply ORDER BY if specified
-        if let Some((ref sort_column, sort_order)) = order_by {
-            let sort_col_idx = column_names
-                .iter()
-                .position(|c| c == sort_column)
-                .ok_or_else(|| DatabaseError::ColumnNotFound(sort_column.clone()))?;
+        if let Some((ref key, sort_order)) = order_by {
+            let sort_col_idx = if let Some(idx) = Self::sort_position(key, column_names.len())? {
+                idx
+            } else {
+                // An output name, or a column the select list renames
+                let sort_column = Self::sort_column(key)?;
+                column_names
+                    .iter()
+                    .position(|c| c == sort_column)
+                    .or_else(|| columns.iter().position(|col| matches!(col, SelectColumn::Regular { name, .. } if name == sort_column)))
+                    .ok_or_else(|| DatabaseError::ColumnNotFound(sort_column.to_string()))?
+            };
 
             result_rows.sort_by(|row_a, row_b| {
                 let val_a = &row_a[sort_col_idx];
                 let val_b = &row_b[sort_col_idx];
 
-                // Parse values for comparison (simplified - comparing as strings)
-                let cmp = val_a.cmp(val_b);
+                let cmp = Self::compare_rendered(val_a, val_b);
 
                 match sort_order {
                     crate::parser::SortOrder::Asc => cmp,
@@ -1123,31 +1701,51 @@ impl QueryExecutor {
         from: String,
         joins: Vec<crate::parser::JoinClause>,
         _filter: Option<Condition>,
-        _order_by: Option<(String, SortOrder)>,
+        order_by: Option<(Expression, SortOrder)>,
         limit: Option<usize>,
         offset: Option<usize>,
         tx_manager: &GlobalTransactionManager,
         database_storage: &crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
-        // Get the main table
-        let main_table = db
-            .get_table(&from)
-            .ok_or_else(|| DatabaseError::TableNotFound(from.clone()))?;
-
         let snapshot = tx_manager.get_snapshot();
 
         // v2.6.0: Multi-JOIN support - process JOINs sequentially (left-to-right)
-        let mut state = Self::init_join_state(&from, main_table, &snapshot, database_storage)?;
+        // v2.7.0: Base relation may be a view
+        let (base_rows, base_columns) = Self::load_join_relation(db, &from, &snapshot, tx_manager, database_storage)?;
+        let mut state = Self::init_join_state(&DerivedTables::relation_name(&from), base_rows, &base_columns);
 
         // Process each JOIN sequentially
         for join in &joins {
-            Self::process_single_join(db, join, &mut state, &snapshot, database_storage)?;
+            Self::process_single_join(db, join, &mut state, &snapshot, tx_manager, database_storage)?;
         }
 
         // Extract result rows from state
         let mut result_rows = state.result_rows;
         let combined_columns = state.combined_columns;
 
+        // v2.7.0: ORDER BY a joined column - a position, `table.column`, or a
+        // column name only one of the tables has
+        if let Some((key, order)) = order_by {
+            let sort_idx = if let Some(idx) = Self::sort_position(&key, combined_columns.len())? {
+                idx
+            } else {
+                let name = Self::sort_column(&key)?;
+                let suffix = format!(".{name}");
+                let mut matching = combined_columns.iter().enumerate().filter(|(_, col)| col.ends_with(&suffix));
+                match (combined_columns.iter().position(|col| col == name), matching.next(), matching.next()) {
+                    (Some(idx), _, _) | (None, Some((idx, _)), None) => idx,
+                    _ => return Err(DatabaseError::ColumnNotFound(name.to_string())),
+                }
+            };
+            result_rows.sort_by(|a, b| {
+                let cmp = Self::compare_rendered(&a[sort_idx], &b[sort_idx]);
+                match order {
+                    SortOrder::Asc => cmp,
+                    SortOrder::Desc => cmp.reverse(),
+                }
+            });
+        }
+
         // Apply DISTINCT if specified
         if distinct {
             use std::collections::HashSet;
@@ -1335,7 +1933,7 @@ impl QueryExecutor {
     }
 
     /// Helper: Execute a Statement that should be a query
-    fn execute_query_stmt(
+    pub(crate) fn execute_query_stmt(
         db: &Database,
         stmt: &crate::parser::Statement,
         tx_manager: &GlobalTransactionManager,
@@ -1345,14 +1943,11 @@ impl QueryExecutor {
             crate::parser::Statement::Select { distinct, columns, from, joins, filter, group_by, order_by, limit, offset } => {
                 Self::select(db, *distinct, columns.clone(), from.clone(), joins.clone(), filter.clone(), group_by.clone(), order_by.clone(), *limit, *offset, tx_manager, database_storage)
             }
-            crate::parser::Statement::Union { left, right, all } => {
-                Self::union(db, left, right, *all, tx_manager, database_storage)
-            }
-            crate::parser::Statement::Intersect { left, right } => {
-                Self::intersect(db, left, right, tx_manager, database_storage)
-            }
-            crate::parser::Statement::Except { left, right } => {
-                Self::except(db, left, right, tx_manager, database_storage)
+            // v2.7.0: with type checks and the trailing ORDER BY / LIMIT
+            crate::parser::Statement::Union { .. }
+            | crate::parser::Statement::Intersect { .. }
+            | crate::parser::Statement::Except { .. } => {
+                SetOperations::execute(db, stmt, tx_manager, database_storage)
             }
             _ => Err(DatabaseError::ParseError("Not a query statement".to_string())),
         }
@@ -1360,37 +1955,58 @@ impl QueryExecutor {
 
     // ===== Multi-JOIN Support Methods (v2.6.0) =====
 
-    /// Initialize join state with base table
-    fn init_join_state(
-        table_name: &str,
-        table: &Table,
+    /// Load a JOIN input as rendered rows plus column names (v2.7.0)
+    ///
+    /// Tables are read with MVCC visibility applied, views are materialized and
+    /// subqueries run.
+    fn load_join_relation(
+        db: &Database,
+        name: &str,
         snapshot: &crate::transaction::Snapshot,
+        tx_manager: &GlobalTransactionManager,
         database_storage: &crate::storage::DatabaseStorage,
-    ) -> Result<IntermediateJoinState, DatabaseError> {
-        let mut state = IntermediateJoinState::new();
+    ) -> Result<(Vec<Vec<String>>, Vec<String>), DatabaseError> {
+        if db.views.contains_key(name) {
+            return ViewResolver::materialize(db, name, tx_manager, database_storage);
+        }
+        if DerivedTables::is_derived(name) {
+            return DerivedTables::load(db, &DerivedTables::parse(name)?, None, tx_manager, database_storage);
+        }
 
-        // Load base table rows
-        let paged_table = database_storage
-            .get_paged_table(table_name)
-            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?;
-        let rows = paged_table.get_all_rows()?;
+        let table = db
+            .get_table(name)
+            .ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))?;
 
         // Convert rows to Vec<Vec<String>> and apply visibility filter
-        for row in rows {
+        let mut rows = Vec::new();
+        for row in Partitions::scan(db, database_storage, name, None)? {
+            let row = row?;
             if row.is_visible_to_snapshot(snapshot) {
-                let row_values: Vec<String> = row.values.iter().map(ToString::to_string).collect();
-                state.result_rows.push(row_values);
+                rows.push(row.values.iter().map(ToString::to_string).collect());
             }
         }
 
+        let columns = table.columns.iter().map(|col| col.name.clone()).collect();
+        Ok((rows, columns))
+    }
+
+    /// Initialize join state with base relation
+    fn init_join_state(
+        table_name: &str,
+        rows: Vec<Vec<String>>,
+        columns: &[String],
+    ) -> IntermediateJoinState {
+        let mut state = IntermediateJoinState::new();
+        state.result_rows = rows;
+
         // Build initial column_map with "table_name.column" → index
-        for (idx, col) in table.columns.iter().enumerate() {
-            let qualified_name = format!("{}.{}", table_name, col.name);
+        for (idx, col) in columns.iter().enumerate() {
+            let qualified_name = format!("{table_name}.{col}");
             state.combined_columns.push(qualified_name.clone());
             state.column_map.insert(qualified_name, idx);
         }
 
-        Ok(state)
+        state
     }
 
     /// Process a single JOIN operation on current intermediate state
@@ -1400,19 +2016,45 @@ impl QueryExecutor {
         join: &crate::parser::JoinClause,
         state: &mut IntermediateJoinState,
         snapshot: &crate::transaction::Snapshot,
+        tx_manager: &GlobalTransactionManager,
         database_storage: &crate::storage::DatabaseStorage,
     ) -> Result<(), DatabaseError> {
         use crate::parser::JoinType;
+        use std::collections::HashMap;
 
-        // 1. Load right table
-        let right_table = db
-            .get_table(&join.table)
-            .ok_or_else(|| DatabaseError::TableNotFound(join.table.clone()))?;
-
-        let right_paged_table = database_storage
-            .get_paged_table(&join.table)
-            .ok_or_else(|| DatabaseError::TableNotFound(join.table.clone()))?;
-        let right_rows = right_paged_table.get_all_rows()?;
+        // 1. Load right relation (v2.7.0: table, view or subquery, visible rows only)
+        let right_name = DerivedTables::relation_name(&join.table);
+        let lateral = if DerivedTables::is_derived(&join.table) {
+            Some(DerivedTables::parse(&join.table)?).filter(|table| table.lateral)
+        } else {
+            None
+        };
+        // v2.7.0: A LATERAL subquery runs once per left row, bound to its values
+        let (right_rows, right_columns, lateral_rows) = if let Some(ref table) = lateral {
+            if matches!(join.join_type, JoinType::Right) {
+                return Err(DatabaseError::ParseError(format!(
+                    "LATERAL subquery '{right_name}' cannot be the right side of a RIGHT JOIN"
+                )));
+            }
+            let mut runs = Vec::with_capacity(state.result_rows.len());
+            let mut columns = None;
+            for left_row in &state.result_rows {
+                let outer = (state.combined_columns.as_slice(), left_row.as_slice());
+                let (rows, run_columns) = DerivedTables::load(db, table, Some(outer), tx_manager, database_storage)?;
+                columns.get_or_insert(run_columns);
+                runs.push(rows);
+            }
+            // No left rows: the column names come from a run with NULLs
+            if columns.is_none() {
+                let nulls = vec!["NULL".to_string(); state.combined_columns.len()];
+                let outer = (state.combined_columns.as_slice(), nulls.as_slice());
+                columns = Some(DerivedTables::load(db, table, Some(outer), tx_manager, database_storage)?.1);
+            }
+            (Vec::new(), columns.unwrap_or_default(), Some(runs))
+        } else {
+            let (rows, columns) = Self::load_join_relation(db, &join.table, snapshot, tx_manager, database_storage)?;
+            (rows, columns, None)
+        };
 
         // 2. Parse ON clause column references (table.column)
         let parse_col_ref = |ref_str: &str| -> Result<(String, String), DatabaseError> {
@@ -1425,76 +2067,89 @@ impl QueryExecutor {
             Ok((parts[0].to_string(), parts[1].to_string()))
         };
 
-        let (left_table_name, left_col_name) = parse_col_ref(&join.on_left)?;
-        let (_right_table_name, right_col_name) = parse_col_ref(&join.on_right)?;
-
-        // 3. Resolve left column index from column_map (already in intermediate state)
-        let left_col_qualified = format!("{}.{}", left_table_name, left_col_name);
-        let left_idx = *state
-            .column_map
-            .get(&left_col_qualified)
-            .ok_or_else(|| DatabaseError::ColumnNotFound(left_col_qualified.clone()))?;
-
-        // 4. Resolve right column index from right table
-        let right_idx = right_table
-            .get_column_index(&right_col_name)
-            .ok_or_else(|| DatabaseError::ColumnNotFound(right_col_name.clone()))?;
+        // v2.7.0: CROSS JOIN and ON TRUE have no join keys
+        let keys = if join.on_left.is_empty() {
+            None
+        } else {
+            let (left_table_name, left_col_name) = parse_col_ref(&join.on_left)?;
+            let (_right_table_name, right_col_name) = parse_col_ref(&join.on_right)?;
+
+            // 3. Resolve left column index from column_map (already in intermediate state)
+            let left_col_qualified = format!("{left_table_name}.{left_col_name}");
+            let left_idx = *state
+                .column_map
+                .get(&left_col_qualified)
+                .ok_or_else(|| DatabaseError::ColumnNotFound(left_col_qualified.clone()))?;
+
+            // 4. Resolve right column index from right relation
+            let right_idx = right_columns
+                .iter()
+                .position(|col| *col == right_col_name)
+                .ok_or_else(|| DatabaseError::ColumnNotFound(right_col_name.clone()))?;
+            Some((left_idx, right_idx))
+        };
+        let keys_match = |left_row: &[String], right_row: &[String]| {
+            keys.is_none_or(|(left_idx, right_idx)| Self::join_keys_match(&left_row[left_idx], &right_row[right_idx]))
+        };
 
-        // 5. Perform nested loop join based on join_type
+        // 5. v2.7.0: An equi-join probes a hash table of the right rows, CROSS and
+        // LATERAL joins compare every pair (nested loop). Both emit the matches of
+        // a left row in the order of the right rows.
+        let hashed: Option<HashMap<String, Vec<usize>>> = match keys {
+            Some((_, right_idx)) if lateral_rows.is_none() => {
+                let mut buckets: HashMap<String, Vec<usize>> = HashMap::new();
+                for (idx, right_row) in right_rows.iter().enumerate() {
+                    buckets.entry(Self::join_key(&right_row[right_idx])).or_default().push(idx);
+                }
+                Some(buckets)
+            }
+            _ => None,
+        };
+        let mut right_matched = vec![false; right_rows.len()];
         let mut new_result_rows = Vec::new();
 
-        for left_row in &state.result_rows {
-            let left_join_value = &left_row[left_idx];
+        for (row_idx, left_row) in state.result_rows.iter().enumerate() {
             let mut matched = false;
+            let mut emit = |right_row: &[String]| {
+                matched = true;
+                // Combine rows: left + right
+                let mut combined_row = left_row.clone();
+                combined_row.extend(right_row.iter().cloned());
+                new_result_rows.push(combined_row);
+            };
 
-            for right_row in &right_rows {
-                if !right_row.is_visible_to_snapshot(snapshot) {
-                    continue;
+            if let (Some(buckets), Some((left_idx, _))) = (&hashed, keys) {
+                for &idx in buckets.get(&Self::join_key(&left_row[left_idx])).into_iter().flatten() {
+                    right_matched[idx] = true;
+                    emit(&right_rows[idx]);
                 }
-
-                let right_join_value = right_row.values[right_idx].to_string();
-
-                if left_join_value == &right_join_value {
-                    matched = true;
-                    // Combine rows: left + right
-                    let mut combined_row = left_row.clone();
-                    combined_row.extend(
-                        right_row.values.iter().map(ToString::to_string)
-                    );
-                    new_result_rows.push(combined_row);
+            } else {
+                let candidates = lateral_rows.as_ref().map_or(&right_rows, |runs| &runs[row_idx]);
+                for (idx, right_row) in candidates.iter().enumerate() {
+                    if keys_match(left_row, right_row) {
+                        if lateral_rows.is_none() {
+                            right_matched[idx] = true;
+                        }
+                        emit(right_row);
+                    }
                 }
             }
 
             // For LEFT JOIN, include non-matching rows with NULLs
             if !matched && matches!(join.join_type, JoinType::Left) {
                 let mut combined_row = left_row.clone();
-                combined_row.extend(vec!["NULL".to_string(); right_table.columns.len()]);
+                combined_row.extend(vec!["NULL".to_string(); right_columns.len()]);
                 new_result_rows.push(combined_row);
             }
         }
 
         // For RIGHT JOIN, include non-matching rows from right table
         if matches!(join.join_type, JoinType::Right) {
-            for right_row in &right_rows {
-                if !right_row.is_visible_to_snapshot(snapshot) {
-                    continue;
-                }
-
-                let right_join_value = right_row.values[right_idx].to_string();
-
-                // Check if this right row matched any left row
-                let matched = state.result_rows.iter().any(|left_row| {
-                    left_row[left_idx] == right_join_value
-                });
-
-                if !matched {
-                    // Add NULLs for all left columns + right row values
-                    let mut combined_row = vec!["NULL".to_string(); state.combined_columns.len()];
-                    combined_row.extend(
-                        right_row.values.iter().map(ToString::to_string)
-                    );
-                    new_result_rows.push(combined_row);
-                }
+            for (right_row, _) in right_rows.iter().zip(&right_matched).filter(|(_, matched)| !**matched) {
+                // Add NULLs for all left columns + right row values
+                let mut combined_row = vec!["NULL".to_string(); state.combined_columns.len()];
+                combined_row.extend(right_row.iter().cloned());
+                new_result_rows.push(combined_row);
             }
         }
 
@@ -1503,8 +2158,8 @@ impl QueryExecutor {
 
         // 7. Extend combined_columns with right table columns
         let start_idx = state.combined_columns.len();
-        for (offset, col) in right_table.columns.iter().enumerate() {
-            let qualified_name = format!("{}.{}", join.table, col.name);
+        for (offset, col) in right_columns.iter().enumerate() {
+            let qualified_name = format!("{right_name}.{col}");
             state.combined_columns.push(qualified_name.clone());
             state.column_map.insert(qualified_name, start_idx + offset);
         }
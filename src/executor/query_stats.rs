@@ -0,0 +1,118 @@
+/// Per-database statement statistics (v2.7.0)
+///
+/// Every executed statement is recorded under its normalized template (see
+/// `plan_cache::normalize_query`), so `SELECT * FROM t WHERE id = 1` and
+/// `... id = 2` share one entry. Statistics are kept in memory only and are
+/// exposed via the `pg_stat_statements` view.
+use super::plan_cache::normalize_query;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Maximum number of templates tracked per database
+const MAX_STATEMENTS: usize = 1000;
+
+/// Aggregated statistics for one normalized statement
+#[derive(Debug, Clone, Default)]
+pub struct StatementStats {
+    pub calls: u64,
+    /// Total execution time in milliseconds
+    pub total_time: f64,
+    pub min_time: f64,
+    pub max_time: f64,
+    /// Rows returned or affected
+    pub rows: u64,
+}
+
+impl StatementStats {
+    /// Mean execution time in milliseconds
+    #[must_use]
+    pub fn mean_time(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            #[allow(clippy::cast_precision_loss)]
+            let calls = self.calls as f64;
+            self.total_time / calls
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryStats {
+    statements: HashMap<String, StatementStats>,
+}
+
+impl QueryStats {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one execution of `query`
+    pub fn record(&mut self, query: &str, elapsed: Duration, rows: usize) {
+        let (template, _) = normalize_query(query);
+        if !self.statements.contains_key(&template) && self.statements.len() >= MAX_STATEMENTS {
+            self.evict_least_called();
+        }
+
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        let stats = self.statements.entry(template).or_default();
+        if stats.calls == 0 || millis < stats.min_time {
+            stats.min_time = millis;
+        }
+        stats.max_time = stats.max_time.max(millis);
+        stats.calls += 1;
+        stats.total_time += millis;
+        stats.rows += rows as u64;
+    }
+
+    /// Tracked templates with their statistics
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &StatementStats)> {
+        self.statements.iter()
+    }
+
+    /// Forget all statistics
+    pub fn reset(&mut self) {
+        self.statements.clear();
+    }
+
+    fn evict_least_called(&mut self) {
+        if let Some(coldest) = self
+            .statements
+            .iter()
+            .min_by_key(|(_, stats)| stats.calls)
+            .map(|(template, _)| template.clone())
+        {
+            self.statements.remove(&coldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_groups_by_template() {
+        let mut stats = QueryStats::new();
+        stats.record("SELECT * FROM t WHERE id = 1", Duration::from_millis(4), 1);
+        stats.record("SELECT * FROM t WHERE id = 2;", Duration::from_millis(2), 0);
+        stats.record("DELETE FROM t", Duration::from_millis(1), 5);
+
+        let select = stats
+            .entries()
+            .find(|(template, _)| template.starts_with("SELECT"))
+            .map(|(_, stats)| stats.clone())
+            .unwrap();
+        assert_eq!(select.calls, 2);
+        assert_eq!(select.rows, 1);
+        assert!((select.total_time - 6.0).abs() < 1e-9);
+        assert!((select.mean_time() - 3.0).abs() < 1e-9);
+        assert!((select.min_time - 2.0).abs() < 1e-9);
+        assert!((select.max_time - 4.0).abs() < 1e-9);
+        assert_eq!(stats.entries().count(), 2);
+
+        stats.reset();
+        assert_eq!(stats.entries().count(), 0);
+    }
+}
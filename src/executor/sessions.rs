@@ -0,0 +1,272 @@
+/// Connected sessions for `pg_stat_activity` (v2.7.0)
+///
+/// The server registers every wire protocol session when it has
+/// authenticated and keeps its state (current query, idle / active) up to
+/// date. The registry is shared by the server instance and all of its
+/// databases, so a query against any database sees every session.
+///
+/// v2.7.0: Sessions also report the locks they hold or wait for, for
+/// `pg_locks`: the lock a statement takes on its database (shared by queries,
+/// exclusive for everything else) and their open transaction.
+///
+/// A session can be asked to end (`terminate`, used by `DROP DATABASE ...
+/// WITH (FORCE)`); the connection waits for that next to client messages.
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// One connected session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendInfo {
+    pub pid: u32,
+    pub usename: String,
+    /// OID of the database (`pg_locks.database`)
+    pub datid: u32,
+    pub datname: String,
+    pub application_name: String,
+    pub client_addr: Option<String>,
+    pub backend_start: DateTime<Utc>,
+    /// `active`, `idle` or `idle in transaction`
+    pub state: String,
+    /// Current query, or the last one while idle
+    pub query: String,
+    pub query_start: Option<DateTime<Utc>>,
+    /// Open transaction (`backend_xid`)
+    pub xid: Option<u64>,
+    /// Lock on the database of the running statement
+    pub database_lock: Option<DatabaseLock>,
+}
+
+/// Lock a statement holds or waits for on its database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseLock {
+    /// Queries share the database; anything else locks it exclusively
+    pub exclusive: bool,
+    pub granted: bool,
+    /// When the session started waiting for it
+    pub wait_start: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+struct Backends {
+    next_pid: u32,
+    sessions: BTreeMap<u32, BackendInfo>,
+    /// Wakes the connection of a session that is asked to end
+    terminators: BTreeMap<u32, Arc<Notify>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionRegistry {
+    backends: Arc<Mutex<Backends>>,
+}
+
+impl SessionRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a session; it is removed again when the returned handle is dropped
+    #[must_use]
+    pub fn register(
+        &self,
+        usename: &str,
+        datid: u32,
+        datname: &str,
+        application_name: &str,
+        client_addr: Option<String>,
+    ) -> Backend {
+        let mut backends = self.backends.lock().expect("session registry poisoned");
+        backends.next_pid += 1;
+        let pid = backends.next_pid;
+        backends.sessions.insert(
+            pid,
+            BackendInfo {
+                pid,
+                usename: usename.to_string(),
+                datid,
+                datname: datname.to_string(),
+                application_name: application_name.to_string(),
+                client_addr,
+                backend_start: Utc::now(),
+                state: "idle".to_string(),
+                query: String::new(),
+                query_start: None,
+                xid: None,
+                database_lock: None,
+            },
+        );
+        let terminated = Arc::new(Notify::new());
+        backends.terminators.insert(pid, Arc::clone(&terminated));
+        Backend { pid, registry: self.clone(), terminated }
+    }
+
+    /// Every connected session, ordered by pid
+    #[must_use]
+    pub fn list(&self) -> Vec<BackendInfo> {
+        let backends = self.backends.lock().expect("session registry poisoned");
+        backends.sessions.values().cloned().collect()
+    }
+
+    /// Sessions connected to `datname`
+    #[must_use]
+    pub fn connected_to(&self, datname: &str) -> Vec<BackendInfo> {
+        self.list().into_iter().filter(|info| info.datname == datname).collect()
+    }
+
+    /// Ask a session to end (nothing happens if it has already ended)
+    pub fn terminate(&self, pid: u32) {
+        let backends = self.backends.lock().expect("session registry poisoned");
+        if let Some(terminated) = backends.terminators.get(&pid) {
+            terminated.notify_one();
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.backends.lock().expect("session registry poisoned").sessions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn update(&self, pid: u32, update: impl FnOnce(&mut BackendInfo)) {
+        let mut backends = self.backends.lock().expect("session registry poisoned");
+        if let Some(info) = backends.sessions.get_mut(&pid) {
+            update(info);
+        }
+    }
+}
+
+/// Handle of a registered session
+#[derive(Debug)]
+pub struct Backend {
+    pid: u32,
+    registry: SessionRegistry,
+    terminated: Arc<Notify>,
+}
+
+impl Backend {
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// The session started running `query`
+    pub fn set_active(&self, query: &str) {
+        self.registry.update(self.pid, |info| {
+            info.state = "active".to_string();
+            info.query = query.to_string();
+            info.query_start = Some(Utc::now());
+        });
+    }
+
+    /// The session waits for the client (v2.7.0: the statement released its
+    /// database lock)
+    pub fn set_idle(&self, in_transaction: bool) {
+        self.registry.update(self.pid, |info| {
+            info.state = if in_transaction { "idle in transaction" } else { "idle" }.to_string();
+            info.database_lock = None;
+        });
+    }
+
+    /// v2.7.0: The session's open transaction (`None` once it ended)
+    pub fn set_xid(&self, xid: Option<u64>) {
+        self.registry.update(self.pid, |info| info.xid = xid);
+    }
+
+    /// v2.7.0: The running statement waits for the lock on its database
+    pub fn wait_for_database(&self, exclusive: bool) {
+        self.registry.update(self.pid, |info| {
+            info.database_lock = Some(DatabaseLock { exclusive, granted: false, wait_start: Utc::now() });
+        });
+    }
+
+    /// v2.7.0: The running statement got the lock it waited for
+    pub fn database_locked(&self) {
+        self.registry.update(self.pid, |info| {
+            if let Some(lock) = &mut info.database_lock {
+                lock.granted = true;
+            }
+        });
+    }
+
+    pub fn set_database(&self, datid: u32, datname: &str) {
+        self.registry.update(self.pid, |info| {
+            info.datid = datid;
+            info.datname = datname.to_string();
+        });
+    }
+
+    pub fn set_application_name(&self, application_name: &str) {
+        self.registry.update(self.pid, |info| info.application_name = application_name.to_string());
+    }
+
+    /// Completes once the session has been asked to end (also when that
+    /// happened before this was awaited)
+    pub async fn terminated(&self) {
+        self.terminated.notified().await;
+    }
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        let mut backends = self.registry.backends.lock().expect("session registry poisoned");
+        backends.sessions.remove(&self.pid);
+        backends.terminators.remove(&self.pid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_drop() {
+        let registry = SessionRegistry::new();
+        let first = registry.register("alice", 16384, "shop", "psql", Some("127.0.0.1".to_string()));
+        let second = registry.register("bob", 16385, "postgres", "", None);
+        assert_ne!(first.pid(), second.pid());
+
+        first.set_active("SELECT 1");
+        second.set_idle(true);
+        let sessions = registry.list();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!((sessions[0].state.as_str(), sessions[0].query.as_str()), ("active", "SELECT 1"));
+        assert_eq!(sessions[1].state, "idle in transaction");
+
+        // v2.7.0: Locks until the statement is done
+        first.set_xid(Some(7));
+        first.wait_for_database(true);
+        assert_eq!(registry.list()[0].database_lock.map(|lock| (lock.exclusive, lock.granted)), Some((true, false)));
+        first.database_locked();
+        assert!(registry.list()[0].database_lock.unwrap().granted);
+        first.set_idle(true);
+        assert_eq!((registry.list()[0].xid, registry.list()[0].database_lock), (Some(7), None));
+
+        drop(first);
+        assert_eq!(registry.list().iter().map(|info| info.usename.as_str()).collect::<Vec<_>>(), ["bob"]);
+        drop(second);
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_terminate() {
+        let registry = SessionRegistry::new();
+        let shop = registry.register("alice", 16384, "shop", "", None);
+        let other = registry.register("bob", 16385, "postgres", "", None);
+        assert_eq!(registry.connected_to("shop").iter().map(|info| info.pid).collect::<Vec<_>>(), [shop.pid()]);
+
+        registry.terminate(shop.pid());
+        tokio::time::timeout(std::time::Duration::from_secs(1), shop.terminated()).await.unwrap();
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(20), other.terminated()).await.is_err());
+
+        let pid = shop.pid();
+        drop(shop);
+        registry.terminate(pid);
+        assert_eq!(registry.len(), 1);
+    }
+}
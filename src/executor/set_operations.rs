@@ -0,0 +1,256 @@
+/// UNION / INTERSECT / EXCEPT as whole queries (v2.7.0)
+///
+/// `QueriesExecutor::{union, intersect, except}` combine the rows of two arms;
+/// this module checks that the arms' columns have compatible types before
+/// running them and applies the ORDER BY / LIMIT / OFFSET written after the
+/// last arm to the combined rows.
+///
+/// Column types are only known for plain table columns, typed literals and
+/// aggregates; anything else (expressions, views, joins) is not checked, the
+/// same way an untyped string literal fits any column in `PostgreSQL`.
+use crate::parser::{AggregateFunction, CountTarget, SelectColumn, SortOrder, Statement};
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{DataType, Database, DatabaseError, Value};
+use super::dispatcher_executor::QueryResult;
+use super::queries::QueryExecutor as QueriesExecutor;
+
+pub struct SetOperations;
+
+impl SetOperations {
+    /// Run a set operation statement
+    pub fn execute(
+        db: &Database,
+        stmt: &Statement,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let (result, order_by, limit, offset) = match stmt {
+            Statement::Union { left, right, all, order_by, limit, offset } => {
+                Self::check_arms(db, "UNION", left, right)?;
+                (QueriesExecutor::union(db, left, right, *all, tx_manager, database_storage)?, order_by, limit, offset)
+            }
+            Statement::Intersect { left, right, order_by, limit, offset } => {
+                Self::check_arms(db, "INTERSECT", left, right)?;
+                (QueriesExecutor::intersect(db, left, right, tx_manager, database_storage)?, order_by, limit, offset)
+            }
+            Statement::Except { left, right, order_by, limit, offset } => {
+                Self::check_arms(db, "EXCEPT", left, right)?;
+                (QueriesExecutor::except(db, left, right, tx_manager, database_storage)?, order_by, limit, offset)
+            }
+            _ => return Err(DatabaseError::ParseError("Not a set operation".to_string())),
+        };
+
+        let QueryResult::Rows(mut rows, columns) = result else {
+            return Ok(result);
+        };
+
+        if let Some((key, order)) = order_by {
+            // A number is a 1-based position in the output columns
+            let idx = if let Some(idx) = QueriesExecutor::sort_position(key, columns.len())? {
+                idx
+            } else {
+                let column = QueriesExecutor::sort_column(key)?;
+                columns
+                    .iter()
+                    .position(|name| name == column)
+                    .ok_or_else(|| DatabaseError::ColumnNotFound(column.to_string()))?
+            };
+            rows.sort_by(|a, b| {
+                let cmp = QueriesExecutor::compare_rendered(&a[idx], &b[idx]);
+                match order {
+                    SortOrder::Asc => cmp,
+                    SortOrder::Desc => cmp.reverse(),
+                }
+            });
+        }
+
+        let rows = rows
+            .into_iter()
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// Both arms must have matching column types where they are known
+    fn check_arms(db: &Database, operation: &str, left: &Statement, right: &Statement) -> Result<(), DatabaseError> {
+        let (left_types, right_types) = (Self::column_types(db, left), Self::column_types(db, right));
+        if left_types.len() != right_types.len() {
+            // Column counts are checked once the arms have run
+            return Ok(());
+        }
+
+        for (left_type, right_type) in left_types.iter().zip(&right_types) {
+            if let (Some(left_type), Some(right_type)) = (left_type, right_type)
+                && !Self::compatible(left_type, right_type)
+            {
+                return Err(DatabaseError::ParseError(format!(
+                    "{operation} types {} and {} cannot be matched",
+                    Self::type_name(left_type),
+                    Self::type_name(right_type)
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Result column types of a query, `None` where they are not known
+    fn column_types(db: &Database, stmt: &Statement) -> Vec<Option<DataType>> {
+        match stmt {
+            Statement::Select { columns, from, joins, .. } => {
+                let table = if joins.is_empty() { db.get_table(from) } else { None };
+                let column_type = |name: &str| {
+                    let name = name.rsplit('.').next().unwrap_or(name);
+                    table?.columns.iter().find(|col| col.name == name).map(|col| col.data_type.clone())
+                };
+
+                let mut types = Vec::new();
+                for column in columns {
+                    match column {
+                        SelectColumn::Regular { name, .. } if name == "*" => match table {
+                            Some(table) => types.extend(table.columns.iter().map(|col| Some(col.data_type.clone()))),
+                            // Unknown width: nothing can be lined up
+                            None => return Vec::new(),
+                        },
+                        SelectColumn::Regular { name, .. } => types.push(column_type(name)),
+                        SelectColumn::Literal(value) => types.push(Self::literal_type(value)),
+                        SelectColumn::Aggregate(AggregateFunction::Count(CountTarget::All | CountTarget::Column(_))) => {
+                            types.push(Some(DataType::Integer));
+                        }
+                        SelectColumn::Aggregate(
+                            AggregateFunction::Sum(name)
+                            | AggregateFunction::Avg(name)
+                            | AggregateFunction::Min(name)
+                            | AggregateFunction::Max(name),
+                        ) => types.push(column_type(name)),
+                        _ => types.push(None),
+                    }
+                }
+                types
+            }
+            // A nested set operation has the columns of its arms
+            Statement::Union { left, right, .. }
+            | Statement::Intersect { left, right, .. }
+            | Statement::Except { left, right, .. } => {
+                let right_types = Self::column_types(db, right);
+                let mut types = Self::column_types(db, left);
+                if types.len() == right_types.len() {
+                    for (left_type, right_type) in types.iter_mut().zip(right_types) {
+                        if left_type.is_none() {
+                            *left_type = right_type;
+                        }
+                    }
+                }
+                types
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Type of a literal in a SELECT list; NULL and string literals fit any column
+    const fn literal_type(value: &Value) -> Option<DataType> {
+        match value {
+            Value::SmallInt(_) => Some(DataType::SmallInt),
+            Value::Integer(_) => Some(DataType::Integer),
+            Value::Real(_) => Some(DataType::Real),
+            Value::Numeric(_) => Some(DataType::Numeric { precision: 0, scale: 0 }),
+            Value::Boolean(_) => Some(DataType::Boolean),
+            Value::Date(_) => Some(DataType::Date),
+            Value::Timestamp(_) => Some(DataType::Timestamp),
+            Value::TimestampTz(_) => Some(DataType::TimestampTz),
+            Value::Uuid(_) => Some(DataType::Uuid),
+            Value::Json(_) => Some(DataType::Json),
+            Value::Bytea(_) => Some(DataType::Bytea),
+            Value::Null | Value::Text(_) | Value::Char(_) | Value::Enum(..) => None,
+        }
+    }
+
+    /// Types that can share a result column: numbers, strings, date/times, JSON
+    /// and identical types otherwise
+    fn compatible(left: &DataType, right: &DataType) -> bool {
+        let group = |data_type: &DataType| match data_type {
+            DataType::SmallInt
+            | DataType::Integer
+            | DataType::BigInt
+            | DataType::Real
+            | DataType::Numeric { .. }
+            | DataType::Serial
+            | DataType::BigSerial => Some(0),
+            DataType::Text | DataType::Varchar { .. } | DataType::Char { .. } => Some(1),
+            DataType::Date | DataType::Timestamp | DataType::TimestampTz => Some(2),
+            DataType::Json | DataType::Jsonb => Some(3),
+            DataType::Boolean | DataType::Uuid | DataType::Bytea | DataType::Enum { .. } => None,
+        };
+
+        match (group(left), group(right)) {
+            (Some(left_group), Some(right_group)) => left_group == right_group,
+            _ => left == right,
+        }
+    }
+
+    /// Type name without modifiers, as `PostgreSQL` prints it in this error
+    fn type_name(data_type: &DataType) -> String {
+        let name = data_type.sql_name();
+        name.split('(').next().unwrap_or(&name).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::run_sql;
+    use crate::storage::DatabaseStorage;
+
+    #[test]
+    fn test_compatible_types() {
+        assert!(SetOperations::compatible(&DataType::Integer, &DataType::Real));
+        assert!(SetOperations::compatible(&DataType::Text, &DataType::Varchar { max_length: 10 }));
+        assert!(SetOperations::compatible(&DataType::Date, &DataType::TimestampTz));
+        assert!(!SetOperations::compatible(&DataType::Integer, &DataType::Text));
+        assert!(!SetOperations::compatible(&DataType::Boolean, &DataType::Integer));
+        assert_eq!(SetOperations::type_name(&DataType::Numeric { precision: 10, scale: 2 }), "numeric");
+    }
+
+    #[test]
+    fn test_set_operations_with_order_by_and_type_checks() {
+        // v2.7.0: ORDER BY / LIMIT apply to the combined rows, arms must have matching types
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        let rows = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows.into_iter().map(|row| row.join(",")).collect::<Vec<_>>(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE a (id INTEGER, name TEXT)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE b (id INTEGER, name TEXT)").unwrap();
+        for sql in [
+            "INSERT INTO a VALUES (3, 'c')",
+            "INSERT INTO a VALUES (10, 'j')",
+            "INSERT INTO b VALUES (1, 'a')",
+            "INSERT INTO b VALUES (3, 'c')",
+        ] {
+            run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        }
+
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, name FROM a UNION SELECT id, name FROM b ORDER BY id").unwrap();
+        assert_eq!(rows(result), ["1,a", "3,c", "10,j"]);
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a UNION ALL SELECT id FROM b ORDER BY id DESC LIMIT 2 OFFSET 1").unwrap();
+        assert_eq!(rows(result), ["3", "3"]);
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a EXCEPT SELECT id FROM b UNION SELECT 7 ORDER BY id").unwrap();
+        assert_eq!(rows(result), ["7", "10"]);
+
+        // ORDER BY a position in the output columns
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id, name FROM a UNION SELECT id, name FROM b ORDER BY 2 DESC").unwrap();
+        assert_eq!(rows(result), ["10,j", "3,c", "1,a"]);
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a UNION SELECT id FROM b ORDER BY 1").unwrap();
+        assert_eq!(rows(result), ["1", "3", "10"]);
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a UNION SELECT id FROM b ORDER BY 2").unwrap_err();
+        assert_eq!(err.to_string(), "Parse error: ORDER BY position 2 is not in select list");
+
+        let err = run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a UNION SELECT name FROM b").unwrap_err();
+        assert_eq!(err.to_string(), "Parse error: UNION types integer and text cannot be matched");
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a INTERSECT SELECT id, name FROM b").is_err());
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM a UNION SELECT id FROM b ORDER BY missing").is_err());
+    }
+}
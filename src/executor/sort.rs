@@ -0,0 +1,262 @@
+/// ORDER BY that spills to disk: external merge sort (v2.7.0)
+///
+/// Rows are collected in memory until their size reaches `work_mem`. That
+/// batch is then sorted and written to a temp file (see `storage::tempfile`)
+/// as a sorted run, and collecting starts over. At the end the runs are merged:
+/// each is read back one row at a time and the smallest head goes out next.
+/// Rows that compare equal keep their input order.
+///
+/// Without a data directory (an in-memory server) nothing is written to disk
+/// and the sort stays in memory, up to the query's `max_query_memory`.
+use std::cmp::Ordering;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::storage::{TempFile, TempFiles};
+use crate::types::DatabaseError;
+use super::memory::QueryMemory;
+
+type Compare<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+
+pub struct ExternalSort<T> {
+    compare: Compare<T>,
+    work_mem: usize,
+    temp_files: Option<TempFiles>,
+    /// Rows of the run being collected and their serialized size
+    buffer: Vec<T>,
+    buffered_bytes: usize,
+    /// Sorted runs written so far
+    runs: Vec<TempFile>,
+    /// Memory of the query the rows in `buffer` count against
+    memory: QueryMemory,
+}
+
+impl<T: Serialize + DeserializeOwned + 'static> ExternalSort<T> {
+    #[must_use]
+    pub fn new(work_mem: usize, temp_files: Option<TempFiles>, compare: impl Fn(&T, &T) -> Ordering + 'static) -> Self {
+        Self {
+            compare: Box::new(compare),
+            work_mem,
+            temp_files,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+            memory: QueryMemory::unlimited(),
+        }
+    }
+
+    /// Count the rows held in memory against the query's `memory` (v2.7.0)
+    #[must_use]
+    pub fn with_memory(mut self, memory: QueryMemory) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), DatabaseError> {
+        let size = bincode::serialized_size(&item).map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+        let size = usize::try_from(size).unwrap_or(usize::MAX);
+        self.buffered_bytes += size;
+        self.buffer.push(item);
+        if self.buffered_bytes > self.work_mem
+            && let Some(temp_files) = &self.temp_files
+        {
+            // The run takes the row just pushed along, the rows held before are given back
+            self.memory.release(self.buffered_bytes - size);
+            let mut run = temp_files.create()?;
+            for row in self.sorted_buffer() {
+                run.append(&row)?;
+            }
+            self.runs.push(run);
+            return Ok(());
+        }
+        self.memory.reserve(size)
+    }
+
+    /// Number of runs spilled to disk so far
+    #[must_use]
+    pub const fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Sorted rows: straight from memory when nothing was spilled, otherwise
+    /// merged from the runs and the rows still in memory
+    pub fn finish(mut self) -> Result<Box<dyn Iterator<Item = Result<T, DatabaseError>>>, DatabaseError> {
+        // The rows are handed on, whoever keeps them counts them
+        self.memory.release(self.buffered_bytes);
+        let last = self.sorted_buffer();
+        if self.runs.is_empty() {
+            return Ok(Box::new(last.into_iter().map(Ok)));
+        }
+
+        // Earlier runs hold earlier rows, so ties go to the first source
+        let mut sources: Vec<Box<dyn Iterator<Item = Result<T, DatabaseError>>>> = Vec::new();
+        for run in self.runs {
+            sources.push(Box::new(run.read()?));
+        }
+        sources.push(Box::new(last.into_iter().map(Ok)));
+        Ok(Box::new(Merge::new(sources, self.compare)?))
+    }
+
+    fn sorted_buffer(&mut self) -> Vec<T> {
+        let mut rows = std::mem::take(&mut self.buffer);
+        self.buffered_bytes = 0;
+        rows.sort_by(|a, b| (self.compare)(a, b));
+        rows
+    }
+}
+
+/// K-way merge of sorted sources
+struct Merge<T> {
+    sources: Vec<Box<dyn Iterator<Item = Result<T, DatabaseError>>>>,
+    /// Next row of each source, `None` once it is exhausted
+    heads: Vec<Option<T>>,
+    compare: Compare<T>,
+}
+
+impl<T> Merge<T> {
+    fn new(
+        mut sources: Vec<Box<dyn Iterator<Item = Result<T, DatabaseError>>>>,
+        compare: Compare<T>,
+    ) -> Result<Self, DatabaseError> {
+        let heads = sources.iter_mut().map(|source| source.next().transpose()).collect::<Result<_, _>>()?;
+        Ok(Self { sources, heads, compare })
+    }
+}
+
+impl<T> Iterator for Merge<T> {
+    type Item = Result<T, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut smallest: Option<usize> = None;
+        for (idx, head) in self.heads.iter().enumerate() {
+            let Some(row) = head else { continue };
+            let smaller = smallest
+                .and_then(|best| self.heads[best].as_ref())
+                .is_none_or(|best| (self.compare)(row, best) == Ordering::Less);
+            if smaller {
+                smallest = Some(idx);
+            }
+        }
+
+        let idx = smallest?;
+        let next = match self.sources[idx].next().transpose() {
+            Ok(next) => next,
+            Err(e) => return Some(Err(e)),
+        };
+        std::mem::replace(&mut self.heads[idx], next).map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Value;
+    use crate::types::value::compare_for_sort;
+    use rust_decimal::Decimal;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn collect<T: Serialize + DeserializeOwned + 'static>(sort: ExternalSort<T>) -> Vec<T> {
+        sort.finish().unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn test_sort_in_memory() {
+        let mut sort = ExternalSort::new(1024, None, |a: &i64, b: &i64| a.cmp(b));
+        for n in [5, -1, 3, 3, 0] {
+            sort.push(n).unwrap();
+        }
+        assert_eq!(sort.spilled_runs(), 0);
+        assert_eq!(collect(sort), [-1, 0, 3, 3, 5]);
+    }
+
+    #[test]
+    fn test_sort_spills_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_files = TempFiles::open(temp_dir.path());
+        let compare = |a: &(Value, usize), b: &(Value, usize)| compare_for_sort(&a.0, &b.0);
+        let mut sort = ExternalSort::new(256, Some(temp_files.clone()), compare);
+
+        let keys: Vec<i64> = (0..500).map(|n| (n * 7919) % 101).collect();
+        for (position, key) in keys.iter().enumerate() {
+            let value = if key % 10 == 0 { Value::Numeric(Decimal::from(*key)) } else { Value::Integer(*key) };
+            sort.push((value, position)).unwrap();
+        }
+        sort.push((Value::Null, 500)).unwrap();
+        assert!(sort.spilled_runs() > 1);
+        assert!(fs::read_dir(temp_files.dir()).unwrap().count() > 1);
+
+        let sorted = collect(sort);
+        assert_eq!(sorted.len(), 501);
+        assert_eq!(sorted[0], (Value::Null, 500));
+        assert!(sorted.windows(2).all(|pair| compare(&pair[0], &pair[1]) != Ordering::Greater));
+        // Equal keys keep their input order across runs
+        assert!(sorted.windows(2).all(|pair| compare(&pair[0], &pair[1]) != Ordering::Equal || pair[0].1 < pair[1].1));
+        // Runs are deleted once merged
+        assert_eq!(fs::read_dir(temp_files.dir()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_order_by_spills() {
+        use crate::executor::{run_sql, QueryResult};
+        use crate::storage::DatabaseStorage;
+        use crate::transaction::GlobalTransactionManager;
+        use crate::types::Database;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        storage.set_work_mem(512);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, sql: &str| {
+            run_sql(db, &mut storage, &tx_manager, sql).unwrap()
+        };
+
+        exec(&mut db, "CREATE TABLE t (id INTEGER, name TEXT)");
+        for id in 0..300 {
+            exec(&mut db, &format!("INSERT INTO t VALUES ({}, 'row{id}')", (id * 37) % 300));
+        }
+
+        let QueryResult::Rows(rows, _) = exec(&mut db, "SELECT id FROM t ORDER BY id DESC") else {
+            panic!("Expected Rows result");
+        };
+        let ids: Vec<i64> = rows.iter().map(|row| row[0].parse().unwrap()).collect();
+        assert_eq!(ids, (0..300).rev().collect::<Vec<_>>());
+        // Every run is gone once the query is done
+        assert_eq!(fs::read_dir(temp_dir.path().join("pgsql_tmp")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_sort_past_max_query_memory() {
+        use crate::executor::{run_sql, QueryResult};
+        use crate::storage::DatabaseStorage;
+        use crate::transaction::GlobalTransactionManager;
+        use crate::types::{Database, DatabaseError};
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut on_disk = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let mut in_memory = DatabaseStorage::in_memory(100);
+        for storage in [&mut on_disk, &mut in_memory] {
+            storage.set_work_mem(512);
+            storage.set_max_query_memory(8 * 1024);
+        }
+        let tx_manager = GlobalTransactionManager::new();
+        let run = |storage: &mut DatabaseStorage| {
+            let mut db = Database::new("test".to_string());
+            let mut exec = |sql: &str| run_sql(&mut db, storage, &tx_manager, sql);
+            exec("CREATE TABLE t (id INTEGER, name TEXT)").unwrap();
+            for id in 0..300 {
+                exec(&format!("INSERT INTO t VALUES ({}, 'row{id}')", (id * 37) % 300)).unwrap();
+            }
+            (exec("SELECT id FROM t ORDER BY id"), exec("SELECT id, name FROM t"))
+        };
+
+        // Spilled runs do not count, the result itself does
+        let (sorted, all) = run(&mut on_disk);
+        assert!(matches!(sorted, Ok(QueryResult::Rows(rows, _)) if rows.len() == 300));
+        assert!(matches!(all, Err(DatabaseError::OutOfMemory(8))), "{all:?}");
+
+        // Without a data directory the sort has to stay in memory
+        let (sorted, _) = run(&mut in_memory);
+        assert!(matches!(sorted, Err(DatabaseError::OutOfMemory(8))), "{sorted:?}");
+    }
+}
@@ -2,7 +2,11 @@
 ///
 /// v2.0.0: Legacy Vec<Row> storage has been removed.
 /// This module provides a unified interface for page-based row storage.
-use crate::types::{Row, DatabaseError};
+use crate::types::{Column, Row, DatabaseError};
+use crate::parser::{Condition, Expression, WhenCondition};
+use super::conditions::ConditionEvaluator;
+use super::views::ViewResolver;
+use super::udf::FunctionRegistry;
 
 /// Trait for row storage operations
 ///
@@ -83,4 +87,206 @@ impl RowStorage for PagedStorage<'_> {
     }
 }
 
-// v2.0.0: LegacyStorage tests removed
+/// Scan-level predicate pushdown (v2.7.0)
+///
+/// Splits a WHERE clause into AND-conjuncts that can be checked against a raw
+/// tuple while its page is being read, and a residual part (subqueries) that
+/// needs the full executor context.
+#[derive(Debug, Default)]
+pub struct ScanPushdown {
+    pushed: Vec<Condition>,
+    residual: Option<Condition>,
+}
+
+impl ScanPushdown {
+    /// Split `filter` into pushable conjuncts and a residual condition
+    #[must_use]
+    pub fn split(filter: Option<&Condition>) -> Self {
+        let mut pushdown = Self::default();
+        let mut residual = Vec::new();
+
+        if let Some(cond) = filter {
+            let mut conjuncts = Vec::new();
+            Self::collect_conjuncts(cond, &mut conjuncts);
+
+            for conjunct in conjuncts {
+                if Self::is_pushable(conjunct) {
+                    pushdown.pushed.push(conjunct.clone());
+                } else {
+                    residual.push(conjunct.clone());
+                }
+            }
+        }
+
+        pushdown.residual = residual
+            .into_iter()
+            .reduce(|left, right| Condition::And(Box::new(left), Box::new(right)));
+        pushdown
+    }
+
+    fn collect_conjuncts<'a>(cond: &'a Condition, out: &mut Vec<&'a Condition>) {
+        if let Condition::And(left, right) = cond {
+            Self::collect_conjuncts(left, out);
+            Self::collect_conjuncts(right, out);
+        } else {
+            out.push(cond);
+        }
+    }
+
+    /// A condition is pushable if it only compares columns of the scanned row with literals
+    #[must_use]
+    pub fn is_pushable(cond: &Condition) -> bool {
+        match cond {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                Self::is_pushable(left) && Self::is_pushable(right)
+            }
+            Condition::Not(inner) => Self::is_pushable(inner),
+            Condition::InSubquery(..)
+            | Condition::NotInSubquery(..)
+            | Condition::Exists(_)
+            | Condition::NotExists(_)
+            | Condition::EqualsSubquery(..)
+            | Condition::GreaterThanSubquery(..)
+            | Condition::LessThanSubquery(..) => false,
+            // v2.7.0: stored functions and subqueries in a CASE need the database
+            Condition::Compare(left, _, right) => {
+                Self::is_pushable_expression(left) && Self::is_pushable_expression(right)
+            }
+            _ => true,
+        }
+    }
+
+    fn is_pushable_expression(expr: &Expression) -> bool {
+        match expr {
+            Expression::Literal(_) | Expression::Column(_) => true,
+            Expression::Binary { left, right, .. } => {
+                Self::is_pushable_expression(left) && Self::is_pushable_expression(right)
+            }
+            Expression::Function { name, args } => {
+                FunctionRegistry::contains(name)
+                    && !FunctionRegistry::is_aggregate(name)
+                    && args.iter().all(Self::is_pushable_expression)
+            }
+            Expression::Case(case) => {
+                case.when_clauses.iter().all(|when| {
+                    let condition = match &when.condition {
+                        WhenCondition::Condition(cond) => Self::is_pushable(cond),
+                        WhenCondition::Value(value) => Self::is_pushable_expression(value),
+                    };
+                    condition && Self::is_pushable_expression(&when.result)
+                }) && case.operand.iter().chain(&case.else_value).all(Self::is_pushable_expression)
+            }
+        }
+    }
+
+    /// Conjuncts evaluated inside the scan
+    #[must_use]
+    pub fn pushed(&self) -> &[Condition] {
+        &self.pushed
+    }
+
+    /// Positions of the table columns the pushed conjuncts look at (v2.7.0)
+    #[must_use]
+    pub fn columns(&self, table_columns: &[Column]) -> Vec<usize> {
+        let mut names = Vec::new();
+        for cond in &self.pushed {
+            ViewResolver::collect_condition_columns(cond, &mut names);
+        }
+        names
+            .iter()
+            .filter_map(|name| table_columns.iter().position(|c| &c.name == name))
+            .collect()
+    }
+
+    /// Part of the filter the executor still has to evaluate
+    #[must_use]
+    pub const fn residual(&self) -> Option<&Condition> {
+        self.residual.as_ref()
+    }
+
+    /// Evaluate the pushed conjuncts against a full tuple
+    pub fn matches(&self, columns: &[Column], row: &Row) -> Result<bool, DatabaseError> {
+        for cond in &self.pushed {
+            if !ConditionEvaluator::evaluate_with_columns(columns, row, cond)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Scan-level projection (v2.7.0)
+///
+/// Maps table column positions to positions inside a projected tuple
+/// produced by `PagedTableScan::with_projection()`.
+#[derive(Debug, Clone)]
+pub struct ScanProjection {
+    columns: Vec<usize>,
+}
+
+impl ScanProjection {
+    /// Build a projection from the table columns a query needs
+    pub fn new(needed: impl IntoIterator<Item = usize>) -> Self {
+        let mut columns: Vec<usize> = needed.into_iter().collect();
+        columns.sort_unstable();
+        columns.dedup();
+        Self { columns }
+    }
+
+    /// Table column positions kept by the scan, ascending
+    #[must_use]
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Position of table column `table_idx` inside a projected tuple
+    #[must_use]
+    pub fn position(&self, table_idx: usize) -> Option<usize> {
+        self.columns.binary_search(&table_idx).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Collation, DataType, Value};
+
+    fn columns() -> Vec<Column> {
+        ["id", "name"]
+            .iter()
+            .map(|name| Column {
+                name: (*name).to_string(),
+                data_type: DataType::Integer,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                foreign_key: None,
+                collation: Collation::C,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_keeps_subqueries_residual() {
+        let subquery = Condition::Exists(Box::new(crate::parser::Statement::ShowTables));
+        let filter = Condition::And(
+            Box::new(Condition::GreaterThan("id".to_string(), Value::Integer(1))),
+            Box::new(subquery.clone()),
+        );
+
+        let pushdown = ScanPushdown::split(Some(&filter));
+        assert_eq!(pushdown.pushed().len(), 1);
+        assert_eq!(pushdown.residual(), Some(&subquery));
+
+        let row = Row::new(vec![Value::Integer(2), Value::Integer(0)]);
+        assert!(pushdown.matches(&columns(), &row).unwrap());
+    }
+
+    #[test]
+    fn test_projection_positions() {
+        let projection = ScanProjection::new([4, 1, 4, 2]);
+        assert_eq!(projection.columns(), &[1, 2, 4]);
+        assert_eq!(projection.position(4), Some(2));
+        assert_eq!(projection.position(3), None);
+    }
+}
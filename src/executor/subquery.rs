@@ -123,8 +123,7 @@ impl SubqueryExecutor {
 
             // Parse the string value back to Value
             // TODO: This is a hack - we should preserve types better
-            let value_str = &rows[0][0];
-            Ok(crate::types::Value::Text(value_str.clone()))
+            Ok(Self::rendered_value(&rows[0][0]))
         } else {
             Err(DatabaseError::ParseError(
                 "Scalar subquery did not return rows".to_string(),
@@ -232,10 +231,7 @@ impl SubqueryExecutor {
             // Take first column from each row
             let values: Vec<Value> = rows
                 .into_iter()
-                .map(|row| {
-                    // TODO: Parse string back to proper Value type
-                    crate::types::Value::Text(row[0].clone())
-                })
+                .map(|row| Self::rendered_value(&row[0]))
                 .collect();
 
             Ok(values)
@@ -243,4 +239,15 @@ impl SubqueryExecutor {
             Ok(Vec::new())
         }
     }
+
+    /// A rendered result value; NULL stays NULL so comparisons with it are UNKNOWN (v2.7.0)
+    ///
+    /// Other values come back as text and are coerced by the comparison.
+    pub(crate) fn rendered_value(text: &str) -> Value {
+        if text == "NULL" {
+            Value::Null
+        } else {
+            Value::Text(text.to_string())
+        }
+    }
 }
@@ -4,14 +4,24 @@
 /// - `pg_catalog.pg_class` (tables, indexes, views)
 /// - `pg_catalog.pg_attribute` (columns)
 /// - `pg_catalog.pg_index` (index definitions)
-/// - `pg_catalog.pg_type` (data types)
+/// - `pg_catalog.pg_type` (data types, including enum types)
+/// - `pg_catalog.pg_enum` (enum labels) - v2.7.0
 /// - `pg_catalog.pg_namespace` (schemas)
 /// - `pg_catalog.pg_database` (databases) - v2.2.1
 /// - `information_schema.tables`
 /// - `information_schema.columns`
+/// - `pg_plan_cache` (plan cache statistics) - v2.7.0
+/// - `pg_stat_statements` (per-statement execution statistics) - v2.7.0
+/// - `pg_stat_user_tables` / `pg_stat_user_indexes` (scan and tuple activity) - v2.7.0
+/// - `pg_stat_activity` (connected sessions) - v2.7.0
+/// - `pg_stats` (column statistics collected by ANALYZE) - v2.7.0
+/// - `pg_depend` (dependencies between objects) - v2.7.0
+/// - `pg_description` (COMMENT ON descriptions) - v2.7.0
 ///
 /// These are read-only metadata tables queried by psql, `pg_dump`, etc.
-use crate::core::{Database, DatabaseError, DataType};
+use crate::core::{Collation, Database, DatabaseError, DataType, DescribedObject};
+use crate::storage::DatabaseStorage;
+use super::dependencies::{DbObject, DependencyGraph, DependencyKind};
 use super::dispatcher_executor::QueryResult;
 
 pub struct SystemCatalog;
@@ -26,6 +36,8 @@ impl SystemCatalog {
                 | "pg_catalog.pg_attribute"
                 | "pg_catalog.pg_index"
                 | "pg_catalog.pg_type"
+                | "pg_catalog.pg_enum"
+                | "pg_enum"
                 | "pg_namespace"
                 | "pg_catalog.pg_database"
                 | "pg_database"
@@ -39,6 +51,24 @@ impl SystemCatalog {
                 | "table_privileges"
                 | "information_schema.tables"
                 | "information_schema.columns"
+                | "pg_catalog.pg_plan_cache"
+                | "pg_plan_cache"
+                | "pg_catalog.pg_stat_statements"
+                | "pg_stat_statements"
+                | "pg_catalog.pg_stat_user_tables"
+                | "pg_stat_user_tables"
+                | "pg_catalog.pg_stat_user_indexes"
+                | "pg_stat_user_indexes"
+                | "pg_catalog.pg_stat_activity"
+                | "pg_stat_activity"
+                | "pg_catalog.pg_locks"
+                | "pg_locks"
+                | "pg_catalog.pg_stats"
+                | "pg_stats"
+                | "pg_catalog.pg_depend"
+                | "pg_depend"
+                | "pg_catalog.pg_description"
+                | "pg_description"
         )
     }
 
@@ -46,12 +76,14 @@ impl SystemCatalog {
     pub fn query(
         table_name: &str,
         db: &Database,
+        database_storage: &DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
         match table_name {
             "pg_catalog.pg_class" => Self::pg_class(db),
             "pg_catalog.pg_attribute" => Self::pg_attribute(db),
             "pg_catalog.pg_index" => Self::pg_index(db),
-            "pg_catalog.pg_type" => Self::pg_type(),
+            "pg_catalog.pg_type" => Self::pg_type(db),
+            "pg_catalog.pg_enum" | "pg_enum" => Self::pg_enum(db),
             "pg_catalog.pg_namespace" | "pg_namespace" => Self::pg_namespace(),
             "pg_catalog.pg_database" | "pg_database" => Self::pg_database(db),
             "pg_catalog.pg_roles" | "pg_roles" => Self::pg_roles(),
@@ -60,6 +92,15 @@ impl SystemCatalog {
             "pg_catalog.table_privileges" | "table_privileges" => Self::table_privileges(db),
             "information_schema.tables" => Self::information_schema_tables(db),
             "information_schema.columns" => Self::information_schema_columns(db),
+            "pg_catalog.pg_plan_cache" | "pg_plan_cache" => Self::pg_plan_cache(db),
+            "pg_catalog.pg_stat_statements" | "pg_stat_statements" => Self::pg_stat_statements(db),
+            "pg_catalog.pg_stat_user_tables" | "pg_stat_user_tables" => Self::pg_stat_user_tables(db, database_storage),
+            "pg_catalog.pg_stat_user_indexes" | "pg_stat_user_indexes" => Self::pg_stat_user_indexes(db),
+            "pg_catalog.pg_stat_activity" | "pg_stat_activity" => Self::pg_stat_activity(db),
+            "pg_catalog.pg_locks" | "pg_locks" => Self::pg_locks(db),
+            "pg_catalog.pg_stats" | "pg_stats" => Self::pg_stats(db),
+            "pg_catalog.pg_depend" | "pg_depend" => Self::pg_depend(db),
+            "pg_catalog.pg_description" | "pg_description" => Self::pg_description(db),
             _ => Err(DatabaseError::TableNotFound(table_name.to_string())),
         }
     }
@@ -179,12 +220,14 @@ impl SystemCatalog {
     /// - indrelid: Table OID
     /// - indkey: Column numbers (space-separated)
     /// - indisunique: Unique index?
+    /// - indisclustered: Index the table was last clustered on? (v2.7.0)
     fn pg_index(db: &Database) -> Result<QueryResult, DatabaseError> {
         let columns = vec![
             "indexrelid".to_string(),
             "indrelid".to_string(),
             "indkey".to_string(),
             "indisunique".to_string(),
+            "indisclustered".to_string(),
         ];
 
         let mut rows = Vec::new();
@@ -205,6 +248,10 @@ impl SystemCatalog {
                 table_oid.to_string(),
                 indkey,
                 index.is_unique().to_string(),
+                db.table_metadata
+                    .get(index.table_name())
+                    .is_some_and(|metadata| metadata.clustered_on.as_deref() == Some(index.name()))
+                    .to_string(),
             ]);
             index_oid += 1;
         }
@@ -214,8 +261,9 @@ impl SystemCatalog {
 
     /// `pg_catalog.pg_type` - Data types
     ///
-    /// Returns all supported data types
-    fn pg_type() -> Result<QueryResult, DatabaseError> {
+    /// Returns all supported data types, followed by the database's enum types
+    /// (v2.7.0, typlen 4 like `PostgreSQL`)
+    fn pg_type(db: &Database) -> Result<QueryResult, DatabaseError> {
         let columns = vec![
             "oid".to_string(),
             "typname".to_string(),
@@ -241,16 +289,64 @@ impl SystemCatalog {
             (17, "bytea", "-1"),
         ];
 
-        let rows = types
+        let mut rows: Vec<Vec<String>> = types
             .into_iter()
             .map(|(oid, name, len)| {
                 vec![oid.to_string(), name.to_string(), len.to_string()]
             })
             .collect();
 
+        for (oid, name, _) in Self::enum_types(db) {
+            rows.push(vec![oid.to_string(), name.to_string(), "4".to_string()]);
+        }
+
         Ok(QueryResult::Rows(rows, columns))
     }
 
+    /// `pg_catalog.pg_enum` - Enum labels (v2.7.0)
+    ///
+    /// Schema:
+    /// - oid: Label OID
+    /// - enumtypid: OID of the owning type in `pg_type`
+    /// - enumsortorder: Position of the label (1-based)
+    /// - enumlabel: Label text
+    fn pg_enum(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "oid".to_string(),
+            "enumtypid".to_string(),
+            "enumsortorder".to_string(),
+            "enumlabel".to_string(),
+        ];
+
+        let mut rows = Vec::new();
+        let mut label_oid = 40000; // Arbitrary offset
+        for (type_oid, _, labels) in Self::enum_types(db) {
+            for (order, label) in labels.iter().enumerate() {
+                rows.push(vec![
+                    label_oid.to_string(),
+                    type_oid.to_string(),
+                    (order + 1).to_string(),
+                    label.clone(),
+                ]);
+                label_oid += 1;
+            }
+        }
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// Enum types sorted by name, with their (fake) type OIDs
+    fn enum_types(db: &Database) -> Vec<(u32, &str, &Vec<String>)> {
+        let mut names: Vec<&String> = db.enums.keys().collect();
+        names.sort();
+
+        names
+            .into_iter()
+            .zip(30000..) // Arbitrary offset
+            .map(|(name, oid)| (oid, name.as_str(), &db.enums[name]))
+            .collect()
+    }
+
     /// `pg_catalog.pg_namespace` - Schemas
     ///
     /// For now, only 'public' schema
@@ -282,7 +378,7 @@ impl SystemCatalog {
         ];
 
         let rows = vec![vec![
-            "13442".to_string(),        // OID
+            db.oid.to_string(),          // OID
             db.name.clone(),             // Current database name
             "10".to_string(),            // Owner OID (postgres)
             "UTF8".to_string(),          // Encoding
@@ -291,6 +387,426 @@ impl SystemCatalog {
         Ok(QueryResult::Rows(rows, columns))
     }
 
+    /// `pg_plan_cache` - Plan cache statistics (v2.7.0)
+    ///
+    /// One row per cached query template, plus a summary row with
+    /// `query = '<total>'` carrying overall hits/misses/invalidations.
+    fn pg_plan_cache(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "query".to_string(),
+            "calls".to_string(),
+            "hits".to_string(),
+            "variants".to_string(),
+            "misses".to_string(),
+            "invalidations".to_string(),
+        ];
+
+        let cache = db.plan_cache.lock().expect("plan cache poisoned");
+        let mut rows: Vec<Vec<String>> = cache
+            .entries()
+            .map(|(template, plan)| {
+                vec![
+                    template.clone(),
+                    plan.calls.to_string(),
+                    plan.hits.to_string(),
+                    plan.variant_count().to_string(),
+                    (plan.calls - plan.hits).to_string(),
+                    String::new(),
+                ]
+            })
+            .collect();
+        rows.sort_by(|a, b| a[0].cmp(&b[0]));
+
+        rows.push(vec![
+            "<total>".to_string(),
+            (cache.hits + cache.misses).to_string(),
+            cache.hits.to_string(),
+            String::new(),
+            cache.misses.to_string(),
+            cache.invalidations.to_string(),
+        ]);
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_stat_statements` - Execution statistics per normalized statement (v2.7.0)
+    ///
+    /// Times are in milliseconds; rows are returned or affected rows.
+    /// Sorted by total execution time, slowest first.
+    fn pg_stat_statements(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "query".to_string(),
+            "calls".to_string(),
+            "total_exec_time".to_string(),
+            "mean_exec_time".to_string(),
+            "min_exec_time".to_string(),
+            "max_exec_time".to_string(),
+            "rows".to_string(),
+        ];
+
+        let stats = db.query_stats.lock().expect("query stats poisoned");
+        let mut entries: Vec<_> = stats.entries().collect();
+        entries.sort_by(|a, b| b.1.total_time.total_cmp(&a.1.total_time).then_with(|| a.0.cmp(b.0)));
+
+        let rows = entries
+            .into_iter()
+            .map(|(template, stats)| {
+                vec![
+                    template.clone(),
+                    stats.calls.to_string(),
+                    format!("{:.3}", stats.total_time),
+                    format!("{:.3}", stats.mean_time()),
+                    format!("{:.3}", stats.min_time),
+                    format!("{:.3}", stats.max_time),
+                    stats.rows.to_string(),
+                ]
+            })
+            .collect();
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_stat_user_tables` - Scan and tuple activity per table (v2.7.0)
+    ///
+    /// Counters are kept since server start. `n_live_tup` is the number of
+    /// stored row versions minus the dead ones not yet vacuumed.
+    fn pg_stat_user_tables(db: &Database, database_storage: &DatabaseStorage) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "schemaname".to_string(),
+            "relname".to_string(),
+            "seq_scan".to_string(),
+            "idx_scan".to_string(),
+            "n_tup_ins".to_string(),
+            "n_tup_upd".to_string(),
+            "n_tup_del".to_string(),
+            "n_live_tup".to_string(),
+            "n_dead_tup".to_string(),
+            "last_vacuum".to_string(),
+        ];
+
+        let mut table_names: Vec<&String> = db.tables.keys().collect();
+        table_names.sort();
+
+        let rows = table_names
+            .into_iter()
+            .map(|name| {
+                let activity = db.activity.table(name);
+                let stored = database_storage.row_count(name).unwrap_or(0) as u64;
+                vec![
+                    "public".to_string(),
+                    name.clone(),
+                    activity.seq_scan.to_string(),
+                    activity.idx_scan.to_string(),
+                    activity.n_tup_ins.to_string(),
+                    activity.n_tup_upd.to_string(),
+                    activity.n_tup_del.to_string(),
+                    stored.saturating_sub(activity.n_dead_tup).to_string(),
+                    activity.n_dead_tup.to_string(),
+                    activity
+                        .last_vacuum
+                        .map_or_else(|| "NULL".to_string(), |at| at.format("%Y-%m-%d %H:%M:%S%.3f+00").to_string()),
+                ]
+            })
+            .collect();
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_stat_user_indexes` - Scan activity per index (v2.7.0)
+    fn pg_stat_user_indexes(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "schemaname".to_string(),
+            "relname".to_string(),
+            "indexrelname".to_string(),
+            "idx_scan".to_string(),
+            "idx_tup_read".to_string(),
+        ];
+
+        let mut indexes: Vec<(&String, &str)> = db
+            .indexes
+            .iter()
+            .map(|(name, index)| (name, index.table_name()))
+            .collect();
+        indexes.sort_by(|a, b| a.1.cmp(b.1).then_with(|| a.0.cmp(b.0)));
+
+        let rows = indexes
+            .into_iter()
+            .map(|(name, table)| {
+                let activity = db.activity.index(name);
+                vec![
+                    "public".to_string(),
+                    table.to_string(),
+                    name.clone(),
+                    activity.idx_scan.to_string(),
+                    activity.idx_tup_read.to_string(),
+                ]
+            })
+            .collect();
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_stats` - Column statistics of the analyzed tables (v2.7.0)
+    ///
+    /// Tables never analyzed have no rows. Value lists are shown as arrays,
+    /// NULL when the column has no most common values.
+    fn pg_stats(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "schemaname".to_string(),
+            "tablename".to_string(),
+            "attname".to_string(),
+            "null_frac".to_string(),
+            "avg_width".to_string(),
+            "n_distinct".to_string(),
+            "most_common_vals".to_string(),
+            "most_common_freqs".to_string(),
+        ];
+        let array = |items: Vec<String>| {
+            if items.is_empty() {
+                return "NULL".to_string();
+            }
+            let items: Vec<String> = items
+                .into_iter()
+                .map(|item| {
+                    if item.is_empty() || item.contains([',', '{', '}', '"', '\\', ' ']) {
+                        format!("\"{}\"", item.replace('\\', "\\\\").replace('"', "\\\""))
+                    } else {
+                        item
+                    }
+                })
+                .collect();
+            format!("{{{}}}", items.join(","))
+        };
+
+        let mut table_names: Vec<&String> = db.tables.keys().collect();
+        table_names.sort();
+
+        let mut rows = Vec::new();
+        for name in table_names {
+            let Some(stats) = db.statistics.table(name) else {
+                continue;
+            };
+            for (column, column_stats) in &stats.columns {
+                rows.push(vec![
+                    "public".to_string(),
+                    name.clone(),
+                    column.clone(),
+                    column_stats.null_frac.to_string(),
+                    column_stats.avg_width.to_string(),
+                    column_stats.n_distinct.to_string(),
+                    array(column_stats.most_common_vals.iter().map(ToString::to_string).collect()),
+                    array(column_stats.most_common_freqs.iter().map(ToString::to_string).collect()),
+                ]);
+            }
+        }
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_depend` - Dependencies between objects (v2.7.0)
+    ///
+    /// Same columns as in `PostgreSQL`, but objects are identified by name
+    /// (`objid`, `refobjid`) rather than OID, as the catalogs here keep no
+    /// stable OIDs. A column is its table with the column number in
+    /// `objsubid` / `refobjsubid`. `deptype` is `n` (normal) or `a` (auto).
+    fn pg_depend(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "classid".to_string(),
+            "objid".to_string(),
+            "objsubid".to_string(),
+            "refclassid".to_string(),
+            "refobjid".to_string(),
+            "refobjsubid".to_string(),
+            "deptype".to_string(),
+        ];
+        let address = |object: &DbObject| -> (&str, String, usize) {
+            let attnum = |table: &str, column: &str| {
+                db.get_table(table)
+                    .and_then(|t| t.columns.iter().position(|c| c.name == column))
+                    .map_or(0, |idx| idx + 1)
+            };
+            match object {
+                DbObject::Table(name) | DbObject::View(name) | DbObject::Index(name) | DbObject::Sequence(name) => {
+                    ("pg_class", name.clone(), 0)
+                }
+                DbObject::Column { table, column } => ("pg_class", table.clone(), attnum(table, column)),
+                DbObject::ForeignKey { table, column } => ("pg_constraint", format!("{table}_{column}_fkey"), 0),
+                DbObject::Default { table, column } => ("pg_attrdef", table.clone(), attnum(table, column)),
+            }
+        };
+
+        let rows = DependencyGraph::build(db)
+            .dependencies()
+            .iter()
+            // Columns are parts of their table rather than objects depending on it
+            .filter(|dependency| !matches!(dependency.dependent, DbObject::Column { .. }))
+            .map(|dependency| {
+                let (classid, objid, objsubid) = address(&dependency.dependent);
+                let (refclassid, refobjid, refobjsubid) = address(&dependency.referenced);
+                vec![
+                    classid.to_string(),
+                    objid,
+                    objsubid.to_string(),
+                    refclassid.to_string(),
+                    refobjid,
+                    refobjsubid.to_string(),
+                    match dependency.kind {
+                        DependencyKind::Normal => "n",
+                        DependencyKind::Auto => "a",
+                    }
+                    .to_string(),
+                ]
+            })
+            .collect();
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_description` - Descriptions set by COMMENT ON (v2.7.0)
+    ///
+    /// `objoid` is the object's OID as listed in `pg_class`, `pg_type` or
+    /// `pg_namespace`, and `classoid` the OID of that catalog; a column is
+    /// its table with the column number in `objsubid`.
+    fn pg_description(db: &Database) -> Result<QueryResult, DatabaseError> {
+        const PG_CLASS: u32 = 1259;
+        const PG_TYPE: u32 = 1247;
+        const PG_NAMESPACE: u32 = 2615;
+
+        let columns = vec![
+            "objoid".to_string(),
+            "classoid".to_string(),
+            "objsubid".to_string(),
+            "description".to_string(),
+        ];
+
+        let rows = db
+            .descriptions
+            .iter()
+            .filter_map(|(object, text)| {
+                let (objoid, classoid, objsubid) = match object {
+                    DescribedObject::Relation(name) => (Self::relation_oid(db, name)?, PG_CLASS, 0),
+                    DescribedObject::Column { table, column } => {
+                        let attnum = db
+                            .get_table(table)
+                            .and_then(|t| t.columns.iter().position(|c| c.name == *column))
+                            .map_or(0, |idx| idx + 1);
+                        (Self::relation_oid(db, table)?, PG_CLASS, attnum)
+                    }
+                    DescribedObject::Type(name) => {
+                        let (oid, _, _) = Self::enum_types(db).into_iter().find(|(_, enum_name, _)| enum_name == name)?;
+                        (oid, PG_TYPE, 0)
+                    }
+                    DescribedObject::Schema(name) => match name.as_str() {
+                        "pg_catalog" => (11, PG_NAMESPACE, 0),
+                        "public" => (2200, PG_NAMESPACE, 0),
+                        _ => return None,
+                    },
+                };
+                Some(vec![objoid.to_string(), classoid.to_string(), objsubid.to_string(), text.to_string()])
+            })
+            .collect();
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// OID of a table, view or index, numbered in the order `pg_class` lists them
+    fn relation_oid(db: &Database, name: &str) -> Option<u32> {
+        let position = db.tables.keys().chain(db.views.keys()).chain(db.indexes.keys()).position(|n| n == name)?;
+        u32::try_from(position).ok().map(|position| 16384 + position)
+    }
+
+    /// `pg_stat_activity` - One row per connected session (v2.7.0)
+    fn pg_stat_activity(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "datname".to_string(),
+            "pid".to_string(),
+            "usename".to_string(),
+            "application_name".to_string(),
+            "client_addr".to_string(),
+            "backend_start".to_string(),
+            "query_start".to_string(),
+            "state".to_string(),
+            "query".to_string(),
+        ];
+        let timestamp = |at: chrono::DateTime<chrono::Utc>| at.format("%Y-%m-%d %H:%M:%S%.3f+00").to_string();
+
+        let rows = db
+            .sessions
+            .list()
+            .into_iter()
+            .map(|info| {
+                vec![
+                    info.datname,
+                    info.pid.to_string(),
+                    info.usename,
+                    info.application_name,
+                    info.client_addr.unwrap_or_else(|| "NULL".to_string()),
+                    timestamp(info.backend_start),
+                    info.query_start.map_or_else(|| "NULL".to_string(), timestamp),
+                    info.state,
+                    info.query,
+                ]
+            })
+            .collect();
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
+    /// `pg_catalog.pg_locks` - Locks held or awaited by connected sessions (v2.7.0)
+    ///
+    /// There are two kinds: each statement locks its database (`object` with
+    /// the database's OID, `ShareLock` for queries and `ExclusiveLock` for
+    /// everything else), and each open transaction holds its `transactionid`.
+    /// A session waiting for its database shows `granted = f` with the time
+    /// it started waiting.
+    fn pg_locks(db: &Database) -> Result<QueryResult, DatabaseError> {
+        let columns = vec![
+            "locktype".to_string(),
+            "database".to_string(),
+            "relation".to_string(),
+            "transactionid".to_string(),
+            "pid".to_string(),
+            "mode".to_string(),
+            "granted".to_string(),
+            "waitstart".to_string(),
+        ];
+        let bool_text = |value: bool| if value { "t" } else { "f" }.to_string();
+
+        let mut rows = Vec::new();
+        for info in db.sessions.list() {
+            if let Some(lock) = info.database_lock {
+                rows.push(vec![
+                    "object".to_string(),
+                    info.datid.to_string(),
+                    "NULL".to_string(),
+                    "NULL".to_string(),
+                    info.pid.to_string(),
+                    if lock.exclusive { "ExclusiveLock" } else { "ShareLock" }.to_string(),
+                    bool_text(lock.granted),
+                    if lock.granted {
+                        "NULL".to_string()
+                    } else {
+                        lock.wait_start.format("%Y-%m-%d %H:%M:%S%.3f+00").to_string()
+                    },
+                ]);
+            }
+            if let Some(xid) = info.xid {
+                rows.push(vec![
+                    "transactionid".to_string(),
+                    "NULL".to_string(),
+                    "NULL".to_string(),
+                    xid.to_string(),
+                    info.pid.to_string(),
+                    "ExclusiveLock".to_string(),
+                    bool_text(true),
+                    "NULL".to_string(),
+                ]);
+            }
+        }
+
+        Ok(QueryResult::Rows(rows, columns))
+    }
+
     /// `information_schema.tables` - Standard SQL metadata
     fn information_schema_tables(db: &Database) -> Result<QueryResult, DatabaseError> {
         let columns = vec![
@@ -335,6 +851,7 @@ impl SystemCatalog {
             "ordinal_position".to_string(),
             "is_nullable".to_string(),
             "data_type".to_string(),
+            "collation_name".to_string(),
         ];
 
         let mut rows = Vec::new();
@@ -348,7 +865,12 @@ impl SystemCatalog {
                     col.name.clone(),
                     (col_idx + 1).to_string(),
                     if col.nullable { "YES" } else { "NO" }.to_string(),
-                    Self::data_type_to_sql_name(&col.data_type),
+                    col.data_type.sql_name(),
+                    // v2.7.0: the default "C" collation is shown as NULL
+                    match col.collation {
+                        Collation::C => "NULL".to_string(),
+                        collation => collation.name().to_string(),
+                    },
                 ]);
             }
         }
@@ -362,6 +884,7 @@ impl SystemCatalog {
             DataType::Boolean => 16,
             DataType::SmallInt => 21,
             DataType::Integer => 23,
+            DataType::BigInt => 20,
             DataType::Serial => 23,
             DataType::BigSerial => 20,
             DataType::Real => 700,
@@ -380,32 +903,6 @@ impl SystemCatalog {
         }
     }
 
-    /// Convert `DataType` to SQL type name
-    fn data_type_to_sql_name(data_type: &DataType) -> String {
-        match data_type {
-            DataType::Boolean => "boolean".to_string(),
-            DataType::SmallInt => "smallint".to_string(),
-            DataType::Integer => "integer".to_string(),
-            DataType::Serial => "serial".to_string(),
-            DataType::BigSerial => "bigserial".to_string(),
-            DataType::Real => "real".to_string(),
-            DataType::Numeric { precision, scale } => {
-                format!("numeric({precision},{scale})")
-            }
-            DataType::Text => "text".to_string(),
-            DataType::Varchar { max_length } => format!("varchar({max_length})"),
-            DataType::Char { length } => format!("char({length})"),
-            DataType::Date => "date".to_string(),
-            DataType::Timestamp => "timestamp".to_string(),
-            DataType::TimestampTz => "timestamptz".to_string(),
-            DataType::Uuid => "uuid".to_string(),
-            DataType::Json => "json".to_string(),
-            DataType::Jsonb => "jsonb".to_string(),
-            DataType::Bytea => "bytea".to_string(),
-            DataType::Enum { name, .. } => name.clone(),
-        }
-    }
-
     /// `pg_catalog.pg_roles` - Database roles (v2.2.2)
     ///
     /// NOTE: This is a minimal stub implementation.
@@ -550,6 +1047,12 @@ mod tests {
     use super::*;
     use crate::core::{Table, Column};
 
+    fn test_storage() -> (tempfile::TempDir, DatabaseStorage) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = DatabaseStorage::new(temp_dir.path().to_str().unwrap(), 32).unwrap();
+        (temp_dir, storage)
+    }
+
     #[test]
     fn test_is_system_catalog() {
         assert!(SystemCatalog::is_system_catalog("pg_catalog.pg_class"));
@@ -591,7 +1094,7 @@ mod tests {
 
     #[test]
     fn test_pg_type() {
-        let result = SystemCatalog::pg_type().unwrap();
+        let result = SystemCatalog::pg_type(&Database::new("test".to_string())).unwrap();
         match result {
             QueryResult::Rows(rows, cols) => {
                 assert_eq!(cols, vec!["oid", "typname", "typlen"]);
@@ -605,6 +1108,166 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pg_enum() {
+        let mut db = Database::new("test".to_string());
+        db.create_enum("mood".to_string(), vec!["sad".to_string(), "happy".to_string()]).unwrap();
+
+        let type_oid = match SystemCatalog::pg_type(&db).unwrap() {
+            QueryResult::Rows(rows, _) => rows.into_iter().find(|r| r[1] == "mood").unwrap()[0].clone(),
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        };
+
+        match SystemCatalog::query("pg_enum", &db, &test_storage().1).unwrap() {
+            QueryResult::Rows(rows, cols) => {
+                assert_eq!(cols, vec!["oid", "enumtypid", "enumsortorder", "enumlabel"]);
+                assert_eq!(rows.len(), 2);
+                assert!(rows.iter().all(|r| r[1] == type_oid));
+                assert_eq!(rows[1][2], "2");
+                assert_eq!(rows[1][3], "happy");
+            }
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        }
+    }
+
+    #[test]
+    fn test_pg_description() {
+        use crate::executor::QueryExecutor;
+        use crate::transaction::GlobalTransactionManager;
+
+        let mut db = Database::new("test".to_string());
+        let (_dir, mut storage) = test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE users (name TEXT)",
+            "CREATE TYPE mood AS ENUM ('sad')",
+            "COMMENT ON TABLE users IS 'Users'",
+            "COMMENT ON COLUMN users.name IS 'Name'",
+            "COMMENT ON TYPE mood IS 'Moods'",
+            "COMMENT ON SCHEMA public IS 'Default schema'",
+        ] {
+            let stmt = crate::parser::parse_statement(sql).unwrap();
+            QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
+        }
+
+        let oid_of = |catalog: QueryResult, name: &str| match catalog {
+            QueryResult::Rows(rows, _) => rows.into_iter().find(|r| r[1] == name).unwrap()[0].clone(),
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        };
+        let table_oid = oid_of(SystemCatalog::pg_class(&db).unwrap(), "users");
+        let type_oid = oid_of(SystemCatalog::pg_type(&db).unwrap(), "mood");
+
+        match SystemCatalog::query("pg_description", &db, &storage).unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(
+                rows,
+                vec![
+                    vec![table_oid.as_str(), "1259", "0", "Users"],
+                    vec![table_oid.as_str(), "1259", "1", "Name"],
+                    vec![type_oid.as_str(), "1247", "0", "Moods"],
+                    vec!["2200", "2615", "0", "Default schema"],
+                ]
+            ),
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        }
+    }
+
+    #[test]
+    fn test_pg_stat_statements() {
+        let db = Database::new("test".to_string());
+        let elapsed = std::time::Duration::from_millis;
+        let mut stats = db.query_stats.lock().unwrap();
+        stats.record("SELECT * FROM t WHERE id = 1", elapsed(1), 1);
+        stats.record("SELECT * FROM t WHERE id = 7", elapsed(3), 1);
+        stats.record("DELETE FROM t", elapsed(10), 4);
+        drop(stats);
+
+        match SystemCatalog::query("pg_stat_statements", &db, &test_storage().1).unwrap() {
+            QueryResult::Rows(rows, cols) => {
+                assert_eq!(cols[..3], ["query", "calls", "total_exec_time"]);
+                assert_eq!(rows.len(), 2);
+                // Slowest statement first
+                assert_eq!(rows[0][0], "DELETE FROM t");
+                assert_eq!(rows[1][0], "SELECT * FROM t WHERE id = $1");
+                assert_eq!(rows[1][1], "2");
+                assert_eq!(rows[1][3], "2.000");
+                assert_eq!(rows[1][6], "2");
+            }
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        }
+    }
+
+    #[test]
+    fn test_pg_stat_user_tables_and_indexes() {
+        use crate::executor::QueryExecutor;
+        use crate::transaction::GlobalTransactionManager;
+
+        let mut db = Database::new("test".to_string());
+        let (_dir, mut storage) = test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE t (id INTEGER, name TEXT)",
+            "CREATE INDEX idx_t_id ON t (id)",
+            "INSERT INTO t (id, name) VALUES (1, 'a')",
+            "INSERT INTO t (id, name) VALUES (2, 'b')",
+            "INSERT INTO t (id, name) VALUES (3, 'c')",
+            "SELECT * FROM t WHERE id = 2",
+            "SELECT * FROM t",
+            "UPDATE t SET name = 'z' WHERE id = 1",
+            "DELETE FROM t WHERE id = 3",
+        ] {
+            let stmt = crate::parser::parse_statement(sql).unwrap();
+            QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
+        }
+
+        match SystemCatalog::query("pg_stat_user_tables", &db, &storage).unwrap() {
+            QueryResult::Rows(rows, cols) => {
+                assert_eq!(cols[1..9], ["relname", "seq_scan", "idx_scan", "n_tup_ins", "n_tup_upd", "n_tup_del", "n_live_tup", "n_dead_tup"]);
+                // SELECT *, UPDATE and DELETE scan the table; the point lookup uses the index
+                assert_eq!(rows, vec![vec!["public", "t", "3", "1", "3", "1", "1", "2", "2", "NULL"]]);
+            }
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        }
+
+        match SystemCatalog::query("pg_stat_user_indexes", &db, &storage).unwrap() {
+            QueryResult::Rows(rows, _) => assert_eq!(rows, vec![vec!["public", "t", "idx_t_id", "1", "1"]]),
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        }
+    }
+
+    #[test]
+    fn test_pg_depend() {
+        use crate::executor::QueryExecutor;
+        use crate::transaction::GlobalTransactionManager;
+
+        let mut db = Database::new("test".to_string());
+        let (_dir, mut storage) = test_storage();
+        let tx_manager = GlobalTransactionManager::new();
+        for sql in [
+            "CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT)",
+            "CREATE TABLE orders (id INTEGER, user_id INTEGER REFERENCES users(id))",
+            "CREATE VIEW names AS SELECT name FROM users",
+        ] {
+            let stmt = crate::parser::parse_statement(sql).unwrap();
+            QueryExecutor::execute(&mut db, stmt, None, &tx_manager, &mut storage, None).unwrap();
+        }
+
+        match SystemCatalog::query("pg_depend", &db, &storage).unwrap() {
+            QueryResult::Rows(rows, cols) => {
+                assert_eq!(cols, vec!["classid", "objid", "objsubid", "refclassid", "refobjid", "refobjsubid", "deptype"]);
+                let has = |row: [&str; 7]| rows.iter().any(|r| *r == row);
+                assert!(has(["pg_class", "names", "0", "pg_class", "users", "2", "n"]));
+                assert!(has(["pg_class", "names", "0", "pg_class", "users", "0", "n"]));
+                assert!(!has(["pg_class", "names", "0", "pg_class", "users", "1", "n"]));
+                assert!(has(["pg_constraint", "orders_user_id_fkey", "0", "pg_class", "users", "1", "n"]));
+                assert!(has(["pg_constraint", "orders_user_id_fkey", "0", "pg_class", "orders", "2", "a"]));
+                assert!(has(["pg_class", "users_id_seq", "0", "pg_class", "users", "1", "a"]));
+                assert!(has(["pg_attrdef", "users", "1", "pg_class", "users_id_seq", "0", "n"]));
+                assert_eq!(rows.len(), 9);
+            }
+            QueryResult::Success(_) => panic!("Expected Rows"),
+        }
+    }
+
     #[test]
     fn test_pg_class() {
         let mut db = Database::new("test".to_string());
@@ -617,6 +1280,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
         );
         db.create_table(table).unwrap();
@@ -649,6 +1313,7 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "name".to_string(),
@@ -657,6 +1322,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -717,6 +1383,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             }],
         );
         db.create_table(table).unwrap();
@@ -734,7 +1401,8 @@ mod tests {
                         "column_name",
                         "ordinal_position",
                         "is_nullable",
-                        "data_type"
+                        "data_type",
+                        "collation_name"
                     ]
                 );
                 assert_eq!(rows.len(), 1);
@@ -743,6 +1411,7 @@ mod tests {
                 assert_eq!(rows[0][4], "1");
                 assert_eq!(rows[0][5], "NO");
                 assert_eq!(rows[0][6], "integer");
+                assert_eq!(rows[0][7], "NULL");
             }
             _ => panic!("Expected Rows"),
         }
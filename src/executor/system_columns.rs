@@ -0,0 +1,320 @@
+/// System columns every table has without declaring them (v2.7.0)
+///
+/// `ctid` names the physical row version as `(page,item)` in the table's
+/// heap, items counted from 1 as in `PostgreSQL`, so `DELETE FROM t WHERE
+/// ctid = '(0,3)'` hits exactly that version; an UPDATE moves a row to a new
+/// ctid. Tables stored USING columnar or lsm have no pages and read NULL. `xmin` is the transaction that wrote the
+/// version and `xmax` the one that deleted or replaced it, 0 while nobody
+/// has; a row a transaction in progress deletes is still visible to others,
+/// with that transaction as `xmax`. A user column of the same name hides
+/// the system column.
+///
+/// System columns are never part of `*`. Queries naming one see them after
+/// the table's own columns: SELECT reads the visible rows through its own
+/// path here, UPDATE and DELETE attach the values for their WHERE clause.
+use std::borrow::Cow;
+use crate::parser::{Condition, Expression, SelectColumn, SortOrder};
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::value::compare_for_sort;
+use crate::types::{Collation, Column, DataType, Database, DatabaseError, Row, Table, Value};
+use super::conditions::ConditionEvaluator;
+use super::dispatcher_executor::QueryResult;
+use super::functions::FunctionExecutor;
+use super::partitions::Partitions;
+use super::pipeline::{Distinct, Paginate};
+use super::queries::QueryExecutor as QueriesExecutor;
+use super::subquery::SubqueryContext;
+use super::views::ViewResolver;
+
+/// Names of the system columns, in the order they follow the table's columns
+pub const SYSTEM_COLUMNS: [&str; 3] = ["ctid", "xmin", "xmax"];
+
+pub struct SystemColumns;
+
+impl SystemColumns {
+    /// Does `name` refer to a system column of a table with `columns`?
+    #[must_use]
+    pub fn is_system(columns: &[Column], name: &str) -> bool {
+        SYSTEM_COLUMNS.contains(&name) && !columns.iter().any(|col| col.name == name)
+    }
+
+    /// Does `condition` read a system column?
+    #[must_use]
+    pub fn used_in(columns: &[Column], condition: Option<&Condition>) -> bool {
+        condition.is_some_and(|cond| {
+            let mut names = Vec::new();
+            ViewResolver::collect_condition_columns(cond, &mut names);
+            names.iter().any(|name| Self::is_system(columns, name))
+        })
+    }
+
+    /// Does a SELECT on a table with `columns` read a system column?
+    #[must_use]
+    pub fn used_by_select(
+        table: &Table,
+        columns: &[SelectColumn],
+        filter: Option<&Condition>,
+        order_by: Option<&(Expression, SortOrder)>,
+    ) -> bool {
+        let mut names = Vec::new();
+        for col in columns {
+            match col {
+                SelectColumn::Regular { name, .. } => names.push(Self::unqualified(&table.name, name).to_string()),
+                SelectColumn::Expression { expr, .. } => ViewResolver::collect_expression_columns(expr, &mut names),
+                _ => {}
+            }
+        }
+        if let Some((key, _)) = order_by {
+            let mut keys = Vec::new();
+            ViewResolver::collect_expression_columns(key, &mut keys);
+            names.extend(keys.iter().map(|name| Self::unqualified(&table.name, name).to_string()));
+        }
+        names.iter().any(|name| Self::is_system(&table.columns, name)) || Self::used_in(&table.columns, filter)
+    }
+
+    /// `columns` followed by the system columns they do not hide
+    #[must_use]
+    pub fn extend(columns: &[Column]) -> Vec<Column> {
+        let mut extended = columns.to_vec();
+        extended.extend(SYSTEM_COLUMNS.iter().filter(|name| Self::is_system(columns, name)).map(|name| Column {
+            name: (*name).to_string(),
+            data_type: if *name == "ctid" { DataType::Text } else { DataType::Integer },
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            foreign_key: None,
+            collation: Collation::C,
+        }));
+        extended
+    }
+
+    /// `row` with the values of the system columns of `extend(columns)` appended
+    #[must_use]
+    pub fn attach(columns: &[Column], row: &Row) -> Row {
+        let mut attached = row.clone();
+        for name in SYSTEM_COLUMNS.iter().filter(|name| Self::is_system(columns, name)) {
+            attached.values.push(Self::value(name, row));
+        }
+        attached
+    }
+
+    /// `row` as UPDATE and DELETE evaluate WHERE on it: with the system
+    /// columns attached when `with_system` is set (see `used_in`)
+    #[must_use]
+    pub fn for_filter<'a>(columns: &[Column], row: &'a Row, with_system: bool) -> Cow<'a, Row> {
+        if with_system {
+            Cow::Owned(Self::attach(columns, row))
+        } else {
+            Cow::Borrowed(row)
+        }
+    }
+
+    fn value(name: &str, row: &Row) -> Value {
+        match name {
+            "ctid" => row.ctid.map_or(Value::Null, |ctid| Value::Text(ctid.to_string())),
+            "xmin" => Value::Integer(i64::try_from(row.xmin).unwrap_or(i64::MAX)),
+            "xmax" => Value::Integer(i64::try_from(row.xmax.unwrap_or(0)).unwrap_or(i64::MAX)),
+            _ => Value::Null,
+        }
+    }
+
+    fn unqualified<'a>(table: &str, name: &'a str) -> &'a str {
+        name.strip_prefix(table).and_then(|rest| rest.strip_prefix('.')).unwrap_or(name)
+    }
+
+    /// SELECT from `table` reading system columns
+    ///
+    /// Supports the select list (columns, literals and expressions), WHERE,
+    /// DISTINCT, ORDER BY, OFFSET and LIMIT.
+    pub fn select(
+        db: &Database,
+        table: &Table,
+        distinct: bool,
+        columns: &[SelectColumn],
+        filter: Option<&Condition>,
+        order_by: Option<&(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &DatabaseStorage,
+    ) -> Result<QueryResult, DatabaseError> {
+        let all_columns = Self::extend(&table.columns);
+        let position = |name: &str| {
+            let name = Self::unqualified(&table.name, name);
+            all_columns
+                .iter()
+                .position(|col| col.name == name)
+                .ok_or_else(|| DatabaseError::ColumnNotFound(name.to_string()))
+        };
+
+        let snapshot = tx_manager.get_snapshot();
+        let subquery_ctx = SubqueryContext::new();
+        let mut rows = Vec::new();
+        for row in Partitions::scan(db, database_storage, &table.name, filter)? {
+            let row = row?;
+            if !row.is_visible_to_snapshot(&snapshot) {
+                continue;
+            }
+            let row = Self::attach(&table.columns, &row);
+            let matches = match filter {
+                Some(cond) => ConditionEvaluator::evaluate_with_context(
+                    &all_columns, &row, cond, db, tx_manager, database_storage, &subquery_ctx,
+                )?,
+                None => true,
+            };
+            if matches {
+                rows.push(row);
+            }
+        }
+
+        if let Some((key, order)) = order_by {
+            let star: Vec<String> = table.columns.iter().map(|col| col.name.clone()).collect();
+            let key = QueriesExecutor::resolve_sort_key(key.clone(), columns, &star)?;
+            let idx = position(QueriesExecutor::sort_column(&key)?)?;
+            rows.sort_by(|a, b| {
+                let cmp = compare_for_sort(&a.values[idx], &b.values[idx]);
+                match order {
+                    SortOrder::Asc => cmp,
+                    SortOrder::Desc => cmp.reverse(),
+                }
+            });
+        }
+
+        let mut column_names = Vec::new();
+        for col in columns {
+            match col {
+                SelectColumn::Regular { name, .. } if name == "*" => {
+                    column_names.extend(table.columns.iter().map(|c| c.name.clone()));
+                }
+                SelectColumn::Regular { name, alias } => {
+                    column_names.push(alias.as_ref().unwrap_or(&all_columns[position(name)?].name).clone());
+                }
+                SelectColumn::Literal(_) => column_names.push("?column?".to_string()),
+                SelectColumn::Expression { expr, alias } => column_names.push(FunctionExecutor::column_name(expr, alias.as_ref())),
+                _ => {
+                    return Err(DatabaseError::ParseError(
+                        "System columns can only be selected with columns, literals and expressions".to_string()
+                    ));
+                }
+            }
+        }
+
+        let project = |row: Row| -> Result<Vec<String>, DatabaseError> {
+            let mut rendered = Vec::with_capacity(column_names.len());
+            for col in columns {
+                match col {
+                    SelectColumn::Regular { name, .. } if name == "*" => {
+                        rendered.extend(row.values[..table.columns.len()].iter().map(ToString::to_string));
+                    }
+                    SelectColumn::Regular { name, .. } => rendered.push(row.values[position(name)?].to_string()),
+                    SelectColumn::Literal(value) => rendered.push(value.to_string()),
+                    SelectColumn::Expression { expr, .. } => {
+                        rendered.push(FunctionExecutor::evaluate_values(expr, &all_columns, &row.values)?.to_string());
+                    }
+                    _ => {}
+                }
+            }
+            Ok(rendered)
+        };
+        let projected: Box<dyn Iterator<Item = Result<Vec<String>, DatabaseError>>> =
+            Box::new(rows.into_iter().map(project));
+        let projected = if distinct { Box::new(Distinct::new(projected)) } else { projected };
+        let result_rows = Paginate::new(projected, offset, limit).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(QueryResult::Rows(result_rows, column_names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryExecutor};
+    use crate::parser::parse_statement;
+
+    fn rows(result: QueryResult) -> Vec<Vec<String>> {
+        match result {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(msg) => panic!("Expected rows, got {msg}"),
+        }
+    }
+
+    #[test]
+    fn test_ctid() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut run = |sql: &str| run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap();
+        run("CREATE TABLE items (id INTEGER, name TEXT)");
+        run("INSERT INTO items VALUES (1, 'a'), (2, 'b'), (2, 'b')");
+
+        let result = run("SELECT ctid, * FROM items ORDER BY ctid");
+        match &result {
+            QueryResult::Rows(_, columns) => assert_eq!(columns, &["ctid", "id", "name"]),
+            QueryResult::Success(_) => panic!("Expected rows"),
+        }
+        assert_eq!(rows(result), vec![
+            vec!["(0,1)".to_string(), "1".to_string(), "a".to_string()],
+            vec!["(0,2)".to_string(), "2".to_string(), "b".to_string()],
+            vec!["(0,3)".to_string(), "2".to_string(), "b".to_string()],
+        ]);
+        // `*` alone leaves it out
+        assert_eq!(rows(run("SELECT * FROM items WHERE id = 1"))[0].len(), 2);
+
+        // Removing one of two identical rows, as frameworks without a key do
+        run("DELETE FROM items WHERE ctid = '(0,3)'");
+        assert_eq!(rows(run("SELECT ctid FROM items WHERE id = 2")), vec![vec!["(0,2)".to_string()]]);
+
+        // An UPDATE writes a new version somewhere else
+        run("UPDATE items SET name = 'c' WHERE ctid = '(0,2)'");
+        let moved = rows(run("SELECT ctid, name FROM items WHERE id = 2"));
+        assert_eq!(moved.len(), 1);
+        assert_ne!(moved[0][0], "(0,2)");
+        assert_eq!(moved[0][1], "c");
+
+        // A column named ctid hides the system column
+        run("CREATE TABLE own (ctid INTEGER)");
+        run("INSERT INTO own VALUES (7)");
+        assert_eq!(rows(run("SELECT ctid FROM own")), vec![vec!["7".to_string()]]);
+    }
+
+    #[test]
+    fn test_xmin_xmax() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut run = |sql: &str, tx_id: Option<u64>| {
+            QueryExecutor::execute(&mut db, parse_statement(sql).unwrap(), None, &tx_manager, &mut storage, tx_id).unwrap()
+        };
+        run("CREATE TABLE items (id INTEGER)", None);
+        let (writer, _) = tx_manager.begin_transaction();
+        run("INSERT INTO items VALUES (1), (2)", Some(writer));
+        tx_manager.commit_transaction(writer);
+
+        let versions = rows(run("SELECT id, xmin, xmax FROM items ORDER BY id", None));
+        assert_eq!(versions, vec![
+            vec!["1".to_string(), writer.to_string(), "0".to_string()],
+            vec!["2".to_string(), writer.to_string(), "0".to_string()],
+        ]);
+        // Comparable as numbers
+        assert_eq!(rows(run(&format!("SELECT id FROM items WHERE xmin = {writer} AND id > 1"), None)).len(), 1);
+
+        // A delete in progress shows as xmax to everybody else
+        let (deleter, _) = tx_manager.begin_transaction();
+        run("DELETE FROM items WHERE id = 2", Some(deleter));
+        assert_eq!(
+            rows(run("SELECT xmax FROM items WHERE id = 2", None)),
+            vec![vec![deleter.to_string()]]
+        );
+        tx_manager.rollback_transaction(deleter);
+
+        // An UPDATE writes a version with its own xmin
+        let (updater, _) = tx_manager.begin_transaction();
+        run("UPDATE items SET id = 3 WHERE id = 1", Some(updater));
+        tx_manager.commit_transaction(updater);
+        assert_eq!(
+            rows(run("SELECT xmin, xmax FROM items WHERE id = 3", None)),
+            vec![vec![updater.to_string(), "0".to_string()]]
+        );
+    }
+}
@@ -4,11 +4,16 @@
 /// - `version()` - Database version string
 /// - `current_database()` - Current database name
 /// - `pg_table_size(table_name)` - Table size in bytes
+/// - `pg_relation_size(name)` - Size of the relation's own pages in bytes
+/// - `pg_database_size([db_name])` - Size of all tables of the database in bytes
+/// - `pg_size_pretty(bytes)` - Size with a unit (`8192 bytes`, `16 kB`, ...)
 /// - `current_user` - Current user name
 /// - `current_schema()` - Current schema name
+/// - `set_config(name, value, is_local)` - Same as SET, returns the new value
+/// - `current_setting(name)` - Value of a run-time parameter, like SHOW
 ///
 /// These functions are intercepted in SELECT queries and evaluated specially.
-use crate::core::{Database, DatabaseError};
+use crate::core::{Database, DatabaseError, Zone};
 
 pub struct SystemFunctions;
 
@@ -21,10 +26,26 @@ impl SystemFunctions {
             "version"
                 | "current_database"
                 | "pg_table_size"
+                | "pg_relation_size"
+                | "pg_database_size"
+                | "pg_size_pretty"
                 | "current_user"
                 | "current_schema"
                 | "pg_backend_pid"
                 | "pg_encoding_to_char"
+                | "set_config"
+                | "current_setting"
+        )
+    }
+
+    /// Functions that return a byte count (v2.7.0)
+    ///
+    /// Their result is a number, so that monitoring queries can do arithmetic on it.
+    #[must_use]
+    pub fn returns_size(name: &str) -> bool {
+        matches!(
+            name.to_lowercase().as_str(),
+            "pg_table_size" | "pg_relation_size" | "pg_database_size"
         )
     }
 
@@ -44,6 +65,19 @@ impl SystemFunctions {
             "current_user" => Ok("rustdb".to_string()),
             "pg_backend_pid" => Ok(std::process::id().to_string()),
             "pg_encoding_to_char" => Ok("UTF8".to_string()),
+            // v2.7.0: pg_dump starts every dump with set_config('search_path', '', false)
+            "set_config" => match args {
+                [_, value, _] => Ok(value.trim_matches('\'').to_string()),
+                _ => Err(DatabaseError::ParseError(
+                    "set_config() requires setting name, new value and is_local".to_string(),
+                )),
+            },
+            "current_setting" => match args {
+                [name] => Self::current_setting(name),
+                _ => Err(DatabaseError::ParseError(
+                    "current_setting() requires setting name".to_string(),
+                )),
+            },
             "pg_table_size" => {
                 if args.is_empty() {
                     return Err(DatabaseError::ParseError(
@@ -52,6 +86,24 @@ impl SystemFunctions {
                 }
                 Self::pg_table_size(&args[0], db, database_storage)
             }
+            "pg_relation_size" => {
+                if args.is_empty() {
+                    return Err(DatabaseError::ParseError(
+                        "pg_relation_size() requires relation name argument".to_string(),
+                    ));
+                }
+                Self::pg_relation_size(&args[0], db, database_storage)
+            }
+            "pg_database_size" => Self::pg_database_size(args.first(), db, database_storage),
+            "pg_size_pretty" => {
+                let bytes = args
+                    .first()
+                    .and_then(|arg| arg.trim_matches('\'').parse::<i64>().ok())
+                    .ok_or_else(|| {
+                        DatabaseError::ParseError("pg_size_pretty() requires a byte count".to_string())
+                    })?;
+                Ok(Self::size_pretty(bytes))
+            }
             _ => Err(DatabaseError::ParseError(format!(
                 "Unknown system function: {name}"
             ))),
@@ -69,6 +121,19 @@ impl SystemFunctions {
         )
     }
 
+    /// `current_setting(name)` and `SHOW name` - Value of a run-time parameter (v2.7.0)
+    ///
+    /// Of the session's parameters only `TimeZone` reaches the executor (see
+    /// `Zone::scope`); the server answers SHOW for the others itself.
+    pub fn current_setting(name: &str) -> Result<String, DatabaseError> {
+        let name = name.trim_matches('\'');
+        if name.eq_ignore_ascii_case("timezone") {
+            Ok(Zone::with_current(|zone| zone.name().to_string()))
+        } else {
+            Err(DatabaseError::UnrecognizedParameter(name.to_lowercase()))
+        }
+    }
+
     /// `pg_table_size(table_name)` - Return table size in bytes
     ///
     /// Returns approximate size based on row count and average row size
@@ -112,6 +177,67 @@ impl SystemFunctions {
         Ok(size_bytes.to_string())
     }
 
+    /// `pg_relation_size(name)` - Size of the relation's pages in bytes (v2.7.0)
+    ///
+    /// Only pages are counted, so a partitioned parent, a table whose rows
+    /// live in a columnar or LSM backend, and an index (kept in memory) all
+    /// report 0, as their own relation holds no pages.
+    fn pg_relation_size(
+        name: &str,
+        db: &Database,
+        database_storage: Option<&crate::storage::DatabaseStorage>,
+    ) -> Result<String, DatabaseError> {
+        let name = name.trim_matches('\'').trim_matches('"');
+        if db.indexes.contains_key(name) {
+            return Ok("0".to_string());
+        }
+        if db.get_table(name).is_none() {
+            return Err(DatabaseError::TableNotFound(name.to_string()));
+        }
+        Ok(Self::paged_size(name, database_storage).to_string())
+    }
+
+    /// `pg_database_size([name])` - Pages of every table of the database in bytes (v2.7.0)
+    ///
+    /// Only the current database can be measured; naming another one is an error.
+    fn pg_database_size(
+        name: Option<&String>,
+        db: &Database,
+        database_storage: Option<&crate::storage::DatabaseStorage>,
+    ) -> Result<String, DatabaseError> {
+        if let Some(name) = name {
+            let name = name.trim_matches('\'').trim_matches('"');
+            if name != db.name {
+                return Err(DatabaseError::DatabaseNotFound(name.to_string()));
+            }
+        }
+        let size: usize = db
+            .tables
+            .keys()
+            .map(|table| Self::paged_size(table, database_storage))
+            .sum();
+        Ok(size.to_string())
+    }
+
+    /// Bytes taken by the pages of a table, 0 without page storage
+    fn paged_size(table_name: &str, database_storage: Option<&crate::storage::DatabaseStorage>) -> usize {
+        database_storage
+            .and_then(|storage| storage.get_table_stats(table_name))
+            .map_or(0, |stats| stats.page_count as usize * crate::storage::page::PAGE_SIZE)
+    }
+
+    /// Human-readable size: the largest unit that still shows at least 10 of it
+    fn size_pretty(bytes: i64) -> String {
+        const UNITS: [&str; 6] = ["bytes", "kB", "MB", "GB", "TB", "PB"];
+        let mut size = bytes;
+        let mut unit = 0;
+        while size.abs() >= 10 * 1024 && unit < UNITS.len() - 1 {
+            size /= 1024;
+            unit += 1;
+        }
+        format!("{size} {}", UNITS[unit])
+    }
+
     /// Estimate table size from schema (when no actual data available)
     fn estimate_size_from_schema(table: &crate::core::Table) -> usize {
         // Assume average of 100 rows per table
@@ -128,6 +254,7 @@ impl SystemFunctions {
                 crate::core::DataType::Boolean => 1,
                 crate::core::DataType::SmallInt => 2,
                 crate::core::DataType::Integer => 4,
+                crate::core::DataType::BigInt => 8,
                 crate::core::DataType::Serial => 4,
                 crate::core::DataType::BigSerial => 8,
                 crate::core::DataType::Real => 4,
@@ -152,7 +279,7 @@ impl SystemFunctions {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{Column, DataType, Table};
+    use crate::core::{Collation, Column, DataType, Table};
 
     #[test]
     fn test_is_system_function() {
@@ -191,6 +318,16 @@ mod tests {
         assert_eq!(result, "rustdb");
     }
 
+    #[test]
+    fn test_current_setting() {
+        let db = Database::new("test".to_string());
+        let setting = |name: &str| SystemFunctions::evaluate("current_setting", &[name.to_string()], &db, None);
+        assert_eq!(setting("TimeZone").unwrap(), "UTC");
+        let zone = Zone::parse("-7").unwrap();
+        assert_eq!(zone.scope(|| setting("timezone")).unwrap(), "-7");
+        assert!(matches!(setting("work_mem"), Err(DatabaseError::UnrecognizedParameter(name)) if name == "work_mem"));
+    }
+
     #[test]
     fn test_pg_backend_pid() {
         let db = Database::new("test".to_string());
@@ -212,6 +349,7 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "name".to_string(),
@@ -220,6 +358,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -251,6 +390,7 @@ mod tests {
                     primary_key: true,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
                 Column {
                     name: "name".to_string(),
@@ -259,6 +399,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ],
         );
@@ -266,4 +407,57 @@ mod tests {
         let size = SystemFunctions::estimate_row_size(&table);
         assert_eq!(size, 4 + 100 + 24); // int + varchar(100) + overhead
     }
+
+    #[test]
+    fn test_size_functions_count_pages() {
+        use crate::executor::{run_sql, QueryResult};
+        use crate::storage::DatabaseStorage;
+        use crate::transaction::GlobalTransactionManager;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("shop".to_string());
+        let mut exec = |db: &mut Database, sql: &str| {
+            run_sql(db, &mut storage, &tx_manager, sql).unwrap()
+        };
+
+        exec(&mut db, "CREATE TABLE items (id INTEGER, name TEXT)");
+        exec(&mut db, "CREATE TABLE empty (id INTEGER)");
+        exec(&mut db, "CREATE INDEX items_id ON items (id)");
+        for id in 0..500 {
+            exec(&mut db, &format!("INSERT INTO items VALUES ({id}, 'item number {id}')"));
+        }
+
+        let QueryResult::Rows(rows, _) = exec(
+            &mut db,
+            "SELECT pg_relation_size('items'), pg_table_size('items'), pg_relation_size('empty'), \
+             pg_relation_size('items_id'), pg_database_size('shop'), pg_database_size(current_database())",
+        ) else {
+            panic!("Expected Rows result");
+        };
+        let sizes: Vec<usize> = rows[0].iter().map(|size| size.parse().unwrap()).collect();
+        assert!(sizes[0] > 8192 && sizes[0].is_multiple_of(8192));
+        assert_eq!(sizes[1], sizes[0]);
+        assert_eq!(&sizes[2..], [0, 0, sizes[0], sizes[0]]);
+
+        // Sizes are numbers, so monitoring queries can compute with them
+        let QueryResult::Rows(rows, _) = exec(&mut db, "SELECT pg_relation_size('items') / 8192, pg_size_pretty(pg_database_size())") else {
+            panic!("Expected Rows result");
+        };
+        assert_eq!(rows[0][0], (sizes[0] / 8192).to_string());
+        assert_eq!(rows[0][1], format!("{} kB", sizes[0] / 1024));
+
+        let other = run_sql(&mut db, &mut storage, &tx_manager, "SELECT pg_database_size('other')");
+        assert!(matches!(other, Err(DatabaseError::DatabaseNotFound(_))));
+    }
+
+    #[test]
+    fn test_size_pretty() {
+        assert_eq!(SystemFunctions::size_pretty(8192), "8192 bytes");
+        assert_eq!(SystemFunctions::size_pretty(10 * 1024), "10 kB");
+        assert_eq!(SystemFunctions::size_pretty(50 * 1024 * 1024), "50 MB");
+        assert_eq!(SystemFunctions::size_pretty(-20_000), "-19 kB");
+    }
 }
@@ -0,0 +1,318 @@
+/// Session-local temporary tables (v2.7.0)
+///
+/// `CREATE TEMP TABLE` creates a table with in-memory pages and no WAL records.
+/// Between statements the owning session keeps the table (schema, metadata,
+/// indexes and pages) in its `TempTables`. While one of its statements runs,
+/// the tables are attached to the database under their plain names, shadowing
+/// permanent tables with the same name. Other sessions and on-disk snapshots
+/// never see them, and dropping the session frees them.
+use std::collections::HashMap;
+use crate::index::Index;
+use crate::parser::{OnCommitAction, Statement};
+use crate::storage::{DatabaseStorage, PagedTable};
+use crate::types::{Database, DatabaseError, Table, TableMetadata};
+use super::dispatcher_executor::QueryResult;
+
+/// One table taken out of the database maps
+struct DetachedTable {
+    name: String,
+    table: Table,
+    metadata: Option<TableMetadata>,
+    indexes: Vec<Index>,
+    pages: Option<(u32, PagedTable)>,
+}
+
+impl DetachedTable {
+    /// Remove table `name` from the database and page storage
+    fn take(db: &mut Database, storage: &mut DatabaseStorage, name: &str) -> Option<Self> {
+        let table = db.tables.remove(name)?;
+        let metadata = db.table_metadata.remove(name);
+        let indexes = Self::take_indexes(db, name);
+        let pages = storage.detach_table(name);
+
+        Some(Self {
+            name: name.to_string(),
+            table,
+            metadata,
+            indexes,
+            pages,
+        })
+    }
+
+    fn take_indexes(db: &mut Database, table_name: &str) -> Vec<Index> {
+        let names: Vec<String> = db
+            .indexes
+            .iter()
+            .filter(|(_, index)| index.table_name() == table_name)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.iter().filter_map(|name| db.indexes.remove(name)).collect()
+    }
+
+    /// Put the table back into the database and page storage
+    fn restore(self, db: &mut Database, storage: &mut DatabaseStorage) {
+        if let Some(metadata) = self.metadata {
+            db.table_metadata.insert(self.name.clone(), metadata);
+        }
+        for index in self.indexes {
+            db.indexes.insert(index.name().to_string(), index);
+        }
+        if let Some(pages) = self.pages {
+            storage.attach_table(self.name.clone(), pages);
+        }
+        db.tables.insert(self.name, self.table);
+    }
+
+    /// Remove all rows (and index entries)
+    fn truncate(&mut self) -> Result<(), DatabaseError> {
+        if let Some((_, pages)) = &mut self.pages {
+            pages.truncate()?;
+        }
+        for index in &mut self.indexes {
+            index.clear();
+        }
+        Ok(())
+    }
+}
+
+struct TempTable {
+    on_commit: OnCommitAction,
+    /// `None` only while attached to the database
+    detached: Option<DetachedTable>,
+}
+
+/// Temporary tables owned by one session
+#[derive(Default)]
+pub struct TempTables {
+    tables: HashMap<String, TempTable>,
+    /// Permanent tables hidden while temporary tables are attached
+    shadowed: Vec<DetachedTable>,
+}
+
+impl TempTables {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Is `name` a temporary table of this session?
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+    }
+
+    /// Does the statement create, read or write a temporary table?
+    ///
+    /// Such statements must run without WAL logging and skip table-level
+    /// permission checks (the session owns the table).
+    #[must_use]
+    pub fn touches(&self, stmt: &Statement) -> bool {
+        match stmt {
+            Statement::CreateTempTable { .. } => true,
+            Statement::Insert { table, .. } | Statement::InsertMany { table, .. } | Statement::Update { table, .. } => {
+                self.contains(table)
+            }
+            Statement::Delete { from, .. } | Statement::Select { from, .. } => self.contains(from),
+            Statement::DropTable { name, .. } | Statement::AlterTable { name, .. } => self.contains(name),
+            Statement::CreateIndex { table, .. } => self.contains(table),
+            _ => false,
+        }
+    }
+
+    /// Run `execute` with this session's temporary tables attached to `db`
+    ///
+    /// A successful `CREATE TEMP TABLE` is registered here; temporary tables that
+    /// the statement dropped are released.
+    pub fn execute<F>(
+        &mut self,
+        db: &mut Database,
+        storage: &mut DatabaseStorage,
+        stmt: Statement,
+        execute: F,
+    ) -> Result<QueryResult, DatabaseError>
+    where
+        F: FnOnce(&mut Database, Statement, &mut DatabaseStorage) -> Result<QueryResult, DatabaseError>,
+    {
+        let created = match &stmt {
+            Statement::CreateTempTable { name, on_commit, .. } => Some((name.clone(), *on_commit)),
+            _ => None,
+        };
+
+        if self.tables.is_empty() && created.is_none() {
+            return execute(db, stmt, storage);
+        }
+
+        self.attach(db, storage, created.as_ref().map(|(name, _)| name.as_str()));
+        let result = execute(db, stmt, storage);
+
+        if result.is_ok()
+            && let Some((name, on_commit)) = created
+        {
+            self.tables.insert(name, TempTable { on_commit, detached: None });
+        }
+        self.detach(db, storage);
+
+        result
+    }
+
+    /// Apply ON COMMIT actions at the end of a transaction
+    pub fn end_transaction(&mut self) -> Result<(), DatabaseError> {
+        let mut dropped = Vec::new();
+
+        for (name, temp) in &mut self.tables {
+            match temp.on_commit {
+                OnCommitAction::PreserveRows => {}
+                OnCommitAction::DeleteRows => {
+                    if let Some(detached) = &mut temp.detached {
+                        detached.truncate()?;
+                    }
+                }
+                OnCommitAction::Drop => dropped.push(name.clone()),
+            }
+        }
+
+        for name in dropped {
+            if let Some(TempTable { detached: Some(mut detached), .. }) = self.tables.remove(&name) {
+                detached.truncate()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn attach(&mut self, db: &mut Database, storage: &mut DatabaseStorage, creating: Option<&str>) {
+        let names = self.tables.keys().map(String::as_str).chain(creating);
+        let shadowed: Vec<String> = names
+            .filter(|name| db.tables.contains_key(*name))
+            .map(str::to_string)
+            .collect();
+        for name in shadowed {
+            if let Some(permanent) = DetachedTable::take(db, storage, &name) {
+                self.shadowed.push(permanent);
+            }
+        }
+
+        for temp in self.tables.values_mut() {
+            if let Some(detached) = temp.detached.take() {
+                detached.restore(db, storage);
+            }
+        }
+    }
+
+    fn detach(&mut self, db: &mut Database, storage: &mut DatabaseStorage) {
+        for (name, temp) in &mut self.tables {
+            temp.detached = DetachedTable::take(db, storage, name);
+
+            if temp.detached.is_none() {
+                // Dropped by the statement: release what is left behind
+                DetachedTable::take_indexes(db, name);
+                if let Some((_, mut pages)) = storage.detach_table(name) {
+                    pages.truncate().ok();
+                }
+            }
+        }
+        self.tables.retain(|_, temp| temp.detached.is_some());
+
+        for permanent in self.shadowed.drain(..) {
+            permanent.restore(db, storage);
+        }
+    }
+}
+
+impl Drop for TempTables {
+    /// Session ended: free the in-memory pages of every temporary table
+    fn drop(&mut self) {
+        for temp in self.tables.values_mut() {
+            if let Some(detached) = &mut temp.detached {
+                detached.truncate().ok();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::QueryExecutor;
+    use crate::parser::parse_statement;
+    use crate::transaction::GlobalTransactionManager;
+    use tempfile::TempDir;
+
+    fn run(
+        temp: &mut TempTables,
+        db: &mut Database,
+        storage: &mut DatabaseStorage,
+        tx_manager: &GlobalTransactionManager,
+        sql: &str,
+    ) -> Result<QueryResult, DatabaseError> {
+        let stmt = parse_statement(sql).unwrap();
+        temp.execute(db, storage, stmt, |db, stmt, storage| {
+            QueryExecutor::execute(db, stmt, None, tx_manager, storage, None)
+        })
+    }
+
+    fn count(result: QueryResult) -> usize {
+        match result {
+            QueryResult::Rows(rows, _) => rows.len(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_temp_table_is_session_local() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut session_a = TempTables::new();
+        let mut session_b = TempTables::new();
+
+        run(&mut session_a, &mut db, &mut storage, &tx_manager, "CREATE TABLE items (id INTEGER)").unwrap();
+        run(&mut session_a, &mut db, &mut storage, &tx_manager, "INSERT INTO items (id) VALUES (1)").unwrap();
+
+        // Temporary table shadows the permanent one inside session A only
+        run(&mut session_a, &mut db, &mut storage, &tx_manager, "CREATE TEMP TABLE items (id INTEGER)").unwrap();
+        run(&mut session_a, &mut db, &mut storage, &tx_manager, "INSERT INTO items (id) VALUES (10)").unwrap();
+        run(&mut session_a, &mut db, &mut storage, &tx_manager, "INSERT INTO items (id) VALUES (11)").unwrap();
+
+        let a = run(&mut session_a, &mut db, &mut storage, &tx_manager, "SELECT * FROM items").unwrap();
+        assert_eq!(count(a), 2);
+        let b = run(&mut session_b, &mut db, &mut storage, &tx_manager, "SELECT * FROM items").unwrap();
+        assert_eq!(count(b), 1);
+
+        // Nothing temporary is left in the shared database between statements
+        assert_eq!(db.tables.len(), 1);
+        assert!(session_a.contains("items"));
+
+        // Dropping the temporary table uncovers the permanent one again
+        run(&mut session_a, &mut db, &mut storage, &tx_manager, "DROP TABLE items").unwrap();
+        assert!(!session_a.contains("items"));
+        let a = run(&mut session_a, &mut db, &mut storage, &tx_manager, "SELECT * FROM items").unwrap();
+        assert_eq!(count(a), 1);
+    }
+
+    #[test]
+    fn test_temp_table_on_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut session = TempTables::new();
+
+        run(&mut session, &mut db, &mut storage, &tx_manager, "CREATE TEMP TABLE kept (id INTEGER)").unwrap();
+        run(&mut session, &mut db, &mut storage, &tx_manager, "CREATE TEMP TABLE cleared (id INTEGER) ON COMMIT DELETE ROWS").unwrap();
+        run(&mut session, &mut db, &mut storage, &tx_manager, "CREATE TEMP TABLE gone (id INTEGER) ON COMMIT DROP").unwrap();
+        for table in ["kept", "cleared", "gone"] {
+            let sql = format!("INSERT INTO {table} (id) VALUES (1)");
+            run(&mut session, &mut db, &mut storage, &tx_manager, &sql).unwrap();
+        }
+
+        session.end_transaction().unwrap();
+
+        let kept = run(&mut session, &mut db, &mut storage, &tx_manager, "SELECT * FROM kept").unwrap();
+        assert_eq!(count(kept), 1);
+        let cleared = run(&mut session, &mut db, &mut storage, &tx_manager, "SELECT * FROM cleared").unwrap();
+        assert_eq!(count(cleared), 0);
+        assert!(!session.contains("gone"));
+        assert!(run(&mut session, &mut db, &mut storage, &tx_manager, "SELECT * FROM gone").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+/// Native (Rust) user-defined functions (v2.7.0)
+///
+/// Embedders register functions at runtime instead of patching the parser:
+///
+/// ```ignore
+/// QueryExecutor::register_scalar_function("double_it", 1, |args: &[Value]| match &args[0] {
+///     Value::Integer(n) => Ok(Value::Integer(n * 2)),
+///     other => Ok(other.clone()),
+/// });
+/// ```
+///
+/// Any `name(args)` call in a SELECT list is parsed as a function expression, so
+/// registered names work immediately. The registry is process-wide and is
+/// consulted by the expression evaluator after built-in system functions and
+/// before stored SQL functions. Aggregates are used like built-in ones:
+/// `SELECT my_agg(col) FROM t [GROUP BY ...]`.
+use crate::parser::Expression;
+use crate::types::{DatabaseError, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Scalar function body
+pub type ScalarFunction = Arc<dyn Fn(&[Value]) -> Result<Value, DatabaseError> + Send + Sync>;
+
+/// Running state of one aggregate evaluation (one per group)
+pub trait Accumulator {
+    /// Feed the next input value (NULLs included)
+    fn update(&mut self, value: &Value) -> Result<(), DatabaseError>;
+    /// Produce the aggregate result
+    fn finish(&mut self) -> Result<Value, DatabaseError>;
+}
+
+/// Creates a fresh accumulator for every group
+pub type AccumulatorFactory = Arc<dyn Fn() -> Box<dyn Accumulator> + Send + Sync>;
+
+#[derive(Clone)]
+struct RegisteredScalar {
+    arity: usize,
+    function: ScalarFunction,
+}
+
+/// Registry of native functions, keyed by lowercase name
+#[derive(Default)]
+pub struct FunctionRegistry {
+    scalars: HashMap<String, RegisteredScalar>,
+    aggregates: HashMap<String, AccumulatorFactory>,
+}
+
+impl FunctionRegistry {
+    fn global() -> &'static RwLock<Self> {
+        static REGISTRY: OnceLock<RwLock<FunctionRegistry>> = OnceLock::new();
+        REGISTRY.get_or_init(|| RwLock::new(Self::default()))
+    }
+
+    /// Register (or replace) a scalar function taking exactly `arity` arguments
+    pub fn register_scalar<F>(name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, DatabaseError> + Send + Sync + 'static,
+    {
+        let mut registry = Self::global().write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        registry.scalars.insert(
+            name.to_lowercase(),
+            RegisteredScalar {
+                arity,
+                function: Arc::new(function),
+            },
+        );
+    }
+
+    /// Register (or replace) an aggregate function taking one argument
+    pub fn register_aggregate<F>(name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn Accumulator> + Send + Sync + 'static,
+    {
+        let mut registry = Self::global().write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        registry.aggregates.insert(name.to_lowercase(), Arc::new(factory));
+    }
+
+    /// Remove a scalar or aggregate function; returns true if it existed
+    pub fn unregister(name: &str) -> bool {
+        let name = name.to_lowercase();
+        let mut registry = Self::global().write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let scalar = registry.scalars.remove(&name).is_some();
+        let aggregate = registry.aggregates.remove(&name).is_some();
+        scalar || aggregate
+    }
+
+    /// Is `name` a registered native function (scalar or aggregate)?
+    #[must_use]
+    pub fn contains(name: &str) -> bool {
+        let name = name.to_lowercase();
+        let registry = Self::global().read().unwrap_or_else(std::sync::PoisonError::into_inner);
+        registry.scalars.contains_key(&name) || registry.aggregates.contains_key(&name)
+    }
+
+    /// Is `name` a registered aggregate?
+    #[must_use]
+    pub fn is_aggregate(name: &str) -> bool {
+        let registry = Self::global().read().unwrap_or_else(std::sync::PoisonError::into_inner);
+        registry.aggregates.contains_key(&name.to_lowercase())
+    }
+
+    /// Call a registered scalar function; `None` if no such function exists
+    pub fn call_scalar(name: &str, args: &[Value]) -> Option<Result<Value, DatabaseError>> {
+        // Clone the entry so the lock is not held while user code runs
+        let entry = {
+            let registry = Self::global().read().unwrap_or_else(std::sync::PoisonError::into_inner);
+            registry.scalars.get(&name.to_lowercase()).cloned()
+        }?;
+
+        if entry.arity != args.len() {
+            return Some(Err(DatabaseError::ParseError(format!(
+                "Function '{name}' expects {} argument(s), got {}",
+                entry.arity,
+                args.len()
+            ))));
+        }
+        Some((entry.function)(args))
+    }
+
+    /// New accumulator for a registered aggregate; `None` if no such aggregate exists
+    #[must_use]
+    pub fn accumulator(name: &str) -> Option<Box<dyn Accumulator>> {
+        let factory = {
+            let registry = Self::global().read().unwrap_or_else(std::sync::PoisonError::into_inner);
+            registry.aggregates.get(&name.to_lowercase()).cloned()
+        }?;
+        Some(factory())
+    }
+
+    /// If `expr` is a call of a registered aggregate, return its name and argument
+    #[must_use]
+    pub fn aggregate_call(expr: &Expression) -> Option<(&str, &Expression)> {
+        match expr {
+            Expression::Function { name, args } if args.len() == 1 && Self::is_aggregate(name) => {
+                Some((name.as_str(), &args[0]))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryExecutor, QueryResult};
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::{Database, DatabaseError};
+
+    struct Product(i64);
+
+    impl Accumulator for Product {
+        fn update(&mut self, value: &Value) -> Result<(), DatabaseError> {
+            if let Value::Integer(n) = value {
+                self.0 *= n;
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<Value, DatabaseError> {
+            Ok(Value::Integer(self.0))
+        }
+    }
+
+    #[test]
+    fn test_scalar_registration() {
+        FunctionRegistry::register_scalar("udf_test_negate", 1, |args| match &args[0] {
+            Value::Integer(n) => Ok(Value::Integer(-n)),
+            _ => Err(DatabaseError::TypeMismatch),
+        });
+
+        let result = FunctionRegistry::call_scalar("UDF_TEST_NEGATE", &[Value::Integer(5)]);
+        assert_eq!(result.unwrap().unwrap(), Value::Integer(-5));
+        assert!(FunctionRegistry::call_scalar("udf_test_negate", &[]).unwrap().is_err());
+
+        assert!(FunctionRegistry::unregister("udf_test_negate"));
+        assert!(FunctionRegistry::call_scalar("udf_test_negate", &[Value::Integer(5)]).is_none());
+    }
+
+    #[test]
+    fn test_aggregate_registration() {
+        FunctionRegistry::register_aggregate("udf_test_product", || Box::new(Product(1)));
+
+        let call = Expression::Function {
+            name: "udf_test_product".to_string(),
+            args: vec![Expression::Column("n".to_string())],
+        };
+        assert!(FunctionRegistry::aggregate_call(&call).is_some());
+
+        let mut acc = FunctionRegistry::accumulator("udf_test_product").unwrap();
+        for n in 1..=4 {
+            acc.update(&Value::Integer(n)).unwrap();
+        }
+        assert_eq!(acc.finish().unwrap(), Value::Integer(24));
+    }
+
+    #[test]
+    fn test_native_udf_scalar_and_aggregate() {
+        // v2.7.0: functions registered from Rust are callable from SQL
+        struct Longest(String);
+
+        impl Accumulator for Longest {
+            fn update(&mut self, value: &Value) -> Result<(), DatabaseError> {
+                let text = value.to_string();
+                if text.len() > self.0.len() {
+                    self.0 = text;
+                }
+                Ok(())
+            }
+
+            fn finish(&mut self) -> Result<Value, DatabaseError> {
+                Ok(Value::Text(self.0.clone()))
+            }
+        }
+
+        QueryExecutor::register_scalar_function("dispatch_test_twice", 1, |args| match &args[0] {
+            Value::SmallInt(n) => Ok(Value::Integer(i64::from(*n) * 2)),
+            Value::Integer(n) => Ok(Value::Integer(n * 2)),
+            other => Ok(other.clone()),
+        });
+        QueryExecutor::register_aggregate_function("dispatch_test_longest", || Box::new(Longest(String::new())));
+
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (1, 'Alice', 30), (2, 'Bob', 25), (3, 'Charlie', 35)").unwrap();
+
+        let mut rows = |sql: &str| match run_sql(&mut db, &mut storage, &tx_manager, sql).unwrap() {
+            QueryResult::Rows(rows, _) => rows,
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        assert_eq!(rows("SELECT dispatch_test_twice(21)"), vec![vec!["42".to_string()]]);
+        assert_eq!(
+            rows("SELECT dispatch_test_twice(age) FROM users WHERE age < 26"),
+            vec![vec!["50".to_string()]]
+        );
+        assert_eq!(
+            rows("SELECT dispatch_test_longest(name) AS longest FROM users"),
+            vec![vec!["Charlie".to_string()]]
+        );
+    }
+}
@@ -28,6 +28,7 @@ impl VacuumExecutor {
         database_storage: &mut crate::storage::DatabaseStorage,
     ) -> Result<QueryResult, DatabaseError> {
         // Get cleanup horizon - only tuples invisible to all transactions can be removed
+        // (v2.7.0: including those still seen by the snapshot of an open transaction)
         let oldest_tx = tx_manager.get_oldest_active_tx();
 
         // Determine which tables to vacuum
@@ -42,20 +43,18 @@ impl VacuumExecutor {
             db.tables.keys().cloned().collect()
         };
 
-        // Vacuum each table
-        let mut total_removed = 0;
+        // Vacuum each table (v2.7.0: removed tuples are counted in pg_stat_user_tables)
         for table_name in &tables_to_vacuum {
             let removed = Self::vacuum_table(
                 table_name,
                 oldest_tx,
                 database_storage,
             )?;
-            total_removed += removed;
+            db.activity.count_vacuum(table_name, removed);  // v2.7.0
         }
+        database_storage.note_vacuum(oldest_tx);  // v2.7.0: see pg_undo_transaction
 
-        Ok(QueryResult::Success(format!(
-            "VACUUM complete. Removed {total_removed} dead tuples."
-        )))
+        Ok(QueryResult::Success("VACUUM".to_string()))
     }
 
     /// Vacuum single table using `PagedTable`
@@ -76,7 +75,7 @@ impl VacuumExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{Table, Column, DataType, Value, Row};
+    use crate::core::{Table, Column, Collation, DataType, Value, Row};
     use crate::storage::DatabaseStorage;
     use tempfile::tempdir;
 
@@ -89,7 +88,8 @@ mod tests {
 
         // Advance tx_manager past the dead tuple xmax values
         for _ in 0..200 {
-            let _ = tx_manager.begin_transaction();
+            let (tx_id, _) = tx_manager.begin_transaction();
+            tx_manager.commit_transaction(tx_id);
         }
 
         // Create table
@@ -101,6 +101,7 @@ mod tests {
                 primary_key: true,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ]);
         db.create_table(table.clone()).unwrap();
@@ -112,16 +113,19 @@ mod tests {
             values: vec![Value::Integer(1)],
             xmin: 100,
             xmax: None, // Alive
+            ctid: None,
         }).unwrap();
         paged_table.insert(Row {
             values: vec![Value::Integer(2)],
             xmin: 100,
             xmax: Some(150), // Dead (tx_manager is at 201, so 150 < 201)
+            ctid: None,
         }).unwrap();
         paged_table.insert(Row {
             values: vec![Value::Integer(3)],
             xmin: 100,
             xmax: Some(160), // Dead (160 < 201)
+            ctid: None,
         }).unwrap();
 
         let before = paged_table.get_all_rows().unwrap().len();
@@ -156,6 +160,7 @@ mod tests {
                 primary_key: false,
                 unique: false,
                 foreign_key: None,
+                collation: Collation::C,
             },
         ]);
         db.create_table(table.clone()).unwrap();
@@ -167,11 +172,13 @@ mod tests {
             values: vec![Value::Integer(1)],
             xmin: 100,
             xmax: None,
+            ctid: None,
         }).unwrap();
         paged_table.insert(Row {
             values: vec![Value::Integer(2)],
             xmin: 100,
             xmax: None,
+            ctid: None,
         }).unwrap();
 
         // Vacuum should not remove anything
@@ -194,7 +201,8 @@ mod tests {
 
         // Advance tx_manager past dead tuples
         for _ in 0..200 {
-            let _ = tx_manager.begin_transaction();
+            let (tx_id, _) = tx_manager.begin_transaction();
+            tx_manager.commit_transaction(tx_id);
         }
 
         // Create two tables
@@ -207,6 +215,7 @@ mod tests {
                     primary_key: false,
                     unique: false,
                     foreign_key: None,
+                    collation: Collation::C,
                 },
             ]);
             db.create_table(table.clone()).unwrap();
@@ -218,11 +227,13 @@ mod tests {
                 values: vec![Value::Integer(1)],
                 xmin: 100,
                 xmax: None,
+                ctid: None,
             }).unwrap();
             paged_table.insert(Row {
                 values: vec![Value::Integer(2)],
                 xmin: 100,
                 xmax: Some(150), // Dead (150 < 201)
+                ctid: None,
             }).unwrap();
         }
 
@@ -239,4 +250,39 @@ mod tests {
             assert_eq!(alive_rows.len(), 1);
         }
     }
+
+    #[test]
+    fn test_vacuum_keeps_versions_open_transactions_see() {
+        let mut db = Database::new("test".to_string());
+        let tx_manager = GlobalTransactionManager::new();
+        let temp_dir = tempdir().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path().to_str().unwrap(), 32).unwrap();
+
+        db.create_table(Table::new("users".to_string(), vec![Column {
+            name: "id".to_string(),
+            data_type: DataType::Integer,
+            nullable: false,
+            primary_key: false,
+            unique: false,
+            foreign_key: None,
+            collation: Collation::C,
+        }])).unwrap();
+        storage.create_table("users".to_string()).unwrap();
+
+        // A long-running transaction starts, then another one deletes the row and commits
+        let (reader, snapshot) = tx_manager.begin_transaction();
+        let (deleter, _) = tx_manager.begin_transaction();
+        tx_manager.commit_transaction(deleter);
+        let row = Row { values: vec![Value::Integer(1)], xmin: 0, xmax: Some(deleter), ctid: None };
+        assert!(row.is_visible_to_snapshot(&snapshot));
+        storage.get_paged_table_mut("users").unwrap().insert(row).unwrap();
+
+        VacuumExecutor::vacuum(&mut db, None, &tx_manager, &mut storage).unwrap();
+        assert_eq!(storage.get_paged_table_mut("users").unwrap().get_all_rows().unwrap().len(), 1);
+
+        // Once it ends nobody can see the row any more
+        tx_manager.commit_transaction(reader);
+        VacuumExecutor::vacuum(&mut db, None, &tx_manager, &mut storage).unwrap();
+        assert!(storage.get_paged_table_mut("users").unwrap().get_all_rows().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,729 @@
+/// Batch-at-a-time evaluation of WHERE and SELECT expressions (v2.7.0)
+///
+/// An opt-in mode for analytical scans (`vectorized_execution`). A sequential
+/// scan hands over `BATCH_SIZE` rows at a time; the columns the query looks at
+/// are unpacked once per batch into typed arrays (`i64`, `f64`, `bool`,
+/// strings) next to a NULL mask, and the compiled WHERE clause and SELECT
+/// arithmetic run as plain loops over those arrays instead of matching on a
+/// `Value` for every row.
+///
+/// Only comparisons of INTEGER, REAL, BOOLEAN and C-collated TEXT columns with
+/// literals (plus AND / OR / NOT, BETWEEN, IN, IS NULL) and integer / real
+/// arithmetic compile. Anything else keeps the query row-at-a-time, and a
+/// batch holding a value of an unexpected type is evaluated row by row, so
+/// results are the same in both modes.
+use std::cmp::Ordering;
+use rust_decimal::prelude::ToPrimitive;
+use crate::parser::{BinaryOperator, CompareOperator, Condition, Expression};
+use crate::types::{Collation, Column, DataType, DatabaseError, Row, Value};
+use super::conditions::ConditionEvaluator;
+use super::functions::FunctionExecutor;
+
+/// Rows evaluated together
+pub const BATCH_SIZE: usize = 1024;
+
+/// Array type a column is unpacked into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Float,
+    Bool,
+    Text,
+}
+
+impl Kind {
+    fn of(column: &Column) -> Option<Self> {
+        match column.data_type {
+            DataType::SmallInt | DataType::Integer | DataType::BigInt | DataType::Serial | DataType::BigSerial => Some(Self::Int),
+            DataType::Real => Some(Self::Float),
+            DataType::Boolean => Some(Self::Bool),
+            DataType::Text | DataType::Varchar { .. } if column.collation == Collation::C => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Vector {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+    Text(Vec<String>),
+}
+
+/// One column of a batch; a NULL has `nulls[i]` set and a default value
+#[derive(Debug, Clone, PartialEq)]
+struct ColumnVector {
+    data: Vector,
+    nulls: Vec<bool>,
+}
+
+impl ColumnVector {
+    /// Unpack column `idx` of `rows`; `None` if a value is not of `kind`
+    fn load(kind: Kind, rows: &[Row], idx: usize) -> Option<Self> {
+        let mut nulls = Vec::with_capacity(rows.len());
+        macro_rules! unpack {
+            ($variant:ident, $default:expr, $($pattern:pat => $value:expr),+) => {{
+                let mut data = Vec::with_capacity(rows.len());
+                for row in rows {
+                    match row.values.get(idx)? {
+                        Value::Null => {
+                            nulls.push(true);
+                            data.push($default);
+                        }
+                        $($pattern => {
+                            nulls.push(false);
+                            data.push($value);
+                        })+
+                        _ => return None,
+                    }
+                }
+                Vector::$variant(data)
+            }};
+        }
+        let data = match kind {
+            Kind::Int => unpack!(Int, 0, Value::Integer(n) => *n, Value::SmallInt(n) => i64::from(*n)),
+            Kind::Float => unpack!(Float, 0.0, Value::Real(f) => *f),
+            Kind::Bool => unpack!(Bool, false, Value::Boolean(b) => *b),
+            Kind::Text => unpack!(Text, String::new(), Value::Text(s) => s.clone()),
+        };
+        Some(Self { data, nulls })
+    }
+
+    /// Keep the entries whose `keep` is set
+    fn retain(&mut self, keep: &[bool]) {
+        fn filter<T: Clone>(values: &[T], keep: &[bool]) -> Vec<T> {
+            values.iter().zip(keep).filter(|(_, keep)| **keep).map(|(value, _)| value.clone()).collect()
+        }
+        self.data = match &self.data {
+            Vector::Int(values) => Vector::Int(filter(values, keep)),
+            Vector::Float(values) => Vector::Float(filter(values, keep)),
+            Vector::Bool(values) => Vector::Bool(filter(values, keep)),
+            Vector::Text(values) => Vector::Text(filter(values, keep)),
+        };
+        self.nulls = filter(&self.nulls, keep);
+    }
+
+    fn value(&self, idx: usize) -> Value {
+        if self.nulls[idx] {
+            return Value::Null;
+        }
+        match &self.data {
+            Vector::Int(values) => Value::Integer(values[idx]),
+            Vector::Float(values) => Value::Real(values[idx]),
+            Vector::Bool(values) => Value::Boolean(values[idx]),
+            Vector::Text(values) => Value::Text(values[idx].clone()),
+        }
+    }
+}
+
+/// Three-valued result per row: `value` only counts where `known` is set
+struct Truth {
+    value: Vec<bool>,
+    known: Vec<bool>,
+}
+
+impl Truth {
+    fn constant(truth: Option<bool>, len: usize) -> Self {
+        Self { value: vec![truth.unwrap_or(false); len], known: vec![truth.is_some(); len] }
+    }
+
+    /// `false AND unknown` is false
+    fn and(mut self, other: &Self) -> Self {
+        for i in 0..self.value.len() {
+            let left = self.known[i] && !self.value[i];
+            let right = other.known[i] && !other.value[i];
+            self.known[i] = (self.known[i] && other.known[i]) || left || right;
+            self.value[i] = self.value[i] && other.value[i];
+        }
+        self
+    }
+
+    /// `true OR unknown` is true
+    fn or(mut self, other: &Self) -> Self {
+        for i in 0..self.value.len() {
+            let left = self.known[i] && self.value[i];
+            let right = other.known[i] && other.value[i];
+            self.known[i] = (self.known[i] && other.known[i]) || left || right;
+            self.value[i] = left || right;
+        }
+        self
+    }
+
+    fn not(mut self) -> Self {
+        for value in &mut self.value {
+            *value = !*value;
+        }
+        self
+    }
+
+    /// Rows the condition is true for
+    fn selected(&self) -> Vec<bool> {
+        self.value.iter().zip(&self.known).map(|(value, known)| *value && *known).collect()
+    }
+}
+
+/// A literal of the column's array type
+#[derive(Debug, Clone, PartialEq)]
+enum Scalar {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    /// `column op literal`, the column by its slot in the batch
+    Compare(usize, CompareOperator, Scalar),
+    IsNull(usize),
+    IsNotNull(usize),
+    Constant(Option<bool>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+    Not(Box<Self>),
+}
+
+impl Predicate {
+    fn eval(&self, batch: &[ColumnVector], len: usize) -> Result<Truth, DatabaseError> {
+        Ok(match self {
+            Self::Compare(slot, op, scalar) => {
+                let column = &batch[*slot];
+                let mut truth = Truth { value: Vec::with_capacity(len), known: Vec::with_capacity(len) };
+                match (&column.data, scalar) {
+                    (Vector::Int(values), Scalar::Int(literal)) => Self::compare(values, &column.nulls, *op, &mut truth, |v| Some(v.cmp(literal)))?,
+                    (Vector::Float(values), Scalar::Float(literal)) => Self::compare(values, &column.nulls, *op, &mut truth, |v| v.partial_cmp(literal))?,
+                    (Vector::Bool(values), Scalar::Bool(literal)) => Self::compare(values, &column.nulls, *op, &mut truth, |v| Some(v.cmp(literal)))?,
+                    (Vector::Text(values), Scalar::Text(literal)) => Self::compare(values, &column.nulls, *op, &mut truth, |v| Some(v.as_str().cmp(literal)))?,
+                    _ => return Err(DatabaseError::TypeMismatch),
+                }
+                truth
+            }
+            Self::IsNull(slot) => Truth { value: batch[*slot].nulls.clone(), known: vec![true; len] },
+            Self::IsNotNull(slot) => Truth { value: batch[*slot].nulls.iter().map(|null| !null).collect(), known: vec![true; len] },
+            Self::Constant(truth) => Truth::constant(*truth, len),
+            Self::And(left, right) => left.eval(batch, len)?.and(&right.eval(batch, len)?),
+            Self::Or(left, right) => left.eval(batch, len)?.or(&right.eval(batch, len)?),
+            Self::Not(inner) => inner.eval(batch, len)?.not(),
+        })
+    }
+
+    /// The comparison loop; values that cannot be ordered (NaN) are an error
+    /// for <, >, <= and >= and unequal for = and <>, as in `ConditionEvaluator`
+    fn compare<T>(
+        values: &[T],
+        nulls: &[bool],
+        op: CompareOperator,
+        truth: &mut Truth,
+        cmp: impl Fn(&T) -> Option<Ordering>,
+    ) -> Result<(), DatabaseError> {
+        for (value, null) in values.iter().zip(nulls) {
+            let result = if *null {
+                false
+            } else {
+                match (op, cmp(value)) {
+                    (CompareOperator::Equal, ordering) => ordering == Some(Ordering::Equal),
+                    (CompareOperator::NotEqual, ordering) => ordering != Some(Ordering::Equal),
+                    (_, None) => return Err(DatabaseError::TypeMismatch),
+                    (CompareOperator::Less, Some(ordering)) => ordering.is_lt(),
+                    (CompareOperator::LessOrEqual, Some(ordering)) => ordering.is_le(),
+                    (CompareOperator::Greater, Some(ordering)) => ordering.is_gt(),
+                    (CompareOperator::GreaterOrEqual, Some(ordering)) => ordering.is_ge(),
+                }
+            };
+            truth.value.push(result);
+            truth.known.push(!*null);
+        }
+        Ok(())
+    }
+}
+
+/// Integer or real arithmetic over batch columns
+#[derive(Debug, Clone, PartialEq)]
+enum Arithmetic {
+    Column(usize),
+    Int(i64),
+    Float(f64),
+    Binary(Box<Self>, BinaryOperator, Box<Self>),
+    /// Integer arithmetic whose result must fit in 32 bits (see `FunctionExecutor::is_int4`)
+    Int4(Box<Self>),
+}
+
+impl Arithmetic {
+    fn eval(&self, batch: &[ColumnVector], len: usize) -> Result<ColumnVector, DatabaseError> {
+        Ok(match self {
+            Self::Column(slot) => batch[*slot].clone(),
+            Self::Int(n) => ColumnVector { data: Vector::Int(vec![*n; len]), nulls: vec![false; len] },
+            Self::Float(f) => ColumnVector { data: Vector::Float(vec![*f; len]), nulls: vec![false; len] },
+            Self::Binary(left, op, right) => {
+                let left = left.eval(batch, len)?;
+                let right = right.eval(batch, len)?;
+                let nulls: Vec<bool> = left.nulls.iter().zip(&right.nulls).map(|(a, b)| *a || *b).collect();
+                let data = match (left.data, right.data) {
+                    (Vector::Int(a), Vector::Int(b)) => Vector::Int(Self::integers(&a, *op, &b, &nulls)?),
+                    (a, b) => Vector::Float(Self::reals(&Self::floats(a)?, *op, &Self::floats(b)?, &nulls)?),
+                };
+                ColumnVector { data, nulls }
+            }
+            Self::Int4(inner) => {
+                let result = inner.eval(batch, len)?;
+                if let Vector::Int(values) = &result.data
+                    && values.iter().zip(&result.nulls).any(|(n, null)| !null && i32::try_from(*n).is_err())
+                {
+                    return Err(DatabaseError::NumericOutOfRange("integer out of range".to_string()));
+                }
+                result
+            }
+        })
+    }
+
+    /// Checked like `FunctionExecutor::apply_operator`; NULL rows are skipped
+    fn integers(a: &[i64], op: BinaryOperator, b: &[i64], nulls: &[bool]) -> Result<Vec<i64>, DatabaseError> {
+        let mut out = Vec::with_capacity(a.len());
+        for i in 0..a.len() {
+            if nulls[i] {
+                out.push(0);
+                continue;
+            }
+            let (x, y) = (a[i], b[i]);
+            let result = match op {
+                BinaryOperator::Add => x.checked_add(y),
+                BinaryOperator::Subtract => x.checked_sub(y),
+                BinaryOperator::Multiply => x.checked_mul(y),
+                BinaryOperator::Divide | BinaryOperator::Modulo if y == 0 => return Err(DatabaseError::DivisionByZero),
+                BinaryOperator::Divide => x.checked_div(y),
+                BinaryOperator::Modulo => x.checked_rem(y),
+                BinaryOperator::Concat => return Err(DatabaseError::TypeMismatch),
+            };
+            out.push(result.ok_or_else(|| DatabaseError::NumericOutOfRange("integer out of range".to_string()))?);
+        }
+        Ok(out)
+    }
+
+    fn reals(a: &[f64], op: BinaryOperator, b: &[f64], nulls: &[bool]) -> Result<Vec<f64>, DatabaseError> {
+        let mut out = Vec::with_capacity(a.len());
+        for i in 0..a.len() {
+            if nulls[i] {
+                out.push(0.0);
+                continue;
+            }
+            let (x, y) = (a[i], b[i]);
+            let result = match op {
+                BinaryOperator::Add => x + y,
+                BinaryOperator::Subtract => x - y,
+                BinaryOperator::Multiply => x * y,
+                BinaryOperator::Divide | BinaryOperator::Modulo if y == 0.0 => return Err(DatabaseError::DivisionByZero),
+                BinaryOperator::Divide => x / y,
+                BinaryOperator::Modulo => x % y,
+                BinaryOperator::Concat => return Err(DatabaseError::TypeMismatch),
+            };
+            if result.is_infinite() && x.is_finite() && y.is_finite() {
+                return Err(DatabaseError::NumericOutOfRange("value out of range: overflow".to_string()));
+            }
+            out.push(result);
+        }
+        Ok(out)
+    }
+
+    fn floats(data: Vector) -> Result<Vec<f64>, DatabaseError> {
+        match data {
+            #[allow(clippy::cast_precision_loss)]
+            Vector::Int(values) => Ok(values.into_iter().map(|n| n as f64).collect()),
+            Vector::Float(values) => Ok(values),
+            Vector::Bool(_) | Vector::Text(_) => Err(DatabaseError::TypeMismatch),
+        }
+    }
+}
+
+/// WHERE clause and SELECT expressions of a scan, compiled for batches
+#[derive(Debug, Clone)]
+pub struct VectorPlan {
+    /// Table columns unpacked per batch, with their array types
+    slots: Vec<(usize, Kind)>,
+    predicate: Option<Predicate>,
+    expressions: Vec<Arithmetic>,
+    /// The originals, for batches that do not unpack
+    filter: Option<Condition>,
+    sources: Vec<Expression>,
+}
+
+impl VectorPlan {
+    /// Compile `filter` and `expressions`; `None` if any part does not compile
+    #[must_use]
+    pub fn compile<'a>(
+        columns: &[Column],
+        filter: Option<&Condition>,
+        expressions: impl IntoIterator<Item = &'a Expression>,
+    ) -> Option<Self> {
+        let mut plan = Self { slots: Vec::new(), predicate: None, expressions: Vec::new(), filter: filter.cloned(), sources: Vec::new() };
+        if let Some(cond) = filter {
+            plan.predicate = Some(plan.condition(columns, cond)?);
+        }
+        for expr in expressions {
+            let (arithmetic, _) = plan.arithmetic(columns, expr)?;
+            plan.expressions.push(arithmetic);
+            plan.sources.push(expr.clone());
+        }
+        Some(plan)
+    }
+
+    /// Table column positions the plan reads
+    #[must_use]
+    pub fn columns(&self) -> Vec<usize> {
+        self.slots.iter().map(|(idx, _)| *idx).collect()
+    }
+
+    /// Rows of `rows` that pass the filter, each with the values of the
+    /// expressions appended after the table columns
+    pub fn run(&self, columns: &[Column], mut rows: Vec<Row>) -> Result<Vec<Row>, DatabaseError> {
+        let Some(mut batch) = self.slots.iter().map(|(idx, kind)| ColumnVector::load(*kind, &rows, *idx)).collect::<Option<Vec<_>>>() else {
+            return self.run_rows(columns, rows);
+        };
+
+        if let Some(predicate) = &self.predicate {
+            let selected = predicate.eval(&batch, rows.len())?.selected();
+            for column in &mut batch {
+                column.retain(&selected);
+            }
+            let mut keep = selected.into_iter();
+            rows.retain(|_| keep.next().unwrap_or(false));
+        }
+
+        // Expressions only run on the rows that passed, like row-at-a-time
+        for expr in &self.expressions {
+            let result = expr.eval(&batch, rows.len())?;
+            for (idx, row) in rows.iter_mut().enumerate() {
+                row.values.push(result.value(idx));
+            }
+        }
+        Ok(rows)
+    }
+
+    fn run_rows(&self, columns: &[Column], rows: Vec<Row>) -> Result<Vec<Row>, DatabaseError> {
+        let mut out = Vec::with_capacity(rows.len());
+        for mut row in rows {
+            if let Some(cond) = &self.filter
+                && !ConditionEvaluator::evaluate_with_columns(columns, &row, cond)?
+            {
+                continue;
+            }
+            for expr in &self.sources {
+                let value = FunctionExecutor::evaluate_values(expr, columns, &row.values[..columns.len()])?;
+                row.values.push(value);
+            }
+            out.push(row);
+        }
+        Ok(out)
+    }
+
+    /// Batch slot of a column, added on first use
+    fn slot(&mut self, columns: &[Column], name: &str) -> Option<(usize, Kind)> {
+        let idx = columns.iter().position(|col| col.name == name)?;
+        let kind = Kind::of(&columns[idx])?;
+        if let Some(slot) = self.slots.iter().position(|(slot_idx, _)| *slot_idx == idx) {
+            return Some((slot, kind));
+        }
+        self.slots.push((idx, kind));
+        Some((self.slots.len() - 1, kind))
+    }
+
+    fn condition(&mut self, columns: &[Column], cond: &Condition) -> Option<Predicate> {
+        let compare = |plan: &mut Self, col: &str, op, value: &Value| plan.compare(columns, col, op, value);
+        Some(match cond {
+            Condition::Equals(col, value) => compare(self, col, CompareOperator::Equal, value)?,
+            Condition::NotEquals(col, value) => compare(self, col, CompareOperator::NotEqual, value)?,
+            Condition::GreaterThan(col, value) => compare(self, col, CompareOperator::Greater, value)?,
+            Condition::LessThan(col, value) => compare(self, col, CompareOperator::Less, value)?,
+            Condition::GreaterThanOrEqual(col, value) => compare(self, col, CompareOperator::GreaterOrEqual, value)?,
+            Condition::LessThanOrEqual(col, value) => compare(self, col, CompareOperator::LessOrEqual, value)?,
+            Condition::Between(col, low, high) => Predicate::And(
+                Box::new(compare(self, col, CompareOperator::GreaterOrEqual, low)?),
+                Box::new(compare(self, col, CompareOperator::LessOrEqual, high)?),
+            ),
+            Condition::In(col, list) => {
+                let mut predicate = Predicate::Constant(Some(false));
+                for value in list {
+                    predicate = Predicate::Or(Box::new(predicate), Box::new(compare(self, col, CompareOperator::Equal, value)?));
+                }
+                predicate
+            }
+            Condition::IsNull(col) => Predicate::IsNull(self.slot(columns, col)?.0),
+            Condition::IsNotNull(col) => Predicate::IsNotNull(self.slot(columns, col)?.0),
+            Condition::And(left, right) => {
+                Predicate::And(Box::new(self.condition(columns, left)?), Box::new(self.condition(columns, right)?))
+            }
+            Condition::Or(left, right) => {
+                Predicate::Or(Box::new(self.condition(columns, left)?), Box::new(self.condition(columns, right)?))
+            }
+            Condition::Not(inner) => Predicate::Not(Box::new(self.condition(columns, inner)?)),
+            Condition::Compare(Expression::Column(col), op, Expression::Literal(value)) => compare(self, col, *op, value)?,
+            Condition::Compare(Expression::Literal(value), op, Expression::Column(col)) => {
+                let flipped = match op {
+                    CompareOperator::Less => CompareOperator::Greater,
+                    CompareOperator::LessOrEqual => CompareOperator::GreaterOrEqual,
+                    CompareOperator::Greater => CompareOperator::Less,
+                    CompareOperator::GreaterOrEqual => CompareOperator::LessOrEqual,
+                    other => *other,
+                };
+                compare(self, col, flipped, value)?
+            }
+            _ => return None,
+        })
+    }
+
+    /// `column op literal` with a literal of the column's array type; a NULL literal is UNKNOWN
+    fn compare(&mut self, columns: &[Column], col: &str, op: CompareOperator, value: &Value) -> Option<Predicate> {
+        let (slot, kind) = self.slot(columns, col)?;
+        let scalar = match (kind, value) {
+            (_, Value::Null) => return Some(Predicate::Constant(None)),
+            (Kind::Int, Value::Integer(n)) => Scalar::Int(*n),
+            (Kind::Int, Value::SmallInt(n)) => Scalar::Int(i64::from(*n)),
+            (Kind::Float, Value::Real(f)) => Scalar::Float(*f),
+            (Kind::Float, Value::Numeric(n)) => Scalar::Float(n.to_f64()?),
+            #[allow(clippy::cast_precision_loss)]
+            (Kind::Float, Value::Integer(n)) => Scalar::Float(*n as f64),
+            (Kind::Float, Value::SmallInt(n)) => Scalar::Float(f64::from(*n)),
+            (Kind::Bool, Value::Boolean(b)) => Scalar::Bool(*b),
+            (Kind::Text, Value::Text(s)) => Scalar::Text(s.clone()),
+            _ => return None,
+        };
+        Some(Predicate::Compare(slot, op, scalar))
+    }
+
+    fn arithmetic(&mut self, columns: &[Column], expr: &Expression) -> Option<(Arithmetic, Kind)> {
+        match expr {
+            Expression::Literal(Value::Integer(n)) => Some((Arithmetic::Int(*n), Kind::Int)),
+            Expression::Literal(Value::SmallInt(n)) => Some((Arithmetic::Int(i64::from(*n)), Kind::Int)),
+            Expression::Literal(Value::Real(f)) => Some((Arithmetic::Float(*f), Kind::Float)),
+            Expression::Column(name) => {
+                let (slot, kind) = self.slot(columns, name)?;
+                matches!(kind, Kind::Int | Kind::Float).then_some((Arithmetic::Column(slot), kind))
+            }
+            Expression::Binary { left, op, right } if *op != BinaryOperator::Concat => {
+                let (left, left_kind) = self.arithmetic(columns, left)?;
+                let (right, right_kind) = self.arithmetic(columns, right)?;
+                let kind = if left_kind == Kind::Int && right_kind == Kind::Int { Kind::Int } else { Kind::Float };
+                let binary = Arithmetic::Binary(Box::new(left), *op, Box::new(right));
+                if FunctionExecutor::is_int4(expr, columns) {
+                    Some((Arithmetic::Int4(Box::new(binary)), kind))
+                } else {
+                    Some((binary, kind))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Rows of a scan run through a `VectorPlan` one batch at a time
+pub struct VectorScan<'a, I> {
+    rows: I,
+    plan: &'a VectorPlan,
+    columns: &'a [Column],
+    ready: std::vec::IntoIter<Row>,
+    done: bool,
+}
+
+impl<'a, I> VectorScan<'a, I> {
+    pub fn new(rows: I, plan: &'a VectorPlan, columns: &'a [Column]) -> Self {
+        Self { rows, plan, columns, ready: Vec::new().into_iter(), done: false }
+    }
+}
+
+impl<I: Iterator<Item = Result<Row, DatabaseError>>> Iterator for VectorScan<'_, I> {
+    type Item = Result<Row, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.ready.next() {
+                return Some(Ok(row));
+            }
+            if self.done {
+                return None;
+            }
+
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            while batch.len() < BATCH_SIZE {
+                match self.rows.next() {
+                    Some(Ok(row)) => batch.push(row),
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => {
+                        self.done = true;
+                        break;
+                    }
+                }
+            }
+            match self.plan.run(self.columns, batch) {
+                Ok(rows) => self.ready = rows.into_iter(),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_statement;
+    use crate::parser::Statement;
+
+    fn column(name: &str, data_type: DataType) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            foreign_key: None,
+            collation: Collation::C,
+        }
+    }
+
+    /// WHERE clause and SELECT expressions of `sql`
+    fn query(sql: &str) -> (Option<Condition>, Vec<Expression>) {
+        let Statement::Select { filter, columns, .. } = parse_statement(sql).unwrap() else {
+            panic!("Expected SELECT");
+        };
+        let expressions = columns
+            .into_iter()
+            .filter_map(|col| match col {
+                crate::parser::SelectColumn::Expression { expr, .. } => Some(expr),
+                _ => None,
+            })
+            .collect();
+        (filter, expressions)
+    }
+
+    #[test]
+    fn test_batches_match_row_at_a_time() {
+        let columns = vec![
+            column("id", DataType::Integer),
+            column("price", DataType::Real),
+            column("active", DataType::Boolean),
+            column("name", DataType::Text),
+        ];
+        let rows: Vec<Row> = (0..2500_i64)
+            .map(|n| {
+                Row::new(vec![
+                    Value::Integer(n),
+                    if n % 7 == 0 { Value::Null } else { Value::Real(n as f64 / 4.0) },
+                    Value::Boolean(n % 3 == 0),
+                    if n % 11 == 0 { Value::Null } else { Value::Text(format!("item{}", n % 50)) },
+                ])
+            })
+            .collect();
+
+        for sql in [
+            "SELECT id * 2 + 1, price / 2 FROM t WHERE id > 100 AND price < 400.5",
+            "SELECT id % 7 FROM t WHERE NOT (price >= 10 OR name = 'item3')",
+            "SELECT id - 1 FROM t WHERE active = true AND id BETWEEN 10 AND 2000 AND name IS NOT NULL",
+            "SELECT price * id FROM t WHERE id IN (1, 5, 700, 2400) OR price IS NULL",
+            "SELECT id FROM t WHERE name <> 'item4' AND id <= 1500",
+        ] {
+            let (filter, expressions) = query(sql);
+            let plan = VectorPlan::compile(&columns, filter.as_ref(), &expressions).unwrap_or_else(|| panic!("{sql} did not compile"));
+            let vectorized: Vec<Row> = VectorScan::new(rows.iter().cloned().map(Ok), &plan, &columns).collect::<Result<_, _>>().unwrap();
+
+            // Row by row through the regular evaluators
+            let expected: Vec<Vec<Value>> = rows
+                .iter()
+                .filter(|row| filter.as_ref().is_none_or(|f| ConditionEvaluator::evaluate_with_columns(&columns, row, f).unwrap()))
+                .map(|row| {
+                    let mut values = row.values.clone();
+                    values.extend(expressions.iter().map(|expr| FunctionExecutor::evaluate_values(expr, &columns, &row.values).unwrap()));
+                    values
+                })
+                .collect();
+            assert!(!expected.is_empty(), "{sql}");
+            assert_eq!(vectorized.into_iter().map(|row| row.values).collect::<Vec<_>>(), expected, "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_what_does_not_compile() {
+        let mut collated = column("name", DataType::Text);
+        collated.collation = Collation::Unicode;
+        let columns = vec![column("id", DataType::Integer), collated, column("born", DataType::Date)];
+
+        for sql in [
+            "SELECT id FROM t WHERE name = 'x'",
+            "SELECT id FROM t WHERE born > '2020-01-01'",
+            "SELECT id FROM t WHERE id = '5'",
+            "SELECT id FROM t WHERE name LIKE 'a%'",
+            "SELECT upper(name) FROM t",
+        ] {
+            let (filter, expressions) = query(sql);
+            assert!(VectorPlan::compile(&columns, filter.as_ref(), &expressions).is_none(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_errors_only_from_selected_rows() {
+        let columns = vec![column("a", DataType::Integer), column("b", DataType::Integer)];
+        let rows = vec![Row::new(vec![Value::Integer(1), Value::Integer(0)]), Row::new(vec![Value::Integer(6), Value::Integer(3)])];
+        let (filter, expressions) = query("SELECT a / b FROM t WHERE b > 0");
+        let plan = VectorPlan::compile(&columns, filter.as_ref(), &expressions).unwrap();
+        let out = plan.run(&columns, rows.clone()).unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].values[2], Value::Integer(2));
+
+        let (filter, expressions) = query("SELECT a / b FROM t");
+        let plan = VectorPlan::compile(&columns, filter.as_ref(), &expressions).unwrap();
+        assert!(matches!(plan.run(&columns, rows), Err(DatabaseError::DivisionByZero)));
+    }
+
+    #[test]
+    fn test_int4_arithmetic_stays_in_range() {
+        let columns = vec![column("a", DataType::Integer), column("b", DataType::BigInt)];
+        let rows = vec![Row::new(vec![Value::Integer(100_000), Value::Integer(100_000)])];
+        let (filter, expressions) = query("SELECT a * a FROM t");
+        let plan = VectorPlan::compile(&columns, filter.as_ref(), &expressions).unwrap();
+        assert!(matches!(plan.run(&columns, rows.clone()), Err(DatabaseError::NumericOutOfRange(_))));
+
+        // A BIGINT operand makes the product bigint
+        let (filter, expressions) = query("SELECT a * b FROM t");
+        let plan = VectorPlan::compile(&columns, filter.as_ref(), &expressions).unwrap();
+        assert_eq!(plan.run(&columns, rows).unwrap()[0].values[2], Value::Integer(10_000_000_000));
+    }
+
+    #[test]
+    fn test_vectorized_select_matches_row_mode() {
+        use crate::executor::{run_sql, QueryResult};
+        use crate::storage::DatabaseStorage;
+        use crate::transaction::GlobalTransactionManager;
+        use crate::types::Database;
+
+        let tx_manager = GlobalTransactionManager::new();
+        let mut storage = DatabaseStorage::in_memory(100);
+        let mut db = Database::new("test".to_string());
+        let exec = |db: &mut Database, storage: &mut DatabaseStorage, sql: &str| {
+            run_sql(db, storage, &tx_manager, sql)
+        };
+
+        exec(&mut db, &mut storage, "CREATE TABLE t (id INTEGER, qty INTEGER, price REAL)").unwrap();
+        let values: Vec<String> = (0..1500)
+            .map(|n| if n % 9 == 0 { format!("({n}, NULL, NULL)") } else { format!("({n}, {}, {}.5)", n % 13, n % 40) })
+            .collect();
+        exec(&mut db, &mut storage, &format!("INSERT INTO t VALUES {}", values.join(", "))).unwrap();
+
+        for sql in [
+            "SELECT id, qty * price FROM t WHERE qty > 3 AND NOT price < 10",
+            "SELECT id, id / qty FROM t WHERE qty <> 0 OR qty IS NULL ORDER BY id DESC",
+            "SELECT * FROM t WHERE price BETWEEN 5 AND 20 LIMIT 50",
+        ] {
+            storage.set_vectorized(false);
+            let QueryResult::Rows(expected, _) = exec(&mut db, &mut storage, sql).unwrap() else { panic!("Expected Rows result") };
+            storage.set_vectorized(true);
+            let QueryResult::Rows(rows, _) = exec(&mut db, &mut storage, sql).unwrap() else { panic!("Expected Rows result") };
+            assert!(!rows.is_empty(), "{sql}");
+            assert_eq!(rows, expected, "{sql}");
+        }
+
+        // Division by zero only fails for a row the WHERE clause keeps
+        let result = exec(&mut db, &mut storage, "SELECT id / qty FROM t");
+        assert!(matches!(result, Err(DatabaseError::DivisionByZero)));
+    }
+}
@@ -0,0 +1,463 @@
+/// View resolution (v2.7.0)
+///
+/// Views are stored as SQL text in `Database::views`. When a query reads from a
+/// view it is resolved here:
+/// - simple views (single table, plain column list, no DISTINCT/GROUP BY/LIMIT)
+///   are inlined into the outer SELECT, so filters and indexes on the base
+///   table keep working
+/// - any other view is materialized by running its query, which is also how
+///   views take part in JOINs
+///
+/// Recursive definitions are rejected; what DROP does with the views that
+/// depend on a relation is decided by `DependencyGraph`.
+use crate::parser::{AggregateFunction, Condition, CountTarget, Expression, SelectColumn, Statement, WhenCondition};
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError};
+use super::dispatcher_executor::QueryResult;
+use super::queries::QueryExecutor as QueriesExecutor;
+use super::set_operations::SetOperations;
+
+pub struct ViewResolver;
+
+impl ViewResolver {
+    /// Parse the stored definition of view `name`
+    pub fn definition(db: &Database, name: &str) -> Result<Statement, DatabaseError> {
+        let query = db
+            .views
+            .get(name)
+            .ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))?;
+        crate::parser::parse_statement(query).map_err(|e| DatabaseError::ParseError(e.to_string()))
+    }
+
+    /// Relations (tables or views) a statement reads from
+    #[must_use]
+    pub fn referenced_relations(stmt: &Statement) -> Vec<String> {
+        let mut out = Vec::new();
+        Self::collect_relations(stmt, &mut out);
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    fn collect_relations(stmt: &Statement, out: &mut Vec<String>) {
+        match stmt {
+            Statement::Select { columns, from, joins, filter, .. } => {
+                out.push(from.clone());
+                out.extend(joins.iter().map(|join| join.table.clone()));
+                if let Some(cond) = filter {
+                    Self::collect_condition_relations(cond, out);
+                }
+                for col in columns {
+                    if let SelectColumn::Subquery { query, .. } = col {
+                        Self::collect_relations(query, out);
+                    }
+                }
+            }
+            Statement::Union { left, right, .. }
+            | Statement::Intersect { left, right, .. }
+            | Statement::Except { left, right, .. } => {
+                Self::collect_relations(left, out);
+                Self::collect_relations(right, out);
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_condition_relations(cond: &Condition, out: &mut Vec<String>) {
+        match cond {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                Self::collect_condition_relations(left, out);
+                Self::collect_condition_relations(right, out);
+            }
+            Condition::InSubquery(_, stmt)
+            | Condition::NotInSubquery(_, stmt)
+            | Condition::Exists(stmt)
+            | Condition::NotExists(stmt)
+            | Condition::EqualsSubquery(_, stmt)
+            | Condition::GreaterThanSubquery(_, stmt)
+            | Condition::LessThanSubquery(_, stmt) => Self::collect_relations(stmt, out),
+            _ => {}
+        }
+    }
+
+    /// Views whose definition reads `relation` directly (sorted)
+    #[must_use]
+    pub fn dependent_views(db: &Database, relation: &str) -> Vec<String> {
+        let mut dependents: Vec<String> = db
+            .views
+            .iter()
+            .filter(|(name, query)| {
+                name.as_str() != relation
+                    && crate::parser::parse_statement(query)
+                        .is_ok_and(|stmt| Self::referenced_relations(&stmt).iter().any(|r| r == relation))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        dependents.sort();
+        dependents
+    }
+
+    /// Error out if expanding view `name` would eventually reference itself
+    pub fn check_cycles(db: &Database, name: &str) -> Result<(), DatabaseError> {
+        let mut path = vec![name.to_string()];
+        Self::visit(db, name, &mut path)
+    }
+
+    fn visit(db: &Database, view: &str, path: &mut Vec<String>) -> Result<(), DatabaseError> {
+        let stmt = Self::definition(db, view)?;
+        for relation in Self::referenced_relations(&stmt) {
+            if !db.views.contains_key(&relation) {
+                continue;
+            }
+            if path.contains(&relation) {
+                path.push(relation);
+                return Err(DatabaseError::ParseError(format!(
+                    "infinite recursion detected in view definition: {}",
+                    path.join(" -> ")
+                )));
+            }
+            path.push(relation.clone());
+            Self::visit(db, &relation, path)?;
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// Merge a simple view into the outer SELECT
+    ///
+    /// Returns `None` if the view cannot be inlined without changing results
+    /// (DISTINCT, GROUP BY, JOIN, LIMIT/OFFSET or computed columns in the view).
+    #[must_use]
+    pub fn inline(view: &Statement, outer: &Statement) -> Option<Statement> {
+        let Statement::Select {
+            distinct: false,
+            columns: view_columns,
+            from: view_from,
+            joins: view_joins,
+            filter: view_filter,
+            group_by: None,
+            order_by: view_order_by,
+            limit: None,
+            offset: None,
+        } = view
+        else {
+            return None;
+        };
+        let Statement::Select { distinct, columns, joins, filter, group_by, order_by, limit, offset, .. } = outer else {
+            return None;
+        };
+
+        if !view_joins.is_empty() || !joins.is_empty() {
+            return None;
+        }
+
+        // The view has to expose plain base-table columns under their own names
+        let mut exposed = Vec::new();
+        for col in view_columns {
+            match col {
+                SelectColumn::Regular { name, alias: None } if !name.contains('.') => exposed.push(name.clone()),
+                _ => return None,
+            }
+        }
+        let select_all = exposed.iter().any(|name| name == "*");
+        let is_exposed = |name: &str| select_all || exposed.iter().any(|e| e == name);
+        let aggregate_exposed = |agg: &AggregateFunction| {
+            let mut names = Vec::new();
+            Self::collect_aggregate_columns(agg, &mut names);
+            names.iter().all(|name| is_exposed(name))
+        };
+
+        // Outer references must stay within the columns the view exposes
+        let mut outer_columns = Vec::new();
+        for col in columns {
+            match col {
+                SelectColumn::Regular { name, .. } if name == "*" => {
+                    if select_all {
+                        outer_columns.push(col.clone());
+                    } else {
+                        outer_columns.extend(exposed.iter().map(|name| SelectColumn::Regular { name: name.clone(), alias: None }));
+                    }
+                }
+                SelectColumn::Regular { name, .. } if is_exposed(name) => outer_columns.push(col.clone()),
+                SelectColumn::Aggregate(agg) if aggregate_exposed(agg) => {
+                    outer_columns.push(col.clone());
+                }
+                SelectColumn::Literal(_) => outer_columns.push(col.clone()),
+                // CASE/subqueries/window functions may reference hidden columns
+                _ if select_all => outer_columns.push(col.clone()),
+                _ => return None,
+            }
+        }
+        if let Some(cond) = filter {
+            let mut referenced = Vec::new();
+            Self::collect_condition_columns(cond, &mut referenced);
+            if !referenced.iter().all(|name| is_exposed(name)) {
+                return None;
+            }
+        }
+        if let Some((key, _)) = order_by {
+            let mut referenced = Vec::new();
+            Self::collect_expression_columns(key, &mut referenced);
+            if !referenced.iter().all(|name| is_exposed(name)) {
+                return None;
+            }
+        }
+        if group_by.iter().flatten().any(|name| !is_exposed(name)) {
+            return None;
+        }
+
+        let filter = match (view_filter.clone(), filter.clone()) {
+            (Some(v), Some(o)) => Some(Condition::And(Box::new(v), Box::new(o))),
+            (v, o) => v.or(o),
+        };
+
+        Some(Statement::Select {
+            distinct: *distinct,
+            columns: outer_columns,
+            from: view_from.clone(),
+            joins: Vec::new(),
+            filter,
+            group_by: group_by.clone(),
+            order_by: order_by.clone().or_else(|| view_order_by.clone()),
+            limit: *limit,
+            offset: *offset,
+        })
+    }
+
+    /// Columns an aggregate reads (v2.7.0: including those of its FILTER)
+    pub(crate) fn collect_aggregate_columns(agg: &AggregateFunction, out: &mut Vec<String>) {
+        match agg {
+            AggregateFunction::Count(CountTarget::All) => {}
+            AggregateFunction::Count(CountTarget::Column(col))
+            | AggregateFunction::Sum(col)
+            | AggregateFunction::Avg(col)
+            | AggregateFunction::Min(col)
+            | AggregateFunction::Max(col) => out.push(col.clone()),
+            AggregateFunction::Filter { function, filter } => {
+                Self::collect_aggregate_columns(function, out);
+                Self::collect_condition_columns(filter, out);
+            }
+        }
+    }
+
+    pub(crate) fn collect_condition_columns(cond: &Condition, out: &mut Vec<String>) {
+        match cond {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                Self::collect_condition_columns(left, out);
+                Self::collect_condition_columns(right, out);
+            }
+            Condition::Not(inner) => Self::collect_condition_columns(inner, out),
+            Condition::ColumnEquals(left, right) => out.extend([left.clone(), right.clone()]),
+            Condition::Compare(left, _, right) => {
+                Self::collect_expression_columns(left, out);
+                Self::collect_expression_columns(right, out);
+            }
+            Condition::Equals(col, _)
+            | Condition::NotEquals(col, _)
+            | Condition::GreaterThan(col, _)
+            | Condition::LessThan(col, _)
+            | Condition::GreaterThanOrEqual(col, _)
+            | Condition::LessThanOrEqual(col, _)
+            | Condition::Between(col, _, _)
+            | Condition::Like(col, _)
+            | Condition::ILike(col, _)
+            | Condition::In(col, _)
+            | Condition::IsNull(col)
+            | Condition::IsNotNull(col)
+            | Condition::InSubquery(col, _)
+            | Condition::NotInSubquery(col, _)
+            | Condition::EqualsSubquery(col, _)
+            | Condition::GreaterThanSubquery(col, _)
+            | Condition::LessThanSubquery(col, _) => out.push(col.clone()),
+            Condition::Exists(_) | Condition::NotExists(_) => {}
+        }
+    }
+
+    /// Columns an expression reads, including those in the conditions of a CASE (v2.7.0)
+    pub(crate) fn collect_expression_columns(expr: &Expression, out: &mut Vec<String>) {
+        match expr {
+            Expression::Literal(_) => {}
+            Expression::Column(name) => out.push(name.clone()),
+            Expression::Binary { left, right, .. } => {
+                Self::collect_expression_columns(left, out);
+                Self::collect_expression_columns(right, out);
+            }
+            Expression::Function { args, .. } => {
+                for arg in args {
+                    Self::collect_expression_columns(arg, out);
+                }
+            }
+            Expression::Case(case) => {
+                for when in &case.when_clauses {
+                    match &when.condition {
+                        WhenCondition::Condition(cond) => Self::collect_condition_columns(cond, out),
+                        WhenCondition::Value(value) => Self::collect_expression_columns(value, out),
+                    }
+                    Self::collect_expression_columns(&when.result, out);
+                }
+                for expr in case.operand.iter().chain(&case.else_value) {
+                    Self::collect_expression_columns(expr, out);
+                }
+            }
+        }
+    }
+
+    /// Run the view's query and return its rows with unqualified column names
+    pub fn materialize(
+        db: &Database,
+        name: &str,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: &crate::storage::DatabaseStorage,
+    ) -> Result<(Vec<Vec<String>>, Vec<String>), DatabaseError> {
+        Self::check_cycles(db, name)?;
+        let stmt = Self::definition(db, name)?;
+
+        let result = match stmt {
+            Statement::Select { distinct, columns, from, joins, filter, group_by, order_by, limit, offset } => {
+                QueriesExecutor::select(db, distinct, columns, from, joins, filter, group_by, order_by, limit, offset, tx_manager, database_storage)?
+            }
+            stmt @ (Statement::Union { .. } | Statement::Intersect { .. } | Statement::Except { .. }) => {
+                SetOperations::execute(db, &stmt, tx_manager, database_storage)?
+            }
+            _ => {
+                return Err(DatabaseError::ParseError(format!(
+                    "View '{name}' contains non-SELECT statement"
+                )));
+            }
+        };
+
+        match result {
+            QueryResult::Rows(rows, columns) => {
+                let columns = columns
+                    .into_iter()
+                    .map(|col| col.rsplit('.').next().unwrap_or(&col).to_string())
+                    .collect();
+                Ok((rows, columns))
+            }
+            QueryResult::Success(_) => Err(DatabaseError::ParseError(format!(
+                "View '{name}' did not produce rows"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryResult};
+    use crate::parser::parse_statement;
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::Database;
+
+    fn db_with_views(views: &[(&str, &str)]) -> Database {
+        let mut db = Database::new("test".to_string());
+        for (name, query) in views {
+            db.views.insert((*name).to_string(), (*query).to_string());
+        }
+        db
+    }
+
+    #[test]
+    fn test_inline_simple_view() {
+        let view = parse_statement("SELECT id, name FROM users WHERE age > 18").unwrap();
+        let outer = parse_statement("SELECT name FROM adults WHERE id = 1").unwrap();
+
+        let merged = ViewResolver::inline(&view, &outer).unwrap();
+        let Statement::Select { from, filter, columns, .. } = merged else { panic!("Expected SELECT") };
+        assert_eq!(from, "users");
+        assert_eq!(columns, vec![SelectColumn::Regular { name: "name".to_string(), alias: None }]);
+        assert!(matches!(filter, Some(Condition::And(_, _))));
+    }
+
+    #[test]
+    fn test_inline_rejects_hidden_column() {
+        let view = parse_statement("SELECT id, name FROM users").unwrap();
+        let outer = parse_statement("SELECT * FROM v WHERE age > 18").unwrap();
+        assert!(ViewResolver::inline(&view, &outer).is_none());
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let db = db_with_views(&[("a", "SELECT * FROM b"), ("b", "SELECT * FROM a")]);
+        let err = ViewResolver::check_cycles(&db, "a").unwrap_err();
+        assert!(err.to_string().contains("a -> b -> a"));
+    }
+
+    #[test]
+    fn test_dependent_views() {
+        let db = db_with_views(&[
+            ("v1", "SELECT * FROM users"),
+            ("v2", "SELECT * FROM orders JOIN users ON orders.user_id = users.id"),
+            ("v3", "SELECT * FROM orders"),
+        ]);
+        assert_eq!(ViewResolver::dependent_views(&db, "users"), vec!["v1", "v2"]);
+        assert!(ViewResolver::dependent_views(&db, "items").is_empty());
+    }
+
+    #[test]
+    fn test_view_inlined_with_outer_filter() {
+        // v2.7.0: outer WHERE/ORDER BY are merged into the view definition
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (1, 'Alice', 30), (2, 'Bob', 25), (3, 'Charlie', 35)").unwrap();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW adults AS SELECT name, age FROM users WHERE age > 26").unwrap();
+        let result = run_sql(&mut db, &mut storage, &tx_manager, "SELECT name FROM adults WHERE age < 33 ORDER BY name").unwrap();
+        match result {
+            QueryResult::Rows(rows, columns) => {
+                assert_eq!(columns, vec!["name".to_string()]);
+                assert_eq!(rows, vec![vec!["Alice".to_string()]]);
+            }
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+
+        // `id` is hidden by the view
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "SELECT id FROM adults").is_err());
+
+        // Dependency tracking on DROP
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE users").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP VIEW adults").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "DROP TABLE users").unwrap();
+    }
+
+    #[test]
+    fn test_view_joined_with_table() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER)").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "INSERT INTO users VALUES (1, 'Alice', 30), (2, 'Bob', 25)").unwrap();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW seniors AS SELECT id, age FROM users WHERE age > 26").unwrap();
+        let result = run_sql(
+            &mut db,
+            &mut storage,
+            &tx_manager,
+            "SELECT * FROM users INNER JOIN seniors ON users.id = seniors.id",
+        )
+        .unwrap();
+        match result {
+            QueryResult::Rows(rows, columns) => {
+                assert_eq!(columns.last().map(String::as_str), Some("seniors.age"));
+                assert_eq!(rows.len(), 1);
+                assert_eq!(rows[0][1..], ["Alice", "30", "1", "30"]);
+            }
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        }
+    }
+
+    #[test]
+    fn test_view_cycle_rejected() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER)").unwrap();
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW v1 AS SELECT * FROM users").unwrap();
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "CREATE VIEW v2 AS SELECT * FROM v2").is_err());
+        assert!(!db.views.contains_key("v2"));
+    }
+}
@@ -2,6 +2,7 @@
 ///
 /// ROW_NUMBER(), RANK(), DENSE_RANK(), LAG(), LEAD() with PARTITION BY and ORDER BY
 use crate::types::{DatabaseError, Row, Value};
+use crate::types::value::compare_for_sort;
 use crate::parser::{WindowFunction, WindowSpec, SortOrder};
 use std::collections::HashMap;
 
@@ -90,7 +91,7 @@ impl WindowFunctionExecutor {
 
             sorted.sort_by(|(_, a), (_, b)| {
                 for (col_idx, order) in &order_indices {
-                    let cmp = a.values[*col_idx].to_string().cmp(&b.values[*col_idx].to_string());
+                    let cmp = compare_for_sort(&a.values[*col_idx], &b.values[*col_idx]);
                     if cmp != std::cmp::Ordering::Equal {
                         return if *order == SortOrder::Asc { cmp } else { cmp.reverse() };
                     }
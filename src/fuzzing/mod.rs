@@ -0,0 +1,372 @@
+/// Entry points for fuzzing and property testing (v2.7.0)
+///
+/// `Sandbox` runs SQL against a database that lives only in memory, with
+/// the query memory and result size capped, so a fuzzer can throw anything
+/// at the executor. `fuzz_parse` and `fuzz_execute` are the bodies of the
+/// cargo-fuzz targets in `fuzz/`; the strategies below generate random
+/// tables and statements for proptest, and `check_constraints` verifies
+/// that a table holds nothing its constraints forbid.
+///
+/// Compiled for the crate's own tests and with the `fuzzing` feature.
+use std::collections::HashSet;
+use proptest::prelude::*;
+use crate::executor::{QueryExecutor, QueryResult};
+use crate::parser::parse_statement;
+use crate::storage::DatabaseStorage;
+use crate::transaction::GlobalTransactionManager;
+use crate::types::{Database, DatabaseError, Value};
+
+/// Pages of the sandbox's buffer pool
+const SANDBOX_PAGES: usize = 64;
+/// Bytes one sandboxed query may hold
+const SANDBOX_QUERY_MEMORY: usize = 64 * 1024 * 1024;
+/// Rows one sandboxed query may return
+const SANDBOX_RESULT_ROWS: usize = 10_000;
+
+/// Database executing statements in memory, each in its own transaction
+pub struct Sandbox {
+    db: Database,
+    storage: DatabaseStorage,
+    tx_manager: GlobalTransactionManager,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sandbox {
+    #[must_use]
+    pub fn new() -> Self {
+        let mut storage = DatabaseStorage::in_memory(SANDBOX_PAGES);
+        storage.set_max_query_memory(SANDBOX_QUERY_MEMORY);
+        storage.set_max_result_rows(SANDBOX_RESULT_ROWS);
+        Self { db: Database::new("sandbox".to_string()), storage, tx_manager: GlobalTransactionManager::new() }
+    }
+
+    /// Parse and run one statement
+    pub fn execute(&mut self, sql: &str) -> Result<QueryResult, DatabaseError> {
+        let stmt = parse_statement(sql).map_err(|e| DatabaseError::ParseError(e.to_string()))?;
+        QueryExecutor::execute(&mut self.db, stmt, None, &self.tx_manager, &mut self.storage, None)
+    }
+
+    /// Run the `;`-separated statements of `script`, ignoring their errors
+    pub fn run_script(&mut self, script: &str) {
+        for sql in script.split(';').filter(|sql| !sql.trim().is_empty()) {
+            let _ = self.execute(sql);
+        }
+    }
+
+    /// Rows of `SELECT * FROM table` as the client sees them
+    pub fn rows(&mut self, table: &str) -> Result<Vec<Vec<String>>, DatabaseError> {
+        match self.execute(&format!("SELECT * FROM {table}"))? {
+            QueryResult::Rows(rows, _) => Ok(rows),
+            QueryResult::Success(message) => Err(DatabaseError::ParseError(format!("SELECT returned {message}"))),
+        }
+    }
+}
+
+/// Body of the `parse_statement` fuzz target: any input parses or fails, never panics
+pub fn fuzz_parse(data: &[u8]) {
+    if let Ok(sql) = std::str::from_utf8(data) {
+        let _ = parse_statement(sql);
+    }
+}
+
+/// Body of the `execute` fuzz target: the input runs as a script in a fresh sandbox
+pub fn fuzz_execute(data: &[u8]) {
+    if let Ok(script) = std::str::from_utf8(data) {
+        Sandbox::new().run_script(script);
+    }
+}
+
+/// Column types of generated tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Text,
+    Boolean,
+}
+
+/// Column of a generated table
+#[derive(Debug, Clone)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub data_type: ColumnType,
+    pub not_null: bool,
+    /// UNIQUE, or PRIMARY KEY together with `not_null`
+    pub unique: bool,
+    pub primary_key: bool,
+}
+
+/// Generated table: columns `c0`, `c1`, ... and an optional `CHECK (column >= min)`
+#[derive(Debug, Clone)]
+pub struct TableSpec {
+    pub name: String,
+    pub columns: Vec<ColumnSpec>,
+    /// Integer column and its lower bound
+    pub check: Option<(usize, i64)>,
+}
+
+impl TableSpec {
+    /// CREATE TABLE, then the CHECK constraint (CREATE TABLE takes none)
+    #[must_use]
+    pub fn create_sql(&self) -> Vec<String> {
+        let definitions: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let data_type = match column.data_type {
+                    ColumnType::Integer => "INTEGER",
+                    ColumnType::Text => "TEXT",
+                    ColumnType::Boolean => "BOOLEAN",
+                };
+                let mut definition = format!("{} {data_type}", column.name);
+                if column.primary_key {
+                    definition.push_str(" PRIMARY KEY");
+                } else {
+                    if column.not_null {
+                        definition.push_str(" NOT NULL");
+                    }
+                    if column.unique {
+                        definition.push_str(" UNIQUE");
+                    }
+                }
+                definition
+            })
+            .collect();
+        let mut statements = vec![format!("CREATE TABLE {} ({})", self.name, definitions.join(", "))];
+        if let Some((column, min)) = self.check {
+            let column = &self.columns[column].name;
+            statements.push(format!("ALTER TABLE {} ADD CONSTRAINT {column}_min CHECK ({column} >= {min})", self.name));
+        }
+        statements
+    }
+
+    #[must_use]
+    pub fn insert_sql(&self, row: &[Value]) -> String {
+        let values: Vec<String> = row.iter().map(sql_literal).collect();
+        format!("INSERT INTO {} VALUES ({})", self.name, values.join(", "))
+    }
+
+    /// Whether `row` breaks a constraint, given the rows already in the table
+    #[must_use]
+    pub fn violates(&self, row: &[Value], existing: &[Vec<Value>]) -> bool {
+        self.columns.iter().zip(row).enumerate().any(|(i, (column, value))| {
+            let null = matches!(value, Value::Null);
+            (column.not_null && null)
+                || (column.unique && !null && existing.iter().any(|other| &other[i] == value))
+                || matches!((self.check, value), (Some((checked, min)), Value::Integer(n)) if checked == i && *n < min)
+        })
+    }
+}
+
+/// SQL literal of a generated value
+#[must_use]
+pub fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Text(s) => format!("'{}'", s.replace('\'', "''")),
+        Value::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Values of a column: few distinct ones, so that unique keys collide
+pub fn value(data_type: ColumnType) -> BoxedStrategy<Value> {
+    let value = match data_type {
+        ColumnType::Integer => (-5_i64..=5).prop_map(Value::Integer).boxed(),
+        // Never "NULL": rows are compared as text
+        ColumnType::Text => proptest::collection::vec(prop_oneof![Just('a'), Just('b'), Just('\''), Just(' '), Just('é')], 0..3)
+            .prop_map(|chars| Value::Text(chars.into_iter().collect()))
+            .boxed(),
+        ColumnType::Boolean => any::<bool>().prop_map(Value::Boolean).boxed(),
+    };
+    prop_oneof![1 => Just(Value::Null), 6 => value].boxed()
+}
+
+/// Tables of one to four columns with random constraints
+pub fn table() -> impl Strategy<Value = TableSpec> {
+    let column_type = prop_oneof![Just(ColumnType::Integer), Just(ColumnType::Text), Just(ColumnType::Boolean)];
+    let columns = proptest::collection::vec((column_type, any::<bool>(), prop::bool::weighted(0.3)), 1..=4);
+    (columns, any::<bool>(), proptest::option::of(-3_i64..=0)).prop_map(|(columns, primary_key, check)| {
+        let columns: Vec<ColumnSpec> = columns
+            .into_iter()
+            .enumerate()
+            .map(|(i, (data_type, not_null, unique))| {
+                // Booleans have two values: a unique one would reject most rows
+                let primary_key = primary_key && i == 0 && data_type != ColumnType::Boolean;
+                let unique = primary_key || (unique && data_type != ColumnType::Boolean);
+                ColumnSpec { name: format!("c{i}"), data_type, not_null: not_null || primary_key, unique, primary_key }
+            })
+            .collect();
+        let check = columns
+            .iter()
+            .position(|column| column.data_type == ColumnType::Integer)
+            .and_then(|column| check.map(|min| (column, min)));
+        TableSpec { name: "fuzz".to_string(), columns, check }
+    })
+}
+
+/// Rows of `table`, NULLs and constraint violations included
+#[must_use]
+pub fn row(table: &TableSpec) -> Vec<BoxedStrategy<Value>> {
+    table.columns.iter().map(|column| value(column.data_type)).collect::<Vec<_>>()
+}
+
+/// INSERT, UPDATE, DELETE and SELECT statements on `table`
+pub fn statement(table: &TableSpec) -> BoxedStrategy<String> {
+    let spec = table.clone();
+    let insert = row(table).prop_map(move |row| spec.insert_sql(&row));
+
+    let columns = table.columns.len();
+    let types: Vec<ColumnType> = table.columns.iter().map(|column| column.data_type).collect();
+    let name = table.name.clone();
+    let condition = {
+        let types = types.clone();
+        (0..columns, prop_oneof![Just("="), Just("<>"), Just("<"), Just(">=")])
+            .prop_flat_map(move |(column, op)| (Just(column), Just(op), value(types[column])))
+            .prop_map(|(column, op, value)| match value {
+                Value::Null => format!("c{column} IS NULL"),
+                value => format!("c{column} {op} {}", sql_literal(&value)),
+            })
+    };
+    let update = {
+        let (name, types) = (name.clone(), types);
+        (0..columns, condition.clone())
+            .prop_flat_map(move |(column, condition)| (Just(column), value(types[column]), Just(condition)))
+            .prop_map(move |(column, value, condition)| {
+                format!("UPDATE {name} SET c{column} = {} WHERE {condition}", sql_literal(&value))
+            })
+    };
+    let delete = {
+        let name = name.clone();
+        condition.clone().prop_map(move |condition| format!("DELETE FROM {name} WHERE {condition}"))
+    };
+    let select = condition.prop_map(move |condition| format!("SELECT * FROM {name} WHERE {condition} ORDER BY c0"));
+
+    prop_oneof![4 => insert, 2 => update, 1 => delete, 1 => select].boxed()
+}
+
+/// A table and statements run against it
+pub fn workload(statements: usize) -> impl Strategy<Value = (TableSpec, Vec<String>)> {
+    table().prop_flat_map(move |table| {
+        let statements = proptest::collection::vec(statement(&table), 0..statements);
+        (Just(table), statements)
+    })
+}
+
+/// Check that the rows of `table` keep NOT NULL, UNIQUE and CHECK
+pub fn check_constraints(sandbox: &mut Sandbox, table: &TableSpec) -> Result<(), String> {
+    let rows = sandbox.rows(&table.name).map_err(|e| e.to_string())?;
+    for (i, column) in table.columns.iter().enumerate() {
+        let values = rows.iter().map(|row| row[i].as_str());
+        if column.not_null && values.clone().any(|value| value == "NULL") {
+            return Err(format!("NULL in NOT NULL column {}", column.name));
+        }
+        if column.unique {
+            let mut seen = HashSet::new();
+            if let Some(value) = values.clone().filter(|&value| value != "NULL").find(|&value| !seen.insert(value)) {
+                return Err(format!("duplicate {value} in unique column {}", column.name));
+            }
+        }
+        if let Some((checked, min)) = table.check
+            && checked == i
+            && let Some(value) = values.filter_map(|value| value.parse::<i64>().ok()).find(|&value| value < min)
+        {
+            return Err(format!("{value} in {} breaks CHECK (>= {min})", column.name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows as the client sees them, in a stable order
+    fn sorted(rows: impl IntoIterator<Item = Vec<String>>) -> Vec<Vec<String>> {
+        let mut rows: Vec<_> = rows.into_iter().collect();
+        rows.sort();
+        rows
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(48))]
+
+        #[test]
+        fn test_inserted_rows_are_selectable(
+            (table, rows) in table().prop_flat_map(|table| {
+                let rows = proptest::collection::vec(row(&table), 0..12);
+                (Just(table), rows)
+            })
+        ) {
+            let mut sandbox = Sandbox::new();
+            for sql in table.create_sql() {
+                sandbox.execute(&sql).unwrap();
+            }
+
+            // An INSERT fails exactly when the row breaks a constraint
+            let mut inserted: Vec<Vec<Value>> = Vec::new();
+            for row in rows {
+                let sql = table.insert_sql(&row);
+                let result = sandbox.execute(&sql);
+                prop_assert_eq!(result.is_err(), table.violates(&row, &inserted), "{}: {:?}", sql, result);
+                if result.is_ok() {
+                    inserted.push(row);
+                }
+            }
+
+            let expected = inserted.iter().map(|row| row.iter().map(ToString::to_string).collect());
+            prop_assert_eq!(sorted(sandbox.rows(&table.name).unwrap()), sorted(expected));
+        }
+
+        #[test]
+        fn test_constraints_never_silently_violated((table, statements) in workload(24)) {
+            let mut sandbox = Sandbox::new();
+            for sql in table.create_sql() {
+                sandbox.execute(&sql).unwrap();
+            }
+            for sql in &statements {
+                let _ = sandbox.execute(sql);
+                if let Err(violation) = check_constraints(&mut sandbox, &table) {
+                    prop_assert!(false, "{} after {}: {:?}", violation, sql, table.create_sql());
+                }
+            }
+        }
+
+        #[test]
+        fn test_generated_statements_parse((table, statements) in workload(8)) {
+            for sql in table.create_sql().iter().chain(&statements) {
+                prop_assert!(parse_statement(sql).is_ok(), "{}", sql);
+            }
+        }
+
+        #[test]
+        fn test_parser_never_panics(input in any::<String>()) {
+            fuzz_parse(input.as_bytes());
+        }
+
+        #[test]
+        fn test_parser_never_panics_on_truncated_sql(
+            (sql, cut) in workload(2)
+                .prop_filter_map("no statements", |(_, statements)| statements.into_iter().next())
+                .prop_flat_map(|sql| { let len = sql.len(); (Just(sql), 0..=len) })
+        ) {
+            let cut = (0..=cut).rev().find(|&i| sql.is_char_boundary(i)).unwrap_or(0);
+            fuzz_parse(&sql.as_bytes()[..cut]);
+        }
+    }
+
+    #[test]
+    fn test_sandbox_runs_scripts() {
+        let mut sandbox = Sandbox::new();
+        sandbox.run_script("CREATE TABLE t (id INTEGER PRIMARY KEY); INSERT INTO t VALUES (1); INSERT INTO t VALUES (1); nonsense; INSERT INTO t VALUES (2)");
+        assert_eq!(sandbox.rows("t").unwrap(), [["1"], ["2"]]);
+
+        // Statements that only work through a connection are errors, not panics
+        assert!(sandbox.execute("BEGIN").is_err());
+        fuzz_execute(b"CREATE TABLE t (id INTEGER); SELECT * FROM t, t; \xff");
+    }
+}
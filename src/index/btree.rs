@@ -1,15 +1,19 @@
-/// B-tree index for fast equality lookups
+/// B-tree index for equality lookups and range scans
 ///
 /// Simplified in-memory B-tree implementation for v1.6.0.
 /// Supports INSERT, DELETE, and SEARCH operations.
 ///
 /// Future improvements:
 /// - Persistent storage on disk
-/// - Range queries (>, <, BETWEEN)
 /// - Bulk loading optimization
 use crate::types::{Value, DatabaseError};
-use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use uuid::Uuid;
 
 /// B-tree index for single or multiple columns (v1.9.0)
 ///
@@ -51,33 +55,83 @@ impl BTreeIndex {
 
 /// Wrapper for Value(s) to make it sortable in `BTreeMap`
 /// Supports both single and composite keys (v1.9.0)
+///
+/// v2.7.0: each column is a typed part, so keys sort like the values
+/// themselves (-10 < -5 < 3) and a composite key sorts column by column -
+/// the order range scans walk.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-struct IndexKey(String);
+struct IndexKey(Vec<KeyPart>);
 
-impl IndexKey {
-    /// Create key from single value
+/// One column of an index key (v2.7.0)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum KeyPart {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Real(RealKey),
+    Numeric(Decimal),
+    Text(String),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Uuid(Uuid),
+    /// JSON, BYTEA, enums: only equality is meaningful
+    Other(String),
+}
+
+/// REAL with a total order (v2.7.0)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct RealKey(f64);
+
+impl PartialEq for RealKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RealKey {}
+
+impl PartialOrd for RealKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RealKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl KeyPart {
     fn from_value(value: &Value) -> Self {
-        // Convert Value to sortable string representation
         match value {
-            Value::Integer(i) => Self(format!("I{i:020}")),
-            Value::SmallInt(i) => Self(format!("S{i:020}")),
-            Value::Text(s) => Self(format!("T{s}")),
-            Value::Char(s) => Self(format!("C{s}")),
-            Value::Boolean(b) => Self(format!("BOOL{b}")),
-            Value::Real(f) => Self(format!("R{f:020.10}")),
-            Value::Null => Self("NULL".to_string()),
-            Value::Uuid(u) => Self(format!("UUID{u}")),
-            // Add more types as needed
-            _ => Self(format!("{value:?}")),
+            Value::Null => Self::Null,
+            Value::Boolean(b) => Self::Boolean(*b),
+            Value::SmallInt(i) => Self::Integer(i64::from(*i)),
+            Value::Integer(i) => Self::Integer(*i),
+            // -0.0 and 0.0 are the same key
+            Value::Real(f) => Self::Real(RealKey(f + 0.0)),
+            Value::Numeric(d) => Self::Numeric(d.normalize()),
+            Value::Text(s) | Value::Char(s) => Self::Text(s.clone()),
+            Value::Date(d) => Self::Date(*d),
+            Value::Timestamp(t) => Self::Timestamp(*t),
+            Value::TimestampTz(t) => Self::TimestampTz(*t),
+            Value::Uuid(u) => Self::Uuid(*u),
+            Value::Json(_) | Value::Bytea(_) | Value::Enum(..) => Self::Other(format!("{value:?}")),
         }
     }
+}
+
+impl IndexKey {
+    /// Create key from single value
+    fn from_value(value: &Value) -> Self {
+        Self(vec![KeyPart::from_value(value)])
+    }
 
     /// Create composite key from multiple values (v1.9.0)
     fn from_values(values: &[Value]) -> Self {
-        let parts: Vec<String> = values.iter().map(|v| {
-            Self::from_value(v).0
-        }).collect();
-        Self(parts.join("||"))  // Use || as separator
+        Self(values.iter().map(KeyPart::from_value).collect())
     }
 }
 
@@ -244,18 +298,90 @@ impl BTreeIndex {
         if values.is_empty() || values.len() > self.column_names.len() {
             return Vec::new();
         }
+        self.search_range(values, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Range scan (v2.7.0)
+    ///
+    /// Rows whose leading columns equal `prefix` and whose next column lies
+    /// between `lower` and `upper`; `WHERE a = 1 AND b > 5` on an index over
+    /// (a, b) is `search_range(&[1], Excluded(5), Unbounded)`. NULLs never
+    /// fall in a range. Row indices come in key order.
+    #[must_use]
+    pub fn search_range(&self, prefix: &[Value], lower: Bound<&Value>, upper: Bound<&Value>) -> Vec<usize> {
+        self.range_entries(prefix, lower, upper).flatten().copied().collect()
+    }
+
+    /// Range scan walked lazily, in key order or against it (v2.7.0)
+    ///
+    /// Rows of `search_range` for an ORDER BY the index answers; the entries
+    /// after the last row a LIMIT needs are never visited. Rows with equal
+    /// keys keep their order both ways.
+    #[must_use]
+    pub fn scan_range<'a>(
+        &'a self,
+        prefix: &[Value],
+        lower: Bound<&Value>,
+        upper: Bound<&Value>,
+        descending: bool,
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let entries = self.range_entries(prefix, lower, upper);
+        if descending {
+            let entries: Vec<&Vec<usize>> = entries.collect();
+            Box::new(entries.into_iter().rev().flatten().copied())
+        } else {
+            Box::new(entries.flatten().copied())
+        }
+    }
 
-        let prefix_key = IndexKey::from_values(values);
-        let prefix_str = &prefix_key.0;
+    /// Row indices of the keys `search_range` matches, one entry per key
+    fn range_entries<'a>(
+        &'a self,
+        prefix: &[Value],
+        lower: Bound<&Value>,
+        upper: Bound<&Value>,
+    ) -> Box<dyn Iterator<Item = &'a Vec<usize>> + 'a> {
+        let column = prefix.len();
+        let bounded = !matches!((lower, upper), (Bound::Unbounded, Bound::Unbounded));
+        if column > self.column_names.len() || (bounded && column == self.column_names.len()) {
+            return Box::new(std::iter::empty());
+        }
 
-        // Find all keys that start with this prefix
-        let mut result = Vec::new();
-        for (key, indices) in &self.tree {
-            if key.0.starts_with(prefix_str) {
-                result.extend_from_slice(indices);
-            }
+        let prefix: Vec<KeyPart> = prefix.iter().map(KeyPart::from_value).collect();
+        let part = |bound: Bound<&Value>| match bound {
+            Bound::Included(value) => Bound::Included(KeyPart::from_value(value)),
+            Bound::Excluded(value) => Bound::Excluded(KeyPart::from_value(value)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let (lower, upper) = (part(lower), part(upper));
+
+        // Start at the first key with the prefix and the lower bound, the
+        // excluded bound itself is skipped below
+        let mut start = prefix.clone();
+        if let Bound::Included(value) | Bound::Excluded(value) = &lower {
+            start.push(value.clone());
         }
-        result
+
+        let entries = self
+            .tree
+            .range((Bound::Included(IndexKey(start)), Bound::Unbounded))
+            .take_while(move |(key, _)| {
+                key.0[..column] == prefix[..]
+                    && (!bounded
+                        || match &upper {
+                            Bound::Included(bound) => key.0[column] <= *bound,
+                            Bound::Excluded(bound) => key.0[column] < *bound,
+                            Bound::Unbounded => true,
+                        })
+            })
+            .filter(move |(key, _)| {
+                !bounded || {
+                    let value = &key.0[column];
+                    !matches!(value, KeyPart::Null) && !matches!(&lower, Bound::Excluded(bound) if value == bound)
+                }
+            })
+            .map(|(_, indices)| indices);
+        Box::new(entries)
     }
 }
 
@@ -378,4 +504,51 @@ mod tests {
         assert_eq!(index.key_count(), 2); // Two distinct keys: 25, 30
         assert_eq!(index.entry_count(), 3); // Three total entries
     }
+
+    #[test]
+    fn test_btree_range_scan() {
+        let mut index = BTreeIndex::new(
+            "idx_score".to_string(),
+            "users".to_string(),
+            "score".to_string(),
+            false,
+        );
+
+        for (row, score) in [-10, 3, -5, 7, 3].into_iter().enumerate() {
+            index.insert(&Value::Integer(score), row).unwrap();
+        }
+        index.insert(&Value::Null, 5).unwrap();
+
+        let above = index.search_range(&[], Bound::Excluded(&Value::Integer(-5)), Bound::Unbounded);
+        assert_eq!(above, vec![1, 4, 3]);
+        let between = index.search_range(&[], Bound::Included(&Value::Integer(-5)), Bound::Included(&Value::Integer(3)));
+        assert_eq!(between, vec![2, 1, 4]);
+        let below = index.search_range(&[], Bound::Unbounded, Bound::Excluded(&Value::Integer(3)));
+        assert_eq!(below, vec![0, 2]);
+
+        let descending: Vec<usize> = index.scan_range(&[], Bound::Excluded(&Value::Integer(-10)), Bound::Unbounded, true).collect();
+        assert_eq!(descending, vec![3, 1, 4, 2]);
+        let first: Vec<usize> = index.scan_range(&[], Bound::Unbounded, Bound::Unbounded, false).take(3).collect();
+        assert_eq!(first, vec![5, 0, 2]);
+    }
+
+    #[test]
+    fn test_btree_composite_prefix_and_range() {
+        let mut index = BTreeIndex::new_composite(
+            "idx_city_age".to_string(),
+            "users".to_string(),
+            vec!["city".to_string(), "age".to_string()],
+            false,
+        );
+
+        let rows = [("NY", 30), ("NY", 5), ("NYC", 40), ("LA", 50), ("NY", 6)];
+        for (row, (city, age)) in rows.into_iter().enumerate() {
+            index.insert_composite(&[Value::Text(city.to_string()), Value::Integer(age)], row).unwrap();
+        }
+
+        let ny = [Value::Text("NY".to_string())];
+        assert_eq!(index.search_prefix(&ny), vec![1, 4, 0]);
+        assert_eq!(index.search_range(&ny, Bound::Excluded(&Value::Integer(5)), Bound::Unbounded), vec![4, 0]);
+        assert_eq!(index.search_range(&ny, Bound::Unbounded, Bound::Included(&Value::Integer(6))), vec![1, 4]);
+    }
 }
@@ -115,6 +115,11 @@ impl HashIndex {
         Ok(())
     }
 
+    /// Clear all entries from index
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
     /// Delete a value from the index
     pub fn delete(&mut self, value: &Value, row_index: usize) {
         let key = IndexKey::from(value);
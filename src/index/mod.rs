@@ -97,6 +97,14 @@ impl Index {
         }
     }
 
+    /// v2.7.0: Remove all entries (e.g. after the table was truncated)
+    pub fn clear(&mut self) {
+        match self {
+            Self::BTree(idx) => idx.clear(),
+            Self::Hash(idx) => idx.clear(),
+        }
+    }
+
     #[must_use] 
     pub fn search(&self, value: &crate::types::Value) -> Vec<usize> {
         match self {
@@ -153,4 +161,33 @@ impl Index {
             _ => None,  // Hash indexes don't support prefix search
         }
     }
+
+    /// v2.7.0: Range scan after an equality prefix (only for B-tree indexes)
+    #[must_use]
+    pub fn search_range(
+        &self,
+        prefix: &[crate::types::Value],
+        lower: std::ops::Bound<&crate::types::Value>,
+        upper: std::ops::Bound<&crate::types::Value>,
+    ) -> Option<Vec<usize>> {
+        match self {
+            Self::BTree(idx) => Some(idx.search_range(prefix, lower, upper)),
+            Self::Hash(_) => None, // Hash indexes only answer equality
+        }
+    }
+
+    /// v2.7.0: `search_range` walked lazily in key order, or against it
+    #[must_use]
+    pub fn scan_range<'a>(
+        &'a self,
+        prefix: &[crate::types::Value],
+        lower: std::ops::Bound<&crate::types::Value>,
+        upper: std::ops::Bound<&crate::types::Value>,
+        descending: bool,
+    ) -> Option<Box<dyn Iterator<Item = usize> + 'a>> {
+        match self {
+            Self::BTree(idx) => Some(idx.scan_range(prefix, lower, upper, descending)),
+            Self::Hash(_) => None,
+        }
+    }
 }
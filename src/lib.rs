@@ -53,6 +53,13 @@ pub mod index;
 // Network protocols (TCP server, text protocol, PostgreSQL wire protocol)
 pub mod network;
 
+// Async client for the PostgreSQL wire protocol (v2.7.0)
+pub mod client;
+
+// Sandboxed executor and proptest strategies for fuzzing (v2.7.0)
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
+
 // Re-export commonly used types for convenience
 pub use core::{Database, Table, Row, Value, Column, DataType, ForeignKey, DatabaseError, ServerInstance};
 pub use parser::{Statement, parse_statement};
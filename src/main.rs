@@ -1,7 +1,12 @@
 use postgrustql::Server;
+use postgrustql::network::{AutoConfig, HbaConfig, HbaRule, IdentMapping, tls};
+use clap::Parser;
 use config::{Config, File, Environment};
 use serde::Deserialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
 /// Конфигурация сервера
 #[derive(Debug, Deserialize)]
@@ -20,6 +25,78 @@ struct ServerConfig {
     data_dir: String,
     #[serde(default = "default_initdb")]
     initdb: bool,
+    /// v2.7.0: Keep everything in memory (no WAL, page files or checkpoints; `data_dir` is ignored)
+    #[serde(default)]
+    in_memory: bool,
+    /// v2.7.0: Log level / filter (`RUST_LOG` overrides it)
+    #[serde(default = "default_log_level")]
+    log_level: String,
+    /// v2.7.0: Log format: pretty, compact or json
+    #[serde(default = "default_log_format")]
+    log_format: String,
+    /// v2.7.0: Log destination: stdout, stderr or a file path
+    #[serde(default = "default_log_destination")]
+    log_destination: String,
+    /// v2.7.0: Log statements running at least this many ms (-1 = off, 0 = all)
+    #[serde(default = "default_log_min_duration_statement")]
+    log_min_duration_statement: i64,
+    /// v2.7.0: Named prepared statements a session keeps before evicting the least recently used
+    #[serde(default = "default_max_prepared_statements")]
+    max_prepared_statements: usize,
+    /// v2.7.0: Memory (kB) a sort uses before spilling to temp files in `data_dir/pgsql_tmp`
+    #[serde(default = "default_work_mem")]
+    work_mem: usize,
+    /// v2.7.0: Memory (kB) one query may hold in sorts, hash tables and its result (0 = unlimited)
+    #[serde(default)]
+    max_query_memory: usize,
+    /// v2.7.0: Disk space (kB) the temp files of one query may take (-1 = no limit)
+    #[serde(default = "default_temp_file_limit")]
+    temp_file_limit: i64,
+    /// v2.7.0: Rows one query may return (0 = unlimited)
+    #[serde(default)]
+    max_result_rows: usize,
+    /// v2.7.0: Memory (kB) the rows of one query result may take; larger results are streamed (0 = unlimited)
+    #[serde(default)]
+    max_result_memory: usize,
+    /// v2.7.0: Minimum length of passwords given to CREATE USER / ALTER USER (0 = any)
+    #[serde(default)]
+    password_min_length: usize,
+    /// v2.7.0: Failed logins of a user from one address that lock them out (0 = never)
+    #[serde(default = "default_auth_lockout_failures")]
+    auth_lockout_failures: u32,
+    /// v2.7.0: Seconds within which failed logins are counted
+    #[serde(default = "default_auth_lockout_window")]
+    auth_lockout_window: u64,
+    /// v2.7.0: Seconds a lockout lasts
+    #[serde(default = "default_auth_lockout_duration")]
+    auth_lockout_duration: u64,
+    /// v2.7.0: Compress large WAL records (full row images); every record carries a CRC either way
+    #[serde(default = "default_wal_compression")]
+    wal_compression: bool,
+    /// v2.7.0: Evaluate WHERE and SELECT expressions of sequential scans in batches of rows
+    #[serde(default)]
+    vectorized_execution: bool,
+    /// v2.7.0: Address of the Prometheus /metrics endpoint (disabled if unset)
+    #[serde(default)]
+    metrics_addr: Option<String>,
+    /// v2.7.0: Address of the HTTP/JSON query API (`POST /query`, disabled if unset)
+    #[serde(default)]
+    http_addr: Option<String>,
+    /// v2.7.0: Host-based authentication rules ([[hba]] tables), first match wins
+    #[serde(default)]
+    hba: Vec<HbaRule>,
+    /// v2.7.0: Ident maps for `cert` rules ([[ident]] tables)
+    #[serde(default)]
+    ident: Vec<IdentMapping>,
+    /// v2.7.0: Server certificate chain (PEM); TLS is enabled with ssl_key_file
+    #[serde(default)]
+    ssl_cert_file: Option<String>,
+    /// v2.7.0: Private key of the server certificate (PEM)
+    #[serde(default)]
+    ssl_key_file: Option<String>,
+    /// v2.7.0: CA whose client certificates are accepted (PEM), needed by `cert` rules
+    #[serde(default)]
+    ssl_ca_file: Option<String>,
 }
 
 fn default_user() -> String { "postgres".to_string() }
@@ -29,69 +106,305 @@ fn default_host() -> String { "127.0.0.1".to_string() }
 fn default_port() -> u16 { 5432 }
 fn default_data_dir() -> String { "./data".to_string() }
 fn default_initdb() -> bool { true }
+fn default_log_level() -> String { "info".to_string() }
+fn default_log_format() -> String { "pretty".to_string() }
+fn default_log_destination() -> String { "stdout".to_string() }
+const fn default_log_min_duration_statement() -> i64 { -1 }
+const fn default_max_prepared_statements() -> usize { 1000 }
+const fn default_work_mem() -> usize { 4096 }
+const fn default_temp_file_limit() -> i64 { -1 }
+const fn default_wal_compression() -> bool { true }
+const fn default_auth_lockout_failures() -> u32 { 5 }
+const fn default_auth_lockout_window() -> u64 { 60 }
+const fn default_auth_lockout_duration() -> u64 { 300 }
 
-impl ServerConfig {
-    /// Load configuration with priority: ENV > config file > defaults
-    fn load() -> Result<Self, config::ConfigError> {
-        let mut builder = Config::builder();
+/// v2.7.0: Command-line flags, between the environment and the config files
+#[derive(Debug, Parser)]
+#[command(name = "postgrustsql")]
+#[command(about = "PostgrustSQL server", long_about = None, disable_version_flag = true)]
+struct Args {
+    /// Config file (default: `/etc/postgrustsql/postgrustsql.toml` or `./postgrustsql.toml`)
+    #[arg(short = 'c', long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Port to listen on
+    #[arg(short = 'p', long)]
+    port: Option<u16>,
+
+    /// Data directory
+    #[arg(short = 'D', long, value_name = "DIR")]
+    data_dir: Option<String>,
+
+    /// Log level or filter, e.g. debug or postgrustql=trace (`RUST_LOG` overrides it)
+    #[arg(long, value_name = "FILTER")]
+    log_level: Option<String>,
+
+    /// Single-user mode: no networking, SQL from stdin
+    #[arg(long)]
+    single_user: bool,
+
+    /// Verify page files and WAL of the data directory and exit
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "")]
+    check_data_dir: Option<String>,
+
+    /// Print version and build information
+    #[arg(short = 'V', long)]
+    version: bool,
+}
+
+impl Args {
+    /// Flags given on the command line, as a configuration source
+    fn overrides(&self) -> Result<Config, config::ConfigError> {
+        Config::builder()
+            .set_override_option("port", self.port)?
+            .set_override_option("data_dir", self.data_dir.clone())?
+            .set_override_option("log_level", self.log_level.clone())?
+            .build()
+    }
+}
+
+/// v2.7.0: `--version`: package version, build profile, target and optional features
+fn version_info() -> String {
+    let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+    let features: Vec<&str> = [
+        ("page_storage", cfg!(feature = "page_storage")),
+        ("parquet", cfg!(feature = "parquet")),
+        ("fuzzing", cfg!(feature = "fuzzing")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect();
+    format!(
+        "postgrustsql {}\nbuild: {profile}, {}-{}\nfeatures: {}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        if features.is_empty() { "none".to_string() } else { features.join(", ") },
+    )
+}
 
+impl ServerConfig {
+    /// Load configuration with priority:
+    /// ENV > command line > ALTER SYSTEM overlay > config file > defaults
+    ///
+    /// v2.7.0: also returns the ALTER SYSTEM file if one was read, to be
+    /// logged once logging is set up
+    fn load(args: &Args) -> Result<(Self, Option<PathBuf>), config::ConfigError> {
         // 1. Try to load config file (optional)
-        // Check multiple locations: /etc/postgrustsql/, ./
+        // Check multiple locations: /etc/postgrustsql/, ./ (v2.7.0: or the one given with --config)
         let config_paths = [
             "/etc/postgrustsql/postgrustsql.toml",
             "./postgrustsql.toml",
         ];
-
-        for path in &config_paths {
-            if Path::new(path).exists() {
-                builder = builder.add_source(File::with_name(path));
-                eprintln!("Loaded config from: {}", path);
-                break;
+        let config_file = match &args.config {
+            Some(path) if !path.exists() => {
+                return Err(config::ConfigError::Message(format!("config file {} not found", path.display())));
             }
+            Some(path) => Some(path.clone()),
+            None => config_paths.iter().map(PathBuf::from).find(|path| path.exists()),
+        };
+        if let Some(path) = &config_file {
+            eprintln!("Loaded config from: {}", path.display());
         }
+        let overrides = args.overrides()?;
 
-        // 2. Override with environment variables (POSTGRUSTQL_*)
-        builder = builder.add_source(
-            Environment::with_prefix("POSTGRUSTQL")
-                .separator("_")
-        );
+        let build = |overlay: Option<&Path>| {
+            let mut builder = Config::builder();
+            if let Some(path) = &config_file {
+                builder = builder.add_source(File::from(path.as_path()));
+            }
+            // 2. v2.7.0: Parameters set with ALTER SYSTEM, kept in the data directory
+            if let Some(overlay) = overlay {
+                builder = builder.add_source(File::from(overlay));
+            }
+            // 3. v2.7.0: Command-line flags
+            builder = builder.add_source(overrides.clone());
+            // 4. Override with environment variables (POSTGRUSTQL_*)
+            builder = builder.add_source(
+                Environment::with_prefix("POSTGRUSTQL")
+                    .separator("_")
+            );
+            // 5. Build and deserialize
+            builder.build()?.try_deserialize::<Self>()
+        };
 
-        // 3. Build and deserialize
-        let config = builder.build()?;
-        config.try_deserialize()
+        // The overlay is found through data_dir, so it is read in a second pass
+        let config = build(None)?;
+        let overlay = AutoConfig::path(Path::new(&config.data_dir));
+        if config.in_memory || !overlay.exists() {
+            return Ok((config, None));
+        }
+        Ok((build(Some(&overlay))?, Some(overlay)))
     }
-}
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = ServerConfig::load().unwrap_or_else(|e| {
-        eprintln!("Warning: Failed to load config: {}. Using defaults.", e);
-        ServerConfig {
+    /// Defaults with the command-line flags, for a configuration that did not load
+    fn defaults(args: &Args) -> Self {
+        Self {
             user: default_user(),
             password: default_password(),
             database: default_database(),
             host: default_host(),
-            port: default_port(),
-            data_dir: default_data_dir(),
+            port: args.port.unwrap_or_else(default_port),
+            data_dir: args.data_dir.clone().unwrap_or_else(default_data_dir),
             initdb: default_initdb(),
+            in_memory: false,
+            log_level: args.log_level.clone().unwrap_or_else(default_log_level),
+            log_format: default_log_format(),
+            log_destination: default_log_destination(),
+            log_min_duration_statement: default_log_min_duration_statement(),
+            max_prepared_statements: default_max_prepared_statements(),
+            work_mem: default_work_mem(),
+            max_query_memory: 0,
+            temp_file_limit: default_temp_file_limit(),
+            max_result_rows: 0,
+            max_result_memory: 0,
+            password_min_length: 0,
+            auth_lockout_failures: default_auth_lockout_failures(),
+            auth_lockout_window: default_auth_lockout_window(),
+            auth_lockout_duration: default_auth_lockout_duration(),
+            wal_compression: default_wal_compression(),
+            vectorized_execution: false,
+            metrics_addr: None,
+            http_addr: None,
+            hba: Vec::new(),
+            ident: Vec::new(),
+            ssl_cert_file: None,
+            ssl_key_file: None,
+            ssl_ca_file: None,
         }
-    });
-
-    println!("╔══════════════════════════════════════════════════════════╗");
-    println!("║          PostgrustSQL Server Starting...                 ║");
-    println!("╠══════════════════════════════════════════════════════════╣");
-    println!("║  • Superuser:    {:<39} ║", config.user);
-    println!("║  • Database:     {:<39} ║", config.database);
-    println!("║  • Address:      {}:{:<29} ║", config.host, config.port);
-    println!("╚══════════════════════════════════════════════════════════╝");
-
-    let server = Server::new_with_config(
-        &config.user,
-        &config.password,
-        &config.database,
-        &config.data_dir,
-        config.initdb,
-    )?;
+    }
+}
+
+/// v2.7.0: Set up `tracing` output from the config (level, format, destination)
+fn init_logging(
+    config: &ServerConfig,
+) -> Result<Option<tracing_appender::non_blocking::WorkerGuard>, Box<dyn std::error::Error>> {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.log_level))?;
+
+    let (writer, guard, ansi) = match config.log_destination.as_str() {
+        "stdout" => (BoxMakeWriter::new(std::io::stdout), None, true),
+        "stderr" => (BoxMakeWriter::new(std::io::stderr), None, true),
+        path => {
+            let path = Path::new(path);
+            let directory = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+            let file_name = path.file_name().ok_or("log_destination must name a file")?;
+            std::fs::create_dir_all(directory)?;
+            let appender = tracing_appender::rolling::never(directory, file_name);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(writer), Some(guard), false)
+        }
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(ansi);
+    let initialized = match config.log_format.as_str() {
+        "json" => builder.json().try_init(),
+        "compact" => builder.compact().try_init(),
+        "pretty" => builder.pretty().try_init(),
+        other => return Err(format!("Unknown log_format '{other}' (expected pretty, compact or json)").into()),
+    };
+    initialized.map_err(|e| e.to_string())?;
+
+    Ok(guard)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    if args.version {
+        println!("{}", version_info());
+        return Ok(());
+    }
+
+    let (config, auto_config) = match ServerConfig::load(&args) {
+        Ok(loaded) => loaded,
+        // v2.7.0: A config file named with --config has to load
+        Err(e) if args.config.is_some() => return Err(format!("Failed to load config: {e}").into()),
+        Err(e) => {
+            eprintln!("Warning: Failed to load config: {}. Using defaults.", e);
+            (ServerConfig::defaults(&args), None)
+        }
+    };
+
+    // Keep the guard alive so buffered file logs are flushed on exit
+    let _log_guard = init_logging(&config)?;
+    if let Some(path) = &auto_config {
+        tracing::info!(path = %path.display(), "loaded ALTER SYSTEM settings");
+    }
+
+    // v2.7.0: `--check-data-dir [path]` verifies page files and WAL offline and exits
+    if let Some(data_dir) = &args.check_data_dir {
+        let data_dir = if data_dir.is_empty() { &config.data_dir } else { data_dir };
+        let report = postgrustql::storage::check_data_dir(Path::new(data_dir))?;
+        print!("{report}");
+        std::process::exit(i32::from(!report.is_clean()));
+    }
+
+    // v2.7.0: In single-user mode stdout carries query results only
+    if !args.single_user {
+        println!("╔══════════════════════════════════════════════════════════╗");
+        println!("║          PostgrustSQL Server Starting...                 ║");
+        println!("╠══════════════════════════════════════════════════════════╣");
+        println!("║  • Superuser:    {:<39} ║", config.user);
+        println!("║  • Database:     {:<39} ║", config.database);
+        println!("║  • Address:      {}:{:<29} ║", config.host, config.port);
+        if config.in_memory {
+            println!("║  • Storage:      {:<39} ║", "in-memory (nothing is persisted)");
+        }
+        println!("╚══════════════════════════════════════════════════════════╝");
+    }
+
+    let server = if config.in_memory {
+        Server::new_in_memory(&config.user, &config.password, &config.database)
+    } else {
+        Server::new_with_config(
+            &config.user,
+            &config.password,
+            &config.database,
+            &config.data_dir,
+            config.initdb,
+        )?
+    };
+    let mut server = server
+    .with_hba(HbaConfig::new(config.hba).with_ident_maps(config.ident))
+    .with_log_min_duration_statement(config.log_min_duration_statement)
+    .with_max_prepared_statements(config.max_prepared_statements)
+    .with_work_mem(config.work_mem)
+    .with_max_query_memory(config.max_query_memory)
+    .with_temp_file_limit(config.temp_file_limit)
+    .with_max_result_rows(config.max_result_rows)
+    .with_max_result_memory(config.max_result_memory)
+    .with_password_min_length(config.password_min_length)
+    .with_login_lockout(
+        config.auth_lockout_failures,
+        Duration::from_secs(config.auth_lockout_window),
+        Duration::from_secs(config.auth_lockout_duration),
+    )
+    .with_wal_compression(config.wal_compression)
+    .with_vectorized_execution(config.vectorized_execution);
+    if let Some(metrics_addr) = &config.metrics_addr {
+        server = server.with_metrics_endpoint(metrics_addr);
+    }
+    if let Some(http_addr) = &config.http_addr {
+        server = server.with_http_endpoint(http_addr);
+    }
+    match (&config.ssl_cert_file, &config.ssl_key_file) {
+        (Some(cert), Some(key)) => {
+            let ca = config.ssl_ca_file.as_deref().map(Path::new);
+            server = server.with_tls(tls::load_server_config(Path::new(cert), Path::new(key), ca)?);
+        }
+        (None, None) => {}
+        _ => return Err("ssl_cert_file and ssl_key_file must be set together".into()),
+    }
+
+    // v2.7.0: `--single-user` runs SQL from stdin as the superuser, without a listener
+    if args.single_user {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        return server.run_single_user(&config.user, &config.database, stdin, tokio::io::stdout()).await;
+    }
 
     let bind_addr = format!("{}:{}", config.host, config.port);
     server.start(&bind_addr).await?;
@@ -368,7 +368,7 @@ impl BinaryCopyDecoder {
                 Ok(Value::SmallInt(val))
             }
 
-            DataType::Integer | DataType::Serial | DataType::BigSerial => {
+            DataType::Integer | DataType::BigInt | DataType::Serial | DataType::BigSerial => {
                 if data.len() != 8 {
                     return Err(format!("Invalid Integer length: {}", data.len()));
                 }
@@ -0,0 +1,175 @@
+/// Text COPY format (v2.7.0)
+///
+/// The default format of `COPY ... FROM STDIN` / `TO STDOUT`, and the one
+/// `pg_dump` writes its table data in. One row per line, columns separated by
+/// tabs, `\N` for NULL. Backslash escapes stand for the characters that would
+/// break the layout (`\t`, `\n`, `\r`, `\\`); `\b`, `\f`, `\v`, octal `\NNN`
+/// and hex `\xHH` are read as well. In a script a line holding only `\.` ends
+/// the data.
+use crate::core::Value;
+
+/// Line that ends inline COPY data in a script (`psql`, `pg_dump`)
+pub const END_OF_DATA: &str = "\\.";
+
+/// Text COPY encoder
+pub struct TextCopyEncoder;
+
+impl TextCopyEncoder {
+    /// Encode a row as one line, newline included
+    #[must_use]
+    pub fn encode_row(values: &[Value]) -> String {
+        let mut line = values.iter().map(Self::encode_field).collect::<Vec<_>>().join("\t");
+        line.push('\n');
+        line
+    }
+
+    fn encode_field(value: &Value) -> String {
+        let text = match value {
+            Value::Null => return "\\N".to_string(),
+            Value::Boolean(b) => return if *b { "t" } else { "f" }.to_string(),
+            Value::TimestampTz(ts) => ts.format("%Y-%m-%d %H:%M:%S%:z").to_string(),
+            other => other.to_string(),
+        };
+
+        let mut escaped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            match ch {
+                '\\' => escaped.push_str("\\\\"),
+                '\t' => escaped.push_str("\\t"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                _ => escaped.push(ch),
+            }
+        }
+        escaped
+    }
+}
+
+/// Text COPY decoder
+pub struct TextCopyDecoder;
+
+impl TextCopyDecoder {
+    /// Decode one line into text values (NULL for `\N`); the columns'
+    /// types convert them when the row is inserted
+    #[must_use]
+    pub fn decode_line(line: &str) -> Vec<Value> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        line.split('\t')
+            .map(|field| if field == "\\N" { Value::Null } else { Value::Text(Self::unescape(field)) })
+            .collect()
+    }
+
+    fn unescape(field: &str) -> String {
+        if !field.contains('\\') {
+            return field.to_string();
+        }
+
+        let mut out = String::with_capacity(field.len());
+        let mut chars = field.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                out.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('b') => out.push('\u{8}'),
+                Some('f') => out.push('\u{c}'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('v') => out.push('\u{b}'),
+                Some('x') if chars.peek().is_some_and(char::is_ascii_hexdigit) => {
+                    let mut code = 0;
+                    for _ in 0..2 {
+                        match chars.peek().and_then(|c| c.to_digit(16)) {
+                            Some(digit) => {
+                                code = code * 16 + digit;
+                                chars.next();
+                            }
+                            None => break,
+                        }
+                    }
+                    out.extend(char::from_u32(code));
+                }
+                Some(digit @ '0'..='7') => {
+                    let mut code = digit.to_digit(8).unwrap_or(0);
+                    for _ in 0..2 {
+                        match chars.peek().and_then(|c| c.to_digit(8)) {
+                            Some(digit) => {
+                                code = code * 8 + digit;
+                                chars.next();
+                            }
+                            None => break,
+                        }
+                    }
+                    out.extend(char::from_u32(code));
+                }
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_copy_roundtrip() {
+        let values = vec![
+            Value::Integer(7),
+            Value::Null,
+            Value::Text("tab\there\nnew line \\ backslash".to_string()),
+            Value::Boolean(true),
+        ];
+        let line = TextCopyEncoder::encode_row(&values);
+        assert_eq!(line, "7\t\\N\ttab\\there\\nnew line \\\\ backslash\tt\n");
+
+        let decoded = TextCopyDecoder::decode_line(line.trim_end_matches('\n'));
+        assert_eq!(decoded[0], Value::Text("7".to_string()));
+        assert_eq!(decoded[1], Value::Null);
+        assert_eq!(decoded[2], values[2]);
+        assert_eq!(decoded[3], Value::Text("t".to_string()));
+    }
+
+    #[test]
+    fn test_text_copy_escapes() {
+        assert_eq!(
+            TextCopyDecoder::decode_line("\\101\\x42\\q\t\t\\\\N"),
+            vec![Value::Text("ABq".to_string()), Value::Text(String::new()), Value::Text("\\N".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_text_copy_rows_take_column_types() {
+        use crate::executor::{QueryExecutor, QueryResult};
+        use crate::parser::{parse_statement, InsertSource, Statement};
+        use crate::storage::DatabaseStorage;
+        use crate::transaction::GlobalTransactionManager;
+        use crate::types::Database;
+
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let mut db = Database::new("test".to_string());
+        let mut exec = |db: &mut Database, stmt: Statement| {
+            QueryExecutor::execute(db, stmt, None, &tx_manager, &mut storage, None).unwrap()
+        };
+
+        exec(&mut db, parse_statement("CREATE TABLE public.t (id integer, price numeric(10,2), ok boolean, at timestamp without time zone, note text)").unwrap());
+        // As pg_dump writes table data
+        let data = "1\t12.50\tt\t2024-01-02 10:00:00\tline\\nbreak\n2\t\\N\tf\t\\N\t\\N\n";
+        let rows = data.lines().map(TextCopyDecoder::decode_line).collect();
+        exec(&mut db, Statement::InsertMany { table: "t".to_string(), columns: None, source: InsertSource::Values(rows) });
+
+        let QueryResult::Rows(rows, _) = exec(&mut db, parse_statement("SELECT id, price, ok, note FROM t WHERE price > 10").unwrap()) else {
+            panic!("Expected Rows result");
+        };
+        assert_eq!(rows, vec![vec!["1".to_string(), "12.50".to_string(), "true".to_string(), "line\nbreak".to_string()]]);
+        let QueryResult::Rows(rows, _) = exec(&mut db, parse_statement("SELECT id FROM t WHERE at IS NULL").unwrap()) else {
+            panic!("Expected Rows result");
+        };
+        assert_eq!(rows, vec![vec!["2".to_string()]]);
+    }
+}
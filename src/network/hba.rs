@@ -0,0 +1,232 @@
+use serde::Deserialize;
+use std::net::IpAddr;
+
+/// Authentication method selected by a host-based rule (v2.7.0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HbaMethod {
+    /// Accept the connection without a password
+    Trust,
+    /// Cleartext password exchange
+    Password,
+    /// SCRAM-SHA-256 SASL exchange (see `scram`)
+    Scram,
+    /// Refuse the connection
+    Reject,
+    /// TLS client certificate whose common name maps to the user (v2.7.0),
+    /// like `PostgreSQL`'s `cert` method (`clientcert=verify-full`)
+    Cert,
+}
+
+/// One pg_hba-style rule: (address, database, user) -> method (v2.7.0)
+///
+/// `address` is a CIDR block (`127.0.0.1/32`, `10.0.0.0/8`, `::1/128`), a bare
+/// IP address or `all`. `database` and `user` are names or `all`. `map` names
+/// the ident map `cert` rules use; without it the certificate's common name
+/// must be the user name.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HbaRule {
+    #[serde(default = "HbaRule::any")]
+    pub address: String,
+    #[serde(default = "HbaRule::any")]
+    pub database: String,
+    #[serde(default = "HbaRule::any")]
+    pub user: String,
+    pub method: HbaMethod,
+    #[serde(default)]
+    pub map: Option<String>,
+}
+
+impl HbaRule {
+    fn any() -> String {
+        "all".to_string()
+    }
+
+    /// Does this rule apply to the connection?
+    #[must_use]
+    pub fn matches(&self, addr: IpAddr, database: &str, user: &str) -> bool {
+        let name_matches = |pattern: &str, name: &str| pattern == "all" || pattern == name;
+        name_matches(&self.database, database)
+            && name_matches(&self.user, user)
+            && address_matches(&self.address, addr)
+    }
+}
+
+/// One pg_ident-style mapping (v2.7.0): in map `map`, a client known to the
+/// outside world as `system_user` (the certificate's common name) may log in
+/// as `database_user`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IdentMapping {
+    pub map: String,
+    pub system_user: String,
+    pub database_user: String,
+}
+
+/// Host-based authentication rules, checked top to bottom (v2.7.0)
+///
+/// The first matching rule wins. Without any rules every connection uses
+/// password authentication; with rules, a connection no rule matches is rejected.
+#[derive(Debug, Clone, Default)]
+pub struct HbaConfig {
+    rules: Vec<HbaRule>,
+    ident: Vec<IdentMapping>,
+}
+
+impl HbaConfig {
+    #[must_use]
+    pub const fn new(rules: Vec<HbaRule>) -> Self {
+        Self { rules, ident: Vec::new() }
+    }
+
+    /// Ident maps referenced by the `map` of `cert` rules
+    #[must_use]
+    pub fn with_ident_maps(mut self, ident: Vec<IdentMapping>) -> Self {
+        self.ident = ident;
+        self
+    }
+
+    /// No rules: every connection uses password authentication
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Authentication method for a connection
+    #[must_use]
+    pub fn method_for(&self, addr: IpAddr, database: &str, user: &str) -> HbaMethod {
+        if self.rules.is_empty() {
+            return HbaMethod::Password;
+        }
+        self.rule_for(addr, database, user).map_or(HbaMethod::Reject, |rule| rule.method)
+    }
+
+    /// Rule deciding how a connection authenticates
+    #[must_use]
+    pub fn rule_for(&self, addr: IpAddr, database: &str, user: &str) -> Option<&HbaRule> {
+        self.rules.iter().find(|rule| rule.matches(addr, database, user))
+    }
+
+    /// May the holder of a certificate named `common_name` log in as `user`
+    /// under the ident map `map` (`None`: only as the user of that name)?
+    #[must_use]
+    pub fn ident_allows(&self, map: Option<&str>, common_name: &str, user: &str) -> bool {
+        map.map_or(common_name == user, |map| {
+            self.ident.iter().any(|mapping| {
+                mapping.map == map && mapping.system_user == common_name && mapping.database_user == user
+            })
+        })
+    }
+}
+
+/// Match an address against `all`, a bare IP or a CIDR block
+fn address_matches(pattern: &str, addr: IpAddr) -> bool {
+    if pattern == "all" {
+        return true;
+    }
+
+    let (network, prefix) = match pattern.split_once('/') {
+        Some((network, prefix)) => (network, prefix.parse::<u32>().ok()),
+        None => (pattern, None),
+    };
+    let Ok(network) = network.parse::<IpAddr>() else {
+        return false;
+    };
+
+    // IPv4 clients may arrive as IPv4-mapped IPv6 addresses
+    let addr = match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        IpAddr::V4(_) => addr,
+    };
+
+    match (network, addr) {
+        (IpAddr::V4(network), IpAddr::V4(addr)) => {
+            let prefix = prefix.unwrap_or(32);
+            if prefix > 32 {
+                return false;
+            }
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(addr)) => {
+            let prefix = prefix.unwrap_or(128);
+            if prefix > 128 {
+                return false;
+            }
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(address: &str, database: &str, user: &str, method: HbaMethod) -> HbaRule {
+        HbaRule {
+            address: address.to_string(),
+            database: database.to_string(),
+            user: user.to_string(),
+            method,
+            map: None,
+        }
+    }
+
+    #[test]
+    fn test_address_matches() {
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+        assert!(address_matches("all", local));
+        assert!(address_matches("127.0.0.1", local));
+        assert!(address_matches("127.0.0.0/8", local));
+        assert!(!address_matches("10.0.0.0/8", local));
+        assert!(address_matches("0.0.0.0/0", local));
+        assert!(address_matches("::1/128", "::1".parse().unwrap()));
+        assert!(address_matches("127.0.0.1/32", "::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!address_matches("not-an-ip", local));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let hba = HbaConfig::new(vec![
+            rule("127.0.0.1/32", "all", "all", HbaMethod::Trust),
+            rule("all", "secret", "all", HbaMethod::Reject),
+            rule("0.0.0.0/0", "all", "all", HbaMethod::Password),
+        ]);
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+        let remote: IpAddr = "192.168.1.20".parse().unwrap();
+
+        assert_eq!(hba.method_for(local, "secret", "alice"), HbaMethod::Trust);
+        assert_eq!(hba.method_for(remote, "secret", "alice"), HbaMethod::Reject);
+        assert_eq!(hba.method_for(remote, "postgres", "alice"), HbaMethod::Password);
+        // No IPv6 rule matches
+        assert_eq!(hba.method_for("::2".parse().unwrap(), "postgres", "alice"), HbaMethod::Reject);
+    }
+
+    #[test]
+    fn test_no_rules_means_password() {
+        let hba = HbaConfig::default();
+        assert_eq!(hba.method_for("10.1.2.3".parse().unwrap(), "postgres", "bob"), HbaMethod::Password);
+    }
+
+    #[test]
+    fn test_ident_maps() {
+        let mut cert_rule = rule("all", "all", "all", HbaMethod::Cert);
+        cert_rule.map = Some("certs".to_string());
+        let hba = HbaConfig::new(vec![cert_rule]).with_ident_maps(vec![IdentMapping {
+            map: "certs".to_string(),
+            system_user: "alice.example.com".to_string(),
+            database_user: "alice".to_string(),
+        }]);
+        let rule = hba.rule_for("10.1.2.3".parse().unwrap(), "postgres", "alice").unwrap();
+        assert_eq!(rule.method, HbaMethod::Cert);
+
+        assert!(hba.ident_allows(rule.map.as_deref(), "alice.example.com", "alice"));
+        assert!(!hba.ident_allows(rule.map.as_deref(), "alice.example.com", "bob"));
+        assert!(!hba.ident_allows(rule.map.as_deref(), "alice", "alice"));
+        assert!(!hba.ident_allows(Some("other"), "alice.example.com", "alice"));
+        // Without a map the common name is the user name
+        assert!(hba.ident_allows(None, "alice", "alice"));
+        assert!(!hba.ident_allows(None, "alice.example.com", "alice"));
+    }
+}
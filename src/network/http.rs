@@ -0,0 +1,330 @@
+/// HTTP/JSON query API (v2.7.0)
+///
+/// `POST /query` with a JSON body `{"sql": "...", "database": "..."}` (or the
+/// SQL as a plain text body) runs one statement in autocommit mode and answers
+/// with JSON. Credentials come from HTTP Basic authentication and go through
+/// the same host-based rules as connections over the wire protocol.
+use crate::executor::QueryResult;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::json;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest request body accepted
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+/// Longest request line or header line accepted, with its line ending
+pub const MAX_LINE_SIZE: usize = 8 * 1024;
+/// Most header lines accepted
+pub const MAX_HEADERS: usize = 100;
+
+/// A parsed HTTP request
+#[derive(Debug, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Why a request could not be read
+#[derive(Debug)]
+pub enum RequestError {
+    Io(std::io::Error),
+    Malformed(&'static str),
+    TooLarge,
+    /// A line over `MAX_LINE_SIZE` or more than `MAX_HEADERS` headers
+    HeadersTooLarge,
+}
+
+impl From<std::io::Error> for RequestError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl HttpRequest {
+    /// Read one request; `None` if the client closed the connection first
+    pub async fn read<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Self>, RequestError> {
+        let mut request_line = String::new();
+        if read_line(reader, &mut request_line).await? == 0 {
+            return Ok(None);
+        }
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(RequestError::Malformed("invalid request line"));
+        };
+        let path = target.split('?').next().unwrap_or(target);
+
+        let mut request = Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            ..Self::default()
+        };
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if read_line(reader, &mut line).await? == 0 || line.trim_end().is_empty() {
+                break;
+            }
+            if request.headers.len() == MAX_HEADERS {
+                return Err(RequestError::HeadersTooLarge);
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or(RequestError::Malformed("invalid header line"))?;
+            request.headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+
+        let length = match request.header("content-length") {
+            Some(value) => value.parse::<usize>().map_err(|_| RequestError::Malformed("invalid Content-Length"))?,
+            None => 0,
+        };
+        if length > MAX_BODY_SIZE {
+            return Err(RequestError::TooLarge);
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body).await?;
+
+        Ok(Some(request))
+    }
+
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// User and password from an `Authorization: Basic` header
+    #[must_use]
+    pub fn basic_auth(&self) -> Option<(String, String)> {
+        let encoded = self.header("authorization")?.strip_prefix("Basic ")?;
+        let decoded = String::from_utf8(BASE64.decode(encoded.trim()).ok()?).ok()?;
+        let (user, password) = decoded.split_once(':')?;
+        Some((user.to_string(), password.to_string()))
+    }
+}
+
+/// Read a line of at most `MAX_LINE_SIZE` bytes; the request is read before
+/// authentication, so a client cannot make the server buffer more
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, line: &mut String) -> Result<usize, RequestError> {
+    let read = reader.take(MAX_LINE_SIZE as u64).read_line(line).await?;
+    if read == MAX_LINE_SIZE && !line.ends_with('\n') {
+        return Err(RequestError::HeadersTooLarge);
+    }
+    Ok(read)
+}
+
+/// Body of `POST /query`
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueryRequest {
+    pub sql: String,
+    pub database: Option<String>,
+}
+
+impl QueryRequest {
+    /// A JSON object with `sql` (and optionally `database`), or the SQL as plain text
+    pub fn from_body(body: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(body).map_err(|_| "request body is not valid UTF-8".to_string())?;
+
+        let request = if text.trim_start().starts_with('{') {
+            let value: serde_json::Value =
+                serde_json::from_str(text).map_err(|e| format!("invalid JSON body: {e}"))?;
+            let field = |name: &str| value.get(name).and_then(serde_json::Value::as_str).map(str::to_string);
+            Self {
+                sql: field("sql").ok_or_else(|| "JSON body must have a string \"sql\" field".to_string())?,
+                database: field("database"),
+            }
+        } else {
+            Self { sql: text.to_string(), database: None }
+        };
+
+        if request.sql.trim().is_empty() {
+            return Err("no SQL statement given".to_string());
+        }
+        Ok(request)
+    }
+}
+
+/// JSON body for a query result
+///
+/// Rows come back as arrays of strings; NULL (rendered as the text `NULL` by
+/// the executor) becomes JSON `null`.
+#[must_use]
+pub fn result_json(result: &QueryResult) -> String {
+    match result {
+        QueryResult::Success(message) => json!({
+            "message": message,
+            "row_count": result.row_count(),
+        }),
+        QueryResult::Rows(rows, columns) => {
+            let rows: Vec<Vec<Option<&str>>> = rows
+                .iter()
+                .map(|row| row.iter().map(|value| (value != "NULL").then_some(value.as_str())).collect())
+                .collect();
+            json!({
+                "columns": columns,
+                "rows": rows,
+                "row_count": rows.len(),
+            })
+        }
+    }
+    .to_string()
+}
+
+#[must_use]
+pub fn error_json(message: &str) -> String {
+    json!({ "error": message }).to_string()
+}
+
+/// Write a complete JSON response and close the connection
+pub async fn write_json<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: &str,
+    extra_headers: &[(&str, &str)],
+    body: &str,
+) -> std::io::Result<()> {
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for (name, value) in extra_headers {
+        response.push_str(&format!("{name}: {value}\r\n"));
+    }
+    response.push_str("\r\n");
+    response.push_str(body);
+
+    writer.write_all(response.as_bytes()).await?;
+    writer.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = "POST /query?pretty HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic YWxpY2U6czNjcjN0\r\nContent-Length: 20\r\n\r\n{\"sql\": \"SELECT 1\"}\n";
+        let mut reader = tokio::io::BufReader::new(raw.as_bytes());
+        let request = HttpRequest::read(&mut reader).await.unwrap().unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/query");
+        assert_eq!(request.header("host"), Some("localhost"));
+        assert_eq!(request.basic_auth(), Some(("alice".to_string(), "s3cr3t".to_string())));
+        assert_eq!(
+            QueryRequest::from_body(&request.body).unwrap(),
+            QueryRequest { sql: "SELECT 1".to_string(), database: None }
+        );
+
+        let raw = format!("POST /query HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1);
+        let mut reader = tokio::io::BufReader::new(raw.as_bytes());
+        assert!(matches!(HttpRequest::read(&mut reader).await, Err(RequestError::TooLarge)));
+
+        // Lines and the number of headers are capped
+        let raw = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_SIZE));
+        let mut reader = tokio::io::BufReader::new(raw.as_bytes());
+        assert!(matches!(HttpRequest::read(&mut reader).await, Err(RequestError::HeadersTooLarge)));
+        let raw = format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(MAX_LINE_SIZE));
+        let mut reader = tokio::io::BufReader::new(raw.as_bytes());
+        assert!(matches!(HttpRequest::read(&mut reader).await, Err(RequestError::HeadersTooLarge)));
+        let raw = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Many: 1\r\n".repeat(MAX_HEADERS + 1));
+        let mut reader = tokio::io::BufReader::new(raw.as_bytes());
+        assert!(matches!(HttpRequest::read(&mut reader).await, Err(RequestError::HeadersTooLarge)));
+        let raw = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Many: 1\r\n".repeat(MAX_HEADERS));
+        let mut reader = tokio::io::BufReader::new(raw.as_bytes());
+        assert_eq!(HttpRequest::read(&mut reader).await.unwrap().unwrap().headers.len(), MAX_HEADERS);
+    }
+
+    #[tokio::test]
+    async fn test_http_end_to_end() {
+        let addr = || std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let (sql_addr, http_addr) = (addr(), addr());
+        let server = crate::network::Server::new_in_memory("postgres", "secret", "postgres").with_http_endpoint(&http_addr);
+        tokio::spawn(async move {
+            let _ = server.start(&sql_addr).await;
+        });
+
+        let post = |auth: &'static str, body: &'static str| {
+            let http_addr = http_addr.clone();
+            async move {
+                let mut socket = loop {
+                    match tokio::net::TcpStream::connect(&http_addr).await {
+                        Ok(socket) => break socket,
+                        Err(_) => tokio::time::sleep(std::time::Duration::from_millis(20)).await,
+                    }
+                };
+                let request = format!(
+                    "POST /query HTTP/1.1\r\nAuthorization: Basic {auth}\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(request.as_bytes()).await.unwrap();
+                let mut response = String::new();
+                socket.read_to_string(&mut response).await.unwrap();
+                let (head, body) = response.split_once("\r\n\r\n").unwrap();
+                (head.lines().next().unwrap().to_string(), serde_json::from_str::<serde_json::Value>(body).unwrap())
+            }
+        };
+        // postgres:secret / postgres:wrong
+        let (good, bad) = ("cG9zdGdyZXM6c2VjcmV0", "cG9zdGdyZXM6d3Jvbmc=");
+
+        let (status, _) = post(good, r#"{"sql": "CREATE TABLE t (id INTEGER, note TEXT)"}"#).await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        post(good, "INSERT INTO t VALUES (1, 'hello')").await;
+        post(good, "INSERT INTO t (id) VALUES (2)").await;
+
+        let (status, body) = post(good, r#"{"sql": "SELECT id, note FROM t ORDER BY id", "database": "postgres"}"#).await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body["rows"], json!([["1", "hello"], ["2", null]]));
+
+        let (status, body) = post(bad, "SELECT 1").await;
+        assert_eq!(status, "HTTP/1.1 401 Unauthorized");
+        assert_eq!(body["error"], "Authentication failed");
+        assert_eq!(post(good, "SELECT * FROM missing").await.0, "HTTP/1.1 400 Bad Request");
+        assert_eq!(post(good, "BEGIN").await.0, "HTTP/1.1 400 Bad Request");
+
+        // An oversized header is refused before authentication; the server
+        // reads all of it, so closing does not reset the connection
+        let mut socket = tokio::net::TcpStream::connect(&http_addr).await.unwrap();
+        let request = format!("POST /query HTTP/1.1\r\nX-Long: {}", "a".repeat(MAX_LINE_SIZE - "X-Long: ".len()));
+        socket.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"), "{response}");
+    }
+
+    #[test]
+    fn test_query_request_body() {
+        let request = QueryRequest::from_body(br#"{"sql": "SELECT * FROM t", "database": "shop"}"#).unwrap();
+        assert_eq!(request.database.as_deref(), Some("shop"));
+        assert_eq!(QueryRequest::from_body(b"SELECT 1").unwrap().sql, "SELECT 1");
+        assert!(QueryRequest::from_body(br#"{"query": "SELECT 1"}"#).is_err());
+        assert!(QueryRequest::from_body(b"  ").is_err());
+    }
+
+    #[test]
+    fn test_result_json() {
+        let rows = QueryResult::Rows(
+            vec![vec!["1".to_string(), "NULL".to_string()]],
+            vec!["id".to_string(), "note".to_string()],
+        );
+        let value: serde_json::Value = serde_json::from_str(&result_json(&rows)).unwrap();
+        assert_eq!(value["columns"], json!(["id", "note"]));
+        assert_eq!(value["rows"], json!([["1", null]]));
+        assert_eq!(value["row_count"], json!(1));
+
+        let request = |authorization: &str| HttpRequest {
+            headers: vec![("authorization".to_string(), authorization.to_string())],
+            ..HttpRequest::default()
+        };
+        assert_eq!(
+            request("Basic cG9zdGdyZXM6cG9zdGdyZXM=").basic_auth(),
+            Some(("postgres".to_string(), "postgres".to_string()))
+        );
+        assert_eq!(request("Basic not base64!").basic_auth(), None);
+    }
+}
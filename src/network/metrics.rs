@@ -0,0 +1,191 @@
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Process-wide connection counters (v2.7.0)
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    connections_total: AtomicU64,
+    connections_active: AtomicU64,
+}
+
+impl ServerMetrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a new connection; it stays active until the guard is dropped
+    #[must_use]
+    pub fn connection_opened(self: &Arc<Self>) -> ConnectionGuard {
+        self.connections_total.fetch_add(1, Ordering::Relaxed);
+        self.connections_active.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(Arc::clone(self))
+    }
+
+    pub fn connections_total(&self) -> u64 {
+        self.connections_total.load(Ordering::Relaxed)
+    }
+
+    pub fn connections_active(&self) -> u64 {
+        self.connections_active.load(Ordering::Relaxed)
+    }
+}
+
+/// Marks a connection as closed when dropped (v2.7.0)
+pub struct ConnectionGuard(Arc<ServerMetrics>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.connections_active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Point-in-time values exported on `/metrics` (v2.7.0)
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    pub connections_total: u64,
+    pub connections_active: u64,
+    pub transactions_committed: u64,
+    pub transactions_aborted: u64,
+    pub transactions_active: usize,
+    pub buffer_pool_hits: u64,
+    pub buffer_pool_misses: u64,
+    pub buffer_pool_hit_ratio: f64,
+    pub buffer_pool_pages: usize,
+    pub buffer_pool_dirty_pages: usize,
+    pub wal_bytes_written: u64,
+    pub prepared_executions: u64,
+    pub prepared_reuses: u64,
+    pub prepared_evictions: u64,
+    pub unnamed_cache_hits: u64,
+    pub unnamed_cache_misses: u64,
+    /// (database, table name, live rows)
+    pub table_rows: Vec<(String, String, usize)>,
+}
+
+impl MetricsSnapshot {
+    /// Render in the Prometheus text exposition format (version 0.0.4)
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: &dyn std::fmt::Display| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "{name} {value}");
+        };
+
+        metric("postgrustql_connections_total", "counter", "Client connections accepted since startup.", &self.connections_total);
+        metric("postgrustql_connections_active", "gauge", "Client connections currently open.", &self.connections_active);
+        metric("postgrustql_transactions_committed_total", "counter", "Transactions committed.", &self.transactions_committed);
+        metric("postgrustql_transactions_aborted_total", "counter", "Transactions rolled back.", &self.transactions_aborted);
+        metric("postgrustql_transactions_active", "gauge", "Transactions currently in progress.", &self.transactions_active);
+        metric("postgrustql_buffer_pool_hits_total", "counter", "Page requests served from the buffer pool.", &self.buffer_pool_hits);
+        metric("postgrustql_buffer_pool_misses_total", "counter", "Page requests that had to read from disk.", &self.buffer_pool_misses);
+        metric("postgrustql_buffer_pool_hit_ratio", "gauge", "Buffer pool hit ratio (0..1).", &self.buffer_pool_hit_ratio);
+        metric("postgrustql_buffer_pool_pages", "gauge", "Pages currently cached in the buffer pool.", &self.buffer_pool_pages);
+        metric("postgrustql_buffer_pool_dirty_pages", "gauge", "Cached pages not yet written to disk.", &self.buffer_pool_dirty_pages);
+        metric("postgrustql_wal_bytes_written_total", "counter", "Bytes appended to the write-ahead log since startup.", &self.wal_bytes_written);
+        metric("postgrustql_prepared_statement_executions_total", "counter", "Executions of named prepared statements.", &self.prepared_executions);
+        metric("postgrustql_prepared_statement_reuses_total", "counter", "Executions of named prepared statements executed before.", &self.prepared_reuses);
+        metric("postgrustql_prepared_statement_evictions_total", "counter", "Named prepared statements evicted from full session caches.", &self.prepared_evictions);
+        metric("postgrustql_unnamed_statement_cache_hits_total", "counter", "Unnamed statements parsed from the shared cache.", &self.unnamed_cache_hits);
+        metric("postgrustql_unnamed_statement_cache_misses_total", "counter", "Unnamed statements that had to be parsed.", &self.unnamed_cache_misses);
+
+        out.push_str("# HELP postgrustql_table_rows Live rows per table.\n");
+        out.push_str("# TYPE postgrustql_table_rows gauge\n");
+        for (database, table, rows) in &self.table_rows {
+            let _ = writeln!(
+                out,
+                "postgrustql_table_rows{{database=\"{}\",table=\"{}\"}} {rows}",
+                escape_label(database),
+                escape_label(table)
+            );
+        }
+
+        out
+    }
+}
+
+/// Escape a label value (backslash, double quote and newline)
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Read an HTTP request and return its method and path (headers are skipped)
+pub async fn read_request(socket: &mut TcpStream) -> std::io::Result<Option<(String, String)>> {
+    let mut reader = BufReader::new(socket);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await? == 0 {
+        return Ok(None);
+    }
+
+    // Skip headers up to the empty line
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header).await? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => Ok(Some((method.to_string(), path.to_string()))),
+        _ => Ok(None),
+    }
+}
+
+/// Write a complete HTTP/1.1 response and close the connection
+pub async fn write_response(
+    socket: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    socket.write_all(response.as_bytes()).await?;
+    socket.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_guard() {
+        let metrics = Arc::new(ServerMetrics::new());
+        let first = metrics.connection_opened();
+        let second = metrics.connection_opened();
+        drop(first);
+
+        assert_eq!(metrics.connections_total(), 2);
+        assert_eq!(metrics.connections_active(), 1);
+        drop(second);
+        assert_eq!(metrics.connections_active(), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let snapshot = MetricsSnapshot {
+            connections_total: 3,
+            buffer_pool_hit_ratio: 0.75,
+            table_rows: vec![
+                ("shop".to_string(), "users".to_string(), 42),
+                ("shop".to_string(), "we\"ird".to_string(), 1),
+            ],
+            ..MetricsSnapshot::default()
+        };
+        let text = snapshot.render();
+
+        assert!(text.contains("# TYPE postgrustql_connections_total counter\npostgrustql_connections_total 3\n"));
+        assert!(text.contains("postgrustql_buffer_pool_hit_ratio 0.75\n"));
+        assert!(text.contains("postgrustql_table_rows{database=\"shop\",table=\"users\"} 42\n"));
+        assert!(text.contains("postgrustql_table_rows{database=\"shop\",table=\"we\\\"ird\"} 1\n"));
+    }
+}
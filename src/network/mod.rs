@@ -3,9 +3,24 @@
 pub mod pg_protocol;
 pub mod prepared_statements;
 pub mod copy_binary;
+pub mod copy_text;  // v2.7.0
+pub mod hba;
+pub mod metrics;
+pub mod http;  // v2.7.0
+pub mod output_format;  // v2.7.0
+pub mod parameters;  // v2.7.0
+pub mod scram;  // v2.7.0
 pub mod server;
+pub mod system_config;  // v2.7.0
+pub mod tls;  // v2.7.0
 
 pub use server::Server;
 pub use pg_protocol::{Message, StartupMessage, frontend, transaction_status};
-pub use prepared_statements::{PreparedStatementCache, substitute_parameters};
+pub use prepared_statements::{PreparedStatementCache, SharedStatementCache, decode_binary_parameter, decode_text_parameter, substitute_parameters};
 pub use copy_binary::{BinaryCopyEncoder, BinaryCopyDecoder};
+pub use copy_text::{TextCopyEncoder, TextCopyDecoder};
+pub use output_format::OutputFormat;
+pub use parameters::SessionParameters;
+pub use system_config::AutoConfig;
+pub use hba::{HbaConfig, HbaMethod, HbaRule, IdentMapping};
+pub use metrics::{MetricsSnapshot, ServerMetrics};
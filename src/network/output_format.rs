@@ -0,0 +1,140 @@
+/// Result output modes of the text protocol (v2.7.0)
+///
+/// `\format csv|json|table` switches how a text protocol session prints
+/// query results, so scripts piping queries through netcat get something
+/// they can parse instead of box-drawing tables.
+///
+/// - `table` (default): `comfy_table` grid followed by `(N rows)`
+/// - `csv`: header line, then one line per row, quoted as RFC 4180 asks;
+///   NULL is an empty unquoted field, the empty string is `""`
+/// - `json`: one line per result, the body the HTTP API returns
+///   (`{"columns": [...], "rows": [[...]], "row_count": N}`)
+///
+/// Command tags (`CREATE TABLE`, `INSERT 0 1`, ...) print as plain text in the
+/// table and csv modes; errors always do and start with `Error:`.
+use std::fmt;
+use std::str::FromStr;
+use comfy_table::{Cell, Table as ComfyTable, presets::UTF8_FULL};
+use crate::executor::QueryResult;
+use super::http::result_json;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" | "aligned" => Ok(Self::Table),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown output format '{other}' (expected table, csv or json)")),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Table => "table",
+            Self::Csv => "csv",
+            Self::Json => "json",
+        })
+    }
+}
+
+impl OutputFormat {
+    /// Render a result, trailing newline included
+    #[must_use]
+    pub fn render(self, result: QueryResult) -> String {
+        match (self, result) {
+            (Self::Json, result) => format!("{}\n", result_json(&result)),
+            (_, QueryResult::Success(msg)) => format!("{msg}\n"),
+            (Self::Table, QueryResult::Rows(rows, columns)) => Self::table(&rows, &columns),
+            (Self::Csv, QueryResult::Rows(rows, columns)) => Self::csv(&rows, &columns),
+        }
+    }
+
+    fn table(rows: &[Vec<String>], columns: &[String]) -> String {
+        if rows.is_empty() {
+            return "(0 rows)\n".to_string();
+        }
+
+        let mut table = ComfyTable::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(columns.iter().map(Cell::new));
+        for row in rows {
+            table.add_row(row.iter().map(Cell::new));
+        }
+
+        format!("{}\n({} rows)\n", table, rows.len())
+    }
+
+    fn csv(rows: &[Vec<String>], columns: &[String]) -> String {
+        let mut out = String::new();
+        let mut push_line = |fields: Vec<String>| {
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        };
+        push_line(columns.iter().map(|column| Self::csv_field(column)).collect());
+        for row in rows {
+            // The executor renders NULL as the text NULL
+            push_line(row.iter().map(|value| if value == "NULL" { String::new() } else { Self::csv_field(value) }).collect());
+        }
+        out
+    }
+
+    fn csv_field(value: &str) -> String {
+        if value.is_empty() || value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> QueryResult {
+        QueryResult::Rows(
+            vec![
+                vec!["1".to_string(), "plain".to_string()],
+                vec!["2".to_string(), "say \"hi\", twice\nok".to_string()],
+                vec!["3".to_string(), "NULL".to_string()],
+                vec!["4".to_string(), String::new()],
+            ],
+            vec!["id".to_string(), "note".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_csv_output() {
+        assert_eq!(
+            OutputFormat::Csv.render(rows()),
+            "id,note\n1,plain\n2,\"say \"\"hi\"\", twice\nok\"\n3,\n4,\"\"\n"
+        );
+        assert_eq!(OutputFormat::Csv.render(QueryResult::Rows(vec![], vec!["id".to_string()])), "id\n");
+        assert_eq!(OutputFormat::Csv.render(QueryResult::Success("INSERT 0 1".to_string())), "INSERT 0 1\n");
+    }
+
+    #[test]
+    fn test_json_output() {
+        let line = OutputFormat::Json.render(rows());
+        assert!(line.ends_with('\n') && line.trim_end().lines().count() == 1);
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["columns"], serde_json::json!(["id", "note"]));
+        assert_eq!(value["rows"][2][1], serde_json::Value::Null);
+        assert_eq!(value["row_count"], 4);
+
+        assert_eq!("JSON".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+}
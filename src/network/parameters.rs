@@ -0,0 +1,367 @@
+/// Run-time parameters of a session and the ones reported to the client (v2.7.0)
+///
+/// Every parameter the server knows is listed in `PARAMETERS` with its
+/// default, the values it takes and who may change it. Sessions change their
+/// own ones at startup or with `SET`; the server's ones come from its
+/// configuration (`ALTER SYSTEM` for the next start) and `SHOW` reports the
+/// values the server runs with. Unknown names are rejected, as are values a
+/// parameter does not take.
+///
+/// A fixed set of parameters is reported with `ParameterStatus` messages:
+/// all of them after authentication, and each one again whenever the session
+/// changes it with `SET`, so drivers keep their cached session state
+/// (encoding, time zone, date style) in sync.
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::core::{DatabaseError, Zone};
+use crate::storage::DatabaseStorage;
+
+/// Values a parameter takes
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Text,
+    /// `on`/`off` and their spellings, shown as `on` or `off`
+    Bool,
+    Integer,
+    /// An amount of memory in kB, or with a `kB`, `MB` or `GB` unit
+    Memory,
+    OneOf(&'static [&'static str]),
+    /// Text goes over the wire as UTF-8 only
+    Encoding,
+    TimeZone,
+}
+
+/// Who may change a parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// At startup and with SET
+    Session,
+    /// In the server configuration, SHOW reports the value the server runs with
+    Server,
+    /// Fixed when the server starts
+    Internal,
+}
+
+struct Parameter {
+    /// Name as shown and reported
+    name: &'static str,
+    default: &'static str,
+    kind: Kind,
+    context: Context,
+    /// Sent in `ParameterStatus`
+    reported: bool,
+}
+
+const fn session(name: &'static str, default: &'static str, kind: Kind) -> Parameter {
+    Parameter { name, default, kind, context: Context::Session, reported: false }
+}
+
+const fn reported(name: &'static str, default: &'static str, kind: Kind) -> Parameter {
+    Parameter { name, default, kind, context: Context::Session, reported: true }
+}
+
+const fn server(name: &'static str, default: &'static str, kind: Kind) -> Parameter {
+    Parameter { name, default, kind, context: Context::Server, reported: false }
+}
+
+const fn internal(name: &'static str, default: &'static str, reported: bool) -> Parameter {
+    Parameter { name, default, kind: Kind::Text, context: Context::Internal, reported }
+}
+
+/// Known parameters
+///
+/// `is_superuser` and `session_authorization` depend on the user and are
+/// reported by the server itself. Server defaults are the ones of a server
+/// built without settings; the server records the values it is given.
+const PARAMETERS: &[Parameter] = &[
+    reported("application_name", "", Kind::Text),
+    reported("client_encoding", "UTF8", Kind::Encoding),
+    reported("DateStyle", "ISO, MDY", Kind::Text),
+    reported("default_transaction_read_only", "off", Kind::Bool),
+    reported("IntervalStyle", "postgres", Kind::OneOf(&["postgres", "postgres_verbose", "sql_standard", "iso_8601"])),
+    reported("standard_conforming_strings", "on", Kind::Bool),
+    reported("TimeZone", "UTC", Kind::TimeZone),
+    // Sent by drivers and pg_dump; accepted, the server behaves the same way for any value
+    session("check_function_bodies", "on", Kind::Bool),
+    session(
+        "client_min_messages",
+        "notice",
+        Kind::OneOf(&["debug5", "debug4", "debug3", "debug2", "debug1", "log", "notice", "warning", "error"]),
+    ),
+    session("default_table_access_method", "heap", Kind::Text),
+    session("default_tablespace", "", Kind::Text),
+    session(
+        "default_transaction_isolation",
+        "read committed",
+        Kind::OneOf(&["serializable", "repeatable read", "read committed", "read uncommitted"]),
+    ),
+    session("extra_float_digits", "1", Kind::Integer),
+    session("idle_in_transaction_session_timeout", "0", Kind::Integer),
+    session("lock_timeout", "0", Kind::Integer),
+    session("row_security", "on", Kind::Bool),
+    session("search_path", "\"$user\", public", Kind::Text),
+    session("statement_timeout", "0", Kind::Integer),
+    session("synchronous_commit", "on", Kind::OneOf(&["on", "off", "local", "remote_write", "remote_apply"])),
+    session("transaction_timeout", "0", Kind::Integer),
+    session("xmloption", "content", Kind::OneOf(&["content", "document"])),
+    // Defaults to the server's value (see `scope`)
+    session("work_mem", "4096", Kind::Memory),
+    server("max_query_memory", "0", Kind::Memory),
+    server("temp_file_limit", "-1", Kind::Integer),
+    server("max_result_rows", "0", Kind::Integer),
+    server("max_result_memory", "0", Kind::Memory),
+    server("max_prepared_statements", "1000", Kind::Integer),
+    server("log_min_duration_statement", "-1", Kind::Integer),
+    server("password_min_length", "0", Kind::Integer),
+    server("auth_lockout_failures", "0", Kind::Integer),
+    server("auth_lockout_window", "60", Kind::Integer),
+    server("auth_lockout_duration", "300", Kind::Integer),
+    server("wal_compression", "on", Kind::Bool),
+    server("vectorized_execution", "off", Kind::Bool),
+    server("metrics_addr", "", Kind::Text),
+    server("http_addr", "", Kind::Text),
+    internal("in_hot_standby", "off", true),
+    internal("integer_datetimes", "on", true),
+    internal("server_encoding", "UTF8", true),
+    internal("server_version", "14.0 (PostgrustSQL)", true),
+    internal("is_superuser", "on", false),
+    internal("session_authorization", "", false),
+];
+
+/// Values the server runs with, by parameter name, for its `Context::Server` parameters
+pub type ServerValues = HashMap<&'static str, String>;
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionParameters {
+    /// Values set at startup or with SET, by lowercased name
+    values: HashMap<String, String>,
+    /// Values of the server's parameters
+    server: Arc<ServerValues>,
+    /// Reported parameters changed since the last `take_changes`
+    changed: Vec<&'static str>,
+    /// `TimeZone`, loaded once for rendering and reading timestamptz values
+    time_zone: Zone,
+}
+
+impl SessionParameters {
+    /// Parameters requested at startup (names lowercased, see
+    /// `StartupMessage::runtime_parameters`)
+    pub fn from_startup(parameters: HashMap<String, String>) -> Result<Self, DatabaseError> {
+        let mut session = Self::default();
+        for (name, value) in parameters {
+            session.set(&name, &value)?;
+        }
+        session.changed.clear();
+        Ok(session)
+    }
+
+    /// Show the server's parameters with the values it runs with
+    #[must_use]
+    pub fn with_server_values(mut self, server: Arc<ServerValues>) -> Self {
+        self.server = server;
+        self
+    }
+
+    /// Current value of a known parameter
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let parameter = Self::parameter(name)?;
+        self.values
+            .get(&parameter.name.to_lowercase())
+            .or_else(|| self.server.get(parameter.name))
+            .map_or(Some(parameter.default), |value| Some(value.as_str()))
+    }
+
+    /// `SHOW name`: the column the value is shown under and the value
+    pub fn show(&self, name: &str) -> Result<(String, String), DatabaseError> {
+        let parameter = Self::known(name)?;
+        Ok((parameter.name.to_string(), self.get(name).unwrap_or_default().to_string()))
+    }
+
+    /// `SET name = value`; `DEFAULT` restores the default
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), DatabaseError> {
+        let parameter = Self::known(name)?;
+        if parameter.context != Context::Session {
+            return Err(DatabaseError::CantChangeRuntimeParam(name.to_lowercase()));
+        }
+        let name = parameter.name.to_lowercase();
+
+        // SET TIME ZONE LOCAL is the server's zone, which is the default
+        if value.eq_ignore_ascii_case("default") || (name == "timezone" && value.eq_ignore_ascii_case("local")) {
+            self.values.remove(&name);
+            if name == "timezone" {
+                self.time_zone = Zone::utc();
+            }
+        } else if name == "timezone" {
+            let zone = Zone::parse(value)
+                .ok_or_else(|| DatabaseError::InvalidParameterValue("TimeZone".to_string(), value.to_string()))?;
+            self.values.insert(name, zone.name().to_string());
+            self.time_zone = zone;
+        } else {
+            let value = Self::check_value(parameter, value)?;
+            self.values.insert(name, value);
+        }
+        if parameter.reported && !self.changed.contains(&parameter.name) {
+            self.changed.push(parameter.name);
+        }
+        Ok(())
+    }
+
+    /// Zone timestamptz values are shown and read in
+    #[must_use]
+    pub const fn time_zone(&self) -> &Zone {
+        &self.time_zone
+    }
+
+    /// Run a query of the session: with its time zone, and sorts and hash
+    /// aggregates sized by its `work_mem` if it set one
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let work_mem = self.values.get("work_mem").and_then(|kb| kb.parse::<usize>().ok()).map(|kb| kb.saturating_mul(1024));
+        DatabaseStorage::with_session_work_mem(work_mem, || self.time_zone.scope(f))
+    }
+
+    /// Every reported parameter with its current value, for the end of startup
+    #[must_use]
+    pub fn reported(&self) -> Vec<(&'static str, String)> {
+        PARAMETERS
+            .iter()
+            .filter(|parameter| parameter.reported)
+            .map(|parameter| (parameter.name, self.get(parameter.name).unwrap_or_default().to_string()))
+            .collect()
+    }
+
+    /// Reported parameters changed since the last call, with their new values
+    pub fn take_changes(&mut self) -> Vec<(&'static str, String)> {
+        std::mem::take(&mut self.changed)
+            .into_iter()
+            .map(|name| (name, self.get(name).unwrap_or_default().to_string()))
+            .collect()
+    }
+
+    fn parameter(name: &str) -> Option<&'static Parameter> {
+        PARAMETERS.iter().find(|parameter| parameter.name.eq_ignore_ascii_case(name))
+    }
+
+    fn known(name: &str) -> Result<&'static Parameter, DatabaseError> {
+        Self::parameter(name).ok_or_else(|| DatabaseError::UnrecognizedParameter(name.to_lowercase()))
+    }
+
+    fn check_value(parameter: &Parameter, value: &str) -> Result<String, DatabaseError> {
+        let invalid = || DatabaseError::InvalidParameterValue(parameter.name.to_string(), value.to_string());
+        match parameter.kind {
+            Kind::Text | Kind::TimeZone => Ok(value.to_string()),
+            Kind::Bool => match value.to_lowercase().as_str() {
+                "on" | "true" | "yes" | "1" => Ok("on".to_string()),
+                "off" | "false" | "no" | "0" => Ok("off".to_string()),
+                _ => Err(invalid()),
+            },
+            Kind::Integer => value.trim().parse::<i64>().map(|n| n.to_string()).map_err(|_| invalid()),
+            Kind::Memory => Self::kilobytes(value).map(|kb| kb.to_string()).ok_or_else(invalid),
+            Kind::OneOf(values) => values
+                .iter()
+                .find(|allowed| allowed.eq_ignore_ascii_case(value.trim()))
+                .map(|allowed| (*allowed).to_string())
+                .ok_or_else(invalid),
+            Kind::Encoding if matches!(value.to_uppercase().as_str(), "UTF8" | "UTF-8" | "UNICODE") => Ok("UTF8".to_string()),
+            Kind::Encoding => Err(invalid()),
+        }
+    }
+
+    /// `4096`, `64kB`, `8MB`, `1GB` in kB
+    fn kilobytes(value: &str) -> Option<usize> {
+        let value = value.trim();
+        let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+        let (number, unit) = value.split_at(digits);
+        let factor = match unit.trim() {
+            "" | "kB" => 1,
+            "MB" => 1024,
+            "GB" => 1024 * 1024,
+            _ => return None,
+        };
+        number.parse::<usize>().ok()?.checked_mul(factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_parameters() {
+        let startup = HashMap::from([
+            ("application_name".to_string(), "reporting".to_string()),
+            ("search_path".to_string(), "public".to_string()),
+        ]);
+        let mut parameters = SessionParameters::from_startup(startup).unwrap();
+        assert_eq!(parameters.get("application_name"), Some("reporting"));
+        assert_eq!(parameters.get("timezone"), Some("UTC"));
+        assert!(parameters.reported().contains(&("TimeZone", "UTC".to_string())));
+        assert!(parameters.take_changes().is_empty());
+
+        parameters.set("timezone", "3").unwrap();
+        parameters.set("client_encoding", "unicode").unwrap();
+        parameters.set("search_path", "app, public").unwrap();
+        parameters.set("TimeZone", "UTC").unwrap();
+        assert_eq!(
+            parameters.take_changes(),
+            vec![("TimeZone", "UTC".to_string()), ("client_encoding", "UTF8".to_string())]
+        );
+        assert_eq!(parameters.get("search_path"), Some("app, public"));
+
+        assert_eq!(parameters.time_zone().name(), "UTC");
+        parameters.set("timezone", "-7").unwrap();
+        assert_eq!(parameters.time_zone().name(), "-7");
+        assert_eq!(
+            parameters.set("TimeZone", "Mars/Olympus_Mons").unwrap_err().to_string(),
+            "invalid value for parameter \"TimeZone\": \"Mars/Olympus_Mons\""
+        );
+        assert_eq!(parameters.get("timezone"), Some("-7"));
+        parameters.set("timezone", "LOCAL").unwrap();
+        assert_eq!(parameters.time_zone(), &Zone::utc());
+        parameters.take_changes();
+
+        parameters.set("application_name", "DEFAULT").unwrap();
+        assert_eq!(parameters.take_changes(), vec![("application_name", String::new())]);
+
+        assert!(parameters.set("client_encoding", "LATIN1").unwrap_err().to_string().contains("client_encoding"));
+        let error = parameters.set("server_version", "9.6").unwrap_err();
+        assert_eq!(error.to_string(), "parameter \"server_version\" cannot be changed");
+        assert_eq!(error.sqlstate(), "55P02");
+        assert!(SessionParameters::from_startup(HashMap::from([("client_encoding".to_string(), "SQL_ASCII".to_string())])).is_err());
+    }
+
+    #[test]
+    fn test_known_parameters() {
+        let server = ServerValues::from([("max_result_rows", "500".to_string())]);
+        let mut parameters = SessionParameters::default().with_server_values(Arc::new(server));
+
+        // Unknown names and values a parameter does not take
+        let error = parameters.set("no_such_parameter", "1").unwrap_err();
+        assert_eq!((error.sqlstate(), error.to_string().as_str()), ("42704", "unrecognized configuration parameter \"no_such_parameter\""));
+        assert_eq!(parameters.show("no_such_parameter").unwrap_err().sqlstate(), "42704");
+        assert!(SessionParameters::from_startup(HashMap::from([("no_such_parameter".to_string(), "1".to_string())])).is_err());
+        for (name, value) in [("statement_timeout", "soon"), ("row_security", "maybe"), ("IntervalStyle", "german")] {
+            let error = parameters.set(name, value).unwrap_err();
+            assert_eq!(error.sqlstate(), "22023", "{name} = {value}");
+        }
+
+        // Values are shown the way the parameter spells them
+        parameters.set("row_security", "false").unwrap();
+        parameters.set("intervalstyle", "ISO_8601").unwrap();
+        assert_eq!(parameters.show("ROW_SECURITY").unwrap(), ("row_security".to_string(), "off".to_string()));
+        assert_eq!(parameters.show("intervalstyle").unwrap(), ("IntervalStyle".to_string(), "iso_8601".to_string()));
+        assert_eq!(parameters.show("search_path").unwrap().1, "\"$user\", public");
+
+        // Server parameters show what the server runs with and cannot be SET
+        assert_eq!(parameters.show("max_result_rows").unwrap(), ("max_result_rows".to_string(), "500".to_string()));
+        assert_eq!(parameters.show("work_mem").unwrap().1, "4096");
+        parameters.set("work_mem", "8MB").unwrap();
+        assert_eq!(parameters.show("work_mem").unwrap().1, "8192");
+        assert_eq!(parameters.scope(|| DatabaseStorage::in_memory(1).work_mem()), 8192 * 1024);
+        assert_eq!(DatabaseStorage::in_memory(1).work_mem(), crate::storage::DEFAULT_WORK_MEM);
+        assert_eq!(parameters.set("max_result_rows", "10").unwrap_err().sqlstate(), "55P02");
+        assert_eq!(SessionParameters::kilobytes("8MB"), Some(8192));
+        assert_eq!(SessionParameters::kilobytes("8 MB"), Some(8192));
+        assert_eq!(SessionParameters::kilobytes("8TB"), None);
+    }
+}
@@ -1,10 +1,20 @@
 use bytes::{BufMut, BytesMut};
 use std::collections::HashMap;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use crate::core::DatabaseError;
 
 /// `PostgreSQL` protocol version 3.0
 pub const PROTOCOL_VERSION: i32 = 196_608; // (3 << 16) | 0
 
+/// v2.7.0: Newer 3.x minor versions are answered with `NegotiateProtocolVersion`
+#[must_use]
+pub const fn is_protocol_3(code: i32) -> bool {
+    code >> 16 == PROTOCOL_VERSION >> 16
+}
+
+/// v2.7.0: Prefix of protocol options in the startup packet (`_pq_.name`)
+pub const PROTOCOL_OPTION_PREFIX: &str = "_pq_.";
+
 /// SSL request code
 pub const SSL_REQUEST_CODE: i32 = 80_877_103; // Special code for SSL negotiation
 
@@ -17,12 +27,14 @@ pub mod backend {
     pub const COMMAND_COMPLETE: u8 = b'C';
     pub const ERROR_RESPONSE: u8 = b'E';
     pub const PARAMETER_STATUS: u8 = b'S';
+    pub const NEGOTIATE_PROTOCOL_VERSION: u8 = b'v'; // v2.7.0
     // Extended Query Protocol (v2.4.0)
     pub const PARSE_COMPLETE: u8 = b'1';
     pub const BIND_COMPLETE: u8 = b'2';
     pub const CLOSE_COMPLETE: u8 = b'3';
     pub const NO_DATA: u8 = b'n';
     pub const PARAMETER_DESCRIPTION: u8 = b't';
+    pub const EMPTY_QUERY_RESPONSE: u8 = b'I'; // v2.7.0
     // COPY Protocol (v2.4.0)
     pub const COPY_IN_RESPONSE: u8 = b'G';
     pub const COPY_OUT_RESPONSE: u8 = b'H';
@@ -42,6 +54,7 @@ pub mod frontend {
     pub const EXECUTE: u8 = b'E';
     pub const CLOSE: u8 = b'C';
     pub const SYNC: u8 = b'S';
+    pub const FLUSH: u8 = b'H'; // v2.7.0
     // COPY Protocol (v2.4.0)
     pub const COPY_DATA: u8 = b'd';
     pub const COPY_DONE: u8 = b'c';
@@ -92,9 +105,12 @@ pub mod error_field {
     pub const SEVERITY: u8 = b'S';
     pub const CODE: u8 = b'C';
     pub const MESSAGE: u8 = b'M';
+    pub const POSITION: u8 = b'P';  // v2.7.0
 }
 
 pub struct StartupMessage {
+    /// v2.7.0: Version requested by the client (any 3.x)
+    pub protocol_version: i32,
     pub parameters: HashMap<String, String>,
 }
 
@@ -126,6 +142,28 @@ impl PasswordMessage {
     }
 }
 
+/// `SASLInitialResponse` from client (v2.7.0)
+/// Format: 'p' + Int32(length) + mechanism (cstring) + Int32(data length, -1 for none) + data
+pub struct SaslInitialResponse {
+    pub mechanism: String,
+    pub data: String,
+}
+
+impl SaslInitialResponse {
+    pub fn from_data(data: &[u8]) -> std::io::Result<Self> {
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid SASLInitialResponse");
+        let (mechanism, pos) = extract_cstring(data).ok_or_else(invalid)?;
+        let length = data.get(pos..pos + 4).ok_or_else(invalid)?;
+        let length = i32::from_be_bytes([length[0], length[1], length[2], length[3]]);
+        // -1: no initial response
+        let response = match usize::try_from(length) {
+            Ok(length) => data.get(pos + 4..pos + 4 + length).ok_or_else(invalid)?,
+            Err(_) => &[],
+        };
+        Ok(Self { mechanism, data: String::from_utf8_lossy(response).to_string() })
+    }
+}
+
 /// `ParseMessage` from client (v2.4.0 - Extended Query Protocol)
 /// Format: 'P' + Int32(length) + statement_name (cstring) + query (cstring) + Int16(num_params) + [Int32(param_oid), ...]
 pub struct ParseMessage {
@@ -180,6 +218,10 @@ pub struct BindMessage {
     pub portal_name: String,
     pub statement_name: String,
     pub param_values: Vec<Option<Vec<u8>>>,
+    /// v2.7.0: Parameter format codes (none = all text, one = for every parameter)
+    pub param_formats: Vec<i16>,
+    /// v2.7.0: Result column format codes (none = all text, one = for every column)
+    pub result_formats: Vec<i16>,
 }
 
 impl BindMessage {
@@ -203,8 +245,15 @@ impl BindMessage {
         let num_format_codes = i16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
         pos += 2;
 
-        // Skip format codes (we'll assume text format for simplicity)
-        pos += num_format_codes * 2;
+        // v2.7.0: Read parameter format codes
+        let mut param_formats = Vec::with_capacity(num_format_codes);
+        for _ in 0..num_format_codes {
+            if pos + 2 > data.len() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Missing format code"));
+            }
+            param_formats.push(i16::from_be_bytes([data[pos], data[pos + 1]]));
+            pos += 2;
+        }
 
         // Read parameter values count
         if pos + 2 > data.len() {
@@ -236,10 +285,26 @@ impl BindMessage {
             }
         }
 
+        // v2.7.0: Read result format codes (optional in old clients)
+        let mut result_formats = Vec::new();
+        if pos + 2 <= data.len() {
+            let num_result_formats = i16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+            pos += 2;
+            for _ in 0..num_result_formats {
+                if pos + 2 > data.len() {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Missing result format code"));
+                }
+                result_formats.push(i16::from_be_bytes([data[pos], data[pos + 1]]));
+                pos += 2;
+            }
+        }
+
         Ok(Self {
             portal_name,
             statement_name,
             param_values,
+            param_formats,
+            result_formats,
         })
     }
 }
@@ -330,7 +395,8 @@ impl StartupMessage {
         // Read protocol version (Int32)
         let protocol_version = reader.read_i32().await?;
 
-        if protocol_version != PROTOCOL_VERSION {
+        // v2.7.0: Any 3.x version is accepted (and negotiated down to 3.0)
+        if !is_protocol_3(protocol_version) {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Unsupported protocol version: {protocol_version}"),
@@ -342,6 +408,12 @@ impl StartupMessage {
         let mut params_buf = vec![0u8; params_length];
         reader.read_exact(&mut params_buf).await?;
 
+        Ok(Self::from_parameters(protocol_version, &params_buf))
+    }
+
+    /// v2.7.0: Build from the version and the parameter bytes that follow it
+    #[must_use]
+    pub fn from_parameters(protocol_version: i32, params_buf: &[u8]) -> Self {
         // Parse null-terminated string pairs
         let mut parameters = HashMap::new();
         let mut i = 0;
@@ -374,10 +446,90 @@ impl StartupMessage {
             }
         }
 
-        Ok(Self { parameters })
+        Self {
+            protocol_version,
+            parameters,
+        }
+    }
+
+    /// v2.7.0: Run-time parameters requested at startup (names lowercased)
+    ///
+    /// Everything but `user`, `database`, `replication` and protocol options,
+    /// plus the `-c name=value` / `--name=value` switches given in `options`;
+    /// parameters sent directly win over the ones in `options`.
+    pub fn runtime_parameters(&self) -> Result<HashMap<String, String>, String> {
+        let mut parameters = HashMap::new();
+        if let Some(options) = self.parameters.get("options") {
+            parameters.extend(parse_startup_options(options)?);
+        }
+        for (name, value) in &self.parameters {
+            if !matches!(name.as_str(), "user" | "database" | "options" | "replication")
+                && !name.starts_with(PROTOCOL_OPTION_PREFIX)
+            {
+                parameters.insert(name.to_lowercase(), value.clone());
+            }
+        }
+        Ok(parameters)
+    }
+
+    /// v2.7.0: `NegotiateProtocolVersion` to send when the client asked for a
+    /// newer minor version or for protocol options (`_pq_.*`), none of which
+    /// are supported; `None` if the startup packet is plain 3.0
+    #[must_use]
+    pub fn negotiation(&self) -> Option<Message> {
+        let mut options: Vec<&str> = self
+            .parameters
+            .keys()
+            .filter(|key| key.starts_with(PROTOCOL_OPTION_PREFIX))
+            .map(String::as_str)
+            .collect();
+        if self.protocol_version == PROTOCOL_VERSION && options.is_empty() {
+            return None;
+        }
+        options.sort_unstable();
+        Some(Message::negotiate_protocol_version(PROTOCOL_VERSION & 0xFFFF, &options))
     }
 }
 
+/// v2.7.0: Parse the `options` startup parameter (`-c name=value`,
+/// `-cname=value` or `--name=value`; a backslash escapes the next character)
+fn parse_startup_options(options: &str) -> Result<Vec<(String, String)>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut chars = options.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => word.extend(chars.next()),
+            c if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let mut settings = Vec::new();
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let setting = if word == "-c" {
+            words.next().ok_or_else(|| "missing setting after -c in options".to_string())?
+        } else if let Some(setting) = word.strip_prefix("--").or_else(|| word.strip_prefix("-c")) {
+            setting.to_string()
+        } else {
+            return Err(format!("invalid command-line argument in options: {word}"));
+        };
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("-c {setting} requires a value"))?;
+        settings.push((name.replace('-', "_").to_lowercase(), value.to_string()));
+    }
+    Ok(settings)
+}
+
 pub struct Message {
     buf: BytesMut,
 }
@@ -432,6 +584,42 @@ impl Message {
         msg
     }
 
+    /// `AuthenticationSASL` message (v2.7.0): the SASL mechanisms the client may use
+    #[must_use]
+    pub fn authentication_sasl(mechanisms: &[&str]) -> Self {
+        let mut msg = Self::new();
+        let len_pos = msg.start(backend::AUTHENTICATION);
+        msg.buf.put_i32(10); // 10 = AuthenticationSASL
+        for mechanism in mechanisms {
+            msg.put_cstring(mechanism);
+        }
+        msg.buf.put_u8(0);
+        msg.finish(len_pos);
+        msg
+    }
+
+    /// `AuthenticationSASLContinue` message (v2.7.0)
+    #[must_use]
+    pub fn authentication_sasl_continue(data: &str) -> Self {
+        let mut msg = Self::new();
+        let len_pos = msg.start(backend::AUTHENTICATION);
+        msg.buf.put_i32(11); // 11 = AuthenticationSASLContinue
+        msg.buf.put_slice(data.as_bytes());
+        msg.finish(len_pos);
+        msg
+    }
+
+    /// `AuthenticationSASLFinal` message (v2.7.0)
+    #[must_use]
+    pub fn authentication_sasl_final(data: &str) -> Self {
+        let mut msg = Self::new();
+        let len_pos = msg.start(backend::AUTHENTICATION);
+        msg.buf.put_i32(12); // 12 = AuthenticationSASLFinal
+        msg.buf.put_slice(data.as_bytes());
+        msg.finish(len_pos);
+        msg
+    }
+
     /// `ParameterStatus` message
     #[must_use] 
     pub fn parameter_status(name: &str, value: &str) -> Self {
@@ -453,24 +641,40 @@ impl Message {
         msg
     }
 
-    /// `ErrorResponse` message
+    /// `ErrorResponse` message (v2.7.0: with the error's SQLSTATE and position)
     #[must_use] 
-    pub fn error_response(message: &str) -> Self {
+    pub fn error_response(error: &DatabaseError) -> Self {
+        Self::error_with("ERROR", error.sqlstate(), &error.to_string(), error.position())
+    }
+
+    /// v2.7.0: `ErrorResponse` that ends the connection (severity FATAL)
+    #[must_use]
+    pub fn fatal(code: &str, message: &str) -> Self {
+        Self::error_with("FATAL", code, message, None)
+    }
+
+    fn error_with(severity: &str, code: &str, message: &str, position: Option<usize>) -> Self {
         let mut msg = Self::new();
         let len_pos = msg.start(backend::ERROR_RESPONSE);
 
         // Severity
         msg.buf.put_u8(error_field::SEVERITY);
-        msg.put_cstring("ERROR");
+        msg.put_cstring(severity);
 
         // SQLSTATE code
         msg.buf.put_u8(error_field::CODE);
-        msg.put_cstring("42000"); // Generic syntax error
+        msg.put_cstring(code);
 
         // Message
         msg.buf.put_u8(error_field::MESSAGE);
         msg.put_cstring(message);
 
+        // v2.7.0: Where in the query text a syntax error is
+        if let Some(position) = position {
+            msg.buf.put_u8(error_field::POSITION);
+            msg.put_cstring(&position.to_string());
+        }
+
         // Terminator
         msg.buf.put_u8(0);
 
@@ -481,19 +685,33 @@ impl Message {
     /// `RowDescription` message
     #[must_use] 
     pub fn row_description(columns: &[String]) -> Self {
+        Self::row_description_with_formats(columns, &[])
+    }
+
+    /// v2.7.0: `RowDescription` of a portal with the result formats given in Bind
+    ///
+    /// Every column is described as `text`, whose binary format is the same
+    /// UTF-8 bytes, so `DataRow` does not depend on the formats.
+    #[must_use]
+    pub fn row_description_with_formats(columns: &[String], formats: &[i16]) -> Self {
         let mut msg = Self::new();
         let len_pos = msg.start(backend::ROW_DESCRIPTION);
 
         msg.buf.put_i16(columns.len() as i16);
 
-        for col in columns {
+        for (i, col) in columns.iter().enumerate() {
+            let format = match formats {
+                [] => 0,
+                [format] => *format,
+                formats => formats.get(i).copied().unwrap_or(0),
+            };
             msg.put_cstring(col);
             msg.buf.put_i32(0); // table OID
             msg.buf.put_i16(0); // column attribute number
             msg.buf.put_i32(oid::TEXT); // data type OID (default to TEXT)
             msg.buf.put_i16(-1); // data type size (-1 = variable)
             msg.buf.put_i32(-1); // type modifier
-            msg.buf.put_i16(0); // format code (0 = text)
+            msg.buf.put_i16(format); // format code (0 = text, 1 = binary)
         }
 
         msg.finish(len_pos);
@@ -501,6 +719,9 @@ impl Message {
     }
 
     /// `DataRow` message
+    ///
+    /// v2.7.0: Result rows render NULL as the text `NULL`; it is sent as a
+    /// NULL field (length -1).
     #[must_use] 
     pub fn data_row(values: &[String]) -> Self {
         let mut msg = Self::new();
@@ -509,6 +730,10 @@ impl Message {
         msg.buf.put_i16(values.len() as i16);
 
         for val in values {
+            if val == "NULL" {
+                msg.buf.put_i32(-1);
+                continue;
+            }
             let val_bytes = val.as_bytes();
             msg.buf.put_i32(val_bytes.len() as i32);
             msg.buf.put_slice(val_bytes);
@@ -555,6 +780,43 @@ impl Message {
         msg
     }
 
+    /// `NegotiateProtocolVersion` message (v2.7.0): the newest minor version
+    /// supported and the protocol options that were not recognized
+    #[must_use]
+    pub fn negotiate_protocol_version(minor_version: i32, unrecognized: &[&str]) -> Self {
+        let mut msg = Self::new();
+        let len_pos = msg.start(backend::NEGOTIATE_PROTOCOL_VERSION);
+        msg.buf.put_i32(minor_version);
+        msg.buf.put_i32(unrecognized.len() as i32);
+        for option in unrecognized {
+            msg.put_cstring(option);
+        }
+        msg.finish(len_pos);
+        msg
+    }
+
+    /// `ParameterDescription` message (v2.7.0 - Extended Query Protocol)
+    #[must_use]
+    pub fn parameter_description(param_types: &[i32]) -> Self {
+        let mut msg = Self::new();
+        let len_pos = msg.start(backend::PARAMETER_DESCRIPTION);
+        msg.buf.put_i16(param_types.len() as i16);
+        for &type_oid in param_types {
+            msg.buf.put_i32(type_oid);
+        }
+        msg.finish(len_pos);
+        msg
+    }
+
+    /// `EmptyQueryResponse` message (v2.7.0), sent instead of `CommandComplete` for an empty query
+    #[must_use]
+    pub fn empty_query_response() -> Self {
+        let mut msg = Self::new();
+        let len_pos = msg.start(backend::EMPTY_QUERY_RESPONSE);
+        msg.finish(len_pos);
+        msg
+    }
+
     /// `NoData` message (v2.4.0 - Extended Query Protocol)
     #[must_use]
     pub fn no_data() -> Self {
@@ -625,10 +887,15 @@ impl Message {
 
     /// Send the message to a writer
     pub async fn send<W: AsyncWriteExt + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_all(&self.buf).await?;
+        self.write(writer).await?;
         writer.flush().await?;
         Ok(())
     }
+
+    /// v2.7.0: Write the message without flushing (it may stay in a buffered writer)
+    pub async fn write<W: AsyncWriteExt + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.buf).await
+    }
 }
 
 /// Read a frontend message
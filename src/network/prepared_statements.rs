@@ -1,6 +1,19 @@
-use crate::parser::Statement;
+use crate::core::DatabaseError;
+use crate::executor::QueryResult;
+use crate::parser::{Lexer, Statement, parse_statement};
 use crate::types::Value;
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// v2.7.0: Named statements a session may keep before the least recently used is evicted
+pub const DEFAULT_MAX_PREPARED_STATEMENTS: usize = 1000;
+
+/// v2.7.0: Parsed unnamed statements kept for all sessions
+pub const MAX_SHARED_STATEMENTS: usize = 256;
+
+/// v2.7.0: Error of a prepared statement whose result columns changed under DDL
+pub const STALE_RESULT_TYPE: &str = "cached plan must not change result type";
 
 /// Prepared statement cache entry (v2.4.0 - Extended Query Protocol)
 #[derive(Clone)]
@@ -8,6 +21,38 @@ pub struct PreparedStatement {
     pub query: String,
     pub statement: Option<Statement>,
     pub param_types: Vec<i32>,
+    /// v2.7.0: Times a portal of this statement was executed
+    pub executions: u64,
+    /// v2.7.0: Cache clock value of the last Parse, Bind or Execute
+    last_used: u64,
+    /// v2.7.0: Catalog version and result columns of the last run
+    result_shape: Option<(u64, Vec<String>)>,
+}
+
+impl PreparedStatement {
+    /// v2.7.0: Parameter types for `ParameterDescription`
+    ///
+    /// Types declared in Parse are kept; unspecified ones (0) and parameters
+    /// Parse did not declare are `text`, which drivers send as plain strings.
+    #[must_use]
+    pub fn described_param_types(&self) -> Vec<i32> {
+        let count = placeholder_count(&self.query).max(self.param_types.len());
+        (0..count)
+            .map(|i| match self.param_types.get(i) {
+                Some(&oid) if oid != 0 => oid,
+                _ => super::pg_protocol::oid::TEXT,
+            })
+            .collect()
+    }
+
+    /// v2.7.0: Result columns of the last run, if the catalog has not changed since
+    #[must_use]
+    pub fn result_columns(&self, catalog_version: u64) -> Option<&[String]> {
+        match &self.result_shape {
+            Some((version, columns)) if *version == catalog_version => Some(columns),
+            _ => None,
+        }
+    }
 }
 
 /// Portal - bound prepared statement with parameters (v2.4.0 - Extended Query Protocol)
@@ -15,33 +60,98 @@ pub struct PreparedStatement {
 pub struct Portal {
     pub statement_name: String,
     pub param_values: Vec<Option<Value>>,
+    /// v2.7.0: Result format codes from Bind
+    pub result_formats: Vec<i16>,
+    /// v2.7.0: Result of a portal already run by Describe, sent by the next Execute
+    /// (only for queries whose columns are not known before they run)
+    pub described: Option<QueryResult>,
 }
 
 /// Cache for prepared statements and portals (v2.4.0 - Extended Query Protocol)
-#[derive(Default)]
+///
+/// v2.7.0: Holds at most `max_statements` named statements; adding one more
+/// evicts the least recently used (together with its portals). The unnamed
+/// statement is replaced by every unnamed Parse and never evicted.
 pub struct PreparedStatementCache {
     statements: HashMap<String, PreparedStatement>,
     portals: HashMap<String, Portal>,
+    max_statements: usize,
+    clock: u64,
+    /// v2.7.0: Named statements evicted to stay within `max_statements`
+    pub evictions: u64,
+}
+
+impl Default for PreparedStatementCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PreparedStatementCache {
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_MAX_PREPARED_STATEMENTS)
+    }
+
+    /// v2.7.0: Cache keeping at most `max_statements` named statements
+    #[must_use]
+    pub fn with_capacity(max_statements: usize) -> Self {
         Self {
             statements: HashMap::new(),
             portals: HashMap::new(),
+            max_statements: max_statements.max(1),
+            clock: 0,
+            evictions: 0,
         }
     }
 
+    const fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
     /// Store a prepared statement
-    pub fn add_statement(&mut self, name: String, query: String, param_types: Vec<i32>) {
+    ///
+    /// v2.7.0: Returns the name of the statement evicted to make room, if any
+    pub fn add_statement(&mut self, name: String, query: String, param_types: Vec<i32>) -> Option<String> {
+        let evicted = if !name.is_empty() && !self.statements.contains_key(&name) && self.named_count() >= self.max_statements {
+            self.evict_least_recently_used()
+        } else {
+            None
+        };
+
+        let last_used = self.tick();
         self.statements.insert(
             name,
             PreparedStatement {
                 query,
                 statement: None,
                 param_types,
+                executions: 0,
+                last_used,
+                result_shape: None,
             },
         );
+        evicted
+    }
+
+    /// v2.7.0: Number of named statements
+    #[must_use]
+    pub fn named_count(&self) -> usize {
+        self.statements.keys().filter(|name| !name.is_empty()).count()
+    }
+
+    fn evict_least_recently_used(&mut self) -> Option<String> {
+        let name = self
+            .statements
+            .iter()
+            .filter(|(name, _)| !name.is_empty())
+            .min_by_key(|(_, stmt)| stmt.last_used)
+            .map(|(name, _)| name.clone())?;
+
+        self.statements.remove(&name);
+        self.portals.retain(|_, portal| portal.statement_name != name);
+        self.evictions += 1;
+        Some(name)
     }
 
     /// Get a prepared statement by name
@@ -54,6 +164,39 @@ impl PreparedStatementCache {
         self.statements.get_mut(name)
     }
 
+    /// v2.7.0: Count an execution of `name`; returns how often it has been executed
+    pub fn record_execution(&mut self, name: &str) -> u64 {
+        let now = self.tick();
+        self.statements.get_mut(name).map_or(0, |stmt| {
+            stmt.last_used = now;
+            stmt.executions += 1;
+            stmt.executions
+        })
+    }
+
+    /// v2.7.0: Check the result columns of a run of `name` under `catalog_version`
+    ///
+    /// After a schema change (a newer catalog version) the statement must still
+    /// return the columns it returned before, since clients keep decoding rows
+    /// by the row description they already have. Once that fails the statement
+    /// stays broken until it is prepared again, as in `PostgreSQL`.
+    pub fn check_result_shape(&mut self, name: &str, catalog_version: u64, columns: &[String]) -> Result<(), DatabaseError> {
+        let Some(stmt) = self.statements.get_mut(name) else {
+            return Ok(());
+        };
+        match &mut stmt.result_shape {
+            Some((version, known)) if *version != catalog_version => {
+                if known != columns {
+                    return Err(DatabaseError::FeatureNotSupported(STALE_RESULT_TYPE.to_string()));
+                }
+                *version = catalog_version;
+            }
+            Some(_) => {}
+            shape @ None => *shape = Some((catalog_version, columns.to_vec())),
+        }
+        Ok(())
+    }
+
     /// Remove a prepared statement
     pub fn remove_statement(&mut self, name: &str) -> bool {
         self.statements.remove(name).is_some()
@@ -66,11 +209,17 @@ impl PreparedStatementCache {
         statement_name: String,
         param_values: Vec<Option<Value>>,
     ) {
+        let now = self.tick();
+        if let Some(stmt) = self.statements.get_mut(&statement_name) {
+            stmt.last_used = now;
+        }
         self.portals.insert(
             portal_name,
             Portal {
                 statement_name,
                 param_values,
+                result_formats: Vec::new(),
+                described: None,
             },
         );
     }
@@ -80,6 +229,11 @@ impl PreparedStatementCache {
         self.portals.get(name)
     }
 
+    /// v2.7.0: Get a portal by name for update
+    pub fn get_portal_mut(&mut self, name: &str) -> Option<&mut Portal> {
+        self.portals.get_mut(name)
+    }
+
     /// Remove a portal
     pub fn remove_portal(&mut self, name: &str) -> bool {
         self.portals.remove(name).is_some()
@@ -92,50 +246,179 @@ impl PreparedStatementCache {
     }
 }
 
+/// Parsed unnamed statements shared by all sessions (v2.7.0)
+///
+/// Drivers send most one-off queries as unnamed statements, so the same text
+/// arrives over and over from different connections. The parse result
+/// (`None` if the query does not parse on its own, e.g. because of `$n`
+/// placeholders) is kept by query text for up to `MAX_SHARED_STATEMENTS`
+/// queries. The cache also counts prepared statement activity of every
+/// session for `/metrics`.
+#[derive(Debug, Default)]
+pub struct SharedStatementCache {
+    parsed: Mutex<HashMap<String, (Option<Statement>, u64)>>,
+    clock: AtomicU64,
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    /// Executions of named statements
+    pub executions: AtomicU64,
+    /// Executions of named statements that had been executed before
+    pub reuses: AtomicU64,
+    /// Named statements evicted from session caches
+    pub evictions: AtomicU64,
+}
+
+impl SharedStatementCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `query`, reusing the result of an earlier Parse of the same text
+    pub fn parse(&self, query: &str) -> Option<Statement> {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        let mut parsed = self.parsed.lock().expect("statement cache poisoned");
+        if let Some((stmt, last_used)) = parsed.get_mut(query) {
+            *last_used = now;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return stmt.clone();
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let stmt = parse_statement(query).ok();
+        if parsed.len() >= MAX_SHARED_STATEMENTS
+            && let Some(coldest) = parsed.iter().min_by_key(|(_, (_, last_used))| *last_used).map(|(query, _)| query.clone())
+        {
+            parsed.remove(&coldest);
+        }
+        parsed.insert(query.to_string(), (stmt.clone(), now));
+        stmt
+    }
+
+    /// Count an execution of a named statement executed `executions` times so far
+    pub fn record_execution(&self, executions: u64) {
+        self.executions.fetch_add(1, Ordering::Relaxed);
+        if executions > 1 {
+            self.reuses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.parsed.lock().expect("statement cache poisoned").len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Convert a text-format parameter using the type declared in Parse (v2.7.0)
+///
+/// Unspecified (0) or unknown types, and values that do not parse as the
+/// declared type, stay text.
+#[must_use]
+pub fn decode_text_parameter(text: &str, type_oid: i32) -> Value {
+    use super::pg_protocol::oid;
+
+    let parsed = match type_oid {
+        oid::INT2 | oid::INT4 | oid::INT8 => text.trim().parse().ok().map(Value::Integer),
+        oid::FLOAT8 => text.trim().parse().ok().map(Value::Real),
+        oid::NUMERIC => text.trim().parse().ok().map(Value::Numeric),
+        oid::BOOL => match text.trim().to_ascii_lowercase().as_str() {
+            "t" | "true" | "on" | "1" => Some(Value::Boolean(true)),
+            "f" | "false" | "off" | "0" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::Text(text.to_string()))
+}
+
+/// v2.7.0: Decode a parameter sent in binary format
+///
+/// Integers, floats and booleans have their own binary layout; the binary
+/// format of the string types is their UTF-8 text.
+pub fn decode_binary_parameter(bytes: &[u8], type_oid: i32) -> Result<Value, DatabaseError> {
+    use super::pg_protocol::oid;
+
+    let wrong_length = || DatabaseError::InvalidBinaryParameter(type_oid);
+    match type_oid {
+        oid::INT2 => bytes.try_into().map(|b| Value::SmallInt(i16::from_be_bytes(b))).map_err(|_| wrong_length()),
+        oid::INT4 => bytes.try_into().map(|b| Value::Integer(i32::from_be_bytes(b).into())).map_err(|_| wrong_length()),
+        oid::INT8 => bytes.try_into().map(|b| Value::Integer(i64::from_be_bytes(b))).map_err(|_| wrong_length()),
+        oid::FLOAT8 => bytes.try_into().map(|b| Value::Real(f64::from_be_bytes(b))).map_err(|_| wrong_length()),
+        oid::BOOL => match bytes {
+            [b] => Ok(Value::Boolean(*b != 0)),
+            _ => Err(wrong_length()),
+        },
+        0 | oid::TEXT | oid::VARCHAR | oid::BPCHAR => Ok(Value::Text(String::from_utf8_lossy(bytes).into_owned())),
+        _ => Err(DatabaseError::FeatureNotSupported(format!(
+            "binary format of parameters of type {type_oid} is not supported"
+        ))),
+    }
+}
+
+/// v2.7.0: Number of parameters of a query: the highest `$n` token, so
+/// not counting `$n` in string literals and quoted identifiers
+#[must_use]
+pub fn placeholder_count(query: &str) -> usize {
+    Lexer::new(query).map_while(Result::ok).filter_map(|token| token.parameter_number()).max().unwrap_or(0)
+}
+
 /// Substitute parameters in SQL query ($1, $2, ...) with actual values (v2.4.0)
+///
+/// v2.7.0: only `$n` tokens are replaced; the rest of the query, string
+/// literals included, is copied as it is.
 pub fn substitute_parameters(query: &str, params: &[Option<Value>]) -> String {
-    let mut result = query.to_string();
-
-    // Replace $1, $2, ... with actual values
-    for (i, param) in params.iter().enumerate() {
-        let placeholder = format!("${}", i + 1);
-
-        let value_str = match param {
-            None => "NULL".to_string(),
-            Some(Value::Integer(n)) => n.to_string(),
-            Some(Value::SmallInt(n)) => n.to_string(),
-            Some(Value::Real(f)) => f.to_string(),
-            Some(Value::Numeric(d)) => d.to_string(),
-            Some(Value::Text(s)) | Some(Value::Char(s)) => {
-                format!("'{}'", s.replace('\'', "''")) // Escape single quotes
-            }
-            Some(Value::Boolean(b)) => {
-                if *b {
-                    "TRUE".to_string()
-                } else {
-                    "FALSE".to_string()
-                }
-            }
-            Some(Value::Date(d)) => format!("'{}'", d.format("%Y-%m-%d")),
-            Some(Value::Timestamp(ts)) => format!("'{}'", ts.format("%Y-%m-%d %H:%M:%S")),
-            Some(Value::TimestampTz(ts)) => format!("'{}'", ts.format("%Y-%m-%d %H:%M:%S%z")),
-            Some(Value::Uuid(u)) => format!("'{u}'"),
-            Some(Value::Json(j)) => format!("'{}'", j.replace('\'', "''")),
-            Some(Value::Bytea(b)) => {
-                // Convert to PostgreSQL hex format: \x followed by hex bytes
-                let hex: String = b.iter().map(|byte| format!("{byte:02x}")).collect();
-                format!("'\\x{hex}'")
-            }
-            Some(Value::Enum(_, v)) => format!("'{v}'"),
-            Some(Value::Null) => "NULL".to_string(),
+    let mut result = String::with_capacity(query.len());
+    let mut copied = 0;
+    for token in Lexer::new(query).map_while(Result::ok) {
+        let Some(param) = token.parameter_number().and_then(|n| n.checked_sub(1)).and_then(|i| params.get(i)) else {
+            continue;
         };
-
-        result = result.replace(&placeholder, &value_str);
+        result.push_str(&query[copied..token.offset]);
+        result.push_str(&parameter_literal(param.as_ref()));
+        copied = token.end();
     }
-
+    result.push_str(&query[copied..]);
     result
 }
 
+/// SQL literal of a parameter value
+fn parameter_literal(param: Option<&Value>) -> String {
+    match param {
+        None => "NULL".to_string(),
+        Some(Value::Integer(n)) => n.to_string(),
+        Some(Value::SmallInt(n)) => n.to_string(),
+        Some(Value::Real(f)) => f.to_string(),
+        Some(Value::Numeric(d)) => d.to_string(),
+        Some(Value::Text(s) | Value::Char(s)) => {
+            format!("'{}'", s.replace('\'', "''")) // Escape single quotes
+        }
+        Some(Value::Boolean(b)) => {
+            if *b {
+                "TRUE".to_string()
+            } else {
+                "FALSE".to_string()
+            }
+        }
+        Some(Value::Date(d)) => format!("'{}'", d.format("%Y-%m-%d")),
+        Some(Value::Timestamp(ts)) => format!("'{}'", ts.format("%Y-%m-%d %H:%M:%S")),
+        Some(Value::TimestampTz(ts)) => format!("'{}'", ts.format("%Y-%m-%d %H:%M:%S%z")),
+        Some(Value::Uuid(u)) => format!("'{u}'"),
+        Some(Value::Json(j)) => format!("'{}'", j.replace('\'', "''")),
+        Some(Value::Bytea(b)) => {
+            // Convert to PostgreSQL hex format: \x followed by hex bytes
+            let hex: String = b.iter().map(|byte| format!("{byte:02x}")).collect();
+            format!("'\\x{hex}'")
+        }
+        Some(Value::Enum(_, v)) => format!("'{v}'"),
+        Some(Value::Null) => "NULL".to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,6 +485,16 @@ mod tests {
         assert_eq!(result, "UPDATE users SET email = NULL WHERE id = 42");
     }
 
+    #[test]
+    fn test_decode_text_parameter() {
+        assert_eq!(decode_text_parameter("42", 23), Value::Integer(42));
+        assert_eq!(decode_text_parameter("t", 16), Value::Boolean(true));
+        assert_eq!(decode_text_parameter("1.5", 701), Value::Real(1.5));
+        // Unspecified type and unparsable values stay text
+        assert_eq!(decode_text_parameter("42", 0), Value::Text("42".to_string()));
+        assert_eq!(decode_text_parameter("abc", 23), Value::Text("abc".to_string()));
+    }
+
     #[test]
     fn test_substitute_parameters_escape_quotes() {
         let query = "INSERT INTO users (name) VALUES ($1)";
@@ -211,4 +504,94 @@ mod tests {
         let result = substitute_parameters(query, &params);
         assert_eq!(result, "INSERT INTO users (name) VALUES ('O''Brien')");
     }
+
+    #[test]
+    fn test_described_param_types() {
+        use crate::network::pg_protocol::oid;
+
+        assert_eq!(placeholder_count("SELECT * FROM t WHERE a = $2 AND b = '$9' AND \"$7\" = $10"), 10);
+        assert_eq!(placeholder_count("SELECT 1"), 0);
+
+        let mut cache = PreparedStatementCache::new();
+        cache.add_statement("s".to_string(), "INSERT INTO t VALUES ($1, $2, $3)".to_string(), vec![oid::INT4, 0]);
+        let stmt = cache.get_statement("s").unwrap();
+        assert_eq!(stmt.described_param_types(), vec![oid::INT4, oid::TEXT, oid::TEXT]);
+    }
+
+    #[test]
+    fn test_decode_binary_parameter() {
+        use crate::network::pg_protocol::oid;
+
+        assert!(matches!(decode_binary_parameter(&42_i32.to_be_bytes(), oid::INT4), Ok(Value::Integer(42))));
+        assert!(matches!(decode_binary_parameter(&[1], oid::BOOL), Ok(Value::Boolean(true))));
+        assert!(matches!(decode_binary_parameter(b"it's", oid::TEXT), Ok(Value::Text(s)) if s == "it's"));
+        assert!(decode_binary_parameter(&[0, 1], oid::INT4).is_err());
+        assert!(decode_binary_parameter(&[0; 16], oid::UUID).is_err());
+    }
+
+    #[test]
+    fn test_substitute_ten_parameters() {
+        let params: Vec<_> = (1..=10).map(|n| Some(Value::Integer(n))).collect();
+        assert_eq!(substitute_parameters("SELECT $1, $10", &params), "SELECT 1, 10");
+        // Only $n tokens are parameters
+        let sql = "SELECT $1 || '$1', \"$2\" FROM t -- $3\n";
+        assert_eq!(substitute_parameters(sql, &params), "SELECT 1 || '$1', \"$2\" FROM t -- $3\n");
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = PreparedStatementCache::with_capacity(2);
+        assert_eq!(cache.add_statement("a".to_string(), "SELECT 1".to_string(), vec![]), None);
+        cache.add_statement("b".to_string(), "SELECT 2".to_string(), vec![]);
+        cache.add_portal("p".to_string(), "a".to_string(), vec![]);
+        cache.add_portal("q".to_string(), "b".to_string(), vec![]);
+
+        // "a" was used more recently, so "b" goes (with its portal)
+        assert_eq!(cache.record_execution("a"), 1);
+        assert_eq!(cache.add_statement("c".to_string(), "SELECT 3".to_string(), vec![]), Some("b".to_string()));
+        assert!(cache.get_statement("b").is_none());
+        assert!(cache.get_portal("q").is_none());
+        assert!(cache.get_portal("p").is_some());
+        assert_eq!(cache.evictions, 1);
+
+        // The unnamed statement does not count against the limit
+        assert_eq!(cache.add_statement(String::new(), "SELECT 4".to_string(), vec![]), None);
+        assert_eq!(cache.named_count(), 2);
+        assert_eq!(cache.record_execution("a"), 2);
+    }
+
+    #[test]
+    fn test_stale_result_shape() {
+        let mut cache = PreparedStatementCache::new();
+        cache.add_statement("s".to_string(), "SELECT * FROM t".to_string(), vec![]);
+        let before = vec!["id".to_string()];
+        let after = vec!["id".to_string(), "note".to_string()];
+
+        assert!(cache.check_result_shape("s", 3, &before).is_ok());
+        // A schema change that leaves the columns alone is fine
+        assert!(cache.check_result_shape("s", 4, &before).is_ok());
+        let error = cache.check_result_shape("s", 5, &after).unwrap_err();
+        assert_eq!(error.to_string(), STALE_RESULT_TYPE);
+        assert_eq!(error.sqlstate(), "0A000");
+        assert!(cache.check_result_shape("s", 5, &after).is_err());
+
+        // Preparing it again starts over
+        cache.add_statement("s".to_string(), "SELECT * FROM t".to_string(), vec![]);
+        assert!(cache.check_result_shape("s", 5, &after).is_ok());
+    }
+
+    #[test]
+    fn test_shared_statement_cache() {
+        let cache = SharedStatementCache::new();
+        assert!(cache.parse("SELECT * FROM t").is_some());
+        assert!(cache.parse("SELECT * FROM t").is_some());
+        assert!(cache.parse("NOT SQL").is_none());
+        assert_eq!(cache.hits.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.misses.load(Ordering::Relaxed), 2);
+
+        for i in 0..MAX_SHARED_STATEMENTS {
+            cache.parse(&format!("SELECT {i}"));
+        }
+        assert_eq!(cache.len(), MAX_SHARED_STATEMENTS);
+    }
 }
@@ -0,0 +1,176 @@
+/// SCRAM-SHA-256 authentication, the server side of the SASL exchange (v2.7.0)
+///
+/// RFC 5802 / RFC 7677 as `PostgreSQL` speaks it: the client names the
+/// mechanism in `SASLInitialResponse` with its client-first-message, the
+/// server answers with a server-first-message (`AuthenticationSASLContinue`)
+/// carrying the salt and iteration count of the user's verifier, and the
+/// client proves it knows the password in its client-final-message. The
+/// password itself never crosses the wire. Channel binding is not offered.
+///
+/// A user without a SCRAM verifier (unknown, or with a password stored in the
+/// old format) still goes through the whole exchange against a made-up
+/// verifier and fails at the end, so the answer does not tell which users exist.
+/// Its salt is derived from the user name and a secret of the server process,
+/// so repeated attempts for the same name see the same salt, as for a real user.
+use crate::types::ScramVerifier;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::sync::OnceLock;
+
+/// SASL mechanism name
+pub const MECHANISM: &str = "SCRAM-SHA-256";
+
+/// An exchange after the server-first-message
+#[derive(Debug)]
+pub struct ScramExchange {
+    verifier: ScramVerifier,
+    /// The verifier is made up: the proof is never accepted
+    mock: bool,
+    /// gs2 header of the client-first-message, echoed base64-encoded in `c=`
+    gs2_header: String,
+    client_first_bare: String,
+    server_first: String,
+    nonce: String,
+}
+
+impl ScramExchange {
+    /// Answer a client-first-message of `user`; `verifier` is the user's
+    /// (`None`: no usable verifier). Returns the exchange and the
+    /// server-first-message.
+    pub fn start(user: &str, verifier: Option<ScramVerifier>, client_first: &str) -> Result<(Self, String), String> {
+        let malformed = || "malformed SCRAM message".to_string();
+        // gs2-header: "n,," or "y,," (no channel binding), optional authzid
+        let mut parts = client_first.splitn(3, ',');
+        let (Some(binding), Some(authzid), Some(client_first_bare)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(malformed());
+        };
+        match binding {
+            "n" | "y" => {}
+            _ if binding.starts_with("p=") => return Err("channel binding is not supported".to_string()),
+            _ => return Err(malformed()),
+        }
+        // The user name comes from the startup message; `n=` is ignored, as in PostgreSQL
+        let client_nonce = attribute(client_first_bare, 'r').ok_or_else(malformed)?;
+
+        let mut random = [0u8; 18];
+        getrandom::getrandom(&mut random).expect("system random number generator is unavailable");
+        let nonce = format!("{client_nonce}{}", BASE64.encode(random));
+
+        let mock = verifier.is_none();
+        let verifier = verifier.unwrap_or_else(|| ScramVerifier::mock(mock_secret(), user));
+        let server_first = format!("r={nonce},s={},i={}", BASE64.encode(&verifier.salt), verifier.iterations);
+        let exchange = Self {
+            verifier,
+            mock,
+            gs2_header: format!("{binding},{authzid},"),
+            client_first_bare: client_first_bare.to_string(),
+            server_first: server_first.clone(),
+            nonce,
+        };
+        Ok((exchange, server_first))
+    }
+
+    /// Check a client-final-message: the server-final-message if the proof
+    /// is right, `None` if it is not
+    pub fn finish(&self, client_final: &str) -> Result<Option<String>, String> {
+        let malformed = || "malformed SCRAM message".to_string();
+        let (without_proof, proof) = client_final.rsplit_once(",p=").ok_or_else(malformed)?;
+        let proof = BASE64.decode(proof).map_err(|_| malformed())?;
+        if attribute(without_proof, 'c') != Some(&BASE64.encode(&self.gs2_header)) {
+            return Err("SCRAM channel binding check failed".to_string());
+        }
+        if attribute(without_proof, 'r') != Some(&self.nonce) {
+            return Err("SCRAM nonce does not match".to_string());
+        }
+
+        let auth_message = format!("{},{},{without_proof}", self.client_first_bare, self.server_first);
+        if self.mock || !self.verifier.verify_proof(auth_message.as_bytes(), &proof) {
+            return Ok(None);
+        }
+        let signature = self.verifier.server_signature(auth_message.as_bytes());
+        Ok(Some(format!("v={}", BASE64.encode(signature))))
+    }
+}
+
+/// Secret the salts of made-up verifiers are derived from, random for each server process
+fn mock_secret() -> &'static [u8; 32] {
+    static SECRET: OnceLock<[u8; 32]> = OnceLock::new();
+    SECRET.get_or_init(|| {
+        let mut secret = [0u8; 32];
+        getrandom::getrandom(&mut secret).expect("system random number generator is unavailable");
+        secret
+    })
+}
+
+/// Value of the `name=` attribute of a SCRAM message
+fn attribute(message: &str, name: char) -> Option<&str> {
+    message
+        .split(',')
+        .find_map(|part| part.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scram_exchange() {
+        // The client side of RFC 7677's example, with the server nonce this exchange picked
+        let salt = BASE64.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let verifier = ScramVerifier::with_salt("pencil", salt, 4096);
+        let (exchange, server_first) =
+            ScramExchange::start("user", Some(verifier.clone()), "n,,n=user,r=rOprNGfwEbeRWgbNEkqO").unwrap();
+        assert!(server_first.starts_with("r=rOprNGfwEbeRWgbNEkqO"));
+        assert!(server_first.ends_with(",s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096"));
+
+        let nonce = attribute(&server_first, 'r').unwrap();
+        let without_proof = format!("c=biws,r={nonce}");
+        let auth_message = format!("n=user,r=rOprNGfwEbeRWgbNEkqO,{server_first},{without_proof}");
+        let proof = client_proof("pencil", &verifier, &auth_message);
+        let server_final = exchange.finish(&format!("{without_proof},p={}", BASE64.encode(proof))).unwrap();
+        assert_eq!(
+            server_final,
+            Some(format!("v={}", BASE64.encode(verifier.server_signature(auth_message.as_bytes()))))
+        );
+
+        // A wrong password, a replayed nonce
+        let proof = client_proof("wrong", &verifier, &auth_message);
+        assert_eq!(exchange.finish(&format!("{without_proof},p={}", BASE64.encode(proof))).unwrap(), None);
+        assert!(exchange.finish("c=biws,r=other,p=AAAA").is_err());
+        assert!(ScramExchange::start("nobody", None, "p=tls-server-end-point,,n=,r=abc").is_err());
+
+        // Without a verifier the exchange runs, and fails
+        let (exchange, server_first) = ScramExchange::start("nobody", None, "n,,n=,r=abc").unwrap();
+        let nonce = attribute(&server_first, 'r').unwrap();
+        assert_eq!(exchange.finish(&format!("c=biws,r={nonce},p=AAAA")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_mock_salt_is_stable() {
+        // Every attempt for an unknown user sees the same salt, like a real user's
+        let salt = |user: &str, client_first: &str| {
+            let (_, server_first) = ScramExchange::start(user, None, client_first).unwrap();
+            attribute(&server_first, 's').unwrap().to_string()
+        };
+        assert_eq!(salt("nobody", "n,,n=,r=abc"), salt("nobody", "n,,n=,r=xyz"));
+        assert_ne!(salt("nobody", "n,,n=,r=abc"), salt("somebody", "n,,n=,r=abc"));
+    }
+
+    /// `ClientProof = ClientKey XOR HMAC(StoredKey, AuthMessage)`
+    fn client_proof(password: &str, verifier: &ScramVerifier, auth_message: &str) -> Vec<u8> {
+        use hmac::{Hmac, Mac};
+        use sha2::{Digest, Sha256};
+
+        let hmac = |key: &[u8], message: &[u8]| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes()
+        };
+        let mut salted = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &verifier.salt, verifier.iterations, &mut salted);
+        let client_key = hmac(&salted, b"Client Key");
+        let stored_key = Sha256::digest(client_key);
+        let signature = hmac(&stored_key, auth_message.as_bytes());
+        client_key.iter().zip(signature).map(|(k, s)| k ^ s).collect()
+    }
+}
@@ -1,16 +1,34 @@
-use crate::executor::{QueryExecutor, QueryResult};
+use crate::executor::{Backend, QueriesExecutor, QueryExecutor, QueryResult, TempTables};
+use crate::executor::cursors::ResultChunks;
+use crate::network::hba::{HbaConfig, HbaMethod};
+use crate::network::scram::{self, ScramExchange};
+use crate::network::http::{self, HttpRequest, QueryRequest, RequestError};
+use crate::network::metrics::{self, MetricsSnapshot, ServerMetrics};
 use crate::network::pg_protocol::{self, Message, StartupMessage, frontend, transaction_status};
-use crate::network::prepared_statements::{PreparedStatementCache, substitute_parameters};
-use crate::parser::parse_statement;
-use crate::storage::StorageEngine;
+use crate::network::output_format::OutputFormat;
+use crate::network::parameters::{ServerValues, SessionParameters};
+use crate::network::prepared_statements::{
+    DEFAULT_MAX_PREPARED_STATEMENTS, PreparedStatementCache, SharedStatementCache, decode_binary_parameter,
+    decode_text_parameter, substitute_parameters,
+};
+use crate::network::system_config::AutoConfig;
+use crate::parser::{Lexer, TokenKind, parse_statement};
+use crate::storage::{PageStorage, StorageEngine};
 use crate::transaction::{GlobalTransactionManager, Transaction};
-use crate::types::{DatabaseError, ServerInstance, Value};
-use comfy_table::{Cell, Table as ComfyTable, presets::UTF8_FULL};
-use std::collections::HashMap;
+use crate::types::{DEFAULT_TEMPLATE, Database, DatabaseError, LockoutPolicy, PasswordPolicy, ScramVerifier, ServerInstance, UserOptions, Value, Zone};
+use std::net::IpAddr;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tracing::{Instrument, debug, error, info, warn};
+
+/// v2.7.0: A client connection, plain TCP or TLS
+trait ClientStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ClientStream for T {}
 
 /// Контекст сессии пользователя
 struct SessionContext {
@@ -18,6 +36,9 @@ struct SessionContext {
     database_name: String,
     is_authenticated: bool,
     prepared_statements: PreparedStatementCache, // v2.4.0: Extended Query Protocol
+    temp_tables: TempTables, // v2.7.0: CREATE TEMP TABLE (dropped with the session)
+    parameters: SessionParameters, // v2.7.0: run-time parameters (startup and SET)
+    backend: Option<Backend>, // v2.7.0: this session in pg_stat_activity
 }
 
 impl SessionContext {
@@ -27,10 +48,81 @@ impl SessionContext {
             database_name: String::new(),
             is_authenticated: false,
             prepared_statements: PreparedStatementCache::new(),
+            temp_tables: TempTables::new(),
+            parameters: SessionParameters::default(),
+            backend: None,
+        }
+    }
+
+    /// v2.7.0: Show the session in `pg_stat_activity` until it ends
+    async fn register(&mut self, instance: &ServerInstance, peer: Option<IpAddr>) {
+        let application_name = self.parameters.get("application_name").unwrap_or_default();
+        let datid = match instance.get_database(&self.database_name) {
+            Some(db) => db.read().await.oid,
+            None => 0,
+        };
+        self.backend = Some(instance.sessions.register(
+            &self.username,
+            datid,
+            &self.database_name,
+            application_name,
+            peer.map(|peer| peer.to_string()),
+        ));
+    }
+
+    /// v2.7.0: SET for this session; `pg_stat_activity` follows `application_name`
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), DatabaseError> {
+        self.parameters.set(name, value)?;
+        if name.eq_ignore_ascii_case("application_name")
+            && let Some(backend) = &self.backend
+        {
+            backend.set_application_name(self.parameters.get(name).unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    fn set_active(&self, query: &str) {
+        if let Some(backend) = &self.backend {
+            backend.set_active(query);
+        }
+    }
+
+    fn set_idle(&self, transaction: &Transaction) {
+        if let Some(backend) = &self.backend {
+            backend.set_idle(transaction.is_active());
+            backend.set_xid(transaction.tx_id()); // v2.7.0: for pg_locks
+        }
+    }
+
+    /// v2.7.0: Lock the session's database for a query; `pg_locks` shows the wait
+    async fn read_database<'a>(&self, db: &'a RwLock<Database>) -> RwLockReadGuard<'a, Database> {
+        if let Some(backend) = &self.backend {
+            backend.wait_for_database(false);
+        }
+        let guard = db.read().await;
+        if let Some(backend) = &self.backend {
+            backend.database_locked();
+        }
+        guard
+    }
+
+    /// v2.7.0: Lock the session's database for any other statement
+    async fn write_database<'a>(&self, db: &'a RwLock<Database>) -> RwLockWriteGuard<'a, Database> {
+        if let Some(backend) = &self.backend {
+            backend.wait_for_database(true);
         }
+        let guard = db.write().await;
+        if let Some(backend) = &self.backend {
+            backend.database_locked();
+        }
+        guard
     }
 
     fn authenticate(&mut self, username: String, database_name: String) {
+        // v2.7.0: Tag the connection span with who is connected
+        let span = tracing::Span::current();
+        span.record("user", username.as_str());
+        span.record("database", database_name.as_str());
         self.username = username;
         self.database_name = database_name;
         self.is_authenticated = true;
@@ -38,10 +130,61 @@ impl SessionContext {
 }
 
 pub struct Server {
-    instance: Arc<Mutex<ServerInstance>>,
+    instance: Arc<RwLock<ServerInstance>>,
     storage: Arc<Mutex<StorageEngine>>,
     tx_manager: GlobalTransactionManager,
-    database_storage: Option<Arc<Mutex<crate::storage::DatabaseStorage>>>,
+    page_storage: Option<Arc<PageStorage>>, // v2.7.0: pages of every database
+    settings: ServerSettings, // v2.7.0
+    metrics: Arc<ServerMetrics>, // v2.7.0: connection counters for /metrics
+}
+
+/// Connection-level server settings shared by every client (v2.7.0)
+#[derive(Debug, Clone)]
+struct ServerSettings {
+    /// Host-based authentication rules
+    hba: HbaConfig,
+    /// Log statements that take at least this long (`None` = disabled)
+    log_min_duration_statement: Option<Duration>,
+    /// Address of the Prometheus `/metrics` endpoint (`None` = disabled)
+    metrics_addr: Option<String>,
+    /// Address of the HTTP/JSON query API (`None` = disabled)
+    http_addr: Option<String>,
+    /// Named prepared statements each session keeps before evicting
+    max_prepared_statements: usize,
+    /// Parsed unnamed statements and prepared statement counters of all sessions
+    statements: Arc<SharedStatementCache>,
+    /// Bytes of result rows a query may hold at once (0 = unlimited)
+    max_result_memory: usize,
+    /// Requirements for passwords set with CREATE USER / ALTER USER
+    password_policy: PasswordPolicy,
+    /// Certificate and client CA for TLS connections (`None` = `SSLRequest` is declined)
+    tls: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
+    /// Values of the server parameters the server was built with, for SHOW
+    parameters: Arc<ServerValues>,
+}
+
+impl ServerSettings {
+    /// Note the value of a server parameter for SHOW
+    fn record(&mut self, name: &'static str, value: impl ToString) {
+        Arc::make_mut(&mut self.parameters).insert(name, value.to_string());
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            hba: HbaConfig::default(),
+            log_min_duration_statement: None,
+            metrics_addr: None,
+            http_addr: None,
+            max_prepared_statements: DEFAULT_MAX_PREPARED_STATEMENTS,
+            statements: Arc::new(SharedStatementCache::new()),
+            max_result_memory: 0,
+            password_policy: PasswordPolicy::default(),
+            tls: None,
+            parameters: Arc::new(ServerValues::new()),
+        }
+    }
 }
 
 impl Server {
@@ -56,10 +199,11 @@ impl Server {
         let mut storage = StorageEngine::new(data_dir)?;
 
         // Загружаем существующий ServerInstance или создаем новый
-        let instance = if init_db {
-            // Пробуем загрузить существующий
-            match storage.load_server_instance() {
-                Ok(mut existing) if !existing.databases.is_empty() => {
+        let mut instance = if init_db {
+            // Пробуем загрузить существующий (v2.7.0: если данные есть, но не
+            // читаются, это ошибка - новый экземпляр затер бы их checkpoint'ом)
+            match storage.load_server_instance()? {
+                mut existing if !existing.databases.is_empty() => {
                     // Проверяем, есть ли суперпользователь
                     if !existing.users.contains_key(superuser) {
                         existing.users.insert(
@@ -72,6 +216,8 @@ impl Server {
                     if !existing.databases.contains_key(initial_db) {
                         existing.create_database(initial_db, superuser)?;
                     }
+                    // v2.7.0: Каталоги данных без шаблонов получают template1
+                    existing.create_template_database(superuser);
 
                     existing
                 }
@@ -86,7 +232,7 @@ impl Server {
         };
 
         // Сохраняем начальный snapshot
-        storage.create_checkpoint_instance(&instance)?;
+        storage.create_checkpoint_instance(&instance.snapshot_exclusive())?;
 
         let tx_manager = GlobalTransactionManager::new();
 
@@ -95,12 +241,23 @@ impl Server {
             .map(|v| v == "1" || v.to_lowercase() == "true")
             .unwrap_or(true); // Changed from false to true in v2.0.2
 
-        let database_storage = if use_page_storage {
-            const BUFFER_POOL_SIZE: usize = 1000; // 1000 pages * 8KB = 8MB cache
-            match crate::storage::DatabaseStorage::new(data_dir, BUFFER_POOL_SIZE) {
-                Ok(db_storage) => Some(Arc::new(Mutex::new(db_storage))),
+        // v2.7.0: Pages of each database under data_dir/base/<database>; page
+        // files of older data directories go to the default database
+        let page_storage = if use_page_storage {
+            const BUFFER_POOL_SIZE: usize = 1000; // 1000 pages * 8KB = 8MB cache per database
+            let pages = PageStorage::new(data_dir, BUFFER_POOL_SIZE).and_then(|pages| {
+                if let Some(database) = instance.default_database() {
+                    let moved = pages.migrate_legacy_layout(database)?;
+                    if moved > 0 {
+                        info!(tables = moved, database = %database, "moved page files into the database directory");
+                    }
+                }
+                Ok(pages)
+            });
+            match pages {
+                Ok(pages) => Some(Arc::new(pages)),
                 Err(e) => {
-                    eprintln!("✗ Failed to initialize storage: {e}");
+                    error!(error = %e, "failed to initialize page storage");
                     None
                 }
             }
@@ -109,55 +266,296 @@ impl Server {
         };
 
         Ok(Self {
-            instance: Arc::new(Mutex::new(instance)),
+            instance: Arc::new(RwLock::new(instance)),
             storage: Arc::new(Mutex::new(storage)),
             tx_manager,
-            database_storage,
+            page_storage,
+            settings: ServerSettings::default(),
+            metrics: Arc::new(ServerMetrics::new()),
         })
     }
 
+    /// v2.7.0: Сервер без каталога данных - без WAL, файлов страниц и checkpoint'ов
+    ///
+    /// Все данные теряются при остановке; удобно для тестов, которым нужна
+    /// одноразовая база с быстрым стартом.
+    #[must_use]
+    pub fn new_in_memory(superuser: &str, password: &str, initial_db: &str) -> Self {
+        const BUFFER_POOL_SIZE: usize = 1000;
+
+        Self {
+            instance: Arc::new(RwLock::new(ServerInstance::initialize(superuser, password, initial_db))),
+            storage: Arc::new(Mutex::new(StorageEngine::in_memory())),
+            tx_manager: GlobalTransactionManager::new(),
+            page_storage: Some(Arc::new(PageStorage::in_memory(BUFFER_POOL_SIZE))),
+            settings: ServerSettings::default(),
+            metrics: Arc::new(ServerMetrics::new()),
+        }
+    }
+
+    /// v2.7.0: Use host-based authentication rules (pg_hba-style) for new connections
+    #[must_use]
+    pub fn with_hba(mut self, hba: HbaConfig) -> Self {
+        self.settings.hba = hba;
+        self
+    }
+
+    /// v2.7.0: Serve Prometheus metrics over HTTP at `http://<addr>/metrics`
+    #[must_use]
+    pub fn with_metrics_endpoint(mut self, addr: &str) -> Self {
+        self.settings.metrics_addr = Some(addr.to_string());
+        self.settings.record("metrics_addr", addr);
+        self
+    }
+
+    /// v2.7.0: Accept SQL over HTTP at `http://<addr>/query` (JSON in, JSON out)
+    #[must_use]
+    pub fn with_http_endpoint(mut self, addr: &str) -> Self {
+        self.settings.http_addr = Some(addr.to_string());
+        self.settings.record("http_addr", addr);
+        self
+    }
+
+    /// v2.7.0: Named prepared statements a session keeps before the least recently used is evicted
+    #[must_use]
+    pub fn with_max_prepared_statements(mut self, max: usize) -> Self {
+        self.settings.max_prepared_statements = max;
+        self.settings.record("max_prepared_statements", max);
+        self
+    }
+
+    /// v2.7.0: Memory in kB the rows of one query result may take (0 = unlimited)
+    ///
+    /// Larger results are written to a temp file and go to `PostgreSQL`
+    /// clients in chunks of about this size; the text protocol and the HTTP API, which need the whole result at
+    /// once, refuse them.
+    #[must_use]
+    pub fn with_max_result_memory(mut self, kilobytes: usize) -> Self {
+        self.settings.max_result_memory = kilobytes.saturating_mul(1024);
+        self.settings.record("max_result_memory", kilobytes);
+        self
+    }
+
+    /// v2.7.0: Passwords given to CREATE USER / ALTER USER must have at least
+    /// this many characters (0 = any length)
+    ///
+    /// A ready SCRAM verifier (as `\password` sends) cannot be checked and is accepted.
+    #[must_use]
+    pub fn with_password_min_length(mut self, min_length: usize) -> Self {
+        self.settings.password_policy.min_length = min_length;
+        self.settings.record("password_min_length", min_length);
+        self
+    }
+
+    /// v2.7.0: Accept TLS on `PostgreSQL` connections that ask for it with
+    /// `SSLRequest` (see `network::tls::load_server_config`)
+    #[must_use]
+    pub fn with_tls(mut self, config: Arc<tokio_rustls::rustls::ServerConfig>) -> Self {
+        self.settings.tls = Some(config);
+        self
+    }
+
+    /// v2.7.0: Lock a user out from a client address for `lockout` after
+    /// `max_failures` failed logins within `window` (0 failures = never)
+    #[must_use]
+    pub fn with_login_lockout(mut self, max_failures: u32, window: Duration, lockout: Duration) -> Self {
+        // Called while building the server, before any connection shares the instance
+        if let Some(instance) = Arc::get_mut(&mut self.instance) {
+            instance.get_mut().login_throttle.set_policy(LockoutPolicy { max_failures, window, lockout });
+        }
+        self.settings.record("auth_lockout_failures", max_failures);
+        self.settings.record("auth_lockout_window", window.as_secs());
+        self.settings.record("auth_lockout_duration", lockout.as_secs());
+        self
+    }
+
+    /// v2.7.0: Memory in kB a sort may use before spilling to temp files (`work_mem`)
+    #[must_use]
+    pub fn with_work_mem(mut self, kilobytes: usize) -> Self {
+        // Called while building the server, before any connection shares the storage
+        if let Some(pages) = self.page_storage.as_mut().and_then(Arc::get_mut) {
+            pages.set_work_mem(kilobytes.saturating_mul(1024));
+        }
+        self.settings.record("work_mem", kilobytes);
+        self
+    }
+
+    /// v2.7.0: Memory in kB one query may hold in sorts, hash tables and its
+    /// result (`max_query_memory`, 0 = unlimited)
+    ///
+    /// Sorts and GROUP BY spill past `work_mem` when there is a data
+    /// directory; a query that still needs more fails with "out of memory".
+    #[must_use]
+    pub fn with_max_query_memory(mut self, kilobytes: usize) -> Self {
+        if let Some(pages) = self.page_storage.as_mut().and_then(Arc::get_mut) {
+            pages.set_max_query_memory(kilobytes.saturating_mul(1024));
+        }
+        self.settings.record("max_query_memory", kilobytes);
+        self
+    }
+
+    /// v2.7.0: Disk space in kB the temp files of one query may take
+    /// (`temp_file_limit`, -1 = no limit)
+    #[must_use]
+    pub fn with_temp_file_limit(mut self, kilobytes: i64) -> Self {
+        if let Some(pages) = &self.page_storage {
+            pages.set_temp_file_limit(usize::try_from(kilobytes).ok().map(|kb| kb.saturating_mul(1024)));
+        }
+        self.settings.record("temp_file_limit", kilobytes);
+        self
+    }
+
+    /// v2.7.0: Rows one query may return (`max_result_rows`, 0 = unlimited)
+    ///
+    /// A longer result fails with an error; cursors read past it a FETCH at a time.
+    #[must_use]
+    pub fn with_max_result_rows(mut self, rows: usize) -> Self {
+        if let Some(pages) = self.page_storage.as_mut().and_then(Arc::get_mut) {
+            pages.set_max_result_rows(rows);
+        }
+        self.settings.record("max_result_rows", rows);
+        self
+    }
+
+    /// v2.7.0: Compress large WAL records such as full row images (`wal_compression`)
+    #[must_use]
+    pub fn with_wal_compression(mut self, enabled: bool) -> Self {
+        if let Some(storage) = Arc::get_mut(&mut self.storage) {
+            storage.get_mut().set_wal_compression(enabled);
+        }
+        self.settings.record("wal_compression", if enabled { "on" } else { "off" });
+        self
+    }
+
+    /// v2.7.0: Evaluate filters and projections of sequential scans in batches
+    /// of rows (`vectorized_execution`)
+    #[must_use]
+    pub fn with_vectorized_execution(mut self, enabled: bool) -> Self {
+        if let Some(pages) = self.page_storage.as_mut().and_then(Arc::get_mut) {
+            pages.set_vectorized(enabled);
+        }
+        self.settings.record("vectorized_execution", if enabled { "on" } else { "off" });
+        self
+    }
+
+    /// v2.7.0: Log statements running at least `millis` ms (`log_min_duration_statement`)
+    ///
+    /// A negative value disables the slow query log; 0 logs every statement.
+    #[must_use]
+    pub fn with_log_min_duration_statement(mut self, millis: i64) -> Self {
+        self.settings.log_min_duration_statement =
+            u64::try_from(millis).ok().map(Duration::from_millis);
+        self.settings.record("log_min_duration_statement", millis);
+        self
+    }
+
+    /// v2.7.0: Register a native scalar function for all databases of this server
+    pub fn register_scalar_function<F>(&self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, DatabaseError> + Send + Sync + 'static,
+    {
+        QueryExecutor::register_scalar_function(name, arity, function);
+    }
+
+    /// v2.7.0: Register a native aggregate function for all databases of this server
+    pub fn register_aggregate_function<F>(&self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn crate::executor::Accumulator> + Send + Sync + 'static,
+    {
+        QueryExecutor::register_aggregate_function(name, factory);
+    }
+
+    /// v2.7.0: Call `observer` with every row inserted into, updated in or
+    /// deleted from `table` (of any database), once the transaction commits
+    pub fn on_change<F>(&self, table: &str, observer: F)
+    where
+        F: Fn(&crate::transaction::RowChange) + Send + Sync + 'static,
+    {
+        self.tx_manager.on_change(table, observer);
+    }
+
     pub async fn start(&self, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
         let listener = TcpListener::bind(addr).await?;
-        println!(
-            "
-╔══════════════════════════════════════════════════════════╗
-║       🚀 PostgrustSQL Server is Ready!                   ║
-║                                                          ║
-║  Listening on: {addr:<41} ║
-╚══════════════════════════════════════════════════════════╝
-"
-        );
+        let settings = Arc::new(self.settings.clone());
+
+        // v2.7.0: Optional Prometheus endpoint next to the SQL listener
+        if let Some(metrics_addr) = &self.settings.metrics_addr {
+            let metrics_listener = TcpListener::bind(metrics_addr).await?;
+            info!(addr = %metrics_addr, "metrics endpoint is ready");
+            tokio::spawn(Self::serve_metrics(
+                metrics_listener,
+                Arc::clone(&self.storage),
+                self.tx_manager.clone(),
+                self.page_storage.as_ref().map(Arc::clone),
+                Arc::clone(&self.metrics),
+                Arc::clone(&self.settings.statements),
+            ));
+        }
+        // v2.7.0: Optional HTTP/JSON query API next to the SQL listener
+        if let Some(http_addr) = &self.settings.http_addr {
+            let http_listener = TcpListener::bind(http_addr).await?;
+            info!(addr = %http_addr, "HTTP query endpoint is ready");
+            tokio::spawn(Self::serve_http(
+                http_listener,
+                Arc::clone(&self.instance),
+                Arc::clone(&self.storage),
+                self.tx_manager.clone(),
+                self.page_storage.as_ref().map(Arc::clone),
+                Arc::clone(&settings),
+            ));
+        }
+        info!(%addr, "server is ready");
 
+        let mut connection_id: u64 = 0;
         loop {
-            let (socket, _addr) = listener.accept().await?;
+            let (socket, peer) = listener.accept().await?;
+            connection_id += 1;
 
             let instance = Arc::clone(&self.instance);
             let storage = Arc::clone(&self.storage);
             let tx_manager = self.tx_manager.clone();
-            let database_storage = self.database_storage.as_ref().map(Arc::clone);
-
-            tokio::spawn(async move {
-                if let Err(e) = Self::handle_client_auto(
-                    socket,
-                    instance,
-                    storage,
-                    tx_manager,
-                    database_storage,
-                )
-                .await
-                {
-                    eprintln!("✗ Error handling client: {e}");
+            let page_storage = self.page_storage.as_ref().map(Arc::clone);
+            let settings = Arc::clone(&settings);
+            let connection = self.metrics.connection_opened();
+
+            // v2.7.0: Every event of a connection is logged inside its span
+            let span = tracing::info_span!(
+                "connection",
+                id = connection_id,
+                %peer,
+                user = tracing::field::Empty,
+                database = tracing::field::Empty,
+            );
+            tokio::spawn(
+                async move {
+                    debug!("connection accepted");
+                    match Self::handle_client_auto(
+                        socket,
+                        instance,
+                        storage,
+                        tx_manager,
+                        page_storage,
+                        settings,
+                    )
+                    .await
+                    {
+                        Ok(()) => debug!("connection closed"),
+                        Err(e) => error!(error = %e, "error handling client"),
+                    }
+                    drop(connection);
                 }
-            });
+                .instrument(span),
+            );
         }
     }
 
     async fn handle_client_auto(
         socket: TcpStream,
-        instance: Arc<Mutex<ServerInstance>>,
+        instance: Arc<RwLock<ServerInstance>>,
         storage: Arc<Mutex<StorageEngine>>,
         tx_manager: GlobalTransactionManager,
-        database_storage: Option<Arc<Mutex<crate::storage::DatabaseStorage>>>,
+        page_storage: Option<Arc<PageStorage>>,
+        settings: Arc<ServerSettings>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Peek at the first 8 bytes to determine protocol
         // Use timeout to avoid deadlock with clients that expect server to speak first
@@ -171,7 +569,7 @@ impl Server {
         let is_postgres = if let Ok(Ok(_)) = peek_result {
             // PostgreSQL protocol starts with Int32 length followed by Int32 code
             // Code can be:
-            // - Protocol version 3.0: 196608 (0x00030000)
+            // - Protocol version 3.x: 196608 (0x00030000) and up
             // - SSL request: 80877103 (0x04D2162F)
             // Text protocol starts with ASCII text
             let length = i32::from_be_bytes([peek_buf[0], peek_buf[1], peek_buf[2], peek_buf[3]]);
@@ -180,174 +578,146 @@ impl Server {
             // If length is reasonable (< 10000) and code matches PostgreSQL protocol or SSL request
             length > 0
                 && length < 10000
-                && (code == pg_protocol::PROTOCOL_VERSION || code == pg_protocol::SSL_REQUEST_CODE)
+                && (pg_protocol::is_protocol_3(code) || code == pg_protocol::SSL_REQUEST_CODE)
         } else {
             false
         };
 
         if is_postgres {
-            Self::handle_postgres_client(socket, instance, storage, tx_manager, database_storage)
+            Self::handle_postgres_client(socket, instance, storage, tx_manager, page_storage, &settings)
                 .await
         } else {
-            Self::handle_text_client(socket, instance, storage, tx_manager, database_storage).await
+            Self::handle_text_client(socket, instance, storage, tx_manager, page_storage, &settings)
+                .await
         }
     }
 
     async fn handle_postgres_client(
         socket: TcpStream,
-        instance: Arc<Mutex<ServerInstance>>,
+        instance: Arc<RwLock<ServerInstance>>,
         storage: Arc<Mutex<StorageEngine>>,
-        tx_manager: GlobalTransactionManager,
-        database_storage: Option<Arc<Mutex<crate::storage::DatabaseStorage>>>,
+        mut tx_manager: GlobalTransactionManager,
+        page_storage: Option<Arc<PageStorage>>,
+        settings: &ServerSettings,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let (mut reader, mut writer) = socket.into_split();
+        let peer = socket.peer_addr()?.ip();
+        let mut socket = socket;
 
         // Check for SSLRequest first
         // Read length
-        let length = reader.read_i32().await?;
-        let code = reader.read_i32().await?;
+        let length = socket.read_i32().await?;
+        let code = socket.read_i32().await?;
+
+        // v2.7.0: TLS if the server has a certificate, otherwise SSL is declined with 'N'
+        let (stream, client_name): (Box<dyn ClientStream>, Option<String>) =
+            match (&settings.tls, code == pg_protocol::SSL_REQUEST_CODE) {
+                (Some(tls), true) => {
+                    socket.write_u8(b'S').await?;
+                    socket.flush().await?;
+                    let stream = tokio_rustls::TlsAcceptor::from(Arc::clone(tls)).accept(socket).await?;
+                    let client_name = crate::network::tls::client_common_name(stream.get_ref().1);
+                    debug!(client_certificate = client_name, "TLS connection established");
+                    (Box::new(stream), client_name)
+                }
+                (None, true) => {
+                    socket.write_u8(b'N').await?;
+                    socket.flush().await?;
+                    (Box::new(socket), None)
+                }
+                (_, false) => (Box::new(socket), None),
+            };
+        let (mut reader, mut writer) = tokio::io::split(stream);
 
         let mut session = SessionContext::new();
+        session.prepared_statements = PreparedStatementCache::with_capacity(settings.max_prepared_statements);
 
-        if code == pg_protocol::SSL_REQUEST_CODE {
-            // Reject SSL - send 'N'
-            writer.write_u8(b'N').await?;
-            writer.flush().await?;
-
+        let startup = if code == pg_protocol::SSL_REQUEST_CODE {
             // Now read the actual startup message
-            let startup = StartupMessage::read(&mut reader).await?;
-
-            // v2.0.0: Standard PostgreSQL authentication flow
-            let user = startup
-                .parameters
-                .get("user")
-                .map_or_else(|| "postgres".to_string(), std::string::ToString::to_string);
-            let database_name = startup
-                .parameters
-                .get("database")
-                .map_or_else(|| "postgres".to_string(), std::string::ToString::to_string);
-
-            // Request password from client
-            Message::authentication_cleartext_password()
-                .send(&mut writer)
-                .await?;
-
-            // Read PasswordMessage
-            let msg_type = reader.read_u8().await?;
-            if msg_type != pg_protocol::frontend::PASSWORD {
-                Message::error_response("Expected password message")
-                    .send(&mut writer)
-                    .await?;
-                return Ok(());
-            }
-
-            let password_msg = pg_protocol::PasswordMessage::read(&mut reader).await?;
-
-            // Authenticate
-            let inst = instance.lock().await;
-            if inst.authenticate(&user, &password_msg.password) {
-                session.authenticate(user.clone(), database_name.clone());
-                println!(
-                    "✓ PostgreSQL client authenticated: user={user}, database={database_name}"
-                );
-            } else {
-                drop(inst);
-                Message::error_response("Authentication failed")
-                    .send(&mut writer)
-                    .await?;
-                return Ok(());
-            }
-        } else if code == pg_protocol::PROTOCOL_VERSION {
+            StartupMessage::read(&mut reader).await?
+        } else if pg_protocol::is_protocol_3(code) {
             // This was a regular startup message, parse the rest
             let params_length = (length - 8) as usize;
             let mut params_buf = vec![0u8; params_length];
             reader.read_exact(&mut params_buf).await?;
+            StartupMessage::from_parameters(code, &params_buf)
+        } else {
+            return Err(format!("Unknown protocol code: {code}").into());
+        };
 
-            // Parse parameters manually
-            let mut parameters = HashMap::new();
-            let mut i = 0;
-            while i < params_buf.len() {
-                let key_start = i;
-                while i < params_buf.len() && params_buf[i] != 0 {
-                    i += 1;
-                }
-                if i >= params_buf.len() {
-                    break;
-                }
-                let key = String::from_utf8_lossy(&params_buf[key_start..i]).to_string();
-                i += 1;
-
-                if i >= params_buf.len() {
-                    break;
-                }
-
-                let value_start = i;
-                while i < params_buf.len() && params_buf[i] != 0 {
-                    i += 1;
-                }
-                let value = String::from_utf8_lossy(&params_buf[value_start..i]).to_string();
-                i += 1;
-
-                if !key.is_empty() {
-                    parameters.insert(key, value);
-                }
-            }
-
-            // v2.0.0: Standard PostgreSQL authentication flow
-            let user = parameters
-                .get("user")
-                .map_or_else(|| "postgres".to_string(), std::string::ToString::to_string);
-            let database_name = parameters
-                .get("database")
-                .map_or_else(|| "postgres".to_string(), std::string::ToString::to_string);
+        // v2.7.0: A newer minor version or `_pq_.*` options are declined
+        // instead of failing the connection; the session speaks 3.0
+        if let Some(negotiation) = startup.negotiation() {
+            debug!(version = startup.protocol_version, "negotiating protocol version 3.0");
+            negotiation.send(&mut writer).await?;
+        }
 
-            // Request password from client
-            Message::authentication_cleartext_password()
-                .send(&mut writer)
-                .await?;
+        // v2.0.0: Standard PostgreSQL authentication flow
+        let user = startup
+            .parameters
+            .get("user")
+            .map_or_else(|| "postgres".to_string(), std::string::ToString::to_string);
+        let database_name = startup
+            .parameters
+            .get("database")
+            .map_or_else(|| "postgres".to_string(), std::string::ToString::to_string);
+
+        // v2.7.0: Host-based rules decide how the client authenticates
+        if !Self::authenticate_client(
+            &mut reader,
+            &mut writer,
+            &instance,
+            &settings.hba,
+            peer,
+            client_name.as_deref(),
+            &user,
+            &database_name,
+        )
+        .await?
+        {
+            return Ok(());
+        }
 
-            // Read PasswordMessage
-            let msg_type = reader.read_u8().await?;
-            if msg_type != pg_protocol::frontend::PASSWORD {
-                Message::error_response("Expected password message")
-                    .send(&mut writer)
-                    .await?;
+        // v2.7.0: Startup parameters are applied (and the database checked)
+        // now rather than failing later on the first query
+        let parameters = match startup.runtime_parameters() {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                Message::fatal("42601", &e).send(&mut writer).await?;
                 return Ok(());
             }
-
-            let password_msg = pg_protocol::PasswordMessage::read(&mut reader).await?;
-
-            // Authenticate
-            let inst = instance.lock().await;
-            if inst.authenticate(&user, &password_msg.password) {
-                session.authenticate(user.clone(), database_name.clone());
-                println!(
-                    "✓ PostgreSQL client authenticated: user={user}, database={database_name}"
-                );
-            } else {
-                drop(inst);
-                Message::error_response("Authentication failed")
-                    .send(&mut writer)
-                    .await?;
+        };
+        let parameters = match SessionParameters::from_startup(parameters) {
+            Ok(parameters) => parameters.with_server_values(Arc::clone(&settings.parameters)),
+            Err(e) => {
+                Message::fatal(e.sqlstate(), &e.to_string()).send(&mut writer).await?;
                 return Ok(());
             }
-        } else {
-            return Err(format!("Unknown protocol code: {code}").into());
+        };
+        if instance.read().await.get_database(&database_name).is_none() {
+            let message = format!("database \"{database_name}\" does not exist");
+            Message::fatal("3D000", &message).send(&mut writer).await?;
+            return Ok(());
         }
+        // v2.7.0: Pages of the session's database
+        let database_storage = match page_storage.as_deref().map(|pages| pages.database(&database_name)).transpose() {
+            Ok(database_storage) => database_storage,
+            Err(e) => {
+                Message::fatal("58030", &e.to_string()).send(&mut writer).await?;
+                return Ok(());
+            }
+        };
+        session.authenticate(user, database_name);
+        session.parameters = parameters;
+        session.register(&*instance.read().await, Some(peer)).await;
 
         // Send AuthenticationOk
         Message::authentication_ok().send(&mut writer).await?;
 
         // Send ParameterStatus messages
-        Message::parameter_status("server_version", "14.0 (PostgrustSQL)")
-            .send(&mut writer)
-            .await?;
-        Message::parameter_status("server_encoding", "UTF8")
-            .send(&mut writer)
-            .await?;
-        Message::parameter_status("client_encoding", "UTF8")
-            .send(&mut writer)
-            .await?;
+        // (v2.7.0: every reported parameter, with the values asked for at startup)
+        for (name, value) in session.parameters.reported() {
+            Message::parameter_status(name, &value).write(&mut writer).await?;
+        }
         Message::parameter_status("is_superuser", "on")
             .send(&mut writer)
             .await?;
@@ -361,1115 +731,862 @@ impl Server {
             .await?;
 
         let mut transaction = Transaction::new();
+        // v2.7.0: Extended query pipelining. Responses to Parse/Bind/Describe/
+        // Execute/Close stay buffered until Sync or Flush, and after an error
+        // every message up to the next Sync is skipped.
+        let mut writer = BufWriter::new(writer);
+        let mut in_pipeline = false;
+        let mut skip_until_sync = false;
 
         loop {
+            if !in_pipeline {
+                session.set_idle(&transaction);
+                writer.flush().await?;
+            }
+
             // Read message from client
-            let (msg_type, data) = match pg_protocol::read_frontend_message(&mut reader).await {
+            // (v2.7.0: or end the session when asked to, by DROP DATABASE ... WITH (FORCE))
+            let message = match &session.backend {
+                Some(backend) => tokio::select! {
+                    message = pg_protocol::read_frontend_message(&mut reader) => message,
+                    () = backend.terminated() => {
+                        Message::fatal("57P01", "terminating connection due to administrator command")
+                            .send(&mut writer)
+                            .await?;
+                        break;
+                    }
+                },
+                None => pg_protocol::read_frontend_message(&mut reader).await,
+            };
+            let (msg_type, data) = match message {
                 Ok(msg) => msg,
                 Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
                 Err(e) => return Err(e.into()),
             };
 
+            in_pipeline = matches!(
+                msg_type,
+                frontend::PARSE | frontend::BIND | frontend::DESCRIBE | frontend::EXECUTE | frontend::CLOSE
+            );
+            if skip_until_sync && in_pipeline {
+                continue;
+            }
+
             match msg_type {
                 frontend::QUERY => {
                     // Extract query string
                     let query = if let Some((q, _)) = pg_protocol::extract_cstring(&data) {
                         q
                     } else {
-                        Message::error_response("Invalid query format")
+                        Message::error_response(&DatabaseError::ProtocolViolation("Invalid query format".to_string()))
                             .send(&mut writer)
                             .await?;
-                        Message::ready_for_query(transaction_status::IDLE)
+                        Message::ready_for_query(Self::ready_status(&transaction))
                             .send(&mut writer)
                             .await?;
                         continue;
                     };
 
                     let query = query.trim();
-                    if query.is_empty() {
-                        Message::command_complete("EMPTY").send(&mut writer).await?;
-                        Message::ready_for_query(transaction_status::IDLE)
+                    if Self::is_empty_query(query) {
+                        Message::empty_query_response().send(&mut writer).await?;
+                        Message::ready_for_query(Self::ready_status(&transaction))
                             .send(&mut writer)
                             .await?;
                         continue;
                     }
 
-                    // Execute query
-                    match parse_statement(query) {
-                        Ok(stmt) => {
-                            let mut inst = instance.lock().await;
-
-                            match stmt {
-                                // User management commands
-                                crate::parser::Statement::CreateUser {
-                                    username,
-                                    password,
-                                    is_superuser,
-                                } => {
-                                    match inst.create_user(&username, &password, is_superuser) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
-                                                .send(&mut writer)
-                                                .await?;
-                                            } else {
-                                                Message::command_complete("CREATE USER")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
-                                                .send(&mut writer)
-                                                .await?;
+                    debug!(query, "simple query");
+                    session.set_active(query);
+                    let started = Instant::now();
+
+                    // Execute query (v2.7.0: through the plan cache)
+                    match Self::parse_cached(&instance, &session.database_name, query).await {
+                        // v2.7.0: Run-time parameters belong to the session; reported
+                        // ones are sent back as ParameterStatus
+                        Ok(crate::parser::Statement::Set { name, value }) => {
+                            match session.set_parameter(&name, &value) {
+                                Ok(()) => {
+                                    Message::command_complete("SET").write(&mut writer).await?;
+                                    Self::send_parameter_changes(&mut session, &mut writer).await?;
+                                }
+                                Err(e) => Message::error_response(&e).write(&mut writer).await?,
+                            }
+                            Message::ready_for_query(Self::ready_status(&transaction)).send(&mut writer).await?;
+                        }
+                        Ok(crate::parser::Statement::Show { name }) => {
+                            match Self::show_parameter(&session, &name) {
+                                Ok(result) => Self::send_postgres_result(result, &mut writer).await?,
+                                Err(e) => Message::error_response(&e).write(&mut writer).await?,
+                            }
+                            Message::ready_for_query(Self::ready_status(&transaction)).send(&mut writer).await?;
+                        }
+                        // v2.7.0: Queries only take read locks, so readers run in parallel
+                        Ok(stmt)
+                            if QueryExecutor::is_read_only(&stmt)
+                                && !session.temp_tables.touches(&stmt) =>
+                        {
+                            // v2.7.0: Under max_result_memory the result is sent in chunks
+                            let mut chunks = Self::result_chunks(&stmt, settings);
+                            let inst = instance.read().await;
+                            let executed = match inst.get_database(&session.database_name) {
+                                Some(db) => {
+                                    let db = session.read_database(db).await;
+                                    match Self::check_statement_permissions(
+                                        &inst,
+                                        Some(&db),
+                                        &session.username,
+                                        &stmt,
+                                    ) {
+                                        Some(err_msg) => Err(err_msg),
+                                        None if chunks.is_some() => Ok(None),
+                                        None => {
+                                            Self::execute_read_only(
+                                                &db,
+                                                database_storage.as_ref(),
+                                                &tx_manager,
+                                                stmt,
+                                                query,
+                                                started,
+                                                settings,
+                                                &session.parameters,
+                                            )
+                                            .await
+                                            .map(Some)
                                         }
                                     }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
                                 }
-                                crate::parser::Statement::DropUser { username } => {
-                                    match inst.drop_user(&username) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
+                                None => Err(DatabaseError::DatabaseNotFound(session.database_name.clone())),
+                            };
+                            drop(inst);
+                            let executed = match (executed, chunks.take()) {
+                                (Ok(None), Some(chunks)) => {
+                                    Self::stream_read_only(
+                                        &instance,
+                                        &session.database_name,
+                                        database_storage.as_ref(),
+                                        &tx_manager,
+                                        chunks,
+                                        query,
+                                        started,
+                                        settings,
+                                        &session.parameters,
+                                        &mut writer,
+                                    )
+                                    .await?
+                                }
+                                (Ok(Some(result)), _) => {
+                                    Self::send_postgres_result(result, &mut writer).await?;
+                                    Ok(())
+                                }
+                                (Ok(None), None) => unreachable!("streamed without chunks"),
+                                (Err(e), _) => Err(e),
+                            };
+                            if let Err(e) = executed {
+                                Message::error_response(&e).send(&mut writer).await?;
+                            }
+                            Message::ready_for_query(Self::ready_status(&transaction)).send(&mut writer).await?;
+                        }
+                        // v2.7.0: Users, roles, databases and privileges lock the whole instance
+                        Ok(stmt) if Self::is_server_statement(&stmt) => {
+                            // The instance is unlocked before anything is written to the client
+                            let mut inst = instance.write().await;
+
+                            // v2.3.0: Check permissions BEFORE executing anything
+                            let db = match inst.get_database(&session.database_name) {
+                                Some(db) => Some(db.read().await),
+                                None => None,
+                            };
+                            let denied = Self::check_statement_permissions(
+                                &inst,
+                                db.as_deref(),
+                                &session.username,
+                                &stmt,
+                            );
+                            drop(db);
+                            let executed = match denied {
+                                Some(err_msg) => Err(err_msg),
+                                None => {
+                                    Self::execute_server_statement(
+                                        &mut inst,
+                                        stmt,
+                                        &session,
+                                        &storage,
+                                        page_storage.as_deref(),
+                                        settings,
+                                    )
+                                    .await
+                                }
+                            };
+                            drop(inst);
+
+                            match executed {
+                                Ok(result) => Self::send_postgres_result(result, &mut writer).await?,
+                                Err(e) => Message::error_response(&e).write(&mut writer).await?,
+                            }
+                            Message::ready_for_query(Self::ready_status(&transaction))
+                                .send(&mut writer)
+                                .await?;
+                        }
+                        // v2.7.0: Everything else only locks the session's database
+                        Ok(stmt) => {
+                            let inst = instance.read().await;
+                            let Some(db) = inst.get_database(&session.database_name) else {
+                                Message::error_response(&DatabaseError::DatabaseNotFound(session.database_name.clone()))
+                                    .send(&mut writer)
+                                    .await?;
+                                Message::ready_for_query(Self::ready_status(&transaction))
+                                    .send(&mut writer)
+                                    .await?;
+                                continue;
+                            };
+                            let mut db = session.write_database(db).await;
+
+                            // v2.3.0: Check permissions BEFORE executing anything
+                            // (v2.7.0: for every statement; the session owns its temp tables)
+                            if !session.temp_tables.touches(&stmt)
+                                && let Some(denied) = Self::check_statement_permissions(
+                                    &inst,
+                                    Some(&db),
+                                    &session.username,
+                                    &stmt,
+                                )
+                            {
+                                Message::error_response(&denied)
+                                    .send(&mut writer)
+                                    .await?;
+                                Message::ready_for_query(Self::ready_status(&transaction))
+                                    .send(&mut writer)
+                                    .await?;
+                                continue;
+                            }
+
+                            // v2.3.0: First transform CREATE TABLE to add owner before permission check
+                            let stmt_with_owner_early = Self::with_owner(stmt, &session.username);
+
+                            match stmt_with_owner_early {
+                                crate::parser::Statement::Begin => {
+                                    if transaction.is_active() {
+                                        Message::error_response(&DatabaseError::TransactionAlreadyActive)
+                                            .send(&mut writer)
+                                            .await?;
+                                    } else {
+                                        let (tx_id, snapshot) =
+                                            tx_manager.begin_transaction();
+                                        transaction.begin(tx_id, snapshot, &db);
+                                        Message::command_complete("BEGIN")
+                                            .send(&mut writer)
+                                            .await?;
+                                    }
+                                    Message::ready_for_query(
+                                        transaction_status::IN_TRANSACTION,
+                                    )
+                                    .send(&mut writer)
+                                    .await?;
+                                }
+                                crate::parser::Statement::Commit => {
+                                    if transaction.is_active() {
+                                        // Remove from active transactions in GlobalTransactionManager
+                                        if let Some(tx_id) = transaction.tx_id() {
+                                            tx_manager.commit_transaction(tx_id);
+                                        }
+                                        transaction.commit();
+                                        tx_manager.pin_snapshot(None);
+                                        // v2.7.0: The checkpoint snapshot locks every database
+                                        drop(db);
+                                        if let Err(e) = session.temp_tables.end_transaction() {
+                                            Message::error_response(&e)
                                                 .send(&mut writer)
                                                 .await?;
-                                            } else {
-                                                Message::command_complete("DROP USER")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
+                                        } else if let Err(e) = Self::save_instance(&inst, &storage).await {
+                                            Message::error_response(&DatabaseError::Internal(format!(
+                                                "Failed to persist: {e}"
+                                            )))
+                                            .send(&mut writer)
+                                            .await?;
+                                        } else {
+                                            Message::command_complete("COMMIT")
                                                 .send(&mut writer)
                                                 .await?;
                                         }
+                                    } else {
+                                        Message::error_response(&DatabaseError::NoTransactionInProgress)
+                                            .send(&mut writer)
+                                            .await?;
                                     }
                                     Message::ready_for_query(transaction_status::IDLE)
                                         .send(&mut writer)
                                         .await?;
                                 }
-                                crate::parser::Statement::AlterUser { username, password } => {
-                                    match inst.users.get_mut(&username) {
-                                        Some(user) => {
-                                            user.set_password(&password);
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
-                                                .send(&mut writer)
-                                                .await?;
-                                            } else {
-                                                Message::command_complete("ALTER USER")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
+                                crate::parser::Statement::Rollback => {
+                                    if transaction.is_active() {
+                                        // Remove from active transactions in GlobalTransactionManager
+                                        if let Some(tx_id) = transaction.tx_id() {
+                                            tx_manager.rollback_transaction(tx_id);
                                         }
-                                        None => {
-                                            Message::error_response(&format!(
-                                                "User '{username}' not found"
-                                            ))
+                                        transaction.rollback(&mut db);
+                                        tx_manager.pin_snapshot(None);
+                                        Message::command_complete("ROLLBACK")
+                                            .send(&mut writer)
+                                            .await?;
+                                    } else {
+                                        Message::error_response(&DatabaseError::NoTransactionInProgress)
                                             .send(&mut writer)
                                             .await?;
-                                        }
                                     }
                                     Message::ready_for_query(transaction_status::IDLE)
                                         .send(&mut writer)
                                         .await?;
                                 }
-                                // Role management commands
-                                crate::parser::Statement::CreateRole { role_name, is_superuser } => {
-                                    match inst.create_role(&role_name, is_superuser) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
-                                                .send(&mut writer)
-                                                .await?;
-                                            } else {
-                                                Message::command_complete("CREATE ROLE")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
-                                                .send(&mut writer)
-                                                .await?;
-                                        }
+                                // v2.7.0: Snapshots shared between connections
+                                stmt @ (crate::parser::Statement::ExportSnapshot
+                                | crate::parser::Statement::SetTransactionSnapshot { .. }) => {
+                                    match Self::share_snapshot(stmt, &mut transaction, &mut tx_manager) {
+                                        Ok(result) => Self::send_postgres_result(result, &mut writer).await?,
+                                        Err(e) => Message::error_response(&e).send(&mut writer).await?,
                                     }
-                                    Message::ready_for_query(transaction_status::IDLE)
+                                    Message::ready_for_query(Self::ready_status(&transaction))
                                         .send(&mut writer)
                                         .await?;
                                 }
-                                crate::parser::Statement::DropRole { role_name } => {
-                                    match inst.drop_role(&role_name) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
+                                // COPY protocol (v2.4.0)
+                                crate::parser::Statement::Copy { table, columns, from_stdin, format } => {
+                                    use crate::parser::CopyFormat;
+
+                                    if !from_stdin {
+                                        // COPY TO STDOUT (export) - v2.4.1 CSV, v2.5.0 Binary
+
+                                        // Get table
+                                        let table_obj = db.get_table(&table);
+                                        if table_obj.is_none() {
+                                            Message::error_response(&DatabaseError::TableNotFound(table))
                                                 .send(&mut writer)
                                                 .await?;
-                                            } else {
-                                                Message::command_complete("DROP ROLE")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
+                                            Message::ready_for_query(Self::ready_status(&transaction))
                                                 .send(&mut writer)
                                                 .await?;
+                                            continue;
                                         }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                crate::parser::Statement::GrantRole { role_name, to_user } => {
-                                    match inst.grant_role_to_user(&role_name, &to_user) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
-                                                .send(&mut writer)
-                                                .await?;
-                                            } else {
-                                                Message::command_complete("GRANT")
-                                                    .send(&mut writer)
-                                                    .await?;
+
+                                        let table_obj = table_obj.unwrap();
+                                        let num_columns = if let Some(ref cols) = columns {
+                                            cols.len() as i16
+                                        } else {
+                                            table_obj.columns.len() as i16
+                                        };
+
+                                        // Get all rows from table
+                                        let db_storage = database_storage
+                                            .as_ref()
+                                            .expect("database_storage required");
+                                        let db_storage_guard = db_storage.read().await;
+                                        let snapshot = tx_manager.get_snapshot();
+
+                                        let rows = if let Some(paged_table) = db_storage_guard.get_paged_table(&table) {
+                                            match paged_table.get_all_rows() {
+                                                Ok(rows) => rows,
+                                                Err(e) => {
+                                                    Message::error_response(&DatabaseError::Internal(format!("Failed to read table: {e}")))
+                                                        .send(&mut writer)
+                                                        .await?;
+                                                    Message::ready_for_query(Self::ready_status(&transaction))
+                                                        .send(&mut writer)
+                                                        .await?;
+                                                    continue;
+                                                }
                                             }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
+                                        } else {
+                                            Message::error_response(&DatabaseError::TableNotFound(table))
                                                 .send(&mut writer)
                                                 .await?;
-                                        }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                crate::parser::Statement::RevokeRole { role_name, from_user } => {
-                                    match inst.revoke_role_from_user(&role_name, &from_user) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
+                                            Message::ready_for_query(Self::ready_status(&transaction))
                                                 .send(&mut writer)
                                                 .await?;
-                                            } else {
-                                                Message::command_complete("REVOKE")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
+                                            continue;
+                                        };
+
+                                        let mut rows_exported = 0;
+
+                                        // Determine which columns to export
+                                        let export_columns: Vec<usize> = if let Some(ref cols) = columns {
+                                            // Get column indices
+                                            cols.iter()
+                                                .filter_map(|col_name| {
+                                                    table_obj.columns.iter().position(|c| &c.name == col_name)
+                                                })
+                                                .collect()
+                                        } else {
+                                            // Export all columns
+                                            (0..table_obj.columns.len()).collect()
+                                        };
+
+                                        // Handle binary vs CSV format
+                                        if format == CopyFormat::Binary {
+                                            // Binary export (v2.5.0)
+                                            use crate::network::copy_binary::BinaryCopyEncoder;
+
+                                            // Send CopyOutResponse with format=1 (binary)
+                                            Message::copy_out_response(1, num_columns)
                                                 .send(&mut writer)
                                                 .await?;
-                                        }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                // Database management commands
-                                crate::parser::Statement::CreateDatabase { name, owner } => {
-                                    let owner = owner.unwrap_or_else(|| session.username.clone());
-                                    match inst.create_database(&name, &owner) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
+
+                                            // Send binary header
+                                            Message::copy_data(&BinaryCopyEncoder::write_header())
                                                 .send(&mut writer)
                                                 .await?;
-                                            } else {
-                                                Message::command_complete("CREATE DATABASE")
+
+                                            // Export rows in binary format
+                                            for row in rows {
+                                                // Filter visible rows (MVCC; v2.7.0: the statement's snapshot,
+                                                // so workers that imported one copy the same rows)
+                                                if !row.is_visible_to_snapshot(&snapshot) {
+                                                    continue;
+                                                }
+
+                                                // Extract exported column values
+                                                let row_values: Vec<crate::types::Value> = export_columns
+                                                    .iter()
+                                                    .map(|&idx| {
+                                                        if idx < row.values.len() {
+                                                            row.values[idx].clone()
+                                                        } else {
+                                                            crate::types::Value::Null
+                                                        }
+                                                    })
+                                                    .collect();
+
+                                                // Encode row to binary
+                                                let binary_row = BinaryCopyEncoder::encode_row(&row_values);
+
+                                                // Send as CopyData
+                                                Message::copy_data(&binary_row)
                                                     .send(&mut writer)
                                                     .await?;
+
+                                                rows_exported += 1;
                                             }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
-                                                .send(&mut writer)
-                                                .await?;
-                                        }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                crate::parser::Statement::DropDatabase { name } => {
-                                    match inst.drop_database(&name) {
-                                        Ok(()) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) =
-                                                storage_guard.save_server_instance(&inst)
-                                            {
-                                                Message::error_response(&format!(
-                                                    "Failed to persist: {e}"
-                                                ))
+
+                                            // Send binary trailer
+                                            Message::copy_data(&BinaryCopyEncoder::write_trailer())
                                                 .send(&mut writer)
                                                 .await?;
-                                            } else {
-                                                Message::command_complete("DROP DATABASE")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
+
+                                            // Send CopyDone
+                                            Message::copy_done()
                                                 .send(&mut writer)
                                                 .await?;
-                                        }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                // Privilege commands
-                                crate::parser::Statement::Grant {
-                                    privilege,
-                                    on,
-                                    to_user,
-                                } => {
-                                    use crate::parser::GrantObject;
-                                    let priv_type = Self::convert_privilege(&privilege);
-
-                                    let result = match on {
-                                        GrantObject::Database(db_name) => {
-                                            // Grant on database
-                                            inst.get_database_metadata_mut(&db_name)
-                                                .map(|meta| {
-                                                    meta.grant(&to_user, priv_type);
-                                                    format!("Granted {privilege:?} on database {db_name} to {to_user}")
-                                                })
-                                                .ok_or_else(|| format!("Database '{db_name}' not found"))
-                                        }
-                                        GrantObject::Table(table_name) => {
-                                            // Grant on table (v2.3.0)
-                                            inst.get_database_mut(&session.database_name)
-                                                .and_then(|db| db.table_metadata.get_mut(&table_name))
-                                                .map(|meta| {
-                                                    meta.grant(&to_user, priv_type);
-                                                    format!("Granted {privilege:?} on table {table_name} to {to_user}")
-                                                })
-                                                .ok_or_else(|| format!("Table '{table_name}' not found"))
-                                        }
-                                    };
 
-                                    match result {
-                                        Ok(_msg) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) = storage_guard.save_server_instance(&inst) {
-                                                Message::error_response(&format!("Failed to persist: {e}"))
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            } else {
-                                                Message::command_complete("GRANT")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(msg) => {
-                                            Message::error_response(&msg)
+                                            // Send CommandComplete
+                                            Message::command_complete(&format!("COPY {rows_exported}"))
                                                 .send(&mut writer)
                                                 .await?;
-                                        }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                crate::parser::Statement::Revoke {
-                                    privilege,
-                                    on,
-                                    from_user,
-                                } => {
-                                    use crate::parser::GrantObject;
-                                    let priv_type = Self::convert_privilege(&privilege);
-
-                                    let result = match on {
-                                        GrantObject::Database(db_name) => {
-                                            // Revoke from database
-                                            inst.get_database_metadata_mut(&db_name)
-                                                .map(|meta| {
-                                                    meta.revoke(&from_user, &priv_type);
-                                                    format!("Revoked {privilege:?} on database {db_name} from {from_user}")
-                                                })
-                                                .ok_or_else(|| format!("Database '{db_name}' not found"))
-                                        }
-                                        GrantObject::Table(table_name) => {
-                                            // Revoke from table (v2.3.0)
-                                            inst.get_database_mut(&session.database_name)
-                                                .and_then(|db| db.table_metadata.get_mut(&table_name))
-                                                .map(|meta| {
-                                                    meta.revoke(&from_user, &priv_type);
-                                                    format!("Revoked {privilege:?} on table {table_name} from {from_user}")
-                                                })
-                                                .ok_or_else(|| format!("Table '{table_name}' not found"))
-                                        }
-                                    };
 
-                                    match result {
-                                        Ok(_msg) => {
-                                            let mut storage_guard = storage.lock().await;
-                                            if let Err(e) = storage_guard.save_server_instance(&inst) {
-                                                Message::error_response(&format!("Failed to persist: {e}"))
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            } else {
-                                                Message::command_complete("REVOKE")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(msg) => {
-                                            Message::error_response(&msg)
+                                            Message::ready_for_query(Self::ready_status(&transaction))
                                                 .send(&mut writer)
                                                 .await?;
+
+                                            continue;
                                         }
-                                    }
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                // Metadata queries
-                                crate::parser::Statement::ShowUsers => {
-                                    let mut rows = vec![];
-                                    for (username, user) in &inst.users {
-                                        rows.push(vec![
-                                            username.clone(),
-                                            if user.is_superuser {
-                                                "yes".to_string()
-                                            } else {
-                                                "no".to_string()
-                                            },
-                                            if user.can_create_db {
-                                                "yes".to_string()
-                                            } else {
-                                                "no".to_string()
-                                            },
-                                        ]);
-                                    }
-                                    let columns = vec![
-                                        "username".to_string(),
-                                        "superuser".to_string(),
-                                        "createdb".to_string(),
-                                    ];
-
-                                    Message::row_description(&columns).send(&mut writer).await?;
-                                    for row in &rows {
-                                        Message::data_row(row).send(&mut writer).await?;
-                                    }
-                                    Message::command_complete(&format!("SELECT {}", rows.len()))
-                                        .send(&mut writer)
-                                        .await?;
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                crate::parser::Statement::ShowDatabases => {
-                                    let mut rows = vec![];
-                                    for (name, meta) in &inst.database_metadata {
-                                        rows.push(vec![name.clone(), meta.owner.clone()]);
-                                    }
-                                    let columns = vec!["name".to_string(), "owner".to_string()];
 
-                                    Message::row_description(&columns).send(&mut writer).await?;
-                                    for row in &rows {
-                                        Message::data_row(row).send(&mut writer).await?;
-                                    }
-                                    Message::command_complete(&format!("SELECT {}", rows.len()))
-                                        .send(&mut writer)
-                                        .await?;
-                                    Message::ready_for_query(transaction_status::IDLE)
-                                        .send(&mut writer)
-                                        .await?;
-                                }
-                                // Regular table operations need database access
-                                other_stmt => {
-                                    // v2.3.0: First transform CREATE TABLE to add owner before permission check
-                                    let stmt_with_owner_early = match other_stmt {
-                                        crate::parser::Statement::CreateTable { name, columns, owner: None } => {
-                                            crate::parser::Statement::CreateTable {
-                                                name,
-                                                columns,
-                                                owner: Some(session.username.clone()),
+                                        // CSV export (v2.4.1), text format (v2.7.0)
+                                        // Send CopyOutResponse with format=0 (text)
+                                        Message::copy_out_response(0, num_columns)
+                                            .send(&mut writer)
+                                            .await?;
+
+                                        // Convert rows to CSV and send via CopyData
+                                        for row in rows {
+                                            // Filter visible rows (MVCC; v2.7.0: the statement's snapshot,
+                                            // so workers that imported one copy the same rows)
+                                            if !row.is_visible_to_snapshot(&snapshot) {
+                                                continue;
                                             }
-                                        }
-                                        other => other,
-                                    };
 
-                                    // v2.3.0: Check permissions BEFORE getting mutable database reference
-                                    // This avoids borrow checker issues
-                                    let needs_permission_check = matches!(
-                                        stmt_with_owner_early,
-                                        crate::parser::Statement::Select { .. }
-                                            | crate::parser::Statement::Insert { .. }
-                                            | crate::parser::Statement::Update { .. }
-                                            | crate::parser::Statement::Delete { .. }
-                                            | crate::parser::Statement::AlterTable { .. }
-                                            | crate::parser::Statement::DropTable { .. }
-                                    );
-
-                                    if needs_permission_check {
-                                        if let Some(err_msg) = Self::check_statement_permissions(
-                                            &inst,
-                                            &session.database_name,
-                                            &session.username,
-                                            &stmt_with_owner_early,
-                                        ) {
-                                            Message::error_response(&err_msg)
-                                                .send(&mut writer)
-                                                .await?;
-                                            Message::ready_for_query(transaction_status::IDLE)
+                                            let csv_line = if format == CopyFormat::Csv {
+                                                // Build CSV line
+                                                let csv_values: Vec<String> = export_columns
+                                                    .iter()
+                                                    .map(|&idx| {
+                                                        if idx < row.values.len() {
+                                                            value_to_csv_string(&row.values[idx])
+                                                        } else {
+                                                            String::new()
+                                                        }
+                                                    })
+                                                    .collect();
+                                                csv_values.join(",") + "\n"
+                                            } else {
+                                                let values: Vec<Value> = export_columns
+                                                    .iter()
+                                                    .map(|&idx| row.values.get(idx).cloned().unwrap_or(Value::Null))
+                                                    .collect();
+                                                crate::network::TextCopyEncoder::encode_row(&values)
+                                            };
+
+                                            // Send as CopyData
+                                            Message::copy_data(csv_line.as_bytes())
                                                 .send(&mut writer)
                                                 .await?;
-                                            continue;
+
+                                            rows_exported += 1;
                                         }
-                                    }
 
-                                    // Получаем текущую БД из сессии
-                                    let db = if let Some(db) =
-                                        inst.get_database_mut(&session.database_name)
-                                    {
-                                        db
-                                    } else {
-                                        Message::error_response(&format!(
-                                            "Database '{}' not found",
-                                            session.database_name
-                                        ))
-                                        .send(&mut writer)
-                                        .await?;
-                                        Message::ready_for_query(transaction_status::IDLE)
+                                        // Send CopyDone
+                                        Message::copy_done()
                                             .send(&mut writer)
                                             .await?;
-                                        continue;
-                                    };
 
-                                    match stmt_with_owner_early {
-                                        crate::parser::Statement::Begin => {
-                                            if transaction.is_active() {
-                                                Message::error_response(
-                                                    "Transaction already active",
-                                                )
-                                                .send(&mut writer)
-                                                .await?;
-                                            } else {
-                                                let (tx_id, snapshot) =
-                                                    tx_manager.begin_transaction();
-                                                transaction.begin(tx_id, snapshot, db);
-                                                Message::command_complete("BEGIN")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                            Message::ready_for_query(
-                                                transaction_status::IN_TRANSACTION,
-                                            )
+                                        // Send CommandComplete
+                                        Message::command_complete(&format!("COPY {rows_exported}"))
                                             .send(&mut writer)
                                             .await?;
-                                        }
-                                        crate::parser::Statement::Commit => {
-                                            if transaction.is_active() {
-                                                // Remove from active transactions in GlobalTransactionManager
-                                                if let Some(tx_id) = transaction.tx_id() {
-                                                    tx_manager.commit_transaction(tx_id);
-                                                }
-                                                transaction.commit();
-                                                let mut storage_guard = storage.lock().await;
-                                                if let Err(e) =
-                                                    storage_guard.save_server_instance(&inst)
-                                                {
-                                                    Message::error_response(&format!(
-                                                        "Failed to persist: {e}"
-                                                    ))
-                                                    .send(&mut writer)
-                                                    .await?;
-                                                } else {
-                                                    Message::command_complete("COMMIT")
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                }
-                                            } else {
-                                                Message::error_response("No active transaction")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                            Message::ready_for_query(transaction_status::IDLE)
-                                                .send(&mut writer)
-                                                .await?;
-                                        }
-                                        crate::parser::Statement::Rollback => {
-                                            if transaction.is_active() {
-                                                // Remove from active transactions in GlobalTransactionManager
-                                                if let Some(tx_id) = transaction.tx_id() {
-                                                    tx_manager.rollback_transaction(tx_id);
-                                                }
-                                                transaction.rollback(db);
-                                                Message::command_complete("ROLLBACK")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            } else {
-                                                Message::error_response("No active transaction")
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                            Message::ready_for_query(transaction_status::IDLE)
-                                                .send(&mut writer)
-                                                .await?;
-                                        }
-                                        // COPY protocol (v2.4.0)
-                                        crate::parser::Statement::Copy { table, columns, from_stdin, format } => {
-                                            use crate::parser::CopyFormat;
 
-                                            if !from_stdin {
-                                                // COPY TO STDOUT (export) - v2.4.1 CSV, v2.5.0 Binary
+                                        Message::ready_for_query(Self::ready_status(&transaction))
+                                            .send(&mut writer)
+                                            .await?;
 
-                                                // Get table
-                                                let table_obj = db.get_table(&table);
-                                                if table_obj.is_none() {
-                                                    Message::error_response(&format!("Table '{table}' not found"))
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                    Message::ready_for_query(transaction_status::IDLE)
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                    continue;
-                                                }
+                                        continue;
+                                    }
 
-                                                let table_obj = table_obj.unwrap();
-                                                let num_columns = if let Some(ref cols) = columns {
-                                                    cols.len() as i16
-                                                } else {
-                                                    table_obj.columns.len() as i16
-                                                };
+                                    // COPY FROM STDIN (import) - v2.4.1 CSV, v2.5.0 Binary
 
-                                                // Get all rows from table
-                                                let db_storage = database_storage
-                                                    .as_ref()
-                                                    .expect("database_storage required");
-                                                let db_storage_guard = db_storage.lock().await;
-
-                                                let rows = if let Some(paged_table) = db_storage_guard.get_paged_table(&table) {
-                                                    match paged_table.get_all_rows() {
-                                                        Ok(rows) => rows,
-                                                        Err(e) => {
-                                                            Message::error_response(&format!("Failed to read table: {e}"))
-                                                                .send(&mut writer)
-                                                                .await?;
-                                                            Message::ready_for_query(transaction_status::IDLE)
-                                                                .send(&mut writer)
-                                                                .await?;
-                                                            continue;
-                                                        }
-                                                    }
-                                                } else {
-                                                    Message::error_response(&format!("Table '{table}' not found in storage"))
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                    Message::ready_for_query(transaction_status::IDLE)
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                    continue;
-                                                };
+                                    // Get table to determine column count
+                                    let table_obj = db.get_table(&table);
+                                    if table_obj.is_none() {
+                                        Message::error_response(&DatabaseError::TableNotFound(table))
+                                            .send(&mut writer)
+                                            .await?;
+                                        Message::ready_for_query(Self::ready_status(&transaction))
+                                            .send(&mut writer)
+                                            .await?;
+                                        continue;
+                                    }
 
-                                                let mut rows_exported = 0;
-
-                                                // Determine which columns to export
-                                                let export_columns: Vec<usize> = if let Some(ref cols) = columns {
-                                                    // Get column indices
-                                                    cols.iter()
-                                                        .filter_map(|col_name| {
-                                                            table_obj.columns.iter().position(|c| &c.name == col_name)
-                                                        })
-                                                        .collect()
-                                                } else {
-                                                    // Export all columns
-                                                    (0..table_obj.columns.len()).collect()
-                                                };
+                                    let table_obj = table_obj.unwrap();
+                                    let num_columns = if let Some(ref cols) = columns {
+                                        cols.len() as i16
+                                    } else {
+                                        table_obj.columns.len() as i16
+                                    };
 
-                                                // Handle binary vs CSV format
-                                                if format == CopyFormat::Binary {
-                                                    // Binary export (v2.5.0)
-                                                    use crate::network::copy_binary::BinaryCopyEncoder;
+                                    // Determine which columns to import
+                                    let import_columns: Vec<crate::core::Column> = if let Some(ref cols) = columns {
+                                        cols.iter()
+                                            .filter_map(|col_name| {
+                                                table_obj.columns.iter().find(|c| &c.name == col_name).cloned()
+                                            })
+                                            .collect()
+                                    } else {
+                                        table_obj.columns.clone()
+                                    };
 
-                                                    // Send CopyOutResponse with format=1 (binary)
-                                                    Message::copy_out_response(1, num_columns)
-                                                        .send(&mut writer)
-                                                        .await?;
+                                    // Handle binary vs CSV format
+                                    if format == CopyFormat::Binary {
+                                        // Binary import (v2.5.0)
+                                        use crate::network::copy_binary::BinaryCopyDecoder;
+                                        use std::io::Cursor;
 
-                                                    // Send binary header
-                                                    Message::copy_data(&BinaryCopyEncoder::write_header())
-                                                        .send(&mut writer)
-                                                        .await?;
+                                        // Send CopyInResponse with format=1 (binary)
+                                        Message::copy_in_response(1, num_columns)
+                                            .send(&mut writer)
+                                            .await?;
 
-                                                    // Export rows in binary format
-                                                    for row in rows {
-                                                        // Filter visible rows (MVCC)
-                                                        let tx_id = transaction.tx_id().unwrap_or(0);
-                                                        if !row.is_visible(tx_id) {
-                                                            continue;
-                                                        }
+                                        // Accumulate binary data
+                                        let mut binary_buffer = Vec::new();
+                                        let mut rows_inserted = 0;
 
-                                                        // Extract exported column values
-                                                        let row_values: Vec<crate::types::Value> = export_columns
-                                                            .iter()
-                                                            .map(|&idx| {
-                                                                if idx < row.values.len() {
-                                                                    row.values[idx].clone()
-                                                                } else {
-                                                                    crate::types::Value::Null
-                                                                }
-                                                            })
-                                                            .collect();
-
-                                                        // Encode row to binary
-                                                        let binary_row = BinaryCopyEncoder::encode_row(&row_values);
-
-                                                        // Send as CopyData
-                                                        Message::copy_data(&binary_row)
-                                                            .send(&mut writer)
+                                        // Read COPY data loop
+                                        loop {
+                                            let (msg_type, data) = match pg_protocol::read_frontend_message(&mut reader).await {
+                                                Ok(msg) => msg,
+                                                Err(_) => break,
+                                            };
+
+                                            match msg_type {
+                                                frontend::COPY_DATA => {
+                                                    binary_buffer.extend_from_slice(&data);
+                                                }
+                                                frontend::COPY_DONE => {
+                                                    // Process accumulated binary data
+                                                    let mut cursor = Cursor::new(binary_buffer.as_slice());
+
+                                                    // Read and validate header
+                                                    if let Err(e) = BinaryCopyDecoder::read_header(&mut cursor) {
+                                                        Message::error_response(&DatabaseError::BadCopyFormat(format!("COPY binary header error: {e}")))
+                                                            .send(&mut writer)
                                                             .await?;
+                                                        break;
+                                                    }
 
-                                                        rows_exported += 1;
+                                                    // Read rows until trailer
+                                                    let mut rows = Vec::new();
+                                                    let mut decoded = true;
+                                                    loop {
+                                                        match BinaryCopyDecoder::decode_row(&mut cursor, &import_columns) {
+                                                            Ok(Some(values)) => rows.push(values),
+                                                            Ok(None) => {
+                                                                // EOF marker reached
+                                                                break;
+                                                            }
+                                                            Err(e) => {
+                                                                Message::error_response(&DatabaseError::BadCopyFormat(format!("COPY decode error: {e}")))
+                                                                    .send(&mut writer)
+                                                                    .await?;
+                                                                decoded = false;
+                                                                break;
+                                                            }
+                                                        }
                                                     }
 
-                                                    // Send binary trailer
-                                                    Message::copy_data(&BinaryCopyEncoder::write_trailer())
-                                                        .send(&mut writer)
-                                                        .await?;
+                                                    // v2.7.0: Insert every row as one batch
+                                                    if decoded {
+                                                        let insert_stmt = crate::parser::Statement::InsertMany {
+                                                            table: table.clone(),
+                                                            columns: columns.clone(),
+                                                            source: crate::parser::InsertSource::Values(rows),
+                                                        };
+
+                                                        let db_storage = database_storage
+                                                            .as_ref()
+                                                            .expect("database_storage required");
+                                                        let mut db_storage_guard = db_storage.write().await;
+                                                        let mut storage_guard = storage.lock().await;
+
+                                                        match session.parameters.scope(|| {
+                                                            QueryExecutor::execute(
+                                                                &mut db,
+                                                                insert_stmt,
+                                                                Some(&mut *storage_guard),
+                                                                &tx_manager,
+                                                                &mut db_storage_guard,
+                                                                transaction.tx_id(),
+                                                            )
+                                                        }) {
+                                                            Ok(result) => rows_inserted = result.row_count(),
+                                                            Err(e) => {
+                                                                Message::error_response(&e)
+                                                                    .send(&mut writer)
+                                                                    .await?;
+                                                            }
+                                                        }
+                                                    }
 
-                                                    // Send CopyDone
-                                                    Message::copy_done()
+                                                    // Send CommandComplete
+                                                    Message::command_complete(&format!("COPY {rows_inserted}"))
                                                         .send(&mut writer)
                                                         .await?;
-
-                                                    // Send CommandComplete
-                                                    Message::command_complete(&format!("COPY {rows_exported}"))
+                                                    break;
+                                                }
+                                                frontend::COPY_FAIL => {
+                                                    Message::error_response(&DatabaseError::CopyFailed)
                                                         .send(&mut writer)
                                                         .await?;
-
-                                                    Message::ready_for_query(transaction_status::IDLE)
+                                                    break;
+                                                }
+                                                _ => {
+                                                    Message::error_response(&DatabaseError::ProtocolViolation(format!("Unexpected message during COPY: {msg_type}")))
                                                         .send(&mut writer)
                                                         .await?;
-
-                                                    continue;
+                                                    break;
                                                 }
+                                            }
+                                        }
 
-                                                // CSV export (v2.4.1)
-                                                // Send CopyOutResponse with format=0 (text)
-                                                Message::copy_out_response(0, num_columns)
-                                                    .send(&mut writer)
-                                                    .await?;
+                                        Message::ready_for_query(Self::ready_status(&transaction))
+                                            .send(&mut writer)
+                                            .await?;
 
-                                                // Convert rows to CSV and send via CopyData
-                                                for row in rows {
-                                                    // Filter visible rows (MVCC)
-                                                    let tx_id = transaction.tx_id().unwrap_or(0);
-                                                    if !row.is_visible(tx_id) {
+                                        continue;
+                                    }
+
+                                    // CSV import (v2.4.1), text format (v2.7.0)
+                                    // Send CopyInResponse with format=0 (text)
+                                    Message::copy_in_response(0, num_columns)
+                                        .send(&mut writer)
+                                        .await?;
+
+                                    // Read COPY data in a loop until CopyDone or CopyFail
+                                    let mut copy_buffer = String::new();
+                                    let mut rows_inserted = 0;
+
+                                    loop {
+                                        let (msg_type, data) = match pg_protocol::read_frontend_message(&mut reader).await {
+                                            Ok(msg) => msg,
+                                            Err(_) => break,
+                                        };
+
+                                        match msg_type {
+                                            frontend::COPY_DATA => {
+                                                // Accumulate data
+                                                if let Ok(chunk) = String::from_utf8(data) {
+                                                    copy_buffer.push_str(&chunk);
+                                                }
+                                            }
+                                            frontend::COPY_DONE => {
+                                                // Process all accumulated data
+                                                let mut rows = Vec::new();
+                                                for line in copy_buffer.lines() {
+                                                    if line.trim().is_empty() {
+                                                        continue;
+                                                    }
+                                                    if format == CopyFormat::Text {
+                                                        if line == crate::network::copy_text::END_OF_DATA {
+                                                            break;
+                                                        }
+                                                        rows.push(crate::network::TextCopyDecoder::decode_line(line));
                                                         continue;
                                                     }
 
-                                                    // Build CSV line
-                                                    let csv_values: Vec<String> = export_columns
-                                                        .iter()
-                                                        .map(|&idx| {
-                                                            if idx < row.values.len() {
-                                                                value_to_csv_string(&row.values[idx])
-                                                            } else {
-                                                                String::new()
-                                                            }
-                                                        })
-                                                        .collect();
+                                                    // Simple CSV parsing - split by comma
+                                                    let values: Vec<&str> = line.split(',').collect();
 
-                                                    let csv_line = csv_values.join(",") + "\n";
+                                                    // Convert to Value types (simplified - all as Text)
+                                                    rows.push(values.iter().map(|v| Value::Text(v.trim().to_string())).collect());
+                                                }
 
-                                                    // Send as CopyData
-                                                    Message::copy_data(csv_line.as_bytes())
-                                                        .send(&mut writer)
-                                                        .await?;
+                                                // v2.7.0: Insert every row as one batch
+                                                let insert_stmt = crate::parser::Statement::InsertMany {
+                                                    table: table.clone(),
+                                                    columns: columns.clone(),
+                                                    source: crate::parser::InsertSource::Values(rows),
+                                                };
 
-                                                    rows_exported += 1;
-                                                }
+                                                let db_storage = database_storage
+                                                    .as_ref()
+                                                    .expect("database_storage required");
+                                                let mut db_storage_guard = db_storage.write().await;
+                                                let mut storage_guard = storage.lock().await;
 
-                                                // Send CopyDone
-                                                Message::copy_done()
-                                                    .send(&mut writer)
-                                                    .await?;
+                                                match session.parameters.scope(|| {
+                                                    QueryExecutor::execute(
+                                                        &mut db,
+                                                        insert_stmt,
+                                                        Some(&mut *storage_guard),
+                                                        &tx_manager,
+                                                        &mut db_storage_guard,
+                                                        transaction.tx_id(),
+                                                    )
+                                                }) {
+                                                    Ok(result) => rows_inserted = result.row_count(),
+                                                    Err(e) => {
+                                                        Message::error_response(&e)
+                                                            .send(&mut writer)
+                                                            .await?;
+                                                    }
+                                                }
+                                                drop(storage_guard);
+                                                drop(db_storage_guard);
 
                                                 // Send CommandComplete
-                                                Message::command_complete(&format!("COPY {rows_exported}"))
-                                                    .send(&mut writer)
-                                                    .await?;
-
-                                                Message::ready_for_query(transaction_status::IDLE)
+                                                Message::command_complete(&format!("COPY {rows_inserted}"))
                                                     .send(&mut writer)
                                                     .await?;
-
-                                                continue;
+                                                break;
                                             }
-
-                                            // COPY FROM STDIN (import) - v2.4.1 CSV, v2.5.0 Binary
-
-                                            // Get table to determine column count
-                                            let table_obj = db.get_table(&table);
-                                            if table_obj.is_none() {
-                                                Message::error_response(&format!("Table '{table}' not found"))
+                                            frontend::COPY_FAIL => {
+                                                Message::error_response(&DatabaseError::CopyFailed)
                                                     .send(&mut writer)
                                                     .await?;
-                                                Message::ready_for_query(transaction_status::IDLE)
-                                                    .send(&mut writer)
-                                                    .await?;
-                                                continue;
+                                                break;
                                             }
-
-                                            let table_obj = table_obj.unwrap();
-                                            let num_columns = if let Some(ref cols) = columns {
-                                                cols.len() as i16
-                                            } else {
-                                                table_obj.columns.len() as i16
-                                            };
-
-                                            // Determine which columns to import
-                                            let import_columns: Vec<crate::core::Column> = if let Some(ref cols) = columns {
-                                                cols.iter()
-                                                    .filter_map(|col_name| {
-                                                        table_obj.columns.iter().find(|c| &c.name == col_name).cloned()
-                                                    })
-                                                    .collect()
-                                            } else {
-                                                table_obj.columns.clone()
-                                            };
-
-                                            // Handle binary vs CSV format
-                                            if format == CopyFormat::Binary {
-                                                // Binary import (v2.5.0)
-                                                use crate::network::copy_binary::BinaryCopyDecoder;
-                                                use std::io::Cursor;
-
-                                                // Send CopyInResponse with format=1 (binary)
-                                                Message::copy_in_response(1, num_columns)
+                                            _ => {
+                                                Message::error_response(&DatabaseError::ProtocolViolation(format!("Unexpected message during COPY: {msg_type}")))
                                                     .send(&mut writer)
                                                     .await?;
+                                                break;
+                                            }
+                                        }
+                                    }
 
-                                                // Accumulate binary data
-                                                let mut binary_buffer = Vec::new();
-                                                let mut rows_inserted = 0;
-
-                                                // Read COPY data loop
-                                                loop {
-                                                    let (msg_type, data) = match pg_protocol::read_frontend_message(&mut reader).await {
-                                                        Ok(msg) => msg,
-                                                        Err(_) => break,
-                                                    };
-
-                                                    match msg_type {
-                                                        frontend::COPY_DATA => {
-                                                            binary_buffer.extend_from_slice(&data);
-                                                        }
-                                                        frontend::COPY_DONE => {
-                                                            // Process accumulated binary data
-                                                            let mut cursor = Cursor::new(binary_buffer.as_slice());
-
-                                                            // Read and validate header
-                                                            if let Err(e) = BinaryCopyDecoder::read_header(&mut cursor) {
-                                                                Message::error_response(&format!("COPY binary header error: {e}"))
-                                                                    .send(&mut writer)
-                                                                    .await?;
-                                                                break;
-                                                            }
-
-                                                            // Read rows until trailer
-                                                            loop {
-                                                                match BinaryCopyDecoder::decode_row(&mut cursor, &import_columns) {
-                                                                    Ok(Some(values)) => {
-                                                                        // Insert row
-                                                                        let insert_stmt = crate::parser::Statement::Insert {
-                                                                            table: table.clone(),
-                                                                            columns: columns.clone(),
-                                                                            values,
-                                                                        };
-
-                                                                        let db_storage = database_storage
-                                                                            .as_ref()
-                                                                            .expect("database_storage required");
-                                                                        let mut db_storage_guard = db_storage.lock().await;
-                                                                        let mut storage_guard = storage.lock().await;
-
-                                                                        match QueryExecutor::execute(
-                                                                            db,
-                                                                            insert_stmt,
-                                                                            Some(&mut *storage_guard),
-                                                                            &tx_manager,
-                                                                            &mut db_storage_guard,
-                                                                            transaction.tx_id(),
-                                                                        ) {
-                                                                            Ok(_) => rows_inserted += 1,
-                                                                            Err(e) => {
-                                                                                Message::error_response(&format!("COPY insert error: {e}"))
-                                                                                    .send(&mut writer)
-                                                                                    .await?;
-                                                                                break;
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                    Ok(None) => {
-                                                                        // EOF marker reached
-                                                                        break;
-                                                                    }
-                                                                    Err(e) => {
-                                                                        Message::error_response(&format!("COPY decode error: {e}"))
-                                                                            .send(&mut writer)
-                                                                            .await?;
-                                                                        break;
-                                                                    }
-                                                                }
-                                                            }
-
-                                                            // Send CommandComplete
-                                                            Message::command_complete(&format!("COPY {rows_inserted}"))
-                                                                .send(&mut writer)
-                                                                .await?;
-                                                            break;
-                                                        }
-                                                        frontend::COPY_FAIL => {
-                                                            Message::error_response("COPY failed by client")
-                                                                .send(&mut writer)
-                                                                .await?;
-                                                            break;
-                                                        }
-                                                        _ => {
-                                                            Message::error_response(&format!("Unexpected message during COPY: {msg_type}"))
-                                                                .send(&mut writer)
-                                                                .await?;
-                                                            break;
-                                                        }
-                                                    }
-                                                }
-
-                                                Message::ready_for_query(transaction_status::IDLE)
-                                                    .send(&mut writer)
-                                                    .await?;
+                                    Message::ready_for_query(Self::ready_status(&transaction))
+                                        .send(&mut writer)
+                                        .await?;
+                                }
+                                _ => {
+                                    let mut storage_guard = storage.lock().await;
+                                    // v2.7.0: Temporary tables are never WAL-logged
+                                    let storage_option = if transaction.is_active()
+                                        || session.temp_tables.touches(&stmt_with_owner_early)
+                                    {
+                                        None
+                                    } else {
+                                        Some(&mut *storage_guard)
+                                    };
 
-                                                continue;
-                                            }
+                                    // v2.0.0: database_storage is now required
+                                    let db_storage = database_storage
+                                        .as_ref()
+                                        .expect("v2.0.0: database_storage is required");
+                                    let mut db_storage_guard = db_storage.write().await;
+
+                                    // Permission checks already done earlier
+                                    let tx_id = transaction.tx_id();
+                                    let executed = session.parameters.scope(|| {
+                                        session.temp_tables.execute(
+                                            &mut db,
+                                            &mut db_storage_guard,
+                                            stmt_with_owner_early,
+                                            |db, stmt, db_storage| {
+                                                QueryExecutor::execute(db, stmt, storage_option, &tx_manager, db_storage, tx_id)
+                                            },
+                                        )
+                                    });
+                                    // v2.7.0: Outside BEGIN each statement commits on its own
+                                    let executed = if transaction.is_active() {
+                                        executed
+                                    } else {
+                                        executed.and_then(|result| {
+                                            session.temp_tables.end_transaction().map(|()| result)
+                                        })
+                                    };
 
-                                            // CSV import (v2.4.1)
-                                            // Send CopyInResponse with format=0 (text)
-                                            Message::copy_in_response(0, num_columns)
+                                    match executed {
+                                        Ok(result) => {
+                                            Self::record_statement(&db, settings, query, started, &result);
+                                            // v2.7.0: The checkpoint snapshot locks every database
+                                            drop(db_storage_guard);
+                                            drop(storage_guard);
+                                            drop(db);
+                                            if transaction.is_active() {
+                                                Self::send_postgres_result(
+                                                    result,
+                                                    &mut writer,
+                                                )
+                                                .await?;
+                                            } else if let Err(e) = Self::save_instance(&inst, &storage).await {
+                                                Message::error_response(&DatabaseError::Internal(format!(
+                                                    "Checkpoint failed: {e}"
+                                                )))
                                                 .send(&mut writer)
                                                 .await?;
-
-                                            // Read COPY data in a loop until CopyDone or CopyFail
-                                            let mut copy_buffer = String::new();
-                                            let mut rows_inserted = 0;
-
-                                            loop {
-                                                let (msg_type, data) = match pg_protocol::read_frontend_message(&mut reader).await {
-                                                    Ok(msg) => msg,
-                                                    Err(_) => break,
-                                                };
-
-                                                match msg_type {
-                                                    frontend::COPY_DATA => {
-                                                        // Accumulate data
-                                                        if let Ok(chunk) = String::from_utf8(data) {
-                                                            copy_buffer.push_str(&chunk);
-                                                        }
-                                                    }
-                                                    frontend::COPY_DONE => {
-                                                        // Process all accumulated data
-                                                        for line in copy_buffer.lines() {
-                                                            if line.trim().is_empty() {
-                                                                continue;
-                                                            }
-
-                                                            // Simple CSV parsing - split by comma
-                                                            let values: Vec<&str> = line.split(',').collect();
-
-                                                            // Convert to Value types (simplified - all as Text)
-                                                            let value_objs: Vec<Value> = values
-                                                                .iter()
-                                                                .map(|v| Value::Text(v.trim().to_string()))
-                                                                .collect();
-
-                                                            // Insert into table
-                                                            let insert_stmt = crate::parser::Statement::Insert {
-                                                                table: table.clone(),
-                                                                columns: columns.clone(),
-                                                                values: value_objs,
-                                                            };
-
-                                                            let db_storage = database_storage
-                                                                .as_ref()
-                                                                .expect("database_storage required");
-                                                            let mut db_storage_guard = db_storage.lock().await;
-                                                            let mut storage_guard = storage.lock().await;
-
-                                                            match QueryExecutor::execute(
-                                                                db,
-                                                                insert_stmt,
-                                                                Some(&mut *storage_guard),
-                                                                &tx_manager,
-                                                                &mut db_storage_guard,
-                                                                transaction.tx_id(),
-                                                            ) {
-                                                                Ok(_) => rows_inserted += 1,
-                                                                Err(e) => {
-                                                                    Message::error_response(&format!("COPY error: {e}"))
-                                                                        .send(&mut writer)
-                                                                        .await?;
-                                                                    break;
-                                                                }
-                                                            }
-                                                        }
-
-                                                        // Send CommandComplete
-                                                        Message::command_complete(&format!("COPY {rows_inserted}"))
-                                                            .send(&mut writer)
-                                                            .await?;
-                                                        break;
-                                                    }
-                                                    frontend::COPY_FAIL => {
-                                                        Message::error_response("COPY failed by client")
-                                                            .send(&mut writer)
-                                                            .await?;
-                                                        break;
-                                                    }
-                                                    _ => {
-                                                        Message::error_response(&format!("Unexpected message during COPY: {msg_type}"))
-                                                            .send(&mut writer)
-                                                            .await?;
-                                                        break;
-                                                    }
-                                                }
+                                            } else {
+                                                Self::send_postgres_result(
+                                                    result,
+                                                    &mut writer,
+                                                )
+                                                .await?;
                                             }
-
-                                            Message::ready_for_query(transaction_status::IDLE)
+                                            Message::ready_for_query(Self::ready_status(&transaction))
                                                 .send(&mut writer)
                                                 .await?;
                                         }
-                                        _ => {
-                                            let mut storage_guard = storage.lock().await;
-                                            let storage_option = if transaction.is_active() {
-                                                None
-                                            } else {
-                                                Some(&mut *storage_guard)
-                                            };
-
-                                            // v2.0.0: database_storage is now required
-                                            let db_storage = database_storage
-                                                .as_ref()
-                                                .expect("v2.0.0: database_storage is required");
-                                            let mut db_storage_guard = db_storage.lock().await;
-
-                                            // Permission checks already done earlier
-                                            match QueryExecutor::execute(
-                                                db,
-                                                stmt_with_owner_early,
-                                                storage_option,
-                                                &tx_manager,
-                                                &mut db_storage_guard,
-                                                transaction.tx_id(),
-                                            ) {
-                                                Ok(result) => {
-                                                    if transaction.is_active() {
-                                                        Self::send_postgres_result(
-                                                            result,
-                                                            &mut writer,
-                                                        )
-                                                        .await?;
-                                                    } else if let Err(e) =
-                                                        storage_guard.save_server_instance(&inst)
-                                                    {
-                                                        Message::error_response(&format!(
-                                                            "Checkpoint failed: {e}"
-                                                        ))
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                    } else {
-                                                        Self::send_postgres_result(
-                                                            result,
-                                                            &mut writer,
-                                                        )
-                                                        .await?;
-                                                    }
-
-                                                    let status = if transaction.is_active() {
-                                                        transaction_status::IN_TRANSACTION
-                                                    } else {
-                                                        transaction_status::IDLE
-                                                    };
-                                                    Message::ready_for_query(status)
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                }
-                                                Err(e) => {
-                                                    Message::error_response(&format!("{e}"))
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                    let status = if transaction.is_active() {
-                                                        transaction_status::IN_TRANSACTION
-                                                    } else {
-                                                        transaction_status::IDLE
-                                                    };
-                                                    Message::ready_for_query(status)
-                                                        .send(&mut writer)
-                                                        .await?;
-                                                }
-                                            }
+                                        Err(e) => {
+                                            Message::error_response(&e)
+                                                .send(&mut writer)
+                                                .await?;
+                                            Message::ready_for_query(Self::ready_status(&transaction))
+                                                .send(&mut writer)
+                                                .await?;
                                         }
                                     }
                                 }
                             }
                         }
                         Err(e) => {
-                            Message::error_response(&format!("Parse error: {e}"))
+                            Message::error_response(&e.into())
                                 .send(&mut writer)
                                 .await?;
-                            let status = if transaction.is_active() {
-                                transaction_status::IN_TRANSACTION
-                            } else {
-                                transaction_status::IDLE
-                            };
-                            Message::ready_for_query(status).send(&mut writer).await?;
+                            Message::ready_for_query(Self::ready_status(&transaction)).send(&mut writer).await?;
                         }
                     }
                 }
@@ -1478,28 +1595,41 @@ impl Server {
                     match pg_protocol::ParseMessage::from_data(&data) {
                         Ok(parse_msg) => {
                             // Store the prepared statement
-                            session.prepared_statements.add_statement(
+                            // (v2.7.0: evicting the least recently used one when the session is at its limit)
+                            if let Some(evicted) = session.prepared_statements.add_statement(
                                 parse_msg.statement_name.clone(),
                                 parse_msg.query.clone(),
                                 parse_msg.param_types.clone(),
-                            );
+                            ) {
+                                settings.statements.evictions.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                debug!(statement = evicted, "prepared statement evicted");
+                            }
 
                             // Try to parse the statement now for validation
-                            if !parse_msg.query.is_empty() {
-                                if let Ok(stmt) = parse_statement(&parse_msg.query) {
-                                    if let Some(prep_stmt) = session.prepared_statements.get_statement_mut(&parse_msg.statement_name) {
-                                        prep_stmt.statement = Some(stmt);
-                                    }
+                            // (v2.7.0: unnamed statements through the cache shared by all sessions)
+                            if !Self::is_empty_query(&parse_msg.query) {
+                                let stmt = if parse_msg.statement_name.is_empty() {
+                                    settings.statements.parse(&parse_msg.query)
+                                } else {
+                                    parse_statement(&parse_msg.query).ok()
+                                };
+                                if let Some(stmt) = stmt
+                                    && let Some(prep_stmt) = session.prepared_statements.get_statement_mut(&parse_msg.statement_name)
+                                {
+                                    prep_stmt.statement = Some(stmt);
                                 }
                             }
 
                             // Send ParseComplete
-                            Message::parse_complete().send(&mut writer).await?;
+                            Message::parse_complete().write(&mut writer).await?;
                         }
                         Err(e) => {
-                            Message::error_response(&format!("Parse error: {e}"))
-                                .send(&mut writer)
-                                .await?;
+                            Self::extended_error(
+                                &mut writer,
+                                &mut skip_until_sync,
+                                &DatabaseError::ProtocolViolation(format!("Parse error: {e}")),
+                            )
+                            .await?;
                         }
                     }
                 }
@@ -1507,18 +1637,41 @@ impl Server {
                     match pg_protocol::BindMessage::from_data(&data) {
                         Ok(bind_msg) => {
                             // Convert binary parameter values to Value enum
+                            // (v2.7.0: typed by the parameter types declared in Parse,
+                            // in the text or binary format given for each)
+                            let param_types = session
+                                .prepared_statements
+                                .get_statement(&bind_msg.statement_name)
+                                .map(|stmt| stmt.param_types.clone())
+                                .unwrap_or_default();
                             let mut param_values = Vec::new();
-                            for param_bytes in &bind_msg.param_values {
+                            let mut decode_error = None;
+                            for (i, param_bytes) in bind_msg.param_values.iter().enumerate() {
+                                let format = match bind_msg.param_formats.as_slice() {
+                                    [] => 0,
+                                    [format] => *format,
+                                    formats => formats.get(i).copied().unwrap_or(0),
+                                };
+                                let type_oid = param_types.get(i).copied().unwrap_or(0);
                                 match param_bytes {
                                     None => param_values.push(None),
+                                    Some(bytes) if format == 1 => match decode_binary_parameter(bytes, type_oid) {
+                                        Ok(value) => param_values.push(Some(value)),
+                                        Err(e) => {
+                                            decode_error = Some(e);
+                                            break;
+                                        }
+                                    },
                                     Some(bytes) => {
-                                        // Simple text format parsing - convert bytes to string
                                         let value_str = String::from_utf8_lossy(bytes);
-                                        // For simplicity, store as Text (proper type inference would be more complex)
-                                        param_values.push(Some(Value::Text(value_str.to_string())));
+                                        param_values.push(Some(decode_text_parameter(&value_str, type_oid)));
                                     }
                                 }
                             }
+                            if let Some(e) = decode_error {
+                                Self::extended_error(&mut writer, &mut skip_until_sync, &e).await?;
+                                continue;
+                            }
 
                             // Store the portal
                             session.prepared_statements.add_portal(
@@ -1526,28 +1679,159 @@ impl Server {
                                 bind_msg.statement_name.clone(),
                                 param_values,
                             );
+                            if let Some(portal) = session.prepared_statements.get_portal_mut(&bind_msg.portal_name) {
+                                portal.result_formats = bind_msg.result_formats;
+                            }
 
                             // Send BindComplete
-                            Message::bind_complete().send(&mut writer).await?;
+                            Message::bind_complete().write(&mut writer).await?;
                         }
                         Err(e) => {
-                            Message::error_response(&format!("Bind error: {e}"))
-                                .send(&mut writer)
-                                .await?;
+                            Self::extended_error(
+                                &mut writer,
+                                &mut skip_until_sync,
+                                &DatabaseError::ProtocolViolation(format!("Bind error: {e}")),
+                            )
+                            .await?;
                         }
                     }
                 }
                 frontend::DESCRIBE => {
                     match pg_protocol::DescribeMessage::from_data(&data) {
-                        Ok(_desc_msg) => {
-                            // For now, we don't provide detailed column descriptions
-                            // Just send NoData (statement has no result columns)
-                            Message::no_data().send(&mut writer).await?;
+                        Ok(desc_msg) if desc_msg.describe_type == 'S' => {
+                            // v2.7.0: Parameter types, then the result columns: they are
+                            // only known once the statement runs, so a query is run with
+                            // NULL parameters; anything else is described as NoData
+                            if let Some(prep_stmt) = session.prepared_statements.get_statement(&desc_msg.name) {
+                                let param_types = prep_stmt.described_param_types();
+                                let query = substitute_parameters(&prep_stmt.query, &vec![None; param_types.len()]);
+                                Message::parameter_description(&param_types).write(&mut writer).await?;
+                                let is_query = !Self::is_empty_query(&query)
+                                    && Self::parse_cached(&instance, &session.database_name, &query)
+                                        .await
+                                        .is_ok_and(|stmt| QueryExecutor::is_read_only(&stmt));
+                                let executed = if is_query {
+                                    Self::run_portal(
+                                        &query,
+                                        &instance,
+                                        &storage,
+                                        &tx_manager,
+                                        database_storage.as_ref(),
+                                        settings,
+                                        &mut session,
+                                        &transaction,
+                                    )
+                                    .await
+                                } else {
+                                    Ok(None)
+                                };
+                                match executed {
+                                    Ok(Some(QueryResult::Rows(_, columns))) => {
+                                        Message::row_description(&columns).write(&mut writer).await?;
+                                    }
+                                    _ => Message::no_data().write(&mut writer).await?,
+                                }
+                            } else {
+                                Self::extended_error(
+                                    &mut writer,
+                                    &mut skip_until_sync,
+                                    &DatabaseError::PreparedStatementNotFound(desc_msg.name),
+                                )
+                                .await?;
+                            }
                         }
-                        Err(e) => {
-                            Message::error_response(&format!("Describe error: {e}"))
-                                .send(&mut writer)
+                        Ok(desc_msg) => {
+                            // v2.7.0: RowDescription (with the result formats from Bind) of a
+                            // portal that returns rows, from the columns its statement returned
+                            // last time or from the catalog. Only a query over a source whose
+                            // columns come out of reading it (a view, a join, a catalog) is run
+                            // now; the following Execute sends the rows it got
+                            let portal = session.prepared_statements.get_portal(&desc_msg.name).and_then(|portal| {
+                                let prep_stmt = session.prepared_statements.get_statement(&portal.statement_name)?;
+                                let query = substitute_parameters(&prep_stmt.query, &portal.param_values);
+                                Some((portal.statement_name.clone(), query))
+                            });
+                            let Some((statement_name, query)) = portal else {
+                                Self::extended_error(
+                                    &mut writer,
+                                    &mut skip_until_sync,
+                                    &DatabaseError::PortalNotFound(desc_msg.name),
+                                )
                                 .await?;
+                                continue;
+                            };
+
+                            let stmt = if Self::is_empty_query(&query) {
+                                None
+                            } else {
+                                Self::parse_cached(&instance, &session.database_name, &query).await.ok()
+                            };
+                            let Some(stmt) = stmt.filter(QueryExecutor::is_read_only) else {
+                                Message::no_data().write(&mut writer).await?;
+                                continue;
+                            };
+
+                            let catalog_version = Self::catalog_version(&instance, &session.database_name).await;
+                            let mut columns = session
+                                .prepared_statements
+                                .get_statement(&statement_name)
+                                .and_then(|prep_stmt| prep_stmt.result_columns(catalog_version))
+                                .map(<[String]>::to_vec);
+                            if columns.is_none() {
+                                let inst = instance.read().await;
+                                if let Some(db) = inst.get_database(&session.database_name) {
+                                    columns = QueriesExecutor::result_columns(&*session.read_database(db).await, &stmt);
+                                }
+                            }
+                            let described = match columns {
+                                Some(columns) => Ok((Some(columns), None)),
+                                None => Self::run_portal(
+                                    &query,
+                                    &instance,
+                                    &storage,
+                                    &tx_manager,
+                                    database_storage.as_ref(),
+                                    settings,
+                                    &mut session,
+                                    &transaction,
+                                )
+                                .await
+                                .and_then(|result| {
+                                    Self::check_result_shape(&mut session, &statement_name, catalog_version, result.as_ref())?;
+                                    match result {
+                                        Some(QueryResult::Rows(rows, columns)) => {
+                                            Ok((Some(columns.clone()), Some(QueryResult::Rows(rows, columns))))
+                                        }
+                                        result => Ok((None, result)),
+                                    }
+                                }),
+                            };
+                            match described {
+                                Ok((columns, result)) => {
+                                    let portal = session
+                                        .prepared_statements
+                                        .get_portal_mut(&desc_msg.name)
+                                        .expect("portal looked up above");
+                                    match columns {
+                                        Some(columns) => {
+                                            Message::row_description_with_formats(&columns, &portal.result_formats)
+                                                .write(&mut writer)
+                                                .await?;
+                                        }
+                                        None => Message::no_data().write(&mut writer).await?,
+                                    }
+                                    portal.described = result;
+                                }
+                                Err(e) => Self::extended_error(&mut writer, &mut skip_until_sync, &e).await?,
+                            }
+                        }
+                        Err(e) => {
+                            Self::extended_error(
+                                &mut writer,
+                                &mut skip_until_sync,
+                                &DatabaseError::ProtocolViolation(format!("Describe error: {e}")),
+                            )
+                            .await?;
                         }
                     }
                 }
@@ -1556,120 +1840,124 @@ impl Server {
                         Ok(exec_msg) => {
                             // Get the portal
                             let portal = session.prepared_statements.get_portal(&exec_msg.portal_name).cloned();
+                            // v2.7.0: Already run by Describe
+                            let described = session
+                                .prepared_statements
+                                .get_portal_mut(&exec_msg.portal_name)
+                                .and_then(|portal| portal.described.take());
 
                             if let Some(portal) = portal {
                                 // Get the prepared statement
                                 let prep_stmt = session.prepared_statements.get_statement(&portal.statement_name).cloned();
 
                                 if let Some(prep_stmt) = prep_stmt {
+                                    // v2.7.0: Reuse statistics of named statements
+                                    if !portal.statement_name.is_empty() {
+                                        let executions = session.prepared_statements.record_execution(&portal.statement_name);
+                                        settings.statements.record_execution(executions);
+                                    }
+
                                     // Substitute parameters in the query
                                     let query = substitute_parameters(&prep_stmt.query, &portal.param_values);
 
-                                    // Execute the query (similar to QUERY handling)
-                                    match parse_statement(&query) {
-                                        Ok(stmt) => {
-                                            let mut inst = instance.lock().await;
-                                            let db = inst.get_database_mut(&session.database_name);
-
-                                            if let Some(db) = db {
-                                                let db_storage = database_storage
-                                                    .as_ref()
-                                                    .expect("v2.0.0: database_storage is required");
-                                                let mut db_storage_guard = db_storage.lock().await;
-                                                let mut storage_guard = storage.lock().await;
-
-                                                match QueryExecutor::execute(
-                                                    db,
-                                                    stmt,
-                                                    Some(&mut *storage_guard),
-                                                    &tx_manager,
-                                                    &mut db_storage_guard,
-                                                    transaction.tx_id(),
-                                                ) {
-                                                    Ok(result) => {
-                                                        Self::send_postgres_result(result, &mut writer).await?;
-                                                    }
-                                                    Err(e) => {
-                                                        Message::error_response(&format!("{e}"))
-                                                            .send(&mut writer)
-                                                            .await?;
-                                                    }
-                                                }
-                                            } else {
-                                                Message::error_response(&format!("Database '{}' not found", session.database_name))
-                                                    .send(&mut writer)
-                                                    .await?;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            Message::error_response(&format!("{e}"))
-                                                .send(&mut writer)
-                                                .await?;
+                                    debug!(query, portal = %exec_msg.portal_name, "execute portal");
+                                    session.set_active(&query);
+                                    let executed = match described {
+                                        Some(result) => Ok(Some(result)),
+                                        None => {
+                                            let catalog_version = Self::catalog_version(&instance, &session.database_name).await;
+                                            Self::run_portal(
+                                                &query,
+                                                &instance,
+                                                &storage,
+                                                &tx_manager,
+                                                database_storage.as_ref(),
+                                                settings,
+                                                &mut session,
+                                                &transaction,
+                                            )
+                                            .await
+                                            .and_then(|result| {
+                                                Self::check_result_shape(&mut session, &portal.statement_name, catalog_version, result.as_ref())
+                                                    .map(|()| result)
+                                            })
                                         }
+                                    };
+
+                                    // v2.7.0: Execute never sends RowDescription (that is Describe's job)
+                                    match executed {
+                                        Ok(Some(result)) => Self::send_portal_result(result, &mut writer).await?,
+                                        Ok(None) => Message::empty_query_response().write(&mut writer).await?,
+                                        Err(e) => Self::extended_error(&mut writer, &mut skip_until_sync, &e).await?,
                                     }
                                 } else {
-                                    Message::error_response(&format!("Prepared statement '{}' not found", portal.statement_name))
-                                        .send(&mut writer)
-                                        .await?;
+                                    Self::extended_error(
+                                        &mut writer,
+                                        &mut skip_until_sync,
+                                        &DatabaseError::PreparedStatementNotFound(portal.statement_name),
+                                    )
+                                    .await?;
                                 }
                             } else {
-                                Message::error_response(&format!("Portal '{}' not found", exec_msg.portal_name))
-                                    .send(&mut writer)
-                                    .await?;
+                                Self::extended_error(
+                                    &mut writer,
+                                    &mut skip_until_sync,
+                                    &DatabaseError::PortalNotFound(exec_msg.portal_name),
+                                )
+                                .await?;
                             }
                         }
                         Err(e) => {
-                            Message::error_response(&format!("Execute error: {e}"))
-                                .send(&mut writer)
-                                .await?;
+                            Self::extended_error(
+                                &mut writer,
+                                &mut skip_until_sync,
+                                &DatabaseError::ProtocolViolation(format!("Execute error: {e}")),
+                            )
+                            .await?;
                         }
                     }
                 }
                 frontend::CLOSE => {
                     match pg_protocol::CloseMessage::from_data(&data) {
                         Ok(close_msg) => {
-                            let success = if close_msg.close_type == 'S' {
-                                // Close statement
-                                session.prepared_statements.remove_statement(&close_msg.name)
+                            // v2.7.0: Closing a statement or portal that does not exist is
+                            // not an error; CloseComplete is sent either way
+                            if close_msg.close_type == 'S' {
+                                session.prepared_statements.remove_statement(&close_msg.name);
                             } else {
-                                // Close portal
-                                session.prepared_statements.remove_portal(&close_msg.name)
-                            };
-
-                            if success {
-                                Message::close_complete().send(&mut writer).await?;
-                            } else {
-                                Message::error_response(&format!("{} '{}' not found",
-                                    if close_msg.close_type == 'S' { "Statement" } else { "Portal" },
-                                    close_msg.name))
-                                    .send(&mut writer)
-                                    .await?;
+                                session.prepared_statements.remove_portal(&close_msg.name);
                             }
+                            Message::close_complete().write(&mut writer).await?;
                         }
                         Err(e) => {
-                            Message::error_response(&format!("Close error: {e}"))
-                                .send(&mut writer)
-                                .await?;
+                            Self::extended_error(
+                                &mut writer,
+                                &mut skip_until_sync,
+                                &DatabaseError::ProtocolViolation(format!("Close error: {e}")),
+                            )
+                            .await?;
                         }
                     }
                 }
                 frontend::SYNC => {
+                    skip_until_sync = false;
+                    // v2.7.0: Parameters SET by the portals since the last Sync
+                    Self::send_parameter_changes(&mut session, &mut writer).await?;
                     // Send ReadyForQuery
-                    let tx_status = if transaction.is_active() {
-                        transaction_status::IN_TRANSACTION
-                    } else {
-                        transaction_status::IDLE
-                    };
-                    Message::ready_for_query(tx_status).send(&mut writer).await?;
+                    Message::ready_for_query(Self::ready_status(&transaction)).send(&mut writer).await?;
+                }
+                // v2.7.0: Send what has been buffered without ending the pipeline
+                frontend::FLUSH => {
+                    writer.flush().await?;
                 }
                 frontend::TERMINATE => {
                     break;
                 }
                 _ => {
-                    Message::error_response(&format!("Unknown message type: {msg_type}"))
+                    Message::error_response(&DatabaseError::ProtocolViolation(format!("Unknown message type: {msg_type}")))
                         .send(&mut writer)
                         .await?;
-                    Message::ready_for_query(transaction_status::IDLE)
+                    Message::ready_for_query(Self::ready_status(&transaction))
                         .send(&mut writer)
                         .await?;
                 }
@@ -1679,6 +1967,389 @@ impl Server {
         Ok(())
     }
 
+    /// v2.7.0: Parse a query through the session database's plan cache
+    ///
+    /// Falls back to the plain parser when the database does not exist,
+    /// so server-level commands keep working.
+    async fn parse_cached(
+        instance: &Arc<RwLock<ServerInstance>>,
+        database_name: &str,
+        query: &str,
+    ) -> Result<crate::parser::Statement, crate::parser::SyntaxError> {
+        let inst = instance.read().await;
+        match inst.get_database(database_name) {
+            Some(db) => db.read().await.plan_cache.lock().expect("plan cache poisoned").get_or_parse(query),
+            None => parse_statement(query),
+        }
+    }
+
+    /// v2.7.0: Catalog version of the session database (0 if it does not exist)
+    async fn catalog_version(instance: &Arc<RwLock<ServerInstance>>, database_name: &str) -> u64 {
+        let inst = instance.read().await;
+        match inst.get_database(database_name) {
+            Some(db) => db.read().await.catalog_version.current(),
+            None => 0,
+        }
+    }
+
+    /// v2.7.0: A named statement run under a newer catalog version must
+    /// return the same columns as before (unnamed ones are parsed anew anyway)
+    fn check_result_shape(
+        session: &mut SessionContext,
+        statement_name: &str,
+        catalog_version: u64,
+        result: Option<&QueryResult>,
+    ) -> Result<(), DatabaseError> {
+        match result {
+            Some(QueryResult::Rows(_, columns)) if !statement_name.is_empty() => {
+                session.prepared_statements.check_result_shape(statement_name, catalog_version, columns)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// v2.7.0: Apply ALTER SYSTEM to the overlay file of the data directory
+    fn alter_system(data_dir: Option<&Path>, stmt: &crate::parser::Statement) -> Result<(), DatabaseError> {
+        use crate::parser::Statement;
+
+        let data_dir = data_dir.ok_or_else(|| {
+            DatabaseError::FeatureNotSupported("ALTER SYSTEM is not available on an in-memory server".to_string())
+        })?;
+        let mut config = AutoConfig::load(data_dir).map_err(DatabaseError::ConfigFile)?;
+        match stmt {
+            Statement::AlterSystemSet { name, value } => config.set(name, value),
+            Statement::AlterSystemReset { name } => config.reset(name.as_deref()),
+            _ => unreachable!("not ALTER SYSTEM"),
+        }
+        .map_err(DatabaseError::ConfigFile)?;
+        config.save(data_dir).map_err(|e| {
+            DatabaseError::ConfigFile(format!("could not write \"{}\": {e}", AutoConfig::path(data_dir).display()))
+        })
+    }
+
+    /// v2.7.0: Statements on users, roles and databases change the server
+    /// instance itself and run under its write lock
+    const fn is_server_statement(stmt: &crate::parser::Statement) -> bool {
+        use crate::parser::Statement;
+
+        matches!(
+            stmt,
+            Statement::CreateUser { .. }
+                | Statement::DropUser { .. }
+                | Statement::AlterUser { .. }
+                | Statement::CreateRole { .. }
+                | Statement::DropRole { .. }
+                | Statement::GrantRole { .. }
+                | Statement::RevokeRole { .. }
+                | Statement::CreateDatabase { .. }
+                | Statement::DropDatabase { .. }
+                | Statement::AlterDatabaseOwner { .. }
+                | Statement::AlterDatabaseTemplate { .. }
+                | Statement::Grant { .. }
+                | Statement::Revoke { .. }
+                | Statement::AlterSystemSet { .. }
+                | Statement::AlterSystemReset { .. }
+                | Statement::ShowUsers
+                | Statement::ShowDatabases
+        )
+    }
+
+    /// v2.7.0: Checkpoint the server instance when one is due
+    ///
+    /// The snapshot read-locks every database, so the caller must not hold
+    /// a database lock or the storage mutex.
+    async fn save_instance(inst: &ServerInstance, storage: &Mutex<StorageEngine>) -> Result<(), DatabaseError> {
+        if !storage.lock().await.should_checkpoint() {
+            return Ok(());
+        }
+        let snapshot = inst.snapshot().await;
+        storage.lock().await.save_server_instance(&snapshot)
+    }
+
+    /// v2.7.0: Run a statement on the server instance itself (users, roles,
+    /// databases, privileges) and persist the change
+    ///
+    /// Called with the instance write lock held; the result is sent to the
+    /// client after the lock is released.
+    async fn execute_server_statement(
+        inst: &mut ServerInstance,
+        stmt: crate::parser::Statement,
+        session: &SessionContext,
+        storage: &Mutex<StorageEngine>,
+        page_storage: Option<&PageStorage>,
+        settings: &ServerSettings,
+    ) -> Result<QueryResult, DatabaseError> {
+        use crate::parser::{GrantObject, Statement};
+
+        let (changed, tag) = match stmt {
+            // User management commands
+            Statement::CreateUser { username, password, options } => (
+                settings
+                    .password_policy
+                    .check(&password)
+                    .and_then(|()| inst.create_user(&username, &password, false))
+                    .and_then(|()| inst.alter_user(&username, &options)),
+                "CREATE USER",
+            ),
+            Statement::DropUser { username } => (inst.drop_user(&username), "DROP USER"),
+            Statement::AlterUser { username, password, options } => (
+                Self::alter_user(inst, settings, &username, password.as_deref(), &options),
+                "ALTER USER",
+            ),
+            // Role management commands
+            Statement::CreateRole { role_name, is_superuser } => {
+                (inst.create_role(&role_name, is_superuser), "CREATE ROLE")
+            }
+            Statement::DropRole { role_name } => (inst.drop_role(&role_name), "DROP ROLE"),
+            Statement::GrantRole { role_name, to_user } => (inst.grant_role_to_user(&role_name, &to_user), "GRANT"),
+            Statement::RevokeRole { role_name, from_user } => {
+                (inst.revoke_role_from_user(&role_name, &from_user), "REVOKE")
+            }
+            // Database management commands
+            // v2.7.0: New databases are copies of a template, template1 by default
+            Statement::CreateDatabase { name, owner, template, is_template } => {
+                let owner = owner.unwrap_or_else(|| session.username.clone());
+                let template = template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+                let own_pid = session.backend.as_ref().map(Backend::pid);
+                let pages = page_storage.expect("v2.0.0: page storage is required");
+                (
+                    Self::create_database(inst, pages, &name, &owner, template, own_pid, is_template).await,
+                    "CREATE DATABASE",
+                )
+            }
+            // v2.7.0: Refused while other sessions are connected, unless
+            // WITH (FORCE) ends them; the pages of its tables go too
+            Statement::DropDatabase { name, force } => {
+                let own_pid = session.backend.as_ref().map(Backend::pid);
+                let pages = page_storage.expect("v2.0.0: page storage is required");
+                inst.drop_database_with_data(&name, own_pid, force, pages)?;
+                // WAL records do not name their database, so recovery must
+                // not see the ones written before the drop: checkpoint now
+                let snapshot = inst.snapshot().await;
+                let checkpointed = storage.lock().await.create_checkpoint_instance(&snapshot);
+                drop(snapshot);
+                checkpointed.map_err(|e| DatabaseError::Internal(format!("Failed to persist: {e}")))?;
+                return Ok(QueryResult::Success("DROP DATABASE".to_string()));
+            }
+            // v2.7.0: ALTER DATABASE ... OWNER TO
+            Statement::AlterDatabaseOwner { name, new_owner } => {
+                (inst.alter_database_owner(&name, &new_owner), "ALTER DATABASE")
+            }
+            // v2.7.0: ALTER DATABASE ... IS_TEMPLATE
+            Statement::AlterDatabaseTemplate { name, is_template } => {
+                (inst.set_database_template(&name, is_template), "ALTER DATABASE")
+            }
+            // Privilege commands
+            Statement::Grant { privilege, on, to_user } => {
+                let priv_type = Self::convert_privilege(&privilege);
+                match on {
+                    GrantObject::Database(db_name) => inst
+                        .get_database_metadata_mut(&db_name)
+                        .map(|meta| meta.grant(&to_user, priv_type))
+                        .ok_or(DatabaseError::DatabaseNotFound(db_name))?,
+                    // v2.3.0
+                    GrantObject::Table(table_name) => inst
+                        .get_database_mut(&session.database_name)
+                        .and_then(|db| db.table_metadata.get_mut(&table_name))
+                        .map(|meta| meta.grant(&to_user, priv_type))
+                        .ok_or(DatabaseError::TableNotFound(table_name))?,
+                }
+                (Ok(()), "GRANT")
+            }
+            Statement::Revoke { privilege, on, from_user } => {
+                let priv_type = Self::convert_privilege(&privilege);
+                match on {
+                    GrantObject::Database(db_name) => inst
+                        .get_database_metadata_mut(&db_name)
+                        .map(|meta| meta.revoke(&from_user, &priv_type))
+                        .ok_or(DatabaseError::DatabaseNotFound(db_name))?,
+                    // v2.3.0
+                    GrantObject::Table(table_name) => inst
+                        .get_database_mut(&session.database_name)
+                        .and_then(|db| db.table_metadata.get_mut(&table_name))
+                        .map(|meta| meta.revoke(&from_user, &priv_type))
+                        .ok_or(DatabaseError::TableNotFound(table_name))?,
+                }
+                (Ok(()), "REVOKE")
+            }
+            // v2.7.0: Written to the data directory, read at the next start
+            stmt @ (Statement::AlterSystemSet { .. } | Statement::AlterSystemReset { .. }) => {
+                let data_dir = storage.lock().await.data_dir().map(Path::to_path_buf);
+                Self::alter_system(data_dir.as_deref(), &stmt)?;
+                return Ok(QueryResult::Success("ALTER SYSTEM".to_string()));
+            }
+            // Metadata queries
+            stmt @ (Statement::ShowUsers | Statement::ShowDatabases) => return Ok(Self::show_metadata(inst, &stmt)),
+            _ => unreachable!("not a server statement"),
+        };
+
+        changed?;
+        Self::save_instance(inst, storage).await.map_err(|e| DatabaseError::Internal(format!("Failed to persist: {e}")))?;
+        Ok(QueryResult::Success(tag.to_string()))
+    }
+
+    /// v2.7.0: CREATE DATABASE - a copy of `template` with the rows of its
+    /// tables, in a page storage of its own
+    async fn create_database(
+        inst: &mut ServerInstance,
+        pages: &PageStorage,
+        name: &str,
+        owner: &str,
+        template: &str,
+        own_pid: Option<u32>,
+        is_template: bool,
+    ) -> Result<(), DatabaseError> {
+        // Checked before any storage is set up, so the pages of an existing
+        // database are never touched
+        if inst.get_database(name).is_some() {
+            return Err(DatabaseError::DatabaseAlreadyExists(name.to_string()));
+        }
+        if inst.get_database(template).is_none() {
+            return Err(DatabaseError::DatabaseNotFound(template.to_string()));
+        }
+
+        let (source, target) = (pages.database(template)?, pages.database(name)?);
+        let created = inst
+            .create_database_from_template(name, owner, template, own_pid, &*source.read().await, &mut *target.write().await)
+            .and_then(|()| inst.set_database_template(name, is_template));
+        if created.is_err() {
+            pages.drop_database(name)?;
+        }
+        created
+    }
+
+    /// v2.7.0: Run a read-only statement (see `QueryExecutor::is_read_only`)
+    ///
+    /// `db` is behind a read guard of its database and only a read lock is
+    /// taken on the page storage, so any number of queries run at the same
+    /// time; statements that write take the write locks and wait for running
+    /// queries to finish. Nothing is persisted afterwards since nothing changed.
+    /// Permissions must have been checked by the caller.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_read_only(
+        db: &Database,
+        database_storage: Option<&Arc<RwLock<crate::storage::DatabaseStorage>>>,
+        tx_manager: &GlobalTransactionManager,
+        stmt: crate::parser::Statement,
+        query: &str,
+        started: Instant,
+        settings: &ServerSettings,
+        parameters: &SessionParameters,
+    ) -> Result<QueryResult, DatabaseError> {
+        let db_storage = database_storage
+            .expect("v2.0.0: database_storage is required")
+            .read()
+            .await;
+        let result = match Self::result_chunks(&stmt, settings) {
+            // v2.7.0: Read in chunks, so an oversized result is refused before it is held in memory
+            Some(mut chunks) => {
+                let columns = parameters.scope(|| chunks.run(db, tx_manager, &db_storage))?;
+                let (mut rows, mut bytes) = (Vec::new(), 0);
+                while let Some(chunk) = chunks.next_chunk()? {
+                    bytes += ResultChunks::size_of(&chunk);
+                    if bytes > settings.max_result_memory {
+                        return Err(DatabaseError::ResultMemoryExceeded(settings.max_result_memory / 1024));
+                    }
+                    rows.extend(chunk);
+                }
+                QueryResult::Rows(rows, columns)
+            }
+            None => parameters
+                .scope(|| QueryExecutor::execute_read(db, stmt, tx_manager, &db_storage))?,
+        };
+        Self::record_statement(db, settings, query, started, &result);
+        Ok(result)
+    }
+
+    /// v2.7.0: Chunks to read a query's result in, if `max_result_memory` is set
+    fn result_chunks(stmt: &crate::parser::Statement, settings: &ServerSettings) -> Option<ResultChunks> {
+        if settings.max_result_memory == 0 {
+            return None;
+        }
+        ResultChunks::new(stmt.clone(), settings.max_result_memory)
+    }
+
+    /// v2.7.0: Send a read-only query's result to a `PostgreSQL` client chunk by chunk
+    ///
+    /// The locks are only held while the query runs and writes out its
+    /// result (see `ResultChunks`). The next chunk is only read once the
+    /// client has taken the previous one, so a large result never sits in
+    /// memory as a whole and a slow client holds up nobody else. An error
+    /// after the first chunk ends the result early, as in `PostgreSQL`.
+    async fn stream_read_only<W: AsyncWriteExt + Unpin>(
+        instance: &RwLock<ServerInstance>,
+        database_name: &str,
+        database_storage: Option<&Arc<RwLock<crate::storage::DatabaseStorage>>>,
+        tx_manager: &GlobalTransactionManager,
+        mut chunks: ResultChunks,
+        query: &str,
+        started: Instant,
+        settings: &ServerSettings,
+        parameters: &SessionParameters,
+        writer: &mut W,
+    ) -> std::io::Result<Result<(), DatabaseError>> {
+        let db_storage = database_storage.expect("v2.0.0: database_storage is required");
+        let columns = {
+            let inst = instance.read().await;
+            let Some(db) = inst.get_database(database_name) else {
+                return Ok(Err(DatabaseError::DatabaseNotFound(database_name.to_string())));
+            };
+            let db = db.read().await;
+            let db_storage = db_storage.read().await;
+            match parameters.scope(|| chunks.run(&db, tx_manager, &db_storage)) {
+                Ok(columns) => columns,
+                Err(e) => return Ok(Err(e)),
+            }
+        };
+
+        Message::row_description(&columns).write(writer).await?;
+        let mut sent = 0;
+        loop {
+            let rows = match chunks.next_chunk() {
+                Ok(Some(rows)) => rows,
+                Ok(None) => break,
+                Err(e) => return Ok(Err(e)),
+            };
+            for row in &rows {
+                Message::data_row(row).write(writer).await?;
+            }
+            writer.flush().await?;
+            sent += rows.len();
+        }
+
+        if let Some(db) = instance.read().await.get_database(database_name) {
+            Self::record_rows(&*db.read().await, settings, query, started, sent);
+        }
+        Message::command_complete(&format!("SELECT {sent}")).write(writer).await?;
+        Ok(Ok(()))
+    }
+
+    /// v2.7.0: `ReadyForQuery` status: in a transaction block or idle
+    const fn ready_status(transaction: &Transaction) -> u8 {
+        if transaction.is_active() {
+            transaction_status::IN_TRANSACTION
+        } else {
+            transaction_status::IDLE
+        }
+    }
+
+    /// v2.7.0: A query of nothing but comments and semicolons is empty
+    /// (an unterminated comment is not: it is a syntax error)
+    fn is_empty_query(query: &str) -> bool {
+        Lexer::new(query).all(|token| token.is_ok_and(|token| token.kind == TokenKind::Comment || token.text == ";"))
+    }
+
+    /// v2.7.0: `ParameterStatus` for every reported parameter the session changed
+    async fn send_parameter_changes<W: AsyncWriteExt + Unpin>(
+        session: &mut SessionContext,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        for (name, value) in session.parameters.take_changes() {
+            Message::parameter_status(name, &value).write(writer).await?;
+        }
+        Ok(())
+    }
+
     async fn send_postgres_result<W: AsyncWriteExt + Unpin>(
         result: QueryResult,
         writer: &mut W,
@@ -1686,49 +2357,904 @@ impl Server {
         match result {
             QueryResult::Success(msg) => {
                 // For non-SELECT queries, send CommandComplete
-                Message::command_complete(&msg).send(writer).await?;
+                Message::command_complete(&msg).write(writer).await?;
             }
             QueryResult::Rows(rows, columns) => {
                 // Send RowDescription
-                Message::row_description(&columns).send(writer).await?;
+                Message::row_description(&columns).write(writer).await?;
+                Self::send_portal_result(QueryResult::Rows(rows, columns), writer).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// v2.7.0: Result of Execute: data rows and `CommandComplete`, without `RowDescription`
+    async fn send_portal_result<W: AsyncWriteExt + Unpin>(result: QueryResult, writer: &mut W) -> std::io::Result<()> {
+        match result {
+            QueryResult::Success(msg) => Message::command_complete(&msg).write(writer).await,
+            QueryResult::Rows(rows, _) => {
+                // Send DataRow for each row
+                for row in &rows {
+                    Message::data_row(row).write(writer).await?;
+                }
+
+                // Send CommandComplete with row count
+                let tag = format!("SELECT {}", rows.len());
+                Message::command_complete(&tag).write(writer).await
+            }
+        }
+    }
+
+    /// v2.7.0: Run the query bound to a portal; `None` for an empty query
+    async fn run_portal(
+        query: &str,
+        instance: &Arc<RwLock<ServerInstance>>,
+        storage: &Mutex<StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        database_storage: Option<&Arc<RwLock<crate::storage::DatabaseStorage>>>,
+        settings: &ServerSettings,
+        session: &mut SessionContext,
+        transaction: &Transaction,
+    ) -> Result<Option<QueryResult>, DatabaseError> {
+        if Self::is_empty_query(query) {
+            return Ok(None);
+        }
+        let started = Instant::now();
+        let stmt = Self::parse_cached(instance, &session.database_name, query).await?;
+        // v2.7.0: ParameterStatus for reported parameters goes out with the next Sync
+        if let crate::parser::Statement::Set { name, value } = &stmt {
+            session.set_parameter(name, value)?;
+            return Ok(Some(QueryResult::Success("SET".to_string())));
+        }
+        if let crate::parser::Statement::Show { name } = &stmt {
+            return Self::show_parameter(session, name).map(Some);
+        }
+
+        let inst = instance.read().await;
+        let Some(db) = inst.get_database(&session.database_name) else {
+            return Err(DatabaseError::DatabaseNotFound(session.database_name.clone()));
+        };
+        let mut db = session.write_database(db).await;
+        // Same permission checks as the simple query protocol
+        if !session.temp_tables.touches(&stmt)
+            && let Some(denied) = Self::check_statement_permissions(&inst, Some(&db), &session.username, &stmt)
+        {
+            return Err(denied);
+        }
+
+        let db_storage = database_storage.expect("v2.0.0: database_storage is required");
+        let mut db_storage_guard = db_storage.write().await;
+        let mut storage_guard = storage.lock().await;
+        let storage_option = if session.temp_tables.touches(&stmt) {
+            None
+        } else {
+            Some(&mut *storage_guard)
+        };
+
+        let tx_id = transaction.tx_id();
+        let executed = session.parameters.scope(|| {
+            session.temp_tables.execute(
+                &mut db,
+                &mut db_storage_guard,
+                stmt,
+                |db, stmt, db_storage| QueryExecutor::execute(db, stmt, storage_option, tx_manager, db_storage, tx_id),
+            )
+        });
+        let executed = if transaction.is_active() {
+            executed
+        } else {
+            executed.and_then(|result| session.temp_tables.end_transaction().map(|()| result))
+        };
+
+        let result = executed?;
+        Self::record_statement(&db, settings, query, started, &result);
+        Ok(Some(result))
+    }
+
+    /// v2.7.0: `pg_export_snapshot()` and `SET TRANSACTION SNAPSHOT`
+    ///
+    /// Both pin a snapshot for the rest of the connection's transaction, so
+    /// the exporting transaction and every importer (e.g. parallel dump
+    /// workers) read the same rows.
+    fn share_snapshot(
+        stmt: crate::parser::Statement,
+        transaction: &mut Transaction,
+        tx_manager: &mut GlobalTransactionManager,
+    ) -> Result<QueryResult, DatabaseError> {
+        let (Some(tx_id), Some(snapshot)) = (transaction.tx_id(), transaction.snapshot().cloned()) else {
+            let command = match stmt {
+                crate::parser::Statement::ExportSnapshot => "pg_export_snapshot()",
+                _ => "SET TRANSACTION SNAPSHOT",
+            };
+            return Err(DatabaseError::NoActiveTransaction(command.to_string()));
+        };
+        match stmt {
+            crate::parser::Statement::ExportSnapshot => {
+                let id = tx_manager.export_snapshot(tx_id, &snapshot);
+                tx_manager.pin_snapshot(Some(snapshot));
+                Ok(QueryResult::Rows(vec![vec![id]], vec!["pg_export_snapshot".to_string()]))
+            }
+            crate::parser::Statement::SetTransactionSnapshot { id } => {
+                let snapshot = tx_manager.import_snapshot(tx_id, &id).ok_or(DatabaseError::InvalidSnapshot(id))?;
+                transaction.set_snapshot(snapshot.clone());
+                tx_manager.pin_snapshot(Some(snapshot));
+                Ok(QueryResult::Success("SET".to_string()))
+            }
+            _ => unreachable!("not a snapshot statement"),
+        }
+    }
+
+    /// v2.7.0: ALTER USER - new password (checked against the password policy)
+    /// and attributes
+    fn alter_user(
+        inst: &mut ServerInstance,
+        settings: &ServerSettings,
+        username: &str,
+        password: Option<&str>,
+        options: &UserOptions,
+    ) -> Result<(), DatabaseError> {
+        if let Some(password) = password {
+            settings.password_policy.check(password)?;
+            inst.set_password(username, password)?;
+        }
+        inst.alter_user(username, options)
+    }
+
+    /// v2.7.0: `SHOW name` - a run-time parameter of the session
+    fn show_parameter(session: &SessionContext, name: &str) -> Result<QueryResult, DatabaseError> {
+        let (column, value) = session.parameters.show(name)?;
+        Ok(QueryResult::Rows(vec![vec![value]], vec![column]))
+    }
+
+    /// v2.7.0: `SHOW USERS` (`\du`) and `SHOW DATABASES` (`\l`), sorted by name
+    fn show_metadata(inst: &ServerInstance, stmt: &crate::parser::Statement) -> QueryResult {
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" }.to_string();
+        let (mut rows, columns): (Vec<Vec<String>>, Vec<&str>) = match stmt {
+            crate::parser::Statement::ShowUsers => (
+                inst.users
+                    .iter()
+                    .map(|(username, user)| {
+                        vec![
+                            username.clone(),
+                            yes_no(user.is_superuser()),
+                            yes_no(user.can_create_db()),
+                            yes_no(user.can_create_role()),
+                            yes_no(user.can_login()),
+                            user.valid_until.map(|at| Zone::utc().format(&at)).unwrap_or_default(),
+                        ]
+                    })
+                    .collect(),
+                vec!["username", "superuser", "createdb", "createrole", "login", "valid_until"],
+            ),
+            _ => (
+                inst.database_metadata
+                    .iter()
+                    .map(|(name, meta)| vec![name.clone(), meta.owner.clone()])
+                    .collect(),
+                vec!["name", "owner"],
+            ),
+        };
+        rows.sort();
+        QueryResult::Rows(rows, columns.into_iter().map(str::to_string).collect())
+    }
+
+    /// v2.7.0: `\c` / `USE` - move the session to another database
+    ///
+    /// Like reconnecting in psql, the session's temporary tables are dropped.
+    async fn switch_database(
+        instance: &RwLock<ServerInstance>,
+        session: &mut SessionContext,
+        transaction: &Transaction,
+        database: &str,
+    ) -> Result<String, String> {
+        if transaction.is_active() {
+            return Err("cannot switch databases inside a transaction block".to_string());
+        }
+        let datid = match instance.read().await.get_database(database) {
+            Some(db) => db.read().await.oid,
+            None => return Err(format!("database \"{database}\" does not exist")),
+        };
+
+        session.database_name = database.to_string();
+        session.temp_tables = TempTables::new();
+        if let Some(backend) = &session.backend {
+            backend.set_database(datid, database);
+        }
+        Ok(format!(
+            "You are now connected to database \"{database}\" as user \"{}\".",
+            session.username
+        ))
+    }
+
+    /// v2.7.0: Report an error of an extended query message; the rest of
+    /// the pipeline up to the next Sync is skipped
+    async fn extended_error<W: AsyncWriteExt + Unpin>(
+        writer: &mut W,
+        skip_until_sync: &mut bool,
+        error: &DatabaseError,
+    ) -> std::io::Result<()> {
+        *skip_until_sync = true;
+        Message::error_response(error).write(writer).await
+    }
+
+    /// v2.3.0: Make the connected user the owner of tables created without one
+    fn with_owner(stmt: crate::parser::Statement, username: &str) -> crate::parser::Statement {
+        use crate::parser::Statement;
+
+        let owner = Some(username.to_string());
+        match stmt {
+            Statement::CreateTable { name, columns, owner: None, partition_by, access_method } => {
+                Statement::CreateTable { name, columns, owner, partition_by, access_method }
+            }
+            Statement::CreatePartition { name, parent, bound, owner: None } => {
+                Statement::CreatePartition { name, parent, bound, owner }
+            }
+            Statement::CreateForeignTable { name, columns, server, options, owner: None } => {
+                Statement::CreateForeignTable { name, columns, server, options, owner }
+            }
+            Statement::CreateTempTable { name, columns, owner: None, on_commit } => {
+                Statement::CreateTempTable { name, columns, owner, on_commit }
+            }
+            other => other,
+        }
+    }
+
+    /// v2.7.0: Answer `GET /metrics` requests with Prometheus text metrics
+    async fn serve_metrics(
+        listener: TcpListener,
+        storage: Arc<Mutex<StorageEngine>>,
+        tx_manager: GlobalTransactionManager,
+        page_storage: Option<Arc<PageStorage>>,
+        server_metrics: Arc<ServerMetrics>,
+        statements: Arc<SharedStatementCache>,
+    ) {
+        loop {
+            let socket = match listener.accept().await {
+                Ok((socket, _)) => socket,
+                Err(e) => {
+                    warn!(error = %e, "metrics endpoint accept failed");
+                    continue;
+                }
+            };
+
+            let storage = Arc::clone(&storage);
+            let tx_manager = tx_manager.clone();
+            let page_storage = page_storage.as_ref().map(Arc::clone);
+            let server_metrics = Arc::clone(&server_metrics);
+            let statements = Arc::clone(&statements);
+            tokio::spawn(async move {
+                if let Err(e) = Self::answer_metrics_request(
+                    socket,
+                    &storage,
+                    &tx_manager,
+                    page_storage.as_deref(),
+                    &server_metrics,
+                    &statements,
+                )
+                .await
+                {
+                    debug!(error = %e, "metrics request failed");
+                }
+            });
+        }
+    }
+
+    async fn answer_metrics_request(
+        mut socket: TcpStream,
+        storage: &Mutex<StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        page_storage: Option<&PageStorage>,
+        server_metrics: &ServerMetrics,
+        statements: &SharedStatementCache,
+    ) -> std::io::Result<()> {
+        use std::sync::atomic::Ordering;
+
+        match metrics::read_request(&mut socket).await? {
+            Some((method, path)) if method == "GET" && path == "/metrics" => {
+                let mut snapshot = MetricsSnapshot {
+                    connections_total: server_metrics.connections_total(),
+                    connections_active: server_metrics.connections_active(),
+                    transactions_committed: tx_manager.committed_count(),
+                    transactions_aborted: tx_manager.aborted_count(),
+                    transactions_active: tx_manager.active_count(),
+                    wal_bytes_written: storage.lock().await.wal_bytes_written(),
+                    prepared_executions: statements.executions.load(Ordering::Relaxed),
+                    prepared_reuses: statements.reuses.load(Ordering::Relaxed),
+                    prepared_evictions: statements.evictions.load(Ordering::Relaxed),
+                    unnamed_cache_hits: statements.hits.load(Ordering::Relaxed),
+                    unnamed_cache_misses: statements.misses.load(Ordering::Relaxed),
+                    ..MetricsSnapshot::default()
+                };
+                // v2.7.0: Buffer pools are summed over the databases
+                for (database, database_storage) in page_storage.map(PageStorage::databases).unwrap_or_default() {
+                    let database_storage = database_storage.read().await;
+                    let pool = database_storage.buffer_pool_stats();
+                    snapshot.buffer_pool_hits += pool.hits;
+                    snapshot.buffer_pool_misses += pool.misses;
+                    snapshot.buffer_pool_pages += pool.size;
+                    snapshot.buffer_pool_dirty_pages += pool.dirty_count;
+                    for table in database_storage.list_tables() {
+                        if let Some(rows) = database_storage.row_count(&table) {
+                            snapshot.table_rows.push((database.clone(), table, rows));
+                        }
+                    }
+                }
+                let requests = snapshot.buffer_pool_hits + snapshot.buffer_pool_misses;
+                if requests > 0 {
+                    snapshot.buffer_pool_hit_ratio = snapshot.buffer_pool_hits as f64 / requests as f64;
+                }
+                snapshot.table_rows.sort();
+                metrics::write_response(&mut socket, "200 OK", "text/plain; version=0.0.4", &snapshot.render()).await
+            }
+            Some(_) => metrics::write_response(&mut socket, "404 Not Found", "text/plain", "Not Found\n").await,
+            None => Ok(()),
+        }
+    }
+
+    /// v2.7.0: Track a finished statement in `pg_stat_statements` and the slow query log
+    /// v2.7.0: Answer requests of the HTTP/JSON query API (`POST /query`)
+    async fn serve_http(
+        listener: TcpListener,
+        instance: Arc<RwLock<ServerInstance>>,
+        storage: Arc<Mutex<StorageEngine>>,
+        tx_manager: GlobalTransactionManager,
+        page_storage: Option<Arc<PageStorage>>,
+        settings: Arc<ServerSettings>,
+    ) {
+        loop {
+            let (socket, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!(error = %e, "HTTP endpoint accept failed");
+                    continue;
+                }
+            };
+
+            let instance = Arc::clone(&instance);
+            let storage = Arc::clone(&storage);
+            let tx_manager = tx_manager.clone();
+            let page_storage = page_storage.as_ref().map(Arc::clone);
+            let settings = Arc::clone(&settings);
+            tokio::spawn(async move {
+                if let Err(e) = Self::answer_http_request(
+                    socket,
+                    peer.ip(),
+                    &instance,
+                    &storage,
+                    &tx_manager,
+                    page_storage.as_deref(),
+                    &settings,
+                )
+                .await
+                {
+                    debug!(error = %e, "HTTP request failed");
+                }
+            });
+        }
+    }
+
+    async fn answer_http_request(
+        socket: TcpStream,
+        peer: IpAddr,
+        instance: &Arc<RwLock<ServerInstance>>,
+        storage: &Mutex<StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        page_storage: Option<&PageStorage>,
+        settings: &ServerSettings,
+    ) -> std::io::Result<()> {
+        let (reader, mut writer) = socket.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let request = match HttpRequest::read(&mut reader).await {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(RequestError::Io(e)) => return Err(e),
+            Err(RequestError::Malformed(reason)) => {
+                return http::write_json(&mut writer, "400 Bad Request", &[], &http::error_json(reason)).await;
+            }
+            Err(RequestError::TooLarge) => {
+                let body = http::error_json("request body too large");
+                return http::write_json(&mut writer, "413 Payload Too Large", &[], &body).await;
+            }
+            Err(RequestError::HeadersTooLarge) => {
+                let body = http::error_json("request line or headers too large");
+                return http::write_json(&mut writer, "431 Request Header Fields Too Large", &[], &body).await;
+            }
+        };
+
+        let (status, body) = match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/query") => {
+                Self::run_http_query(&request, peer, instance, storage, tx_manager, page_storage, settings).await
+            }
+            (_, "/query") => ("405 Method Not Allowed", http::error_json("use POST /query")),
+            _ => ("404 Not Found", http::error_json("not found")),
+        };
+        let headers: &[(&str, &str)] = if status.starts_with("401") {
+            &[("WWW-Authenticate", "Basic realm=\"postgrustql\"")]
+        } else {
+            &[]
+        };
+        http::write_json(&mut writer, status, headers, &body).await
+    }
+
+    /// Authenticate an HTTP request and run its statement in autocommit mode
+    async fn run_http_query(
+        request: &HttpRequest,
+        peer: IpAddr,
+        instance: &Arc<RwLock<ServerInstance>>,
+        storage: &Mutex<StorageEngine>,
+        tx_manager: &GlobalTransactionManager,
+        page_storage: Option<&PageStorage>,
+        settings: &ServerSettings,
+    ) -> (&'static str, String) {
+        let bad_request = |message: &str| ("400 Bad Request", http::error_json(message));
+
+        let query = match QueryRequest::from_body(&request.body) {
+            Ok(query) => query,
+            Err(e) => return bad_request(&e),
+        };
+        // Same defaults as a PostgreSQL startup message without user / database
+        let (user, password) = request
+            .basic_auth()
+            .unwrap_or_else(|| ("postgres".to_string(), String::new()));
+        let database_name = query.database.clone().unwrap_or_else(|| "postgres".to_string());
+
+        let authenticated = match settings.hba.method_for(peer, &database_name, &user) {
+            HbaMethod::Reject => {
+                warn!(user, database = database_name, "HTTP request rejected by hba rules");
+                return (
+                    "403 Forbidden",
+                    http::error_json(&format!(
+                        "No pg_hba entry for host \"{peer}\", user \"{user}\", database \"{database_name}\""
+                    )),
+                );
+            }
+            HbaMethod::Trust => instance.read().await.check_login(&user).is_ok(),
+            // The HTTP API has no TLS, so there is no client certificate, and
+            // Basic authentication sends the password itself rather than a SCRAM proof
+            HbaMethod::Cert | HbaMethod::Scram => false,
+            HbaMethod::Password => {
+                let authenticated = instance.read().await.authenticate_from(&user, &password, peer);
+                match authenticated {
+                    Ok(()) => true,
+                    // v2.7.0: Locked out after repeated failures
+                    Err(e @ DatabaseError::LoginLockedOut(..)) => {
+                        warn!(user, database = database_name, client = %peer, "HTTP login rejected: locked out");
+                        return ("429 Too Many Requests", http::error_json(&e.to_string()));
+                    }
+                    Err(_) => false,
+                }
+            }
+        };
+        if !authenticated {
+            warn!(user, database = database_name, "HTTP authentication failed");
+            return ("401 Unauthorized", http::error_json("Authentication failed"));
+        }
+
+        debug!(query = query.sql, user, "HTTP query");
+        let started = Instant::now();
+        let stmt = match Self::parse_cached(instance, &database_name, &query.sql).await {
+            Ok(stmt) => stmt,
+            Err(e) => return bad_request(&format!("Parse error: {e}")),
+        };
+        if !Self::runs_over_http(&stmt) {
+            return bad_request("Transactions, COPY, temporary tables and user, role or database commands need a PostgreSQL connection");
+        }
+
+        // v2.7.0: Queries only take read locks (see `execute_read_only`)
+        if QueryExecutor::is_read_only(&stmt) {
+            let inst = instance.read().await;
+            let Some(db) = inst.get_database(&database_name) else {
+                return bad_request(&format!("Database '{database_name}' not found"));
+            };
+            let db = db.read().await;
+            if let Some(denied) = Self::check_statement_permissions(&inst, Some(&db), &user, &stmt) {
+                return ("403 Forbidden", http::error_json(&denied.to_string()));
+            }
+            // v2.7.0: Pages of the requested database
+            let database_storage = match page_storage.map(|pages| pages.database(&database_name)).transpose() {
+                Ok(database_storage) => database_storage,
+                Err(e) => return ("500 Internal Server Error", http::error_json(&e.to_string())),
+            };
+            return match Self::execute_read_only(
+                &db,
+                database_storage.as_ref(),
+                tx_manager,
+                stmt,
+                &query.sql,
+                started,
+                settings,
+                &SessionParameters::default(),
+            )
+            .await
+            {
+                Ok(result) => ("200 OK", http::result_json(&result)),
+                Err(e) => bad_request(&e.to_string()),
+            };
+        }
+
+        let inst = instance.read().await;
+        let Some(db) = inst.get_database(&database_name) else {
+            return bad_request(&format!("Database '{database_name}' not found"));
+        };
+        let mut db = db.write().await;
+        if let Some(denied) = Self::check_statement_permissions(&inst, Some(&db), &user, &stmt) {
+            return ("403 Forbidden", http::error_json(&denied.to_string()));
+        }
+        let stmt = Self::with_owner(stmt, &user);
+        let db_storage = match page_storage.map(|pages| pages.database(&database_name)).transpose() {
+            Ok(database_storage) => database_storage.expect("v2.0.0: database_storage is required"),
+            Err(e) => return ("500 Internal Server Error", http::error_json(&e.to_string())),
+        };
+
+        let mut storage_guard = storage.lock().await;
+        let mut db_storage_guard = db_storage.write().await;
+
+        match QueryExecutor::execute(&mut db, stmt, Some(&mut storage_guard), tx_manager, &mut db_storage_guard, None) {
+            Ok(result) => {
+                Self::record_statement(&db, settings, &query.sql, started, &result);
+                drop(db_storage_guard);
+                drop(storage_guard);
+                drop(db);
+                if let Err(e) = Self::save_instance(&inst, storage).await {
+                    return ("500 Internal Server Error", http::error_json(&format!("Checkpoint failed: {e}")));
+                }
+                ("200 OK", http::result_json(&result))
+            }
+            Err(e) => bad_request(&e.to_string()),
+        }
+    }
+
+    /// Every HTTP request is its own autocommit transaction without session state,
+    /// and instance-level commands are only handled on wire protocol connections
+    const fn runs_over_http(stmt: &crate::parser::Statement) -> bool {
+        use crate::parser::Statement;
+
+        !matches!(
+            stmt,
+            Statement::Begin
+                | Statement::Commit
+                | Statement::Rollback
+                | Statement::ExportSnapshot
+                | Statement::SetTransactionSnapshot { .. }
+                | Statement::Copy { .. }
+                | Statement::CreateTempTable { .. }
+                | Statement::CreateUser { .. }
+                | Statement::DropUser { .. }
+                | Statement::AlterUser { .. }
+                | Statement::CreateRole { .. }
+                | Statement::DropRole { .. }
+                | Statement::GrantRole { .. }
+                | Statement::RevokeRole { .. }
+                | Statement::CreateDatabase { .. }
+                | Statement::DropDatabase { .. }
+                | Statement::AlterDatabaseOwner { .. }
+                | Statement::AlterDatabaseTemplate { .. }
+                | Statement::Grant { .. }
+                | Statement::Revoke { .. }
+                | Statement::AlterSystemSet { .. }
+                | Statement::AlterSystemReset { .. }
+                | Statement::ShowUsers
+                | Statement::ShowDatabases
+        )
+    }
+
+    fn record_statement(
+        db: &crate::types::Database,
+        settings: &ServerSettings,
+        query: &str,
+        started: Instant,
+        result: &QueryResult,
+    ) {
+        Self::record_rows(db, settings, query, started, result.row_count());
+    }
+
+    fn record_rows(db: &crate::types::Database, settings: &ServerSettings, query: &str, started: Instant, rows: usize) {
+        let elapsed = started.elapsed();
+        db.query_stats.lock().expect("query stats poisoned").record(query, elapsed, rows);
+
+        if settings
+            .log_min_duration_statement
+            .is_some_and(|min_duration| elapsed >= min_duration)
+        {
+            warn!(duration_ms = elapsed.as_secs_f64() * 1000.0, rows, query, "slow query");
+        }
+    }
+
+    /// v2.7.0: Authenticate a startup request using the host-based rules
+    ///
+    /// Returns `false` (after sending an error response) if the client is rejected.
+    async fn authenticate_client<R, W>(
+        reader: &mut R,
+        writer: &mut W,
+        instance: &Arc<RwLock<ServerInstance>>,
+        hba: &HbaConfig,
+        peer: IpAddr,
+        client_name: Option<&str>,
+        user: &str,
+        database_name: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        match hba.method_for(peer, database_name, user) {
+            HbaMethod::Reject => {
+                warn!(user, database = database_name, "connection rejected by hba rules");
+                Message::error_response(&DatabaseError::InvalidAuthorization(format!(
+                    "No pg_hba entry for host \"{peer}\", user \"{user}\", database \"{database_name}\""
+                )))
+                .send(writer)
+                .await?;
+                Ok(false)
+            }
+            HbaMethod::Trust => {
+                if !instance.read().await.users.contains_key(user) {
+                    Message::error_response(&DatabaseError::InvalidAuthorization(format!("Role \"{user}\" does not exist")))
+                        .send(writer)
+                        .await?;
+                    return Ok(false);
+                }
+                // v2.7.0: NOLOGIN
+                let login = instance.read().await.check_login(user);
+                if let Err(e) = login {
+                    Message::error_response(&e).send(writer).await?;
+                    return Ok(false);
+                }
+                info!(user, database = database_name, method = "trust", "client authenticated");
+                Ok(true)
+            }
+            // v2.7.0: The TLS handshake verified the certificate against the
+            // CA; its common name must map to the user
+            HbaMethod::Cert => {
+                let Some(client_name) = client_name else {
+                    warn!(user, database = database_name, client = %peer, "no client certificate for cert authentication");
+                    Message::error_response(&DatabaseError::InvalidAuthorization(
+                        "connection requires a valid client certificate".to_string(),
+                    ))
+                        .send(writer)
+                        .await?;
+                    return Ok(false);
+                };
+                let map = hba.rule_for(peer, database_name, user).and_then(|rule| rule.map.as_deref());
+                if !hba.ident_allows(map, client_name, user) || !instance.read().await.users.contains_key(user) {
+                    warn!(
+                        user,
+                        database = database_name,
+                        client = %peer,
+                        certificate = client_name,
+                        "certificate name does not map to the user"
+                    );
+                    Message::error_response(&DatabaseError::InvalidAuthorization(format!(
+                        "certificate authentication failed for user \"{user}\""
+                    )))
+                        .send(writer)
+                        .await?;
+                    return Ok(false);
+                }
+                let login = instance.read().await.check_login(user);
+                if let Err(e) = login {
+                    Message::error_response(&e).send(writer).await?;
+                    return Ok(false);
+                }
+                info!(user, database = database_name, method = "cert", certificate = client_name, "client authenticated");
+                Ok(true)
+            }
+            // v2.7.0: SASL exchange, the password never crosses the wire
+            HbaMethod::Scram => {
+                let verifier = instance.read().await.scram_verifier(user);
+                let exchanged = Self::scram_exchange(reader, writer, user, verifier).await?;
+                let proof_valid = match exchanged {
+                    Ok(server_final) => server_final,
+                    Err(e) => {
+                        Message::error_response(&e).send(writer).await?;
+                        return Ok(false);
+                    }
+                };
+                let authenticated = instance.read().await.finish_scram_from(user, proof_valid.is_some(), peer);
+                match (authenticated, proof_valid) {
+                    (Ok(()), Some(server_final)) => {
+                        Message::authentication_sasl_final(&server_final).send(writer).await?;
+                        info!(user, database = database_name, method = "scram-sha-256", "client authenticated");
+                        Ok(true)
+                    }
+                    (Err(e), _) => {
+                        warn!(user, database = database_name, client = %peer, error = %e, "SCRAM authentication failed");
+                        Message::error_response(&e).send(writer).await?;
+                        Ok(false)
+                    }
+                    (Ok(()), None) => unreachable!("a login without a valid proof is refused"),
+                }
+            }
+            HbaMethod::Password => {
+                // Request password from client
+                Message::authentication_cleartext_password()
+                    .send(writer)
+                    .await?;
 
-                // Send DataRow for each row
-                for row in &rows {
-                    Message::data_row(row).send(writer).await?;
+                // Read PasswordMessage
+                let msg_type = reader.read_u8().await?;
+                if msg_type != pg_protocol::frontend::PASSWORD {
+                    Message::error_response(&DatabaseError::ProtocolViolation("Expected password message".to_string()))
+                        .send(writer)
+                        .await?;
+                    return Ok(false);
                 }
 
-                // Send CommandComplete with row count
-                let tag = format!("SELECT {}", rows.len());
-                Message::command_complete(&tag).send(writer).await?;
+                let password_msg = pg_protocol::PasswordMessage::read(reader).await?;
+
+                // Authenticate (v2.7.0: repeated failures lock the user out from this address)
+                let authenticated = instance.read().await.authenticate_from(user, &password_msg.password, peer);
+                match authenticated {
+                    Ok(()) => {
+                        info!(user, database = database_name, method = "password", "client authenticated");
+                        Ok(true)
+                    }
+                    Err(e) => {
+                        warn!(user, database = database_name, client = %peer, error = %e, "password authentication failed");
+                        Message::error_response(&e).send(writer).await?;
+                        Ok(false)
+                    }
+                }
             }
         }
-        Ok(())
+    }
+
+    /// v2.7.0: SCRAM-SHA-256 SASL exchange up to the client's proof
+    ///
+    /// `Ok(Some(server_final))` if the proof is right, `Ok(None)` if it is
+    /// not, `Err` for a client that breaks off or does not follow the protocol.
+    async fn scram_exchange<R, W>(
+        reader: &mut R,
+        writer: &mut W,
+        user: &str,
+        verifier: Option<ScramVerifier>,
+    ) -> Result<Result<Option<String>, DatabaseError>, Box<dyn std::error::Error>>
+    where
+        R: AsyncReadExt + Unpin,
+        W: AsyncWriteExt + Unpin,
+    {
+        Message::authentication_sasl(&[scram::MECHANISM]).send(writer).await?;
+        let (msg_type, data) = pg_protocol::read_frontend_message(reader).await?;
+        if msg_type != frontend::PASSWORD {
+            return Ok(Err(DatabaseError::ProtocolViolation("Expected SASL response".to_string())));
+        }
+        let initial = pg_protocol::SaslInitialResponse::from_data(&data)?;
+        if initial.mechanism != scram::MECHANISM {
+            return Ok(Err(DatabaseError::ProtocolViolation(format!(
+                "SASL mechanism \"{}\" is not supported",
+                initial.mechanism
+            ))));
+        }
+        let (exchange, server_first) = match ScramExchange::start(user, verifier, &initial.data) {
+            Ok(started) => started,
+            Err(e) => return Ok(Err(DatabaseError::ProtocolViolation(e))),
+        };
+        Message::authentication_sasl_continue(&server_first).send(writer).await?;
+
+        let (msg_type, data) = pg_protocol::read_frontend_message(reader).await?;
+        if msg_type != frontend::PASSWORD {
+            return Ok(Err(DatabaseError::ProtocolViolation("Expected SASL response".to_string())));
+        }
+        Ok(exchange.finish(&String::from_utf8_lossy(&data)).map_err(DatabaseError::ProtocolViolation))
     }
 
     async fn handle_text_client(
         mut socket: TcpStream,
-        instance: Arc<Mutex<ServerInstance>>,
+        instance: Arc<RwLock<ServerInstance>>,
         storage: Arc<Mutex<StorageEngine>>,
         tx_manager: GlobalTransactionManager,
-        database_storage: Option<Arc<Mutex<crate::storage::DatabaseStorage>>>,
+        page_storage: Option<Arc<PageStorage>>,
+        settings: &ServerSettings,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let peer = socket.peer_addr()?.ip();
         let (reader, mut writer) = socket.split();
         let mut reader = BufReader::new(reader);
 
+        // v2.7.0: The text protocol has no authentication exchange; once hba rules
+        // are configured, it is only open where they trust postgres
+        if !settings.hba.is_empty() && settings.hba.method_for(peer, "postgres", "postgres") != HbaMethod::Trust {
+            warn!(client = %peer, "text protocol connection refused by hba rules");
+            writer
+                .write_all(b"Error: text protocol connections are not allowed by the hba rules, connect with a PostgreSQL client\n")
+                .await?;
+            return Ok(());
+        }
+
         // Text protocol: простая аутентификация через первые команды или использование дефолтного пользователя
         let mut session = SessionContext::new();
         session.authenticate("postgres".to_string(), "postgres".to_string());
+        session.register(&*instance.read().await, Some(peer)).await;
 
         writer
             .write_all(b"Welcome to PostgrustSQL!\nType your SQL queries (end with semicolon)\nSupports: BEGIN, COMMIT, ROLLBACK for transactions\n")
             .await?;
+        Self::run_text_session(&mut reader, &mut writer, session, instance, storage, tx_manager, page_storage, settings)
+            .await
+    }
+
+    /// v2.7.0: Maintenance session without networking, one statement per line
+    ///
+    /// Runs the text protocol over `reader` and `writer` (stdin and stdout
+    /// for `--single-user`) as `username`, until end of input or `quit`.
+    pub async fn run_single_user<R, W>(
+        &self,
+        username: &str,
+        database: &str,
+        mut reader: R,
+        mut writer: W,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        if self.instance.read().await.get_database(database).is_none() {
+            return Err(format!("database \"{database}\" does not exist").into());
+        }
+        let mut session = SessionContext::new();
+        session.authenticate(username.to_string(), database.to_string());
+        session.register(&*self.instance.read().await, None).await;
+
+        writer
+            .write_all(b"PostgrustSQL single-user mode\nOne statement per line, quit or end of input to exit\n")
+            .await?;
+        Self::run_text_session(
+            &mut reader,
+            &mut writer,
+            session,
+            Arc::clone(&self.instance),
+            Arc::clone(&self.storage),
+            self.tx_manager.clone(),
+            self.page_storage.as_ref().map(Arc::clone),
+            &self.settings,
+        )
+        .await?;
+        writer.flush().await?;
+
+        // Like PostgreSQL on leaving single-user mode, checkpoint so that
+        // nothing of the session is left in memory only
+        let instance = self.instance.read().await;
+        let snapshot = instance.snapshot().await;
+        self.storage.lock().await.create_checkpoint_instance(&snapshot)?;
+        drop(snapshot);
+        drop(instance);
+        if let Some(pages) = &self.page_storage {
+            for (_, database) in pages.databases() {
+                database.read().await.checkpoint()?;
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_text_session<R, W>(
+        reader: &mut R,
+        writer: &mut W,
+        mut session: SessionContext,
+        instance: Arc<RwLock<ServerInstance>>,
+        storage: Arc<Mutex<StorageEngine>>,
+        mut tx_manager: GlobalTransactionManager,
+        page_storage: Option<Arc<PageStorage>>,
+        settings: &ServerSettings,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        // v2.7.0: Pages of the session's database, looked up again after \c
+        let mut database_storage =
+            page_storage.as_deref().map(|pages| pages.database(&session.database_name)).transpose()?;
+        session.parameters = std::mem::take(&mut session.parameters).with_server_values(Arc::clone(&settings.parameters));
+
         writer.write_all(b"postgrustql>\n").await?;
         writer.flush().await?;
 
         let mut line = String::new();
         let mut transaction = Transaction::new();
+        let mut output_format = OutputFormat::default();
 
         loop {
+            session.set_idle(&transaction);
             line.clear();
             let n = reader.read_line(&mut line).await?;
 
@@ -1749,68 +3275,158 @@ impl Server {
                 break;
             }
 
-            // Execute query
-            let response = match parse_statement(query) {
-                Ok(stmt) => {
-                    let mut inst = instance.lock().await;
+            // v2.7.0: \format csv|json|table switches the result output mode
+            if let Some(rest) = query.strip_prefix("\\format") {
+                let response = match rest.trim().trim_end_matches(';') {
+                    "" => format!("Output format is {output_format}\n"),
+                    name => match name.parse::<OutputFormat>() {
+                        Ok(format) => {
+                            output_format = format;
+                            format!("Output format is {format}\n")
+                        }
+                        Err(e) => format!("Error: {e}\n"),
+                    },
+                };
+                writer.write_all(response.as_bytes()).await?;
+                writer.write_all(b"postgrustql>\n").await?;
+                writer.flush().await?;
+                continue;
+            }
 
-                    // Проверяем, существует ли БД
-                    if inst.databases.contains_key(&session.database_name) {
-                        // Получаем мутабельную ссылку на БД
-                        let db = inst.get_database_mut(&session.database_name).unwrap();
+            debug!(query, "text query");
+            session.set_active(query);
+            let started = Instant::now();
 
-                        match stmt {
-                            // User management commands (v2.2.2)
-                            crate::parser::Statement::CreateUser {
-                                username,
-                                password,
-                                is_superuser,
-                            } => {
-                                match inst.create_user(&username, &password, is_superuser) {
-                                    Ok(()) => {
-                                        let mut storage_guard = storage.lock().await;
-                                        if let Err(e) = storage_guard.save_server_instance(&inst) {
-                                            format!("Error: Failed to persist user: {e}\n")
-                                        } else {
-                                            "CREATE USER\n".to_string()
-                                        }
+            // Execute query
+            let response = match Self::parse_cached(&instance, &session.database_name, query).await {
+                // v2.7.0: Run-time parameters belong to the session
+                Ok(crate::parser::Statement::Set { name, value }) => match session.set_parameter(&name, &value) {
+                    Ok(()) => output_format.render(QueryResult::Success("SET".to_string())),
+                    Err(e) => format!("Error: {e}\n"),
+                },
+                Ok(crate::parser::Statement::Show { name }) => match Self::show_parameter(&session, &name) {
+                    Ok(result) => output_format.render(result),
+                    Err(e) => format!("Error: {e}\n"),
+                },
+                // v2.7.0: Queries only take read locks (see `execute_read_only`)
+                Ok(stmt) if QueryExecutor::is_read_only(&stmt) && !session.temp_tables.touches(&stmt) => {
+                    let inst = instance.read().await;
+                    match inst.get_database(&session.database_name) {
+                        Some(db) => {
+                            let db = session.read_database(db).await;
+                            match Self::execute_read_only(
+                                &db,
+                                database_storage.as_ref(),
+                                &tx_manager,
+                                stmt,
+                                query,
+                                started,
+                                settings,
+                                &session.parameters,
+                            )
+                            .await
+                            {
+                                Ok(result) => output_format.render(result),
+                                Err(e) => format!("Error: {e}\n"),
+                            }
+                        }
+                        None => format!("Error: Database '{}' not found\n", session.database_name),
+                    }
+                }
+                // v2.7.0: psql-style \c / USE
+                Ok(crate::parser::Statement::Connect { database }) => {
+                    let switched = match Self::switch_database(&instance, &mut session, &transaction, &database).await {
+                        Ok(message) => page_storage
+                            .as_deref()
+                            .map(|pages| pages.database(&session.database_name))
+                            .transpose()
+                            .map(|pages| (message, pages))
+                            .map_err(|e| e.to_string()),
+                        Err(e) => Err(e),
+                    };
+                    match switched {
+                        Ok((message, pages)) => {
+                            database_storage = pages;
+                            format!("{message}\n")
+                        }
+                        Err(e) => format!("Error: {e}\n"),
+                    }
+                }
+                // v2.7.0: \l and \du list the server's users and databases
+                Ok(stmt @ (crate::parser::Statement::ShowUsers | crate::parser::Statement::ShowDatabases)) => {
+                    output_format.render(Self::show_metadata(&*instance.read().await, &stmt))
+                }
+                // v2.7.0: User commands change the server instance itself
+                Ok(stmt @ (crate::parser::Statement::CreateUser { .. }
+                | crate::parser::Statement::DropUser { .. }
+                | crate::parser::Statement::AlterUser { .. })) => {
+                    let mut inst = instance.write().await;
+
+                    match stmt {
+                        // User management commands (v2.2.2)
+                        crate::parser::Statement::CreateUser {
+                            username,
+                            password,
+                            options,
+                        } => {
+                            let created = settings
+                                .password_policy
+                                .check(&password)
+                                .and_then(|()| inst.create_user(&username, &password, false))
+                                .and_then(|()| inst.alter_user(&username, &options));
+                            match created {
+                                Ok(()) => {
+                                    if let Err(e) = Self::save_instance(&inst, &storage).await {
+                                        format!("Error: Failed to persist user: {e}\n")
+                                    } else {
+                                        "CREATE USER\n".to_string()
                                     }
-                                    Err(e) => format!("Error: {e}\n"),
                                 }
+                                Err(e) => format!("Error: {e}\n"),
                             }
-                            crate::parser::Statement::DropUser { username } => {
-                                match inst.drop_user(&username) {
-                                    Ok(()) => {
-                                        let mut storage_guard = storage.lock().await;
-                                        if let Err(e) = storage_guard.save_server_instance(&inst) {
-                                            format!("Error: Failed to persist: {e}\n")
-                                        } else {
-                                            "DROP USER\n".to_string()
-                                        }
+                        }
+                        crate::parser::Statement::DropUser { username } => {
+                            match inst.drop_user(&username) {
+                                Ok(()) => {
+                                    if let Err(e) = Self::save_instance(&inst, &storage).await {
+                                        format!("Error: Failed to persist: {e}\n")
+                                    } else {
+                                        "DROP USER\n".to_string()
                                     }
-                                    Err(e) => format!("Error: {e}\n"),
                                 }
+                                Err(e) => format!("Error: {e}\n"),
                             }
-                            crate::parser::Statement::AlterUser { username, password } => {
-                                match inst.users.get_mut(&username) {
-                                    Some(user) => {
-                                        user.set_password(&password);
-                                        let mut storage_guard = storage.lock().await;
-                                        if let Err(e) = storage_guard.save_server_instance(&inst) {
-                                            format!("Error: Failed to persist: {e}\n")
-                                        } else {
-                                            "ALTER USER\n".to_string()
-                                        }
+                        }
+                        crate::parser::Statement::AlterUser { username, password, options } => {
+                            let altered = Self::alter_user(&mut inst, settings, &username, password.as_deref(), &options);
+                            match altered {
+                                Ok(()) => {
+                                    if let Err(e) = Self::save_instance(&inst, &storage).await {
+                                        format!("Error: Failed to persist: {e}\n")
+                                    } else {
+                                        "ALTER USER\n".to_string()
                                     }
-                                    None => format!("Error: User '{}' not found\n", username),
                                 }
+                                Err(e) => format!("Error: {e}\n"),
                             }
+                        }
+                        _ => unreachable!("not a user command"),
+                    }
+                }
+                Ok(stmt) => {
+                    let inst = instance.read().await;
+
+                    // Проверяем, существует ли БД
+                    if let Some(db) = inst.get_database(&session.database_name) {
+                        let mut db = session.write_database(db).await;
+
+                        match stmt {
                             crate::parser::Statement::Begin => {
                                 if transaction.is_active() {
                                     "Warning: Transaction already active\n".to_string()
                                 } else {
                                     let (tx_id, snapshot) = tx_manager.begin_transaction();
-                                    transaction.begin(tx_id, snapshot, db);
+                                    transaction.begin(tx_id, snapshot, &db);
                                     format!("Transaction started (ID: {tx_id})\n")
                                 }
                             }
@@ -1821,9 +3437,12 @@ impl Server {
                                         tx_manager.commit_transaction(tx_id);
                                     }
                                     transaction.commit();
+                                    tx_manager.pin_snapshot(None);
                                     // Save server instance after commit
-                                    let mut storage_guard = storage.lock().await;
-                                    if let Err(e) = storage_guard.save_server_instance(&inst) {
+                                    drop(db);
+                                    if let Err(e) = session.temp_tables.end_transaction() {
+                                        format!("Error: {e}\n")
+                                    } else if let Err(e) = Self::save_instance(&inst, &storage).await {
                                         format!("Warning: Failed to persist changes: {e}\n")
                                     } else {
                                         "Transaction committed\n".to_string()
@@ -1838,18 +3457,30 @@ impl Server {
                                     if let Some(tx_id) = transaction.tx_id() {
                                         tx_manager.rollback_transaction(tx_id);
                                     }
-                                    transaction.rollback(db);
+                                    transaction.rollback(&mut db);
+                                    tx_manager.pin_snapshot(None);
                                     "Transaction rolled back\n".to_string()
                                 } else {
                                     "Error: No active transaction\n".to_string()
                                 }
                             }
+                            // v2.7.0: Snapshots shared between connections
+                            stmt @ (crate::parser::Statement::ExportSnapshot
+                            | crate::parser::Statement::SetTransactionSnapshot { .. }) => {
+                                match Self::share_snapshot(stmt, &mut transaction, &mut tx_manager) {
+                                    Ok(result) => output_format.render(result),
+                                    Err(e) => format!("Error: {e}\n"),
+                                }
+                            }
                             other_stmt => {
                                 // Get storage lock for WAL logging and checkpointing
                                 let mut storage_guard = storage.lock().await;
 
-                                // Execute with WAL logging (only if not in transaction)
-                                let storage_option = if transaction.is_active() {
+                                // Execute with WAL logging (only if not in transaction,
+                                // v2.7.0: and never for temporary tables)
+                                let storage_option = if transaction.is_active()
+                                    || session.temp_tables.touches(&other_stmt)
+                                {
                                     None
                                 } else {
                                     Some(&mut *storage_guard)
@@ -1859,26 +3490,40 @@ impl Server {
                                 let db_storage = database_storage
                                     .as_ref()
                                     .expect("v2.0.0: database_storage is required");
-                                let mut db_storage_guard = db_storage.lock().await;
+                                let mut db_storage_guard = db_storage.write().await;
+
+                                let tx_id = transaction.tx_id();
+                                let executed = session.parameters.scope(|| {
+                                    session.temp_tables.execute(
+                                        &mut db,
+                                        &mut db_storage_guard,
+                                        other_stmt,
+                                        |db, stmt, db_storage| {
+                                            QueryExecutor::execute(db, stmt, storage_option, &tx_manager, db_storage, tx_id)
+                                        },
+                                    )
+                                });
+                                let executed = if transaction.is_active() {
+                                    executed
+                                } else {
+                                    executed.and_then(|result| {
+                                        session.temp_tables.end_transaction().map(|()| result)
+                                    })
+                                };
 
-                                match QueryExecutor::execute(
-                                    db,
-                                    other_stmt,
-                                    storage_option,
-                                    &tx_manager,
-                                    &mut db_storage_guard,
-                                    transaction.tx_id(),
-                                ) {
+                                match executed {
                                     Ok(result) => {
+                                        Self::record_statement(&db, settings, query, started, &result);
+                                        drop(db_storage_guard);
+                                        drop(storage_guard);
+                                        drop(db);
                                         // Checkpoint if needed (only if not in transaction)
                                         if transaction.is_active() {
-                                            Self::format_result(result)
-                                        } else if let Err(e) =
-                                            storage_guard.save_server_instance(&inst)
-                                        {
+                                            output_format.render(result)
+                                        } else if let Err(e) = Self::save_instance(&inst, &storage).await {
                                             format!("Warning: Failed to checkpoint: {e}\n")
                                         } else {
-                                            Self::format_result(result)
+                                            output_format.render(result)
                                         }
                                     }
                                     Err(e) => format!("Error: {e}\n"),
@@ -1900,30 +3545,6 @@ impl Server {
         Ok(())
     }
 
-    fn format_result(result: QueryResult) -> String {
-        match result {
-            QueryResult::Success(msg) => format!("{msg}\n"),
-            QueryResult::Rows(rows, columns) => {
-                if rows.is_empty() {
-                    return "(0 rows)\n".to_string();
-                }
-
-                let mut table = ComfyTable::new();
-                table.load_preset(UTF8_FULL);
-
-                // Add header
-                table.set_header(columns.iter().map(Cell::new));
-
-                // Add rows
-                for row in rows {
-                    table.add_row(row.iter().map(Cell::new));
-                }
-
-                format!("{}\n({} rows)\n", table, table.row_iter().count() - 1)
-            }
-        }
-    }
-
     const fn convert_privilege(
         priv_type: &crate::parser::PrivilegeType,
     ) -> crate::types::Privilege {
@@ -1940,83 +3561,269 @@ impl Server {
 
     /// v2.3.0: Check permissions for a statement before execution
     ///
-    /// Returns None if permission is granted, Some(error_message) if denied
+    /// Returns None if permission is granted, Some(error) if denied
     fn check_statement_permissions(
         instance: &ServerInstance,
-        db_name: &str,
+        db: Option<&Database>,
         username: &str,
         stmt: &crate::parser::Statement,
-    ) -> Option<String> {
-        use crate::parser::Statement;
+    ) -> Option<DatabaseError> {
+        use crate::parser::{AlterTableOperation, GrantObject, ObjectRef, Statement};
         use crate::types::Privilege;
 
+        let can = |table: &str, privilege: &Privilege| {
+            db.is_some_and(|db| instance.check_table_permission(username, db, table, privilege))
+        };
+        let owns = |table: &str| db.is_some_and(|db| instance.is_table_owner_or_superuser(username, db, table));
+
         match stmt {
+            // v2.7.0: SELECT without FROM / from a function reads no table directly
+            Statement::Select { from, .. } if from.is_empty() || from.contains('(') => {}
+
+            // v2.7.0: A cursor needs what its query reads
+            Statement::DeclareCursor { query, .. } => {
+                return Self::check_statement_permissions(instance, db, username, query);
+            }
+
             // SELECT - check SELECT privilege
             Statement::Select { from, .. } => {
-                if !instance.check_table_permission(username, db_name, from, &Privilege::Select) {
-                    return Some(format!(
-                        "Permission denied: User '{}' does not have SELECT privilege on table '{}'",
-                        username, from
-                    ));
+                if !can(from, &Privilege::Select) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' does not have SELECT privilege on table '{from}'"
+                    )));
                 }
             }
 
             // INSERT - check INSERT privilege
-            Statement::Insert { table, .. } => {
-                if !instance.check_table_permission(username, db_name, table, &Privilege::Insert) {
-                    return Some(format!(
-                        "Permission denied: User '{}' does not have INSERT privilege on table '{}'",
-                        username, table
-                    ));
+            Statement::Insert { table, .. } | Statement::InsertMany { table, .. } => {
+                if !can(table, &Privilege::Insert) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' does not have INSERT privilege on table '{table}'"
+                    )));
+                }
+                // v2.7.0: INSERT ... SELECT also needs what the query reads
+                if let Statement::InsertMany { source: crate::parser::InsertSource::Query(query), .. } = stmt {
+                    return Self::check_statement_permissions(instance, db, username, query);
                 }
             }
 
             // UPDATE - check UPDATE privilege
             Statement::Update { table, .. } => {
-                if !instance.check_table_permission(username, db_name, table, &Privilege::Update) {
-                    return Some(format!(
-                        "Permission denied: User '{}' does not have UPDATE privilege on table '{}'",
-                        username, table
-                    ));
+                if !can(table, &Privilege::Update) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' does not have UPDATE privilege on table '{table}'"
+                    )));
                 }
             }
 
             // DELETE - check DELETE privilege
             Statement::Delete { from, .. } => {
-                if !instance.check_table_permission(username, db_name, from, &Privilege::Delete) {
-                    return Some(format!(
-                        "Permission denied: User '{}' does not have DELETE privilege on table '{}'",
-                        username, from
-                    ));
+                if !can(from, &Privilege::Delete) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' does not have DELETE privilege on table '{from}'"
+                    )));
                 }
             }
 
             // ALTER TABLE - check owner or superuser
-            Statement::AlterTable { name, .. } => {
-                if !instance.is_table_owner_or_superuser(username, db_name, name) {
-                    return Some(format!(
-                        "Permission denied: User '{}' must be table owner or superuser to ALTER TABLE '{}'",
-                        username, name
-                    ));
+            Statement::AlterTable { name, operation } => {
+                if !owns(name) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' must be table owner or superuser to ALTER TABLE '{name}'"
+                    )));
+                }
+                // v2.7.0: The new owner must exist, and a regular owner may only
+                // hand the table to themselves or to a role they belong to
+                if let AlterTableOperation::OwnerTo(new_owner) = operation {
+                    if !instance.users.contains_key(new_owner) && !instance.roles.contains_key(new_owner) {
+                        return Some(DatabaseError::RoleNotFound(new_owner.clone()));
+                    }
+                    if !instance.is_superuser(username) && !instance.is_member_of(username, new_owner) {
+                        return Some(DatabaseError::PermissionDenied(format!(
+                            "User '{username}' must be a member of role '{new_owner}' to transfer ownership of '{name}'"
+                        )));
+                    }
+                }
+            }
+
+            // v2.7.0: COMMENT ON a table or column - check owner or superuser
+            Statement::CommentOn { object: ObjectRef::Table(table) | ObjectRef::Column { table, .. }, .. }
+                if !owns(table) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be table owner or superuser to COMMENT ON '{table}'"
+                )));
+            }
+
+            // v2.7.0: Undoing a transaction rewrites rows of any table and any user
+            Statement::AlterSystemSet { .. } | Statement::AlterSystemReset { .. } if !instance.is_superuser(username) => {
+                return Some(DatabaseError::PermissionDenied(format!("User '{username}' must be superuser to execute ALTER SYSTEM")));
+            }
+
+            Statement::UndoTransaction { .. } if !instance.is_superuser(username) => {
+                return Some(DatabaseError::PermissionDenied(format!("User '{username}' must be superuser to undo a transaction")));
+            }
+
+            // v2.7.0: Views, types and schemas record no owner, so only a superuser changes it
+            Statement::AlterOwner { owner, .. } => {
+                if !instance.is_superuser(username) {
+                    return Some(DatabaseError::PermissionDenied(format!("User '{username}' must be superuser to change owners")));
+                }
+                if !instance.users.contains_key(owner) && !instance.roles.contains_key(owner) {
+                    return Some(DatabaseError::RoleNotFound(owner.clone()));
                 }
             }
 
             // DROP TABLE - check owner or superuser
-            Statement::DropTable { name } => {
-                if !instance.is_table_owner_or_superuser(username, db_name, name) {
-                    return Some(format!(
-                        "Permission denied: User '{}' must be table owner or superuser to DROP TABLE '{}'",
-                        username, name
-                    ));
+            Statement::DropTable { name, .. } => {
+                if !owns(name) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' must be table owner or superuser to DROP TABLE '{name}'"
+                    )));
+                }
+            }
+
+            // v2.7.0: Indexes and partitions belong to their table's owner
+            Statement::CreateIndex { table, .. }
+                if !owns(table) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be table owner or superuser to CREATE INDEX on '{table}'"
+                )));
+            }
+            Statement::DropIndex { name } => {
+                let index = db.and_then(|db| db.indexes.get(name));
+                if let Some(index) = index
+                    && !owns(index.table_name())
+                {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' must be table owner or superuser to DROP INDEX '{name}'"
+                    )));
+                }
+            }
+            Statement::Cluster { table: Some(table), .. }
+                if !owns(table) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be table owner or superuser to CLUSTER '{table}'"
+                )));
+            }
+            Statement::CreatePartition { parent, .. }
+                if !owns(parent) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be table owner or superuser to create a partition of '{parent}'"
+                )));
+            }
+
+            // v2.7.0: CREATEDB attribute
+            Statement::CreateDatabase { .. } if !instance.can_create_database(username) => {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must have CREATEDB or be superuser to CREATE DATABASE"
+                )));
+            }
+            // v2.7.0: Databases that are not templates are copied by their owner only
+            Statement::CreateDatabase { template: Some(template), .. }
+                if instance.get_database_metadata(template).is_some()
+                    && !instance.can_copy_database(username, template) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be database owner or superuser to copy database '{template}'"
+                )));
+            }
+
+            // v2.7.0: Users may change their own password; anything else about
+            // users needs CREATEROLE, and superusers are managed by superusers
+            Statement::AlterUser { username: target, password: Some(_), options }
+                if target == username && options.is_empty() => {}
+            Statement::CreateUser { username: target, options, .. }
+            | Statement::AlterUser { username: target, options, .. } => {
+                if let Some(denied) = Self::check_user_management(instance, username, target, options.superuser.is_some()) {
+                    return Some(denied);
+                }
+            }
+            Statement::DropUser { username: target } => {
+                if let Some(denied) = Self::check_user_management(instance, username, target, false) {
+                    return Some(denied);
+                }
+            }
+
+            // v2.7.0: Database-level operations require database owner or superuser
+            Statement::DropDatabase { name, .. }
+                if instance.get_database_metadata(name).is_some()
+                    && !instance.is_database_owner_or_superuser(username, name) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be database owner or superuser to DROP DATABASE '{name}'"
+                )));
+            }
+            Statement::AlterDatabaseTemplate { name, .. }
+                if instance.get_database_metadata(name).is_some()
+                    && !instance.is_database_owner_or_superuser(username, name) =>
+            {
+                return Some(DatabaseError::PermissionDenied(format!(
+                    "User '{username}' must be database owner or superuser to ALTER DATABASE '{name}'"
+                )));
+            }
+            Statement::AlterDatabaseOwner { name, new_owner } => {
+                if instance.get_database_metadata(name).is_some()
+                    && !instance.is_database_owner_or_superuser(username, name)
+                {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' must be database owner or superuser to ALTER DATABASE '{name}'"
+                    )));
+                }
+                if !instance.is_superuser(username) && !instance.is_member_of(username, new_owner) {
+                    return Some(DatabaseError::PermissionDenied(format!(
+                        "User '{username}' must be a member of role '{new_owner}' to transfer ownership of '{name}'"
+                    )));
                 }
             }
 
+            // v2.7.0: Only the owner may grant or revoke privileges on an object
+            Statement::Grant { on, .. } | Statement::Revoke { on, .. } => match on {
+                GrantObject::Database(name) => {
+                    if instance.get_database_metadata(name).is_some()
+                        && !instance.is_database_owner_or_superuser(username, name)
+                    {
+                        return Some(DatabaseError::PermissionDenied(format!(
+                            "User '{username}' must be database owner or superuser to grant privileges on database '{name}'"
+                        )));
+                    }
+                }
+                GrantObject::Table(table) => {
+                    if !owns(table) {
+                        return Some(DatabaseError::PermissionDenied(format!(
+                            "User '{username}' must be table owner or superuser to grant privileges on table '{table}'"
+                        )));
+                    }
+                }
+            },
+
             // Other statements - no table-level permissions required
             _ => {}
         }
 
         None // Permission granted
     }
+
+    /// v2.7.0: May `username` create, alter or drop the user `target`?
+    fn check_user_management(
+        instance: &ServerInstance,
+        username: &str,
+        target: &str,
+        sets_superuser: bool,
+    ) -> Option<DatabaseError> {
+        if !instance.can_create_role(username) {
+            return Some(DatabaseError::PermissionDenied(format!(
+                "User '{username}' must have CREATEROLE or be superuser to manage users"
+            )));
+        }
+        if (sets_superuser || instance.is_superuser(target)) && !instance.is_superuser(username) {
+            return Some(DatabaseError::PermissionDenied(format!("User '{username}' must be superuser to manage superusers")));
+        }
+        None
+    }
 }
 
 /// Convert a Value to CSV-formatted string (v2.4.1)
@@ -2057,3 +3864,754 @@ fn value_to_csv_string(value: &crate::types::Value) -> String {
         Value::Enum(_, s) => s.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::testing::{connect, connect_to_new_server, spawn};
+    use crate::client::{Client, ClientConfig, ClientError, Row, parse_row_description, put_bind_execute_sync, put_cstring, put_message, put_parse};
+    use crate::network::pg_protocol::backend;
+    use bytes::{BufMut, BytesMut};
+
+    /// Send one line over the text protocol and read the answer up to the next prompt
+    async fn text_query(reader: &mut BufReader<TcpStream>, query: &str) -> String {
+        reader.get_mut().write_all(format!("{query}\n").as_bytes()).await.unwrap();
+        read_prompt(reader).await
+    }
+
+    async fn read_prompt(reader: &mut BufReader<TcpStream>) -> String {
+        let mut answer = String::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 || line.trim_end() == "postgrustql>" {
+                return answer;
+            }
+            answer.push_str(&line);
+        }
+    }
+
+    /// Start an in-memory server and connect a text protocol client
+    async fn start_server() -> (Arc<RwLock<ServerInstance>>, BufReader<TcpStream>) {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let server = Server::new_in_memory("postgres", "postgres", "postgres");
+        let instance = Arc::clone(&server.instance);
+        let listen_addr = addr.clone();
+        tokio::spawn(async move {
+            let _ = server.start(&listen_addr).await;
+        });
+
+        let socket = loop {
+            match TcpStream::connect(&addr).await {
+                Ok(socket) => break socket,
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        };
+        let mut client = BufReader::new(socket);
+        read_prompt(&mut client).await; // greeting
+        (instance, client)
+    }
+
+    #[tokio::test]
+    async fn test_negotiates_protocol_version() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let server = Server::new_in_memory("postgres", "postgres", "postgres");
+        let listen_addr = addr.clone();
+        tokio::spawn(async move {
+            let _ = server.start(&listen_addr).await;
+        });
+        let mut socket = loop {
+            match TcpStream::connect(&addr).await {
+                Ok(socket) => break socket,
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        };
+
+        // Protocol 3.2 with an option this server does not know
+        let mut params = Vec::new();
+        for part in ["user", "postgres", "database", "postgres", "_pq_.test_option", "on"] {
+            params.extend_from_slice(part.as_bytes());
+            params.push(0);
+        }
+        params.push(0);
+        let mut startup = Vec::new();
+        startup.extend_from_slice(&(params.len() as i32 + 8).to_be_bytes());
+        startup.extend_from_slice(&((3 << 16) | 2_i32).to_be_bytes());
+        startup.extend_from_slice(&params);
+        socket.write_all(&startup).await.unwrap();
+
+        assert_eq!(socket.read_u8().await.unwrap(), pg_protocol::backend::NEGOTIATE_PROTOCOL_VERSION);
+        let mut body = vec![0; socket.read_i32().await.unwrap() as usize - 4];
+        socket.read_exact(&mut body).await.unwrap();
+        assert_eq!(&body[..8], &[0, 0, 0, 0, 0, 0, 0, 1]); // minor version 0, one option
+        assert_eq!(&body[8..], b"_pq_.test_option\0");
+
+        // The connection goes on with authentication
+        assert_eq!(socket.read_u8().await.unwrap(), pg_protocol::backend::AUTHENTICATION);
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_auth() {
+        use crate::network::hba::{HbaRule, IdentMapping};
+        use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+        use tokio_rustls::rustls::pki_types::{PrivateKeyDer, ServerName};
+        use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.distinguished_name.push(DnType::CommonName, "test CA");
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let signed = |common_name: &str| {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params.distinguished_name.push(DnType::CommonName, common_name);
+            (params.signed_by(&key, &ca, &ca_key).unwrap(), key)
+        };
+        let (server_cert, server_key) = signed("localhost");
+        let (client_cert, client_key) = signed("alice.example.com");
+
+        let tls = crate::network::tls::server_config_from_pem(
+            server_cert.pem().as_bytes(),
+            server_key.serialize_pem().as_bytes(),
+            Some(ca.pem().as_bytes()),
+        )
+        .unwrap();
+        let cert_rule = HbaRule {
+            address: "all".to_string(),
+            database: "all".to_string(),
+            user: "all".to_string(),
+            method: HbaMethod::Cert,
+            map: Some("certs".to_string()),
+        };
+        let hba = HbaConfig::new(vec![cert_rule]).with_ident_maps(vec![IdentMapping {
+            map: "certs".to_string(),
+            system_user: "alice.example.com".to_string(),
+            database_user: "alice".to_string(),
+        }]);
+        let server = Server::new_in_memory("postgres", "postgres", "postgres").with_hba(hba).with_tls(tls);
+        server.instance.write().await.create_user("alice", "unused password", false).unwrap();
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let listen_addr = addr.clone();
+        tokio::spawn(async move {
+            let _ = server.start(&listen_addr).await;
+        });
+
+        // SSLRequest, TLS handshake, startup; true if the server answers AuthenticationOk
+        let login = async |user: &str, with_certificate: bool| {
+            let mut socket = loop {
+                match TcpStream::connect(&addr).await {
+                    Ok(socket) => break socket,
+                    Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+                }
+            };
+            socket.write_i32(8).await.unwrap();
+            socket.write_i32(pg_protocol::SSL_REQUEST_CODE).await.unwrap();
+            assert_eq!(socket.read_u8().await.unwrap(), b'S');
+
+            let mut roots = RootCertStore::empty();
+            roots.add(ca.der().clone()).unwrap();
+            let builder = ClientConfig::builder().with_root_certificates(roots);
+            let config = if with_certificate {
+                let key = PrivateKeyDer::try_from(client_key.serialize_der()).unwrap();
+                builder.with_client_auth_cert(vec![client_cert.der().clone()], key).unwrap()
+            } else {
+                builder.with_no_client_auth()
+            };
+            let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+            let mut stream = connector.connect(ServerName::try_from("localhost").unwrap(), socket).await.unwrap();
+
+            let mut params = Vec::new();
+            for part in ["user", user, "database", "postgres"] {
+                params.extend_from_slice(part.as_bytes());
+                params.push(0);
+            }
+            params.push(0);
+            stream.write_i32(params.len() as i32 + 8).await.unwrap();
+            stream.write_i32(pg_protocol::PROTOCOL_VERSION).await.unwrap();
+            stream.write_all(&params).await.unwrap();
+            stream.flush().await.unwrap();
+
+            if stream.read_u8().await.unwrap() != pg_protocol::backend::AUTHENTICATION {
+                return false;
+            }
+            stream.read_i32().await.unwrap();
+            stream.read_i32().await.unwrap() == 0
+        };
+
+        assert!(login("alice", true).await);
+        // The certificate maps to alice only, and the rule needs a certificate
+        assert!(!login("postgres", true).await);
+        assert!(!login("alice", false).await);
+    }
+
+    #[tokio::test]
+    async fn test_text_meta_commands() {
+        let (instance, mut client) = start_server().await;
+        instance.write().await.create_database("shop", "postgres").unwrap();
+
+        let answer = text_query(&mut client, "\\c shop").await;
+        assert!(answer.contains("connected to database \"shop\""), "{answer}");
+        text_query(&mut client, "CREATE TABLE orders (id INTEGER);").await;
+        assert!(text_query(&mut client, "\\dt").await.contains("orders"));
+
+        text_query(&mut client, "USE postgres;").await;
+        assert!(!text_query(&mut client, "\\dt").await.contains("orders"));
+        assert!(text_query(&mut client, "\\c missing").await.contains("database \"missing\" does not exist"));
+
+        let databases = text_query(&mut client, "\\l").await;
+        assert!(databases.contains("shop") && databases.contains("postgres"), "{databases}");
+        assert!(text_query(&mut client, "\\du").await.contains("postgres"));
+
+        // pg_stat_activity follows the switch
+        text_query(&mut client, "\\connect shop").await;
+        let sessions = instance.read().await.sessions.list();
+        assert_eq!(sessions[0].datname, "shop");
+    }
+
+    #[tokio::test]
+    async fn test_text_output_formats() {
+        let (_instance, mut client) = start_server().await;
+        text_query(&mut client, "CREATE TABLE t (id INTEGER, note TEXT);").await;
+        text_query(&mut client, "INSERT INTO t VALUES (1, 'a, b');").await;
+        assert!(text_query(&mut client, "SELECT * FROM t;").await.contains("(1 rows)"));
+
+        assert_eq!(text_query(&mut client, "\\format csv").await, "Output format is csv\n");
+        assert_eq!(text_query(&mut client, "SELECT * FROM t;").await, "id,note\n1,\"a, b\"\n");
+        assert_eq!(text_query(&mut client, "INSERT INTO t VALUES (2, NULL);").await, "INSERT 0 1\n");
+
+        text_query(&mut client, "\\format json").await;
+        let answer = text_query(&mut client, "SELECT note FROM t WHERE id = 2;").await;
+        assert_eq!(answer, "{\"columns\":[\"note\"],\"row_count\":1,\"rows\":[[null]]}\n");
+
+        assert!(text_query(&mut client, "\\format xml").await.starts_with("Error:"));
+        assert_eq!(text_query(&mut client, "\\format").await, "Output format is json\n");
+        text_query(&mut client, "\\format table").await;
+        assert!(text_query(&mut client, "SELECT * FROM t;").await.contains("(2 rows)"));
+    }
+
+    #[tokio::test]
+    async fn test_readers_share_the_database_lock() {
+        let (instance, mut client) = start_server().await;
+        text_query(&mut client, "CREATE TABLE t (id INTEGER);").await;
+        text_query(&mut client, "INSERT INTO t VALUES (1);").await;
+
+        // A query still running in another session
+        let inst = instance.read().await;
+        let running_query = inst.get_database("postgres").unwrap().read().await;
+
+        let answer = tokio::time::timeout(Duration::from_secs(5), text_query(&mut client, "SELECT * FROM t;"))
+            .await
+            .expect("a read must not wait for other readers");
+        assert!(answer.contains("│ 1 "), "{answer}");
+
+        // A write waits until the running query is done
+        let insert = text_query(&mut client, "INSERT INTO t VALUES (2);");
+        tokio::pin!(insert);
+        assert!(tokio::time::timeout(Duration::from_millis(200), &mut insert).await.is_err());
+        drop(running_query);
+        let answer = tokio::time::timeout(Duration::from_secs(5), insert).await.unwrap();
+        assert!(!answer.starts_with("Error"), "{answer}");
+    }
+
+    #[tokio::test]
+    async fn test_databases_are_locked_separately() {
+        let (instance, mut client) = start_server().await;
+        text_query(&mut client, "CREATE TABLE t (id INTEGER);").await;
+        instance.write().await.create_database("other", "postgres").unwrap();
+
+        // A long write in another database does not hold up this one
+        let inst = instance.read().await;
+        let other_write = inst.get_database("other").unwrap().write().await;
+        let answer = tokio::time::timeout(Duration::from_secs(5), text_query(&mut client, "INSERT INTO t VALUES (1);"))
+            .await
+            .expect("a write must not wait for another database");
+        assert!(!answer.starts_with("Error"), "{answer}");
+
+        // ...while one in the same database does
+        let postgres_write = inst.get_database("postgres").unwrap().write().await;
+        drop(other_write);
+        let insert = text_query(&mut client, "INSERT INTO t VALUES (2);");
+        tokio::pin!(insert);
+        assert!(tokio::time::timeout(Duration::from_millis(200), &mut insert).await.is_err());
+        drop(postgres_write);
+        let answer = tokio::time::timeout(Duration::from_secs(5), insert).await.unwrap();
+        assert!(!answer.starts_with("Error"), "{answer}");
+    }
+
+    #[tokio::test]
+    async fn test_single_user_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+        let server = Server::new_with_config("postgres", "postgres", "postgres", data_dir, true).unwrap();
+        let input = "CREATE TABLE t (id INTEGER);\nINSERT INTO t VALUES (1);\nSELECT id FROM t;\n\nALTER USER postgres WITH PASSWORD 'rescued';\nquit\nSELECT 2;\n";
+        let mut output = Vec::new();
+        server.run_single_user("postgres", "postgres", input.as_bytes(), &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("PostgrustSQL single-user mode\n"), "{output}");
+        assert!(output.contains("INSERT 0 1\n") && output.contains("ALTER USER\n"), "{output}");
+        assert!(output.trim_end().ends_with("Goodbye!"), "nothing runs after quit: {output}");
+        drop(server);
+
+        // Leaving the session checkpoints the catalog
+        let server = Server::new_with_config("postgres", "postgres", "postgres", data_dir, true).unwrap();
+        let instance = server.instance.read().await;
+        assert!(instance.authenticate("postgres", "rescued"));
+        assert!(instance.get_database("postgres").unwrap().read().await.tables.contains_key("t"));
+        drop(instance);
+
+        let error = server.run_single_user("postgres", "missing", &b""[..], Vec::new()).await.unwrap_err();
+        assert_eq!(error.to_string(), "database \"missing\" does not exist");
+    }
+
+    #[tokio::test]
+    async fn test_show_and_set_parameters() {
+        let server = Server::new_in_memory("postgres", "postgres", "postgres").with_max_result_rows(500);
+        let input = "SHOW max_result_rows\nSET statement_timeout = 'soon'\nSET statement_timeout = 5000\nSHOW statement_timeout\nSET nope = 1\nSHOW nope\nSET max_result_rows = 10\n";
+        let mut output = Vec::new();
+        server.run_single_user("postgres", "postgres", input.as_bytes(), &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let answers: Vec<&str> = output.split("postgrustql>\n").skip(1).map(str::trim).collect();
+        assert!(answers[0].contains("max_result_rows") && answers[0].contains("500"), "{output}");
+        assert_eq!(answers[1], "Error: invalid value for parameter \"statement_timeout\": \"soon\"");
+        assert_eq!(answers[2], "SET");
+        assert!(answers[3].contains("5000"), "{output}");
+        assert_eq!(answers[4], "Error: unrecognized configuration parameter \"nope\"");
+        assert_eq!(answers[5], "Error: unrecognized configuration parameter \"nope\"");
+        assert_eq!(answers[6], "Error: parameter \"max_result_rows\" cannot be changed");
+    }
+
+    #[tokio::test]
+    async fn test_session_work_mem_sizes_sorts() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+        // A sort that spills at all goes past temp_file_limit
+        let server = Server::new_with_config("postgres", "postgres", "postgres", data_dir, true).unwrap().with_temp_file_limit(1);
+        let values: Vec<String> = (0..500).map(|id| format!("({id}, 'row number {id}')")).collect();
+        let select = "SELECT id, name FROM t ORDER BY name";
+        let input = format!(
+            "CREATE TABLE t (id INTEGER, name TEXT)\nINSERT INTO t VALUES {}\n{select}\nSET work_mem = 1\n{select}\nSET work_mem = DEFAULT\n{select}\n",
+            values.join(", ")
+        );
+        let mut output = Vec::new();
+        server.run_single_user("postgres", "postgres", input.as_bytes(), &mut output).await.unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let answers: Vec<&str> = output.split("postgrustql>\n").skip(1).map(str::trim).collect();
+        // Within the server's 4 MB the sort stays in memory, within the session's 1 kB it spills
+        assert!(answers[2].contains("(500 rows)"), "{}", answers[2]);
+        assert_eq!(answers[4], "Error: temporary file size exceeds temp_file_limit (1kB)");
+        assert!(answers[6].contains("(500 rows)"), "{}", answers[6]);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_data_dir_is_not_reinitialized() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().to_str().unwrap();
+        let legacy = dir.path().join("server_instance.db");
+        std::fs::write(&legacy, b"not a snapshot").unwrap();
+
+        assert!(Server::new_with_config("postgres", "postgres", "postgres", data_dir, true).is_err());
+        // The unreadable snapshot is left for the operator, nothing replaces it
+        assert_eq!(std::fs::read(&legacy).unwrap(), b"not a snapshot");
+        assert!(!dir.path().join("checkpoint").exists());
+    }
+
+    #[tokio::test]
+    async fn test_pg_locks() {
+        let addr = spawn(Server::new_in_memory("postgres", "secret", "postgres"));
+        let mut client = connect(&addr).await;
+        let mut other = connect(&addr).await;
+        client.batch_execute("CREATE TABLE t (id INTEGER)").await.unwrap();
+        other.batch_execute("BEGIN").await.unwrap();
+        other.batch_execute("INSERT INTO t VALUES (1)").await.unwrap();
+
+        let locks = client.simple_query("SELECT locktype, database, mode, granted FROM pg_locks").await.unwrap();
+        let kinds: Vec<(String, String, String)> =
+            locks.iter().map(|row| (row.get("locktype"), row.get("mode"), row.get("granted"))).collect();
+        // The database is given by its OID, as in pg_database
+        let oid: String = client.simple_query("SELECT oid FROM pg_database").await.unwrap()[0].get("oid");
+        let databases: Vec<Option<String>> = locks.iter().map(|row| row.get("database")).collect();
+        assert!(databases.contains(&Some(oid)), "{databases:?}");
+        // The query shares the database; the open transaction holds its id
+        assert!(kinds.contains(&("object".to_string(), "ShareLock".to_string(), "t".to_string())), "{kinds:?}");
+        assert!(kinds.contains(&("transactionid".to_string(), "ExclusiveLock".to_string(), "t".to_string())), "{kinds:?}");
+        assert_eq!(kinds.len(), 2);
+
+        other.batch_execute("COMMIT").await.unwrap();
+        let locks = client.simple_query("SELECT locktype FROM pg_locks").await.unwrap();
+        assert_eq!(locks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_time_zone() {
+        let mut client = connect_to_new_server().await;
+        client.batch_execute("CREATE TABLE events (id INTEGER, at TIMESTAMPTZ)").await.unwrap();
+        client.batch_execute("INSERT INTO events VALUES (1, '2024-03-01 12:00:00+00')").await.unwrap();
+
+        // Without an offset the time is read in the session's zone
+        client.batch_execute("SET TIME ZONE 5.5").await.unwrap();
+        assert_eq!(client.parameter("TimeZone"), Some("5.5"));
+        client.batch_execute("INSERT INTO events VALUES (2, '2024-03-01 17:30:00')").await.unwrap();
+        let at = |rows: Vec<Row>| rows.iter().map(|row| row.get::<_, String>("at")).collect::<Vec<_>>();
+        assert_eq!(
+            at(client.simple_query("SELECT id, at FROM events").await.unwrap()),
+            vec!["2024-03-01 17:30:00+05:30", "2024-03-01 17:30:00+05:30"]
+        );
+
+        client.batch_execute("SET TIME ZONE DEFAULT").await.unwrap();
+        assert_eq!(client.parameter("TimeZone"), Some("UTC"));
+        assert_eq!(
+            at(client.simple_query("SELECT id, at FROM events WHERE at = '2024-03-01 12:00:00'").await.unwrap()),
+            vec!["2024-03-01 12:00:00+00", "2024-03-01 12:00:00+00"]
+        );
+        let error = client.batch_execute("SET TIME ZONE 'Mars/Olympus_Mons'").await.unwrap_err();
+        assert!(error.to_string().contains("invalid value for parameter \"TimeZone\""), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_show_time_zone() {
+        let mut client = connect_to_new_server().await;
+        let shown = |rows: Vec<Row>| rows[0].get::<_, String>("TimeZone");
+        assert_eq!(shown(client.simple_query("SHOW TimeZone").await.unwrap()), "UTC");
+
+        client.batch_execute("SET TIME ZONE 5.5").await.unwrap();
+        assert_eq!(shown(client.simple_query("SHOW timezone").await.unwrap()), "5.5");
+        assert_eq!(shown(client.query("SHOW TIME ZONE", &[]).await.unwrap()), "5.5");
+        let rows = client.simple_query("SELECT current_setting('TimeZone')").await.unwrap();
+        assert_eq!(rows[0].get::<_, String>(0), "5.5");
+
+        match client.simple_query("SHOW no_such_setting").await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!((code.as_str(), message.as_str()), ("42704", "unrecognized configuration parameter \"no_such_setting\""));
+            }
+            other => panic!("expected server error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_describe_portal_and_empty_query() {
+        let mut client = connect_to_new_server().await;
+        client.batch_execute("CREATE TABLE t (id INTEGER, name TEXT)").await.unwrap();
+        client.batch_execute("INSERT INTO t VALUES (1, 'a')").await.unwrap();
+
+        // Describe of the portal carries the result formats from Bind;
+        // Execute only sends the rows
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, "", "SELECT id, name FROM t", &[]);
+        put_message(&mut msg, frontend::BIND, |body| {
+            put_cstring(body, "");
+            put_cstring(body, "");
+            body.put_i16(0);
+            body.put_i16(0);
+            body.put_i16(1);
+            body.put_i16(1); // binary
+        });
+        put_message(&mut msg, frontend::DESCRIBE, |body| {
+            body.put_u8(b'P');
+            put_cstring(body, "");
+        });
+        put_message(&mut msg, frontend::EXECUTE, |body| {
+            put_cstring(body, "");
+            body.put_i32(0);
+        });
+        put_message(&mut msg, frontend::SYNC, |_| {});
+        client.send_raw(&msg).await;
+
+        let messages = client.receive_until_ready().await;
+        let types: Vec<u8> = messages.iter().map(|(msg_type, _)| *msg_type).collect();
+        assert_eq!(
+            types,
+            [
+                backend::PARSE_COMPLETE,
+                backend::BIND_COMPLETE,
+                backend::ROW_DESCRIPTION,
+                backend::DATA_ROW,
+                backend::COMMAND_COMPLETE,
+                backend::READY_FOR_QUERY,
+            ]
+        );
+        let description = &messages[2].1;
+        assert_eq!(&description[description.len() - 2..], &1_i16.to_be_bytes());
+        assert_eq!(parse_row_description(description).unwrap(), ["id", "name"]);
+
+        // The columns come from the catalog: a query that fails when it runs
+        // is described, and fails at Execute
+        client.batch_execute("INSERT INTO t VALUES (2, 'b')").await.unwrap();
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, "", "SELECT name, (SELECT id FROM t) AS first FROM t", &[]);
+        put_bind_execute_sync(&mut msg, "", &[]);
+        client.send_raw(&msg).await;
+        let messages = client.receive_until_ready().await;
+        let types: Vec<u8> = messages.iter().map(|(msg_type, _)| *msg_type).collect();
+        assert_eq!(
+            types,
+            [
+                backend::PARSE_COMPLETE,
+                backend::BIND_COMPLETE,
+                backend::ROW_DESCRIPTION,
+                backend::ERROR_RESPONSE,
+                backend::READY_FOR_QUERY,
+            ]
+        );
+        assert_eq!(parse_row_description(&messages[2].1).unwrap(), ["name", "first"]);
+
+        // An empty query, and closing what does not exist
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, "", "", &[]);
+        put_bind_execute_sync(&mut msg, "", &[]);
+        put_message(&mut msg, frontend::CLOSE, |body| {
+            body.put_u8(b'S');
+            put_cstring(body, "never_prepared");
+        });
+        put_message(&mut msg, frontend::SYNC, |_| {});
+        client.send_raw(&msg).await;
+
+        let types: Vec<u8> = client.receive_until_ready().await.into_iter().map(|(t, _)| t).collect();
+        assert_eq!(
+            types,
+            [
+                backend::PARSE_COMPLETE,
+                backend::BIND_COMPLETE,
+                backend::NO_DATA,
+                backend::EMPTY_QUERY_RESPONSE,
+                backend::READY_FOR_QUERY,
+            ]
+        );
+        let types: Vec<u8> = client.receive_until_ready().await.into_iter().map(|(t, _)| t).collect();
+        assert_eq!(types, [backend::CLOSE_COMPLETE, backend::READY_FOR_QUERY]);
+
+        let mut msg = BytesMut::new();
+        put_message(&mut msg, frontend::QUERY, |body| put_cstring(body, "  "));
+        client.send_raw(&msg).await;
+        let types: Vec<u8> = client.receive_until_ready().await.into_iter().map(|(t, _)| t).collect();
+        assert_eq!(types, [backend::EMPTY_QUERY_RESPONSE, backend::READY_FOR_QUERY]);
+
+        // Semicolons and comments alone are empty too, and leave a transaction block open
+        client.batch_execute("BEGIN").await.unwrap();
+        for query in [";", "-- nothing here", "/* nothing */ ; ;"] {
+            let mut msg = BytesMut::new();
+            put_message(&mut msg, frontend::QUERY, |body| put_cstring(body, query));
+            client.send_raw(&msg).await;
+            let messages = client.receive_until_ready().await;
+            let types: Vec<u8> = messages.iter().map(|(t, _)| *t).collect();
+            assert_eq!(types, [backend::EMPTY_QUERY_RESPONSE, backend::READY_FOR_QUERY], "{query}");
+            assert_eq!(messages[1].1, [transaction_status::IN_TRANSACTION], "{query}");
+        }
+        client.batch_execute("ROLLBACK").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_extended_queries() {
+        let mut client = connect_to_new_server().await;
+        client.batch_execute("CREATE TABLE t (id INTEGER)").await.unwrap();
+
+        let bind_execute = |buf: &mut BytesMut, statement: &str| {
+            put_message(buf, frontend::BIND, |body| {
+                put_cstring(body, "");
+                put_cstring(body, statement);
+                body.put_i16(0);
+                body.put_i16(0);
+                body.put_i16(0);
+            });
+            put_message(buf, frontend::EXECUTE, |body| {
+                put_cstring(body, "");
+                body.put_i32(0);
+            });
+        };
+
+        // Two cycles and a Flush: the answers arrive without a Sync
+        let mut msg = BytesMut::new();
+        put_parse(&mut msg, "ins", "INSERT INTO t VALUES (1)", &[]);
+        bind_execute(&mut msg, "ins");
+        put_parse(&mut msg, "", "SELECT id FROM t", &[]);
+        bind_execute(&mut msg, "");
+        put_message(&mut msg, frontend::FLUSH, |_| {});
+        client.send_raw(&msg).await;
+
+        let (mut seen, mut completed) = (Vec::new(), 0);
+        while completed < 2 {
+            let (msg_type, _) = tokio::time::timeout(std::time::Duration::from_secs(5), client.receive_raw())
+                .await
+                .expect("Flush did not send the buffered responses");
+            completed += usize::from(msg_type == backend::COMMAND_COMPLETE);
+            seen.push(msg_type);
+        }
+        assert!(seen.contains(&backend::DATA_ROW));
+        assert!(!seen.contains(&backend::READY_FOR_QUERY));
+
+        // Executing the missing statement fails; the second cycle is skipped up to Sync
+        let mut msg = BytesMut::new();
+        put_message(&mut msg, frontend::SYNC, |_| {});
+        bind_execute(&mut msg, "missing");
+        bind_execute(&mut msg, "ins");
+        put_message(&mut msg, frontend::SYNC, |_| {});
+        client.send_raw(&msg).await;
+
+        let mut seen = vec![client.receive_raw().await.0];
+        while seen.len() < 2 || seen.last() != Some(&backend::READY_FOR_QUERY) {
+            seen.push(client.receive_raw().await.0);
+        }
+        assert_eq!(
+            seen,
+            [backend::READY_FOR_QUERY, backend::BIND_COMPLETE, backend::ERROR_RESPONSE, backend::READY_FOR_QUERY]
+        );
+        assert_eq!(client.simple_query("SELECT * FROM t").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_startup_parameters() {
+        let addr = spawn(Server::new_in_memory("postgres", "secret", "postgres"));
+        let mut client = connect(&addr).await;
+        let config = ClientConfig::new(&addr)
+            .with_password("secret")
+            .with_parameter("application_name", "reporting")
+            .with_parameter("options", "-c client_encoding=utf8 --search-path=public");
+        let reporting = config.connect().await.unwrap();
+        assert_eq!(reporting.parameter("application_name"), Some("reporting"));
+
+        let sessions = client.simple_query("SELECT * FROM pg_stat_activity").await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        let reporting_session = sessions.iter().find(|row| row.get::<_, String>("application_name") == "reporting").unwrap();
+        assert_eq!(reporting_session.get::<_, String>("state"), "idle");
+        assert_eq!(reporting_session.get::<_, String>("datname"), "postgres");
+        let own_session = sessions.iter().find(|row| row.get::<_, String>("state") == "active").unwrap();
+        assert_eq!(own_session.get::<_, String>("query"), "SELECT * FROM pg_stat_activity");
+
+        reporting.close().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(client.simple_query("SELECT * FROM pg_stat_activity").await.unwrap().len(), 1);
+
+        // Checked at startup instead of on the first query
+        let error = |result: Result<Client, ClientError>| match result {
+            Err(ClientError::Server { message, .. }) => message,
+            Err(e) => panic!("expected server error, got {e}"),
+            Ok(_) => panic!("expected server error"),
+        };
+        let missing = ClientConfig::new(&addr).with_password("secret").with_database("missing");
+        assert_eq!(error(missing.connect().await), "database \"missing\" does not exist");
+        let latin1 = ClientConfig::new(&addr).with_password("secret").with_parameter("client_encoding", "LATIN1");
+        assert!(error(latin1.connect().await).contains("client_encoding"));
+        let bad_options = ClientConfig::new(&addr).with_password("secret").with_parameter("options", "-x");
+        assert!(error(bad_options.connect().await).contains("invalid command-line argument"));
+    }
+
+    #[tokio::test]
+    async fn test_numeric_overflow_sqlstate() {
+        let mut client = connect_to_new_server().await;
+        client.batch_execute("CREATE TABLE big (n BIGINT)").await.unwrap();
+        client.batch_execute("INSERT INTO big VALUES (9223372036854775807)").await.unwrap();
+        client.batch_execute("INSERT INTO big VALUES (1)").await.unwrap();
+
+        let code = |result: Result<Vec<Row>, ClientError>| match result {
+            Err(ClientError::Server { code, message, .. }) => (code, message),
+            other => panic!("expected server error, got {other:?}"),
+        };
+        assert_eq!(code(client.simple_query("SELECT SUM(n) FROM big").await), ("22003".to_string(), "integer out of range".to_string()));
+        assert_eq!(code(client.simple_query("SELECT n + 1 FROM big").await).0, "22003");
+        assert_eq!(code(client.simple_query("SELECT n / 0 FROM big").await).0, "22012");
+
+        // INTEGER is 32-bit: literals that fit and INTEGER columns overflow past 2^31 - 1
+        assert_eq!(code(client.simple_query("SELECT 2147483647 + 1").await).0, "22003");
+        client.batch_execute("CREATE TABLE small (a INTEGER)").await.unwrap();
+        assert_eq!(code(client.simple_query("INSERT INTO small VALUES (2147483648)").await).0, "22003");
+        client.batch_execute("INSERT INTO small VALUES (3)").await.unwrap();
+        assert_eq!(code(client.simple_query("SELECT a * 1000000000 FROM small").await).0, "22003");
+        let rows = client.simple_query("SELECT 2147483648 + 1, n * 1000000000 FROM big WHERE n = 1").await.unwrap();
+        assert_eq!((rows[0].get::<_, i64>(0), rows[0].get::<_, i64>(1)), (2_147_483_649, 1_000_000_000));
+        assert_eq!(code(client.simple_query("SELECT * FROM missing").await).0, "42P01");
+    }
+
+    #[tokio::test]
+    async fn test_string_too_long_sqlstate() {
+        let mut client = connect_to_new_server().await;
+        client.batch_execute("CREATE TABLE codes (code VARCHAR(3))").await.unwrap();
+
+        match client.batch_execute("INSERT INTO codes VALUES ('abcd')").await {
+            Err(ClientError::Server { code, message, .. }) => {
+                assert_eq!((code.as_str(), message.as_str()), ("22001", "value too long for type character varying(3)"));
+            }
+            other => panic!("expected server error, got {other:?}"),
+        }
+
+        // UPDATE with a bound parameter goes through the same length check
+        client.batch_execute("INSERT INTO codes VALUES ('abc')").await.unwrap();
+        match client.execute("UPDATE codes SET code = $1", &[&"abcd"]).await {
+            Err(ClientError::Server { code, .. }) => assert_eq!(code, "22001"),
+            other => panic!("expected server error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_results_are_streamed() {
+        let server = Server::new_in_memory("postgres", "secret", "postgres").with_max_result_memory(4);
+        let mut client = connect(&spawn(server)).await;
+        client.batch_execute("CREATE TABLE big (id INTEGER, payload TEXT)").await.unwrap();
+        let values: Vec<String> = (0..300).map(|id| format!("({id}, '{}')", "x".repeat(40))).collect();
+        client.batch_execute(&format!("INSERT INTO big VALUES {}", values.join(", "))).await.unwrap();
+
+        // About 30 kB of rows against a 4 kB budget: sent in several chunks
+        let rows = client.simple_query("SELECT id, payload FROM big ORDER BY id DESC").await.unwrap();
+        let ids: Vec<i64> = rows.iter().map(|row| row.get("id")).collect();
+        assert_eq!(ids, (0..300).rev().collect::<Vec<_>>());
+        let rows = client.simple_query("SELECT id FROM big LIMIT 150 OFFSET 100").await.unwrap();
+        assert_eq!(rows.len(), 150);
+        assert_eq!(rows[0].get::<_, i64>("id"), 100);
+    }
+
+    #[tokio::test]
+    async fn test_prepared_statement_after_ddl() {
+        let mut client = connect_to_new_server().await;
+        client.batch_execute("CREATE TABLE items (id INTEGER)").await.unwrap();
+
+        let star = client.prepare("SELECT * FROM items").await.unwrap();
+        let ids = client.prepare("SELECT id FROM items").await.unwrap();
+        assert!(client.query_prepared(&star, &[]).await.unwrap().is_empty());
+        assert!(client.query_prepared(&ids, &[]).await.unwrap().is_empty());
+
+        client.batch_execute("ALTER TABLE items ADD COLUMN note TEXT").await.unwrap();
+        client.batch_execute("INSERT INTO items VALUES (1, 'x')").await.unwrap();
+        // Same columns as before: still fine; `*` now has one more column
+        assert_eq!(client.query_prepared(&ids, &[]).await.unwrap().len(), 1);
+        for _ in 0..2 {
+            match client.query_prepared(&star, &[]).await {
+                Err(ClientError::Server { code, message, .. }) => {
+                    assert_eq!(code, "0A000");
+                    assert_eq!(message, "cached plan must not change result type");
+                }
+                other => panic!("expected a stale plan error, got {other:?}"),
+            }
+        }
+        let star = client.prepare("SELECT * FROM items").await.unwrap();
+        assert_eq!(client.query_prepared(&star, &[]).await.unwrap()[0].columns(), ["id", "note"]);
+
+        // A dropped table fails the statement as well
+        client.batch_execute("DROP TABLE items").await.unwrap();
+        assert!(client.query_prepared(&ids, &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_reports_parameter_status() {
+        let mut client = connect_to_new_server().await;
+        assert_eq!(client.parameter("TimeZone"), Some("UTC"));
+        assert_eq!(client.parameter("DateStyle"), Some("ISO, MDY"));
+
+        client.batch_execute("SET TimeZone = 'Europe/Berlin'").await.unwrap();
+        assert_eq!(client.parameter("TimeZone"), Some("Europe/Berlin"));
+        client.batch_execute("SET application_name TO batch_loader").await.unwrap();
+        assert_eq!(client.parameter("application_name"), Some("batch_loader"));
+        let sessions = client.simple_query("SELECT * FROM pg_stat_activity").await.unwrap();
+        assert_eq!(sessions[0].get::<_, String>("application_name"), "batch_loader");
+
+        // Through the extended protocol the status arrives before ReadyForQuery too
+        let set_style = client.prepare("SET DateStyle = 'ISO, DMY'").await.unwrap();
+        client.execute_prepared(&set_style, &[]).await.unwrap();
+        assert_eq!(client.parameter("DateStyle"), Some("ISO, DMY"));
+
+        let error = client.batch_execute("SET client_encoding = 'LATIN1'").await.unwrap_err();
+        assert!(error.to_string().contains("client_encoding"), "{error}");
+        assert!(client.batch_execute("SET server_version = '9.6'").await.is_err());
+        assert_eq!(client.parameter("client_encoding"), Some("UTF8"));
+    }
+}
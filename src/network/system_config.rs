@@ -0,0 +1,202 @@
+/// Server parameters persisted by ALTER SYSTEM (v2.7.0)
+///
+/// `ALTER SYSTEM SET name = value` writes the parameter to
+/// `postgrustsql.auto.toml` in the data directory, and `ALTER SYSTEM RESET`
+/// removes it again. The server merges that file over `postgrustsql.toml`
+/// when it starts (environment variables still win), so changes take effect
+/// at the next restart. The file is rewritten as a whole on every change and
+/// is not meant to be edited by hand.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the overlay file in the data directory
+pub const AUTO_CONFIG_FILE: &str = "postgrustsql.auto.toml";
+
+const HEADER: &str = "# Do not edit this file manually!\n# It is overwritten by the ALTER SYSTEM command.\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Integer,
+    Text,
+}
+
+/// Parameters ALTER SYSTEM may change and the values they take
+///
+/// Users, the data directory and the hba / ident tables stay in
+/// `postgrustsql.toml`.
+const PARAMETERS: &[(&str, Kind)] = &[
+    ("host", Kind::Text),
+    ("port", Kind::Integer),
+    ("log_level", Kind::Text),
+    ("log_format", Kind::Text),
+    ("log_destination", Kind::Text),
+    ("log_min_duration_statement", Kind::Integer),
+    ("max_prepared_statements", Kind::Integer),
+    ("work_mem", Kind::Integer),
+    ("max_query_memory", Kind::Integer),
+    ("temp_file_limit", Kind::Integer),
+    ("max_result_rows", Kind::Integer),
+    ("max_result_memory", Kind::Integer),
+    ("password_min_length", Kind::Integer),
+    ("auth_lockout_failures", Kind::Integer),
+    ("auth_lockout_window", Kind::Integer),
+    ("auth_lockout_duration", Kind::Integer),
+    ("wal_compression", Kind::Bool),
+    ("vectorized_execution", Kind::Bool),
+    ("metrics_addr", Kind::Text),
+    ("http_addr", Kind::Text),
+    ("ssl_cert_file", Kind::Text),
+    ("ssl_key_file", Kind::Text),
+    ("ssl_ca_file", Kind::Text),
+];
+
+/// Contents of the overlay file: parameter names with their TOML values
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoConfig {
+    values: BTreeMap<String, String>,
+}
+
+impl AutoConfig {
+    /// Path of the overlay file of a data directory
+    #[must_use]
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(AUTO_CONFIG_FILE)
+    }
+
+    /// Read the overlay of a data directory; none yet is an empty one
+    pub fn load(data_dir: &Path) -> Result<Self, String> {
+        let path = Self::path(data_dir);
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("could not parse \"{}\": {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("could not read \"{}\": {e}", path.display())),
+        }
+    }
+
+    /// Write the overlay through a temporary file, so a crash leaves either
+    /// the old or the new contents
+    pub fn save(&self, data_dir: &Path) -> std::io::Result<()> {
+        let path = Self::path(data_dir);
+        let temp = path.with_extension("toml.tmp");
+        fs::write(&temp, self.to_string())?;
+        fs::rename(&temp, &path)
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut values = BTreeMap::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (name, value) = line.split_once('=').ok_or_else(|| format!("invalid line \"{line}\""))?;
+            values.insert(name.trim().to_string(), value.trim().to_string());
+        }
+        Ok(Self { values })
+    }
+
+    /// `ALTER SYSTEM SET name = value`; a `DEFAULT` value resets the parameter
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let name = name.to_lowercase();
+        let kind = Self::kind(&name)?;
+        if value.eq_ignore_ascii_case("default") {
+            self.values.remove(&name);
+            return Ok(());
+        }
+
+        let invalid = || format!("invalid value for parameter \"{name}\": \"{value}\"");
+        let literal = match kind {
+            Kind::Bool => match value.to_lowercase().as_str() {
+                "on" | "true" | "yes" | "1" => "true".to_string(),
+                "off" | "false" | "no" | "0" => "false".to_string(),
+                _ => return Err(invalid()),
+            },
+            Kind::Integer => value.trim().parse::<i64>().map_err(|_| invalid())?.to_string(),
+            Kind::Text => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+        };
+        self.values.insert(name, literal);
+        Ok(())
+    }
+
+    /// `ALTER SYSTEM RESET name`, or every parameter for `RESET ALL` (`None`)
+    pub fn reset(&mut self, name: Option<&str>) -> Result<(), String> {
+        match name {
+            Some(name) => {
+                let name = name.to_lowercase();
+                Self::kind(&name)?;
+                self.values.remove(&name);
+            }
+            None => self.values.clear(),
+        }
+        Ok(())
+    }
+
+    /// TOML value of a parameter in the overlay
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    fn kind(name: &str) -> Result<Kind, String> {
+        PARAMETERS
+            .iter()
+            .find(|(parameter, _)| *parameter == name)
+            .map(|(_, kind)| *kind)
+            .ok_or_else(|| format!("parameter \"{name}\" cannot be changed with ALTER SYSTEM"))
+    }
+}
+
+impl std::fmt::Display for AutoConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(HEADER)?;
+        for (name, value) in &self.values {
+            writeln!(f, "{name} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_reset_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AutoConfig::load(dir.path()).unwrap();
+        assert_eq!(config, AutoConfig::default());
+
+        config.set("work_mem", "8192").unwrap();
+        config.set("WAL_COMPRESSION", "off").unwrap();
+        config.set("log_destination", "/var/log/pgr \"main\".log").unwrap();
+        config.set("port", "5433").unwrap();
+        config.save(dir.path()).unwrap();
+
+        let text = fs::read_to_string(AutoConfig::path(dir.path())).unwrap();
+        assert!(text.starts_with(HEADER));
+        assert!(text.contains("wal_compression = false\n"));
+        assert!(text.contains("log_destination = \"/var/log/pgr \\\"main\\\".log\"\n"));
+
+        let mut loaded = AutoConfig::load(dir.path()).unwrap();
+        assert_eq!(loaded, config);
+        loaded.set("port", "DEFAULT").unwrap();
+        loaded.reset(Some("work_mem")).unwrap();
+        assert_eq!(loaded.get("port"), None);
+        assert_eq!(loaded.get("work_mem"), None);
+        assert_eq!(loaded.get("wal_compression"), Some("false"));
+        loaded.reset(None).unwrap();
+        assert_eq!(loaded, AutoConfig::default());
+    }
+
+    #[test]
+    fn test_rejects_unknown_parameters_and_bad_values() {
+        let mut config = AutoConfig::default();
+        assert_eq!(
+            config.set("data_dir", "/tmp").unwrap_err(),
+            "parameter \"data_dir\" cannot be changed with ALTER SYSTEM"
+        );
+        assert!(config.set("password", "secret").is_err());
+        assert!(config.reset(Some("no_such_parameter")).is_err());
+        assert_eq!(config.set("work_mem", "lots").unwrap_err(), "invalid value for parameter \"work_mem\": \"lots\"");
+        assert!(config.set("vectorized_execution", "maybe").is_err());
+        assert_eq!(config, AutoConfig::default());
+    }
+}
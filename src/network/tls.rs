@@ -0,0 +1,73 @@
+/// TLS for `PostgreSQL` protocol connections (v2.7.0)
+///
+/// A client opening with `SSLRequest` gets `S` and a TLS handshake when the
+/// server has a certificate, `N` (plain connection) otherwise. With a CA file
+/// clients may present a certificate signed by that CA; `cert` hba rules then
+/// log them in by the certificate's common name, mapped to a database user
+/// through an ident map. A client certificate is not required for the
+/// handshake itself, so TLS clients without one can still use password rules.
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig, ServerConnection};
+use crate::types::DatabaseError;
+
+/// Server TLS settings from PEM files: the certificate chain, its private key
+/// and the CA that signs client certificates (`None`: clients are not asked for one)
+pub fn load_server_config(
+    cert_file: &Path,
+    key_file: &Path,
+    ca_file: Option<&Path>,
+) -> Result<Arc<ServerConfig>, DatabaseError> {
+    let read = |path: &Path| {
+        std::fs::read(path)
+            .map_err(|e| DatabaseError::TlsConfig(format!("could not read \"{}\": {e}", path.display())))
+    };
+    let ca = ca_file.map(read).transpose()?;
+    server_config_from_pem(&read(cert_file)?, &read(key_file)?, ca.as_deref())
+}
+
+/// Same as `load_server_config`, from PEM text
+pub fn server_config_from_pem(cert: &[u8], key: &[u8], ca: Option<&[u8]>) -> Result<Arc<ServerConfig>, DatabaseError> {
+    let invalid = |what: &str, e: &dyn std::fmt::Display| DatabaseError::TlsConfig(format!("{what}: {e}"));
+
+    let chain = CertificateDer::pem_slice_iter(cert)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid("server certificate", &e))?;
+    if chain.is_empty() {
+        return Err(DatabaseError::TlsConfig("server certificate: no certificate found".to_string()));
+    }
+    let key = PrivateKeyDer::from_pem_slice(key).map_err(|e| invalid("server key", &e))?;
+
+    let builder = ServerConfig::builder();
+    let builder = match ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            for cert in CertificateDer::pem_slice_iter(ca) {
+                roots.add(cert.map_err(|e| invalid("CA certificate", &e))?).map_err(|e| invalid("CA certificate", &e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                .allow_unauthenticated()
+                .build()
+                .map_err(|e| invalid("CA certificate", &e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder.with_single_cert(chain, key).map_err(|e| invalid("server certificate", &e))?;
+    Ok(Arc::new(config))
+}
+
+/// Common name of the client certificate, if the client sent one
+///
+/// The handshake only succeeds with certificates the CA signed, so the name
+/// can be trusted.
+#[must_use]
+pub fn client_common_name(connection: &ServerConnection) -> Option<String> {
+    let cert = connection.peer_certificates()?.first()?;
+    let (_, cert) = x509_parser::parse_x509_certificate(cert).ok()?;
+    let name = cert.subject().iter_common_name().next()?.as_str().ok()?;
+    Some(name.to_string())
+}
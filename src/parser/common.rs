@@ -1,16 +1,17 @@
 use crate::types::{DataType, Value};
-use chrono::{NaiveDate, NaiveDateTime, DateTime, Utc};
+use chrono::{NaiveDate, NaiveDateTime};
 use uuid::Uuid;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use nom::{
     branch::alt,
-    bytes::complete::{tag, tag_no_case, take_while, take_while1},
-    character::complete::{alpha1, char, digit1, multispace0},
-    combinator::{map, map_res, opt, recognize},
+    bytes::complete::{is_not, take_while, take_while1},
+    character::complete::{alpha1, digit1, multispace0},
+    combinator::{map, map_res, opt, recognize, verify},
+    multi::{many0, many1},
     sequence::{delimited, pair, tuple},
-    IResult,
 };
+use super::error::{IResult, char, expecting, is_word_char, tag, tag_no_case};
 
 pub fn ws<'a, F, O>(inner: F) -> impl FnMut(&'a str) -> IResult<&'a str, O>
 where
@@ -19,30 +20,63 @@ where
     delimited(multispace0, inner, multispace0)
 }
 
+// Keywords the grammar uses to tell clauses apart, so they cannot be names
+// unless double-quoted, in alphabetical order (v2.7.0)
+const RESERVED_KEYWORDS: &[&str] = &[
+    "ALL", "AND", "AS", "ASC", "CASE", "CHECK", "COLLATE", "CONSTRAINT", "CREATE", "CROSS", "DEFAULT",
+    "DESC", "DISTINCT", "ELSE", "END", "EXCEPT", "FALSE", "FETCH", "FOR", "FOREIGN", "FROM", "FULL",
+    "GRANT", "GROUP", "HAVING", "IN", "INNER", "INTERSECT", "INTO", "IS", "JOIN", "LATERAL", "LIKE",
+    "LIMIT", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER", "PRIMARY", "REFERENCES",
+    "RETURNING", "SELECT", "TABLE", "THEN", "TO", "TRUE", "UNION", "UNIQUE", "USING", "WHEN", "WHERE",
+    "WINDOW", "WITH",
+];
+
+/// Whether `word` is a reserved keyword, in any case (v2.7.0)
+pub fn is_reserved_keyword(word: &str) -> bool {
+    RESERVED_KEYWORDS
+        .binary_search_by(|keyword| keyword.bytes().cmp(word.bytes().map(|b| b.to_ascii_uppercase())))
+        .is_ok()
+}
+
+// Bare word: a letter or `_`, then letters, digits and `_` (v2.7.0)
+pub fn word(input: &str) -> IResult<&str, &str> {
+    recognize(pair(alt((alpha1, tag("_"))), take_while(is_word_char)))(input)
+}
+
+// Name: a word that is not a reserved keyword, or any text in double quotes
+// with `""` for a quote, e.g. "order" (v2.7.0)
 pub fn identifier(input: &str) -> IResult<&str, String> {
-    map(
-        recognize(pair(
-            alt((alpha1, tag("_"))),
-            take_while(|c: char| c.is_alphanumeric() || c == '_'),
-        )),
-        |s: &str| s.to_string(),
-    )(input)
+    expecting("identifier", alt((
+        map(verify(word, |word: &str| !is_reserved_keyword(word)), str::to_string),
+        map(
+            delimited(char('"'), recognize(many1(alt((tag("\"\""), is_not("\""))))), char('"')),
+            |name: &str| name.replace("\"\"", "\""),
+        ),
+    )))(input)
+}
+
+// Table or type name, optionally qualified with the `public` schema as pg_dump writes it (v2.7.0)
+pub fn table_name(input: &str) -> IResult<&str, String> {
+    expecting("identifier", nom::sequence::preceded(opt(tag_no_case("public.")), identifier))(input)
 }
 
 // Identifier that is not a reserved keyword (v2.6.0)
-// Used in condition parsing to avoid conflicts with EXISTS, NOT, etc.
+// Used in condition parsing to avoid conflicts with EXISTS; v2.7.0: NOT, AND
+// and OR are reserved keywords and no identifiers at all
 pub fn non_keyword_identifier(input: &str) -> IResult<&str, String> {
-    use nom::combinator::verify;
+    verify(identifier, |s: &String| !s.eq_ignore_ascii_case("EXISTS"))(input)
+}
 
-    verify(identifier, |s: &String| {
-        let upper = s.to_uppercase();
-        // Check if it's NOT a keyword that could conflict with condition parsing
-        !matches!(upper.as_str(), "EXISTS" | "NOT" | "AND" | "OR")
-    })(input)
+// Column reference, optionally qualified with its table: `col` or `t.col` (v2.7.0)
+pub fn column_ref(input: &str) -> IResult<&str, String> {
+    map(
+        recognize(pair(non_keyword_identifier, opt(pair(char('.'), identifier)))),
+        |s: &str| s.to_string(),
+    )(input)
 }
 
 pub fn data_type(input: &str) -> IResult<&str, DataType> {
-    alt((
+    expecting("data type", alt((
         // Auto-increment types
         map(tag_no_case("BIGSERIAL"), |_| DataType::BigSerial),
         map(tag_no_case("SERIAL"), |_| DataType::Serial),
@@ -71,15 +105,15 @@ pub fn data_type(input: &str) -> IResult<&str, DataType> {
         // Integer types
         map(tag_no_case("SMALLINT"), |_| DataType::SmallInt),
         map(tag_no_case("INTEGER"), |_| DataType::Integer),
+        map(alt((tag_no_case("BIGINT"), tag_no_case("INT8"))), |_| DataType::BigInt),
         map(tag_no_case("INT"), |_| DataType::Integer),
-        map(tag_no_case("BIGINT"), |_| DataType::Integer), // Same as INTEGER for now
         // Floating point
         map(alt((tag_no_case("REAL"), tag_no_case("FLOAT"))), |_| DataType::Real),
         map(tag_no_case("DOUBLE PRECISION"), |_| DataType::Real),
         // String types with length
         map(
             tuple((
-                tag_no_case("VARCHAR"),
+                alt((tag_no_case("CHARACTER VARYING"), tag_no_case("VARCHAR"))),
                 opt(delimited(
                     ws(char('(')),
                     ws(map_res(digit1, |s: &str| s.parse::<usize>())),
@@ -90,7 +124,7 @@ pub fn data_type(input: &str) -> IResult<&str, DataType> {
         ),
         map(
             tuple((
-                tag_no_case("CHAR"),
+                alt((tag_no_case("CHARACTER"), tag_no_case("CHAR"))),
                 opt(delimited(
                     ws(char('(')),
                     ws(map_res(digit1, |s: &str| s.parse::<usize>())),
@@ -103,8 +137,8 @@ pub fn data_type(input: &str) -> IResult<&str, DataType> {
         // Boolean
         map(alt((tag_no_case("BOOLEAN"), tag_no_case("BOOL"))), |_| DataType::Boolean),
         // Date/Time types
-        map(tag_no_case("TIMESTAMPTZ"), |_| DataType::TimestampTz),
-        map(tag_no_case("TIMESTAMP"), |_| DataType::Timestamp),
+        map(alt((tag_no_case("TIMESTAMPTZ"), tag_no_case("TIMESTAMP WITH TIME ZONE"))), |_| DataType::TimestampTz),
+        map(alt((tag_no_case("TIMESTAMP WITHOUT TIME ZONE"), tag_no_case("TIMESTAMP"))), |_| DataType::Timestamp),
         map(tag_no_case("DATE"), |_| DataType::Date),
         // Special types
         map(tag_no_case("UUID"), |_| DataType::Uuid),
@@ -117,7 +151,7 @@ pub fn data_type(input: &str) -> IResult<&str, DataType> {
             name,
             values: vec![] // Empty values, will be resolved from Database.enums
         }),
-    ))(input)
+    )))(input)
 }
 
 pub fn value(input: &str) -> IResult<&str, Value> {
@@ -143,24 +177,29 @@ pub fn value(input: &str) -> IResult<&str, Value> {
             }
         ),
 
-        // Date/Timestamp/Text in quotes
+        // Date/Timestamp/Text in quotes (v2.7.0: '' is the empty string, '''' a quote)
         map_res(
-            delimited(char('\''), take_while1(|c| c != '\''), char('\'')),
+            delimited(char('\''), recognize(many0(alt((tag("''"), is_not("'"))))), char('\'')),
             |s: &str| -> Result<Value, String> {
+                let s = &s.replace("''", "'");
+                // Dates and timestamps start with the year; other text skips trying them
+                if !s.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-') {
+                    return Ok(Value::Text(s.clone()));
+                }
                 // Try to parse as date first
                 if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
                     return Ok(Value::Date(d));
                 }
-                // Try timestamp with timezone
-                if let Ok(t) = DateTime::parse_from_rfc3339(s) {
-                    return Ok(Value::TimestampTz(t.with_timezone(&Utc)));
+                // Try timestamp with timezone (v2.7.0: also '2024-03-01 13:00:00+01')
+                if let Some(t) = crate::core::value::parse_timestamptz(s) {
+                    return Ok(Value::TimestampTz(t));
                 }
                 // Try timestamp without timezone
                 if let Ok(t) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
                     return Ok(Value::Timestamp(t));
                 }
                 // Otherwise, treat as text
-                Ok(Value::Text(s.to_string()))
+                Ok(Value::Text(s.clone()))
             }
         ),
 
@@ -197,6 +236,18 @@ pub fn value(input: &str) -> IResult<&str, Value> {
     ))(input)
 }
 
+// Quoted string that may be empty, with '' for a quote (v2.7.0)
+pub fn quoted_string(input: &str) -> IResult<&str, String> {
+    map(
+        delimited(
+            char('\''),
+            recognize(nom::multi::many0(alt((tag("''"), nom::bytes::complete::is_not("'"))))),
+            char('\''),
+        ),
+        |s: &str| s.replace("''", "'"),
+    )(input)
+}
+
 pub fn string_literal(input: &str) -> IResult<&str, String> {
     map(
         delimited(char('\''), take_while1(|c| c != '\''), char('\'')),
@@ -1,22 +1,30 @@
-use crate::types::DataType;
-use super::common::{ws, identifier, data_type, string_literal};
-use super::statement::{Statement, ColumnDef, PrivilegeType};
+use chrono::{DateTime, NaiveTime, Utc};
+use crate::types::{ConstraintKind, DataType, ForeignKey, PartitionBound, PartitionStrategy, Partitioning, UserOptions, Value};
+use super::common::{ws, identifier, table_name, data_type, quoted_string, string_literal, value};
+use super::statement::{Statement, ColumnDef, ObjectRef, EnumValuePosition, OnCommitAction, PrivilegeType};
 use nom::{
     branch::alt,
-    bytes::complete::{tag, tag_no_case, take_while1},
-    character::complete::char,
-    combinator::{map, opt},
-    multi::separated_list1,
+    bytes::complete::take_while1,
+    combinator::{map, map_opt, opt, recognize},
+    multi::{many0, many1, separated_list1},
     sequence::{delimited, preceded, tuple},
-    IResult,
 };
+use nom::error::ParseError;
+use super::error::{GrammarError, IResult, char, tag, tag_no_case};
 
 fn column_def(input: &str) -> IResult<&str, ColumnDef> {
     let (input, name) = ws(identifier)(input)?;
     let (input, data_type) = ws(data_type)(input)?;
-    let (input, primary_key) = opt(ws(tag_no_case("PRIMARY KEY")))(input)?;
-    let (input, unique_kw) = opt(ws(tag_no_case("UNIQUE")))(input)?;
-    let (input, not_null) = opt(ws(tag_no_case("NOT NULL")))(input)?;
+    let (input, collation) = opt(collate_clause)(input)?;
+    let (input, generated) = opt(generated_column)(input)?;
+    // v2.7.0: Column constraints in any order (`NOT NULL UNIQUE` as well as `UNIQUE NOT NULL`)
+    let (input, constraints) = many0(ws(alt((
+        tag_no_case("PRIMARY KEY"),
+        tag_no_case("UNIQUE"),
+        tag_no_case("NOT NULL"),
+    ))))(input)?;
+    let has = |keyword: &str| constraints.iter().any(|c| c.eq_ignore_ascii_case(keyword));
+    let (primary_key, unique_kw, not_null) = (has("PRIMARY KEY"), has("UNIQUE"), has("NOT NULL"));
 
     // Parse REFERENCES table(column) for foreign key
     let (input, foreign_key) = opt(tuple((
@@ -35,10 +43,10 @@ fn column_def(input: &str) -> IResult<&str, ColumnDef> {
     let nullable = if is_serial {
         false
     } else {
-        not_null.is_none() && primary_key.is_none()
+        !not_null && !primary_key
     };
-    let primary_key = is_serial || primary_key.is_some();
-    let unique = unique_kw.is_some();
+    let primary_key = is_serial || primary_key;
+    let unique = unique_kw;
 
     Ok((
         input,
@@ -49,65 +57,288 @@ fn column_def(input: &str) -> IResult<&str, ColumnDef> {
             primary_key,
             unique,
             foreign_key,
+            generated,
+            collation,
         },
     ))
 }
 
+/// COLLATE "name" or COLLATE name (v2.7.0)
+fn collate_clause(input: &str) -> IResult<&str, String> {
+    preceded(
+        ws(tag_no_case("COLLATE")),
+        ws(alt((
+            map(delimited(char('"'), take_while1(|c: char| c != '"'), char('"')), str::to_string),
+            identifier,
+        ))),
+    )(input)
+}
+
+/// GENERATED ALWAYS AS (expr) STORED - returns the expression source (v2.7.0)
+fn generated_column(input: &str) -> IResult<&str, String> {
+    let (input, _) = ws(tag_no_case("GENERATED"))(input)?;
+    let (input, _) = ws(tag_no_case("ALWAYS"))(input)?;
+    let (input, _) = ws(tag_no_case("AS"))(input)?;
+    let (input, expr) = delimited(
+        ws(char('(')),
+        recognize(super::queries::expression),
+        ws(char(')')),
+    )(input)?;
+    let (input, _) = ws(tag_no_case("STORED"))(input)?;
+    Ok((input, expr.trim().to_string()))
+}
+
 pub fn create_table(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("CREATE TABLE"))(input)?;
+    let (input, name) = ws(table_name)(input)?;
+    let (input, columns) = delimited(
+        ws(char('(')),
+        separated_list1(ws(char(',')), column_def),
+        ws(char(')')),
+    )(input)?;
+
+    let (input, partition_by) = opt(partition_by)(input)?;
+    let (input, access_method) = opt(preceded(ws(tag_no_case("USING")), ws(identifier)))(input)?;
+
+    Ok((input, Statement::CreateTable { name, columns, owner: None, partition_by, access_method }))
+}
+
+/// PARTITION BY RANGE|LIST (col) (v2.7.0)
+fn partition_by(input: &str) -> IResult<&str, Partitioning> {
+    let (input, _) = ws(tag_no_case("PARTITION BY"))(input)?;
+    let (input, strategy) = alt((
+        map(ws(tag_no_case("RANGE")), |_| PartitionStrategy::Range),
+        map(ws(tag_no_case("LIST")), |_| PartitionStrategy::List),
+    ))(input)?;
+    let (input, column) = delimited(ws(char('(')), ws(identifier), ws(char(')')))(input)?;
+
+    Ok((input, Partitioning::new(strategy, column)))
+}
+
+/// CREATE TABLE name PARTITION OF parent
+///     FOR VALUES FROM (v | MINVALUE) TO (v | MAXVALUE) | FOR VALUES IN (v, ...) | DEFAULT (v2.7.0)
+pub fn create_partition(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("CREATE TABLE"))(input)?;
+    let (input, name) = ws(table_name)(input)?;
+    let (input, _) = ws(tag_no_case("PARTITION OF"))(input)?;
+    let (input, parent) = ws(identifier)(input)?;
+
+    let range_value = |unbounded: &'static str| {
+        delimited(
+            ws(char('(')),
+            alt((map(ws(tag_no_case(unbounded)), |_| None), map(ws(value), Some))),
+            ws(char(')')),
+        )
+    };
+    let (input, bound) = alt((
+        map(ws(tag_no_case("DEFAULT")), |_| PartitionBound::Default),
+        map(
+            tuple((
+                ws(tag_no_case("FOR VALUES FROM")),
+                range_value("MINVALUE"),
+                ws(tag_no_case("TO")),
+                range_value("MAXVALUE"),
+            )),
+            |(_, from, _, to)| PartitionBound::Range { from, to },
+        ),
+        map(
+            preceded(
+                ws(tag_no_case("FOR VALUES IN")),
+                delimited(ws(char('(')), separated_list1(ws(char(',')), ws(value)), ws(char(')'))),
+            ),
+            PartitionBound::List,
+        ),
+    ))(input)?;
+
+    Ok((input, Statement::CreatePartition { name, parent, bound, owner: None }))
+}
+
+/// CREATE TEMP|TEMPORARY TABLE name (...) [ON COMMIT PRESERVE ROWS|DELETE ROWS|DROP] (v2.7.0)
+pub fn create_temp_table(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("CREATE"))(input)?;
+    let (input, _) = ws(alt((tag_no_case("TEMPORARY"), tag_no_case("TEMP"))))(input)?;
+    let (input, _) = ws(tag_no_case("TABLE"))(input)?;
     let (input, name) = ws(identifier)(input)?;
     let (input, columns) = delimited(
         ws(char('(')),
         separated_list1(ws(char(',')), column_def),
         ws(char(')')),
     )(input)?;
+    let (input, on_commit) = opt(preceded(
+        tuple((ws(tag_no_case("ON")), ws(tag_no_case("COMMIT")))),
+        alt((
+            map(
+                tuple((ws(tag_no_case("PRESERVE")), ws(tag_no_case("ROWS")))),
+                |_| OnCommitAction::PreserveRows,
+            ),
+            map(
+                tuple((ws(tag_no_case("DELETE")), ws(tag_no_case("ROWS")))),
+                |_| OnCommitAction::DeleteRows,
+            ),
+            map(ws(tag_no_case("DROP")), |_| OnCommitAction::Drop),
+        )),
+    ))(input)?;
 
-    Ok((input, Statement::CreateTable { name, columns, owner: None }))
+    Ok((
+        input,
+        Statement::CreateTempTable {
+            name,
+            columns,
+            owner: None,
+            on_commit: on_commit.unwrap_or_default(),
+        },
+    ))
 }
 
-pub fn drop_table(input: &str) -> IResult<&str, Statement> {
-    let (input, _) = ws(tag_no_case("DROP TABLE"))(input)?;
+/// CREATE FOREIGN TABLE name (...) SERVER server [OPTIONS (key 'value', ...)] (v2.7.0)
+pub fn create_foreign_table(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("CREATE FOREIGN TABLE"))(input)?;
     let (input, name) = ws(identifier)(input)?;
+    let (input, columns) = delimited(
+        ws(char('(')),
+        separated_list1(ws(char(',')), column_def),
+        ws(char(')')),
+    )(input)?;
+    let (input, _) = ws(tag_no_case("SERVER"))(input)?;
+    let (input, server) = ws(identifier)(input)?;
+    let (input, options) = opt(preceded(
+        ws(tag_no_case("OPTIONS")),
+        delimited(
+            ws(char('(')),
+            separated_list1(ws(char(',')), tuple((ws(identifier), ws(string_literal)))),
+            ws(char(')')),
+        ),
+    ))(input)?;
+
+    Ok((
+        input,
+        Statement::CreateForeignTable {
+            name,
+            columns,
+            server,
+            options: options.unwrap_or_default(),
+            owner: None,
+        },
+    ))
+}
 
-    Ok((input, Statement::DropTable { name }))
+pub fn drop_table(input: &str) -> IResult<&str, Statement> {
+    // v2.7.0: Foreign tables are dropped like any other table
+    let (input, _) = ws(alt((tag_no_case("DROP FOREIGN TABLE"), tag_no_case("DROP TABLE"))))(input)?;
+    let (input, name) = ws(table_name)(input)?;
+    let (input, cascade) = drop_behavior(input)?;
+
+    Ok((input, Statement::DropTable { name, cascade }))
 }
 
+/// `[CASCADE | RESTRICT]` of DROP: true for CASCADE (v2.7.0)
+fn drop_behavior(input: &str) -> IResult<&str, bool> {
+    let (input, behavior) = opt(alt((
+        map(ws(tag_no_case("CASCADE")), |_| true),
+        map(ws(tag_no_case("RESTRICT")), |_| false),
+    )))(input)?;
+    Ok((input, behavior.unwrap_or(false)))
+}
+
+/// One option of CREATE DATABASE (v2.7.0)
+enum DatabaseOption {
+    Owner(String),
+    Template(String),
+    IsTemplate(bool),
+}
+
+/// `[= ] value` of a database option
+fn option_value<'a, O>(
+    value: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    preceded(opt(ws(char('='))), ws(value))
+}
+
+fn boolean(input: &str) -> IResult<&str, bool> {
+    alt((map(tag_no_case("TRUE"), |_| true), map(tag_no_case("FALSE"), |_| false)))(input)
+}
+
+/// CREATE DATABASE name [WITH] [OWNER [=] user] [TEMPLATE [=] template] [`IS_TEMPLATE` [=] bool]
+///
+/// v2.7.0: TEMPLATE and `IS_TEMPLATE`, options in any order
 pub fn create_database(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("CREATE DATABASE"))(input)?;
     let (input, name) = ws(identifier)(input)?;
 
     // Support both "WITH OWNER" (PostgreSQL) and "OWNER" (backwards compat)
-    let (input, owner) = opt(alt((
-        preceded(ws(tag_no_case("WITH OWNER")), ws(identifier)),
-        preceded(ws(tag_no_case("OWNER")), ws(identifier)),
-    )))(input)?;
+    let (input, _) = opt(ws(tag_no_case("WITH")))(input)?;
+    let (input, list) = many0(ws(alt((
+        map(preceded(tag_no_case("OWNER"), option_value(identifier)), DatabaseOption::Owner),
+        map(preceded(tag_no_case("TEMPLATE"), option_value(identifier)), DatabaseOption::Template),
+        map(preceded(tag_no_case("IS_TEMPLATE"), option_value(boolean)), DatabaseOption::IsTemplate),
+    ))))(input)?;
+
+    let (mut owner, mut template, mut is_template) = (None, None, false);
+    for item in list {
+        match item {
+            DatabaseOption::Owner(user) => owner = Some(user),
+            DatabaseOption::Template(source) => template = Some(source),
+            DatabaseOption::IsTemplate(flag) => is_template = flag,
+        }
+    }
 
     Ok((input, Statement::CreateDatabase {
         name,
         owner,
+        template,
+        is_template,
     }))
 }
 
+/// DROP DATABASE name [[WITH] (FORCE)]
 pub fn drop_database(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("DROP DATABASE"))(input)?;
     let (input, name) = ws(identifier)(input)?;
+    // v2.7.0: FORCE ends the sessions connected to the database
+    let (input, force) = opt(preceded(
+        opt(ws(tag_no_case("WITH"))),
+        delimited(ws(char('(')), ws(tag_no_case("FORCE")), ws(char(')'))),
+    ))(input)?;
 
     Ok((input, Statement::DropDatabase {
         name,
+        force: force.is_some(),
     }))
 }
 
+/// ALTER DATABASE name OWNER TO user | [WITH] `IS_TEMPLATE` [=] bool (v2.7.0)
+pub fn alter_database(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("ALTER DATABASE"))(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    alt((
+        map(preceded(ws(tag_no_case("OWNER TO")), ws(identifier)), |new_owner| {
+            Statement::AlterDatabaseOwner { name: name.clone(), new_owner }
+        }),
+        map(
+            preceded(
+                tuple((opt(ws(tag_no_case("WITH"))), ws(tag_no_case("IS_TEMPLATE")))),
+                option_value(boolean),
+            ),
+            |is_template| Statement::AlterDatabaseTemplate { name: name.clone(), is_template },
+        ),
+    ))(input)
+}
+
+/// CREATE USER name [WITH] PASSWORD '...' [attribute ...]
+///
+/// v2.7.0: Attributes (see `user_options`) may come before or after the password.
 pub fn create_user(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("CREATE USER"))(input)?;
     let (input, username) = ws(identifier)(input)?;
-    let (input, _) = ws(tag_no_case("WITH PASSWORD"))(input)?;
-    let (input, password) = ws(string_literal)(input)?;
-    let (input, is_superuser) = opt(ws(tag_no_case("SUPERUSER")))(input)?;
+    let (rest, (password, options)) = user_options(input)?;
+    let Some(password) = password else {
+        return Err(nom::Err::Error(GrammarError::from_error_kind(input, nom::error::ErrorKind::Verify)));
+    };
 
-    Ok((input, Statement::CreateUser {
+    Ok((rest, Statement::CreateUser {
         username,
         password,
-        is_superuser: is_superuser.is_some(),
+        options,
     }))
 }
 
@@ -120,18 +351,79 @@ pub fn drop_user(input: &str) -> IResult<&str, Statement> {
     }))
 }
 
+/// ALTER USER name [WITH] attribute ...
+///
+/// v2.7.0: WITH is optional (`\password` sends ALTER USER name PASSWORD '...')
 pub fn alter_user(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("ALTER USER"))(input)?;
     let (input, username) = ws(identifier)(input)?;
-    let (input, _) = ws(tag_no_case("WITH PASSWORD"))(input)?;
-    let (input, password) = ws(string_literal)(input)?;
+    let (input, (password, options)) = user_options(input)?;
 
     Ok((input, Statement::AlterUser {
         username,
         password,
+        options,
     }))
 }
 
+/// One attribute of CREATE USER / ALTER USER (v2.7.0)
+enum UserOption {
+    Password(String),
+    Superuser(bool),
+    CreateDb(bool),
+    CreateRole(bool),
+    Login(bool),
+    ValidUntil(Option<DateTime<Utc>>),
+}
+
+/// `[WITH] attribute ...`: PASSWORD '...', [NO]SUPERUSER, [NO]CREATEDB,
+/// [NO]CREATEROLE, [NO]LOGIN, VALID UNTIL '...' in any order (v2.7.0)
+///
+/// VALID UNTIL takes 'infinity' or a timestamp, read as UTC without an offset.
+fn user_options(input: &str) -> IResult<&str, (Option<String>, UserOptions)> {
+    let flag = |yes: &'static str, no: &'static str| {
+        alt((map(tag_no_case(no), |_| false), map(tag_no_case(yes), |_| true)))
+    };
+    let valid_until = |text: String| -> Option<Option<DateTime<Utc>>> {
+        if text.eq_ignore_ascii_case("infinity") {
+            return Some(None);
+        }
+        match crate::core::value::parse_timestamp(&text)? {
+            Value::TimestampTz(at) => Some(Some(at)),
+            Value::Timestamp(at) => Some(Some(at.and_utc())),
+            Value::Date(day) => Some(Some(day.and_time(NaiveTime::MIN).and_utc())),
+            _ => None,
+        }
+    };
+    let option = ws(alt((
+        map(preceded(ws(tag_no_case("PASSWORD")), string_literal), UserOption::Password),
+        map(flag("SUPERUSER", "NOSUPERUSER"), UserOption::Superuser),
+        map(flag("CREATEDB", "NOCREATEDB"), UserOption::CreateDb),
+        map(flag("CREATEROLE", "NOCREATEROLE"), UserOption::CreateRole),
+        map(flag("LOGIN", "NOLOGIN"), UserOption::Login),
+        map(
+            preceded(ws(tag_no_case("VALID UNTIL")), map_opt(string_literal, valid_until)),
+            UserOption::ValidUntil,
+        ),
+    )));
+
+    let (input, _) = opt(ws(tag_no_case("WITH")))(input)?;
+    let (input, list) = many1(option)(input)?;
+    let mut password = None;
+    let mut options = UserOptions::default();
+    for item in list {
+        match item {
+            UserOption::Password(text) => password = Some(text),
+            UserOption::Superuser(flag) => options.superuser = Some(flag),
+            UserOption::CreateDb(flag) => options.create_db = Some(flag),
+            UserOption::CreateRole(flag) => options.create_role = Some(flag),
+            UserOption::Login(flag) => options.login = Some(flag),
+            UserOption::ValidUntil(at) => options.valid_until = Some(at),
+        }
+    }
+    Ok((input, (password, options)))
+}
+
 pub fn create_role(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("CREATE ROLE"))(input)?;
     let (input, role_name) = ws(identifier)(input)?;
@@ -246,7 +538,7 @@ pub fn revoke(input: &str) -> IResult<&str, Statement> {
 
 pub fn create_type(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("CREATE TYPE"))(input)?;
-    let (input, name) = ws(identifier)(input)?;
+    let (input, name) = ws(table_name)(input)?;
     let (input, _) = ws(tag_no_case("AS ENUM"))(input)?;
     let (input, _) = ws(char('('))(input)?;
     let (input, values) = separated_list1(
@@ -264,11 +556,65 @@ pub fn create_type(input: &str) -> IResult<&str, Statement> {
     }))
 }
 
+/// ALTER TYPE name ADD VALUE [IF NOT EXISTS] 'label' [BEFORE | AFTER 'label'] (v2.7.0)
+pub fn alter_type(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("ALTER TYPE"))(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    let (input, _) = ws(tag_no_case("ADD VALUE"))(input)?;
+    let (input, if_not_exists) = opt(ws(tag_no_case("IF NOT EXISTS")))(input)?;
+    let (input, value) = ws(string_literal)(input)?;
+    let (input, position) = opt(alt((
+        map(preceded(ws(tag_no_case("BEFORE")), ws(string_literal)), EnumValuePosition::Before),
+        map(preceded(ws(tag_no_case("AFTER")), ws(string_literal)), EnumValuePosition::After),
+    )))(input)?;
+
+    Ok((input, Statement::AlterTypeAddValue {
+        name,
+        value,
+        if_not_exists: if_not_exists.is_some(),
+        position,
+    }))
+}
+
+/// Body of a table constraint: CHECK (cond), UNIQUE (cols) or
+/// FOREIGN KEY (col) REFERENCES table (col) (v2.7.0)
+fn table_constraint(input: &str) -> IResult<&str, ConstraintKind> {
+    alt((
+        map(
+            preceded(
+                ws(tag_no_case("CHECK")),
+                delimited(ws(char('(')), recognize(super::queries::condition), ws(char(')'))),
+            ),
+            |source: &str| ConstraintKind::Check(source.trim().to_string())
+        ),
+        map(
+            preceded(
+                ws(tag_no_case("UNIQUE")),
+                delimited(ws(char('(')), separated_list1(ws(char(',')), ws(identifier)), ws(char(')'))),
+            ),
+            ConstraintKind::Unique
+        ),
+        map(
+            tuple((
+                preceded(ws(tag_no_case("FOREIGN KEY")), delimited(ws(char('(')), ws(identifier), ws(char(')')))),
+                preceded(ws(tag_no_case("REFERENCES")), ws(identifier)),
+                delimited(ws(char('(')), ws(identifier), ws(char(')'))),
+            )),
+            |(column, referenced_table, referenced_column)| ConstraintKind::ForeignKey {
+                column,
+                references: ForeignKey { referenced_table, referenced_column },
+            }
+        ),
+    ))(input)
+}
+
 pub fn alter_table(input: &str) -> IResult<&str, Statement> {
     use super::statement::AlterTableOperation;
     
     let (input, _) = ws(tag_no_case("ALTER TABLE"))(input)?;
-    let (input, table_name) = ws(identifier)(input)?;
+    // v2.7.0: ONLY (no inheritance here, so it changes nothing) as pg_dump writes it
+    let (input, _) = opt(ws(tag_no_case("ONLY ")))(input)?;
+    let (input, table_name) = ws(self::table_name)(input)?;
     
     // Try different ALTER TABLE operations
     let (input, operation) = alt((
@@ -284,9 +630,9 @@ pub fn alter_table(input: &str) -> IResult<&str, Statement> {
         map(
             preceded(
                 ws(tag_no_case("DROP COLUMN")),
-                ws(identifier)
+                tuple((ws(identifier), drop_behavior))
             ),
-            AlterTableOperation::DropColumn
+            |(name, cascade)| AlterTableOperation::DropColumn { name, cascade }
         ),
         // RENAME COLUMN
         map(
@@ -312,6 +658,44 @@ pub fn alter_table(input: &str) -> IResult<&str, Statement> {
             ),
             AlterTableOperation::OwnerTo
         ),
+        // v2.7.0: ADD CONSTRAINT name ... [NOT VALID]
+        map(
+            tuple((
+                preceded(ws(tag_no_case("ADD CONSTRAINT")), ws(identifier)),
+                table_constraint,
+                opt(ws(tag_no_case("NOT VALID"))),
+            )),
+            |(name, kind, not_valid)| AlterTableOperation::AddConstraint { name, kind, not_valid: not_valid.is_some() }
+        ),
+        // v2.7.0: VALIDATE CONSTRAINT name
+        map(
+            preceded(ws(tag_no_case("VALIDATE CONSTRAINT")), ws(identifier)),
+            AlterTableOperation::ValidateConstraint
+        ),
+        // v2.7.0: DROP CONSTRAINT [IF EXISTS] name [CASCADE | RESTRICT]
+        map(
+            tuple((
+                preceded(ws(tag_no_case("DROP CONSTRAINT")), opt(ws(tag_no_case("IF EXISTS")))),
+                ws(identifier),
+                drop_behavior,
+            )),
+            |(if_exists, name, _)| AlterTableOperation::DropConstraint { name, if_exists: if_exists.is_some() }
+        ),
+        // v2.7.0: ALTER COLUMN col SET NOT NULL | DROP NOT NULL
+        map(
+            tuple((
+                preceded(ws(tag_no_case("ALTER COLUMN")), ws(identifier)),
+                alt((
+                    map(ws(tag_no_case("SET NOT NULL")), |_| true),
+                    map(ws(tag_no_case("DROP NOT NULL")), |_| false),
+                )),
+            )),
+            |(column, not_null)| if not_null {
+                AlterTableOperation::SetNotNull(column)
+            } else {
+                AlterTableOperation::DropNotNull(column)
+            }
+        ),
     ))(input)?;
     
     Ok((input, Statement::AlterTable {
@@ -337,7 +721,16 @@ pub fn parse_create_index(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("INDEX"))(input)?;
     let (input, name) = ws(identifier)(input)?;
     let (input, _) = ws(tag_no_case("ON"))(input)?;
-    let (input, table) = ws(identifier)(input)?;
+    let (input, table) = ws(table_name)(input)?;
+
+    // Optional USING clause
+    let using = |i| {
+        let (i, _) = ws(tag_no_case("USING"))(i)?;
+        let (i, type_name) = ws(identifier)(i)?;
+        Ok((i, type_name))
+    };
+    // v2.7.0: PostgreSQL puts it before the columns (`ON t USING btree (col)`)
+    let (input, method) = opt(using)(input)?;
 
     // Column(s) in parentheses - v1.9.0: supports comma-separated list
     let (input, columns) = delimited(
@@ -346,12 +739,10 @@ pub fn parse_create_index(input: &str) -> IResult<&str, Statement> {
         ws(char(')'))
     )(input)?;
 
-    // Optional USING clause
-    let (input, index_type) = opt(|i| {
-        let (i, _) = ws(tag_no_case("USING"))(i)?;
-        let (i, type_name) = ws(identifier)(i)?;
-        Ok((i, type_name))
-    })(input)?;
+    let (input, index_type) = match method {
+        Some(method) => (input, Some(method)),
+        None => opt(using)(input)?,
+    };
 
     let index_type = match index_type.as_deref() {
         Some("hash" | "HASH") => crate::index::IndexType::Hash,
@@ -394,6 +785,38 @@ pub fn parse_vacuum(input: &str) -> IResult<&str, Statement> {
     Ok((input, Statement::Vacuum { table }))
 }
 
+/// Parse CLUSTER statement (v2.7.0)
+///
+/// Syntax:
+/// - CLUSTER;                          -- recluster every table clustered before
+/// - CLUSTER `table_name`;               -- recluster on the index used last time
+/// - CLUSTER `table_name` USING `index_name`;
+pub fn parse_cluster(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("CLUSTER"))(input)?;
+    let (input, table) = opt(ws(identifier))(input)?;
+    let (input, index) = if table.is_some() {
+        opt(preceded(ws(tag_no_case("USING")), ws(identifier)))(input)?
+    } else {
+        (input, None)
+    };
+
+    Ok((input, Statement::Cluster { table, index }))
+}
+
+/// Parse ANALYZE statement (v2.7.0)
+///
+/// Syntax:
+/// - ANALYZE;              -- analyze all tables
+/// - ANALYZE `table_name`;   -- analyze specific table
+pub fn parse_analyze(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("ANALYZE"))(input)?;
+
+    // Optional table name
+    let (input, table) = opt(ws(identifier))(input)?;
+
+    Ok((input, Statement::Analyze { table }))
+}
+
 /// Parse CREATE VIEW statement (v1.10.0)
 ///
 /// Syntax: CREATE VIEW name AS SELECT ...
@@ -414,24 +837,149 @@ pub fn parse_create_view(input: &str) -> IResult<&str, Statement> {
 
 /// Parse DROP VIEW statement (v1.10.0)
 ///
-/// Syntax: DROP VIEW name
+/// Syntax: DROP VIEW name [CASCADE | RESTRICT]
 pub fn parse_drop_view(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("DROP"))(input)?;
     let (input, _) = ws(tag_no_case("VIEW"))(input)?;
     let (input, name) = ws(identifier)(input)?;
+    let (input, cascade) = drop_behavior(input)?;
+
+    Ok((input, Statement::DropView { name, cascade }))
+}
+
+// Function body: 'text' ('' escapes a quote) or $$text$$ (v2.7.0)
+fn function_body(input: &str) -> IResult<&str, String> {
+    alt((
+        map(
+            delimited(tag("$$"), nom::bytes::complete::take_until("$$"), tag("$$")),
+            |body: &str| body.trim().to_string(),
+        ),
+        map(
+            delimited(
+                char('\''),
+                nom::combinator::recognize(nom::multi::many0(alt((
+                    tag("''"),
+                    nom::bytes::complete::is_not("'"),
+                )))),
+                char('\''),
+            ),
+            |body: &str| body.trim().replace("''", "'"),
+        ),
+    ))(input)
+}
+
+fn language_sql(input: &str) -> IResult<&str, ()> {
+    map(
+        tuple((ws(tag_no_case("LANGUAGE")), ws(alt((tag_no_case("SQL"), tag_no_case("'sql'")))))),
+        |_| (),
+    )(input)
+}
+
+/// Parse CREATE FUNCTION statement (v2.7.0)
+///
+/// Syntax: CREATE [OR REPLACE] FUNCTION name([arg type, ...]) RETURNS [SETOF] type
+///         [LANGUAGE SQL] AS 'body' [LANGUAGE SQL]
+pub fn parse_create_function(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("CREATE"))(input)?;
+    let (input, or_replace) = opt(tuple((ws(tag_no_case("OR")), ws(tag_no_case("REPLACE")))))(input)?;
+    let (input, _) = ws(tag_no_case("FUNCTION"))(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    let (input, params) = delimited(
+        ws(char('(')),
+        nom::multi::separated_list0(ws(char(',')), tuple((ws(identifier), ws(data_type)))),
+        ws(char(')')),
+    )(input)?;
+    let (input, _) = ws(tag_no_case("RETURNS"))(input)?;
+    let (input, returns_set) = opt(ws(tag_no_case("SETOF")))(input)?;
+    let (input, returns) = ws(data_type)(input)?;
+    let (input, _) = opt(language_sql)(input)?;
+    let (input, _) = ws(tag_no_case("AS"))(input)?;
+    let (input, body) = ws(function_body)(input)?;
+    let (input, _) = opt(language_sql)(input)?;
+
+    Ok((input, Statement::CreateFunction {
+        name,
+        params,
+        returns,
+        returns_set: returns_set.is_some(),
+        body,
+        or_replace: or_replace.is_some(),
+    }))
+}
+
+/// Parse DROP FUNCTION statement (v2.7.0)
+///
+/// Syntax: DROP FUNCTION [IF EXISTS] name [(type, ...)]
+pub fn parse_drop_function(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("DROP"))(input)?;
+    let (input, _) = ws(tag_no_case("FUNCTION"))(input)?;
+    let (input, if_exists) = opt(tuple((ws(tag_no_case("IF")), ws(tag_no_case("EXISTS")))))(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    // Argument types are accepted for compatibility; functions are not overloaded
+    let (input, _) = opt(delimited(
+        ws(char('(')),
+        nom::multi::separated_list0(ws(char(',')), ws(data_type)),
+        ws(char(')')),
+    ))(input)?;
+
+    Ok((input, Statement::DropFunction {
+        name,
+        if_exists: if_exists.is_some(),
+    }))
+}
+
+/// COMMENT ON {TABLE | COLUMN | VIEW | INDEX | TYPE | DATABASE | SCHEMA} name IS 'text' | NULL (v2.7.0)
+pub fn comment_on(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("COMMENT ON"))(input)?;
+    let (input, object) = alt((
+        map(
+            preceded(
+                ws(tag_no_case("COLUMN ")),
+                tuple((table_name, preceded(char('.'), identifier))),
+            ),
+            |(table, column)| ObjectRef::Column { table, column },
+        ),
+        map(preceded(ws(tag_no_case("TABLE ")), ws(table_name)), ObjectRef::Table),
+        map(preceded(ws(tag_no_case("VIEW ")), ws(table_name)), ObjectRef::View),
+        map(preceded(ws(tag_no_case("INDEX ")), ws(table_name)), ObjectRef::Index),
+        map(preceded(ws(tag_no_case("TYPE ")), ws(table_name)), ObjectRef::Type),
+        map(preceded(ws(tag_no_case("DATABASE ")), ws(identifier)), ObjectRef::Database),
+        map(preceded(ws(tag_no_case("SCHEMA ")), ws(identifier)), ObjectRef::Schema),
+    ))(input)?;
+    let (input, _) = ws(tag_no_case("IS"))(input)?;
+    let (input, comment) = ws(alt((
+        map(tag_no_case("NULL"), |_| None),
+        map(quoted_string, Some),
+    )))(input)?;
+
+    Ok((input, Statement::CommentOn { object, comment }))
+}
+
+/// ALTER {VIEW | TYPE | SCHEMA} name OWNER TO role (v2.7.0)
+///
+/// Tables and databases have their own ALTER statements.
+pub fn alter_owner(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("ALTER"))(input)?;
+    let (input, object) = alt((
+        map(preceded(ws(tag_no_case("VIEW ")), ws(table_name)), ObjectRef::View),
+        map(preceded(ws(tag_no_case("TYPE ")), ws(table_name)), ObjectRef::Type),
+        map(preceded(ws(tag_no_case("SCHEMA ")), ws(identifier)), ObjectRef::Schema),
+    ))(input)?;
+    let (input, _) = ws(tag_no_case("OWNER TO"))(input)?;
+    let (input, owner) = ws(identifier)(input)?;
 
-    Ok((input, Statement::DropView { name }))
+    Ok((input, Statement::AlterOwner { object, owner }))
 }
 
 /// Parse COPY command (v2.4.0)
-/// COPY table FROM STDIN [WITH (FORMAT csv)]
+/// COPY table FROM STDIN [WITH (FORMAT text|csv|binary)]
 /// COPY table TO STDOUT [WITH (FORMAT csv)]
 /// COPY table (col1, col2) FROM STDIN
-pub fn parse_copy(input: &str) -> nom::IResult<&str, Statement> {
+pub fn parse_copy(input: &str) -> IResult<&str, Statement> {
     use crate::parser::statement::CopyFormat;
 
     let (input, _) = ws(tag_no_case("COPY"))(input)?;
-    let (input, table) = ws(identifier)(input)?;
+    let (input, table) = ws(table_name)(input)?;
 
     // Optional column list
     let (input, columns) = opt(delimited(
@@ -460,7 +1008,7 @@ pub fn parse_copy(input: &str) -> nom::IResult<&str, Statement> {
             preceded(
                 ws(tag_no_case("FORMAT")),
                 alt((
-                    map(ws(tag_no_case("csv")), |_| CopyFormat::Text),
+                    map(ws(tag_no_case("csv")), |_| CopyFormat::Csv),
                     map(ws(tag_no_case("text")), |_| CopyFormat::Text),
                     map(ws(tag_no_case("binary")), |_| CopyFormat::Binary),
                 )),
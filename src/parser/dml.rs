@@ -1,30 +1,45 @@
-use super::common::{ws, identifier, value};
-use super::statement::Statement;
-use super::queries::condition;
+use super::common::{ws, identifier, table_name, value};
+use super::statement::{InsertSource, Statement};
+use super::queries::{condition, expression, select};
 use nom::{
-    bytes::complete::tag_no_case,
-    character::complete::char,
-    combinator::opt,
+    combinator::{map, opt},
     multi::separated_list1,
     sequence::{delimited, preceded, tuple},
-    IResult,
 };
+use super::error::{IResult, char, tag_no_case};
 
 pub fn insert(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("INSERT INTO"))(input)?;
-    let (input, table) = ws(identifier)(input)?;
+    let (input, table) = ws(table_name)(input)?;
     let (input, columns) = opt(delimited(
         ws(char('(')),
         separated_list1(ws(char(',')), identifier),
         ws(char(')')),
     ))(input)?;
+
+    // v2.7.0: INSERT ... SELECT
+    if let Ok((input, query)) = select(input) {
+        let source = InsertSource::Query(Box::new(query));
+        return Ok((input, Statement::InsertMany { table, columns, source }));
+    }
+
     let (input, _) = ws(tag_no_case("VALUES"))(input)?;
-    let (input, values) = delimited(
-        ws(char('(')),
-        separated_list1(ws(char(',')), value),
-        ws(char(')')),
+    let (input, mut rows) = separated_list1(
+        ws(char(',')),
+        delimited(
+            ws(char('(')),
+            separated_list1(ws(char(',')), value),
+            ws(char(')')),
+        ),
     )(input)?;
 
+    // v2.7.0: Several rows are inserted as one batch
+    if rows.len() > 1 {
+        let source = InsertSource::Values(rows);
+        return Ok((input, Statement::InsertMany { table, columns, source }));
+    }
+    let values = rows.remove(0);
+
     Ok((
         input,
         Statement::Insert {
@@ -41,12 +56,13 @@ pub fn update(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("SET"))(input)?;
     let (input, assignments) = separated_list1(
         ws(char(',')),
-        tuple((ws(identifier), ws(char('=')), ws(value))),
+        tuple((ws(identifier), ws(char('=')), ws(expression))),
     )(input)?;
     let assignments = assignments
         .into_iter()
         .map(|(col, _, val)| (col, val))
         .collect();
+    let (input, from) = joined_tables("FROM")(input)?;
     let (input, filter) = opt(preceded(ws(tag_no_case("WHERE")), condition))(input)?;
 
     Ok((
@@ -54,6 +70,7 @@ pub fn update(input: &str) -> IResult<&str, Statement> {
         Statement::Update {
             table,
             assignments,
+            from,
             filter,
         },
     ))
@@ -62,7 +79,18 @@ pub fn update(input: &str) -> IResult<&str, Statement> {
 pub fn delete(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("DELETE FROM"))(input)?;
     let (input, from) = ws(identifier)(input)?;
+    let (input, using) = joined_tables("USING")(input)?;
     let (input, filter) = opt(preceded(ws(tag_no_case("WHERE")), condition))(input)?;
 
-    Ok((input, Statement::Delete { from, filter }))
+    Ok((input, Statement::Delete { from, using, filter }))
+}
+
+// Optional `USING t1, t2` / `FROM t1, t2` list of DELETE and UPDATE (v2.7.0)
+fn joined_tables(keyword: &'static str) -> impl FnMut(&str) -> IResult<&str, Vec<String>> {
+    move |input| {
+        map(
+            opt(preceded(ws(tag_no_case(keyword)), separated_list1(ws(char(',')), ws(identifier)))),
+            Option::unwrap_or_default,
+        )(input)
+    }
 }
@@ -0,0 +1,369 @@
+// Syntax error reporting (v2.7.0)
+//
+// The grammar runs with `GrammarError` instead of nom's default error, which
+// keeps the alternative that got furthest into the input. When a statement
+// does not parse, it is parsed a second time with the failures gathered: the
+// `tag`, `tag_no_case` and `char` below replace nom's and note every literal
+// that did not match, so the furthest point the grammar reached is known
+// together with what would have been accepted there. Statements that parse
+// pay nothing for this. `SyntaxError` turns it into the message users see.
+use super::lexer::{Lexer, Source, Token, TokenKind};
+use nom::error::{ErrorKind, FromExternalError, ParseError};
+use nom::InputLength;
+use std::cell::RefCell;
+use std::fmt;
+
+pub type IResult<I, O> = nom::IResult<I, O, GrammarError<I>>;
+
+/// Expected lists longer than this are left out of the message
+const MAX_EXPECTED_SHOWN: usize = 8;
+
+thread_local! {
+    /// Failures of the statement being parsed again for its error, if any
+    static FAILURES: RefCell<Option<Failures>> = const { RefCell::new(None) };
+}
+
+/// Something the grammar would have accepted where it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    Literal(&'static str),
+    Char(char),
+    /// A kind of token, e.g. "identifier"
+    Item(&'static str),
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(word) if word.chars().all(|c| c.is_ascii_alphabetic() || c == '_' || c == ' ') => {
+                f.write_str(&word.to_uppercase())
+            }
+            Self::Literal(symbol) => write!(f, "\"{symbol}\""),
+            Self::Char(c) => write!(f, "\"{c}\""),
+            Self::Item(item) => f.write_str(item),
+        }
+    }
+}
+
+/// nom error of the grammar: where the alternative that got furthest failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrammarError<I> {
+    input: I,
+}
+
+impl<I: InputLength> ParseError<I> for GrammarError<I> {
+    fn from_error_kind(input: I, _kind: ErrorKind) -> Self {
+        // A parser with nothing to name, e.g. digits: what was expected
+        // further back than this no longer applies
+        note_failure(input.input_len(), None);
+        Self { input }
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        // Less input left is further along
+        if other.input.input_len() < self.input.input_len() { other } else { self }
+    }
+}
+
+impl<I: InputLength, E> FromExternalError<I, E> for GrammarError<I> {
+    fn from_external_error(input: I, kind: ErrorKind, _e: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+/// The furthest failures of a parse: input left there and what was expected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failures {
+    remaining: usize,
+    expected: Vec<Expected>,
+}
+
+impl Default for Failures {
+    fn default() -> Self {
+        Self { remaining: usize::MAX, expected: Vec::new() }
+    }
+}
+
+impl Failures {
+    /// Only failures at the furthest point count; a failure further along
+    /// replaces them, whether or not it knows what was expected
+    fn record(&mut self, remaining: usize, expected: Option<Expected>) {
+        if remaining < self.remaining {
+            self.remaining = remaining;
+            self.expected.clear();
+        }
+        if let Some(expected) = expected
+            && remaining == self.remaining
+            && !self.expected.contains(&expected)
+        {
+            self.expected.push(expected);
+        }
+    }
+}
+
+/// Run `parse` noting the failures of `tag`, `tag_no_case`, `char` and `expecting`
+pub fn gather_failures<T>(parse: impl FnOnce() -> T) -> (T, Failures) {
+    let outer = FAILURES.replace(Some(Failures::default()));
+    let result = parse();
+    let failures = FAILURES.replace(outer).unwrap_or_default();
+    (result, failures)
+}
+
+fn note_failure(remaining: usize, expected: Option<Expected>) {
+    FAILURES.with_borrow_mut(|failures| {
+        if let Some(failures) = failures {
+            failures.record(remaining, expected);
+        }
+    });
+}
+
+fn failed<O>(input: &str, expected: Expected) -> IResult<&str, O> {
+    note_failure(input.len(), Some(expected));
+    Err(nom::Err::Error(GrammarError { input }))
+}
+
+/// nom's `tag`, noting the literal when it does not match
+pub fn tag<'a>(literal: &'static str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    move |input| {
+        nom::bytes::complete::tag::<_, _, ()>(literal)(input).or_else(|_| failed(input, Expected::Literal(literal)))
+    }
+}
+
+/// nom's `tag_no_case`, noting the keyword when it does not match
+///
+/// A keyword only matches a whole word, so INT does not match the start of
+/// INTEGR and NULL not the start of NULLABLE. When the first words of a
+/// keyword such as ORDER BY match, the failure is noted at the word that
+/// does not, expecting the rest (BY).
+pub fn tag_no_case<'a>(literal: &'static str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    let whole_word = literal.ends_with(is_word_char);
+    move |input| match nom::bytes::complete::tag_no_case::<_, _, ()>(literal)(input) {
+        Ok((rest, matched)) if !(whole_word && rest.starts_with(is_word_char)) => Ok((rest, matched)),
+        _ => {
+            let mut matched = 0;
+            for (space, _) in literal.match_indices(' ') {
+                match input.get(..space).filter(|start| start.eq_ignore_ascii_case(&literal[..space])) {
+                    Some(_) if input[space..].starts_with(' ') => matched = space + 1,
+                    Some(_) if input.len() == space => matched = space,
+                    _ => break,
+                }
+            }
+            if matched > 0 {
+                note_failure(input.len() - matched, Some(Expected::Literal(literal[matched..].trim_start())));
+            }
+            failed(input, Expected::Literal(literal))
+        }
+    }
+}
+
+/// Letters, digits and `_` make up words
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// nom's `char`, noting the character when it does not match
+pub fn char<'a>(c: char) -> impl Fn(&'a str) -> IResult<&'a str, char> {
+    move |input| nom::character::complete::char::<_, ()>(c)(input).or_else(|_| failed(input, Expected::Char(c)))
+}
+
+/// Report a parser that fails right away as expecting `item`, e.g. an
+/// identifier, rather than everything it tried
+pub fn expecting<'a, O, F>(item: &'static str, mut parser: F) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    move |input| {
+        let before = FAILURES.with_borrow(Clone::clone);
+        match parser(input) {
+            Err(nom::Err::Error(e)) if e.input.len() == input.len() => {
+                if before.is_some() {
+                    FAILURES.set(before);
+                }
+                failed(input, Expected::Item(item))
+            }
+            other => other,
+        }
+    }
+}
+
+/// A statement (or expression) that does not parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// What is wrong: "syntax error", or e.g. "unterminated quoted string"
+    /// for a token the lexer found no end of
+    pub reason: &'static str,
+    /// 1-based character position of the offending token in the text parsed
+    pub position: usize,
+    /// The offending token; `None` at the end of the input
+    pub token: Option<String>,
+    /// What would have been accepted instead, if known
+    pub expected: Vec<Expected>,
+}
+
+impl SyntaxError {
+    /// Error at `rest`, the unparsed part of `text` (a slice of it)
+    pub(super) fn at(text: &str, rest: &str, expected: Vec<Expected>) -> Self {
+        let rest = skip_comments(rest);
+        let offset = rest.as_ptr() as usize - text.as_ptr() as usize;
+        Self {
+            reason: "syntax error",
+            position: text[..offset].chars().count() + 1,
+            token: token(rest).map(str::to_string),
+            expected,
+        }
+    }
+
+    /// Error where the grammar stopped in the text of `source`, or at its
+    /// furthest failure if that got further
+    pub(super) fn new(source: &Source<'_>, stopped: &str, failures: Failures) -> Self {
+        let (remaining, expected) = if failures.remaining > stopped.len() {
+            (stopped.len(), Vec::new())
+        } else {
+            (failures.remaining, failures.expected)
+        };
+        let offset = source.original_offset(source.text.len() - remaining);
+        Self::at(source.original, &source.original[offset..source.end().max(offset)], expected)
+    }
+
+    /// The token at `offset` in `text` has no end, e.g. a string with no closing quote
+    pub(super) fn unterminated(text: &str, offset: usize, reason: &'static str) -> Self {
+        Self {
+            reason,
+            position: text[..offset].chars().count() + 1,
+            token: Some(text[offset..].trim_end().to_string()),
+            expected: Vec::new(),
+        }
+    }
+
+    /// `token` of `text` cannot stand for a value, e.g. an escape in an E'...' string
+    pub(super) fn invalid(text: &str, token: Token<'_>, reason: &'static str) -> Self {
+        Self {
+            reason,
+            position: text[..token.offset].chars().count() + 1,
+            token: Some(token.text.to_string()),
+            expected: Vec::new(),
+        }
+    }
+}
+
+/// Where a parse stopped: the unparsed rest, or where it failed
+pub(super) fn stopped_at<'a, O>(result: &IResult<&'a str, O>, input: &'a str) -> &'a str {
+    match result {
+        Ok((rest, _)) => rest,
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => e.input,
+        Err(nom::Err::Incomplete(_)) => &input[input.len()..],
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.token {
+            Some(token) => write!(f, "{} at or near \"{token}\"", self.reason)?,
+            None => write!(f, "{} at end of input", self.reason)?,
+        }
+        write!(f, " at character {}", self.position)?;
+
+        if let Some((last, rest)) = self.expected.split_last()
+            && self.expected.len() <= MAX_EXPECTED_SHOWN
+        {
+            f.write_str(", expected ")?;
+            for (i, expected) in rest.iter().enumerate() {
+                let separator = if i == 0 { "" } else { ", " };
+                write!(f, "{separator}{expected}")?;
+            }
+            if !rest.is_empty() {
+                f.write_str(" or ")?;
+            }
+            write!(f, "{last}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+impl From<SyntaxError> for crate::core::DatabaseError {
+    fn from(error: SyntaxError) -> Self {
+        Self::SyntaxError { message: error.to_string(), position: error.position }
+    }
+}
+
+impl From<SyntaxError> for String {
+    fn from(error: SyntaxError) -> Self {
+        error.to_string()
+    }
+}
+
+/// `text` from its first token that is not a comment
+fn skip_comments(text: &str) -> &str {
+    let first = Lexer::new(text).find(|token| !matches!(token, Ok(Token { kind: TokenKind::Comment, .. })));
+    match first {
+        Some(Ok(token)) => &text[token.offset..],
+        _ => text.trim_start(),
+    }
+}
+
+/// The token `text` starts with, or all of it if that token does not end
+fn token(text: &str) -> Option<&str> {
+    match Lexer::new(text).next()? {
+        Ok(token) => Some(token.text),
+        Err(_) => Some(text.trim_end()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::branch::alt;
+    use nom::combinator::opt;
+    use nom::sequence::tuple;
+
+    #[test]
+    fn test_gathers_the_furthest_failures() {
+        let mut parser = tuple((
+            alt((
+                tuple((tag_no_case("DROP"), tag(" "), tag_no_case("TABLE"))),
+                tuple((tag_no_case("DROP"), tag(" "), tag_no_case("INDEX"))),
+                tuple((tag_no_case("CREATE"), tag(" "), tag_no_case("TABLE"))),
+            )),
+            opt(char(';')),
+        ));
+        let Err(nom::Err::Error(error)) = parser("drop view v") else {
+            panic!("must not parse");
+        };
+        assert_eq!(error.input, "view v");
+
+        // Failures inside opt() count as well, and nothing is noted outside
+        let (parsed, failures) = gather_failures(|| parser("DROP TABLE x"));
+        assert!(parsed.is_ok());
+        assert_eq!(failures, Failures { remaining: 2, expected: vec![Expected::Char(';')] });
+        let (_, failures) = gather_failures(|| parser("drop view v"));
+        assert_eq!(failures.expected, [Expected::Literal("TABLE"), Expected::Literal("INDEX")]);
+        assert_eq!(FAILURES.with_borrow(Clone::clone), None);
+    }
+
+    #[test]
+    fn test_message() {
+        let text = "SELECT 1 FORM t";
+        let error = SyntaxError::at(text, &text[9..], Vec::new());
+        assert_eq!(error.position, 10);
+        assert_eq!(error.to_string(), "syntax error at or near \"FORM\" at character 10");
+
+        let text = "INSERT INTO t VALUES (1";
+        let expected = vec![Expected::Char(','), Expected::Char(')')];
+        let error = SyntaxError::at(text, &text[text.len()..], expected);
+        assert_eq!(error.token, None);
+        assert_eq!(error.to_string(), "syntax error at end of input at character 24, expected \",\" or \")\"");
+
+        let text = "SELECT 'é' x";
+        let error = SyntaxError::at(text, &text[12..], vec![Expected::Item("identifier")]);
+        assert_eq!(error.to_string(), "syntax error at or near \"x\" at character 12, expected identifier");
+        assert_eq!(token("'a b' c"), Some("'a b'"));
+        assert_eq!(token("<= 1"), Some("<="));
+        assert_eq!(token("\"my table\" x"), Some("\"my table\""));
+    }
+}
@@ -0,0 +1,479 @@
+// SQL lexer (v2.7.0)
+//
+// Splits SQL text into tokens: keywords, identifiers, literals, parameters,
+// operators and comments. The plan cache, `$n` parameters and SQL function
+// bodies use the tokens; `Source` writes a statement back from its tokens
+// without comments and extra whitespace before the nom grammar parses it.
+//
+// Not done: the statement parsers still parse text, not the token stream.
+use super::common::is_reserved_keyword;
+use super::error::{SyntaxError, is_word_char};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Reserved keyword, e.g. SELECT
+    Keyword,
+    /// Name or keyword that is not reserved
+    Identifier,
+    /// "Name" in double quotes
+    QuotedIdentifier,
+    /// 'text', E'text' or $$text$$
+    String,
+    /// 42, 4.2 or 4.2e1
+    Number,
+    /// $1, $2, ...
+    Parameter,
+    /// Operator or punctuation, e.g. `<=` or `(`
+    Operator,
+    /// -- to the end of the line, or /* ... */
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The token as written
+    pub text: &'a str,
+    /// Byte offset of the token in the SQL text
+    pub offset: usize,
+}
+
+impl Token<'_> {
+    /// Byte offset just past the token
+    #[must_use]
+    pub const fn end(&self) -> usize {
+        self.offset + self.text.len()
+    }
+
+    /// Number of a `$n` parameter
+    #[must_use]
+    pub fn parameter_number(&self) -> Option<usize> {
+        match self.kind {
+            TokenKind::Parameter => self.text[1..].parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Characters operators are made of
+const OPERATOR_CHARS: &str = "+-*/<>=~!@#%^&|`?";
+
+/// Tokens of `sql`, ending with an error if a quote or comment does not end
+pub struct Lexer<'a> {
+    sql: &'a str,
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    #[must_use]
+    pub const fn new(sql: &'a str) -> Self {
+        Self { sql, offset: 0 }
+    }
+
+    /// Length and kind of the token `rest` starts with
+    fn scan(rest: &str) -> Result<(usize, TokenKind), &'static str> {
+        let bytes = rest.as_bytes();
+        let second = bytes.get(1).copied().unwrap_or_default();
+        Ok(match bytes[0] {
+            b'(' | b')' | b',' | b';' | b'[' | b']' => (1, TokenKind::Operator),
+            b'0'..=b'9' => (number(rest), TokenKind::Number),
+            b'.' if second.is_ascii_digit() => (number(rest), TokenKind::Number),
+            b'-' if second == b'-' => (rest.find('\n').unwrap_or(rest.len()), TokenKind::Comment),
+            b'/' if second == b'*' => (block_comment(rest).ok_or("unterminated /* comment")?, TokenKind::Comment),
+            b'\'' => (quoted(rest, b'\'', false).ok_or("unterminated quoted string")?, TokenKind::String),
+            b'e' | b'E' if second == b'\'' => {
+                (1 + quoted(&rest[1..], b'\'', true).ok_or("unterminated quoted string")?, TokenKind::String)
+            }
+            b'"' => (quoted(rest, b'"', false).ok_or("unterminated quoted identifier")?, TokenKind::QuotedIdentifier),
+            b'$' if second.is_ascii_digit() => (1 + digits(&rest[1..]), TokenKind::Parameter),
+            b'$' => match dollar_quoted(rest) {
+                Some(len) => (len?, TokenKind::String),
+                None => (1, TokenKind::Operator),
+            },
+            b':' if second == b':' => (2, TokenKind::Operator),
+            b if b.is_ascii_alphabetic() || b == b'_' => word(rest),
+            b if OPERATOR_CHARS.as_bytes().contains(&b) => (operator(rest), TokenKind::Operator),
+            _ => match rest.chars().next().unwrap_or_default() {
+                c if c.is_alphabetic() => word(rest),
+                c => (c.len_utf8(), TokenKind::Operator),
+            },
+        })
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, SyntaxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.sql[self.offset..];
+        let spaces = rest.bytes().position(|b| !b.is_ascii_whitespace()).unwrap_or(rest.len());
+        let rest = match rest.as_bytes().get(spaces) {
+            Some(b) if !b.is_ascii() => rest.trim_start(),
+            _ => &rest[spaces..],
+        };
+        if rest.is_empty() {
+            return None;
+        }
+        let offset = self.sql.len() - rest.len();
+        match Self::scan(rest) {
+            Ok((len, kind)) => {
+                self.offset = offset + len;
+                Some(Ok(Token { kind, text: &rest[..len], offset }))
+            }
+            Err(reason) => {
+                self.offset = self.sql.len();
+                Some(Err(SyntaxError::unterminated(self.sql, offset, reason)))
+            }
+        }
+    }
+}
+
+/// All tokens of `sql`, comments included
+pub fn tokenize(sql: &str) -> Result<Vec<Token<'_>>, SyntaxError> {
+    Lexer::new(sql).collect()
+}
+
+/// Length of a literal in `quote`s that `rest` starts with; a doubled quote
+/// stands for itself, as does a quote after a backslash in E'' strings
+fn quoted(rest: &str, quote: u8, backslash_escapes: bool) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Length of a /* comment */ that `rest` starts with; comments nest
+fn block_comment(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut depth = 0_usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [b'/', b'*', ..] => depth += 1,
+            [b'*', b'/', ..] => depth -= 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Length of a $tag$ ... $tag$ string that `rest` starts with, `None` if
+/// `rest` starts with no opening $tag$
+fn dollar_quoted(rest: &str) -> Option<Result<usize, &'static str>> {
+    let tag_len = rest[1..].find(|c: char| !is_word_char(c))?;
+    if !rest[1 + tag_len..].starts_with('$') || rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let delimiter = &rest[..tag_len + 2];
+    Some(
+        rest[delimiter.len()..]
+            .find(delimiter)
+            .map(|end| 2 * delimiter.len() + end)
+            .ok_or("unterminated dollar-quoted string"),
+    )
+}
+
+/// Length and kind of a word: a keyword or an identifier, which may have `$` in it
+fn word(rest: &str) -> (usize, TokenKind) {
+    let len = match rest.bytes().position(|b| !(b.is_ascii_alphanumeric() || b == b'_' || b == b'$')) {
+        Some(len) if !rest.as_bytes()[len].is_ascii() => {
+            rest.find(|c: char| !is_word_char(c) && c != '$').unwrap_or(rest.len())
+        }
+        Some(len) => len,
+        None => rest.len(),
+    };
+    let kind = if is_reserved_keyword(&rest[..len]) { TokenKind::Keyword } else { TokenKind::Identifier };
+    (len, kind)
+}
+
+fn digits(rest: &str) -> usize {
+    rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())
+}
+
+/// Length of a number: digits, a fraction and an exponent, each optional
+/// but the digits on one side of the point
+fn number(rest: &str) -> usize {
+    let mut len = digits(rest);
+    if rest[len..].starts_with('.') && !rest[len + 1..].starts_with('.') {
+        len += 1 + digits(&rest[len + 1..]);
+    }
+    let exponent = rest[len..].strip_prefix(['e', 'E']).map(|e| e.strip_prefix(['+', '-']).unwrap_or(e));
+    if let Some(exponent) = exponent.filter(|e| e.starts_with(|c: char| c.is_ascii_digit())) {
+        len = rest.len() - exponent.len() + digits(exponent);
+    }
+    len
+}
+
+/// Length of an operator: the longest run of operator characters that does
+/// not start a comment, where a trailing + or - is only kept after one of
+/// ~ ! @ # % ^ & | ? or a backtick (so `=-1` is = followed by -1), as in `PostgreSQL`
+fn operator(rest: &str) -> usize {
+    let mut len = rest.find(|c: char| !OPERATOR_CHARS.contains(c)).unwrap_or(rest.len());
+    if let Some(comment) = [rest[..len].find("--"), rest[..len].find("/*")].into_iter().flatten().min() {
+        len = comment.max(1);
+    }
+    if len > 1 && !rest[..len].contains(|c: char| "~!@#%^&|`?".contains(c)) {
+        len = rest[..len].trim_end_matches(['+', '-']).len().max(1);
+    }
+    len
+}
+
+/// How a token is written for the grammar: as it is, but an E'...' string
+/// as the standard string with the same value
+fn written(token: Token<'_>) -> Result<Cow<'_, str>, &'static str> {
+    match token.text.strip_prefix(['e', 'E']) {
+        Some(quoted) if token.kind == TokenKind::String => {
+            let value = unescape(&quoted[1..quoted.len() - 1])?;
+            Ok(Cow::Owned(format!("'{}'", value.replace('\'', "''"))))
+        }
+        _ => Ok(Cow::Borrowed(token.text)),
+    }
+}
+
+/// Value of the text between the quotes of an E'...' string: '' and the
+/// backslash escapes of `PostgreSQL`, `\n`, `\t`, `\ooo`, `\xhh`, `\uXXXX` and so on,
+/// with any other character after a backslash standing for itself
+fn unescape(body: &str) -> Result<String, &'static str> {
+    let mut value = Vec::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(at) = rest.find(['\\', '\'']) {
+        value.extend_from_slice(&rest.as_bytes()[..at]);
+        let escaped = &rest[at + 1..];
+        if rest.as_bytes()[at] == b'\'' {
+            value.push(b'\'');
+            rest = &escaped[1..];
+            continue;
+        }
+        let Some(c) = escaped.chars().next() else { break };
+        let len = match c {
+            'b' | 'f' | 'n' | 'r' | 't' => {
+                value.push(match c {
+                    'b' => 0x08,
+                    'f' => 0x0c,
+                    'n' => b'\n',
+                    'r' => b'\r',
+                    _ => b'\t',
+                });
+                1
+            }
+            '0'..='7' => {
+                let len = escaped.bytes().take(3).take_while(|b| (b'0'..=b'7').contains(b)).count();
+                value.push(escaped.bytes().take(len).fold(0_u8, |byte, digit| byte.wrapping_mul(8).wrapping_add(digit - b'0')));
+                len
+            }
+            'x' if escaped[1..].starts_with(|c: char| c.is_ascii_hexdigit()) => {
+                let len = escaped[1..].bytes().take(2).take_while(u8::is_ascii_hexdigit).count();
+                value.push(u8::from_str_radix(&escaped[1..=len], 16).map_err(|_| "invalid hexadecimal escape")?);
+                1 + len
+            }
+            'u' | 'U' => {
+                let len = if c == 'u' { 4 } else { 8 };
+                let code = escaped
+                    .get(1..=len)
+                    .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or("invalid Unicode escape")?;
+                let c = u32::from_str_radix(code, 16).ok().and_then(char::from_u32).ok_or("invalid Unicode escape value")?;
+                value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                1 + len
+            }
+            c => {
+                value.extend_from_slice(&escaped.as_bytes()[..c.len_utf8()]);
+                c.len_utf8()
+            }
+        };
+        rest = &escaped[len..];
+    }
+    value.extend_from_slice(rest.as_bytes());
+    if value.contains(&0) {
+        return Err("invalid byte sequence for encoding \"UTF8\": 0x00");
+    }
+    String::from_utf8(value).map_err(|_| "invalid byte sequence for encoding \"UTF8\"")
+}
+
+/// Normalized SQL, the text the grammar parses: the tokens of a statement
+/// written back without comments, with a single space between tokens that
+/// had anything in between and E'...' strings as standard ones
+///
+/// Most statements are written that way already, and their text is borrowed.
+/// Syntax errors in the normalized text are mapped back to positions in the
+/// original.
+pub struct Source<'a> {
+    /// The SQL text as given
+    pub original: &'a str,
+    /// The text the grammar parses
+    pub text: Cow<'a, str>,
+    /// Offsets in `text` from which it lines up with `original` again, after
+    /// a gap that was collapsed: offset in `text` and in `original`
+    shifts: Vec<(usize, usize)>,
+    /// Offset in `original` just past the last token
+    end: usize,
+}
+
+impl<'a> Source<'a> {
+    /// Tokens of `original`; a statement (`statement`) leaves out trailing semicolons
+    pub fn new(original: &'a str, statement: bool) -> Result<Self, SyntaxError> {
+        let mut source = Self { original, text: Cow::Borrowed(""), shifts: Vec::new(), end: 0 };
+        let mut text_len = 0;
+        // Semicolons are held back until a token follows them
+        let mut semicolons = Vec::new();
+        for token in Lexer::new(original) {
+            let token = token?;
+            match token.kind {
+                TokenKind::Comment => {}
+                TokenKind::Operator if statement && token.text == ";" => semicolons.push(token),
+                _ => {
+                    for semicolon in std::mem::take(&mut semicolons) {
+                        source.push(semicolon, &mut text_len)?;
+                    }
+                    source.push(token, &mut text_len)?;
+                }
+            }
+        }
+        Ok(source)
+    }
+
+    fn push(&mut self, token: Token<'a>, text_len: &mut usize) -> Result<(), SyntaxError> {
+        let written = written(token).map_err(|reason| SyntaxError::invalid(self.original, token, reason))?;
+        let rewritten = matches!(written, Cow::Owned(_));
+        let gap = &self.original[self.end..token.offset];
+        let separator = if *text_len == 0 || gap.is_empty() { "" } else { " " };
+        if gap != separator || rewritten {
+            // From here on the text is written rather than borrowed; leading
+            // whitespace only moves where the borrowed text starts
+            if let Cow::Borrowed(text) = self.text
+                && (*text_len > 0 || rewritten)
+            {
+                self.text = Cow::Owned(String::with_capacity(self.original.len()) + text);
+            }
+            if gap != separator {
+                self.shifts.push((*text_len + separator.len(), token.offset));
+            }
+        }
+        match &mut self.text {
+            Cow::Borrowed(_) => {
+                let start = self.shifts.first().map_or(0, |&(_, original_at)| original_at);
+                self.text = Cow::Borrowed(&self.original[start..token.end()]);
+            }
+            Cow::Owned(text) => {
+                text.push_str(separator);
+                text.push_str(&written);
+            }
+        }
+        *text_len += separator.len() + written.len();
+        if rewritten {
+            // The token was written shorter; the text lines up again after it
+            self.shifts.push((*text_len, token.end()));
+        }
+        self.end = token.end();
+        Ok(())
+    }
+
+    /// Byte offset in `original` of an offset in `text`; an offset inside a
+    /// rewritten E'...' string lands at or before the same byte of the original
+    #[must_use]
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let mut offset = match self.shifts.partition_point(|&(at, _)| at <= offset).checked_sub(1) {
+            Some(i) => self.shifts[i].1 + offset - self.shifts[i].0,
+            None => offset,
+        };
+        while !self.original.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Byte offset in `original` just past the last token
+    #[must_use]
+    pub const fn end(&self) -> usize {
+        self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(sql).unwrap().into_iter().map(|token| (token.kind, token.text)).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("select \"Order\".id, E'it\\'s' || 'a''b' FROM t -- all\nWHERE x>=-1.5e3 AND y = $2::text"),
+            [
+                (Keyword, "select"),
+                (QuotedIdentifier, "\"Order\""),
+                (Operator, "."),
+                (Identifier, "id"),
+                (Operator, ","),
+                (String, "E'it\\'s'"),
+                (Operator, "||"),
+                (String, "'a''b'"),
+                (Keyword, "FROM"),
+                (Identifier, "t"),
+                (Comment, "-- all"),
+                (Keyword, "WHERE"),
+                (Identifier, "x"),
+                (Operator, ">="),
+                (Operator, "-"),
+                (Number, "1.5e3"),
+                (Keyword, "AND"),
+                (Identifier, "y"),
+                (Operator, "="),
+                (Parameter, "$2"),
+                (Operator, "::"),
+                (Identifier, "text"),
+            ]
+        );
+        assert_eq!(
+            kinds("$fn$ SELECT ';' $fn$ /* a /* nested */ comment */ $$x$$"),
+            [(String, "$fn$ SELECT ';' $fn$"), (Comment, "/* a /* nested */ comment */"), (String, "$$x$$")]
+        );
+
+        let error = tokenize("SELECT 'it''s").unwrap_err();
+        assert_eq!(error.to_string(), "unterminated quoted string at or near \"'it''s\" at character 8");
+        assert!(tokenize("SELECT \"a").is_err());
+        assert!(tokenize("/* a /* b */").is_err());
+        assert!(tokenize("SELECT $x$ body").is_err());
+    }
+
+    #[test]
+    fn test_source() {
+        let sql = "SELECT /* columns */ id,\n\t name  FROM t -- rows\n ORDER\n  BY id;;";
+        let source = Source::new(sql, true).unwrap();
+        assert_eq!(source.text, "SELECT id, name FROM t ORDER BY id");
+        // Offsets map back into the token they fall in
+        assert_eq!(source.original_offset(source.text.find("name").unwrap() + 1), sql.find("name").unwrap() + 1);
+        assert_eq!(source.original_offset(7), sql.find("id").unwrap());
+        assert_eq!(source.original_offset(source.text.len()), sql.rfind("id").unwrap() + 2);
+        assert_eq!(Source::new("'a  b'  ;", false).unwrap().text, "'a  b' ;");
+        // Text already written that way is borrowed
+        assert!(matches!(Source::new("  SELECT 1;", true).unwrap().text, Cow::Borrowed("SELECT 1")));
+
+        // E'...' strings are written as standard ones, and the text lines up again after them
+        let sql = "SELECT E'a\\tb', e'it\\'s\\101\\q' AS x";
+        let source = Source::new(sql, false).unwrap();
+        assert_eq!(source.text, "SELECT 'a\tb', 'it''sAq' AS x");
+        assert_eq!(source.original_offset(source.text.find("AS").unwrap()), sql.find("AS").unwrap());
+        let error = Source::new("SELECT E'\\u12'", false).err().unwrap();
+        assert_eq!(error.to_string(), "invalid Unicode escape at or near \"E'\\u12'\" at character 8");
+        assert!(Source::new("SELECT E'\\0'", false).is_err());
+    }
+}
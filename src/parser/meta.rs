@@ -1,11 +1,15 @@
-use super::common::ws;
+use super::common::{identifier, quoted_string, ws};
 use super::statement::Statement;
 use nom::{
     branch::alt,
-    bytes::complete::{tag, tag_no_case},
-    combinator::rest,
-    IResult,
+    bytes::complete::take_while1,
+    character::complete::{multispace0, multispace1},
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::{preceded, terminated, tuple},
 };
+use nom::error::ParseError;
+use super::error::{GrammarError, IResult, char, tag, tag_no_case};
 
 pub fn show_tables(input: &str) -> IResult<&str, Statement> {
     // Support both "SHOW TABLES" (MySQL-style) and "\dt" or "\d" (psql-style)
@@ -17,6 +21,15 @@ pub fn show_tables(input: &str) -> IResult<&str, Statement> {
     Ok((input, Statement::ShowTables))
 }
 
+// v2.7.0: "\d name" or "\d+ name" (psql-style)
+pub fn describe_table(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = preceded(multispace0, tag("\\d"))(input)?;
+    let (input, verbose) = opt(char('+'))(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    Ok((input, Statement::DescribeTable { name, verbose: verbose.is_some() }))
+}
+
 pub fn show_users(input: &str) -> IResult<&str, Statement> {
     // Support both "SHOW USERS" and "\du" (psql-style)
     let (input, _) = ws(alt((
@@ -35,30 +48,100 @@ pub fn show_databases(input: &str) -> IResult<&str, Statement> {
     Ok((input, Statement::ShowDatabases))
 }
 
+// v2.7.0: "\c dbname", "\connect dbname" (psql-style) or "USE dbname"
+pub fn connect(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = preceded(
+        multispace0,
+        alt((
+            tag("\\connect"),
+            tag("\\c"),
+            tag_no_case("USE"),
+        )),
+    )(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, database) = ws(identifier)(input)?;
+    Ok((input, Statement::Connect { database }))
+}
+
+// v2.7.0: SET [SESSION | LOCAL] name { = | TO } value [, ...]
+// or SET TIME ZONE value (the TimeZone parameter)
+// Values are quoted strings or bare words and numbers (`on`, `0`, `DEFAULT`)
+pub fn set(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("SET"))(input)?;
+    let (input, _) = opt(ws(alt((tag_no_case("SESSION "), tag_no_case("LOCAL ")))))(input)?;
+    let (input, name) = alt((
+        map(ws(tuple((tag_no_case("TIME"), multispace1, tag_no_case("ZONE"), multispace1))), |_| "timezone".to_string()),
+        terminated(ws(identifier), ws(alt((tag("="), tag_no_case("TO"))))),
+    ))(input)?;
+    let (input, value) = setting_value(input)?;
+    Ok((input, Statement::Set { name: name.to_lowercase(), value }))
+}
+
+// v2.7.0: SHOW name, or SHOW TIME ZONE (the TimeZone parameter)
+pub fn show(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tag_no_case("SHOW"))(input)?;
+    let (input, name) = alt((
+        map(ws(tuple((tag_no_case("TIME"), multispace1, tag_no_case("ZONE")))), |_| "timezone".to_string()),
+        ws(identifier),
+    ))(input)?;
+    Ok((input, Statement::Show { name: name.to_lowercase() }))
+}
+
+// v2.7.0: ALTER SYSTEM SET name { = | TO } value, ALTER SYSTEM RESET { name | ALL }
+pub fn alter_system(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(tuple((tag_no_case("ALTER"), multispace1, tag_no_case("SYSTEM"))))(input)?;
+    alt((
+        map(
+            preceded(
+                ws(tag_no_case("SET")),
+                tuple((terminated(ws(identifier), ws(alt((tag("="), tag_no_case("TO"))))), setting_value)),
+            ),
+            |(name, value)| Statement::AlterSystemSet { name: name.to_lowercase(), value },
+        ),
+        map(
+            preceded(ws(tag_no_case("RESET")), alt((map(ws(tag_no_case("ALL")), |_| None), map(ws(identifier), Some)))),
+            |name| Statement::AlterSystemReset { name: name.map(|name| name.to_lowercase()) },
+        ),
+    ))(input)
+}
+
+// Value of SET or ALTER SYSTEM: quoted strings or bare words and numbers,
+// list values joined with ", "
+fn setting_value(input: &str) -> IResult<&str, String> {
+    map(
+        separated_list1(
+            ws(char(',')),
+            ws(alt((
+                quoted_string,
+                map(take_while1(|c: char| c.is_alphanumeric() || "_.-+".contains(c)), str::to_string),
+            ))),
+        ),
+        |values| values.join(", "),
+    )(input)
+}
+
 // EXPLAIN command (v1.8.0)
 pub fn explain(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(tag_no_case("EXPLAIN"))(input)?;
-    let (input, query_str) = rest(input)?;
 
-    // Parse the inner statement
-    match crate::parser::parse_statement(query_str.trim()) {
-        Ok(inner_stmt) => {
-            // Only allow EXPLAIN for SELECT statements
-            if matches!(inner_stmt, Statement::Select { .. }) {
-                Ok((input, Statement::Explain {
-                    statement: Box::new(inner_stmt),
-                }))
-            } else {
-                // For now, only support EXPLAIN SELECT
-                Err(nom::Err::Error(nom::error::Error::new(
-                    input,
-                    nom::error::ErrorKind::Tag,
-                )))
-            }
-        }
-        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
-            input,
-            nom::error::ErrorKind::Tag,
-        ))),
+    // Parse the inner statement (v2.7.0: its syntax errors are reported as they are)
+    let (rest, inner_stmt) = super::statement(input)?;
+    // v2.7.0: Queries and DML - EXPLAIN shows the plan without running them
+    if matches!(
+        inner_stmt,
+        Statement::Select { .. }
+            | Statement::Union { .. }
+            | Statement::Intersect { .. }
+            | Statement::Except { .. }
+            | Statement::Insert { .. }
+            | Statement::InsertMany { .. }
+            | Statement::Update { .. }
+            | Statement::Delete { .. }
+    ) {
+        Ok((rest, Statement::Explain {
+            statement: Box::new(inner_stmt),
+        }))
+    } else {
+        Err(nom::Err::Error(GrammarError::from_error_kind(input, nom::error::ErrorKind::Tag)))
     }
 }
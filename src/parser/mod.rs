@@ -6,6 +6,8 @@ mod dml;
 mod queries;
 mod meta;
 mod transaction;
+mod error;  // v2.7.0
+mod lexer;  // v2.7.0
 
 // Re-export all public types for backward compatibility
 pub use statement::{
@@ -23,29 +25,48 @@ pub use statement::{
     GrantObject,     // v2.3.0
     CaseExpression,  // v1.10.0
     WhenClause,      // v1.10.0
+    WhenCondition,   // v2.7.0
     CopyFormat,      // v2.4.0
     WindowFunction,  // v2.6.0
     WindowSpec,      // v2.6.0
+    Expression,      // v2.7.0
+    BinaryOperator,  // v2.7.0
+    CompareOperator, // v2.7.0
+    OnCommitAction,  // v2.7.0
+    EnumValuePosition,  // v2.7.0
+    DerivedTable,       // v2.7.0
+    InsertSource,       // v2.7.0
+    ObjectRef,      // v2.7.0
 };
+pub use error::{Expected, SyntaxError};  // v2.7.0
+pub use lexer::{Lexer, Token, TokenKind, tokenize};  // v2.7.0
+use lexer::Source;
 
 // Main parser function that combines all parsers
 use nom::branch::alt;
 
-pub fn parse_statement(input: &str) -> Result<Statement, String> {
-    let input = input.trim();
-    let input = input.trim_end_matches(';');
+/// v2.7.0: A syntax error reports where the statement stops parsing, as a
+/// character position in `input`, and what could have come there
+pub fn parse_statement(input: &str) -> Result<Statement, SyntaxError> {
+    complete(&Source::new(input, true)?, statement)
+}
 
+// Any statement, for `parse_statement` and statements nested in others (EXPLAIN)
+fn statement(input: &str) -> error::IResult<&str, Statement> {
     // Split into three alt blocks due to nom's 21-element tuple limit
-    let result = alt((
+    alt((
         alt((
             meta::explain,  // v1.8.0 - must come before show_* to avoid conflicts
+            meta::connect,  // v2.7.0
             meta::show_users,
             meta::show_databases,
+            meta::describe_table,  // v2.7.0 - before show_tables (\d)
             meta::show_tables,
             transaction::begin_transaction,
             transaction::commit_transaction,
             transaction::rollback_transaction,
             ddl::create_type,
+            ddl::alter_type,  // v2.7.0
             ddl::create_user,
             ddl::drop_user,
             ddl::alter_user,
@@ -54,6 +75,8 @@ pub fn parse_statement(input: &str) -> Result<Statement, String> {
             ddl::grant_role,   // v2.3.0 - must come before grant (database privilege)
             ddl::revoke_role,  // v2.3.0 - must come before revoke (database privilege)
             ddl::create_database,
+            ddl::alter_database,  // v2.7.0
+            ddl::create_foreign_table,  // v2.7.0
         )),
         alt((
             ddl::drop_database,
@@ -61,32 +84,65 @@ pub fn parse_statement(input: &str) -> Result<Statement, String> {
             ddl::revoke,
             ddl::parse_create_view,  // v1.10.0 - before create_table to avoid conflicts
             ddl::parse_drop_view,    // v1.10.0
+            ddl::parse_create_function,  // v2.7.0
+            ddl::parse_drop_function,    // v2.7.0
             ddl::parse_copy,         // v2.4.0
+            ddl::create_temp_table,  // v2.7.0 - before create_table
+            ddl::create_partition,   // v2.7.0 - before create_table
             ddl::create_table,
             ddl::drop_table,
             ddl::alter_table,
             ddl::parse_create_index,
             ddl::parse_drop_index,
             ddl::parse_vacuum,
+            ddl::parse_analyze,     // v2.7.0
             dml::insert,
-            queries::select,
+            alt((transaction::undo_transaction, transaction::export_snapshot, queries::select)),  // v2.7.0
             dml::update,
             dml::delete,
         )),
-    ))(input);
+        alt((
+            transaction::set_transaction_snapshot,  // v2.7.0 - before set
+            meta::set,         // v2.7.0
+            meta::show,        // v2.7.0 - after show_* (SHOW TABLES, USERS, DATABASES)
+            meta::alter_system,  // v2.7.0
+            ddl::comment_on,   // v2.7.0
+            ddl::alter_owner,  // v2.7.0 - after alter_type (ADD VALUE)
+            transaction::declare_cursor,  // v2.7.0
+            transaction::fetch,           // v2.7.0
+            transaction::close_cursor,    // v2.7.0
+            ddl::parse_cluster,  // v2.7.0
+        )),
+    ))(input)
+}
 
-    match result {
-        Ok((remaining, stmt)) => {
-            if remaining.trim().is_empty() {
-                Ok(stmt)
-            } else {
-                Err(format!("Unexpected input after statement: {remaining}"))
-            }
-        }
-        Err(e) => Err(format!("Parse error: {e:?}")),
+// v2.7.0: The value `parser` parses from all of the source text; if it does
+// not, it runs again gathering what it expected where it stopped
+fn complete<T>(source: &Source<'_>, parser: impl Fn(&str) -> error::IResult<&str, T>) -> Result<T, SyntaxError> {
+    let text = &*source.text;
+    if let Ok(("", parsed)) = parser(text) {
+        Ok(parsed)
+    } else {
+        let (result, failures) = error::gather_failures(|| parser(text));
+        Err(SyntaxError::new(source, error::stopped_at(&result, text), failures))
     }
 }
 
+/// Parse a standalone scalar expression, e.g. a function call (v2.7.0)
+pub fn parse_expression(input: &str) -> Result<Expression, SyntaxError> {
+    complete(&Source::new(input, false)?, queries::expression)
+}
+
+/// Parse a standalone WHERE-style condition, e.g. a CHECK constraint (v2.7.0)
+pub fn parse_condition(input: &str) -> Result<Condition, SyntaxError> {
+    complete(&Source::new(input, false)?, queries::condition)
+}
+
+/// Parse a subquery in FROM as kept in `Statement::Select::from` (v2.7.0)
+pub fn parse_derived_table(input: &str) -> Result<DerivedTable, SyntaxError> {
+    complete(&Source::new(input, false)?, queries::derived_table)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +161,42 @@ mod tests {
         assert!(matches!(stmt, Statement::Insert { .. }));
     }
 
+    #[test]
+    fn test_parse_doubled_quotes_and_constraint_order() {
+        // v2.7.0: '' inside a literal is a quote
+        let Statement::Insert { values, .. } = parse_statement("INSERT INTO t VALUES ('it''s', '''', '')").unwrap() else {
+            panic!("Expected INSERT");
+        };
+        let text = |s: &str| crate::types::Value::Text(s.to_string());
+        assert_eq!(values, [text("it's"), text("'"), text("")]);
+
+        // v2.7.0: E'...' strings take backslash escapes
+        let Statement::Insert { values, .. } = parse_statement("INSERT INTO t VALUES (E'a\\nb', e'it\\'s', E'\\x41\\u00e9')").unwrap() else {
+            panic!("Expected INSERT");
+        };
+        assert_eq!(values, [text("a\nb"), text("it's"), text("Aé")]);
+        assert!(parse_statement("SELECT datname FROM pg_database WHERE datname <> E'\\n' ORDER BY datname").is_ok());
+
+        // v2.7.0: Column constraints in any order
+        let Statement::CreateTable { columns, .. } = parse_statement("CREATE TABLE t (a TEXT NOT NULL UNIQUE, b INTEGER UNIQUE PRIMARY KEY)").unwrap() else {
+            panic!("Expected CREATE TABLE");
+        };
+        assert_eq!((columns[0].nullable, columns[0].unique), (false, true));
+        assert_eq!((columns[1].primary_key, columns[1].unique), (true, true));
+    }
+
+    #[test]
+    fn test_parse_insert_many() {
+        let stmt = parse_statement("INSERT INTO users (id, name) VALUES (1, 'a'), (2, 'b')").unwrap();
+        let Statement::InsertMany { source: InsertSource::Values(rows), .. } = stmt else {
+            panic!("Expected a multi-row INSERT, got {stmt:?}");
+        };
+        assert_eq!(rows.len(), 2);
+
+        let stmt = parse_statement("INSERT INTO archive SELECT * FROM users WHERE id > 10").unwrap();
+        assert!(matches!(stmt, Statement::InsertMany { source: InsertSource::Query(_), .. }));
+    }
+
     #[test]
     fn test_parse_select() {
         let sql = "SELECT * FROM users WHERE id = 1";
@@ -132,13 +224,141 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_select_with_not() {
+        use crate::types::Value;
+
+        let filter = |sql: &str| match parse_statement(sql).unwrap() {
+            Statement::Select { filter: Some(filter), .. } => filter,
+            other => panic!("Expected SELECT with WHERE, got {other:?}"),
+        };
+        let age = |v: i16| Box::new(Condition::Equals("age".to_string(), Value::SmallInt(v)));
+
+        assert_eq!(
+            filter("SELECT * FROM users WHERE NOT (age = 1 OR age = 2)"),
+            Condition::Not(Box::new(Condition::Or(age(1), age(2))))
+        );
+        assert_eq!(
+            filter("SELECT * FROM users WHERE age NOT IN (1, 2)"),
+            Condition::Not(Box::new(Condition::In("age".to_string(), vec![Value::SmallInt(1), Value::SmallInt(2)])))
+        );
+        // A column that merely starts with "not"
+        assert_eq!(
+            filter("SELECT * FROM users WHERE notes = 'x'"),
+            Condition::Equals("notes".to_string(), Value::Text("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_update_with_expressions() {
+        use crate::types::Value;
+
+        let Statement::Update { assignments, filter, .. } =
+            parse_statement("UPDATE orders SET total = price * qty, name = UPPER(name), note = NULL WHERE id = 1").unwrap()
+        else {
+            panic!("Expected UPDATE");
+        };
+        let column = |name: &str| Box::new(Expression::Column(name.to_string()));
+        assert_eq!(
+            assignments,
+            vec![
+                (
+                    "total".to_string(),
+                    Expression::Binary { left: column("price"), op: BinaryOperator::Multiply, right: column("qty") }
+                ),
+                (
+                    "name".to_string(),
+                    Expression::Function { name: "UPPER".to_string(), args: vec![Expression::Column("name".to_string())] }
+                ),
+                ("note".to_string(), Expression::Literal(Value::Null)),
+            ]
+        );
+        assert_eq!(filter, Some(Condition::Equals("id".to_string(), Value::SmallInt(1))));
+    }
+
+    #[test]
+    fn test_parse_delete_using_and_update_from() {
+        let Statement::Delete { from, using, filter } =
+            parse_statement("DELETE FROM a USING b WHERE a.id = b.id AND b.flag").unwrap()
+        else {
+            panic!("Expected DELETE");
+        };
+        assert_eq!((from.as_str(), using), ("a", vec!["b".to_string()]));
+        assert_eq!(
+            filter,
+            Some(Condition::And(
+                Box::new(Condition::ColumnEquals("a.id".to_string(), "b.id".to_string())),
+                Box::new(Condition::Equals("b.flag".to_string(), crate::types::Value::Boolean(true))),
+            ))
+        );
+
+        let Statement::Update { assignments, from, .. } =
+            parse_statement("UPDATE a SET price = b.price FROM b, c WHERE a.id = b.id").unwrap()
+        else {
+            panic!("Expected UPDATE");
+        };
+        assert_eq!(assignments, vec![("price".to_string(), Expression::Column("b.price".to_string()))]);
+        assert_eq!(from, vec!["b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_set_operation_with_order_by_and_limit() {
+        let Statement::Union { left, right, all, order_by, limit, offset } =
+            parse_statement("SELECT a FROM x EXCEPT SELECT a FROM y UNION ALL SELECT a FROM z ORDER BY a DESC LIMIT 5 OFFSET 1")
+                .unwrap()
+        else {
+            panic!("Expected UNION");
+        };
+        // Left-associative, and the trailing clauses belong to the whole query
+        assert!(matches!(*left, Statement::Except { .. }));
+        assert!(matches!(*right, Statement::Select { order_by: None, limit: None, .. }));
+        assert!(all);
+        assert_eq!(order_by, Some((Expression::Column("a".to_string()), SortOrder::Desc)));
+        assert_eq!((limit, offset), (Some(5), Some(1)));
+
+        // INTERSECT binds tighter; a parenthesized arm keeps its own LIMIT
+        let Statement::Union { right, .. } =
+            parse_statement("SELECT a FROM x UNION (SELECT a FROM y LIMIT 1) INTERSECT SELECT a FROM z").unwrap()
+        else {
+            panic!("Expected UNION");
+        };
+        let Statement::Intersect { left, .. } = *right else {
+            panic!("Expected INTERSECT");
+        };
+        assert!(matches!(*left, Statement::Select { limit: Some(1), .. }));
+
+        assert!(parse_statement("SELECT a FROM x ORDER BY a UNION SELECT a FROM y").is_err());
+    }
+
+    #[test]
+    fn test_parse_derived_tables() {
+        let Statement::Select { from, joins, .. } = parse_statement(
+            "SELECT * FROM (SELECT id, name FROM users) AS u(uid, n) CROSS JOIN LATERAL (SELECT total FROM orders WHERE orders.user_id = u.uid) t",
+        )
+        .unwrap() else {
+            panic!("Expected SELECT");
+        };
+
+        let base = parse_derived_table(&from).unwrap();
+        assert_eq!((base.alias.as_str(), base.columns.as_slice(), base.lateral), ("u", ["uid".to_string(), "n".to_string()].as_slice(), false));
+        assert!(matches!(*base.query, Statement::Select { .. }));
+
+        assert_eq!(joins.len(), 1);
+        assert!(joins[0].on_left.is_empty() && joins[0].on_right.is_empty());
+        let lateral = parse_derived_table(&joins[0].table).unwrap();
+        assert_eq!((lateral.alias.as_str(), lateral.lateral), ("t", true));
+
+        // The alias is required
+        assert!(parse_statement("SELECT * FROM (SELECT id FROM users) WHERE id = 1").is_err());
+    }
+
     #[test]
     fn test_parse_select_with_order_by_asc() {
         let sql = "SELECT * FROM users ORDER BY age ASC";
         let stmt = parse_statement(sql).unwrap();
         match stmt {
             Statement::Select { order_by: Some((col, SortOrder::Asc)), .. } => {
-                assert_eq!(col, "age");
+                assert_eq!(col, Expression::Column("age".to_string()));
             }
             _ => panic!("Expected ORDER BY ASC"),
         }
@@ -150,7 +370,7 @@ mod tests {
         let stmt = parse_statement(sql).unwrap();
         match stmt {
             Statement::Select { order_by: Some((col, SortOrder::Desc)), .. } => {
-                assert_eq!(col, "age");
+                assert_eq!(col, Expression::Column("age".to_string()));
             }
             _ => panic!("Expected ORDER BY DESC"),
         }
@@ -176,7 +396,7 @@ mod tests {
                 limit: Some(5),
                 ..
             } => {
-                assert_eq!(col, "age");
+                assert_eq!(col, Expression::Column("age".to_string()));
             }
             _ => panic!("Expected ORDER BY DESC LIMIT 5"),
         }
@@ -195,7 +415,7 @@ mod tests {
                 ..
             } => {
                 assert_eq!(columns.len(), 2);
-                assert_eq!(col, "age");
+                assert_eq!(col, Expression::Column("age".to_string()));
             }
             _ => panic!("Expected complex SELECT"),
         }
@@ -277,6 +497,153 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_analyze() {
+        assert_eq!(parse_statement("ANALYZE").unwrap(), Statement::Analyze { table: None });
+        assert_eq!(parse_statement("analyze orders").unwrap(), Statement::Analyze { table: Some("orders".to_string()) });
+    }
+
+    #[test]
+    fn test_parse_cursors() {
+        match parse_statement("DECLARE big NO SCROLL CURSOR WITHOUT HOLD FOR SELECT * FROM orders WHERE id > 5").unwrap() {
+            Statement::DeclareCursor { name, query } => {
+                assert_eq!(name, "big");
+                assert!(matches!(*query, Statement::Select { .. }));
+            }
+            other => panic!("Expected DECLARE, got {other:?}"),
+        }
+        let fetch = |sql: &str| parse_statement(sql).unwrap();
+        let expected = |cursor: &str, count, move_only| Statement::Fetch { cursor: cursor.to_string(), count, move_only };
+        assert_eq!(fetch("FETCH big"), expected("big", Some(1), false));
+        assert_eq!(fetch("fetch 100 from big"), expected("big", Some(100), false));
+        assert_eq!(fetch("FETCH FORWARD 5 IN big"), expected("big", Some(5), false));
+        assert_eq!(fetch("FETCH FORWARD ALL FROM big"), expected("big", None, false));
+        assert_eq!(fetch("FETCH ALL big"), expected("big", None, false));
+        assert_eq!(fetch("FETCH next_batch"), expected("next_batch", Some(1), false));
+        assert_eq!(fetch("MOVE NEXT FROM big"), expected("big", Some(1), true));
+        assert_eq!(fetch("CLOSE big"), Statement::CloseCursor { cursor: Some("big".to_string()) });
+        assert_eq!(fetch("CLOSE ALL"), Statement::CloseCursor { cursor: None });
+        assert!(parse_statement("DECLARE c CURSOR FOR DELETE FROM orders").is_err());
+    }
+
+    #[test]
+    fn test_parse_cluster() {
+        assert_eq!(parse_statement("CLUSTER").unwrap(), Statement::Cluster { table: None, index: None });
+        assert_eq!(parse_statement("CLUSTER orders").unwrap(), Statement::Cluster { table: Some("orders".to_string()), index: None });
+        assert_eq!(
+            parse_statement("cluster orders using idx_orders_date").unwrap(),
+            Statement::Cluster { table: Some("orders".to_string()), index: Some("idx_orders_date".to_string()) }
+        );
+    }
+
+    #[test]
+    fn test_parse_undo_transaction() {
+        assert_eq!(parse_statement("SELECT pg_undo_transaction(42)").unwrap(), Statement::UndoTransaction { xid: 42 });
+        assert_eq!(parse_statement("select PG_UNDO_TRANSACTION( 7 );").unwrap(), Statement::UndoTransaction { xid: 7 });
+        // Other functions are still plain SELECTs
+        assert!(matches!(parse_statement("SELECT pg_backend_pid()").unwrap(), Statement::Select { .. }));
+    }
+
+    #[test]
+    fn test_parse_snapshot_export_import() {
+        assert_eq!(parse_statement("SELECT pg_export_snapshot();").unwrap(), Statement::ExportSnapshot);
+        assert_eq!(
+            parse_statement("set transaction snapshot '00000003-00000001'").unwrap(),
+            Statement::SetTransactionSnapshot { id: "00000003-00000001".to_string() }
+        );
+        assert!(parse_statement("SET TRANSACTION SNAPSHOT").is_err());
+    }
+
+    #[test]
+    fn test_parse_pg_dump_statements() {
+        assert_eq!(
+            parse_statement("SET client_encoding = 'UTF8'").unwrap(),
+            Statement::Set { name: "client_encoding".to_string(), value: "UTF8".to_string() }
+        );
+        assert_eq!(
+            parse_statement("SET SESSION search_path TO public, '$user'").unwrap(),
+            Statement::Set { name: "search_path".to_string(), value: "public, $user".to_string() }
+        );
+        assert_eq!(
+            parse_statement("SET default_tablespace = ''").unwrap(),
+            Statement::Set { name: "default_tablespace".to_string(), value: String::new() }
+        );
+        assert_eq!(
+            parse_statement("SET TIME ZONE 'Europe/Berlin'").unwrap(),
+            Statement::Set { name: "timezone".to_string(), value: "Europe/Berlin".to_string() }
+        );
+        assert!(parse_statement("SELECT pg_catalog.set_config('search_path', '', false)").is_ok());
+        assert_eq!(parse_statement("SHOW TimeZone").unwrap(), Statement::Show { name: "timezone".to_string() });
+        assert_eq!(parse_statement("show time zone").unwrap(), Statement::Show { name: "timezone".to_string() });
+        assert_eq!(parse_statement("SHOW TABLES").unwrap(), Statement::ShowTables);
+
+        assert_eq!(
+            parse_statement("ALTER SYSTEM SET Work_Mem TO '8MB'").unwrap(),
+            Statement::AlterSystemSet { name: "work_mem".to_string(), value: "8MB".to_string() }
+        );
+        assert_eq!(
+            parse_statement("alter system set wal_compression = off").unwrap(),
+            Statement::AlterSystemSet { name: "wal_compression".to_string(), value: "off".to_string() }
+        );
+        assert_eq!(
+            parse_statement("ALTER SYSTEM RESET port").unwrap(),
+            Statement::AlterSystemReset { name: Some("port".to_string()) }
+        );
+        assert_eq!(parse_statement("ALTER SYSTEM RESET ALL").unwrap(), Statement::AlterSystemReset { name: None });
+
+        match parse_statement("CREATE TABLE public.t (name character varying(20), at timestamp without time zone, tz timestamp with time zone, c character(2))").unwrap() {
+            Statement::CreateTable { name, columns, .. } => {
+                assert_eq!(name, "t");
+                assert_eq!(columns[0].data_type, crate::types::DataType::Varchar { max_length: 20 });
+                assert_eq!(columns[1].data_type, crate::types::DataType::Timestamp);
+                assert_eq!(columns[2].data_type, crate::types::DataType::TimestampTz);
+                assert_eq!(columns[3].data_type, crate::types::DataType::Char { length: 2 });
+            }
+            other => panic!("Expected CREATE TABLE, got {other:?}"),
+        }
+        assert!(matches!(
+            parse_statement("ALTER TABLE ONLY public.t OWNER TO postgres").unwrap(),
+            Statement::AlterTable { name, operation: AlterTableOperation::OwnerTo(owner) } if name == "t" && owner == "postgres"
+        ));
+        assert_eq!(
+            parse_statement("ALTER TYPE public.mood OWNER TO postgres").unwrap(),
+            Statement::AlterOwner { object: ObjectRef::Type("mood".to_string()), owner: "postgres".to_string() }
+        );
+        assert!(matches!(
+            parse_statement("CREATE INDEX idx_t_name ON public.t USING btree (name)").unwrap(),
+            Statement::CreateIndex { table, index_type: crate::index::IndexType::BTree, .. } if table == "t"
+        ));
+        assert!(matches!(
+            parse_statement("COPY public.t (name, at) FROM stdin").unwrap(),
+            Statement::Copy { table, from_stdin: true, format: CopyFormat::Text, .. } if table == "t"
+        ));
+        assert!(matches!(
+            parse_statement("COPY t TO STDOUT WITH (FORMAT csv)").unwrap(),
+            Statement::Copy { format: CopyFormat::Csv, .. }
+        ));
+
+        assert_eq!(
+            parse_statement("COMMENT ON COLUMN public.t.name IS 'It''s the name'").unwrap(),
+            Statement::CommentOn {
+                object: ObjectRef::Column { table: "t".to_string(), column: "name".to_string() },
+                comment: Some("It's the name".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_statement("COMMENT ON TABLE t IS NULL").unwrap(),
+            Statement::CommentOn { object: ObjectRef::Table("t".to_string()), comment: None }
+        );
+        assert_eq!(
+            parse_statement("\\d+ users").unwrap(),
+            Statement::DescribeTable { name: "users".to_string(), verbose: true }
+        );
+        assert_eq!(
+            parse_statement("\\d users").unwrap(),
+            Statement::DescribeTable { name: "users".to_string(), verbose: false }
+        );
+        assert_eq!(parse_statement("\\d").unwrap(), Statement::ShowTables);
+    }
+
     #[test]
     fn test_parse_case_simple() {
         let sql = "SELECT name, CASE WHEN age < 18 THEN 'minor' ELSE 'adult' END FROM users";
@@ -285,7 +652,7 @@ mod tests {
             Statement::Select { columns, .. } => {
                 assert_eq!(columns.len(), 2);
                 match &columns[1] {
-                    SelectColumn::Case(case_expr) => {
+                    SelectColumn::Expression { expr: Expression::Case(case_expr), alias: None } => {
                         assert_eq!(case_expr.when_clauses.len(), 1);
                         assert!(case_expr.else_value.is_some());
                     }
@@ -304,10 +671,10 @@ mod tests {
             Statement::Select { columns, .. } => {
                 assert_eq!(columns.len(), 1);
                 match &columns[0] {
-                    SelectColumn::Case(case_expr) => {
+                    SelectColumn::Expression { expr: Expression::Case(case_expr), alias } => {
                         assert_eq!(case_expr.when_clauses.len(), 2);
                         assert!(case_expr.else_value.is_some());
-                        assert_eq!(case_expr.alias, Some("category".to_string()));
+                        assert_eq!(alias, &Some("category".to_string()));
                     }
                     _ => panic!("Expected CASE expression"),
                 }
@@ -323,7 +690,7 @@ mod tests {
         match stmt {
             Statement::Select { columns, .. } => {
                 match &columns[0] {
-                    SelectColumn::Case(case_expr) => {
+                    SelectColumn::Expression { expr: Expression::Case(case_expr), .. } => {
                         assert_eq!(case_expr.when_clauses.len(), 1);
                         assert!(case_expr.else_value.is_none());
                     }
@@ -333,4 +700,527 @@ mod tests {
             _ => panic!("Expected SELECT"),
         }
     }
+
+    #[test]
+    fn test_parse_case_anywhere() {
+        // Simple CASE in the SELECT list
+        let stmt = parse_statement("SELECT CASE status WHEN 1 THEN 'one' WHEN 2 THEN 'two' END FROM t").unwrap();
+        let Statement::Select { columns, .. } = stmt else { panic!("Expected SELECT") };
+        let SelectColumn::Expression { expr: Expression::Case(case_expr), .. } = &columns[0] else {
+            panic!("Expected CASE expression");
+        };
+        assert_eq!(case_expr.operand, Some(Expression::Column("status".to_string())));
+        assert_eq!(
+            case_expr.when_clauses[1].condition,
+            WhenCondition::Value(Expression::Literal(crate::types::Value::SmallInt(2)))
+        );
+
+        // Compared in WHERE, sorted on in ORDER BY
+        let sql = "SELECT id FROM t WHERE CASE WHEN a > 1 THEN 'x' ELSE 'y' END = 'x' ORDER BY CASE kind WHEN 'b' THEN 0 ELSE 1 END DESC";
+        let Statement::Select { filter, order_by, .. } = parse_statement(sql).unwrap() else { panic!("Expected SELECT") };
+        assert!(matches!(
+            filter,
+            Some(Condition::Compare(Expression::Case(_), CompareOperator::Equal, Expression::Literal(crate::types::Value::Text(_))))
+        ));
+        assert!(matches!(order_by, Some((Expression::Case(_), SortOrder::Desc))));
+
+        // In UPDATE ... SET, and a CASE alone as a condition
+        let sql = "UPDATE t SET level = CASE WHEN score >= 90 THEN 'high' ELSE level END WHERE CASE WHEN flag THEN true ELSE false END";
+        let Statement::Update { assignments, filter, .. } = parse_statement(sql).unwrap() else { panic!("Expected UPDATE") };
+        assert!(matches!(assignments[0].1, Expression::Case(_)));
+        assert!(matches!(filter, Some(Condition::Compare(Expression::Case(_), CompareOperator::Equal, _))));
+
+        // Arithmetic comparisons keep working next to the column forms
+        let Statement::Select { filter, .. } = parse_statement("SELECT * FROM t WHERE price * qty <> 100").unwrap() else {
+            panic!("Expected SELECT")
+        };
+        assert!(matches!(filter, Some(Condition::Compare(Expression::Binary { .. }, CompareOperator::NotEqual, _))));
+    }
+
+    #[test]
+    fn test_parse_aggregate_filter() {
+        let sql = "SELECT COUNT(*) FILTER (WHERE status = 'done'), SUM(total) filter (where total > 10 AND paid), COUNT(id) FROM orders";
+        let Statement::Select { columns, .. } = parse_statement(sql).unwrap() else { panic!("Expected SELECT") };
+        assert_eq!(columns.len(), 3);
+        assert_eq!(
+            columns[0],
+            SelectColumn::Aggregate(AggregateFunction::Filter {
+                function: Box::new(AggregateFunction::Count(CountTarget::All)),
+                filter: Box::new(Condition::Equals("status".to_string(), crate::types::Value::Text("done".to_string()))),
+            })
+        );
+        assert!(matches!(
+            &columns[1],
+            SelectColumn::Aggregate(AggregateFunction::Filter { function, filter })
+                if **function == AggregateFunction::Sum("total".to_string()) && matches!(**filter, Condition::And(..))
+        ));
+        assert_eq!(columns[2], SelectColumn::Aggregate(AggregateFunction::Count(CountTarget::Column("id".to_string()))));
+
+        assert!(parse_statement("SELECT COUNT(*) FILTER (status = 'done') FROM orders").is_err());
+    }
+
+    #[test]
+    fn test_parse_collate_and_ilike() {
+        let stmt = parse_statement("CREATE TABLE t (name TEXT COLLATE \"de_DE\" NOT NULL, code VARCHAR(5) collate nocase, id INTEGER)").unwrap();
+        let Statement::CreateTable { columns, .. } = stmt else { panic!("Expected CreateTable") };
+        assert_eq!(columns[0].collation.as_deref(), Some("de_DE"));
+        assert!(!columns[0].nullable);
+        assert_eq!(columns[1].collation.as_deref(), Some("nocase"));
+        assert_eq!(columns[2].collation, None);
+
+        let Statement::Select { filter, .. } = parse_statement("SELECT * FROM t WHERE name ILIKE '%mü%'").unwrap() else {
+            panic!("Expected SELECT")
+        };
+        assert_eq!(filter, Some(Condition::ILike("name".to_string(), "%mü%".to_string())));
+    }
+
+    #[test]
+    fn test_parse_create_temp_table() {
+        let stmt = parse_statement("CREATE TEMP TABLE scratch (id INTEGER, note TEXT)").unwrap();
+        match stmt {
+            Statement::CreateTempTable { name, columns, on_commit, .. } => {
+                assert_eq!(name, "scratch");
+                assert_eq!(columns.len(), 2);
+                assert_eq!(on_commit, OnCommitAction::PreserveRows);
+            }
+            _ => panic!("Expected CreateTempTable"),
+        }
+
+        let stmt = parse_statement("CREATE TEMPORARY TABLE s (id INTEGER) ON COMMIT DELETE ROWS").unwrap();
+        assert!(matches!(
+            stmt,
+            Statement::CreateTempTable { on_commit: OnCommitAction::DeleteRows, .. }
+        ));
+
+        let stmt = parse_statement("create temp table s (id INTEGER) on commit drop").unwrap();
+        assert!(matches!(
+            stmt,
+            Statement::CreateTempTable { on_commit: OnCommitAction::Drop, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_partitioning() {
+        let stmt = parse_statement("CREATE TABLE events (id INTEGER, ts DATE) PARTITION BY RANGE (ts)").unwrap();
+        match stmt {
+            Statement::CreateTable { partition_by: Some(partitioning), .. } => {
+                assert_eq!(partitioning.strategy, crate::types::PartitionStrategy::Range);
+                assert_eq!(partitioning.column, "ts");
+            }
+            _ => panic!("Expected partitioned CreateTable"),
+        }
+
+        let stmt = parse_statement(
+            "CREATE TABLE events_old PARTITION OF events FOR VALUES FROM (MINVALUE) TO ('2024-01-01')",
+        )
+        .unwrap();
+        match stmt {
+            Statement::CreatePartition { name, parent, bound: crate::types::PartitionBound::Range { from, to }, .. } => {
+                assert_eq!(name, "events_old");
+                assert_eq!(parent, "events");
+                assert!(from.is_none());
+                assert!(to.is_some());
+            }
+            _ => panic!("Expected range CreatePartition"),
+        }
+
+        let stmt = parse_statement("CREATE TABLE eu PARTITION OF users FOR VALUES IN ('de', 'fr')").unwrap();
+        assert!(matches!(
+            stmt,
+            Statement::CreatePartition { bound: crate::types::PartitionBound::List(ref values), .. } if values.len() == 2
+        ));
+        let stmt = parse_statement("CREATE TABLE other PARTITION OF users DEFAULT").unwrap();
+        assert!(matches!(
+            stmt,
+            Statement::CreatePartition { bound: crate::types::PartitionBound::Default, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_create_table_using() {
+        let stmt = parse_statement("CREATE TABLE facts (id INTEGER, amount INTEGER) USING columnar").unwrap();
+        assert!(matches!(
+            stmt,
+            Statement::CreateTable { access_method: Some(ref method), .. } if method == "columnar"
+        ));
+
+        let stmt = parse_statement("CREATE TABLE events (id INTEGER) PARTITION BY RANGE (id) USING lsm").unwrap();
+        assert!(matches!(
+            stmt,
+            Statement::CreateTable { partition_by: Some(_), access_method: Some(ref method), .. } if method == "lsm"
+        ));
+
+        let stmt = parse_statement("CREATE TABLE plain (id INTEGER)").unwrap();
+        assert!(matches!(stmt, Statement::CreateTable { access_method: None, .. }));
+    }
+
+    #[test]
+    fn test_parse_create_foreign_table() {
+        let stmt = parse_statement(
+            "CREATE FOREIGN TABLE visits (id INTEGER, city TEXT) SERVER csv OPTIONS (path '/tmp/visits.csv', header 'true')",
+        )
+        .unwrap();
+        match stmt {
+            Statement::CreateForeignTable { name, columns, server, options, owner } => {
+                assert_eq!(name, "visits");
+                assert_eq!(columns.len(), 2);
+                assert_eq!(server, "csv");
+                assert_eq!(
+                    options,
+                    vec![
+                        ("path".to_string(), "/tmp/visits.csv".to_string()),
+                        ("header".to_string(), "true".to_string()),
+                    ]
+                );
+                assert_eq!(owner, None);
+            }
+            _ => panic!("Expected CreateForeignTable"),
+        }
+
+        let stmt = parse_statement("DROP FOREIGN TABLE visits").unwrap();
+        assert_eq!(stmt, Statement::DropTable { name: "visits".to_string(), cascade: false });
+    }
+
+    #[test]
+    fn test_parse_drop_cascade() {
+        let stmt = parse_statement("DROP TABLE users CASCADE").unwrap();
+        assert_eq!(stmt, Statement::DropTable { name: "users".to_string(), cascade: true });
+        let stmt = parse_statement("drop table users restrict").unwrap();
+        assert_eq!(stmt, Statement::DropTable { name: "users".to_string(), cascade: false });
+        let stmt = parse_statement("DROP VIEW adults CASCADE").unwrap();
+        assert_eq!(stmt, Statement::DropView { name: "adults".to_string(), cascade: true });
+        assert!(parse_statement("DROP TABLE users CASCADE RESTRICT").is_err());
+        let stmt = parse_statement("ALTER TABLE users DROP COLUMN name CASCADE").unwrap();
+        assert_eq!(
+            stmt,
+            Statement::AlterTable {
+                name: "users".to_string(),
+                operation: AlterTableOperation::DropColumn { name: "name".to_string(), cascade: true },
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_table_constraints() {
+        use crate::types::{ConstraintKind, ForeignKey};
+        let operation = |sql: &str| match parse_statement(sql).unwrap() {
+            Statement::AlterTable { operation, .. } => operation,
+            other => panic!("Expected AlterTable, got {other:?}"),
+        };
+
+        assert_eq!(
+            operation("ALTER TABLE orders ADD CONSTRAINT qty_positive CHECK (qty > 0 AND (qty < 100 OR bulk = TRUE))"),
+            AlterTableOperation::AddConstraint {
+                name: "qty_positive".to_string(),
+                kind: ConstraintKind::Check("qty > 0 AND (qty < 100 OR bulk = TRUE)".to_string()),
+                not_valid: false,
+            }
+        );
+        assert_eq!(
+            operation("ALTER TABLE orders ADD CONSTRAINT orders_user_fk FOREIGN KEY (user_id) REFERENCES users (id) NOT VALID"),
+            AlterTableOperation::AddConstraint {
+                name: "orders_user_fk".to_string(),
+                kind: ConstraintKind::ForeignKey {
+                    column: "user_id".to_string(),
+                    references: ForeignKey { referenced_table: "users".to_string(), referenced_column: "id".to_string() },
+                },
+                not_valid: true,
+            }
+        );
+        assert_eq!(
+            operation("alter table users add constraint name_key unique (first, last)"),
+            AlterTableOperation::AddConstraint {
+                name: "name_key".to_string(),
+                kind: ConstraintKind::Unique(vec!["first".to_string(), "last".to_string()]),
+                not_valid: false,
+            }
+        );
+        assert_eq!(
+            operation("ALTER TABLE orders VALIDATE CONSTRAINT orders_user_fk"),
+            AlterTableOperation::ValidateConstraint("orders_user_fk".to_string())
+        );
+        assert_eq!(
+            operation("ALTER TABLE orders DROP CONSTRAINT IF EXISTS qty_positive CASCADE"),
+            AlterTableOperation::DropConstraint { name: "qty_positive".to_string(), if_exists: true }
+        );
+        assert_eq!(operation("ALTER TABLE users ALTER COLUMN name SET NOT NULL"), AlterTableOperation::SetNotNull("name".to_string()));
+        assert_eq!(operation("alter table users alter column name drop not null"), AlterTableOperation::DropNotNull("name".to_string()));
+    }
+
+    #[test]
+    fn test_parse_alter_type_add_value() {
+        let stmt = parse_statement("ALTER TYPE status ADD VALUE 'archived'").unwrap();
+        assert_eq!(
+            stmt,
+            Statement::AlterTypeAddValue {
+                name: "status".to_string(),
+                value: "archived".to_string(),
+                if_not_exists: false,
+                position: None,
+            }
+        );
+
+        let stmt = parse_statement("alter type status add value if not exists 'draft' before 'active'").unwrap();
+        assert_eq!(
+            stmt,
+            Statement::AlterTypeAddValue {
+                name: "status".to_string(),
+                value: "draft".to_string(),
+                if_not_exists: true,
+                position: Some(EnumValuePosition::Before("active".to_string())),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_alter_database_owner() {
+        let stmt = parse_statement("ALTER DATABASE shop OWNER TO alice").unwrap();
+        assert_eq!(
+            stmt,
+            Statement::AlterDatabaseOwner {
+                name: "shop".to_string(),
+                new_owner: "alice".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_create_database_template() {
+        assert_eq!(
+            parse_statement("CREATE DATABASE shop").unwrap(),
+            Statement::CreateDatabase { name: "shop".to_string(), owner: None, template: None, is_template: false }
+        );
+        assert_eq!(
+            parse_statement("CREATE DATABASE shop WITH TEMPLATE = base OWNER alice IS_TEMPLATE true").unwrap(),
+            Statement::CreateDatabase {
+                name: "shop".to_string(),
+                owner: Some("alice".to_string()),
+                template: Some("base".to_string()),
+                is_template: true,
+            }
+        );
+        assert_eq!(
+            parse_statement("ALTER DATABASE shop WITH IS_TEMPLATE = false").unwrap(),
+            Statement::AlterDatabaseTemplate { name: "shop".to_string(), is_template: false }
+        );
+        assert!(parse_statement("CREATE DATABASE shop TEMPLATE").is_err());
+    }
+
+    #[test]
+    fn test_parse_drop_database_force() {
+        assert_eq!(
+            parse_statement("DROP DATABASE shop").unwrap(),
+            Statement::DropDatabase { name: "shop".to_string(), force: false }
+        );
+        for sql in ["DROP DATABASE shop WITH (FORCE)", "drop database shop (force)"] {
+            assert_eq!(parse_statement(sql).unwrap(), Statement::DropDatabase { name: "shop".to_string(), force: true });
+        }
+    }
+
+    #[test]
+    fn test_parse_user_options() {
+        use crate::types::UserOptions;
+
+        assert_eq!(
+            parse_statement("CREATE USER app WITH PASSWORD 'pw' SUPERUSER").unwrap(),
+            Statement::CreateUser {
+                username: "app".to_string(),
+                password: "pw".to_string(),
+                options: UserOptions { superuser: Some(true), ..UserOptions::default() },
+            }
+        );
+        let Statement::CreateUser { options, .. } =
+            parse_statement("CREATE USER app NOLOGIN CREATEDB PASSWORD 'pw' VALID UNTIL '2030-01-01'").unwrap()
+        else {
+            panic!("expected CREATE USER");
+        };
+        assert_eq!(options.login, Some(false));
+        assert_eq!(options.create_db, Some(true));
+        assert_eq!(options.valid_until.flatten().unwrap().to_rfc3339(), "2030-01-01T00:00:00+00:00");
+        // CREATE USER needs a password
+        assert!(parse_statement("CREATE USER app CREATEDB").is_err());
+
+        assert_eq!(
+            parse_statement("ALTER USER app PASSWORD 'new'").unwrap(),
+            Statement::AlterUser {
+                username: "app".to_string(),
+                password: Some("new".to_string()),
+                options: UserOptions::default(),
+            }
+        );
+        assert_eq!(
+            parse_statement("ALTER USER app WITH NOCREATEROLE LOGIN VALID UNTIL 'infinity'").unwrap(),
+            Statement::AlterUser {
+                username: "app".to_string(),
+                password: None,
+                options: UserOptions {
+                    create_role: Some(false),
+                    login: Some(true),
+                    valid_until: Some(None),
+                    ..UserOptions::default()
+                },
+            }
+        );
+        assert!(parse_statement("ALTER USER app VALID UNTIL 'someday'").is_err());
+    }
+
+    #[test]
+    fn test_parse_connect() {
+        let shop = Statement::Connect { database: "shop".to_string() };
+        assert_eq!(parse_statement("\\c shop").unwrap(), shop);
+        assert_eq!(parse_statement("\\connect shop").unwrap(), shop);
+        assert_eq!(parse_statement("use shop;").unwrap(), shop);
+        assert!(parse_statement("\\c").is_err());
+        assert!(parse_statement("\\cshop").is_err());
+    }
+
+    #[test]
+    fn test_parse_create_function() {
+        let sql = "CREATE OR REPLACE FUNCTION add(a int, b int) RETURNS int AS 'SELECT a + b' LANGUAGE SQL";
+        match parse_statement(sql).unwrap() {
+            Statement::CreateFunction { name, params, returns_set, body, or_replace, .. } => {
+                assert_eq!(name, "add");
+                assert_eq!(params.len(), 2);
+                assert!(!returns_set);
+                assert_eq!(body, "SELECT a + b");
+                assert!(or_replace);
+            }
+            _ => panic!("Expected CREATE FUNCTION"),
+        }
+    }
+
+    #[test]
+    fn test_parse_expressions_and_from_function() {
+        let sql = "SELECT add(age, 1) * 2 AS doubled FROM gen(3)";
+        match parse_statement(sql).unwrap() {
+            Statement::Select { columns, from, .. } => {
+                assert_eq!(from, "gen(3)");
+                match &columns[0] {
+                    SelectColumn::Expression { expr: Expression::Binary { op, .. }, alias } => {
+                        assert_eq!(*op, BinaryOperator::Multiply);
+                        assert_eq!(alias.as_deref(), Some("doubled"));
+                    }
+                    _ => panic!("Expected expression column"),
+                }
+            }
+            _ => panic!("Expected SELECT"),
+        }
+
+        // Plain columns stay regular columns
+        match parse_statement("SELECT name, age FROM users").unwrap() {
+            Statement::Select { columns, .. } => {
+                assert_eq!(columns[0], SelectColumn::Regular { name: "name".to_string(), alias: None });
+            }
+            _ => panic!("Expected SELECT"),
+        }
+
+        // An aliased column is still a column reference
+        match parse_statement("SELECT name AS n, id FROM pc").unwrap() {
+            Statement::Select { columns, .. } => assert_eq!(
+                columns,
+                [
+                    SelectColumn::Regular { name: "name".to_string(), alias: Some("n".to_string()) },
+                    SelectColumn::Regular { name: "id".to_string(), alias: None },
+                ]
+            ),
+            _ => panic!("Expected SELECT"),
+        }
+    }
+
+    #[test]
+    fn test_syntax_error_reports_position_and_expected_tokens() {
+        let error = parse_statement("SELEC 1").unwrap_err();
+        assert_eq!((error.position, error.token.as_deref()), (1, Some("SELEC")));
+        assert_eq!(error.to_string(), "syntax error at or near \"SELEC\" at character 1");
+
+        // The furthest any alternative got, in characters of the original text
+        let error = parse_statement("  CREATE TABLE (id INTEGER)").unwrap_err();
+        assert_eq!(error.position, 16);
+        assert_eq!(error.expected, [Expected::Item("identifier")]);
+        let error = parse_statement("INSERT INTO t VALUES ('x';").unwrap_err();
+        assert_eq!(error.token, None);
+        assert_eq!(error.to_string(), "syntax error at end of input at character 26, expected \",\" or \")\"");
+        let error = parse_statement("INSERT INTO t (a, b VALUES (1, 2)").unwrap_err();
+        assert_eq!(error.to_string(), "syntax error at or near \"VALUES\" at character 21, expected \",\" or \")\"");
+        let error = parse_statement("UPDATE t SET a = 'ü' b = 1").unwrap_err();
+        assert_eq!((error.position, error.token.as_deref()), (22, Some("b")));
+
+        // Only what would continue the statement where it went wrong: the
+        // rest of a keyword, or nothing named where a number was due
+        let error = parse_statement("SELECT * FROM t ORDER id").unwrap_err();
+        assert_eq!(error.to_string(), "syntax error at or near \"id\" at character 23, expected BY");
+        let error = parse_statement("ALTER TABLE t ADD").unwrap_err();
+        assert_eq!(error.expected, [Expected::Literal("COLUMN"), Expected::Literal("CONSTRAINT")]);
+        let error = parse_statement("SELECT * FROM t LIMIT x").unwrap_err();
+        assert_eq!((error.token.as_deref(), error.expected.as_slice()), (Some("x"), &[][..]));
+
+        // Inside EXPLAIN and standalone expressions as well
+        assert_eq!(parse_statement("EXPLAIN SELEC 1").unwrap_err().position, 9);
+        assert_eq!(parse_expression("1 +").unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn test_keywords_and_reserved_words() {
+        let lower = parse_statement("select distinct id from t where id = 1 order by id desc limit 1").unwrap();
+        let mixed = parse_statement("SeLeCt DISTINCT id From t WHERE id = 1 Order By id DESC LIMIT 1").unwrap();
+        assert_eq!(lower, mixed);
+
+        // Keywords only match whole words
+        match parse_statement("CREATE TABLE t (a INTEGR, b int)").unwrap() {
+            Statement::CreateTable { columns, .. } => {
+                assert!(matches!(&columns[0].data_type, crate::types::DataType::Enum { name, .. } if name == "INTEGR"));
+                assert_eq!(columns[1].data_type, crate::types::DataType::Integer);
+            }
+            other => panic!("Expected CREATE TABLE, got {other:?}"),
+        }
+        match parse_statement("SELECT nullable FROM t WHERE true_flag = 1").unwrap() {
+            Statement::Select { columns, .. } => assert_eq!(columns, [SelectColumn::Regular { name: "nullable".to_string(), alias: None }]),
+            other => panic!("Expected SELECT, got {other:?}"),
+        }
+
+        // Reserved keywords are no names unless double-quoted
+        let error = parse_statement("CREATE TABLE select (id INTEGER)").unwrap_err();
+        assert_eq!(error.to_string(), "syntax error at or near \"select\" at character 14, expected identifier");
+        let error = parse_statement("UPDATE t SET WHERE id = 1").unwrap_err();
+        assert_eq!((error.token.as_deref(), error.expected.as_slice()), (Some("WHERE"), &[Expected::Item("identifier")][..]));
+        match parse_statement("CREATE TABLE \"order\" (\"from\" TEXT, \"say \"\"hi\"\"\" TEXT)").unwrap() {
+            Statement::CreateTable { name, columns, .. } => {
+                assert_eq!(name, "order");
+                assert_eq!(columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["from", "say \"hi\""]);
+            }
+            other => panic!("Expected CREATE TABLE, got {other:?}"),
+        }
+
+        // AS is optional for aliases
+        match parse_statement("SELECT id ident, price * 2 AS doubled, (SELECT 1) one FROM t").unwrap() {
+            Statement::Select { columns, from, .. } => {
+                assert_eq!(from, "t");
+                assert_eq!(columns[0], SelectColumn::Regular { name: "id".to_string(), alias: Some("ident".to_string()) });
+                assert!(matches!(&columns[1], SelectColumn::Expression { alias: Some(alias), .. } if alias == "doubled"));
+                assert!(matches!(&columns[2], SelectColumn::Subquery { alias: Some(alias), .. } if alias == "one"));
+            }
+            other => panic!("Expected SELECT, got {other:?}"),
+        }
+        assert_eq!(
+            parse_statement("SELECT id AS ident FROM t").unwrap(),
+            parse_statement("SELECT id ident FROM t").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_comments_and_line_breaks_between_tokens() {
+        let plain = parse_statement("SELECT id, name FROM t WHERE id = 1 ORDER BY name").unwrap();
+        let spread = "SELECT /* columns */ id,\n\tname -- the label\nFROM t\nWHERE id = 1 ORDER\n  BY name;";
+        assert_eq!(parse_statement(spread).unwrap(), plain);
+
+        // Quotes are matched by the lexer, before the grammar
+        let error = parse_statement("SELECT * FROM t WHERE name = 'it''s").unwrap_err();
+        assert_eq!(error.to_string(), "unterminated quoted string at or near \"'it''s\" at character 30");
+        let error = parse_statement("SELECT 1 /* never closed").unwrap_err();
+        assert_eq!((error.reason, error.position), ("unterminated /* comment", 10));
+
+        // Positions still count characters of the original text
+        let error = parse_statement("SELECT id -- ü\n  FROM t WHERE = 1").unwrap_err();
+        assert_eq!((error.position, error.token.as_deref()), (31, Some("=")));
+    }
 }
@@ -1,18 +1,18 @@
-use super::common::{ws, identifier, non_keyword_identifier, value};
+use super::common::{ws, column_ref, identifier, string_literal, value};
 use super::statement::{
     Statement, Condition, SelectColumn, AggregateFunction, CountTarget,
-    JoinClause, JoinType, SortOrder, CaseExpression, WhenClause,
-    WindowFunction, WindowSpec,
+    JoinClause, JoinType, SortOrder, CaseExpression, WhenClause, WhenCondition,
+    WindowFunction, WindowSpec, Expression, BinaryOperator, CompareOperator, DerivedTable,
 };
 use nom::{
     branch::alt,
-    bytes::complete::{tag, tag_no_case, take_while1},
-    character::complete::{char, digit1},
-    combinator::{map, opt, recognize},
-    multi::separated_list1,
+    bytes::complete::take_while1,
+    character::complete::digit1,
+    combinator::{map, opt, recognize, verify},
+    multi::{many0, separated_list0, separated_list1},
     sequence::{delimited, pair, preceded, tuple},
-    IResult,
 };
+use super::error::{IResult, char, expecting, tag, tag_no_case};
 
 // Parse a subquery: (SELECT ...)  (v2.6.0)
 // Using a closure to enable recursive parsing
@@ -43,10 +43,19 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
             ),
             |stmt| Condition::NotExists(stmt),
         ),
+        // NOT cond, (cond) (v2.7.0)
+        map(
+            preceded(
+                ws(keyword("NOT")),
+                condition_term,
+            ),
+            |cond| Condition::Not(Box::new(cond)),
+        ),
+        delimited(ws(char('(')), condition, ws(char(')'))),
         // col IN (SELECT ...) or col NOT IN (SELECT ...) (v2.6.0)
         map(
             tuple((
-                ws(non_keyword_identifier),
+                ws(column_ref),
                 opt(ws(tag_no_case("NOT"))),
                 ws(tag_no_case("IN")),
                 subquery,
@@ -61,23 +70,23 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
         ),
         // col = (SELECT ...) (v2.6.0)
         map(
-            tuple((ws(non_keyword_identifier), ws(char('=')), subquery)),
+            tuple((ws(column_ref), ws(char('=')), subquery)),
             |(col, _, stmt)| Condition::EqualsSubquery(col, stmt),
         ),
         // col > (SELECT ...) (v2.6.0)
         map(
-            tuple((ws(non_keyword_identifier), ws(char('>')), subquery)),
+            tuple((ws(column_ref), ws(char('>')), subquery)),
             |(col, _, stmt)| Condition::GreaterThanSubquery(col, stmt),
         ),
         // col < (SELECT ...) (v2.6.0)
         map(
-            tuple((ws(non_keyword_identifier), ws(char('<')), subquery)),
+            tuple((ws(column_ref), ws(char('<')), subquery)),
             |(col, _, stmt)| Condition::LessThanSubquery(col, stmt),
         ),
         // IS NULL / IS NOT NULL (v1.8.0)
         map(
             tuple((
-                ws(non_keyword_identifier),
+                ws(column_ref),
                 ws(tag_no_case("IS")),
                 ws(tag_no_case("NOT")),
                 ws(tag_no_case("NULL")),
@@ -85,13 +94,13 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
             |(col, _, _, _)| Condition::IsNotNull(col),
         ),
         map(
-            tuple((ws(non_keyword_identifier), ws(tag_no_case("IS")), ws(tag_no_case("NULL")))),
+            tuple((ws(column_ref), ws(tag_no_case("IS")), ws(tag_no_case("NULL")))),
             |(col, _, _)| Condition::IsNull(col),
         ),
         // BETWEEN (v1.8.0)
         map(
             tuple((
-                ws(non_keyword_identifier),
+                ws(column_ref),
                 ws(tag_no_case("BETWEEN")),
                 ws(value),
                 ws(tag_no_case("AND")),
@@ -99,9 +108,14 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
             )),
             |(col, _, low, _, high)| Condition::Between(col, low, high),
         ),
+        // ILIKE (v2.7.0)
+        map(
+            tuple((ws(column_ref), ws(keyword("ILIKE")), ws(string_literal))),
+            |(col, _, pattern)| Condition::ILike(col, pattern),
+        ),
         // LIKE (v1.8.0)
         map(
-            tuple((ws(non_keyword_identifier), ws(tag_no_case("LIKE")), ws(value))),
+            tuple((ws(column_ref), ws(tag_no_case("LIKE")), ws(value))),
             |(col, _, val)| {
                 if let crate::types::Value::Text(pattern) = val {
                     Condition::Like(col, pattern)
@@ -111,10 +125,11 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
                 }
             },
         ),
-        // IN (v1.8.0)
+        // IN (v1.8.0), NOT IN (v2.7.0)
         map(
             tuple((
-                ws(non_keyword_identifier),
+                ws(column_ref),
+                opt(ws(tag_no_case("NOT"))),
                 ws(tag_no_case("IN")),
                 delimited(
                     ws(char('(')),
@@ -122,12 +137,29 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
                     ws(char(')')),
                 ),
             )),
-            |(col, _, values)| Condition::In(col, values),
+            |(col, not, _, values)| {
+                if not.is_some() {
+                    Condition::Not(Box::new(Condition::In(col, values)))
+                } else {
+                    Condition::In(col, values)
+                }
+            },
+        ),
+        // col = other_col, e.g. the join condition of DELETE ... USING (v2.7.0)
+        map(
+            tuple((
+                ws(column_ref),
+                ws(char('=')),
+                ws(verify(column_ref, |name: &str| {
+                    !["TRUE", "FALSE", "NULL"].iter().any(|kw| name.eq_ignore_ascii_case(kw))
+                })),
+            )),
+            |(left, _, right)| Condition::ColumnEquals(left, right),
         ),
         // Comparison operators (including >=, <=)
         map(
             tuple((
-                ws(non_keyword_identifier),
+                ws(column_ref),
                 ws(alt((
                     tag(">="),
                     tag("<="),
@@ -148,13 +180,43 @@ fn condition_term(input: &str) -> IResult<&str, Condition> {
                 _ => unreachable!(),
             },
         ),
+        // expr op expr, e.g. CASE ... END = 'x' or price * qty > 100 (v2.7.0)
+        map(
+            tuple((expression, ws(compare_operator), expression)),
+            |(left, op, right)| Condition::Compare(left, op, right),
+        ),
+        // A CASE on its own is a boolean (v2.7.0)
+        map(
+            verify(expression, |expr: &Expression| matches!(expr, Expression::Case(_))),
+            |expr| Condition::Compare(expr, CompareOperator::Equal, Expression::Literal(crate::types::Value::Boolean(true))),
+        ),
+        // A boolean column on its own (v2.7.0)
+        map(ws(column_ref), |col| Condition::Equals(col, crate::types::Value::Boolean(true))),
+    ))(input)
+}
+
+// Comparison operator between two expressions (v2.7.0)
+fn compare_operator(input: &str) -> IResult<&str, CompareOperator> {
+    alt((
+        map(tag(">="), |_| CompareOperator::GreaterOrEqual),
+        map(tag("<="), |_| CompareOperator::LessOrEqual),
+        map(tag("<>"), |_| CompareOperator::NotEqual),
+        map(tag("!="), |_| CompareOperator::NotEqual),
+        map(char('='), |_| CompareOperator::Equal),
+        map(char('>'), |_| CompareOperator::Greater),
+        map(char('<'), |_| CompareOperator::Less),
     ))(input)
 }
 
+// A whole keyword, so that OR does not match the start of ORDER (v2.7.0)
+pub(super) fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, String> {
+    expecting(word, map(verify(super::common::word, move |found: &str| found.eq_ignore_ascii_case(word)), str::to_string))
+}
+
 // Parse AND conditions (higher priority than OR)
 fn condition_and(input: &str) -> IResult<&str, Condition> {
     let (input, first) = condition_term(input)?;
-    let (input, rest) = opt(preceded(ws(tag_no_case("AND")), condition_and))(input)?;
+    let (input, rest) = opt(preceded(ws(keyword("AND")), condition_and))(input)?;
 
     match rest {
         Some(right) => Ok((input, Condition::And(Box::new(first), Box::new(right)))),
@@ -165,7 +227,7 @@ fn condition_and(input: &str) -> IResult<&str, Condition> {
 // Parse OR conditions (lower priority than AND)
 pub fn condition(input: &str) -> IResult<&str, Condition> {
     let (input, first) = condition_and(input)?;
-    let (input, rest) = opt(preceded(ws(tag_no_case("OR")), condition))(input)?;
+    let (input, rest) = opt(preceded(ws(keyword("OR")), condition))(input)?;
 
     match rest {
         Some(right) => Ok((input, Condition::Or(Box::new(first), Box::new(right)))),
@@ -317,49 +379,152 @@ fn aggregate_function(input: &str) -> IResult<&str, AggregateFunction> {
     ))(input)
 }
 
-// Parse WHEN clause: WHEN condition THEN value
-fn when_clause(input: &str) -> IResult<&str, WhenClause> {
-    let (input, _) = ws(tag_no_case("WHEN"))(input)?;
-    let (input, cond) = condition(input)?;
-    let (input, _) = ws(tag_no_case("THEN"))(input)?;
-    let (input, result) = ws(value)(input)?;
+// Parse FILTER (WHERE condition) after an aggregate (v2.7.0)
+fn aggregate_filter(input: &str) -> IResult<&str, Condition> {
+    preceded(
+        ws(keyword("FILTER")),
+        delimited(ws(char('(')), preceded(ws(keyword("WHERE")), condition), ws(char(')'))),
+    )(input)
+}
 
-    Ok((input, WhenClause { condition: cond, result }))
+// Parse WHEN clause: WHEN condition THEN expr (v2.7.0: WHEN value for simple CASE)
+fn when_clause(simple: bool) -> impl FnMut(&str) -> IResult<&str, WhenClause> {
+    move |input| {
+        let (input, _) = ws(keyword("WHEN"))(input)?;
+        let (input, condition) = if simple {
+            map(expression, WhenCondition::Value)(input)?
+        } else {
+            map(condition, WhenCondition::Condition)(input)?
+        };
+        let (input, _) = ws(keyword("THEN"))(input)?;
+        let (input, result) = expression(input)?;
+
+        Ok((input, WhenClause { condition, result }))
+    }
 }
 
-// Parse CASE expression: CASE WHEN ... THEN ... [WHEN ... THEN ...] [ELSE ...] END [AS alias]
+// Parse CASE expression: CASE [operand] WHEN ... THEN ... [WHEN ... THEN ...] [ELSE ...] END
 fn case_expression(input: &str) -> IResult<&str, CaseExpression> {
-    let (input, _) = ws(tag_no_case("CASE"))(input)?;
+    let (input, _) = ws(keyword("CASE"))(input)?;
+
+    // v2.7.0: simple CASE compares an operand with the WHEN values
+    let (input, operand) = opt(preceded(nom::combinator::not(ws(keyword("WHEN"))), expression))(input)?;
 
     // Parse one or more WHEN clauses
-    let (input, when_clauses) = nom::multi::many1(when_clause)(input)?;
+    let (input, when_clauses) = nom::multi::many1(when_clause(operand.is_some()))(input)?;
 
     // Parse optional ELSE clause
-    let (input, else_value) = opt(preceded(ws(tag_no_case("ELSE")), ws(value)))(input)?;
-
-    let (input, _) = ws(tag_no_case("END"))(input)?;
+    let (input, else_value) = opt(preceded(ws(keyword("ELSE")), expression))(input)?;
 
-    // Parse optional AS alias
-    let (input, alias) = opt(preceded(ws(tag_no_case("AS")), ws(identifier)))(input)?;
+    let (input, _) = ws(keyword("END"))(input)?;
 
     Ok((input, CaseExpression {
+        operand,
         when_clauses,
         else_value,
-        alias,
     }))
 }
 
-// Parse select column: either regular column/*, aggregate function, CASE expression, or literal
+// Parse function call: name(arg, ...), optionally `pg_catalog.name(...)` (v2.7.0)
+fn function_call(input: &str) -> IResult<&str, Expression> {
+    map(
+        tuple((
+            ws(preceded(opt(tag_no_case("pg_catalog.")), identifier)),
+            char('('),
+            separated_list0(ws(char(',')), expression),
+            ws(char(')')),
+        )),
+        |(name, _, args, _)| Expression::Function { name, args },
+    )(input)
+}
+
+// Parse expression operand: (expr), CASE, function call, literal or column (v2.7.0)
+fn expression_factor(input: &str) -> IResult<&str, Expression> {
+    alt((
+        delimited(ws(char('(')), expression, ws(char(')'))),
+        map(case_expression, |case| Expression::Case(Box::new(case))),
+        function_call,
+        // Literal must not be the prefix of an identifier (`nullable`, `true_flag`)
+        map(
+            ws(nom::sequence::terminated(
+                value,
+                nom::combinator::not(nom::character::complete::satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+            )),
+            Expression::Literal,
+        ),
+        map(ws(column_ref), Expression::Column),
+    ))(input)
+}
+
+// Fold `operand (op operand)*` left-to-right into binary expressions (v2.7.0)
+fn fold_binary(first: Expression, rest: Vec<(BinaryOperator, Expression)>) -> Expression {
+    rest.into_iter().fold(first, |left, (op, right)| Expression::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    })
+}
+
+// Parse * / % (v2.7.0)
+fn expression_term(input: &str) -> IResult<&str, Expression> {
+    let (input, first) = expression_factor(input)?;
+    let (input, rest) = many0(pair(
+        ws(alt((
+            map(char('*'), |_| BinaryOperator::Multiply),
+            map(char('/'), |_| BinaryOperator::Divide),
+            map(char('%'), |_| BinaryOperator::Modulo),
+        ))),
+        expression_factor,
+    ))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+// Parse scalar expression: + - || over terms (v2.7.0)
+pub fn expression(input: &str) -> IResult<&str, Expression> {
+    let (input, first) = expression_term(input)?;
+    let (input, rest) = many0(pair(
+        ws(alt((
+            map(tag("||"), |_| BinaryOperator::Concat),
+            map(char('+'), |_| BinaryOperator::Add),
+            map(char('-'), |_| BinaryOperator::Subtract),
+        ))),
+        expression_term,
+    ))(input)?;
+    Ok((input, fold_binary(first, rest)))
+}
+
+// Alias of a select column or subquery, `AS` being optional (v2.7.0)
+//
+// Without AS a reserved keyword ends the column instead, and so do LEFT and
+// RIGHT, which start a join after a subquery in FROM.
+fn alias(input: &str) -> IResult<&str, String> {
+    alt((
+        preceded(ws(keyword("AS")), ws(identifier)),
+        ws(verify(identifier, |name: &str| {
+            !name.eq_ignore_ascii_case("LEFT") && !name.eq_ignore_ascii_case("RIGHT")
+        })),
+    ))(input)
+}
+
+// Parse select column: either regular column/*, aggregate function, expression, or literal
 fn select_column(input: &str) -> IResult<&str, SelectColumn> {
     alt((
-        map(case_expression, SelectColumn::Case),
-        map(aggregate_function, SelectColumn::Aggregate),
+        // v2.7.0: agg FILTER (WHERE cond)
+        map(
+            pair(aggregate_function, opt(aggregate_filter)),
+            |(function, filter)| {
+                SelectColumn::Aggregate(match filter {
+                    Some(filter) => AggregateFunction::Filter { function: Box::new(function), filter: Box::new(filter) },
+                    None => function,
+                })
+            },
+        ),
         // Window function: ROW_NUMBER() OVER (...), etc. (v2.6.0)
         map(
             tuple((
                 window_function,
                 window_spec,
-                opt(preceded(ws(tag_no_case("AS")), ws(identifier))),
+                opt(alias),
             )),
             |(function, spec, alias)| SelectColumn::Window {
                 function,
@@ -371,34 +536,65 @@ fn select_column(input: &str) -> IResult<&str, SelectColumn> {
         map(
             tuple((
                 subquery,
-                opt(preceded(ws(tag_no_case("AS")), ws(identifier))),
+                opt(alias),
             )),
             |(query, alias)| SelectColumn::Subquery {
                 query,
                 alias,
             },
         ),
-        // Literal value: numbers, strings, booleans, NULL (v2.6.0)
-        map(ws(value), SelectColumn::Literal),
+        // Function call, arithmetic or CASE (v2.7.0) - bare columns/literals fall through
+        // unless they have an alias
         map(
-            alt((map(ws(char('*')), |_| "*".to_string()), identifier)),
-            SelectColumn::Regular,
+            alt((
+                pair(
+                    verify(expression, |expr: &Expression| !matches!(expr, Expression::Column(_))),
+                    map(alias, Some),
+                ),
+                pair(
+                    verify(expression, |expr: &Expression| {
+                        matches!(expr, Expression::Binary { .. } | Expression::Function { .. } | Expression::Case(_))
+                    }),
+                    opt(alias),
+                ),
+            )),
+            |(expr, alias)| SelectColumn::Expression { expr, alias },
         ),
+        // Literal value: numbers, strings, booleans, NULL (v2.6.0)
+        map(ws(value), SelectColumn::Literal),
+        map(ws(char('*')), |_| SelectColumn::Regular { name: "*".to_string(), alias: None }),
+        // Column reference, v2.7.0: with an optional alias
+        map(pair(identifier, opt(alias)), |(name, alias)| SelectColumn::Regular { name, alias }),
     ))(input)
 }
 
 // Parse JOIN clause: [INNER|LEFT|RIGHT] JOIN table ON left.col = right.col
 pub fn join_clause(input: &str) -> IResult<&str, JoinClause> {
-    let (input, join_type) = alt((
-        map(ws(tag_no_case("INNER JOIN")), |_| JoinType::Inner),
-        map(ws(tag_no_case("LEFT JOIN")), |_| JoinType::Left),
-        map(ws(tag_no_case("RIGHT JOIN")), |_| JoinType::Right),
-        map(ws(tag_no_case("JOIN")), |_| JoinType::Inner), // Default to INNER
+    // v2.7.0: CROSS JOIN and `FROM a, b` join every pair of rows
+    let (input, (join_type, cross)) = alt((
+        map(ws(tag_no_case("CROSS JOIN")), |_| (JoinType::Inner, true)),
+        map(ws(char(',')), |_| (JoinType::Inner, true)),
+        map(ws(tag_no_case("INNER JOIN")), |_| (JoinType::Inner, false)),
+        map(ws(tag_no_case("LEFT JOIN")), |_| (JoinType::Left, false)),
+        map(ws(tag_no_case("RIGHT JOIN")), |_| (JoinType::Right, false)),
+        map(ws(tag_no_case("JOIN")), |_| (JoinType::Inner, false)), // Default to INNER
     ))(input)?;
 
-    let (input, table) = ws(identifier)(input)?;
+    // v2.7.0: a subquery is kept as source text, like a function call in FROM
+    let (input, table) = alt((
+        map(ws(recognize(derived_table)), |text: &str| text.trim().to_string()),
+        ws(identifier),
+    ))(input)?;
+    if cross {
+        return Ok((input, JoinClause { join_type, table, on_left: String::new(), on_right: String::new() }));
+    }
     let (input, _) = ws(tag_no_case("ON"))(input)?;
 
+    // v2.7.0: ON TRUE, typically for LATERAL subqueries
+    if let Ok((input, _)) = ws(keyword("TRUE"))(input) {
+        return Ok((input, JoinClause { join_type, table, on_left: String::new(), on_right: String::new() }));
+    }
+
     // Parse left_table.column
     let (input, on_left) = recognize(tuple((
         ws(identifier),
@@ -426,17 +622,50 @@ pub fn join_clause(input: &str) -> IResult<&str, JoinClause> {
     ))
 }
 
+// Subquery in FROM: [LATERAL] (SELECT ...) [AS] alias [(col, ...)] (v2.7.0)
+pub fn derived_table(input: &str) -> IResult<&str, DerivedTable> {
+    let (input, lateral) = opt(ws(keyword("LATERAL")))(input)?;
+    let (input, query) = subquery(input)?;
+    // The alias is required, so a clause keyword after the subquery is an error
+    let (input, alias) = alias(input)?;
+    let (input, columns) = opt(delimited(
+        ws(char('(')),
+        separated_list1(ws(char(',')), ws(identifier)),
+        ws(char(')')),
+    ))(input)?;
+
+    Ok((
+        input,
+        DerivedTable {
+            query,
+            alias,
+            columns: columns.unwrap_or_default(),
+            lateral: lateral.is_some(),
+        },
+    ))
+}
+
 // Parse optional WHERE clause
 pub fn where_clause(input: &str) -> IResult<&str, Option<Condition>> {
     opt(preceded(ws(tag_no_case("WHERE")), condition))(input)
 }
 
 // Parse optional ORDER BY clause
-pub fn order_by(input: &str) -> IResult<&str, Option<(String, SortOrder)>> {
+pub fn order_by(input: &str) -> IResult<&str, Option<(Expression, SortOrder)>> {
     let result = opt(preceded(
         ws(tag_no_case("ORDER BY")),
         tuple((
-            ws(identifier),
+            // v2.7.0: a column, or an expression such as CASE
+            alt((
+                verify(expression, |expr: &Expression| {
+                    matches!(expr, Expression::Binary { .. } | Expression::Function { .. } | Expression::Case(_))
+                }),
+                // v2.7.0: an output column position, resolved by the executor
+                map(ws(digit1), |digits: &str| {
+                    Expression::Literal(crate::types::Value::Integer(digits.parse().unwrap_or(i64::MAX)))
+                }),
+                map(ws(identifier), Expression::Column),
+            )),
             opt(alt((
                 map(ws(tag_no_case("ASC")), |_| SortOrder::Asc),
                 map(ws(tag_no_case("DESC")), |_| SortOrder::Desc),
@@ -485,8 +714,18 @@ fn select_base(input: &str) -> IResult<&str, Statement> {
     let distinct = distinct.is_some();
 
     let (input, columns) = separated_list1(ws(char(',')), select_column)(input)?;
-    let (input, _) = ws(tag_no_case("FROM"))(input)?;
-    let (input, from) = ws(identifier)(input)?;
+
+    // v2.7.0: FROM is optional (`SELECT 1 + 2`), and may be a set-returning
+    // function call or a subquery, kept as its source text (`FROM gen(3)`)
+    let (input, from) = opt(preceded(
+        ws(tag_no_case("FROM")),
+        alt((
+            map(ws(recognize(derived_table)), |text: &str| text.trim().to_string()),
+            map(ws(recognize(function_call)), |call: &str| call.trim().to_string()),
+            ws(identifier),
+        )),
+    ))(input)?;
+    let from = from.unwrap_or_default();
 
     // Parse optional JOIN clauses
     let (input, joins) = nom::multi::many0(join_clause)(input)?;
@@ -496,15 +735,8 @@ fn select_base(input: &str) -> IResult<&str, Statement> {
     // Parse optional GROUP BY clause
     let (input, group_by) = group_by(input)?;
 
-    // Parse optional ORDER BY clause
-    let (input, order_by) = order_by(input)?;
-
-    // Parse optional LIMIT clause
-    let (input, limit) = limit(input)?;
-
-    // Parse optional OFFSET clause
-    let (input, offset) = offset(input)?;
-
+    // v2.7.0: ORDER BY / LIMIT / OFFSET are parsed by `select`, so that after
+    // a set operation they apply to the combined result
     Ok((
         input,
         Statement::Select {
@@ -514,64 +746,77 @@ fn select_base(input: &str) -> IResult<&str, Statement> {
             joins,
             filter,
             group_by,
-            order_by,
-            limit,
-            offset,
+            order_by: None,
+            limit: None,
+            offset: None,
         },
     ))
 }
 
+// Trailing ORDER BY / LIMIT / OFFSET (v2.7.0)
+type SortAndPage = (Option<(Expression, SortOrder)>, Option<usize>, Option<usize>);
+
+fn sort_and_page(input: &str) -> IResult<&str, SortAndPage> {
+    tuple((order_by, limit, offset))(input)
+}
+
+// One arm of a set operation; in parentheses it may have its own ORDER BY / LIMIT (v2.7.0)
+fn select_arm(input: &str) -> IResult<&str, Statement> {
+    alt((delimited(ws(char('(')), select, ws(char(')'))), select_base))(input)
+}
+
+// Arms joined by INTERSECT, which binds tighter than UNION and EXCEPT (v2.7.0)
+fn intersect_chain(input: &str) -> IResult<&str, Statement> {
+    let (mut input, mut result) = select_arm(input)?;
+    while let Ok((rest, right)) = preceded(ws(keyword("INTERSECT")), select_arm)(input) {
+        result = Statement::Intersect {
+            left: Box::new(result),
+            right: Box::new(right),
+            order_by: None,
+            limit: None,
+            offset: None,
+        };
+        input = rest;
+    }
+    Ok((input, result))
+}
+
 // Parse SELECT with set operations (UNION/INTERSECT/EXCEPT) (v1.10.0)
+//
+// v2.7.0: Set operations are left-associative (`a EXCEPT b UNION c` is
+// `(a EXCEPT b) UNION c`), and a trailing ORDER BY / LIMIT / OFFSET belongs to
+// the whole query rather than to the last arm.
 pub fn select(input: &str) -> IResult<&str, Statement> {
-    let (input, left) = select_base(input)?;
-
-    // Check for set operations
-    let (input, set_op) = opt(alt((
-        map(
-            tuple((
-                ws(tag_no_case("UNION")),
-                opt(ws(tag_no_case("ALL"))),
-            )),
-            |(_, all)| ("UNION", all.is_some()),
-        ),
-        map(ws(tag_no_case("INTERSECT")), |_| ("INTERSECT", false)),
-        map(ws(tag_no_case("EXCEPT")), |_| ("EXCEPT", false)),
-    )))(input)?;
+    let (mut input, mut result) = intersect_chain(input)?;
+    loop {
+        // Some(all) for UNION [ALL], None for EXCEPT
+        let (rest, set_op) = opt(alt((
+            map(preceded(ws(keyword("UNION")), opt(ws(keyword("ALL")))), |all| Some(all.is_some())),
+            map(ws(keyword("EXCEPT")), |_| None),
+        )))(input)?;
+        let Some(set_op) = set_op else { break };
+
+        let (rest, right) = intersect_chain(rest)?;
+        let (left, right) = (Box::new(result), Box::new(right));
+        result = match set_op {
+            Some(all) => Statement::Union { left, right, all, order_by: None, limit: None, offset: None },
+            None => Statement::Except { left, right, order_by: None, limit: None, offset: None },
+        };
+        input = rest;
+    }
 
-    match set_op {
-        Some(("UNION", all)) => {
-            let (input, right) = select(input)?;
-            Ok((
-                input,
-                Statement::Union {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    all,
-                },
-            ))
-        }
-        Some(("INTERSECT", _)) => {
-            let (input, right) = select(input)?;
-            Ok((
-                input,
-                Statement::Intersect {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                },
-            ))
-        }
-        Some(("EXCEPT", _)) => {
-            let (input, right) = select(input)?;
-            Ok((
-                input,
-                Statement::Except {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                },
-            ))
-        }
-        _ => Ok((input, left)),
+    let (input, (sort, page_limit, page_offset)) = sort_and_page(input)?;
+    if let Statement::Select { order_by, limit, offset, .. }
+    | Statement::Union { order_by, limit, offset, .. }
+    | Statement::Intersect { order_by, limit, offset, .. }
+    | Statement::Except { order_by, limit, offset, .. } = &mut result
+    {
+        // A parenthesized query keeps its own clauses unless they are given again
+        *order_by = sort.or_else(|| order_by.take());
+        *limit = page_limit.or(*limit);
+        *offset = page_offset.or(*offset);
     }
+    Ok((input, result))
 }
 
 #[cfg(test)]
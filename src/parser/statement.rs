@@ -1,4 +1,4 @@
-use crate::types::DataType;
+use crate::types::{DataType, UserOptions};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
@@ -6,9 +6,34 @@ pub enum Statement {
         name: String,
         columns: Vec<ColumnDef>,
         owner: Option<String>,  // v2.3.0: Table owner
+        partition_by: Option<crate::types::Partitioning>,  // v2.7.0: PARTITION BY RANGE|LIST (col)
+        access_method: Option<String>,  // v2.7.0: USING heap|columnar|lsm
+    },
+    /// v2.7.0: CREATE TABLE name PARTITION OF parent FOR VALUES ... | DEFAULT
+    CreatePartition {
+        name: String,
+        parent: String,
+        bound: crate::types::PartitionBound,
+        owner: Option<String>,
+    },
+    /// v2.7.0: CREATE TEMP TABLE (session-local, in-memory, no WAL)
+    CreateTempTable {
+        name: String,
+        columns: Vec<ColumnDef>,
+        owner: Option<String>,
+        on_commit: OnCommitAction,
+    },
+    /// v2.7.0: CREATE FOREIGN TABLE name (...) SERVER server OPTIONS (key 'value', ...)
+    CreateForeignTable {
+        name: String,
+        columns: Vec<ColumnDef>,
+        server: String,
+        options: Vec<(String, String)>,
+        owner: Option<String>,
     },
     DropTable {
         name: String,
+        cascade: bool, // v2.7.0: CASCADE (RESTRICT otherwise)
     },
     AlterTable {
         name: String,
@@ -19,6 +44,12 @@ pub enum Statement {
         columns: Option<Vec<String>>,
         values: Vec<crate::types::Value>,
     },
+    /// INSERT of several rows: multi-row VALUES or INSERT ... SELECT (v2.7.0)
+    InsertMany {
+        table: String,
+        columns: Option<Vec<String>>,
+        source: InsertSource,
+    },
     Select {
         distinct: bool,
         columns: Vec<SelectColumn>,
@@ -26,49 +57,72 @@ pub enum Statement {
         joins: Vec<JoinClause>,
         filter: Option<Condition>,
         group_by: Option<Vec<String>>,
-        order_by: Option<(String, SortOrder)>,
+        order_by: Option<(Expression, SortOrder)>,
         limit: Option<usize>,
         offset: Option<usize>,
     },
     /// Set operations (v1.10.0)
+    ///
+    /// v2.7.0: ORDER BY / LIMIT / OFFSET after the last arm apply to the
+    /// combined result; they are kept on the outermost set operation.
     Union {
         left: Box<Statement>,
         right: Box<Statement>,
         all: bool,  // UNION ALL if true, UNION (DISTINCT) if false
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
     },
     Intersect {
         left: Box<Statement>,
         right: Box<Statement>,
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
     },
     Except {
         left: Box<Statement>,
         right: Box<Statement>,
+        order_by: Option<(Expression, SortOrder)>,
+        limit: Option<usize>,
+        offset: Option<usize>,
     },
     Update {
         table: String,
-        assignments: Vec<(String, crate::types::Value)>,
+        /// v2.7.0: `SET col = expr`, evaluated against the old row version
+        assignments: Vec<(String, Expression)>,
+        /// v2.7.0: `FROM t1, t2` - tables joined to the target by the WHERE clause
+        from: Vec<String>,
         filter: Option<Condition>,
     },
     Delete {
         from: String,
+        /// v2.7.0: `USING t1, t2` - tables joined to the target by the WHERE clause
+        using: Vec<String>,
         filter: Option<Condition>,
     },
     Begin,
     Commit,
     Rollback,
     ShowTables,
+    // v2.7.0: \d table / \d+ table (psql-style); verbose adds descriptions
+    DescribeTable {
+        name: String,
+        verbose: bool,
+    },
     // User management
     CreateUser {
         username: String,
         password: String,
-        is_superuser: bool,
+        options: UserOptions, // v2.7.0: SUPERUSER, CREATEDB, LOGIN, VALID UNTIL...
     },
     DropUser {
         username: String,
     },
     AlterUser {
         username: String,
-        password: String,
+        password: Option<String>, // v2.7.0: ALTER USER may change only attributes
+        options: UserOptions,
     },
     // Role management
     CreateRole {
@@ -90,9 +144,25 @@ pub enum Statement {
     CreateDatabase {
         name: String,
         owner: Option<String>,
+        /// v2.7.0: Database to copy, `template1` when not given
+        template: Option<String>,
+        /// v2.7.0: `IS_TEMPLATE`
+        is_template: bool,
     },
     DropDatabase {
         name: String,
+        /// v2.7.0: WITH (FORCE) - terminate connected sessions
+        force: bool,
+    },
+    // v2.7.0: ALTER DATABASE name OWNER TO user
+    AlterDatabaseOwner {
+        name: String,
+        new_owner: String,
+    },
+    // v2.7.0: ALTER DATABASE name IS_TEMPLATE true|false
+    AlterDatabaseTemplate {
+        name: String,
+        is_template: bool,
     },
     // Privileges
     Grant {
@@ -108,11 +178,22 @@ pub enum Statement {
     // Metadata queries
     ShowUsers,
     ShowDatabases,
+    // v2.7.0: \c / USE - switch the text protocol session to another database
+    Connect {
+        database: String,
+    },
     // Enum types
     CreateType {
         name: String,
         values: Vec<String>,
     },
+    // v2.7.0: ALTER TYPE name ADD VALUE [IF NOT EXISTS] 'v' [BEFORE | AFTER 'x']
+    AlterTypeAddValue {
+        name: String,
+        value: String,
+        if_not_exists: bool,
+        position: Option<EnumValuePosition>,
+    },
     // Indexes
     CreateIndex {
         name: String,
@@ -128,6 +209,66 @@ pub enum Statement {
     Vacuum {
         table: Option<String>, // None = all tables
     },
+    // v2.7.0: Rewrite a table in index order
+    Cluster {
+        table: Option<String>, // None = every table clustered before
+        index: Option<String>, // None = the index the table was clustered on
+    },
+    // v2.7.0: SELECT pg_undo_transaction(xid) - undo a committed transaction
+    UndoTransaction {
+        xid: u64,
+    },
+    // v2.7.0: SELECT pg_export_snapshot() - share the transaction's snapshot
+    ExportSnapshot,
+    // v2.7.0: SET TRANSACTION SNAPSHOT 'id' - read through an exported snapshot
+    SetTransactionSnapshot {
+        id: String,
+    },
+    // v2.7.0: Planner statistics
+    Analyze {
+        table: Option<String>, // None = all tables
+    },
+    // v2.7.0: SET [SESSION | LOCAL] name = value
+    Set {
+        name: String,   // lowercased, setting names are case-insensitive
+        value: String,  // list values joined with ", "
+    },
+    // v2.7.0: SHOW name | SHOW TIME ZONE - current value of a run-time parameter
+    Show {
+        name: String,   // lowercased
+    },
+    // v2.7.0: ALTER SYSTEM SET name = value, persisted for the next start
+    AlterSystemSet {
+        name: String,   // lowercased
+        value: String,  // DEFAULT removes the setting
+    },
+    // v2.7.0: ALTER SYSTEM RESET name | ALL
+    AlterSystemReset {
+        name: Option<String>, // None = ALL
+    },
+    // v2.7.0: COMMENT ON object IS 'text' | NULL
+    CommentOn {
+        object: ObjectRef,
+        comment: Option<String>, // None = remove the comment
+    },
+    // v2.7.0: ALTER VIEW | TYPE | SCHEMA name OWNER TO role
+    AlterOwner {
+        object: ObjectRef,
+        owner: String,
+    },
+    // v2.7.0: Cursors, open until the end of their transaction
+    DeclareCursor {
+        name: String,
+        query: Box<Self>, // a SELECT
+    },
+    Fetch {
+        cursor: String,
+        count: Option<usize>, // None = ALL
+        move_only: bool,      // MOVE: advance without returning rows
+    },
+    CloseCursor {
+        cursor: Option<String>, // None = ALL
+    },
     // Query analysis (v1.8.0)
     Explain {
         statement: Box<Statement>,
@@ -139,6 +280,20 @@ pub enum Statement {
     },
     DropView {
         name: String,
+        cascade: bool, // v2.7.0: CASCADE (RESTRICT otherwise)
+    },
+    // Stored functions (v2.7.0)
+    CreateFunction {
+        name: String,
+        params: Vec<(String, DataType)>,  // (parameter name, type)
+        returns: DataType,
+        returns_set: bool,  // RETURNS SETOF type
+        body: String,       // SQL body as string (like views)
+        or_replace: bool,
+    },
+    DropFunction {
+        name: String,
+        if_exists: bool,
     },
     // COPY protocol (v2.4.0)
     Copy {
@@ -149,9 +304,45 @@ pub enum Statement {
     },
 }
 
+/// Rows of a multi-row INSERT (v2.7.0)
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertSource {
+    Values(Vec<Vec<crate::types::Value>>),
+    Query(Box<Statement>),
+}
+
+/// What happens to a temporary table at the end of each transaction (v2.7.0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnCommitAction {
+    #[default]
+    PreserveRows,
+    DeleteRows,
+    Drop,
+}
+
+/// Where `ALTER TYPE ... ADD VALUE` places the new label (v2.7.0)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumValuePosition {
+    Before(String),
+    After(String),
+}
+
+/// Object named by COMMENT ON or ALTER ... OWNER TO (v2.7.0)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectRef {
+    Table(String),
+    Column { table: String, column: String },
+    View(String),
+    Index(String),
+    Type(String),
+    Database(String),
+    Schema(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CopyFormat {
-    Text,   // CSV/TSV
+    Text,   // v2.7.0: PostgreSQL text format: tab-separated, \N for NULL
+    Csv,
     Binary,
 }
 
@@ -174,15 +365,27 @@ pub struct ColumnDef {
     pub primary_key: bool,
     pub unique: bool,
     pub foreign_key: Option<crate::types::ForeignKey>,
+    /// v2.7.0: GENERATED ALWAYS AS (expr) STORED - expression source text
+    pub generated: Option<String>,
+    /// v2.7.0: COLLATE "name" - resolved when the column is created
+    pub collation: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AlterTableOperation {
     AddColumn(ColumnDef),
-    DropColumn(String),
+    DropColumn { name: String, cascade: bool },  // v2.7.0: CASCADE / RESTRICT
     RenameColumn { old_name: String, new_name: String },
     RenameTable(String),
     OwnerTo(String),  // v2.3.0: ALTER TABLE ... OWNER TO new_owner
+    /// v2.7.0: ADD CONSTRAINT name CHECK | UNIQUE | FOREIGN KEY ... [NOT VALID]
+    AddConstraint { name: String, kind: crate::types::ConstraintKind, not_valid: bool },
+    /// v2.7.0: VALIDATE CONSTRAINT name - checks the rows a NOT VALID constraint skipped
+    ValidateConstraint(String),
+    /// v2.7.0: DROP CONSTRAINT [IF EXISTS] name
+    DropConstraint { name: String, if_exists: bool },
+    SetNotNull(String),   // v2.7.0: ALTER COLUMN col SET NOT NULL
+    DropNotNull(String),  // v2.7.0: ALTER COLUMN col DROP NOT NULL
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -195,11 +398,15 @@ pub enum Condition {
     LessThanOrEqual(String, crate::types::Value),     // v1.8.0
     Between(String, crate::types::Value, crate::types::Value), // v1.8.0: col BETWEEN a AND b
     Like(String, String),                              // v1.8.0: col LIKE pattern
+    ILike(String, String),                             // v2.7.0: col ILIKE pattern
     In(String, Vec<crate::types::Value>),             // v1.8.0: col IN (list)
     IsNull(String),                                    // v1.8.0: col IS NULL
     IsNotNull(String),                                 // v1.8.0: col IS NOT NULL
     And(Box<Condition>, Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
+    Not(Box<Self>),                                    // v2.7.0: NOT cond
+    ColumnEquals(String, String),                      // v2.7.0: col = other_col
+    Compare(Expression, CompareOperator, Expression),  // v2.7.0: expr op expr, e.g. CASE ... END = 'x'
 
     // v2.6.0: Subquery conditions
     InSubquery(String, Box<Statement>),                // col IN (SELECT ...)
@@ -211,41 +418,41 @@ pub enum Condition {
     LessThanSubquery(String, Box<Statement>),          // col < (SELECT ...)
 }
 
-/// v2.6.0: Source for FROM clause - table name or subquery
-#[derive(Debug, Clone, PartialEq)]
-pub enum SelectSource {
-    Table(String),              // Regular table name
-    Subquery {                  // Derived table (FROM subquery)
-        query: Box<Statement>,
-        alias: String,          // Required for subqueries
-    },
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SortOrder {
     Asc,
     Desc,
 }
 
-/// CASE expression (v1.10.0)
+/// CASE expression (v1.10.0; v2.7.0: an `Expression`, usable wherever a value is)
 #[derive(Debug, Clone, PartialEq)]
 pub struct CaseExpression {
+    /// v2.7.0: simple CASE - `CASE x WHEN 1 THEN ...` compares `x` with each WHEN value
+    pub operand: Option<Expression>,
     pub when_clauses: Vec<WhenClause>,      // WHEN conditions
-    pub else_value: Option<crate::types::Value>, // ELSE value (optional)
-    pub alias: Option<String>,               // AS alias (optional)
+    pub else_value: Option<Expression>,     // ELSE value (optional, NULL otherwise)
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct WhenClause {
-    pub condition: Condition,                // WHEN condition
-    pub result: crate::types::Value,         // THEN result
+    pub condition: WhenCondition,            // WHEN condition
+    pub result: Expression,                  // THEN result
+}
+
+/// What a WHEN branch tests (v2.7.0)
+#[derive(Debug, Clone, PartialEq)]
+pub enum WhenCondition {
+    Condition(Condition),  // searched CASE: WHEN a > 1
+    Value(Expression),     // simple CASE: WHEN 1, equal to the operand
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SelectColumn {
-    Regular(String),              // Regular column name or *
+    Regular {                     // Regular column name or *
+        name: String,
+        alias: Option<String>,    // v2.7.0: `col AS alias`
+    },
     Aggregate(AggregateFunction), // Aggregate function
-    Case(CaseExpression),         // CASE expression (v1.10.0)
     Literal(crate::types::Value), // Literal value (v2.6.0: for SELECT 1, SELECT 'text', etc.)
     Subquery {                    // v2.6.0: Scalar subquery in SELECT list
         query: Box<Statement>,
@@ -256,15 +463,65 @@ pub enum SelectColumn {
         spec: WindowSpec,
         alias: Option<String>,
     },
+    Expression {                  // v2.7.0: Arithmetic / function call
+        expr: Expression,
+        alias: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Scalar expression (v2.7.0)
+///
+/// Used for function calls, arithmetic and CASE in the SELECT list, UPDATE ... SET,
+/// ORDER BY and comparisons in WHERE, and in SQL function bodies.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(crate::types::Value),
+    Column(String),
+    Binary {
+        left: Box<Self>,
+        op: BinaryOperator,
+        right: Box<Self>,
+    },
+    Function {
+        name: String,
+        args: Vec<Self>,
+    },
+    Case(Box<CaseExpression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,       // +
+    Subtract,  // -
+    Multiply,  // *
+    Divide,    // /
+    Modulo,    // %
+    Concat,    // ||
+}
+
+/// Comparison between two expressions in WHERE (v2.7.0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOperator {
+    Equal,         // =
+    NotEqual,      // != or <>
+    Less,          // <
+    LessOrEqual,   // <=
+    Greater,       // >
+    GreaterOrEqual, // >=
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AggregateFunction {
     Count(CountTarget),
     Sum(String),
     Avg(String),
     Min(String),
     Max(String),
+    /// v2.7.0: agg FILTER (WHERE cond) - only rows satisfying `filter` are aggregated
+    Filter {
+        function: Box<Self>,
+        filter: Box<Condition>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -302,6 +559,22 @@ pub struct JoinClause {
     pub table: String,
     pub on_left: String,  // left_table.column
     pub on_right: String, // right_table.column
+    // v2.7.0: both empty for CROSS JOIN, `FROM a, b` and `ON TRUE`
+}
+
+/// Subquery in FROM or JOIN (v2.7.0)
+///
+/// Like a function call in FROM, it is kept as source text in
+/// `Statement::Select::from` / `JoinClause::table` and parsed again with
+/// `parse_derived_table` when the query runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedTable {
+    pub query: Box<Statement>,
+    pub alias: String,
+    /// Column names after the alias, replacing the ones of the query
+    pub columns: Vec<String>,
+    /// LATERAL: the query may refer to columns of the preceding FROM items
+    pub lateral: bool,
 }
 
 /// v2.3.0: Object type for GRANT/REVOKE
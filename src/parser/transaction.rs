@@ -1,10 +1,13 @@
-use super::common::ws;
+use super::common::{identifier, quoted_string, ws};
+use super::queries::{keyword, select};
 use super::statement::Statement;
 use nom::{
     branch::alt,
-    bytes::complete::tag_no_case,
-    IResult,
+    character::complete::digit1,
+    combinator::{map, map_res, opt, value},
+    sequence::{pair, preceded},
 };
+use super::error::{IResult, tag_no_case};
 
 pub fn begin_transaction(input: &str) -> IResult<&str, Statement> {
     let (input, _) = ws(alt((
@@ -30,3 +33,71 @@ pub fn rollback_transaction(input: &str) -> IResult<&str, Statement> {
     )))(input)?;
     Ok((input, Statement::Rollback))
 }
+
+/// v2.7.0: SELECT `pg_undo_transaction(xid)`
+///
+/// Parsed as a statement of its own: unlike other functions it writes.
+pub fn undo_transaction(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(keyword("SELECT"))(input)?;
+    let (input, _) = ws(tag_no_case("pg_undo_transaction"))(input)?;
+    let (input, _) = ws(tag_no_case("("))(input)?;
+    let (input, xid) = ws(map_res(digit1, str::parse::<u64>))(input)?;
+    let (input, _) = ws(tag_no_case(")"))(input)?;
+    Ok((input, Statement::UndoTransaction { xid }))
+}
+
+/// v2.7.0: SELECT `pg_export_snapshot()`
+pub fn export_snapshot(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(keyword("SELECT"))(input)?;
+    let (input, _) = ws(tag_no_case("pg_export_snapshot"))(input)?;
+    let (input, _) = ws(tag_no_case("("))(input)?;
+    let (input, _) = ws(tag_no_case(")"))(input)?;
+    Ok((input, Statement::ExportSnapshot))
+}
+
+/// v2.7.0: SET TRANSACTION SNAPSHOT 'id'
+pub fn set_transaction_snapshot(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(keyword("SET"))(input)?;
+    let (input, _) = ws(keyword("TRANSACTION"))(input)?;
+    let (input, _) = ws(keyword("SNAPSHOT"))(input)?;
+    let (input, id) = ws(quoted_string)(input)?;
+    Ok((input, Statement::SetTransactionSnapshot { id }))
+}
+
+/// v2.7.0: DECLARE name [BINARY] [NO SCROLL] CURSOR [WITHOUT HOLD] FOR SELECT ...
+pub fn declare_cursor(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(keyword("DECLARE"))(input)?;
+    let (input, name) = ws(identifier)(input)?;
+    let (input, _) = opt(ws(keyword("BINARY")))(input)?;
+    let (input, _) = opt(pair(ws(keyword("NO")), ws(keyword("SCROLL"))))(input)?;
+    let (input, _) = ws(keyword("CURSOR"))(input)?;
+    let (input, _) = opt(pair(ws(keyword("WITHOUT")), ws(keyword("HOLD"))))(input)?;
+    let (input, _) = ws(keyword("FOR"))(input)?;
+    let (input, query) = select(input)?;
+    Ok((input, Statement::DeclareCursor { name, query: Box::new(query) }))
+}
+
+/// v2.7.0: FETCH | MOVE [NEXT | FORWARD [n | ALL] | n | ALL] [FROM | IN] cursor
+pub fn fetch(input: &str) -> IResult<&str, Statement> {
+    let count = || ws(map_res(digit1, |n: &str| n.parse::<usize>().map(Some)));
+    let (input, move_only) = ws(alt((value(false, keyword("FETCH")), value(true, keyword("MOVE")))))(input)?;
+    let (input, count) = opt(alt((
+        value(Some(1), ws(keyword("NEXT"))),
+        preceded(
+            ws(keyword("FORWARD")),
+            map(opt(alt((count(), value(None, ws(keyword("ALL")))))), |count| count.unwrap_or(Some(1))),
+        ),
+        value(None, ws(keyword("ALL"))),
+        count(),
+    )))(input)?;
+    let (input, _) = opt(ws(alt((keyword("FROM"), keyword("IN")))))(input)?;
+    let (input, cursor) = ws(identifier)(input)?;
+    Ok((input, Statement::Fetch { cursor, count: count.unwrap_or(Some(1)), move_only }))
+}
+
+/// v2.7.0: CLOSE cursor | ALL
+pub fn close_cursor(input: &str) -> IResult<&str, Statement> {
+    let (input, _) = ws(keyword("CLOSE"))(input)?;
+    let (input, cursor) = alt((value(None, ws(keyword("ALL"))), map(ws(identifier), Some)))(input)?;
+    Ok((input, Statement::CloseCursor { cursor }))
+}
@@ -0,0 +1,46 @@
+/// Storage backend trait for non-heap tables (v2.7.0)
+///
+/// Heap tables keep their rows in pages managed by `PageManager`. Tables
+/// created with `USING columnar` or `USING lsm` hand their rows to a
+/// `StorageBackend` instead; `PagedTable` forwards every operation to it, so
+/// the executor does not care which layout a table uses. Foreign tables plug
+/// in the same way, reading their rows from an external file on every scan.
+use crate::types::{AccessMethod, DatabaseError, Row};
+
+pub trait StorageBackend: Send + Sync + std::fmt::Debug {
+    /// Access method this backend implements
+    fn access_method(&self) -> AccessMethod;
+
+    fn insert(&mut self, row: Row);
+
+    /// Stored row versions, dead ones included
+    fn row_count(&self) -> usize;
+
+    /// Number of chunks a scan walks through
+    fn chunk_count(&self) -> usize;
+
+    /// Rows of chunk `idx` (see `chunk_count`)
+    ///
+    /// Backends may skip decoding columns outside `columns`; those read as NULL.
+    /// Only backends reading external data can fail.
+    fn chunk_rows(&self, idx: usize, columns: Option<&[usize]>) -> Result<Vec<Row>, DatabaseError>;
+
+    /// Set xmax on every row matching `predicate` (MVCC delete)
+    fn delete_where(&mut self, predicate: &dyn Fn(&Row) -> bool, tx_id: u64) -> usize;
+
+    /// Mark matching rows deleted and append their new versions
+    fn update_where(&mut self, predicate: &dyn Fn(&Row) -> bool, updater: &dyn Fn(&Row) -> Row, tx_id: u64) -> usize;
+
+    fn truncate(&mut self);
+
+    /// Physically remove rows deleted before `oldest_tx`; returns how many
+    fn vacuum(&mut self, oldest_tx: u64) -> usize;
+
+    fn get_all_rows(&self) -> Result<Vec<Row>, DatabaseError> {
+        let mut rows = Vec::new();
+        for idx in 0..self.chunk_count() {
+            rows.extend(self.chunk_rows(idx, None)?);
+        }
+        Ok(rows)
+    }
+}
@@ -1,7 +1,12 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+use std::sync::{Mutex, MutexGuard};
 use super::page::{Page, PageId};
 use crate::types::DatabaseError;
 
+/// v2.7.0: Most shards a buffer pool is split into
+pub const BUFFER_POOL_SHARDS: usize = 16;
+
 /// Simple LRU cache for page eviction
 struct LruCache {
     /// Queue of page IDs (most recently used at back)
@@ -187,6 +192,13 @@ impl BufferPool {
         self.pages.remove(&page_id)
     }
 
+    /// v2.7.0: Forget every page of a dropped table, dirty or not
+    pub fn remove_table(&mut self, table_id: u32) {
+        self.pages.retain(|page_id, _| page_id.table_id != table_id);
+        self.dirty_pages.retain(|page_id| page_id.table_id != table_id);
+        self.lru.queue.retain(|page_id| page_id.table_id != table_id);
+    }
+
     /// Flush all dirty pages (returns them for writing)
     pub fn flush_all(&mut self) -> Vec<(PageId, Page)> {
         let dirty_ids: Vec<_> = self.dirty_pages.iter().copied().collect();
@@ -202,6 +214,50 @@ impl BufferPool {
     }
 }
 
+/// v2.7.0: Buffer pool split into shards by page-id hash
+///
+/// Every shard is an independent `BufferPool` (own LRU, own dirty set) behind
+/// its own mutex, so threads working on different pages - scans of different
+/// tables in particular - rarely wait for each other. A page always lives in
+/// the same shard.
+pub struct ShardedBufferPool {
+    shards: Vec<Mutex<BufferPool>>,
+}
+
+impl ShardedBufferPool {
+    /// `capacity` pages in total, spread over up to `BUFFER_POOL_SHARDS` shards
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        let shard_count = BUFFER_POOL_SHARDS.min(capacity.max(1));
+        let shard_capacity = capacity.div_ceil(shard_count).max(1);
+        Self {
+            shards: (0..shard_count).map(|_| Mutex::new(BufferPool::new(shard_capacity))).collect(),
+        }
+    }
+
+    #[must_use]
+    pub const fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Index of the shard holding `page_id`
+    #[must_use]
+    pub fn shard_index(&self, page_id: PageId) -> usize {
+        let hash = BuildHasherDefault::<DefaultHasher>::default().hash_one(page_id);
+        (hash % self.shards.len() as u64) as usize
+    }
+
+    /// Lock the shard holding `page_id`
+    pub fn shard(&self, page_id: PageId) -> MutexGuard<'_, BufferPool> {
+        self.shards[self.shard_index(page_id)].lock().unwrap()
+    }
+
+    /// Lock every shard, one after the other
+    pub fn each_shard(&self) -> impl Iterator<Item = MutexGuard<'_, BufferPool>> {
+        self.shards.iter().map(|shard| shard.lock().unwrap())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -335,4 +391,23 @@ mod tests {
         let dirty_pages = pool.flush_all();
         assert_eq!(dirty_pages.len(), 3);
     }
+
+    #[test]
+    fn test_sharded_pool() {
+        let pool = ShardedBufferPool::new(64);
+        assert_eq!(pool.shard_count(), BUFFER_POOL_SHARDS);
+        assert_eq!(ShardedBufferPool::new(3).shard_count(), 3);
+
+        for i in 0..32 {
+            let page = create_test_page(1, i);
+            pool.shard(page.header.page_id).insert_page(page).unwrap();
+        }
+
+        // Pages of one table are spread over the shards and found again
+        let used = pool.each_shard().filter(|shard| shard.size() > 0).count();
+        assert!(used > 1);
+        assert_eq!(pool.each_shard().map(|shard| shard.size()).sum::<usize>(), 32);
+        let page_id = PageId::new(1, 7);
+        assert!(pool.shard(page_id).get_page(page_id).is_some());
+    }
 }
@@ -0,0 +1,501 @@
+/// Column-wise row store for `CREATE TABLE ... USING columnar` (v2.7.0)
+///
+/// New rows go to an uncompressed tail. Once the tail holds `STRIPE_ROWS`
+/// rows it is sealed into a stripe: every column is encoded on its own with
+/// whichever of plain, run-length, dictionary or delta encoding is smallest.
+/// MVCC headers (xmin / xmax) stay uncompressed next to the column chunks, so
+/// DELETE and UPDATE only touch the headers of a stripe.
+///
+/// Scans decode only the columns they ask for, which makes aggregates over a
+/// few columns of a wide table much cheaper than reading whole tuples.
+/// Stripes are kept in memory, like the pages of temporary tables.
+use serde::{Deserialize, Serialize};
+use crate::types::{AccessMethod, DatabaseError, Row, Value};
+use super::backend::StorageBackend;
+
+/// Rows per stripe
+pub const STRIPE_ROWS: usize = 1024;
+
+/// Distinct values allowed in a dictionary-encoded chunk (codes are one byte)
+const MAX_DICTIONARY_SIZE: usize = 256;
+
+/// One column of a stripe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum ColumnChunk {
+    Plain(Vec<Value>),
+    /// (value, repeat count)
+    RunLength(Vec<(Value, u32)>),
+    /// Distinct values + one code per row
+    Dictionary { values: Vec<Value>, codes: Vec<u8> },
+    /// Non-NULL integers: first value + zigzag varint deltas
+    Delta { first: i64, deltas: Vec<u8> },
+}
+
+impl ColumnChunk {
+    /// Encode `values` with the encoding that serializes smallest
+    fn encode(values: Vec<Value>) -> Self {
+        let mut candidates = Vec::new();
+        if let Some(chunk) = Self::run_length(&values) {
+            candidates.push(chunk);
+        }
+        if let Some(chunk) = Self::dictionary(&values) {
+            candidates.push(chunk);
+        }
+        if let Some(chunk) = Self::delta(&values) {
+            candidates.push(chunk);
+        }
+
+        let mut best = Self::Plain(values);
+        let mut best_size = best.encoded_size();
+        for candidate in candidates {
+            let size = candidate.encoded_size();
+            if size < best_size {
+                best = candidate;
+                best_size = size;
+            }
+        }
+        best
+    }
+
+    fn run_length(values: &[Value]) -> Option<Self> {
+        let mut runs: Vec<(Value, u32)> = Vec::new();
+        for value in values {
+            match runs.last_mut() {
+                Some((last, count)) if last == value => *count += 1,
+                _ => runs.push((value.clone(), 1)),
+            }
+        }
+        // Only worth trying when values actually repeat
+        (runs.len() * 2 <= values.len()).then_some(Self::RunLength(runs))
+    }
+
+    fn dictionary(values: &[Value]) -> Option<Self> {
+        let mut dictionary: Vec<Value> = Vec::new();
+        let mut codes = Vec::with_capacity(values.len());
+        for value in values {
+            let code = match dictionary.iter().position(|known| known == value) {
+                Some(code) => code,
+                None if dictionary.len() < MAX_DICTIONARY_SIZE => {
+                    dictionary.push(value.clone());
+                    dictionary.len() - 1
+                }
+                None => return None,
+            };
+            codes.push(u8::try_from(code).ok()?);
+        }
+        Some(Self::Dictionary { values: dictionary, codes })
+    }
+
+    fn delta(values: &[Value]) -> Option<Self> {
+        let integers: Vec<i64> = values
+            .iter()
+            .map(|value| match value {
+                Value::Integer(i) => Some(*i),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        let (&first, _) = integers.split_first()?;
+
+        let mut deltas = Vec::new();
+        for pair in integers.windows(2) {
+            write_varint(&mut deltas, zigzag(pair[1].wrapping_sub(pair[0])));
+        }
+        Some(Self::Delta { first, deltas })
+    }
+
+    fn decode(&self, len: usize) -> Vec<Value> {
+        match self {
+            Self::Plain(values) => values.clone(),
+            Self::RunLength(runs) => runs
+                .iter()
+                .flat_map(|(value, count)| std::iter::repeat_n(value.clone(), *count as usize))
+                .collect(),
+            Self::Dictionary { values, codes } => {
+                codes.iter().map(|&code| values[usize::from(code)].clone()).collect()
+            }
+            Self::Delta { first, deltas } => {
+                let mut out = Vec::with_capacity(len);
+                let mut current = *first;
+                out.push(Value::Integer(current));
+                let mut bytes = deltas.as_slice();
+                while let Some(delta) = read_varint(&mut bytes) {
+                    current = current.wrapping_add(unzigzag(delta));
+                    out.push(Value::Integer(current));
+                }
+                out
+            }
+        }
+    }
+
+    fn encoded_size(&self) -> usize {
+        bincode::serialized_size(self).map_or(usize::MAX, |size| usize::try_from(size).unwrap_or(usize::MAX))
+    }
+}
+
+const fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)).cast_unsigned()
+}
+
+const fn unzigzag(value: u64) -> i64 {
+    (value >> 1).cast_signed() ^ -((value & 1).cast_signed())
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+}
+
+/// A sealed, compressed group of rows
+#[derive(Debug, Clone)]
+struct Stripe {
+    xmin: Vec<u64>,
+    xmax: Vec<Option<u64>>,
+    columns: Vec<ColumnChunk>,
+}
+
+impl Stripe {
+    fn seal(rows: Vec<Row>) -> Self {
+        // Rows written before ALTER TABLE ADD COLUMN are shorter; pad them with NULL
+        let width = rows.iter().map(|row| row.values.len()).max().unwrap_or(0);
+        let mut xmin = Vec::with_capacity(rows.len());
+        let mut xmax = Vec::with_capacity(rows.len());
+        let mut columns: Vec<Vec<Value>> = (0..width).map(|_| Vec::with_capacity(rows.len())).collect();
+
+        for row in rows {
+            xmin.push(row.xmin);
+            xmax.push(row.xmax);
+            let mut values = row.values.into_iter();
+            for column in &mut columns {
+                column.push(values.next().unwrap_or(Value::Null));
+            }
+        }
+
+        Self {
+            xmin,
+            xmax,
+            columns: columns.into_iter().map(ColumnChunk::encode).collect(),
+        }
+    }
+
+    const fn len(&self) -> usize {
+        self.xmin.len()
+    }
+
+    /// Decode the rows; columns outside `columns` (if given) read as NULL
+    fn rows(&self, columns: Option<&[usize]>) -> Vec<Row> {
+        let len = self.len();
+        let mut decoded: Vec<Option<std::vec::IntoIter<Value>>> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, chunk)| {
+                columns
+                    .is_none_or(|wanted| wanted.contains(&idx))
+                    .then(|| chunk.decode(len).into_iter())
+            })
+            .collect();
+
+        (0..len)
+            .map(|i| Row {
+                values: decoded
+                    .iter_mut()
+                    .map(|column| column.as_mut().and_then(Iterator::next).unwrap_or(Value::Null))
+                    .collect(),
+                xmin: self.xmin[i],
+                xmax: self.xmax[i],
+                ctid: None,
+            })
+            .collect()
+    }
+}
+
+/// Column-wise storage of one table
+#[derive(Debug, Clone, Default)]
+pub struct ColumnarStore {
+    stripes: Vec<Stripe>,
+    /// Rows not yet sealed into a stripe
+    tail: Vec<Row>,
+}
+
+impl ColumnarStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serialized size of the sealed stripes plus the tail, in bytes
+    #[must_use]
+    pub fn encoded_size(&self) -> usize {
+        let headers = self.row_count() * (2 * std::mem::size_of::<u64>() + 1);
+        let columns: usize = self
+            .stripes
+            .iter()
+            .flat_map(|stripe| &stripe.columns)
+            .map(ColumnChunk::encoded_size)
+            .sum();
+        let tail: usize = self
+            .tail
+            .iter()
+            .map(|row| bincode::serialized_size(&row.values).map_or(0, |size| usize::try_from(size).unwrap_or(0)))
+            .sum();
+        headers + columns + tail
+    }
+}
+
+impl StorageBackend for ColumnarStore {
+    fn access_method(&self) -> AccessMethod {
+        AccessMethod::Columnar
+    }
+
+    fn insert(&mut self, row: Row) {
+        self.tail.push(row);
+        if self.tail.len() >= STRIPE_ROWS {
+            let rows = std::mem::take(&mut self.tail);
+            self.stripes.push(Stripe::seal(rows));
+        }
+    }
+
+    fn row_count(&self) -> usize {
+        self.stripes.iter().map(Stripe::len).sum::<usize>() + self.tail.len()
+    }
+
+    /// Every stripe plus the tail
+    fn chunk_count(&self) -> usize {
+        self.stripes.len() + 1
+    }
+
+    /// Only the columns in `columns` are decoded
+    fn chunk_rows(&self, idx: usize, columns: Option<&[usize]>) -> Result<Vec<Row>, DatabaseError> {
+        Ok(match self.stripes.get(idx) {
+            Some(stripe) => stripe.rows(columns),
+            None if idx == self.stripes.len() => self.tail.clone(),
+            None => Vec::new(),
+        })
+    }
+
+    /// Only the xmax headers of a stripe change; its column chunks stay as they are
+    fn delete_where(&mut self, predicate: &dyn Fn(&Row) -> bool, tx_id: u64) -> usize {
+        let mut deleted = 0;
+        for stripe in &mut self.stripes {
+            for (i, row) in stripe.rows(None).iter().enumerate() {
+                if predicate(row) {
+                    stripe.xmax[i] = Some(tx_id);
+                    deleted += 1;
+                }
+            }
+        }
+        for row in &mut self.tail {
+            if predicate(row) {
+                row.mark_deleted(tx_id);
+                deleted += 1;
+            }
+        }
+        deleted
+    }
+
+    fn update_where(&mut self, predicate: &dyn Fn(&Row) -> bool, updater: &dyn Fn(&Row) -> Row, tx_id: u64) -> usize {
+        let mut new_rows = Vec::new();
+        let mut new_version = |row: &Row| {
+            let mut new_row = updater(row);
+            new_row.xmin = tx_id;
+            new_row.xmax = None;
+            new_rows.push(new_row);
+        };
+
+        for stripe in &mut self.stripes {
+            for (i, row) in stripe.rows(None).iter().enumerate() {
+                if predicate(row) {
+                    stripe.xmax[i] = Some(tx_id);
+                    new_version(row);
+                }
+            }
+        }
+        for row in &mut self.tail {
+            if predicate(row) {
+                row.mark_deleted(tx_id);
+                new_version(row);
+            }
+        }
+
+        let count = new_rows.len();
+        for row in new_rows {
+            self.insert(row);
+        }
+        count
+    }
+
+    fn truncate(&mut self) {
+        self.stripes.clear();
+        self.tail.clear();
+    }
+
+    /// Stripes holding dead rows are decoded and sealed again
+    fn vacuum(&mut self, oldest_tx: u64) -> usize {
+        let mut removed = 0;
+        let mut stripes = Vec::with_capacity(self.stripes.len());
+
+        for stripe in std::mem::take(&mut self.stripes) {
+            let dead = stripe.xmax.iter().filter(|xmax| xmax.is_some_and(|xmax| xmax <= oldest_tx)).count();
+            if dead == 0 {
+                stripes.push(stripe);
+                continue;
+            }
+            removed += dead;
+            let live: Vec<Row> = stripe.rows(None).into_iter().filter(|row| !row.is_dead(oldest_tx)).collect();
+            if !live.is_empty() {
+                stripes.push(Stripe::seal(live));
+            }
+        }
+        self.stripes = stripes;
+
+        let before = self.tail.len();
+        self.tail.retain(|row| !row.is_dead(oldest_tx));
+        removed + before - self.tail.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryResult};
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::Database;
+
+    fn wide_row(i: i64) -> Row {
+        Row::new_with_xmin(
+            vec![
+                Value::Integer(i),
+                Value::Text(if i % 3 == 0 { "red" } else { "blue" }.to_string()),
+                Value::Boolean(i < 100),
+                if i % 10 == 0 { Value::Null } else { Value::Real(i as f64 / 2.0) },
+            ],
+            1,
+        )
+    }
+
+    #[test]
+    fn test_chunk_encodings_roundtrip() {
+        let sequential: Vec<Value> = (0..100).map(|i| Value::Integer(1000 + i * 3)).collect();
+        let chunk = ColumnChunk::encode(sequential.clone());
+        assert!(matches!(chunk, ColumnChunk::Delta { .. }));
+        assert_eq!(chunk.decode(100), sequential);
+
+        let runs: Vec<Value> = (0..100).map(|i| Value::Boolean(i < 70)).collect();
+        let chunk = ColumnChunk::encode(runs.clone());
+        assert!(matches!(chunk, ColumnChunk::RunLength(_)));
+        assert_eq!(chunk.decode(100), runs);
+
+        let labels: Vec<Value> = (0..100).map(|i| Value::Text(format!("label {}", i % 4))).collect();
+        let chunk = ColumnChunk::encode(labels.clone());
+        assert!(matches!(chunk, ColumnChunk::Dictionary { .. }));
+        assert_eq!(chunk.decode(100), labels);
+
+        let negative = vec![Value::Integer(i64::MAX), Value::Integer(i64::MIN), Value::Integer(-5)];
+        assert_eq!(ColumnChunk::encode(negative.clone()).decode(3), negative);
+    }
+
+    #[test]
+    fn test_store_scan_and_compression() {
+        let mut store = ColumnarStore::new();
+        let count = STRIPE_ROWS * 2 + 10;
+        for i in 0..count {
+            store.insert(wide_row(i as i64));
+        }
+        assert_eq!(store.row_count(), count);
+        assert_eq!(store.chunk_count(), 3);
+
+        let rows = store.get_all_rows().unwrap();
+        assert_eq!(rows.len(), count);
+        assert_eq!(rows[STRIPE_ROWS + 5].values, wide_row((STRIPE_ROWS + 5) as i64).values);
+
+        // Only the requested column is decoded
+        let first = store.chunk_rows(0, Some(&[0])).unwrap();
+        assert_eq!(first[7].values, vec![Value::Integer(7), Value::Null, Value::Null, Value::Null]);
+
+        let heap_size: usize = rows.iter().map(|row| bincode::serialized_size(row).unwrap() as usize).sum();
+        assert!(store.encoded_size() < heap_size);
+    }
+
+    #[test]
+    fn test_mvcc_and_vacuum() {
+        let mut store = ColumnarStore::new();
+        for i in 0..(STRIPE_ROWS + 4) {
+            store.insert(wide_row(i as i64));
+        }
+
+        let is_even = |row: &Row| matches!(row.values[0], Value::Integer(i) if i % 2 == 0);
+        let deleted = store.delete_where(&is_even, 5);
+        assert_eq!(deleted, STRIPE_ROWS / 2 + 2);
+
+        let updated = store.update_where(
+            &|row| row.xmax.is_none() && matches!(row.values[0], Value::Integer(1)),
+            &|row| Row::new(vec![Value::Integer(-1), row.values[1].clone(), row.values[2].clone(), row.values[3].clone()]),
+            6,
+        );
+        assert_eq!(updated, 1);
+
+        let removed = store.vacuum(10);
+        assert_eq!(removed, deleted + updated);
+        let rows = store.get_all_rows().unwrap();
+        assert_eq!(rows.len(), STRIPE_ROWS + 4 - deleted);
+        assert!(rows.iter().all(|row| row.xmax.is_none()));
+        assert!(rows.iter().any(|row| row.values[0] == Value::Integer(-1) && row.xmin == 6));
+    }
+
+    #[test]
+    fn test_columnar_table() {
+        // v2.7.0: CREATE TABLE ... USING columnar
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        let first = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows[0][0].clone(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE bad (id INTEGER) USING rowstore").is_err());
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE facts (id INTEGER, region TEXT, amount INTEGER, note TEXT) USING columnar").unwrap();
+        assert_eq!(db.get_table("facts").unwrap().access_method, crate::types::AccessMethod::Columnar);
+        assert_eq!(storage.get_paged_table("facts").unwrap().access_method(), crate::types::AccessMethod::Columnar);
+
+        // Enough rows to seal two stripes and leave a tail
+        let regions = ["north", "south", "east"];
+        for i in 0..2100 {
+            let sql = format!("INSERT INTO facts VALUES ({i}, '{}', {i}, 'row {i}')", regions[i % 3]);
+            run_sql(&mut db, &mut storage, &tx_manager, &sql).unwrap();
+        }
+
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM facts").unwrap()), "2100");
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(amount) FROM facts WHERE region = 'north'").unwrap()), "700");
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT note FROM facts WHERE id = 1500").unwrap()), "row 1500");
+
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE facts SET amount = 0 WHERE region = 'north'").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "DELETE FROM facts WHERE region = 'south'").unwrap();
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM facts").unwrap()), "1400");
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM facts WHERE amount = 0").unwrap()), "700");
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT region FROM facts WHERE id = 2").unwrap()), "east");
+
+        match run_sql(&mut db, &mut storage, &tx_manager, "EXPLAIN SELECT * FROM facts").unwrap() {
+            QueryResult::Rows(rows, _) => assert!(rows.iter().any(|row| row[0].contains("Storage: columnar"))),
+            QueryResult::Success(_) => panic!("Expected plan rows"),
+        }
+    }
+}
@@ -1,30 +1,163 @@
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::path::Path;
-use crate::types::{DatabaseError, Row};
-use super::page_manager::PageManager;
+use crate::types::{AccessMethod, DatabaseError, Row};
+use super::page_manager::{PageManager, TEMP_TABLE_ID_BASE};
 use super::paged_table::PagedTable;
+use super::backend::StorageBackend;
+use super::columnar::ColumnarStore;
+use super::lsm::LsmStore;
+use super::tempfile::TempFiles;
+
+/// v2.7.0: Default `work_mem`, as in `PostgreSQL`: 4 MB
+pub const DEFAULT_WORK_MEM: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    /// v2.7.0: `work_mem` the session whose query runs on this thread set
+    static SESSION_WORK_MEM: Cell<Option<usize>> = const { Cell::new(None) };
+}
 
 /// `DatabaseStorage` - manages page-based storage for all tables in a database
 pub struct DatabaseStorage {
     /// Page manager (shared across all tables)
-    page_manager: Arc<Mutex<PageManager>>,
+    page_manager: Arc<PageManager>,
     /// `PagedTable` instances: `table_name` -> (`table_id`, `PagedTable`)
     paged_tables: HashMap<String, (u32, PagedTable)>,
     /// Next available table ID
     next_table_id: u32,
+    /// v2.7.0: Next available temporary table ID (in-memory pages)
+    next_temp_table_id: u32,
+    /// v2.7.0: Bytes a sort keeps in memory before spilling to temp files
+    work_mem: usize,
+    /// v2.7.0: Bytes one query may hold in memory, 0 = unlimited
+    max_query_memory: usize,
+    /// v2.7.0: Rows a query may return, 0 = unlimited
+    max_result_rows: usize,
+    /// v2.7.0: Temp files of queries, `None` when nothing may be written to disk
+    temp_files: Option<TempFiles>,
+    /// v2.7.0: Evaluate filters and projections of sequential scans in batches
+    vectorized: bool,
+    /// v2.7.0: Highest horizon VACUUM (or CLUSTER) removed row versions up to
+    /// since startup: versions deleted by transactions up to it may be gone
+    vacuum_horizon: u64,
 }
 
 impl DatabaseStorage {
     /// Create new database storage
     pub fn new<P: AsRef<Path>>(data_dir: P, buffer_pool_size: usize) -> Result<Self, DatabaseError> {
-        let page_manager = Arc::new(Mutex::new(PageManager::new(data_dir, buffer_pool_size)?));
+        let page_manager = PageManager::new(data_dir, buffer_pool_size)?;
+        let temp_files = page_manager.data_dir().map(TempFiles::open);
+        Ok(Self::with_page_manager(page_manager, temp_files))
+    }
+
+    /// v2.7.0: Storage whose pages never leave memory (no data directory)
+    #[must_use]
+    pub fn in_memory(buffer_pool_size: usize) -> Self {
+        Self::with_page_manager(PageManager::in_memory(buffer_pool_size), None)
+    }
 
-        Ok(Self {
-            page_manager,
+    /// v2.7.0: Storage over `page_manager` whose queries spill into `temp_files`
+    /// (one database of a `PageStorage`, sharing the server's temp directory)
+    pub(crate) fn with_page_manager(page_manager: PageManager, temp_files: Option<TempFiles>) -> Self {
+        Self {
+            page_manager: Arc::new(page_manager),
             paged_tables: HashMap::new(),
             next_table_id: 1,
-        })
+            next_temp_table_id: TEMP_TABLE_ID_BASE,
+            work_mem: DEFAULT_WORK_MEM,
+            max_query_memory: 0,
+            max_result_rows: 0,
+            temp_files,
+            vectorized: false,
+            vacuum_horizon: 0,
+        }
+    }
+
+    /// v2.7.0: Bytes a sort keeps in memory before spilling to temp files:
+    /// the session's `work_mem` inside `with_session_work_mem`, the server's otherwise
+    #[must_use]
+    pub fn work_mem(&self) -> usize {
+        SESSION_WORK_MEM.with(Cell::get).unwrap_or(self.work_mem)
+    }
+
+    /// v2.7.0: Run `f` with a session's `work_mem` (bytes; `None` = the server's)
+    pub fn with_session_work_mem<R>(bytes: Option<usize>, f: impl FnOnce() -> R) -> R {
+        /// Puts the previous value back, also when `f` panics
+        struct Restore(Option<usize>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SESSION_WORK_MEM.with(|current| current.set(self.0));
+            }
+        }
+
+        let _restore = Restore(SESSION_WORK_MEM.with(|current| current.replace(bytes)));
+        f()
+    }
+
+    /// v2.7.0: Set `work_mem` (bytes)
+    pub const fn set_work_mem(&mut self, bytes: usize) {
+        self.work_mem = bytes;
+    }
+
+    /// v2.7.0: Bytes one query may hold in memory (`max_query_memory`, 0 = unlimited)
+    #[must_use]
+    pub const fn max_query_memory(&self) -> usize {
+        self.max_query_memory
+    }
+
+    /// v2.7.0: Set `max_query_memory` (bytes)
+    pub const fn set_max_query_memory(&mut self, bytes: usize) {
+        self.max_query_memory = bytes;
+    }
+
+    /// v2.7.0: Rows a query may return (`max_result_rows`, 0 = unlimited)
+    #[must_use]
+    pub const fn max_result_rows(&self) -> usize {
+        self.max_result_rows
+    }
+
+    /// v2.7.0: Set `max_result_rows`
+    pub const fn set_max_result_rows(&mut self, rows: usize) {
+        self.max_result_rows = rows;
+    }
+
+    /// v2.7.0: Memory accounting for a new query, limited by `max_query_memory`
+    #[must_use]
+    pub fn query_memory(&self) -> crate::executor::memory::QueryMemory {
+        crate::executor::memory::QueryMemory::new(self.max_query_memory)
+    }
+
+    /// v2.7.0: Row versions deleted by transactions up to this ID may have
+    /// been removed by VACUUM
+    #[must_use]
+    pub const fn vacuum_horizon(&self) -> u64 {
+        self.vacuum_horizon
+    }
+
+    /// v2.7.0: Record that dead row versions up to `horizon` were removed
+    pub fn note_vacuum(&mut self, horizon: u64) {
+        self.vacuum_horizon = self.vacuum_horizon.max(horizon);
+    }
+
+    /// v2.7.0: Whether sequential scans evaluate WHERE and SELECT expressions
+    /// a batch of rows at a time (`vectorized_execution`)
+    #[must_use]
+    pub const fn vectorized(&self) -> bool {
+        self.vectorized
+    }
+
+    /// v2.7.0: Turn batched evaluation on or off
+    pub const fn set_vectorized(&mut self, enabled: bool) {
+        self.vectorized = enabled;
+    }
+
+    /// v2.7.0: Temp files under `data_dir/pgsql_tmp` for sorts and spills,
+    /// `None` when nothing may be written to disk
+    #[must_use]
+    pub const fn temp_files(&self) -> Option<&TempFiles> {
+        self.temp_files.as_ref()
     }
 
     /// Create a new paged table
@@ -42,12 +175,66 @@ impl DatabaseStorage {
         Ok(())
     }
 
+    /// v2.7.0: Create a table with the given access method (`USING heap|columnar|lsm`)
+    pub fn create_table_using(&mut self, table_name: String, access_method: AccessMethod) -> Result<(), DatabaseError> {
+        let backend: Box<dyn StorageBackend> = match access_method {
+            AccessMethod::Heap => return self.create_table(table_name),
+            AccessMethod::Columnar => Box::new(ColumnarStore::new()),
+            AccessMethod::Lsm => Box::new(LsmStore::new()),
+            AccessMethod::Foreign => {
+                return Err(DatabaseError::ParseError(
+                    "Foreign tables are created with CREATE FOREIGN TABLE".to_string(),
+                ));
+            }
+        };
+        self.create_table_with_backend(table_name, backend)
+    }
+
+    /// v2.7.0: Create a table whose rows are kept (or read) by `backend`
+    pub fn create_table_with_backend(&mut self, table_name: String, backend: Box<dyn StorageBackend>) -> Result<(), DatabaseError> {
+        if self.paged_tables.contains_key(&table_name) {
+            return Err(DatabaseError::TableAlreadyExists(table_name));
+        }
+
+        let table_id = self.next_table_id;
+        self.next_table_id += 1;
+
+        let paged_table = PagedTable::with_backend(table_id, self.page_manager.clone(), backend);
+        self.paged_tables.insert(table_name, (table_id, paged_table));
+
+        Ok(())
+    }
+
+    /// v2.7.0: Create a paged table whose pages live only in memory
+    pub fn create_temp_table(&mut self, table_name: String) -> Result<(), DatabaseError> {
+        if self.paged_tables.contains_key(&table_name) {
+            return Err(DatabaseError::TableAlreadyExists(table_name));
+        }
+
+        let table_id = self.next_temp_table_id;
+        self.next_temp_table_id = self.next_temp_table_id.wrapping_add(1).max(TEMP_TABLE_ID_BASE);
+
+        let paged_table = PagedTable::new(table_id, self.page_manager.clone());
+        self.paged_tables.insert(table_name, (table_id, paged_table));
+
+        Ok(())
+    }
+
+    /// v2.7.0: Take a paged table out of the name map without touching its pages
+    pub fn detach_table(&mut self, table_name: &str) -> Option<(u32, PagedTable)> {
+        self.paged_tables.remove(table_name)
+    }
+
+    /// v2.7.0: Put a detached paged table back under `table_name`
+    pub fn attach_table(&mut self, table_name: String, entry: (u32, PagedTable)) {
+        self.paged_tables.insert(table_name, entry);
+    }
+
     /// Drop a paged table
     pub fn drop_table(&mut self, table_name: &str) -> Result<(), DatabaseError> {
         if let Some((table_id, _)) = self.paged_tables.remove(table_name) {
             // Delete all pages for this table
-            let pm = self.page_manager.lock().unwrap();
-            pm.delete_table_pages(table_id)?;
+            self.page_manager.delete_table_pages(table_id)?;
             Ok(())
         } else {
             Err(DatabaseError::TableNotFound(table_name.to_string()))
@@ -102,8 +289,7 @@ impl DatabaseStorage {
 
     /// Flush all dirty pages to disk (checkpoint)
     pub fn checkpoint(&self) -> Result<usize, DatabaseError> {
-        let pm = self.page_manager.lock().unwrap();
-        pm.checkpoint()
+        self.page_manager.checkpoint()
     }
 
     /// Get statistics for a table
@@ -123,6 +309,12 @@ impl DatabaseStorage {
     pub fn row_count(&self, table_name: &str) -> Option<usize> {
         self.get_paged_table(table_name).map(super::paged_table::PagedTable::row_count)
     }
+
+    /// v2.7.0: Buffer pool statistics (shared by all tables)
+    #[must_use]
+    pub fn buffer_pool_stats(&self) -> super::page_manager::BufferPoolStats {
+        self.page_manager.get_stats()
+    }
 }
 
 #[cfg(test)]
@@ -212,6 +404,44 @@ mod tests {
         assert_eq!(updated, 5);
     }
 
+    #[test]
+    fn test_temp_table_pages_stay_in_memory() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = DatabaseStorage::new(temp_dir.path(), 100).unwrap();
+
+        storage.create_temp_table("scratch".to_string()).unwrap();
+        for i in 0..10 {
+            storage.insert("scratch", Row::new(vec![Value::Integer(i)])).unwrap();
+        }
+        storage.checkpoint().unwrap();
+
+        let (table_id, mut paged_table) = storage.detach_table("scratch").unwrap();
+        assert!(table_id >= TEMP_TABLE_ID_BASE);
+        assert!(!temp_dir.path().join(format!("table_{table_id}")).exists());
+        assert_eq!(paged_table.get_all_rows().unwrap().len(), 10);
+
+        paged_table.truncate().unwrap();
+        assert_eq!(paged_table.get_all_rows().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_in_memory_storage() {
+        // A tiny buffer pool forces evictions into the in-memory page store
+        let mut storage = DatabaseStorage::in_memory(2);
+        storage.create_table("users".to_string()).unwrap();
+
+        for i in 0..2000 {
+            storage.insert("users", Row::new(vec![Value::Integer(i), Value::Text(format!("user {i}"))])).unwrap();
+        }
+        storage.checkpoint().unwrap();
+
+        assert!(storage.get_table_stats("users").unwrap().page_count > 2);
+        assert_eq!(storage.get_all_rows("users").unwrap().len(), 2000);
+
+        storage.drop_table("users").unwrap();
+        assert!(storage.get_paged_table("users").is_none());
+    }
+
     #[test]
     fn test_checkpoint() {
         let temp_dir = TempDir::new().unwrap();
@@ -1,15 +1,47 @@
-use crate::types::{Column, Database, DatabaseError, Row, ServerInstance, Table};
+use crate::types::{Column, Database, DatabaseError, DatabaseMetadata, InstanceSnapshot, Role, Row, ServerInstance, Table, User};
+use crate::storage::format;
 use crate::storage::wal::{Operation, WalManager};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// v2.7.0: Каталог инкрементальных checkpoint'ов: `<БД>.db` на каждую БД + `instance.cat`
+const CHECKPOINT_DIR: &str = "checkpoint";
+const CATALOG_FILE: &str = "instance.cat";
+
+/// v2.7.0: Всё, кроме самих БД, и LSN checkpoint'а
+#[derive(Serialize)]
+struct CatalogRef<'a> {
+    checkpoint_lsn: u64,
+    databases: Vec<&'a String>,
+    database_metadata: &'a HashMap<String, DatabaseMetadata>,
+    users: &'a HashMap<String, User>,
+    roles: &'a HashMap<String, Role>,
+}
+
+/// Тот же каталог при загрузке
+#[derive(Deserialize)]
+struct Catalog {
+    checkpoint_lsn: u64,
+    databases: Vec<String>,
+    database_metadata: HashMap<String, DatabaseMetadata>,
+    users: HashMap<String, User>,
+    roles: HashMap<String, Role>,
+}
+
 pub struct StorageEngine {
-    data_dir: PathBuf,
+    /// Каталог данных (v2.7.0: `None` = in-memory режим без snapshot'ов и WAL)
+    data_dir: Option<PathBuf>,
     wal: WalManager,
     /// Счетчик операций с момента последнего snapshot
     operations_since_snapshot: usize,
     /// Порог операций для создания нового snapshot
     snapshot_threshold: usize,
+    /// v2.7.0: БД, изменённые с последнего checkpoint'а
+    dirty_databases: HashSet<String>,
+    /// v2.7.0: БД, записанные checkpoint'ами этого процесса (`None` - ещё ни одного)
+    persisted_databases: Option<HashSet<String>>,
 }
 
 impl StorageEngine {
@@ -20,46 +52,178 @@ impl StorageEngine {
         let wal = WalManager::new(&data_dir)?;
 
         Ok(Self {
-            data_dir,
+            data_dir: Some(data_dir),
             wal,
             operations_since_snapshot: 0,
             snapshot_threshold: 100, // Создаем snapshot каждые 100 операций
+            dirty_databases: HashSet::new(),
+            persisted_databases: None,
         })
     }
 
+    /// v2.7.0: Каталог данных (`None` в in-memory режиме)
+    #[must_use]
+    pub fn data_dir(&self) -> Option<&Path> {
+        self.data_dir.as_deref()
+    }
+
+    /// v2.7.0: Хранилище без каталога данных - ничего не пишется на диск
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self {
+            data_dir: None,
+            wal: WalManager::in_memory(),
+            operations_since_snapshot: 0,
+            snapshot_threshold: 100,
+            dirty_databases: HashSet::new(),
+            persisted_databases: None,
+        }
+    }
+
+    /// v2.7.0: Работает ли хранилище без диска
+    #[must_use]
+    pub const fn is_in_memory(&self) -> bool {
+        self.data_dir.is_none()
+    }
+
+    /// v2.7.0: Сжимать ли большие записи WAL
+    pub const fn set_wal_compression(&mut self, enabled: bool) {
+        self.wal.set_compression(enabled);
+    }
+
+    /// v2.7.0: Сколько байт записано в WAL с момента запуска
+    #[must_use]
+    pub const fn wal_bytes_written(&self) -> u64 {
+        self.wal.bytes_written()
+    }
+
+    /// v2.7.0: БД изменилась - следующий checkpoint запишет её заново
+    pub fn mark_database_dirty(&mut self, name: &str) {
+        if !self.dirty_databases.contains(name) {
+            self.dirty_databases.insert(name.to_string());
+        }
+    }
+
     /// Сохраняет snapshot серверного экземпляра в binary формате
-    fn save_snapshot(&self, instance: &ServerInstance) -> Result<(), DatabaseError> {
-        let instance_path = self.data_dir.join("server_instance.db");
-        let encoded = bincode::serialize(instance)
-            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
-        fs::write(instance_path, encoded)?;
+    ///
+    /// v2.7.0: Инкрементально - в `checkpoint/` переписываются только файлы
+    /// изменённых (и новых) БД, файлы удалённых БД стираются. Каталог с
+    /// пользователями, ролями и LSN checkpoint'а небольшой и пишется всегда,
+    /// последним: пока он не заменён, recovery видит прежний checkpoint.
+    fn save_snapshot(&mut self, instance: &InstanceSnapshot, checkpoint_lsn: u64) -> Result<(), DatabaseError> {
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(());
+        };
+        let dir = data_dir.join(CHECKPOINT_DIR);
+        fs::create_dir_all(&dir)?;
+
+        let names: HashSet<String> = instance.databases().map(|(name, _)| name.clone()).collect();
+        for (name, db) in instance.databases() {
+            let unchanged = self.persisted_databases.as_ref().is_some_and(|persisted| persisted.contains(name))
+                && !self.dirty_databases.contains(name);
+            if !unchanged {
+                Self::write_file(&dir.join(format!("{name}.db")), db)?;
+            }
+        }
+
+        // Файлы БД, которых больше нет (после перезапуска - всё, чего нет в снимке)
+        match &self.persisted_databases {
+            Some(persisted) => {
+                for name in persisted.difference(&names) {
+                    fs::remove_file(dir.join(format!("{name}.db"))).ok();
+                }
+            }
+            None => {
+                for entry in fs::read_dir(&dir)?.flatten() {
+                    let path = entry.path();
+                    let stale = path.extension().and_then(|ext| ext.to_str()) == Some("db")
+                        && path.file_stem().and_then(|stem| stem.to_str()).is_some_and(|name| !names.contains(name));
+                    if stale {
+                        fs::remove_file(path).ok();
+                    }
+                }
+            }
+        }
+
+        let inst = instance.instance();
+        let catalog = CatalogRef {
+            checkpoint_lsn,
+            databases: instance.databases().map(|(name, _)| name).collect(),
+            database_metadata: &inst.database_metadata,
+            users: &inst.users,
+            roles: &inst.roles,
+        };
+        Self::write_file(&dir.join(CATALOG_FILE), &catalog)?;
+
+        // Прежний snapshot одним файлом больше не нужен - но только если
+        // записанный checkpoint читается (иначе в нём единственная копия данных)
+        let legacy = data_dir.join("server_instance.db");
+        if legacy.exists() {
+            Self::read_checkpoint(&dir)?;
+            fs::remove_file(legacy)?;
+        }
+
+        self.persisted_databases = Some(names);
+        self.dirty_databases.clear();
+        Ok(())
+    }
+
+    /// v2.7.0: Пишет во временный файл и переименовывает - файл всегда целый
+    fn write_file<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), DatabaseError> {
+        let encoded = format::encode(value)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, encoded)?;
+        fs::rename(tmp, path)?;
         Ok(())
     }
 
+
+    /// v2.7.0: Читает инкрементальный checkpoint из `dir`
+    fn read_checkpoint(dir: &Path) -> Result<(ServerInstance, u64), DatabaseError> {
+        let catalog: Catalog = format::decode(&fs::read(dir.join(CATALOG_FILE))?)?;
+        let mut instance = ServerInstance::new();
+        for name in catalog.databases {
+            let db = format::decode_database(&fs::read(dir.join(format!("{name}.db")))?)?;
+            instance.databases.insert(name, tokio::sync::RwLock::new(db));
+        }
+        instance.database_metadata = catalog.database_metadata;
+        instance.users = catalog.users;
+        instance.roles = catalog.roles;
+        Ok((instance, catalog.checkpoint_lsn))
+    }
+
     /// Загружает snapshot серверного экземпляра из binary формата
-    fn load_snapshot(&self) -> Result<Option<ServerInstance>, DatabaseError> {
-        let instance_path = self.data_dir.join("server_instance.db");
+    ///
+    /// v2.7.0: Вместе с LSN checkpoint'а (0 для snapshot'ов старых форматов)
+    fn load_snapshot(&self) -> Result<Option<(ServerInstance, u64)>, DatabaseError> {
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(None);
+        };
+
+        // v2.7.0: Инкрементальные checkpoint'ы
+        let dir = data_dir.join(CHECKPOINT_DIR);
+        if dir.join(CATALOG_FILE).exists() {
+            return Self::read_checkpoint(&dir).map(Some);
+        }
+
+        let instance_path = data_dir.join("server_instance.db");
 
-        // Проверяем новый формат (server_instance.db)
+        // Проверяем формат одним файлом (server_instance.db)
         if instance_path.exists() {
-            let data = fs::read(instance_path)?;
-            let instance = bincode::deserialize(&data)
-                .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
-            return Ok(Some(instance));
+            let instance = format::decode_server_instance(&fs::read(instance_path)?)?;
+            return Ok(Some((instance, 0)));
         }
 
         // Fallback: пробуем загрузить старый формат (отдельные БД)
         // Это для обратной совместимости
-        let main_db_path = self.data_dir.join("main.db");
+        let main_db_path = data_dir.join("main.db");
         if main_db_path.exists() {
-            let data = fs::read(&main_db_path)?;
-            let db: Database = bincode::deserialize(&data)
-                .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+            let db = format::decode_database(&fs::read(&main_db_path)?)?;
 
             // Создаем ServerInstance из старой БД
             let mut instance = ServerInstance::new();
-            instance.databases.insert(db.name.clone(), db);
-            return Ok(Some(instance));
+            instance.databases.insert(db.name.clone(), tokio::sync::RwLock::new(db));
+            return Ok(Some((instance, 0)));
         }
 
         Ok(None)
@@ -68,19 +232,21 @@ impl StorageEngine {
     /// Загружает `ServerInstance` из snapshot + применяет WAL
     pub fn load_server_instance(&self) -> Result<ServerInstance, DatabaseError> {
         // Загружаем последний snapshot
-        let mut instance = self.load_snapshot()?.unwrap_or_else(ServerInstance::new);
+        let (mut instance, checkpoint_lsn) = self.load_snapshot()?.unwrap_or_else(|| (ServerInstance::new(), 0));
 
-        // Применяем все операции из WAL
+        // Применяем операции из WAL (v2.7.0: только записанные после checkpoint'а)
+        // TODO: WAL нужно расширить для поддержки multi-database операций
+        // Пока применяем к первой по имени БД, кроме шаблонов (legacy behavior)
+        let target = instance.default_database().cloned();
         let logs = self.wal.read_all_logs()?;
-        for entry in logs {
-            // Применяем операции ко всем БД
-            // TODO: WAL нужно расширить для поддержки multi-database операций
-            // Пока применяем к первой найденной БД (legacy behavior)
-            if let Some(db) = instance.databases.values_mut().next() {
+        for entry in logs.into_iter().filter(|entry| entry.sequence > checkpoint_lsn) {
+            if let Some(db) = target.as_ref().and_then(|name| instance.get_database_mut(name)) {
                 WalManager::apply_operation(db, &entry.operation)?;
             }
         }
 
+        instance.share_sessions();
+        instance.assign_database_oids();
         Ok(instance)
     }
 
@@ -88,18 +254,16 @@ impl StorageEngine {
     #[allow(dead_code)]
     pub fn load_database(&self, name: &str) -> Result<Database, DatabaseError> {
         // Сначала пытаемся загрузить из ServerInstance
-        let instance = self.load_server_instance()?;
+        let mut instance = self.load_server_instance()?;
 
-        if let Some(db) = instance.databases.get(name) {
-            return Ok(db.clone());
+        if let Some(db) = instance.databases.remove(name) {
+            return Ok(db.into_inner());
         }
 
         // Fallback: проверяем legacy формат {name}.db
-        let db_path = self.data_dir.join(format!("{name}.db"));
-        if db_path.exists() {
-            let data = fs::read(&db_path)?;
-            let mut db: Database = bincode::deserialize(&data)
-                .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+        let db_path = self.data_dir.as_ref().map(|data_dir| data_dir.join(format!("{name}.db")));
+        if let Some(db_path) = db_path.filter(|path| path.exists()) {
+            let mut db = format::decode_database(&fs::read(&db_path)?)?;
 
             // Применяем WAL операции
             let logs = self.wal.read_all_logs()?;
@@ -128,7 +292,7 @@ impl StorageEngine {
     }
 
     /// Сохраняет `ServerInstance` (создаёт checkpoint только при необходимости)
-    pub fn save_server_instance(&mut self, instance: &ServerInstance) -> Result<(), DatabaseError> {
+    pub fn save_server_instance(&mut self, instance: &InstanceSnapshot) -> Result<(), DatabaseError> {
         // Делаем checkpoint только если достигли порога операций
         if self.should_checkpoint() {
             self.create_checkpoint_instance(instance)?;
@@ -140,10 +304,9 @@ impl StorageEngine {
     pub fn save_database(&mut self, db: &Database) -> Result<(), DatabaseError> {
         // Legacy: сохраняем отдельную БД
         if self.should_checkpoint() {
-            let db_path = self.data_dir.join(format!("{}.db", db.name));
-            let encoded = bincode::serialize(db)
-                .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
-            fs::write(db_path, encoded)?;
+            if let Some(data_dir) = &self.data_dir {
+                fs::write(data_dir.join(format!("{}.db", db.name)), format::encode(db)?)?;
+            }
 
             self.wal.checkpoint()?;
             self.wal.cleanup_old_logs(2)?;
@@ -153,12 +316,14 @@ impl StorageEngine {
     }
 
     /// Создает checkpoint для `ServerInstance`
-    pub fn create_checkpoint_instance(&mut self, instance: &ServerInstance) -> Result<(), DatabaseError> {
-        // Сохраняем snapshot
-        self.save_snapshot(instance)?;
+    pub fn create_checkpoint_instance(&mut self, instance: &InstanceSnapshot) -> Result<(), DatabaseError> {
+        // Записываем маркер checkpoint в WAL (v2.7.0: его LSN уходит в snapshot).
+        // Снимок держит блокировки всех БД, а WAL пишут только через `&mut self`,
+        // так что после маркера в снимке нет ни одной операции
+        let checkpoint_lsn = self.wal.checkpoint()?;
 
-        // Записываем маркер checkpoint в WAL
-        self.wal.checkpoint()?;
+        // Сохраняем snapshot
+        self.save_snapshot(instance, checkpoint_lsn)?;
 
         // Удаляем старые WAL файлы (оставляем последние 2)
         self.wal.cleanup_old_logs(2)?;
@@ -173,10 +338,9 @@ impl StorageEngine {
     #[allow(dead_code)]
     pub fn create_checkpoint(&mut self, db: &Database) -> Result<(), DatabaseError> {
         // Legacy: сохраняем отдельную БД
-        let db_path = self.data_dir.join(format!("{}.db", db.name));
-        let encoded = bincode::serialize(db)
-            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
-        fs::write(db_path, encoded)?;
+        if let Some(data_dir) = &self.data_dir {
+            fs::write(data_dir.join(format!("{}.db", db.name)), format::encode(db)?)?;
+        }
 
         self.wal.checkpoint()?;
         self.wal.cleanup_old_logs(2)?;
@@ -214,6 +378,14 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Логирует пачку операций одной записью в WAL (v2.7.0: многострочный INSERT)
+    pub fn log_batch(&mut self, operations: Vec<Operation>) -> Result<(), DatabaseError> {
+        let count = operations.len();
+        self.wal.append_batch(operations)?;
+        self.operations_since_snapshot += count;
+        Ok(())
+    }
+
     /// Логирует UPDATE операцию
     pub fn log_update(
         &mut self,
@@ -281,16 +453,62 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Логирует ALTER TABLE ALTER COLUMN SET / DROP NOT NULL операцию (v2.7.0)
+    pub fn log_alter_table_set_nullable(&mut self, table_name: &str, column_name: &str, nullable: bool) -> Result<(), DatabaseError> {
+        self.wal.append(Operation::AlterTableSetNullable {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+            nullable,
+        })?;
+        self.operations_since_snapshot += 1;
+        Ok(())
+    }
+
+    /// Логирует CREATE TYPE ... AS ENUM операцию (v2.7.0)
+    pub fn log_create_enum(&mut self, name: &str, values: &[String]) -> Result<(), DatabaseError> {
+        self.wal.append(Operation::CreateEnum {
+            name: name.to_string(),
+            values: values.to_vec(),
+        })?;
+        self.operations_since_snapshot += 1;
+        Ok(())
+    }
+
+    /// Логирует ALTER TYPE ... ADD VALUE операцию (v2.7.0)
+    pub fn log_alter_enum(&mut self, name: &str, values: &[String]) -> Result<(), DatabaseError> {
+        self.wal.append(Operation::AlterEnum {
+            name: name.to_string(),
+            values: values.to_vec(),
+        })?;
+        self.operations_since_snapshot += 1;
+        Ok(())
+    }
+
+    /// Логирует выданное значение SERIAL-колонки (v2.7.0)
+    pub fn log_serial_value(&mut self, table_name: &str, column_name: &str, value: i64) -> Result<(), DatabaseError> {
+        self.wal.append(Operation::SerialValue {
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+            value,
+        })?;
+        self.operations_since_snapshot += 1;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn delete_database(&self, name: &str) -> Result<(), DatabaseError> {
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(());
+        };
+
         // Удаляем binary формат
-        let db_path = self.data_dir.join(format!("{name}.db"));
+        let db_path = data_dir.join(format!("{name}.db"));
         if db_path.exists() {
             fs::remove_file(db_path)?;
         }
 
         // Удаляем старый JSON формат если есть
-        let json_path = self.data_dir.join(format!("{name}.json"));
+        let json_path = data_dir.join(format!("{name}.json"));
         if json_path.exists() {
             fs::remove_file(json_path)?;
         }
@@ -302,8 +520,11 @@ impl StorageEngine {
     pub fn list_databases(&self) -> Result<Vec<String>, DatabaseError> {
         let mut databases = Vec::new();
         let mut seen = std::collections::HashSet::new();
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(databases);
+        };
 
-        for entry in fs::read_dir(&self.data_dir)? {
+        for entry in fs::read_dir(data_dir)? {
             let entry = entry?;
             let path = entry.path();
             if path.is_file() {
@@ -323,6 +544,7 @@ impl StorageEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Collation;
     use tempfile::TempDir;
 
     #[test]
@@ -335,6 +557,20 @@ mod tests {
         assert!(data_path.is_dir());
     }
 
+    #[test]
+    fn test_in_memory_storage_engine() {
+        let mut storage = StorageEngine::in_memory();
+        assert!(storage.is_in_memory());
+
+        let mut instance = ServerInstance::initialize("postgres", "secret", "postgres");
+        storage.log_drop_table("users").unwrap();
+        storage.create_checkpoint_instance(&instance.snapshot_exclusive()).unwrap();
+
+        // Nothing was persisted, so there is nothing to recover
+        assert!(storage.load_server_instance().unwrap().databases.is_empty());
+        assert!(storage.list_databases().unwrap().is_empty());
+    }
+
     #[test]
     fn test_save_and_load_database() {
         let temp_dir = TempDir::new().unwrap();
@@ -349,6 +585,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                     foreign_key: None,
+                    collation: Collation::C,
                     unique: false,
             },
             crate::types::Column {
@@ -357,6 +594,7 @@ mod tests {
                 nullable: false,
                 primary_key: false,
                     foreign_key: None,
+                    collation: Collation::C,
                     unique: false,
             },
         ];
@@ -446,6 +684,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                     foreign_key: None,
+                    collation: Collation::C,
                     unique: false,
             },
         ];
@@ -480,6 +719,7 @@ mod tests {
                     nullable: false,
                     primary_key: true,
                     foreign_key: None,
+                    collation: Collation::C,
                     unique: false,
                 },
                 crate::types::Column {
@@ -488,6 +728,7 @@ mod tests {
                     nullable: false,
                     primary_key: false,
                     foreign_key: None,
+                    collation: Collation::C,
                     unique: false,
                 },
             ];
@@ -571,6 +812,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                     foreign_key: None,
+                    collation: Collation::C,
                     unique: false,
             }];
             let table = crate::types::Table::new("test".to_string(), columns);
@@ -596,4 +838,210 @@ mod tests {
             assert_eq!(table.rows[1].values[0], crate::types::Value::Integer(2));
         }
     }
+
+    #[test]
+    fn test_wal_serial_recovery() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let mut storage = StorageEngine::new(temp_dir.path()).unwrap();
+            let table = crate::types::Table::new(
+                "orders".to_string(),
+                vec![crate::types::Column {
+                    name: "id".to_string(),
+                    data_type: crate::types::DataType::Serial,
+                    nullable: false,
+                    primary_key: true,
+                    foreign_key: None,
+                    collation: Collation::C,
+                    unique: false,
+                }],
+            );
+            storage.log_create_table(&table).unwrap();
+            storage.log_serial_value("orders", "id", 41).unwrap();
+            storage.log_serial_value("orders", "id", 7).unwrap();
+        }
+
+        // After the crash the counter continues behind the highest value handed out
+        let storage = StorageEngine::new(temp_dir.path()).unwrap();
+        let db = storage.load_database("shop").unwrap();
+        let sequence = &db.get_table("orders").unwrap().sequences["id"];
+        assert_eq!(sequence.next_value(), 42);
+        assert_eq!(sequence.next_value(), 43);
+
+        // Snapshots keep the plain next value
+        let encoded = bincode::serialize(sequence).unwrap();
+        assert_eq!(bincode::deserialize::<i64>(&encoded).unwrap(), 44);
+    }
+
+    #[test]
+    fn test_wal_enum_recovery() {
+        let temp_dir = TempDir::new().unwrap();
+        let status = crate::types::DataType::Enum {
+            name: "status".to_string(),
+            values: vec!["active".to_string()],
+        };
+
+        {
+            let mut storage = StorageEngine::new(temp_dir.path()).unwrap();
+
+            storage.log_create_enum("status", &["active".to_string()]).unwrap();
+            let table = crate::types::Table::new(
+                "tasks".to_string(),
+                vec![crate::types::Column {
+                    name: "state".to_string(),
+                    data_type: status,
+                    nullable: true,
+                    primary_key: false,
+                    foreign_key: None,
+                    collation: Collation::C,
+                    unique: false,
+                }],
+            );
+            storage.log_create_table(&table).unwrap();
+            storage
+                .log_alter_enum("status", &["active".to_string(), "archived".to_string()])
+                .unwrap();
+            // Краш без checkpoint
+        }
+
+        let storage = StorageEngine::new(temp_dir.path()).unwrap();
+        let loaded_db = storage.load_database("enum_test").unwrap();
+
+        let expected = vec!["active".to_string(), "archived".to_string()];
+        assert_eq!(loaded_db.get_enum("status"), Some(&expected));
+        let column = &loaded_db.get_table("tasks").unwrap().columns[0];
+        assert_eq!(
+            column.data_type,
+            crate::types::DataType::Enum { name: "status".to_string(), values: expected }
+        );
+    }
+
+    #[test]
+    fn test_incremental_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let table = |name: &str| {
+            crate::types::Table::new(
+                name.to_string(),
+                vec![crate::types::Column {
+                    name: "id".to_string(),
+                    data_type: crate::types::DataType::Integer,
+                    nullable: false,
+                    primary_key: false,
+                    foreign_key: None,
+                    collation: Collation::C,
+                    unique: false,
+                }],
+            )
+        };
+        let checkpoint_dir = temp_dir.path().join(CHECKPOINT_DIR);
+
+        {
+            let mut storage = StorageEngine::new(temp_dir.path()).unwrap();
+            let mut instance = ServerInstance::initialize("postgres", "secret", "postgres");
+            instance.create_database("shop", "postgres").unwrap();
+            storage.create_checkpoint_instance(&instance.snapshot_exclusive()).unwrap();
+            assert!(checkpoint_dir.join("postgres.db").exists() && checkpoint_dir.join("shop.db").exists());
+
+            // Только помеченная БД пишется заново
+            instance.get_database_mut("shop").unwrap().create_table(table("orders")).unwrap();
+            instance.get_database_mut("postgres").unwrap().create_table(table("unmarked")).unwrap();
+            let (postgres, shop) = (fs::read(checkpoint_dir.join("postgres.db")).unwrap(), fs::read(checkpoint_dir.join("shop.db")).unwrap());
+            storage.mark_database_dirty("shop");
+            storage.create_checkpoint_instance(&instance.snapshot_exclusive()).unwrap();
+            assert_eq!(fs::read(checkpoint_dir.join("postgres.db")).unwrap(), postgres);
+            assert_ne!(fs::read(checkpoint_dir.join("shop.db")).unwrap(), shop);
+
+            // Удалённая БД исчезает из checkpoint'а
+            instance.drop_database("postgres").unwrap();
+            storage.create_checkpoint_instance(&instance.snapshot_exclusive()).unwrap();
+            assert!(!checkpoint_dir.join("postgres.db").exists());
+
+            // Записанное до checkpoint'а уже в нём, recovery повторяет только то, что после
+            storage.log_insert("orders", &Row::new(vec![crate::types::Value::Integer(1)])).unwrap();
+            storage.create_checkpoint_instance(&instance.snapshot_exclusive()).unwrap();
+            storage.log_insert("orders", &Row::new(vec![crate::types::Value::Integer(2)])).unwrap();
+        }
+
+        let storage = StorageEngine::new(temp_dir.path()).unwrap();
+        let mut instance = storage.load_server_instance().unwrap();
+        let mut names: Vec<_> = instance.databases.keys().collect();
+        names.sort();
+        assert_eq!(names, ["shop", "template1"]);
+        assert!(instance.users.contains_key("postgres"));
+        #[allow(deprecated)]
+        let rows = &instance.get_database_mut("shop").unwrap().get_table("orders").unwrap().rows;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values[0], crate::types::Value::Integer(2));
+    }
+
+    /// Каталог данных, записанный сервером 2.5.0 (формат версии 1):
+    /// `server_instance.db` без заголовка и WAL без заголовка
+    fn copy_v1_fixture() -> TempDir {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/v1_data_dir");
+        let temp_dir = TempDir::new().unwrap();
+        fs::copy(fixture.join("server_instance.db"), temp_dir.path().join("server_instance.db")).unwrap();
+        fs::create_dir(temp_dir.path().join("wal")).unwrap();
+        for entry in fs::read_dir(fixture.join("wal")).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, temp_dir.path().join("wal").join(path.file_name().unwrap())).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_load_v1_data_dir() {
+        let temp_dir = copy_v1_fixture();
+        let mut storage = StorageEngine::new(temp_dir.path()).unwrap();
+        let mut instance = storage.load_server_instance().unwrap();
+
+        // Пользователи: флаги превращаются в атрибуты, входить могут все
+        assert!(instance.users["postgres"].is_superuser());
+        let alice = &instance.users["alice"];
+        assert!(!alice.is_superuser() && !alice.can_create_db() && alice.can_login());
+        assert!(alice.verify_password("secret"));
+        assert!(!instance.database_metadata["postgres"].is_template);
+
+        let db = instance.get_database_mut("postgres").unwrap();
+        assert_eq!(db.oid, crate::types::Database::new(String::new()).oid);
+        assert_eq!(db.get_enum("mood").unwrap(), &["sad", "ok", "happy"]);
+        assert_eq!(db.views["adults"], "SELECT name, age FROM users WHERE age >= 18");
+        assert_eq!(db.table_metadata["users"].owner, "postgres");
+
+        // Таблицы из snapshot'а, строки и ADD COLUMN - из WAL
+        let users = db.get_table("users").unwrap();
+        let names: Vec<_> = users.columns.iter().map(|column| column.name.as_str()).collect();
+        assert_eq!(names, ["id", "name", "email", "age", "active", "feeling", "city"]);
+        assert!(users.columns.iter().all(|column| column.collation == Collation::default()));
+        assert_eq!(users.rows.len(), 3);
+        assert_eq!(users.rows[1].values[1], crate::types::Value::Text("Bob".to_string()));
+        assert_eq!(users.rows[1].values[2], crate::types::Value::Null);
+        assert_eq!(users.sequences["id"].peek(), 4);
+        let orders = db.get_table("orders").unwrap();
+        assert_eq!(orders.rows.len(), 100);
+        assert_eq!(orders.rows[99].values[3], crate::types::Value::Text("order 100".to_string()));
+
+        // Первый checkpoint пишет текущую версию и убирает файл версии 1
+        storage.create_checkpoint_instance(&instance.snapshot_exclusive()).unwrap();
+        assert!(!temp_dir.path().join("server_instance.db").exists());
+        let catalog = fs::read(temp_dir.path().join(CHECKPOINT_DIR).join(CATALOG_FILE)).unwrap();
+        assert_eq!(format::split(&catalog).unwrap().0, format::FORMAT_VERSION);
+
+        let mut reloaded = StorageEngine::new(temp_dir.path()).unwrap().load_server_instance().unwrap();
+        assert!(reloaded.users["alice"].can_login());
+        let db = reloaded.get_database_mut("postgres").unwrap();
+        assert_eq!(db.get_table("users").unwrap().rows.len(), 3);
+        assert_eq!(db.get_table("orders").unwrap().rows.len(), 100);
+    }
+
+    #[test]
+    fn test_newer_format_version_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut encoded = format::encode(&crate::types::Database::new("shop".to_string())).unwrap();
+        encoded[8..12].copy_from_slice(&(format::FORMAT_VERSION + 1).to_le_bytes());
+        fs::write(temp_dir.path().join("main.db"), encoded).unwrap();
+
+        let storage = StorageEngine::new(temp_dir.path()).unwrap();
+        assert!(matches!(storage.load_server_instance(), Err(DatabaseError::BinarySerialization(_))));
+    }
 }
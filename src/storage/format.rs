@@ -0,0 +1,285 @@
+//! v2.7.0: Версии формата файлов данных
+//!
+//! Snapshot'ы, checkpoint'ы и бинарные дампы начинаются с заголовка:
+//! `FILE_MAGIC` и номер версии (u32, little endian), дальше bincode. Bincode
+//! не пропускает и не дополняет поля, поэтому любое изменение сохраняемых
+//! типов - это новая версия и явная миграция со старой раскладки.
+//!
+//! Файлы без заголовка записаны до v2.7.0 - это версия 1. Они читаются через
+//! структуры модуля `v1` и переводятся в текущие типы.
+
+use crate::types::{Database, DatabaseError, ServerInstance};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const FILE_MAGIC: &[u8; 8] = b"PGRDATA\0";
+/// Текущая версия формата
+pub const FORMAT_VERSION: u32 = 2;
+/// Файлы без заголовка
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
+
+/// Сериализует значение с заголовком текущей версии
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, DatabaseError> {
+    let mut encoded = Vec::with_capacity(FILE_MAGIC.len() + 4);
+    encoded.extend_from_slice(FILE_MAGIC);
+    encoded.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bincode::serialize_into(&mut encoded, value).map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+    Ok(encoded)
+}
+
+/// Версия формата и данные после заголовка
+///
+/// Версии новее текущей (файлы более новой версии сервера) - ошибка.
+pub fn split(data: &[u8]) -> Result<(u32, &[u8]), DatabaseError> {
+    let Some(rest) = data.strip_prefix(FILE_MAGIC.as_slice()) else {
+        return Ok((LEGACY_FORMAT_VERSION, data));
+    };
+    let (version, payload) = rest.split_first_chunk::<4>().ok_or_else(|| {
+        DatabaseError::BinarySerialization("data file header is truncated".to_string())
+    })?;
+    let version = u32::from_le_bytes(*version);
+    if version != FORMAT_VERSION {
+        return Err(DatabaseError::BinarySerialization(format!(
+            "unsupported data file format version {version} (this server reads versions {LEGACY_FORMAT_VERSION} to {FORMAT_VERSION})"
+        )));
+    }
+    Ok((version, payload))
+}
+
+fn deserialize<T: DeserializeOwned>(data: &[u8]) -> Result<T, DatabaseError> {
+    bincode::deserialize(data).map_err(|e| DatabaseError::BinarySerialization(e.to_string()))
+}
+
+/// Читает файл, у которого нет версии 1 (каталог checkpoint'а)
+pub fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, DatabaseError> {
+    match split(data)? {
+        (FORMAT_VERSION, payload) => deserialize(payload),
+        _ => Err(DatabaseError::BinarySerialization("data file has no format header".to_string())),
+    }
+}
+
+/// Читает БД любой поддерживаемой версии
+pub fn decode_database(data: &[u8]) -> Result<Database, DatabaseError> {
+    match split(data)? {
+        (FORMAT_VERSION, payload) => deserialize(payload),
+        (_, payload) => deserialize::<v1::Database>(payload).map(Database::from),
+    }
+}
+
+/// Читает `ServerInstance` любой поддерживаемой версии
+pub fn decode_server_instance(data: &[u8]) -> Result<ServerInstance, DatabaseError> {
+    match split(data)? {
+        (FORMAT_VERSION, payload) => deserialize(payload),
+        (_, payload) => deserialize::<v1::ServerInstance>(payload).map(ServerInstance::from),
+    }
+}
+
+/// Раскладка версии 1 (до v2.7.0) и миграция в текущие типы
+///
+/// Типы, которые с тех пор не менялись (`Row`, `Value`, `DataType`, `Role`,
+/// `ForeignKey`, `Privilege`), используются как есть.
+pub mod v1 {
+    use crate::storage::wal;
+    use crate::types::{
+        self, Collation, DataType, ForeignKey, Privilege, Role, Row, SerialSequence, UserAttributes,
+    };
+    use serde::Deserialize;
+    use std::collections::{HashMap, HashSet};
+    use tokio::sync::RwLock;
+
+    #[derive(Deserialize)]
+    pub struct ServerInstance {
+        pub databases: HashMap<String, Database>,
+        pub database_metadata: HashMap<String, DatabaseMetadata>,
+        pub users: HashMap<String, User>,
+        pub roles: HashMap<String, Role>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Database {
+        pub name: String,
+        pub tables: HashMap<String, Table>,
+        pub enums: HashMap<String, Vec<String>>,
+        pub views: HashMap<String, String>,
+        pub table_metadata: HashMap<String, TableMetadata>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Table {
+        pub name: String,
+        pub columns: Vec<Column>,
+        pub rows: Vec<Row>,
+        pub sequences: HashMap<String, i64>,
+        pub owner: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Column {
+        pub name: String,
+        pub data_type: DataType,
+        pub nullable: bool,
+        pub primary_key: bool,
+        pub unique: bool,
+        pub foreign_key: Option<ForeignKey>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct DatabaseMetadata {
+        pub name: String,
+        pub owner: String,
+        pub privileges: HashMap<String, HashSet<Privilege>>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct TableMetadata {
+        pub table_name: String,
+        pub owner: String,
+        pub privileges: HashMap<String, HashSet<Privilege>>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct User {
+        pub username: String,
+        pub password_hash: String,
+        pub is_superuser: bool,
+        pub can_create_db: bool,
+        pub can_create_user: bool,
+        pub roles: HashSet<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct LogEntry {
+        pub sequence: u64,
+        pub timestamp: u64,
+        pub operation: Operation,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Operation {
+        CreateTable { table_name: String, table: Table },
+        DropTable { table_name: String },
+        Insert { table_name: String, row: Row },
+        Update { table_name: String, old_row_index: usize, new_row: Row },
+        Delete { table_name: String, row_index: usize },
+        Checkpoint { timestamp: u64 },
+        AlterTableAddColumn { table_name: String, column: Column },
+        AlterTableDropColumn { table_name: String, column_name: String },
+        AlterTableRenameColumn { table_name: String, old_name: String, new_name: String },
+        AlterTableRename { old_table_name: String, new_table_name: String },
+    }
+
+    impl From<ServerInstance> for types::ServerInstance {
+        fn from(old: ServerInstance) -> Self {
+            let mut instance = Self::new();
+            instance.databases = old
+                .databases
+                .into_iter()
+                .map(|(name, db)| (name, RwLock::new(db.into())))
+                .collect();
+            instance.database_metadata = old.database_metadata.into_iter().map(|(name, meta)| (name, meta.into())).collect();
+            instance.users = old.users.into_iter().map(|(name, user)| (name, user.into())).collect();
+            instance.roles = old.roles;
+            instance
+        }
+    }
+
+    impl From<Database> for types::Database {
+        /// OID остаётся 0 - его назначит `ServerInstance::assign_database_oids`
+        fn from(old: Database) -> Self {
+            let mut db = Self::new(old.name);
+            db.oid = 0;
+            db.tables = old.tables.into_iter().map(|(name, table)| (name, table.into())).collect();
+            db.enums = old.enums.into();
+            db.views = old.views;
+            db.table_metadata = old.table_metadata.into_iter().map(|(name, meta)| (name, meta.into())).collect();
+            db
+        }
+    }
+
+    impl From<Table> for types::Table {
+        #[allow(deprecated)]
+        fn from(old: Table) -> Self {
+            let columns = old.columns.into_iter().map(types::Column::from).collect();
+            let mut table = Self::new_with_owner(old.name, columns, old.owner);
+            table.rows = old.rows;
+            table.sequences = old
+                .sequences
+                .into_iter()
+                .map(|(column, next_value)| (column, SerialSequence::new(next_value)))
+                .collect();
+            table
+        }
+    }
+
+    impl From<Column> for types::Column {
+        fn from(old: Column) -> Self {
+            Self {
+                name: old.name,
+                data_type: old.data_type,
+                nullable: old.nullable,
+                primary_key: old.primary_key,
+                unique: old.unique,
+                foreign_key: old.foreign_key,
+                collation: Collation::default(),
+            }
+        }
+    }
+
+    impl From<DatabaseMetadata> for types::DatabaseMetadata {
+        fn from(old: DatabaseMetadata) -> Self {
+            Self { name: old.name, owner: old.owner, privileges: old.privileges, is_template: false }
+        }
+    }
+
+    impl From<TableMetadata> for types::TableMetadata {
+        fn from(old: TableMetadata) -> Self {
+            Self { table_name: old.table_name, owner: old.owner, privileges: old.privileges, clustered_on: None }
+        }
+    }
+
+    impl From<User> for types::User {
+        /// Входить могли все пользователи версии 1
+        fn from(old: User) -> Self {
+            let mut attributes = UserAttributes::LOGIN;
+            attributes.set(UserAttributes::SUPERUSER, old.is_superuser);
+            attributes.set(UserAttributes::CREATEDB, old.can_create_db);
+            attributes.set(UserAttributes::CREATEROLE, old.can_create_user);
+            Self {
+                username: old.username,
+                password_hash: old.password_hash,
+                attributes,
+                roles: old.roles,
+                valid_until: None,
+            }
+        }
+    }
+
+    impl From<LogEntry> for wal::LogEntry {
+        fn from(old: LogEntry) -> Self {
+            Self { sequence: old.sequence, timestamp: old.timestamp, operation: old.operation.into() }
+        }
+    }
+
+    impl From<Operation> for wal::Operation {
+        fn from(old: Operation) -> Self {
+            match old {
+                Operation::CreateTable { table_name, table } => Self::CreateTable { table_name, table: table.into() },
+                Operation::DropTable { table_name } => Self::DropTable { table_name },
+                Operation::Insert { table_name, row } => Self::Insert { table_name, row },
+                Operation::Update { table_name, old_row_index, new_row } => Self::Update { table_name, old_row_index, new_row },
+                Operation::Delete { table_name, row_index } => Self::Delete { table_name, row_index },
+                Operation::Checkpoint { timestamp } => Self::Checkpoint { timestamp },
+                Operation::AlterTableAddColumn { table_name, column } => {
+                    Self::AlterTableAddColumn { table_name, column: column.into() }
+                }
+                Operation::AlterTableDropColumn { table_name, column_name } => Self::AlterTableDropColumn { table_name, column_name },
+                Operation::AlterTableRenameColumn { table_name, old_name, new_name } => {
+                    Self::AlterTableRenameColumn { table_name, old_name, new_name }
+                }
+                Operation::AlterTableRename { old_table_name, new_table_name } => {
+                    Self::AlterTableRename { old_table_name, new_table_name }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+/// Log-structured merge store for `CREATE TABLE ... USING lsm` (v2.7.0)
+///
+/// Every row version gets its own, ever increasing key. Writes go to the
+/// memtable (a `BTreeMap`); once it holds `MEMTABLE_ROWS` entries it is frozen
+/// into an immutable run sorted by key. DELETE and UPDATE never touch a run:
+/// they write the changed row under the same key into the memtable, and on
+/// reads the newest entry for a key wins. When there are more than `MAX_RUNS`
+/// runs they are compacted (merged) into one; VACUUM compacts too and drops
+/// dead rows on the way.
+///
+/// Inserts never look for free space in existing pages, which makes them much
+/// cheaper than heap inserts. Reads pay for it by merging the memtable with
+/// every run. Runs are kept in memory, like the pages of temporary tables.
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use crate::types::{AccessMethod, DatabaseError, Row};
+use super::backend::StorageBackend;
+
+/// Memtable entries before it is frozen into a run
+pub const MEMTABLE_ROWS: usize = 1024;
+
+/// Runs allowed before they are compacted into one
+pub const MAX_RUNS: usize = 4;
+
+type Run = Vec<(u64, Row)>;
+
+#[derive(Debug, Default)]
+pub struct LsmStore {
+    memtable: BTreeMap<u64, Row>,
+    /// Immutable runs sorted by key, oldest first
+    runs: Vec<Run>,
+    next_key: u64,
+    /// Distinct keys (row versions) stored
+    rows: usize,
+}
+
+impl LsmStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Frozen runs not yet compacted
+    #[must_use]
+    pub const fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn put(&mut self, key: u64, row: Row) {
+        self.memtable.insert(key, row);
+        if self.memtable.len() >= MEMTABLE_ROWS {
+            let run = std::mem::take(&mut self.memtable).into_iter().collect();
+            self.runs.push(run);
+            if self.runs.len() > MAX_RUNS {
+                self.compact();
+            }
+        }
+    }
+
+    /// Merge every run into one (newest entry per key wins)
+    fn compact(&mut self) {
+        let merged = std::mem::take(&mut self.runs)
+            .into_iter()
+            .reduce(merge)
+            .unwrap_or_default();
+        self.runs.push(merged);
+    }
+
+    /// Current version of every key, in key order
+    fn merged(&self) -> Run {
+        let memtable: Run = self.memtable.iter().map(|(key, row)| (*key, row.clone())).collect();
+        self.runs
+            .iter()
+            .cloned()
+            .chain(std::iter::once(memtable))
+            .reduce(merge)
+            .unwrap_or_default()
+    }
+}
+
+/// Merge two runs sorted by key; on equal keys the entry from `newer` wins
+fn merge(older: Run, newer: Run) -> Run {
+    let mut out = Vec::with_capacity(older.len() + newer.len());
+    let mut older = older.into_iter().peekable();
+    let mut newer = newer.into_iter().peekable();
+
+    loop {
+        let next = match (older.peek(), newer.peek()) {
+            (Some((old_key, _)), Some((new_key, _))) => old_key.cmp(new_key),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match next {
+            Ordering::Less => out.extend(older.next()),
+            Ordering::Equal => {
+                older.next();
+                out.extend(newer.next());
+            }
+            Ordering::Greater => out.extend(newer.next()),
+        }
+    }
+    out
+}
+
+impl StorageBackend for LsmStore {
+    fn access_method(&self) -> AccessMethod {
+        AccessMethod::Lsm
+    }
+
+    fn insert(&mut self, row: Row) {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.rows += 1;
+        self.put(key, row);
+    }
+
+    fn row_count(&self) -> usize {
+        self.rows
+    }
+
+    /// The merged view is produced in one go
+    fn chunk_count(&self) -> usize {
+        1
+    }
+
+    /// Rows are stored whole, so every column is returned
+    fn chunk_rows(&self, idx: usize, _columns: Option<&[usize]>) -> Result<Vec<Row>, DatabaseError> {
+        if idx == 0 {
+            Ok(self.merged().into_iter().map(|(_, row)| row).collect())
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// The new xmax is written to the memtable under the row's key
+    fn delete_where(&mut self, predicate: &dyn Fn(&Row) -> bool, tx_id: u64) -> usize {
+        let deleted: Vec<(u64, Row)> = self
+            .merged()
+            .into_iter()
+            .filter(|(_, row)| predicate(row))
+            .collect();
+
+        let count = deleted.len();
+        for (key, mut row) in deleted {
+            row.mark_deleted(tx_id);
+            self.put(key, row);
+        }
+        count
+    }
+
+    fn update_where(&mut self, predicate: &dyn Fn(&Row) -> bool, updater: &dyn Fn(&Row) -> Row, tx_id: u64) -> usize {
+        let matching: Vec<(u64, Row)> = self
+            .merged()
+            .into_iter()
+            .filter(|(_, row)| predicate(row))
+            .collect();
+
+        let count = matching.len();
+        for (key, mut row) in matching {
+            let mut new_row = updater(&row);
+            new_row.xmin = tx_id;
+            new_row.xmax = None;
+
+            row.mark_deleted(tx_id);
+            self.put(key, row);
+            self.insert(new_row);
+        }
+        count
+    }
+
+    fn truncate(&mut self) {
+        self.memtable.clear();
+        self.runs.clear();
+        self.rows = 0;
+    }
+
+    /// Compacts everything into a single run without the dead rows
+    fn vacuum(&mut self, oldest_tx: u64) -> usize {
+        let (dead, live): (Run, Run) = self
+            .merged()
+            .into_iter()
+            .partition(|(_, row)| row.is_dead(oldest_tx));
+
+        self.memtable.clear();
+        self.runs = if live.is_empty() { Vec::new() } else { vec![live] };
+        self.rows -= dead.len();
+        dead.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{run_sql, QueryResult};
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::{Database, Value};
+
+    fn row(i: i64) -> Row {
+        Row::new_with_xmin(vec![Value::Integer(i), Value::Text(format!("event {i}"))], 1)
+    }
+
+    #[test]
+    fn test_flush_and_compaction() {
+        let mut store = LsmStore::new();
+        for i in 0..(MEMTABLE_ROWS * 3 + 10) {
+            store.insert(row(i as i64));
+        }
+        assert_eq!(store.run_count(), 3);
+        assert_eq!(store.row_count(), MEMTABLE_ROWS * 3 + 10);
+
+        // The fifth run triggers a compaction
+        for i in 0..(MEMTABLE_ROWS * 2) {
+            store.insert(row(i as i64));
+        }
+        assert_eq!(store.run_count(), 1);
+
+        let rows = store.get_all_rows().unwrap();
+        assert_eq!(rows.len(), MEMTABLE_ROWS * 5 + 10);
+        assert_eq!(rows[42].values[0], Value::Integer(42));
+    }
+
+    #[test]
+    fn test_newest_version_wins_and_vacuum() {
+        let mut store = LsmStore::new();
+        for i in 0..(MEMTABLE_ROWS + 100) {
+            store.insert(row(i as i64));
+        }
+
+        // Rows of the frozen run are changed through the memtable
+        let below_10 = |row: &Row| matches!(row.values[0], Value::Integer(i) if i < 10);
+        assert_eq!(store.delete_where(&below_10, 5), 10);
+        let last = |row: &Row| row.xmax.is_none() && matches!(row.values[0], Value::Integer(i) if i == (MEMTABLE_ROWS + 99) as i64);
+        let updated = store.update_where(&last, &|row| Row::new(vec![Value::Integer(-1), row.values[1].clone()]), 6);
+        assert_eq!(updated, 1);
+
+        let rows = store.get_all_rows().unwrap();
+        assert_eq!(rows.len(), MEMTABLE_ROWS + 101);
+        assert_eq!(rows.iter().filter(|row| row.xmax.is_some()).count(), 11);
+        assert_eq!(rows[3].xmax, Some(5));
+
+        assert_eq!(store.vacuum(10), 11);
+        let rows = store.get_all_rows().unwrap();
+        assert_eq!(rows.len(), MEMTABLE_ROWS + 90);
+        assert_eq!(store.row_count(), rows.len());
+        assert_eq!(rows.last().unwrap().values[0], Value::Integer(-1));
+        assert_eq!(store.run_count(), 1);
+    }
+
+    #[test]
+    fn test_lsm_table() {
+        // v2.7.0: CREATE TABLE ... USING lsm
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(100);
+        let tx_manager = GlobalTransactionManager::new();
+        let first = |result: QueryResult| match result {
+            QueryResult::Rows(rows, _) => rows[0][0].clone(),
+            QueryResult::Success(_) => panic!("Expected Rows result"),
+        };
+
+        run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE events (id INTEGER, kind TEXT) USING lsm").unwrap();
+        assert_eq!(storage.get_paged_table("events").unwrap().access_method(), crate::types::AccessMethod::Lsm);
+        assert!(run_sql(&mut db, &mut storage, &tx_manager, "CREATE TABLE parts (id INTEGER) PARTITION BY RANGE (id) USING lsm").is_err());
+
+        // Enough rows to freeze a run and keep writing to the memtable
+        for i in 0..1500 {
+            let sql = format!("INSERT INTO events VALUES ({i}, '{}')", if i % 2 == 0 { "click" } else { "view" });
+            run_sql(&mut db, &mut storage, &tx_manager, &sql).unwrap();
+        }
+
+        run_sql(&mut db, &mut storage, &tx_manager, "UPDATE events SET kind = 'purchase' WHERE id = 7").unwrap();
+        run_sql(&mut db, &mut storage, &tx_manager, "DELETE FROM events WHERE kind = 'click'").unwrap();
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM events").unwrap()), "750");
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT kind FROM events WHERE id = 7").unwrap()), "purchase");
+        assert_eq!(first(run_sql(&mut db, &mut storage, &tx_manager, "SELECT COUNT(*) FROM events WHERE kind = 'view'").unwrap()), "749");
+    }
+}
@@ -7,11 +7,26 @@ pub mod buffer_pool;
 pub mod page_manager;
 pub mod paged_table;
 pub mod database_storage;
+pub mod page_storage; // v2.7.0
+pub mod backend; // v2.7.0
+pub mod columnar; // v2.7.0
+pub mod lsm; // v2.7.0
+pub mod tempfile; // v2.7.0
+pub mod verify; // v2.7.0
+pub mod version_counts; // v2.7.0
+pub mod format; // v2.7.0
 
 pub use disk::StorageEngine;
 pub use wal::{Operation, WalManager};
 pub use page::{Page, PageId, PageHeader, PAGE_SIZE};
-pub use buffer_pool::BufferPool;
-pub use page_manager::{PageManager, BufferPoolStats};
-pub use paged_table::{PagedTable, PagedTableStats};
-pub use database_storage::DatabaseStorage;
+pub use buffer_pool::{BufferPool, ShardedBufferPool};
+pub use page_manager::{PageManager, BufferPoolStats, TEMP_TABLE_ID_BASE};
+pub use paged_table::{PagedTable, PagedTableScan, PagedTableStats, RowFetch, ScanPredicate};
+pub use database_storage::{DatabaseStorage, DEFAULT_WORK_MEM};
+pub use page_storage::PageStorage;
+pub use backend::StorageBackend;
+pub use columnar::ColumnarStore;
+pub use lsm::LsmStore;
+pub use tempfile::{TempFile, TempFiles};
+pub use verify::{check_data_dir, Corruption, DataDirReport};
+pub use version_counts::VersionCounts;
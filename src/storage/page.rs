@@ -1,9 +1,17 @@
 use serde::{Deserialize, Serialize};
-use crate::types::{DatabaseError, Row};
+use crate::types::{Ctid, DatabaseError, Row};
+use super::wal::crc32;
 
 /// Page size (8 KB, same as `PostgreSQL`)
 pub const PAGE_SIZE: usize = 8192;
 
+/// v2.7.0: Byte range of `PageHeader::checksum` in the serialized page
+/// (bincode writes the fixed-size header fields first, in declaration order)
+const CHECKSUM_RANGE: std::ops::Range<usize> = 16..20;
+
+/// v2.7.0: Bytes a row grows by when its xmax is set (`None` -> `Some(u64)`)
+pub const XMAX_GROWTH: usize = 8;
+
 /// Page ID - uniquely identifies a page
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PageId {
@@ -42,7 +50,8 @@ pub struct PageHeader {
     pub lower: u16,
     /// Upper bound of free space (grows downward)
     pub upper: u16,
-    /// Checksum for integrity check (not implemented yet)
+    /// v2.7.0: CRC32 of the serialized page with this field zeroed, set when
+    /// the page is written to disk (0 = written before checksums existed)
     pub checksum: u32,
 }
 
@@ -164,8 +173,10 @@ impl Page {
         let length = slot.length as usize;
         let row_bytes = &self.data[offset..offset + length];
 
-        bincode::deserialize(row_bytes)
-            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))
+        let mut row: Row = bincode::deserialize(row_bytes)
+            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+        row.ctid = Some(Ctid { page: self.header.page_id.page_number, slot: slot_index });  // v2.7.0
+        Ok(row)
     }
 
     /// Mark a row as deleted (doesn't reclaim space)
@@ -197,11 +208,58 @@ impl Page {
             let offset = slot.offset as usize;
             self.data[offset..offset + row_bytes.len()].copy_from_slice(&row_bytes);
             slot.length = row_bytes.len() as u16;
-            Ok(true)
-        } else {
-            // Doesn't fit - caller needs to delete and insert elsewhere
-            Ok(false)
+            return Ok(true);
+        }
+
+        // v2.7.0: A row that grew (e.g. once xmax is set) moves within the
+        // page, so its slot - and its position in the table - stays the same
+        if usize::from(self.free_space()) < row_bytes.len() {
+            let used: usize = self.slots.iter().filter(|slot| slot.is_used).map(|slot| usize::from(slot.length)).sum();
+            let available = PAGE_SIZE - usize::from(self.header.lower);
+            let old_length = usize::from(self.slots[slot_index as usize].length);
+            if used - old_length + row_bytes.len() > available {
+                // Doesn't fit - caller needs to delete and insert elsewhere
+                return Ok(false);
+            }
+            self.slots[slot_index as usize].is_used = false;
+            self.compact();
+        }
+        let offset = self.header.upper - row_bytes.len() as u16;
+        self.data[offset as usize..offset as usize + row_bytes.len()].copy_from_slice(&row_bytes);
+        self.slots[slot_index as usize] = Slot { offset, length: row_bytes.len() as u16, is_used: true };
+        self.header.upper = offset;
+        self.header.free_space = self.free_space();
+        Ok(true)
+    }
+
+    /// v2.7.0: Can this page take a new row of `row_size` bytes and still
+    /// set xmax on each of its rows in place (see `XMAX_GROWTH`)?
+    #[must_use]
+    pub const fn can_fit_version(&self, row_size: usize) -> bool {
+        self.can_fit(row_size + XMAX_GROWTH * (self.slots.len() + 1))
+    }
+
+    /// v2.7.0: Pack the rows of used slots against the end of the page,
+    /// reclaiming the space of deleted and moved rows; slots keep their index
+    fn compact(&mut self) {
+        let rows: Vec<(usize, Vec<u8>)> = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_used)
+            .map(|(idx, slot)| {
+                let offset = usize::from(slot.offset);
+                (idx, self.data[offset..offset + usize::from(slot.length)].to_vec())
+            })
+            .collect();
+        let mut upper = PAGE_SIZE as u16;
+        for (idx, bytes) in rows {
+            upper -= bytes.len() as u16;
+            self.data[upper as usize..upper as usize + bytes.len()].copy_from_slice(&bytes);
+            self.slots[idx].offset = upper;
         }
+        self.header.upper = upper;
+        self.header.free_space = self.free_space();
     }
 
     /// Get all rows in this page
@@ -215,16 +273,60 @@ impl Page {
             .collect()
     }
 
-    /// Serialize page to bytes for disk storage
+    /// Serialize page to bytes for disk storage, with its checksum filled in
     pub fn to_bytes(&self) -> Result<Vec<u8>, DatabaseError> {
-        bincode::serialize(self)
-            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))
+        let mut bytes = bincode::serialize(self)
+            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+        let checksum = Self::checksum_of(&bytes)?;
+        bytes[CHECKSUM_RANGE].copy_from_slice(&checksum.to_le_bytes());
+        Ok(bytes)
     }
 
-    /// Deserialize page from bytes
+    /// Deserialize page from bytes, rejecting a page whose checksum does not match
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatabaseError> {
-        bincode::deserialize(bytes)
-            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))
+        let page: Self = bincode::deserialize(bytes)
+            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+        let checksum = Self::checksum_of(bytes)?;
+        if page.header.checksum != 0 && page.header.checksum != checksum {
+            return Err(DatabaseError::BinarySerialization(format!(
+                "page {} of table {}: checksum mismatch (stored {:08x}, computed {checksum:08x})",
+                page.header.page_id.page_number, page.header.page_id.table_id, page.header.checksum
+            )));
+        }
+        Ok(page)
+    }
+
+    /// v2.7.0: CRC32 of serialized page bytes, skipping the checksum field
+    fn checksum_of(bytes: &[u8]) -> Result<u32, DatabaseError> {
+        if bytes.len() < CHECKSUM_RANGE.end {
+            return Err(DatabaseError::BinarySerialization("page is truncated".to_string()));
+        }
+        Ok(crc32(&[&bytes[..CHECKSUM_RANGE.start], &[0; 4], &bytes[CHECKSUM_RANGE.end..]]))
+    }
+
+    /// v2.7.0: Structural problems of a page read from disk: slots outside
+    /// the row area, free space bounds out of order, rows that do not decode
+    #[must_use]
+    pub fn structural_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.data.len() != PAGE_SIZE {
+            problems.push(format!("data is {} bytes, expected {PAGE_SIZE}", self.data.len()));
+        }
+        if usize::from(self.header.slot_count) != self.slots.len() {
+            problems.push(format!("header counts {} slots, page has {}", self.header.slot_count, self.slots.len()));
+        }
+        if self.header.lower > self.header.upper || usize::from(self.header.upper) > PAGE_SIZE {
+            problems.push(format!("free space bounds out of order (lower {}, upper {})", self.header.lower, self.header.upper));
+        }
+        for (idx, slot) in self.slots.iter().enumerate().filter(|(_, slot)| slot.is_used) {
+            let end = usize::from(slot.offset) + usize::from(slot.length);
+            if slot.offset < self.header.upper || end > self.data.len() {
+                problems.push(format!("slot {idx} points outside the page ({}..{end})", slot.offset));
+            } else if self.get_row(idx as u16).is_err() {
+                problems.push(format!("slot {idx} does not hold a readable row"));
+            }
+        }
+        problems
     }
 }
 
@@ -302,6 +404,29 @@ mod tests {
         assert_eq!(retrieved.values[1], Value::Text("Bob".to_string()));
     }
 
+    #[test]
+    fn test_update_row_grows() {
+        let mut page = Page::new(PageId::new(1, 0));
+        let mut rows = Vec::new();
+        while page.can_fit_version(20) {
+            let row = Row::new_with_xmin(vec![Value::Integer(rows.len() as i64)], 1);
+            page.insert_row(&row).unwrap();
+            rows.push(row);
+        }
+
+        // Every row can still be marked deleted, and keeps its slot
+        for (idx, row) in rows.iter_mut().enumerate() {
+            row.mark_deleted(2);
+            assert!(page.update_row(idx as u16, row).unwrap());
+        }
+        page.delete_row(0).unwrap();
+        let all_rows = page.get_all_rows();
+        assert_eq!(all_rows.len(), rows.len() - 1);
+        assert!(all_rows.iter().all(|row| row.xmax == Some(2)));
+        assert_eq!(page.get_row(1).unwrap().values, vec![Value::Integer(1)]);
+        assert!(page.structural_problems().is_empty());
+    }
+
     #[test]
     fn test_serialization() {
         let page_id = PageId::new(1, 0);
@@ -317,4 +442,28 @@ mod tests {
         assert_eq!(deserialized.header.slot_count, 1);
         assert_eq!(deserialized.get_row(0).unwrap().values, row.values);
     }
+
+    #[test]
+    fn test_checksum() {
+        let mut page = Page::new(PageId::new(1, 0));
+        page.insert_row(&Row::new(vec![Value::Integer(42)])).unwrap();
+
+        let mut bytes = page.to_bytes().unwrap();
+        let stored = Page::from_bytes(&bytes).unwrap();
+        assert_ne!(stored.header.checksum, 0);
+        assert!(stored.structural_problems().is_empty());
+
+        // A flipped bit in the row data is caught
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        assert!(Page::from_bytes(&bytes).is_err());
+
+        // Pages written before checksums existed still load
+        let legacy = bincode::serialize(&page).unwrap();
+        assert_eq!(Page::from_bytes(&legacy).unwrap().header.checksum, 0);
+
+        // Slots pointing past the page are structural damage
+        page.slots[0].length = u16::MAX;
+        assert_eq!(page.structural_problems().len(), 1);
+    }
 }
@@ -1,17 +1,26 @@
+use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 use super::page::{Page, PageId};
-use super::buffer_pool::BufferPool;
+use super::buffer_pool::ShardedBufferPool;
 use crate::types::DatabaseError;
 
+/// v2.7.0: Table IDs at or above this value belong to temporary tables,
+/// whose pages never touch the disk
+pub const TEMP_TABLE_ID_BASE: u32 = 0x8000_0000;
+
 /// `PageManager` - manages disk I/O for pages
 pub struct PageManager {
-    /// Root data directory
-    data_dir: PathBuf,
-    /// Buffer pool for caching pages
-    buffer_pool: Arc<Mutex<BufferPool>>,
+    /// Root data directory (v2.7.0: `None` = in-memory mode, no page files)
+    data_dir: Option<PathBuf>,
+    /// Buffer pool for caching pages (v2.7.0: sharded by page id)
+    buffer_pool: ShardedBufferPool,
+    /// v2.7.0: Backing store for pages that never touch the disk
+    /// (temporary tables, every table in in-memory mode), one map per
+    /// buffer pool shard
+    memory_pages: Vec<Mutex<HashMap<PageId, Page>>>,
 }
 
 impl PageManager {
@@ -20,22 +29,58 @@ impl PageManager {
         let data_dir = data_dir.as_ref().to_path_buf();
         fs::create_dir_all(&data_dir)?;
 
-        Ok(Self {
+        Ok(Self::with_data_dir(Some(data_dir), buffer_pool_size))
+    }
+
+    /// v2.7.0: `PageManager` that keeps every page in memory
+    #[must_use]
+    pub fn in_memory(buffer_pool_size: usize) -> Self {
+        Self::with_data_dir(None, buffer_pool_size)
+    }
+
+    fn with_data_dir(data_dir: Option<PathBuf>, buffer_pool_size: usize) -> Self {
+        let buffer_pool = ShardedBufferPool::new(buffer_pool_size);
+        let memory_pages = (0..buffer_pool.shard_count()).map(|_| Mutex::new(HashMap::new())).collect();
+        Self {
             data_dir,
-            buffer_pool: Arc::new(Mutex::new(BufferPool::new(buffer_pool_size))),
-        })
+            buffer_pool,
+            memory_pages,
+        }
+    }
+
+    /// v2.7.0: Data directory of the page files (`None` if every page lives in memory)
+    #[must_use]
+    pub fn data_dir(&self) -> Option<&Path> {
+        self.data_dir.as_deref()
     }
 
-    /// Get path to page file
-    fn get_page_path(&self, page_id: PageId) -> PathBuf {
-        let table_dir = self.data_dir.join(format!("table_{}", page_id.table_id));
-        fs::create_dir_all(&table_dir).ok();
-        table_dir.join(format!("page_{:08}.dat", page_id.page_number))
+    /// v2.7.0: In-memory pages of the shard holding `page_id`
+    fn memory_shard(&self, page_id: PageId) -> std::sync::MutexGuard<'_, HashMap<PageId, Page>> {
+        self.memory_pages[self.buffer_pool.shard_index(page_id)].lock().unwrap()
+    }
+
+    /// v2.7.0: Does this table ID belong to a temporary table?
+    #[must_use]
+    pub const fn is_temp_table(table_id: u32) -> bool {
+        table_id >= TEMP_TABLE_ID_BASE
+    }
+
+    /// v2.7.0: Directory holding the page files of a table (`None` if its pages live in memory)
+    fn table_dir(&self, table_id: u32) -> Option<PathBuf> {
+        if Self::is_temp_table(table_id) {
+            return None;
+        }
+        self.data_dir.as_ref().map(|data_dir| data_dir.join(format!("table_{table_id}")))
     }
 
     /// Read a page from disk
     fn read_page_from_disk(&self, page_id: PageId) -> Result<Page, DatabaseError> {
-        let path = self.get_page_path(page_id);
+        let Some(table_dir) = self.table_dir(page_id.table_id) else {
+            let memory_pages = self.memory_shard(page_id);
+            return Ok(memory_pages.get(&page_id).cloned().unwrap_or_else(|| Page::new(page_id)));
+        };
+
+        let path = table_dir.join(format!("page_{:08}.dat", page_id.page_number));
 
         if !path.exists() {
             // Page doesn't exist - create new empty page
@@ -51,7 +96,13 @@ impl PageManager {
 
     /// Write a page to disk
     fn write_page_to_disk(&self, page: &Page) -> Result<(), DatabaseError> {
-        let path = self.get_page_path(page.header.page_id);
+        let Some(table_dir) = self.table_dir(page.header.page_id.table_id) else {
+            self.memory_shard(page.header.page_id).insert(page.header.page_id, page.clone());
+            return Ok(());
+        };
+
+        fs::create_dir_all(&table_dir)?;
+        let path = table_dir.join(format!("page_{:08}.dat", page.header.page_id.page_number));
 
         let bytes = page.to_bytes()?;
         let mut file = OpenOptions::new()
@@ -68,7 +119,7 @@ impl PageManager {
 
     /// Get a page (from buffer pool or disk)
     pub fn get_page(&self, page_id: PageId) -> Result<Page, DatabaseError> {
-        let mut pool = self.buffer_pool.lock().unwrap();
+        let mut pool = self.buffer_pool.shard(page_id);
 
         // Try buffer pool first
         if let Some(page) = pool.get_page(page_id) {
@@ -80,7 +131,7 @@ impl PageManager {
         let page = self.read_page_from_disk(page_id)?;
 
         // Insert into buffer pool
-        let mut pool = self.buffer_pool.lock().unwrap();
+        let mut pool = self.buffer_pool.shard(page_id);
         if let Some(evicted_page_id) = pool.insert_page(page.clone())? {
             // Need to write evicted page if it's dirty
             if pool.get_dirty_pages().contains(&evicted_page_id)
@@ -107,7 +158,7 @@ impl PageManager {
 
     /// Flush a specific page to disk
     pub fn flush_page(&self, page_id: PageId) -> Result<(), DatabaseError> {
-        let mut pool = self.buffer_pool.lock().unwrap();
+        let mut pool = self.buffer_pool.shard(page_id);
 
         if let Some(page) = pool.get_page(page_id) {
             let page_clone = page.clone();
@@ -122,16 +173,19 @@ impl PageManager {
 
     /// Flush all dirty pages to disk (checkpoint)
     pub fn checkpoint(&self) -> Result<usize, DatabaseError> {
-        let mut pool = self.buffer_pool.lock().unwrap();
-        let dirty_pages = pool.flush_all();
-        let count = dirty_pages.len();
+        let mut count = 0;
 
-        pool.clear_all_dirty();
-        drop(pool);
+        // v2.7.0: One shard at a time, so other shards stay usable meanwhile
+        for mut pool in self.buffer_pool.each_shard() {
+            let dirty_pages = pool.flush_all();
+            pool.clear_all_dirty();
+            drop(pool);
 
-        // Write all dirty pages
-        for (_page_id, page) in dirty_pages {
-            self.write_page_to_disk(&page)?;
+            // Write all dirty pages
+            count += dirty_pages.len();
+            for (_page_id, page) in dirty_pages {
+                self.write_page_to_disk(&page)?;
+            }
         }
 
         Ok(count)
@@ -146,8 +200,7 @@ impl PageManager {
         self.write_page_to_disk(&page)?;
 
         // Add to buffer pool
-        let mut pool = self.buffer_pool.lock().unwrap();
-        pool.insert_page(page)?;
+        self.buffer_pool.shard(page_id).insert_page(page)?;
 
         Ok(page_id)
     }
@@ -155,7 +208,13 @@ impl PageManager {
     /// Get number of pages for a table
     #[must_use] 
     pub fn get_page_count(&self, table_id: u32) -> usize {
-        let table_dir = self.data_dir.join(format!("table_{table_id}"));
+        let Some(table_dir) = self.table_dir(table_id) else {
+            return self
+                .memory_pages
+                .iter()
+                .map(|shard| shard.lock().unwrap().keys().filter(|id| id.table_id == table_id).count())
+                .sum();
+        };
 
         if !table_dir.exists() {
             return 0;
@@ -178,40 +237,53 @@ impl PageManager {
 
     /// Delete all pages for a table
     pub fn delete_table_pages(&self, table_id: u32) -> Result<(), DatabaseError> {
-        let table_dir = self.data_dir.join(format!("table_{table_id}"));
+        // v2.7.0: Every buffered page goes first, dirty ones included, so no
+        // later flush writes the table back to disk
+        for mut pool in self.buffer_pool.each_shard() {
+            pool.remove_table(table_id);
+        }
+
+        let Some(table_dir) = self.table_dir(table_id) else {
+            for memory_pages in &self.memory_pages {
+                memory_pages.lock().unwrap().retain(|page_id, _| page_id.table_id != table_id);
+            }
+            return Ok(());
+        };
 
         if table_dir.exists() {
             fs::remove_dir_all(&table_dir)?;
         }
 
-        // Remove from buffer pool
-        let mut pool = self.buffer_pool.lock().unwrap();
-        let page_count = self.get_page_count(table_id);
-        for page_number in 0..page_count as u32 {
-            let page_id = PageId::new(table_id, page_number);
-            pool.remove_page(page_id);
-        }
-
         Ok(())
     }
 
     /// Get buffer pool statistics
     #[must_use] 
     pub fn get_stats(&self) -> BufferPoolStats {
-        let pool = self.buffer_pool.lock().unwrap();
-        BufferPoolStats {
-            size: pool.size(),
-            dirty_count: pool.dirty_count(),
-            hit_rate: pool.hit_rate(),
-            hits: pool.hits,
-            misses: pool.misses,
+        let mut stats = BufferPoolStats {
+            size: 0,
+            dirty_count: 0,
+            hit_rate: 0.0,
+            hits: 0,
+            misses: 0,
+        };
+        for pool in self.buffer_pool.each_shard() {
+            stats.size += pool.size();
+            stats.dirty_count += pool.dirty_count();
+            stats.hits += pool.hits;
+            stats.misses += pool.misses;
         }
+        let total = stats.hits + stats.misses;
+        if total > 0 {
+            stats.hit_rate = stats.hits as f64 / total as f64;
+        }
+        stats
     }
 
     /// Get reference to buffer pool (for advanced operations)
-    #[must_use] 
-    pub fn buffer_pool(&self) -> Arc<Mutex<BufferPool>> {
-        Arc::clone(&self.buffer_pool)
+    #[must_use]
+    pub const fn buffer_pool(&self) -> &ShardedBufferPool {
+        &self.buffer_pool
     }
 }
 
@@ -227,7 +299,7 @@ impl PageMutGuard<'_> {
     where
         F: FnOnce(&mut Page) -> Result<R, DatabaseError>,
     {
-        let mut pool = self.page_manager.buffer_pool.lock().unwrap();
+        let mut pool = self.page_manager.buffer_pool.shard(self.page_id);
 
         if let Some(page) = pool.get_page_mut(self.page_id) {
             f(page)
@@ -359,10 +431,20 @@ mod tests {
 
         assert_eq!(pm.get_page_count(1), 3);
 
+        // v2.7.0: A dirty page still in the buffer pool is not written back later
+        let guard = pm.get_page_mut(PageId::new(1, 0)).unwrap();
+        guard.get_mut(|page| {
+            page.insert_row(&Row::new(vec![Value::Integer(1)]))?;
+            Ok(())
+        }).unwrap();
+        drop(guard);
+
         // Delete table
         pm.delete_table_pages(1).unwrap();
 
         assert_eq!(pm.get_page_count(1), 0);
+        assert_eq!(pm.checkpoint().unwrap(), 0);
+        assert!(!temp_dir.path().join("table_1").exists());
     }
 
     #[test]
@@ -393,4 +475,27 @@ mod tests {
         assert_eq!(rows.len(), 1);
         assert_eq!(rows[0].values[0], Value::Text("persistent".to_string()));
     }
+
+    #[test]
+    fn test_shards_lock_independently() {
+        let pm = std::sync::Arc::new(PageManager::in_memory(64));
+        let first = PageId::new(1, 0);
+        let other = (0..64)
+            .map(|i| PageId::new(2, i))
+            .find(|id| pm.buffer_pool().shard_index(*id) != pm.buffer_pool().shard_index(first))
+            .unwrap();
+        pm.create_page(first.table_id, first.page_number).unwrap();
+        pm.create_page(other.table_id, other.page_number).unwrap();
+
+        // A page of another shard is readable while this shard is locked
+        let held = pm.buffer_pool().shard(first);
+        let reader = {
+            let pm = std::sync::Arc::clone(&pm);
+            std::thread::spawn(move || pm.get_page(other).map(|page| page.header.page_id))
+        };
+        assert_eq!(reader.join().unwrap().unwrap(), other);
+        drop(held);
+
+        assert_eq!(pm.get_stats().size, 2);
+    }
 }
@@ -0,0 +1,249 @@
+/// Page storage of every database on the server (v2.7.0)
+///
+/// Each database gets its own `DatabaseStorage`: its own page manager,
+/// buffer pool, table ids and table name lookup, with page files under
+/// `data_dir/base/<database>/table_<id>/`. Two databases can therefore both
+/// have a `users` table without sharing pages. A database's storage is set
+/// up the first time it is used. Temp files of all databases stay in
+/// `data_dir/pgsql_tmp`.
+///
+/// Data directories written before this layout keep their page files
+/// directly in `data_dir/table_<id>/`; `migrate_legacy_layout` moves them
+/// into the directory of the database they belong to.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use crate::types::DatabaseError;
+use super::database_storage::{DatabaseStorage, DEFAULT_WORK_MEM};
+use super::page_manager::PageManager;
+use super::tempfile::TempFiles;
+
+/// Directory of the per-database page directories inside the data directory
+pub const BASE_DIR: &str = "base";
+
+pub struct PageStorage {
+    /// Data directory (`None`: every page lives in memory)
+    data_dir: Option<PathBuf>,
+    /// Pages each database's buffer pool holds
+    buffer_pool_size: usize,
+    /// Settings given to every database's storage
+    work_mem: usize,
+    max_query_memory: usize,
+    max_result_rows: usize,
+    vectorized: bool,
+    /// Temp files shared by every database
+    temp_files: Option<TempFiles>,
+    /// Storages set up so far, by database name
+    databases: Mutex<HashMap<String, Arc<RwLock<DatabaseStorage>>>>,
+}
+
+impl PageStorage {
+    /// Page storage under `data_dir`
+    pub fn new<P: AsRef<Path>>(data_dir: P, buffer_pool_size: usize) -> Result<Self, DatabaseError> {
+        let data_dir = data_dir.as_ref().to_path_buf();
+        fs::create_dir_all(data_dir.join(BASE_DIR))?;
+        let temp_files = Some(TempFiles::open(&data_dir));
+        Ok(Self::with_data_dir(Some(data_dir), buffer_pool_size, temp_files))
+    }
+
+    /// Page storage that never touches the disk
+    #[must_use]
+    pub fn in_memory(buffer_pool_size: usize) -> Self {
+        Self::with_data_dir(None, buffer_pool_size, None)
+    }
+
+    fn with_data_dir(data_dir: Option<PathBuf>, buffer_pool_size: usize, temp_files: Option<TempFiles>) -> Self {
+        Self {
+            data_dir,
+            buffer_pool_size,
+            work_mem: DEFAULT_WORK_MEM,
+            max_query_memory: 0,
+            max_result_rows: 0,
+            vectorized: false,
+            temp_files,
+            databases: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Directory of the page files of `database` under `data_dir`
+    #[must_use]
+    pub fn database_dir(data_dir: &Path, database: &str) -> PathBuf {
+        data_dir.join(BASE_DIR).join(database)
+    }
+
+    /// Storage of `database`, set up on first use
+    pub fn database(&self, database: &str) -> Result<Arc<RwLock<DatabaseStorage>>, DatabaseError> {
+        let mut databases = self.lock();
+        if let Some(storage) = databases.get(database) {
+            return Ok(Arc::clone(storage));
+        }
+
+        let page_manager = match &self.data_dir {
+            Some(data_dir) => PageManager::new(Self::database_dir(data_dir, database), self.buffer_pool_size)?,
+            None => PageManager::in_memory(self.buffer_pool_size),
+        };
+        let mut storage = DatabaseStorage::with_page_manager(page_manager, self.temp_files.clone());
+        storage.set_work_mem(self.work_mem);
+        storage.set_max_query_memory(self.max_query_memory);
+        storage.set_max_result_rows(self.max_result_rows);
+        storage.set_vectorized(self.vectorized);
+        let storage = Arc::new(RwLock::new(storage));
+        databases.insert(database.to_string(), Arc::clone(&storage));
+        Ok(storage)
+    }
+
+    /// Storages set up so far, by database name
+    #[must_use]
+    pub fn databases(&self) -> Vec<(String, Arc<RwLock<DatabaseStorage>>)> {
+        let mut databases: Vec<_> =
+            self.lock().iter().map(|(name, storage)| (name.clone(), Arc::clone(storage))).collect();
+        databases.sort_by(|a, b| a.0.cmp(&b.0));
+        databases
+    }
+
+    /// Forget the storage of a dropped database and remove its page files
+    ///
+    /// Pages still in its buffer pool are thrown away, dirty or not.
+    pub fn drop_database(&self, database: &str) -> Result<(), DatabaseError> {
+        self.lock().remove(database);
+        if let Some(data_dir) = &self.data_dir {
+            let dir = Self::database_dir(data_dir, database);
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `work_mem` (bytes) of the storages set up from now on
+    pub const fn set_work_mem(&mut self, bytes: usize) {
+        self.work_mem = bytes;
+    }
+
+    /// Set `max_query_memory` (bytes, 0 = unlimited) of the storages set up from now on
+    pub const fn set_max_query_memory(&mut self, bytes: usize) {
+        self.max_query_memory = bytes;
+    }
+
+    /// Set `max_result_rows` (0 = unlimited) of the storages set up from now on
+    pub const fn set_max_result_rows(&mut self, rows: usize) {
+        self.max_result_rows = rows;
+    }
+
+    /// Bytes the temp files of one query may hold (`temp_file_limit`, `None` =
+    /// no limit), in every database
+    pub fn set_temp_file_limit(&self, bytes: Option<usize>) {
+        if let Some(temp_files) = &self.temp_files {
+            temp_files.set_limit(bytes);
+        }
+    }
+
+    /// Turn batched evaluation on or off for the storages set up from now on
+    pub const fn set_vectorized(&mut self, enabled: bool) {
+        self.vectorized = enabled;
+    }
+
+    /// Move page files of the old layout (`data_dir/table_<id>/`) into the
+    /// directory of `database`; returns how many tables were moved
+    ///
+    /// Call it before `database` is used. A table whose directory already
+    /// exists under `database` is left where it is.
+    pub fn migrate_legacy_layout(&self, database: &str) -> Result<usize, DatabaseError> {
+        let Some(data_dir) = &self.data_dir else {
+            return Ok(0);
+        };
+        let legacy: Vec<(String, PathBuf)> = fs::read_dir(data_dir)?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                name.strip_prefix("table_")?.parse::<u32>().ok()?;
+                Some((name, entry.path()))
+            })
+            .collect();
+        if legacy.is_empty() {
+            return Ok(0);
+        }
+
+        let target = Self::database_dir(data_dir, database);
+        fs::create_dir_all(&target)?;
+        let mut moved = 0;
+        for (name, path) in legacy {
+            let destination = target.join(&name);
+            if !destination.exists() {
+                fs::rename(path, destination)?;
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<RwLock<DatabaseStorage>>>> {
+        self.databases.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Row, Value};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_databases_are_isolated() {
+        let temp_dir = TempDir::new().unwrap();
+        let pages = PageStorage::new(temp_dir.path(), 16).unwrap();
+
+        let shop = pages.database("shop").unwrap();
+        let blog = pages.database("blog").unwrap();
+        assert!(Arc::ptr_eq(&shop, &pages.database("shop").unwrap()));
+        {
+            let mut shop = shop.blocking_write();
+            let mut blog = blog.blocking_write();
+            shop.create_table("users".to_string()).unwrap();
+            blog.create_table("users".to_string()).unwrap();
+            shop.insert("users", Row::new(vec![Value::Text("alice".to_string())])).unwrap();
+            blog.insert("users", Row::new(vec![Value::Text("bob".to_string())])).unwrap();
+            blog.insert("users", Row::new(vec![Value::Text("carol".to_string())])).unwrap();
+            assert_eq!(shop.row_count("users"), Some(1));
+            assert_eq!(blog.row_count("users"), Some(2));
+            shop.checkpoint().unwrap();
+            blog.checkpoint().unwrap();
+        }
+        let shop_dir = PageStorage::database_dir(temp_dir.path(), "shop");
+        assert!(shop_dir.join("table_1").join("page_00000000.dat").exists());
+        assert!(PageStorage::database_dir(temp_dir.path(), "blog").join("table_1").exists());
+        let names: Vec<String> = pages.databases().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["blog", "shop"]);
+
+        pages.drop_database("shop").unwrap();
+        assert!(!shop_dir.exists());
+        assert_eq!(pages.databases().len(), 1);
+        assert_eq!(blog.blocking_read().row_count("users"), Some(2));
+        // A database created again under the same name starts empty
+        assert!(pages.database("shop").unwrap().blocking_read().list_tables().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_legacy_layout() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let mut legacy = DatabaseStorage::new(temp_dir.path(), 16).unwrap();
+            legacy.create_table("users".to_string()).unwrap();
+            legacy.insert("users", Row::new(vec![Value::Integer(1)])).unwrap();
+            legacy.checkpoint().unwrap();
+        }
+        fs::create_dir(temp_dir.path().join("table_notes")).unwrap();
+
+        let pages = PageStorage::new(temp_dir.path(), 16).unwrap();
+        assert_eq!(pages.migrate_legacy_layout("main").unwrap(), 1);
+        assert!(!temp_dir.path().join("table_1").exists());
+        assert!(temp_dir.path().join("table_notes").exists());
+        assert!(PageStorage::database_dir(temp_dir.path(), "main").join("table_1").join("page_00000000.dat").exists());
+        assert_eq!(pages.migrate_legacy_layout("main").unwrap(), 0);
+
+        assert_eq!(PageStorage::in_memory(16).migrate_legacy_layout("main").unwrap(), 0);
+    }
+}
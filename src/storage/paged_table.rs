@@ -1,33 +1,101 @@
 use std::sync::{Arc, Mutex};
-use crate::types::{DatabaseError, Row};
+use crate::transaction::Snapshot;
+use crate::types::{DatabaseError, Row, Value};
 use super::page_manager::PageManager;
 use super::page::PageId;
+use super::backend::StorageBackend;
+use super::version_counts::VersionCounts;
 
 /// `PagedTable` - table storage using page-based architecture
 pub struct PagedTable {
     /// Table ID (unique identifier)
     pub table_id: u32,
     /// Page manager for disk I/O
-    page_manager: Arc<Mutex<PageManager>>,
+    page_manager: Arc<PageManager>,
     /// Number of pages currently allocated
     page_count: u32,
     /// Total row count (cached)
     row_count: usize,
+    /// v2.7.0: Rows stored on each page, to find the page of a row ordinal
+    page_rows: Vec<usize>,
+    /// v2.7.0: Rows of a `USING columnar|lsm` table (its pages stay unused)
+    backend: Option<Box<dyn StorageBackend>>,
+    /// v2.7.0: Row versions by xmin/xmax, from the first full scan that
+    /// counted them on (see `count_versions`)
+    versions: Mutex<Option<VersionCounts>>,
 }
 
 impl PagedTable {
     /// Create a new paged table
-    pub const fn new(table_id: u32, page_manager: Arc<Mutex<PageManager>>) -> Self {
+    #[must_use]
+    pub const fn new(table_id: u32, page_manager: Arc<PageManager>) -> Self {
         Self {
             table_id,
             page_manager,
             page_count: 0,
             row_count: 0,
+            page_rows: Vec::new(),
+            backend: None,
+            versions: Mutex::new(None),
+        }
+    }
+
+    /// v2.7.0: Create a table that keeps its rows in `backend` instead of pages
+    #[must_use]
+    pub fn with_backend(table_id: u32, page_manager: Arc<PageManager>, backend: Box<dyn StorageBackend>) -> Self {
+        Self {
+            backend: Some(backend),
+            ..Self::new(table_id, page_manager)
+        }
+    }
+
+    /// v2.7.0: Access method of the table (`Heap` unless a backend holds the rows)
+    #[must_use]
+    pub fn access_method(&self) -> crate::types::AccessMethod {
+        self.backend.as_ref().map_or(crate::types::AccessMethod::Heap, |backend| backend.access_method())
+    }
+
+    /// v2.7.0: Rows visible to `snapshot`, known without a scan once
+    /// `count_versions` was given the versions of the table
+    ///
+    /// `horizon` is the vacuum horizon; older versions are folded.
+    pub fn visible_row_count(&self, snapshot: &Snapshot, horizon: u64) -> Option<usize> {
+        let mut versions = self.versions.lock().expect("version counts poisoned");
+        let counts = versions.as_mut()?;
+        counts.settle(horizon);
+        Some(counts.visible_to(snapshot))
+    }
+
+    /// v2.7.0: Keep count of the row versions from now on, starting from
+    /// `rows`, every version a full scan of the table just returned
+    pub fn count_versions<'a>(&self, rows: impl IntoIterator<Item = &'a Row>) {
+        let mut versions = self.versions.lock().expect("version counts poisoned");
+        if versions.is_none() {
+            *versions = Some(VersionCounts::from_rows(rows));
+        }
+    }
+
+    fn track_versions(&mut self, update: impl FnOnce(&mut VersionCounts)) {
+        if let Some(counts) = self.versions.get_mut().expect("version counts poisoned") {
+            update(counts);
         }
     }
 
     /// Insert a row into the table
     pub fn insert(&mut self, row: Row) -> Result<(), DatabaseError> {
+        let (xmin, xmax) = (row.xmin, row.xmax);
+        self.insert_version(row)?;
+        self.track_versions(|counts| counts.add(xmin, xmax));
+        Ok(())
+    }
+
+    fn insert_version(&mut self, row: Row) -> Result<(), DatabaseError> {
+        if let Some(ref mut backend) = self.backend {
+            backend.insert(row);
+            self.row_count += 1;
+            return Ok(());
+        }
+
         // Try to find a page with free space
         let mut inserted = false;
 
@@ -35,11 +103,11 @@ impl PagedTable {
             let page_id = PageId::new(self.table_id, page_num);
 
             // Try to insert into this page
-            let pm = self.page_manager.lock().unwrap();
-            let guard = pm.get_page_mut(page_id)?;
+            let guard = self.page_manager.get_page_mut(page_id)?;
 
             let result = guard.get_mut(|page| {
-                if page.can_fit(bincode::serialize(&row).unwrap().len()) {
+                // v2.7.0: leaving room to set xmax on the rows of the page
+                if page.can_fit_version(bincode::serialize(&row).unwrap().len()) {
                     page.insert_row(&row)?;
                     Ok(true)
                 } else {
@@ -48,30 +116,26 @@ impl PagedTable {
             })?;
 
             drop(guard);
-            drop(pm);
 
             if result {
                 inserted = true;
                 self.row_count += 1;
+                self.page_rows[page_num as usize] += 1;
                 break;
             }
         }
 
         // If not inserted, create a new page
         if !inserted {
-            let new_page_id = {
-                let pm = self.page_manager.lock().unwrap();
-                pm.create_page(self.table_id, self.page_count)?
-            };
+            let new_page_id = self.page_manager.create_page(self.table_id, self.page_count)?;
 
-            let pm = self.page_manager.lock().unwrap();
-            let guard = pm.get_page_mut(new_page_id)?;
+            let guard = self.page_manager.get_page_mut(new_page_id)?;
             guard.get_mut(|page| page.insert_row(&row))?;
             drop(guard);
-            drop(pm);
 
             self.page_count += 1;
             self.row_count += 1;
+            self.page_rows.push(1);
         }
 
         Ok(())
@@ -79,9 +143,13 @@ impl PagedTable {
 
     /// Get all rows from the table
     pub fn get_all_rows(&self) -> Result<Vec<Row>, DatabaseError> {
+        if let Some(ref backend) = self.backend {
+            return backend.get_all_rows();
+        }
+
         let mut all_rows = Vec::new();
 
-        let pm = self.page_manager.lock().unwrap();
+        let pm = &self.page_manager;
 
         for page_num in 0..self.page_count {
             let page_id = PageId::new(self.table_id, page_num);
@@ -92,8 +160,26 @@ impl PagedTable {
         Ok(all_rows)
     }
 
+    /// Lazily scan rows one page at a time (v2.7.0)
+    ///
+    /// Unlike `get_all_rows()`, only the current page is held in memory,
+    /// so consumers that stop early (LIMIT) never touch the remaining pages.
+    #[must_use]
+    pub fn scan(&self) -> PagedTableScan<'_> {
+        PagedTableScan::over(vec![self])
+    }
+
+    /// v2.7.0: Read rows by ordinal - their position in `get_all_rows()`,
+    /// the positions indexes keep - one page at a time
+    ///
+    /// Only the pages holding the rows asked for are read.
+    #[must_use]
+    pub const fn fetch(&self) -> RowFetch<'_> {
+        RowFetch { table: self, page: None, backend_rows: None }
+    }
+
     /// Get row count
-    #[must_use] 
+    #[must_use]
     pub const fn row_count(&self) -> usize {
         self.row_count
     }
@@ -103,9 +189,16 @@ impl PagedTable {
     where
         F: Fn(&Row) -> bool,
     {
+        if let Some(ref mut backend) = self.backend {
+            // v2.7.0: The backend does not say which versions it marked
+            *self.versions.get_mut().expect("version counts poisoned") = None;
+            return Ok(backend.delete_where(&predicate, tx_id));
+        }
+
         let mut deleted_count = 0;
+        let mut marked = Vec::new();
 
-        let pm = self.page_manager.lock().unwrap();
+        let pm = &self.page_manager;
 
         for page_num in 0..self.page_count {
             let page_id = PageId::new(self.table_id, page_num);
@@ -117,8 +210,10 @@ impl PagedTable {
                     if let Ok(mut row) = page.get_row(slot_idx as u16)
                         && predicate(&row) {
                             // MVCC: mark row as deleted instead of physical removal
+                            let old_xmax = row.xmax;
                             row.mark_deleted(tx_id);
                             page.update_row(slot_idx as u16, &row)?;
+                            marked.push((row.xmin, old_xmax));
                             local_count += 1;
                         }
                 }
@@ -127,6 +222,12 @@ impl PagedTable {
 
             deleted_count += count;
         }
+        self.track_versions(|counts| {
+            for (xmin, xmax) in marked {
+                counts.remove(xmin, xmax);
+                counts.add(xmin, Some(tx_id));
+            }
+        });
 
         // Note: row_count stays the same (rows are marked, not removed)
         // VACUUM will physically remove them later
@@ -139,8 +240,16 @@ impl PagedTable {
         F: Fn(&Row) -> bool,
         U: Fn(&Row) -> Row,
     {
-        let pm = self.page_manager.lock().unwrap();
+        if let Some(ref mut backend) = self.backend {
+            *self.versions.get_mut().expect("version counts poisoned") = None;  // v2.7.0: as in `delete_where`
+            let count = backend.update_where(&predicate, &updater, tx_id);
+            self.row_count += count;
+            return Ok(count);
+        }
+
+        let pm = &self.page_manager;
         let mut new_rows = Vec::new();
+        let mut marked = Vec::new();
         let mut updated_count = 0;
 
         // Phase 1: Mark old rows and collect new versions
@@ -153,8 +262,10 @@ impl PagedTable {
                     if let Ok(mut row) = page.get_row(slot_idx as u16)
                         && predicate(&row) {
                             // Mark old version as deleted
+                            let old_xmax = row.xmax;
                             row.mark_deleted(tx_id);
                             page.update_row(slot_idx as u16, &row)?;
+                            marked.push((row.xmin, old_xmax));
 
                             // Create new version
                             let mut new_row = updater(&row);
@@ -168,8 +279,14 @@ impl PagedTable {
             })?;
         }
 
-        // Phase 2: Insert new versions (drop lock first to avoid deadlock)
-        drop(pm);
+        self.track_versions(|counts| {
+            for (xmin, xmax) in marked {
+                counts.remove(xmin, xmax);
+                counts.add(xmin, Some(tx_id));
+            }
+        });
+
+        // Phase 2: Insert new versions
         for new_row in new_rows {
             self.insert(new_row)?;
         }
@@ -177,10 +294,46 @@ impl PagedTable {
         Ok(updated_count)
     }
 
+    /// v2.7.0: Remove every row by releasing all pages of the table
+    pub fn truncate(&mut self) -> Result<(), DatabaseError> {
+        if let Some(ref mut backend) = self.backend {
+            backend.truncate();
+            self.row_count = 0;
+            self.track_versions(|counts| *counts = VersionCounts::default());
+            return Ok(());
+        }
+
+        self.page_manager.delete_table_pages(self.table_id)?;
+
+        self.page_count = 0;
+        self.row_count = 0;
+        self.page_rows.clear();
+        self.track_versions(|counts| *counts = VersionCounts::default());
+        Ok(())
+    }
+
+    /// v2.7.0: Replace the contents of the table with `rows`, stored in their order (CLUSTER)
+    ///
+    /// The old pages are released and the rows are packed into new ones from
+    /// the first page on.
+    pub fn rewrite(&mut self, rows: Vec<Row>) -> Result<(), DatabaseError> {
+        if let Some(ref backend) = self.backend {
+            return Err(DatabaseError::ParseError(format!(
+                "Cannot rewrite a table stored USING {}",
+                backend.access_method().name()
+            )));
+        }
+
+        self.truncate()?;
+        for row in rows {
+            self.insert(row)?;
+        }
+        Ok(())
+    }
+
     /// Flush all dirty pages to disk
     pub fn flush(&self) -> Result<(), DatabaseError> {
-        let pm = self.page_manager.lock().unwrap();
-        pm.checkpoint()?;
+        self.page_manager.checkpoint()?;
         Ok(())
     }
 
@@ -195,8 +348,16 @@ impl PagedTable {
     /// # Returns
     /// Number of tuples removed
     pub fn vacuum(&mut self, oldest_tx: u64) -> Result<usize, DatabaseError> {
+        if let Some(ref mut backend) = self.backend {
+            *self.versions.get_mut().expect("version counts poisoned") = None;  // v2.7.0: as in `delete_where`
+            let removed = backend.vacuum(oldest_tx);
+            self.row_count = backend.row_count();
+            return Ok(removed);
+        }
+
         let mut removed_count = 0;
-        let page_manager = self.page_manager.lock().unwrap();
+        let mut removed = Vec::new();
+        let page_manager = &self.page_manager;
 
         // Iterate through all pages
         for page_num in 0..self.page_count {
@@ -215,6 +376,7 @@ impl PagedTable {
                         // Check if row is dead (has xmax and xmax < oldest_tx)
                         if row.is_dead(oldest_tx) {
                             dead_slots.push(slot_idx as u16);
+                            removed.push((row.xmin, row.xmax));
                         }
                     }
                 }
@@ -229,7 +391,14 @@ impl PagedTable {
             })?;
 
             removed_count += count;
+            self.page_rows[page_num as usize] -= count;
         }
+        self.track_versions(|counts| {
+            for (xmin, xmax) in removed {
+                counts.remove(xmin, xmax);
+            }
+            counts.settle(oldest_tx);
+        });
 
         Ok(removed_count)
     }
@@ -245,6 +414,172 @@ impl PagedTable {
     }
 }
 
+/// Tuple-level predicate evaluated while a page is being scanned (v2.7.0)
+pub type ScanPredicate<'a> = Box<dyn FnMut(&Row) -> Result<bool, DatabaseError> + 'a>;
+
+/// Page-at-a-time row iterator returned by `PagedTable::scan()` (v2.7.0)
+pub struct PagedTableScan<'a> {
+    /// Tables scanned one after another (several for a partitioned table)
+    tables: std::vec::IntoIter<&'a PagedTable>,
+    table: Option<&'a PagedTable>,
+    next_page: u32,
+    current: std::vec::IntoIter<Row>,
+    /// Pushed-down predicate: rejected tuples never leave the scan
+    predicate: Option<ScanPredicate<'a>>,
+    /// Column positions to keep (sorted, unique); `None` keeps every column
+    projection: Option<Vec<usize>>,
+    /// v2.7.0: Columns a storage backend has to decode (the rest read as NULL); `None` decodes all
+    reading: Option<Vec<usize>>,
+}
+
+impl<'a> PagedTableScan<'a> {
+    /// Scan `tables` in order as one relation - the partitions of a partitioned table (v2.7.0)
+    #[must_use]
+    pub fn over(tables: Vec<&'a PagedTable>) -> Self {
+        let mut tables = tables.into_iter();
+        Self {
+            table: tables.next(),
+            tables,
+            next_page: 0,
+            current: Vec::new().into_iter(),
+            predicate: None,
+            projection: None,
+            reading: None,
+        }
+    }
+
+    /// Filter tuples inside the scan instead of in the executor
+    #[must_use]
+    pub fn with_predicate(mut self, predicate: ScanPredicate<'a>) -> Self {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    /// Return only the given column positions, in ascending order
+    ///
+    /// The predicate (if any) still sees the full tuple.
+    #[must_use]
+    pub fn with_projection(mut self, mut columns: Vec<usize>) -> Self {
+        columns.sort_unstable();
+        columns.dedup();
+        self.projection = Some(columns);
+        self
+    }
+
+    /// Only `columns` are needed by the predicate and the consumer (v2.7.0)
+    ///
+    /// Columnar tables skip decoding every other column, which then reads as
+    /// NULL at its usual position. Heap pages and LSM runs always yield whole tuples.
+    #[must_use]
+    pub fn reading(mut self, mut columns: Vec<usize>) -> Self {
+        columns.sort_unstable();
+        columns.dedup();
+        self.reading = Some(columns);
+        self
+    }
+
+    fn project(&self, mut row: Row) -> Row {
+        if let Some(ref columns) = self.projection {
+            let mut values = std::mem::take(&mut row.values);
+            row.values = columns
+                .iter()
+                .map(|&idx| values.get_mut(idx).map_or(Value::Null, |v| std::mem::replace(v, Value::Null)))
+                .collect();
+        }
+        row
+    }
+}
+
+impl Iterator for PagedTableScan<'_> {
+    type Item = Result<Row, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.current.next() {
+                if let Some(ref mut predicate) = self.predicate {
+                    match predicate(&row) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                return Some(Ok(self.project(row)));
+            }
+
+            let table = self.table?;
+
+            // v2.7.0: Backend tables are read one chunk (e.g. columnar stripe) at a time
+            if let Some(ref backend) = table.backend {
+                let chunk = self.next_page as usize;
+                if chunk >= backend.chunk_count() {
+                    self.table = self.tables.next();
+                    self.next_page = 0;
+                } else {
+                    self.next_page += 1;
+                    match backend.chunk_rows(chunk, self.reading.as_deref()) {
+                        Ok(rows) => self.current = rows.into_iter(),
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                continue;
+            }
+
+            if self.next_page >= table.page_count {
+                self.table = self.tables.next();
+                self.next_page = 0;
+                continue;
+            }
+
+            let page_id = PageId::new(table.table_id, self.next_page);
+            self.next_page += 1;
+
+            match table.page_manager.get_page(page_id) {
+                Ok(page) => self.current = page.get_all_rows().into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Rows of a `PagedTable` by ordinal, see `PagedTable::fetch` (v2.7.0)
+pub struct RowFetch<'a> {
+    table: &'a PagedTable,
+    /// The page read last and its rows
+    page: Option<(u32, Vec<Row>)>,
+    /// Every row of a backend table, read on the first fetch
+    backend_rows: Option<Vec<Row>>,
+}
+
+impl RowFetch<'_> {
+    /// The row at `ordinal`, `None` past the end of the table
+    pub fn row(&mut self, ordinal: usize) -> Result<Option<Row>, DatabaseError> {
+        if let Some(ref backend) = self.table.backend {
+            if self.backend_rows.is_none() {
+                self.backend_rows = Some(backend.get_all_rows()?);
+            }
+            return Ok(self.backend_rows.as_ref().and_then(|rows| rows.get(ordinal).cloned()));
+        }
+
+        let mut slot = ordinal;
+        let Some(page_num) = self.table.page_rows.iter().position(|&rows| {
+            let here = slot < rows;
+            if !here {
+                slot -= rows;
+            }
+            here
+        }) else {
+            return Ok(None);
+        };
+        let page_num = page_num as u32;
+
+        if self.page.as_ref().is_none_or(|(cached, _)| *cached != page_num) {
+            let page = self.table.page_manager.get_page(PageId::new(self.table.table_id, page_num))?;
+            self.page = Some((page_num, page.get_all_rows()));
+        }
+        Ok(self.page.as_ref().and_then(|(_, rows)| rows.get(slot).cloned()))
+    }
+}
+
 /// Statistics for a paged table
 #[derive(Debug, Clone)]
 pub struct PagedTableStats {
@@ -262,7 +597,7 @@ mod tests {
     #[test]
     fn test_paged_table_creation() {
         let temp_dir = TempDir::new().unwrap();
-        let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+        let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
 
         let table = PagedTable::new(1, pm);
         assert_eq!(table.table_id, 1);
@@ -273,7 +608,7 @@ mod tests {
     #[test]
     fn test_insert_and_get_rows() {
         let temp_dir = TempDir::new().unwrap();
-        let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+        let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
 
         let mut table = PagedTable::new(1, pm);
 
@@ -292,7 +627,7 @@ mod tests {
     #[test]
     fn test_delete_rows() {
         let temp_dir = TempDir::new().unwrap();
-        let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+        let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
 
         let mut table = PagedTable::new(1, pm);
 
@@ -319,7 +654,7 @@ mod tests {
     #[test]
     fn test_update_rows() {
         let temp_dir = TempDir::new().unwrap();
-        let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+        let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
 
         let mut table = PagedTable::new(1, pm);
 
@@ -342,7 +677,7 @@ mod tests {
     #[test]
     fn test_multiple_pages() {
         let temp_dir = TempDir::new().unwrap();
-        let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+        let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
 
         let mut table = PagedTable::new(1, pm);
 
@@ -362,13 +697,34 @@ mod tests {
         assert_eq!(all_rows.len(), 200);
     }
 
+    #[test]
+    fn test_fetch_by_ordinal() {
+        let temp_dir = TempDir::new().unwrap();
+        let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
+        let mut table = PagedTable::new(1, pm);
+
+        for i in 0..200 {
+            table.insert(Row::new(vec![Value::Integer(i), Value::Text(format!("row {i} padded to fill pages"))])).unwrap();
+        }
+        table.delete_where(|row| matches!(row.values[0], Value::Integer(n) if n % 3 == 0), 2).unwrap();
+        table.vacuum(3).unwrap();
+        assert!(table.page_count > 1);
+
+        let all_rows = table.get_all_rows().unwrap();
+        let mut fetch = table.fetch();
+        for ordinal in [120, 0, 1, 70, all_rows.len() - 1] {
+            assert_eq!(fetch.row(ordinal).unwrap().unwrap().values, all_rows[ordinal].values);
+        }
+        assert!(fetch.row(all_rows.len()).unwrap().is_none());
+    }
+
     #[test]
     fn test_persistence() {
         let temp_dir = TempDir::new().unwrap();
 
         // Insert data
         {
-            let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+            let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
             let mut table = PagedTable::new(1, pm);
 
             for i in 0..10 {
@@ -381,14 +737,11 @@ mod tests {
 
         // Read data back
         {
-            let pm = Arc::new(Mutex::new(PageManager::new(temp_dir.path(), 100).unwrap()));
+            let pm = Arc::new(PageManager::new(temp_dir.path(), 100).unwrap());
             let mut table = PagedTable::new(1, pm.clone());
 
             // Manually set page_count by checking disk
-            let page_count = {
-                let pm_lock = pm.lock().unwrap();
-                pm_lock.get_page_count(1)
-            };
+            let page_count = pm.get_page_count(1);
             table.page_count = page_count as u32;
 
             let all_rows = table.get_all_rows().unwrap();
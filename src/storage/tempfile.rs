@@ -0,0 +1,297 @@
+/// Temp files of queries under `data_dir/pgsql_tmp` (v2.7.0)
+///
+/// Sorts, spilled hash aggregation and COPY buffers write their overflow here.
+/// Every file is handed out for the query running on the current thread and
+/// is tracked until it is dropped. When a query ends — done, failed, or
+/// cancelled and dropped halfway — whatever it still holds is removed with it.
+/// Only a result written out to be sent in chunks outlives its query.
+/// Files left behind by a crash are removed when the storage is opened again.
+///
+/// The files a query holds at once may add up to `temp_file_limit`; writing
+/// past it fails the query.
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::types::DatabaseError;
+
+/// Name of the temp directory inside the data directory
+pub const TEMP_DIR: &str = "pgsql_tmp";
+
+/// Query and file ids are unique within the process
+static NEXT_QUERY: AtomicU64 = AtomicU64::new(1);
+static NEXT_FILE: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Query running on this thread, 0 outside of one
+    static CURRENT_QUERY: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Live temp files by query
+type Registry = Arc<Mutex<HashMap<u64, QueryFiles>>>;
+
+/// Temp files of one query
+#[derive(Debug, Default)]
+struct QueryFiles {
+    paths: HashSet<PathBuf>,
+    /// Bytes written to them
+    bytes: usize,
+}
+
+/// Hands out temp files in one directory; clones share the same bookkeeping
+#[derive(Debug, Clone)]
+pub struct TempFiles {
+    dir: PathBuf,
+    registry: Registry,
+    /// v2.7.0: Bytes the files of one query may hold (`temp_file_limit`)
+    limit: Arc<AtomicUsize>,
+}
+
+impl TempFiles {
+    /// Temp files in `data_dir/pgsql_tmp`, removing any a crashed server left there
+    #[must_use]
+    pub fn open(data_dir: &Path) -> Self {
+        let dir = data_dir.join(TEMP_DIR);
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        Self { dir, registry: Arc::default(), limit: Arc::new(AtomicUsize::new(usize::MAX)) }
+    }
+
+    /// v2.7.0: Bytes the temp files of one query may hold, `None` for no limit;
+    /// applies to every clone
+    pub fn set_limit(&self, bytes: Option<usize>) {
+        self.limit.store(bytes.unwrap_or(usize::MAX), Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Start tracking the files of a query run on this thread
+    ///
+    /// Statements executed by another statement (a partition insert, a DROP
+    /// of partitions) share the outermost query: its guard is the one that
+    /// cleans up.
+    #[must_use]
+    pub fn begin_query(&self) -> QueryTempFiles {
+        let outer = CURRENT_QUERY.with(Cell::get);
+        let id = if outer == 0 {
+            let id = NEXT_QUERY.fetch_add(1, Ordering::Relaxed);
+            CURRENT_QUERY.with(|current| current.set(id));
+            id
+        } else {
+            0
+        };
+        QueryTempFiles { id, registry: Arc::clone(&self.registry) }
+    }
+
+    /// New empty temp file of the current query
+    pub fn create(&self) -> Result<TempFile, DatabaseError> {
+        self.create_for(CURRENT_QUERY.with(Cell::get))
+    }
+
+    /// New empty temp file that outlives the current query, for a result
+    /// sent after the query is done (see `ResultChunks`)
+    ///
+    /// It counts against `temp_file_limit` on its own and is removed when
+    /// dropped.
+    pub fn create_result(&self) -> Result<TempFile, DatabaseError> {
+        self.create_for(NEXT_QUERY.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn create_for(&self, query: u64) -> Result<TempFile, DatabaseError> {
+        fs::create_dir_all(&self.dir)?;
+        let id = NEXT_FILE.fetch_add(1, Ordering::Relaxed);
+        let path = self.dir.join(format!("{TEMP_DIR}{}.{id}", std::process::id()));
+        let writer = BufWriter::new(File::create(&path)?);
+
+        self.lock().entry(query).or_default().paths.insert(path.clone());
+        Ok(TempFile {
+            path,
+            query,
+            registry: Arc::clone(&self.registry),
+            limit: Arc::clone(&self.limit),
+            writer: Some(writer),
+            rows: 0,
+            bytes: 0,
+        })
+    }
+
+    /// Temp files that exist right now, over all queries
+    #[must_use]
+    pub fn live_files(&self) -> usize {
+        self.lock().values().map(|files| files.paths.len()).sum()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, QueryFiles>> {
+        self.registry.lock().expect("temp file registry poisoned")
+    }
+}
+
+/// Guard of a running query: removes its remaining temp files when dropped
+#[derive(Debug)]
+pub struct QueryTempFiles {
+    /// 0 for a statement nested in another query
+    id: u64,
+    registry: Registry,
+}
+
+impl Drop for QueryTempFiles {
+    fn drop(&mut self) {
+        if self.id == 0 {
+            return;
+        }
+        CURRENT_QUERY.with(|current| current.set(0));
+        let files = self.registry.lock().map(|mut registry| registry.remove(&self.id)).unwrap_or_default();
+        for path in files.into_iter().flat_map(|files| files.paths) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Rows written to a temp file one after another, removed when dropped
+///
+/// Holds the sorted runs of ORDER BY and the spilled partitions of GROUP BY.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    query: u64,
+    registry: Registry,
+    limit: Arc<AtomicUsize>,
+    writer: Option<BufWriter<File>>,
+    rows: usize,
+    /// Bytes written, counted in the query's total
+    bytes: usize,
+}
+
+impl TempFile {
+    pub fn append<T: Serialize>(&mut self, row: &T) -> Result<(), DatabaseError> {
+        // v2.7.0: The query's files must stay within temp_file_limit
+        let size = bincode::serialized_size(row).map_or(0, |size| usize::try_from(size).unwrap_or(usize::MAX));
+        if let Ok(mut registry) = self.registry.lock() {
+            let files = registry.entry(self.query).or_default();
+            let limit = self.limit.load(Ordering::Relaxed);
+            if files.bytes.saturating_add(size) > limit {
+                return Err(DatabaseError::TempFileLimitExceeded(limit / 1024));
+            }
+            files.bytes += size;
+        }
+        self.bytes += size;
+
+        let writer = self.writer.as_mut().expect("temp file is open for writing");
+        bincode::serialize_into(writer, row).map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Rows written so far
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Read the rows back in the order they were written
+    pub fn read<T: DeserializeOwned>(mut self) -> Result<impl Iterator<Item = Result<T, DatabaseError>>, DatabaseError> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        // The file is moved into the iterator and goes away once it is read
+        Ok((0..self.rows).map(move |_| {
+            let _keep = &self;
+            bincode::deserialize_from(&mut reader).map_err(|e| DatabaseError::BinarySerialization(e.to_string()))
+        }))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        self.writer = None;
+        let _ = fs::remove_file(&self.path);
+        if let Ok(mut registry) = self.registry.lock()
+            && let Some(files) = registry.get_mut(&self.query)
+        {
+            files.paths.remove(&self.path);
+            files.bytes = files.bytes.saturating_sub(self.bytes);
+            if files.paths.is_empty() {
+                registry.remove(&self.query);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn files_in(dir: &Path) -> usize {
+        fs::read_dir(dir).map_or(0, Iterator::count)
+    }
+
+    #[test]
+    fn test_temp_files_cleaned_per_query_and_on_restart() {
+        let data_dir = TempDir::new().unwrap();
+        let temp_files = TempFiles::open(data_dir.path());
+
+        // Read back and dropped: gone right away
+        let mut file = temp_files.create().unwrap();
+        file.append(&(1_i64, "one".to_string())).unwrap();
+        file.append(&(2_i64, "two".to_string())).unwrap();
+        let rows: Vec<(i64, String)> = file.read().unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows, [(1, "one".to_string()), (2, "two".to_string())]);
+        assert_eq!((files_in(temp_files.dir()), temp_files.live_files()), (0, 0));
+
+        // Still held when the query ends (a nested statement does not end it)
+        let query = temp_files.begin_query();
+        let nested = temp_files.begin_query();
+        let leaked = temp_files.create().unwrap();
+        drop(nested);
+        assert_eq!(temp_files.live_files(), 1);
+        drop(query);
+        assert_eq!((files_in(temp_files.dir()), temp_files.live_files()), (0, 0));
+        drop(leaked);
+
+        // Left behind by a crash: removed on the next start
+        let crashed = temp_files.create().unwrap();
+        std::mem::forget(crashed);
+        assert_eq!(files_in(temp_files.dir()), 1);
+        let restarted = TempFiles::open(data_dir.path());
+        assert_eq!(files_in(restarted.dir()), 0);
+    }
+
+    #[test]
+    fn test_temp_file_limit() {
+        let data_dir = TempDir::new().unwrap();
+        let temp_files = TempFiles::open(data_dir.path());
+        temp_files.set_limit(Some(2048));
+
+        // The limit covers all files of the query together
+        let query = temp_files.begin_query();
+        let mut first = temp_files.create().unwrap();
+        let mut second = temp_files.create().unwrap();
+        first.append(&vec![0_u8; 1000]).unwrap();
+        second.append(&vec![0_u8; 1000]).unwrap();
+        let err = first.append(&vec![0_u8; 100]).unwrap_err();
+        assert!(matches!(err, DatabaseError::TempFileLimitExceeded(2)), "{err:?}");
+
+        // A dropped file gives its space back
+        drop(second);
+        first.append(&vec![0_u8; 100]).unwrap();
+        drop(first);
+        drop(query);
+
+        temp_files.set_limit(None);
+        let mut file = temp_files.create().unwrap();
+        file.append(&vec![0_u8; 10_000]).unwrap();
+    }
+}
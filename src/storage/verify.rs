@@ -0,0 +1,243 @@
+/// Offline check of a data directory: `postgrustql --check-data-dir` (v2.7.0)
+///
+/// Walks every page file (`base/<database>/table_<id>/page_<n>.dat`, and
+/// `table_<id>/page_<n>.dat` of older data directories) and every WAL file
+/// without starting the server or replaying anything. A page must decode,
+/// match its checksum, carry the id its file name says, and keep its slots
+/// inside the row area; a table's pages must be numbered without gaps. A WAL
+/// file must read to the end with every record's CRC intact and sequence
+/// numbers increasing. Everything found goes into one report.
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::types::DatabaseError;
+use super::page::Page;
+use super::page_storage::BASE_DIR;
+use super::wal::WalManager;
+
+/// One problem found, with the file it is in
+#[derive(Debug)]
+pub struct Corruption {
+    pub path: PathBuf,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct DataDirReport {
+    pub tables_checked: usize,
+    pub pages_checked: usize,
+    pub wal_files_checked: usize,
+    pub wal_records_checked: usize,
+    pub problems: Vec<Corruption>,
+}
+
+impl DataDirReport {
+    #[must_use]
+    pub const fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn problem(&mut self, path: &Path, detail: impl Into<String>) {
+        self.problems.push(Corruption { path: path.to_path_buf(), detail: detail.into() });
+    }
+}
+
+impl fmt::Display for DataDirReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Checked {} page(s) of {} table(s)", self.pages_checked, self.tables_checked)?;
+        writeln!(f, "Checked {} WAL record(s) in {} file(s)", self.wal_records_checked, self.wal_files_checked)?;
+        if self.is_clean() {
+            return writeln!(f, "No corruption found");
+        }
+        writeln!(f, "{} problem(s) found:", self.problems.len())?;
+        for problem in &self.problems {
+            writeln!(f, "  {}: {}", problem.path.display(), problem.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check the page files and WAL under `data_dir`
+pub fn check_data_dir(data_dir: &Path) -> Result<DataDirReport, DatabaseError> {
+    if !data_dir.is_dir() {
+        return Err(DatabaseError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("data directory '{}' does not exist", data_dir.display()),
+        )));
+    }
+
+    let mut report = DataDirReport::default();
+    // v2.7.0: Each database keeps its tables in its own directory
+    let mut page_dirs = vec![data_dir.to_path_buf()];
+    let base_dir = data_dir.join(BASE_DIR);
+    if base_dir.is_dir() {
+        let mut databases: Vec<PathBuf> = fs::read_dir(&base_dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        databases.sort();
+        page_dirs.extend(databases);
+    }
+    for page_dir in page_dirs {
+        let mut table_dirs: Vec<(u32, PathBuf)> = fs::read_dir(&page_dir)?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                let table_id = entry.file_name().to_str()?.strip_prefix("table_")?.parse().ok()?;
+                Some((table_id, entry.path()))
+            })
+            .collect();
+        table_dirs.sort();
+        for (table_id, dir) in table_dirs {
+            check_table(table_id, &dir, &mut report)?;
+        }
+    }
+
+    let wal_dir = data_dir.join("wal");
+    if wal_dir.is_dir() {
+        let mut wal_files: Vec<PathBuf> = fs::read_dir(&wal_dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("wal"))
+            .collect();
+        wal_files.sort();
+        for path in wal_files {
+            check_wal_file(&path, &mut report)?;
+        }
+    }
+
+    Ok(report)
+}
+
+fn check_table(table_id: u32, dir: &Path, report: &mut DataDirReport) -> Result<(), DatabaseError> {
+    report.tables_checked += 1;
+    let mut page_numbers = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("dat") {
+            continue;
+        }
+        let Some(page_number) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|stem| stem.strip_prefix("page_"))
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            report.problem(&path, "not a page file name");
+            continue;
+        };
+        page_numbers.push(page_number);
+        report.pages_checked += 1;
+
+        let page = match Page::from_bytes(&fs::read(&path)?) {
+            Ok(page) => page,
+            Err(e) => {
+                report.problem(&path, format!("unreadable page: {e}"));
+                continue;
+            }
+        };
+        let page_id = page.header.page_id;
+        if page_id.table_id != table_id || page_id.page_number != page_number {
+            report.problem(
+                &path,
+                format!("page header says page {} of table {}", page_id.page_number, page_id.table_id),
+            );
+        }
+        for problem in page.structural_problems() {
+            report.problem(&path, problem);
+        }
+    }
+
+    // Page count is the number of files, so a gap hides the last pages
+    page_numbers.sort_unstable();
+    if let Some((missing, _)) = (0u32..).zip(&page_numbers).find(|(expected, found)| expected != *found) {
+        report.problem(dir, format!("page numbers have gaps (first missing: {missing})"));
+    }
+    Ok(())
+}
+
+fn check_wal_file(path: &Path, report: &mut DataDirReport) -> Result<(), DatabaseError> {
+    report.wal_files_checked += 1;
+    let scan = WalManager::scan_wal_file(path)?;
+    report.wal_records_checked += scan.entries.len();
+    for damage in scan.damage {
+        report.problem(path, format!("offset {}: {}", damage.offset, damage.reason));
+    }
+    for pair in scan.entries.windows(2) {
+        if pair[1].sequence <= pair[0].sequence {
+            report.problem(
+                path,
+                format!("sequence {} follows {}: records are out of order", pair[1].sequence, pair[0].sequence),
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Operation, PageManager, PageId, PagedTable, PageStorage};
+    use crate::types::{Row, Value};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_data_dir_reports_corruption() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let page_manager = std::sync::Arc::new(PageManager::new(temp_dir.path(), 8).unwrap());
+            let mut table = PagedTable::new(1, page_manager.clone());
+            for n in 0..300 {
+                table.insert(Row::new(vec![Value::Integer(n), Value::Text("x".repeat(100))])).unwrap();
+            }
+            page_manager.checkpoint().unwrap();
+            let mut wal = WalManager::new(temp_dir.path()).unwrap();
+            for n in 0..3 {
+                wal.append(Operation::DropTable { table_name: format!("t{n}") }).unwrap();
+            }
+        }
+
+        // Pages of a database directory are checked as well
+        {
+            let pages = PageStorage::new(temp_dir.path(), 8).unwrap();
+            let shop = pages.database("shop").unwrap();
+            let mut shop = shop.blocking_write();
+            shop.create_table("orders".to_string()).unwrap();
+            shop.insert("orders", Row::new(vec![Value::Integer(1)])).unwrap();
+            shop.checkpoint().unwrap();
+        }
+
+        let report = check_data_dir(temp_dir.path()).unwrap();
+        assert!(report.is_clean(), "{report}");
+        assert_eq!(report.tables_checked, 2);
+        assert!(report.pages_checked > 2);
+        assert_eq!(report.wal_records_checked, 3);
+
+        // A flipped byte in a page, a page under the wrong name, a torn WAL tail
+        let table_dir = temp_dir.path().join("table_1");
+        let page_path = table_dir.join("page_00000000.dat");
+        let mut bytes = fs::read(&page_path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        fs::write(&page_path, bytes).unwrap();
+        fs::rename(table_dir.join("page_00000001.dat"), table_dir.join("page_00000007.dat")).unwrap();
+        let wal_path = fs::read_dir(temp_dir.path().join("wal")).unwrap().next().unwrap().unwrap().path();
+        let wal_bytes = fs::read(&wal_path).unwrap();
+        fs::write(&wal_path, &wal_bytes[..wal_bytes.len() - 3]).unwrap();
+
+        let report = check_data_dir(temp_dir.path()).unwrap();
+        let details: Vec<&str> = report.problems.iter().map(|problem| problem.detail.as_str()).collect();
+        assert!(details.iter().any(|d| d.contains("checksum mismatch")), "{report}");
+        assert!(details.iter().any(|d| d.contains("page header says page 1")), "{report}");
+        assert!(details.iter().any(|d| d.contains("gaps (first missing: 1)")), "{report}");
+        assert!(details.iter().any(|d| d.contains("WAL record is truncated")), "{report}");
+        assert_eq!(report.wal_records_checked, 2);
+        assert!(report.to_string().contains("4 problem(s) found"));
+
+        // The page manager refuses the damaged page instead of reading garbage
+        let page_manager = PageManager::new(temp_dir.path(), 8).unwrap();
+        assert!(page_manager.get_page(PageId::new(1, 0)).is_err());
+        assert!(check_data_dir(&temp_dir.path().join("missing")).is_err());
+    }
+}
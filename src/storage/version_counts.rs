@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use crate::transaction::Snapshot;
+use crate::types::Row;
+
+/// Row versions of one table counted by their xmin and xmax (v2.7.0)
+///
+/// Whether a snapshot sees a version depends on nothing but its xmin and
+/// xmax, so these counts give the number of rows any snapshot sees without
+/// reading a page: an unfiltered `SELECT COUNT(*)` answers from here instead
+/// of scanning the table. `PagedTable` keeps them current as versions are
+/// written, deleted and vacuumed. Versions behind the vacuum horizon look the
+/// same to every snapshot and are folded into a single number (`settle`), so
+/// only the versions of recent transactions are kept one by one.
+#[derive(Debug, Clone, Default)]
+pub struct VersionCounts {
+    /// Versions every snapshot sees
+    settled: usize,
+    /// The other versions by (xmin, xmax)
+    versions: HashMap<(u64, Option<u64>), usize>,
+}
+
+impl VersionCounts {
+    /// Counts of `rows`, every version of a table
+    pub fn from_rows<'a>(rows: impl IntoIterator<Item = &'a Row>) -> Self {
+        let mut counts = Self::default();
+        for row in rows {
+            counts.add(row.xmin, row.xmax);
+        }
+        counts
+    }
+
+    /// A version was written
+    pub fn add(&mut self, xmin: u64, xmax: Option<u64>) {
+        *self.versions.entry((xmin, xmax)).or_default() += 1;
+    }
+
+    /// A version was changed or removed
+    pub fn remove(&mut self, xmin: u64, xmax: Option<u64>) {
+        if let Some(count) = self.versions.get_mut(&(xmin, xmax)) {
+            *count -= 1;
+            if *count == 0 {
+                self.versions.remove(&(xmin, xmax));
+            }
+        } else if xmax.is_none() {
+            // Settled long ago; versions deleted before the horizon were dropped
+            self.settled = self.settled.saturating_sub(1);
+        }
+    }
+
+    /// Number of rows `snapshot` sees
+    #[must_use]
+    pub fn visible_to(&self, snapshot: &Snapshot) -> usize {
+        self.settled
+            + self
+                .versions
+                .iter()
+                .filter(|((xmin, xmax), _)| Row::version_visible_to_snapshot(*xmin, *xmax, snapshot))
+                .map(|(_, count)| count)
+                .sum::<usize>()
+    }
+
+    /// Fold the versions of transactions up to `horizon`, which every
+    /// snapshot sees as committed (`GlobalTransactionManager::get_oldest_active_tx`)
+    pub fn settle(&mut self, horizon: u64) {
+        let mut settled = 0;
+        self.versions.retain(|&(xmin, xmax), count| match xmax {
+            // Deleted for everybody
+            Some(xmax) if xmax <= horizon => false,
+            None if xmin <= horizon => {
+                settled += *count;
+                false
+            }
+            _ => true,
+        });
+        self.settled += settled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Value;
+
+    fn version(xmin: u64, xmax: Option<u64>) -> Row {
+        Row { values: vec![Value::Integer(1)], xmin, xmax, ctid: None }
+    }
+
+    #[test]
+    fn test_counts_match_row_visibility() {
+        let rows = vec![
+            version(1, None),
+            version(1, Some(3)),
+            version(2, None),
+            version(3, None),
+            version(3, Some(5)),
+            version(5, None),
+        ];
+        let counts = VersionCounts::from_rows(&rows);
+        for snapshot in [
+            Snapshot::new(2, 2, vec![]),
+            Snapshot::new(3, 4, vec![3]),
+            Snapshot::new(4, 5, vec![]),
+            Snapshot::new(5, 6, vec![5]),
+            Snapshot::new(6, 6, vec![]),
+        ] {
+            let scanned = rows.iter().filter(|row| row.is_visible_to_snapshot(&snapshot)).count();
+            assert_eq!(counts.visible_to(&snapshot), scanned);
+        }
+    }
+
+    #[test]
+    fn test_settle_keeps_counts() {
+        let mut counts = VersionCounts::from_rows(&[version(1, None), version(1, Some(2)), version(3, None)]);
+        counts.settle(2);
+        assert_eq!(counts.settled, 1);
+        assert_eq!(counts.versions.len(), 1);
+        assert_eq!(counts.visible_to(&Snapshot::new(4, 4, vec![])), 2);
+
+        // Deleting a settled version and vacuuming one dropped as dead
+        counts.remove(1, None);
+        counts.add(1, Some(4));
+        counts.remove(1, Some(2));
+        assert_eq!(counts.visible_to(&Snapshot::new(4, 4, vec![4])), 2);
+        assert_eq!(counts.visible_to(&Snapshot::new(5, 5, vec![])), 1);
+    }
+}
@@ -1,10 +1,51 @@
+use crate::storage::format;
 use crate::types::{Column, Database, DatabaseError, Row, Table};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// v2.7.0: Заголовок WAL файла с CRC и сжатием записей
+///
+/// Запись в таком файле: длина данных (4 байта), CRC32 флага и данных
+/// (4 байта), флаг (1 байт: 0 - bincode как есть, 1 - сжатый snappy), данные.
+/// Файлы без заголовка - старый формат: длина + bincode, записи в раскладке
+/// версии 1 (см. `format`). Записи с заголовком - в раскладке `format::FORMAT_VERSION`.
+const WAL_MAGIC: &[u8; 8] = b"PGRWAL02";
+/// Флаги записи
+const RECORD_PLAIN: u8 = 0;
+const RECORD_COMPRESSED: u8 = 1;
+/// v2.7.0: Записи меньше этого размера не сжимаются (обычно это не полные строки)
+const COMPRESSION_THRESHOLD: usize = 256;
+
+/// CRC-32 (IEEE 802.3), таблица считается при компиляции
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for part in parts {
+        for byte in *part {
+            crc = CRC_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8);
+        }
+    }
+    !crc
+}
+
 /// Типы операций, записываемых в WAL
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Operation {
@@ -58,6 +99,28 @@ pub enum Operation {
         old_table_name: String,
         new_table_name: String,
     },
+    /// ALTER TABLE ALTER COLUMN SET NOT NULL | DROP NOT NULL (v2.7.0)
+    AlterTableSetNullable {
+        table_name: String,
+        column_name: String,
+        nullable: bool,
+    },
+    /// CREATE TYPE ... AS ENUM (v2.7.0)
+    CreateEnum {
+        name: String,
+        values: Vec<String>,
+    },
+    /// ALTER TYPE ... ADD VALUE (v2.7.0) - полный список меток после изменения
+    AlterEnum {
+        name: String,
+        values: Vec<String>,
+    },
+    /// Значение SERIAL-колонки занято (v2.7.0): счётчик не выдаст его повторно
+    SerialValue {
+        table_name: String,
+        column_name: String,
+        value: i64,
+    },
 }
 
 /// Запись в WAL логе
@@ -87,10 +150,24 @@ impl LogEntry {
     }
 }
 
+/// v2.7.0: Результат чтения WAL файла
+pub(crate) struct WalScan {
+    pub entries: Vec<LogEntry>,
+    /// Повреждения: смещение записи и что с ней не так
+    pub damage: Vec<WalDamage>,
+    /// Файл в формате с CRC (есть заголовок `PGRWAL02`)
+    pub checked: bool,
+}
+
+pub(crate) struct WalDamage {
+    pub offset: u64,
+    pub reason: String,
+}
+
 /// Write-Ahead Log Manager
 pub struct WalManager {
-    /// Директория для WAL файлов
-    wal_dir: PathBuf,
+    /// Директория для WAL файлов (v2.7.0: `None` = in-memory режим, WAL не пишется)
+    wal_dir: Option<PathBuf>,
     /// Текущий sequence number
     current_sequence: u64,
     /// Текущий активный WAL файл
@@ -99,6 +176,10 @@ pub struct WalManager {
     current_wal_name: String,
     /// Максимальный размер WAL файла в байтах (по умолчанию 1MB)
     max_wal_size: u64,
+    /// v2.7.0: Байт записано в WAL с момента запуска (для метрик)
+    bytes_written: u64,
+    /// v2.7.0: Сжимать большие записи (полные образы строк)
+    compression: bool,
 }
 
 impl WalManager {
@@ -108,11 +189,13 @@ impl WalManager {
         fs::create_dir_all(&wal_dir)?;
 
         let mut manager = Self {
-            wal_dir,
+            wal_dir: Some(wal_dir),
             current_sequence: 0,
             current_wal_file: None,
             current_wal_name: String::new(),
             max_wal_size: 1024 * 1024, // 1MB
+            bytes_written: 0,
+            compression: true,
         };
 
         // Находим последний sequence number из существующих логов
@@ -123,11 +206,33 @@ impl WalManager {
         Ok(manager)
     }
 
+    /// v2.7.0: WAL без файлов - операции только нумеруются
+    #[must_use]
+    pub const fn in_memory() -> Self {
+        Self {
+            wal_dir: None,
+            current_sequence: 0,
+            current_wal_file: None,
+            current_wal_name: String::new(),
+            max_wal_size: 1024 * 1024,
+            bytes_written: 0,
+            compression: true,
+        }
+    }
+
+    /// v2.7.0: Включает/выключает сжатие больших записей (`wal_compression`)
+    pub const fn set_compression(&mut self, enabled: bool) {
+        self.compression = enabled;
+    }
+
     /// Восстанавливает sequence number из существующих WAL файлов
     fn recover_sequence(&mut self) -> Result<(), DatabaseError> {
         let mut max_sequence = 0u64;
+        let Some(wal_dir) = &self.wal_dir else {
+            return Ok(());
+        };
 
-        for entry in fs::read_dir(&self.wal_dir)? {
+        for entry in fs::read_dir(wal_dir)? {
             let entry = entry?;
             let path = entry.path();
 
@@ -158,12 +263,19 @@ impl WalManager {
             .unwrap()
             .as_secs();
         let wal_name = format!("{timestamp:016x}.wal");
-        let wal_path = self.wal_dir.join(&wal_name);
+        let Some(wal_dir) = &self.wal_dir else {
+            return Ok(());
+        };
+        let wal_path = wal_dir.join(&wal_name);
 
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(wal_path)?;
+        // Новый файл начинается с заголовка (в тот же файл можно вернуться в пределах секунды)
+        if file.metadata()?.len() == 0 {
+            file.write_all(WAL_MAGIC)?;
+        }
 
         self.current_wal_file = Some(file);
         self.current_wal_name = wal_name;
@@ -173,21 +285,31 @@ impl WalManager {
 
     /// Записывает операцию в WAL
     pub fn append(&mut self, operation: Operation) -> Result<u64, DatabaseError> {
-        // Увеличиваем sequence
-        self.current_sequence += 1;
-
-        let entry = LogEntry::new(self.current_sequence, operation);
+        self.append_batch(vec![operation])
+    }
 
-        // Сериализуем в bincode
-        let encoded = bincode::serialize(&entry)
-            .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+    /// Записывает несколько операций одной записью на диск (v2.7.0)
+    ///
+    /// Каждая операция получает свой sequence, но все записи уходят в файл
+    /// одним `write_all` с одним `flush`. Возвращает sequence последней.
+    pub fn append_batch(&mut self, operations: Vec<Operation>) -> Result<u64, DatabaseError> {
+        let mut buffer = Vec::new();
+        for operation in operations {
+            // Увеличиваем sequence
+            self.current_sequence += 1;
+
+            let entry = LogEntry::new(self.current_sequence, operation);
+
+            // Сериализуем в bincode
+            let encoded = bincode::serialize(&entry)
+                .map_err(|e| DatabaseError::BinarySerialization(e.to_string()))?;
+            self.encode_record(&encoded, &mut buffer);
+        }
 
         if let Some(ref mut file) = self.current_wal_file {
-            // Записываем длину (4 байта) + данные
-            let len = encoded.len() as u32;
-            file.write_all(&len.to_le_bytes())?;
-            file.write_all(&encoded)?;
+            file.write_all(&buffer)?;
             file.flush()?;
+            self.bytes_written += buffer.len() as u64;
 
             // Проверяем размер файла для rotation
             let metadata = file.metadata()?;
@@ -199,49 +321,136 @@ impl WalManager {
         Ok(self.current_sequence)
     }
 
+    /// v2.7.0: Запись с CRC, большие данные сжимаются, если так короче
+    fn encode_record(&self, encoded: &[u8], buffer: &mut Vec<u8>) {
+        let compressed = if self.compression && encoded.len() >= COMPRESSION_THRESHOLD {
+            snap::raw::Encoder::new().compress_vec(encoded).ok().filter(|data| data.len() < encoded.len())
+        } else {
+            None
+        };
+        let (flag, data) = match &compressed {
+            Some(data) => (RECORD_COMPRESSED, data.as_slice()),
+            None => (RECORD_PLAIN, encoded),
+        };
+
+        let len = data.len() as u32;
+        buffer.extend_from_slice(&len.to_le_bytes());
+        buffer.extend_from_slice(&crc32(&[&[flag], data]).to_le_bytes());
+        buffer.push(flag);
+        buffer.extend_from_slice(data);
+    }
+
     /// Читает все записи из WAL файла (binary format)
+    ///
+    /// v2.7.0: Чтение останавливается на первой повреждённой записи (не сходится
+    /// CRC, запись оборвана, не разжимается) - дальше в этом файле доверять
+    /// нельзя даже границам записей. Обычно это хвост, недописанный при падении.
     fn read_wal_file<P: AsRef<Path>>(path: P) -> Result<Vec<LogEntry>, DatabaseError> {
+        let path = path.as_ref();
+        let scan = Self::scan_wal_file(path)?;
+        for damage in &scan.damage {
+            tracing::warn!(file = %path.display(), offset = damage.offset, "{}", damage.reason);
+        }
+        Ok(scan.entries)
+    }
+
+    /// v2.7.0: Читает WAL файл и собирает найденные повреждения (для
+    /// `read_wal_file` и офлайн-проверки `--check-data-dir`)
+    pub(crate) fn scan_wal_file(path: &Path) -> Result<WalScan, DatabaseError> {
         let mut file = File::open(path)?;
-        let mut entries = Vec::new();
+        let file_len = file.metadata()?.len();
+        let mut scan = WalScan { entries: Vec::new(), damage: Vec::new(), checked: false };
+
+        let mut magic = [0u8; 8];
+        scan.checked = file.read_exact(&mut magic).is_ok() && &magic == WAL_MAGIC;
+        if !scan.checked {
+            file.seek(SeekFrom::Start(0))?;
+        }
 
         loop {
-            // Читаем длину записи (4 байта)
-            let mut len_bytes = [0u8; 4];
-            match file.read_exact(&mut len_bytes) {
-                Ok(()) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                    // Конец файла - это нормально
-                    break;
-                }
-                Err(e) => return Err(e.into()),
+            let offset = file.stream_position()?;
+            if offset >= file_len {
+                // Конец файла - это нормально
+                break;
             }
+            let damaged = |reason: &str| WalDamage { offset, reason: reason.to_string() };
+
+            // Длина записи (4 байта), в новом формате ещё CRC и флаг
+            let mut header = [0u8; 9];
+            let header = &mut header[..if scan.checked { 9 } else { 4 }];
+            if !Self::read_part(&mut file, header)? {
+                scan.damage.push(damaged("WAL record is truncated, ignoring the rest of the file"));
+                break;
+            }
+            let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
 
-            let len = u32::from_le_bytes(len_bytes) as usize;
-
-            // Читаем данные
+            // Читаем данные (длина из повреждённой записи может быть любой)
+            if offset + header.len() as u64 + len as u64 > file_len {
+                scan.damage.push(damaged("WAL record is truncated, ignoring the rest of the file"));
+                break;
+            }
             let mut data = vec![0u8; len];
             file.read_exact(&mut data)?;
 
-            // Десериализуем
-            match bincode::deserialize::<LogEntry>(&data) {
-                Ok(entry) => entries.push(entry),
-                Err(e) => {
-                    eprintln!("Warning: failed to parse WAL entry: {e}");
-                    // Продолжаем, игнорируя поврежденные записи
+            if scan.checked {
+                let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+                let flag = header[8];
+                if crc32(&[&[flag], &data]) != crc {
+                    scan.damage.push(damaged("WAL record checksum mismatch, ignoring the rest of the file"));
+                    break;
+                }
+                if flag == RECORD_COMPRESSED {
+                    match snap::raw::Decoder::new().decompress_vec(&data) {
+                        Ok(decompressed) => data = decompressed,
+                        Err(e) => {
+                            scan.damage.push(damaged(&format!("WAL record does not decompress ({e}), ignoring the rest of the file")));
+                            break;
+                        }
+                    }
                 }
             }
+
+            // Десериализуем (v2.7.0: записи файлов без заголовка - в раскладке версии 1)
+            let entry = if scan.checked {
+                bincode::deserialize::<LogEntry>(&data)
+            } else {
+                bincode::deserialize::<format::v1::LogEntry>(&data).map(LogEntry::from)
+            };
+            match entry {
+                Ok(entry) => scan.entries.push(entry),
+                // Продолжаем, игнорируя поврежденные записи
+                Err(e) => scan.damage.push(damaged(&format!("failed to parse WAL entry: {e}"))),
+            }
         }
 
-        Ok(entries)
+        Ok(scan)
+    }
+
+    /// Заполняет `buf` целиком; `false`, если файл кончился раньше
+    fn read_part(file: &mut File, buf: &mut [u8]) -> Result<bool, DatabaseError> {
+        match file.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// v2.7.0: Сколько байт записано в WAL с момента запуска
+    #[must_use]
+    pub const fn bytes_written(&self) -> u64 {
+        self.bytes_written
     }
 
     /// Читает все WAL записи (для recovery)
     pub fn read_all_logs(&self) -> Result<Vec<LogEntry>, DatabaseError> {
         let mut all_entries = Vec::new();
         let mut wal_files = Vec::new();
+        let Some(wal_dir) = &self.wal_dir else {
+            return Ok(all_entries);
+        };
 
         // Собираем все WAL файлы
-        for entry in fs::read_dir(&self.wal_dir)? {
+        for entry in fs::read_dir(wal_dir)? {
             let entry = entry?;
             let path = entry.path();
 
@@ -336,6 +545,25 @@ impl WalManager {
                     db.tables.insert(new_table_name.clone(), table);
                 }
             }
+            Operation::AlterTableSetNullable { table_name, column_name, nullable } => {
+                if let Some(table) = db.get_table_mut(table_name)
+                    && let Some(column) = table.columns.iter_mut().find(|c| c.name == *column_name) {
+                        column.nullable = *nullable;
+                    }
+            }
+            Operation::CreateEnum { name, values } => {
+                if !db.enums.contains_key(name) {
+                    db.create_enum(name.clone(), values.clone())?;
+                }
+            }
+            Operation::AlterEnum { name, values } => {
+                db.set_enum_values(name, values.clone()).ok(); // Игнорируем ошибки
+            }
+            Operation::SerialValue { table_name, column_name, value } => {
+                if let Some(table) = db.get_table_mut(table_name) {
+                    table.sequences.entry(column_name.clone()).or_default().advance_past(*value);
+                }
+            }
         }
 
         Ok(())
@@ -344,8 +572,11 @@ impl WalManager {
     /// Удаляет старые WAL файлы (после checkpoint)
     pub fn cleanup_old_logs(&self, keep_count: usize) -> Result<(), DatabaseError> {
         let mut wal_files = Vec::new();
+        let Some(wal_dir) = &self.wal_dir else {
+            return Ok(());
+        };
 
-        for entry in fs::read_dir(&self.wal_dir)? {
+        for entry in fs::read_dir(wal_dir)? {
             let entry = entry?;
             let path = entry.path();
 
@@ -369,21 +600,22 @@ impl WalManager {
     }
 
     /// Записывает checkpoint маркер
-    pub fn checkpoint(&mut self) -> Result<(), DatabaseError> {
+    ///
+    /// v2.7.0: Возвращает его sequence - LSN checkpoint'а, с которого начинается recovery
+    pub fn checkpoint(&mut self) -> Result<u64, DatabaseError> {
         self.append(Operation::Checkpoint {
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
-        })?;
-        Ok(())
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Column, DataType, Value};
+    use crate::types::{Collation, Column, DataType, Value};
     use tempfile::TempDir;
 
     #[test]
@@ -392,7 +624,7 @@ mod tests {
         let wal = WalManager::new(temp_dir.path()).unwrap();
 
         assert_eq!(wal.current_sequence, 0);
-        assert!(wal.wal_dir.exists());
+        assert!(wal.wal_dir.as_ref().unwrap().exists());
     }
 
     #[test]
@@ -406,6 +638,7 @@ mod tests {
             nullable: false,
             primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
         }];
 
@@ -423,6 +656,22 @@ mod tests {
         assert_eq!(logs[0].sequence, 1);
     }
 
+    #[test]
+    fn test_wal_append_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut wal = WalManager::new(temp_dir.path()).unwrap();
+
+        let operations = (1..=3)
+            .map(|id| Operation::Insert { table_name: "t".to_string(), row: Row::new(vec![Value::Integer(id)]) })
+            .collect();
+        assert_eq!(wal.append_batch(operations).unwrap(), 3);
+        assert_eq!(wal.append(Operation::DropTable { table_name: "t".to_string() }).unwrap(), 4);
+
+        let logs = wal.read_all_logs().unwrap();
+        assert_eq!(logs.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert!(matches!(&logs[2].operation, Operation::Insert { row, .. } if row.values == [Value::Integer(3)]));
+    }
+
     #[test]
     fn test_wal_apply_operations() {
         let mut db = Database::new("test".to_string());
@@ -433,6 +682,7 @@ mod tests {
             nullable: false,
             primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
         }];
 
@@ -470,6 +720,7 @@ mod tests {
                 nullable: false,
                 primary_key: true,
                 foreign_key: None,
+                collation: Collation::C,
                 unique: false,
             }];
 
@@ -520,4 +771,58 @@ mod tests {
 
         assert!(wal_files.len() <= 2);
     }
+
+    #[test]
+    fn test_wal_crc_and_compression() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut wal = WalManager::new(temp_dir.path()).unwrap();
+        let wal_file = wal.wal_dir.as_ref().unwrap().join(&wal.current_wal_name);
+
+        // Полный образ строки с повторяющимся текстом сжимается
+        let row = Row::new(vec![Value::Integer(1), Value::Text("abc".repeat(1000))]);
+        let plain = bincode::serialize(&LogEntry::new(1, Operation::Insert { table_name: "t".to_string(), row: row.clone() })).unwrap();
+        wal.append(Operation::Insert { table_name: "t".to_string(), row: row.clone() }).unwrap();
+        assert!(wal.bytes_written() < plain.len() as u64 / 4);
+        wal.append(Operation::DropTable { table_name: "t".to_string() }).unwrap();
+        wal.append(Operation::DropTable { table_name: "u".to_string() }).unwrap();
+
+        let logs = wal.read_all_logs().unwrap();
+        assert_eq!(logs.len(), 3);
+        assert!(matches!(&logs[0].operation, Operation::Insert { row: logged, .. } if logged.values == row.values));
+
+        // Испорченный байт в последней записи: читается всё до неё
+        let mut bytes = fs::read(&wal_file).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        fs::write(&wal_file, &bytes).unwrap();
+        let logs = wal.read_all_logs().unwrap();
+        assert_eq!(logs.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), [1, 2]);
+
+        // Оборванный хвост (падение посреди записи) - не ошибка
+        fs::write(&wal_file, &bytes[..bytes.len() - 5]).unwrap();
+        assert_eq!(wal.read_all_logs().unwrap().len(), 2);
+        assert_eq!(WalManager::new(temp_dir.path()).unwrap().current_sequence, 2);
+    }
+
+    #[test]
+    fn test_wal_reads_legacy_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_dir = temp_dir.path().join("wal");
+        fs::create_dir_all(&wal_dir).unwrap();
+
+        // Старый формат: длина + bincode, без заголовка и CRC
+        let mut bytes = Vec::new();
+        for sequence in 1..=2 {
+            let entry = bincode::serialize(&LogEntry::new(sequence, Operation::Checkpoint { timestamp: 0 })).unwrap();
+            bytes.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&entry);
+        }
+        fs::write(wal_dir.join("0000000000000001.wal"), bytes).unwrap();
+
+        let mut wal = WalManager::new(temp_dir.path()).unwrap();
+        assert_eq!(wal.current_sequence, 2);
+        wal.append(Operation::Checkpoint { timestamp: 0 }).unwrap();
+        let logs = wal.read_all_logs().unwrap();
+        assert_eq!(logs.iter().map(|entry| entry.sequence).collect::<Vec<_>>(), [1, 2, 3]);
+    }
 }
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::RwLock;
-use std::collections::HashSet;
+use std::sync::{Mutex, RwLock};
+use std::collections::HashMap;
+use crate::executor::cursors::Cursors;
+use super::observers::{ChangeObserver, ChangeObservers, RowChange};
 
 /// Snapshot for REPEATABLE READ / READ COMMITTED isolation
 ///
@@ -46,7 +48,28 @@ pub struct GlobalTransactionManager {
 
     /// Active (uncommitted) transactions
     /// Protected by RwLock for concurrent access from multiple connections
-    active_transactions: Arc<RwLock<HashSet<u64>>>,
+    /// v2.7.0: mapped to the xmin of the snapshot each one took at BEGIN
+    active_transactions: Arc<RwLock<HashMap<u64, u64>>>,
+
+    /// v2.7.0: Number of committed transactions (for metrics)
+    committed: Arc<AtomicU64>,
+
+    /// v2.7.0: Number of rolled back transactions (for metrics)
+    aborted: Arc<AtomicU64>,
+
+    /// v2.7.0: Cursors declared by active transactions, dropped when they end
+    cursors: Arc<Mutex<HashMap<u64, Cursors>>>,
+
+    /// v2.7.0: Snapshots exported by `pg_export_snapshot()`, by identifier,
+    /// with the transaction that exported them; dropped when it ends
+    exported_snapshots: Arc<Mutex<HashMap<String, (u64, Snapshot)>>>,
+
+    /// v2.7.0: Snapshot every statement reads through instead of a fresh one
+    /// (`SET TRANSACTION SNAPSHOT`). Not shared: each connection pins its own
+    pinned_snapshot: Option<Snapshot>,
+
+    /// v2.7.0: Callbacks for the row changes of committed transactions
+    change_observers: Arc<ChangeObservers>,
 }
 
 impl GlobalTransactionManager {
@@ -56,7 +79,13 @@ impl GlobalTransactionManager {
         Self {
             // Start from 1 (0 is reserved for initial data)
             next_tx_id: Arc::new(AtomicU64::new(1)),
-            active_transactions: Arc::new(RwLock::new(HashSet::new())),
+            active_transactions: Arc::new(RwLock::new(HashMap::new())),
+            committed: Arc::new(AtomicU64::new(0)),
+            aborted: Arc::new(AtomicU64::new(0)),
+            cursors: Arc::new(Mutex::new(HashMap::new())),
+            exported_snapshots: Arc::new(Mutex::new(HashMap::new())),
+            pinned_snapshot: None,
+            change_observers: Arc::new(ChangeObservers::default()),
         }
     }
 
@@ -77,7 +106,7 @@ impl GlobalTransactionManager {
         // This ensures we don't see our own transaction in the snapshot
         let active_txs = {
             let active = self.active_transactions.read().expect("RwLock poisoned");
-            active.iter().copied().collect::<Vec<_>>()
+            active.keys().copied().collect::<Vec<_>>()
         };
 
         // xmin is the oldest active transaction
@@ -87,7 +116,7 @@ impl GlobalTransactionManager {
         // Register this transaction as active
         {
             let mut active = self.active_transactions.write().expect("RwLock poisoned");
-            active.insert(tx_id);
+            active.insert(tx_id, xmin);
         }
 
         let snapshot = Snapshot {
@@ -106,6 +135,11 @@ impl GlobalTransactionManager {
     pub fn commit_transaction(&self, tx_id: u64) {
         let mut active = self.active_transactions.write().expect("RwLock poisoned");
         active.remove(&tx_id);
+        drop(active);
+        self.committed.fetch_add(1, Ordering::Relaxed);
+        self.cursors.lock().expect("cursors poisoned").remove(&tx_id);
+        self.drop_exported_snapshots(tx_id);
+        self.change_observers.commit(tx_id);
     }
 
     /// Rolls back a transaction
@@ -115,6 +149,118 @@ impl GlobalTransactionManager {
     pub fn rollback_transaction(&self, tx_id: u64) {
         let mut active = self.active_transactions.write().expect("RwLock poisoned");
         active.remove(&tx_id);
+        self.aborted.fetch_add(1, Ordering::Relaxed);
+        self.cursors.lock().expect("cursors poisoned").remove(&tx_id);
+        self.drop_exported_snapshots(tx_id);
+        self.change_observers.rollback(tx_id);
+    }
+
+    /// Works on the cursors of a transaction (v2.7.0)
+    pub fn with_cursors<R>(&self, tx_id: u64, f: impl FnOnce(&mut Cursors) -> R) -> R {
+        let mut cursors = self.cursors.lock().expect("cursors poisoned");
+        let own = cursors.entry(tx_id).or_default();
+        let result = f(own);
+        if own.is_empty() {
+            cursors.remove(&tx_id);
+        }
+        result
+    }
+
+    /// Exports `snapshot` of transaction `tx_id` for other connections to
+    /// read through; returns its identifier (v2.7.0)
+    ///
+    /// The exporting transaction is added to the active transactions of the
+    /// exported copy, so importers never see what it writes.
+    #[must_use]
+    pub fn export_snapshot(&self, tx_id: u64, snapshot: &Snapshot) -> String {
+        let mut snapshot = snapshot.clone();
+        if !snapshot.active_txs.contains(&tx_id) {
+            snapshot.active_txs.push(tx_id);
+        }
+        snapshot.xmin = snapshot.xmin.min(tx_id);
+
+        let mut exported = self.exported_snapshots.lock().expect("exported snapshots poisoned");
+        let count = exported.values().filter(|(owner, _)| *owner == tx_id).count() + 1;
+        let id = format!("{tx_id:08X}-{count:08X}");
+        exported.insert(id.clone(), (tx_id, snapshot));
+        id
+    }
+
+    /// Snapshot exported as `id`, while the transaction that exported it is
+    /// still in progress (v2.7.0)
+    ///
+    /// Transaction `tx_id` reads through it from now on, so VACUUM keeps the
+    /// row versions it can see for as long as `tx_id` runs.
+    #[must_use]
+    pub fn import_snapshot(&self, tx_id: u64, id: &str) -> Option<Snapshot> {
+        let snapshot = {
+            let exported = self.exported_snapshots.lock().expect("exported snapshots poisoned");
+            exported.get(id).map(|(_, snapshot)| snapshot.clone())?
+        };
+        let mut active = self.active_transactions.write().expect("RwLock poisoned");
+        if let Some(xmin) = active.get_mut(&tx_id) {
+            *xmin = (*xmin).min(snapshot.xmin);
+        }
+        Some(snapshot)
+    }
+
+    fn drop_exported_snapshots(&self, tx_id: u64) {
+        let mut exported = self.exported_snapshots.lock().expect("exported snapshots poisoned");
+        exported.retain(|_, (owner, _)| *owner != tx_id);
+    }
+
+    /// Reads through `snapshot` instead of a fresh one per statement, or
+    /// stops doing so with `None` (v2.7.0)
+    ///
+    /// Only this handle and clones made from it afterwards are affected.
+    pub fn pin_snapshot(&mut self, snapshot: Option<Snapshot>) {
+        self.pinned_snapshot = snapshot;
+    }
+
+    /// Calls `observer` with every row `table` gets inserted, updated or
+    /// deleted, once the transaction that did it commits (v2.7.0)
+    ///
+    /// Every clone of this manager shares the observers, so they see the
+    /// changes made by all connections (see `ChangeObservers`).
+    pub fn on_change(&self, table: &str, observer: impl Fn(&RowChange) + Send + Sync + 'static) {
+        let observer: Arc<ChangeObserver> = Arc::new(observer);
+        self.change_observers.register(table, observer);
+    }
+
+    /// Is anybody observing the changes of `table`? (v2.7.0)
+    #[must_use]
+    pub fn observes(&self, table: &str) -> bool {
+        self.change_observers.observes(table)
+    }
+
+    /// Holds back row changes of transaction `tx_id` for the change
+    /// observers until it commits (v2.7.0)
+    pub fn record_changes(&self, tx_id: u64, changes: impl IntoIterator<Item = RowChange>) {
+        self.change_observers.record(tx_id, changes);
+    }
+
+    /// Number of transactions committed since startup (v2.7.0)
+    #[must_use]
+    pub fn committed_count(&self) -> u64 {
+        self.committed.load(Ordering::Relaxed)
+    }
+
+    /// Number of transactions rolled back since startup (v2.7.0)
+    #[must_use]
+    pub fn aborted_count(&self) -> u64 {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// Number of transactions currently in progress (v2.7.0)
+    #[must_use]
+    pub fn active_count(&self) -> usize {
+        self.active_transactions.read().expect("RwLock poisoned").len()
+    }
+
+    /// Is transaction `tx_id` still in progress? (v2.7.0)
+    #[must_use]
+    pub fn is_active(&self, tx_id: u64) -> bool {
+        self.active_transactions.read().expect("RwLock poisoned").contains_key(&tx_id)
     }
 
     /// Gets the current transaction ID (for auto-commit queries)
@@ -125,34 +271,49 @@ impl GlobalTransactionManager {
         self.next_tx_id.load(Ordering::SeqCst)
     }
 
-    /// Gets the oldest active transaction ID (for VACUUM)
-    ///
-    /// Returns the minimum transaction ID among all active transactions.
-    /// Rows deleted by transactions <= this ID can be safely removed by VACUUM.
+    /// Oldest transaction ID any active transaction may still need (v2.7.0)
     ///
-    /// If no transactions are active, returns current_tx_id - 1.
+    /// The minimum over active transactions of their own ID and the xmin of
+    /// the snapshot they took at BEGIN: a transaction that was still running
+    /// when that snapshot was taken stays invisible to it even after it
+    /// commits, so the row versions it deleted must be kept. With nothing
+    /// active this is the next transaction ID.
     #[must_use]
-    pub fn get_oldest_active_tx(&self) -> u64 {
+    pub fn oldest_active_xid(&self) -> u64 {
         let active = self.active_transactions.read().expect("RwLock poisoned");
-        let current = self.current_tx_id();
+        active
+            .iter()
+            .map(|(&tx_id, &xmin)| tx_id.min(xmin))
+            .min()
+            .unwrap_or_else(|| self.current_tx_id())
+    }
 
-        // Return minimum active tx_id, or current-1 if none active
-        active.iter().min().copied()
-            .unwrap_or_else(|| current.saturating_sub(1))
-            .max(1)
+    /// Gets the cleanup horizon for VACUUM
+    ///
+    /// Rows deleted by transactions <= this ID can be safely removed by VACUUM
+    /// (`Row::is_dead`). v2.7.0: the newest ID older than `oldest_active_xid`,
+    /// so a long-running transaction keeps every version it can still see.
+    #[must_use]
+    pub fn get_oldest_active_tx(&self) -> u64 {
+        self.oldest_active_xid().saturating_sub(1)
     }
 
     /// Creates a new snapshot for READ COMMITTED isolation
     ///
     /// READ COMMITTED takes a new snapshot before each statement,
     /// so it can see changes committed by other transactions.
+    /// v2.7.0: unless a snapshot is pinned (see `pin_snapshot`)
     #[must_use]
     pub fn get_snapshot(&self) -> Snapshot {
+        if let Some(snapshot) = &self.pinned_snapshot {
+            return snapshot.clone();
+        }
+
         let xmax = self.next_tx_id.load(Ordering::SeqCst);
 
         let active_txs = {
             let active = self.active_transactions.read().expect("RwLock poisoned");
-            active.iter().copied().collect::<Vec<_>>()
+            active.keys().copied().collect::<Vec<_>>()
         };
 
         let xmin = active_txs.iter().min().copied().unwrap_or(xmax);
@@ -179,7 +340,7 @@ mod tests {
     fn test_new_global_transaction_manager() {
         let gtm = GlobalTransactionManager::new();
         assert_eq!(gtm.current_tx_id(), 1);
-        assert_eq!(gtm.get_oldest_active_tx(), 1);
+        assert_eq!(gtm.get_oldest_active_tx(), 0);
     }
 
     #[test]
@@ -237,7 +398,7 @@ mod tests {
         let gtm = GlobalTransactionManager::new();
 
         let (tx1, _) = gtm.begin_transaction();
-        assert_eq!(gtm.get_oldest_active_tx(), 1);
+        assert_eq!(gtm.get_oldest_active_tx(), 0);
 
         gtm.commit_transaction(tx1);
 
@@ -251,7 +412,7 @@ mod tests {
         let gtm = GlobalTransactionManager::new();
 
         let (tx1, _) = gtm.begin_transaction();
-        assert_eq!(gtm.get_oldest_active_tx(), 1);
+        assert_eq!(gtm.get_oldest_active_tx(), 0);
 
         gtm.rollback_transaction(tx1);
 
@@ -269,25 +430,58 @@ mod tests {
         let (tx3, _) = gtm.begin_transaction();  // ID = 3
 
         // Oldest active should be tx1
-        assert_eq!(gtm.get_oldest_active_tx(), 1);
+        assert_eq!(gtm.oldest_active_xid(), 1);
 
-        // Commit tx1
+        // Commit tx1: tx2 and tx3 began while it ran and still cannot see it
         gtm.commit_transaction(tx1);
+        assert_eq!(gtm.oldest_active_xid(), 1);
 
-        // Now oldest active should be tx2
-        assert_eq!(gtm.get_oldest_active_tx(), 2);
-
-        // Commit tx2
+        // Commit tx2: tx3 still holds a snapshot from before tx1 committed
         gtm.commit_transaction(tx2);
+        assert_eq!(gtm.oldest_active_xid(), 1);
 
-        // Now oldest active should be tx3
-        assert_eq!(gtm.get_oldest_active_tx(), 3);
-
-        // Commit tx3
+        // A transaction started once tx3 is done needs nothing older than itself
         gtm.commit_transaction(tx3);
+        let (tx4, _) = gtm.begin_transaction();
+        assert_eq!(gtm.oldest_active_xid(), 4);
+        assert_eq!(gtm.get_oldest_active_tx(), 3);
 
         // No active transactions, should return current-1
-        assert_eq!(gtm.get_oldest_active_tx(), 3);
+        gtm.commit_transaction(tx4);
+        assert_eq!(gtm.oldest_active_xid(), 5);
+        assert_eq!(gtm.get_oldest_active_tx(), 4);
+    }
+
+    #[test]
+    fn test_export_and_import_snapshot() {
+        let gtm = GlobalTransactionManager::new();
+        let (old, _) = gtm.begin_transaction();
+        let (exporter, snapshot) = gtm.begin_transaction();
+        let id = gtm.export_snapshot(exporter, &snapshot);
+
+        // Transactions that commit afterwards stay invisible to importers
+        gtm.commit_transaction(old);
+        let (later, _) = gtm.begin_transaction();
+        gtm.commit_transaction(later);
+
+        let (importer, _) = gtm.begin_transaction();
+        let imported = gtm.import_snapshot(importer, &id).unwrap();
+        assert_eq!(imported.xmax, snapshot.xmax);
+        assert!(imported.active_txs.contains(&old));
+        // ... and so does whatever the exporter writes
+        assert!(imported.active_txs.contains(&exporter));
+        // VACUUM keeps what the importer can still see
+        assert_eq!(gtm.oldest_active_xid(), old);
+
+        let mut pinned = gtm.clone();
+        pinned.pin_snapshot(Some(imported));
+        assert_eq!(pinned.get_snapshot().xmax, snapshot.xmax);
+        assert_ne!(gtm.get_snapshot().xmax, snapshot.xmax);
+
+        // Only while the exporting transaction runs
+        gtm.commit_transaction(exporter);
+        assert!(gtm.import_snapshot(importer, &id).is_none());
+        gtm.commit_transaction(importer);
     }
 
     #[test]
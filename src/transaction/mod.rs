@@ -3,7 +3,9 @@
 mod snapshot;
 mod manager;
 mod global_manager;
+mod observers;  // v2.7.0
 
 pub use snapshot::Transaction;
 pub use manager::TransactionManager;
 pub use global_manager::{GlobalTransactionManager, Snapshot};
+pub use observers::{ChangeKind, ChangeObserver, RowChange};  // v2.7.0
@@ -0,0 +1,153 @@
+use crate::types::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// What happened to a row (v2.7.0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// One row change of a committed transaction, as handed to change observers (v2.7.0)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub table: String,
+    pub kind: ChangeKind,
+    /// Values before the change (UPDATE, DELETE)
+    pub old: Option<Vec<Value>>,
+    /// Values after the change (INSERT, UPDATE)
+    pub new: Option<Vec<Value>>,
+    /// Transaction that made the change
+    pub tx_id: u64,
+}
+
+impl RowChange {
+    #[must_use]
+    pub fn insert(table: &str, new: Vec<Value>, tx_id: u64) -> Self {
+        Self { table: table.to_string(), kind: ChangeKind::Insert, old: None, new: Some(new), tx_id }
+    }
+
+    #[must_use]
+    pub fn update(table: &str, old: Vec<Value>, new: Vec<Value>, tx_id: u64) -> Self {
+        Self { table: table.to_string(), kind: ChangeKind::Update, old: Some(old), new: Some(new), tx_id }
+    }
+
+    #[must_use]
+    pub fn delete(table: &str, old: Vec<Value>, tx_id: u64) -> Self {
+        Self { table: table.to_string(), kind: ChangeKind::Delete, old: Some(old), new: None, tx_id }
+    }
+}
+
+/// Callback registered for the changes of one table (v2.7.0)
+pub type ChangeObserver = dyn Fn(&RowChange) + Send + Sync;
+
+/// Per-table change observers for embedders (v2.7.0)
+///
+/// DML records the rows it changes for the tables somebody observes; the
+/// changes of a transaction are held back until it commits and dropped if
+/// it rolls back. Observers run synchronously on the committing connection,
+/// in the order the changes were made, so they should be quick and must not
+/// run queries against the same server.
+#[derive(Default)]
+pub struct ChangeObservers {
+    observers: RwLock<HashMap<String, Vec<Arc<ChangeObserver>>>>,
+    /// Changes of transactions in progress
+    pending: Mutex<HashMap<u64, Vec<RowChange>>>,
+}
+
+impl ChangeObservers {
+    pub fn register(&self, table: &str, observer: Arc<ChangeObserver>) {
+        let mut observers = self.observers.write().expect("change observers poisoned");
+        observers.entry(table.to_string()).or_default().push(observer);
+    }
+
+    /// Does anybody observe `table`? DML skips collecting changes otherwise
+    #[must_use]
+    pub fn observes(&self, table: &str) -> bool {
+        self.observers.read().expect("change observers poisoned").contains_key(table)
+    }
+
+    /// Hold `changes` of transaction `tx_id` until it ends
+    pub fn record(&self, tx_id: u64, changes: impl IntoIterator<Item = RowChange>) {
+        let mut pending = self.pending.lock().expect("pending changes poisoned");
+        pending.entry(tx_id).or_default().extend(changes);
+    }
+
+    /// Hand the changes of committed transaction `tx_id` to the observers
+    pub fn commit(&self, tx_id: u64) {
+        let Some(changes) = self.pending.lock().expect("pending changes poisoned").remove(&tx_id) else {
+            return;
+        };
+        let observers = self.observers.read().expect("change observers poisoned").clone();
+        for change in &changes {
+            for observer in observers.get(&change.table).into_iter().flatten() {
+                observer(change);
+            }
+        }
+    }
+
+    /// Forget the changes of rolled back transaction `tx_id`
+    pub fn rollback(&self, tx_id: u64) {
+        self.pending.lock().expect("pending changes poisoned").remove(&tx_id);
+    }
+}
+
+impl fmt::Debug for ChangeObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let observers = self.observers.read().expect("change observers poisoned");
+        let mut tables: Vec<&String> = observers.keys().collect();
+        tables.sort();
+        f.debug_struct("ChangeObservers").field("tables", &tables).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::QueryExecutor;
+    use crate::parser::parse_statement;
+    use crate::storage::DatabaseStorage;
+    use crate::transaction::GlobalTransactionManager;
+    use crate::types::Database;
+
+    #[test]
+    fn test_observers_see_committed_changes() {
+        let mut db = Database::new("test".to_string());
+        let mut storage = DatabaseStorage::in_memory(16);
+        let tx_manager = GlobalTransactionManager::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        tx_manager.on_change("users", move |change| sink.lock().unwrap().push(change.clone()));
+
+        let mut exec = |sql: &str, tx_id: Option<u64>| {
+            QueryExecutor::execute(&mut db, parse_statement(sql).unwrap(), None, &tx_manager, &mut storage, tx_id).unwrap();
+        };
+        exec("CREATE TABLE users (id INTEGER, name TEXT)", None);
+        exec("CREATE TABLE orders (id INTEGER)", None);
+        exec("INSERT INTO users VALUES (1, 'alice')", None);
+        exec("INSERT INTO orders VALUES (10)", None);
+        let kinds = |seen: &Mutex<Vec<RowChange>>| seen.lock().unwrap().iter().map(|c| c.kind).collect::<Vec<_>>();
+        assert_eq!(kinds(&seen), vec![ChangeKind::Insert]);
+
+        // Held back until COMMIT
+        let (tx_id, _) = tx_manager.begin_transaction();
+        exec("UPDATE users SET name = 'alicia' WHERE id = 1", Some(tx_id));
+        exec("DELETE FROM users WHERE id = 1", Some(tx_id));
+        assert_eq!(kinds(&seen).len(), 1);
+        tx_manager.commit_transaction(tx_id);
+        assert_eq!(kinds(&seen), vec![ChangeKind::Insert, ChangeKind::Update, ChangeKind::Delete]);
+        let update = seen.lock().unwrap()[1].clone();
+        assert_eq!(update.old.unwrap()[1], Value::Text("alice".to_string()));
+        assert_eq!(update.new.unwrap()[1], Value::Text("alicia".to_string()));
+        assert_eq!(update.tx_id, tx_id);
+
+        // ... and dropped on ROLLBACK
+        let (tx_id, _) = tx_manager.begin_transaction();
+        exec("INSERT INTO users VALUES (2, 'bob')", Some(tx_id));
+        tx_manager.rollback_transaction(tx_id);
+        assert_eq!(kinds(&seen).len(), 3);
+    }
+}
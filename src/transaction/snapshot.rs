@@ -75,6 +75,11 @@ impl Transaction {
         self.mvcc_snapshot.as_ref()
     }
 
+    /// Reads through `mvcc_snapshot` from now on (v2.7.0: SET TRANSACTION SNAPSHOT)
+    pub fn set_snapshot(&mut self, mvcc_snapshot: MvccSnapshot) {
+        self.mvcc_snapshot = Some(mvcc_snapshot);
+    }
+
     /// Checks if there's an active transaction
     #[must_use]
     pub const fn is_active(&self) -> bool {
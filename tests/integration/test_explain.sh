@@ -100,7 +100,7 @@ else
     exit 1
 fi
 
-# Test 5: EXPLAIN with hash index should show O(1) cost
+# Test 5: EXPLAIN with hash index
 if echo "$OUTPUT" | grep -qi "index scan.*idx_city.*hash"; then
     echo "✓ EXPLAIN shows hash index scan"
 else
@@ -109,10 +109,10 @@ else
     exit 1
 fi
 
-if echo "$OUTPUT" | grep -q "Cost: O(1)"; then
-    echo "✓ EXPLAIN shows O(1) cost for hash index"
+if echo "$OUTPUT" | grep -q "Index Cond: city = 'NYC'"; then
+    echo "✓ EXPLAIN shows the condition answered by the index"
 else
-    echo "✗ EXPLAIN O(1) cost failed"
+    echo "✗ EXPLAIN index condition failed"
     echo "$OUTPUT"
     exit 1
 fi
@@ -126,15 +126,15 @@ else
 fi
 
 # Test 7: Check for cost estimates
-if echo "$OUTPUT" | grep -q "O(log n)"; then
-    echo "✓ EXPLAIN shows O(log n) cost for B-tree"
+if echo "$OUTPUT" | grep -q "(cost=[0-9.]*\.\.[0-9.]*"; then
+    echo "✓ EXPLAIN shows cost estimates"
 else
-    echo "✗ EXPLAIN O(log n) cost failed"
+    echo "✗ EXPLAIN cost estimates failed"
     exit 1
 fi
 
 # Test 8: Check for row estimates
-if echo "$OUTPUT" | grep -q "Rows:"; then
+if echo "$OUTPUT" | grep -q "rows=[0-9]"; then
     echo "✓ EXPLAIN shows row estimates"
 else
     echo "✗ EXPLAIN row estimates failed"
@@ -151,7 +151,7 @@ echo "  - EXPLAIN for sequential scan working"
 echo "  - EXPLAIN for B-tree index scan working"
 echo "  - EXPLAIN for hash index scan working"
 echo "  - EXPLAIN for unique index working"
-echo "  - Cost estimates working (O(1), O(log n), O(n))"
+echo "  - Cost estimates working"
 echo "  - Row estimates working"
 echo ""
 echo "EXPLAIN features in v1.8.0:"
@@ -85,9 +85,9 @@ echo "✓ Server stopped"
 
 echo ""
 echo "=== Verifying persistence ==="
-if [ -f data/server_instance.db ]; then
-    echo "✓ Database file exists (data/server_instance.db)"
-    echo "  File size: $(ls -lh data/server_instance.db | awk '{print $5}')"
+if [ -f data/checkpoint/instance.cat ]; then
+    echo "✓ Database file exists (data/checkpoint/instance.cat)"
+    echo "  File size: $(ls -lh data/checkpoint/instance.cat | awk '{print $5}')"
     echo "✓ Page-based storage working"
 else
     echo "✗ Database file not found"
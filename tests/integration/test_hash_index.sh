@@ -38,21 +38,43 @@ cleanup() {
 }
 trap cleanup EXIT
 
-# Create test SQL file
-cat > /tmp/test_hash.sql << 'EOF'
+# Function to run SQL
+run_sql() {
+    printf "%s\n" "$1" | nc 127.0.0.1 5432 2>&1
+}
+
+# Create an index on its own connection, then check through EXPLAIN that
+# the planner uses it with the expected access method
+check_index() {
+    local create="$1" probe="$2" expected="$3" label="$4"
+    local result plan
+    result=$(run_sql "$create")
+    plan=$(run_sql "EXPLAIN $probe")
+    if echo "$result" | grep -q "CREATE INDEX" && echo "$plan" | grep -qF "$expected"; then
+        echo "✓ $label succeeded"
+    else
+        echo "✗ $label failed"
+        echo "$result"
+        echo "$plan"
+        exit 1
+    fi
+}
+
+# Create test SQL files
+cat > /tmp/test_hash_setup.sql << 'EOF'
 CREATE TABLE products (id INTEGER PRIMARY KEY, category TEXT NOT NULL, price INTEGER, name TEXT);
 INSERT INTO products (id, category, price, name) VALUES (1, 'Electronics', 999, 'Laptop');
 INSERT INTO products (id, category, price, name) VALUES (2, 'Books', 29, 'SQL Guide');
 INSERT INTO products (id, category, price, name) VALUES (3, 'Electronics', 499, 'Phone');
 INSERT INTO products (id, category, price, name) VALUES (4, 'Books', 19, 'Programming');
 INSERT INTO products (id, category, price, name) VALUES (5, 'Electronics', 199, 'Headphones');
-CREATE INDEX idx_category ON products(category) USING HASH;
-CREATE INDEX idx_price ON products(price) USING BTREE;
-CREATE INDEX idx_name ON products(name);
 CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT);
 INSERT INTO users (id, email) VALUES (1, 'alice@example.com');
 INSERT INTO users (id, email) VALUES (2, 'bob@example.com');
-CREATE UNIQUE INDEX idx_email ON users(email) USING HASH;
+quit
+EOF
+
+cat > /tmp/test_hash.sql << 'EOF'
 SELECT * FROM products WHERE category = 'Electronics';
 SELECT * FROM products WHERE category = 'Books';
 DROP INDEX idx_category;
@@ -62,43 +84,33 @@ EOF
 
 echo ""
 echo "Running hash index tests..."
-OUTPUT=$((sleep 1; cat /tmp/test_hash.sql) | nc 127.0.0.1 5432 2>&1)
+(sleep 1; cat /tmp/test_hash_setup.sql) | nc 127.0.0.1 5432 > /dev/null 2>&1
 
 # Validate results
 echo ""
 echo "Validating results..."
 
-if echo "$OUTPUT" | grep -qi "idx_category.*created.*hash\|created.*idx_category.*hash"; then
-    echo "✓ CREATE INDEX USING HASH succeeded"
-else
-    echo "✗ CREATE INDEX USING HASH failed"
-    echo "$OUTPUT"
-    exit 1
-fi
+check_index "CREATE INDEX idx_category ON products(category) USING HASH;" \
+    "SELECT * FROM products WHERE category = 'Books';" \
+    "Index Scan using idx_category on products (hash)" \
+    "CREATE INDEX USING HASH"
 
-if echo "$OUTPUT" | grep -qi "idx_price.*created.*btree\|created.*idx_price.*btree"; then
-    echo "✓ CREATE INDEX USING BTREE succeeded"
-else
-    echo "✗ CREATE INDEX USING BTREE failed"
-    echo "$OUTPUT"
-    exit 1
-fi
+check_index "CREATE INDEX idx_price ON products(price) USING BTREE;" \
+    "SELECT * FROM products WHERE price = 29;" \
+    "Index Scan using idx_price on products (btree)" \
+    "CREATE INDEX USING BTREE"
 
-if echo "$OUTPUT" | grep -qi "idx_name.*created.*btree\|created.*idx_name.*btree"; then
-    echo "✓ CREATE INDEX without USING (default BTREE) succeeded"
-else
-    echo "✗ CREATE INDEX default failed"
-    echo "$OUTPUT"
-    exit 1
-fi
+check_index "CREATE INDEX idx_name ON products(name);" \
+    "SELECT * FROM products WHERE name = 'Phone';" \
+    "Index Scan using idx_name on products (btree)" \
+    "CREATE INDEX without USING (default BTREE)"
 
-if echo "$OUTPUT" | grep -qi "idx_email.*created.*hash\|created.*idx_email.*hash"; then
-    echo "✓ CREATE UNIQUE INDEX USING HASH succeeded"
-else
-    echo "✗ CREATE UNIQUE INDEX USING HASH failed"
-    echo "$OUTPUT"
-    exit 1
-fi
+check_index "CREATE UNIQUE INDEX idx_email ON users(email) USING HASH;" \
+    "SELECT * FROM users WHERE email = 'bob@example.com';" \
+    "Unique Index Scan using idx_email on users (hash)" \
+    "CREATE UNIQUE INDEX USING HASH"
+
+OUTPUT=$((sleep 1; cat /tmp/test_hash.sql) | nc 127.0.0.1 5432 2>&1)
 
 if echo "$OUTPUT" | grep -q "Laptop"; then
     echo "✓ Hash index equality query returns correct results"
@@ -108,7 +120,8 @@ else
     exit 1
 fi
 
-if echo "$OUTPUT" | grep -qi "idx_category.*dropped\|dropped.*idx_category"; then
+if echo "$OUTPUT" | grep -q "DROP INDEX" \
+    && ! run_sql "EXPLAIN SELECT * FROM products WHERE category = 'Books';" | grep -q "idx_category"; then
     echo "✓ DROP INDEX on hash index succeeded"
 else
     echo "✗ DROP INDEX failed"
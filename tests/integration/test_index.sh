@@ -43,6 +43,11 @@ run_sql() {
     printf "%s\n" "$1" | nc 127.0.0.1 5432 2>&1
 }
 
+# Check through EXPLAIN that the planner answers a query with the index
+uses_index() {
+    run_sql "EXPLAIN $1" | grep -qF "$2"
+}
+
 echo ""
 echo "1. Create test table with data"
 run_sql "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER);" > /dev/null
@@ -56,7 +61,8 @@ echo "✓ Table and data created"
 echo ""
 echo "2. Create B-tree index on age column"
 RESULT=$(run_sql "CREATE INDEX idx_age ON users(age);" 2>&1)
-if echo "$RESULT" | grep -qi "idx_age.*created\|created.*idx_age"; then
+if echo "$RESULT" | grep -q "CREATE INDEX" \
+    && uses_index "SELECT * FROM users WHERE age = 30;" "Index Scan using idx_age on users (btree)"; then
     echo "✓ CREATE INDEX succeeded"
 else
     echo "✗ CREATE INDEX failed: $RESULT"
@@ -66,7 +72,8 @@ fi
 echo ""
 echo "3. Create UNIQUE index on name column"
 RESULT=$(run_sql "CREATE UNIQUE INDEX idx_name ON users(name);" 2>&1)
-if echo "$RESULT" | grep -qi "idx_name.*created\|created.*idx_name"; then
+if echo "$RESULT" | grep -q "CREATE INDEX" \
+    && uses_index "SELECT * FROM users WHERE name = 'Bob';" "Unique Index Scan using idx_name on users (btree)"; then
     echo "✓ CREATE UNIQUE INDEX succeeded"
 else
     echo "✗ CREATE UNIQUE INDEX failed: $RESULT"
@@ -86,7 +93,8 @@ fi
 echo ""
 echo "5. Drop index"
 RESULT=$(run_sql "DROP INDEX idx_age;" 2>&1)
-if echo "$RESULT" | grep -qi "idx_age.*dropped\|dropped.*idx_age"; then
+if echo "$RESULT" | grep -q "DROP INDEX" \
+    && ! uses_index "SELECT * FROM users WHERE age = 30;" "idx_age"; then
     echo "✓ DROP INDEX succeeded"
 else
     echo "✗ DROP INDEX failed: $RESULT"
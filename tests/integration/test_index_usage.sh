@@ -43,6 +43,11 @@ run_sql() {
     printf "%s\n" "$1" | nc 127.0.0.1 5432 2>&1
 }
 
+# Check through EXPLAIN that the planner answers a query with the index
+uses_index() {
+    run_sql "EXPLAIN $1" | grep -qF "$2"
+}
+
 echo ""
 echo "1. Create table with test data (1000 rows)"
 run_sql "CREATE TABLE products (id INTEGER PRIMARY KEY, name TEXT NOT NULL, category TEXT, price INTEGER);" > /dev/null
@@ -70,7 +75,8 @@ fi
 echo ""
 echo "3. Create index on category column"
 RESULT=$(run_sql "CREATE INDEX idx_category ON products(category);" 2>&1)
-if echo "$RESULT" | grep -q "Index 'idx_category' created"; then
+if echo "$RESULT" | grep -q "CREATE INDEX" \
+    && uses_index "SELECT * FROM products WHERE category = 'Cat5';" "Index Scan using idx_category on products (btree)"; then
     echo "✓ Index created on category"
 else
     echo "✗ Index creation failed: $RESULT"
@@ -91,7 +97,8 @@ fi
 echo ""
 echo "5. Create index on price column"
 RESULT=$(run_sql "CREATE INDEX idx_price ON products(price);" 2>&1)
-if echo "$RESULT" | grep -q "Index 'idx_price' created"; then
+if echo "$RESULT" | grep -q "CREATE INDEX" \
+    && uses_index "SELECT * FROM products WHERE price = 500;" "Index Scan using idx_price on products (btree)"; then
     echo "✓ Index created on price"
 else
     echo "✗ Index creation failed: $RESULT"
@@ -56,11 +56,11 @@ fi
 # Run VACUUM - should remove the dead tuple
 VACUUM_OUTPUT=$(run_sql "VACUUM users;")
 echo "✓ Ran VACUUM"
-echo "  Result: $(echo "$VACUUM_OUTPUT" | grep -i "removed")"
 
 # Verify VACUUM removed dead tuple
-if echo "$VACUUM_OUTPUT" | grep -q "Removed 1 dead tuple"; then
-    echo "✓ VACUUM successfully removed 1 dead tuple!"
+DEAD=$(run_sql "SELECT n_dead_tup FROM pg_stat_user_tables WHERE relname = 'users';" | grep -o "[0-9]\+" | head -1)
+if echo "$VACUUM_OUTPUT" | grep -q "VACUUM" && [ "$DEAD" = "0" ]; then
+    echo "✓ VACUUM successfully removed the dead tuple!"
 else
     echo "⚠ VACUUM result: $VACUUM_OUTPUT"
     echo "  (May show 0 if transaction horizon prevents cleanup)"
@@ -88,9 +88,9 @@ echo "  Visible rows after UPDATE: $VISIBLE (should be 3)"
 # Run VACUUM
 VACUUM_OUTPUT=$(run_sql "VACUUM products;")
 echo "✓ Ran VACUUM"
-echo "  Result: $(echo "$VACUUM_OUTPUT" | grep -i "removed")"
 
-if echo "$VACUUM_OUTPUT" | grep -q "Removed 2 dead tuple"; then
+DEAD=$(run_sql "SELECT n_dead_tup FROM pg_stat_user_tables WHERE relname = 'products';" | grep -o "[0-9]\+" | head -1)
+if echo "$VACUUM_OUTPUT" | grep -q "VACUUM" && [ "$DEAD" = "0" ]; then
     echo "✓ VACUUM successfully removed 2 dead tuples from UPDATE!"
 else
     echo "⚠ VACUUM result: $VACUUM_OUTPUT"
@@ -102,9 +102,8 @@ echo "-------------------------"
 
 VACUUM_OUTPUT=$(run_sql "VACUUM;")
 echo "✓ Ran VACUUM (all tables)"
-echo "  Result: $(echo "$VACUUM_OUTPUT" | grep -i "removed")"
 
-if echo "$VACUUM_OUTPUT" | grep -qi "vacuum complete"; then
+if echo "$VACUUM_OUTPUT" | grep -q "VACUUM"; then
     echo "✓ VACUUM completed successfully"
 fi
 
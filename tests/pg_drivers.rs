@@ -0,0 +1,299 @@
+// Wire protocol conformance: the server as tokio-postgres and rust-postgres see it
+use postgrustql::network::Server;
+use postgrustql::network::hba::{HbaConfig, HbaMethod, HbaRule};
+use std::time::Duration;
+use tokio_postgres::error::{ErrorPosition, SqlState};
+use tokio_postgres::types::Type;
+use tokio_postgres::{Client, Column, NoTls, SimpleQueryMessage};
+
+/// Start an in-memory server on a free port and return its address
+async fn start_server() -> String {
+    serve(Server::new_in_memory("postgres", "secret", "postgres")).await
+}
+
+/// Start `server` on a free port and return its address
+async fn serve(server: Server) -> String {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let listen_addr = addr.clone();
+    tokio::spawn(async move {
+        let _ = server.start(&listen_addr).await;
+    });
+    while tokio::net::TcpStream::connect(&addr).await.is_err() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    addr
+}
+
+fn connection_string(addr: &str) -> String {
+    let (host, port) = addr.split_once(':').unwrap();
+    format!("host={host} port={port} user=postgres password=secret dbname=postgres")
+}
+
+async fn connect(addr: &str) -> Client {
+    let (client, connection) = tokio_postgres::connect(&connection_string(addr), NoTls).await.unwrap();
+    tokio::spawn(connection);
+    client
+}
+
+/// Rows of a simple query, every value as text
+async fn simple_rows(client: &Client, sql: &str) -> Vec<Vec<Option<String>>> {
+    client
+        .simple_query(sql)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|message| match message {
+            SimpleQueryMessage::Row(row) => {
+                Some((0..row.len()).map(|i| row.get(i).map(str::to_string)).collect())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_simple_query_protocol() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+
+    client.batch_execute("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price NUMERIC(10,2))").await.unwrap();
+    let messages = client.simple_query("INSERT INTO items VALUES (1, 'apple', 1.50), (2, 'pear', 0.75)").await.unwrap();
+    assert!(matches!(messages.as_slice(), [SimpleQueryMessage::CommandComplete(2)]));
+
+    let rows = simple_rows(&client, "SELECT id, name, price FROM items ORDER BY id").await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Some("1".to_string()), Some("apple".to_string()), Some("1.50".to_string())],
+            vec![Some("2".to_string()), Some("pear".to_string()), Some("0.75".to_string())],
+        ]
+    );
+    assert_eq!(client.execute("UPDATE items SET name = 'plum' WHERE id = 2", &[]).await.unwrap(), 1);
+    assert_eq!(client.execute("DELETE FROM items", &[]).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_extended_query_protocol() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE users (id INTEGER, name TEXT, active BOOLEAN)").await.unwrap();
+
+    // Parameter types declared by the client go over the wire in binary
+    let insert = client.prepare_typed("INSERT INTO users VALUES ($1, $2, $3)", &[Type::INT4, Type::TEXT, Type::BOOL]).await.unwrap();
+    assert_eq!(insert.params(), [Type::INT4, Type::TEXT, Type::BOOL]);
+    for (id, name, active) in [(1_i32, "ann", true), (2, "bob", false), (3, "it's", true)] {
+        assert_eq!(client.execute(&insert, &[&id, &name, &active]).await.unwrap(), 1);
+    }
+
+    // Undeclared parameters are text, and so are all result columns
+    let select = client.prepare("SELECT id, name, active FROM users WHERE id >= $1 ORDER BY id").await.unwrap();
+    assert_eq!(select.params(), [Type::TEXT]);
+    assert_eq!(select.columns().iter().map(Column::name).collect::<Vec<_>>(), ["id", "name", "active"]);
+    assert!(select.columns().iter().all(|c| *c.type_() == Type::TEXT));
+    let rows = client.query(&select, &[&"2"]).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, &str>("id"), "2");
+    assert_eq!(rows[0].get::<_, &str>("name"), "bob");
+    assert_eq!(rows[1].get::<_, &str>("name"), "it's");
+
+    // The same statement runs again with other parameters
+    assert_eq!(client.query(&select, &[&"3"]).await.unwrap().len(), 1);
+
+    let row = client.query_one("SELECT name FROM users WHERE id = $1", &[&"1"]).await.unwrap();
+    assert_eq!(row.get::<_, Option<&str>>(0), Some("ann"));
+    assert!(client.query_opt("SELECT name FROM users WHERE id = $1", &[&"42"]).await.unwrap().is_none());
+    let row = client.query_one("SELECT name FROM users WHERE name = $1", &[&None::<&str>]).await;
+    assert!(row.is_err(), "NULL matches no row");
+}
+
+#[tokio::test]
+async fn test_null_results() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE notes (id INTEGER, note TEXT)").await.unwrap();
+    client.batch_execute("INSERT INTO notes VALUES (1, NULL), (2, 'x')").await.unwrap();
+
+    let rows = simple_rows(&client, "SELECT note FROM notes ORDER BY id").await;
+    assert_eq!(rows, vec![vec![None], vec![Some("x".to_string())]]);
+    let rows = client.query("SELECT note FROM notes ORDER BY id", &[]).await.unwrap();
+    assert_eq!(rows[0].get::<_, Option<&str>>(0), None);
+}
+
+#[tokio::test]
+async fn test_transactions() {
+    let addr = start_server().await;
+    let mut client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE accounts (id INTEGER, balance INTEGER)").await.unwrap();
+    client.batch_execute("INSERT INTO accounts VALUES (1, 100), (2, 100)").await.unwrap();
+
+    let transaction = client.transaction().await.unwrap();
+    transaction.execute("UPDATE accounts SET balance = balance - 30 WHERE id = 1", &[]).await.unwrap();
+    transaction.execute("UPDATE accounts SET balance = balance + 30 WHERE id = 2", &[]).await.unwrap();
+    transaction.commit().await.unwrap();
+    let rows = simple_rows(&client, "SELECT balance FROM accounts ORDER BY id").await;
+    assert_eq!(rows, vec![vec![Some("70".to_string())], vec![Some("130".to_string())]]);
+
+    // Other sessions see committed changes
+    let other = connect(&addr).await;
+    let rows = simple_rows(&other, "SELECT SUM(balance) FROM accounts").await;
+    assert_eq!(rows, vec![vec![Some("200".to_string())]]);
+}
+
+#[tokio::test]
+async fn test_error_paths() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE t (id INTEGER PRIMARY KEY)").await.unwrap();
+    client.batch_execute("INSERT INTO t VALUES (1)").await.unwrap();
+
+    let error = client.batch_execute("SELEC 1").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::SYNTAX_ERROR));
+    let error = client.batch_execute("SELECT * FROM t WHERE id = 1 AN id = 2").await.unwrap_err();
+    let db_error = error.as_db_error().unwrap();
+    assert_eq!(db_error.message(), "Parse error: syntax error at or near \"AN\" at character 30");
+    assert_eq!(db_error.position(), Some(&ErrorPosition::Original(30)));
+    let error = client.execute("INSERT INTO t VALUES (", &[]).await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::SYNTAX_ERROR));
+    assert_eq!(error.as_db_error().unwrap().position(), Some(&ErrorPosition::Original(23)));
+    let error = client.batch_execute("SELECT 'it''s").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::SYNTAX_ERROR));
+    assert_eq!(error.as_db_error().unwrap().position(), Some(&ErrorPosition::Original(8)));
+    let error = client.batch_execute("SELECT * FROM missing").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::UNDEFINED_TABLE));
+    let error = client.execute("INSERT INTO t VALUES ($1)", &[&"1"]).await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::UNIQUE_VIOLATION));
+
+    // Bad data is not bad syntax
+    let error = client.batch_execute("INSERT INTO t VALUES ('abc')").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::INVALID_TEXT_REPRESENTATION));
+    let error = client.batch_execute("INSERT INTO t VALUES (TRUE)").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::DATATYPE_MISMATCH));
+
+    // A failed statement inside a transaction leaves it to be rolled back
+    client.batch_execute("BEGIN").await.unwrap();
+    client.batch_execute("INSERT INTO t VALUES (2)").await.unwrap();
+    let error = client.batch_execute("INSERT INTO t VALUES (1)").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::UNIQUE_VIOLATION));
+    client.batch_execute("ROLLBACK").await.unwrap();
+
+    // The connection stays usable after every error
+    assert_eq!(client.query("SELECT id FROM t WHERE id = 1", &[]).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_authentication_failure() {
+    let addr = start_server().await;
+    let (host, port) = addr.split_once(':').unwrap();
+    let wrong = format!("host={host} port={port} user=postgres password=wrong dbname=postgres");
+    let Err(error) = tokio_postgres::connect(&wrong, NoTls).await else {
+        panic!("connected with a wrong password");
+    };
+    assert_eq!(error.code(), Some(&SqlState::INVALID_PASSWORD));
+}
+
+#[tokio::test]
+async fn test_scram_authentication() {
+    let rule = HbaRule {
+        address: "all".to_string(),
+        database: "all".to_string(),
+        user: "all".to_string(),
+        method: HbaMethod::Scram,
+        map: None,
+    };
+    let server = Server::new_in_memory("postgres", "secret", "postgres").with_hba(HbaConfig::new(vec![rule]));
+    let addr = serve(server).await;
+
+    let client = connect(&addr).await;
+    assert_eq!(simple_rows(&client, "SELECT 1").await, vec![vec![Some("1".to_string())]]);
+
+    let (host, port) = addr.split_once(':').unwrap();
+    let wrong = format!("host={host} port={port} user=postgres password=wrong dbname=postgres");
+    let Err(error) = tokio_postgres::connect(&wrong, NoTls).await else {
+        panic!("connected with a wrong password");
+    };
+    assert_eq!(error.code(), Some(&SqlState::INVALID_PASSWORD));
+}
+
+#[tokio::test]
+async fn test_server_statements() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+
+    let messages = client.simple_query("CREATE USER bob WITH PASSWORD 'Secret123!'").await.unwrap();
+    assert!(matches!(messages.as_slice(), [SimpleQueryMessage::CommandComplete(_)]));
+    let error = client.simple_query("CREATE USER bob WITH PASSWORD 'Secret123!'").await.unwrap_err();
+    assert!(error.as_db_error().is_some());
+
+    // The new user can log in from another session
+    let (host, port) = addr.split_once(':').unwrap();
+    let bob = format!("host={host} port={port} user=bob password=Secret123! dbname=postgres");
+    let (other, connection) = tokio_postgres::connect(&bob, NoTls).await.unwrap();
+    tokio::spawn(connection);
+    other.simple_query("SELECT 1").await.unwrap();
+    client.simple_query("DROP USER bob").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_blocking_client() {
+    let addr = start_server().await;
+    let config = connection_string(&addr);
+    tokio::task::spawn_blocking(move || {
+        let mut client = postgres::Client::connect(&config, postgres::NoTls).unwrap();
+        client.batch_execute("CREATE TABLE notes (id INTEGER, body TEXT)").unwrap();
+        let mut transaction = client.transaction().unwrap();
+        transaction.execute("INSERT INTO notes VALUES ($1, $2)", &[&"1", &"first"]).unwrap();
+        transaction.commit().unwrap();
+        let row = client.query_one("SELECT body FROM notes WHERE id = $1", &[&"1"]).unwrap();
+        assert_eq!(row.get::<_, &str>(0), "first");
+        client.close().unwrap();
+    })
+    .await
+    .unwrap();
+}
+
+// Known differences from PostgreSQL, kept here until the server catches up
+
+#[tokio::test]
+#[ignore = "booleans are sent as true/false"]
+async fn test_boolean_results() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE flags (id INTEGER, on_off BOOLEAN)").await.unwrap();
+    client.batch_execute("INSERT INTO flags VALUES (1, NULL), (2, FALSE)").await.unwrap();
+
+    let rows = simple_rows(&client, "SELECT on_off FROM flags ORDER BY id").await;
+    assert_eq!(rows, vec![vec![None], vec![Some("f".to_string())]]);
+}
+
+#[tokio::test]
+#[ignore = "a simple query runs a single statement"]
+async fn test_multiple_statements_in_one_query() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE t (id INTEGER); INSERT INTO t VALUES (1); INSERT INTO t VALUES (2)").await.unwrap();
+    assert_eq!(simple_rows(&client, "SELECT id FROM t").await.len(), 2);
+}
+
+#[tokio::test]
+#[ignore = "rows written by a rolled back transaction stay visible"]
+async fn test_rollback_discards_changes() {
+    let addr = start_server().await;
+    let mut client = connect(&addr).await;
+    client.batch_execute("CREATE TABLE t (id INTEGER)").await.unwrap();
+
+    let transaction = client.transaction().await.unwrap();
+    transaction.execute("INSERT INTO t VALUES (1)", &[]).await.unwrap();
+    transaction.rollback().await.unwrap();
+    assert!(simple_rows(&client, "SELECT id FROM t").await.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "statements after an error in a transaction block still run"]
+async fn test_failed_transaction_rejects_statements() {
+    let addr = start_server().await;
+    let client = connect(&addr).await;
+    client.batch_execute("BEGIN").await.unwrap();
+    assert!(client.batch_execute("SELECT * FROM missing").await.is_err());
+    let error = client.batch_execute("SELECT 1").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::IN_FAILED_SQL_TRANSACTION));
+}